@@ -26,18 +26,34 @@ fn main() {
     CxxQtBuilder::new_qml_module(
         QmlModule::new("ZestBay")
             .qml_file("qml/main.qml")
+            .qml_file("qml/Headless.qml")
             .qml_file("qml/GraphView.qml")
+            .qml_file("qml/CompareView.qml")
+            .qml_file("qml/RemoteView.qml")
             .qml_file("qml/PluginBrowser.qml")
             .qml_file("qml/PluginParams.qml")
+            .qml_file("qml/PluginDock.qml")
             .qml_file("qml/RuleEditor.qml")
             .qml_file("qml/PluginManager.qml")
             .qml_file("qml/MidiMappings.qml")
+            .qml_file("qml/MidiClock.qml")
+            .qml_file("qml/CommandLog.qml")
+            .qml_file("qml/Hooks.qml")
+            .qml_file("qml/Import.qml")
+            .qml_file("qml/Sessions.qml")
             .qml_file("qml/Preferences.qml")
             .qml_file("qml/CpuOverlay.qml")
             .qml_file("qml/About.qml")
             .qml_file(QmlFile::from("qml/Theme.qml").singleton(true)),
     )
     .qt_module("Network")
-    .files(["src/ui/qobject_bridge.rs"])
+    .qt_module("Widgets")
+    .files([
+        "src/ui/qobject_bridge.rs",
+        "src/ui/plugin_dock.rs",
+        "src/lv2/qt_ui.rs",
+    ])
+    .cpp_file("src/lv2/qt_ui_host.cpp")
+    .cpp_file("src/ui/plugin_dock.cpp")
     .build();
 }
@@ -6,14 +6,22 @@
 //! that occur when Qt6/Wayland and X11/GLX coexist in the same process.
 //!
 //! Communication with the host is via stdin/stdout JSON messages.
+//!
+//! Started with `--single`, the process hosts one isolated UI and exits when
+//! it closes. Isolated UIs may also be GTK 2/3 UIs (GTK is loaded on demand)
+//! and exchange control values with the host through a shared-memory block
+//! instead of JSON messages.
 
 mod protocol;
+#[allow(dead_code)]
+mod shm;
 
 use protocol::{BridgeMessage, HostMessage};
 use std::collections::HashMap;
 use std::ffi::{CStr, CString, c_void};
 use std::io::{BufRead, Write};
 use std::os::raw::{c_char, c_int, c_uint, c_ulong};
+use std::path::Path;
 use std::ptr;
 
 // ---------------------------------------------------------------------------
@@ -116,6 +124,104 @@ struct XWindowAttributes {
     do_not_propagate_mask: c_ulong, override_redirect: c_int, screen: *mut c_void,
 }
 
+// ---------------------------------------------------------------------------
+// GTK (loaded on demand)
+// ---------------------------------------------------------------------------
+
+const LV2_UI_GTK2: &str = "http://lv2plug.in/ns/extensions/ui#GtkUI";
+const LV2_UI_GTK3: &str = "http://lv2plug.in/ns/extensions/ui#Gtk3UI";
+const GTK_WINDOW_TOPLEVEL: c_int = 0;
+
+/// The GTK entry points needed to show a GTK plugin UI in a toplevel
+/// window. GTK is dlopen'ed rather than linked: only isolated GTK UIs need
+/// it, and each of those gets a process of its own, so GTK 2 or GTK 3 can
+/// be loaded to match the UI.
+struct GtkApi {
+    ui_type_uri: &'static str,
+    window_new: unsafe extern "C" fn(c_int) -> *mut c_void,
+    window_set_title: unsafe extern "C" fn(*mut c_void, *const c_char),
+    container_add: unsafe extern "C" fn(*mut c_void, *mut c_void),
+    widget_show_all: unsafe extern "C" fn(*mut c_void),
+    widget_destroy: unsafe extern "C" fn(*mut c_void),
+    events_pending: unsafe extern "C" fn() -> c_int,
+    main_iteration_do: unsafe extern "C" fn(c_int) -> c_int,
+    signal_connect_data: unsafe extern "C" fn(
+        *mut c_void,
+        *const c_char,
+        *const c_void,
+        *mut c_void,
+        *const c_void,
+        c_uint,
+    ) -> c_ulong,
+}
+
+impl GtkApi {
+    fn load(ui_type_uri: &str) -> Result<Self, String> {
+        let (ui_type_uri, lib_name) = match ui_type_uri {
+            LV2_UI_GTK3 => (LV2_UI_GTK3, c"libgtk-3.so.0"),
+            LV2_UI_GTK2 => (LV2_UI_GTK2, c"libgtk-x11-2.0.so.0"),
+            other => return Err(format!("Unsupported UI type: {}", other)),
+        };
+        unsafe {
+            let lib = libc::dlopen(lib_name.as_ptr(), libc::RTLD_NOW | libc::RTLD_GLOBAL);
+            if lib.is_null() {
+                return Err(format!("{:?} is not installed", lib_name));
+            }
+            let init_check: unsafe extern "C" fn(*mut c_int, *mut c_void) -> c_int =
+                gtk_symbol(lib, c"gtk_init_check")?;
+            let api = Self {
+                ui_type_uri,
+                window_new: gtk_symbol(lib, c"gtk_window_new")?,
+                window_set_title: gtk_symbol(lib, c"gtk_window_set_title")?,
+                container_add: gtk_symbol(lib, c"gtk_container_add")?,
+                widget_show_all: gtk_symbol(lib, c"gtk_widget_show_all")?,
+                widget_destroy: gtk_symbol(lib, c"gtk_widget_destroy")?,
+                events_pending: gtk_symbol(lib, c"gtk_events_pending")?,
+                main_iteration_do: gtk_symbol(lib, c"gtk_main_iteration_do")?,
+                signal_connect_data: gtk_symbol(lib, c"g_signal_connect_data")?,
+            };
+
+            // Same as the in-process GTK host: plugin UIs expect X11.
+            std::env::set_var("GDK_BACKEND", "x11");
+            if init_check(ptr::null_mut(), ptr::null_mut()) == 0 {
+                return Err("gtk_init failed (no X11 display?)".into());
+            }
+            Ok(api)
+        }
+    }
+
+    /// Dispatch all pending GTK events without blocking.
+    fn pump(&self) {
+        unsafe {
+            while (self.events_pending)() != 0 {
+                (self.main_iteration_do)(0);
+            }
+        }
+    }
+}
+
+/// Look up a function in the loaded GTK library. `T` must be the
+/// function's pointer type.
+unsafe fn gtk_symbol<T>(lib: *mut c_void, name: &CStr) -> Result<T, String> {
+    debug_assert_eq!(std::mem::size_of::<T>(), std::mem::size_of::<*mut c_void>());
+    let p = unsafe { libc::dlsym(lib, name.as_ptr()) };
+    if p.is_null() {
+        Err(format!("GTK symbol {:?} not found", name))
+    } else {
+        Ok(unsafe { std::mem::transmute_copy(&p) })
+    }
+}
+
+unsafe extern "C" fn gtk_window_destroyed(_widget: *mut c_void, data: *mut c_void) {
+    if !data.is_null() {
+        let win = data as *mut PluginUiWindow;
+        unsafe {
+            (*win).gtk_window = ptr::null_mut();
+            (*win).closed = true;
+        }
+    }
+}
+
 // ---------------------------------------------------------------------------
 // LV2 UI types
 // ---------------------------------------------------------------------------
@@ -224,24 +330,70 @@ struct PluginUiWindow {
     idle_iface: Option<*const LV2UiIdleInterface>,
     closed: bool,
     _lib: *mut c_void,
+    /// Toplevel window of a GTK UI (null for X11 UIs, or once destroyed).
+    gtk_window: *mut c_void,
+    /// Control block shared with the host, for isolated UIs.
+    shm: Option<shm::ControlBlock>,
+    shm_seen: Vec<u32>,
+    /// Feature data the UI may keep pointers into (GTK UIs).
+    _features: Option<Box<UiFeatures>>,
+}
+
+/// URID map and options features for a GTK UI, boxed and kept with the
+/// window since the UI may use the map for its whole lifetime.
+struct UiFeatures {
+    urid_map: LV2UridMap,
+    sample_rate: f32,
+    options: [LV2Option; 2],
+    urid_feature: LV2Feature,
+    options_feature: LV2Feature,
+}
+
+impl UiFeatures {
+    unsafe fn new(sample_rate: f32) -> Box<Self> {
+        unsafe {
+            let sr_urid = (*URID_MAPPER).map_uri("http://lv2plug.in/ns/ext/parameters#sampleRate");
+            let float_urid = (*URID_MAPPER).map_uri("http://lv2plug.in/ns/ext/atom#Float");
+            let end = LV2Option { context: 0, subject: 0, key: 0, size: 0, type_: 0, value: ptr::null() };
+            let mut f = Box::new(Self {
+                urid_map: LV2UridMap { handle: ptr::null_mut(), map: urid_map_callback },
+                sample_rate,
+                options: [
+                    LV2Option { context: 0, subject: 0, key: sr_urid, size: 4, type_: float_urid, value: ptr::null() },
+                    end,
+                ],
+                urid_feature: LV2Feature { uri: LV2_URID_MAP_URI.as_ptr(), data: ptr::null_mut() },
+                options_feature: LV2Feature { uri: LV2_OPTIONS_URI.as_ptr(), data: ptr::null_mut() },
+            });
+            f.options[0].value = &f.sample_rate as *const f32 as *const c_void;
+            f.urid_feature.data = &mut f.urid_map as *mut _ as *mut c_void;
+            f.options_feature.data = f.options.as_ptr() as *mut c_void;
+            f
+        }
+    }
 }
 
 // Global for the port write callback to find
 static mut BRIDGE_STDOUT: *mut std::io::Stdout = ptr::null_mut();
-static mut CURRENT_INSTANCE_ID: u64 = 0;
 
+/// `controller` is the UI's `PluginUiWindow`.
 unsafe extern "C" fn port_write_callback(
-    _controller: *mut c_void,
+    controller: *mut c_void,
     port_index: c_uint,
     buffer_size: c_uint,
     protocol: c_uint,
     buffer: *const c_void,
 ) {
     unsafe {
-        if protocol == 0 && buffer_size == 4 && !buffer.is_null() {
+        if protocol == 0 && buffer_size == 4 && !buffer.is_null() && !controller.is_null() {
             let value = *(buffer as *const f32);
+            let win = &*(controller as *const PluginUiWindow);
+            if let Some(ref block) = win.shm {
+                block.write_from_ui(port_index as usize, value);
+                return;
+            }
             let msg = BridgeMessage::PortWrite {
-                instance_id: CURRENT_INSTANCE_ID,
+                instance_id: win.instance_id,
                 port_index: port_index as usize,
                 value,
             };
@@ -276,22 +428,93 @@ unsafe extern "C" fn ui_resize_callback(
 // Window management
 // ---------------------------------------------------------------------------
 
-fn open_ui(msg: HostMessage, windows: &mut HashMap<u64, PluginUiWindow>) -> BridgeMessage {
-    let (instance_id, plugin_uri, ui_uri, bundle_path, binary_path, title,
-         control_values, urid_map_initial, sample_rate) = match msg {
+/// Load `binary_path` and find the descriptor of `ui_uri` in it.
+unsafe fn load_ui_descriptor(
+    binary_path: &str,
+    ui_uri: &str,
+) -> Result<(*mut c_void, *const LV2UI_Descriptor), String> {
+    unsafe {
+        let c_binary = CString::new(binary_path).map_err(|_| "Invalid UI binary path".to_string())?;
+        let lib = libc::dlopen(c_binary.as_ptr(), libc::RTLD_LAZY | libc::RTLD_LOCAL);
+        if lib.is_null() {
+            return Err(format!("dlopen failed: {:?}", c_binary));
+        }
+
+        let desc_sym = libc::dlsym(lib, c"lv2ui_descriptor".as_ptr());
+        if desc_sym.is_null() {
+            libc::dlclose(lib);
+            return Err("No lv2ui_descriptor symbol".into());
+        }
+
+        let lv2ui_descriptor_fn: unsafe extern "C" fn(c_uint) -> *const LV2UI_Descriptor =
+            std::mem::transmute(desc_sym);
+
+        let c_ui_uri = CString::new(ui_uri).unwrap_or_default();
+        for idx in 0..100u32 {
+            let desc = lv2ui_descriptor_fn(idx);
+            if desc.is_null() { break; }
+            if CStr::from_ptr((*desc).uri) == c_ui_uri.as_c_str() {
+                return Ok((lib, desc));
+            }
+        }
+
+        libc::dlclose(lib);
+        Err("UI descriptor not found".into())
+    }
+}
+
+fn open_ui(
+    msg: HostMessage,
+    windows: &mut HashMap<u64, Box<PluginUiWindow>>,
+    gtk: &mut Option<GtkApi>,
+) -> BridgeMessage {
+    let (instance_id, plugin_uri, ui_uri, ui_type_uri, bundle_path, binary_path, title,
+         control_values, urid_map_initial, sample_rate, shm_path) = match msg {
         HostMessage::Open {
-            instance_id, plugin_uri, ui_uri, bundle_path, binary_path,
-            title, control_values, urid_map, sample_rate, ..
-        } => (instance_id, plugin_uri, ui_uri, bundle_path, binary_path,
-              title, control_values, urid_map, sample_rate),
+            instance_id, plugin_uri, ui_uri, ui_type_uri, bundle_path, binary_path,
+            title, control_values, urid_map, sample_rate, shm_path, ..
+        } => (instance_id, plugin_uri, ui_uri, ui_type_uri, bundle_path, binary_path,
+              title, control_values, urid_map, sample_rate, shm_path),
         _ => unreachable!(),
     };
 
+    let shm = match shm_path {
+        Some(path) => match shm::ControlBlock::open(Path::new(&path)) {
+            Ok(block) => Some(block),
+            Err(e) => {
+                return BridgeMessage::OpenFailed {
+                    instance_id,
+                    error: format!("Cannot map control block {}: {}", path, e),
+                };
+            }
+        },
+        None => None,
+    };
+
     unsafe {
         // Initialize URID mapper with host's mappings
         let mapper = Box::new(UridMapper::new(urid_map_initial));
         URID_MAPPER = Box::into_raw(mapper);
 
+        if ui_type_uri == LV2_UI_GTK3 || ui_type_uri == LV2_UI_GTK2 {
+            let result = load_gtk(gtk, &ui_type_uri).and_then(|api| {
+                open_gtk_ui(
+                    api, instance_id, &plugin_uri, &ui_uri, &bundle_path, &binary_path,
+                    &title, &control_values, sample_rate, shm,
+                )
+            });
+            return match result {
+                Ok(win) => {
+                    windows.insert(instance_id, win);
+                    BridgeMessage::Opened {
+                        instance_id,
+                        window_id: 0,
+                    }
+                }
+                Err(error) => BridgeMessage::OpenFailed { instance_id, error },
+            };
+        }
+
         // Open our own X11 display (clean, no GTK/Qt/Wayland interference)
         let display = XOpenDisplay(ptr::null());
         if display.is_null() {
@@ -372,6 +595,10 @@ fn open_ui(msg: HostMessage, windows: &mut HashMap<u64, PluginUiWindow>) -> Brid
             idle_iface: None,
             closed: false,
             _lib: ptr::null_mut(),
+            gtk_window: ptr::null_mut(),
+            shm,
+            shm_seen: Vec::new(),
+            _features: None,
         });
 
         let mut resize_data = LV2UIResize {
@@ -405,61 +632,22 @@ fn open_ui(msg: HostMessage, windows: &mut HashMap<u64, PluginUiWindow>) -> Brid
         ];
 
         // Load UI binary
-        let c_binary = CString::new(binary_path.as_str()).unwrap();
-        let lib = libc::dlopen(c_binary.as_ptr(), libc::RTLD_LAZY | libc::RTLD_LOCAL);
-        if lib.is_null() {
-            XDestroyWindow(display, host_window);
-            XCloseDisplay(display);
-            return BridgeMessage::OpenFailed {
-                instance_id,
-                error: format!("dlopen failed: {:?}", c_binary),
-            };
-        }
-
-        let desc_sym = libc::dlsym(lib, c"lv2ui_descriptor".as_ptr());
-        if desc_sym.is_null() {
-            libc::dlclose(lib);
-            XDestroyWindow(display, host_window);
-            XCloseDisplay(display);
-            return BridgeMessage::OpenFailed {
-                instance_id,
-                error: "No lv2ui_descriptor symbol".into(),
-            };
-        }
-
-        let lv2ui_descriptor_fn: unsafe extern "C" fn(c_uint) -> *const LV2UI_Descriptor =
-            std::mem::transmute(desc_sym);
-
-        let c_ui_uri = CString::new(ui_uri.as_str()).unwrap();
-        let mut ui_descriptor: *const LV2UI_Descriptor = ptr::null();
-        for idx in 0..100u32 {
-            let desc = lv2ui_descriptor_fn(idx);
-            if desc.is_null() { break; }
-            if CStr::from_ptr((*desc).uri) == c_ui_uri.as_c_str() {
-                ui_descriptor = desc;
-                break;
+        let (lib, ui_descriptor) = match load_ui_descriptor(&binary_path, &ui_uri) {
+            Ok(loaded) => loaded,
+            Err(error) => {
+                XDestroyWindow(display, host_window);
+                XCloseDisplay(display);
+                return BridgeMessage::OpenFailed { instance_id, error };
             }
-        }
-
-        if ui_descriptor.is_null() {
-            libc::dlclose(lib);
-            XDestroyWindow(display, host_window);
-            XCloseDisplay(display);
-            return BridgeMessage::OpenFailed {
-                instance_id,
-                error: "UI descriptor not found".into(),
-            };
-        }
+        };
 
         // Instantiate
-        CURRENT_INSTANCE_ID = instance_id;
         let c_plugin_uri = CString::new(plugin_uri.as_str()).unwrap();
         let c_bundle = CString::new(bundle_path.as_str()).unwrap();
         let mut widget: *mut c_void = ptr::null_mut();
 
-        // Use a non-null dummy controller (some plugins check for null)
-        let controller_dummy: u64 = 1;
-        let controller_ptr = &controller_dummy as *const u64 as *mut c_void;
+        // The window doubles as the controller (some plugins check for null)
+        let controller_ptr = &mut *plugin_win as *mut PluginUiWindow as *mut c_void;
 
         let ui_handle = if let Some(instantiate_fn) = (*ui_descriptor).instantiate {
             instantiate_fn(
@@ -523,9 +711,123 @@ fn open_ui(msg: HostMessage, windows: &mut HashMap<u64, PluginUiWindow>) -> Brid
         plugin_win.child_window = child;
         plugin_win._lib = lib;
 
-        windows.insert(instance_id, *plugin_win);
+        windows.insert(instance_id, plugin_win);
+
+        BridgeMessage::Opened {
+            instance_id,
+            window_id: host_window as u64,
+        }
+    }
+}
+
+fn load_gtk<'a>(gtk: &'a mut Option<GtkApi>, ui_type_uri: &str) -> Result<&'a GtkApi, String> {
+    if gtk.is_none() {
+        *gtk = Some(GtkApi::load(ui_type_uri)?);
+    }
+    match gtk {
+        Some(api) if api.ui_type_uri == ui_type_uri => Ok(api),
+        _ => Err("A different GTK version is already loaded in this bridge process".into()),
+    }
+}
+
+/// Open a GTK UI in a toplevel GtkWindow. Only used for isolated UIs, so
+/// the GTK main loop here serves this one UI.
+#[allow(clippy::too_many_arguments)]
+fn open_gtk_ui(
+    gtk: &GtkApi,
+    instance_id: u64,
+    plugin_uri: &str,
+    ui_uri: &str,
+    bundle_path: &str,
+    binary_path: &str,
+    title: &str,
+    control_values: &[(usize, f32)],
+    sample_rate: f32,
+    shm: Option<shm::ControlBlock>,
+) -> Result<Box<PluginUiWindow>, String> {
+    unsafe {
+        let (lib, descriptor) = load_ui_descriptor(binary_path, ui_uri)?;
+        let features = UiFeatures::new(sample_rate);
+        let feature_ptrs: [*const LV2Feature; 3] =
+            [&features.urid_feature, &features.options_feature, ptr::null()];
+
+        let mut win = Box::new(PluginUiWindow {
+            instance_id,
+            display: ptr::null_mut(),
+            host_window: 0,
+            child_window: 0,
+            wm_delete: 0,
+            ui_handle: ptr::null_mut(),
+            descriptor,
+            idle_iface: None,
+            closed: false,
+            _lib: lib,
+            gtk_window: ptr::null_mut(),
+            shm,
+            shm_seen: Vec::new(),
+            _features: None,
+        });
+        let win_ptr = &mut *win as *mut PluginUiWindow as *mut c_void;
+
+        let c_plugin_uri = CString::new(plugin_uri).unwrap_or_default();
+        let c_bundle = CString::new(bundle_path).unwrap_or_default();
+        let mut widget: *mut c_void = ptr::null_mut();
+        let ui_handle = match (*descriptor).instantiate {
+            Some(instantiate_fn) => instantiate_fn(
+                descriptor,
+                c_plugin_uri.as_ptr(),
+                c_bundle.as_ptr(),
+                port_write_callback,
+                win_ptr,
+                &mut widget,
+                feature_ptrs.as_ptr(),
+            ),
+            None => ptr::null_mut(),
+        };
+        if ui_handle.is_null() {
+            return Err("UI instantiate returned null".into());
+        }
+        if widget.is_null() {
+            if let Some(cleanup) = (*descriptor).cleanup {
+                cleanup(ui_handle);
+            }
+            return Err("UI did not provide a widget".into());
+        }
+
+        let window = (gtk.window_new)(GTK_WINDOW_TOPLEVEL);
+        let c_title = CString::new(title).unwrap_or_else(|_| c"ZestBay Plugin".to_owned());
+        (gtk.window_set_title)(window, c_title.as_ptr());
+        (gtk.container_add)(window, widget);
+        (gtk.signal_connect_data)(
+            window,
+            c"destroy".as_ptr(),
+            gtk_window_destroyed as *const c_void,
+            win_ptr,
+            ptr::null(),
+            0,
+        );
+
+        win.idle_iface = (*descriptor).extension_data.and_then(|ext_data| {
+            let ext = ext_data(LV2_UI_IDLE_INTERFACE.as_ptr());
+            (!ext.is_null()).then_some(ext as *const LV2UiIdleInterface)
+        });
+        if let Some(port_event_fn) = (*descriptor).port_event {
+            for &(port_index, value) in control_values {
+                port_event_fn(
+                    ui_handle,
+                    port_index as c_uint,
+                    std::mem::size_of::<f32>() as c_uint,
+                    0,
+                    &value as *const f32 as *const c_void,
+                );
+            }
+        }
 
-        BridgeMessage::Opened { instance_id }
+        (gtk.widget_show_all)(window);
+        win.ui_handle = ui_handle;
+        win.gtk_window = window;
+        win._features = Some(features);
+        Ok(win)
     }
 }
 
@@ -551,7 +853,23 @@ fn find_child_window(display: *mut c_void, parent: c_ulong) -> c_ulong {
 
 fn idle_window(win: &mut PluginUiWindow) {
     unsafe {
-        while XPending(win.display) > 0 {
+        // Forward values the host published since the last pass.
+        if let Some(ref block) = win.shm
+            && let Some(port_event_fn) = (*win.descriptor).port_event
+        {
+            let ui_handle = win.ui_handle;
+            block.take_host_updates(&mut win.shm_seen, |port_index, value| {
+                port_event_fn(
+                    ui_handle,
+                    port_index as c_uint,
+                    std::mem::size_of::<f32>() as c_uint,
+                    0,
+                    &value as *const f32 as *const c_void,
+                );
+            });
+        }
+
+        while !win.display.is_null() && XPending(win.display) > 0 {
             let mut event: XEvent = std::mem::zeroed();
             XNextEvent(win.display, &mut event);
 
@@ -598,12 +916,24 @@ fn idle_window(win: &mut PluginUiWindow) {
     }
 }
 
-fn close_window(win: &mut PluginUiWindow) {
+fn close_window(win: &mut PluginUiWindow, gtk: Option<&GtkApi>) {
     unsafe {
+        // GTK UIs: destroy the window (and with it the widget) first, as
+        // suil does, then let the UI clean up.
+        if !win.gtk_window.is_null()
+            && let Some(gtk) = gtk
+        {
+            let window = win.gtk_window;
+            win.gtk_window = ptr::null_mut();
+            (gtk.widget_destroy)(window);
+        }
         if let Some(cleanup) = (*win.descriptor).cleanup {
             cleanup(win.ui_handle);
         }
         win.ui_handle = ptr::null_mut();
+        if win.display.is_null() {
+            return;
+        }
         XDestroyWindow(win.display, win.host_window);
         win.host_window = 0;
         if !win._lib.is_null() {
@@ -631,7 +961,11 @@ fn main() {
 
     let stdin = std::io::stdin();
     let mut stdin_lines = stdin.lock().lines();
-    let mut windows: HashMap<u64, PluginUiWindow> = HashMap::new();
+    let mut windows: HashMap<u64, Box<PluginUiWindow>> = HashMap::new();
+    let mut gtk: Option<GtkApi> = None;
+    // One isolated UI per process: exit once it is gone.
+    let single = std::env::args().any(|arg| arg == "--single");
+    let mut served = false;
 
     // Set stdin to non-blocking so we can interleave with X11 events
     unsafe {
@@ -652,8 +986,9 @@ fn main() {
                         match msg {
                             HostMessage::Quit => { got_quit = true; break; }
                             HostMessage::Open { .. } => {
-                                let result = open_ui(msg, &mut windows);
+                                let result = open_ui(msg, &mut windows, &mut gtk);
                                 send_message(&mut stdout, &result);
+                                served = true;
                             }
                             HostMessage::PortEvent { instance_id, port_index, value } => {
                                 if let Some(win) = windows.get(&instance_id) {
@@ -669,7 +1004,7 @@ fn main() {
                             }
                             HostMessage::Close { instance_id } => {
                                 if let Some(mut win) = windows.remove(&instance_id) {
-                                    close_window(&mut win);
+                                    close_window(&mut win, gtk.as_ref());
                                     send_message(&mut stdout, &BridgeMessage::Closed { instance_id });
                                 }
                             }
@@ -682,6 +1017,10 @@ fn main() {
 
         if got_quit { break; }
 
+        if let Some(ref gtk) = gtk {
+            gtk.pump();
+        }
+
         // Idle all windows
         let mut closed_ids = Vec::new();
         for (id, win) in windows.iter_mut() {
@@ -693,12 +1032,12 @@ fn main() {
 
         for id in closed_ids {
             if let Some(mut win) = windows.remove(&id) {
-                close_window(&mut win);
+                close_window(&mut win, gtk.as_ref());
                 send_message(&mut stdout, &BridgeMessage::Closed { instance_id: id });
             }
         }
 
-        if windows.is_empty() && got_quit {
+        if windows.is_empty() && (got_quit || (single && served)) {
             break;
         }
 
@@ -708,6 +1047,6 @@ fn main() {
 
     // Cleanup remaining windows
     for (_, mut win) in windows.drain() {
-        close_window(&mut win);
+        close_window(&mut win, gtk.as_ref());
     }
 }
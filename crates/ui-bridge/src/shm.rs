@@ -0,0 +1,234 @@
+//! Shared-memory control block for isolated plugin UIs.
+//!
+//! An isolated UI runs alone in a bridge process. Instead of pushing every
+//! control value through the JSON pipe, the host mirrors the plugin's
+//! `SharedPortUpdates` into a small file under `/dev/shm` that both
+//! processes map. Every LV2 port index has a slot holding two
+//! single-writer channels: values published by the host (parameters and
+//! meters) and values written by the UI. A channel is a value plus a
+//! sequence number bumped after the value is stored, so readers only
+//! compare sequence numbers.
+//!
+//! Copy of the host's `src/ui_bridge/shm.rs`; keep the two in sync.
+
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::os::fd::AsRawFd;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, Ordering};
+
+const MAGIC: u32 = 0x5a42_5543;
+
+/// Upper bound on the slot count, so a damaged header cannot make the
+/// bridge map an arbitrarily large file.
+pub const MAX_SLOTS: usize = 4096;
+
+#[repr(C)]
+struct Header {
+    magic: AtomicU32,
+    slots: AtomicU32,
+}
+
+#[repr(C)]
+struct Slot {
+    host_value: AtomicU32,
+    host_seq: AtomicU32,
+    ui_value: AtomicU32,
+    ui_seq: AtomicU32,
+}
+
+pub struct ControlBlock {
+    base: *mut u8,
+    len: usize,
+    slots: usize,
+    path: PathBuf,
+    /// The creating side removes the file when it drops the block.
+    owner: bool,
+}
+
+// SAFETY: the mapping is only accessed through atomics.
+unsafe impl Send for ControlBlock {}
+unsafe impl Sync for ControlBlock {}
+
+/// Default location of the control block for one UI instance.
+pub fn default_path(instance_id: u64) -> PathBuf {
+    let dir = Path::new("/dev/shm");
+    let dir = if dir.is_dir() {
+        dir.to_path_buf()
+    } else {
+        std::env::temp_dir()
+    };
+    dir.join(format!("zestbay-ui-{}-{}", std::process::id(), instance_id))
+}
+
+fn mapped_len(slots: usize) -> usize {
+    std::mem::size_of::<Header>() + slots * std::mem::size_of::<Slot>()
+}
+
+impl ControlBlock {
+    /// Create (or truncate) the file at `path` with room for port indices
+    /// `0..slots` and map it.
+    pub fn create(path: &Path, slots: usize) -> io::Result<Self> {
+        if slots > MAX_SLOTS {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{} control slots exceed the limit of {}", slots, MAX_SLOTS),
+            ));
+        }
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path)?;
+        let len = mapped_len(slots);
+        file.set_len(len as u64)?;
+        let block = Self::map(&file, len, slots, path, true)?;
+        block.header().slots.store(slots as u32, Ordering::Relaxed);
+        block.header().magic.store(MAGIC, Ordering::Release);
+        Ok(block)
+    }
+
+    /// Map a block created by the other process.
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let file = OpenOptions::new().read(true).write(true).open(path)?;
+        let file_len = file.metadata()?.len() as usize;
+        if file_len < mapped_len(0) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "control block is truncated",
+            ));
+        }
+        let header = Self::map(&file, mapped_len(0), 0, path, false)?;
+        if header.header().magic.load(Ordering::Acquire) != MAGIC {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "not a ZestBay control block",
+            ));
+        }
+        let slots = header.header().slots.load(Ordering::Relaxed) as usize;
+        drop(header);
+        if slots > MAX_SLOTS || mapped_len(slots) > file_len {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "control block header does not match its size",
+            ));
+        }
+        Self::map(&file, mapped_len(slots), slots, path, false)
+    }
+
+    fn map(file: &File, len: usize, slots: usize, path: &Path, owner: bool) -> io::Result<Self> {
+        let base = unsafe {
+            libc::mmap(
+                std::ptr::null_mut(),
+                len,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_SHARED,
+                file.as_raw_fd(),
+                0,
+            )
+        };
+        if base == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Self {
+            base: base as *mut u8,
+            len,
+            slots,
+            path: path.to_path_buf(),
+            owner,
+        })
+    }
+
+    fn header(&self) -> &Header {
+        unsafe { &*(self.base as *const Header) }
+    }
+
+    fn slot(&self, port_index: usize) -> Option<&Slot> {
+        if port_index >= self.slots {
+            return None;
+        }
+        unsafe {
+            let first = self.base.add(std::mem::size_of::<Header>()) as *const Slot;
+            Some(&*first.add(port_index))
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn slots(&self) -> usize {
+        self.slots
+    }
+
+    /// Host side: publish a port value. Unchanged values are not
+    /// republished, so the UI only sees real changes.
+    pub fn publish(&self, port_index: usize, value: f32) {
+        if let Some(slot) = self.slot(port_index) {
+            let bits = value.to_bits();
+            if slot.host_seq.load(Ordering::Relaxed) != 0
+                && slot.host_value.load(Ordering::Relaxed) == bits
+            {
+                return;
+            }
+            slot.host_value.store(bits, Ordering::Relaxed);
+            slot.host_seq.fetch_add(1, Ordering::Release);
+        }
+    }
+
+    /// UI side: record a value written by the plugin UI.
+    pub fn write_from_ui(&self, port_index: usize, value: f32) {
+        if let Some(slot) = self.slot(port_index) {
+            slot.ui_value.store(value.to_bits(), Ordering::Relaxed);
+            slot.ui_seq.fetch_add(1, Ordering::Release);
+        }
+    }
+
+    /// UI side: call `f` for every port the host published since the last
+    /// call. `seen` holds the reader's sequence numbers, one per slot.
+    pub fn take_host_updates(&self, seen: &mut Vec<u32>, mut f: impl FnMut(usize, f32)) {
+        seen.resize(self.slots, 0);
+        for (port_index, last) in seen.iter_mut().enumerate() {
+            if let Some(slot) = self.slot(port_index) {
+                let seq = slot.host_seq.load(Ordering::Acquire);
+                if seq != *last {
+                    *last = seq;
+                    f(
+                        port_index,
+                        f32::from_bits(slot.host_value.load(Ordering::Relaxed)),
+                    );
+                }
+            }
+        }
+    }
+
+    /// Host side: call `f` for every port the UI wrote since the last call.
+    pub fn take_ui_updates(&self, seen: &mut Vec<u32>, mut f: impl FnMut(usize, f32)) {
+        seen.resize(self.slots, 0);
+        for (port_index, last) in seen.iter_mut().enumerate() {
+            if let Some(slot) = self.slot(port_index) {
+                let seq = slot.ui_seq.load(Ordering::Acquire);
+                if seq != *last {
+                    *last = seq;
+                    f(
+                        port_index,
+                        f32::from_bits(slot.ui_value.load(Ordering::Relaxed)),
+                    );
+                }
+            }
+        }
+    }
+}
+
+impl Drop for ControlBlock {
+    fn drop(&mut self) {
+        unsafe {
+            libc::munmap(self.base as *mut libc::c_void, self.len);
+        }
+        if self.owner {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+}
+
@@ -0,0 +1,120 @@
+//! Access tokens for the remote API and what each one may do.
+//!
+//! Every client presents a token. Besides the main token, which may do
+//! everything, the user can hand out named tokens with a narrower
+//! [`ApiScope`], such as a read-only one for a status display, and revoke
+//! them one by one.
+
+use serde::{Deserialize, Serialize};
+
+/// What a token may do. Each scope includes the ones before it.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum ApiScope {
+    /// See the graph and follow its changes.
+    #[default]
+    Read,
+    /// Connect and disconnect ports and run the patchbay.
+    Control,
+    /// Bypass plugins and change their parameters as well.
+    Plugins,
+}
+
+impl ApiScope {
+    pub fn allows(self, needed: ApiScope) -> bool {
+        self >= needed
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ApiScope::Read => "read",
+            ApiScope::Control => "control",
+            ApiScope::Plugins => "plugins",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        [ApiScope::Read, ApiScope::Control, ApiScope::Plugins]
+            .into_iter()
+            .find(|s| s.as_str() == name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ApiToken {
+    /// What the token was handed out for, shown in the preferences.
+    pub name: String,
+    pub token: String,
+    #[serde(default)]
+    pub scope: ApiScope,
+}
+
+/// The token matching `presented`, compared in constant time so the
+/// response time gives nothing away about how much of a guess was right.
+pub fn find_token<'a>(tokens: &'a [ApiToken], presented: &str) -> Option<&'a ApiToken> {
+    let mut found = None;
+    for token in tokens {
+        if !token.token.is_empty() && constant_time_eq(&token.token, presented) {
+            found = Some(token);
+        }
+    }
+    found
+}
+
+fn constant_time_eq(a: &str, b: &str) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.bytes()
+        .zip(b.bytes())
+        .fold(0u8, |diff, (x, y)| diff | (x ^ y))
+        == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn token(name: &str, token: &str, scope: ApiScope) -> ApiToken {
+        ApiToken {
+            name: name.into(),
+            token: token.into(),
+            scope,
+        }
+    }
+
+    #[test]
+    fn test_scopes_include_lower_ones() {
+        assert!(ApiScope::Plugins.allows(ApiScope::Control));
+        assert!(ApiScope::Control.allows(ApiScope::Read));
+        assert!(!ApiScope::Control.allows(ApiScope::Plugins));
+        assert!(!ApiScope::Read.allows(ApiScope::Control));
+    }
+
+    #[test]
+    fn test_tokens_are_found_by_exact_value() {
+        let tokens = [
+            token("Main", "abc123", ApiScope::Plugins),
+            token("Display", "def456", ApiScope::Read),
+            token("Empty", "", ApiScope::Plugins),
+        ];
+        assert_eq!(find_token(&tokens, "def456").unwrap().name, "Display");
+        assert!(find_token(&tokens, "def45").is_none());
+        assert!(find_token(&tokens, "def457").is_none());
+        assert!(find_token(&tokens, "").is_none());
+    }
+
+    #[test]
+    fn test_scope_names_round_trip() {
+        for scope in [ApiScope::Read, ApiScope::Control, ApiScope::Plugins] {
+            assert_eq!(ApiScope::parse(scope.as_str()), Some(scope));
+            let json = serde_json::to_string(&scope).unwrap();
+            assert_eq!(json, format!("\"{}\"", scope.as_str()));
+        }
+        let old: ApiToken = serde_json::from_str(r#"{"name":"a","token":"b"}"#).unwrap();
+        assert_eq!(old.scope, ApiScope::Read);
+        assert_eq!(ApiScope::parse("admin"), None);
+    }
+}
@@ -0,0 +1,405 @@
+//! Versioned config files.
+//!
+//! A file saved through a [`ConfigSchema`] is wrapped as
+//! `{"schema_version": N, "data": ...}`. Bare JSON, as written before files
+//! were versioned, counts as version 1. At load, a file older than its schema
+//! runs through the registered migration steps; the original is kept next to
+//! it as `<file>.v<N>.bak` and the upgraded file replaces it.
+//!
+//! To change a file's format, append a step to its schema. Steps work on the
+//! raw JSON payload, so they keep working after the Rust types move on.
+//!
+//! Admins and distributions can ship defaults in [`SYSTEM_CONFIG_DIRS`].
+//! Those files are never written: they are upgraded in memory and only used
+//! where the user has no file of their own, see [`ConfigSchema::load_layered`].
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+pub const VERSION_KEY: &str = "schema_version";
+const DATA_KEY: &str = "data";
+
+/// Read-only directories holding system-wide defaults, most specific first:
+/// the admin's, then the distribution's.
+pub const SYSTEM_CONFIG_DIRS: [&str; 2] = ["/etc/zestbay", "/usr/share/zestbay"];
+
+/// How a user's file combines with the system default of the same name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Layering {
+    /// The user's file replaces the default as a whole.
+    Replace,
+    /// Top-level keys of the user's file override those of the default, so
+    /// settings the user never changed follow the default.
+    MergeKeys,
+}
+
+/// Upgrades a payload from one schema version to the next.
+pub type MigrationStep = fn(Value) -> Result<Value, String>;
+
+/// The format history of one kind of config file.
+#[derive(Debug, Clone, Copy)]
+pub struct ConfigSchema {
+    pub name: &'static str,
+    /// `steps[i]` upgrades version `i + 1` to version `i + 2`.
+    pub steps: &'static [MigrationStep],
+}
+
+/// Patchbay rules: `rules.json`, comparison instance rules and rule backups.
+pub const RULES: ConfigSchema = ConfigSchema::new("rules", &[]);
+/// Active plugin instances: `plugins.json` and its known-good snapshot.
+pub const PLUGINS: ConfigSchema = ConfigSchema::new("plugins", &[]);
+/// Node positions in the graph view: `layout.json`.
+pub const LAYOUT: ConfigSchema = ConfigSchema::new("layout", &[]);
+/// User settings: `preferences.json`.
+pub const PREFERENCES: ConfigSchema = ConfigSchema::new("preferences", &[]);
+/// Nodes hidden from the graph view: `hidden.json`.
+pub const HIDDEN: ConfigSchema = ConfigSchema::new("hidden", &[]);
+/// Nodes auto-layout leaves in place: `pinned.json`.
+pub const PINNED: ConfigSchema = ConfigSchema::new("pinned", &[]);
+/// Graph view pan and zoom: `viewport.json`.
+pub const VIEWPORT: ConfigSchema = ConfigSchema::new("viewport", &[]);
+/// Main window position and size: `window.json`.
+pub const WINDOW: ConfigSchema = ConfigSchema::new("window", &[]);
+/// Plugin and MIDI links restored at startup: `links.json`.
+pub const LINKS: ConfigSchema = ConfigSchema::new("links", &[]);
+/// Per-link gain stages: `link_gains.json`.
+pub const LINK_GAINS: ConfigSchema = ConfigSchema::new("link_gains", &[]);
+/// Manual link overrides that outlive a restart: `link_overrides.json`.
+pub const LINK_OVERRIDES: ConfigSchema = ConfigSchema::new("link_overrides", &[]);
+/// Sidechain sources by plugin: `sidechains.json`.
+pub const SIDECHAINS: ConfigSchema = ConfigSchema::new("sidechains", &[]);
+/// MIDI CC to parameter mappings: `midi_mappings.json`.
+pub const MIDI_MAPPINGS: ConfigSchema = ConfigSchema::new("midi_mappings", &[]);
+/// Role policies of the patchbay: `roles.json`.
+pub const ROLES: ConfigSchema = ConfigSchema::new("roles", &[]);
+/// Stable identities of the nodes seen so far: `node_ids.json`.
+pub const NODE_IDS: ConfigSchema = ConfigSchema::new("node_ids", &[]);
+/// Plugin UI window positions: `plugin_windows.json`.
+pub const PLUGIN_WINDOWS: ConfigSchema = ConfigSchema::new("plugin_windows", &[]);
+/// Plugin UIs run out of process: `ui_isolation.json`.
+pub const UI_ISOLATION: ConfigSchema = ConfigSchema::new("ui_isolation", &[]);
+/// Plugin UI scale factors: `ui_scale.json`.
+pub const UI_SCALE: ConfigSchema = ConfigSchema::new("ui_scale", &[]);
+/// Hook scripts: `hooks.json`.
+pub const HOOKS: ConfigSchema = ConfigSchema::new("hooks", &[]);
+/// Saved plugin chains: `chain_presets.json`.
+pub const CHAIN_PRESETS: ConfigSchema = ConfigSchema::new("chain_presets", &[]);
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(PathBuf, std::io::Error),
+    Parse(serde_json::Error),
+    /// A migration step rejected the payload of version `from`.
+    Migration {
+        from: u32,
+        message: String,
+    },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(path, e) => write!(f, "{}: {}", path.display(), e),
+            Self::Parse(e) => write!(f, "invalid JSON: {}", e),
+            Self::Migration { from, message } => {
+                write!(f, "migration from version {} failed: {}", from, message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl From<serde_json::Error> for ConfigError {
+    fn from(e: serde_json::Error) -> Self {
+        Self::Parse(e)
+    }
+}
+
+impl ConfigSchema {
+    pub const fn new(name: &'static str, steps: &'static [MigrationStep]) -> Self {
+        Self { name, steps }
+    }
+
+    /// The version files are written at.
+    pub fn version(&self) -> u32 {
+        self.steps.len() as u32 + 1
+    }
+
+    /// Split file contents into their version and payload.
+    pub fn split(raw: Value) -> (u32, Value) {
+        match raw {
+            Value::Object(mut map)
+                if map.contains_key(DATA_KEY)
+                    && map.get(VERSION_KEY).is_some_and(Value::is_u64) =>
+            {
+                let version = map[VERSION_KEY].as_u64().unwrap_or(1).max(1) as u32;
+                (version, map.remove(DATA_KEY).unwrap_or_default())
+            }
+            raw => (1, raw),
+        }
+    }
+
+    /// Parse file contents and bring the payload up to the current version.
+    /// Also returns the version the contents were at.
+    pub fn upgrade(&self, contents: &str) -> Result<(Value, u32), ConfigError> {
+        let (from, mut data) = Self::split(serde_json::from_str(contents)?);
+        if from > self.version() {
+            log::warn!(
+                "{} config is version {}, newer than this build understands ({}); loading as is",
+                self.name,
+                from,
+                self.version()
+            );
+            return Ok((data, from));
+        }
+        for (i, step) in self.steps.iter().enumerate().skip(from as usize - 1) {
+            let version = i as u32 + 1;
+            data = step(data).map_err(|message| ConfigError::Migration {
+                from: version,
+                message,
+            })?;
+        }
+        Ok((data, from))
+    }
+
+    /// Read `path` and return its payload, or `None` if the file does not
+    /// exist. An outdated file is backed up and rewritten at the current
+    /// version.
+    pub fn load_value(&self, path: &Path) -> Result<Option<Value>, ConfigError> {
+        let contents = match std::fs::read_to_string(path) {
+            Ok(s) => s,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(ConfigError::Io(path.to_path_buf(), e)),
+        };
+        let (data, from) = self.upgrade(&contents)?;
+        if from < self.version() {
+            let backup = backup_path(path, from);
+            std::fs::write(&backup, &contents).map_err(|e| ConfigError::Io(backup.clone(), e))?;
+            self.save(path, &data)?;
+            log::info!(
+                "Migrated {:?} from version {} to {} (original kept as {:?})",
+                path,
+                from,
+                self.version(),
+                backup
+            );
+        }
+        Ok(Some(data))
+    }
+
+    pub fn load<T: DeserializeOwned>(&self, path: &Path) -> Result<Option<T>, ConfigError> {
+        match self.load_value(path)? {
+            Some(data) => Ok(Some(serde_json::from_value(data)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Pretty-printed file contents for `data` at the current version.
+    pub fn to_string<T: Serialize + ?Sized>(&self, data: &T) -> Result<String, ConfigError> {
+        let wrapped = serde_json::json!({
+            VERSION_KEY: self.version(),
+            DATA_KEY: data,
+        });
+        Ok(serde_json::to_string_pretty(&wrapped)?)
+    }
+
+    /// Like [`Self::load_value`], but an outdated file is only upgraded in
+    /// memory. For files the user cannot write, such as system defaults.
+    pub fn read_value(&self, path: &Path) -> Result<Option<Value>, ConfigError> {
+        match std::fs::read_to_string(path) {
+            Ok(contents) => Ok(Some(self.upgrade(&contents)?.0)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(ConfigError::Io(path.to_path_buf(), e)),
+        }
+    }
+
+    /// Load the user's file at `user` layered over the first of `defaults`
+    /// that exists. `None` if there is neither.
+    pub fn load_layered<T: DeserializeOwned>(
+        &self,
+        user: &Path,
+        defaults: &[PathBuf],
+        layering: Layering,
+    ) -> Result<Option<T>, ConfigError> {
+        let user_data = self.load_value(user)?;
+        if layering == Layering::Replace && user_data.is_some() {
+            return Ok(Some(serde_json::from_value(user_data.unwrap_or_default())?));
+        }
+        let mut default_data = None;
+        for path in defaults {
+            if let Some(data) = self.read_value(path)? {
+                default_data = Some(data);
+                break;
+            }
+        }
+        let data = match (default_data, user_data) {
+            (Some(Value::Object(mut merged)), Some(Value::Object(user))) => {
+                merged.extend(user);
+                Value::Object(merged)
+            }
+            (_, Some(user)) => user,
+            (Some(default), None) => default,
+            (None, None) => return Ok(None),
+        };
+        Ok(Some(serde_json::from_value(data)?))
+    }
+
+    pub fn save<T: Serialize + ?Sized>(&self, path: &Path, data: &T) -> Result<(), ConfigError> {
+        if let Some(parent) = path.parent() {
+            let _ = std::fs::create_dir_all(parent);
+        }
+        let json = self.to_string(data)?;
+        std::fs::write(path, json).map_err(|e| ConfigError::Io(path.to_path_buf(), e))
+    }
+}
+
+/// Where the version-`version` original of `path` is kept after migrating.
+pub fn backup_path(path: &Path, version: u32) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".v{}.bak", version));
+    path.with_file_name(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rename_target(mut data: Value) -> Result<Value, String> {
+        for rule in data.as_array_mut().ok_or("expected an array")? {
+            if let Some(target) = rule.as_object_mut().and_then(|r| r.remove("dest")) {
+                rule["target"] = target;
+            }
+        }
+        Ok(data)
+    }
+
+    fn add_enabled(mut data: Value) -> Result<Value, String> {
+        for rule in data.as_array_mut().ok_or("expected an array")? {
+            rule["enabled"] = Value::Bool(true);
+        }
+        Ok(data)
+    }
+
+    const TEST_RULES: ConfigSchema = ConfigSchema::new("test", &[rename_target, add_enabled]);
+
+    fn temp_file(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("zestbay-config-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir.join("rules.json")
+    }
+
+    #[test]
+    fn test_bare_json_is_version_one() {
+        let (value, from) = TEST_RULES.upgrade(r#"[{"dest": "Speakers"}]"#).unwrap();
+        assert_eq!(from, 1);
+        assert_eq!(
+            value,
+            serde_json::json!([{"target": "Speakers", "enabled": true}])
+        );
+
+        let (value, from) = TEST_RULES
+            .upgrade(r#"{"schema_version": 2, "data": [{"target": "Speakers"}]}"#)
+            .unwrap();
+        assert_eq!(from, 2);
+        assert_eq!(
+            value,
+            serde_json::json!([{"target": "Speakers", "enabled": true}])
+        );
+    }
+
+    #[test]
+    fn test_failed_step_reports_version() {
+        let err = TEST_RULES.upgrade(r#"{"dest": "Speakers"}"#).unwrap_err();
+        assert!(matches!(err, ConfigError::Migration { from: 1, .. }));
+    }
+
+    #[test]
+    fn test_newer_file_loads_as_is() {
+        let (value, from) = TEST_RULES
+            .upgrade(r#"{"schema_version": 9, "data": [{"future": 1}]}"#)
+            .unwrap();
+        assert_eq!(from, 9);
+        assert_eq!(value, serde_json::json!([{"future": 1}]));
+    }
+
+    #[test]
+    fn test_load_backs_up_and_rewrites_old_file() {
+        let path = temp_file("migrate");
+        let original = r#"[{"dest": "Speakers"}]"#;
+        std::fs::write(&path, original).unwrap();
+
+        let loaded = TEST_RULES.load_value(&path).unwrap().unwrap();
+        assert_eq!(loaded[0]["target"], "Speakers");
+        assert_eq!(
+            std::fs::read_to_string(backup_path(&path, 1)).unwrap(),
+            original
+        );
+
+        let rewritten: Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(rewritten[VERSION_KEY], 3);
+        assert_eq!(TEST_RULES.load_value(&path).unwrap().unwrap(), loaded);
+
+        assert!(
+            TEST_RULES
+                .load_value(&path.with_file_name("missing.json"))
+                .unwrap()
+                .is_none()
+        );
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn test_system_default_is_upgraded_in_memory_only() {
+        let path = temp_file("system");
+        let original = r#"[{"dest": "Speakers"}]"#;
+        std::fs::write(&path, original).unwrap();
+
+        let loaded = TEST_RULES.read_value(&path).unwrap().unwrap();
+        assert_eq!(loaded[0]["target"], "Speakers");
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), original);
+        assert!(!backup_path(&path, 1).exists());
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn test_user_file_layers_over_default() {
+        let system = temp_file("layer-system");
+        let user = temp_file("layer-user");
+        std::fs::write(&system, r#"{"theme": "dark", "tray": true}"#).unwrap();
+        let defaults = [user.with_file_name("absent.json"), system.clone()];
+
+        let prefs: Value = PREFERENCES
+            .load_layered(&user, &defaults, Layering::MergeKeys)
+            .unwrap()
+            .unwrap();
+        assert_eq!(prefs, serde_json::json!({"theme": "dark", "tray": true}));
+
+        std::fs::write(&user, r#"{"theme": "light"}"#).unwrap();
+        let prefs: Value = PREFERENCES
+            .load_layered(&user, &defaults, Layering::MergeKeys)
+            .unwrap()
+            .unwrap();
+        assert_eq!(prefs, serde_json::json!({"theme": "light", "tray": true}));
+        let replaced: Value = PREFERENCES
+            .load_layered(&user, &defaults, Layering::Replace)
+            .unwrap()
+            .unwrap();
+        assert_eq!(replaced, serde_json::json!({"theme": "light"}));
+
+        let none: Option<Value> = PREFERENCES
+            .load_layered(&user.with_file_name("absent.json"), &[], Layering::Replace)
+            .unwrap();
+        assert!(none.is_none());
+        for path in [system, user] {
+            let _ = std::fs::remove_dir_all(path.parent().unwrap());
+        }
+    }
+}
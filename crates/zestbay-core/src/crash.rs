@@ -0,0 +1,196 @@
+//! Crash reports: the text the crash handler writes, and finding the reports
+//! the user has not been shown yet.
+//!
+//! Reports are plain text so they can be pasted into an issue as they are.
+//! Each one is `crash-<unix ms>.txt`, which is how the next start tells new
+//! reports from ones it already offered.
+
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+
+/// Log lines kept for a report.
+pub const LOG_LINES: usize = 200;
+
+pub const ISSUES_URL: &str = "https://github.com/lemonxah/zestbay/issues/new";
+
+const CAUSE_PREFIX: &str = "Cause: ";
+
+/// The most recent log lines, oldest first.
+#[derive(Debug, Clone)]
+pub struct LogRing {
+    lines: VecDeque<String>,
+    capacity: usize,
+}
+
+impl LogRing {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            lines: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    pub fn push(&mut self, line: String) {
+        if self.lines.len() == self.capacity {
+            self.lines.pop_front();
+        }
+        self.lines.push_back(line);
+    }
+
+    pub fn lines(&self) -> impl Iterator<Item = &str> {
+        self.lines.iter().map(String::as_str)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CrashReport {
+    pub time_ms: u64,
+    pub version: String,
+    /// The panic message, or the fatal signal.
+    pub cause: String,
+    pub thread: String,
+    pub backtrace: String,
+    /// What the app was doing: mode flags, the PipeWire remote, active
+    /// plugins.
+    pub state: Vec<(String, String)>,
+    pub log: Vec<String>,
+}
+
+impl CrashReport {
+    pub fn file_name(&self) -> String {
+        format!("crash-{}.txt", self.time_ms)
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = format!(
+            "ZestBay crash report\n\nVersion: {}\nTime: {} ms since epoch\nThread: {}\n{}{}\n",
+            self.version, self.time_ms, self.thread, CAUSE_PREFIX, self.cause
+        );
+        text.push_str("\n== State ==\n");
+        for (key, value) in &self.state {
+            text.push_str(&format!("{}: {}\n", key, value));
+        }
+        text.push_str("\n== Backtrace ==\n");
+        text.push_str(self.backtrace.trim_end());
+        text.push_str(&format!("\n\n== Last {} log lines ==\n", self.log.len()));
+        for line in &self.log {
+            text.push_str(line);
+            text.push('\n');
+        }
+        text
+    }
+}
+
+/// The cause line of a report written by [`CrashReport::to_text`].
+pub fn report_cause(text: &str) -> Option<&str> {
+    text.lines()
+        .find_map(|line| line.strip_prefix(CAUSE_PREFIX))
+}
+
+/// When the report at `path` was written, from its file name.
+pub fn report_time(path: &Path) -> Option<u64> {
+    path.file_name()?
+        .to_str()?
+        .strip_prefix("crash-")?
+        .strip_suffix(".txt")?
+        .parse()
+        .ok()
+}
+
+/// Reports in `dir` written after `seen_ms`, oldest first.
+pub fn unseen_reports(dir: &Path, seen_ms: u64) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut reports: Vec<(u64, PathBuf)> = entries
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter_map(|path| Some((report_time(&path)?, path)))
+        .filter(|(time, _)| *time > seen_ms)
+        .collect();
+    reports.sort();
+    reports.into_iter().map(|(_, path)| path).collect()
+}
+
+/// A new-issue link with the title and a short body filled in. The report
+/// itself is too long for a URL and is attached by the user.
+pub fn issue_url(version: &str, cause: &str) -> String {
+    let title = format!("Crash: {}", cause.chars().take(80).collect::<String>());
+    let body = format!(
+        "ZestBay {} crashed.\n\nWhat I was doing:\n\n\
+         (Please attach the crash report ZestBay saved.)\n",
+        version
+    );
+    format!(
+        "{}?title={}&body={}",
+        ISSUES_URL,
+        percent_encode(&title),
+        percent_encode(&body)
+    )
+}
+
+fn percent_encode(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for byte in text.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                out.push(byte as char)
+            }
+            _ => out.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_log_ring_keeps_latest_lines() {
+        let mut ring = LogRing::new(3);
+        for i in 0..5 {
+            ring.push(format!("line {}", i));
+        }
+        assert_eq!(
+            ring.lines().collect::<Vec<_>>(),
+            ["line 2", "line 3", "line 4"]
+        );
+    }
+
+    #[test]
+    fn test_report_text_and_unseen_reports() {
+        let report = CrashReport {
+            time_ms: 1_700_000_000_123,
+            version: "0.8.3".into(),
+            cause: "SIGSEGV".into(),
+            thread: "pipewire".into(),
+            state: vec![("Plugins".into(), "Gate (LV2)".into())],
+            log: vec!["[INFO] Starting ZestBay".into()],
+            ..Default::default()
+        };
+        let text = report.to_text();
+        assert_eq!(report_cause(&text), Some("SIGSEGV"));
+        assert!(text.contains("Plugins: Gate (LV2)"));
+
+        let dir = std::env::temp_dir().join(format!("zestbay-crash-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join(report.file_name()), &text).unwrap();
+        std::fs::write(dir.join("crash-1000.txt"), "old").unwrap();
+        std::fs::write(dir.join("notes.txt"), "not a report").unwrap();
+
+        let unseen = unseen_reports(&dir, 5000);
+        assert_eq!(unseen, vec![dir.join(report.file_name())]);
+        assert_eq!(unseen_reports(&dir, 0).len(), 2);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_issue_url_is_encoded() {
+        let url = issue_url("0.8.3", "panicked at 'index out of bounds' & more");
+        assert!(url.starts_with(ISSUES_URL));
+        assert!(url.contains("title=Crash%3A%20panicked%20at%20%27index"));
+        assert!(!url.contains(' '));
+        assert!(!url.contains("& more"));
+    }
+}
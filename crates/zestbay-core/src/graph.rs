@@ -0,0 +1,24 @@
+pub mod audit;
+pub mod channels;
+pub mod conversion;
+pub mod demo;
+pub mod heartbeat;
+pub mod identity;
+pub mod order;
+pub mod pairs;
+pub mod state;
+pub mod subscription;
+pub mod transaction;
+pub mod types;
+
+pub use audit::{AuditEntry, AuditFilter, AuditLog, CommandOrigin};
+pub use channels::ChannelOverrides;
+pub use conversion::{ConversionPolicy, ConvertParam, ConvertValue};
+pub use demo::DemoGraph;
+pub use heartbeat::{Heartbeat, StallChange, StallWatch};
+pub use identity::NodeIdentities;
+pub use order::{NodeOrder, NodeSortKey};
+pub use pairs::{PairOverrides, StereoPair};
+pub use state::GraphState;
+pub use subscription::{GraphChange, GraphFilter, GraphObject};
+pub use types::*;
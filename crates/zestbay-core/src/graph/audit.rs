@@ -0,0 +1,482 @@
+//! A log of the commands sent to the PipeWire thread and what asked for
+//! them, so a link that appeared or vanished can be traced back to a click,
+//! a rule or a restore.
+//!
+//! Entries are appended to a file as JSON lines. Past [`MAX_LOG_BYTES`] the
+//! file moves to `<file>.1`, replacing the previous one.
+
+use std::fmt;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use super::state::GraphState;
+use super::types::{LinkOp, ObjectId, PwCommand};
+
+pub const MAX_LOG_BYTES: u64 = 4 * 1024 * 1024;
+
+/// What issued a command.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum CommandOrigin {
+    /// An action in the UI.
+    User,
+    /// The patchbay rule with this id.
+    Rule { id: String },
+    /// Routing to a role policy or the default target, for a stream no
+    /// rule covers.
+    Fallback,
+    /// Restoring saved plugins, links and MIDI mappings.
+    Restore,
+    /// Inserting a node into an existing link.
+    InsertNodeOnLink,
+    /// The remote API or MQTT.
+    Remote,
+    /// Housekeeping such as sidechain upkeep, resyncs and shutdown.
+    System,
+}
+
+impl fmt::Display for CommandOrigin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::User => f.write_str("user"),
+            Self::Rule { id } => write!(f, "rule {}", id),
+            Self::Fallback => f.write_str("fallback"),
+            Self::Restore => f.write_str("restore"),
+            Self::InsertNodeOnLink => f.write_str("insert_node_on_link"),
+            Self::Remote => f.write_str("remote"),
+            Self::System => f.write_str("system"),
+        }
+    }
+}
+
+/// One logged command.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditEntry {
+    /// Milliseconds since the Unix epoch.
+    pub time_ms: u64,
+    pub origin: CommandOrigin,
+    /// The command's name, e.g. `Disconnect`.
+    pub command: String,
+    /// Its arguments, with nodes and ports named as they were when it was
+    /// sent.
+    pub detail: String,
+}
+
+impl AuditEntry {
+    /// An entry for `cmd`. `graph` is used to name the ports, nodes and
+    /// links it refers to; ids are shown for anything it does not know.
+    pub fn new(
+        time_ms: u64,
+        origin: CommandOrigin,
+        cmd: &PwCommand,
+        graph: Option<&GraphState>,
+    ) -> Self {
+        let (command, detail) = describe(cmd, graph);
+        Self {
+            time_ms,
+            origin,
+            command: command.to_string(),
+            detail,
+        }
+    }
+}
+
+/// Which entries a query returns.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct AuditFilter {
+    pub since_ms: Option<u64>,
+    pub until_ms: Option<u64>,
+    /// Text that must appear, ignoring case, in the command, its detail or
+    /// its origin.
+    pub text: String,
+}
+
+impl AuditFilter {
+    pub fn matches(&self, entry: &AuditEntry) -> bool {
+        if self.since_ms.is_some_and(|t| entry.time_ms < t)
+            || self.until_ms.is_some_and(|t| entry.time_ms > t)
+        {
+            return false;
+        }
+        let text = self.text.trim().to_lowercase();
+        text.is_empty()
+            || entry.command.to_lowercase().contains(&text)
+            || entry.detail.to_lowercase().contains(&text)
+            || entry.origin.to_string().to_lowercase().contains(&text)
+    }
+}
+
+/// Appends entries to, and searches, a command log file.
+#[derive(Debug, Clone)]
+pub struct AuditLog {
+    path: PathBuf,
+}
+
+impl AuditLog {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn rotated_path(&self) -> PathBuf {
+        let mut name = self.path.file_name().unwrap_or_default().to_os_string();
+        name.push(".1");
+        self.path.with_file_name(name)
+    }
+
+    pub fn append(&self, entry: &AuditEntry) -> std::io::Result<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        if std::fs::metadata(&self.path).is_ok_and(|m| m.len() >= MAX_LOG_BYTES) {
+            std::fs::rename(&self.path, self.rotated_path())?;
+        }
+        let mut line = serde_json::to_string(entry)?;
+        line.push('\n');
+        std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?
+            .write_all(line.as_bytes())
+    }
+
+    /// The last `limit` entries `filter` matches, oldest first. Lines that
+    /// do not parse, such as one cut short by a crash, are skipped.
+    pub fn query(&self, filter: &AuditFilter, limit: usize) -> Vec<AuditEntry> {
+        let mut entries = Vec::new();
+        for path in [self.rotated_path(), self.path.clone()] {
+            let Ok(file) = std::fs::File::open(&path) else {
+                continue;
+            };
+            entries.extend(
+                BufReader::new(file)
+                    .lines()
+                    .map_while(Result::ok)
+                    .filter_map(|line| serde_json::from_str::<AuditEntry>(&line).ok())
+                    .filter(|e| filter.matches(e)),
+            );
+        }
+        let skip = entries.len().saturating_sub(limit);
+        entries.split_off(skip)
+    }
+
+    /// Delete the log and its rotated predecessor.
+    pub fn clear(&self) {
+        let _ = std::fs::remove_file(&self.path);
+        let _ = std::fs::remove_file(self.rotated_path());
+    }
+}
+
+fn port_label(graph: Option<&GraphState>, id: ObjectId) -> String {
+    let port = graph.and_then(|g| g.get_port(id));
+    let node = port
+        .as_ref()
+        .and_then(|p| graph.and_then(|g| g.get_node(p.node_id)));
+    match (node, port) {
+        (Some(node), Some(port)) => {
+            format!("{}:{} ({})", node.display_name(), port.display_name(), id)
+        }
+        _ => format!("port {}", id),
+    }
+}
+
+fn connection_label(graph: Option<&GraphState>, output: ObjectId, input: ObjectId) -> String {
+    format!(
+        "{} -> {}",
+        port_label(graph, output),
+        port_label(graph, input)
+    )
+}
+
+fn link_label(graph: Option<&GraphState>, link_id: ObjectId) -> String {
+    match graph.and_then(|g| g.get_link(link_id)) {
+        Some(link) => format!(
+            "link {}: {}",
+            link_id,
+            connection_label(graph, link.output_port_id, link.input_port_id)
+        ),
+        None => format!("link {}", link_id),
+    }
+}
+
+fn node_label(graph: Option<&GraphState>, id: ObjectId) -> String {
+    match graph.and_then(|g| g.get_node(id)) {
+        Some(node) => format!("{} ({})", node.display_name(), id),
+        None => format!("node {}", id),
+    }
+}
+
+fn on_off(on: bool) -> &'static str {
+    if on { "on" } else { "off" }
+}
+
+/// The name of `cmd` and a readable account of its arguments.
+pub fn describe(cmd: &PwCommand, graph: Option<&GraphState>) -> (&'static str, String) {
+    match cmd {
+        PwCommand::Connect {
+            output_port_id,
+            input_port_id,
+        } => (
+            "Connect",
+            connection_label(graph, *output_port_id, *input_port_id),
+        ),
+        PwCommand::Disconnect { link_id } => ("Disconnect", link_label(graph, *link_id)),
+        PwCommand::Transaction { label, ops } => {
+            let steps: Vec<String> = ops
+                .iter()
+                .map(|op| match op {
+                    LinkOp::Connect {
+                        output_port_id,
+                        input_port_id,
+                    } => format!(
+                        "connect {}",
+                        connection_label(graph, *output_port_id, *input_port_id)
+                    ),
+                    LinkOp::Disconnect { link_id } => {
+                        format!("disconnect {}", link_label(graph, *link_id))
+                    }
+                })
+                .collect();
+            ("Transaction", format!("{}: {}", label, steps.join("; ")))
+        }
+        PwCommand::AddPlugin {
+            plugin_uri,
+            instance_id,
+            display_name,
+            format,
+            ..
+        } => (
+            "AddPlugin",
+            format!(
+                "\"{}\" ({} {}) as instance {}",
+                display_name, format, plugin_uri, instance_id
+            ),
+        ),
+        PwCommand::ReloadPlugin {
+            plugin_uri,
+            instance_id,
+            display_name,
+            oversampling,
+            ..
+        } => (
+            "ReloadPlugin",
+            format!(
+                "\"{}\" ({}) instance {}, oversampling {}x",
+                display_name, plugin_uri, instance_id, oversampling
+            ),
+        ),
+        PwCommand::RemovePlugin { instance_id } => {
+            ("RemovePlugin", format!("instance {}", instance_id))
+        }
+        PwCommand::SetPluginParameter {
+            instance_id,
+            port_index,
+            value,
+        } => (
+            "SetPluginParameter",
+            format!("instance {} port {} = {}", instance_id, port_index, value),
+        ),
+        PwCommand::SetPluginBypass {
+            instance_id,
+            bypassed,
+        } => (
+            "SetPluginBypass",
+            format!("instance {} bypass {}", instance_id, on_off(*bypassed)),
+        ),
+        PwCommand::SetPluginQuantum {
+            instance_id,
+            quantum,
+        } => (
+            "SetPluginQuantum",
+            match quantum {
+                Some(q) => format!("instance {} quantum {}", instance_id, q),
+                None => format!("instance {} quantum default", instance_id),
+            },
+        ),
+        PwCommand::SetNodeConversion { node_id, params } => (
+            "SetNodeConversion",
+            format!(
+                "{} {}",
+                node_label(graph, *node_id),
+                params
+                    .iter()
+                    .map(|p| p.to_string())
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            ),
+        ),
+        PwCommand::SetNodeMute { node_id, mute } => (
+            "SetNodeMute",
+            format!("{} mute {}", node_label(graph, *node_id), on_off(*mute)),
+        ),
+        PwCommand::RampNodeVolume {
+            node_id,
+            from,
+            to,
+            duration_ms,
+        } => (
+            "RampNodeVolume",
+            format!(
+                "{} volume {:.2} -> {:.2} over {} ms",
+                node_label(graph, *node_id),
+                from,
+                to,
+                duration_ms
+            ),
+        ),
+        PwCommand::SetDefaultSink { node_name } => ("SetDefaultSink", node_name.clone()),
+        PwCommand::OpenPluginUI { instance_id } => {
+            ("OpenPluginUI", format!("instance {}", instance_id))
+        }
+        PwCommand::ClosePluginUI { instance_id } => {
+            ("ClosePluginUI", format!("instance {}", instance_id))
+        }
+        PwCommand::StartMidiLearn { label, .. } => ("StartMidiLearn", label.clone()),
+        PwCommand::CancelMidiLearn => ("CancelMidiLearn", String::new()),
+        PwCommand::AddMidiMapping(mapping) => ("AddMidiMapping", mapping.label.clone()),
+        PwCommand::RemoveMidiMapping(source) => (
+            "RemoveMidiMapping",
+            format!("{} CC {}", source.device_name, source.cc),
+        ),
+        PwCommand::RemoveMidiMappingsForPlugin { instance_id } => (
+            "RemoveMidiMappingsForPlugin",
+            format!("instance {}", instance_id),
+        ),
+        PwCommand::RemoveMidiMappingsForDevice { device_name } => {
+            ("RemoveMidiMappingsForDevice", device_name.clone())
+        }
+        PwCommand::SetMidiLinkFilter { filter_id, options } => {
+            let mut dropped = Vec::new();
+            if options.drop_sysex {
+                dropped.push("SysEx");
+            }
+            if options.drop_active_sensing {
+                dropped.push("active sensing");
+            }
+            if options.drop_clock {
+                dropped.push("clock");
+            }
+            (
+                "SetMidiLinkFilter",
+                format!("filter {} drops {}", filter_id, dropped.join(", ")),
+            )
+        }
+        PwCommand::RemoveMidiLinkFilter { filter_id } => {
+            ("RemoveMidiLinkFilter", format!("filter {}", filter_id))
+        }
+        PwCommand::SetLinkGain { gain_id, gain_db } => (
+            "SetLinkGain",
+            format!("gain {} at {:+.1} dB", gain_id, gain_db),
+        ),
+        PwCommand::RemoveLinkGain { gain_id } => ("RemoveLinkGain", format!("gain {}", gain_id)),
+        PwCommand::StartProbe { probe_id, inputs } => (
+            "StartProbe",
+            format!("probe {} with {} inputs", probe_id, inputs),
+        ),
+        PwCommand::StopProbe { probe_id } => ("StopProbe", format!("probe {}", probe_id)),
+        PwCommand::SaveLv2States => ("SaveLv2States", String::new()),
+        PwCommand::SetTickInterval { interval_ms } => {
+            ("SetTickInterval", format!("{} ms", interval_ms))
+        }
+        PwCommand::RefreshGraph => ("RefreshGraph", String::new()),
+        PwCommand::ResyncGraph => ("ResyncGraph", String::new()),
+        PwCommand::Shutdown => ("Shutdown", String::new()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{Node, NodeType, Port, PortDirection};
+
+    fn temp_log(name: &str) -> AuditLog {
+        let dir =
+            std::env::temp_dir().join(format!("zestbay-audit-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        AuditLog::new(dir.join("commands.log"))
+    }
+
+    fn entry(time_ms: u64, origin: CommandOrigin, link_id: ObjectId) -> AuditEntry {
+        AuditEntry::new(time_ms, origin, &PwCommand::Disconnect { link_id }, None)
+    }
+
+    #[test]
+    fn test_describe_names_ports() {
+        let graph = GraphState::new();
+        graph.insert_node(Node {
+            description: "USB Mic".into(),
+            media_type: None,
+            ..Node::test(10, "alsa_input.usb-mic", NodeType::Source)
+        });
+        graph.insert_port(Port {
+            id: 11,
+            node_id: 10,
+            name: "capture_FL".into(),
+            direction: PortDirection::Output,
+            media_type: None,
+            channel: Some("FL".into()),
+            physical_index: None,
+            port_group: None,
+            port_alias: None,
+        });
+        let cmd = PwCommand::Connect {
+            output_port_id: 11,
+            input_port_id: 40,
+        };
+        let (name, detail) = describe(&cmd, Some(&graph));
+        assert_eq!(name, "Connect");
+        assert_eq!(detail, "USB Mic:FL (11) -> port 40");
+    }
+
+    #[test]
+    fn test_log_appends_and_filters() {
+        let log = temp_log("filter");
+        log.append(&entry(1_000, CommandOrigin::User, 5)).unwrap();
+        log.append(&entry(2_000, CommandOrigin::Rule { id: "mic".into() }, 6))
+            .unwrap();
+        log.append(&entry(3_000, CommandOrigin::Restore, 7))
+            .unwrap();
+
+        let all = log.query(&AuditFilter::default(), 100);
+        assert_eq!(all.len(), 3);
+        assert_eq!(all[1].origin, CommandOrigin::Rule { id: "mic".into() });
+
+        let by_rule = AuditFilter {
+            text: "RULE MIC".into(),
+            ..Default::default()
+        };
+        assert_eq!(log.query(&by_rule, 100).len(), 1);
+
+        let window = AuditFilter {
+            since_ms: Some(1_500),
+            until_ms: Some(3_000),
+            ..Default::default()
+        };
+        let found = log.query(&window, 1);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].time_ms, 3_000);
+
+        log.clear();
+        assert!(log.query(&AuditFilter::default(), 100).is_empty());
+    }
+
+    #[test]
+    fn test_unparsable_lines_are_skipped() {
+        let log = temp_log("garbage");
+        log.append(&entry(1_000, CommandOrigin::System, 5)).unwrap();
+        let mut file = std::fs::OpenOptions::new()
+            .append(true)
+            .open(log.path())
+            .unwrap();
+        file.write_all(b"{\"timeMs\": 2000, \"orig").unwrap();
+        assert_eq!(log.query(&AuditFilter::default(), 10).len(), 1);
+        let _ = std::fs::remove_dir_all(log.path().parent().unwrap());
+    }
+}
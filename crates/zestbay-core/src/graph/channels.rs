@@ -0,0 +1,104 @@
+//! Channel positions set by hand for device ports.
+//!
+//! Some interfaces report their ports as `AUX0`, `AUX1`, ... or `UNK` even
+//! when they are a plain stereo pair, so pairing and channel-matched
+//! auto-connect cannot tell left from right. PipeWire does not let another
+//! client change a port's `audio.channel`, so the position is overridden
+//! locally instead: [`super::GraphState`] applies [`ChannelOverrides`] to
+//! ports as they come in, and everything reading the graph sees the
+//! corrected position.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+/// The SPA audio channel positions, as PipeWire names them.
+pub const CHANNEL_POSITIONS: [&str; 37] = [
+    "MONO", "FL", "FR", "FC", "LFE", "SL", "SR", "FLC", "FRC", "RC", "RL", "RR", "TC", "TFL",
+    "TFC", "TFR", "TRL", "TRC", "TRR", "RLC", "RRC", "FLW", "FRW", "LFE2", "FLH", "FCH", "FRH",
+    "TFLC", "TFRC", "TSL", "TSR", "LLFE", "RLFE", "BC", "BLC", "BRC", "UNK",
+];
+
+/// Whether `position` is a channel position PipeWire knows: one of
+/// [`CHANNEL_POSITIONS`] or `AUX<n>`.
+pub fn is_channel_position(position: &str) -> bool {
+    CHANNEL_POSITIONS.contains(&position)
+        || position
+            .strip_prefix("AUX")
+            .is_some_and(|n| !n.is_empty() && n.len() <= 2 && n.bytes().all(|b| b.is_ascii_digit()))
+}
+
+/// Overridden positions by node name, then port name: `port_channels.json`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ChannelOverrides(pub BTreeMap<String, BTreeMap<String, String>>);
+
+impl ChannelOverrides {
+    pub fn get(&self, node_name: &str, port_name: &str) -> Option<&str> {
+        self.0.get(node_name)?.get(port_name).map(String::as_str)
+    }
+
+    /// Override the position of a port, or with `None` go back to the one
+    /// it reports. Returns true when that changed anything.
+    pub fn set(&mut self, node_name: &str, port_name: &str, position: Option<&str>) -> bool {
+        match position {
+            Some(position) => {
+                let ports = self.0.entry(node_name.to_string()).or_default();
+                ports
+                    .insert(port_name.to_string(), position.to_string())
+                    .as_deref()
+                    != Some(position)
+            }
+            None => {
+                let Some(ports) = self.0.get_mut(node_name) else {
+                    return false;
+                };
+                let removed = ports.remove(port_name).is_some();
+                if ports.is_empty() {
+                    self.0.remove(node_name);
+                }
+                removed
+            }
+        }
+    }
+
+    /// Drop every override of a node.
+    pub fn clear(&mut self, node_name: &str) -> bool {
+        self.0.remove(node_name).is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_channel_positions_include_aux_channels() {
+        assert!(is_channel_position("FL"));
+        assert!(is_channel_position("AUX0"));
+        assert!(is_channel_position("AUX63"));
+        assert!(!is_channel_position("AUX"));
+        assert!(!is_channel_position("AUX100"));
+        assert!(!is_channel_position("fl"));
+        assert!(!is_channel_position("LEFT"));
+    }
+
+    #[test]
+    fn test_overrides_are_set_and_cleared_per_port() {
+        let mut overrides = ChannelOverrides::default();
+        assert!(overrides.set("alsa_input.usb", "capture_AUX0", Some("FL")));
+        assert!(!overrides.set("alsa_input.usb", "capture_AUX0", Some("FL")));
+        assert!(overrides.set("alsa_input.usb", "capture_AUX1", Some("FR")));
+        assert_eq!(overrides.get("alsa_input.usb", "capture_AUX1"), Some("FR"));
+        assert_eq!(overrides.get("alsa_input.pci", "capture_AUX1"), None);
+
+        assert!(overrides.set("alsa_input.usb", "capture_AUX0", None));
+        assert!(!overrides.set("alsa_input.usb", "capture_AUX0", None));
+        assert!(overrides.clear("alsa_input.usb"));
+        assert_eq!(overrides, ChannelOverrides::default());
+
+        let json = r#"{"alsa_input.usb":{"capture_AUX0":"FL"}}"#;
+        let parsed: ChannelOverrides = serde_json::from_str(json).unwrap();
+        assert_eq!(parsed.get("alsa_input.usb", "capture_AUX0"), Some("FL"));
+    }
+}
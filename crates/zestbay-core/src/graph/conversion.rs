@@ -0,0 +1,177 @@
+//! Per-node sample rate and channel conversion settings.
+//!
+//! PipeWire converts audio in each node's adapter using the graph-wide
+//! defaults. A [`ConversionPolicy`] overrides them for one node, such as a
+//! 44.1 kHz-only turntable that should always be resampled at the highest
+//! quality, by setting audioconvert options through the node's `Props`.
+//! Nodes without a policy are never touched.
+
+use serde::{Deserialize, Serialize};
+
+/// One audioconvert option, as set through `Props:params`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConvertParam {
+    pub key: &'static str,
+    pub value: ConvertValue,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConvertValue {
+    Int(i32),
+    Bool(bool),
+}
+
+impl std::fmt::Display for ConvertParam {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.value {
+            ConvertValue::Int(v) => write!(f, "{}={}", self.key, v),
+            ConvertValue::Bool(v) => write!(f, "{}={}", self.key, v),
+        }
+    }
+}
+
+const RESAMPLE_QUALITY: &str = "resample.quality";
+const RESAMPLE_DISABLE: &str = "resample.disable";
+const UPMIX: &str = "channelmix.upmix";
+const NORMALIZE: &str = "channelmix.normalize";
+
+/// Conversion settings for one node. Fields left `None` keep the graph
+/// default.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConversionPolicy {
+    /// Resampler quality, 0 (fastest) to [`MAX_QUALITY`](Self::MAX_QUALITY).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resample_quality: Option<u8>,
+    /// Pass audio through at its own rate instead of resampling.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub disable_resample: Option<bool>,
+    /// Spread fewer channels over more, e.g. stereo to surround.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub upmix: Option<bool>,
+    /// Keep the level from rising when channels are mixed down.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub normalize: Option<bool>,
+}
+
+impl ConversionPolicy {
+    /// PipeWire's best resampler quality.
+    pub const MAX_QUALITY: u8 = 14;
+
+    /// PipeWire's own default quality.
+    pub const DEFAULT_QUALITY: u8 = 4;
+
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// The options to set on the node.
+    pub fn params(&self) -> Vec<ConvertParam> {
+        let mut params = Vec::new();
+        if let Some(quality) = self.resample_quality {
+            params.push(ConvertParam {
+                key: RESAMPLE_QUALITY,
+                value: ConvertValue::Int(quality.min(Self::MAX_QUALITY) as i32),
+            });
+        }
+        let flags = [
+            (RESAMPLE_DISABLE, self.disable_resample),
+            (UPMIX, self.upmix),
+            (NORMALIZE, self.normalize),
+        ];
+        for (key, value) in flags {
+            if let Some(value) = value {
+                params.push(ConvertParam {
+                    key,
+                    value: ConvertValue::Bool(value),
+                });
+            }
+        }
+        params
+    }
+
+    /// The options to set when this policy replaces `old`: the new values,
+    /// plus PipeWire's defaults for options `old` set and this one leaves
+    /// alone.
+    pub fn params_replacing(&self, old: &ConversionPolicy) -> Vec<ConvertParam> {
+        let mut params = self.params();
+        let defaults = [
+            (
+                old.resample_quality.is_some() && self.resample_quality.is_none(),
+                RESAMPLE_QUALITY,
+                ConvertValue::Int(Self::DEFAULT_QUALITY as i32),
+            ),
+            (
+                old.disable_resample.is_some() && self.disable_resample.is_none(),
+                RESAMPLE_DISABLE,
+                ConvertValue::Bool(false),
+            ),
+            (
+                old.upmix.is_some() && self.upmix.is_none(),
+                UPMIX,
+                ConvertValue::Bool(true),
+            ),
+            (
+                old.normalize.is_some() && self.normalize.is_none(),
+                NORMALIZE,
+                ConvertValue::Bool(false),
+            ),
+        ];
+        for (cleared, key, value) in defaults {
+            if cleared {
+                params.push(ConvertParam { key, value });
+            }
+        }
+        params
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_params_only_for_set_fields() {
+        assert!(ConversionPolicy::default().params().is_empty());
+        let policy = ConversionPolicy {
+            resample_quality: Some(99),
+            upmix: Some(false),
+            ..Default::default()
+        };
+        let params: Vec<String> = policy.params().iter().map(|p| p.to_string()).collect();
+        assert_eq!(params, ["resample.quality=14", "channelmix.upmix=false"]);
+    }
+
+    #[test]
+    fn test_replacing_restores_cleared_fields() {
+        let old = ConversionPolicy {
+            resample_quality: Some(10),
+            normalize: Some(true),
+            ..Default::default()
+        };
+        let new = ConversionPolicy {
+            normalize: Some(true),
+            ..Default::default()
+        };
+        let params: Vec<String> = new
+            .params_replacing(&old)
+            .iter()
+            .map(|p| p.to_string())
+            .collect();
+        assert_eq!(params, ["channelmix.normalize=true", "resample.quality=4"]);
+    }
+
+    #[test]
+    fn test_json_leaves_out_defaults() {
+        let policy = ConversionPolicy {
+            resample_quality: Some(12),
+            ..Default::default()
+        };
+        let json = serde_json::to_string(&policy).unwrap();
+        assert_eq!(json, r#"{"resampleQuality":12}"#);
+        let back: ConversionPolicy = serde_json::from_str(&json).unwrap();
+        assert_eq!(back, policy);
+        let empty: ConversionPolicy = serde_json::from_str("{}").unwrap();
+        assert!(empty.is_empty());
+    }
+}
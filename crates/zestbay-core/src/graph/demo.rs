@@ -0,0 +1,524 @@
+//! A made-up graph for running the UI without a PipeWire daemon, e.g. to
+//! work on the UI or to take screenshots that come out the same every time.
+//!
+//! [`DemoGraph`] fills a [`GraphState`] with a fixed set of devices, streams
+//! and links, and carries out [`PwCommand`]s on it the way the PipeWire
+//! thread would, returning the events that thread would send.
+
+use std::collections::HashMap;
+
+use super::state::GraphState;
+use super::types::{
+    Link, LinkOp, MediaType, Node, NodeType, ObjectId, PluginEvent, Port, PortDirection, PwCommand,
+    PwEvent,
+};
+
+/// Sample rate every demo node reports.
+pub const DEMO_RATE: u32 = 48_000;
+
+/// Ids below this are left free, as PipeWire uses them for its core objects.
+const FIRST_ID: ObjectId = 30;
+
+struct DemoNode {
+    name: &'static str,
+    description: &'static str,
+    node_type: NodeType,
+    media_type: MediaType,
+    /// Port name prefix and channels, e.g. `("playback", &["FL", "FR"])`.
+    ports: (&'static str, &'static [&'static str]),
+    application: Option<&'static str>,
+    media_role: Option<&'static str>,
+}
+
+/// The default sink.
+const SPEAKERS: &str = "alsa_output.pci-0000_00_1f.3.analog-stereo";
+const MICROPHONE: &str = "alsa_input.usb-Microphone-00.mono-fallback";
+
+const NODES: &[DemoNode] = &[
+    DemoNode {
+        name: SPEAKERS,
+        description: "Built-in Audio Analog Stereo",
+        node_type: NodeType::Sink,
+        media_type: MediaType::Audio,
+        ports: ("playback", &["FL", "FR"]),
+        application: None,
+        media_role: None,
+    },
+    DemoNode {
+        name: "alsa_output.usb-Headset-00.analog-stereo",
+        description: "USB Headset",
+        node_type: NodeType::Sink,
+        media_type: MediaType::Audio,
+        ports: ("playback", &["FL", "FR"]),
+        application: None,
+        media_role: None,
+    },
+    DemoNode {
+        name: MICROPHONE,
+        description: "USB Microphone",
+        node_type: NodeType::Source,
+        media_type: MediaType::Audio,
+        ports: ("capture", &["MONO"]),
+        application: None,
+        media_role: None,
+    },
+    DemoNode {
+        name: "Midi-Bridge.MIDI-Keyboard",
+        description: "MIDI Keyboard",
+        node_type: NodeType::Source,
+        media_type: MediaType::Midi,
+        ports: ("capture", &["0"]),
+        application: None,
+        media_role: None,
+    },
+    DemoNode {
+        name: "Firefox",
+        description: "",
+        node_type: NodeType::StreamOutput,
+        media_type: MediaType::Audio,
+        ports: ("output", &["FL", "FR"]),
+        application: Some("Firefox"),
+        media_role: None,
+    },
+    DemoNode {
+        name: "mpv",
+        description: "",
+        node_type: NodeType::StreamOutput,
+        media_type: MediaType::Audio,
+        ports: ("output", &["FL", "FR"]),
+        application: Some("mpv"),
+        media_role: Some("Music"),
+    },
+    DemoNode {
+        name: "Voice Chat",
+        description: "",
+        node_type: NodeType::StreamInput,
+        media_type: MediaType::Audio,
+        ports: ("input", &["MONO"]),
+        application: Some("Voice Chat"),
+        media_role: Some("Communication"),
+    },
+];
+
+/// Links present at startup, as (output node, output port, input node,
+/// input port) names.
+const LINKS: &[(&str, &str, &str, &str)] = &[
+    ("Firefox", "output_FL", SPEAKERS, "playback_FL"),
+    ("Firefox", "output_FR", SPEAKERS, "playback_FR"),
+    ("mpv", "output_FL", SPEAKERS, "playback_FL"),
+    ("mpv", "output_FR", SPEAKERS, "playback_FR"),
+    (MICROPHONE, "capture_MONO", "Voice Chat", "input_MONO"),
+];
+
+/// Stands in for the PipeWire daemon. Ids are handed out in order, so the
+/// same commands always produce the same graph.
+#[derive(Debug)]
+pub struct DemoGraph {
+    next_id: ObjectId,
+    /// Node of each hosted plugin, by instance id.
+    plugins: HashMap<u64, ObjectId>,
+}
+
+impl Default for DemoGraph {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DemoGraph {
+    pub fn new() -> Self {
+        Self {
+            next_id: FIRST_ID,
+            plugins: HashMap::new(),
+        }
+    }
+
+    fn alloc_id(&mut self) -> ObjectId {
+        let id = self.next_id;
+        self.next_id += 1;
+        id
+    }
+
+    /// Add the demo devices, streams and links to `graph`, returning the
+    /// events announcing them.
+    pub fn populate(&mut self, graph: &GraphState) -> Vec<PwEvent> {
+        let mut events = Vec::new();
+        let mut ids = HashMap::new();
+        for demo in NODES {
+            let id = self.alloc_id();
+            ids.insert(demo.name, id);
+            let node = Node {
+                id,
+                name: demo.name.to_string(),
+                description: demo.description.to_string(),
+                media_type: Some(demo.media_type),
+                node_type: Some(demo.node_type),
+                is_virtual: false,
+                is_jack: false,
+                is_bridge: false,
+                media_role: demo.media_role.map(str::to_string),
+                application_id: demo.application.map(|_| format!("pid:{}", 1000 + id)),
+                application_name: demo.application.map(str::to_string),
+                application_binary: demo.application.map(|a| a.to_lowercase()),
+                device_serial: None,
+                ready: true,
+            };
+            graph.insert_node(node.clone());
+            events.push(PwEvent::NodeChanged(node));
+
+            let (prefix, channels) = demo.ports;
+            let direction = if demo.node_type.has_outputs() {
+                PortDirection::Output
+            } else {
+                PortDirection::Input
+            };
+            for channel in channels {
+                let port = self.port(id, format!("{}_{}", prefix, channel), direction);
+                let port = Port {
+                    media_type: Some(demo.media_type),
+                    channel: (demo.media_type == MediaType::Audio).then(|| channel.to_string()),
+                    ..port
+                };
+                graph.insert_port(port.clone());
+                events.push(PwEvent::PortChanged(port));
+            }
+            if demo.media_type == MediaType::Audio {
+                let channels = channels.len() as u32;
+                graph.update_node_format(id, |f| {
+                    f.rate = Some(DEMO_RATE);
+                    f.channels = Some(channels);
+                    f.sample_format = Some("F32".to_string());
+                });
+            }
+        }
+
+        for (out_node, out_port, in_node, in_port) in LINKS {
+            let find = |node: &str, port: &str| {
+                graph
+                    .get_ports_for_node(ids[node])
+                    .into_iter()
+                    .find(|p| p.name == port)
+                    .map(|p| p.id)
+            };
+            if let (Some(output), Some(input)) = (find(out_node, out_port), find(in_node, in_port))
+            {
+                events.extend(self.connect(graph, output, input));
+            }
+        }
+
+        graph.set_default_sink(Some(SPEAKERS.to_string()));
+        events
+    }
+
+    fn port(&mut self, node_id: ObjectId, name: String, direction: PortDirection) -> Port {
+        Port {
+            id: self.alloc_id(),
+            node_id,
+            name,
+            direction,
+            media_type: Some(MediaType::Audio),
+            channel: None,
+            physical_index: None,
+            port_group: None,
+            port_alias: None,
+        }
+    }
+
+    fn connect(&mut self, graph: &GraphState, output: ObjectId, input: ObjectId) -> Vec<PwEvent> {
+        let (Some(out_port), Some(in_port)) = (graph.get_port(output), graph.get_port(input))
+        else {
+            log::warn!("Demo: no port {} or {}", output, input);
+            return Vec::new();
+        };
+        if out_port.direction != PortDirection::Output
+            || in_port.direction != PortDirection::Input
+            || graph.find_link(output, input).is_some()
+        {
+            return Vec::new();
+        }
+        let link = Link {
+            id: self.alloc_id(),
+            output_node_id: out_port.node_id,
+            output_port_id: output,
+            input_node_id: in_port.node_id,
+            input_port_id: input,
+            active: true,
+        };
+        graph.insert_link(link.clone());
+        vec![PwEvent::LinkChanged(link)]
+    }
+
+    fn disconnect(graph: &GraphState, link_id: ObjectId) -> Vec<PwEvent> {
+        match graph.remove_link(link_id) {
+            Some(_) => vec![PwEvent::LinkRemoved(link_id)],
+            None => Vec::new(),
+        }
+    }
+
+    /// A plugin node with stereo audio ports, named like a hosted plugin's.
+    fn add_plugin(
+        &mut self,
+        graph: &GraphState,
+        instance_id: u64,
+        display_name: String,
+    ) -> Vec<PwEvent> {
+        let id = self.alloc_id();
+        let node = Node {
+            id,
+            name: display_name.clone(),
+            description: display_name.clone(),
+            media_type: Some(MediaType::Audio),
+            node_type: Some(NodeType::Plugin),
+            is_virtual: false,
+            is_jack: false,
+            is_bridge: false,
+            media_role: None,
+            application_id: None,
+            application_name: Some("ZestBay".to_string()),
+            application_binary: None,
+            device_serial: None,
+            ready: true,
+        };
+        self.plugins.insert(instance_id, id);
+        graph.insert_node(node.clone());
+        let mut events = vec![
+            PwEvent::Plugin(PluginEvent::PluginAdded {
+                instance_id,
+                pw_node_id: id,
+                display_name,
+            }),
+            PwEvent::NodeChanged(node),
+        ];
+        for (prefix, direction) in [
+            ("input", PortDirection::Input),
+            ("output", PortDirection::Output),
+        ] {
+            for channel in 0..2 {
+                let port = self.port(id, format!("{}_{}", prefix, channel), direction);
+                graph.insert_port(port.clone());
+                events.push(PwEvent::PortChanged(port));
+            }
+        }
+        graph.update_node_format(id, |f| {
+            f.rate = Some(DEMO_RATE);
+            f.channels = Some(2);
+            f.sample_format = Some("F32".to_string());
+        });
+        events
+    }
+
+    fn remove_plugin(&mut self, graph: &GraphState, instance_id: u64) -> Vec<PwEvent> {
+        let Some(node_id) = self.plugins.remove(&instance_id) else {
+            return Vec::new();
+        };
+        let ports = graph.get_ports_for_node(node_id);
+        let mut events: Vec<PwEvent> = graph
+            .cleanup_node(node_id)
+            .into_iter()
+            .map(PwEvent::LinkRemoved)
+            .collect();
+        events.extend(ports.into_iter().map(|p| PwEvent::PortRemoved {
+            port_id: p.id,
+            node_id,
+        }));
+        graph.remove_node(node_id);
+        events.push(PwEvent::NodeRemoved(node_id));
+        events.push(PwEvent::Plugin(PluginEvent::PluginRemoved { instance_id }));
+        events
+    }
+
+    /// Carry out `cmd` on `graph`, returning the events it causes. Commands
+    /// with nothing to show, such as parameter changes, are accepted and
+    /// dropped; [`PwCommand::Shutdown`] is left to the caller.
+    pub fn handle(&mut self, graph: &GraphState, cmd: PwCommand) -> Vec<PwEvent> {
+        match cmd {
+            PwCommand::Connect {
+                output_port_id,
+                input_port_id,
+            } => self.connect(graph, output_port_id, input_port_id),
+            PwCommand::Disconnect { link_id } => Self::disconnect(graph, link_id),
+            PwCommand::Transaction { ops, .. } => ops
+                .into_iter()
+                .flat_map(|op| match op {
+                    LinkOp::Connect {
+                        output_port_id,
+                        input_port_id,
+                    } => self.connect(graph, output_port_id, input_port_id),
+                    LinkOp::Disconnect { link_id } => Self::disconnect(graph, link_id),
+                })
+                .collect(),
+            PwCommand::AddPlugin {
+                instance_id,
+                display_name,
+                ..
+            } => self.add_plugin(graph, instance_id, display_name),
+            PwCommand::ReloadPlugin {
+                instance_id,
+                display_name,
+                ..
+            } => {
+                let mut events = self.remove_plugin(graph, instance_id);
+                events.extend(self.add_plugin(graph, instance_id, display_name));
+                events
+            }
+            PwCommand::RemovePlugin { instance_id } => self.remove_plugin(graph, instance_id),
+            PwCommand::OpenPluginUI { instance_id } => {
+                vec![PwEvent::Plugin(PluginEvent::PluginError {
+                    instance_id: Some(instance_id),
+                    message: "Plugin UIs are not available in demo mode".to_string(),
+                    fatal: false,
+                })]
+            }
+            PwCommand::StartMidiLearn {
+                instance_id,
+                port_index,
+                ..
+            } => vec![PwEvent::Plugin(PluginEvent::MidiLearnStarted {
+                instance_id,
+                port_index,
+            })],
+            PwCommand::CancelMidiLearn => {
+                vec![PwEvent::Plugin(PluginEvent::MidiLearnCancelled)]
+            }
+            PwCommand::AddMidiMapping(mapping) => {
+                vec![PwEvent::Plugin(PluginEvent::MidiMappingAdded(mapping))]
+            }
+            PwCommand::RemoveMidiMapping(source) => {
+                vec![PwEvent::Plugin(PluginEvent::MidiMappingRemoved(source))]
+            }
+            PwCommand::SetDefaultSink { node_name } => {
+                graph.set_default_sink(Some(node_name));
+                Vec::new()
+            }
+            PwCommand::SetPluginParameter { .. }
+            | PwCommand::SetPluginBypass { .. }
+            | PwCommand::SetPluginQuantum { .. }
+            | PwCommand::SetNodeConversion { .. }
+            | PwCommand::SetNodeMute { .. }
+            | PwCommand::RampNodeVolume { .. }
+            | PwCommand::ClosePluginUI { .. }
+            | PwCommand::RemoveMidiMappingsForPlugin { .. }
+            | PwCommand::RemoveMidiMappingsForDevice { .. }
+            | PwCommand::SetMidiLinkFilter { .. }
+            | PwCommand::RemoveMidiLinkFilter { .. }
+            | PwCommand::SetLinkGain { .. }
+            | PwCommand::RemoveLinkGain { .. }
+            | PwCommand::StartProbe { .. }
+            | PwCommand::StopProbe { .. }
+            | PwCommand::SaveLv2States
+            | PwCommand::SetTickInterval { .. }
+            | PwCommand::RefreshGraph
+            | PwCommand::ResyncGraph
+            | PwCommand::Shutdown => Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn port_id(graph: &GraphState, node: &str, port: &str) -> ObjectId {
+        let node = graph
+            .get_all_nodes()
+            .into_iter()
+            .find(|n| n.name == node)
+            .unwrap();
+        graph
+            .get_ports_for_node(node.id)
+            .into_iter()
+            .find(|p| p.name == port)
+            .unwrap()
+            .id
+    }
+
+    #[test]
+    fn test_populate_is_reproducible() {
+        let first = GraphState::new();
+        let second = GraphState::new();
+        DemoGraph::new().populate(&first);
+        DemoGraph::new().populate(&second);
+
+        assert_eq!(first.get_all_nodes().len(), NODES.len());
+        assert_eq!(first.get_all_links().len(), LINKS.len());
+        assert_eq!(
+            port_id(&first, "mpv", "output_FR"),
+            port_id(&second, "mpv", "output_FR")
+        );
+        assert_eq!(first.default_sink().as_deref(), Some(SPEAKERS));
+    }
+
+    #[test]
+    fn test_commands_change_the_graph() {
+        let graph = GraphState::new();
+        let mut demo = DemoGraph::new();
+        demo.populate(&graph);
+        let firefox = port_id(&graph, "Firefox", "output_FL");
+        let headset = port_id(
+            &graph,
+            "alsa_output.usb-Headset-00.analog-stereo",
+            "playback_FL",
+        );
+
+        let cmd = PwCommand::Connect {
+            output_port_id: firefox,
+            input_port_id: headset,
+        };
+        let events = demo.handle(&graph, cmd.clone());
+        let link = graph.find_link(firefox, headset).unwrap();
+        assert!(matches!(events[..], [PwEvent::LinkChanged(ref l)] if l.id == link.id));
+        assert!(demo.handle(&graph, cmd).is_empty());
+
+        let backwards = PwCommand::Connect {
+            output_port_id: headset,
+            input_port_id: firefox,
+        };
+        assert!(demo.handle(&graph, backwards).is_empty());
+
+        demo.handle(&graph, PwCommand::Disconnect { link_id: link.id });
+        assert!(graph.find_link(firefox, headset).is_none());
+    }
+
+    #[test]
+    fn test_plugins_come_and_go_with_their_links() {
+        let graph = GraphState::new();
+        let mut demo = DemoGraph::new();
+        demo.populate(&graph);
+
+        let events = demo.handle(
+            &graph,
+            PwCommand::AddPlugin {
+                plugin_uri: "urn:demo:eq".into(),
+                instance_id: 7,
+                display_name: "EQ".into(),
+                format: "LV2".into(),
+                lv2_state: Vec::new(),
+                oversampling: 1,
+            },
+        );
+        let Some(PwEvent::Plugin(PluginEvent::PluginAdded { pw_node_id, .. })) = events.first()
+        else {
+            panic!("expected PluginAdded first, got {:?}", events);
+        };
+        let node_id = *pw_node_id;
+        assert_eq!(graph.get_ports_for_node(node_id).len(), 4);
+        demo.handle(
+            &graph,
+            PwCommand::Connect {
+                output_port_id: port_id(&graph, "EQ", "output_0"),
+                input_port_id: port_id(&graph, SPEAKERS, "playback_FL"),
+            },
+        );
+
+        let links_before = graph.get_all_links().len();
+        let events = demo.handle(&graph, PwCommand::RemovePlugin { instance_id: 7 });
+        assert!(graph.get_node(node_id).is_none());
+        assert_eq!(graph.get_all_links().len(), links_before - 1);
+        assert!(matches!(
+            events.last(),
+            Some(PwEvent::Plugin(PluginEvent::PluginRemoved {
+                instance_id: 7
+            }))
+        ));
+    }
+}
@@ -0,0 +1,129 @@
+//! Liveness of the PipeWire thread as seen from the UI thread.
+//!
+//! The PipeWire thread bumps a [`Heartbeat`] on every tick of its main loop;
+//! the UI thread feeds the count to a [`StallWatch`], which notices when it
+//! stops moving, e.g. because the thread is blocked in a plugin or a
+//! PipeWire call.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+/// A counter shared between the PipeWire thread and the UI thread.
+#[derive(Debug, Clone, Default)]
+pub struct Heartbeat(Arc<AtomicU64>);
+
+impl Heartbeat {
+    pub fn beat(&self) {
+        self.0.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn count(&self) -> u64 {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// A change in whether the watched thread is responding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StallChange {
+    /// No beat for longer than the timeout.
+    Stalled,
+    /// Beats resumed after a stall.
+    Recovered,
+}
+
+/// Tracks a [`Heartbeat`] count and reports when it stops and restarts.
+#[derive(Debug, Clone)]
+pub struct StallWatch {
+    timeout: Duration,
+    last_count: u64,
+    last_change: Instant,
+    stalled: bool,
+}
+
+impl StallWatch {
+    pub fn new(timeout: Duration, now: Instant) -> Self {
+        Self {
+            timeout,
+            last_count: 0,
+            last_change: now,
+            stalled: false,
+        }
+    }
+
+    /// Feed the current count. Returns a change when the thread has just
+    /// stalled or just recovered.
+    pub fn check(&mut self, count: u64, now: Instant) -> Option<StallChange> {
+        if count != self.last_count {
+            self.last_count = count;
+            self.last_change = now;
+            if self.stalled {
+                self.stalled = false;
+                return Some(StallChange::Recovered);
+            }
+            return None;
+        }
+        if !self.stalled && now.duration_since(self.last_change) > self.timeout {
+            self.stalled = true;
+            return Some(StallChange::Stalled);
+        }
+        None
+    }
+
+    pub fn is_stalled(&self) -> bool {
+        self.stalled
+    }
+
+    /// How long since the last beat.
+    pub fn silent_for(&self, now: Instant) -> Duration {
+        now.duration_since(self.last_change)
+    }
+
+    /// Start over for a new thread, whose heartbeat counts from zero.
+    pub fn reset(&mut self, now: Instant) {
+        *self = Self::new(self.timeout, now);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stall_is_reported_once_and_recovers() {
+        let start = Instant::now();
+        let beat = Heartbeat::default();
+        let mut watch = StallWatch::new(Duration::from_secs(5), start);
+
+        beat.beat();
+        assert_eq!(watch.check(beat.count(), start), None);
+        let later = start + Duration::from_secs(3);
+        assert_eq!(watch.check(beat.count(), later), None);
+
+        let stuck = start + Duration::from_secs(6);
+        assert_eq!(watch.check(beat.count(), stuck), Some(StallChange::Stalled));
+        assert!(watch.is_stalled());
+        assert_eq!(watch.silent_for(stuck), Duration::from_secs(6));
+        let still = start + Duration::from_secs(20);
+        assert_eq!(watch.check(beat.count(), still), None);
+
+        beat.beat();
+        assert_eq!(
+            watch.check(beat.count(), still),
+            Some(StallChange::Recovered)
+        );
+        assert!(!watch.is_stalled());
+    }
+
+    #[test]
+    fn test_reset_for_a_new_thread() {
+        let start = Instant::now();
+        let mut watch = StallWatch::new(Duration::from_secs(5), start);
+        let stuck = start + Duration::from_secs(10);
+        assert_eq!(watch.check(0, stuck), Some(StallChange::Stalled));
+
+        watch.reset(stuck);
+        assert!(!watch.is_stalled());
+        assert_eq!(watch.check(0, stuck + Duration::from_secs(1)), None);
+    }
+}
@@ -0,0 +1,322 @@
+//! Persistent identities for devices and application streams.
+//!
+//! PipeWire object ids change on every restart, and display names change when
+//! a device is renamed or a player switches track, so neither can key saved
+//! layout positions or hidden and pinned lists. [`NodeIdentities`]
+//! fingerprints each node from the properties that do survive a restart and
+//! hands out one UUID per fingerprint, kept across sessions.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use super::types::*;
+
+/// A UUID assigned to one fingerprint.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Identity {
+    pub id: String,
+    pub fingerprint: String,
+    /// Display name the node had when last seen, for entries whose node is
+    /// not in the graph right now.
+    pub name: String,
+}
+
+/// Registry of every identity handed out so far, saved as `node_ids.json`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NodeIdentities {
+    identities: Vec<Identity>,
+    /// Identities of the nodes in the graph right now, including the
+    /// session-only ones below.
+    #[serde(skip)]
+    assigned: HashMap<ObjectId, String>,
+    /// Identities for extra streams sharing a fingerprint, such as a second
+    /// browser tab. Which tab comes first is down to arrival order, so these
+    /// last only as long as their node and are never saved.
+    #[serde(skip)]
+    session: Vec<Identity>,
+    #[serde(skip)]
+    dirty: bool,
+}
+
+impl NodeIdentities {
+    /// Assign identities to `nodes`, creating new ones for fingerprints not
+    /// seen before, and return them keyed by node id. Plugins and nodes
+    /// without a usable fingerprint are left out.
+    ///
+    /// A node keeps its identity for as long as it exists. Nodes sharing a
+    /// fingerprint, such as two tabs of the same browser, all want the same
+    /// saved identity: the first to arrive takes it and the rest get
+    /// session-only ones, since nothing about them survives a restart.
+    pub fn identify(&mut self, nodes: &[Node]) -> HashMap<ObjectId, String> {
+        // Registries written before session identities numbered the extra
+        // streams `#1`, `#2` by arrival order; those never match again.
+        let before = self.identities.len();
+        self.identities.retain(|i| !is_ordinal(&i.fingerprint));
+        if self.identities.len() != before {
+            self.dirty = true;
+        }
+
+        let mut by_fingerprint: HashMap<String, Vec<&Node>> = HashMap::new();
+        for node in nodes {
+            if let Some(fp) = fingerprint(node) {
+                by_fingerprint.entry(fp).or_default().push(node);
+            }
+        }
+
+        let previous = std::mem::take(&mut self.assigned);
+        let mut session = std::mem::take(&mut self.session);
+        for (fp, mut group) in by_fingerprint {
+            group.sort_by_key(|n| n.id);
+            let saved = self.claim(fp.clone(), None);
+            let mut saved_taken = group.iter().any(|n| previous.get(&n.id) == Some(&saved));
+            for node in group {
+                let name = node.display_name();
+                let kept = previous
+                    .get(&node.id)
+                    .filter(|id| **id == saved || session.iter().any(|s| s.id == **id));
+                let id = match kept {
+                    Some(id) if *id == saved => self.claim(fp.clone(), Some(name)),
+                    Some(id) => id.clone(),
+                    None if !saved_taken => {
+                        saved_taken = true;
+                        self.claim(fp.clone(), Some(name))
+                    }
+                    None => {
+                        let id = uuid::Uuid::new_v4().to_string();
+                        session.push(Identity {
+                            id: id.clone(),
+                            fingerprint: fp.clone(),
+                            name: String::new(),
+                        });
+                        id
+                    }
+                };
+                if let Some(s) = session.iter_mut().find(|s| s.id == id) {
+                    s.name = name.to_string();
+                }
+                self.assigned.insert(node.id, id);
+            }
+        }
+        session.retain(|s| self.assigned.values().any(|id| *id == s.id));
+        self.session = session;
+        self.assigned.clone()
+    }
+
+    /// The saved identity each of `nodes` shares with every other node of
+    /// the same fingerprint, for keying saved rules and links: those should
+    /// follow an application as a whole rather than whichever of its streams
+    /// happened to arrive first. Only identities already handed out by
+    /// [`identify`](Self::identify) are returned.
+    pub fn saved_ids(&self, nodes: &[Node]) -> HashMap<ObjectId, String> {
+        nodes
+            .iter()
+            .filter_map(|node| {
+                let fp = fingerprint(node)?;
+                let identity = self.identities.iter().find(|i| i.fingerprint == fp)?;
+                Some((node.id, identity.id.clone()))
+            })
+            .collect()
+    }
+
+    /// The identity for `fingerprint`, created if needed. `name` updates the
+    /// last-seen display name.
+    fn claim(&mut self, fingerprint: String, name: Option<&str>) -> String {
+        if let Some(identity) = self
+            .identities
+            .iter_mut()
+            .find(|i| i.fingerprint == fingerprint)
+        {
+            if let Some(name) = name.filter(|n| identity.name != *n) {
+                identity.name = name.to_string();
+                self.dirty = true;
+            }
+            return identity.id.clone();
+        }
+
+        let id = uuid::Uuid::new_v4().to_string();
+        self.identities.push(Identity {
+            id: id.clone(),
+            fingerprint,
+            name: name.unwrap_or_default().to_string(),
+        });
+        self.dirty = true;
+        id
+    }
+
+    pub fn get(&self, id: &str) -> Option<&Identity> {
+        self.identities
+            .iter()
+            .chain(&self.session)
+            .find(|i| i.id == id)
+    }
+
+    /// Whether anything changed since the last call, so the registry needs
+    /// saving.
+    pub fn take_dirty(&mut self) -> bool {
+        std::mem::take(&mut self.dirty)
+    }
+}
+
+/// Whether `fingerprint` carries a `#N` arrival-order suffix.
+fn is_ordinal(fingerprint: &str) -> bool {
+    fingerprint
+        .rsplit_once('#')
+        .is_some_and(|(_, n)| !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()))
+}
+
+/// What identifies `node` across restarts, or `None` for plugins (which carry
+/// their own instance ids) and nodes with nothing stable to go on.
+///
+/// Streams are keyed by the application binary, falling back to its name,
+/// plus direction and media role. Devices are keyed by serial number and
+/// profile when PipeWire reports one, so they survive moving to another USB
+/// port, and by `node.name` otherwise.
+pub fn fingerprint(node: &Node) -> Option<String> {
+    let node_type = node.node_type?;
+    let kind = match node_type {
+        NodeType::Plugin => return None,
+        NodeType::Sink => "sink",
+        NodeType::Source => "source",
+        NodeType::StreamOutput => "out",
+        NodeType::StreamInput => "in",
+        NodeType::Duplex => "duplex",
+    };
+
+    if matches!(node_type, NodeType::StreamOutput | NodeType::StreamInput) {
+        let app = node
+            .application_binary
+            .as_deref()
+            .or(node.application_name.as_deref())
+            .filter(|a| !a.is_empty())
+            .unwrap_or(&node.name);
+        if app.is_empty() {
+            return None;
+        }
+        let role = node.media_role.as_deref().unwrap_or_default();
+        return Some(format!("stream:{}:{}:{}", kind, app, role));
+    }
+
+    if let Some(serial) = node.device_serial.as_deref() {
+        let profile = node.name.rsplit('.').next().unwrap_or_default();
+        return Some(format!("device:{}:{}:{}", kind, serial, profile));
+    }
+    if node.name.is_empty() {
+        return None;
+    }
+    Some(format!("node:{}:{}", kind, node.name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(id: ObjectId, name: &str, description: &str, node_type: NodeType) -> Node {
+        Node {
+            description: description.into(),
+            ..Node::test(id, name, node_type)
+        }
+    }
+
+    const SPEAKERS: &str = "alsa_output.pci-0000_00_1f.3.analog-stereo";
+
+    fn stream(id: ObjectId, binary: &str, title: &str) -> Node {
+        Node {
+            application_binary: Some(binary.into()),
+            ..node(id, binary, title, NodeType::StreamOutput)
+        }
+    }
+
+    #[test]
+    fn test_identity_survives_restart_and_rename() {
+        let mut registry = NodeIdentities::default();
+        let first = registry.identify(&[
+            node(40, SPEAKERS, "Speakers", NodeType::Sink),
+            stream(50, "firefox", "Some Video"),
+        ]);
+        assert!(registry.take_dirty());
+
+        let saved = serde_json::to_string(&registry).unwrap();
+        let mut registry: NodeIdentities = serde_json::from_str(&saved).unwrap();
+        let second = registry.identify(&[
+            stream(81, "firefox", "Another Video"),
+            node(80, SPEAKERS, "Desk", NodeType::Sink),
+        ]);
+
+        assert_eq!(first[&40], second[&80]);
+        assert_eq!(first[&50], second[&81]);
+        assert_eq!(registry.get(&second[&80]).unwrap().name, "Desk");
+    }
+
+    #[test]
+    fn test_same_app_streams_get_distinct_ids() {
+        let mut registry = NodeIdentities::default();
+        let ids = registry.identify(&[
+            stream(61, "firefox", "Tab B"),
+            stream(60, "firefox", "Tab A"),
+            node(70, "eq", "EQ", NodeType::Plugin),
+        ]);
+        assert_eq!(ids.len(), 2);
+        assert_ne!(ids[&60], ids[&61]);
+        assert_eq!(registry.get(&ids[&61]).unwrap().name, "Tab B");
+
+        // Each stream keeps its identity while it lives, but only the first
+        // one's is saved.
+        let again = registry.identify(&[
+            stream(60, "firefox", "Tab A"),
+            stream(61, "firefox", "Tab B"),
+        ]);
+        assert_eq!(again, ids);
+        assert_eq!(registry.identities.len(), 1);
+        let saved = serde_json::to_string(&registry).unwrap();
+        assert!(!saved.contains(&ids[&61]));
+
+        // Once the first stream is gone, the other does not take over its
+        // identity; the next new stream does.
+        let later = registry.identify(&[
+            stream(61, "firefox", "Tab B"),
+            stream(62, "firefox", "Tab C"),
+        ]);
+        assert_eq!(later[&61], ids[&61]);
+        assert_eq!(later[&62], ids[&60]);
+
+        let gone = registry.identify(&[stream(62, "firefox", "Tab C")]);
+        assert!(registry.get(&ids[&61]).is_none());
+        assert_eq!(
+            registry.saved_ids(&[stream(90, "firefox", "Tab D")])[&90],
+            gone[&62]
+        );
+    }
+
+    #[test]
+    fn test_arrival_order_identities_are_dropped() {
+        let old = r#"{"identities":[
+            {"id":"a","fingerprint":"stream:out:firefox:","name":"Tab A"},
+            {"id":"b","fingerprint":"stream:out:firefox:#1","name":"Tab B"}
+        ]}"#;
+        let mut registry: NodeIdentities = serde_json::from_str(old).unwrap();
+        let ids = registry.identify(&[]);
+        assert!(ids.is_empty());
+        assert!(registry.take_dirty());
+        assert!(registry.get("b").is_none());
+        assert_eq!(registry.get("a").unwrap().name, "Tab A");
+    }
+
+    #[test]
+    fn test_device_serial_outranks_node_name() {
+        let mut usb = node(
+            1,
+            "alsa_output.usb-port1.analog-stereo",
+            "Interface",
+            NodeType::Sink,
+        );
+        usb.device_serial = Some("Y8ABC123".into());
+        let mut moved = usb.clone();
+        moved.name = "alsa_output.usb-port2.analog-stereo".into();
+        assert_eq!(fingerprint(&usb), fingerprint(&moved));
+        assert_eq!(
+            fingerprint(&usb).unwrap(),
+            "device:sink:Y8ABC123:analog-stereo"
+        );
+    }
+}
@@ -0,0 +1,146 @@
+//! The order nodes are listed in for the UI.
+//!
+//! Lists built straight from the graph's maps come out in hash order, which
+//! changes from poll to poll and makes QML delegates reshuffle. Node lists
+//! are sorted by the user's [`NodeOrder`] instead, with the object id as
+//! the last tie-breaker, so the same graph always lists the same way.
+
+use std::cmp::{Ordering, Reverse};
+
+use super::state::natural_cmp;
+use super::types::{NodeType, ObjectId};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NodeOrder {
+    #[default]
+    Alphabetical,
+    /// Sources, then processing, then sinks; alphabetical within a type.
+    ByType,
+    /// Nodes that appeared or were linked or unlinked last come first.
+    RecentActivity,
+}
+
+impl NodeOrder {
+    pub const ALL: [NodeOrder; 3] = [
+        NodeOrder::Alphabetical,
+        NodeOrder::ByType,
+        NodeOrder::RecentActivity,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            NodeOrder::Alphabetical => "alphabetical",
+            NodeOrder::ByType => "type",
+            NodeOrder::RecentActivity => "activity",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            NodeOrder::Alphabetical => "Alphabetical",
+            NodeOrder::ByType => "By type",
+            NodeOrder::RecentActivity => "By recent activity",
+        }
+    }
+
+    pub fn from_pref(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|o| o.as_str() == value)
+    }
+
+    pub fn compare(self, a: &NodeSortKey, b: &NodeSortKey) -> Ordering {
+        let by_name = || {
+            natural_cmp(&a.name.to_lowercase(), &b.name.to_lowercase())
+                .then_with(|| a.name.cmp(b.name))
+        };
+        match self {
+            NodeOrder::Alphabetical => by_name(),
+            NodeOrder::ByType => type_rank(a.node_type)
+                .cmp(&type_rank(b.node_type))
+                .then_with(by_name),
+            NodeOrder::RecentActivity => Reverse(a.activity)
+                .cmp(&Reverse(b.activity))
+                .then_with(by_name),
+        }
+        .then_with(|| a.id.cmp(&b.id))
+    }
+}
+
+/// What a listed node is sorted on.
+#[derive(Debug, Clone, Copy)]
+pub struct NodeSortKey<'a> {
+    pub id: ObjectId,
+    pub name: &'a str,
+    pub node_type: Option<NodeType>,
+    /// From [`GraphState::node_activity`](super::GraphState::node_activity);
+    /// higher is more recent.
+    pub activity: u64,
+}
+
+/// Signal flow order: where audio comes from, what processes it, where it
+/// goes. Untyped nodes go last.
+fn type_rank(node_type: Option<NodeType>) -> u8 {
+    match node_type {
+        Some(NodeType::Source) => 0,
+        Some(NodeType::StreamOutput) => 1,
+        Some(NodeType::Duplex) => 2,
+        Some(NodeType::Plugin) => 3,
+        Some(NodeType::StreamInput) => 4,
+        Some(NodeType::Sink) => 5,
+        None => 6,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(id: ObjectId, name: &str, node_type: NodeType, activity: u64) -> NodeSortKey<'_> {
+        NodeSortKey {
+            id,
+            name,
+            node_type: Some(node_type),
+            activity,
+        }
+    }
+
+    fn sorted(order: NodeOrder, keys: &[NodeSortKey]) -> Vec<ObjectId> {
+        let mut keys = keys.to_vec();
+        keys.sort_by(|a, b| order.compare(a, b));
+        keys.iter().map(|k| k.id).collect()
+    }
+
+    #[test]
+    fn test_orders() {
+        let keys = [
+            key(4, "speakers", NodeType::Sink, 10),
+            key(2, "Firefox", NodeType::StreamOutput, 30),
+            key(3, "EQ", NodeType::Plugin, 20),
+            key(1, "Mic 10", NodeType::Source, 0),
+            key(5, "Mic 9", NodeType::Source, 0),
+        ];
+        assert_eq!(sorted(NodeOrder::Alphabetical, &keys), [3, 2, 5, 1, 4]);
+        assert_eq!(sorted(NodeOrder::ByType, &keys), [5, 1, 2, 3, 4]);
+        assert_eq!(sorted(NodeOrder::RecentActivity, &keys), [2, 3, 4, 5, 1]);
+    }
+
+    #[test]
+    fn test_equal_keys_sort_by_id_whatever_the_input_order() {
+        let a = [
+            key(7, "Firefox", NodeType::StreamOutput, 0),
+            key(3, "Firefox", NodeType::StreamOutput, 0),
+        ];
+        let b = [a[1], a[0]];
+        for order in NodeOrder::ALL {
+            assert_eq!(sorted(order, &a), [3, 7]);
+            assert_eq!(sorted(order, &b), [3, 7]);
+        }
+    }
+
+    #[test]
+    fn test_pref_round_trip() {
+        for order in NodeOrder::ALL {
+            assert_eq!(NodeOrder::from_pref(order.as_str()), Some(order));
+        }
+        assert_eq!(NodeOrder::from_pref("random"), None);
+    }
+}
@@ -0,0 +1,320 @@
+//! Stereo pairs among a device's ports.
+//!
+//! Multichannel interfaces expose their channels one by one (`capture_1`,
+//! `capture_2`, ... or `AUX0`, `AUX1`, ...), though most are used as stereo
+//! pairs. [`stereo_pairs`] guesses the pairs from channel positions and
+//! names, so a connect gesture can take both sides at once, and applies the
+//! user's [`PairOverrides`] for a device where the guess is wrong.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use super::types::{MediaType, ObjectId, Port, PortDirection};
+
+/// A left and a right port.
+pub type StereoPair = (ObjectId, ObjectId);
+
+/// Channel positions that make a pair, left first.
+const POSITION_PAIRS: [(&str, &str); 8] = [
+    ("FL", "FR"),
+    ("RL", "RR"),
+    ("SL", "SR"),
+    ("FLC", "FRC"),
+    ("RLC", "RRC"),
+    ("FLW", "FRW"),
+    ("TFL", "TFR"),
+    ("TRL", "TRR"),
+];
+
+/// Hand-made changes to one device's pairs, by port name.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PairOverrides {
+    /// Pairs to use, left first, whatever was detected.
+    #[serde(default)]
+    pub pairs: Vec<(String, String)>,
+    /// Ports to keep out of any pair.
+    #[serde(default)]
+    pub single: Vec<String>,
+}
+
+impl PairOverrides {
+    pub fn is_empty(&self) -> bool {
+        self.pairs.is_empty() && self.single.is_empty()
+    }
+
+    fn mentions(&self, name: &str) -> bool {
+        self.single.iter().any(|s| s == name)
+            || self.pairs.iter().any(|(l, r)| l == name || r == name)
+    }
+
+    /// Pair `left` with `right`, replacing any pair either was in.
+    pub fn pair(&mut self, left: &str, right: &str) {
+        self.unpair(left);
+        self.unpair(right);
+        self.single.retain(|s| s != left && s != right);
+        self.pairs.push((left.to_string(), right.to_string()));
+    }
+
+    /// Keep `name` out of any pair, its partner too.
+    pub fn split(&mut self, name: &str, partner: Option<&str>) {
+        self.unpair(name);
+        for port in std::iter::once(name).chain(partner) {
+            if !self.single.iter().any(|s| s == port) {
+                self.single.push(port.to_string());
+            }
+        }
+    }
+
+    fn unpair(&mut self, name: &str) {
+        self.pairs.retain(|(l, r)| l != name && r != name);
+    }
+}
+
+/// The stereo pairs among `ports`, which may span directions and nodes.
+/// Only audio ports of the same node, direction and port group (the device,
+/// on a bridge node) pair up. `overrides` apply to every node in `ports`, so
+/// pass one device's ports with it.
+pub fn stereo_pairs(ports: &[Port], overrides: Option<&PairOverrides>) -> Vec<StereoPair> {
+    let mut groups: BTreeMap<(ObjectId, bool, Option<&str>), Vec<&Port>> = BTreeMap::new();
+    for port in ports {
+        if matches!(port.media_type, Some(MediaType::Audio) | None) {
+            let is_output = port.direction == PortDirection::Output;
+            groups
+                .entry((port.node_id, is_output, port.port_group.as_deref()))
+                .or_default()
+                .push(port);
+        }
+    }
+
+    let mut pairs = Vec::new();
+    for group in groups.values() {
+        let by_name = |name: &str| group.iter().find(|p| p.name == name).map(|p| p.id);
+        for (left, right) in detect(group) {
+            let overridden = overrides.is_some_and(|o| {
+                group
+                    .iter()
+                    .any(|p| (p.id == left || p.id == right) && o.mentions(&p.name))
+            });
+            if !overridden {
+                pairs.push((left, right));
+            }
+        }
+        for (left, right) in overrides.iter().flat_map(|o| &o.pairs) {
+            if let (Some(left), Some(right)) = (by_name(left), by_name(right)) {
+                pairs.push((left, right));
+            }
+        }
+    }
+    pairs
+}
+
+/// The port `port_id` is paired with.
+pub fn partner(pairs: &[StereoPair], port_id: ObjectId) -> Option<ObjectId> {
+    pairs.iter().find_map(|&(l, r)| {
+        if l == port_id {
+            Some(r)
+        } else if r == port_id {
+            Some(l)
+        } else {
+            None
+        }
+    })
+}
+
+/// The pairs among ports of one node and direction.
+fn detect(ports: &[&Port]) -> Vec<StereoPair> {
+    let label = |p: &Port| match p.channel.as_deref() {
+        Some(ch) if !ch.is_empty() && ch != "UNK" => ch.to_string(),
+        _ => p.name.clone(),
+    };
+    let labelled: Vec<(String, ObjectId)> = ports.iter().map(|p| (label(p), p.id)).collect();
+    let find = |label: &str| labelled.iter().find(|(l, _)| l == label).map(|(_, id)| *id);
+
+    let mut pairs = Vec::new();
+    let mut used = Vec::new();
+    let mut add = |pairs: &mut Vec<StereoPair>, left: ObjectId, right: ObjectId| {
+        if !used.contains(&left) && !used.contains(&right) {
+            used.extend([left, right]);
+            pairs.push((left, right));
+        }
+    };
+
+    // Channel positions, then "_L"/"_R" style names.
+    for (left, right) in POSITION_PAIRS {
+        if let (Some(l), Some(r)) = (find(left), find(right)) {
+            add(&mut pairs, l, r);
+        }
+    }
+    for (label, id) in &labelled {
+        if let Some(stem) = strip_side(label, true)
+            && let Some(&(_, right)) = labelled
+                .iter()
+                .find(|(other, _)| strip_side(other, false) == Some(stem))
+        {
+            add(&mut pairs, *id, right);
+        }
+    }
+
+    // Numbered channels: 1 with 2, 3 with 4, ... or 0 with 1 when
+    // numbering starts at 0.
+    let mut numbered: BTreeMap<&str, BTreeMap<u32, ObjectId>> = BTreeMap::new();
+    for (label, id) in &labelled {
+        if let Some((stem, n)) = split_number(label) {
+            numbered.entry(stem).or_default().insert(n, *id);
+        }
+    }
+    for channels in numbered.values() {
+        let Some(&first) = channels.keys().next() else {
+            continue;
+        };
+        for (&n, &left) in channels {
+            if n % 2 == first % 2
+                && let Some(&right) = channels.get(&(n + 1))
+            {
+                add(&mut pairs, left, right);
+            }
+        }
+    }
+    pairs
+}
+
+/// `label` without a trailing left (or right) marker: `_L`, ` L`, `-L`,
+/// `_left`, in any case.
+fn strip_side(label: &str, left: bool) -> Option<&str> {
+    let markers: [&str; 2] = if left { ["l", "left"] } else { ["r", "right"] };
+    let lower = label.to_ascii_lowercase();
+    markers.iter().find_map(|marker| {
+        let stem_len = lower.strip_suffix(marker)?.len();
+        let stem = &label[..stem_len];
+        stem.strip_suffix(['_', ' ', '-'])
+    })
+}
+
+/// `label` split into its text and trailing number: `capture_12` is
+/// `("capture_", 12)`.
+fn split_number(label: &str) -> Option<(&str, u32)> {
+    let stem = label.trim_end_matches(|c: char| c.is_ascii_digit());
+    if stem.len() == label.len() {
+        return None;
+    }
+    Some((stem, label[stem.len()..].parse().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ports(
+        node_id: ObjectId,
+        direction: PortDirection,
+        labels: &[(&str, Option<&str>)],
+    ) -> Vec<Port> {
+        labels
+            .iter()
+            .enumerate()
+            .map(|(i, (name, channel))| Port {
+                id: node_id * 100 + i as ObjectId,
+                node_id,
+                name: name.to_string(),
+                direction,
+                media_type: Some(MediaType::Audio),
+                channel: channel.map(str::to_string),
+                physical_index: None,
+                port_group: None,
+                port_alias: None,
+            })
+            .collect()
+    }
+
+    fn sorted(mut pairs: Vec<StereoPair>) -> Vec<StereoPair> {
+        pairs.sort();
+        pairs
+    }
+
+    #[test]
+    fn test_detects_numbered_and_positioned_pairs() {
+        // 1-based names without channels: 1+2, 3+4; 5 is left over.
+        let jack = ports(
+            1,
+            PortDirection::Output,
+            &[
+                ("capture_1", None),
+                ("capture_2", None),
+                ("capture_3", None),
+                ("capture_4", None),
+                ("capture_5", None),
+            ],
+        );
+        assert_eq!(sorted(stereo_pairs(&jack, None)), [(100, 101), (102, 103)]);
+
+        // 0-based AUX channels pair 0+1, 2+3.
+        let aux = ports(
+            2,
+            PortDirection::Input,
+            &[
+                ("playback_AUX0", Some("AUX0")),
+                ("playback_AUX1", Some("AUX1")),
+                ("playback_AUX2", Some("AUX2")),
+                ("playback_AUX3", Some("AUX3")),
+            ],
+        );
+        assert_eq!(sorted(stereo_pairs(&aux, None)), [(200, 201), (202, 203)]);
+
+        let surround = ports(
+            3,
+            PortDirection::Input,
+            &[
+                ("playback_FL", Some("FL")),
+                ("playback_FR", Some("FR")),
+                ("playback_RL", Some("RL")),
+                ("playback_RR", Some("RR")),
+                ("Synth_L", None),
+                ("Synth R", None),
+            ],
+        );
+        assert_eq!(
+            sorted(stereo_pairs(&surround, None)),
+            [(300, 301), (302, 303), (304, 305)]
+        );
+        assert_eq!(partner(&stereo_pairs(&surround, None), 305), Some(304));
+    }
+
+    #[test]
+    fn test_overrides_split_and_repair() {
+        let all = ports(
+            1,
+            PortDirection::Output,
+            &[
+                ("capture_1", None),
+                ("capture_2", None),
+                ("capture_3", None),
+                ("capture_4", None),
+            ],
+        );
+        let mut overrides = PairOverrides::default();
+        overrides.split("capture_1", Some("capture_2"));
+        assert_eq!(stereo_pairs(&all, Some(&overrides)), [(102, 103)]);
+
+        overrides.pair("capture_2", "capture_3");
+        assert_eq!(sorted(stereo_pairs(&all, Some(&overrides))), [(101, 102)]);
+        assert_eq!(overrides.single, ["capture_1"]);
+    }
+
+    #[test]
+    fn test_directions_and_midi_do_not_mix() {
+        let mut all = ports(1, PortDirection::Output, &[("capture_1", None)]);
+        all.extend(ports(1, PortDirection::Input, &[("playback_2", None)]));
+        all[1].id = 101;
+        let mut midi = ports(
+            2,
+            PortDirection::Output,
+            &[("midi_1", None), ("midi_2", None)],
+        );
+        for port in &mut midi {
+            port.media_type = Some(MediaType::Midi);
+        }
+        all.extend(midi);
+        assert!(stereo_pairs(&all, None).is_empty());
+    }
+}
@@ -0,0 +1,1496 @@
+use parking_lot::{Mutex, RwLock};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::Arc;
+use std::sync::mpsc::{self, Receiver};
+
+use super::channels::ChannelOverrides;
+use super::subscription::{GraphChange, GraphFilter, Subscriber};
+use super::types::*;
+
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let mut ai = a.as_bytes().iter().peekable();
+    let mut bi = b.as_bytes().iter().peekable();
+
+    loop {
+        match (ai.peek(), bi.peek()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(&&ac), Some(&&bc)) => {
+                let a_digit = ac.is_ascii_digit();
+                let b_digit = bc.is_ascii_digit();
+
+                if a_digit && b_digit {
+                    let mut an: u64 = 0;
+                    while let Some(&&c) = ai.peek() {
+                        if c.is_ascii_digit() {
+                            an = an * 10 + (c - b'0') as u64;
+                            ai.next();
+                        } else {
+                            break;
+                        }
+                    }
+                    let mut bn: u64 = 0;
+                    while let Some(&&c) = bi.peek() {
+                        if c.is_ascii_digit() {
+                            bn = bn * 10 + (c - b'0') as u64;
+                            bi.next();
+                        } else {
+                            break;
+                        }
+                    }
+                    match an.cmp(&bn) {
+                        Ordering::Equal => continue,
+                        ord => return ord,
+                    }
+                } else {
+                    match ac.cmp(&bc) {
+                        Ordering::Equal => {
+                            ai.next();
+                            bi.next();
+                        }
+                        ord => return ord,
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Where a port sorts among its node's ports: MIDI, then audio, then CV.
+fn port_rank(port: &Port) -> u8 {
+    match port.media_type {
+        Some(MediaType::Midi) => 0,
+        Some(MediaType::Cv) => 2,
+        _ => 1,
+    }
+}
+
+/// Objects dropped by a consistency pass, so the caller can emit the removal
+/// events PipeWire never delivered.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct PruneReport {
+    pub nodes: Vec<ObjectId>,
+    /// (port_id, node_id)
+    pub ports: Vec<(ObjectId, ObjectId)>,
+    pub links: Vec<ObjectId>,
+}
+
+impl PruneReport {
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty() && self.ports.is_empty() && self.links.is_empty()
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct GraphState {
+    nodes: RwLock<HashMap<ObjectId, Node>>,
+    ports: RwLock<HashMap<ObjectId, Port>>,
+    links: RwLock<HashMap<ObjectId, Link>>,
+    /// Nodes that have owned at least one port. A node in this set with no
+    /// ports left is a ghost candidate: its ports went away but its own
+    /// removal may have been missed.
+    port_owners: RwLock<HashSet<ObjectId>>,
+    /// `node.name` of the default audio sink, from the session manager's
+    /// "default" metadata.
+    default_sink: RwLock<Option<String>>,
+    /// Negotiated formats, kept apart from [`Node`] so registry
+    /// re-enumeration does not reset them.
+    node_formats: RwLock<HashMap<ObjectId, NodeFormat>>,
+    /// Software volume from each node's `Props` param, where reported.
+    node_volumes: RwLock<HashMap<ObjectId, f32>>,
+    /// Change counter at which each node appeared or last had a link added
+    /// or removed, for listing by recent activity.
+    activity: RwLock<HashMap<ObjectId, u64>>,
+    /// Channel positions set by hand, applied to ports as they come in.
+    channel_overrides: RwLock<ChannelOverrides>,
+    /// The `audio.channel` each port reports, for overridden ports to go
+    /// back to.
+    reported_channels: RwLock<HashMap<ObjectId, Option<String>>>,
+    change_counter: RwLock<u64>,
+    subscribers: Mutex<Vec<Subscriber>>,
+}
+
+impl GraphState {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self::default())
+    }
+
+    fn mark_changed(&self) {
+        let mut counter = self.change_counter.write();
+        *counter = counter.wrapping_add(1);
+    }
+
+    pub fn change_counter(&self) -> u64 {
+        *self.change_counter.read()
+    }
+
+    fn record_activity(&self, node_ids: &[ObjectId]) {
+        let now = self.change_counter();
+        let mut activity = self.activity.write();
+        for &id in node_ids {
+            activity.insert(id, now);
+        }
+    }
+
+    /// When the node last appeared or had a link added or removed, as a
+    /// change counter value; higher is more recent, 0 is never.
+    pub fn node_activity(&self, id: ObjectId) -> u64 {
+        self.activity.read().get(&id).copied().unwrap_or(0)
+    }
+
+    /// Receive the changes that pass `filter` from now on. The subscription
+    /// ends when the receiver is dropped.
+    pub fn subscribe(&self, filter: GraphFilter) -> Receiver<GraphChange> {
+        let (tx, rx) = mpsc::channel();
+        self.subscribers.lock().push(Subscriber { filter, tx });
+        rx
+    }
+
+    /// Send changes to the subscribers whose filters pass them. `changes` is
+    /// only called when someone is subscribed, so callers can clone freely.
+    /// Must not be called with the node map locked for writing.
+    fn notify<I: IntoIterator<Item = GraphChange>>(&self, changes: impl FnOnce() -> I) {
+        let mut subscribers = self.subscribers.lock();
+        if subscribers.is_empty() {
+            return;
+        }
+        let nodes = self.nodes.read();
+        for change in changes() {
+            subscribers.retain(|s| {
+                !s.filter.matches(&change, |id| nodes.get(&id)) || s.tx.send(change.clone()).is_ok()
+            });
+        }
+    }
+
+    pub fn insert_node(&self, node: Node) {
+        let media_type = node.media_type;
+        let node_id = node.id;
+        let overrides = self.channel_overrides.read().0.get(&node.name).cloned();
+        let is_new = self.nodes.write().insert(node_id, node).is_none();
+
+        if media_type.is_some() || overrides.is_some() {
+            let mut ports = self.ports.write();
+            for port in ports.values_mut().filter(|p| p.node_id == node_id) {
+                if port.media_type.is_none() {
+                    port.media_type = media_type;
+                }
+                // Ports seen before their node get its overrides now.
+                if let Some(channel) = overrides.as_ref().and_then(|o| o.get(&port.name)) {
+                    port.channel = Some(channel.clone());
+                }
+            }
+        }
+
+        self.mark_changed();
+        if is_new {
+            self.record_activity(&[node_id]);
+        }
+        self.notify(|| self.get_node(node_id).map(GraphChange::NodeChanged));
+    }
+
+    pub fn remove_node(&self, id: ObjectId) -> Option<Node> {
+        self.port_owners.write().remove(&id);
+        self.node_formats.write().remove(&id);
+        self.node_volumes.write().remove(&id);
+        self.activity.write().remove(&id);
+        let node = self.nodes.write().remove(&id);
+        if let Some(ref n) = node {
+            self.mark_changed();
+            self.notify(|| Some(GraphChange::NodeRemoved(n.clone())));
+        }
+        node
+    }
+
+    pub fn set_default_sink(&self, name: Option<String>) {
+        let mut current = self.default_sink.write();
+        if *current != name {
+            *current = name.clone();
+            drop(current);
+            self.mark_changed();
+            self.notify(|| Some(GraphChange::DefaultSinkChanged(name)));
+        }
+    }
+
+    pub fn default_sink(&self) -> Option<String> {
+        self.default_sink.read().clone()
+    }
+
+    pub fn get_node(&self, id: ObjectId) -> Option<Node> {
+        self.nodes.read().get(&id).cloned()
+    }
+
+    /// All nodes, by id.
+    pub fn get_all_nodes(&self) -> Vec<Node> {
+        let mut nodes: Vec<Node> = self.nodes.read().values().cloned().collect();
+        nodes.sort_by_key(|n| n.id);
+        nodes
+    }
+
+    pub fn get_node_format(&self, id: ObjectId) -> Option<NodeFormat> {
+        self.node_formats.read().get(&id).cloned()
+    }
+
+    /// Apply `update` to the node's format, returning true if it changed.
+    pub fn update_node_format(&self, id: ObjectId, update: impl FnOnce(&mut NodeFormat)) -> bool {
+        let mut formats = self.node_formats.write();
+        let format = formats.entry(id).or_default();
+        let before = format.clone();
+        update(format);
+        let changed = *format != before;
+        drop(formats);
+        if changed {
+            self.mark_changed();
+            self.notify(|| Some(GraphChange::FormatChanged(id)));
+        }
+        changed
+    }
+
+    pub fn get_node_volume(&self, id: ObjectId) -> Option<f32> {
+        self.node_volumes.read().get(&id).copied()
+    }
+
+    /// Record the node's software volume as PipeWire reported it. Not a
+    /// change to the graph, so subscribers are not told.
+    pub fn set_node_volume(&self, id: ObjectId, volume: f32) {
+        self.node_volumes.write().insert(id, volume);
+    }
+
+    pub fn set_node_type(&self, id: ObjectId, node_type: NodeType) {
+        self.update_node(id, |node| {
+            let changed = node.node_type != Some(node_type);
+            node.node_type = Some(node_type);
+            changed
+        });
+    }
+
+    pub fn set_node_description(&self, id: ObjectId, description: &str) {
+        self.update_node(id, |node| {
+            let changed = node.description != description;
+            if changed {
+                node.description = description.to_string();
+            }
+            changed
+        });
+    }
+
+    /// Apply `update` to a node; it returns whether anything changed.
+    fn update_node(&self, id: ObjectId, update: impl FnOnce(&mut Node) -> bool) {
+        let changed = self.nodes.write().get_mut(&id).is_some_and(update);
+        if changed {
+            self.mark_changed();
+            self.notify(|| self.get_node(id).map(GraphChange::NodeChanged));
+        }
+    }
+
+    pub fn insert_port(&self, mut port: Port) {
+        self.reported_channels
+            .write()
+            .insert(port.id, port.channel.clone());
+        if let Some(channel) = self.channel_override(&port) {
+            port.channel = Some(channel);
+        }
+        self.port_owners.write().insert(port.node_id);
+        self.ports.write().insert(port.id, port.clone());
+        self.mark_changed();
+        self.notify(|| Some(GraphChange::PortChanged(port)));
+    }
+
+    pub fn remove_port(&self, id: ObjectId) -> Option<Port> {
+        self.reported_channels.write().remove(&id);
+        let port = self.ports.write().remove(&id);
+        if let Some(ref p) = port {
+            self.mark_changed();
+            self.notify(|| Some(GraphChange::PortRemoved(p.clone())));
+        }
+        port
+    }
+
+    /// Remove all links that reference the given port and return their IDs.
+    /// Call this after `remove_port` so that stale links don't linger in the
+    /// graph when PipeWire removes ports before their parent node.
+    pub fn cleanup_port(&self, port_id: ObjectId) -> Vec<ObjectId> {
+        let mut links = self.links.write();
+        let mut removed = Vec::new();
+        links.retain(|_, l| {
+            if l.output_port_id == port_id || l.input_port_id == port_id {
+                removed.push(l.clone());
+                false
+            } else {
+                true
+            }
+        });
+        drop(links);
+        if !removed.is_empty() {
+            self.mark_changed();
+        }
+        let ids = removed.iter().map(|l| l.id).collect();
+        self.notify(|| removed.into_iter().map(GraphChange::LinkRemoved));
+        ids
+    }
+
+    pub fn get_port(&self, id: ObjectId) -> Option<Port> {
+        self.ports.read().get(&id).cloned()
+    }
+
+    fn channel_override(&self, port: &Port) -> Option<String> {
+        let nodes = self.nodes.read();
+        let node = nodes.get(&port.node_id)?;
+        self.channel_overrides
+            .read()
+            .get(&node.name, &port.name)
+            .map(String::from)
+    }
+
+    /// The channel position the port itself reports, whatever it is
+    /// overridden with.
+    pub fn reported_channel(&self, port_id: ObjectId) -> Option<String> {
+        match self.reported_channels.read().get(&port_id) {
+            Some(channel) => channel.clone(),
+            None => self.get_port(port_id)?.channel,
+        }
+    }
+
+    /// Replace the channel overrides and apply them to the ports already in
+    /// the graph.
+    pub fn set_channel_overrides(&self, overrides: ChannelOverrides) {
+        *self.channel_overrides.write() = overrides;
+        let mut changed = Vec::new();
+        for port in self.get_all_ports() {
+            let channel = self
+                .channel_override(&port)
+                .or_else(|| self.reported_channel(port.id));
+            if channel != port.channel {
+                let port = Port { channel, ..port };
+                self.ports.write().insert(port.id, port.clone());
+                changed.push(port);
+            }
+        }
+        if !changed.is_empty() {
+            self.mark_changed();
+            self.notify(|| changed.into_iter().map(GraphChange::PortChanged));
+        }
+    }
+
+    /// All ports, by id.
+    pub fn get_all_ports(&self) -> Vec<Port> {
+        let mut ports: Vec<Port> = self.ports.read().values().cloned().collect();
+        ports.sort_by_key(|p| p.id);
+        ports
+    }
+
+    pub fn get_ports_for_node(&self, node_id: ObjectId) -> Vec<Port> {
+        let mut ports: Vec<Port> = self
+            .ports
+            .read()
+            .values()
+            .filter(|p| p.node_id == node_id)
+            .cloned()
+            .collect();
+        ports.sort_by(|a, b| {
+            a.direction.cmp(&b.direction).then_with(|| {
+                // MIDI ports first and CV ports last within each direction group
+                port_rank(a)
+                    .cmp(&port_rank(b))
+                    .then_with(|| natural_cmp(&a.name, &b.name).then(a.id.cmp(&b.id)))
+            })
+        });
+        ports
+    }
+
+    pub fn get_input_ports(&self, node_id: ObjectId) -> Vec<Port> {
+        let mut ports: Vec<Port> = self
+            .ports
+            .read()
+            .values()
+            .filter(|p| p.node_id == node_id && p.direction == PortDirection::Input)
+            .cloned()
+            .collect();
+        ports.sort_by(|a, b| {
+            // MIDI ports first, CV ports last
+            port_rank(a)
+                .cmp(&port_rank(b))
+                .then_with(|| natural_cmp(&a.name, &b.name).then(a.id.cmp(&b.id)))
+        });
+        ports
+    }
+
+    pub fn get_output_ports(&self, node_id: ObjectId) -> Vec<Port> {
+        let mut ports: Vec<Port> = self
+            .ports
+            .read()
+            .values()
+            .filter(|p| p.node_id == node_id && p.direction == PortDirection::Output)
+            .cloned()
+            .collect();
+        ports.sort_by(|a, b| {
+            // MIDI ports first, CV ports last
+            port_rank(a)
+                .cmp(&port_rank(b))
+                .then_with(|| natural_cmp(&a.name, &b.name).then(a.id.cmp(&b.id)))
+        });
+        ports
+    }
+
+    pub fn insert_link(&self, link: Link) {
+        let is_new = self.links.write().insert(link.id, link.clone()).is_none();
+        self.mark_changed();
+        if is_new {
+            self.record_activity(&[link.output_node_id, link.input_node_id]);
+        }
+        self.notify(|| Some(GraphChange::LinkChanged(link)));
+    }
+
+    pub fn remove_link(&self, id: ObjectId) -> Option<Link> {
+        let link = self.links.write().remove(&id);
+        if let Some(ref l) = link {
+            self.mark_changed();
+            self.record_activity(&[l.output_node_id, l.input_node_id]);
+            self.notify(|| Some(GraphChange::LinkRemoved(l.clone())));
+        }
+        link
+    }
+
+    pub fn get_link(&self, id: ObjectId) -> Option<Link> {
+        self.links.read().get(&id).cloned()
+    }
+
+    /// All links, by id.
+    pub fn get_all_links(&self) -> Vec<Link> {
+        let mut links: Vec<Link> = self.links.read().values().cloned().collect();
+        links.sort_by_key(|l| l.id);
+        links
+    }
+
+    pub fn find_link(&self, output_port_id: ObjectId, input_port_id: ObjectId) -> Option<Link> {
+        self.links
+            .read()
+            .values()
+            .find(|l| l.output_port_id == output_port_id && l.input_port_id == input_port_id)
+            .cloned()
+    }
+
+    /// For a bridge node, returns the distinct port groups and a display name
+    /// derived from the port.alias of the first port in each group.
+    /// Returns a map from port_group -> device display name.
+    pub fn get_bridge_port_groups(&self, node_id: ObjectId) -> BTreeMap<String, String> {
+        let ports = self.ports.read();
+        let mut groups: BTreeMap<String, String> = BTreeMap::new();
+        for port in ports.values() {
+            if port.node_id != node_id {
+                continue;
+            }
+            if let Some(ref group) = port.port_group {
+                if groups.contains_key(group) {
+                    continue;
+                }
+                // Derive device name from port.alias: "DeviceName:PortName" -> "DeviceName"
+                let device_name = if let Some(ref alias) = port.port_alias {
+                    if let Some(colon_pos) = alias.find(':') {
+                        alias[..colon_pos].to_string()
+                    } else {
+                        alias.clone()
+                    }
+                } else {
+                    group.clone()
+                };
+                groups.insert(group.clone(), device_name);
+            }
+        }
+        groups
+    }
+
+    /// Get ports for a bridge node filtered to a specific port group.
+    pub fn get_ports_for_bridge_group(&self, node_id: ObjectId, group: &str) -> Vec<Port> {
+        let mut ports: Vec<Port> = self
+            .ports
+            .read()
+            .values()
+            .filter(|p| {
+                p.node_id == node_id
+                    && p.port_group.as_deref() == Some(group)
+            })
+            .cloned()
+            .collect();
+        ports.sort_by(|a, b| {
+            a.direction
+                .cmp(&b.direction)
+                .then_with(|| natural_cmp(&a.name, &b.name).then(a.id.cmp(&b.id)))
+        });
+        ports
+    }
+
+    /// The audio feedback loop a link from `output_port_id` to
+    /// `input_port_id` would close, as the nodes the sound would pass
+    /// through from the input's node back to the output's, or `None` when
+    /// it closes none.
+    ///
+    /// Inside a node every input is taken to feed every output, except
+    /// that the devices of a split bridge node (one port group each) are
+    /// kept apart. MIDI and video ports are not followed.
+    pub fn feedback_path(
+        &self,
+        output_port_id: ObjectId,
+        input_port_id: ObjectId,
+    ) -> Option<Vec<ObjectId>> {
+        self.feedback_path_with(output_port_id, input_port_id, &HashSet::new(), &[])
+    }
+
+    /// [`Self::feedback_path`] as if the links `removed` were gone and
+    /// links between the port pairs `added` were there.
+    fn feedback_path_with(
+        &self,
+        output_port_id: ObjectId,
+        input_port_id: ObjectId,
+        removed: &HashSet<ObjectId>,
+        added: &[(ObjectId, ObjectId)],
+    ) -> Option<Vec<ObjectId>> {
+        let ports = self.ports.read();
+        let links: Vec<(ObjectId, ObjectId)> = self
+            .links
+            .read()
+            .values()
+            .filter(|l| !removed.contains(&l.id))
+            .map(|l| (l.output_port_id, l.input_port_id))
+            .chain(added.iter().copied())
+            .collect();
+        let carries_audio = |p: &Port| p.media_type.is_none_or(|m| m == MediaType::Audio);
+        let same_device = |a: &Port, b: &Port| {
+            a.node_id == b.node_id
+                && match (&a.port_group, &b.port_group) {
+                    (Some(a), Some(b)) => a == b,
+                    _ => true,
+                }
+        };
+        let (output, input) = (ports.get(&output_port_id)?, ports.get(&input_port_id)?);
+        if !carries_audio(output) || !carries_audio(input) {
+            return None;
+        }
+
+        // Breadth first over input ports, from the new link's input to
+        // any input on the output's device, keeping how each was reached.
+        let mut reached_from: HashMap<ObjectId, ObjectId> = HashMap::new();
+        let mut queue = std::collections::VecDeque::from([input_port_id]);
+        let mut seen = HashSet::from([input_port_id]);
+        while let Some(port_id) = queue.pop_front() {
+            let port = &ports[&port_id];
+            if same_device(port, output) {
+                let mut path = vec![port.node_id];
+                let mut at = port_id;
+                while let Some(&prev) = reached_from.get(&at) {
+                    let node_id = ports[&prev].node_id;
+                    if path.last() != Some(&node_id) {
+                        path.push(node_id);
+                    }
+                    at = prev;
+                }
+                path.reverse();
+                return Some(path);
+            }
+            for (from_id, to_id) in &links {
+                let Some(from) = ports.get(from_id) else {
+                    continue;
+                };
+                if !same_device(port, from) || !carries_audio(from) {
+                    continue;
+                }
+                let Some(to) = ports.get(to_id) else {
+                    continue;
+                };
+                if carries_audio(to) && seen.insert(to.id) {
+                    reached_from.insert(to.id, port_id);
+                    queue.push_back(to.id);
+                }
+            }
+        }
+        None
+    }
+
+    /// The first link `cmd` makes that would close an audio feedback loop
+    /// through two or more nodes. The links of a transaction are checked
+    /// in order, each as if the steps before it were done. A device
+    /// feeding itself is left to the self-loop checks.
+    pub fn closed_feedback_loop(&self, cmd: &PwCommand) -> Option<FeedbackLoop> {
+        let single;
+        let ops = match cmd {
+            PwCommand::Connect {
+                output_port_id,
+                input_port_id,
+            } => {
+                single = [LinkOp::Connect {
+                    output_port_id: *output_port_id,
+                    input_port_id: *input_port_id,
+                }];
+                &single[..]
+            }
+            PwCommand::Transaction { ops, .. } => &ops[..],
+            _ => return None,
+        };
+        let mut removed = HashSet::new();
+        let mut added = Vec::new();
+        for op in ops {
+            match *op {
+                LinkOp::Disconnect { link_id } => {
+                    removed.insert(link_id);
+                }
+                LinkOp::Connect {
+                    output_port_id,
+                    input_port_id,
+                } => {
+                    if let Some(nodes) =
+                        self.feedback_path_with(output_port_id, input_port_id, &removed, &added)
+                        && nodes.len() >= 2
+                    {
+                        return Some(FeedbackLoop {
+                            output_port_id,
+                            input_port_id,
+                            nodes,
+                        });
+                    }
+                    added.push((output_port_id, input_port_id));
+                }
+            }
+        }
+        None
+    }
+
+    /// A feedback loop as "A → B → A", by node display name.
+    pub fn feedback_loop_text(&self, nodes: &[ObjectId]) -> String {
+        let mut names: Vec<String> = nodes
+            .iter()
+            .map(|&id| {
+                self.get_node(id)
+                    .map(|n| n.display_name().to_string())
+                    .unwrap_or_else(|| format!("node {}", id))
+            })
+            .collect();
+        if let Some(first) = names.first().cloned() {
+            names.push(first);
+        }
+        names.join(" → ")
+    }
+
+    /// Remove all ports and links belonging to a node.  Returns the IDs of
+    /// links that were removed so the caller can emit proper events.
+    pub fn cleanup_node(&self, node_id: ObjectId) -> Vec<ObjectId> {
+        let port_ids: Vec<ObjectId> = self
+            .ports
+            .read()
+            .values()
+            .filter(|p| p.node_id == node_id)
+            .map(|p| p.id)
+            .collect();
+
+        let mut removed_links = Vec::new();
+        {
+            let mut links = self.links.write();
+            links.retain(|_, l| {
+                if port_ids.contains(&l.output_port_id)
+                    || port_ids.contains(&l.input_port_id)
+                {
+                    removed_links.push(l.clone());
+                    false
+                } else {
+                    true
+                }
+            });
+        }
+
+        let mut removed_ports = Vec::new();
+        {
+            let mut ports = self.ports.write();
+            for port_id in port_ids {
+                removed_ports.extend(ports.remove(&port_id));
+            }
+        }
+
+        self.mark_changed();
+        let link_ids = removed_links.iter().map(|l| l.id).collect();
+        self.notify(|| {
+            let links = removed_links.into_iter().map(GraphChange::LinkRemoved);
+            links.chain(removed_ports.into_iter().map(GraphChange::PortRemoved))
+        });
+        link_ids
+    }
+
+    /// Forget every node, port and link, ahead of a full registry resync.
+    pub fn clear(&self) {
+        self.nodes.write().clear();
+        self.ports.write().clear();
+        self.links.write().clear();
+        self.port_owners.write().clear();
+        self.node_formats.write().clear();
+        self.node_volumes.write().clear();
+        self.activity.write().clear();
+        self.reported_channels.write().clear();
+        self.mark_changed();
+        self.notify(|| Some(GraphChange::Cleared));
+    }
+
+    /// Drop objects that can no longer be valid: ports whose node is gone and
+    /// links whose ports or nodes are gone. These are left behind when
+    /// PipeWire's removal events are missed (e.g. after a device hiccup).
+    pub fn prune_orphans(&self) -> PruneReport {
+        let mut report = PruneReport::default();
+        let mut removed = Vec::new();
+        let nodes = self.nodes.read();
+        {
+            let mut ports = self.ports.write();
+            ports.retain(|&id, p| {
+                if nodes.contains_key(&p.node_id) {
+                    true
+                } else {
+                    report.ports.push((id, p.node_id));
+                    removed.push(GraphChange::PortRemoved(p.clone()));
+                    false
+                }
+            });
+        }
+        {
+            let ports = self.ports.read();
+            let mut links = self.links.write();
+            links.retain(|&id, l| {
+                let valid = ports.contains_key(&l.output_port_id)
+                    && ports.contains_key(&l.input_port_id)
+                    && nodes.contains_key(&l.output_node_id)
+                    && nodes.contains_key(&l.input_node_id);
+                if !valid {
+                    report.links.push(id);
+                    removed.push(GraphChange::LinkRemoved(l.clone()));
+                }
+                valid
+            });
+        }
+        drop(nodes);
+
+        if !report.is_empty() {
+            self.mark_changed();
+        }
+        self.notify(|| removed);
+        report
+    }
+
+    /// Nodes that used to have ports but have none left. Some of these are
+    /// mid-renegotiation, so callers should verify them against PipeWire
+    /// rather than dropping them outright.
+    pub fn ghost_candidates(&self) -> Vec<ObjectId> {
+        let ports = self.ports.read();
+        let owners = self.port_owners.read();
+        self.nodes
+            .read()
+            .keys()
+            .filter(|id| owners.contains(id) && !ports.values().any(|p| p.node_id == **id))
+            .copied()
+            .collect()
+    }
+
+    /// Keep only objects whose IDs are in `live` (the set PipeWire just
+    /// enumerated), then prune anything orphaned by that.
+    pub fn retain_objects(&self, live: &HashSet<ObjectId>) -> PruneReport {
+        let mut report = PruneReport::default();
+        let mut removed = Vec::new();
+        self.nodes.write().retain(|&id, n| {
+            let keep = live.contains(&id);
+            if !keep {
+                report.nodes.push(id);
+                removed.push(GraphChange::NodeRemoved(n.clone()));
+            }
+            keep
+        });
+        self.node_formats.write().retain(|id, _| live.contains(id));
+        self.node_volumes.write().retain(|id, _| live.contains(id));
+        self.ports.write().retain(|&id, p| {
+            let keep = live.contains(&id);
+            if !keep {
+                report.ports.push((id, p.node_id));
+                removed.push(GraphChange::PortRemoved(p.clone()));
+            }
+            keep
+        });
+        {
+            // Nodes that survived without ports are confirmed live by
+            // PipeWire; stop treating them as ghost candidates.
+            let ports = self.ports.read();
+            self.port_owners
+                .write()
+                .retain(|id| ports.values().any(|p| p.node_id == *id));
+        }
+        self.links.write().retain(|&id, l| {
+            let keep = live.contains(&id);
+            if !keep {
+                report.links.push(id);
+                removed.push(GraphChange::LinkRemoved(l.clone()));
+            }
+            keep
+        });
+        self.notify(|| removed);
+
+        let orphans = self.prune_orphans();
+        report.ports.extend(orphans.ports);
+        report.links.extend(orphans.links);
+
+        if !report.is_empty() {
+            self.mark_changed();
+        }
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_node(id: ObjectId, name: &str) -> Node {
+        Node::test(id, name, NodeType::Plugin)
+    }
+
+    fn make_port(id: ObjectId, node_id: ObjectId, name: &str, dir: PortDirection) -> Port {
+        Port {
+            id,
+            node_id,
+            name: name.to_string(),
+            direction: dir,
+            media_type: Some(MediaType::Audio),
+            channel: None,
+            physical_index: None,
+            port_group: None,
+            port_alias: None,
+        }
+    }
+
+    fn make_link(id: ObjectId, out_node: ObjectId, out_port: ObjectId, in_node: ObjectId, in_port: ObjectId) -> Link {
+        Link {
+            id,
+            output_node_id: out_node,
+            output_port_id: out_port,
+            input_node_id: in_node,
+            input_port_id: in_port,
+            active: true,
+        }
+    }
+
+    // ---- natural_cmp ----
+
+    #[test]
+    fn natural_cmp_equal() {
+        assert_eq!(natural_cmp("abc", "abc"), Ordering::Equal);
+    }
+
+    #[test]
+    fn natural_cmp_alphabetical() {
+        assert_eq!(natural_cmp("abc", "def"), Ordering::Less);
+        assert_eq!(natural_cmp("def", "abc"), Ordering::Greater);
+    }
+
+    #[test]
+    fn natural_cmp_numeric_sorting() {
+        assert_eq!(natural_cmp("item2", "item10"), Ordering::Less);
+        assert_eq!(natural_cmp("item10", "item2"), Ordering::Greater);
+        assert_eq!(natural_cmp("item10", "item10"), Ordering::Equal);
+    }
+
+    #[test]
+    fn natural_cmp_mixed() {
+        let mut items = vec!["plugin10", "plugin2", "plugin1", "plugin20"];
+        items.sort_by(|a, b| natural_cmp(a, b));
+        assert_eq!(items, vec!["plugin1", "plugin2", "plugin10", "plugin20"]);
+    }
+
+    #[test]
+    fn natural_cmp_empty_strings() {
+        assert_eq!(natural_cmp("", ""), Ordering::Equal);
+        assert_eq!(natural_cmp("", "a"), Ordering::Less);
+        assert_eq!(natural_cmp("a", ""), Ordering::Greater);
+    }
+
+    #[test]
+    fn natural_cmp_pure_numbers() {
+        assert_eq!(natural_cmp("2", "10"), Ordering::Less);
+        assert_eq!(natural_cmp("100", "20"), Ordering::Greater);
+    }
+
+    // ---- GraphState: nodes ----
+
+    #[test]
+    fn graph_state_insert_and_get_node() {
+        let gs = GraphState::new();
+        gs.insert_node(make_node(1, "Node1"));
+
+        let node = gs.get_node(1);
+        assert!(node.is_some());
+        assert_eq!(node.unwrap().name, "Node1");
+    }
+
+    #[test]
+    fn graph_state_remove_node() {
+        let gs = GraphState::new();
+        gs.insert_node(make_node(1, "Node1"));
+
+        let removed = gs.remove_node(1);
+        assert!(removed.is_some());
+        assert!(gs.get_node(1).is_none());
+    }
+
+    #[test]
+    fn graph_state_node_volume_goes_with_the_node() {
+        let gs = GraphState::new();
+        gs.insert_node(make_node(1, "Node1"));
+        assert_eq!(gs.get_node_volume(1), None);
+
+        gs.set_node_volume(1, 0.4);
+        assert_eq!(gs.get_node_volume(1), Some(0.4));
+        gs.remove_node(1);
+        assert_eq!(gs.get_node_volume(1), None);
+    }
+
+    #[test]
+    fn graph_state_remove_nonexistent_node() {
+        let gs = GraphState::new();
+        assert!(gs.remove_node(999).is_none());
+    }
+
+    #[test]
+    fn graph_state_get_all_nodes() {
+        let gs = GraphState::new();
+        gs.insert_node(make_node(1, "A"));
+        gs.insert_node(make_node(2, "B"));
+
+        let all = gs.get_all_nodes();
+        assert_eq!(all.len(), 2);
+    }
+
+    // ---- GraphState: ports ----
+
+    #[test]
+    fn graph_state_insert_and_get_port() {
+        let gs = GraphState::new();
+        gs.insert_port(make_port(10, 1, "out_0", PortDirection::Output));
+
+        let port = gs.get_port(10);
+        assert!(port.is_some());
+        assert_eq!(port.unwrap().name, "out_0");
+    }
+
+    #[test]
+    fn graph_state_remove_port() {
+        let gs = GraphState::new();
+        gs.insert_port(make_port(10, 1, "out_0", PortDirection::Output));
+
+        let removed = gs.remove_port(10);
+        assert!(removed.is_some());
+        assert!(gs.get_port(10).is_none());
+    }
+
+    #[test]
+    fn graph_state_get_ports_for_node() {
+        let gs = GraphState::new();
+        gs.insert_port(make_port(10, 1, "output_0", PortDirection::Output));
+        gs.insert_port(make_port(11, 1, "input_0", PortDirection::Input));
+        gs.insert_port(make_port(12, 2, "output_0", PortDirection::Output)); // different node
+
+        let ports = gs.get_ports_for_node(1);
+        assert_eq!(ports.len(), 2);
+    }
+
+    #[test]
+    fn graph_state_get_input_output_ports() {
+        let gs = GraphState::new();
+        gs.insert_port(make_port(10, 1, "output_0", PortDirection::Output));
+        gs.insert_port(make_port(11, 1, "output_1", PortDirection::Output));
+        gs.insert_port(make_port(12, 1, "input_0", PortDirection::Input));
+
+        assert_eq!(gs.get_input_ports(1).len(), 1);
+        assert_eq!(gs.get_output_ports(1).len(), 2);
+    }
+
+    #[test]
+    fn graph_state_ports_sort_midi_first_and_cv_last() {
+        let gs = GraphState::new();
+        let mut cv = make_port(10, 1, "cv_in_0", PortDirection::Input);
+        cv.media_type = Some(MediaType::Cv);
+        let mut midi = make_port(11, 1, "midi_in", PortDirection::Input);
+        midi.media_type = Some(MediaType::Midi);
+        gs.insert_port(cv);
+        gs.insert_port(midi);
+        gs.insert_port(make_port(12, 1, "input_0", PortDirection::Input));
+
+        let names: Vec<String> = gs.get_input_ports(1).into_iter().map(|p| p.name).collect();
+        assert_eq!(names, ["midi_in", "input_0", "cv_in_0"]);
+    }
+
+    // ---- GraphState: links ----
+
+    #[test]
+    fn graph_state_insert_and_get_link() {
+        let gs = GraphState::new();
+        gs.insert_link(make_link(100, 1, 10, 2, 20));
+
+        let link = gs.get_link(100);
+        assert!(link.is_some());
+        assert_eq!(link.unwrap().output_port_id, 10);
+    }
+
+    #[test]
+    fn graph_state_remove_link() {
+        let gs = GraphState::new();
+        gs.insert_link(make_link(100, 1, 10, 2, 20));
+
+        let removed = gs.remove_link(100);
+        assert!(removed.is_some());
+        assert!(gs.get_link(100).is_none());
+    }
+
+    #[test]
+    fn graph_state_find_link() {
+        let gs = GraphState::new();
+        gs.insert_link(make_link(100, 1, 10, 2, 20));
+
+        assert!(gs.find_link(10, 20).is_some());
+        assert!(gs.find_link(10, 99).is_none());
+    }
+
+    #[test]
+    fn graph_state_get_all_links() {
+        let gs = GraphState::new();
+        gs.insert_link(make_link(100, 1, 10, 2, 20));
+        gs.insert_link(make_link(101, 3, 30, 4, 40));
+
+        assert_eq!(gs.get_all_links().len(), 2);
+    }
+
+    // ---- GraphState: cleanup ----
+
+    #[test]
+    fn graph_state_cleanup_port_removes_associated_links() {
+        let gs = GraphState::new();
+        gs.insert_link(make_link(100, 1, 10, 2, 20));
+        gs.insert_link(make_link(101, 3, 30, 4, 40));
+        gs.insert_link(make_link(102, 1, 10, 5, 50)); // also uses port 10
+
+        let removed = gs.cleanup_port(10);
+        assert_eq!(removed.len(), 2); // links 100 and 102
+        assert!(gs.get_link(100).is_none());
+        assert!(gs.get_link(102).is_none());
+        assert!(gs.get_link(101).is_some()); // unaffected
+    }
+
+    #[test]
+    fn graph_state_cleanup_node_removes_ports_and_links() {
+        let gs = GraphState::new();
+        gs.insert_port(make_port(10, 1, "out", PortDirection::Output));
+        gs.insert_port(make_port(11, 1, "in", PortDirection::Input));
+        gs.insert_port(make_port(20, 2, "out", PortDirection::Output));
+        gs.insert_link(make_link(100, 1, 10, 2, 20));
+
+        let removed_links = gs.cleanup_node(1);
+        assert_eq!(removed_links.len(), 1);
+        assert!(gs.get_port(10).is_none());
+        assert!(gs.get_port(11).is_none());
+        assert!(gs.get_port(20).is_some()); // different node
+    }
+
+    // ---- GraphState: consistency checks ----
+
+    #[test]
+    fn graph_state_prune_orphans_drops_ports_and_links_without_owner() {
+        let gs = GraphState::new();
+        gs.insert_node(make_node(1, "A"));
+        gs.insert_node(make_node(2, "B"));
+        gs.insert_port(make_port(10, 1, "out", PortDirection::Output));
+        gs.insert_port(make_port(20, 2, "in", PortDirection::Input));
+        gs.insert_port(make_port(30, 3, "in", PortDirection::Input)); // node 3 never seen
+        gs.insert_link(make_link(100, 1, 10, 2, 20));
+        gs.insert_link(make_link(101, 1, 10, 3, 30));
+
+        let report = gs.prune_orphans();
+        assert_eq!(report.ports, vec![(30, 3)]);
+        assert_eq!(report.links, vec![101]);
+        assert!(report.nodes.is_empty());
+        assert!(gs.get_link(100).is_some());
+        assert!(gs.get_port(10).is_some());
+    }
+
+    #[test]
+    fn graph_state_prune_orphans_noop_when_consistent() {
+        let gs = GraphState::new();
+        gs.insert_node(make_node(1, "A"));
+        gs.insert_port(make_port(10, 1, "out", PortDirection::Output));
+        let before = gs.change_counter();
+
+        assert!(gs.prune_orphans().is_empty());
+        assert_eq!(gs.change_counter(), before);
+    }
+
+    #[test]
+    fn graph_state_ghost_candidates_need_previous_ports() {
+        let gs = GraphState::new();
+        gs.insert_node(make_node(1, "Lost device"));
+        gs.insert_node(make_node(2, "Dummy-Driver")); // never had ports
+        gs.insert_port(make_port(10, 1, "out", PortDirection::Output));
+        assert!(gs.ghost_candidates().is_empty());
+
+        gs.remove_port(10);
+        assert_eq!(gs.ghost_candidates(), vec![1]);
+
+        gs.insert_port(make_port(11, 1, "out", PortDirection::Output));
+        assert!(gs.ghost_candidates().is_empty());
+    }
+
+    #[test]
+    fn graph_state_retain_objects_drops_unlisted() {
+        let gs = GraphState::new();
+        gs.insert_node(make_node(1, "A"));
+        gs.insert_node(make_node(2, "Ghost"));
+        gs.insert_port(make_port(10, 1, "out", PortDirection::Output));
+        gs.insert_port(make_port(20, 2, "in", PortDirection::Input));
+        gs.insert_link(make_link(100, 1, 10, 2, 20));
+
+        let live: HashSet<ObjectId> = [1, 10].into_iter().collect();
+        let mut report = gs.retain_objects(&live);
+        report.links.sort();
+        assert_eq!(report.nodes, vec![2]);
+        assert_eq!(report.ports, vec![(20, 2)]);
+        assert_eq!(report.links, vec![100]);
+        assert!(gs.get_node(1).is_some());
+        assert!(gs.get_port(10).is_some());
+        assert!(gs.ghost_candidates().is_empty());
+
+        // A node confirmed live without ports is no longer a ghost candidate
+        gs.remove_port(10);
+        assert_eq!(gs.ghost_candidates(), vec![1]);
+        gs.retain_objects(&[1].into_iter().collect());
+        assert!(gs.get_node(1).is_some());
+        assert!(gs.ghost_candidates().is_empty());
+    }
+
+    #[test]
+    fn graph_state_clear_empties_everything() {
+        let gs = GraphState::new();
+        gs.insert_node(make_node(1, "A"));
+        gs.insert_port(make_port(10, 1, "out", PortDirection::Output));
+        gs.insert_link(make_link(100, 1, 10, 2, 20));
+        let before = gs.change_counter();
+
+        gs.clear();
+        assert!(gs.get_all_nodes().is_empty());
+        assert!(gs.get_port(10).is_none());
+        assert!(gs.get_all_links().is_empty());
+        assert!(gs.ghost_candidates().is_empty());
+        assert!(gs.change_counter() > before);
+    }
+
+    // ---- GraphState: change counter ----
+
+    #[test]
+    fn graph_state_change_counter_increments() {
+        let gs = GraphState::new();
+        let initial = gs.change_counter();
+
+        gs.insert_node(make_node(1, "A"));
+        assert!(gs.change_counter() > initial);
+
+        let after_node = gs.change_counter();
+        gs.insert_port(make_port(10, 1, "p", PortDirection::Output));
+        assert!(gs.change_counter() > after_node);
+    }
+
+    // ---- GraphState: node media type propagates to ports ----
+
+    #[test]
+    fn graph_state_insert_node_propagates_media_type() {
+        let gs = GraphState::new();
+        // Insert port first (no media type yet)
+        let mut port = make_port(10, 1, "p", PortDirection::Output);
+        port.media_type = None;
+        gs.insert_port(port);
+
+        // Now insert node with Audio media type
+        gs.insert_node(make_node(1, "A"));
+
+        let port = gs.get_port(10).unwrap();
+        assert_eq!(port.media_type, Some(MediaType::Audio));
+    }
+
+    // ---- GraphState: set_node_type / set_node_description ----
+
+    #[test]
+    fn graph_state_set_node_type() {
+        let gs = GraphState::new();
+        gs.insert_node(make_node(1, "A"));
+
+        gs.set_node_type(1, NodeType::Sink);
+        let node = gs.get_node(1).unwrap();
+        assert_eq!(node.node_type, Some(NodeType::Sink));
+    }
+
+    #[test]
+    fn graph_state_set_node_description() {
+        let gs = GraphState::new();
+        gs.insert_node(make_node(1, "A"));
+
+        gs.set_node_description(1, "My Plugin");
+        let node = gs.get_node(1).unwrap();
+        assert_eq!(node.description, "My Plugin");
+    }
+
+    // ---- Node/Port display_name ----
+
+    #[test]
+    fn node_display_name_prefers_description() {
+        let mut node = make_node(1, "name");
+        node.description = "description".to_string();
+        assert_eq!(node.display_name(), "description");
+    }
+
+    #[test]
+    fn node_display_name_falls_back_to_name() {
+        let node = make_node(1, "name");
+        assert_eq!(node.display_name(), "name");
+    }
+
+    #[test]
+    fn node_display_name_falls_back_to_unknown() {
+        let mut node = make_node(1, "");
+        node.description = "".to_string();
+        assert_eq!(node.display_name(), "Unknown");
+    }
+
+    #[test]
+    fn port_display_name_prefers_channel() {
+        let mut port = make_port(10, 1, "name", PortDirection::Output);
+        port.channel = Some("FL".to_string());
+        assert_eq!(port.display_name(), "FL");
+    }
+
+    #[test]
+    fn port_display_name_falls_back_to_name() {
+        let port = make_port(10, 1, "output_0", PortDirection::Output);
+        assert_eq!(port.display_name(), "output_0");
+    }
+
+    // ---- NodeType predicates ----
+
+    #[test]
+    fn node_type_has_outputs() {
+        assert!(NodeType::Source.has_outputs());
+        assert!(NodeType::StreamOutput.has_outputs());
+        assert!(NodeType::Duplex.has_outputs());
+        assert!(NodeType::Plugin.has_outputs());
+        assert!(!NodeType::Sink.has_outputs());
+        assert!(!NodeType::StreamInput.has_outputs());
+    }
+
+    #[test]
+    fn node_type_has_inputs() {
+        assert!(NodeType::Sink.has_inputs());
+        assert!(NodeType::StreamInput.has_inputs());
+        assert!(NodeType::Duplex.has_inputs());
+        assert!(NodeType::Plugin.has_inputs());
+        assert!(!NodeType::Source.has_inputs());
+        assert!(!NodeType::StreamOutput.has_inputs());
+    }
+
+    // ---- Bridge port groups ----
+
+    #[test]
+    fn graph_state_bridge_port_groups() {
+        let gs = GraphState::new();
+        let mut port1 = make_port(10, 1, "p1", PortDirection::Output);
+        port1.port_group = Some("group1".to_string());
+        port1.port_alias = Some("Device1:Port1".to_string());
+
+        let mut port2 = make_port(11, 1, "p2", PortDirection::Output);
+        port2.port_group = Some("group2".to_string());
+        port2.port_alias = Some("Device2:Port2".to_string());
+
+        gs.insert_port(port1);
+        gs.insert_port(port2);
+
+        let groups = gs.get_bridge_port_groups(1);
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups.get("group1").unwrap(), "Device1");
+        assert_eq!(groups.get("group2").unwrap(), "Device2");
+    }
+
+    // ---- Feedback loops ----
+
+    /// Plugin A (1) feeds plugin B (2); each has ports 10/11 and 20/21.
+    fn chain() -> Arc<GraphState> {
+        let gs = GraphState::new();
+        for (node, name) in [(1, "A"), (2, "B")] {
+            gs.insert_node(make_node(node, name));
+            gs.insert_port(make_port(node * 10, node, "in", PortDirection::Input));
+            gs.insert_port(make_port(node * 10 + 1, node, "out", PortDirection::Output));
+        }
+        gs.insert_link(make_link(100, 1, 11, 2, 20));
+        gs
+    }
+
+    #[test]
+    fn graph_state_finds_feedback_through_a_chain() {
+        let gs = chain();
+        assert_eq!(gs.feedback_path(21, 10), Some(vec![1, 2]));
+        assert_eq!(gs.feedback_path(11, 10), Some(vec![1]));
+        // Onwards, not back: no loop.
+        gs.insert_node(make_node(3, "Speakers"));
+        gs.insert_port(make_port(30, 3, "playback", PortDirection::Input));
+        assert_eq!(gs.feedback_path(21, 30), None);
+    }
+
+    #[test]
+    fn graph_state_checks_every_link_a_command_makes_for_feedback() {
+        let gs = chain();
+        let back = PwCommand::Connect {
+            output_port_id: 21,
+            input_port_id: 10,
+        };
+        let found = gs.closed_feedback_loop(&back).unwrap();
+        assert_eq!(found.nodes, vec![1, 2]);
+        assert_eq!(gs.feedback_loop_text(&found.nodes), "A → B → A");
+        // A device feeding itself is a self-loop, not reported here.
+        let own = PwCommand::Connect {
+            output_port_id: 11,
+            input_port_id: 10,
+        };
+        assert_eq!(gs.closed_feedback_loop(&own), None);
+
+        // Taking A -> B down first leaves nothing to loop through.
+        let rewire = PwCommand::Transaction {
+            label: "Swap".into(),
+            ops: vec![
+                LinkOp::Disconnect { link_id: 100 },
+                LinkOp::Connect {
+                    output_port_id: 21,
+                    input_port_id: 10,
+                },
+            ],
+        };
+        assert_eq!(gs.closed_feedback_loop(&rewire), None);
+
+        // Splicing C onto A -> B and sending C back to A: the loop is only
+        // closed by the links the transaction makes itself.
+        gs.insert_node(make_node(3, "C"));
+        gs.insert_port(make_port(30, 3, "in", PortDirection::Input));
+        gs.insert_port(make_port(31, 3, "out", PortDirection::Output));
+        let splice = PwCommand::Transaction {
+            label: "Insert C".into(),
+            ops: vec![
+                LinkOp::Connect {
+                    output_port_id: 21,
+                    input_port_id: 30,
+                },
+                LinkOp::Connect {
+                    output_port_id: 31,
+                    input_port_id: 10,
+                },
+            ],
+        };
+        let found = gs.closed_feedback_loop(&splice).unwrap();
+        assert_eq!((found.output_port_id, found.input_port_id), (31, 10));
+        assert_eq!(found.nodes, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn graph_state_feedback_ignores_midi_and_other_bridge_devices() {
+        let gs = chain();
+        let mut midi_out = make_port(22, 2, "midi_out", PortDirection::Output);
+        midi_out.media_type = Some(MediaType::Midi);
+        gs.insert_port(midi_out);
+        assert_eq!(gs.feedback_path(22, 10), None);
+
+        // A bridge node with two devices: a link from device x's output to
+        // device y's input is no loop unless y comes back to x.
+        gs.insert_node(make_node(5, "Bridge"));
+        for (id, dir, group) in [
+            (50, PortDirection::Input, "x"),
+            (51, PortDirection::Output, "x"),
+            (52, PortDirection::Input, "y"),
+            (53, PortDirection::Output, "y"),
+        ] {
+            let mut port = make_port(id, 5, "p", dir);
+            port.port_group = Some(group.into());
+            gs.insert_port(port);
+        }
+        assert_eq!(gs.feedback_path(51, 52), None);
+        gs.insert_link(make_link(101, 5, 53, 1, 10));
+        gs.insert_link(make_link(102, 1, 11, 5, 50));
+        assert_eq!(gs.feedback_path(51, 52), Some(vec![5, 1, 5]));
+    }
+
+    // ---- Subscriptions ----
+
+    #[test]
+    fn graph_state_subscription_filters_changes() {
+        let gs = GraphState::new();
+        let mut camera = make_node(2, "Camera");
+        camera.media_type = Some(MediaType::Video);
+        gs.insert_node(make_node(1, "Speakers"));
+        gs.insert_node(camera);
+
+        let rx = gs.subscribe(GraphFilter::all().media(MediaType::Audio));
+        gs.insert_port(make_port(10, 1, "playback_FL", PortDirection::Input));
+        gs.insert_port(make_port(20, 2, "capture_0", PortDirection::Output));
+        gs.remove_node(2);
+
+        let changes: Vec<GraphChange> = rx.try_iter().collect();
+        assert_eq!(changes.len(), 1);
+        assert!(matches!(&changes[0], GraphChange::PortChanged(p) if p.id == 10));
+
+        let removed = gs.cleanup_node(1);
+        assert!(removed.is_empty());
+        assert!(matches!(rx.try_recv(), Ok(GraphChange::PortRemoved(p)) if p.id == 10));
+    }
+
+    #[test]
+    fn graph_state_drops_closed_subscriptions() {
+        let gs = GraphState::new();
+        drop(gs.subscribe(GraphFilter::all()));
+        gs.insert_node(make_node(1, "Speakers"));
+        assert!(gs.subscribers.lock().is_empty());
+    }
+
+    #[test]
+    fn graph_state_lists_in_id_order_and_tracks_activity() {
+        let gs = GraphState::new();
+        for id in [9, 3, 6] {
+            gs.insert_node(make_node(id, "Node"));
+        }
+        gs.insert_link(make_link(40, 9, 91, 3, 31));
+        gs.insert_link(make_link(20, 3, 32, 6, 61));
+        let ids: Vec<ObjectId> = gs.get_all_nodes().iter().map(|n| n.id).collect();
+        assert_eq!(ids, [3, 6, 9]);
+        let ids: Vec<ObjectId> = gs.get_all_links().iter().map(|l| l.id).collect();
+        assert_eq!(ids, [20, 40]);
+
+        assert!(gs.node_activity(6) > gs.node_activity(9));
+        gs.insert_node(make_node(9, "Renamed"));
+        gs.remove_link(40);
+        assert!(gs.node_activity(9) > gs.node_activity(6));
+        assert_eq!(gs.node_activity(3), gs.node_activity(9));
+        gs.remove_node(9);
+        assert_eq!(gs.node_activity(9), 0);
+    }
+
+    #[test]
+    fn graph_state_applies_channel_overrides_to_ports() {
+        let gs = GraphState::new();
+        gs.insert_node(make_node(1, "alsa_input.usb"));
+        let mut port = make_port(10, 1, "capture_AUX0", PortDirection::Output);
+        port.channel = Some("AUX0".to_string());
+        gs.insert_port(port.clone());
+
+        let mut overrides = ChannelOverrides::default();
+        overrides.set("alsa_input.usb", "capture_AUX0", Some("FL"));
+        gs.set_channel_overrides(overrides);
+        assert_eq!(gs.get_port(10).unwrap().channel.as_deref(), Some("FL"));
+        assert_eq!(gs.reported_channel(10).as_deref(), Some("AUX0"));
+
+        // Re-announced ports keep the override.
+        gs.insert_port(port);
+        assert_eq!(gs.get_port(10).unwrap().channel.as_deref(), Some("FL"));
+
+        gs.set_channel_overrides(ChannelOverrides::default());
+        assert_eq!(gs.get_port(10).unwrap().channel.as_deref(), Some("AUX0"));
+    }
+}
@@ -0,0 +1,208 @@
+//! Change notifications from [`GraphState`](super::GraphState).
+//!
+//! A subscriber registers a [`GraphFilter`] and gets a channel that receives
+//! only the [`GraphChange`]s touching nodes the filter selects, instead of
+//! re-reading the whole graph whenever anything moved.
+
+use std::sync::mpsc::Sender;
+
+use super::types::*;
+use crate::patchbay::rules::pattern_matches;
+
+/// One change to the graph, carrying the object as it was after an update or
+/// just before its removal.
+#[derive(Debug, Clone)]
+pub enum GraphChange {
+    NodeChanged(Node),
+    NodeRemoved(Node),
+    PortChanged(Port),
+    PortRemoved(Port),
+    LinkChanged(Link),
+    LinkRemoved(Link),
+    /// The negotiated format of a node changed.
+    FormatChanged(ObjectId),
+    DefaultSinkChanged(Option<String>),
+    /// Every object was dropped ahead of a full resync.
+    Cleared,
+}
+
+/// The kind of graph object a change is about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GraphObject {
+    Node,
+    Port,
+    Link,
+}
+
+impl GraphChange {
+    /// The nodes this change concerns. Empty for changes to the graph as a
+    /// whole, which every subscriber receives.
+    pub fn node_ids(&self) -> Vec<ObjectId> {
+        match self {
+            Self::NodeChanged(n) | Self::NodeRemoved(n) => vec![n.id],
+            Self::PortChanged(p) | Self::PortRemoved(p) => vec![p.node_id],
+            Self::LinkChanged(l) | Self::LinkRemoved(l) => {
+                vec![l.output_node_id, l.input_node_id]
+            }
+            Self::FormatChanged(id) => vec![*id],
+            Self::DefaultSinkChanged(_) | Self::Cleared => Vec::new(),
+        }
+    }
+
+    /// The kind of object this change is about. `None` for changes to the
+    /// graph as a whole.
+    pub fn object(&self) -> Option<GraphObject> {
+        match self {
+            Self::NodeChanged(_) | Self::NodeRemoved(_) | Self::FormatChanged(_) => {
+                Some(GraphObject::Node)
+            }
+            Self::PortChanged(_) | Self::PortRemoved(_) => Some(GraphObject::Port),
+            Self::LinkChanged(_) | Self::LinkRemoved(_) => Some(GraphObject::Link),
+            Self::DefaultSinkChanged(_) | Self::Cleared => None,
+        }
+    }
+}
+
+/// Which changes a subscriber wants. Empty lists select everything.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GraphFilter {
+    /// Media types of interest. Nodes whose media type is not known yet
+    /// always pass.
+    pub media_types: Vec<MediaType>,
+    /// Rule-style patterns (`*` wildcards) matched against node display
+    /// names.
+    pub node_patterns: Vec<String>,
+    /// Kinds of object of interest. Changes to the graph as a whole always
+    /// pass.
+    pub objects: Vec<GraphObject>,
+}
+
+impl GraphFilter {
+    /// A filter that lets every change through.
+    pub fn all() -> Self {
+        Self::default()
+    }
+
+    pub fn media(mut self, media_type: MediaType) -> Self {
+        self.media_types.push(media_type);
+        self
+    }
+
+    pub fn node(mut self, pattern: impl Into<String>) -> Self {
+        self.node_patterns.push(pattern.into());
+        self
+    }
+
+    pub fn object(mut self, object: GraphObject) -> Self {
+        self.objects.push(object);
+        self
+    }
+
+    pub fn matches_node(&self, node: &Node) -> bool {
+        let media_ok = self.media_types.is_empty()
+            || node
+                .media_type
+                .is_none_or(|mt| self.media_types.contains(&mt));
+        let name_ok = self.node_patterns.is_empty()
+            || self
+                .node_patterns
+                .iter()
+                .any(|p| pattern_matches(p, node.display_name()));
+        media_ok && name_ok
+    }
+
+    /// Whether `change` should reach the subscriber. `lookup` resolves the
+    /// node ids of ports, links and formats; a node it cannot resolve, such
+    /// as the owner of a port removed after its node, passes the filter.
+    pub fn matches<'a>(
+        &self,
+        change: &GraphChange,
+        lookup: impl Fn(ObjectId) -> Option<&'a Node>,
+    ) -> bool {
+        if let Some(object) = change.object()
+            && !self.objects.is_empty()
+            && !self.objects.contains(&object)
+        {
+            return false;
+        }
+        match change {
+            GraphChange::NodeChanged(n) | GraphChange::NodeRemoved(n) => self.matches_node(n),
+            _ => {
+                let ids = change.node_ids();
+                ids.is_empty()
+                    || ids
+                        .into_iter()
+                        .any(|id| lookup(id).is_none_or(|n| self.matches_node(n)))
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(super) struct Subscriber {
+    pub filter: GraphFilter,
+    pub tx: Sender<GraphChange>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(id: ObjectId, name: &str, media_type: Option<MediaType>) -> Node {
+        Node {
+            description: name.into(),
+            media_type,
+            ..Node::test(id, name, NodeType::Sink)
+        }
+    }
+
+    fn link(output_node_id: ObjectId, input_node_id: ObjectId) -> Link {
+        Link {
+            id: 100,
+            output_node_id,
+            output_port_id: 10,
+            input_node_id,
+            input_port_id: 20,
+            active: true,
+        }
+    }
+
+    #[test]
+    fn test_filter_by_media_and_name() {
+        let filter = GraphFilter::all().media(MediaType::Audio).node("USB*");
+        assert!(filter.matches_node(&node(1, "USB Headset", Some(MediaType::Audio))));
+        assert!(filter.matches_node(&node(1, "USB Headset", None)));
+        assert!(!filter.matches_node(&node(1, "USB Camera", Some(MediaType::Video))));
+        assert!(!filter.matches_node(&node(1, "Speakers", Some(MediaType::Audio))));
+        assert!(GraphFilter::all().matches_node(&node(1, "Anything", Some(MediaType::Video))));
+    }
+
+    #[test]
+    fn test_link_matches_when_either_end_does() {
+        let nodes = [
+            node(1, "Firefox", Some(MediaType::Audio)),
+            node(2, "Webcam", Some(MediaType::Video)),
+            node(3, "Screen", Some(MediaType::Video)),
+        ];
+        let lookup = |id| nodes.iter().find(|n| n.id == id);
+        let filter = GraphFilter::all().media(MediaType::Audio);
+
+        assert!(filter.matches(&GraphChange::LinkChanged(link(1, 2)), lookup));
+        assert!(!filter.matches(&GraphChange::LinkChanged(link(3, 2)), lookup));
+        // Unknown nodes pass rather than hide a removal.
+        assert!(filter.matches(&GraphChange::LinkRemoved(link(3, 9)), lookup));
+        assert!(filter.matches(&GraphChange::Cleared, lookup));
+    }
+
+    #[test]
+    fn test_filter_by_object_kind() {
+        let nodes = [node(1, "Firefox", Some(MediaType::Audio))];
+        let lookup = |id| nodes.iter().find(|n| n.id == id);
+        let filter = GraphFilter::all().object(GraphObject::Link);
+
+        assert!(filter.matches(&GraphChange::LinkRemoved(link(1, 2)), lookup));
+        assert!(!filter.matches(&GraphChange::NodeChanged(nodes[0].clone()), lookup));
+        assert!(!filter.matches(&GraphChange::FormatChanged(1), lookup));
+        assert!(filter.matches(&GraphChange::DefaultSinkChanged(None), lookup));
+    }
+}
@@ -0,0 +1,316 @@
+use std::collections::VecDeque;
+use std::fmt;
+
+use super::state::GraphState;
+use super::types::{LinkOp, ObjectId};
+
+/// A link change with both endpoints resolved. Steps are identified by their
+/// ports rather than by link id, so they can be verified and inverted even
+/// though PipeWire assigns a fresh id every time a link is created.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LinkStep {
+    Connect {
+        output_port_id: ObjectId,
+        input_port_id: ObjectId,
+    },
+    Disconnect {
+        output_port_id: ObjectId,
+        input_port_id: ObjectId,
+    },
+}
+
+impl LinkStep {
+    pub fn inverse(self) -> Self {
+        match self {
+            LinkStep::Connect {
+                output_port_id,
+                input_port_id,
+            } => LinkStep::Disconnect {
+                output_port_id,
+                input_port_id,
+            },
+            LinkStep::Disconnect {
+                output_port_id,
+                input_port_id,
+            } => LinkStep::Connect {
+                output_port_id,
+                input_port_id,
+            },
+        }
+    }
+
+    /// Whether the graph already reflects this step.
+    pub fn is_satisfied(&self, graph: &GraphState) -> bool {
+        match *self {
+            LinkStep::Connect {
+                output_port_id,
+                input_port_id,
+            } => graph.find_link(output_port_id, input_port_id).is_some(),
+            LinkStep::Disconnect {
+                output_port_id,
+                input_port_id,
+            } => graph.find_link(output_port_id, input_port_id).is_none(),
+        }
+    }
+}
+
+impl fmt::Display for LinkStep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LinkStep::Connect {
+                output_port_id,
+                input_port_id,
+            } => write!(f, "connect port {} -> {}", output_port_id, input_port_id),
+            LinkStep::Disconnect {
+                output_port_id,
+                input_port_id,
+            } => write!(f, "disconnect port {} -> {}", output_port_id, input_port_id),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TxOutcome {
+    Committed,
+    /// `failed` could not be applied; every earlier step was undone.
+    RolledBack { failed: LinkStep },
+}
+
+/// A group of link operations applied one at a time. After each step the
+/// caller round-trips to the server and calls [`Transaction::verify`]; the
+/// first step that does not show up in the graph flips the transaction into
+/// rollback, which undoes the applied steps in reverse order.
+#[derive(Debug)]
+pub struct Transaction {
+    pub label: String,
+    ops: VecDeque<LinkOp>,
+    /// Inverse of every step applied so far, oldest first.
+    undo: Vec<LinkStep>,
+    rollback: VecDeque<LinkStep>,
+    in_flight: Option<LinkStep>,
+    failed: Option<LinkStep>,
+}
+
+impl Transaction {
+    pub fn new(label: impl Into<String>, ops: Vec<LinkOp>) -> Self {
+        Self {
+            label: label.into(),
+            ops: ops.into(),
+            undo: Vec::new(),
+            rollback: VecDeque::new(),
+            in_flight: None,
+            failed: None,
+        }
+    }
+
+    pub fn has_in_flight(&self) -> bool {
+        self.in_flight.is_some()
+    }
+
+    pub fn is_rolling_back(&self) -> bool {
+        self.failed.is_some()
+    }
+
+    /// Take the next step to apply, or `None` once the transaction has
+    /// finished. Steps the graph already satisfies are skipped, as are
+    /// disconnects of links that have disappeared in the meantime.
+    pub fn next_step(&mut self, graph: &GraphState) -> Option<LinkStep> {
+        debug_assert!(self.in_flight.is_none());
+        loop {
+            let step = if self.failed.is_some() {
+                self.rollback.pop_front()?
+            } else {
+                match self.ops.pop_front()? {
+                    LinkOp::Connect {
+                        output_port_id,
+                        input_port_id,
+                    } => LinkStep::Connect {
+                        output_port_id,
+                        input_port_id,
+                    },
+                    LinkOp::Disconnect { link_id } => match graph.get_link(link_id) {
+                        Some(link) => LinkStep::Disconnect {
+                            output_port_id: link.output_port_id,
+                            input_port_id: link.input_port_id,
+                        },
+                        None => continue,
+                    },
+                }
+            };
+            if step.is_satisfied(graph) {
+                continue;
+            }
+            self.in_flight = Some(step);
+            return Some(step);
+        }
+    }
+
+    /// Check the in-flight step once the server has processed it. Returns
+    /// `false` if it did not take effect. Failures while rolling back are
+    /// reported but do not stop the remaining undo steps.
+    pub fn verify(&mut self, graph: &GraphState) -> bool {
+        let Some(step) = self.in_flight.take() else {
+            return true;
+        };
+        let ok = step.is_satisfied(graph);
+        if self.failed.is_none() {
+            if ok {
+                self.undo.push(step.inverse());
+            } else {
+                self.failed = Some(step);
+                self.rollback = self.undo.drain(..).rev().collect();
+            }
+        }
+        ok
+    }
+
+    pub fn outcome(&self) -> TxOutcome {
+        match self.failed {
+            Some(failed) => TxOutcome::RolledBack { failed },
+            None => TxOutcome::Committed,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::types::*;
+
+    /// Apply whatever step the transaction hands out, making it succeed
+    /// unless `fail` says otherwise, and return the steps in order.
+    fn run(
+        tx: &mut Transaction,
+        graph: &GraphState,
+        fail: impl Fn(&LinkStep) -> bool,
+    ) -> Vec<LinkStep> {
+        let mut next_id = 500;
+        let mut applied = Vec::new();
+        while let Some(step) = tx.next_step(graph) {
+            applied.push(step);
+            if !fail(&step) {
+                match step {
+                    LinkStep::Connect {
+                        output_port_id,
+                        input_port_id,
+                    } => {
+                        graph.insert_link(Link::test(
+                            next_id,
+                            (1, output_port_id),
+                            (2, input_port_id),
+                        ));
+                        next_id += 1;
+                    }
+                    LinkStep::Disconnect {
+                        output_port_id,
+                        input_port_id,
+                    } => {
+                        let id = graph.find_link(output_port_id, input_port_id).unwrap().id;
+                        graph.remove_link(id);
+                    }
+                }
+            }
+            tx.verify(graph);
+        }
+        applied
+    }
+
+    #[test]
+    fn test_transaction_commits() {
+        let graph = GraphState::new();
+        graph.insert_link(Link::test(100, (1, 10), (2, 20)));
+        let mut tx = Transaction::new(
+            "insert",
+            vec![
+                LinkOp::Disconnect { link_id: 100 },
+                LinkOp::Connect {
+                    output_port_id: 10,
+                    input_port_id: 30,
+                },
+                LinkOp::Connect {
+                    output_port_id: 31,
+                    input_port_id: 20,
+                },
+            ],
+        );
+        let applied = run(&mut tx, &graph, |_| false);
+        assert_eq!(applied.len(), 3);
+        assert_eq!(tx.outcome(), TxOutcome::Committed);
+        assert!(graph.find_link(10, 20).is_none());
+        assert!(graph.find_link(10, 30).is_some());
+        assert!(graph.find_link(31, 20).is_some());
+    }
+
+    #[test]
+    fn test_transaction_rolls_back_on_failure() {
+        let graph = GraphState::new();
+        graph.insert_link(Link::test(100, (1, 10), (2, 20)));
+        let mut tx = Transaction::new(
+            "insert",
+            vec![
+                LinkOp::Disconnect { link_id: 100 },
+                LinkOp::Connect {
+                    output_port_id: 10,
+                    input_port_id: 30,
+                },
+                LinkOp::Connect {
+                    output_port_id: 31,
+                    input_port_id: 20,
+                },
+            ],
+        );
+        let failing = LinkStep::Connect {
+            output_port_id: 31,
+            input_port_id: 20,
+        };
+        let applied = run(&mut tx, &graph, |s| *s == failing);
+
+        assert_eq!(tx.outcome(), TxOutcome::RolledBack { failed: failing });
+        // Three forward steps, then the two successful ones undone in reverse.
+        assert_eq!(applied.len(), 5);
+        assert_eq!(
+            applied[3],
+            LinkStep::Disconnect {
+                output_port_id: 10,
+                input_port_id: 30,
+            }
+        );
+        assert!(graph.find_link(10, 20).is_some());
+        assert!(graph.find_link(10, 30).is_none());
+        assert!(graph.find_link(31, 20).is_none());
+    }
+
+    #[test]
+    fn test_transaction_skips_satisfied_steps() {
+        let graph = GraphState::new();
+        graph.insert_link(Link::test(100, (1, 10), (2, 20)));
+        let mut tx = Transaction::new(
+            "noop",
+            vec![
+                LinkOp::Connect {
+                    output_port_id: 10,
+                    input_port_id: 20,
+                },
+                LinkOp::Disconnect { link_id: 999 },
+            ],
+        );
+        assert!(tx.next_step(&graph).is_none());
+        assert_eq!(tx.outcome(), TxOutcome::Committed);
+    }
+
+    #[test]
+    fn test_failure_on_first_step_has_nothing_to_undo() {
+        let graph = GraphState::new();
+        let mut tx = Transaction::new(
+            "connect",
+            vec![LinkOp::Connect {
+                output_port_id: 1,
+                input_port_id: 2,
+            }],
+        );
+        let applied = run(&mut tx, &graph, |_| true);
+        assert_eq!(applied.len(), 1);
+        assert!(tx.is_rolling_back());
+        assert!(matches!(tx.outcome(), TxOutcome::RolledBack { .. }));
+    }
+}
@@ -0,0 +1,530 @@
+use serde::{Deserialize, Serialize};
+
+use super::conversion::ConvertParam;
+use crate::midi::link_filter::MidiLinkOptions;
+use crate::midi::types::{MappingMode, MidiCcMapping, MidiCcSource};
+
+pub type ObjectId = u32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum MediaType {
+    Audio,
+    Video,
+    Midi,
+    /// Audio-rate control signals, such as the CV ports of LV2 plugins.
+    Cv,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum NodeType {
+    Sink,
+    Source,
+    StreamOutput,
+    StreamInput,
+    Duplex,
+    Plugin,
+}
+
+impl NodeType {
+    pub fn has_outputs(&self) -> bool {
+        matches!(
+            self,
+            NodeType::Source | NodeType::StreamOutput | NodeType::Duplex | NodeType::Plugin
+        )
+    }
+
+    pub fn has_inputs(&self) -> bool {
+        matches!(
+            self,
+            NodeType::Sink | NodeType::StreamInput | NodeType::Duplex | NodeType::Plugin
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum PortDirection {
+    Input,
+    Output,
+}
+
+#[derive(Debug, Clone)]
+pub struct Node {
+    pub id: ObjectId,
+    pub name: String,
+    pub description: String,
+    pub media_type: Option<MediaType>,
+    pub node_type: Option<NodeType>,
+    pub is_virtual: bool,
+    pub is_jack: bool,
+    pub is_bridge: bool,
+    /// PipeWire `media.role` (e.g. "Music", "Communication"), set by
+    /// applications on their streams.
+    pub media_role: Option<String>,
+    /// Identifies the client application owning a stream: `application.id`
+    /// when set, otherwise `pid:<application.process.id>`.
+    pub application_id: Option<String>,
+    /// PipeWire `application.name`, used to label grouped streams.
+    pub application_name: Option<String>,
+    /// PipeWire `application.process.binary`, which unlike the name does not
+    /// change with the application's locale or version.
+    pub application_binary: Option<String>,
+    /// Serial number of the device behind the node, from `device.serial` or
+    /// the Bluetooth address.
+    pub device_serial: Option<String>,
+    pub ready: bool,
+}
+
+impl Node {
+    /// A ready audio node with nothing else set, for tests to adjust with
+    /// struct update syntax.
+    #[cfg(test)]
+    pub(crate) fn test(id: ObjectId, name: &str, node_type: NodeType) -> Self {
+        Self {
+            id,
+            name: name.to_string(),
+            description: String::new(),
+            media_type: Some(MediaType::Audio),
+            node_type: Some(node_type),
+            is_virtual: false,
+            is_jack: false,
+            is_bridge: false,
+            media_role: None,
+            application_id: None,
+            application_name: None,
+            application_binary: None,
+            device_serial: None,
+            ready: true,
+        }
+    }
+
+    pub fn display_name(&self) -> &str {
+        if !self.description.is_empty() {
+            &self.description
+        } else if !self.name.is_empty() {
+            &self.name
+        } else {
+            "Unknown"
+        }
+    }
+}
+
+/// Format a node negotiated with PipeWire, from its `Format` param and
+/// `node.latency` property. Fields stay `None` until PipeWire reports them,
+/// and the format fields are cleared again while the node is suspended.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NodeFormat {
+    pub rate: Option<u32>,
+    pub channels: Option<u32>,
+    /// Sample format without endianness, e.g. "F32" or "S24_32".
+    pub sample_format: Option<String>,
+    /// Quantum the node asked for through `node.latency`.
+    pub quantum: Option<u32>,
+}
+
+impl NodeFormat {
+    /// Short badge text such as "48kHz / 2ch / F32".
+    pub fn label(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some(rate) = self.rate {
+            parts.push(format_rate(rate));
+        }
+        if let Some(channels) = self.channels {
+            parts.push(format!("{}ch", channels));
+        }
+        if let Some(ref sample_format) = self.sample_format {
+            parts.push(sample_format.clone());
+        }
+        parts.join(" / ")
+    }
+
+    /// Whether audio from a node with this format into one with `target`
+    /// passes through a sample rate converter.
+    pub fn resamples_to(&self, target: &NodeFormat) -> bool {
+        matches!((self.rate, target.rate), (Some(a), Some(b)) if a != b)
+    }
+
+    /// Whether the sample format changes between this node and `target`.
+    pub fn converts_to(&self, target: &NodeFormat) -> bool {
+        matches!(
+            (&self.sample_format, &target.sample_format),
+            (Some(a), Some(b)) if a != b
+        )
+    }
+}
+
+/// A sample rate in kHz, e.g. "48kHz" or "44.1kHz".
+pub fn format_rate(rate: u32) -> String {
+    if rate.is_multiple_of(1000) {
+        format!("{}kHz", rate / 1000)
+    } else {
+        format!("{:.1}kHz", rate as f64 / 1000.0)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Port {
+    pub id: ObjectId,
+    pub node_id: ObjectId,
+    pub name: String,
+    pub direction: PortDirection,
+    pub media_type: Option<MediaType>,
+    pub channel: Option<String>,
+    pub physical_index: Option<u32>,
+    pub port_group: Option<String>,
+    pub port_alias: Option<String>,
+}
+
+impl Port {
+    /// An audio port with no channel or grouping, for tests to adjust with
+    /// struct update syntax.
+    #[cfg(test)]
+    pub(crate) fn test(
+        id: ObjectId,
+        node_id: ObjectId,
+        name: &str,
+        direction: PortDirection,
+    ) -> Self {
+        Self {
+            id,
+            node_id,
+            name: name.to_string(),
+            direction,
+            media_type: Some(MediaType::Audio),
+            channel: None,
+            physical_index: None,
+            port_group: None,
+            port_alias: None,
+        }
+    }
+
+    pub fn display_name(&self) -> &str {
+        if let Some(ref channel) = self.channel {
+            channel
+        } else if !self.name.is_empty() {
+            &self.name
+        } else {
+            "port"
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Link {
+    pub id: ObjectId,
+    pub output_node_id: ObjectId,
+    pub output_port_id: ObjectId,
+    pub input_node_id: ObjectId,
+    pub input_port_id: ObjectId,
+    pub active: bool,
+}
+
+impl Link {
+    /// An active link between two `(node, port)` pairs, for tests.
+    #[cfg(test)]
+    pub(crate) fn test(
+        id: ObjectId,
+        (output_node_id, output_port_id): (ObjectId, ObjectId),
+        (input_node_id, input_port_id): (ObjectId, ObjectId),
+    ) -> Self {
+        Self {
+            id,
+            output_node_id,
+            output_port_id,
+            input_node_id,
+            input_port_id,
+            active: true,
+        }
+    }
+}
+
+/// A link that would close an audio feedback loop, with the nodes the
+/// sound would go round through, starting at the link's input node.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FeedbackLoop {
+    pub output_port_id: ObjectId,
+    pub input_port_id: ObjectId,
+    pub nodes: Vec<ObjectId>,
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Clone)]
+pub enum PwEvent {
+    NodeChanged(Node),
+    NodeRemoved(ObjectId),
+    PortChanged(Port),
+    PortRemoved {
+        port_id: ObjectId,
+        node_id: ObjectId,
+    },
+    LinkChanged(Link),
+    LinkRemoved(ObjectId),
+    Error(String),
+    BatchComplete,
+    Plugin(PluginEvent),
+    /// The PipeWire daemon is not reachable yet; another attempt follows.
+    WaitingForDaemon {
+        attempt: u32,
+    },
+    /// Connected to the PipeWire daemon; the graph is being enumerated.
+    Connected,
+    /// A probe node was stopped: the peak heard at each of its inputs, in
+    /// dBFS.
+    ProbeFinished {
+        probe_id: u32,
+        peaks_db: Vec<f32>,
+    },
+}
+
+/// One link change inside a [`PwCommand::Transaction`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LinkOp {
+    Connect {
+        output_port_id: ObjectId,
+        input_port_id: ObjectId,
+    },
+    Disconnect {
+        link_id: ObjectId,
+    },
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Clone)]
+pub enum PwCommand {
+    Connect {
+        output_port_id: ObjectId,
+        input_port_id: ObjectId,
+    },
+    Disconnect {
+        link_id: ObjectId,
+    },
+    /// Apply several link changes as a unit. Each step is verified before the
+    /// next is sent; if one fails, the steps already applied are undone.
+    Transaction {
+        label: String,
+        ops: Vec<LinkOp>,
+    },
+    AddPlugin {
+        plugin_uri: String,
+        instance_id: u64,
+        display_name: String,
+        /// "LV2", "CLAP", or "VST3"
+        format: String,
+        lv2_state: Vec<crate::plugin::state::StateEntry>,
+        /// 1 (off), 2 or 4
+        oversampling: u32,
+    },
+    /// Re-create a running plugin under the same instance id, e.g. to apply
+    /// a new oversampling factor. LV2 state is carried over.
+    ReloadPlugin {
+        plugin_uri: String,
+        instance_id: u64,
+        display_name: String,
+        format: String,
+        oversampling: u32,
+    },
+    RemovePlugin {
+        instance_id: u64,
+    },
+    SetPluginParameter {
+        instance_id: u64,
+        port_index: usize,
+        value: f32,
+    },
+    SetPluginBypass {
+        instance_id: u64,
+        bypassed: bool,
+    },
+    /// Ask PipeWire for a processing buffer of `quantum` samples through the
+    /// plugin node's `node.latency`, or withdraw the request with `None`.
+    SetPluginQuantum {
+        instance_id: u64,
+        quantum: Option<u32>,
+    },
+    /// Set audioconvert options such as `resample.quality` on any node
+    /// through the `params` of its `Props` parameter.
+    SetNodeConversion {
+        node_id: ObjectId,
+        params: Vec<ConvertParam>,
+    },
+    /// Mute or unmute any node through its `Props` parameter.
+    SetNodeMute {
+        node_id: ObjectId,
+        mute: bool,
+    },
+    /// Ramp a node's `Props` volume from `from` to `to` over `duration_ms`,
+    /// equal-power, for crossfades. `from` is applied before any command
+    /// sent after this one; a zero duration sets `to` at once.
+    RampNodeVolume {
+        node_id: ObjectId,
+        from: f32,
+        to: f32,
+        duration_ms: u32,
+    },
+    /// Make the audio sink with `node_name` the default, as a desktop's
+    /// sound settings would, through the session manager's metadata.
+    SetDefaultSink {
+        node_name: String,
+    },
+    OpenPluginUI {
+        instance_id: u64,
+    },
+    ClosePluginUI {
+        instance_id: u64,
+    },
+    StartMidiLearn {
+        instance_id: u64,
+        port_index: usize,
+        label: String,
+        mode: MappingMode,
+    },
+    CancelMidiLearn,
+    AddMidiMapping(MidiCcMapping),
+    RemoveMidiMapping(MidiCcSource),
+    RemoveMidiMappingsForPlugin {
+        instance_id: u64,
+    },
+    RemoveMidiMappingsForDevice {
+        device_name: String,
+    },
+    /// Create the internal filter node of a filtered MIDI link, or change
+    /// what an existing one drops.
+    SetMidiLinkFilter {
+        filter_id: u32,
+        options: MidiLinkOptions,
+    },
+    /// Remove the internal filter node of a MIDI link.
+    RemoveMidiLinkFilter {
+        filter_id: u32,
+    },
+    /// Create the internal gain node of a link with a gain, or change the
+    /// gain of an existing one.
+    SetLinkGain {
+        gain_id: u32,
+        gain_db: f32,
+    },
+    /// Remove the internal gain node of a link.
+    RemoveLinkGain {
+        gain_id: u32,
+    },
+    /// Create a probe node with `inputs` mono inputs that keep the peak of
+    /// whatever they hear.
+    StartProbe {
+        probe_id: u32,
+        inputs: usize,
+    },
+    /// Remove a probe node and report its peaks with
+    /// [`PwEvent::ProbeFinished`].
+    StopProbe {
+        probe_id: u32,
+    },
+    /// Save the state of every LV2 plugin with the state extension. Each
+    /// is reported with [`PluginEvent::Lv2StateSaved`], and then
+    /// [`PluginEvent::Lv2StatesSaved`] follows.
+    SaveLv2States,
+    /// How often the PipeWire thread runs queued operations and flushes
+    /// graph changes. Takes effect from its next tick.
+    SetTickInterval {
+        interval_ms: u64,
+    },
+    /// Re-enumerate the PipeWire registry and drop any nodes, ports or links
+    /// that no longer exist on the server.
+    RefreshGraph,
+    /// Drop the local graph entirely and rebuild it from a fresh registry
+    /// enumeration. Hosted plugin instances are kept.
+    ResyncGraph,
+    /// Disconnect from PipeWire and end the thread.
+    Shutdown,
+}
+
+impl From<LinkOp> for PwCommand {
+    fn from(op: LinkOp) -> Self {
+        match op {
+            LinkOp::Connect {
+                output_port_id,
+                input_port_id,
+            } => PwCommand::Connect {
+                output_port_id,
+                input_port_id,
+            },
+            LinkOp::Disconnect { link_id } => PwCommand::Disconnect { link_id },
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum PluginEvent {
+    PluginAdded {
+        instance_id: u64,
+        pw_node_id: ObjectId,
+        display_name: String,
+    },
+    PluginRemoved {
+        instance_id: u64,
+    },
+    /// Descriptions, units and step labels a CLAP or VST3 instance reported
+    /// for its parameters, by port index.
+    ParamDocs {
+        instance_id: u64,
+        docs: Vec<(usize, crate::plugin::ParamDoc)>,
+    },
+    ParameterChanged {
+        instance_id: u64,
+        port_index: usize,
+        value: f32,
+    },
+    PluginUiOpened {
+        instance_id: u64,
+    },
+    PluginUiClosed {
+        instance_id: u64,
+    },
+    /// The X11 top-level window an open plugin UI lives in, so it can be
+    /// docked in the main window.
+    PluginUiWindow {
+        instance_id: u64,
+        window_id: u64,
+    },
+    /// An open plugin UI window was moved or resized.
+    PluginUiGeometry {
+        instance_id: u64,
+        geometry: crate::plugin::ui_geometry::UiGeometry,
+    },
+    /// The shared in-process GTK UI thread stopped responding, stuck in the
+    /// UI of `instance_id` if known. Other in-process UIs are frozen too.
+    PluginUiStalled {
+        instance_id: Option<u64>,
+    },
+    /// The shared GTK UI thread is responding again.
+    PluginUiRecovered,
+    PluginError {
+        instance_id: Option<u64>,
+        message: String,
+        fatal: bool,
+    },
+    MidiLearnStarted {
+        instance_id: u64,
+        port_index: usize,
+    },
+    MidiLearnCancelled,
+    MidiMappingAdded(MidiCcMapping),
+    MidiMappingRemoved(MidiCcSource),
+    MidiMappingConflict {
+        source: MidiCcSource,
+        existing_label: String,
+    },
+    MidiCcReceived {
+        device_name: String,
+        channel: u8,
+        cc: u8,
+        message_type: crate::midi::MidiMessageType,
+    },
+    Lv2StateSaved {
+        instance_id: u64,
+        state: Vec<crate::plugin::state::StateEntry>,
+    },
+    /// All states asked for by [`PwCommand::SaveLv2States`] were sent.
+    Lv2StatesSaved,
+}
+
+/// Backward-compatible alias for `PluginEvent`.
+pub type Lv2Event = PluginEvent;
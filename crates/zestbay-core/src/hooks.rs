@@ -0,0 +1,180 @@
+//! Hook scripts: external commands run when something happens.
+//!
+//! Each hook names an event and a shell command. When the event fires, the
+//! command runs with a JSON payload on stdin and `ZESTBAY_EVENT` set to the
+//! event name; it is killed if it outlives its timeout. Hooks live in
+//! `hooks.json` and can be switched off one by one.
+
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+pub const DEFAULT_TIMEOUT_SECS: u64 = 10;
+pub const MAX_TIMEOUT_SECS: u64 = 300;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HookEvent {
+    /// An application started recording audio.
+    RecordingStarted,
+    /// A sink, source or duplex device appeared.
+    DeviceConnected,
+    /// A scene (a saved rule set) was switched to.
+    SceneActivated,
+    /// Plugin overruns reached the warning threshold within a minute.
+    XrunStorm,
+}
+
+impl HookEvent {
+    pub const ALL: [HookEvent; 4] = [
+        HookEvent::RecordingStarted,
+        HookEvent::DeviceConnected,
+        HookEvent::SceneActivated,
+        HookEvent::XrunStorm,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            HookEvent::RecordingStarted => "recording_started",
+            HookEvent::DeviceConnected => "device_connected",
+            HookEvent::SceneActivated => "scene_activated",
+            HookEvent::XrunStorm => "xrun_storm",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            HookEvent::RecordingStarted => "Recording started",
+            HookEvent::DeviceConnected => "Device connected",
+            HookEvent::SceneActivated => "Scene activated",
+            HookEvent::XrunStorm => "Overrun storm",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Hook {
+    pub id: String,
+    #[serde(default)]
+    pub name: String,
+    pub event: HookEvent,
+    /// Run with `sh -c`.
+    pub command: String,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_timeout_secs() -> u64 {
+    DEFAULT_TIMEOUT_SECS
+}
+
+impl Hook {
+    /// How long the command may run, between 1 second and
+    /// [`MAX_TIMEOUT_SECS`].
+    pub fn timeout(&self) -> Duration {
+        Duration::from_secs(self.timeout_secs.clamp(1, MAX_TIMEOUT_SECS))
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.id.is_empty() {
+            return Err("hook without an id".to_string());
+        }
+        if self.command.trim().is_empty() {
+            return Err(format!("hook \"{}\" has no command", self.label()));
+        }
+        Ok(())
+    }
+
+    /// The name, or the command when it has none.
+    pub fn label(&self) -> &str {
+        if self.name.is_empty() {
+            &self.command
+        } else {
+            &self.name
+        }
+    }
+}
+
+/// The enabled hooks for `event`.
+pub fn hooks_for(hooks: &[Hook], event: HookEvent) -> impl Iterator<Item = &Hook> {
+    hooks.iter().filter(move |h| h.enabled && h.event == event)
+}
+
+/// The stdin payload: the fields of `data` plus `event` and `time_ms`.
+pub fn payload(event: HookEvent, time_ms: u64, data: serde_json::Value) -> String {
+    let mut payload = match data {
+        serde_json::Value::Object(fields) => fields,
+        _ => serde_json::Map::new(),
+    };
+    payload.insert("event".into(), event.as_str().into());
+    payload.insert("time_ms".into(), time_ms.into());
+    serde_json::Value::Object(payload).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hooks_file_defaults() {
+        let hooks: Vec<Hook> = serde_json::from_str(
+            r#"[{"id": "a", "event": "device_connected", "command": "notify-send hi"}]"#,
+        )
+        .unwrap();
+        assert!(hooks[0].enabled);
+        assert_eq!(
+            hooks[0].timeout(),
+            Duration::from_secs(DEFAULT_TIMEOUT_SECS)
+        );
+        assert_eq!(hooks[0].label(), "notify-send hi");
+        assert!(hooks[0].validate().is_ok());
+
+        let zero = Hook {
+            timeout_secs: 0,
+            command: " ".into(),
+            ..hooks[0].clone()
+        };
+        assert_eq!(zero.timeout(), Duration::from_secs(1));
+        assert!(zero.validate().is_err());
+    }
+
+    #[test]
+    fn test_hooks_for_skips_disabled_and_other_events() {
+        let hook = |id: &str, event, enabled| Hook {
+            id: id.into(),
+            name: String::new(),
+            event,
+            command: "true".into(),
+            enabled,
+            timeout_secs: 5,
+        };
+        let hooks = [
+            hook("a", HookEvent::XrunStorm, true),
+            hook("b", HookEvent::XrunStorm, false),
+            hook("c", HookEvent::RecordingStarted, true),
+        ];
+        let ids: Vec<&str> = hooks_for(&hooks, HookEvent::XrunStorm)
+            .map(|h| h.id.as_str())
+            .collect();
+        assert_eq!(ids, ["a"]);
+    }
+
+    #[test]
+    fn test_payload_carries_event_and_data() {
+        let payload = payload(
+            HookEvent::SceneActivated,
+            1234,
+            serde_json::json!({"scene": "Podcast", "event": "spoofed"}),
+        );
+        let value: serde_json::Value = serde_json::from_str(&payload).unwrap();
+        assert_eq!(value["event"], "scene_activated");
+        assert_eq!(value["time_ms"], 1234);
+        assert_eq!(value["scene"], "Podcast");
+    }
+}
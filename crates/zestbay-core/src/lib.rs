@@ -0,0 +1,40 @@
+//! The parts of ZestBay that do not need PipeWire, Qt or a plugin host.
+//!
+//! - [`graph`]: the PipeWire object model ([`graph::Node`], [`graph::Port`],
+//!   [`graph::Link`]), the shared [`graph::GraphState`] the PipeWire thread
+//!   keeps up to date, and the commands and events exchanged with it.
+//! - [`patchbay`]: the rules engine. [`patchbay::PatchbayManager`] plans
+//!   connections against a [`patchbay::GraphSnapshot`] and returns
+//!   [`graph::PwCommand`]s, so it can run against a live graph or a
+//!   synthetic one.
+//! - [`plugin`]: the plugin catalog and instance registry
+//!   ([`plugin::PluginManager`]) and the format-agnostic plugin types.
+//! - [`midi`]: MIDI CC mapping types.
+//! - [`access`]: remote API tokens and their permission scopes.
+//! - [`config`]: schema versions and migrations for the JSON config files.
+//! - [`crash`]: crash report text and finding reports not yet shown.
+//! - [`hooks`]: hook scripts run on events, and their payloads.
+//! - [`portable`]: the whole configuration in one file, remapped onto
+//!   another machine's devices on import.
+//! - [`profile`]: performance profiles and the timer intervals they set.
+//! - [`session`]: named sessions bundling the patch's config files.
+//! - [`ui_state`]: validated types for the layout, viewport and window
+//!   state the UI saves.
+//! - [`update`]: release lookups for the optional update check.
+//!
+//! The application links this crate and adds the PipeWire thread, the
+//! LV2/CLAP/VST3 hosts and the Qt UI on top.
+
+pub mod access;
+pub mod config;
+pub mod crash;
+pub mod graph;
+pub mod hooks;
+pub mod midi;
+pub mod patchbay;
+pub mod plugin;
+pub mod portable;
+pub mod profile;
+pub mod session;
+pub mod ui_state;
+pub mod update;
@@ -0,0 +1,4 @@
+pub mod link_filter;
+pub mod types;
+
+pub use types::*;
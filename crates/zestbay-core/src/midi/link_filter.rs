@@ -0,0 +1,187 @@
+//! Per-link MIDI filtering.
+//!
+//! Some gear chokes on SysEx dumps or the active sensing bytes a keyboard
+//! sends several times a second. A link with [`MidiLinkOptions`] set is
+//! routed through an internal filter node that drops those messages; the
+//! graph view still draws it as one link. The options are saved with the
+//! link's ends, by node and port name like rules, and the filter comes back
+//! whenever both ends do.
+
+use serde::{Deserialize, Serialize};
+
+/// Node name prefix of the internal filter nodes, followed by the filter id.
+pub const FILTER_NODE_PREFIX: &str = "zestbay-midi-filter-";
+
+/// The node name of filter `id`.
+pub fn filter_node_name(id: u32) -> String {
+    format!("{}{}", FILTER_NODE_PREFIX, id)
+}
+
+/// The filter id behind a node name, if it is a filter node.
+pub fn filter_id_of(node_name: &str) -> Option<u32> {
+    node_name.strip_prefix(FILTER_NODE_PREFIX)?.parse().ok()
+}
+
+const SYSEX_START: u8 = 0xF0;
+const SYSEX_END: u8 = 0xF7;
+const ACTIVE_SENSING: u8 = 0xFE;
+/// Timing clock, start, continue, stop and song position.
+const CLOCK_MESSAGES: [u8; 5] = [0xF8, 0xFA, 0xFB, 0xFC, 0xF2];
+
+/// Which messages a link drops.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MidiLinkOptions {
+    #[serde(default)]
+    pub drop_sysex: bool,
+    #[serde(default)]
+    pub drop_active_sensing: bool,
+    /// Clock and transport: timing clock, start, continue, stop and song
+    /// position.
+    #[serde(default)]
+    pub drop_clock: bool,
+}
+
+impl MidiLinkOptions {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// The options packed into a byte, for handing to the RT thread.
+    pub fn to_bits(self) -> u8 {
+        u8::from(self.drop_sysex)
+            | u8::from(self.drop_active_sensing) << 1
+            | u8::from(self.drop_clock) << 2
+    }
+
+    pub fn from_bits(bits: u8) -> Self {
+        Self {
+            drop_sysex: bits & 1 != 0,
+            drop_active_sensing: bits & 2 != 0,
+            drop_clock: bits & 4 != 0,
+        }
+    }
+}
+
+/// Filtering state carried from one MIDI event to the next: a SysEx message
+/// may arrive split over several events, and only the first starts with
+/// 0xF0.
+#[derive(Debug, Default)]
+pub struct MidiLinkFilterState {
+    in_sysex: bool,
+}
+
+impl MidiLinkFilterState {
+    /// Whether to pass on one MIDI event.
+    pub fn passes(&mut self, options: MidiLinkOptions, event: &[u8]) -> bool {
+        let Some(&first) = event.first() else {
+            return true;
+        };
+        let sysex = if first == SYSEX_START {
+            self.in_sysex = event.last() != Some(&SYSEX_END);
+            true
+        } else if self.in_sysex && first < 0x80 {
+            // A continuation; the last one ends with 0xF7.
+            self.in_sysex = event.last() != Some(&SYSEX_END);
+            true
+        } else if first == SYSEX_END && self.in_sysex {
+            self.in_sysex = false;
+            true
+        } else {
+            // Real-time bytes may come between SysEx parts; any other
+            // status byte ends the SysEx.
+            if first < 0xF8 {
+                self.in_sysex = false;
+            }
+            false
+        };
+        if sysex {
+            return !options.drop_sysex;
+        }
+        match first {
+            ACTIVE_SENSING => !options.drop_active_sensing,
+            b if CLOCK_MESSAGES.contains(&b) => !options.drop_clock,
+            _ => true,
+        }
+    }
+}
+
+/// A filtered link, saved by node display name and port name.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SavedMidiLinkFilter {
+    /// Names the filter node while ZestBay runs.
+    pub id: u32,
+    pub source: String,
+    pub output_port: String,
+    pub target: String,
+    pub input_port: String,
+    pub options: MidiLinkOptions,
+}
+
+impl SavedMidiLinkFilter {
+    pub fn matches(&self, source: &str, output_port: &str, target: &str, input_port: &str) -> bool {
+        self.source == source
+            && self.output_port == output_port
+            && self.target == target
+            && self.input_port == input_port
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn passed(options: MidiLinkOptions, events: &[&[u8]]) -> Vec<usize> {
+        let mut state = MidiLinkFilterState::default();
+        (0..events.len())
+            .filter(|&i| state.passes(options, events[i]))
+            .collect()
+    }
+
+    #[test]
+    fn test_drops_selected_messages() {
+        let events: [&[u8]; 5] = [&[0x90, 60, 100], &[0xFE], &[0xF8], &[0xFA], &[0xB0, 7, 90]];
+        let everything = MidiLinkOptions::default();
+        assert_eq!(passed(everything, &events), [0, 1, 2, 3, 4]);
+        let quiet = MidiLinkOptions {
+            drop_active_sensing: true,
+            ..Default::default()
+        };
+        assert_eq!(passed(quiet, &events), [0, 2, 3, 4]);
+        let no_clock = MidiLinkOptions {
+            drop_clock: true,
+            ..Default::default()
+        };
+        assert_eq!(passed(no_clock, &events), [0, 1, 4]);
+    }
+
+    #[test]
+    fn test_split_sysex_is_dropped_whole() {
+        let events: [&[u8]; 6] = [
+            &[0xF0, 0x41, 0x10],
+            &[0xF8],
+            &[0x42, 0x12, 0x00],
+            &[0x01, 0xF7],
+            &[0x90, 60, 100],
+            &[0x40, 0x00],
+        ];
+        let options = MidiLinkOptions {
+            drop_sysex: true,
+            ..Default::default()
+        };
+        // The clock byte inside the dump and the note after it pass; the
+        // stray data bytes after the dump ended are not SysEx.
+        assert_eq!(passed(options, &events), [1, 4, 5]);
+    }
+
+    #[test]
+    fn test_bits_and_node_names_round_trip() {
+        for bits in 0..8 {
+            assert_eq!(MidiLinkOptions::from_bits(bits).to_bits(), bits);
+        }
+        assert!(MidiLinkOptions::from_bits(0).is_empty());
+        assert_eq!(filter_id_of(&filter_node_name(7)), Some(7));
+        assert_eq!(filter_id_of("zestbay-midi-filter-x"), None);
+        assert_eq!(filter_id_of("Synth"), None);
+    }
+}
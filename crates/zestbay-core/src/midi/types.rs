@@ -14,23 +14,19 @@ use crate::plugin::types::PluginInstanceId;
 // Mapping mode (toggle vs. momentary for button-type controls)
 // ---------------------------------------------------------------------------
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
 pub enum MidiMessageType {
+    #[default]
     Cc,
     Note,
 }
 
-impl Default for MidiMessageType {
-    fn default() -> Self {
-        Self::Cc
-    }
-}
-
 /// How a MIDI CC value is interpreted when the target parameter is boolean-ish.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub enum MappingMode {
     /// Continuous: CC 0-127 is mapped linearly to [min, max].
     /// This is the default for sliders / knobs.
+    #[default]
     Continuous,
     /// Toggle: a CC value > 63 flips the parameter on/off.
     /// Each "press" (transition from <= 63 to > 63) toggles.
@@ -40,12 +36,6 @@ pub enum MappingMode {
     Momentary,
 }
 
-impl Default for MappingMode {
-    fn default() -> Self {
-        Self::Continuous
-    }
-}
-
 // ---------------------------------------------------------------------------
 // A single CC mapping
 // ---------------------------------------------------------------------------
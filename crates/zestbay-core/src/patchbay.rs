@@ -0,0 +1,19 @@
+pub mod audition;
+pub mod chain;
+pub mod clock;
+pub mod crossfade;
+pub mod link_gain;
+pub mod manager;
+pub mod overrides;
+pub mod probe;
+pub mod propose;
+pub mod roles;
+pub mod rules;
+pub mod snapshot;
+pub mod storm;
+pub mod temporary;
+pub mod transition;
+pub mod wireplumber;
+
+pub use manager::PatchbayManager;
+pub use snapshot::GraphSnapshot;
@@ -0,0 +1,115 @@
+//! Solo audition: hear one node on a monitoring sink, such as headphones,
+//! without touching where it normally goes.
+//!
+//! Each output of the node is mirrored to an input of the sink through a
+//! [`TemporaryRoute`](super::temporary::TemporaryRoute), so whatever else
+//! fed the sink drops out while auditioning and comes back afterwards. The
+//! node's own links are left as they are.
+
+use super::manager::PatchbayManager;
+use super::snapshot::GraphSnapshot;
+use super::temporary::PortPair;
+use crate::graph::ObjectId;
+
+/// The links that mirror `node_id` to `sink_id`: each output to the sink
+/// input on the same channel, a mono output to every input. Each input is
+/// used once.
+pub fn audition_pairs(
+    graph: &GraphSnapshot,
+    node_id: ObjectId,
+    sink_id: ObjectId,
+) -> Vec<PortPair> {
+    if node_id == sink_id {
+        return Vec::new();
+    }
+    let outputs = graph.output_ports(node_id);
+    let mut inputs = graph.input_ports(sink_id);
+
+    if let [mono] = outputs.as_slice() {
+        return inputs
+            .iter()
+            .filter(|p| match (mono.media_type, p.media_type) {
+                (Some(a), Some(b)) => a == b,
+                _ => true,
+            })
+            .map(|p| (mono.id, p.id))
+            .collect();
+    }
+
+    let mut pairs = Vec::new();
+    for output in &outputs {
+        if let Some(input) = PatchbayManager::find_matching_port(output, &inputs) {
+            let input_id = input.id;
+            pairs.push((output.id, input_id));
+            inputs.retain(|p| p.id != input_id);
+        }
+    }
+    pairs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{MediaType, Node, NodeType, Port, PortDirection};
+
+    /// A port of node `id / 10` on `channel`.
+    fn port(id: ObjectId, direction: PortDirection, channel: &str, media_type: MediaType) -> Port {
+        let name = format!("{:?}_{}", direction, channel);
+        Port {
+            media_type: Some(media_type),
+            channel: Some(channel.into()),
+            ..Port::test(id, id / 10, &name, direction)
+        }
+    }
+
+    /// A stereo player (1), a mono mic (2) and headphones (3) with a MIDI
+    /// input.
+    fn desk() -> GraphSnapshot {
+        let mut graph = GraphSnapshot::default();
+        for (id, node_type) in [
+            (1, NodeType::StreamOutput),
+            (2, NodeType::Source),
+            (3, NodeType::Sink),
+        ] {
+            graph.add_node(Node::test(id, &format!("node{}", id), node_type));
+        }
+        let ports = [
+            (11, PortDirection::Output, "FL", MediaType::Audio),
+            (12, PortDirection::Output, "FR", MediaType::Audio),
+            (21, PortDirection::Output, "MONO", MediaType::Audio),
+            (31, PortDirection::Input, "FL", MediaType::Audio),
+            (32, PortDirection::Input, "FR", MediaType::Audio),
+            (33, PortDirection::Input, "MIDI", MediaType::Midi),
+        ];
+        for (id, direction, channel, media_type) in ports {
+            graph.add_port(port(id, direction, channel, media_type));
+        }
+        graph
+    }
+
+    #[test]
+    fn test_stereo_pairs_by_channel() {
+        let mut pairs = audition_pairs(&desk(), 1, 3);
+        pairs.sort();
+        assert_eq!(pairs, [(11, 31), (12, 32)]);
+    }
+
+    #[test]
+    fn test_mono_goes_to_every_audio_input() {
+        let mut pairs = audition_pairs(&desk(), 2, 3);
+        pairs.sort();
+        assert_eq!(pairs, [(21, 31), (21, 32)]);
+    }
+
+    #[test]
+    fn test_inputs_are_used_once_and_self_audition_is_empty() {
+        let mut graph = desk();
+        graph.add_port(port(13, PortDirection::Output, "FL", MediaType::Audio));
+        let pairs = audition_pairs(&graph, 1, 3);
+        let mut inputs: Vec<_> = pairs.iter().map(|p| p.1).collect();
+        inputs.sort();
+        inputs.dedup();
+        assert_eq!(inputs.len(), pairs.len());
+        assert!(audition_pairs(&graph, 3, 3).is_empty());
+    }
+}
@@ -0,0 +1,507 @@
+use std::collections::HashSet;
+
+use crate::graph::state::natural_cmp;
+use crate::graph::{GraphState, LinkOp, MediaType, NodeType, ObjectId, Port, PortDirection};
+
+/// Audio ports of a node in one direction, in natural name order so that
+/// index `i` means "channel i" (FL before FR, in_1 before in_2, ...).
+fn audio_ports(graph: &GraphState, node_id: ObjectId, direction: PortDirection) -> Vec<Port> {
+    let mut ports: Vec<Port> = graph
+        .get_ports_for_node(node_id)
+        .into_iter()
+        .filter(|p| p.direction == direction && p.media_type == Some(MediaType::Audio))
+        .collect();
+    ports.sort_by(|a, b| natural_cmp(&a.name, &b.name));
+    ports
+}
+
+/// Nodes feeding audio into `node_id`, and nodes it feeds, deduplicated.
+fn audio_neighbours(graph: &GraphState, node_id: ObjectId) -> (Vec<ObjectId>, Vec<ObjectId>) {
+    let mut upstream = Vec::new();
+    let mut downstream = Vec::new();
+    for link in graph.get_all_links() {
+        let is_audio = graph
+            .get_port(link.output_port_id)
+            .is_some_and(|p| p.media_type == Some(MediaType::Audio));
+        if !is_audio {
+            continue;
+        }
+        if link.input_node_id == node_id && !upstream.contains(&link.output_node_id) {
+            upstream.push(link.output_node_id);
+        }
+        if link.output_node_id == node_id && !downstream.contains(&link.input_node_id) {
+            downstream.push(link.input_node_id);
+        }
+    }
+    (upstream, downstream)
+}
+
+fn is_plugin(graph: &GraphState, node_id: ObjectId) -> bool {
+    graph
+        .get_node(node_id)
+        .is_some_and(|n| n.node_type == Some(NodeType::Plugin))
+}
+
+/// The linear run of plugin nodes that `node_id` belongs to, in signal order.
+/// Two plugins are in the same chain when one feeds only the other and the
+/// other is fed only by the first. Returns just `[node_id]` for a lone plugin
+/// and an empty list if `node_id` is not a plugin.
+pub fn detect_chain(graph: &GraphState, node_id: ObjectId) -> Vec<ObjectId> {
+    if !is_plugin(graph, node_id) {
+        return Vec::new();
+    }
+
+    let mut chain = vec![node_id];
+
+    let mut current = node_id;
+    loop {
+        let (upstream, _) = audio_neighbours(graph, current);
+        let [prev] = upstream[..] else { break };
+        if chain.contains(&prev) || !is_plugin(graph, prev) {
+            break;
+        }
+        let (_, prev_downstream) = audio_neighbours(graph, prev);
+        if prev_downstream != [current] {
+            break;
+        }
+        chain.insert(0, prev);
+        current = prev;
+    }
+
+    let mut current = node_id;
+    loop {
+        let (_, downstream) = audio_neighbours(graph, current);
+        let [next] = downstream[..] else { break };
+        if chain.contains(&next) || !is_plugin(graph, next) {
+            break;
+        }
+        let (next_upstream, _) = audio_neighbours(graph, next);
+        if next_upstream != [current] {
+            break;
+        }
+        chain.push(next);
+        current = next;
+    }
+
+    chain
+}
+
+/// Work out the link changes that turn the chain made of `order`'s nodes into
+/// one running in `order`. The chain's external inputs (links into its current
+/// head) move to the new head and its external outputs (links out of its
+/// current tail) move to the new tail, channel by channel.
+pub fn plan_reorder(graph: &GraphState, order: &[ObjectId]) -> Result<Vec<LinkOp>, String> {
+    if order.len() < 2 {
+        return Err("a chain needs at least two nodes".to_string());
+    }
+    let members: HashSet<ObjectId> = order.iter().copied().collect();
+    if members.len() != order.len() {
+        return Err("node list contains duplicates".to_string());
+    }
+    if let Some(id) = order.iter().find(|id| graph.get_node(**id).is_none()) {
+        return Err(format!("node {} not found", id));
+    }
+
+    let current = detect_chain(graph, order[0]);
+    if current.len() != order.len() || !current.iter().all(|id| members.contains(id)) {
+        return Err("nodes do not form a linear plugin chain".to_string());
+    }
+    let head = current[0];
+    let tail = current[current.len() - 1];
+
+    // Existing links that belong to the chain: into the head from outside,
+    // between members, and out of the tail.
+    let old: Vec<(ObjectId, ObjectId, ObjectId)> = graph
+        .get_all_links()
+        .into_iter()
+        .filter(|l| {
+            let audio = graph
+                .get_port(l.output_port_id)
+                .is_some_and(|p| p.media_type == Some(MediaType::Audio));
+            let out_in = members.contains(&l.output_node_id);
+            let in_in = members.contains(&l.input_node_id);
+            let into_head = !out_in && l.input_node_id == head;
+            let out_of_tail = l.output_node_id == tail && !in_in;
+            audio && ((out_in && in_in) || into_head || out_of_tail)
+        })
+        .map(|l| (l.id, l.output_port_id, l.input_port_id))
+        .collect();
+
+    let head_inputs = audio_ports(graph, head, PortDirection::Input);
+    let tail_outputs = audio_ports(graph, tail, PortDirection::Output);
+    let channel_of = |ports: &[Port], id: ObjectId| ports.iter().position(|p| p.id == id).unwrap_or(0);
+
+    let upstream: Vec<(ObjectId, usize)> = old
+        .iter()
+        .filter(|(_, out, _)| graph.get_port(*out).is_some_and(|p| !members.contains(&p.node_id)))
+        .map(|(_, out, inp)| (*out, channel_of(&head_inputs, *inp)))
+        .collect();
+    let downstream: Vec<(usize, ObjectId)> = old
+        .iter()
+        .filter(|(_, _, inp)| graph.get_port(*inp).is_some_and(|p| !members.contains(&p.node_id)))
+        .map(|(_, out, inp)| (channel_of(&tail_outputs, *out), *inp))
+        .collect();
+
+    let mut wanted: Vec<(ObjectId, ObjectId)> = Vec::new();
+    let mut push = |pair: (ObjectId, ObjectId)| {
+        if !wanted.contains(&pair) {
+            wanted.push(pair);
+        }
+    };
+
+    let first_inputs = audio_ports(graph, order[0], PortDirection::Input);
+    if first_inputs.is_empty() {
+        return Err(format!("node {} has no audio inputs", order[0]));
+    }
+    for (out, ch) in &upstream {
+        push((*out, first_inputs[(*ch).min(first_inputs.len() - 1)].id));
+    }
+
+    for pair in order.windows(2) {
+        let outs = audio_ports(graph, pair[0], PortDirection::Output);
+        let ins = audio_ports(graph, pair[1], PortDirection::Input);
+        if outs.is_empty() || ins.is_empty() {
+            return Err(format!("nodes {} and {} cannot be linked", pair[0], pair[1]));
+        }
+        for ch in 0..outs.len().max(ins.len()) {
+            push((outs[ch.min(outs.len() - 1)].id, ins[ch.min(ins.len() - 1)].id));
+        }
+    }
+
+    let last_outputs = audio_ports(graph, order[order.len() - 1], PortDirection::Output);
+    if last_outputs.is_empty() {
+        return Err(format!("node {} has no audio outputs", order[order.len() - 1]));
+    }
+    for (ch, inp) in &downstream {
+        push((last_outputs[(*ch).min(last_outputs.len() - 1)].id, *inp));
+    }
+
+    let mut ops: Vec<LinkOp> = old
+        .iter()
+        .filter(|(_, out, inp)| !wanted.contains(&(*out, *inp)))
+        .map(|(id, _, _)| LinkOp::Disconnect { link_id: *id })
+        .collect();
+    ops.extend(
+        wanted
+            .into_iter()
+            .filter(|(out, inp)| !old.iter().any(|(_, o, i)| o == out && i == inp))
+            .map(|(output_port_id, input_port_id)| LinkOp::Connect {
+                output_port_id,
+                input_port_id,
+            }),
+    );
+    Ok(ops)
+}
+
+/// Work out the link changes that wrap `chain` in a dry/wet mixer node. The
+/// mixer's audio inputs are named `dry_N` and `wet_N`; sources feeding the
+/// chain head are tapped into the dry inputs, the tail is moved onto the wet
+/// inputs, and whatever the tail fed is fed from the mixer outputs instead.
+pub fn plan_chain_mix(
+    graph: &GraphState,
+    chain: &[ObjectId],
+    mix_node: ObjectId,
+) -> Result<Vec<LinkOp>, String> {
+    let (Some(&head), Some(&tail)) = (chain.first(), chain.last()) else {
+        return Err("empty chain".to_string());
+    };
+    if chain.contains(&mix_node) {
+        return Err("mixer node is part of the chain".to_string());
+    }
+
+    let mix_inputs = audio_ports(graph, mix_node, PortDirection::Input);
+    let dry: Vec<&Port> = mix_inputs.iter().filter(|p| p.name.starts_with("dry_")).collect();
+    let wet: Vec<&Port> = mix_inputs.iter().filter(|p| p.name.starts_with("wet_")).collect();
+    let mix_outputs = audio_ports(graph, mix_node, PortDirection::Output);
+    if dry.is_empty() || wet.is_empty() || mix_outputs.is_empty() {
+        return Err(format!("node {} is not a dry/wet mixer", mix_node));
+    }
+
+    let head_inputs = audio_ports(graph, head, PortDirection::Input);
+    let tail_outputs = audio_ports(graph, tail, PortDirection::Output);
+    if tail_outputs.is_empty() {
+        return Err(format!("node {} has no audio outputs", tail));
+    }
+    let channel_of = |ports: &[Port], id: ObjectId| ports.iter().position(|p| p.id == id).unwrap_or(0);
+    let pick = |ports: &[&Port], ch: usize| ports[ch.min(ports.len() - 1)].id;
+
+    let mut ops = Vec::new();
+    let mut connect = |output_port_id: ObjectId, input_port_id: ObjectId| {
+        let op = LinkOp::Connect {
+            output_port_id,
+            input_port_id,
+        };
+        if graph.find_link(output_port_id, input_port_id).is_none() && !ops.contains(&op) {
+            ops.push(op);
+        }
+    };
+
+    for l in graph.get_all_links() {
+        let external_source = l.input_node_id == head
+            && !chain.contains(&l.output_node_id)
+            && l.output_node_id != mix_node;
+        if external_source && head_inputs.iter().any(|p| p.id == l.input_port_id) {
+            connect(l.output_port_id, pick(&dry, channel_of(&head_inputs, l.input_port_id)));
+        }
+    }
+
+    for ch in 0..tail_outputs.len().max(wet.len()) {
+        connect(tail_outputs[ch.min(tail_outputs.len() - 1)].id, pick(&wet, ch));
+    }
+
+    let mix_outputs: Vec<&Port> = mix_outputs.iter().collect();
+    let mut disconnects = Vec::new();
+    for l in graph.get_all_links() {
+        let external_target = l.output_node_id == tail
+            && !chain.contains(&l.input_node_id)
+            && l.input_node_id != mix_node;
+        if external_target && tail_outputs.iter().any(|p| p.id == l.output_port_id) {
+            disconnects.push(LinkOp::Disconnect { link_id: l.id });
+            connect(pick(&mix_outputs, channel_of(&tail_outputs, l.output_port_id)), l.input_port_id);
+        }
+    }
+
+    // Connect everything before breaking the tail's old links, so the signal
+    // is never interrupted mid-transaction.
+    ops.extend(disconnects);
+    Ok(ops)
+}
+
+/// Links putting `plugin` right after `source`: the source feeds the plugin,
+/// and everything the source fed is fed by the plugin instead.
+pub fn plan_insert_after(
+    graph: &GraphState,
+    source: ObjectId,
+    plugin: ObjectId,
+) -> Result<Vec<LinkOp>, String> {
+    if source == plugin {
+        return Err("cannot insert a node after itself".to_string());
+    }
+    let source_outputs = audio_ports(graph, source, PortDirection::Output);
+    if source_outputs.is_empty() {
+        return Err(format!("node {} has no audio outputs", source));
+    }
+    let plugin_inputs = audio_ports(graph, plugin, PortDirection::Input);
+    let plugin_outputs = audio_ports(graph, plugin, PortDirection::Output);
+    if plugin_inputs.is_empty() || plugin_outputs.is_empty() {
+        return Err(format!("node {} has no audio inputs or outputs", plugin));
+    }
+    let pick = |ports: &[Port], ch: usize| ports[ch.min(ports.len() - 1)].id;
+
+    let mut ops = Vec::new();
+    let mut connect = |output_port_id: ObjectId, input_port_id: ObjectId| {
+        let op = LinkOp::Connect {
+            output_port_id,
+            input_port_id,
+        };
+        if graph.find_link(output_port_id, input_port_id).is_none() && !ops.contains(&op) {
+            ops.push(op);
+        }
+    };
+
+    for ch in 0..source_outputs.len().max(plugin_inputs.len()) {
+        connect(pick(&source_outputs, ch), pick(&plugin_inputs, ch));
+    }
+
+    let mut disconnects = Vec::new();
+    for l in graph.get_all_links() {
+        if l.output_node_id != source || l.input_node_id == plugin {
+            continue;
+        }
+        let Some(ch) = source_outputs.iter().position(|p| p.id == l.output_port_id) else {
+            continue;
+        };
+        disconnects.push(LinkOp::Disconnect { link_id: l.id });
+        connect(pick(&plugin_outputs, ch), l.input_port_id);
+    }
+
+    // As with the chain mixer, the old links go last.
+    ops.extend(disconnects);
+    Ok(ops)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{Link, Node};
+
+    fn add_node(graph: &GraphState, id: ObjectId, node_type: NodeType) {
+        graph.insert_node(Node::test(id, &format!("node{}", id), node_type));
+        // Ports: id*10 + 1/2 inputs, id*10 + 3/4 outputs (stereo).
+        for (offset, name, dir) in [
+            (1, "in_1", PortDirection::Input),
+            (2, "in_2", PortDirection::Input),
+            (3, "out_1", PortDirection::Output),
+            (4, "out_2", PortDirection::Output),
+        ] {
+            graph.insert_port(Port::test(id * 10 + offset, id, name, dir));
+        }
+    }
+
+    fn link(graph: &GraphState, id: ObjectId, from: ObjectId, to: ObjectId) {
+        for ch in 0..2 {
+            graph.insert_link(Link::test(
+                id + ch,
+                (from, from * 10 + 3 + ch),
+                (to, to * 10 + 1 + ch),
+            ));
+        }
+    }
+
+    /// source(1) -> A(2) -> B(3) -> C(4) -> sink(5)
+    fn chain_graph() -> std::sync::Arc<GraphState> {
+        let graph = GraphState::new();
+        add_node(&graph, 1, NodeType::StreamOutput);
+        add_node(&graph, 2, NodeType::Plugin);
+        add_node(&graph, 3, NodeType::Plugin);
+        add_node(&graph, 4, NodeType::Plugin);
+        add_node(&graph, 5, NodeType::Sink);
+        link(&graph, 100, 1, 2);
+        link(&graph, 110, 2, 3);
+        link(&graph, 120, 3, 4);
+        link(&graph, 130, 4, 5);
+        graph
+    }
+
+    fn apply(graph: &GraphState, ops: &[LinkOp]) {
+        let mut next_id = 1000;
+        for op in ops {
+            match *op {
+                LinkOp::Disconnect { link_id } => {
+                    graph.remove_link(link_id);
+                }
+                LinkOp::Connect {
+                    output_port_id,
+                    input_port_id,
+                } => {
+                    let out = graph.get_port(output_port_id).unwrap();
+                    let inp = graph.get_port(input_port_id).unwrap();
+                    graph.insert_link(Link {
+                        id: next_id,
+                        output_node_id: out.node_id,
+                        output_port_id,
+                        input_node_id: inp.node_id,
+                        input_port_id,
+                        active: true,
+                    });
+                    next_id += 1;
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_detect_chain_from_any_member() {
+        let graph = chain_graph();
+        assert_eq!(detect_chain(&graph, 2), vec![2, 3, 4]);
+        assert_eq!(detect_chain(&graph, 4), vec![2, 3, 4]);
+        assert!(detect_chain(&graph, 1).is_empty());
+    }
+
+    #[test]
+    fn test_detect_chain_stops_at_branch() {
+        let graph = chain_graph();
+        // B also feeds the sink directly, so C is no longer B's only target.
+        link(&graph, 140, 3, 5);
+        assert_eq!(detect_chain(&graph, 2), vec![2, 3]);
+        assert_eq!(detect_chain(&graph, 4), vec![4]);
+    }
+
+    #[test]
+    fn test_reorder_rewires_chain() {
+        let graph = chain_graph();
+        let ops = plan_reorder(&graph, &[4, 2, 3]).unwrap();
+        apply(&graph, &ops);
+
+        assert_eq!(detect_chain(&graph, 2), vec![4, 2, 3]);
+        assert!(graph.find_link(13, 41).is_some());
+        assert!(graph.find_link(14, 42).is_some());
+        assert!(graph.find_link(33, 51).is_some());
+        assert!(graph.find_link(34, 52).is_some());
+        assert!(graph.find_link(13, 21).is_none());
+        assert!(graph.find_link(43, 51).is_none());
+    }
+
+    #[test]
+    fn test_reorder_same_order_is_noop() {
+        let graph = chain_graph();
+        assert!(plan_reorder(&graph, &[2, 3, 4]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_reorder_rejects_non_chain() {
+        let graph = chain_graph();
+        assert!(plan_reorder(&graph, &[2, 4]).is_err());
+        assert!(plan_reorder(&graph, &[1, 2, 3, 4]).is_err());
+        assert!(plan_reorder(&graph, &[2, 2, 3]).is_err());
+        assert!(plan_reorder(&graph, &[2]).is_err());
+    }
+
+    /// A chain mixer node with dry_1/2 (id*10 + 1/2), wet_1/2 (+3/4) and
+    /// out_1/2 (+5/6).
+    fn add_mixer(graph: &GraphState, id: ObjectId) {
+        add_node(graph, id, NodeType::Plugin);
+        for port in graph.get_ports_for_node(id) {
+            graph.remove_port(port.id);
+        }
+        for (offset, name, dir) in [
+            (1, "dry_1", PortDirection::Input),
+            (2, "dry_2", PortDirection::Input),
+            (3, "wet_1", PortDirection::Input),
+            (4, "wet_2", PortDirection::Input),
+            (5, "out_1", PortDirection::Output),
+            (6, "out_2", PortDirection::Output),
+        ] {
+            graph.insert_port(Port::test(id * 10 + offset, id, name, dir));
+        }
+    }
+
+    #[test]
+    fn test_chain_mix_wraps_chain() {
+        let graph = chain_graph();
+        add_mixer(&graph, 6);
+        let ops = plan_chain_mix(&graph, &[2, 3, 4], 6).unwrap();
+        assert!(matches!(ops.last(), Some(LinkOp::Disconnect { .. })));
+        apply(&graph, &ops);
+
+        // Source still feeds the head, and now also the dry inputs.
+        assert!(graph.find_link(13, 21).is_some());
+        assert!(graph.find_link(13, 61).is_some());
+        assert!(graph.find_link(14, 62).is_some());
+        // Tail feeds wet, mixer feeds the old destination.
+        assert!(graph.find_link(43, 63).is_some());
+        assert!(graph.find_link(44, 64).is_some());
+        assert!(graph.find_link(65, 51).is_some());
+        assert!(graph.find_link(66, 52).is_some());
+        assert!(graph.find_link(43, 51).is_none());
+        // The mixer is not mistaken for part of the chain.
+        assert_eq!(detect_chain(&graph, 2), vec![2, 3, 4]);
+
+        assert!(plan_chain_mix(&graph, &[2, 3, 4], 6).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_chain_mix_rejects_non_mixer() {
+        let graph = chain_graph();
+        assert!(plan_chain_mix(&graph, &[2, 3], 5).is_err());
+        assert!(plan_chain_mix(&graph, &[2, 3], 3).is_err());
+        assert!(plan_chain_mix(&graph, &[], 5).is_err());
+    }
+
+    #[test]
+    fn test_insert_after_source_takes_over_its_links() {
+        let graph = chain_graph();
+        add_node(&graph, 6, NodeType::Plugin);
+        let ops = plan_insert_after(&graph, 1, 6).unwrap();
+        apply(&graph, &ops);
+
+        assert!(graph.find_link(13, 61).is_some());
+        assert!(graph.find_link(14, 62).is_some());
+        assert!(graph.find_link(63, 21).is_some());
+        assert!(graph.find_link(64, 22).is_some());
+        assert!(graph.find_link(13, 21).is_none());
+
+        assert!(plan_insert_after(&graph, 1, 6).unwrap().is_empty());
+        assert!(plan_insert_after(&graph, 1, 1).is_err());
+    }
+}
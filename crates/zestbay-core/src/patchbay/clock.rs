@@ -0,0 +1,334 @@
+//! MIDI clock routing: one clock source fanned out to chosen receivers.
+//!
+//! A synth fed by two clocks, say a sequencer and a drum machine, jumps
+//! between their tempos, and nothing in ordinary patching stops that. The
+//! clock panel picks one source; [`routing_ops`] links it to each enabled
+//! receiver and unlinks any other clock source feeding the same receiver.
+//!
+//! Sources are guessed from names, since PipeWire doesn't say which MIDI
+//! outputs send clock. Hardware devices on a MIDI bridge node are handled
+//! one by one, as the graph view shows them.
+
+use serde::{Deserialize, Serialize};
+
+use super::snapshot::GraphSnapshot;
+use crate::graph::{LinkOp, MediaType, Node, ObjectId, Port, PortDirection};
+
+/// Words in a node or port name that suggest it sends or takes clock.
+const CLOCK_HINTS: [&str; 5] = ["clock", "sync", "transport", "tempo", "timing"];
+
+/// Applications that send clock from their MIDI outputs.
+const CLOCK_APPS: [&str; 12] = [
+    "ardour",
+    "bitwig",
+    "carla",
+    "hydrogen",
+    "lmms",
+    "qtractor",
+    "reaper",
+    "renoise",
+    "rosegarden",
+    "seq24",
+    "seq66",
+    "zrythm",
+];
+
+/// Where clock comes from or goes to: a node, or one device of a MIDI
+/// bridge node.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Endpoint {
+    pub node_id: ObjectId,
+    /// The device's port group, on a bridge node.
+    pub group: Option<String>,
+}
+
+impl Endpoint {
+    /// The endpoint `port` belongs to.
+    pub fn of_port(graph: &GraphSnapshot, port: &Port) -> Self {
+        let bridged = graph.node(port.node_id).is_some_and(|n| n.is_bridge);
+        Self {
+            node_id: port.node_id,
+            group: port.port_group.clone().filter(|_| bridged),
+        }
+    }
+
+    fn owns(&self, port: &Port) -> bool {
+        port.node_id == self.node_id && (self.group.is_none() || port.port_group == self.group)
+    }
+
+    /// The endpoint's MIDI ports in one direction, in graph order.
+    pub fn midi_ports(&self, graph: &GraphSnapshot, direction: PortDirection) -> Vec<Port> {
+        let ports = match direction {
+            PortDirection::Input => graph.input_ports(self.node_id),
+            PortDirection::Output => graph.output_ports(self.node_id),
+        };
+        ports
+            .into_iter()
+            .filter(|p| p.media_type == Some(MediaType::Midi) && self.owns(p))
+            .collect()
+    }
+}
+
+/// Every endpoint with MIDI ports.
+pub fn endpoints(graph: &GraphSnapshot) -> Vec<Endpoint> {
+    let mut endpoints: Vec<Endpoint> = graph
+        .ports()
+        .iter()
+        .filter(|p| p.media_type == Some(MediaType::Midi))
+        .map(|p| Endpoint::of_port(graph, p))
+        .collect();
+    endpoints.sort();
+    endpoints.dedup();
+    endpoints
+}
+
+fn has_hint(text: &str, hints: &[&str]) -> bool {
+    let text = text.to_lowercase();
+    hints.iter().any(|hint| text.contains(hint))
+}
+
+fn node_hints(node: &Node) -> bool {
+    let app = [&node.application_name, &node.application_binary];
+    has_hint(&node.name, &CLOCK_HINTS)
+        || has_hint(&node.description, &CLOCK_HINTS)
+        || app
+            .into_iter()
+            .flatten()
+            .any(|name| has_hint(name, &CLOCK_APPS))
+}
+
+fn port_hints(port: &Port) -> bool {
+    has_hint(&port.name, &CLOCK_HINTS)
+        || port
+            .port_alias
+            .as_deref()
+            .is_some_and(|alias| has_hint(alias, &CLOCK_HINTS))
+}
+
+/// Whether `endpoint` looks like it sends clock: a MIDI output with a clock
+/// word in its name, or one belonging to a sequencer or DAW.
+pub fn is_clock_source(graph: &GraphSnapshot, endpoint: &Endpoint) -> bool {
+    let outputs = endpoint.midi_ports(graph, PortDirection::Output);
+    if outputs.is_empty() {
+        return false;
+    }
+    // A bridge node's name says nothing about the device behind it.
+    let node_named =
+        endpoint.group.is_none() && graph.node(endpoint.node_id).is_some_and(node_hints);
+    node_named || outputs.iter().any(port_hints)
+}
+
+/// The port to use on `endpoint`: one named for clock if any, otherwise
+/// the first MIDI port.
+fn clock_port(
+    graph: &GraphSnapshot,
+    endpoint: &Endpoint,
+    direction: PortDirection,
+) -> Option<Port> {
+    let ports = endpoint.midi_ports(graph, direction);
+    let hinted = ports.iter().position(port_hints).unwrap_or(0);
+    ports.into_iter().nth(hinted)
+}
+
+/// The link changes that make `source` the only clock source of each of
+/// `receivers`: a link to each receiver that lacks one, and no links from
+/// the other `clock_sources` into them.
+pub fn routing_ops(
+    graph: &GraphSnapshot,
+    source: &Endpoint,
+    receivers: &[Endpoint],
+    clock_sources: &[Endpoint],
+) -> Vec<LinkOp> {
+    let Some(output) = clock_port(graph, source, PortDirection::Output) else {
+        return Vec::new();
+    };
+    let mut ops = Vec::new();
+    for receiver in receivers.iter().filter(|r| *r != source) {
+        let Some(input) = clock_port(graph, receiver, PortDirection::Input) else {
+            continue;
+        };
+        if graph.find_link(output.id, input.id).is_none() {
+            ops.push(LinkOp::Connect {
+                output_port_id: output.id,
+                input_port_id: input.id,
+            });
+        }
+        for link in graph.links().iter().filter(|l| l.input_port_id == input.id) {
+            let from = graph
+                .port(link.output_port_id)
+                .map(|p| Endpoint::of_port(graph, p));
+            if let Some(from) = from
+                && from != *source
+                && clock_sources.contains(&from)
+            {
+                ops.push(LinkOp::Disconnect { link_id: link.id });
+            }
+        }
+    }
+    ops
+}
+
+/// Receivers fed by more than one of `clock_sources`, with those sources.
+pub fn fan_in(graph: &GraphSnapshot, clock_sources: &[Endpoint]) -> Vec<(Endpoint, Vec<Endpoint>)> {
+    let mut feeds: Vec<(Endpoint, Vec<Endpoint>)> = Vec::new();
+    for link in graph.links() {
+        let (Some(out), Some(inp)) = (
+            graph.port(link.output_port_id),
+            graph.port(link.input_port_id),
+        ) else {
+            continue;
+        };
+        if inp.media_type != Some(MediaType::Midi) {
+            continue;
+        }
+        let from = Endpoint::of_port(graph, out);
+        if !clock_sources.contains(&from) {
+            continue;
+        }
+        let to = Endpoint::of_port(graph, inp);
+        match feeds.iter_mut().find(|(r, _)| *r == to) {
+            Some((_, sources)) if !sources.contains(&from) => sources.push(from),
+            Some(_) => {}
+            None => feeds.push((to, vec![from])),
+        }
+    }
+    feeds.retain(|(_, sources)| sources.len() > 1);
+    feeds
+}
+
+/// The saved clock setup. Nodes are kept by layout key, so the routing
+/// comes back with them.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClockRouting {
+    /// The clock source, if one is chosen.
+    #[serde(default)]
+    pub source: Option<String>,
+    /// Receivers listed in the panel, whether they get clock or not.
+    #[serde(default)]
+    pub receivers: Vec<ClockReceiver>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClockReceiver {
+    pub key: String,
+    pub enabled: bool,
+}
+
+impl ClockRouting {
+    pub fn is_enabled(&self, key: &str) -> bool {
+        self.receivers.iter().any(|r| r.key == key && r.enabled)
+    }
+
+    pub fn set_enabled(&mut self, key: &str, enabled: bool) {
+        match self.receivers.iter_mut().find(|r| r.key == key) {
+            Some(receiver) => receiver.enabled = enabled,
+            None => self.receivers.push(ClockReceiver {
+                key: key.to_string(),
+                enabled,
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{Link, NodeType};
+
+    fn node(id: ObjectId, name: &str, is_bridge: bool) -> Node {
+        Node {
+            media_type: Some(MediaType::Midi),
+            is_bridge,
+            ..Node::test(id, name, NodeType::Duplex)
+        }
+    }
+
+    fn port(id: ObjectId, node_id: ObjectId, name: &str, direction: PortDirection) -> Port {
+        Port {
+            media_type: Some(MediaType::Midi),
+            ..Port::test(id, node_id, name, direction)
+        }
+    }
+
+    fn link(id: ObjectId, out: &Port, inp: &Port) -> Link {
+        Link::test(id, (out.node_id, out.id), (inp.node_id, inp.id))
+    }
+
+    /// A sequencer (1), a plain controller (2), a synth (3) fed by both,
+    /// and a bridge node (4) with a drum machine sending clock and a
+    /// keyboard.
+    fn studio() -> GraphSnapshot {
+        let mut drum_out = port(41, 4, "capture_1", PortDirection::Output);
+        drum_out.port_group = Some("drums".into());
+        drum_out.port_alias = Some("TR-8S:MIDI Clock Out".into());
+        let mut keys_out = port(42, 4, "capture_2", PortDirection::Output);
+        keys_out.port_group = Some("keys".into());
+        let mut keys_in = port(43, 4, "playback_1", PortDirection::Input);
+        keys_in.port_group = Some("keys".into());
+        let ports = vec![
+            port(11, 1, "seq_out", PortDirection::Output),
+            port(21, 2, "out", PortDirection::Output),
+            port(31, 3, "midi_in", PortDirection::Input),
+            drum_out,
+            keys_out,
+            keys_in,
+        ];
+        let links = vec![
+            link(100, &ports[0], &ports[2]),
+            link(101, &ports[3], &ports[2]),
+            link(102, &ports[1], &ports[2]),
+        ];
+        let nodes = vec![
+            node(1, "Transport Sequencer", false),
+            node(2, "Controller", false),
+            node(3, "Synth", false),
+            node(4, "Midi-Bridge", true),
+        ];
+        GraphSnapshot::new(nodes, ports, links)
+    }
+
+    fn at(node_id: ObjectId, group: Option<&str>) -> Endpoint {
+        Endpoint {
+            node_id,
+            group: group.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_detects_clock_sources() {
+        let graph = studio();
+        let sources: Vec<Endpoint> = endpoints(&graph)
+            .into_iter()
+            .filter(|e| is_clock_source(&graph, e))
+            .collect();
+        assert_eq!(sources, [at(1, None), at(4, Some("drums"))]);
+    }
+
+    #[test]
+    fn test_fan_in_lists_only_clock_sources() {
+        let graph = studio();
+        let sources = [at(1, None), at(4, Some("drums"))];
+        assert_eq!(fan_in(&graph, &sources), [(at(3, None), sources.to_vec())]);
+        assert!(fan_in(&graph, &sources[..1]).is_empty());
+    }
+
+    #[test]
+    fn test_routing_keeps_one_clock_per_receiver() {
+        let graph = studio();
+        let sources = [at(1, None), at(4, Some("drums"))];
+        let receivers = [at(3, None), at(4, Some("keys")), at(1, None)];
+        let ops = routing_ops(&graph, &sources[0], &receivers, &sources);
+        let summary: Vec<String> = ops
+            .iter()
+            .map(|op| match op {
+                LinkOp::Connect {
+                    output_port_id,
+                    input_port_id,
+                } => format!("+{}>{}", output_port_id, input_port_id),
+                LinkOp::Disconnect { link_id } => format!("-{}", link_id),
+            })
+            .collect();
+        // The controller's link (102) stays: it isn't a clock source.
+        assert_eq!(summary, ["-101", "+11>43"]);
+    }
+}
@@ -0,0 +1,134 @@
+//! Crossfading between output devices when the default sink changes.
+//!
+//! Switching the default sink lets the session manager move every stream to
+//! the new device at once: a hard cut on one and an abrupt start on the
+//! other. When ZestBay makes the switch with crossfading on, it first links
+//! the streams playing on the old device to the new one as well and ramps
+//! the two devices' volume in opposite directions. The default only changes
+//! once the old device is silent, so the session manager's move is not
+//! heard; the extra links are dropped then and the old device's volume is
+//! put back.
+
+use std::f32::consts::FRAC_PI_2;
+
+use super::manager::PatchbayManager;
+use super::snapshot::GraphSnapshot;
+use super::temporary::PortPair;
+use crate::graph::{NodeType, ObjectId};
+
+/// Crossfade length unless the preference says otherwise.
+pub const DEFAULT_CROSSFADE_MS: u32 = 200;
+
+/// The volume `t` (0 to 1) of the way through a ramp from `from` to `to`.
+/// Rising ramps follow a sine and falling ones a cosine, so two devices
+/// ramped in opposite directions keep the same total power.
+pub fn ramp_volume(from: f32, to: f32, t: f32) -> f32 {
+    let t = t.clamp(0.0, 1.0);
+    if to >= from {
+        from + (to - from) * (t * FRAC_PI_2).sin()
+    } else {
+        to + (from - to) * (t * FRAC_PI_2).cos()
+    }
+}
+
+/// The links that play the streams on `old_sink` on `new_sink` as well:
+/// each stream output feeding the old sink goes to the new sink's input on
+/// the same channel. Other feeds, such as plugins, are not moved by the
+/// session manager and are left alone, as are pairs already linked.
+pub fn handover_pairs(
+    graph: &GraphSnapshot,
+    old_sink: ObjectId,
+    new_sink: ObjectId,
+) -> Vec<PortPair> {
+    if old_sink == new_sink {
+        return Vec::new();
+    }
+    let inputs = graph.input_ports(new_sink);
+    let mut pairs = Vec::new();
+    for link in graph.links() {
+        if link.input_node_id != old_sink
+            || graph.node(link.output_node_id).and_then(|n| n.node_type)
+                != Some(NodeType::StreamOutput)
+        {
+            continue;
+        }
+        let Some(old_input) = graph.port(link.input_port_id) else {
+            continue;
+        };
+        let Some(input) = PatchbayManager::find_matching_port(old_input, &inputs) else {
+            continue;
+        };
+        let pair = (link.output_port_id, input.id);
+        if graph.find_link(pair.0, pair.1).is_none() && !pairs.contains(&pair) {
+            pairs.push(pair);
+        }
+    }
+    pairs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{Link, Node, Port, PortDirection};
+
+    fn add_node(graph: &mut GraphSnapshot, id: ObjectId, node_type: NodeType) {
+        graph.add_node(Node::test(id, &format!("node{}", id), node_type));
+        let direction = if node_type == NodeType::Sink {
+            PortDirection::Input
+        } else {
+            PortDirection::Output
+        };
+        for (i, channel) in ["FL", "FR"].into_iter().enumerate() {
+            let name = format!("{:?}_{}", direction, channel);
+            graph.add_port(Port {
+                channel: Some(channel.into()),
+                ..Port::test(id * 10 + i as ObjectId + 1, id, &name, direction)
+            });
+        }
+    }
+
+    fn link(graph: &mut GraphSnapshot, id: ObjectId, output: ObjectId, input: ObjectId) {
+        graph.add_link(Link::test(id, (output / 10, output), (input / 10, input)));
+    }
+
+    /// A player (1) and a plugin (2) on speakers (3), and headphones (4).
+    fn desk() -> GraphSnapshot {
+        let mut graph = GraphSnapshot::default();
+        add_node(&mut graph, 1, NodeType::StreamOutput);
+        add_node(&mut graph, 2, NodeType::Plugin);
+        add_node(&mut graph, 3, NodeType::Sink);
+        add_node(&mut graph, 4, NodeType::Sink);
+        link(&mut graph, 100, 11, 31);
+        link(&mut graph, 101, 12, 32);
+        link(&mut graph, 102, 21, 31);
+        graph
+    }
+
+    #[test]
+    fn test_streams_are_handed_over_by_channel() {
+        let mut pairs = handover_pairs(&desk(), 3, 4);
+        pairs.sort();
+        assert_eq!(pairs, [(11, 41), (12, 42)]);
+        assert!(handover_pairs(&desk(), 3, 3).is_empty());
+    }
+
+    #[test]
+    fn test_existing_links_are_not_doubled() {
+        let mut graph = desk();
+        link(&mut graph, 103, 11, 41);
+        assert_eq!(handover_pairs(&graph, 3, 4), [(12, 42)]);
+    }
+
+    #[test]
+    fn test_ramps_keep_power_constant() {
+        assert_eq!(ramp_volume(0.0, 1.0, 0.0), 0.0);
+        assert_eq!(ramp_volume(1.0, 0.0, 0.0), 1.0);
+        assert!(ramp_volume(0.0, 1.0, 1.0) > 0.999);
+        assert!(ramp_volume(1.0, 0.0, 2.0) < 0.001);
+        for step in 0..=10 {
+            let t = step as f32 / 10.0;
+            let power = ramp_volume(0.0, 1.0, t).powi(2) + ramp_volume(1.0, 0.0, t).powi(2);
+            assert!((power - 1.0).abs() < 1e-5);
+        }
+    }
+}
@@ -0,0 +1,133 @@
+//! Per-link gain for mixing several sources into one sink.
+//!
+//! When a game, voice chat and music all feed a stream mix, each link can
+//! carry its own gain so they are balanced right in the patchbay. A link
+//! with a gain set is routed through an internal gain node; the graph view
+//! still draws it as one link. Gains are saved with the link's ends, by
+//! node and port name like rules, and come back whenever both ends do.
+
+use serde::{Deserialize, Serialize};
+
+/// Node name prefix of the internal gain nodes, followed by the gain id.
+pub const GAIN_NODE_PREFIX: &str = "zestbay-link-gain-";
+
+/// The lowest gain; a link set here is silent.
+pub const MIN_GAIN_DB: f32 = -60.0;
+pub const MAX_GAIN_DB: f32 = 12.0;
+
+/// The node name of gain node `id`.
+pub fn gain_node_name(id: u32) -> String {
+    format!("{}{}", GAIN_NODE_PREFIX, id)
+}
+
+/// The gain id behind a node name, if it is a gain node.
+pub fn gain_id_of(node_name: &str) -> Option<u32> {
+    node_name.strip_prefix(GAIN_NODE_PREFIX)?.parse().ok()
+}
+
+/// The linear factor for `db`, clamped to the allowed range, with
+/// [`MIN_GAIN_DB`] and below as silence.
+pub fn db_to_gain(db: f32) -> f32 {
+    if db.is_nan() || db <= MIN_GAIN_DB {
+        return 0.0;
+    }
+    10f32.powf(db.min(MAX_GAIN_DB) / 20.0)
+}
+
+/// Applies a gain to one block at a time. A changed gain is ramped to over
+/// the block rather than jumped to, so moving the slider does not click.
+#[derive(Debug)]
+pub struct GainSmoother {
+    current: f32,
+}
+
+impl GainSmoother {
+    pub fn new(gain: f32) -> Self {
+        Self { current: gain }
+    }
+
+    /// Write `input` times the gain to `output`, ending the block at
+    /// `target`.
+    pub fn process(&mut self, target: f32, input: &[f32], output: &mut [f32]) {
+        let n = input.len().min(output.len());
+        if self.current == target {
+            for (out, sample) in output[..n].iter_mut().zip(input) {
+                *out = sample * target;
+            }
+            return;
+        }
+        let step = (target - self.current) / n.max(1) as f32;
+        for (i, (out, sample)) in output[..n].iter_mut().zip(input).enumerate() {
+            *out = sample * (self.current + step * (i + 1) as f32);
+        }
+        self.current = target;
+    }
+}
+
+/// A link with a gain, saved by node display name and port name.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SavedLinkGain {
+    /// Names the gain node while ZestBay runs.
+    pub id: u32,
+    pub source: String,
+    pub output_port: String,
+    pub target: String,
+    pub input_port: String,
+    pub gain_db: f32,
+}
+
+impl SavedLinkGain {
+    pub fn matches(&self, source: &str, output_port: &str, target: &str, input_port: &str) -> bool {
+        self.source == source
+            && self.output_port == output_port
+            && self.target == target
+            && self.input_port == input_port
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_db_is_converted_and_clamped() {
+        assert_eq!(db_to_gain(0.0), 1.0);
+        assert!((db_to_gain(-6.0) - 0.501).abs() < 0.001);
+        assert!((db_to_gain(40.0) - db_to_gain(MAX_GAIN_DB)).abs() < 1e-6);
+        assert_eq!(db_to_gain(MIN_GAIN_DB), 0.0);
+        assert_eq!(db_to_gain(f32::NAN), 0.0);
+    }
+
+    #[test]
+    fn test_gain_changes_are_ramped_over_a_block() {
+        let mut smoother = GainSmoother::new(1.0);
+        let mut out = [0.0; 4];
+        smoother.process(0.0, &[1.0; 4], &mut out);
+        assert_eq!(out, [0.75, 0.5, 0.25, 0.0]);
+        smoother.process(0.0, &[1.0; 4], &mut out);
+        assert_eq!(out, [0.0; 4]);
+        smoother.process(0.5, &[2.0, -2.0], &mut out[..2]);
+        assert_eq!(out[..2], [0.5, -1.0]);
+    }
+
+    #[test]
+    fn test_node_names_and_saved_gains_round_trip() {
+        assert_eq!(gain_id_of(&gain_node_name(3)), Some(3));
+        assert_eq!(gain_id_of("zestbay-link-gain-"), None);
+        assert_eq!(gain_id_of("zestbay-midi-filter-3"), None);
+        let gain = SavedLinkGain {
+            id: 3,
+            source: "Game".into(),
+            output_port: "output_FL".into(),
+            target: "Stream Mix".into(),
+            input_port: "playback_FL".into(),
+            gain_db: -4.5,
+        };
+        let json = serde_json::to_string(&gain).unwrap();
+        assert!(json.contains("\"gainDb\":-4.5"));
+        let back: SavedLinkGain = serde_json::from_str(&json).unwrap();
+        assert!(back.matches("Game", "output_FL", "Stream Mix", "playback_FL"));
+        assert!(!back.matches("Game", "output_FR", "Stream Mix", "playback_FL"));
+    }
+}
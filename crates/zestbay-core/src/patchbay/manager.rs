@@ -0,0 +1,1632 @@
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+
+use super::overrides::{LinkOverride, OverrideMode, unix_now};
+use super::roles::RolePolicy;
+use super::rules::{pattern_matches, AutoConnectRule, PortMapping};
+use super::snapshot::GraphSnapshot;
+use crate::graph::{
+    CommandOrigin, GraphFilter, Link, MediaType, Node, NodeType, ObjectId, Port, PwCommand,
+};
+
+/// The rules engine. It holds rules, policies and the state of manual
+/// disconnects, and plans link changes against a [`GraphSnapshot`]; it never
+/// talks to PipeWire itself.
+#[derive(Clone)]
+pub struct PatchbayManager {
+    rules: Vec<AutoConnectRule>,
+    pub enabled: bool,
+    pub rules_dirty: bool,
+    /// Display name of the default target node. When a source node has no
+    /// matching rules, its output ports will be connected to this node instead.
+    default_target: Option<String>,
+    /// media.role → target policies, consulted for application streams with
+    /// no matching rule before falling back to `default_target`.
+    role_policies: Vec<RolePolicy>,
+    /// (rule id, source node id) pairs held off after a manual disconnect,
+    /// with the time the rule may apply again.
+    cooldowns: HashMap<(String, ObjectId), Instant>,
+    /// (rule id, source node id) pairs a `once_per_appearance` rule has
+    /// already connected. Dropped when the source node goes away.
+    applied_once: HashSet<(String, ObjectId)>,
+    /// Rule id → index into its `fallback_targets` of the fallback in use
+    /// while its target is missing.
+    active_fallbacks: HashMap<String, usize>,
+    /// What a manual disconnect of a rule-owned link records.
+    override_mode: OverrideMode,
+    /// Links disconnected by hand that rules leave alone for now.
+    overrides: Vec<LinkOverride>,
+    pub overrides_dirty: bool,
+    /// Node id → saved identity, for rules that name their nodes by
+    /// identity rather than display name.
+    identities: HashMap<ObjectId, String>,
+    /// Bumped by every edit that does not come from a scan.
+    revision: u64,
+}
+
+impl Default for PatchbayManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PatchbayManager {
+    pub fn new() -> Self {
+        Self {
+            rules: Vec::new(),
+            enabled: true,
+            rules_dirty: false,
+            default_target: None,
+            role_policies: Vec::new(),
+            cooldowns: HashMap::new(),
+            applied_once: HashSet::new(),
+            active_fallbacks: HashMap::new(),
+            override_mode: OverrideMode::Off,
+            overrides: Vec::new(),
+            overrides_dirty: false,
+            identities: HashMap::new(),
+            revision: 0,
+        }
+    }
+
+    pub fn set_rules(&mut self, rules: Vec<AutoConnectRule>) {
+        self.revision += 1;
+        self.rules = rules;
+        self.rules_dirty = true;
+    }
+
+    pub fn add_rule(&mut self, rule: AutoConnectRule) {
+        self.revision += 1;
+        self.rules.push(rule);
+        self.rules_dirty = true;
+    }
+
+    pub fn remove_rule(&mut self, id: &str) {
+        self.revision += 1;
+        self.rules.retain(|r| r.id != id);
+        self.rules_dirty = true;
+    }
+
+    /// Set the saved identity of each node in the graph. A change counts as
+    /// an edit, since it can change which nodes the rules match.
+    pub fn set_identities(&mut self, identities: HashMap<ObjectId, String>) {
+        if self.identities != identities {
+            self.revision += 1;
+            self.identities = identities;
+        }
+    }
+
+    fn identity(&self, node_id: ObjectId) -> Option<&str> {
+        self.identities.get(&node_id).map(String::as_str)
+    }
+
+    /// Give rules saved before identities existed the identity of the nodes
+    /// they name, so they keep matching after a rename. Only rules naming a
+    /// node exactly are updated, and only when a single node with an
+    /// identity matches. Returns true if any rule changed.
+    pub fn adopt_identities(&mut self, nodes: &[Node]) -> bool {
+        let sole = |pattern: &str, matches: &dyn Fn(&Node) -> bool| -> Option<String> {
+            if pattern.contains(['*', '?']) {
+                return None;
+            }
+            let mut named = nodes
+                .iter()
+                .filter(|n| matches(n))
+                .filter_map(|n| self.identities.get(&n.id));
+            let identity = named.next()?;
+            named
+                .all(|other| other == identity)
+                .then(|| identity.clone())
+        };
+
+        let mut adopted = Vec::new();
+        for (i, rule) in self.rules.iter().enumerate() {
+            let source = sole(&rule.source_pattern, &|n: &Node| {
+                rule.matches_source(n.display_name(), n.node_type)
+            })
+            .filter(|_| rule.source_identity.is_none());
+            let target = sole(&rule.target_pattern, &|n: &Node| {
+                pattern_matches(&rule.target_pattern, n.display_name())
+                    && rule.target_node_type.is_none_or(|t| n.node_type == Some(t))
+            })
+            .filter(|_| rule.target_identity.is_none());
+            if source.is_some() || target.is_some() {
+                adopted.push((i, source, target));
+            }
+        }
+        if adopted.is_empty() {
+            return false;
+        }
+
+        self.revision += 1;
+        for (i, source, target) in adopted {
+            let rule = &mut self.rules[i];
+            rule.source_identity = rule.source_identity.take().or(source);
+            rule.target_identity = rule.target_identity.take().or(target);
+        }
+        self.rules_dirty = true;
+        true
+    }
+
+    pub fn set_default_target(&mut self, name: Option<String>) {
+        self.revision += 1;
+        self.default_target = name;
+    }
+
+    pub fn default_target(&self) -> Option<&str> {
+        self.default_target.as_deref()
+    }
+
+    pub fn set_role_policies(&mut self, policies: Vec<RolePolicy>) {
+        self.revision += 1;
+        self.role_policies = policies;
+    }
+
+    /// Insert or replace the policy for `policy.role`.
+    pub fn set_role_policy(&mut self, policy: RolePolicy) {
+        self.revision += 1;
+        if let Some(existing) = self
+            .role_policies
+            .iter_mut()
+            .find(|p| p.role.eq_ignore_ascii_case(&policy.role))
+        {
+            *existing = policy;
+        } else {
+            self.role_policies.push(policy);
+        }
+    }
+
+    pub fn remove_role_policy(&mut self, role: &str) {
+        self.revision += 1;
+        self.role_policies
+            .retain(|p| !p.role.eq_ignore_ascii_case(role));
+    }
+
+    pub fn role_policies(&self) -> &[RolePolicy] {
+        &self.role_policies
+    }
+
+    /// Changes when rules, policies, overrides or cooldowns are edited, but
+    /// not when a scan updates them. A scan run on a copy can replace the
+    /// original only if the revision still matches.
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    pub fn rules(&self) -> &[AutoConnectRule] {
+        &self.rules
+    }
+
+    pub fn toggle_rule(&mut self, id: &str) -> Option<bool> {
+        self.revision += 1;
+        if let Some(rule) = self.rules.iter_mut().find(|r| r.id == id) {
+            rule.enabled = !rule.enabled;
+            self.rules_dirty = true;
+            Some(rule.enabled)
+        } else {
+            None
+        }
+    }
+
+    pub fn set_rule_cooldown(&mut self, id: &str, secs: u64) -> bool {
+        self.revision += 1;
+        let Some(rule) = self.rules.iter_mut().find(|r| r.id == id) else {
+            return false;
+        };
+        rule.cooldown_secs = secs;
+        if secs == 0 {
+            self.cooldowns.retain(|(rule_id, _), _| rule_id != id);
+        }
+        self.rules_dirty = true;
+        true
+    }
+
+    pub fn set_rule_once_per_appearance(&mut self, id: &str, once: bool) -> bool {
+        self.revision += 1;
+        let Some(rule) = self.rules.iter_mut().find(|r| r.id == id) else {
+            return false;
+        };
+        rule.once_per_appearance = once;
+        if !once {
+            self.applied_once.retain(|(rule_id, _)| rule_id != id);
+        }
+        self.rules_dirty = true;
+        true
+    }
+
+    pub fn set_rule_fallbacks(&mut self, id: &str, targets: Vec<String>) -> bool {
+        self.revision += 1;
+        let Some(rule) = self.rules.iter_mut().find(|r| r.id == id) else {
+            return false;
+        };
+        rule.fallback_targets = targets;
+        self.active_fallbacks.remove(id);
+        self.rules_dirty = true;
+        true
+    }
+
+    /// The fallback target pattern `rule_id` is using while its target is
+    /// missing, as of the last scan.
+    pub fn active_fallback(&self, rule_id: &str) -> Option<&str> {
+        let index = *self.active_fallbacks.get(rule_id)?;
+        let rule = self.rules.iter().find(|r| r.id == rule_id)?;
+        rule.fallback_targets.get(index).map(String::as_str)
+    }
+
+    pub fn set_override_mode(&mut self, mode: OverrideMode) {
+        self.revision += 1;
+        self.override_mode = mode;
+    }
+
+    pub fn overrides(&self) -> &[LinkOverride] {
+        &self.overrides
+    }
+
+    pub fn set_overrides(&mut self, overrides: Vec<LinkOverride>) {
+        self.revision += 1;
+        self.overrides = overrides;
+    }
+
+    pub fn remove_override(&mut self, index: usize) -> bool {
+        self.revision += 1;
+        if index >= self.overrides.len() {
+            return false;
+        }
+        let removed = self.overrides.remove(index);
+        self.overrides_dirty |= removed.is_persistent();
+        true
+    }
+
+    pub fn clear_overrides(&mut self) {
+        self.revision += 1;
+        self.overrides_dirty |= self.overrides.iter().any(|o| o.is_persistent());
+        self.overrides.clear();
+    }
+
+    /// Drop the override for a link the user has connected again by hand.
+    pub fn clear_override_for(
+        &mut self,
+        source_node: &Node,
+        target_node: &Node,
+        output_port: &Port,
+        input_port: &Port,
+    ) -> bool {
+        self.revision += 1;
+        let before = self.overrides.len();
+        let mut persistent = false;
+        self.overrides.retain(|o| {
+            let hit = o.matches(
+                source_node.display_name(),
+                &output_port.name,
+                target_node.display_name(),
+                &input_port.name,
+            );
+            persistent |= hit && o.is_persistent();
+            !hit
+        });
+        self.overrides_dirty |= persistent;
+        self.overrides.len() != before
+    }
+
+    fn is_overridden(
+        &self,
+        source: &Node,
+        output_port: &Port,
+        target: &Node,
+        input_port: &Port,
+    ) -> bool {
+        self.overrides.iter().any(|o| {
+            o.matches(
+                source.display_name(),
+                &output_port.name,
+                target.display_name(),
+                &input_port.name,
+            )
+        })
+    }
+
+    /// The rule a link between these ports belongs to. An enabled rule is
+    /// preferred over a disabled one that also matches.
+    pub fn owning_rule(
+        &self,
+        source_node: &Node,
+        target_node: &Node,
+        output_port: &Port,
+        input_port: &Port,
+    ) -> Option<&AutoConnectRule> {
+        let mut owners = self.rules.iter().filter(|r| {
+            r.matches_source_node(source_node, self.identity(source_node.id))
+                && self.rule_links_to(r, target_node, &output_port.name, &input_port.name)
+        });
+        let first = owners.next()?;
+        if first.enabled {
+            return Some(first);
+        }
+        owners.find(|r| r.enabled).or(Some(first))
+    }
+
+    /// Whether an enabled rule links `source` to `target`.
+    pub fn has_rule_linking(&self, source: &Node, target: &Node) -> bool {
+        self.rules.iter().any(|r| {
+            r.enabled
+                && r.matches_source_node(source, self.identity(source.id))
+                && r.matches_target_node(target, self.identity(target.id))
+        })
+    }
+
+    /// Whether `rule` links to `target` through these ports: its own
+    /// target through the ports it maps, or the fallback it is using through
+    /// any.
+    fn rule_links_to(
+        &self,
+        rule: &AutoConnectRule,
+        target: &Node,
+        output_port: &str,
+        input_port: &str,
+    ) -> bool {
+        if rule.matches_target_node(target, self.identity(target.id)) {
+            return rule.maps_ports(output_port, input_port);
+        }
+        self.active_fallback(&rule.id)
+            .is_some_and(|pattern| pattern_matches(pattern, target.display_name()))
+    }
+
+    /// The rules `node` is the source of.
+    pub fn rules_from<'a>(&'a self, node: &'a Node) -> impl Iterator<Item = &'a AutoConnectRule> {
+        self.rules
+            .iter()
+            .filter(|r| r.matches_source_node(node, self.identity(node.id)))
+    }
+
+    pub fn learn_from_link(
+        &mut self,
+        source_node: &Node,
+        target_node: &Node,
+        output_port: &Port,
+        input_port: &Port,
+    ) -> bool {
+        self.revision += 1;
+        if source_node.id == target_node.id {
+            return false;
+        }
+        if !Self::is_routable_node(source_node) || !Self::is_routable_node(target_node) {
+            return false;
+        }
+
+        let source_name = source_node.display_name().to_string();
+        let source_identity = self.identities.get(&source_node.id).cloned();
+        let target_identity = self.identities.get(&target_node.id).cloned();
+
+        let existing = self.rules.iter_mut().find(|r| {
+            let same_source = match (&r.source_identity, &source_identity) {
+                (Some(a), Some(b)) => a == b,
+                _ => r.source_pattern == source_name,
+            };
+            same_source && r.matches_target_node(target_node, target_identity.as_deref())
+        });
+
+        if let Some(rule) = existing {
+            let changed = rule.add_port_mapping(output_port.name.clone(), input_port.name.clone());
+            if changed {
+                self.rules_dirty = true;
+            }
+            return changed;
+        }
+
+        let mut rule = AutoConnectRule::new(
+            source_name,
+            source_node.node_type,
+            target_node.display_name(),
+            target_node.node_type,
+            Some(target_node.id),
+        );
+        rule.source_identity = source_identity;
+        rule.target_identity = target_identity;
+        rule.add_port_mapping(output_port.name.clone(), input_port.name.clone());
+        self.rules.push(rule);
+        self.rules_dirty = true;
+        true
+    }
+
+    pub fn unlearn_from_link(
+        &mut self,
+        source_node: &Node,
+        target_node: &Node,
+        output_port: &Port,
+        input_port: &Port,
+    ) -> bool {
+        self.revision += 1;
+        if !Self::is_routable_node(source_node) || !Self::is_routable_node(target_node) {
+            return false;
+        }
+
+        let source_identity = self.identities.get(&source_node.id).map(String::as_str);
+        let target_identity = self.identities.get(&target_node.id).map(String::as_str);
+        let mut changed = false;
+        let mut emptied = Vec::new();
+
+        for rule in &mut self.rules {
+            if !rule.matches_source_node(source_node, source_identity) {
+                continue;
+            }
+            if !rule.matches_target_node(target_node, target_identity) {
+                continue;
+            }
+
+            // Rules that pause on a manual disconnect keep the link and are
+            // held off this source instead.
+            if rule.enabled && rule.pauses_on_disconnect() {
+                if rule.cooldown_secs > 0 {
+                    self.cooldowns.insert(
+                        (rule.id.clone(), source_node.id),
+                        Instant::now() + Duration::from_secs(rule.cooldown_secs),
+                    );
+                }
+                continue;
+            }
+
+            // With overrides on, a link the rule owns is left down for the
+            // override's scope and stays in the rule.
+            if rule.enabled
+                && rule.maps_ports(&output_port.name, &input_port.name)
+                && let Some(o) = LinkOverride::new(
+                    self.override_mode,
+                    source_node,
+                    output_port,
+                    target_node,
+                    input_port,
+                )
+            {
+                if !self
+                    .overrides
+                    .iter()
+                    .any(|e| e.matches(&o.source, &o.output_port, &o.target, &o.input_port))
+                {
+                    self.overrides_dirty |= o.is_persistent();
+                    self.overrides.push(o);
+                }
+                continue;
+            }
+
+            let before = rule.port_mappings.len();
+            rule.port_mappings.retain(|m| {
+                !(m.output_port_name == output_port.name && m.input_port_name == input_port.name)
+            });
+            if rule.port_mappings.len() != before {
+                changed = true;
+                if rule.port_mappings.is_empty() {
+                    emptied.push(rule.id.clone());
+                }
+            }
+        }
+
+        if !emptied.is_empty() {
+            self.rules.retain(|r| !emptied.contains(&r.id));
+        }
+
+        if changed {
+            self.rules_dirty = true;
+        }
+        changed
+    }
+
+    pub fn snapshot_current_connections(&mut self, graph: &GraphSnapshot) {
+        self.revision += 1;
+        let links = graph.links();
+
+        let mut rule_map: HashMap<(String, String, Option<NodeType>, ObjectId), AutoConnectRule> =
+            HashMap::new();
+
+        for link in links {
+            let source = graph.node(link.output_node_id);
+            let target = graph.node(link.input_node_id);
+            let out_port = graph.port(link.output_port_id);
+            let in_port = graph.port(link.input_port_id);
+
+            if let (Some(source), Some(target), Some(out_port), Some(in_port)) =
+                (source, target, out_port, in_port)
+            {
+                if source.id == target.id {
+                    continue;
+                }
+                if !Self::is_routable_node(source) || !Self::is_routable_node(target) {
+                    continue;
+                }
+                let key = (
+                    source.display_name().to_string(),
+                    target.display_name().to_string(),
+                    target.node_type,
+                    target.id,
+                );
+
+                let rule = rule_map.entry(key).or_insert_with(|| {
+                    let mut rule = AutoConnectRule::new(
+                        source.display_name(),
+                        source.node_type,
+                        target.display_name(),
+                        target.node_type,
+                        Some(target.id),
+                    );
+                    rule.source_identity = self.identities.get(&source.id).cloned();
+                    rule.target_identity = self.identities.get(&target.id).cloned();
+                    rule
+                });
+
+                rule.add_port_mapping(out_port.name.clone(), in_port.name.clone());
+            }
+        }
+
+        self.rules = rule_map.into_values().collect();
+        self.rules_dirty = true;
+    }
+
+    pub fn refresh_target_ids(&mut self, graph: &GraphSnapshot) {
+        let nodes = graph.nodes();
+        let mut dirty = false;
+
+        for rule in &mut self.rules {
+            if let Some(old_id) = rule.target_node_id {
+                let identities = &self.identities;
+                let named =
+                    |n: &Node| match (rule.target_identity.as_deref(), identities.get(&n.id)) {
+                        (Some(expected), Some(identity)) => expected == identity,
+                        _ => pattern_matches(&rule.target_pattern, n.display_name()),
+                    };
+                let id_still_valid = nodes.iter().any(|n| n.id == old_id && n.ready && named(n));
+
+                if !id_still_valid {
+                    let new_match = nodes.iter().find(|n| {
+                        n.ready
+                            && n.node_type.map(|t| t.has_inputs()).unwrap_or(false)
+                            && named(n)
+                            && (rule.target_node_type.is_none()
+                                || n.node_type == rule.target_node_type)
+                    });
+
+                    let new_id = new_match.map(|n| n.id);
+                    if rule.target_node_id != new_id {
+                        log::info!(
+                            "Rule '{}→{}': updating stale target_node_id {:?} → {:?}",
+                            rule.source_pattern,
+                            rule.target_pattern,
+                            rule.target_node_id,
+                            new_id,
+                        );
+                        rule.target_node_id = new_id;
+                        dirty = true;
+                    }
+                }
+            }
+        }
+
+        if dirty {
+            self.rules_dirty = true;
+        }
+    }
+
+    /// Plan the link changes that bring `graph` in line with the rules,
+    /// updating stale target ids, cooldowns and overrides along the way.
+    pub fn scan(&mut self, graph: &GraphSnapshot) -> Vec<PwCommand> {
+        self.scan_with_origins(graph)
+            .into_iter()
+            .map(|(cmd, _)| cmd)
+            .collect()
+    }
+
+    /// [`Self::scan`], with the rule behind each command: the rule that
+    /// wants a link, or the rule a disconnected link was not allowed by.
+    pub fn scan_with_origins(&mut self, graph: &GraphSnapshot) -> Vec<(PwCommand, CommandOrigin)> {
+        if !self.enabled || (self.rules.is_empty() && self.role_policies.is_empty()) {
+            return Vec::new();
+        }
+
+        self.refresh_target_ids(graph);
+        self.clean_incompatible_mappings(graph);
+        self.update_fallbacks(graph.nodes());
+
+        let mut commands = Vec::new();
+        let nodes = graph.nodes();
+
+        let live: HashSet<ObjectId> = nodes.iter().map(|n| n.id).collect();
+        let now = Instant::now();
+        self.cooldowns
+            .retain(|(_, id), until| *until > now && live.contains(id));
+        self.applied_once.retain(|(_, id)| live.contains(id));
+        let unix = unix_now();
+        let node_exists = |id: ObjectId| live.contains(&id);
+        self.overrides_dirty |= self
+            .overrides
+            .iter()
+            .any(|o| o.is_persistent() && !o.is_live(node_exists, unix));
+        self.overrides.retain(|o| o.is_live(node_exists, unix));
+        let mut applied = Vec::new();
+
+        for node in nodes {
+            if !node.ready {
+                continue;
+            }
+
+            if !Self::is_routable_node(node) {
+                continue;
+            }
+
+            let output_ports = graph.output_ports(node.id);
+            if output_ports.is_empty() {
+                continue;
+            }
+
+            let matching_rules: Vec<&AutoConnectRule> = self
+                .rules
+                .iter()
+                .filter(|r| r.enabled && r.matches_source_node(node, self.identity(node.id)))
+                .collect();
+
+            if matching_rules.is_empty() {
+                // No rules match this source — use the role policy or default
+                // target if set, but only for application streams
+                // (StreamOutput), not hardware sources like microphones or
+                // other node types.
+                let is_app_stream = node.node_type == Some(NodeType::StreamOutput);
+                if is_app_stream
+                    && let Some(target) = self.find_fallback_target(node, nodes)
+                {
+                    // Auto-connect by port matching (no explicit port mappings)
+                    let target_ports = graph.input_ports(target.id);
+                    for source_port in &output_ports {
+                        if let Some(target_port) =
+                            Self::find_matching_port(source_port, &target_ports)
+                            && graph.find_link(source_port.id, target_port.id).is_none()
+                        {
+                            commands.push((
+                                PwCommand::Connect {
+                                    output_port_id: source_port.id,
+                                    input_port_id: target_port.id,
+                                },
+                                CommandOrigin::Fallback,
+                            ));
+                        }
+                    }
+                }
+            } else {
+                for rule in &matching_rules {
+                    let key = (rule.id.clone(), node.id);
+                    if self.cooldowns.contains_key(&key)
+                        || (rule.once_per_appearance && self.applied_once.contains(&key))
+                    {
+                        continue;
+                    }
+                    // A fallback target gets the sources by channel.
+                    let target = match self.find_matching_target(rule, nodes, node.id) {
+                        Some(target) => Some((target, rule.port_mappings.as_slice())),
+                        None => self
+                            .find_active_fallback(rule, nodes, node.id)
+                            .map(|target| (target, &[][..])),
+                    };
+                    if let Some((target, mappings)) = target {
+                        let origin = CommandOrigin::Rule {
+                            id: rule.id.clone(),
+                        };
+                        commands.extend(
+                            self.generate_connections(graph, mappings, node, target, &output_ports)
+                                .into_iter()
+                                .map(|cmd| (cmd, origin.clone())),
+                        );
+                        if rule.once_per_appearance {
+                            applied.push(key);
+                        }
+                    }
+                }
+            }
+        }
+
+        self.applied_once.extend(applied);
+
+        for link in graph.links() {
+            if let Some(rule) = self.removal_rule(graph, link) {
+                let origin = CommandOrigin::Rule {
+                    id: rule.id.clone(),
+                };
+                commands.push((PwCommand::Disconnect { link_id: link.id }, origin));
+            }
+        }
+
+        commands
+    }
+
+    /// The links a stream that just appeared gets right away, without
+    /// waiting for the graph to settle: for short-lived streams such as
+    /// notification sounds that may be gone before the next [`Self::scan`].
+    ///
+    /// Only rules that name the stream exactly, as learned rules do, are
+    /// used, and only while their own target is present; patterns,
+    /// fallbacks, default targets and removals are left to the scan, which
+    /// still runs once the graph settles. Nothing is recorded, so that scan
+    /// sees the stream as it would have without this.
+    pub fn route_new_stream(
+        &self,
+        graph: &GraphSnapshot,
+        node_id: ObjectId,
+    ) -> Vec<(PwCommand, CommandOrigin)> {
+        if !self.enabled {
+            return Vec::new();
+        }
+        let Some(node) = graph.node(node_id) else {
+            return Vec::new();
+        };
+        if !node.ready || node.node_type != Some(NodeType::StreamOutput) {
+            return Vec::new();
+        }
+        let output_ports = graph.output_ports(node.id);
+        if output_ports.is_empty() {
+            return Vec::new();
+        }
+
+        let mut commands = Vec::new();
+        let identity = self.identity(node.id);
+        let exact = self.rules.iter().filter(|r| {
+            r.enabled
+                && (r.source_pattern == node.display_name()
+                    || (r.source_identity.is_some() && identity.is_some()))
+                && r.matches_source_node(node, identity)
+        });
+        for rule in exact {
+            let key = (rule.id.clone(), node.id);
+            if self.cooldowns.contains_key(&key)
+                || (rule.once_per_appearance && self.applied_once.contains(&key))
+            {
+                continue;
+            }
+            let Some(target) = self.find_matching_target(rule, graph.nodes(), node.id) else {
+                continue;
+            };
+            let origin = CommandOrigin::Rule {
+                id: rule.id.clone(),
+            };
+            commands.extend(
+                self.generate_connections(graph, &rule.port_mappings, node, target, &output_ports)
+                    .into_iter()
+                    .map(|cmd| (cmd, origin.clone())),
+            );
+        }
+        commands
+    }
+
+    /// Drop cooldowns that have run out. Returns true if any did, so the
+    /// caller can rescan and let the paused rules apply again.
+    pub fn expire_cooldowns(&mut self) -> bool {
+        let now = Instant::now();
+        let before = self.cooldowns.len();
+        self.cooldowns.retain(|_, until| *until > now);
+        self.cooldowns.len() != before
+    }
+
+    /// The commands [`Self::scan`] would issue for `graph`, without changing
+    /// any state. For dry runs and simulations.
+    pub fn simulate(&self, graph: &GraphSnapshot) -> Vec<PwCommand> {
+        self.clone().scan(graph)
+    }
+
+    /// The graph changes a scan can act on, for subscribing to a
+    /// [`GraphState`](crate::graph::GraphState). Video nodes are never
+    /// routed, so their changes do not call for a scan.
+    pub fn graph_filter() -> GraphFilter {
+        GraphFilter::all()
+            .media(MediaType::Audio)
+            .media(MediaType::Midi)
+    }
+
+    fn generate_connections(
+        &self,
+        graph: &GraphSnapshot,
+        port_mappings: &[PortMapping],
+        source: &Node,
+        target: &Node,
+        source_ports: &[Port],
+    ) -> Vec<PwCommand> {
+        let mut commands = Vec::new();
+        let target_ports = graph.input_ports(target.id);
+
+        if port_mappings.is_empty() {
+            for source_port in source_ports {
+                if let Some(target_port) = Self::find_matching_port(source_port, &target_ports)
+                    && graph.find_link(source_port.id, target_port.id).is_none()
+                    && !self.is_overridden(source, source_port, target, target_port)
+                {
+                    commands.push(PwCommand::Connect {
+                        output_port_id: source_port.id,
+                        input_port_id: target_port.id,
+                    });
+                }
+            }
+        } else {
+            for mapping in port_mappings {
+                let out_port = source_ports
+                    .iter()
+                    .find(|p| p.name == mapping.output_port_name);
+                let in_port = target_ports
+                    .iter()
+                    .find(|p| p.name == mapping.input_port_name);
+
+                if let (Some(out_port), Some(in_port)) = (out_port, in_port) {
+                    // Skip media type mismatches (e.g. Midi → Audio)
+                    if let (Some(out_mt), Some(in_mt)) = (out_port.media_type, in_port.media_type)
+                        && out_mt != in_mt
+                    {
+                        continue;
+                    }
+                    if graph.find_link(out_port.id, in_port.id).is_none()
+                        && !self.is_overridden(source, out_port, target, in_port)
+                    {
+                        commands.push(PwCommand::Connect {
+                            output_port_id: out_port.id,
+                            input_port_id: in_port.id,
+                        });
+                    }
+                }
+            }
+        }
+
+        commands
+    }
+
+    /// The input among `targets` that `source` pairs with: same channel, then
+    /// same name, then same physical index.
+    pub(super) fn find_matching_port<'a>(source: &Port, targets: &'a [Port]) -> Option<&'a Port> {
+        // Filter targets to compatible media types (don't connect Midi→Audio or Audio→Midi)
+        let compatible: Vec<&Port> = targets
+            .iter()
+            .filter(|p| match (source.media_type, p.media_type) {
+                (Some(a), Some(b)) => a == b,
+                _ => true, // If either is unknown, allow
+            })
+            .collect();
+
+        if compatible.is_empty() {
+            return None;
+        }
+
+        if let Some(ref channel) = source.channel
+            && let Some(target) = compatible.iter().find(|p| p.channel.as_ref() == Some(channel))
+        {
+            return Some(target);
+        }
+
+        if let Some(target) = compatible.iter().find(|p| p.name == source.name) {
+            return Some(target);
+        }
+
+        let source_index = source.physical_index.unwrap_or(0);
+        compatible
+            .iter()
+            .find(|p| p.physical_index.unwrap_or(0) == source_index)
+            .copied()
+            .or_else(|| compatible.first().copied())
+    }
+
+    /// Target for a stream that no rule matches: the sink its `media.role`
+    /// policy points at, otherwise the default target.
+    fn find_fallback_target<'a>(&self, source: &Node, nodes: &'a [Node]) -> Option<&'a Node> {
+        let is_candidate = |n: &&Node| {
+            n.id != source.id && n.ready && n.node_type.map(|t| t.has_inputs()).unwrap_or(false)
+        };
+
+        if let Some(policy) = self
+            .role_policies
+            .iter()
+            .find(|p| p.matches_role(source.media_role.as_deref()))
+            && let Some(target) = nodes
+                .iter()
+                .filter(is_candidate)
+                .find(|n| policy.matches_target(n.display_name()))
+        {
+            return Some(target);
+        }
+
+        let default_name = self.default_target.as_ref()?;
+        nodes
+            .iter()
+            .filter(is_candidate)
+            .find(|n| n.display_name() == default_name)
+    }
+
+    /// Whether a link from a rule-less source is explained by its role policy
+    /// or the default target.
+    fn is_fallback_link(&self, source: &Node, target: &Node) -> bool {
+        if let Some(policy) = self
+            .role_policies
+            .iter()
+            .find(|p| p.matches_role(source.media_role.as_deref()))
+            && policy.matches_target(target.display_name())
+        {
+            return true;
+        }
+        self.default_target
+            .as_deref()
+            .is_some_and(|name| target.display_name() == name)
+    }
+
+    fn find_matching_target<'a>(
+        &self,
+        rule: &AutoConnectRule,
+        nodes: &'a [Node],
+        exclude_node_id: ObjectId,
+    ) -> Option<&'a Node> {
+        if let Some(target_id) = rule.target_node_id
+            && target_id != exclude_node_id
+            && let Some(node) = nodes.iter().find(|n| n.id == target_id && n.ready)
+            && node.node_type.map(|t| t.has_inputs()).unwrap_or(false) {
+                return Some(node);
+        }
+
+        nodes.iter().find(|n| {
+            n.id != exclude_node_id
+                && n.ready
+                && n.node_type.map(|t| t.has_inputs()).unwrap_or(false)
+                && rule.matches_target_node(n, self.identity(n.id))
+        })
+    }
+
+    /// Pick the fallback each rule uses: none while a node matches its
+    /// target, otherwise the first of its fallback targets that is present.
+    fn update_fallbacks(&mut self, nodes: &[Node]) {
+        let mut active = HashMap::new();
+        for rule in self.rules.iter().filter(|r| !r.fallback_targets.is_empty()) {
+            if self
+                .find_matching_target(rule, nodes, ObjectId::MAX)
+                .is_some()
+            {
+                continue;
+            }
+            let Some(index) = rule.fallback_targets.iter().position(|pattern| {
+                nodes.iter().any(|n| {
+                    Self::is_target_candidate(n) && pattern_matches(pattern, n.display_name())
+                })
+            }) else {
+                continue;
+            };
+            active.insert(rule.id.clone(), index);
+        }
+        for rule in &self.rules {
+            let before = self.active_fallbacks.get(&rule.id);
+            let after = active.get(&rule.id);
+            if before == after {
+                continue;
+            }
+            match after {
+                Some(&index) => log::info!(
+                    "Rule '{}→{}': target missing, using fallback '{}'",
+                    rule.source_pattern,
+                    rule.target_pattern,
+                    rule.fallback_targets[index],
+                ),
+                None if before.is_some() => log::info!(
+                    "Rule '{}→{}': leaving fallback",
+                    rule.source_pattern,
+                    rule.target_pattern,
+                ),
+                None => {}
+            }
+        }
+        self.active_fallbacks = active;
+    }
+
+    /// The node matching the fallback `rule` is using, if any.
+    fn find_active_fallback<'a>(
+        &self,
+        rule: &AutoConnectRule,
+        nodes: &'a [Node],
+        exclude_node_id: ObjectId,
+    ) -> Option<&'a Node> {
+        let pattern = self.active_fallback(&rule.id)?;
+        nodes.iter().find(|n| {
+            n.id != exclude_node_id
+                && Self::is_target_candidate(n)
+                && pattern_matches(pattern, n.display_name())
+        })
+    }
+
+    fn is_target_candidate(node: &Node) -> bool {
+        node.ready && node.node_type.map(|t| t.has_inputs()).unwrap_or(false)
+    }
+
+    /// Remove port mappings where media types are incompatible (e.g. a rule
+    /// learned before MIDI port detection that maps Midi → Audio).
+    fn clean_incompatible_mappings(&mut self, graph: &GraphSnapshot) {
+        let nodes = graph.nodes();
+        let mut dirty = false;
+
+        for rule in &mut self.rules {
+            if rule.port_mappings.is_empty() {
+                continue;
+            }
+
+            // Find a target node for this rule
+            let target = nodes.iter().find(|n| {
+                n.ready
+                    && rule.matches_target_node(n, self.identities.get(&n.id).map(String::as_str))
+            });
+            let Some(target) = target else { continue };
+
+            // Find a source node
+            let source = nodes.iter().find(|n| {
+                n.ready
+                    && rule.matches_source_node(n, self.identities.get(&n.id).map(String::as_str))
+            });
+            let Some(source) = source else { continue };
+
+            let source_ports = graph.output_ports(source.id);
+            let target_ports = graph.input_ports(target.id);
+
+            let before = rule.port_mappings.len();
+            rule.port_mappings.retain(|m| {
+                let out_port = source_ports.iter().find(|p| p.name == m.output_port_name);
+                let in_port = target_ports.iter().find(|p| p.name == m.input_port_name);
+                if let (Some(op), Some(ip)) = (out_port, in_port) {
+                    match (op.media_type, ip.media_type) {
+                        (Some(a), Some(b)) if a != b => {
+                            log::info!(
+                                "Removing incompatible port mapping: {} ({:?}) -> {} ({:?})",
+                                m.output_port_name, a, m.input_port_name, b,
+                            );
+                            false
+                        }
+                        _ => true,
+                    }
+                } else {
+                    true // ports not found yet — keep mapping
+                }
+            });
+            if rule.port_mappings.len() != before {
+                dirty = true;
+            }
+        }
+
+        // Remove rules with no remaining mappings
+        let before = self.rules.len();
+        self.rules.retain(|r| !r.port_mappings.is_empty() || r.port_mappings.is_empty());
+        // Actually, rules with empty port_mappings use auto-matching, so keep them.
+        // Only remove if they were explicitly cleaned to zero.
+        if dirty {
+            self.rules_dirty = true;
+        }
+        let _ = before; // suppress unused
+    }
+
+    fn is_routable_node(node: &Node) -> bool {
+        !matches!(node.media_type, Some(MediaType::Video))
+    }
+
+    /// The rule that claims `link`'s source or target without allowing the
+    /// link, so it should be removed. `None` keeps the link.
+    fn removal_rule(&self, graph: &GraphSnapshot, link: &Link) -> Option<&AutoConnectRule> {
+        let source_node = graph.node(link.output_node_id)?;
+        let target_node = graph.node(link.input_node_id)?;
+
+        if !Self::is_routable_node(source_node) || !Self::is_routable_node(target_node) {
+            return None;
+        }
+
+        let out_port = graph.port(link.output_port_id);
+        let in_port = graph.port(link.input_port_id);
+
+        // If either port has already been removed from the graph (e.g. during
+        // device disconnection), the link is stale and will be cleaned up by
+        // PipeWire.  Don't actively try to remove it — doing so can race with
+        // PipeWire's own cleanup and disrupt the audio graph.
+        if out_port.is_none() || in_port.is_none() {
+            return None;
+        }
+
+        let link_authorized_by = |rule: &AutoConnectRule| -> bool {
+            if !rule.enabled {
+                return false;
+            }
+            if !rule.matches_source_node(source_node, self.identity(source_node.id)) {
+                return false;
+            }
+            // Safe to unwrap: we checked both are Some above
+            let out_p = out_port.as_ref().unwrap();
+            let in_p = in_port.as_ref().unwrap();
+            self.rule_links_to(rule, target_node, &out_p.name, &in_p.name)
+        };
+
+        let source_rule = self.rules.iter().find(|r| {
+            r.enabled && r.matches_source_node(source_node, self.identity(source_node.id))
+        });
+
+        if source_rule.is_some() {
+            let authorized = self.rules.iter().any(&link_authorized_by);
+            if !authorized {
+                return source_rule;
+            }
+        }
+
+        // If the source has no rules, check if this link is to its role
+        // policy or the default target — if so, it's authorized by the
+        // fallback routing.
+        if source_rule.is_none() && self.is_fallback_link(source_node, target_node) {
+            return None;
+        }
+
+        let target_rule = self.rules.iter().find(|r| {
+            r.enabled && r.matches_target_node(target_node, self.identity(target_node.id))
+        });
+
+        if target_rule.is_some() {
+            let authorized = self.rules.iter().any(link_authorized_by);
+            if !authorized {
+                return target_rule;
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::PortDirection;
+
+    /// Add a node with stereo ports: outputs `output_FL`/`output_FR` with ids
+    /// `id*10+1`/`id*10+2` and inputs `playback_FL`/`playback_FR` with ids
+    /// `id*10+3`/`id*10+4`, as its type allows.
+    fn add_stereo(graph: &mut GraphSnapshot, id: ObjectId, name: &str, node_type: NodeType) {
+        graph.add_node(Node::test(id, name, node_type));
+        for (i, ch) in ["FL", "FR"].into_iter().enumerate() {
+            let i = i as ObjectId;
+            let ports = [
+                (PortDirection::Output, "output", 1, node_type.has_outputs()),
+                (PortDirection::Input, "playback", 3, node_type.has_inputs()),
+            ];
+            for (direction, prefix, base, present) in ports {
+                if present {
+                    graph.add_port(Port {
+                        id: id * 10 + base + i,
+                        node_id: id,
+                        name: format!("{}_{}", prefix, ch),
+                        direction,
+                        media_type: Some(MediaType::Audio),
+                        channel: Some(ch.into()),
+                        physical_index: None,
+                        port_group: None,
+                        port_alias: None,
+                    });
+                }
+            }
+        }
+    }
+
+    fn connects(commands: &[PwCommand]) -> Vec<(ObjectId, ObjectId)> {
+        let mut pairs: Vec<_> = commands
+            .iter()
+            .filter_map(|c| match *c {
+                PwCommand::Connect {
+                    output_port_id,
+                    input_port_id,
+                } => Some((output_port_id, input_port_id)),
+                _ => None,
+            })
+            .collect();
+        pairs.sort();
+        pairs
+    }
+
+    fn disconnects(commands: &[PwCommand]) -> usize {
+        commands
+            .iter()
+            .filter(|c| matches!(c, PwCommand::Disconnect { .. }))
+            .count()
+    }
+
+    /// Disconnect `out -> in` by hand: tell the manager, then drop the link.
+    fn manual_disconnect(
+        mgr: &mut PatchbayManager,
+        graph: &mut GraphSnapshot,
+        out: ObjectId,
+        inp: ObjectId,
+    ) {
+        let link = graph.find_link(out, inp).unwrap().clone();
+        let (source, target) = (
+            graph.node(link.output_node_id).unwrap().clone(),
+            graph.node(link.input_node_id).unwrap().clone(),
+        );
+        let (out_port, in_port) = (
+            graph.port(out).unwrap().clone(),
+            graph.port(inp).unwrap().clone(),
+        );
+        mgr.unlearn_from_link(&source, &target, &out_port, &in_port);
+        graph.apply(&[PwCommand::Disconnect { link_id: link.id }]);
+    }
+
+    /// Firefox (1) and mpv (2) playing, Headphones (3) and Speakers (4).
+    fn desk() -> GraphSnapshot {
+        let mut graph = GraphSnapshot::default();
+        add_stereo(&mut graph, 1, "Firefox", NodeType::StreamOutput);
+        add_stereo(&mut graph, 2, "mpv", NodeType::StreamOutput);
+        add_stereo(&mut graph, 3, "Headphones", NodeType::Sink);
+        add_stereo(&mut graph, 4, "Speakers", NodeType::Sink);
+        graph
+    }
+
+    fn firefox_to(target: &str) -> AutoConnectRule {
+        AutoConnectRule::new(
+            "Firefox",
+            Some(NodeType::StreamOutput),
+            target,
+            Some(NodeType::Sink),
+            None,
+        )
+    }
+
+    #[test]
+    fn test_heuristic_rule_matches_channels_and_converges() {
+        let mut graph = desk();
+        let mut mgr = PatchbayManager::new();
+        mgr.set_rules(vec![firefox_to("Headphones")]);
+
+        let commands = mgr.scan(&graph);
+        assert_eq!(connects(&commands), vec![(11, 33), (12, 34)]);
+
+        graph.apply(&commands);
+        assert!(mgr.scan(&graph).is_empty());
+    }
+
+    #[test]
+    fn test_port_mappings_connect_only_mapped_ports() {
+        let graph = desk();
+        let mut rule = firefox_to("Headphones");
+        rule.add_port_mapping("output_FL".into(), "playback_FR".into());
+        let mut mgr = PatchbayManager::new();
+        mgr.set_rules(vec![rule]);
+
+        assert_eq!(connects(&mgr.scan(&graph)), vec![(11, 34)]);
+    }
+
+    #[test]
+    fn test_rule_removes_unauthorized_links() {
+        let mut graph = desk();
+        graph.apply(&[PwCommand::Connect {
+            output_port_id: 11,
+            input_port_id: 43,
+        }]);
+        let mut mgr = PatchbayManager::new();
+        mgr.set_rules(vec![firefox_to("Headphones")]);
+
+        let commands = mgr.scan(&graph);
+        assert_eq!(connects(&commands), vec![(11, 33), (12, 34)]);
+        assert_eq!(disconnects(&commands), 1);
+    }
+
+    #[test]
+    fn test_commands_carry_the_rule_behind_them() {
+        let mut graph = desk();
+        graph.apply(&[PwCommand::Connect {
+            output_port_id: 11,
+            input_port_id: 43,
+        }]);
+        let rule = firefox_to("Headphones");
+        let origin = CommandOrigin::Rule {
+            id: rule.id.clone(),
+        };
+        let mut mgr = PatchbayManager::new();
+        mgr.set_rules(vec![rule]);
+
+        let planned = mgr.scan_with_origins(&graph);
+        assert_eq!(planned.len(), 3);
+        assert!(planned.iter().all(|(_, o)| *o == origin));
+    }
+
+    #[test]
+    fn test_role_policy_routes_streams_without_rules() {
+        let mut graph = desk();
+        let mut music = Node::test(2, "mpv", NodeType::StreamOutput);
+        music.media_role = Some("Music".into());
+        graph.add_node(music);
+        let mut mgr = PatchbayManager::new();
+        mgr.set_role_policies(vec![RolePolicy::new("music", "Speakers")]);
+
+        assert_eq!(connects(&mgr.scan(&graph)), vec![(21, 43), (22, 44)]);
+    }
+
+    #[test]
+    fn test_disabled_manager_plans_nothing() {
+        let graph = desk();
+        let mut mgr = PatchbayManager::new();
+        mgr.set_rules(vec![firefox_to("Headphones")]);
+        mgr.enabled = false;
+
+        assert!(mgr.scan(&graph).is_empty());
+    }
+
+    #[test]
+    fn test_manual_disconnect_unlearns_by_default() {
+        let mut graph = desk();
+        let mut rule = firefox_to("Headphones");
+        rule.add_port_mapping("output_FL".into(), "playback_FL".into());
+        rule.add_port_mapping("output_FR".into(), "playback_FR".into());
+        let mut mgr = PatchbayManager::new();
+        mgr.set_rules(vec![rule]);
+        graph.apply(&mgr.scan(&graph));
+
+        manual_disconnect(&mut mgr, &mut graph, 11, 33);
+        assert_eq!(mgr.rules()[0].port_mappings.len(), 1);
+        assert!(mgr.scan(&graph).is_empty());
+    }
+
+    #[test]
+    fn test_cooldown_holds_rule_after_manual_disconnect() {
+        let mut graph = desk();
+        let mut rule = firefox_to("Headphones");
+        rule.cooldown_secs = 60;
+        let mut mgr = PatchbayManager::new();
+        mgr.set_rules(vec![rule]);
+        graph.apply(&mgr.scan(&graph));
+
+        manual_disconnect(&mut mgr, &mut graph, 11, 33);
+        assert!(mgr.scan(&graph).is_empty());
+        assert!(!mgr.expire_cooldowns());
+
+        for until in mgr.cooldowns.values_mut() {
+            *until = Instant::now();
+        }
+        assert!(mgr.expire_cooldowns());
+        assert_eq!(connects(&mgr.scan(&graph)), vec![(11, 33)]);
+    }
+
+    #[test]
+    fn test_new_stream_is_routed_only_by_exact_rules() {
+        let mut graph = desk();
+        add_stereo(&mut graph, 5, "Notification", NodeType::StreamOutput);
+        let mut mgr = PatchbayManager::new();
+        let mut pattern = AutoConnectRule::new(
+            "mp*",
+            Some(NodeType::StreamOutput),
+            "Speakers",
+            Some(NodeType::Sink),
+            None,
+        );
+        pattern.id = "pattern".into();
+        let mut learned = AutoConnectRule::new(
+            "Notification",
+            Some(NodeType::StreamOutput),
+            "Speakers",
+            Some(NodeType::Sink),
+            Some(4),
+        );
+        learned.add_port_mapping("output_FL".into(), "playback_FL".into());
+        mgr.set_rules(vec![pattern, firefox_to("Missing"), learned]);
+
+        let commands = mgr.route_new_stream(&graph, 5);
+        let plain: Vec<PwCommand> = commands.iter().map(|(c, _)| c.clone()).collect();
+        assert_eq!(connects(&plain), vec![(51, 43)]);
+        assert!(matches!(&commands[0].1, CommandOrigin::Rule { .. }));
+
+        // Patterns wait for the scan, as do rules whose target is missing,
+        // and sinks are never routed this way.
+        assert!(mgr.route_new_stream(&graph, 2).is_empty());
+        assert!(mgr.route_new_stream(&graph, 1).is_empty());
+        assert!(mgr.route_new_stream(&graph, 4).is_empty());
+
+        // Once linked, there is nothing left to do.
+        graph.apply(&plain);
+        assert!(mgr.route_new_stream(&graph, 5).is_empty());
+        mgr.enabled = false;
+        graph.remove_node(5);
+        add_stereo(&mut graph, 6, "Notification", NodeType::StreamOutput);
+        assert!(mgr.route_new_stream(&graph, 6).is_empty());
+    }
+
+    #[test]
+    fn test_rules_follow_identities_across_renames() {
+        let mut graph = desk();
+        let mut mgr = PatchbayManager::new();
+        let mut pattern = firefox_to("Speakers");
+        pattern.source_pattern = "mp*".into();
+        mgr.set_rules(vec![firefox_to("Headphones"), pattern]);
+        let ids = |pairs: &[(ObjectId, &str)]| {
+            pairs
+                .iter()
+                .map(|&(id, key)| (id, key.to_string()))
+                .collect()
+        };
+        mgr.set_identities(ids(&[(1, "ff"), (2, "mpv"), (3, "cans"), (4, "spk")]));
+
+        // Rules saved by name pick up the identity of the node they name;
+        // patterns are left alone.
+        assert!(mgr.adopt_identities(graph.nodes()));
+        assert!(!mgr.adopt_identities(graph.nodes()));
+        assert_eq!(mgr.rules()[0].source_identity.as_deref(), Some("ff"));
+        assert_eq!(mgr.rules()[0].target_identity.as_deref(), Some("cans"));
+        assert_eq!(mgr.rules()[1].source_identity, None);
+        assert_eq!(mgr.rules()[1].target_identity.as_deref(), Some("spk"));
+
+        // The headphones come back under a new id and name.
+        graph.remove_node(3);
+        add_stereo(&mut graph, 7, "Studio Cans", NodeType::Sink);
+        mgr.set_identities(ids(&[(1, "ff"), (2, "mpv"), (7, "cans"), (4, "spk")]));
+        let commands = mgr.scan(&graph);
+        assert!(connects(&commands).starts_with(&[(11, 73), (12, 74)]));
+        graph.apply(&commands);
+        assert!(mgr.scan(&graph).is_empty());
+
+        // A learned rule carries the identities of its nodes.
+        let port = |id| graph.port(id).unwrap().clone();
+        assert!(mgr.learn_from_link(
+            graph.node(2).unwrap(),
+            graph.node(7).unwrap(),
+            &port(21),
+            &port(73)
+        ));
+        let learned = mgr.rules().last().unwrap();
+        assert_eq!(learned.source_identity.as_deref(), Some("mpv"));
+        assert_eq!(learned.target_identity.as_deref(), Some("cans"));
+    }
+
+    #[test]
+    fn test_once_per_appearance() {
+        let mut graph = desk();
+        let mut rule = firefox_to("Headphones");
+        rule.once_per_appearance = true;
+        let mut mgr = PatchbayManager::new();
+        mgr.set_rules(vec![rule]);
+        graph.apply(&mgr.scan(&graph));
+
+        manual_disconnect(&mut mgr, &mut graph, 11, 33);
+        manual_disconnect(&mut mgr, &mut graph, 12, 34);
+        assert_eq!(mgr.rules().len(), 1);
+        assert!(mgr.scan(&graph).is_empty());
+
+        // Firefox goes away and comes back as a new node.
+        graph.remove_node(1);
+        assert!(mgr.scan(&graph).is_empty());
+        add_stereo(&mut graph, 5, "Firefox", NodeType::StreamOutput);
+        assert_eq!(connects(&mgr.scan(&graph)), vec![(51, 33), (52, 34)]);
+    }
+
+    #[test]
+    fn test_override_lasts_until_node_is_gone() {
+        let mut graph = desk();
+        let mut mgr = PatchbayManager::new();
+        mgr.set_rules(vec![firefox_to("Headphones")]);
+        mgr.set_override_mode(OverrideMode::UntilNodeGone);
+        graph.apply(&mgr.scan(&graph));
+
+        manual_disconnect(&mut mgr, &mut graph, 11, 33);
+        assert_eq!(mgr.overrides().len(), 1);
+        assert!(!mgr.overrides_dirty);
+        assert!(mgr.scan(&graph).is_empty());
+
+        graph.remove_node(3);
+        mgr.scan(&graph);
+        assert!(mgr.overrides().is_empty());
+        add_stereo(&mut graph, 3, "Headphones", NodeType::Sink);
+        assert_eq!(connects(&mgr.scan(&graph)), vec![(11, 33), (12, 34)]);
+    }
+
+    #[test]
+    fn test_override_cleared_by_manual_connect() {
+        let mut graph = desk();
+        let mut mgr = PatchbayManager::new();
+        mgr.set_rules(vec![firefox_to("Headphones")]);
+        mgr.set_override_mode(OverrideMode::Permanent);
+        graph.apply(&mgr.scan(&graph));
+
+        manual_disconnect(&mut mgr, &mut graph, 11, 33);
+        assert!(mgr.overrides_dirty);
+        let source = graph.node(1).unwrap().clone();
+        let target = graph.node(3).unwrap().clone();
+        let out_port = graph.port(11).unwrap().clone();
+        let in_port = graph.port(33).unwrap().clone();
+        assert!(mgr.clear_override_for(&source, &target, &out_port, &in_port));
+        assert_eq!(connects(&mgr.scan(&graph)), vec![(11, 33)]);
+    }
+
+    #[test]
+    fn test_simulate_leaves_state_alone() {
+        let graph = desk();
+        let mut rule = firefox_to("Headphones");
+        rule.once_per_appearance = true;
+        let mut mgr = PatchbayManager::new();
+        mgr.set_rules(vec![rule]);
+
+        assert_eq!(connects(&mgr.simulate(&graph)), vec![(11, 33), (12, 34)]);
+        assert!(mgr.applied_once.is_empty());
+        assert_eq!(connects(&mgr.scan(&graph)), vec![(11, 33), (12, 34)]);
+    }
+
+    #[test]
+    fn test_revision_tracks_edits_not_scans() {
+        let mut graph = desk();
+        let mut mgr = PatchbayManager::new();
+        mgr.set_rules(vec![firefox_to("Headphones")]);
+        let revision = mgr.revision();
+
+        let mut copy = mgr.clone();
+        graph.apply(&copy.scan(&graph));
+        assert_eq!(copy.revision(), revision);
+
+        manual_disconnect(&mut mgr, &mut graph, 11, 33);
+        assert_ne!(mgr.revision(), revision);
+    }
+
+    #[test]
+    fn test_owning_rule_prefers_enabled_and_respects_port_mappings() {
+        let graph = desk();
+        let (firefox, headphones) = (graph.node(1).unwrap(), graph.node(3).unwrap());
+        let (out_fl, out_fr) = (graph.port(11).unwrap(), graph.port(12).unwrap());
+        let in_fl = graph.port(33).unwrap();
+
+        let mut disabled = firefox_to("Headphones");
+        disabled.enabled = false;
+        let mut mapped = firefox_to("Head*");
+        mapped.add_port_mapping("output_FL".into(), "playback_FL".into());
+        let mut mgr = PatchbayManager::new();
+        mgr.set_rules(vec![disabled.clone(), mapped.clone()]);
+
+        let owner = |mgr: &PatchbayManager, out| {
+            mgr.owning_rule(firefox, headphones, out, in_fl)
+                .map(|r| r.id.clone())
+        };
+        assert_eq!(owner(&mgr, out_fl), Some(mapped.id.clone()));
+        assert_eq!(owner(&mgr, out_fr), Some(disabled.id.clone()));
+        assert_eq!(mgr.rules_from(firefox).count(), 2);
+        assert_eq!(mgr.rules_from(headphones).count(), 0);
+
+        mgr.remove_rule(&disabled.id);
+        assert_eq!(owner(&mgr, out_fr), None);
+    }
+
+    #[test]
+    fn test_fallbacks_are_used_in_order_until_the_target_returns() {
+        let mut graph = desk();
+        let mut rule = firefox_to("USB DAC");
+        rule.fallback_targets = vec!["Headphones".into(), "Speakers".into()];
+        let id = rule.id.clone();
+        let mut mgr = PatchbayManager::new();
+        mgr.set_rules(vec![rule]);
+
+        let commands = mgr.scan(&graph);
+        assert_eq!(connects(&commands), vec![(11, 33), (12, 34)]);
+        assert_eq!(mgr.active_fallback(&id), Some("Headphones"));
+        graph.apply(&commands);
+        assert!(mgr.scan(&graph).is_empty());
+
+        graph.remove_node(3);
+        let commands = mgr.scan(&graph);
+        assert_eq!(connects(&commands), vec![(11, 43), (12, 44)]);
+        assert_eq!(mgr.active_fallback(&id), Some("Speakers"));
+        graph.apply(&commands);
+
+        // The target is back: the sources move to it and leave the fallback.
+        add_stereo(&mut graph, 5, "USB DAC", NodeType::Sink);
+        let commands = mgr.scan(&graph);
+        assert_eq!(connects(&commands), vec![(11, 53), (12, 54)]);
+        assert_eq!(disconnects(&commands), 2);
+        assert_eq!(mgr.active_fallback(&id), None);
+    }
+
+    #[test]
+    fn test_fallback_links_by_channel_and_belong_to_the_rule() {
+        let mut graph = desk();
+        let mut rule = firefox_to("USB DAC");
+        rule.add_port_mapping("output_FL".into(), "dac_in_1".into());
+        let id = rule.id.clone();
+        let mut mgr = PatchbayManager::new();
+        mgr.set_rules(vec![rule]);
+        assert!(mgr.scan(&graph).is_empty());
+
+        assert!(mgr.set_rule_fallbacks(&id, vec!["Speak*".into()]));
+        let commands = mgr.scan(&graph);
+        assert_eq!(connects(&commands), vec![(11, 43), (12, 44)]);
+        graph.apply(&commands);
+        let (firefox, speakers) = (graph.node(1).unwrap(), graph.node(4).unwrap());
+        let owner = mgr.owning_rule(
+            firefox,
+            speakers,
+            graph.port(12).unwrap(),
+            graph.port(44).unwrap(),
+        );
+        assert_eq!(owner.map(|r| r.id.as_str()), Some(id.as_str()));
+    }
+}
@@ -0,0 +1,191 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::graph::{Node, ObjectId, Port};
+
+/// What a manual disconnect of a rule-owned link does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OverrideMode {
+    /// Unlearn the link from the rule (the original behaviour).
+    #[default]
+    Off,
+    /// Leave the link down until either node leaves the graph.
+    UntilNodeGone,
+    /// Leave the link down for this many minutes.
+    Minutes(u32),
+    /// Leave the link down until the override is cleared by hand.
+    Permanent,
+}
+
+impl OverrideMode {
+    /// Parse the `manual_override_scope` preference.
+    pub fn from_pref(scope: &str, minutes: u32) -> Self {
+        match scope {
+            "node" => Self::UntilNodeGone,
+            "timed" if minutes > 0 => Self::Minutes(minutes),
+            "permanent" => Self::Permanent,
+            _ => Self::Off,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum OverrideScope {
+    /// Until either node goes away. Node ids do not survive a restart, so
+    /// these are never saved.
+    UntilNodeGone {
+        source_id: ObjectId,
+        target_id: ObjectId,
+    },
+    /// Until the given Unix time, in seconds.
+    Until {
+        expires_at: u64,
+    },
+    Permanent,
+}
+
+/// A link the user disconnected by hand that rules must not restore while
+/// the override lasts. Identified by node display name and port name, the
+/// same identity rules use.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LinkOverride {
+    pub source: String,
+    pub output_port: String,
+    pub target: String,
+    pub input_port: String,
+    pub scope: OverrideScope,
+}
+
+pub fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+impl LinkOverride {
+    /// The override `mode` calls for, or None when overrides are off.
+    pub fn new(
+        mode: OverrideMode,
+        source: &Node,
+        output_port: &Port,
+        target: &Node,
+        input_port: &Port,
+    ) -> Option<Self> {
+        let scope = match mode {
+            OverrideMode::Off => return None,
+            OverrideMode::UntilNodeGone => OverrideScope::UntilNodeGone {
+                source_id: source.id,
+                target_id: target.id,
+            },
+            OverrideMode::Minutes(minutes) => OverrideScope::Until {
+                expires_at: unix_now() + u64::from(minutes) * 60,
+            },
+            OverrideMode::Permanent => OverrideScope::Permanent,
+        };
+        Some(Self {
+            source: source.display_name().to_string(),
+            output_port: output_port.name.clone(),
+            target: target.display_name().to_string(),
+            input_port: input_port.name.clone(),
+            scope,
+        })
+    }
+
+    pub fn matches(&self, source: &str, output_port: &str, target: &str, input_port: &str) -> bool {
+        self.source == source
+            && self.output_port == output_port
+            && self.target == target
+            && self.input_port == input_port
+    }
+
+    /// Whether the override still holds, given the node ids in the graph and
+    /// the current Unix time.
+    pub fn is_live(&self, node_exists: impl Fn(ObjectId) -> bool, now: u64) -> bool {
+        match self.scope {
+            OverrideScope::UntilNodeGone {
+                source_id,
+                target_id,
+            } => node_exists(source_id) && node_exists(target_id),
+            OverrideScope::Until { expires_at } => now < expires_at,
+            OverrideScope::Permanent => true,
+        }
+    }
+
+    /// Whether the override should be written to disk.
+    pub fn is_persistent(&self) -> bool {
+        !matches!(self.scope, OverrideScope::UntilNodeGone { .. })
+    }
+
+    pub fn scope_label(&self) -> String {
+        match self.scope {
+            OverrideScope::UntilNodeGone { .. } => "until either node is gone".to_string(),
+            OverrideScope::Until { expires_at } => {
+                let left = expires_at.saturating_sub(unix_now());
+                format!("for {} more min", left.div_ceil(60))
+            }
+            OverrideScope::Permanent => "permanently".to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn link_override(scope: OverrideScope) -> LinkOverride {
+        LinkOverride {
+            source: "Firefox".into(),
+            output_port: "output_FL".into(),
+            target: "Headphones".into(),
+            input_port: "playback_FL".into(),
+            scope,
+        }
+    }
+
+    #[test]
+    fn test_mode_from_pref() {
+        assert_eq!(OverrideMode::from_pref("off", 5), OverrideMode::Off);
+        assert_eq!(
+            OverrideMode::from_pref("node", 5),
+            OverrideMode::UntilNodeGone
+        );
+        assert_eq!(
+            OverrideMode::from_pref("timed", 5),
+            OverrideMode::Minutes(5)
+        );
+        assert_eq!(OverrideMode::from_pref("timed", 0), OverrideMode::Off);
+        assert_eq!(
+            OverrideMode::from_pref("permanent", 0),
+            OverrideMode::Permanent
+        );
+    }
+
+    #[test]
+    fn test_override_lifetime() {
+        let by_node = link_override(OverrideScope::UntilNodeGone {
+            source_id: 1,
+            target_id: 2,
+        });
+        assert!(by_node.is_live(|_| true, 0));
+        assert!(!by_node.is_live(|id| id != 2, 0));
+        assert!(!by_node.is_persistent());
+
+        let timed = link_override(OverrideScope::Until { expires_at: 100 });
+        assert!(timed.is_live(|_| false, 99));
+        assert!(!timed.is_live(|_| true, 100));
+        assert!(timed.is_persistent());
+
+        let permanent = link_override(OverrideScope::Permanent);
+        assert!(permanent.is_live(|_| false, u64::MAX));
+    }
+
+    #[test]
+    fn test_override_matches_port_pair() {
+        let o = link_override(OverrideScope::Permanent);
+        assert!(o.matches("Firefox", "output_FL", "Headphones", "playback_FL"));
+        assert!(!o.matches("Firefox", "output_FR", "Headphones", "playback_FR"));
+    }
+}
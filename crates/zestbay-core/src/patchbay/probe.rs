@@ -0,0 +1,273 @@
+//! "Does sound reach here?": following audio from one node to another and
+//! listening along the way.
+//!
+//! The route is found over the audio links, then a probe node with one
+//! input per tap listens to the outputs of every node on it for a moment
+//! and keeps the peak of each. The first node whose outputs are silent is
+//! where the sound is lost, which answers the usual "is the mic muted, is
+//! the link there, is the plugin passing it on" checklist in one go.
+
+use std::collections::{HashMap, VecDeque};
+use std::time::Duration;
+
+use serde::Serialize;
+
+use super::GraphSnapshot;
+use crate::graph::{MediaType, ObjectId, Port};
+
+/// Node name prefix of the probe node, followed by the probe id.
+pub const PROBE_NODE_PREFIX: &str = "zestbay-probe-";
+
+/// How long the taps are listened to.
+pub const PROBE_LISTEN: Duration = Duration::from_millis(1500);
+
+/// Peaks at or below this count as silence.
+pub const SILENCE_DB: f32 = -80.0;
+
+pub fn probe_node_name(id: u32) -> String {
+    format!("{}{}", PROBE_NODE_PREFIX, id)
+}
+
+pub fn probe_id_of(node_name: &str) -> Option<u32> {
+    node_name.strip_prefix(PROBE_NODE_PREFIX)?.parse().ok()
+}
+
+/// One node on a route and the output ports listened to there.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProbeHop {
+    pub node_id: ObjectId,
+    pub taps: Vec<ObjectId>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum ProbeFinding {
+    /// No audio links lead from the source to the target.
+    NoRoute,
+    /// Nothing comes out of the source itself.
+    SourceSilent,
+    /// Sound reaches the node before `node_id` but nothing comes out of it.
+    #[serde(rename_all = "camelCase")]
+    LostAt { node_id: ObjectId },
+    /// Sound comes out of the target (a sink's monitor, a plugin's outputs).
+    Reaches,
+    /// Sound reaches the target's inputs; it has no outputs to listen to.
+    ReachesInputs,
+}
+
+fn is_audio(port: &Port) -> bool {
+    port.media_type.is_none_or(|m| m == MediaType::Audio)
+}
+
+/// The shortest audio route from `source` to `target`, or `None` when no
+/// links lead there. Every node but the target is tapped at the outputs
+/// that carry the sound on; the target at all its audio outputs, if any.
+pub fn route(graph: &GraphSnapshot, source: ObjectId, target: ObjectId) -> Option<Vec<ProbeHop>> {
+    // (from node, to node, output port) for every audio link.
+    let edges: Vec<(ObjectId, ObjectId, ObjectId)> = graph
+        .links()
+        .iter()
+        .filter(|l| {
+            graph.port(l.output_port_id).is_some_and(is_audio)
+                && graph.port(l.input_port_id).is_some_and(is_audio)
+        })
+        .map(|l| (l.output_node_id, l.input_node_id, l.output_port_id))
+        .collect();
+
+    graph.node(source)?;
+    let mut reached_from: HashMap<ObjectId, ObjectId> = HashMap::new();
+    let mut queue = VecDeque::from([source]);
+    while let Some(node) = queue.pop_front() {
+        if node == target {
+            break;
+        }
+        for &(from, to, _) in &edges {
+            if from == node && to != source && !reached_from.contains_key(&to) {
+                reached_from.insert(to, node);
+                queue.push_back(to);
+            }
+        }
+    }
+
+    let mut path = vec![target];
+    while *path.last()? != source {
+        path.push(*reached_from.get(path.last()?)?);
+    }
+    path.reverse();
+
+    let hops = path
+        .iter()
+        .enumerate()
+        .map(|(i, &node_id)| {
+            let taps = match path.get(i + 1) {
+                Some(&next) => {
+                    let mut taps: Vec<ObjectId> = edges
+                        .iter()
+                        .filter(|&&(from, to, _)| from == node_id && to == next)
+                        .map(|&(_, _, port)| port)
+                        .collect();
+                    taps.sort_unstable();
+                    taps.dedup();
+                    taps
+                }
+                None => graph
+                    .output_ports(node_id)
+                    .iter()
+                    .filter(|p| is_audio(p))
+                    .map(|p| p.id)
+                    .collect(),
+            };
+            ProbeHop { node_id, taps }
+        })
+        .collect();
+    Some(hops)
+}
+
+/// Every tap of the route, in hop order; the probe's inputs follow it.
+pub fn taps(hops: &[ProbeHop]) -> Vec<ObjectId> {
+    hops.iter().flat_map(|h| h.taps.iter().copied()).collect()
+}
+
+/// The loudest peak at each hop, from the peaks in [`taps`] order; `None`
+/// for a hop without taps.
+pub fn hop_levels(hops: &[ProbeHop], peaks_db: &[f32]) -> Vec<Option<f32>> {
+    let mut peaks = peaks_db.iter().copied();
+    hops.iter()
+        .map(|hop| {
+            hop.taps
+                .iter()
+                .filter_map(|_| peaks.next())
+                .reduce(f32::max)
+        })
+        .collect()
+}
+
+/// Where the sound gets to along the route.
+pub fn finding(hops: &[ProbeHop], levels: &[Option<f32>]) -> ProbeFinding {
+    if hops.is_empty() {
+        return ProbeFinding::NoRoute;
+    }
+    let silent = |i: usize| {
+        levels
+            .get(i)
+            .copied()
+            .flatten()
+            .is_some_and(|db| db <= SILENCE_DB)
+    };
+    if silent(0) {
+        return ProbeFinding::SourceSilent;
+    }
+    if let Some(i) = (1..hops.len()).find(|&i| silent(i)) {
+        return ProbeFinding::LostAt {
+            node_id: hops[i].node_id,
+        };
+    }
+    match levels.last() {
+        Some(Some(_)) => ProbeFinding::Reaches,
+        _ => ProbeFinding::ReachesInputs,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{Link, Node, NodeType, PortDirection};
+
+    /// Links port `output` of node `output / 10` to `input`.
+    fn link(graph: &mut GraphSnapshot, id: ObjectId, output: ObjectId, input: ObjectId) {
+        graph.add_link(Link::test(id, (output / 10, output), (input / 10, input)));
+    }
+
+    /// Mic (1) -> EQ (2) -> speakers (3), in stereo from the EQ on, and a
+    /// recorder (4) nothing feeds.
+    fn desk() -> GraphSnapshot {
+        let mut graph = GraphSnapshot::default();
+        for (id, node_type) in [
+            (1, NodeType::Source),
+            (2, NodeType::Plugin),
+            (3, NodeType::Sink),
+            (4, NodeType::StreamInput),
+        ] {
+            graph.add_node(Node::test(id, &format!("node{}", id), node_type));
+        }
+        let ports = [
+            (10, PortDirection::Output),
+            (20, PortDirection::Input),
+            (21, PortDirection::Output),
+            (22, PortDirection::Output),
+            (30, PortDirection::Input),
+            (31, PortDirection::Input),
+            (32, PortDirection::Output),
+            (33, PortDirection::Output),
+            (40, PortDirection::Input),
+        ];
+        for (id, direction) in ports {
+            graph.add_port(Port::test(id, id / 10, &format!("p{}", id), direction));
+        }
+        link(&mut graph, 100, 10, 20);
+        link(&mut graph, 101, 21, 30);
+        link(&mut graph, 102, 22, 31);
+        graph
+    }
+
+    #[test]
+    fn test_route_taps_the_outputs_carrying_the_sound() {
+        let graph = desk();
+        let hops = route(&graph, 1, 3).unwrap();
+        assert_eq!(
+            hops,
+            [
+                ProbeHop {
+                    node_id: 1,
+                    taps: vec![10],
+                },
+                ProbeHop {
+                    node_id: 2,
+                    taps: vec![21, 22],
+                },
+                ProbeHop {
+                    node_id: 3,
+                    taps: vec![32, 33],
+                },
+            ]
+        );
+        assert_eq!(taps(&hops), [10, 21, 22, 32, 33]);
+        assert_eq!(route(&graph, 1, 4), None);
+        assert_eq!(route(&graph, 3, 1), None);
+    }
+
+    #[test]
+    fn test_finding_names_where_the_sound_stops() {
+        let hops = route(&desk(), 1, 3).unwrap();
+        let check = |peaks: &[f32]| finding(&hops, &hop_levels(&hops, peaks));
+        assert_eq!(
+            check(&[-20.0, -18.0, -90.0, -19.0, -19.0]),
+            ProbeFinding::Reaches
+        );
+        assert_eq!(
+            check(&[-120.0, -120.0, -120.0, -120.0, -120.0]),
+            ProbeFinding::SourceSilent
+        );
+        // The EQ passes nothing on.
+        assert_eq!(
+            check(&[-20.0, -100.0, -100.0, -100.0, -100.0]),
+            ProbeFinding::LostAt { node_id: 2 }
+        );
+    }
+
+    #[test]
+    fn test_target_without_outputs_is_judged_by_its_inputs() {
+        let mut graph = desk();
+        link(&mut graph, 103, 21, 40);
+        let hops = route(&graph, 1, 4).unwrap();
+        assert_eq!(hops.last().unwrap().taps, Vec::<ObjectId>::new());
+        let levels = hop_levels(&hops, &[-20.0, -20.0]);
+        assert_eq!(levels, [Some(-20.0), Some(-20.0), None]);
+        assert_eq!(finding(&hops, &levels), ProbeFinding::ReachesInputs);
+        assert_eq!(probe_id_of(&probe_node_name(7)), Some(7));
+        assert_eq!(
+            serde_json::to_value(ProbeFinding::LostAt { node_id: 2 }).unwrap(),
+            serde_json::json!({ "kind": "lostAt", "nodeId": 2 })
+        );
+    }
+}
@@ -0,0 +1,337 @@
+//! Guessing the usual counterpart of a node for one-key quick connect.
+//!
+//! A node with outputs is offered a target, a node with only inputs a
+//! source. Candidates share a media type with the node and are not already
+//! linked to it in that direction. An enabled rule linking the two wins,
+//! then how often the user connected the pair by hand, then the default
+//! target. With none of these there is no proposal.
+
+use serde::{Deserialize, Serialize};
+
+use super::manager::PatchbayManager;
+use super::snapshot::GraphSnapshot;
+use crate::graph::{MediaType, Node, ObjectId, PortDirection, PwCommand};
+
+/// Pairs kept in the history; the least recently used go first.
+pub const HISTORY_LIMIT: usize = 200;
+
+/// A source and target the user connected by hand, keyed by display name
+/// like rules are.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub source: String,
+    pub target: String,
+    pub count: u32,
+    pub last_ms: u64,
+}
+
+/// Manual connections, saved to `connection_history.json`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ConnectionHistory {
+    entries: Vec<HistoryEntry>,
+}
+
+impl ConnectionHistory {
+    pub fn record(&mut self, source: &str, target: &str, now_ms: u64) {
+        if let Some(entry) = self
+            .entries
+            .iter_mut()
+            .find(|e| e.source == source && e.target == target)
+        {
+            entry.count += 1;
+            entry.last_ms = now_ms;
+            return;
+        }
+        if self.entries.len() >= HISTORY_LIMIT
+            && let Some(oldest) = self
+                .entries
+                .iter()
+                .enumerate()
+                .min_by_key(|(_, e)| e.last_ms)
+                .map(|(i, _)| i)
+        {
+            self.entries.remove(oldest);
+        }
+        self.entries.push(HistoryEntry {
+            source: source.to_string(),
+            target: target.to_string(),
+            count: 1,
+            last_ms: now_ms,
+        });
+    }
+
+    pub fn get(&self, source: &str, target: &str) -> Option<&HistoryEntry> {
+        self.entries
+            .iter()
+            .find(|e| e.source == source && e.target == target)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProposalReason {
+    Rule,
+    History,
+    Default,
+}
+
+impl ProposalReason {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ProposalReason::Rule => "rule",
+            ProposalReason::History => "history",
+            ProposalReason::Default => "default",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Proposal {
+    pub node_id: ObjectId,
+    /// Whether the proposed node is the target (the node's outputs go to it)
+    /// or the source.
+    pub direction: PortDirection,
+    pub reason: ProposalReason,
+}
+
+/// The most likely counterpart of `node_id`. `defaults` are names of default
+/// targets, matched against display and node names; they only apply when
+/// proposing a target. Rules come from `patchbay`, if there is one.
+pub fn propose_connection(
+    graph: &GraphSnapshot,
+    node_id: ObjectId,
+    patchbay: Option<&PatchbayManager>,
+    history: &ConnectionHistory,
+    defaults: &[&str],
+) -> Option<Proposal> {
+    let node = graph.node(node_id)?;
+    [PortDirection::Input, PortDirection::Output]
+        .into_iter()
+        .find_map(|direction| propose_in(graph, node, direction, patchbay, history, defaults))
+}
+
+/// Propose a node whose ports face `direction` relative to `node`: a target
+/// for `Input`, a source for `Output`.
+fn propose_in(
+    graph: &GraphSnapshot,
+    node: &Node,
+    direction: PortDirection,
+    patchbay: Option<&PatchbayManager>,
+    history: &ConnectionHistory,
+    defaults: &[&str],
+) -> Option<Proposal> {
+    let own_direction = match direction {
+        PortDirection::Input => PortDirection::Output,
+        PortDirection::Output => PortDirection::Input,
+    };
+    let media = media_types(graph, node.id, own_direction);
+    if media.is_empty() {
+        return None;
+    }
+    graph
+        .nodes()
+        .iter()
+        .filter(|other| other.id != node.id)
+        .filter(|other| {
+            media_types(graph, other.id, direction)
+                .iter()
+                .any(|m| media.contains(m))
+        })
+        .filter(|other| !linked(graph, node, other, direction))
+        .filter_map(|other| {
+            let (source, target) = match direction {
+                PortDirection::Input => (node, other),
+                PortDirection::Output => (other, node),
+            };
+            let rule = patchbay.is_some_and(|p| p.has_rule_linking(source, target));
+            let used = history.get(source.display_name(), target.display_name());
+            let default = direction == PortDirection::Input
+                && defaults
+                    .iter()
+                    .any(|d| *d == other.display_name() || *d == other.name);
+            let reason = if rule {
+                ProposalReason::Rule
+            } else if used.is_some() {
+                ProposalReason::History
+            } else if default {
+                ProposalReason::Default
+            } else {
+                return None;
+            };
+            let score = (
+                rule,
+                used.map_or(0, |e| e.count),
+                used.map_or(0, |e| e.last_ms),
+                default,
+                std::cmp::Reverse(other.id),
+            );
+            Some((score, other.id, reason))
+        })
+        .max_by_key(|(score, _, _)| *score)
+        .map(|(_, node_id, reason)| Proposal {
+            node_id,
+            direction,
+            reason,
+        })
+}
+
+/// Connect commands linking each output of `source` to its matching input
+/// on `target`, skipping links that already exist.
+pub fn plan_connection(
+    graph: &GraphSnapshot,
+    source: ObjectId,
+    target: ObjectId,
+) -> Vec<PwCommand> {
+    let target_ports = graph.input_ports(target);
+    graph
+        .output_ports(source)
+        .iter()
+        .filter_map(|out| {
+            let inp = PatchbayManager::find_matching_port(out, &target_ports)?;
+            graph
+                .find_link(out.id, inp.id)
+                .is_none()
+                .then_some(PwCommand::Connect {
+                    output_port_id: out.id,
+                    input_port_id: inp.id,
+                })
+        })
+        .collect()
+}
+
+fn media_types(
+    graph: &GraphSnapshot,
+    node_id: ObjectId,
+    direction: PortDirection,
+) -> Vec<MediaType> {
+    let ports = match direction {
+        PortDirection::Input => graph.input_ports(node_id),
+        PortDirection::Output => graph.output_ports(node_id),
+    };
+    let mut media: Vec<MediaType> = ports.iter().filter_map(|p| p.media_type).collect();
+    media.dedup();
+    media
+}
+
+/// Whether `node` already feeds `other` (or is fed by it, for `Output`).
+fn linked(graph: &GraphSnapshot, node: &Node, other: &Node, direction: PortDirection) -> bool {
+    graph.links().iter().any(|l| match direction {
+        PortDirection::Input => l.output_node_id == node.id && l.input_node_id == other.id,
+        PortDirection::Output => l.output_node_id == other.id && l.input_node_id == node.id,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{Link, NodeType, Port};
+    use crate::patchbay::rules::AutoConnectRule;
+
+    fn node(graph: &mut GraphSnapshot, id: ObjectId, name: &str, node_type: NodeType) {
+        graph.add_node(Node {
+            description: name.to_string(),
+            ..Node::test(id, &name.to_lowercase(), node_type)
+        });
+        let has_inputs = matches!(node_type, NodeType::Sink | NodeType::StreamInput);
+        let direction = if has_inputs {
+            PortDirection::Input
+        } else {
+            PortDirection::Output
+        };
+        graph.add_port(Port {
+            id: id * 10,
+            node_id: id,
+            name: "FL".into(),
+            direction,
+            media_type: Some(MediaType::Audio),
+            channel: None,
+            physical_index: None,
+            port_group: None,
+            port_alias: None,
+        });
+    }
+
+    fn graph() -> GraphSnapshot {
+        let mut graph = GraphSnapshot::default();
+        node(&mut graph, 1, "Firefox", NodeType::StreamOutput);
+        node(&mut graph, 2, "Speakers", NodeType::Sink);
+        node(&mut graph, 3, "Headphones", NodeType::Sink);
+        node(&mut graph, 4, "Microphone", NodeType::Source);
+        graph
+    }
+
+    #[test]
+    fn test_rule_beats_history_beats_default() {
+        let graph = graph();
+        let mut history = ConnectionHistory::default();
+        let mut patchbay = PatchbayManager::new();
+        let propose = |patchbay: &PatchbayManager, history: &ConnectionHistory| {
+            propose_connection(&graph, 1, Some(patchbay), history, &["speakers"])
+                .map(|p| (p.node_id, p.reason))
+        };
+
+        assert_eq!(
+            propose(&patchbay, &history),
+            Some((2, ProposalReason::Default))
+        );
+        history.record("Firefox", "Headphones", 1000);
+        assert_eq!(
+            propose(&patchbay, &history),
+            Some((3, ProposalReason::History))
+        );
+        patchbay.add_rule(AutoConnectRule::new(
+            "Firefox", None, "Speakers", None, None,
+        ));
+        assert_eq!(
+            propose(&patchbay, &history),
+            Some((2, ProposalReason::Rule))
+        );
+    }
+
+    #[test]
+    fn test_input_only_node_gets_a_source_and_linked_nodes_are_skipped() {
+        let mut graph = graph();
+        let mut history = ConnectionHistory::default();
+        history.record("Microphone", "Headphones", 1000);
+        history.record("Firefox", "Headphones", 2000);
+
+        let proposal = propose_connection(&graph, 3, None, &history, &[]).unwrap();
+        assert_eq!(proposal.node_id, 1);
+        assert_eq!(proposal.direction, PortDirection::Output);
+        assert!(matches!(
+            plan_connection(&graph, 1, 3)[..],
+            [PwCommand::Connect {
+                output_port_id: 10,
+                input_port_id: 30
+            }]
+        ));
+
+        graph.add_link(Link {
+            id: 100,
+            output_node_id: 1,
+            output_port_id: 10,
+            input_node_id: 3,
+            input_port_id: 30,
+            active: true,
+        });
+        let proposal = propose_connection(&graph, 3, None, &history, &[]).unwrap();
+        assert_eq!(proposal.node_id, 4);
+        assert!(plan_connection(&graph, 1, 3).is_empty());
+        assert!(propose_connection(&graph, 2, None, &history, &[]).is_none());
+    }
+
+    #[test]
+    fn test_history_counts_and_evicts_least_recent() {
+        let mut history = ConnectionHistory::default();
+        history.record("a", "b", 1);
+        history.record("a", "b", 5);
+        assert_eq!(
+            history.get("a", "b").map(|e| (e.count, e.last_ms)),
+            Some((2, 5))
+        );
+        for i in 0..HISTORY_LIMIT {
+            history.record("x", &i.to_string(), 10 + i as u64);
+        }
+        assert!(history.get("a", "b").is_none());
+        assert_eq!(history.entries.len(), HISTORY_LIMIT);
+    }
+}
@@ -0,0 +1,84 @@
+use serde::{Deserialize, Serialize};
+
+use super::rules::pattern_matches;
+
+/// Routes application streams by their `media.role` property rather than by
+/// name. Policies only apply to streams that no explicit rule matches, and
+/// take precedence over the default target.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct RolePolicy {
+    /// Role value as reported by the application, e.g. "Music",
+    /// "Communication" or "Notification". Matched case-insensitively.
+    pub role: String,
+    /// Display name (glob allowed) of the sink or plugin to route to.
+    pub target_pattern: String,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+impl RolePolicy {
+    pub fn new(role: impl Into<String>, target_pattern: impl Into<String>) -> Self {
+        Self {
+            role: role.into(),
+            target_pattern: target_pattern.into(),
+            enabled: true,
+        }
+    }
+
+    pub fn matches_role(&self, role: Option<&str>) -> bool {
+        match role {
+            Some(r) => self.enabled && self.role.eq_ignore_ascii_case(r),
+            None => false,
+        }
+    }
+
+    pub fn matches_target(&self, display_name: &str) -> bool {
+        pattern_matches(&self.target_pattern, display_name)
+    }
+}
+
+/// Roles PulseAudio/WirePlumber clients commonly set, offered as suggestions
+/// in the UI.
+pub const KNOWN_ROLES: &[&str] = &[
+    "Music",
+    "Movie",
+    "Game",
+    "Communication",
+    "Notification",
+    "Alarm",
+    "Phone",
+    "Accessibility",
+    "Production",
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_role_matching() {
+        let policy = RolePolicy::new("Communication", "Headset*");
+        assert!(policy.matches_role(Some("Communication")));
+        assert!(policy.matches_role(Some("communication")));
+        assert!(!policy.matches_role(Some("Music")));
+        assert!(!policy.matches_role(None));
+    }
+
+    #[test]
+    fn test_disabled_policy_never_matches() {
+        let mut policy = RolePolicy::new("Music", "Speakers");
+        policy.enabled = false;
+        assert!(!policy.matches_role(Some("Music")));
+    }
+
+    #[test]
+    fn test_target_matching() {
+        let policy = RolePolicy::new("Music", "Speakers*");
+        assert!(policy.matches_target("Speakers (HDMI)"));
+        assert!(!policy.matches_target("Headset"));
+    }
+}
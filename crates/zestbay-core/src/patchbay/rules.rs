@@ -1,6 +1,6 @@
 use serde::{Deserialize, Serialize};
 
-use crate::pipewire::{NodeType, ObjectId};
+use crate::graph::{Node, NodeType, ObjectId};
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub struct PortMapping {
@@ -19,6 +19,29 @@ pub struct AutoConnectRule {
     #[serde(default)]
     pub port_mappings: Vec<PortMapping>,
     pub enabled: bool,
+    /// Seconds to hold the rule off a source after one of its links is
+    /// disconnected by hand, instead of forgetting the link. 0 disables.
+    #[serde(default)]
+    pub cooldown_secs: u64,
+    /// Connect each source only once per appearance: after the rule has
+    /// applied, it is left alone until the source node goes away and comes
+    /// back.
+    #[serde(default)]
+    pub once_per_appearance: bool,
+    /// Target patterns to use, in order, while no node matches the target:
+    /// the first one present gets the sources until the target is back.
+    /// Ports are matched by channel there, as port mappings name the
+    /// target's ports.
+    #[serde(default)]
+    pub fallback_targets: Vec<String>,
+    /// Saved identity of the source node the rule was made for, which
+    /// outlives renames. When set, it decides the match for any node that
+    /// has an identity; the pattern covers the rest.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_identity: Option<String>,
+    /// Saved identity of the target node, as for `source_identity`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target_identity: Option<String>,
 }
 
 impl AutoConnectRule {
@@ -38,6 +61,11 @@ impl AutoConnectRule {
             target_node_id,
             port_mappings: Vec::new(),
             enabled: true,
+            cooldown_secs: 0,
+            once_per_appearance: false,
+            fallback_targets: Vec::new(),
+            source_identity: None,
+            target_identity: None,
         }
     }
 
@@ -54,6 +82,21 @@ impl AutoConnectRule {
         }
     }
 
+    /// Whether the rule links these ports: it maps them, or it maps no
+    /// ports at all and links whatever matches.
+    pub fn maps_ports(&self, output_port_name: &str, input_port_name: &str) -> bool {
+        self.port_mappings.is_empty()
+            || self.port_mappings.iter().any(|m| {
+                m.output_port_name == output_port_name && m.input_port_name == input_port_name
+            })
+    }
+
+    /// Whether disconnecting one of this rule's links by hand pauses the
+    /// rule rather than unlearning the link.
+    pub fn pauses_on_disconnect(&self) -> bool {
+        self.cooldown_secs > 0 || self.once_per_appearance
+    }
+
     pub fn matches_source(&self, display_name: &str, node_type: Option<NodeType>) -> bool {
         if let Some(expected) = self.source_node_type
             && node_type != Some(expected)
@@ -83,6 +126,27 @@ impl AutoConnectRule {
         pattern_matches(&self.target_pattern, display_name)
     }
 
+    /// [`matches_source`](Self::matches_source) for a node whose saved
+    /// identity, if it has one, is `identity`.
+    pub fn matches_source_node(&self, node: &Node, identity: Option<&str>) -> bool {
+        if let (Some(expected), Some(identity)) = (self.source_identity.as_deref(), identity) {
+            return expected == identity;
+        }
+        self.matches_source(node.display_name(), node.node_type)
+    }
+
+    /// [`matches_target`](Self::matches_target) for a node whose saved
+    /// identity, if it has one, is `identity`.
+    pub fn matches_target_node(&self, node: &Node, identity: Option<&str>) -> bool {
+        if self.target_node_id == Some(node.id) {
+            return true;
+        }
+        if let (Some(expected), Some(identity)) = (self.target_identity.as_deref(), identity) {
+            return expected == identity;
+        }
+        self.matches_target(node.display_name(), node.node_type, node.id)
+    }
+
     pub fn target_label(&self) -> String {
         let type_str = self
             .target_node_type
@@ -212,4 +276,29 @@ mod tests {
         assert!(rule.matches_target("Headphones", Some(NodeType::Sink), 99));
         assert!(!rule.matches_target("Headphones", Some(NodeType::Source), 99));
     }
+
+    #[test]
+    fn test_rule_identity_outranks_pattern() {
+        let mut rule = AutoConnectRule::new("Firefox", None, "Headphones", None, None);
+        rule.target_identity = Some("dac".into());
+        let renamed = Node::test(7, "alsa_output.usb-dac", NodeType::Sink);
+        assert!(rule.matches_target_node(&renamed, Some("dac")));
+        assert!(!rule.matches_target_node(&renamed, Some("other")));
+        // Nodes without an identity, such as plugins, fall back to the pattern.
+        assert!(!rule.matches_target_node(&renamed, None));
+        let source = Node::test(8, "Firefox", NodeType::StreamOutput);
+        assert!(rule.matches_source_node(&source, Some("firefox")));
+    }
+
+    #[test]
+    fn test_rule_options_default_when_missing() {
+        let json = r#"{"id":"r1","source_pattern":"Firefox","source_node_type":null,
+            "target_pattern":"Headphones","target_node_type":null,"target_node_id":null,
+            "enabled":true}"#;
+        let rule: AutoConnectRule = serde_json::from_str(json).unwrap();
+        assert_eq!(rule.cooldown_secs, 0);
+        assert!(!rule.once_per_appearance);
+        assert!(!rule.pauses_on_disconnect());
+        assert!(rule.source_identity.is_none() && rule.target_identity.is_none());
+    }
 }
@@ -0,0 +1,157 @@
+//! A plain, owned copy of the graph for the rules engine to plan against.
+//!
+//! [`PatchbayManager`](super::PatchbayManager) never reads the live
+//! [`GraphState`]; callers take a snapshot and pass it in. Tests and dry runs
+//! build snapshots from synthetic nodes, ports and links instead, and
+//! [`GraphSnapshot::apply`] plays planned commands back into the snapshot so
+//! several scans can be simulated in a row.
+
+use crate::graph::state::natural_cmp;
+use crate::graph::{
+    GraphState, Link, MediaType, Node, ObjectId, Port, PortDirection, PwCommand,
+};
+
+#[derive(Debug, Clone, Default)]
+pub struct GraphSnapshot {
+    nodes: Vec<Node>,
+    ports: Vec<Port>,
+    links: Vec<Link>,
+}
+
+impl GraphSnapshot {
+    pub fn new(nodes: Vec<Node>, ports: Vec<Port>, links: Vec<Link>) -> Self {
+        Self {
+            nodes,
+            ports,
+            links,
+        }
+    }
+
+    pub fn from_state(graph: &GraphState) -> Self {
+        Self::new(
+            graph.get_all_nodes(),
+            graph.get_all_ports(),
+            graph.get_all_links(),
+        )
+    }
+
+    pub fn add_node(&mut self, node: Node) {
+        self.nodes.retain(|n| n.id != node.id);
+        self.nodes.push(node);
+    }
+
+    pub fn add_port(&mut self, port: Port) {
+        self.ports.retain(|p| p.id != port.id);
+        self.ports.push(port);
+    }
+
+    pub fn add_link(&mut self, link: Link) {
+        self.links.retain(|l| l.id != link.id);
+        self.links.push(link);
+    }
+
+    /// Remove a node along with its ports and every link touching it, as
+    /// PipeWire does when a device goes away.
+    pub fn remove_node(&mut self, id: ObjectId) {
+        self.nodes.retain(|n| n.id != id);
+        self.ports.retain(|p| p.node_id != id);
+        self.links
+            .retain(|l| l.output_node_id != id && l.input_node_id != id);
+    }
+
+    /// Play connect and disconnect commands back into the snapshot. New links
+    /// get ids above every id in use. Other commands are ignored.
+    pub fn apply(&mut self, commands: &[PwCommand]) {
+        for cmd in commands {
+            match *cmd {
+                PwCommand::Connect {
+                    output_port_id,
+                    input_port_id,
+                } => {
+                    let (Some(out), Some(inp)) =
+                        (self.port(output_port_id), self.port(input_port_id))
+                    else {
+                        continue;
+                    };
+                    if self.find_link(output_port_id, input_port_id).is_some() {
+                        continue;
+                    }
+                    let link = Link {
+                        id: self.next_id(),
+                        output_node_id: out.node_id,
+                        output_port_id,
+                        input_node_id: inp.node_id,
+                        input_port_id,
+                        active: true,
+                    };
+                    self.links.push(link);
+                }
+                PwCommand::Disconnect { link_id } => {
+                    self.links.retain(|l| l.id != link_id);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn next_id(&self) -> ObjectId {
+        let nodes = self.nodes.iter().map(|n| n.id);
+        let ports = self.ports.iter().map(|p| p.id);
+        let links = self.links.iter().map(|l| l.id);
+        nodes.chain(ports).chain(links).max().map_or(1, |id| id + 1)
+    }
+
+    pub fn nodes(&self) -> &[Node] {
+        &self.nodes
+    }
+
+    pub fn ports(&self) -> &[Port] {
+        &self.ports
+    }
+
+    pub fn links(&self) -> &[Link] {
+        &self.links
+    }
+
+    pub fn node(&self, id: ObjectId) -> Option<&Node> {
+        self.nodes.iter().find(|n| n.id == id)
+    }
+
+    pub fn port(&self, id: ObjectId) -> Option<&Port> {
+        self.ports.iter().find(|p| p.id == id)
+    }
+
+    /// Input ports of a node, MIDI first, then in natural name order — the
+    /// same order [`GraphState::get_input_ports`] returns.
+    pub fn input_ports(&self, node_id: ObjectId) -> Vec<Port> {
+        self.ports_of(node_id, PortDirection::Input)
+    }
+
+    /// Output ports of a node, ordered like [`Self::input_ports`].
+    pub fn output_ports(&self, node_id: ObjectId) -> Vec<Port> {
+        self.ports_of(node_id, PortDirection::Output)
+    }
+
+    fn ports_of(&self, node_id: ObjectId, direction: PortDirection) -> Vec<Port> {
+        let mut ports: Vec<Port> = self
+            .ports
+            .iter()
+            .filter(|p| p.node_id == node_id && p.direction == direction)
+            .cloned()
+            .collect();
+        ports.sort_by(|a, b| {
+            let a_midi = a.media_type == Some(MediaType::Midi);
+            let b_midi = b.media_type == Some(MediaType::Midi);
+            b_midi
+                .cmp(&a_midi)
+                .then_with(|| natural_cmp(&a.name, &b.name))
+        });
+        ports
+    }
+
+    pub fn find_link(&self, output_port_id: ObjectId, input_port_id: ObjectId) -> Option<&Link> {
+        self.links
+            .iter()
+            .find(|l| l.output_port_id == output_port_id && l.input_port_id == input_port_id)
+    }
+}
@@ -0,0 +1,138 @@
+//! A circuit breaker for graph event storms.
+//!
+//! A flapping device, such as a Bluetooth headset dropping in and out,
+//! floods the graph with nodes, ports and links coming and going. Applying
+//! rules after each change only adds links to the churn. The guard counts
+//! the changes the rules react to; above a set rate it trips, and rules and
+//! link saving wait until the rate has fallen to half of it.
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// How far back changes are counted.
+pub const STORM_WINDOW: Duration = Duration::from_secs(10);
+
+/// Changes per second, averaged over [`STORM_WINDOW`], that trip the guard
+/// unless the preference says otherwise.
+pub const DEFAULT_STORM_EVENTS_PER_SEC: u32 = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StormChange {
+    Started { events_per_sec: f32 },
+    Ended { lasted: Duration },
+}
+
+#[derive(Debug, Default)]
+pub struct StormGuard {
+    /// When changes were counted, and how many.
+    samples: VecDeque<(Instant, u32)>,
+    total: u32,
+    tripped_at: Option<Instant>,
+}
+
+impl StormGuard {
+    /// Count `events` changes seen at `now`, and trip or reset the guard
+    /// against `limit` changes per second; 0 turns it off. Call it on every
+    /// poll, with 0 when nothing changed, so a storm can end.
+    pub fn record(&mut self, now: Instant, events: u32, limit: u32) -> Option<StormChange> {
+        if events > 0 {
+            self.samples.push_back((now, events));
+            self.total += events;
+        }
+        while let Some(&(at, count)) = self.samples.front() {
+            if now.duration_since(at) < STORM_WINDOW {
+                break;
+            }
+            self.samples.pop_front();
+            self.total -= count;
+        }
+
+        let rate = self.events_per_sec();
+        match self.tripped_at {
+            None if limit > 0 && rate > limit as f32 => {
+                self.tripped_at = Some(now);
+                Some(StormChange::Started {
+                    events_per_sec: rate,
+                })
+            }
+            Some(since) if limit == 0 || rate <= limit as f32 / 2.0 => {
+                self.tripped_at = None;
+                Some(StormChange::Ended {
+                    lasted: now.duration_since(since),
+                })
+            }
+            _ => None,
+        }
+    }
+
+    pub fn is_tripped(&self) -> bool {
+        self.tripped_at.is_some()
+    }
+
+    /// Changes per second over the last [`STORM_WINDOW`].
+    pub fn events_per_sec(&self) -> f32 {
+        self.total as f32 / STORM_WINDOW.as_secs_f32()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_storm_trips_above_the_limit_and_ends_at_half() {
+        let start = Instant::now();
+        let at = |s: f32| start + Duration::from_secs_f32(s);
+        let mut guard = StormGuard::default();
+        // 30 changes a second against a limit of 20.
+        let mut started = None;
+        for tick in 0..100 {
+            if let Some(change) = guard.record(at(tick as f32 * 0.1), 3, 20) {
+                started = Some((tick, change));
+                break;
+            }
+        }
+        let (tick, change) = started.unwrap();
+        assert!(matches!(change, StormChange::Started { events_per_sec } if events_per_sec > 20.0));
+        assert!(guard.is_tripped());
+
+        // Quiet from here: it ends once the window holds 10 a second or less.
+        let quiet_from = tick as f32 * 0.1;
+        let mut ended = None;
+        for step in 1..200 {
+            let now = quiet_from + step as f32 * 0.1;
+            if let Some(change) = guard.record(at(now), 0, 20) {
+                ended = Some((now, change));
+                break;
+            }
+        }
+        let (now, change) = ended.unwrap();
+        assert!(now > quiet_from + 1.0 && now <= quiet_from + STORM_WINDOW.as_secs_f32());
+        assert!(matches!(change, StormChange::Ended { .. }));
+        assert!(!guard.is_tripped());
+    }
+
+    #[test]
+    fn test_a_single_burst_does_not_trip() {
+        let start = Instant::now();
+        let mut guard = StormGuard::default();
+        // A dock unplugged: many changes at once, then nothing.
+        assert_eq!(guard.record(start, 150, 20), None);
+        for s in 1..=20 {
+            assert_eq!(guard.record(start + Duration::from_secs(s), 0, 20), None);
+        }
+        assert_eq!(guard.events_per_sec(), 0.0);
+    }
+
+    #[test]
+    fn test_zero_limit_turns_the_guard_off() {
+        let start = Instant::now();
+        let mut guard = StormGuard::default();
+        assert_eq!(guard.record(start, 1000, 0), None);
+        assert!(guard.record(start, 1000, 20).is_some());
+        assert!(matches!(
+            guard.record(start, 0, 0),
+            Some(StormChange::Ended { .. })
+        ));
+    }
+}
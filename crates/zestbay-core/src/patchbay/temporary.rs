@@ -0,0 +1,291 @@
+//! Temporary routes: a link that takes over an input port for a while and
+//! then puts things back.
+//!
+//! Creating one disconnects whatever else fed the input port, so for "hear
+//! the raw mic for 30 seconds" the processed mic drops out instead of being
+//! mixed in. When the route expires or is cancelled, its link goes and the
+//! displaced links come back. While it lasts, rules must neither remove the
+//! temporary link nor restore the displaced ones; [`TemporaryRoutes::filter`]
+//! drops those commands from a scan.
+
+use std::time::{Duration, Instant};
+
+use super::snapshot::GraphSnapshot;
+use crate::graph::{CommandOrigin, LinkOp, ObjectId, PortDirection, PwCommand};
+
+/// Longest a temporary route may last.
+pub const MAX_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// An (output port, input port) pair.
+pub type PortPair = (ObjectId, ObjectId);
+
+#[derive(Debug, Clone)]
+pub struct TemporaryRoute {
+    pub id: u32,
+    pub link: PortPair,
+    /// Links into the input port that were disconnected to make way.
+    pub displaced: Vec<PortPair>,
+    pub expires: Instant,
+}
+
+#[derive(Debug, Default)]
+pub struct TemporaryRoutes {
+    routes: Vec<TemporaryRoute>,
+    next_id: u32,
+}
+
+impl TemporaryRoutes {
+    /// Plan a temporary link from `output_port_id` to `input_port_id` and
+    /// start its timer. Returns the route id and the link changes to send.
+    pub fn create(
+        &mut self,
+        graph: &GraphSnapshot,
+        output_port_id: ObjectId,
+        input_port_id: ObjectId,
+        ttl: Duration,
+        now: Instant,
+    ) -> Result<(u32, Vec<LinkOp>), String> {
+        let (Some(out), Some(inp)) = (graph.port(output_port_id), graph.port(input_port_id)) else {
+            return Err("port not found".to_string());
+        };
+        if out.direction != PortDirection::Output || inp.direction != PortDirection::Input {
+            return Err("a temporary route goes from an output to an input".to_string());
+        }
+        if ttl.is_zero() || ttl > MAX_TTL {
+            return Err(format!(
+                "a temporary route lasts between 1 and {} seconds",
+                MAX_TTL.as_secs()
+            ));
+        }
+        if self.routes.iter().any(|r| r.link.1 == input_port_id) {
+            return Err("the input already has a temporary route".to_string());
+        }
+
+        let mut ops = Vec::new();
+        if graph.find_link(output_port_id, input_port_id).is_none() {
+            ops.push(LinkOp::Connect {
+                output_port_id,
+                input_port_id,
+            });
+        }
+        let mut displaced = Vec::new();
+        for link in graph.links() {
+            if link.input_port_id == input_port_id && link.output_port_id != output_port_id {
+                displaced.push((link.output_port_id, link.input_port_id));
+                ops.push(LinkOp::Disconnect { link_id: link.id });
+            }
+        }
+
+        self.next_id += 1;
+        let id = self.next_id;
+        self.routes.push(TemporaryRoute {
+            id,
+            link: (output_port_id, input_port_id),
+            displaced,
+            expires: now + ttl,
+        });
+        Ok((id, ops))
+    }
+
+    pub fn routes(&self) -> &[TemporaryRoute] {
+        &self.routes
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.routes.is_empty()
+    }
+
+    /// End the route `id` early.
+    pub fn cancel(&mut self, id: u32) -> Option<TemporaryRoute> {
+        let index = self.routes.iter().position(|r| r.id == id)?;
+        Some(self.routes.remove(index))
+    }
+
+    /// Remove and return the routes whose time is up.
+    pub fn take_expired(&mut self, now: Instant) -> Vec<TemporaryRoute> {
+        let (expired, live) = std::mem::take(&mut self.routes)
+            .into_iter()
+            .partition(|r| r.expires <= now);
+        self.routes = live;
+        expired
+    }
+
+    /// Drop rule commands that would remove a temporary link or restore a
+    /// link one displaced.
+    pub fn filter(
+        &self,
+        graph: &GraphSnapshot,
+        commands: Vec<(PwCommand, CommandOrigin)>,
+    ) -> Vec<(PwCommand, CommandOrigin)> {
+        if self.routes.is_empty() {
+            return commands;
+        }
+        commands
+            .into_iter()
+            .filter(|(cmd, _)| match *cmd {
+                PwCommand::Connect {
+                    output_port_id,
+                    input_port_id,
+                } => !self
+                    .routes
+                    .iter()
+                    .any(|r| r.displaced.contains(&(output_port_id, input_port_id))),
+                PwCommand::Disconnect { link_id } => !graph.links().iter().any(|l| {
+                    l.id == link_id
+                        && self
+                            .routes
+                            .iter()
+                            .any(|r| r.link == (l.output_port_id, l.input_port_id))
+                }),
+                _ => true,
+            })
+            .collect()
+    }
+}
+
+impl TemporaryRoute {
+    /// The link changes that undo the route: its link goes and the displaced
+    /// links whose ports are still there come back.
+    pub fn plan_revert(&self, graph: &GraphSnapshot) -> Vec<LinkOp> {
+        let mut ops: Vec<LinkOp> = self
+            .displaced
+            .iter()
+            .filter(|(out, inp)| {
+                graph.port(*out).is_some()
+                    && graph.port(*inp).is_some()
+                    && graph.find_link(*out, *inp).is_none()
+            })
+            .map(|&(output_port_id, input_port_id)| LinkOp::Connect {
+                output_port_id,
+                input_port_id,
+            })
+            .collect();
+        if let Some(link) = graph.find_link(self.link.0, self.link.1) {
+            ops.push(LinkOp::Disconnect { link_id: link.id });
+        }
+        ops
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{Link, Port};
+
+    fn port(graph: &mut GraphSnapshot, id: ObjectId, direction: PortDirection) {
+        graph.add_port(Port::test(id, id / 10, &format!("p{}", id), direction));
+    }
+
+    fn link(graph: &mut GraphSnapshot, id: ObjectId, out: ObjectId, inp: ObjectId) {
+        graph.add_link(Link::test(id, (out / 10, out), (inp / 10, inp)));
+    }
+
+    /// Mic (port 10) -> plugin (20 in, 21 out) -> headphones (30).
+    fn monitor() -> GraphSnapshot {
+        let mut graph = GraphSnapshot::default();
+        port(&mut graph, 10, PortDirection::Output);
+        port(&mut graph, 20, PortDirection::Input);
+        port(&mut graph, 21, PortDirection::Output);
+        port(&mut graph, 30, PortDirection::Input);
+        link(&mut graph, 100, 10, 20);
+        link(&mut graph, 101, 21, 30);
+        graph
+    }
+
+    #[test]
+    fn test_route_displaces_and_reverts() {
+        let mut graph = monitor();
+        let mut routes = TemporaryRoutes::default();
+        let now = Instant::now();
+        let (id, ops) = routes
+            .create(&graph, 10, 30, Duration::from_secs(30), now)
+            .unwrap();
+        assert_eq!(
+            ops,
+            [
+                LinkOp::Connect {
+                    output_port_id: 10,
+                    input_port_id: 30
+                },
+                LinkOp::Disconnect { link_id: 101 },
+            ]
+        );
+
+        graph.apply(&[
+            PwCommand::Disconnect { link_id: 101 },
+            PwCommand::Connect {
+                output_port_id: 10,
+                input_port_id: 30,
+            },
+        ]);
+        let temp = graph.find_link(10, 30).unwrap().id;
+        assert!(
+            routes
+                .take_expired(now + Duration::from_secs(29))
+                .is_empty()
+        );
+        let expired = routes.take_expired(now + Duration::from_secs(30));
+        assert_eq!(expired.len(), 1);
+        assert_eq!(expired[0].id, id);
+        assert_eq!(
+            expired[0].plan_revert(&graph),
+            [
+                LinkOp::Connect {
+                    output_port_id: 21,
+                    input_port_id: 30
+                },
+                LinkOp::Disconnect { link_id: temp },
+            ]
+        );
+        assert!(routes.is_empty());
+    }
+
+    #[test]
+    fn test_rules_leave_the_route_alone() {
+        let mut graph = monitor();
+        let mut routes = TemporaryRoutes::default();
+        routes
+            .create(&graph, 10, 30, Duration::from_secs(30), Instant::now())
+            .unwrap();
+        graph.apply(&[
+            PwCommand::Disconnect { link_id: 101 },
+            PwCommand::Connect {
+                output_port_id: 10,
+                input_port_id: 30,
+            },
+        ]);
+        let temp = graph.find_link(10, 30).unwrap().id;
+
+        let scan = vec![
+            (PwCommand::Disconnect { link_id: temp }, CommandOrigin::User),
+            (
+                PwCommand::Connect {
+                    output_port_id: 21,
+                    input_port_id: 30,
+                },
+                CommandOrigin::User,
+            ),
+            (PwCommand::Disconnect { link_id: 100 }, CommandOrigin::User),
+        ];
+        let kept = routes.filter(&graph, scan);
+        assert!(matches!(
+            kept[..],
+            [(PwCommand::Disconnect { link_id: 100 }, _)]
+        ));
+    }
+
+    #[test]
+    fn test_create_checks_its_arguments() {
+        let graph = monitor();
+        let mut routes = TemporaryRoutes::default();
+        let now = Instant::now();
+        let ttl = Duration::from_secs(30);
+        assert!(routes.create(&graph, 30, 10, ttl, now).is_err());
+        assert!(routes.create(&graph, 10, 99, ttl, now).is_err());
+        assert!(routes.create(&graph, 10, 30, Duration::ZERO, now).is_err());
+        let (id, _) = routes.create(&graph, 10, 30, ttl, now).unwrap();
+        assert!(routes.create(&graph, 21, 30, ttl, now).is_err());
+        assert!(routes.cancel(id).is_some());
+        assert!(routes.cancel(id).is_none());
+    }
+}
@@ -0,0 +1,167 @@
+//! Scene transitions: ordered steps run when a scene is switched to.
+//!
+//! Switching a scene swaps the whole rule set at once, and whatever is
+//! playing can pop or briefly go somewhere it should not. A scene can carry
+//! a transition instead: steps such as muting the speakers, switching the
+//! links, unbypassing a chain and unmuting again, each after its own delay.
+//! Nodes and plugins are named by display name, like in rules.
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+/// What one step of a transition does.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "camelCase")]
+pub enum SceneAction {
+    Mute {
+        node: String,
+    },
+    Unmute {
+        node: String,
+    },
+    /// Load the scene's rules and apply them.
+    SwitchLinks,
+    Bypass {
+        plugin: String,
+    },
+    Unbypass {
+        plugin: String,
+    },
+    /// Make a sink the default output, crossfading when that is on.
+    DefaultOutput {
+        node: String,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransitionStep {
+    /// Wait after the previous step, or after the switch started for the
+    /// first one.
+    #[serde(default)]
+    pub delay_ms: u32,
+    #[serde(flatten)]
+    pub action: SceneAction,
+}
+
+impl TransitionStep {
+    pub fn new(delay_ms: u32, action: SceneAction) -> Self {
+        Self { delay_ms, action }
+    }
+}
+
+/// `steps` with a [`SceneAction::SwitchLinks`] step at the end if they have
+/// none, so a transition always switches the rules.
+pub fn with_switch(mut steps: Vec<TransitionStep>) -> Vec<TransitionStep> {
+    if !steps.iter().any(|s| s.action == SceneAction::SwitchLinks) {
+        steps.push(TransitionStep::new(0, SceneAction::SwitchLinks));
+    }
+    steps
+}
+
+/// A transition being run. Each step's delay counts from when the step
+/// before it actually ran, so a late poll never squeezes two steps together.
+#[derive(Debug)]
+pub struct TransitionRunner {
+    steps: VecDeque<TransitionStep>,
+    due: Instant,
+}
+
+impl TransitionRunner {
+    pub fn new(steps: Vec<TransitionStep>, now: Instant) -> Self {
+        let steps = VecDeque::from(with_switch(steps));
+        let due = now + Duration::from_millis(steps[0].delay_ms as u64);
+        Self { steps, due }
+    }
+
+    /// The actions due at `now`, in order. Steps after one that has just run
+    /// only follow in the same call when their delay is zero.
+    pub fn take_due(&mut self, now: Instant) -> Vec<SceneAction> {
+        let mut actions = Vec::new();
+        while now >= self.due {
+            let Some(step) = self.steps.pop_front() else {
+                break;
+            };
+            actions.push(step.action);
+            if let Some(next) = self.steps.front() {
+                self.due = now + Duration::from_millis(next.delay_ms as u64);
+            }
+        }
+        actions
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.steps.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mute(node: &str) -> SceneAction {
+        SceneAction::Mute { node: node.into() }
+    }
+
+    #[test]
+    fn test_steps_run_in_order_after_their_delays() {
+        let start = Instant::now();
+        let ms = |n| start + Duration::from_millis(n);
+        let mut runner = TransitionRunner::new(
+            vec![
+                TransitionStep::new(0, mute("Speakers")),
+                TransitionStep::new(100, SceneAction::SwitchLinks),
+                TransitionStep::new(
+                    0,
+                    SceneAction::Unbypass {
+                        plugin: "Reverb".into(),
+                    },
+                ),
+                TransitionStep::new(
+                    200,
+                    SceneAction::Unmute {
+                        node: "Speakers".into(),
+                    },
+                ),
+            ],
+            start,
+        );
+        assert_eq!(runner.take_due(start), [mute("Speakers")]);
+        assert!(runner.take_due(ms(50)).is_empty());
+        // Late by 100 ms: the unmute still waits its 200 ms after the switch.
+        assert_eq!(runner.take_due(ms(200)).len(), 2);
+        assert!(runner.take_due(ms(350)).is_empty());
+        assert_eq!(
+            runner.take_due(ms(400)),
+            [SceneAction::Unmute {
+                node: "Speakers".into()
+            }]
+        );
+        assert!(runner.is_done());
+    }
+
+    #[test]
+    fn test_transition_always_switches_links() {
+        let steps = with_switch(vec![TransitionStep::new(50, mute("Speakers"))]);
+        assert_eq!(steps.last().unwrap().action, SceneAction::SwitchLinks);
+        let again = with_switch(steps.clone());
+        assert_eq!(again, steps);
+    }
+
+    #[test]
+    fn test_steps_round_trip_as_json() {
+        let json = r#"[{"action":"mute","node":"Speakers","delayMs":0},
+            {"action":"switchLinks","delayMs":150},
+            {"action":"defaultOutput","node":"Headphones"}]"#;
+        let steps: Vec<TransitionStep> = serde_json::from_str(json).unwrap();
+        assert_eq!(steps[1], TransitionStep::new(150, SceneAction::SwitchLinks));
+        assert_eq!(steps[2].delay_ms, 0);
+        let back = serde_json::to_string(&steps).unwrap();
+        assert_eq!(
+            serde_json::from_str::<Vec<TransitionStep>>(&back).unwrap(),
+            steps
+        );
+    }
+}
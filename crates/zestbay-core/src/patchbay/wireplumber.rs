@@ -0,0 +1,303 @@
+//! One-shot import of the stream targets WirePlumber has remembered.
+//!
+//! WirePlumber keeps the sink or source each application was last moved to
+//! in a key file under `$XDG_STATE_HOME/wireplumber` (`restore-stream`, or
+//! `stream-properties` in newer releases). Keys name the stream by one of
+//! its properties and values carry the target's `node.name`:
+//!
+//! ```text
+//! [restore-stream]
+//! Output/Audio:application.name:Firefox={"volume":1.0, "target":"alsa_output.usb-Headset"}
+//! Output/Audio:media.role:Music={"target":"alsa_output.pci-0000_00_1f.3.analog-stereo"}
+//! ```
+//!
+//! Application entries become [`AutoConnectRule`]s and role entries become
+//! [`RolePolicy`]s. Rules match display names while WirePlumber stores
+//! `node.name`s, so targets are resolved against the nodes currently in the
+//! graph and entries whose target is not present are skipped.
+
+use std::path::{Path, PathBuf};
+
+use super::roles::RolePolicy;
+use super::rules::AutoConnectRule;
+use crate::graph::{Node, NodeType};
+
+/// State files WirePlumber stores stream targets in, newest first.
+const STATE_FILES: &[&str] = &["stream-properties", "restore-stream"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamDirection {
+    /// A playback stream, routed to a sink.
+    Output,
+    /// A capture stream, fed from a source.
+    Input,
+}
+
+/// One remembered target: streams whose `match_key` property equals
+/// `match_value` go to (or record from) the node named `target`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StreamTarget {
+    pub direction: StreamDirection,
+    pub match_key: String,
+    pub match_value: String,
+    pub target: String,
+}
+
+/// Rules and policies generated from WirePlumber's state.
+#[derive(Debug, Default)]
+pub struct WirePlumberImport {
+    pub rules: Vec<AutoConnectRule>,
+    pub role_policies: Vec<RolePolicy>,
+    /// Descriptions of entries that could not be turned into rules.
+    pub skipped: Vec<String>,
+}
+
+/// WirePlumber's state directory.
+pub fn state_dir() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_STATE_HOME")
+        .map(PathBuf::from)
+        .filter(|p| p.is_absolute())
+        .or_else(|| dirs::home_dir().map(|h| h.join(".local/state")))?;
+    Some(base.join("wireplumber"))
+}
+
+/// The target node name in a stored value: a JSON object with a `target`
+/// field, a JSON string, or the bare name older releases wrote.
+fn parse_target(value: &str) -> Option<String> {
+    let value = value.trim();
+    let target = match serde_json::from_str::<serde_json::Value>(value) {
+        Ok(serde_json::Value::Object(map)) => map.get("target")?.as_str()?.to_string(),
+        Ok(serde_json::Value::String(s)) => s,
+        Ok(_) => return None,
+        Err(_) => value.to_string(),
+    };
+    (!target.is_empty() && target != "-1").then_some(target)
+}
+
+/// Read the stream targets from the text of a WirePlumber state file.
+pub fn parse_state_file(text: &str) -> Vec<StreamTarget> {
+    let mut targets = Vec::new();
+    for line in text.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with('[') {
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let mut parts = key.splitn(3, ':');
+        let (Some(kind), Some(match_key), Some(rest)) = (parts.next(), parts.next(), parts.next())
+        else {
+            continue;
+        };
+        let direction = match kind {
+            "Output/Audio" => StreamDirection::Output,
+            "Input/Audio" => StreamDirection::Input,
+            _ => continue,
+        };
+        // Older releases stored each property under its own key, e.g.
+        // `...:application.name:Firefox:target.node=alsa_output...`.
+        let (match_value, legacy) = match rest.rsplit_once(':') {
+            Some((v, field)) if field.starts_with("target") => (v, true),
+            _ => (rest, false),
+        };
+        if !legacy && !value.trim_start().starts_with('{') {
+            continue;
+        }
+        if let Some(target) = parse_target(value) {
+            targets.push(StreamTarget {
+                direction,
+                match_key: match_key.to_string(),
+                match_value: match_value.to_string(),
+                target,
+            });
+        }
+    }
+    targets
+}
+
+/// Read every stream target stored in `dir`. Entries in newer files win.
+pub fn read_state_dir(dir: &Path) -> Result<Vec<StreamTarget>, String> {
+    let mut targets: Vec<StreamTarget> = Vec::new();
+    let mut found = false;
+    for name in STATE_FILES {
+        let Ok(text) = std::fs::read_to_string(dir.join(name)) else {
+            continue;
+        };
+        found = true;
+        for entry in parse_state_file(&text) {
+            let known = targets.iter().any(|t| {
+                t.direction == entry.direction
+                    && t.match_key == entry.match_key
+                    && t.match_value == entry.match_value
+            });
+            if !known {
+                targets.push(entry);
+            }
+        }
+    }
+    if !found {
+        return Err(format!("no WirePlumber state found in {:?}", dir));
+    }
+    Ok(targets)
+}
+
+/// Turn stored targets into rules and role policies for the nodes in
+/// `nodes`.
+pub fn convert(targets: &[StreamTarget], nodes: &[Node]) -> WirePlumberImport {
+    let mut import = WirePlumberImport::default();
+    for entry in targets {
+        let wanted = match entry.direction {
+            StreamDirection::Output => NodeType::Sink,
+            StreamDirection::Input => NodeType::Source,
+        };
+        let Some(target) = nodes
+            .iter()
+            .find(|n| n.name == entry.target && n.node_type == Some(wanted))
+        else {
+            import.skipped.push(format!(
+                "{} ({} not present)",
+                entry.match_value, entry.target
+            ));
+            continue;
+        };
+
+        if entry.match_key == "media.role" {
+            if entry.direction == StreamDirection::Output {
+                import
+                    .role_policies
+                    .push(RolePolicy::new(&entry.match_value, target.display_name()));
+            } else {
+                import
+                    .skipped
+                    .push(format!("{} (capture role)", entry.match_value));
+            }
+            continue;
+        }
+
+        // Stream display names are often the media title rather than the
+        // application, so take the name from a running stream if there is
+        // one and fall back to the stored value.
+        let stream_type = match entry.direction {
+            StreamDirection::Output => NodeType::StreamOutput,
+            StreamDirection::Input => NodeType::StreamInput,
+        };
+        let stream_name = nodes
+            .iter()
+            .filter(|n| n.node_type == Some(stream_type))
+            .find(|n| match entry.match_key.as_str() {
+                "application.name" => {
+                    n.application_name.as_deref() == Some(entry.match_value.as_str())
+                }
+                "node.name" => n.name == entry.match_value,
+                _ => false,
+            })
+            .map(|n| n.display_name().to_string())
+            .unwrap_or_else(|| entry.match_value.clone());
+
+        let rule = match entry.direction {
+            StreamDirection::Output => AutoConnectRule::new(
+                stream_name,
+                Some(stream_type),
+                target.display_name(),
+                Some(NodeType::Sink),
+                None,
+            ),
+            StreamDirection::Input => AutoConnectRule::new(
+                target.display_name(),
+                Some(NodeType::Source),
+                stream_name,
+                Some(stream_type),
+                None,
+            ),
+        };
+        import.rules.push(rule);
+    }
+    import
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(name: &str, description: &str, node_type: NodeType) -> Node {
+        Node {
+            description: description.into(),
+            media_type: None,
+            ..Node::test(0, name, node_type)
+        }
+    }
+
+    #[test]
+    fn test_parse_state_file() {
+        let text = "[restore-stream]\n\
+            Output/Audio:application.name:Firefox={\"volume\":1.0, \"target\":\"alsa_output.usb\"}\n\
+            Output/Audio:media.role:Music={\"mute\":false}\n\
+            Input/Audio:application.name:OBS:target.node=alsa_input.usb\n\
+            Output/Video:application.name:Cheese={\"target\":\"v4l2\"}\n";
+        let targets = parse_state_file(text);
+        assert_eq!(
+            targets,
+            vec![
+                StreamTarget {
+                    direction: StreamDirection::Output,
+                    match_key: "application.name".into(),
+                    match_value: "Firefox".into(),
+                    target: "alsa_output.usb".into(),
+                },
+                StreamTarget {
+                    direction: StreamDirection::Input,
+                    match_key: "application.name".into(),
+                    match_value: "OBS".into(),
+                    target: "alsa_input.usb".into(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_convert_resolves_targets() {
+        let nodes = vec![
+            node("alsa_output.usb", "USB Headset", NodeType::Sink),
+            node("alsa_input.usb", "USB Microphone", NodeType::Source),
+        ];
+        let targets = vec![
+            StreamTarget {
+                direction: StreamDirection::Output,
+                match_key: "application.name".into(),
+                match_value: "Firefox".into(),
+                target: "alsa_output.usb".into(),
+            },
+            StreamTarget {
+                direction: StreamDirection::Input,
+                match_key: "application.name".into(),
+                match_value: "OBS".into(),
+                target: "alsa_input.usb".into(),
+            },
+            StreamTarget {
+                direction: StreamDirection::Output,
+                match_key: "media.role".into(),
+                match_value: "Music".into(),
+                target: "alsa_output.usb".into(),
+            },
+            StreamTarget {
+                direction: StreamDirection::Output,
+                match_key: "application.name".into(),
+                match_value: "mpv".into(),
+                target: "alsa_output.hdmi".into(),
+            },
+        ];
+        let import = convert(&targets, &nodes);
+
+        assert_eq!(import.rules.len(), 2);
+        assert_eq!(import.rules[0].source_pattern, "Firefox");
+        assert_eq!(import.rules[0].target_pattern, "USB Headset");
+        assert_eq!(import.rules[1].source_pattern, "USB Microphone");
+        assert_eq!(import.rules[1].target_pattern, "OBS");
+        assert_eq!(
+            import.role_policies,
+            vec![RolePolicy::new("Music", "USB Headset")]
+        );
+        assert_eq!(import.skipped.len(), 1);
+    }
+}
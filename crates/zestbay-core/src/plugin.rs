@@ -0,0 +1,28 @@
+//! Format-agnostic plugin types and the plugin catalog.
+//!
+//! The LV2, CLAP and VST3 backends live in the application and feed into
+//! the unified [`PluginManager`].
+
+pub mod chain_file;
+pub mod compat;
+pub mod cpu_history;
+pub mod cv;
+pub mod docs;
+pub mod formats;
+pub mod manager;
+pub mod meter;
+pub mod param_file;
+pub mod preset;
+pub mod quantum;
+pub mod quick_params;
+pub mod randomize;
+pub mod replace;
+pub mod snippet;
+pub mod state;
+pub mod suppression;
+pub mod tail;
+pub mod types;
+pub mod ui_geometry;
+
+pub use manager::PluginManager;
+pub use types::*;
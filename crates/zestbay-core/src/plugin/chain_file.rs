@@ -0,0 +1,190 @@
+//! `.zestchain` files: a plugin chain to share with other users.
+//!
+//! A chain file is a [`PluginSnippet`] with a name and a list of the plugins
+//! it needs, so a missing plugin can be reported by name and author before
+//! anything is created.
+
+use serde::{Deserialize, Serialize};
+
+use super::snippet::{PluginSnippet, SNIPPET_KIND, SNIPPET_VERSION, SnippetLink, SnippetPlugin};
+use super::types::{PluginFormat, PluginInfo};
+
+pub const CHAIN_FILE_EXTENSION: &str = "zestchain";
+pub const CHAIN_FILE_KIND: &str = "zestbay-chain";
+pub const CHAIN_FILE_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChainFile {
+    pub kind: String,
+    pub version: u32,
+    pub name: String,
+    pub requires: Vec<RequiredPlugin>,
+    pub plugins: Vec<SnippetPlugin>,
+    #[serde(default)]
+    pub links: Vec<SnippetLink>,
+}
+
+/// A plugin the chain uses, described well enough to find and install it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RequiredPlugin {
+    pub uri: String,
+    pub format: PluginFormat,
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+}
+
+impl ChainFile {
+    /// Wrap `snippet` as a chain named `name`, listing each plugin it uses
+    /// once with its name and author from `catalog`.
+    pub fn new(name: &str, snippet: PluginSnippet, catalog: &[PluginInfo]) -> Self {
+        let mut requires: Vec<RequiredPlugin> = Vec::new();
+        for plugin in &snippet.plugins {
+            if requires
+                .iter()
+                .any(|r| r.uri == plugin.uri && r.format == plugin.format)
+            {
+                continue;
+            }
+            let info = catalog
+                .iter()
+                .find(|p| p.uri == plugin.uri && p.format == plugin.format);
+            requires.push(RequiredPlugin {
+                uri: plugin.uri.clone(),
+                format: plugin.format,
+                name: info.map_or_else(|| plugin.name.clone(), |p| p.name.clone()),
+                author: info.and_then(|p| p.author.clone()),
+            });
+        }
+        Self {
+            kind: CHAIN_FILE_KIND.to_string(),
+            version: CHAIN_FILE_VERSION,
+            name: name.to_string(),
+            requires,
+            plugins: snippet.plugins,
+            links: snippet.links,
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let chain: Self =
+            serde_json::from_str(text).map_err(|e| format!("not a chain file: {}", e))?;
+        if chain.kind != CHAIN_FILE_KIND {
+            return Err(format!("not a chain file (kind '{}')", chain.kind));
+        }
+        if chain.version > CHAIN_FILE_VERSION {
+            return Err(format!(
+                "chain file version {} is newer than this build understands",
+                chain.version
+            ));
+        }
+        chain.snippet().validate()?;
+        Ok(chain)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+
+    /// The chain's plugins and wiring, to create them like a paste.
+    pub fn snippet(&self) -> PluginSnippet {
+        PluginSnippet {
+            kind: SNIPPET_KIND.to_string(),
+            version: SNIPPET_VERSION,
+            plugins: self.plugins.clone(),
+            links: self.links.clone(),
+        }
+    }
+
+    /// Required plugins that are not in `catalog`.
+    pub fn missing(&self, catalog: &[PluginInfo]) -> Vec<&RequiredPlugin> {
+        self.requires
+            .iter()
+            .filter(|r| {
+                !catalog
+                    .iter()
+                    .any(|p| p.uri == r.uri && p.format == r.format)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plugin::snippet::SnippetParameter;
+
+    fn plugin(uri: &str, name: &str) -> SnippetPlugin {
+        SnippetPlugin {
+            uri: uri.to_string(),
+            format: PluginFormat::Lv2,
+            name: name.to_string(),
+            bypassed: false,
+            oversampling: 1,
+            parameters: vec![SnippetParameter {
+                symbol: "gain".to_string(),
+                name: "Gain".to_string(),
+                value: 2.0,
+            }],
+            state: Vec::new(),
+        }
+    }
+
+    fn catalog_entry(uri: &str, name: &str) -> PluginInfo {
+        PluginInfo {
+            author: Some("Someone".into()),
+            control_inputs: 1,
+            ..PluginInfo::test(uri, name)
+        }
+    }
+
+    fn mic_chain() -> PluginSnippet {
+        PluginSnippet {
+            kind: SNIPPET_KIND.to_string(),
+            version: SNIPPET_VERSION,
+            plugins: vec![
+                plugin("urn:gate", "Gate"),
+                plugin("urn:comp", "Comp L"),
+                plugin("urn:comp", "Comp R"),
+            ],
+            links: vec![SnippetLink {
+                from: 0,
+                output_port: "output_0".to_string(),
+                to: 1,
+                input_port: "input_0".to_string(),
+            }],
+        }
+    }
+
+    #[test]
+    fn test_requires_lists_each_plugin_once() {
+        let catalog = [catalog_entry("urn:gate", "Noise Gate")];
+        let chain = ChainFile::new("Mic", mic_chain(), &catalog);
+
+        assert_eq!(chain.requires.len(), 2);
+        assert_eq!(chain.requires[0].name, "Noise Gate");
+        assert_eq!(chain.requires[0].author.as_deref(), Some("Someone"));
+        assert_eq!(chain.requires[1].name, "Comp L");
+        assert_eq!(ChainFile::parse(&chain.to_json()).unwrap(), chain);
+    }
+
+    #[test]
+    fn test_missing_reports_plugins_not_in_catalog() {
+        let catalog = [catalog_entry("urn:gate", "Noise Gate")];
+        let chain = ChainFile::new("Mic", mic_chain(), &catalog);
+
+        let missing = chain.missing(&catalog);
+        assert_eq!(missing.len(), 1);
+        assert_eq!(missing[0].uri, "urn:comp");
+        assert_eq!(chain.missing(&[]).len(), 2);
+    }
+
+    #[test]
+    fn test_parse_rejects_snippets_and_broken_links() {
+        assert!(ChainFile::parse(&mic_chain().to_json()).is_err());
+
+        let mut chain = ChainFile::new("Mic", mic_chain(), &[]);
+        chain.links[0].to = 9;
+        assert!(ChainFile::parse(&chain.to_json()).is_err());
+    }
+}
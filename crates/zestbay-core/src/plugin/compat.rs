@@ -0,0 +1,208 @@
+//! Why a plugin is marked incompatible, in terms a user can act on.
+
+use serde::Serialize;
+
+use super::types::{PluginFormat, PluginInfo};
+
+/// One reason a plugin cannot be used, or may not work.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum CompatIssue {
+    /// LV2 host features the plugin requires and ZestBay does not provide.
+    #[serde(rename_all = "camelCase")]
+    MissingFeatures { features: Vec<String> },
+    /// Ports of a kind ZestBay cannot connect, such as the deprecated LV2
+    /// event port, listed by symbol.
+    #[serde(rename_all = "camelCase")]
+    UnsupportedPorts { ports: Vec<String> },
+    /// No audio ports and no MIDI input, so there is nothing to route
+    /// through it; usually a UI-only or control-only plugin.
+    NothingToRoute,
+    /// Creating an instance failed this session.
+    #[serde(rename_all = "camelCase")]
+    InstantiationFailed { message: String },
+}
+
+impl CompatIssue {
+    /// Whether the plugin cannot be added at all because of this issue.
+    pub fn is_blocking(&self) -> bool {
+        matches!(
+            self,
+            Self::MissingFeatures { .. } | Self::UnsupportedPorts { .. }
+        )
+    }
+
+    /// Whether installing or reinstalling something on this system could
+    /// fix it. Missing host features and port kinds need ZestBay itself to
+    /// support them, whatever else is installed.
+    pub fn fixable_by_user(&self) -> bool {
+        matches!(self, Self::InstantiationFailed { .. })
+    }
+
+    pub fn summary(&self) -> String {
+        match self {
+            Self::MissingFeatures { features } => {
+                let names: Vec<&str> = features.iter().map(|f| feature_name(f)).collect();
+                format!("Requires host features ZestBay lacks: {}", names.join(", "))
+            }
+            Self::UnsupportedPorts { ports } => {
+                format!("Has ports ZestBay cannot connect: {}", ports.join(", "))
+            }
+            Self::NothingToRoute => "Has no audio or MIDI ports to connect".to_string(),
+            Self::InstantiationFailed { message } => format!("Failed to load: {}", message),
+        }
+    }
+
+    /// What the user can do about it.
+    pub fn advice(&self) -> &'static str {
+        match self {
+            Self::MissingFeatures { .. } => {
+                "These are provided by the host, not by separate packages, so installing \
+                 libraries will not help. The plugin needs a ZestBay version that supports them."
+            }
+            Self::UnsupportedPorts { .. } => {
+                "The plugin uses a port type ZestBay does not host. A newer build of the \
+                 plugin may have replaced it with atom ports."
+            }
+            Self::NothingToRoute => {
+                "It can be added, but does nothing in the graph. It is probably meant to \
+                 be used through its own UI or inside another plugin."
+            }
+            Self::InstantiationFailed { .. } => {
+                "Often a missing library or resource file. Reinstalling the plugin or \
+                 installing its dependencies may fix it; the log has details."
+            }
+        }
+    }
+}
+
+/// The short name of an LV2 feature URI, e.g. `worker#schedule` for
+/// `http://lv2plug.in/ns/ext/worker#schedule`.
+pub fn feature_name(uri: &str) -> &str {
+    let trimmed = uri.trim_end_matches('/');
+    let start = trimmed.rfind('/').map_or(0, |i| i + 1);
+    &trimmed[start..]
+}
+
+/// Everything known to be wrong with `plugin`. `provided_features` are the
+/// LV2 features the host offers; `failure` is the error from the last
+/// attempt to instantiate it, if that failed.
+pub fn issues(
+    plugin: &PluginInfo,
+    provided_features: &[&str],
+    failure: Option<&str>,
+) -> Vec<CompatIssue> {
+    let mut issues = Vec::new();
+
+    let missing: Vec<String> = plugin
+        .required_features
+        .iter()
+        .filter(|f| !provided_features.contains(&f.as_str()))
+        .cloned()
+        .collect();
+    if !missing.is_empty() {
+        issues.push(CompatIssue::MissingFeatures { features: missing });
+    }
+    if !plugin.unsupported_ports.is_empty() {
+        issues.push(CompatIssue::UnsupportedPorts {
+            ports: plugin.unsupported_ports.clone(),
+        });
+    }
+
+    // Only LV2 port lists are known before instantiation.
+    let has_midi_in = plugin
+        .ports
+        .iter()
+        .any(|p| p.port_type == super::types::PluginPortType::AtomInput);
+    if plugin.format == PluginFormat::Lv2
+        && plugin.audio_inputs == 0
+        && plugin.audio_outputs == 0
+        && !has_midi_in
+    {
+        issues.push(CompatIssue::NothingToRoute);
+    }
+
+    if let Some(message) = failure {
+        issues.push(CompatIssue::InstantiationFailed {
+            message: message.to_string(),
+        });
+    }
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plugin::types::PluginCategory;
+
+    const PROVIDED: &[&str] = &["http://lv2plug.in/ns/ext/urid#map"];
+
+    fn lv2_plugin(required: &[&str], audio: usize) -> PluginInfo {
+        PluginInfo {
+            uri: "urn:test".into(),
+            name: "Test".into(),
+            format: PluginFormat::Lv2,
+            category: PluginCategory::from_class_label(""),
+            author: None,
+            ports: Vec::new(),
+            audio_inputs: audio,
+            audio_outputs: audio,
+            control_inputs: 0,
+            control_outputs: 0,
+            required_features: required.iter().map(|s| s.to_string()).collect(),
+            compatible: true,
+            has_ui: false,
+            library_path: String::new(),
+            version: None,
+            modified: None,
+            unsupported_ports: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_missing_features_block_and_are_not_user_fixable() {
+        let plugin = lv2_plugin(
+            &[
+                "http://lv2plug.in/ns/ext/urid#map",
+                "http://lv2plug.in/ns/ext/time#Position",
+            ],
+            2,
+        );
+        let found = issues(&plugin, PROVIDED, None);
+        assert_eq!(
+            found,
+            vec![CompatIssue::MissingFeatures {
+                features: vec!["http://lv2plug.in/ns/ext/time#Position".into()]
+            }]
+        );
+        assert!(found[0].is_blocking());
+        assert!(!found[0].fixable_by_user());
+        assert!(found[0].summary().ends_with("time#Position"));
+    }
+
+    #[test]
+    fn test_portless_plugin_and_load_failure() {
+        let plugin = lv2_plugin(&[], 0);
+        let found = issues(&plugin, PROVIDED, Some("libfoo.so: not found"));
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0], CompatIssue::NothingToRoute);
+        assert!(!found.iter().any(CompatIssue::is_blocking));
+        assert!(found[1].fixable_by_user());
+
+        let json = serde_json::to_value(&found[1]).unwrap();
+        assert_eq!(json["kind"], "instantiationFailed");
+        assert_eq!(json["message"], "libfoo.so: not found");
+    }
+
+    #[test]
+    fn test_feature_name() {
+        assert_eq!(
+            feature_name("http://lv2plug.in/ns/ext/worker#schedule"),
+            "worker#schedule"
+        );
+        assert_eq!(
+            feature_name("http://lv2plug.in/ns/ext/data-access"),
+            "data-access"
+        );
+    }
+}
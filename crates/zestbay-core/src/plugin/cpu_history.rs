@@ -0,0 +1,198 @@
+//! Rolling per-plugin DSP load history, and alerts when a plugin stays over
+//! a limit for a while.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use serde::{Deserialize, Serialize};
+
+/// Samples kept per plugin.
+pub const HISTORY_LEN: usize = 1200;
+
+/// An alert ends once load drops below this fraction of the threshold, so a
+/// plugin hovering around the limit is not reported over and over.
+pub const RELEASE_RATIO: f32 = 0.8;
+
+/// One measurement window of a plugin's load.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CpuSample {
+    /// End of the window, in milliseconds since the Unix epoch.
+    pub time_ms: u64,
+    /// Real-time thread load, as a percentage of the buffer budget.
+    pub dsp_percent: f32,
+    /// Worker thread time, as a percentage of the buffer budget.
+    pub worker_percent: f32,
+}
+
+/// Recent samples per plugin stable id, oldest first. Keyed by stable id so
+/// it survives restarts and can be saved with the session.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct CpuHistory {
+    plugins: HashMap<String, VecDeque<CpuSample>>,
+}
+
+impl CpuHistory {
+    /// Append a sample, dropping the oldest past [`HISTORY_LEN`].
+    pub fn record(&mut self, stable_id: &str, sample: CpuSample) {
+        let samples = self.plugins.entry(stable_id.to_string()).or_default();
+        if samples.len() == HISTORY_LEN {
+            samples.pop_front();
+        }
+        samples.push_back(sample);
+    }
+
+    pub fn samples(&self, stable_id: &str) -> Option<&VecDeque<CpuSample>> {
+        self.plugins.get(stable_id)
+    }
+
+    pub fn forget(&mut self, stable_id: &str) {
+        self.plugins.remove(stable_id);
+    }
+
+    /// Drop the history of plugins `keep` rejects, e.g. ones that no longer
+    /// exist.
+    pub fn retain(&mut self, keep: impl Fn(&str) -> bool) {
+        self.plugins.retain(|id, _| keep(id));
+    }
+
+    /// Whether `stable_id` has been at or above `percent` for at least
+    /// `window_ms` up to its latest sample. A gap longer than the window,
+    /// such as a restart, breaks the run.
+    pub fn sustained_above(&self, stable_id: &str, percent: f32, window_ms: u64) -> bool {
+        let Some(samples) = self.plugins.get(stable_id) else {
+            return false;
+        };
+        let Some(latest) = samples.back() else {
+            return false;
+        };
+        let mut start = latest.time_ms;
+        for sample in samples.iter().rev() {
+            if sample.dsp_percent < percent || start.saturating_sub(sample.time_ms) > window_ms {
+                break;
+            }
+            start = sample.time_ms;
+            if latest.time_ms - start >= window_ms {
+                return true;
+            }
+        }
+        false
+    }
+}
+
+/// Plugins currently over their alert threshold, so each overload is
+/// reported once.
+#[derive(Debug, Clone, Default)]
+pub struct CpuAlerts {
+    active: HashSet<String>,
+}
+
+impl CpuAlerts {
+    /// Check `stable_id` against its history after a new sample. Returns
+    /// `true` when it has just been over `percent` for `window_ms`. A
+    /// threshold of 0 turns alerts off.
+    pub fn update(
+        &mut self,
+        history: &CpuHistory,
+        stable_id: &str,
+        percent: f32,
+        window_ms: u64,
+    ) -> bool {
+        if percent <= 0.0 {
+            self.active.clear();
+            return false;
+        }
+        if self.active.contains(stable_id) {
+            let latest = history
+                .samples(stable_id)
+                .and_then(|s| s.back())
+                .map_or(0.0, |s| s.dsp_percent);
+            if latest < percent * RELEASE_RATIO {
+                self.active.remove(stable_id);
+            }
+            return false;
+        }
+        if history.sustained_above(stable_id, percent, window_ms) {
+            self.active.insert(stable_id.to_string());
+            return true;
+        }
+        false
+    }
+
+    pub fn is_active(&self, stable_id: &str) -> bool {
+        self.active.contains(stable_id)
+    }
+
+    pub fn forget(&mut self, stable_id: &str) {
+        self.active.remove(stable_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(time_ms: u64, dsp_percent: f32) -> CpuSample {
+        CpuSample {
+            time_ms,
+            dsp_percent,
+            worker_percent: 0.0,
+        }
+    }
+
+    #[test]
+    fn test_history_is_capped_and_serializes_by_stable_id() {
+        let mut history = CpuHistory::default();
+        for i in 0..HISTORY_LEN as u64 + 10 {
+            history.record("eq", sample(i * 500, 1.0));
+        }
+        let samples = history.samples("eq").unwrap();
+        assert_eq!(samples.len(), HISTORY_LEN);
+        assert_eq!(samples.front().unwrap().time_ms, 5000);
+
+        let mut small = CpuHistory::default();
+        small.record("eq", sample(1000, 12.5));
+        let json = serde_json::to_value(&small).unwrap();
+        assert_eq!(json["eq"][0]["dspPercent"], 12.5);
+        let back: CpuHistory = serde_json::from_value(json).unwrap();
+        assert_eq!(back.samples("eq").unwrap()[0], sample(1000, 12.5));
+    }
+
+    #[test]
+    fn test_sustained_needs_the_whole_window() {
+        let mut history = CpuHistory::default();
+        history.record("eq", sample(0, 10.0));
+        for t in 1..=4 {
+            history.record("eq", sample(t * 1000, 90.0));
+        }
+        assert!(!history.sustained_above("eq", 80.0, 5000));
+        assert!(history.sustained_above("eq", 80.0, 3000));
+
+        history.record("eq", sample(20_000, 95.0));
+        assert!(!history.sustained_above("eq", 80.0, 3000));
+        assert!(!history.sustained_above("missing", 80.0, 3000));
+    }
+
+    #[test]
+    fn test_alert_fires_once_until_load_drops() {
+        let mut history = CpuHistory::default();
+        let mut alerts = CpuAlerts::default();
+        let mut fired = 0;
+        for t in 0..10 {
+            history.record("eq", sample(t * 1000, 90.0));
+            fired += alerts.update(&history, "eq", 80.0, 3000) as u32;
+        }
+        assert_eq!(fired, 1);
+        assert!(alerts.is_active("eq"));
+
+        history.record("eq", sample(10_000, 75.0));
+        assert!(!alerts.update(&history, "eq", 80.0, 3000));
+        assert!(alerts.is_active("eq"));
+
+        history.record("eq", sample(11_000, 10.0));
+        assert!(!alerts.update(&history, "eq", 80.0, 3000));
+        assert!(!alerts.is_active("eq"));
+
+        assert!(!alerts.update(&history, "eq", 0.0, 3000));
+    }
+}
@@ -0,0 +1,71 @@
+//! CV (control voltage) ports: control signals at audio rate.
+//!
+//! An LV2 `lv2:CVPort` carries a float per sample like an audio port, but
+//! the samples are a control value (a pitch, an envelope, an LFO) rather
+//! than sound. Plugin filter nodes put these ports in the
+//! [`CV_PORT_GROUP`](super::types::CV_PORT_GROUP) group, so the graph shows
+//! them as their own media and only links them to other CV ports.
+//!
+//! CV is not resampled like audio when a plugin is oversampled; a control
+//! signal should not ring. Each input sample is held for the extra frames,
+//! and every `factor`-th output frame is kept.
+
+/// Stretch the first `frames` samples of `buf` in place to
+/// `frames * factor`, holding each sample for `factor` frames.
+pub fn hold_in_place(buf: &mut [f32], frames: usize, factor: usize) {
+    if factor < 2 {
+        return;
+    }
+    let high = (frames * factor).min(buf.len());
+    // Backwards, so each sample is read before anything is written over it.
+    for i in (0..high).rev() {
+        buf[i] = buf[i / factor];
+    }
+}
+
+/// Shrink `frames * factor` samples of `buf` in place to `frames`, keeping
+/// every `factor`-th sample.
+pub fn pick_in_place(buf: &mut [f32], frames: usize, factor: usize) {
+    if factor < 2 {
+        return;
+    }
+    let frames = frames.min(buf.len().div_ceil(factor));
+    for i in 0..frames {
+        buf[i] = buf[i * factor];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_held_samples_repeat_factor_times() {
+        let mut buf = [1.0, 2.0, 3.0, 0.0, 0.0, 0.0, 0.0, 0.0];
+        hold_in_place(&mut buf, 3, 2);
+        assert_eq!(buf, [1.0, 1.0, 2.0, 2.0, 3.0, 3.0, 0.0, 0.0]);
+        hold_in_place(&mut buf, 2, 4);
+        assert_eq!(buf, [1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0]);
+    }
+
+    #[test]
+    fn test_picking_undoes_holding() {
+        let mut buf = [0.5, -0.25, 0.75, 1.0, 0.0, 0.0, 0.0, 0.0];
+        hold_in_place(&mut buf, 2, 4);
+        pick_in_place(&mut buf, 2, 4);
+        assert_eq!(buf[..2], [0.5, -0.25]);
+    }
+
+    #[test]
+    fn test_factor_one_and_short_buffers_are_left_alone() {
+        let mut buf = [1.0, 2.0, 3.0];
+        hold_in_place(&mut buf, 3, 1);
+        pick_in_place(&mut buf, 3, 1);
+        assert_eq!(buf, [1.0, 2.0, 3.0]);
+        // Only as much as fits is stretched.
+        hold_in_place(&mut buf, 3, 2);
+        assert_eq!(buf, [1.0, 1.0, 2.0]);
+        pick_in_place(&mut buf, 3, 2);
+        assert_eq!(buf, [1.0, 2.0, 2.0]);
+    }
+}
@@ -0,0 +1,122 @@
+//! Parameter documentation: descriptions, units and labelled values taken
+//! from plugin metadata, for tooltips in the parameter window.
+
+use serde::{Deserialize, Serialize};
+
+/// Stepped parameters with more steps than this get no per-step labels.
+pub const MAX_LABELLED_STEPS: usize = 32;
+
+/// A labelled value of a control port (LV2 `lv2:scalePoint`, or the text a
+/// CLAP or VST3 plugin gives for one step of a stepped parameter).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScalePoint {
+    pub value: f32,
+    pub label: String,
+}
+
+/// What a parameter does. LV2 plugins describe their ports in their bundle,
+/// so this is filled at scan time; CLAP and VST3 only report units and step
+/// labels once an instance is created.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ParamDoc {
+    /// Free-text description (LV2 `rdfs:comment`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    /// Unit symbol such as `dB` or `Hz`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unit: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub scale_points: Vec<ScalePoint>,
+}
+
+impl ParamDoc {
+    pub fn is_empty(&self) -> bool {
+        self.comment.is_none() && self.unit.is_none() && self.scale_points.is_empty()
+    }
+
+    /// The label of the scale point at `value`, if there is one.
+    pub fn label_for(&self, value: f32) -> Option<&str> {
+        self.scale_points
+            .iter()
+            .find(|p| (p.value - value).abs() < 1e-4)
+            .map(|p| p.label.as_str())
+    }
+}
+
+/// The unit symbol for one of the units defined by the LV2 units extension,
+/// for hosts whose LV2 installation lacks the extension's own data.
+pub fn lv2_unit_symbol(uri: &str) -> Option<&'static str> {
+    let name = uri.strip_prefix("http://lv2plug.in/ns/extensions/units#")?;
+    Some(match name {
+        "bar" => "bars",
+        "beat" => "beats",
+        "bpm" => "BPM",
+        "cent" => "ct",
+        "cm" => "cm",
+        "coef" => "",
+        "db" => "dB",
+        "degree" => "°",
+        "frame" => "frames",
+        "hz" => "Hz",
+        "inch" => "in",
+        "khz" => "kHz",
+        "km" => "km",
+        "m" => "m",
+        "mhz" => "MHz",
+        "midiNote" => "note",
+        "mile" => "mi",
+        "min" => "min",
+        "mm" => "mm",
+        "ms" => "ms",
+        "oct" => "oct",
+        "pc" => "%",
+        "s" => "s",
+        "semitone12TET" => "semi",
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lv2_units() {
+        assert_eq!(
+            lv2_unit_symbol("http://lv2plug.in/ns/extensions/units#db"),
+            Some("dB")
+        );
+        assert_eq!(
+            lv2_unit_symbol("http://lv2plug.in/ns/extensions/units#pc"),
+            Some("%")
+        );
+        assert_eq!(
+            lv2_unit_symbol("http://lv2plug.in/ns/extensions/units#furlong"),
+            None
+        );
+        assert_eq!(lv2_unit_symbol("http://example.org/units#db"), None);
+    }
+
+    #[test]
+    fn test_scale_point_lookup_and_serialization() {
+        let doc = ParamDoc {
+            comment: None,
+            unit: None,
+            scale_points: vec![
+                ScalePoint {
+                    value: 0.0,
+                    label: "Low Pass".into(),
+                },
+                ScalePoint {
+                    value: 1.0,
+                    label: "High Pass".into(),
+                },
+            ],
+        };
+        assert_eq!(doc.label_for(1.0), Some("High Pass"));
+        assert_eq!(doc.label_for(0.5), None);
+
+        assert!(ParamDoc::default().is_empty());
+        assert_eq!(serde_json::to_string(&ParamDoc::default()).unwrap(), "{}");
+    }
+}
@@ -0,0 +1,209 @@
+//! One plugin installed in several formats.
+//!
+//! Many plugins ship as LV2, CLAP and VST3 at once and would otherwise be
+//! listed three times. Versions are matched by name and vendor, ignoring
+//! case, punctuation and format tags such as "(LV2)", and the catalog shows
+//! one of them, picked by the user's format order.
+
+use std::collections::HashMap;
+
+use super::types::{PluginFormat, PluginInfo};
+
+/// Format order used when the preference is unset: LV2 first, since it
+/// has the most complete support here.
+pub const DEFAULT_FORMAT_ORDER: [PluginFormat; 4] = [
+    PluginFormat::Lv2,
+    PluginFormat::Clap,
+    PluginFormat::Vst3,
+    PluginFormat::Builtin,
+];
+
+/// Parse a comma-separated format order such as `"clap,lv2"`. Unknown names
+/// are skipped and formats left out follow in the default order.
+pub fn parse_format_order(pref: &str) -> Vec<PluginFormat> {
+    let mut order: Vec<PluginFormat> = Vec::new();
+    for name in pref.split(',') {
+        if let Some(format) = PluginFormat::parse(name.trim())
+            && !order.contains(&format)
+        {
+            order.push(format);
+        }
+    }
+    for format in DEFAULT_FORMAT_ORDER {
+        if !order.contains(&format) {
+            order.push(format);
+        }
+    }
+    order
+}
+
+/// The preference string for `order`, as read by [`parse_format_order`].
+pub fn format_order_pref(order: &[PluginFormat]) -> String {
+    order
+        .iter()
+        .map(|f| f.as_str().to_lowercase())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// What versions of the same plugin have in common: the name without a
+/// format tag, and the vendor.
+pub fn identity_key(info: &PluginInfo) -> String {
+    let mut name = info.name.to_lowercase();
+    for tag in ["lv2", "clap", "vst3", "vst"] {
+        let trimmed = name.trim_end();
+        let stripped = trimmed
+            .strip_suffix(&format!("({})", tag))
+            .or_else(|| trimmed.strip_suffix(&format!("[{}]", tag)))
+            .or_else(|| {
+                trimmed
+                    .strip_suffix(tag)
+                    .filter(|rest| rest.ends_with([' ', '-', '_']))
+            });
+        if let Some(stripped) = stripped {
+            name = stripped.to_string();
+        }
+    }
+    let vendor = info.author.as_deref().unwrap_or("").to_lowercase();
+    let squash = |s: &str| {
+        s.chars()
+            .filter(|c| c.is_alphanumeric())
+            .collect::<String>()
+    };
+    format!("{}|{}", squash(&name), squash(&vendor))
+}
+
+/// The catalog with one version per plugin: a compatible one if there is
+/// any, in the first format of `order` it comes in. Plugins sharing a name
+/// within one format are all kept. Catalog order is preserved.
+pub fn preferred_versions<'a>(
+    plugins: &'a [PluginInfo],
+    order: &[PluginFormat],
+) -> Vec<&'a PluginInfo> {
+    let rank = |format: PluginFormat| {
+        order
+            .iter()
+            .position(|f| *f == format)
+            .unwrap_or(order.len())
+    };
+    let keys: Vec<String> = plugins.iter().map(identity_key).collect();
+
+    let mut best: HashMap<&str, (bool, usize, PluginFormat)> = HashMap::new();
+    for (plugin, key) in plugins.iter().zip(&keys) {
+        let candidate = (!plugin.compatible, rank(plugin.format), plugin.format);
+        best.entry(key)
+            .and_modify(|b| {
+                if (candidate.0, candidate.1) < (b.0, b.1) {
+                    *b = candidate;
+                }
+            })
+            .or_insert(candidate);
+    }
+
+    plugins
+        .iter()
+        .zip(&keys)
+        .filter(|(plugin, key)| best[key.as_str()].2 == plugin.format)
+        .map(|(plugin, _)| plugin)
+        .collect()
+}
+
+/// The other formats `info` is installed in.
+pub fn other_versions<'a>(
+    plugins: &'a [PluginInfo],
+    info: &PluginInfo,
+) -> impl Iterator<Item = &'a PluginInfo> {
+    let key = identity_key(info);
+    let format = info.format;
+    plugins
+        .iter()
+        .filter(move |p| p.format != format && identity_key(p) == key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plugin::types::PluginCategory;
+
+    fn entry(uri: &str, name: &str, vendor: &str, format: PluginFormat) -> PluginInfo {
+        PluginInfo {
+            uri: uri.into(),
+            name: name.into(),
+            format,
+            category: PluginCategory::from_class_label(""),
+            author: Some(vendor.into()),
+            ports: Vec::new(),
+            audio_inputs: 2,
+            audio_outputs: 2,
+            control_inputs: 1,
+            control_outputs: 0,
+            required_features: Vec::new(),
+            compatible: true,
+            has_ui: false,
+            library_path: String::new(),
+            version: None,
+            modified: None,
+            unsupported_ports: Vec::new(),
+        }
+    }
+
+    fn catalog() -> Vec<PluginInfo> {
+        vec![
+            entry("urn:eq", "Pro EQ", "Acme", PluginFormat::Lv2),
+            entry("acme.eq", "Pro EQ (CLAP)", "ACME", PluginFormat::Clap),
+            entry("ABCD", "Pro-EQ VST3", "Acme", PluginFormat::Vst3),
+            entry("urn:eq-other", "Pro EQ", "Someone Else", PluginFormat::Lv2),
+            entry("urn:gate", "Gate", "Acme", PluginFormat::Lv2),
+        ]
+    }
+
+    fn uris(plugins: &[&PluginInfo]) -> Vec<String> {
+        plugins.iter().map(|p| p.uri.clone()).collect()
+    }
+
+    #[test]
+    fn test_one_version_per_plugin_by_format_order() {
+        let plugins = catalog();
+        let order = parse_format_order("clap");
+        assert_eq!(
+            uris(&preferred_versions(&plugins, &order)),
+            ["acme.eq", "urn:eq-other", "urn:gate"]
+        );
+        let order = parse_format_order("vst3,lv2");
+        assert_eq!(
+            uris(&preferred_versions(&plugins, &order)),
+            ["ABCD", "urn:eq-other", "urn:gate"]
+        );
+        let others: Vec<_> = other_versions(&plugins, &plugins[0])
+            .map(|p| p.format)
+            .collect();
+        assert_eq!(others, [PluginFormat::Clap, PluginFormat::Vst3]);
+    }
+
+    #[test]
+    fn test_incompatible_versions_lose() {
+        let mut plugins = catalog();
+        plugins[1].compatible = false;
+        let order = parse_format_order("clap");
+        assert_eq!(
+            uris(&preferred_versions(&plugins, &order))[0],
+            "urn:eq".to_string()
+        );
+    }
+
+    #[test]
+    fn test_format_order_pref_round_trip() {
+        let order = parse_format_order(" VST3 , bogus, clap,vst3");
+        assert_eq!(
+            order,
+            [
+                PluginFormat::Vst3,
+                PluginFormat::Clap,
+                PluginFormat::Lv2,
+                PluginFormat::Builtin
+            ]
+        );
+        assert_eq!(parse_format_order(&format_order_pref(&order)), order);
+        assert_eq!(parse_format_order(""), DEFAULT_FORMAT_ORDER);
+    }
+}
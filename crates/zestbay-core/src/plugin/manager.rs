@@ -0,0 +1,572 @@
+//! Unified plugin manager that wraps format-specific backends (LV2, CLAP, VST3).
+//!
+//! This is the single entry-point that the UI and PipeWire layers talk to for
+//! plugin catalog queries, instance registration, and parameter bookkeeping.
+
+use std::collections::HashMap;
+
+use super::chain_file::ChainFile;
+use super::compat::{self, CompatIssue};
+use super::formats;
+use super::preset::{self, PluginPreset};
+use super::snippet::{PluginSnippet, SnippetPlugin};
+use super::types::*;
+use crate::graph::PwCommand;
+
+/// A saved plugin chain ("FX rack"): its plugins in signal order with their
+/// parameters and the links between them, inserted onto a link as a unit.
+/// Kept in the `.zestchain` format, so a preset and a shared chain are the
+/// same thing.
+pub type ChainPreset = ChainFile;
+
+/// A unified manager holding the catalog of available plugins (from all
+/// formats) and the registry of active plugin instances.
+pub struct PluginManager {
+    /// All available plugins, merged from all format-specific scanners.
+    available_plugins: Vec<PluginInfo>,
+    /// Currently active instances, keyed by instance ID.
+    active_instances: HashMap<PluginInstanceId, PluginInstanceInfo>,
+    /// Parameter docs reported by CLAP and VST3 instances, keyed by plugin
+    /// URI then port index. LV2 docs are on the catalog's ports.
+    param_docs: HashMap<String, HashMap<usize, ParamDoc>>,
+    /// Why the last instance of a plugin failed to load, keyed by URI.
+    load_failures: HashMap<String, String>,
+    /// Which format's version of a plugin the catalog lists, best first.
+    format_order: Vec<PluginFormat>,
+    /// List every format a plugin is installed in, not just the preferred.
+    show_all_versions: bool,
+    /// Saved presets, keyed by plugin URI, sorted by name.
+    presets: HashMap<String, Vec<PluginPreset>>,
+    /// Saved plugin chains, sorted by name.
+    chain_presets: Vec<ChainPreset>,
+    /// The sample rate reported by PipeWire (set after PW init).
+    pub sample_rate: f64,
+}
+
+impl Default for PluginManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PluginManager {
+    pub fn new() -> Self {
+        Self {
+            available_plugins: Vec::new(),
+            active_instances: HashMap::new(),
+            param_docs: HashMap::new(),
+            load_failures: HashMap::new(),
+            format_order: formats::DEFAULT_FORMAT_ORDER.to_vec(),
+            show_all_versions: false,
+            presets: HashMap::new(),
+            chain_presets: Vec::new(),
+            sample_rate: 48000.0,
+        }
+    }
+
+    // ----- Catalog -----
+
+    /// Replace the entire catalog (called after scanning all formats).
+    pub fn set_available_plugins(&mut self, plugins: Vec<PluginInfo>) {
+        self.available_plugins = plugins;
+    }
+
+    /// Append additional plugins (e.g. after a single-format rescan).
+    pub fn extend_available_plugins(&mut self, plugins: Vec<PluginInfo>) {
+        self.available_plugins.extend(plugins);
+    }
+
+    /// Sort the catalog alphabetically by name (case-insensitive).
+    pub fn sort_catalog(&mut self) {
+        self.available_plugins
+            .sort_by_key(|a| a.name.to_lowercase());
+    }
+
+    pub fn available_plugins(&self) -> &[PluginInfo] {
+        &self.available_plugins
+    }
+
+    /// The plugins to offer in the browser: one version of each unless
+    /// every version is shown.
+    pub fn catalog(&self) -> Vec<&PluginInfo> {
+        if self.show_all_versions {
+            self.available_plugins.iter().collect()
+        } else {
+            formats::preferred_versions(&self.available_plugins, &self.format_order)
+        }
+    }
+
+    /// The formats other than its own that `info` is installed in.
+    pub fn other_formats(&self, info: &PluginInfo) -> Vec<PluginFormat> {
+        let mut other = Vec::new();
+        for version in formats::other_versions(&self.available_plugins, info) {
+            if !other.contains(&version.format) {
+                other.push(version.format);
+            }
+        }
+        other
+    }
+
+    pub fn set_format_order(&mut self, order: Vec<PluginFormat>) {
+        self.format_order = order;
+    }
+
+    pub fn set_show_all_versions(&mut self, show: bool) {
+        self.show_all_versions = show;
+    }
+
+    pub fn find_plugin(&self, uri: &str) -> Option<&PluginInfo> {
+        self.available_plugins.iter().find(|p| p.uri == uri)
+    }
+
+    pub fn find_plugin_with_format(&self, uri: &str, format: PluginFormat) -> Option<&PluginInfo> {
+        self.available_plugins
+            .iter()
+            .find(|p| p.uri == uri && p.format == format)
+    }
+
+    // ----- Parameter docs -----
+
+    /// Remember the docs an instance of `uri` reported for its parameters.
+    pub fn set_param_docs(&mut self, uri: &str, docs: Vec<(usize, ParamDoc)>) {
+        self.param_docs
+            .insert(uri.to_string(), docs.into_iter().collect());
+    }
+
+    /// Docs for the control inputs of `uri` that have any, by port index.
+    /// CLAP and VST3 plugins have none until an instance has been created
+    /// this session.
+    pub fn param_docs(&self, uri: &str) -> Vec<(usize, &ParamDoc)> {
+        let mut docs: Vec<(usize, &ParamDoc)> = match self.param_docs.get(uri) {
+            Some(reported) => reported.iter().map(|(&i, doc)| (i, doc)).collect(),
+            None => self
+                .find_plugin(uri)
+                .map(|p| {
+                    p.ports
+                        .iter()
+                        .filter(|port| port.port_type == PluginPortType::ControlInput)
+                        .filter(|port| !port.doc.is_empty())
+                        .map(|port| (port.index, &port.doc))
+                        .collect()
+                })
+                .unwrap_or_default(),
+        };
+        docs.sort_by_key(|(i, _)| *i);
+        docs
+    }
+
+    // ----- Compatibility -----
+
+    /// Remember why an instance of `uri` failed to load, for the
+    /// compatibility report.
+    pub fn record_load_failure(&mut self, uri: &str, message: &str) {
+        self.load_failures
+            .insert(uri.to_string(), message.to_string());
+    }
+
+    pub fn clear_load_failure(&mut self, uri: &str) {
+        self.load_failures.remove(uri);
+    }
+
+    /// What is known to be wrong with a catalog plugin, or `None` if `uri`
+    /// is not in the catalog.
+    pub fn compat_issues(&self, uri: &str, provided_features: &[&str]) -> Option<Vec<CompatIssue>> {
+        let plugin = self.find_plugin(uri)?;
+        let failure = self.load_failures.get(uri).map(String::as_str);
+        Some(compat::issues(plugin, provided_features, failure))
+    }
+
+    // ----- Presets -----
+
+    /// Replace all presets, e.g. with the ones saved last session.
+    pub fn set_presets(&mut self, presets: HashMap<String, Vec<PluginPreset>>) {
+        self.presets = presets;
+    }
+
+    pub fn presets(&self) -> &HashMap<String, Vec<PluginPreset>> {
+        &self.presets
+    }
+
+    /// The presets of plugin `uri`, by name.
+    pub fn presets_for(&self, uri: &str) -> &[PluginPreset] {
+        self.presets.get(uri).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// Save an instance's current parameters as preset `name` of its
+    /// plugin, replacing a preset of that name. Returns false when there is
+    /// no such instance or the name is blank.
+    pub fn save_preset(&mut self, instance_id: PluginInstanceId, name: &str) -> bool {
+        let name = name.trim();
+        let Some(info) = self.active_instances.get(&instance_id) else {
+            return false;
+        };
+        if name.is_empty() {
+            return false;
+        }
+        let captured = PluginPreset::capture(name, &info.parameters);
+        let presets = self.presets.entry(info.plugin_uri.clone()).or_default();
+        preset::store(presets, captured);
+        true
+    }
+
+    pub fn delete_preset(&mut self, uri: &str, name: &str) -> bool {
+        let Some(presets) = self.presets.get_mut(uri) else {
+            return false;
+        };
+        let before = presets.len();
+        presets.retain(|p| p.name != name);
+        let deleted = presets.len() != before;
+        if presets.is_empty() {
+            self.presets.remove(uri);
+        }
+        deleted
+    }
+
+    /// Load preset `name` of plugin `uri` into an instance: its parameters
+    /// are updated here and the returned `SetPluginParameter` commands, one
+    /// per parameter the preset has a value for, set them on the running
+    /// plugin. `None` when the instance or the preset does not exist.
+    pub fn load_preset(
+        &mut self,
+        instance_id: PluginInstanceId,
+        uri: &str,
+        name: &str,
+    ) -> Option<Vec<PwCommand>> {
+        let preset = self.presets.get(uri)?.iter().find(|p| p.name == name)?;
+        let info = self.active_instances.get_mut(&instance_id)?;
+        let values = preset.values_for(&info.parameters);
+        let mut commands = Vec::with_capacity(values.len());
+        for (port_index, value) in values {
+            if let Some(param) = info
+                .parameters
+                .iter_mut()
+                .find(|p| p.port_index == port_index)
+            {
+                param.value = value;
+            }
+            commands.push(PwCommand::SetPluginParameter {
+                instance_id,
+                port_index,
+                value,
+            });
+        }
+        Some(commands)
+    }
+
+    // ----- Chain presets -----
+
+    /// Replace all chain presets, e.g. with the ones saved last session.
+    pub fn set_chain_presets(&mut self, mut chains: Vec<ChainPreset>) {
+        chains.sort_by(|a, b| a.name.cmp(&b.name));
+        chains.dedup_by(|a, b| a.name == b.name);
+        self.chain_presets = chains;
+    }
+
+    pub fn chain_presets(&self) -> &[ChainPreset] {
+        &self.chain_presets
+    }
+
+    pub fn chain_preset(&self, name: &str) -> Option<&ChainPreset> {
+        self.chain_presets.iter().find(|c| c.name == name)
+    }
+
+    /// Chain preset `name`, if it can be spliced into a link as saved:
+    /// every plugin it uses is installed, the head takes audio in and the
+    /// tail sends audio out. Inserting a chain with a plugin left out
+    /// would cut the signal path at the gap, so the error names what is
+    /// missing instead.
+    pub fn insertable_chain_preset(&self, name: &str) -> Result<&ChainPreset, String> {
+        let chain = self
+            .chain_preset(name)
+            .ok_or_else(|| format!("no chain preset named '{}'", name))?;
+        let missing = chain.missing(&self.available_plugins);
+        if !missing.is_empty() {
+            let names: Vec<&str> = missing.iter().map(|r| r.name.as_str()).collect();
+            return Err(format!(
+                "{}: not installed: {}",
+                chain.name,
+                names.join(", ")
+            ));
+        }
+        let (Some(head), Some(tail)) = (chain.plugins.first(), chain.plugins.last()) else {
+            return Err(format!("{}: the chain is empty", chain.name));
+        };
+        let info =
+            |plugin: &SnippetPlugin| self.find_plugin_with_format(&plugin.uri, plugin.format);
+        if info(head).is_none_or(|p| p.audio_inputs == 0) {
+            return Err(format!("{}: {} has no audio inputs", chain.name, head.name));
+        }
+        if info(tail).is_none_or(|p| p.audio_outputs == 0) {
+            return Err(format!(
+                "{}: {} has no audio outputs",
+                chain.name, tail.name
+            ));
+        }
+        Ok(chain)
+    }
+
+    /// Save the instances `chain`, head first, as chain preset `name`
+    /// along with those of `links` that run between them, replacing a
+    /// chain of that name. Returns false when the name is blank or an
+    /// instance does not exist.
+    pub fn save_chain_preset(
+        &mut self,
+        name: &str,
+        chain: &[PluginInstanceId],
+        links: &[SavedPluginLink],
+    ) -> bool {
+        let name = name.trim();
+        if name.is_empty() || chain.is_empty() {
+            return false;
+        }
+        let Some(instances) = chain
+            .iter()
+            .map(|id| self.active_instances.get(id))
+            .collect::<Option<Vec<_>>>()
+        else {
+            return false;
+        };
+        let snippet = PluginSnippet::copy(&instances, links);
+        let saved = ChainFile::new(name, snippet, &self.available_plugins);
+        match self
+            .chain_presets
+            .binary_search_by(|c| c.name.as_str().cmp(name))
+        {
+            Ok(i) => self.chain_presets[i] = saved,
+            Err(i) => self.chain_presets.insert(i, saved),
+        }
+        true
+    }
+
+    pub fn delete_chain_preset(&mut self, name: &str) -> bool {
+        let before = self.chain_presets.len();
+        self.chain_presets.retain(|c| c.name != name);
+        self.chain_presets.len() != before
+    }
+
+    // ----- Active instances -----
+
+    pub fn register_instance(&mut self, info: PluginInstanceInfo) {
+        self.active_instances.insert(info.id, info);
+    }
+
+    pub fn set_instance_pw_node_id(&mut self, instance_id: PluginInstanceId, pw_node_id: u32) {
+        if let Some(info) = self.active_instances.get_mut(&instance_id) {
+            info.pw_node_id = Some(pw_node_id);
+        }
+    }
+
+    pub fn remove_instance(&mut self, instance_id: PluginInstanceId) {
+        self.active_instances.remove(&instance_id);
+    }
+
+    pub fn update_parameter(
+        &mut self,
+        instance_id: PluginInstanceId,
+        port_index: usize,
+        value: f32,
+    ) {
+        if let Some(info) = self.active_instances.get_mut(&instance_id) {
+            if let Some(param) = info
+                .parameters
+                .iter_mut()
+                .find(|p| p.port_index == port_index)
+            {
+                param.value = value;
+            } else {
+                info.parameters.push(ParameterValue {
+                    port_index,
+                    symbol: String::new(),
+                    name: String::new(),
+                    value,
+                    min: 0.0,
+                    max: 1.0,
+                    default: 0.0,
+                    is_toggle: false,
+                });
+            }
+        }
+    }
+
+    pub fn active_instances(&self) -> &HashMap<PluginInstanceId, PluginInstanceInfo> {
+        &self.active_instances
+    }
+
+    pub fn get_instance(&self, id: PluginInstanceId) -> Option<&PluginInstanceInfo> {
+        self.active_instances.get(&id)
+    }
+
+    pub fn get_instance_mut(&mut self, id: PluginInstanceId) -> Option<&mut PluginInstanceInfo> {
+        self.active_instances.get_mut(&id)
+    }
+
+    pub fn find_by_stable_id(&self, stable_id: &str) -> Option<&PluginInstanceInfo> {
+        self.active_instances
+            .values()
+            .find(|info| info.stable_id == stable_id)
+    }
+
+    pub fn find_by_stable_id_mut(&mut self, stable_id: &str) -> Option<&mut PluginInstanceInfo> {
+        self.active_instances
+            .values_mut()
+            .find(|info| info.stable_id == stable_id)
+    }
+
+    pub fn instance_id_for_stable_id(&self, stable_id: &str) -> Option<PluginInstanceId> {
+        self.active_instances
+            .iter()
+            .find(|(_, info)| info.stable_id == stable_id)
+            .map(|(id, _)| *id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn instance(id: PluginInstanceId, gain: f32) -> PluginInstanceInfo {
+        PluginInstanceInfo {
+            id,
+            stable_id: format!("comp-{}", id),
+            plugin_uri: "urn:test:comp".into(),
+            format: PluginFormat::Lv2,
+            display_name: "Comp".into(),
+            pw_node_id: None,
+            parameters: vec![ParameterValue {
+                port_index: 2,
+                symbol: "gain".into(),
+                name: "Gain".into(),
+                value: gain,
+                min: -12.0,
+                max: 12.0,
+                default: 0.0,
+                is_toggle: false,
+            }],
+            active: true,
+            bypassed: false,
+            lv2_state: Vec::new(),
+            oversampling: 1,
+        }
+    }
+
+    #[test]
+    fn test_preset_saved_from_one_instance_loads_into_another() {
+        let mut mgr = PluginManager::new();
+        mgr.register_instance(instance(1, 6.0));
+        mgr.register_instance(instance(2, 0.0));
+        assert!(mgr.save_preset(1, " Punchy "));
+        assert!(!mgr.save_preset(1, "  "));
+        assert!(!mgr.save_preset(9, "Punchy"));
+        assert_eq!(mgr.presets_for("urn:test:comp")[0].name, "Punchy");
+
+        let commands = mgr.load_preset(2, "urn:test:comp", "Punchy").unwrap();
+        assert!(matches!(
+            commands[..],
+            [PwCommand::SetPluginParameter {
+                instance_id: 2,
+                port_index: 2,
+                value: 6.0,
+            }]
+        ));
+        assert_eq!(mgr.get_instance(2).unwrap().parameters[0].value, 6.0);
+        assert!(mgr.load_preset(2, "urn:test:comp", "Missing").is_none());
+    }
+
+    #[test]
+    fn test_chain_preset_keeps_order_and_internal_links() {
+        let mut mgr = PluginManager::new();
+        let mut eq = instance(1, 3.0);
+        eq.display_name = "EQ".into();
+        mgr.register_instance(eq);
+        mgr.register_instance(instance(2, -3.0));
+        let links = vec![
+            SavedPluginLink {
+                output_node_name: "EQ".into(),
+                output_port_name: "out".into(),
+                input_node_name: "Comp".into(),
+                input_port_name: "in".into(),
+                output_node_identity: None,
+                input_node_identity: None,
+            },
+            SavedPluginLink {
+                output_node_name: "Comp".into(),
+                output_port_name: "out".into(),
+                input_node_name: "Speakers".into(),
+                input_port_name: "playback_FL".into(),
+                output_node_identity: None,
+                input_node_identity: None,
+            },
+        ];
+        assert!(mgr.save_chain_preset("Vocal", &[2, 1], &links));
+        assert!(mgr.save_chain_preset("Bass", &[1], &[]));
+        assert!(!mgr.save_chain_preset(" ", &[1], &[]));
+        assert!(!mgr.save_chain_preset("Broken", &[1, 9], &[]));
+
+        let names: Vec<&str> = mgr
+            .chain_presets()
+            .iter()
+            .map(|c| c.name.as_str())
+            .collect();
+        assert_eq!(names, ["Bass", "Vocal"]);
+        let vocal = mgr.chain_preset("Vocal").unwrap();
+        assert_eq!(vocal.plugins[0].name, "Comp");
+        assert_eq!(vocal.plugins[1].parameters[0].value, 3.0);
+        assert_eq!(vocal.links.len(), 1);
+        assert_eq!((vocal.links[0].from, vocal.links[0].to), (1, 0));
+
+        // Saving under a taken name replaces that chain.
+        assert!(mgr.save_chain_preset("Vocal", &[1], &[]));
+        assert_eq!(mgr.chain_presets().len(), 2);
+        assert_eq!(mgr.chain_preset("Vocal").unwrap().plugins.len(), 1);
+        assert!(mgr.delete_chain_preset("Vocal"));
+        assert!(!mgr.delete_chain_preset("Vocal"));
+    }
+
+    #[test]
+    fn test_chain_preset_with_a_missing_plugin_is_not_insertable() {
+        let mut mgr = PluginManager::new();
+        let mut eq = instance(1, 0.0);
+        eq.plugin_uri = "urn:test:eq".into();
+        eq.display_name = "EQ".into();
+        mgr.register_instance(eq);
+        mgr.register_instance(instance(2, 0.0));
+        let mut limiter = instance(3, 0.0);
+        limiter.plugin_uri = "urn:test:limiter".into();
+        limiter.display_name = "Limiter".into();
+        mgr.register_instance(limiter);
+        assert!(mgr.save_chain_preset("Master", &[1, 2, 3], &[]));
+        assert!(mgr.save_chain_preset("Meter", &[1, 2], &[]));
+
+        mgr.set_available_plugins(vec![
+            PluginInfo::test("urn:test:eq", "EQ"),
+            PluginInfo::test("urn:test:limiter", "Limiter"),
+            PluginInfo {
+                audio_outputs: 0,
+                ..PluginInfo::test("urn:test:comp", "Comp")
+            },
+        ]);
+        assert!(mgr.insertable_chain_preset("Master").is_ok());
+        assert!(mgr.insertable_chain_preset("Nope").is_err());
+        let err = mgr.insertable_chain_preset("Meter").unwrap_err();
+        assert!(err.contains("no audio outputs"), "{}", err);
+
+        // Without the compressor in the middle, the chain would be cut.
+        mgr.set_available_plugins(vec![
+            PluginInfo::test("urn:test:eq", "EQ"),
+            PluginInfo::test("urn:test:limiter", "Limiter"),
+        ]);
+        let err = mgr.insertable_chain_preset("Master").unwrap_err();
+        assert!(err.contains("not installed: Comp"), "{}", err);
+    }
+
+    #[test]
+    fn test_deleting_the_last_preset_forgets_the_plugin() {
+        let mut mgr = PluginManager::new();
+        mgr.register_instance(instance(1, 1.0));
+        mgr.save_preset(1, "A");
+        assert!(!mgr.delete_preset("urn:test:comp", "B"));
+        assert!(mgr.delete_preset("urn:test:comp", "A"));
+        assert!(mgr.presets().is_empty());
+        assert!(mgr.presets_for("urn:test:comp").is_empty());
+    }
+}
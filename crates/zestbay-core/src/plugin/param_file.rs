@@ -0,0 +1,301 @@
+//! Parameter files: one plugin instance's parameters as JSON or CSV.
+//!
+//! Parameters are named by symbol, so a file can be edited by hand or in a
+//! spreadsheet, kept in git and loaded into another instance of the same
+//! plugin. Names and ranges are written for the reader and ignored when
+//! loading; only `symbol` and `value` matter.
+
+use serde::{Deserialize, Serialize};
+
+use super::types::{ParameterValue, PluginFormat, PluginInstanceInfo};
+
+pub const PARAM_FILE_KIND: &str = "zestbay-params";
+pub const PARAM_FILE_VERSION: u32 = 1;
+
+const CSV_HEADER: [&str; 5] = ["symbol", "name", "value", "min", "max"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParamFileFormat {
+    Json,
+    Csv,
+}
+
+impl ParamFileFormat {
+    /// CSV for `.csv` files, JSON otherwise.
+    pub fn from_path(path: &std::path::Path) -> Self {
+        if path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("csv"))
+        {
+            ParamFileFormat::Csv
+        } else {
+            ParamFileFormat::Json
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ParamFile {
+    pub kind: String,
+    pub version: u32,
+    /// The plugin the values were taken from.
+    #[serde(default)]
+    pub plugin: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format: Option<PluginFormat>,
+    #[serde(default)]
+    pub name: String,
+    pub parameters: Vec<ParamEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ParamEntry {
+    pub symbol: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub name: String,
+    pub value: f32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max: Option<f32>,
+}
+
+impl ParamFile {
+    pub fn new(info: &PluginInstanceInfo) -> Self {
+        Self {
+            kind: PARAM_FILE_KIND.to_string(),
+            version: PARAM_FILE_VERSION,
+            plugin: info.plugin_uri.clone(),
+            format: Some(info.format),
+            name: info.display_name.clone(),
+            parameters: info
+                .parameters
+                .iter()
+                .map(|p| ParamEntry {
+                    symbol: p.symbol.clone(),
+                    name: p.name.clone(),
+                    value: p.value,
+                    min: Some(p.min),
+                    max: Some(p.max),
+                })
+                .collect(),
+        }
+    }
+
+    pub fn render(&self, format: ParamFileFormat) -> String {
+        match format {
+            ParamFileFormat::Json => serde_json::to_string_pretty(self).unwrap_or_default(),
+            ParamFileFormat::Csv => {
+                let mut out = CSV_HEADER.join(",");
+                out.push('\n');
+                for p in &self.parameters {
+                    let fields = [
+                        csv_field(&p.symbol),
+                        csv_field(&p.name),
+                        p.value.to_string(),
+                        p.min.map(|v| v.to_string()).unwrap_or_default(),
+                        p.max.map(|v| v.to_string()).unwrap_or_default(),
+                    ];
+                    out.push_str(&fields.join(","));
+                    out.push('\n');
+                }
+                out
+            }
+        }
+    }
+
+    pub fn parse(text: &str, format: ParamFileFormat) -> Result<Self, String> {
+        match format {
+            ParamFileFormat::Json => {
+                let file: Self = serde_json::from_str(text)
+                    .map_err(|e| format!("not a parameter file: {}", e))?;
+                if file.kind != PARAM_FILE_KIND {
+                    return Err(format!("not a parameter file (kind \"{}\")", file.kind));
+                }
+                if file.version > PARAM_FILE_VERSION {
+                    return Err(format!(
+                        "parameter file version {} is newer than this ZestBay supports",
+                        file.version
+                    ));
+                }
+                Ok(file)
+            }
+            ParamFileFormat::Csv => Self::parse_csv(text),
+        }
+    }
+
+    /// Reads the columns by their header, in any order; only `symbol` and
+    /// `value` are needed.
+    fn parse_csv(text: &str) -> Result<Self, String> {
+        let mut rows = text
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(n, line)| (n + 1, split_csv_line(line)));
+        let (_, header) = rows.next().ok_or("the file is empty")?;
+        let column = |name: &str| {
+            header
+                .iter()
+                .position(|h| h.trim().eq_ignore_ascii_case(name))
+        };
+        let (Some(symbol_col), Some(value_col)) = (column("symbol"), column("value")) else {
+            return Err("the first line must name the symbol and value columns".into());
+        };
+        let name_col = column("name");
+
+        let mut parameters = Vec::new();
+        for (line, fields) in rows {
+            let field = |col: usize| fields.get(col).map(|f| f.trim()).unwrap_or_default();
+            let symbol = field(symbol_col);
+            if symbol.is_empty() {
+                continue;
+            }
+            let value = field(value_col)
+                .parse::<f32>()
+                .map_err(|_| format!("line {}: \"{}\" is not a number", line, field(value_col)))?;
+            parameters.push(ParamEntry {
+                symbol: symbol.to_string(),
+                name: name_col.map(|c| field(c).to_string()).unwrap_or_default(),
+                value,
+                min: None,
+                max: None,
+            });
+        }
+        Ok(Self {
+            kind: PARAM_FILE_KIND.to_string(),
+            version: PARAM_FILE_VERSION,
+            plugin: String::new(),
+            format: None,
+            name: String::new(),
+            parameters,
+        })
+    }
+
+    /// `(port_index, value)` for each entry matching one of `params` by
+    /// symbol, clamped to its range, and the symbols that matched nothing.
+    pub fn values_for(&self, params: &[ParameterValue]) -> (Vec<(usize, f32)>, Vec<String>) {
+        let mut values = Vec::new();
+        let mut unknown = Vec::new();
+        for entry in &self.parameters {
+            match params.iter().find(|p| p.symbol == entry.symbol) {
+                Some(p) if entry.value.is_finite() => {
+                    let (lo, hi) = (p.min.min(p.max), p.min.max(p.max));
+                    values.push((p.port_index, entry.value.clamp(lo, hi)));
+                }
+                _ => unknown.push(entry.symbol.clone()),
+            }
+        }
+        (values, unknown)
+    }
+}
+
+fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+/// One CSV line split into fields, with `"quoted, fields"` and `""` as an
+/// escaped quote.
+fn split_csv_line(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+    fields.push(field);
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plugin::types::PluginInstanceInfo;
+
+    fn param(port_index: usize, symbol: &str, name: &str, value: f32) -> ParameterValue {
+        ParameterValue {
+            port_index,
+            symbol: symbol.into(),
+            name: name.into(),
+            value,
+            min: -24.0,
+            max: 24.0,
+            default: 0.0,
+            is_toggle: false,
+        }
+    }
+
+    fn eq() -> PluginInstanceInfo {
+        PluginInstanceInfo {
+            id: 1,
+            stable_id: "eq-1".into(),
+            plugin_uri: "urn:test:eq".into(),
+            format: PluginFormat::Lv2,
+            display_name: "EQ".into(),
+            pw_node_id: None,
+            parameters: vec![
+                param(4, "gain_low", "Gain, low", -3.5),
+                param(5, "gain_high", "Gain \"high\"", 2.0),
+            ],
+            active: true,
+            bypassed: false,
+            lv2_state: Vec::new(),
+            oversampling: 1,
+        }
+    }
+
+    #[test]
+    fn test_files_round_trip_in_both_formats() {
+        let file = ParamFile::new(&eq());
+        for format in [ParamFileFormat::Json, ParamFileFormat::Csv] {
+            let back = ParamFile::parse(&file.render(format), format).unwrap();
+            assert_eq!(
+                back.values_for(&eq().parameters),
+                (vec![(4, -3.5), (5, 2.0)], vec![])
+            );
+            assert_eq!(back.parameters[1].name, "Gain \"high\"");
+        }
+    }
+
+    #[test]
+    fn test_csv_columns_are_found_by_header() {
+        let csv = "Value,Symbol\n12,gain_high\n\n-99,gain_low\n1,missing\n";
+        let file = ParamFile::parse(csv, ParamFileFormat::Csv).unwrap();
+        let (values, unknown) = file.values_for(&eq().parameters);
+        // Out of range values are clamped.
+        assert_eq!(values, [(5, 12.0), (4, -24.0)]);
+        assert_eq!(unknown, ["missing"]);
+        assert!(ParamFile::parse("symbol\nfoo\n", ParamFileFormat::Csv).is_err());
+        assert!(ParamFile::parse("symbol,value\nfoo,loud\n", ParamFileFormat::Csv).is_err());
+    }
+
+    #[test]
+    fn test_other_json_is_rejected() {
+        assert!(
+            ParamFile::parse(
+                r#"{"kind":"zestbay-plugins","version":1,"parameters":[]}"#,
+                ParamFileFormat::Json
+            )
+            .is_err()
+        );
+        let minimal = r#"{"kind":"zestbay-params","version":1,"parameters":[{"symbol":"gain_low","value":1}]}"#;
+        let file = ParamFile::parse(minimal, ParamFileFormat::Json).unwrap();
+        assert_eq!(file.values_for(&eq().parameters).0, [(4, 1.0)]);
+        assert_eq!(
+            ParamFileFormat::from_path(std::path::Path::new("eq.CSV")),
+            ParamFileFormat::Csv
+        );
+    }
+}
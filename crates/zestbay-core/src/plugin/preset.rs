@@ -0,0 +1,120 @@
+//! Named presets: a plugin's parameter values saved under a name.
+//!
+//! Presets belong to a plugin, by URI, not to one instance of it, so a
+//! preset saved from one EQ can be loaded into every other instance of that
+//! EQ. Values are kept by parameter symbol like parameter files, so a
+//! preset still loads after a plugin update adds or reorders parameters.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use super::types::ParameterValue;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PluginPreset {
+    pub name: String,
+    /// Parameter values by symbol.
+    pub values: BTreeMap<String, f32>,
+}
+
+impl PluginPreset {
+    pub fn capture(name: &str, params: &[ParameterValue]) -> Self {
+        Self {
+            name: name.to_string(),
+            values: params
+                .iter()
+                .filter(|p| !p.symbol.is_empty() && p.value.is_finite())
+                .map(|p| (p.symbol.clone(), p.value))
+                .collect(),
+        }
+    }
+
+    /// `(port_index, value)` for every one of `params` the preset has a
+    /// value for, clamped to its range.
+    pub fn values_for(&self, params: &[ParameterValue]) -> Vec<(usize, f32)> {
+        params
+            .iter()
+            .filter_map(|p| {
+                let value = *self.values.get(&p.symbol)?;
+                let (lo, hi) = (p.min.min(p.max), p.min.max(p.max));
+                Some((p.port_index, value.clamp(lo, hi)))
+            })
+            .collect()
+    }
+}
+
+/// Add `preset` to `presets`, replacing one of the same name, and keep
+/// them sorted by name.
+pub fn store(presets: &mut Vec<PluginPreset>, preset: PluginPreset) {
+    presets.retain(|p| p.name != preset.name);
+    presets.push(preset);
+    presets.sort_by_key(|p| p.name.to_lowercase());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn param(port_index: usize, symbol: &str, value: f32) -> ParameterValue {
+        ParameterValue {
+            port_index,
+            symbol: symbol.into(),
+            name: symbol.into(),
+            value,
+            min: 0.0,
+            max: 10.0,
+            default: 0.0,
+            is_toggle: false,
+        }
+    }
+
+    #[test]
+    fn test_presets_load_by_symbol() {
+        let preset =
+            PluginPreset::capture("Warm", &[param(3, "drive", 4.0), param(4, "tone", 7.5)]);
+        // A newer version of the plugin moved the ports and added one.
+        let newer = [
+            param(5, "tone", 0.0),
+            param(6, "mix", 10.0),
+            param(7, "drive", 0.0),
+        ];
+        assert_eq!(preset.values_for(&newer), [(5, 7.5), (7, 4.0)]);
+    }
+
+    #[test]
+    fn test_values_are_clamped_and_unnamed_ones_skipped() {
+        let preset = PluginPreset::capture(
+            "Loud",
+            &[
+                param(1, "", 2.0),
+                param(2, "gain", f32::NAN),
+                param(3, "level", 12.0),
+            ],
+        );
+        assert_eq!(preset.values.len(), 1);
+        assert_eq!(preset.values_for(&[param(3, "level", 0.0)]), [(3, 10.0)]);
+    }
+
+    #[test]
+    fn test_store_replaces_by_name_and_sorts() {
+        let mut presets = Vec::new();
+        store(
+            &mut presets,
+            PluginPreset::capture("vocals", &[param(1, "a", 1.0)]),
+        );
+        store(&mut presets, PluginPreset::capture("Bass", &[]));
+        store(
+            &mut presets,
+            PluginPreset::capture("vocals", &[param(1, "a", 2.0)]),
+        );
+        let names: Vec<&str> = presets.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, ["Bass", "vocals"]);
+        assert_eq!(presets[1].values["a"], 2.0);
+        let json = serde_json::to_string(&presets[1]).unwrap();
+        assert_eq!(
+            serde_json::from_str::<PluginPreset>(&json).unwrap(),
+            presets[1]
+        );
+    }
+}
@@ -0,0 +1,75 @@
+//! Processing buffer (quantum) requests for plugin chains.
+//!
+//! A hosted plugin's filter node can ask PipeWire for a quantum through its
+//! `node.latency` property. A request applies to every plugin of a chain, so
+//! a mic chain can ask for 64 samples while a playback EQ chain asks for 1024.
+//! PipeWire runs each driver group at the smallest quantum any of its nodes
+//! asks for, so a request is a ceiling for the nodes it shares a driver with.
+
+/// Quanta offered in the UI.
+pub const QUANTUM_CHOICES: [u32; 7] = [32, 64, 128, 256, 512, 1024, 2048];
+pub const MIN_QUANTUM: u32 = 16;
+pub const MAX_QUANTUM: u32 = 8192;
+
+/// Accept powers of two PipeWire can schedule.
+pub fn check_quantum(quantum: u32) -> Result<u32, String> {
+    if !quantum.is_power_of_two() || !(MIN_QUANTUM..=MAX_QUANTUM).contains(&quantum) {
+        return Err(format!(
+            "quantum {} is not a power of two between {} and {}",
+            quantum, MIN_QUANTUM, MAX_QUANTUM
+        ));
+    }
+    Ok(quantum)
+}
+
+/// The `node.latency` value asking for `quantum` samples at `rate`.
+pub fn node_latency(quantum: u32, rate: u32) -> String {
+    format!("{}/{}", quantum, rate)
+}
+
+/// How long one buffer of `quantum` samples lasts at `rate`.
+pub fn latency_ms(quantum: u32, rate: u32) -> f64 {
+    if rate == 0 {
+        return 0.0;
+    }
+    quantum as f64 * 1000.0 / rate as f64
+}
+
+/// The request shared by every plugin of a chain, or `None` if they have
+/// none or disagree.
+pub fn chain_quantum(requests: &[Option<u32>]) -> Option<u32> {
+    let first = (*requests.first()?)?;
+    requests.iter().all(|r| *r == Some(first)).then_some(first)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_quantum() {
+        for q in QUANTUM_CHOICES {
+            assert_eq!(check_quantum(q), Ok(q));
+        }
+        assert!(check_quantum(0).is_err());
+        assert!(check_quantum(100).is_err());
+        assert!(check_quantum(8).is_err());
+        assert!(check_quantum(16384).is_err());
+    }
+
+    #[test]
+    fn test_node_latency_and_duration() {
+        assert_eq!(node_latency(64, 48000), "64/48000");
+        assert!((latency_ms(64, 48000) - 1.333).abs() < 0.001);
+        assert_eq!(latency_ms(1024, 0), 0.0);
+    }
+
+    #[test]
+    fn test_chain_quantum_needs_agreement() {
+        assert_eq!(chain_quantum(&[Some(64), Some(64)]), Some(64));
+        assert_eq!(chain_quantum(&[Some(64), Some(1024)]), None);
+        assert_eq!(chain_quantum(&[Some(64), None]), None);
+        assert_eq!(chain_quantum(&[None]), None);
+        assert_eq!(chain_quantum(&[]), None);
+    }
+}
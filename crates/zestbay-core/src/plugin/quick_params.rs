@@ -0,0 +1,114 @@
+//! The few parameters shown as knobs on a plugin's node in the graph.
+//!
+//! Each instance shows the parameters pinned for it, in the order they were
+//! pinned, or else its first few, so a gain or mix can be nudged without
+//! opening any window. Pins are kept by symbol per instance stable ID.
+
+use super::types::ParameterValue;
+
+/// How many parameters an instance shows without pins, unless the
+/// preference says otherwise.
+pub const DEFAULT_QUICK_PARAMS: usize = 3;
+
+/// The most parameters one node shows, pinned or not; more knobs than this
+/// do not fit on a node.
+pub const MAX_QUICK_PARAMS: usize = 6;
+
+/// The parameters to show: the pinned ones that exist, or else the first
+/// `count` that can be adjusted at all.
+pub fn quick_params<'a>(
+    params: &'a [ParameterValue],
+    pinned: &[String],
+    count: usize,
+) -> Vec<&'a ParameterValue> {
+    let shown: Vec<&ParameterValue> = pinned
+        .iter()
+        .filter_map(|symbol| params.iter().find(|p| &p.symbol == symbol))
+        .take(MAX_QUICK_PARAMS)
+        .collect();
+    if !shown.is_empty() {
+        return shown;
+    }
+    params
+        .iter()
+        .filter(|p| p.max > p.min)
+        .take(count.min(MAX_QUICK_PARAMS))
+        .collect()
+}
+
+/// Pin or unpin `symbol`. Returns false when nothing changed, including
+/// when [`MAX_QUICK_PARAMS`] are pinned already.
+pub fn set_pinned(pins: &mut Vec<String>, symbol: &str, pinned: bool) -> bool {
+    let at = pins.iter().position(|s| s == symbol);
+    match (at, pinned) {
+        (None, true) if pins.len() < MAX_QUICK_PARAMS => {
+            pins.push(symbol.to_string());
+            true
+        }
+        (Some(i), false) => {
+            pins.remove(i);
+            true
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn param(port_index: usize, symbol: &str, min: f32, max: f32) -> ParameterValue {
+        ParameterValue {
+            port_index,
+            symbol: symbol.into(),
+            name: symbol.into(),
+            value: min,
+            min,
+            max,
+            default: min,
+            is_toggle: false,
+        }
+    }
+
+    fn symbols(shown: &[&ParameterValue]) -> Vec<String> {
+        shown.iter().map(|p| p.symbol.clone()).collect()
+    }
+
+    #[test]
+    fn test_first_adjustable_params_without_pins() {
+        let params = vec![
+            param(0, "latency", 0.0, 0.0),
+            param(1, "gain", -24.0, 24.0),
+            param(2, "mix", 0.0, 1.0),
+            param(3, "freq", 20.0, 20000.0),
+        ];
+        assert_eq!(symbols(&quick_params(&params, &[], 2)), ["gain", "mix"]);
+        assert!(quick_params(&params, &[], 0).is_empty());
+        assert_eq!(quick_params(&params, &[], 100).len(), 3);
+    }
+
+    #[test]
+    fn test_pins_are_shown_in_pin_order() {
+        let params = vec![param(1, "gain", -24.0, 24.0), param(2, "mix", 0.0, 1.0)];
+        let pins = vec!["mix".to_string(), "gone".to_string(), "gain".to_string()];
+        assert_eq!(symbols(&quick_params(&params, &pins, 0)), ["mix", "gain"]);
+        // Pins of parameters the plugin no longer has fall back to the first few.
+        let stale = vec!["gone".to_string()];
+        assert_eq!(symbols(&quick_params(&params, &stale, 1)), ["gain"]);
+    }
+
+    #[test]
+    fn test_pinning_is_limited() {
+        let mut pins = Vec::new();
+        assert!(set_pinned(&mut pins, "gain", true));
+        assert!(!set_pinned(&mut pins, "gain", true));
+        assert!(set_pinned(&mut pins, "mix", true));
+        assert!(set_pinned(&mut pins, "gain", false));
+        assert_eq!(pins, ["mix"]);
+        for i in 0..10 {
+            set_pinned(&mut pins, &format!("p{}", i), true);
+        }
+        assert_eq!(pins.len(), MAX_QUICK_PARAMS);
+        assert!(!set_pinned(&mut pins, "gone", false));
+    }
+}
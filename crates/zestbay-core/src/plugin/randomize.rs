@@ -0,0 +1,177 @@
+//! Random parameter variations for sound design, with an undo history.
+
+use std::collections::HashMap;
+
+use super::types::ParameterValue;
+
+/// How many randomizations of one plugin can be undone.
+pub const UNDO_DEPTH: usize = 32;
+
+/// Small SplitMix64 generator. Variations only need to sound different, not
+/// be unpredictable, and a fixed seed keeps tests repeatable.
+#[derive(Debug, Clone)]
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    /// Seeded from the system's random source.
+    pub fn from_entropy() -> Self {
+        Self(uuid::Uuid::new_v4().as_u128() as u64)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in `[0, 1)`.
+    pub fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+}
+
+/// New values for a random variation of `params`. `amount` (0 to 1) is how
+/// far a parameter may move, as a fraction of its range; toggles flip with
+/// probability `amount / 2`. Parameters whose symbol or name is in `locked`
+/// are left alone. Returns `(port_index, value)` for the parameters that
+/// changed.
+pub fn randomize(
+    params: &[ParameterValue],
+    amount: f32,
+    locked: &[String],
+    rng: &mut Rng,
+) -> Vec<(usize, f32)> {
+    let amount = amount.clamp(0.0, 1.0);
+    params
+        .iter()
+        .filter(|p| !locked.iter().any(|l| *l == p.symbol || *l == p.name))
+        .filter(|p| p.max > p.min)
+        .filter_map(|p| {
+            let value = if p.is_toggle {
+                let on = p.value > (p.min + p.max) / 2.0;
+                let on = on != (rng.next_f32() < amount / 2.0);
+                if on { p.max } else { p.min }
+            } else {
+                let offset = (rng.next_f32() * 2.0 - 1.0) * amount * (p.max - p.min);
+                (p.value + offset).clamp(p.min, p.max)
+            };
+            (value != p.value).then_some((p.port_index, value))
+        })
+        .collect()
+}
+
+/// Values to restore, per plugin stable id, most recent last.
+#[derive(Debug, Clone, Default)]
+pub struct ParamHistory {
+    entries: HashMap<String, Vec<Vec<(usize, f32)>>>,
+}
+
+impl ParamHistory {
+    /// Remember the values `stable_id` had before a change. The oldest entry
+    /// is dropped past [`UNDO_DEPTH`].
+    pub fn record(&mut self, stable_id: &str, previous: Vec<(usize, f32)>) {
+        if previous.is_empty() {
+            return;
+        }
+        let stack = self.entries.entry(stable_id.to_string()).or_default();
+        if stack.len() == UNDO_DEPTH {
+            stack.remove(0);
+        }
+        stack.push(previous);
+    }
+
+    /// The values to restore to undo the latest change to `stable_id`.
+    pub fn undo(&mut self, stable_id: &str) -> Option<Vec<(usize, f32)>> {
+        let stack = self.entries.get_mut(stable_id)?;
+        let previous = stack.pop();
+        if stack.is_empty() {
+            self.entries.remove(stable_id);
+        }
+        previous
+    }
+
+    pub fn can_undo(&self, stable_id: &str) -> bool {
+        self.entries.contains_key(stable_id)
+    }
+
+    pub fn forget(&mut self, stable_id: &str) {
+        self.entries.remove(stable_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn param(port_index: usize, symbol: &str, value: f32, is_toggle: bool) -> ParameterValue {
+        ParameterValue {
+            port_index,
+            symbol: symbol.into(),
+            name: symbol.to_uppercase(),
+            value,
+            min: 0.0,
+            max: 1.0,
+            default: 0.0,
+            is_toggle,
+        }
+    }
+
+    #[test]
+    fn test_randomize_stays_in_range_and_skips_locked() {
+        let params = [
+            param(0, "cutoff", 0.5, false),
+            param(1, "res", 0.9, false),
+            param(2, "enable", 1.0, true),
+        ];
+        let mut rng = Rng::new(7);
+        for _ in 0..100 {
+            let changes = randomize(&params, 1.0, &["RES".to_string()], &mut rng);
+            for (port, value) in changes {
+                assert_ne!(port, 1);
+                assert!((0.0..=1.0).contains(&value));
+                if port == 2 {
+                    assert_eq!(value, 0.0);
+                }
+            }
+        }
+        assert!(randomize(&params, 0.0, &[], &mut rng).is_empty());
+    }
+
+    #[test]
+    fn test_small_amount_stays_close() {
+        let params = [param(0, "cutoff", 0.5, false)];
+        let mut rng = Rng::new(42);
+        for _ in 0..100 {
+            for (_, value) in randomize(&params, 0.1, &[], &mut rng) {
+                assert!((value - 0.5).abs() <= 0.1);
+            }
+        }
+    }
+
+    #[test]
+    fn test_history_undoes_in_reverse_and_caps_depth() {
+        let mut history = ParamHistory::default();
+        history.record("a", vec![(0, 0.1)]);
+        history.record("a", vec![(0, 0.2)]);
+        history.record("a", Vec::new());
+        assert_eq!(history.undo("a"), Some(vec![(0, 0.2)]));
+        assert_eq!(history.undo("a"), Some(vec![(0, 0.1)]));
+        assert!(!history.can_undo("a"));
+        assert_eq!(history.undo("a"), None);
+
+        for i in 0..UNDO_DEPTH + 5 {
+            history.record("b", vec![(0, i as f32)]);
+        }
+        let mut count = 0;
+        while history.undo("b").is_some() {
+            count += 1;
+        }
+        assert_eq!(count, UNDO_DEPTH);
+    }
+}
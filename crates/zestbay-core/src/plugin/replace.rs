@@ -0,0 +1,161 @@
+//! Carrying a plugin's settings and connections over to a different plugin
+//! that replaces it.
+
+use super::types::{ParameterValue, PluginInfo, PluginPortType};
+
+/// Control values for `new_plugin`. Each parameter takes its value from the
+/// one in `old` with the same symbol, or failing that the same name, clamped
+/// to the new range. Parameters with no match start at their default.
+pub fn transfer_parameters(old: &[ParameterValue], new_plugin: &PluginInfo) -> Vec<ParameterValue> {
+    new_plugin
+        .ports
+        .iter()
+        .filter(|port| port.port_type == PluginPortType::ControlInput)
+        .map(|port| {
+            let carried = old
+                .iter()
+                .find(|p| !p.symbol.is_empty() && p.symbol == port.symbol)
+                .or_else(|| {
+                    old.iter()
+                        .find(|p| !p.name.is_empty() && p.name.eq_ignore_ascii_case(&port.name))
+                });
+            let value = match carried {
+                Some(p) if port.min_value <= port.max_value => {
+                    p.value.clamp(port.min_value, port.max_value)
+                }
+                Some(p) => p.value,
+                None => port.default_value,
+            };
+            ParameterValue {
+                port_index: port.index,
+                symbol: port.symbol.clone(),
+                name: port.name.clone(),
+                value,
+                min: port.min_value,
+                max: port.max_value,
+                default: port.default_value,
+                is_toggle: port.is_toggle,
+            }
+        })
+        .collect()
+}
+
+/// The port of `new_plugin` that takes over links to the replaced plugin's
+/// `port_name`. Audio channels keep their number, and channels the new plugin
+/// lacks fold onto its last one, so a stereo input into a mono plugin ends up
+/// on its single input. `None` when the new plugin has no audio port in that
+/// direction.
+pub fn replacement_port(port_name: &str, new_plugin: &PluginInfo) -> Option<String> {
+    let remap = |prefix: &str, count: usize| -> Option<Option<String>> {
+        let channel: usize = port_name.strip_prefix(prefix)?.parse().ok()?;
+        Some((count > 0).then(|| format!("{}{}", prefix, channel.min(count - 1))))
+    };
+    remap("input_", new_plugin.audio_inputs)
+        .or_else(|| remap("output_", new_plugin.audio_outputs))
+        .unwrap_or_else(|| Some(port_name.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plugin::types::{ParamDoc, PluginCategory, PluginFormat, PluginPortInfo};
+
+    fn control(index: usize, symbol: &str, name: &str, min: f32, max: f32) -> PluginPortInfo {
+        PluginPortInfo {
+            index,
+            symbol: symbol.into(),
+            name: name.into(),
+            port_type: PluginPortType::ControlInput,
+            default_value: min,
+            min_value: min,
+            max_value: max,
+            is_toggle: false,
+            is_sidechain: false,
+            reports_latency: false,
+            doc: ParamDoc::default(),
+        }
+    }
+
+    fn plugin(ports: Vec<PluginPortInfo>, audio_inputs: usize, audio_outputs: usize) -> PluginInfo {
+        PluginInfo {
+            uri: "urn:test".into(),
+            name: "Test".into(),
+            format: PluginFormat::Lv2,
+            category: PluginCategory::from_class_label(""),
+            author: None,
+            control_inputs: ports.len(),
+            ports,
+            audio_inputs,
+            audio_outputs,
+            control_outputs: 0,
+            required_features: Vec::new(),
+            compatible: true,
+            has_ui: false,
+            library_path: String::new(),
+            version: None,
+            modified: None,
+            unsupported_ports: Vec::new(),
+        }
+    }
+
+    fn value(symbol: &str, name: &str, value: f32) -> ParameterValue {
+        ParameterValue {
+            port_index: 0,
+            symbol: symbol.into(),
+            name: name.into(),
+            value,
+            min: -24.0,
+            max: 24.0,
+            default: 0.0,
+            is_toggle: false,
+        }
+    }
+
+    #[test]
+    fn test_parameters_match_by_symbol_then_name() {
+        let new_plugin = plugin(
+            vec![
+                control(3, "gain", "Output Gain", -12.0, 12.0),
+                control(4, "lowshelf_freq", "Low Shelf", 20.0, 500.0),
+                control(5, "q", "Q", 0.1, 10.0),
+            ],
+            2,
+            2,
+        );
+        let old = [
+            value("gain", "Gain", 18.0),
+            value("ls_f", "low shelf", 120.0),
+        ];
+
+        let params = transfer_parameters(&old, &new_plugin);
+        assert_eq!(params.len(), 3);
+        assert_eq!(params[0].port_index, 3);
+        assert_eq!(params[0].value, 12.0);
+        assert_eq!(params[1].value, 120.0);
+        assert_eq!(params[2].value, 0.1);
+    }
+
+    #[test]
+    fn test_ports_fold_onto_available_channels() {
+        let mono = plugin(Vec::new(), 1, 1);
+        assert_eq!(
+            replacement_port("input_1", &mono).as_deref(),
+            Some("input_0")
+        );
+        assert_eq!(
+            replacement_port("output_0", &mono).as_deref(),
+            Some("output_0")
+        );
+        assert_eq!(
+            replacement_port("midi_in", &mono).as_deref(),
+            Some("midi_in")
+        );
+
+        let instrument = plugin(Vec::new(), 0, 2);
+        assert_eq!(replacement_port("input_0", &instrument), None);
+        assert_eq!(
+            replacement_port("output_1", &instrument).as_deref(),
+            Some("output_1")
+        );
+    }
+}
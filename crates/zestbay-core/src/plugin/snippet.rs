@@ -0,0 +1,293 @@
+//! Portable copies of plugin instances and the links between them.
+//!
+//! A snippet names plugins by URI and parameters by symbol, never by
+//! instance or port index, so it can be pasted into another session or
+//! shared as text. Links only run between plugins in the snippet and refer
+//! to them by position.
+
+use serde::{Deserialize, Serialize};
+
+use super::state::StateEntry;
+use super::types::{ParameterValue, PluginFormat, PluginInstanceInfo, SavedPluginLink};
+
+/// Marks JSON as a snippet, so pasting arbitrary text fails early.
+pub const SNIPPET_KIND: &str = "zestbay-plugins";
+pub const SNIPPET_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PluginSnippet {
+    pub kind: String,
+    pub version: u32,
+    pub plugins: Vec<SnippetPlugin>,
+    #[serde(default)]
+    pub links: Vec<SnippetLink>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SnippetPlugin {
+    pub uri: String,
+    pub format: PluginFormat,
+    pub name: String,
+    #[serde(default)]
+    pub bypassed: bool,
+    #[serde(default = "default_oversampling")]
+    pub oversampling: u32,
+    #[serde(default)]
+    pub parameters: Vec<SnippetParameter>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub state: Vec<StateEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SnippetParameter {
+    pub symbol: String,
+    #[serde(default)]
+    pub name: String,
+    pub value: f32,
+}
+
+/// A link from `plugins[from]` to `plugins[to]`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SnippetLink {
+    pub from: usize,
+    pub output_port: String,
+    pub to: usize,
+    pub input_port: String,
+}
+
+fn default_oversampling() -> u32 {
+    1
+}
+
+impl PluginSnippet {
+    /// Copy `instances` along with those of `links` that run between two of
+    /// them.
+    pub fn copy(instances: &[&PluginInstanceInfo], links: &[SavedPluginLink]) -> Self {
+        let position = |name: &str| instances.iter().position(|i| i.display_name == name);
+        let plugins = instances
+            .iter()
+            .map(|info| SnippetPlugin {
+                uri: info.plugin_uri.clone(),
+                format: info.format,
+                name: info.display_name.clone(),
+                bypassed: info.bypassed,
+                oversampling: info.oversampling,
+                parameters: info
+                    .parameters
+                    .iter()
+                    .map(|p| SnippetParameter {
+                        symbol: p.symbol.clone(),
+                        name: p.name.clone(),
+                        value: p.value,
+                    })
+                    .collect(),
+                state: info.lv2_state.clone(),
+            })
+            .collect();
+        let links = links
+            .iter()
+            .filter_map(|link| {
+                Some(SnippetLink {
+                    from: position(&link.output_node_name)?,
+                    output_port: link.output_port_name.clone(),
+                    to: position(&link.input_node_name)?,
+                    input_port: link.input_port_name.clone(),
+                })
+            })
+            .collect();
+        Self {
+            kind: SNIPPET_KIND.to_string(),
+            version: SNIPPET_VERSION,
+            plugins,
+            links,
+        }
+    }
+
+    /// Read a snippet, rejecting anything that is not one or that refers
+    /// outside itself.
+    pub fn parse(json: &str) -> Result<Self, String> {
+        let snippet: Self =
+            serde_json::from_str(json).map_err(|e| format!("not a plugin snippet: {}", e))?;
+        if snippet.kind != SNIPPET_KIND {
+            return Err(format!("not a plugin snippet (kind '{}')", snippet.kind));
+        }
+        if snippet.version > SNIPPET_VERSION {
+            return Err(format!(
+                "snippet version {} is newer than this build understands",
+                snippet.version
+            ));
+        }
+        snippet.validate()?;
+        Ok(snippet)
+    }
+
+    /// Check that the values are usable and the links stay inside the
+    /// snippet.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.plugins.is_empty() {
+            return Err("no plugins".to_string());
+        }
+        for plugin in &self.plugins {
+            if plugin.uri.is_empty() {
+                return Err(format!("'{}' has no plugin URI", plugin.name));
+            }
+            if ![1, 2, 4].contains(&plugin.oversampling) {
+                return Err(format!(
+                    "'{}' has an invalid oversampling factor",
+                    plugin.name
+                ));
+            }
+            if plugin.parameters.iter().any(|p| !p.value.is_finite()) {
+                return Err(format!("'{}' has a non-finite parameter", plugin.name));
+            }
+        }
+        let count = self.plugins.len();
+        if self.links.iter().any(|l| l.from >= count || l.to >= count) {
+            return Err("links a plugin it does not contain".to_string());
+        }
+        Ok(())
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+
+    /// The snippet's links between the pasted plugins, given each plugin's
+    /// new display name, or `None` for plugins that were not pasted.
+    pub fn links_between(&self, names: &[Option<String>]) -> Vec<SavedPluginLink> {
+        self.links
+            .iter()
+            .filter_map(|link| {
+                let from = names.get(link.from)?.as_ref()?;
+                let to = names.get(link.to)?.as_ref()?;
+                Some(SavedPluginLink {
+                    output_node_name: from.clone(),
+                    output_port_name: link.output_port.clone(),
+                    input_node_name: to.clone(),
+                    input_port_name: link.input_port.clone(),
+                    output_node_identity: None,
+                    input_node_identity: None,
+                })
+            })
+            .collect()
+    }
+}
+
+impl SnippetPlugin {
+    /// The copied values, in the shape [`super::replace::transfer_parameters`]
+    /// takes to match them against the plugin they are pasted into.
+    pub fn parameter_values(&self) -> Vec<ParameterValue> {
+        self.parameters
+            .iter()
+            .map(|p| ParameterValue {
+                port_index: 0,
+                symbol: p.symbol.clone(),
+                name: p.name.clone(),
+                value: p.value,
+                min: p.value,
+                max: p.value,
+                default: p.value,
+                is_toggle: false,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn instance(name: &str, gain: f32) -> PluginInstanceInfo {
+        PluginInstanceInfo {
+            id: 1,
+            stable_id: format!("{}-id", name),
+            plugin_uri: "urn:test:gain".to_string(),
+            format: PluginFormat::Lv2,
+            display_name: name.to_string(),
+            pw_node_id: Some(40),
+            parameters: vec![ParameterValue {
+                port_index: 3,
+                symbol: "gain".to_string(),
+                name: "Gain".to_string(),
+                value: gain,
+                min: -20.0,
+                max: 20.0,
+                default: 0.0,
+                is_toggle: false,
+            }],
+            active: true,
+            bypassed: false,
+            lv2_state: Vec::new(),
+            oversampling: 1,
+        }
+    }
+
+    fn link(from: &str, to: &str) -> SavedPluginLink {
+        SavedPluginLink {
+            output_node_name: from.to_string(),
+            output_port_name: "output_0".to_string(),
+            input_node_name: to.to_string(),
+            input_port_name: "input_0".to_string(),
+            output_node_identity: None,
+            input_node_identity: None,
+        }
+    }
+
+    #[test]
+    fn test_copy_keeps_only_internal_links() {
+        let (a, b) = (instance("Gate", 1.0), instance("Comp", -3.0));
+        let links = [
+            link("Mic", "Gate"),
+            link("Gate", "Comp"),
+            link("Comp", "Speakers"),
+        ];
+        let snippet = PluginSnippet::copy(&[&a, &b], &links);
+
+        assert_eq!(snippet.plugins.len(), 2);
+        assert_eq!(snippet.plugins[1].parameters[0].value, -3.0);
+        assert_eq!(
+            snippet.links,
+            vec![SnippetLink {
+                from: 0,
+                output_port: "output_0".to_string(),
+                to: 1,
+                input_port: "input_0".to_string(),
+            }]
+        );
+        assert_eq!(PluginSnippet::parse(&snippet.to_json()).unwrap(), snippet);
+    }
+
+    #[test]
+    fn test_parse_rejects_foreign_or_broken_snippets() {
+        assert!(PluginSnippet::parse("hello").is_err());
+        assert!(PluginSnippet::parse(r#"{"kind": "other", "version": 1, "plugins": []}"#).is_err());
+
+        let a = instance("Gate", 1.0);
+        let mut snippet = PluginSnippet::copy(&[&a], &[]);
+        snippet.links.push(SnippetLink {
+            from: 0,
+            output_port: "output_0".to_string(),
+            to: 5,
+            input_port: "input_0".to_string(),
+        });
+        assert!(PluginSnippet::parse(&snippet.to_json()).is_err());
+    }
+
+    #[test]
+    fn test_links_between_skips_missing_plugins() {
+        let (a, b) = (instance("Gate", 1.0), instance("Comp", 0.0));
+        let snippet = PluginSnippet::copy(&[&a, &b], &[link("Gate", "Comp")]);
+
+        let pasted =
+            snippet.links_between(&[Some("Gate #2".to_string()), Some("Comp #2".to_string())]);
+        assert_eq!(pasted.len(), 1);
+        assert_eq!(pasted[0].output_node_name, "Gate #2");
+        assert_eq!(pasted[0].input_node_name, "Comp #2");
+
+        assert!(
+            snippet
+                .links_between(&[Some("Gate #2".to_string()), None])
+                .is_empty()
+        );
+    }
+}
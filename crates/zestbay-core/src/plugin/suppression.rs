@@ -0,0 +1,124 @@
+//! Picking a noise suppression plugin for "Add Noise Suppression".
+//!
+//! Known suppressors are tried first, in order of preference; after that
+//! any compatible effect whose name says it removes noise will do. Among
+//! equally preferred plugins, one with as many channels as the source wins.
+
+use super::types::PluginInfo;
+
+/// URIs of suppressors known to work well on voice, best first. Variants of
+/// one plugin (mono and stereo) share a rank.
+pub const KNOWN_SUPPRESSORS: &[&[&str]] = &[
+    // RNNoise, from noise-suppression-for-voice
+    &[
+        "https://github.com/werman/noise-suppression-for-voice#mono",
+        "https://github.com/werman/noise-suppression-for-voice#stereo",
+    ],
+    &["https://github.com/lucianodato/speech-denoiser"],
+    &[
+        "https://github.com/lucianodato/noise-repellent#new",
+        "https://github.com/lucianodato/noise-repellent",
+        "https://github.com/lucianodato/noise-repellent-stereo",
+    ],
+    &[
+        "http://lsp-plug.in/plugins/lv2/gate_mono",
+        "http://lsp-plug.in/plugins/lv2/gate_stereo",
+    ],
+];
+
+/// Lowercase name fragments of plugins that are not in
+/// [`KNOWN_SUPPRESSORS`] but are likely to be one.
+const NAME_HINTS: &[&str] = &[
+    "rnnoise",
+    "noise suppress",
+    "noise reduction",
+    "denoise",
+    "noise repellent",
+];
+
+fn rank(plugin: &PluginInfo) -> Option<usize> {
+    if let Some(rank) = KNOWN_SUPPRESSORS
+        .iter()
+        .position(|uris| uris.contains(&plugin.uri.as_str()))
+    {
+        return Some(rank);
+    }
+    let name = plugin.name.to_lowercase();
+    NAME_HINTS
+        .iter()
+        .any(|hint| name.contains(hint))
+        .then_some(KNOWN_SUPPRESSORS.len())
+}
+
+/// The best suppressor in `catalog` for a source with `channels` audio
+/// channels, if one is installed.
+pub fn find_suppressor(catalog: &[PluginInfo], channels: usize) -> Option<&PluginInfo> {
+    catalog
+        .iter()
+        .filter(|p| p.compatible && p.is_effect())
+        .filter_map(|p| Some((rank(p)?, p)))
+        .min_by_key(|(rank, p)| (*rank, p.audio_inputs.abs_diff(channels.max(1))))
+        .map(|(_, p)| p)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plugin::types::{PluginCategory, PluginFormat};
+
+    fn entry(uri: &str, name: &str, channels: usize) -> PluginInfo {
+        PluginInfo {
+            uri: uri.into(),
+            name: name.into(),
+            format: PluginFormat::Lv2,
+            category: PluginCategory::from_class_label(""),
+            author: None,
+            ports: Vec::new(),
+            audio_inputs: channels,
+            audio_outputs: channels,
+            control_inputs: 1,
+            control_outputs: 0,
+            required_features: Vec::new(),
+            compatible: true,
+            has_ui: false,
+            library_path: String::new(),
+            version: None,
+            modified: None,
+            unsupported_ports: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_prefers_known_suppressor_with_matching_channels() {
+        let catalog = [
+            entry("http://lsp-plug.in/plugins/lv2/gate_mono", "Gate Mono", 1),
+            entry(KNOWN_SUPPRESSORS[0][1], "Noise Suppressor Stereo", 2),
+            entry(KNOWN_SUPPRESSORS[0][0], "Noise Suppressor Mono", 1),
+        ];
+        assert_eq!(
+            find_suppressor(&catalog, 1).unwrap().uri,
+            KNOWN_SUPPRESSORS[0][0]
+        );
+        assert_eq!(
+            find_suppressor(&catalog, 2).unwrap().uri,
+            KNOWN_SUPPRESSORS[0][1]
+        );
+    }
+
+    #[test]
+    fn test_falls_back_to_name_hints() {
+        let catalog = [
+            entry("urn:eq", "Parametric EQ", 2),
+            entry("urn:dfn", "DeepFilter Denoiser", 2),
+        ];
+        assert_eq!(find_suppressor(&catalog, 1).unwrap().uri, "urn:dfn");
+    }
+
+    #[test]
+    fn test_skips_incompatible_and_instruments() {
+        let mut broken = entry(KNOWN_SUPPRESSORS[0][0], "Noise Suppressor Mono", 1);
+        broken.compatible = false;
+        let synth = entry("urn:synth", "RNNoise Synth", 0);
+        assert!(find_suppressor(&[broken, synth, entry("urn:eq", "EQ", 2)], 1).is_none());
+    }
+}
@@ -0,0 +1,112 @@
+//! Tail detection for removed and bypassed plugins.
+//!
+//! Cutting a reverb or delay off mid-decay is audible. When tails are let
+//! out, the host keeps the plugin running on silent input and a
+//! [`TailGate`] watches its output: the tail is over once the output has
+//! stayed below [`TAIL_THRESHOLD`] for a short while, or when the time
+//! allowed for it runs out.
+
+/// Output level below which a tail counts as died away: -70 dBFS.
+pub const TAIL_THRESHOLD: f32 = 3.162_277_7e-4;
+
+/// Longest tail let out unless the preference says otherwise.
+pub const DEFAULT_TAIL_SECONDS: u32 = 10;
+
+/// How long the output has to stay below the threshold. Long enough to
+/// span the gap between the repeats of a slow delay fading out.
+const QUIET_SECONDS: f64 = 0.25;
+
+#[derive(Debug, Clone, Default)]
+pub struct TailGate {
+    /// Quiet samples needed before the tail counts as over.
+    hold: usize,
+    quiet: usize,
+    remaining: usize,
+    active: bool,
+}
+
+impl TailGate {
+    pub fn new(sample_rate: f64) -> Self {
+        Self {
+            hold: (QUIET_SECONDS * sample_rate).max(1.0) as usize,
+            ..Default::default()
+        }
+    }
+
+    /// Start watching a tail that may last at most `timeout` samples.
+    pub fn start(&mut self, timeout: usize) {
+        self.active = true;
+        self.quiet = 0;
+        self.remaining = timeout;
+    }
+
+    pub fn stop(&mut self) {
+        self.active = false;
+    }
+
+    /// Whether a tail is being watched and has not ended yet.
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    /// Watch one block of plugin output. Returns true when the tail ends in
+    /// this block, after which the gate is no longer active. RT-safe.
+    pub fn observe<'a>(&mut self, outputs: impl IntoIterator<Item = &'a [f32]>, n: usize) -> bool {
+        if !self.active {
+            return false;
+        }
+        let peak = outputs
+            .into_iter()
+            .flat_map(|o| o.iter().take(n))
+            .fold(0.0f32, |peak, s| peak.max(s.abs()));
+        if peak < TAIL_THRESHOLD {
+            self.quiet += n;
+        } else {
+            self.quiet = 0;
+        }
+        self.remaining = self.remaining.saturating_sub(n);
+        if self.quiet >= self.hold || self.remaining == 0 {
+            self.active = false;
+            return true;
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn feed(gate: &mut TailGate, level: f32, blocks: usize) -> Option<usize> {
+        let block = [level; 100];
+        (0..blocks).find(|_| gate.observe([&block[..], &block[..]], block.len()))
+    }
+
+    #[test]
+    fn test_tail_ends_after_staying_quiet() {
+        let mut gate = TailGate::new(1000.0); // 250 quiet samples
+        gate.start(10_000);
+        assert_eq!(feed(&mut gate, 0.5, 20), None);
+        // A blip resets the quiet count.
+        assert_eq!(feed(&mut gate, 1e-5, 2), None);
+        assert_eq!(feed(&mut gate, 0.01, 1), None);
+        assert_eq!(feed(&mut gate, 1e-5, 10), Some(2));
+        assert!(!gate.is_active());
+    }
+
+    #[test]
+    fn test_endless_tail_times_out() {
+        let mut gate = TailGate::new(1000.0);
+        gate.start(1000);
+        assert_eq!(feed(&mut gate, 0.5, 20), Some(9));
+    }
+
+    #[test]
+    fn test_inactive_gate_never_ends() {
+        let mut gate = TailGate::new(1000.0);
+        assert_eq!(feed(&mut gate, 0.0, 20), None);
+        gate.start(1000);
+        gate.stop();
+        assert_eq!(feed(&mut gate, 0.0, 20), None);
+    }
+}
@@ -5,6 +5,8 @@ use std::sync::Arc;
 
 use serde::{Deserialize, Serialize};
 
+pub use super::docs::{MAX_LABELLED_STEPS, ParamDoc, ScalePoint};
+
 /// Unique identifier for a running plugin instance (format-agnostic).
 pub type PluginInstanceId = u64;
 
@@ -17,6 +19,8 @@ pub enum PluginFormat {
     Lv2,
     Clap,
     Vst3,
+    /// Processing nodes implemented by ZestBay itself.
+    Builtin,
 }
 
 impl PluginFormat {
@@ -25,8 +29,17 @@ impl PluginFormat {
             Self::Lv2 => "LV2",
             Self::Clap => "CLAP",
             Self::Vst3 => "VST3",
+            Self::Builtin => "Builtin",
         }
     }
+
+    /// The format named `name`, as written by [`as_str`](Self::as_str) in
+    /// any case.
+    pub fn parse(name: &str) -> Option<Self> {
+        [Self::Lv2, Self::Clap, Self::Vst3, Self::Builtin]
+            .into_iter()
+            .find(|f| f.as_str().eq_ignore_ascii_case(name))
+    }
 }
 
 impl std::fmt::Display for PluginFormat {
@@ -50,6 +63,10 @@ pub enum PluginPortType {
     /// practice but still kept in the unified enum for completeness.
     AtomInput,
     AtomOutput,
+    /// LV2 CV ports: audio-rate control signals, kept apart from the audio
+    /// channels.
+    CvInput,
+    CvOutput,
 }
 
 impl PluginPortType {
@@ -61,17 +78,21 @@ impl PluginPortType {
         matches!(self, Self::ControlInput | Self::ControlOutput)
     }
 
+    pub fn is_cv(&self) -> bool {
+        matches!(self, Self::CvInput | Self::CvOutput)
+    }
+
     pub fn is_input(&self) -> bool {
         matches!(
             self,
-            Self::AudioInput | Self::ControlInput | Self::AtomInput
+            Self::AudioInput | Self::ControlInput | Self::AtomInput | Self::CvInput
         )
     }
 
     pub fn is_output(&self) -> bool {
         matches!(
             self,
-            Self::AudioOutput | Self::ControlOutput | Self::AtomOutput
+            Self::AudioOutput | Self::ControlOutput | Self::AtomOutput | Self::CvOutput
         )
     }
 }
@@ -93,8 +114,26 @@ pub struct PluginPortInfo {
     /// CLAP stepped 0–1, VST3 `stepCount == 1`).
     #[serde(default)]
     pub is_toggle: bool,
+    /// Whether this audio input is a sidechain (LV2 `lv2:isSideChain`).
+    #[serde(default)]
+    pub is_sidechain: bool,
+    /// Whether this control output reports the plugin's latency in samples
+    /// (LV2 `lv2:reportsLatency`).
+    #[serde(default)]
+    pub reports_latency: bool,
+    /// Description, unit and scale points from the plugin's metadata.
+    #[serde(default, skip_serializing_if = "ParamDoc::is_empty")]
+    pub doc: ParamDoc,
 }
 
+/// `port.group` given to the sidechain inputs of plugin filter nodes, so the
+/// UI can tell them apart from the main inputs.
+pub const SIDECHAIN_PORT_GROUP: &str = "sidechain";
+
+/// `port.group` given to the CV ports of plugin filter nodes; the graph
+/// gives ports in it [`MediaType::Cv`](crate::graph::MediaType::Cv).
+pub const CV_PORT_GROUP: &str = "cv";
+
 // ---------------------------------------------------------------------------
 // Plugin category
 // ---------------------------------------------------------------------------
@@ -217,13 +256,60 @@ pub struct PluginInfo {
     pub compatible: bool,
     /// Whether the plugin provides a native UI.
     pub has_ui: bool,
-    /// Filesystem path to the plugin library (.clap file, .vst3 bundle).
-    /// Empty for LV2 (which uses lilv for discovery).
+    /// Filesystem path to the plugin library (.clap file, .vst3 bundle,
+    /// LV2 shared object). Empty for builtin plugins.
     #[serde(default)]
     pub library_path: String,
+    /// Version the plugin declares: LV2 `minor.micro`, CLAP and VST3 as
+    /// given.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    /// When `library_path` was last modified, in seconds since the Unix
+    /// epoch.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub modified: Option<u64>,
+    /// Symbols of ports of a kind that cannot be connected (LV2 only).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub unsupported_ports: Vec<String>,
+}
+
+/// When the file or bundle at `path` was last modified, in seconds since the
+/// Unix epoch.
+pub fn modified_secs(path: &str) -> Option<u64> {
+    if path.is_empty() {
+        return None;
+    }
+    let modified = std::fs::metadata(path).ok()?.modified().ok()?;
+    let since_epoch = modified.duration_since(std::time::UNIX_EPOCH).ok()?;
+    Some(since_epoch.as_secs())
 }
 
 impl PluginInfo {
+    /// A compatible stereo LV2 effect with nothing else set, for tests to
+    /// adjust with struct update syntax.
+    #[cfg(test)]
+    pub(crate) fn test(uri: &str, name: &str) -> Self {
+        Self {
+            uri: uri.to_string(),
+            name: name.to_string(),
+            format: PluginFormat::Lv2,
+            category: PluginCategory::from_class_label(""),
+            author: None,
+            ports: Vec::new(),
+            audio_inputs: 2,
+            audio_outputs: 2,
+            control_inputs: 0,
+            control_outputs: 0,
+            required_features: Vec::new(),
+            compatible: true,
+            has_ui: false,
+            library_path: String::new(),
+            version: None,
+            modified: None,
+            unsupported_ports: Vec::new(),
+        }
+    }
+
     pub fn is_effect(&self) -> bool {
         self.audio_inputs > 0 && self.audio_outputs > 0
     }
@@ -269,7 +355,9 @@ pub struct PluginInstanceInfo {
     pub active: bool,
     pub bypassed: bool,
     /// Cached LV2 state entries (populated from PW thread on remove, used for persistence)
-    pub lv2_state: Vec<crate::lv2::state::StateEntry>,
+    pub lv2_state: Vec<crate::plugin::state::StateEntry>,
+    /// Oversampling factor the plugin runs at: 1 (off), 2 or 4
+    pub oversampling: u32,
 }
 
 // ---------------------------------------------------------------------------
@@ -304,6 +392,18 @@ pub struct SavedPluginLink {
     pub output_port_name: String,
     pub input_node_name: String,
     pub input_port_name: String,
+    /// Saved identity of the output node, tried before its name on restore.
+    /// Plugins and bridge devices have none.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output_node_identity: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub input_node_identity: Option<String>,
+}
+
+impl SavedPluginLink {
+    pub fn touches(&self, node_name: &str) -> bool {
+        self.output_node_name == node_name || self.input_node_name == node_name
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -404,6 +504,7 @@ mod tests {
     // ---- AtomicF32 ----
 
     #[test]
+    #[allow(clippy::approx_constant)]
     fn atomic_f32_store_load() {
         let a = AtomicF32::new(0.0);
         assert!((a.load() - 0.0).abs() < f32::EPSILON);
@@ -430,6 +531,17 @@ mod tests {
         assert!(a.load().is_nan());
     }
 
+    // ---- PluginPortInfo ----
+
+    #[test]
+    fn port_info_without_sidechain_flag_deserializes() {
+        let json = r#"{"index":2,"symbol":"in_l","name":"In L","port_type":"AudioInput",
+            "default_value":0.0,"min_value":0.0,"max_value":0.0}"#;
+        let port: PluginPortInfo = serde_json::from_str(json).unwrap();
+        assert!(!port.is_sidechain);
+        assert!(!port.is_toggle);
+    }
+
     // ---- PortSlot ----
 
     #[test]
@@ -503,6 +615,7 @@ mod tests {
         assert_eq!(PluginFormat::Lv2.as_str(), "LV2");
         assert_eq!(PluginFormat::Clap.as_str(), "CLAP");
         assert_eq!(PluginFormat::Vst3.as_str(), "VST3");
+        assert_eq!(PluginFormat::Builtin.as_str(), "Builtin");
     }
 
     #[test]
@@ -523,15 +636,19 @@ mod tests {
         assert!(PluginPortType::ControlInput.is_control());
         assert!(PluginPortType::ControlOutput.is_control());
         assert!(!PluginPortType::AudioInput.is_control());
+        assert!(PluginPortType::CvInput.is_cv());
+        assert!(!PluginPortType::CvOutput.is_audio());
 
         assert!(PluginPortType::AudioInput.is_input());
         assert!(PluginPortType::ControlInput.is_input());
         assert!(PluginPortType::AtomInput.is_input());
+        assert!(PluginPortType::CvInput.is_input());
         assert!(!PluginPortType::AudioOutput.is_input());
 
         assert!(PluginPortType::AudioOutput.is_output());
         assert!(PluginPortType::ControlOutput.is_output());
         assert!(PluginPortType::AtomOutput.is_output());
+        assert!(PluginPortType::CvOutput.is_output());
         assert!(!PluginPortType::AudioInput.is_output());
     }
 
@@ -575,12 +692,23 @@ mod tests {
     #[test]
     fn plugin_info_is_effect() {
         let info = PluginInfo {
-            uri: String::new(), name: String::new(), format: PluginFormat::Vst3,
-            category: PluginCategory::Reverb, author: None, ports: Vec::new(),
-            audio_inputs: 2, audio_outputs: 2,
-            control_inputs: 0, control_outputs: 0,
-            required_features: Vec::new(), compatible: true, has_ui: false,
+            uri: String::new(),
+            name: String::new(),
+            format: PluginFormat::Vst3,
+            category: PluginCategory::Reverb,
+            author: None,
+            ports: Vec::new(),
+            audio_inputs: 2,
+            audio_outputs: 2,
+            control_inputs: 0,
+            control_outputs: 0,
+            required_features: Vec::new(),
+            compatible: true,
+            has_ui: false,
             library_path: String::new(),
+            version: None,
+            modified: None,
+            unsupported_ports: Vec::new(),
         };
         assert!(info.is_effect());
         assert!(!info.is_instrument());
@@ -590,12 +718,23 @@ mod tests {
     #[test]
     fn plugin_info_is_instrument() {
         let info = PluginInfo {
-            uri: String::new(), name: String::new(), format: PluginFormat::Vst3,
-            category: PluginCategory::Instrument, author: None, ports: Vec::new(),
-            audio_inputs: 0, audio_outputs: 2,
-            control_inputs: 0, control_outputs: 0,
-            required_features: Vec::new(), compatible: true, has_ui: false,
+            uri: String::new(),
+            name: String::new(),
+            format: PluginFormat::Vst3,
+            category: PluginCategory::Instrument,
+            author: None,
+            ports: Vec::new(),
+            audio_inputs: 0,
+            audio_outputs: 2,
+            control_inputs: 0,
+            control_outputs: 0,
+            required_features: Vec::new(),
+            compatible: true,
+            has_ui: false,
             library_path: String::new(),
+            version: None,
+            modified: None,
+            unsupported_ports: Vec::new(),
         };
         assert!(info.is_instrument());
         assert!(!info.is_effect());
@@ -605,12 +744,23 @@ mod tests {
     #[test]
     fn plugin_info_is_analyser() {
         let info = PluginInfo {
-            uri: String::new(), name: String::new(), format: PluginFormat::Vst3,
-            category: PluginCategory::Analyser, author: None, ports: Vec::new(),
-            audio_inputs: 2, audio_outputs: 0,
-            control_inputs: 0, control_outputs: 0,
-            required_features: Vec::new(), compatible: true, has_ui: false,
+            uri: String::new(),
+            name: String::new(),
+            format: PluginFormat::Vst3,
+            category: PluginCategory::Analyser,
+            author: None,
+            ports: Vec::new(),
+            audio_inputs: 2,
+            audio_outputs: 0,
+            control_inputs: 0,
+            control_outputs: 0,
+            required_features: Vec::new(),
+            compatible: true,
+            has_ui: false,
             library_path: String::new(),
+            version: None,
+            modified: None,
+            unsupported_ports: Vec::new(),
         };
         assert!(info.is_analyser());
         assert!(!info.is_effect());
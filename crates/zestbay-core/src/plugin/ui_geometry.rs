@@ -0,0 +1,213 @@
+//! Saved position and size of plugin UI windows.
+//!
+//! The application persists geometry per plugin (by stable ID) and, before
+//! asking for a UI to be opened, hands it over here by instance ID. The UI
+//! threads pick it up when they create the window and report changes back
+//! with `PluginEvent::PluginUiGeometry`.
+//!
+//! With the window it can also hand over the monitor the UI belongs on: the
+//! one it was last left on, a chosen one or the main window's. A saved
+//! geometry on another monitor, or none at all, opens the window centered
+//! there instead of wherever the window manager likes.
+
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+
+/// Frame position and size of a plugin UI window, in screen pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UiGeometry {
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+}
+
+impl UiGeometry {
+    /// The monitor showing the middle of the window.
+    pub fn monitor<'a>(&self, monitors: &'a [MonitorArea]) -> Option<&'a MonitorArea> {
+        let (x, y) = (self.x + self.width / 2, self.y + self.height / 2);
+        monitors.iter().find(|m| m.contains(x, y))
+    }
+}
+
+/// A monitor's area of the desktop, in screen pixels.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MonitorArea {
+    pub name: String,
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+}
+
+impl MonitorArea {
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        x >= self.x && x < self.x + self.width && y >= self.y && y < self.y + self.height
+    }
+}
+
+static RESTORE: Mutex<BTreeMap<u64, UiGeometry>> = Mutex::new(BTreeMap::new());
+static MONITOR: Mutex<BTreeMap<u64, MonitorArea>> = Mutex::new(BTreeMap::new());
+
+/// Set (or clear) the geometry the next UI window of `instance_id` opens with.
+pub fn set_restore(instance_id: u64, geometry: Option<UiGeometry>) {
+    let mut restore = RESTORE.lock().unwrap();
+    match geometry {
+        Some(geometry) => {
+            restore.insert(instance_id, geometry);
+        }
+        None => {
+            restore.remove(&instance_id);
+        }
+    }
+}
+
+/// The geometry a new UI window of `instance_id` should open with, if any.
+pub fn restore_for(instance_id: u64) -> Option<UiGeometry> {
+    RESTORE.lock().unwrap().get(&instance_id).copied()
+}
+
+/// Set (or clear) the monitor the next UI window of `instance_id` opens on.
+pub fn set_monitor(instance_id: u64, monitor: Option<MonitorArea>) {
+    let mut monitors = MONITOR.lock().unwrap();
+    match monitor {
+        Some(monitor) => {
+            monitors.insert(instance_id, monitor);
+        }
+        None => {
+            monitors.remove(&instance_id);
+        }
+    }
+}
+
+/// Where a new UI window of `instance_id` opens, given its own size when
+/// nothing was saved. `None` leaves it to the window manager.
+pub fn placement_for(instance_id: u64, width: i32, height: i32) -> Option<UiGeometry> {
+    let monitor = MONITOR.lock().unwrap().get(&instance_id).cloned();
+    place(restore_for(instance_id), monitor.as_ref(), width, height)
+}
+
+/// The saved geometry when it is on `monitor`, otherwise the window
+/// centered on `monitor` at its saved size, or `width` x `height` without
+/// one, shrunk to fit.
+pub fn place(
+    saved: Option<UiGeometry>,
+    monitor: Option<&MonitorArea>,
+    width: i32,
+    height: i32,
+) -> Option<UiGeometry> {
+    let Some(monitor) = monitor else {
+        return saved;
+    };
+    if let Some(saved) = saved
+        && saved.monitor(std::slice::from_ref(monitor)).is_some()
+    {
+        return Some(saved);
+    }
+    let (width, height) = saved.map_or((width, height), |g| (g.width, g.height));
+    let (width, height) = (
+        width.min(monitor.width).max(1),
+        height.min(monitor.height).max(1),
+    );
+    Some(UiGeometry {
+        x: monitor.x + (monitor.width - width) / 2,
+        y: monitor.y + (monitor.height - height) / 2,
+        width,
+        height,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn monitors() -> Vec<MonitorArea> {
+        vec![
+            MonitorArea {
+                name: "DP-1".into(),
+                x: 0,
+                y: 0,
+                width: 2560,
+                height: 1440,
+            },
+            MonitorArea {
+                name: "HDMI-1".into(),
+                x: 2560,
+                y: 0,
+                width: 3840,
+                height: 2160,
+            },
+        ]
+    }
+
+    #[test]
+    fn test_window_is_on_the_monitor_showing_its_middle() {
+        let monitors = monitors();
+        let straddling = UiGeometry {
+            x: 2000,
+            y: 100,
+            width: 800,
+            height: 400,
+        };
+        assert_eq!(straddling.monitor(&monitors).unwrap().name, "DP-1");
+        let moved = UiGeometry {
+            x: 2300,
+            ..straddling
+        };
+        assert_eq!(moved.monitor(&monitors).unwrap().name, "HDMI-1");
+        let off_screen = UiGeometry {
+            x: -5000,
+            ..straddling
+        };
+        assert_eq!(off_screen.monitor(&monitors), None);
+    }
+
+    #[test]
+    fn test_saved_geometry_on_the_monitor_is_kept() {
+        let monitors = monitors();
+        let saved = UiGeometry {
+            x: 100,
+            y: 200,
+            width: 640,
+            height: 480,
+        };
+        assert_eq!(
+            place(Some(saved), Some(&monitors[0]), 300, 300),
+            Some(saved)
+        );
+        assert_eq!(place(Some(saved), None, 300, 300), Some(saved));
+        assert_eq!(place(None, None, 300, 300), None);
+    }
+
+    #[test]
+    fn test_window_is_centered_on_another_monitor() {
+        let monitors = monitors();
+        let saved = UiGeometry {
+            x: 100,
+            y: 200,
+            width: 640,
+            height: 480,
+        };
+        assert_eq!(
+            place(Some(saved), Some(&monitors[1]), 300, 300),
+            Some(UiGeometry {
+                x: 2560 + (3840 - 640) / 2,
+                y: (2160 - 480) / 2,
+                width: 640,
+                height: 480,
+            })
+        );
+        // Without a saved size the window's own is used, shrunk to fit.
+        assert_eq!(
+            place(None, Some(&monitors[0]), 4000, 300),
+            Some(UiGeometry {
+                x: 0,
+                y: (1440 - 300) / 2,
+                width: 2560,
+                height: 300,
+            })
+        );
+    }
+}
@@ -0,0 +1,466 @@
+//! Portable configuration: the whole setup in one file, to carry it to
+//! another machine.
+//!
+//! A `.zestbay` file holds what a session does plus the preferences and
+//! plugin and chain presets, and lists the devices and streams of the
+//! machine it was exported on. The other machine's devices have other
+//! names, so on import each listed node is matched to one present there, by
+//! name, then by description, then as the only device of its type left on
+//! both sides; references to a matched node under its old name, description
+//! or layout key are rewritten to the new ones.
+//!
+//! Preferences that only make sense on one machine, such as the PipeWire
+//! instance or access tokens, are left out of the file and kept on import.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::config::VERSION_KEY;
+use crate::graph::NodeType;
+use crate::session::SESSION_FILES;
+
+pub const CONFIG_BUNDLE_EXTENSION: &str = "zestbay";
+pub const CONFIG_BUNDLE_KIND: &str = "zestbay-config";
+pub const CONFIG_BUNDLE_VERSION: u32 = 1;
+
+/// The config files a bundle carries: a session's, and these.
+pub const EXTRA_BUNDLE_FILES: [&str; 6] = [
+    "preferences.json",
+    "plugin_presets.json",
+    "chain_presets.json",
+    "pinned.json",
+    "quick_params.json",
+    "scene_transitions.json",
+];
+
+/// The files that refer to nodes by name, description or layout key, and
+/// are rewritten for the nodes matched on import.
+pub const REMAPPED_FILES: [&str; 7] = [
+    "links.json",
+    "sidechains.json",
+    "link_gains.json",
+    "rules.json",
+    "layout.json",
+    "hidden.json",
+    "pinned.json",
+];
+
+/// Preferences that stay with the machine.
+pub const LOCAL_PREFERENCES: [&str; 21] = [
+    "pipewire_remote",
+    "compare_remote",
+    "remote_instance",
+    "remote_instance_token",
+    "remote_instance_cert",
+    "plugin_ui_monitor",
+    "mqtt_enabled",
+    "mqtt_broker",
+    "mqtt_topic",
+    "mqtt_username",
+    "mqtt_password",
+    "mqtt_tls",
+    "mqtt_tls_ca",
+    "mqtt_scope",
+    "remote_api_enabled",
+    "remote_api_bind",
+    "remote_api_token",
+    "remote_api_tokens",
+    "remote_api_tls_cert",
+    "remote_api_tls_key",
+    "remote_api_cors_origin",
+];
+
+/// Every file a bundle can carry.
+pub fn bundle_files() -> impl Iterator<Item = &'static str> {
+    SESSION_FILES.into_iter().chain(EXTRA_BUNDLE_FILES)
+}
+
+/// A node as the bundle records it: what other files may refer to it by.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BundleNode {
+    pub name: String,
+    pub description: String,
+    pub node_type: Option<NodeType>,
+    /// Its key in `layout.json`, `hidden.json` and `pinned.json`.
+    pub layout_key: String,
+}
+
+impl BundleNode {
+    fn is_device(&self) -> bool {
+        matches!(
+            self.node_type,
+            Some(NodeType::Sink | NodeType::Source | NodeType::Duplex)
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConfigBundle {
+    pub kind: String,
+    pub version: u32,
+    /// When it was exported, in seconds since the Unix epoch.
+    #[serde(default)]
+    pub exported: u64,
+    /// The devices and streams of the exporting machine, plugins aside.
+    #[serde(default)]
+    pub nodes: Vec<BundleNode>,
+    /// The contents of each of [`bundle_files`] that existed, by name.
+    #[serde(default)]
+    pub files: BTreeMap<String, Value>,
+}
+
+impl ConfigBundle {
+    pub fn new(exported: u64, nodes: Vec<BundleNode>, files: BTreeMap<String, Value>) -> Self {
+        let mut bundle = Self {
+            kind: CONFIG_BUNDLE_KIND.to_string(),
+            version: CONFIG_BUNDLE_VERSION,
+            exported,
+            nodes,
+            files,
+        };
+        if let Some(prefs) = bundle.files.get_mut("preferences.json")
+            && let Some(prefs) = payload_mut(prefs).as_object_mut()
+        {
+            for key in LOCAL_PREFERENCES {
+                prefs.remove(key);
+            }
+        }
+        bundle
+    }
+
+    pub fn render(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let bundle: Self = serde_json::from_str(text)
+            .map_err(|e| format!("not a ZestBay configuration file: {}", e))?;
+        if bundle.kind != CONFIG_BUNDLE_KIND {
+            return Err(format!(
+                "not a ZestBay configuration file (kind \"{}\")",
+                bundle.kind
+            ));
+        }
+        if bundle.version > CONFIG_BUNDLE_VERSION {
+            return Err(format!(
+                "configuration file version {} is newer than this ZestBay supports",
+                bundle.version
+            ));
+        }
+        Ok(bundle)
+    }
+
+    /// Each of [`bundle_files`] with what the bundle holds for it; `None`
+    /// for files it has none of, which are removed on import. Entries under
+    /// any other name are ignored.
+    pub fn contents(&self) -> impl Iterator<Item = (&'static str, Option<&Value>)> {
+        bundle_files().map(|name| (name, self.files.get(name)))
+    }
+
+    /// Rewrite references to the nodes in `remap` in the files that have
+    /// them. Returns how many were rewritten.
+    pub fn remap(&mut self, remap: &NodeRemap) -> usize {
+        let mut count = 0;
+        for name in REMAPPED_FILES {
+            if let Some(value) = self.files.get_mut(name) {
+                count += rename_strings(value, &remap.renames);
+            }
+        }
+        count
+    }
+
+    /// Carry this machine's [`LOCAL_PREFERENCES`] over from `local`, the
+    /// preferences file as it is now.
+    pub fn keep_local_preferences(&mut self, local: &Value) {
+        let Some(local) = local_payload(local).as_object() else {
+            return;
+        };
+        let prefs = self
+            .files
+            .entry("preferences.json".to_string())
+            .or_insert_with(|| Value::Object(Default::default()));
+        let Some(prefs) = payload_mut(prefs).as_object_mut() else {
+            return;
+        };
+        for key in LOCAL_PREFERENCES {
+            match local.get(key) {
+                Some(value) => prefs.insert(key.to_string(), value.clone()),
+                None => prefs.remove(key),
+            };
+        }
+    }
+}
+
+/// The payload of a file saved through a [`crate::config::ConfigSchema`],
+/// or the file itself if it was saved bare.
+fn payload_mut(value: &mut Value) -> &mut Value {
+    if value.get(VERSION_KEY).is_some() && value.get("data").is_some() {
+        &mut value["data"]
+    } else {
+        value
+    }
+}
+
+fn local_payload(value: &Value) -> &Value {
+    match value.get("data") {
+        Some(data) if value.get(VERSION_KEY).is_some() => data,
+        _ => value,
+    }
+}
+
+/// How the exporting machine's nodes map onto this one's.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NodeRemap {
+    /// Old name, description or layout key, to the new one.
+    pub renames: BTreeMap<String, String>,
+    /// Exported nodes found here.
+    pub matched: usize,
+    /// Descriptions of the exported devices not found here.
+    pub missing: Vec<String>,
+}
+
+/// Match the `exported` nodes to the `local` ones.
+pub fn remap_nodes(exported: &[BundleNode], local: &[BundleNode]) -> NodeRemap {
+    let mut pairs: Vec<Option<usize>> = vec![None; exported.len()];
+    let mut used = vec![false; local.len()];
+    // By name, then by description.
+    let matchers: [fn(&BundleNode, &BundleNode) -> bool; 2] = [
+        |a, b| a.name == b.name,
+        |a, b| a.description.eq_ignore_ascii_case(&b.description),
+    ];
+    for same in matchers {
+        for (e, node) in exported.iter().enumerate() {
+            if pairs[e].is_some() {
+                continue;
+            }
+            let found = (0..local.len()).find(|&l| {
+                !used[l] && node.node_type == local[l].node_type && same(node, &local[l])
+            });
+            if let Some(l) = found {
+                used[l] = true;
+                pairs[e] = Some(l);
+            }
+        }
+    }
+    // A device left without a match on both sides, such as the one audio
+    // interface of each machine, is taken to be the same.
+    let mut by_type: BTreeMap<NodeType, (Vec<usize>, Vec<usize>)> = BTreeMap::new();
+    for (e, node) in exported.iter().enumerate() {
+        if pairs[e].is_none() && node.is_device() {
+            by_type
+                .entry(node.node_type.unwrap())
+                .or_default()
+                .0
+                .push(e);
+        }
+    }
+    for (l, node) in local.iter().enumerate() {
+        if !used[l] && node.is_device() {
+            by_type
+                .entry(node.node_type.unwrap())
+                .or_default()
+                .1
+                .push(l);
+        }
+    }
+    for (left, right) in by_type.values() {
+        if let ([e], [l]) = (left.as_slice(), right.as_slice()) {
+            pairs[*e] = Some(*l);
+        }
+    }
+
+    // Names still in use here are not renamed, or what refers to them
+    // would move to another node.
+    let taken = |s: &str| {
+        local
+            .iter()
+            .any(|n| n.name == s || n.description == s || n.layout_key == s)
+    };
+    let mut remap = NodeRemap::default();
+    for (node, found) in exported.iter().zip(&pairs) {
+        let Some(l) = *found else {
+            if node.is_device() {
+                remap.missing.push(node.description.clone());
+            }
+            continue;
+        };
+        remap.matched += 1;
+        let to = &local[l];
+        // Rules and links name nodes by the identity behind a `Node:` key.
+        let identity = |n: &BundleNode| n.layout_key.strip_prefix("Node:").map(str::to_string);
+        let identities = identity(node).zip(identity(to));
+        for (old, new) in [
+            (&node.name, &to.name),
+            (&node.description, &to.description),
+            (&node.layout_key, &to.layout_key),
+        ]
+        .into_iter()
+        .chain(identities.as_ref().map(|(old, new)| (old, new)))
+        {
+            if old != new && !old.is_empty() && !taken(old) {
+                remap
+                    .renames
+                    .entry(old.clone())
+                    .or_insert_with(|| new.clone());
+            }
+        }
+    }
+    remap
+}
+
+/// Replace every string, and object key, equal to one of `renames`. Returns
+/// how many were replaced.
+pub fn rename_strings(value: &mut Value, renames: &BTreeMap<String, String>) -> usize {
+    match value {
+        Value::String(s) => match renames.get(s.as_str()) {
+            Some(new) => {
+                *s = new.clone();
+                1
+            }
+            None => 0,
+        },
+        Value::Array(items) => items.iter_mut().map(|v| rename_strings(v, renames)).sum(),
+        Value::Object(map) => {
+            let mut count = 0;
+            let entries = std::mem::take(map);
+            for (key, mut v) in entries {
+                count += rename_strings(&mut v, renames);
+                let key = match renames.get(&key) {
+                    Some(new) => {
+                        count += 1;
+                        new.clone()
+                    }
+                    None => key,
+                };
+                map.insert(key, v);
+            }
+            count
+        }
+        _ => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(name: &str, description: &str, node_type: NodeType, key: &str) -> BundleNode {
+        BundleNode {
+            name: name.to_string(),
+            description: description.to_string(),
+            node_type: Some(node_type),
+            layout_key: key.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_nodes_match_by_name_description_then_lone_device() {
+        let exported = vec![
+            node("firefox", "Firefox", NodeType::StreamOutput, "Node:a"),
+            node("alsa_output.usb-X", "Studio Out", NodeType::Sink, "Node:b"),
+            node("alsa_input.usb-X", "Studio Mic", NodeType::Source, "Node:c"),
+            node("bluez_output.1", "Headphones", NodeType::Sink, "Node:d"),
+        ];
+        let local = vec![
+            node("firefox", "Firefox", NodeType::StreamOutput, "Node:1"),
+            node(
+                "alsa_output.pci",
+                "Built-in Audio",
+                NodeType::Sink,
+                "Node:2",
+            ),
+            node("bluez_output.2", "Headphones", NodeType::Sink, "Node:3"),
+            node("alsa_input.pci", "Built-in Mic", NodeType::Source, "Node:4"),
+            node("alsa_input.usb-Y", "Webcam Mic", NodeType::Source, "Node:5"),
+        ];
+        let remap = remap_nodes(&exported, &local);
+        assert_eq!(remap.renames["Node:a"], "Node:1");
+        assert_eq!(remap.renames["a"], "1");
+        assert_eq!(remap.renames["bluez_output.1"], "bluez_output.2");
+        // The lone sink left on each side pairs up.
+        assert_eq!(remap.renames["alsa_output.usb-X"], "alsa_output.pci");
+        assert_eq!(remap.renames["Studio Out"], "Built-in Audio");
+        // Two sources are left here, so the mic is not guessed.
+        assert_eq!(remap.missing, vec!["Studio Mic".to_string()]);
+        assert_eq!(remap.matched, 3);
+        assert!(!remap.renames.contains_key("firefox"));
+        assert!(!remap.renames.contains_key("Headphones"));
+    }
+
+    #[test]
+    fn test_remap_rewrites_references_in_node_files_only() {
+        let mut files = BTreeMap::new();
+        files.insert(
+            "links.json".to_string(),
+            serde_json::json!([{"output_node_name": "eq", "input_node_name": "old_sink"}]),
+        );
+        files.insert(
+            "layout.json".to_string(),
+            serde_json::json!({"schema_version": 1, "data": {"Node:b": [10.0, 20.0]}}),
+        );
+        files.insert(
+            "plugins.json".to_string(),
+            serde_json::json!([{"display_name": "old_sink"}]),
+        );
+        let mut bundle = ConfigBundle::new(0, Vec::new(), files);
+        let remap = NodeRemap {
+            renames: [("old_sink", "new_sink"), ("Node:b", "Node:2")]
+                .into_iter()
+                .map(|(a, b)| (a.to_string(), b.to_string()))
+                .collect(),
+            ..Default::default()
+        };
+        assert_eq!(bundle.remap(&remap), 2);
+        assert_eq!(bundle.files["links.json"][0]["input_node_name"], "new_sink");
+        assert_eq!(
+            bundle.files["layout.json"]["data"]["Node:2"],
+            serde_json::json!([10.0, 20.0])
+        );
+        assert_eq!(bundle.files["plugins.json"][0]["display_name"], "old_sink");
+    }
+
+    #[test]
+    fn test_local_preferences_stay_on_the_machine() {
+        let mut files = BTreeMap::new();
+        files.insert(
+            "preferences.json".to_string(),
+            serde_json::json!({"schema_version": 1, "data": {
+                "pipewire_remote": "pipewire-studio",
+                "remote_api_token": "secret",
+                "remote_api_enabled": true,
+                "remote_api_bind": "0.0.0.0:7380",
+                "mqtt_enabled": true,
+                "mqtt_broker": "broker.example:1883",
+                "mqtt_topic": "studio",
+                "poll_interval_ms": 50
+            }}),
+        );
+        let mut bundle =
+            ConfigBundle::parse(&ConfigBundle::new(1, Vec::new(), files).render()).unwrap();
+        let prefs = &bundle.files["preferences.json"]["data"];
+        assert_eq!(prefs, &serde_json::json!({"poll_interval_ms": 50}));
+
+        bundle.keep_local_preferences(&serde_json::json!({
+            "remote_api_token": "mine",
+            "remote_api_enabled": false,
+            "remote_api_bind": "127.0.0.1:7380",
+            "mqtt_enabled": false,
+            "mqtt_broker": "localhost:1883",
+            "mqtt_topic": "zestbay"
+        }));
+        let prefs = &bundle.files["preferences.json"]["data"];
+        assert_eq!(prefs["remote_api_token"], "mine");
+        assert_eq!(prefs["remote_api_enabled"], false);
+        assert_eq!(prefs["remote_api_bind"], "127.0.0.1:7380");
+        assert_eq!(prefs["mqtt_enabled"], false);
+        assert_eq!(prefs["mqtt_broker"], "localhost:1883");
+        assert_eq!(prefs["mqtt_topic"], "zestbay");
+        assert_eq!(prefs["poll_interval_ms"], 50);
+        assert!(prefs.get("pipewire_remote").is_none());
+
+        assert!(ConfigBundle::parse(r#"{"kind":"zestbay-session","version":1}"#).is_err());
+    }
+}
@@ -0,0 +1,198 @@
+//! Performance profiles: how often ZestBay wakes up.
+//!
+//! The UI poll, the PipeWire thread's tick, the plugin meters and graph
+//! repaints each run on a timer. A profile sets all four at once:
+//! `balanced` is the intervals in the preferences, `powersave` stretches
+//! them for laptops on battery, and `performance` shortens them for
+//! smoother meters and dragging at the cost of more wakeups.
+
+use serde::{Deserialize, Serialize};
+
+/// How often plugin meters are read on the balanced profile.
+pub const DEFAULT_METER_INTERVAL_MS: u64 = 33;
+
+/// The shortest time between graph repaints on the balanced profile.
+pub const DEFAULT_REPAINT_INTERVAL_MS: u64 = 16;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PerformanceProfile {
+    Powersave,
+    #[default]
+    Balanced,
+    Performance,
+}
+
+impl PerformanceProfile {
+    pub const ALL: [PerformanceProfile; 3] = [
+        PerformanceProfile::Powersave,
+        PerformanceProfile::Balanced,
+        PerformanceProfile::Performance,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PerformanceProfile::Powersave => "powersave",
+            PerformanceProfile::Balanced => "balanced",
+            PerformanceProfile::Performance => "performance",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|p| p.as_str() == s)
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            PerformanceProfile::Powersave => "Power Saving",
+            PerformanceProfile::Balanced => "Balanced",
+            PerformanceProfile::Performance => "Performance",
+        }
+    }
+
+    /// The intervals of this profile, given the balanced ones.
+    pub fn settings(self, balanced: ProfileSettings) -> ProfileSettings {
+        match self {
+            PerformanceProfile::Balanced => balanced,
+            PerformanceProfile::Powersave => ProfileSettings {
+                poll_interval_ms: balanced.poll_interval_ms.max(250),
+                pw_tick_interval_ms: balanced.pw_tick_interval_ms.max(50),
+                meter_interval_ms: balanced.meter_interval_ms.max(100),
+                repaint_interval_ms: balanced.repaint_interval_ms.max(50),
+            },
+            PerformanceProfile::Performance => ProfileSettings {
+                poll_interval_ms: balanced.poll_interval_ms.min(33),
+                pw_tick_interval_ms: balanced.pw_tick_interval_ms.min(5),
+                meter_interval_ms: balanced.meter_interval_ms.min(16),
+                repaint_interval_ms: balanced.repaint_interval_ms.min(8),
+            },
+        }
+    }
+}
+
+/// The timer intervals a profile sets, in milliseconds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProfileSettings {
+    pub poll_interval_ms: u64,
+    pub pw_tick_interval_ms: u64,
+    pub meter_interval_ms: u64,
+    pub repaint_interval_ms: u64,
+}
+
+/// Picks the profile in use from the chosen one and the power source.
+///
+/// On battery the battery profile takes over, unless a profile is picked
+/// by hand while on battery; that pick holds until the power source
+/// changes again.
+#[derive(Debug, Default)]
+pub struct ProfileChooser {
+    on_battery: bool,
+    picked: bool,
+}
+
+impl ProfileChooser {
+    /// Note the power source. Returns true when it changed.
+    pub fn set_on_battery(&mut self, on_battery: bool) -> bool {
+        if self.on_battery == on_battery {
+            return false;
+        }
+        self.on_battery = on_battery;
+        self.picked = false;
+        true
+    }
+
+    pub fn on_battery(&self) -> bool {
+        self.on_battery
+    }
+
+    /// Note that a profile was picked by hand.
+    pub fn picked(&mut self) {
+        self.picked = true;
+    }
+
+    pub fn active(
+        &self,
+        chosen: PerformanceProfile,
+        battery: Option<PerformanceProfile>,
+    ) -> PerformanceProfile {
+        match battery {
+            Some(profile) if self.on_battery && !self.picked => profile,
+            _ => chosen,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn balanced() -> ProfileSettings {
+        ProfileSettings {
+            poll_interval_ms: 100,
+            pw_tick_interval_ms: 10,
+            meter_interval_ms: DEFAULT_METER_INTERVAL_MS,
+            repaint_interval_ms: DEFAULT_REPAINT_INTERVAL_MS,
+        }
+    }
+
+    #[test]
+    fn test_profiles_stretch_or_shorten_every_interval() {
+        let base = balanced();
+        let save = PerformanceProfile::Powersave.settings(base);
+        let fast = PerformanceProfile::Performance.settings(base);
+        assert_eq!(PerformanceProfile::Balanced.settings(base), base);
+        assert!(save.poll_interval_ms > base.poll_interval_ms);
+        assert!(save.pw_tick_interval_ms > base.pw_tick_interval_ms);
+        assert!(save.meter_interval_ms > base.meter_interval_ms);
+        assert!(save.repaint_interval_ms > base.repaint_interval_ms);
+        assert!(fast.poll_interval_ms < base.poll_interval_ms);
+        assert!(fast.pw_tick_interval_ms < base.pw_tick_interval_ms);
+        assert!(fast.meter_interval_ms < base.meter_interval_ms);
+        assert!(fast.repaint_interval_ms < base.repaint_interval_ms);
+
+        // Preferences already past a profile's bound are kept.
+        let slow = ProfileSettings {
+            poll_interval_ms: 500,
+            ..base
+        };
+        assert_eq!(
+            PerformanceProfile::Powersave
+                .settings(slow)
+                .poll_interval_ms,
+            500
+        );
+    }
+
+    #[test]
+    fn test_profile_names_round_trip() {
+        for profile in PerformanceProfile::ALL {
+            assert_eq!(PerformanceProfile::parse(profile.as_str()), Some(profile));
+            let json = serde_json::to_string(&profile).unwrap();
+            assert_eq!(json, format!("\"{}\"", profile.as_str()));
+        }
+        assert_eq!(PerformanceProfile::parse("turbo"), None);
+        assert_eq!(PerformanceProfile::default(), PerformanceProfile::Balanced);
+    }
+
+    #[test]
+    fn test_battery_profile_applies_until_a_profile_is_picked() {
+        use PerformanceProfile::*;
+        let mut chooser = ProfileChooser::default();
+        let battery = Some(Powersave);
+        assert_eq!(chooser.active(Balanced, battery), Balanced);
+
+        assert!(chooser.set_on_battery(true));
+        assert!(!chooser.set_on_battery(true));
+        assert_eq!(chooser.active(Balanced, battery), Powersave);
+        assert_eq!(chooser.active(Balanced, None), Balanced);
+
+        chooser.picked();
+        assert_eq!(chooser.active(Performance, battery), Performance);
+
+        // Back on AC and off again: the battery profile applies again.
+        assert!(chooser.set_on_battery(false));
+        assert!(chooser.set_on_battery(true));
+        assert_eq!(chooser.active(Performance, battery), Powersave);
+    }
+}
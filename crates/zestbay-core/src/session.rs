@@ -0,0 +1,190 @@
+//! Named sessions: a whole patch saved under a name.
+//!
+//! The working state is always the config files ZestBay reads at startup.
+//! A session is a copy of the ones that make up the patch (plugins and
+//! their parameters, links, rules and layout) bundled into one
+//! `.zestsession` file in `sessions/`. Loading one writes its files back
+//! and restarts ZestBay on them, the way switching PipeWire instances does.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+pub const SESSION_FILE_EXTENSION: &str = "zestsession";
+pub const SESSION_FILE_KIND: &str = "zestbay-session";
+pub const SESSION_FILE_VERSION: u32 = 1;
+
+/// The config files a session carries.
+pub const SESSION_FILES: [&str; 9] = [
+    "plugins.json",
+    "links.json",
+    "sidechains.json",
+    "link_gains.json",
+    "midi_mappings.json",
+    "rules.json",
+    "layout.json",
+    "hidden.json",
+    "viewport.json",
+];
+
+/// How many sessions the recent list keeps.
+pub const MAX_RECENT_SESSIONS: usize = 10;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionFile {
+    pub kind: String,
+    pub version: u32,
+    pub name: String,
+    /// When it was saved, in seconds since the Unix epoch.
+    #[serde(default)]
+    pub saved: u64,
+    /// The contents of each of [`SESSION_FILES`] that existed, by name.
+    #[serde(default)]
+    pub files: BTreeMap<String, serde_json::Value>,
+}
+
+impl SessionFile {
+    pub fn new(name: &str, saved: u64, files: BTreeMap<String, serde_json::Value>) -> Self {
+        Self {
+            kind: SESSION_FILE_KIND.to_string(),
+            version: SESSION_FILE_VERSION,
+            name: name.to_string(),
+            saved,
+            files,
+        }
+    }
+
+    pub fn render(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let file: Self =
+            serde_json::from_str(text).map_err(|e| format!("not a session file: {}", e))?;
+        if file.kind != SESSION_FILE_KIND {
+            return Err(format!("not a session file (kind \"{}\")", file.kind));
+        }
+        if file.version > SESSION_FILE_VERSION {
+            return Err(format!(
+                "session file version {} is newer than this ZestBay supports",
+                file.version
+            ));
+        }
+        Ok(file)
+    }
+
+    /// Each of [`SESSION_FILES`] with what the session holds for it; `None`
+    /// for files it has none of, which are removed on load. Entries under
+    /// any other name are ignored, so a session cannot write elsewhere.
+    pub fn contents(&self) -> impl Iterator<Item = (&'static str, Option<&serde_json::Value>)> {
+        SESSION_FILES
+            .iter()
+            .map(|&name| (name, self.files.get(name)))
+    }
+}
+
+/// The file a session named `name` is saved in, or `None` for a name with
+/// nothing usable in it.
+pub fn session_file_name(name: &str) -> Option<String> {
+    let name = name.trim();
+    if name.is_empty() || name.chars().all(|c| c == '.') {
+        return None;
+    }
+    let safe: String = name
+        .chars()
+        .map(|c| if c == '/' || c.is_control() { '_' } else { c })
+        .collect();
+    Some(format!("{}.{}", safe, SESSION_FILE_EXTENSION))
+}
+
+/// The session in use and the ones opened lately: `sessions.json`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionIndex {
+    /// The session last saved or loaded; `None` after New Session.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub current: Option<String>,
+    /// Most recent first.
+    #[serde(default)]
+    pub recent: Vec<String>,
+}
+
+impl SessionIndex {
+    /// Make `name` the current session and the most recent one.
+    pub fn opened(&mut self, name: &str) {
+        self.recent.retain(|n| n != name);
+        self.recent.insert(0, name.to_string());
+        self.recent.truncate(MAX_RECENT_SESSIONS);
+        self.current = Some(name.to_string());
+    }
+
+    /// Drop a deleted session from the list.
+    pub fn forget(&mut self, name: &str) {
+        self.recent.retain(|n| n != name);
+        if self.current.as_deref() == Some(name) {
+            self.current = None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sessions_round_trip_and_only_carry_known_files() {
+        let mut files = BTreeMap::new();
+        files.insert(
+            "plugins.json".to_string(),
+            serde_json::json!([{"uri": "urn:eq"}]),
+        );
+        files.insert("../../.bashrc".to_string(), serde_json::json!("echo hi"));
+        let session = SessionFile::new("Live set", 1_700_000_000, files);
+        let back = SessionFile::parse(&session.render()).unwrap();
+        assert_eq!(back, session);
+
+        let contents: Vec<_> = back.contents().collect();
+        assert_eq!(contents.len(), SESSION_FILES.len());
+        assert!(contents.iter().all(|(name, _)| !name.contains('/')));
+        assert_eq!(contents[0].1, Some(&serde_json::json!([{"uri": "urn:eq"}])));
+        assert_eq!(contents[1], ("links.json", None));
+
+        assert!(SessionFile::parse(r#"{"kind":"zestbay-chain","version":1,"name":"x"}"#).is_err());
+        assert!(
+            SessionFile::parse(r#"{"kind":"zestbay-session","version":99,"name":"x"}"#).is_err()
+        );
+    }
+
+    #[test]
+    fn test_session_names_become_safe_file_names() {
+        assert_eq!(
+            session_file_name("  Live set ").as_deref(),
+            Some("Live set.zestsession")
+        );
+        assert_eq!(
+            session_file_name("a/b\n").as_deref(),
+            Some("a_b.zestsession")
+        );
+        assert_eq!(session_file_name(" "), None);
+        assert_eq!(session_file_name(".."), None);
+    }
+
+    #[test]
+    fn test_recent_sessions_move_to_the_front_and_are_capped() {
+        let mut index = SessionIndex::default();
+        for i in 0..12 {
+            index.opened(&format!("s{}", i));
+        }
+        assert_eq!(index.recent.len(), MAX_RECENT_SESSIONS);
+        assert_eq!(index.recent[0], "s11");
+        index.opened("s5");
+        assert_eq!(index.recent[..2], ["s5", "s11"]);
+        assert_eq!(index.recent.len(), MAX_RECENT_SESSIONS);
+
+        index.forget("s5");
+        assert_eq!(index.current, None);
+        assert_eq!(index.recent[0], "s11");
+        index.forget("s11");
+        assert_eq!(index.recent[0], "s10");
+    }
+}
@@ -0,0 +1,250 @@
+//! Graph view and window state the UI saves between sessions.
+//!
+//! QML hands these over as JSON strings. Each type here is what the Rust
+//! side accepts for one file: [`UiState::parse`] deserializes and validates
+//! the string, and only the re-serialized value reaches disk, so a bad
+//! payload from QML is rejected instead of written as is. Loading goes
+//! through the same checks, and every file is versioned by its
+//! [`ConfigSchema`].
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::config::{self, ConfigSchema};
+
+/// Smallest and largest graph view zoom, as clamped by the view.
+pub const MIN_ZOOM: f64 = 0.25;
+pub const MAX_ZOOM: f64 = 3.0;
+/// Larger than any real window, small enough to catch garbage.
+pub const MAX_WINDOW_SIZE: u32 = 32768;
+const MAX_KEY_LEN: usize = 1024;
+
+#[derive(Debug)]
+pub enum UiStateError {
+    Parse(serde_json::Error),
+    /// The JSON has the right shape but `field` holds an unusable value.
+    Invalid {
+        field: String,
+        reason: &'static str,
+    },
+}
+
+impl UiStateError {
+    fn invalid(field: impl Into<String>, reason: &'static str) -> Self {
+        Self::Invalid {
+            field: field.into(),
+            reason,
+        }
+    }
+}
+
+impl fmt::Display for UiStateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Parse(e) => write!(f, "invalid JSON: {}", e),
+            Self::Invalid { field, reason } => write!(f, "{}: {}", field, reason),
+        }
+    }
+}
+
+impl std::error::Error for UiStateError {}
+
+impl From<serde_json::Error> for UiStateError {
+    fn from(e: serde_json::Error) -> Self {
+        Self::Parse(e)
+    }
+}
+
+/// One kind of saved UI state.
+pub trait UiState: Serialize + DeserializeOwned + Default {
+    const SCHEMA: ConfigSchema;
+    /// File name under the config directory.
+    const FILE: &'static str;
+
+    /// Reject values the UI could not have meant to save.
+    fn validate(&self) -> Result<(), UiStateError>;
+
+    fn parse(json: &str) -> Result<Self, UiStateError> {
+        let value: Self = serde_json::from_str(json)?;
+        value.validate()?;
+        Ok(value)
+    }
+}
+
+fn check_key(field: &str, key: &str) -> Result<(), UiStateError> {
+    if key.is_empty() {
+        return Err(UiStateError::invalid(field, "empty node key"));
+    }
+    if key.len() > MAX_KEY_LEN {
+        return Err(UiStateError::invalid(field, "node key too long"));
+    }
+    Ok(())
+}
+
+/// Node positions by layout key: `layout.json`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct NodeLayout(pub BTreeMap<String, [f64; 2]>);
+
+impl UiState for NodeLayout {
+    const SCHEMA: ConfigSchema = config::LAYOUT;
+    const FILE: &'static str = "layout.json";
+
+    fn validate(&self) -> Result<(), UiStateError> {
+        for (key, pos) in &self.0 {
+            check_key(key, key)?;
+            if !pos.iter().all(|v| v.is_finite()) {
+                return Err(UiStateError::invalid(
+                    key.as_str(),
+                    "position is not finite",
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A set of layout keys: `hidden.json` and `pinned.json`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct NodeKeys(pub Vec<String>);
+
+impl NodeKeys {
+    fn check(&self, name: &str) -> Result<(), UiStateError> {
+        for (i, key) in self.0.iter().enumerate() {
+            check_key(&format!("{}[{}]", name, i), key)?;
+        }
+        Ok(())
+    }
+}
+
+/// Nodes hidden from the graph view.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct HiddenNodes(pub NodeKeys);
+
+impl UiState for HiddenNodes {
+    const SCHEMA: ConfigSchema = config::HIDDEN;
+    const FILE: &'static str = "hidden.json";
+
+    fn validate(&self) -> Result<(), UiStateError> {
+        self.0.check("hidden")
+    }
+}
+
+/// Nodes auto-layout leaves where they are.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct PinnedNodes(pub NodeKeys);
+
+impl UiState for PinnedNodes {
+    const SCHEMA: ConfigSchema = config::PINNED;
+    const FILE: &'static str = "pinned.json";
+
+    fn validate(&self) -> Result<(), UiStateError> {
+        self.0.check("pinned")
+    }
+}
+
+/// Graph view pan and zoom: `viewport.json`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Viewport {
+    pub pan_x: f64,
+    pub pan_y: f64,
+    pub zoom: f64,
+}
+
+impl Default for Viewport {
+    fn default() -> Self {
+        Self {
+            pan_x: 0.0,
+            pan_y: 0.0,
+            zoom: 1.0,
+        }
+    }
+}
+
+impl UiState for Viewport {
+    const SCHEMA: ConfigSchema = config::VIEWPORT;
+    const FILE: &'static str = "viewport.json";
+
+    fn validate(&self) -> Result<(), UiStateError> {
+        if !self.pan_x.is_finite() || !self.pan_y.is_finite() {
+            return Err(UiStateError::invalid("pan", "not finite"));
+        }
+        if !(MIN_ZOOM..=MAX_ZOOM).contains(&self.zoom) {
+            return Err(UiStateError::invalid("zoom", "out of range"));
+        }
+        Ok(())
+    }
+}
+
+/// Main window position and size: `window.json`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct WindowGeometry {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl UiState for WindowGeometry {
+    const SCHEMA: ConfigSchema = config::WINDOW;
+    const FILE: &'static str = "window.json";
+
+    fn validate(&self) -> Result<(), UiStateError> {
+        for (field, size) in [("width", self.width), ("height", self.height)] {
+            if size == 0 || size > MAX_WINDOW_SIZE {
+                return Err(UiStateError::invalid(field, "out of range"));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_layout_round_trips_existing_files() {
+        let layout = NodeLayout::parse(r#"{"Speakers": [120.5, -40], "Firefox": [0, 0]}"#).unwrap();
+        assert_eq!(layout.0["Speakers"], [120.5, -40.0]);
+        let json = serde_json::to_string(&layout).unwrap();
+        assert_eq!(NodeLayout::parse(&json).unwrap(), layout);
+    }
+
+    #[test]
+    fn test_malformed_payloads_are_rejected() {
+        assert!(matches!(
+            NodeLayout::parse(r#"{"Speakers": [1, null]}"#),
+            Err(UiStateError::Parse(_))
+        ));
+        assert!(matches!(
+            NodeLayout::parse(r#"{"": [1, 2]}"#),
+            Err(UiStateError::Invalid { .. })
+        ));
+        assert!(HiddenNodes::parse(r#"["Speakers", 3]"#).is_err());
+        assert!(PinnedNodes::parse(r#"{"Speakers": true}"#).is_err());
+        assert!(WindowGeometry::parse(r#"{"x": 0, "y": 0, "width": -5, "height": 600}"#).is_err());
+
+        let err = Viewport::parse(r#"{"panX": 0, "panY": 0, "zoom": 50}"#).unwrap_err();
+        assert_eq!(err.to_string(), "zoom: out of range");
+    }
+
+    #[test]
+    fn test_unknown_fields_are_dropped() {
+        let geo = WindowGeometry::parse(
+            r#"{"x": -10, "y": 20, "width": 800, "height": 600, "junk": "x"}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            serde_json::to_value(&geo).unwrap(),
+            serde_json::json!({"x": -10, "y": 20, "width": 800, "height": 600})
+        );
+    }
+}
@@ -0,0 +1,123 @@
+//! The optional update check: what the latest GitHub release is, whether it
+//! is newer than the running build, and how to get it on this system.
+
+use serde::Deserialize;
+
+/// GitHub API endpoint for the newest published release.
+pub const LATEST_RELEASE_URL: &str =
+    "https://api.github.com/repos/lemonxah/zestbay/releases/latest";
+
+/// The fields of a GitHub release the update check shows.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Release {
+    pub tag_name: String,
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Release notes, in Markdown.
+    #[serde(default)]
+    pub body: Option<String>,
+    pub html_url: String,
+    #[serde(default)]
+    pub published_at: Option<String>,
+}
+
+impl Release {
+    pub fn parse(json: &str) -> Result<Self, String> {
+        serde_json::from_str(json).map_err(|e| format!("unexpected release data: {}", e))
+    }
+
+    /// The tag without its `v` prefix.
+    pub fn version(&self) -> &str {
+        self.tag_name.trim_start_matches('v')
+    }
+
+    pub fn is_newer_than(&self, current: &str) -> bool {
+        is_newer(self.version(), current)
+    }
+}
+
+/// `major.minor.patch` of a version string, ignoring a leading `v` and any
+/// pre-release or build suffix. Missing components count as 0.
+pub fn parse_version(version: &str) -> Option<(u64, u64, u64)> {
+    let core = version
+        .trim()
+        .trim_start_matches('v')
+        .split(['-', '+'])
+        .next()?;
+    let mut parts = core.split('.').map(|p| p.parse::<u64>());
+    let major = parts.next()?.ok()?;
+    let minor = parts.next().unwrap_or(Ok(0)).ok()?;
+    let patch = parts.next().unwrap_or(Ok(0)).ok()?;
+    Some((major, minor, patch))
+}
+
+/// Whether `latest` is a higher version than `current`. Unparseable
+/// versions are never newer.
+pub fn is_newer(latest: &str, current: &str) -> bool {
+    match (parse_version(latest), parse_version(current)) {
+        (Some(latest), Some(current)) => latest > current,
+        _ => false,
+    }
+}
+
+/// How to upgrade, from the contents of `/etc/os-release` and whether the
+/// app runs inside a Flatpak.
+pub fn upgrade_hint(os_release: &str, flatpak: bool) -> &'static str {
+    if flatpak {
+        return "Update with your software center or `flatpak update`.";
+    }
+    let ids: Vec<&str> = os_release
+        .lines()
+        .filter_map(|line| {
+            line.strip_prefix("ID=")
+                .or_else(|| line.strip_prefix("ID_LIKE="))
+        })
+        .flat_map(|value| value.trim_matches('"').split_whitespace())
+        .collect();
+    if ids.contains(&"arch") {
+        "Update the zestbay AUR package with your AUR helper, e.g. `yay -Syu zestbay`."
+    } else if ids.contains(&"nixos") {
+        "Update your channel or flake input and rebuild."
+    } else {
+        "Download the release from GitHub, or pull and rebuild with `cargo build --release`."
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_versions_compare_numerically() {
+        assert_eq!(parse_version("v0.10.2"), Some((0, 10, 2)));
+        assert_eq!(parse_version("1.2"), Some((1, 2, 0)));
+        assert_eq!(parse_version("0.9.0-rc1"), Some((0, 9, 0)));
+        assert_eq!(parse_version("nightly"), None);
+
+        assert!(is_newer("v0.10.0", "0.8.3"));
+        assert!(!is_newer("0.8.3", "0.8.3"));
+        assert!(!is_newer("0.8.2", "0.8.3"));
+        assert!(!is_newer("nightly", "0.8.3"));
+    }
+
+    #[test]
+    fn test_release_parses_github_response() {
+        let release = Release::parse(
+            r#"{"tag_name": "v0.9.0", "name": "0.9.0", "body": "- Fixes",
+                "html_url": "https://github.com/lemonxah/zestbay/releases/tag/v0.9.0",
+                "published_at": "2026-10-01T12:00:00Z", "assets": []}"#,
+        )
+        .unwrap();
+        assert_eq!(release.version(), "0.9.0");
+        assert!(release.is_newer_than("0.8.3"));
+        assert_eq!(release.body.as_deref(), Some("- Fixes"));
+    }
+
+    #[test]
+    fn test_upgrade_hint_follows_distribution() {
+        let endeavour = "NAME=\"EndeavourOS\"\nID=\"endeavouros\"\nID_LIKE=\"arch\"\n";
+        assert!(upgrade_hint(endeavour, false).contains("AUR"));
+        assert!(upgrade_hint(endeavour, true).contains("flatpak"));
+        assert!(upgrade_hint("ID=fedora\n", false).contains("cargo build"));
+    }
+}
@@ -5,8 +5,8 @@ import QtQuick.Layouts
 ApplicationWindow {
     id: aboutWindow
     title: "About ZestBay"
-    width: 400
-    height: 320
+    width: 460
+    height: 480
     minimumWidth: 360
     minimumHeight: 280
     visible: false
@@ -14,12 +14,30 @@ ApplicationWindow {
 
     required property var controller
 
+    property var update: ({})
+
+    function refreshUpdate() {
+        try {
+            update = JSON.parse(controller.get_update_json())
+        } catch(e) {
+            update = {}
+        }
+    }
+
     function open() {
+        refreshUpdate()
         visible = true
         raise()
         requestActivate()
     }
 
+    Connections {
+        target: controller
+        function onUpdate_status_changed() {
+            aboutWindow.refreshUpdate()
+        }
+    }
+
     ColumnLayout {
         anchors.fill: parent
         anchors.margins: 24
@@ -107,7 +125,73 @@ ApplicationWindow {
             }
         }
 
-        Item { Layout.fillHeight: true }
+        Rectangle {
+            Layout.fillWidth: true
+            height: 1
+            color: Theme.separator
+        }
+
+        RowLayout {
+            Layout.fillWidth: true
+            spacing: 8
+
+            Label {
+                Layout.fillWidth: true
+                wrapMode: Text.WordWrap
+                text: {
+                    if (update.checking)
+                        return "Checking for updates..."
+                    if (update.error)
+                        return "Update check failed: " + update.error
+                    if (update.newer)
+                        return "ZestBay " + update.latest + " is available."
+                    if (update.latest)
+                        return "You are running the latest release."
+                    return update.enabled ? "" : "Automatic update checks are off (Preferences)."
+                }
+            }
+
+            Button {
+                text: "Check Now"
+                enabled: !update.checking
+                onClicked: controller.check_for_updates()
+            }
+        }
+
+        Label {
+            visible: update.newer === true
+            text: update.hint || ""
+            wrapMode: Text.WordWrap
+            opacity: 0.7
+            Layout.fillWidth: true
+        }
+
+        ScrollView {
+            visible: update.newer === true
+            Layout.fillWidth: true
+            Layout.fillHeight: true
+            clip: true
+
+            TextArea {
+                readOnly: true
+                wrapMode: Text.WordWrap
+                textFormat: TextEdit.MarkdownText
+                text: (update.name ? "## " + update.name + "\n\n" : "") + (update.notes || "")
+                onLinkActivated: function(link) { Qt.openUrlExternally(link) }
+            }
+        }
+
+        Label {
+            visible: update.newer === true && !!update.url
+            text: "<a href=\"" + (update.url || "") + "\">Open the release page</a>"
+            textFormat: Text.RichText
+            onLinkActivated: function(link) { Qt.openUrlExternally(link) }
+        }
+
+        Item {
+            visible: update.newer !== true
+            Layout.fillHeight: true
+        }
 
         RowLayout {
             Layout.fillWidth: true
@@ -0,0 +1,184 @@
+import QtQuick
+import QtQuick.Controls
+import QtQuick.Layouts
+import ZestBay
+
+ApplicationWindow {
+    id: commandLog
+    title: "Command Log"
+    color: Theme.windowBg
+    width: 820
+    height: 500
+    minimumWidth: 500
+    minimumHeight: 300
+    visible: false
+
+    required property var controller
+
+    property var entries: []
+    property bool logEnabled: false
+    property string logPath: ""
+
+    function loadEntries() {
+        var filter = { text: filterField.text }
+        try {
+            var result = JSON.parse(controller.get_pw_command_log_json(JSON.stringify(filter), 1000))
+            entries = result.entries || []
+            logEnabled = result.enabled === true
+            logPath = result.path || ""
+        } catch(e) {
+            entries = []
+        }
+        entryList.positionViewAtEnd()
+    }
+
+    function open() {
+        loadEntries()
+        visible = true
+        raise()
+        requestActivate()
+    }
+
+    function formatTime(ms) {
+        var d = new Date(ms)
+        var pad = (n, w) => String(n).padStart(w, "0")
+        return d.toLocaleDateString(Qt.locale(), Locale.ShortFormat) + " "
+            + pad(d.getHours(), 2) + ":" + pad(d.getMinutes(), 2) + ":"
+            + pad(d.getSeconds(), 2) + "." + pad(d.getMilliseconds(), 3)
+    }
+
+    function originColor(kind) {
+        if (kind === "rule" || kind === "fallback") return Theme.statusActive
+        if (kind === "restore") return Theme.colLv2
+        if (kind === "remote") return Theme.statusBypassed
+        return Theme.textPrimary
+    }
+
+    ColumnLayout {
+        anchors.fill: parent
+        anchors.margins: 16
+        spacing: 8
+
+        RowLayout {
+            Layout.fillWidth: true
+            spacing: 8
+
+            TextField {
+                id: filterField
+                Layout.fillWidth: true
+                placeholderText: "Filter by command, port, node or origin (e.g. \"rule\", \"Disconnect\", \"restore\")"
+                onTextChanged: filterTimer.restart()
+            }
+
+            Button {
+                text: "Refresh"
+                onClicked: commandLog.loadEntries()
+            }
+        }
+
+        Timer {
+            id: filterTimer
+            interval: 250
+            onTriggered: commandLog.loadEntries()
+        }
+
+        Label {
+            text: logEnabled
+                  ? entries.length + " command" + (entries.length !== 1 ? "s" : "") + (filterField.text !== "" ? " matching" : "") + " — " + logPath
+                  : "Command logging is off. Turn on \"Log PipeWire commands\" in Preferences to record new commands."
+            opacity: 0.6
+            font.pointSize: 9
+            elide: Text.ElideMiddle
+            Layout.fillWidth: true
+        }
+
+        ListView {
+            id: entryList
+            Layout.fillWidth: true
+            Layout.fillHeight: true
+            clip: true
+            model: entries.length
+            spacing: 2
+
+            ScrollBar.vertical: ScrollBar { policy: ScrollBar.AsNeeded }
+
+            delegate: Rectangle {
+                required property int index
+                width: entryList.width - 12
+                height: 40
+                color: index % 2 === 0 ? Theme.rowEven : Theme.rowOdd
+                radius: 3
+
+                property var entry: entries[index] || {}
+
+                ColumnLayout {
+                    anchors.fill: parent
+                    anchors.leftMargin: 8
+                    anchors.rightMargin: 8
+                    spacing: 0
+
+                    RowLayout {
+                        spacing: 8
+
+                        Label {
+                            text: commandLog.formatTime(entry.timeMs || 0)
+                            font.pointSize: 8
+                            font.family: "monospace"
+                            opacity: 0.6
+                        }
+                        Label {
+                            text: entry.command || ""
+                            font.pointSize: 9
+                            font.bold: true
+                        }
+                        Label {
+                            text: entry.originLabel || ""
+                            font.pointSize: 8
+                            font.italic: true
+                            color: commandLog.originColor(entry.origin ? entry.origin.kind : "")
+                        }
+                    }
+
+                    Label {
+                        text: entry.detail || ""
+                        font.pointSize: 8
+                        font.family: "monospace"
+                        opacity: 0.8
+                        elide: Text.ElideRight
+                        Layout.fillWidth: true
+                        ToolTip.visible: detailHover.hovered && truncated
+                        ToolTip.text: entry.detail || ""
+
+                        HoverHandler { id: detailHover }
+                    }
+                }
+            }
+        }
+
+        Rectangle {
+            Layout.fillWidth: true
+            height: 1
+            color: Theme.separator
+        }
+
+        RowLayout {
+            Layout.fillWidth: true
+
+            Button {
+                text: "Clear Log"
+                enabled: entries.length > 0
+                onClicked: {
+                    controller.clear_pw_command_log()
+                    commandLog.loadEntries()
+                }
+            }
+
+            Item { Layout.fillWidth: true }
+
+            Button {
+                text: "Close"
+                onClicked: commandLog.visible = false
+            }
+        }
+    }
+}
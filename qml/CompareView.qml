@@ -0,0 +1,295 @@
+import QtQuick
+import QtQuick.Controls
+import QtQuick.Layouts
+import ZestBay
+
+// Side panel showing a second PipeWire instance as source / stream / sink
+// swimlanes. Its rules are separate from the main graph's; only the focused
+// node name is shared, so the same device can be spotted in both graphs.
+Rectangle {
+    id: compareView
+    color: Theme.panelBg
+    visible: snapshot !== null
+    width: 520
+
+    required property var controller
+
+    signal nodeFocused(string name)
+
+    property var snapshot: null
+    property string crossHighlightName: ""
+    property int pendingOutputPortId: -1
+    property int minimumWidth: 300
+
+    function refresh() {
+        try {
+            snapshot = JSON.parse(controller.get_compare_json())
+        } catch (e) {
+            snapshot = null
+        }
+        if (!snapshot)
+            pendingOutputPortId = -1
+    }
+
+    function lane(kind) {
+        if (!snapshot)
+            return []
+        return snapshot.nodes.filter(function(n) {
+            if (kind === "source")
+                return n.type === AppController.NodeKind.Source
+            if (kind === "sink")
+                return n.type === AppController.NodeKind.Sink
+            return n.type !== AppController.NodeKind.Source && n.type !== AppController.NodeKind.Sink
+        })
+    }
+
+    function portLabel(portId) {
+        if (!snapshot)
+            return ""
+        for (var i = 0; i < snapshot.nodes.length; i++) {
+            var node = snapshot.nodes[i]
+            for (var j = 0; j < node.ports.length; j++) {
+                if (node.ports[j].id === portId)
+                    return node.name + ": " + node.ports[j].name
+            }
+        }
+        return "#" + portId
+    }
+
+    function linksFrom(portId) {
+        if (!snapshot)
+            return []
+        return snapshot.links.filter(function(l) { return l.outputPortId === portId })
+    }
+
+    function portClicked(port) {
+        if (port.direction === "Output") {
+            pendingOutputPortId = pendingOutputPortId === port.id ? -1 : port.id
+        } else if (pendingOutputPortId >= 0) {
+            controller.compare_connect_ports(pendingOutputPortId, port.id)
+            pendingOutputPortId = -1
+        }
+    }
+
+    Component.onCompleted: refresh()
+
+    Connections {
+        target: compareView.controller
+        function onCompare_changed() { compareView.refresh() }
+    }
+
+    Rectangle {
+        id: resizeHandle
+        anchors.left: parent.left
+        anchors.top: parent.top
+        anchors.bottom: parent.bottom
+        width: 4
+        color: Theme.separator
+
+        MouseArea {
+            anchors.fill: parent
+            anchors.leftMargin: -2
+            anchors.rightMargin: -2
+            cursorShape: Qt.SplitHCursor
+            property real pressX: 0
+            onPressed: mouse => pressX = mouse.x
+            onPositionChanged: mouse => {
+                var maxWidth = compareView.parent ? compareView.parent.width - 200 : compareView.width
+                compareView.width = Math.max(compareView.minimumWidth,
+                                             Math.min(maxWidth, compareView.width - (mouse.x - pressX)))
+            }
+        }
+    }
+
+    ColumnLayout {
+        anchors.fill: parent
+        anchors.leftMargin: resizeHandle.width
+        spacing: 0
+
+        RowLayout {
+            Layout.fillWidth: true
+            Layout.margins: 6
+            spacing: 8
+
+            Label {
+                text: compareView.snapshot ? compareView.snapshot.remote : ""
+                font.bold: true
+                elide: Text.ElideRight
+                Layout.fillWidth: true
+            }
+
+            Label {
+                text: compareView.snapshot && compareView.snapshot.connected
+                      ? "Connected" : "Waiting for PipeWire…"
+                color: compareView.snapshot && compareView.snapshot.connected
+                       ? Theme.statusActive : Theme.statusBypassed
+            }
+
+            Switch {
+                text: "Rules (" + (compareView.snapshot ? compareView.snapshot.ruleCount : 0) + ")"
+                checked: compareView.snapshot ? compareView.snapshot.rulesEnabled : false
+                onToggled: compareView.controller.toggle_compare_rules(checked)
+                ToolTip.visible: hovered
+                ToolTip.text: "Apply this instance's own patchbay rules"
+            }
+
+            Button {
+                text: "Close"
+                ToolTip.visible: hovered
+                ToolTip.text: "Stop comparing with this instance"
+                onClicked: compareView.controller.set_compare_remote("")
+            }
+        }
+
+        Label {
+            Layout.fillWidth: true
+            Layout.leftMargin: 6
+            Layout.bottomMargin: 4
+            visible: compareView.pendingOutputPortId >= 0
+            text: "Connecting from " + compareView.portLabel(compareView.pendingOutputPortId)
+                  + " — click an input port"
+            color: Theme.colLinkConnecting
+            elide: Text.ElideRight
+        }
+
+        Rectangle {
+            Layout.fillWidth: true
+            height: 1
+            color: Theme.separatorLight
+        }
+
+        RowLayout {
+            Layout.fillWidth: true
+            Layout.fillHeight: true
+            spacing: 0
+
+            Repeater {
+                model: [
+                    { kind: "source", title: "Sources", color: Theme.colSource },
+                    { kind: "stream", title: "Streams", color: Theme.colStreamOut },
+                    { kind: "sink", title: "Sinks", color: Theme.colSink }
+                ]
+
+                delegate: ColumnLayout {
+                    id: laneColumn
+                    required property var modelData
+                    Layout.fillWidth: true
+                    Layout.fillHeight: true
+                    Layout.preferredWidth: 1
+                    spacing: 0
+
+                    Rectangle {
+                        Layout.fillWidth: true
+                        height: 24
+                        color: Theme.surfaceBg
+
+                        Label {
+                            anchors.centerIn: parent
+                            text: laneColumn.modelData.title
+                            font.bold: true
+                            color: laneColumn.modelData.color
+                        }
+                    }
+
+                    ListView {
+                        Layout.fillWidth: true
+                        Layout.fillHeight: true
+                        clip: true
+                        spacing: 4
+                        model: compareView.lane(laneColumn.modelData.kind)
+                        ScrollBar.vertical: ScrollBar {}
+
+                        delegate: Rectangle {
+                            id: nodeCard
+                            required property var modelData
+                            width: ListView.view.width
+                            height: nodeColumn.implicitHeight + 8
+                            color: Theme.nodeBg
+                            radius: 4
+                            border.width: compareView.crossHighlightName === modelData.name ? 2.5 : 1
+                            border.color: compareView.crossHighlightName === modelData.name
+                                          ? Theme.snapGuide : Theme.nodeBorder
+
+                            ColumnLayout {
+                                id: nodeColumn
+                                anchors.left: parent.left
+                                anchors.right: parent.right
+                                anchors.top: parent.top
+                                anchors.margins: 4
+                                spacing: 1
+
+                                Label {
+                                    Layout.fillWidth: true
+                                    text: nodeCard.modelData.name
+                                    font.bold: true
+                                    elide: Text.ElideRight
+
+                                    MouseArea {
+                                        anchors.fill: parent
+                                        cursorShape: Qt.PointingHandCursor
+                                        onClicked: compareView.nodeFocused(nodeCard.modelData.name)
+                                    }
+                                }
+
+                                Repeater {
+                                    model: nodeCard.modelData.ports
+
+                                    delegate: ColumnLayout {
+                                        id: portColumn
+                                        required property var modelData
+                                        readonly property bool isOutput: modelData.direction === "Output"
+                                        Layout.fillWidth: true
+                                        spacing: 0
+
+                                        Label {
+                                            Layout.fillWidth: true
+                                            text: (portColumn.isOutput ? "▸ " : "◂ ") + portColumn.modelData.name
+                                            font.pointSize: 9
+                                            elide: Text.ElideRight
+                                            color: compareView.pendingOutputPortId === portColumn.modelData.id
+                                                   ? Theme.colLinkConnecting
+                                                   : (portColumn.isOutput ? Theme.colPortOut : Theme.colPortIn)
+
+                                            MouseArea {
+                                                anchors.fill: parent
+                                                cursorShape: Qt.PointingHandCursor
+                                                onClicked: compareView.portClicked(portColumn.modelData)
+                                            }
+                                        }
+
+                                        Repeater {
+                                            model: portColumn.isOutput ? compareView.linksFrom(portColumn.modelData.id) : []
+
+                                            delegate: Label {
+                                                required property var modelData
+                                                Layout.fillWidth: true
+                                                Layout.leftMargin: 12
+                                                text: "→ " + compareView.portLabel(modelData.inputPortId)
+                                                font.pointSize: 8
+                                                elide: Text.ElideRight
+                                                color: modelData.active ? Theme.colLinkActive : Theme.colLinkInactive
+
+                                                MouseArea {
+                                                    anchors.fill: parent
+                                                    cursorShape: Qt.PointingHandCursor
+                                                    onClicked: compareView.controller.compare_disconnect_link(parent.modelData.id)
+                                                }
+
+                                                ToolTip.visible: linkHover.hovered
+                                                ToolTip.text: "Click to disconnect"
+
+                                                HoverHandler {
+                                                    id: linkHover
+                                                }
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
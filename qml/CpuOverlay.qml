@@ -67,14 +67,27 @@ ApplicationWindow {
             activeIds[pid] = true
 
             if (!ph[pid]) {
-                // New plugin — assign a color and fill history with zeros
+                // New plugin — assign a color and seed its history from the
+                // samples kept by the controller, padded with zeros
                 ph[pid] = {
                     name: p.name,
                     color: pluginColors[Object.keys(ph).length % pluginColors.length],
                     history: []
                 }
-                for (var z = 0; z < historyLength - 1; z++)
+                var kept = []
+                if (p.stableId) {
+                    try {
+                        kept = JSON.parse(controller.get_plugin_cpu_history_json(p.stableId)).samples
+                    } catch(e) {
+                        kept = []
+                    }
+                }
+                // The latest sample is the one pushed below
+                kept = kept.slice(Math.max(0, kept.length - historyLength), Math.max(0, kept.length - 1))
+                for (var z = 0; z < historyLength - 1 - kept.length; z++)
                     ph[pid].history.push(0)
+                for (var k = 0; k < kept.length; k++)
+                    ph[pid].history.push(kept[k].dspPercent || 0)
             }
             ph[pid].name = p.name
             ph[pid].history.push(p.dspPercent || 0)
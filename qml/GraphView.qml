@@ -1,5 +1,7 @@
 import QtQuick
 import QtQuick.Controls
+import QtQuick.Layouts
+import ZestBay
 
 Item {
     id: graphView
@@ -7,7 +9,9 @@ Item {
     required property var controller
 
     signal openPluginBrowser()
+    signal openPluginReplace(string stableId, string name)
     signal openPluginParams(int nodeId)
+    signal nodeFocused(string name)
 
     property real zoom: 1.0
     property real panX: 0
@@ -55,9 +59,18 @@ Item {
 
     property int contextNodeId: -1
     property var contextNode: null
+    // How long a link dropped with Alt held lasts before it reverts.
+    property int temporaryLinkSecs: 30
+    // What quick connect would do for the context node, from propose_connection.
+    property var contextProposal: null
     property var pendingPluginPosition: null
     property string defaultNodeKey: ""
 
+    // Name of the node focused in the comparison view; nodes with the same
+    // name are outlined here.
+    property string crossHighlightName: ""
+    onCrossHighlightNameChanged: canvas.requestPaint()
+
     // Local bypass overrides for instant visual feedback
     // Maps nodeId -> bool (true = bypassed). Cleared on each refreshData().
     property var localBypassState: ({})
@@ -75,6 +88,13 @@ Item {
     readonly property real portRadius: 5
     readonly property real nodePadding: 8
     readonly property real buttonRowHeight: 22
+    readonly property real knobRowHeight: 38
+    readonly property real knobRadius: 9
+
+    // Knobs drawn on plugin nodes, by node id, from get_node_quick_params_json.
+    property var quickParams: ({})
+    // The knob being dragged: { nodeId, param, startY, startValue }.
+    property var knobDrag: null
 
     readonly property color colSink: Theme.colSink
     readonly property color colSource: Theme.colSource
@@ -92,9 +112,12 @@ Item {
     readonly property color colPortOut: Theme.colPortOut
     readonly property color colMidi: Theme.colMidi
     readonly property color colMidiPort: Theme.colMidiPort
+    readonly property color colCvPort: Theme.colCvPort
     readonly property color colLinkActive: Theme.colLinkActive
     readonly property color colLinkInactive: Theme.colLinkInactive
     readonly property color colLinkMidi: Theme.colLinkMidi
+    readonly property color colLinkCv: Theme.colLinkCv
+    readonly property color colLinkSidechain: Theme.colLinkSidechain
     readonly property color colLinkConnecting: Theme.colLinkConnecting
     readonly property color colDefaultOutline: Theme.colDefaultOutline
 
@@ -104,6 +127,12 @@ Item {
         function onDarkChanged() { canvas.requestPaint() }
     }
 
+    // MIDI ports first and CV ports last, as the backend sorts them.
+    function portRank(p) {
+        if (p.mediaType === AppController.MediaKind.Midi) return 0
+        return p.mediaType === AppController.MediaKind.Cv ? 2 : 1
+    }
+
     function naturalCmp(a, b) {
         var ax = a.split(/(\d+)/), bx = b.split(/(\d+)/)
         for (var i = 0; i < Math.min(ax.length, bx.length); i++) {
@@ -188,15 +217,28 @@ Item {
         }
         portsByNode = newPorts
 
+        var newQuick = {}
+        for (var qi = 0; qi < nodes.length; qi++) {
+            if (nodes[qi].type !== AppController.NodeKind.Plugin) continue
+            try {
+                var quick = JSON.parse(controller.get_node_quick_params_json(nodes[qi].id))
+                if (quick.parameters && quick.parameters.length > 0)
+                    newQuick[nodes[qi].id] = quick.parameters
+            } catch(e) {}
+        }
+        quickParams = newQuick
+
         var newPortMedia = {}
         for (var nid in newPorts) {
             var pp = newPorts[nid]
             for (var pi2 = 0; pi2 < pp.length; pi2++) {
-                newPortMedia[pp[pi2].id] = pp[pi2].mediaType || "Unknown"
+                newPortMedia[pp[pi2].id] = pp[pi2].mediaType || AppController.MediaKind.Unknown
             }
         }
         portMediaTypes = newPortMedia
 
+        migrateLegacyKeys()
+
         for (var ni = 0; ni < nodes.length; ni++) {
             var n = nodes[ni]
             if (!(n.id in nodePositions)) {
@@ -206,7 +248,7 @@ Item {
                     var saved = savedLayout[key]
                     nodePositions[n.id] = { x: saved[0], y: saved[1] }
                     restoredFromLayout = true
-                } else if (n.type === "Plugin" && pendingPluginPosition) {
+                } else if (n.type === AppController.NodeKind.Plugin && pendingPluginPosition) {
                     nodePositions[n.id] = { x: pendingPluginPosition.x, y: pendingPluginPosition.y }
                     pendingPluginPosition = null
                     persistLayout()
@@ -214,7 +256,7 @@ Item {
                     nodePositions[n.id] = { x: 0, y: 0 }
                     autoPlaceNewNode(n.id)
                 }
-                if (n.type === "Plugin" && key && !pinnedNodes[key]) {
+                if (n.type === AppController.NodeKind.Plugin && key && !pinnedNodes[key]) {
                     pinnedNodes[key] = true
                     pinnedNodes = pinnedNodes
                     persistPinned()
@@ -434,6 +476,22 @@ Item {
     Menu {
         id: nodeContextMenu
 
+        onAboutToShow: {
+            isolateUiItem.checked = isolateUiItem.visible
+                && controller.is_plugin_ui_isolated(contextNodeId)
+            contextProposal = proposeConnection(contextNodeId)
+        }
+
+        MenuItem {
+            text: contextProposal
+                  ? (contextProposal.direction === "target" ? "Connect to " : "Connect from ")
+                    + contextProposal.name
+                  : ""
+            visible: contextProposal !== null
+            height: visible ? implicitHeight : 0
+            onTriggered: quickConnect(contextNodeId)
+        }
+
         MenuItem {
             text: "Hide"
             onTriggered: {
@@ -464,15 +522,45 @@ Item {
             }
         }
 
+        MenuItem {
+            text: "Expand Group"
+            visible: contextNode !== null && contextNode.isGroup === true
+            height: visible ? implicitHeight : 0
+            onTriggered: {
+                if (contextNodeId >= 0)
+                    controller.set_app_group_expanded(contextNodeId, true)
+            }
+        }
+
+        MenuItem {
+            text: "Collapse Group"
+            visible: contextNode !== null && contextNode.parentId !== undefined
+            height: visible ? implicitHeight : 0
+            onTriggered: {
+                if (contextNode && contextNode.parentId !== undefined)
+                    controller.set_app_group_expanded(contextNode.parentId, false)
+            }
+        }
+
+        MenuItem {
+            text: "Add Noise Suppression"
+            visible: contextNode !== null && contextNode.type === AppController.NodeKind.Source && contextNode.mediaType === AppController.MediaKind.Audio
+            height: visible ? implicitHeight : 0
+            onTriggered: {
+                if (contextNodeId >= 0)
+                    controller.add_noise_suppression(contextNodeId)
+            }
+        }
+
         MenuSeparator {
-            visible: contextNode !== null && (contextNode.type === "Sink" || contextNode.type === "Duplex" || contextNode.type === "Plugin")
+            visible: contextNode !== null && (contextNode.type === AppController.NodeKind.Sink || contextNode.type === AppController.NodeKind.Duplex || contextNode.type === AppController.NodeKind.Plugin)
             height: visible ? implicitHeight : 0
         }
 
         MenuItem {
             text: contextNode && contextNode.layoutKey === defaultNodeKey && defaultNodeKey !== ""
                   ? "Clear Default" : "Set as Default"
-            visible: contextNode !== null && (contextNode.type === "Sink" || contextNode.type === "Duplex" || contextNode.type === "Plugin")
+            visible: contextNode !== null && (contextNode.type === AppController.NodeKind.Sink || contextNode.type === AppController.NodeKind.Duplex || contextNode.type === AppController.NodeKind.Plugin)
             height: visible ? implicitHeight : 0
             onTriggered: {
                 if (contextNode) {
@@ -490,13 +578,20 @@ Item {
         }
 
         MenuSeparator {
-            visible: contextNode && contextNode.type === "Plugin"
+            visible: contextNode && contextNode.type === AppController.NodeKind.Plugin
+            height: visible ? implicitHeight : 0
+        }
+
+        MenuItem {
+            text: contextNode && contextNode.abSource === "B" ? "Switch to A" : "Switch to B"
+            visible: contextNode !== null && contextNode.abSource !== undefined
             height: visible ? implicitHeight : 0
+            onTriggered: controller.toggle_ab_switch(contextNodeId)
         }
 
         MenuItem {
             text: "Rename..."
-            visible: contextNode !== null && contextNode.type === "Plugin"
+            visible: contextNode !== null && contextNode.type === AppController.NodeKind.Plugin
             height: visible ? implicitHeight : 0
             onTriggered: {
                 renameField.text = contextNode ? contextNode.name : ""
@@ -504,105 +599,929 @@ Item {
             }
         }
 
-        MenuItem {
-            text: "Open UI..."
-            visible: contextNode !== null && contextNode.type === "Plugin" && contextNode.pluginHasUi !== false
-            height: visible ? implicitHeight : 0
-            onTriggered: {
-                if (contextNodeId >= 0)
-                    controller.open_plugin_ui(contextNodeId)
+        MenuItem {
+            text: "Duplicate"
+            visible: contextNode !== null && contextNode.type === AppController.NodeKind.Plugin && contextNode.stableId !== undefined
+            height: visible ? implicitHeight : 0
+            onTriggered: {
+                if (contextNode)
+                    controller.duplicate_plugin(contextNode.stableId, false)
+            }
+        }
+
+        MenuItem {
+            text: "Duplicate with Connections"
+            visible: contextNode !== null && contextNode.type === AppController.NodeKind.Plugin && contextNode.stableId !== undefined
+            height: visible ? implicitHeight : 0
+            onTriggered: {
+                if (contextNode)
+                    controller.duplicate_plugin(contextNode.stableId, true)
+            }
+        }
+
+        MenuItem {
+            text: "Copy"
+            visible: contextNode !== null && contextNode.type === AppController.NodeKind.Plugin && contextNode.stableId !== undefined
+            height: visible ? implicitHeight : 0
+            onTriggered: {
+                if (contextNodeId < 0)
+                    return
+                if (selectedNodes[contextNodeId])
+                    copySelection()
+                else
+                    copyNodes([contextNodeId])
+            }
+        }
+
+        MenuItem {
+            text: "Replace With..."
+            visible: contextNode !== null && contextNode.type === AppController.NodeKind.Plugin && contextNode.stableId !== undefined
+            height: visible ? implicitHeight : 0
+            onTriggered: {
+                if (contextNode)
+                    graphView.openPluginReplace(contextNode.stableId, contextNode.name)
+            }
+        }
+
+        MenuItem {
+            text: "Sidechain Source..."
+            visible: contextNode !== null && contextNode.type === AppController.NodeKind.Plugin && hasSidechainInputs(contextNodeId)
+            height: visible ? implicitHeight : 0
+            onTriggered: {
+                sidechainMenu.pluginStableId = contextNode ? contextNode.stableId : ""
+                sidechainMenu.candidates = sidechainCandidates(contextNodeId)
+                sidechainMenu.popup()
+            }
+        }
+
+        MenuItem {
+            text: contextNode && controller.get_audition_node() === contextNodeId
+                  ? "Stop Audition" : "Audition On..."
+            visible: contextNode !== null && !contextNode.isGroup && hasAudioOutputs(contextNodeId)
+            height: visible ? implicitHeight : 0
+            onTriggered: {
+                if (controller.get_audition_node() === contextNodeId) {
+                    controller.audition_node(contextNodeId, 0, false)
+                    return
+                }
+                auditionMenu.nodeId = contextNodeId
+                auditionMenu.sinks = auditionSinks(contextNodeId)
+                auditionMenu.popup()
+            }
+        }
+
+        MenuItem {
+            text: "Does Sound Reach..."
+            visible: contextNode !== null && !contextNode.isGroup && hasAudioOutputs(contextNodeId)
+            height: visible ? implicitHeight : 0
+            onTriggered: {
+                probeMenu.nodeId = contextNodeId
+                probeMenu.targets = probeTargets(contextNodeId)
+                probeMenu.popup()
+            }
+        }
+
+        MenuItem {
+            text: "Make Default Output"
+            visible: contextNode !== null && !contextNode.isGroup && contextNode.type === AppController.NodeKind.Sink
+                     && contextNode.mediaType === AppController.MediaKind.Audio
+                     && controller.get_default_output() !== contextNodeId
+            height: visible ? implicitHeight : 0
+            onTriggered: controller.set_default_output(contextNodeId)
+        }
+
+        MenuItem {
+            text: "Conversion Settings..."
+            visible: contextNode !== null && !contextNode.isGroup && contextNode.type !== AppController.NodeKind.Plugin
+                     && contextNode.mediaType === AppController.MediaKind.Audio
+            height: visible ? implicitHeight : 0
+            onTriggered: conversionDialog.openFor(contextNodeId, contextNode.name)
+        }
+
+        MenuItem {
+            text: "Add Chain Dry/Wet"
+            visible: contextNode !== null && contextNode.type === AppController.NodeKind.Plugin
+            height: visible ? implicitHeight : 0
+            onTriggered: {
+                if (contextNodeId >= 0)
+                    controller.add_chain_mix(contextNodeId)
+            }
+        }
+
+        MenuItem {
+            text: "Chain Processing Buffer..."
+            visible: contextNode !== null && contextNode.type === AppController.NodeKind.Plugin && contextNode.stableId !== undefined
+            height: visible ? implicitHeight : 0
+            onTriggered: {
+                quantumMenu.nodeId = contextNodeId
+                quantumMenu.info = JSON.parse(controller.get_chain_quantum_json(contextNodeId))
+                quantumMenu.popup()
+            }
+        }
+
+        MenuItem {
+            text: "Export Chain as Filter-Chain"
+            visible: contextNode !== null && contextNode.type === AppController.NodeKind.Plugin && contextNode.pluginFormat === "LV2"
+            height: visible ? implicitHeight : 0
+            onTriggered: {
+                if (contextNodeId < 0)
+                    return
+                var path = controller.export_chain_filter_chain(contextNodeId)
+                if (path.length > 0) {
+                    exportDialog.path = path
+                    exportDialog.open()
+                }
+            }
+        }
+
+        MenuItem {
+            text: "Export Chain for Sharing"
+            visible: contextNode !== null && contextNode.type === AppController.NodeKind.Plugin && contextNode.stableId !== undefined
+            height: visible ? implicitHeight : 0
+            onTriggered: {
+                if (contextNodeId < 0)
+                    return
+                var path = controller.export_chain_file(contextNodeId)
+                if (path.length > 0) {
+                    chainFileDialog.path = path
+                    chainFileDialog.open()
+                }
+            }
+        }
+
+        MenuItem {
+            text: "Save Chain as Preset..."
+            visible: contextNode !== null && contextNode.type === AppController.NodeKind.Plugin && contextNode.stableId !== undefined
+            height: visible ? implicitHeight : 0
+            onTriggered: {
+                if (contextNodeId >= 0)
+                    chainPresetDialog.openFor(contextNodeId)
+            }
+        }
+
+        MenuItem {
+            text: "Open UI..."
+            visible: contextNode !== null && contextNode.type === AppController.NodeKind.Plugin && contextNode.pluginHasUi !== false
+            height: visible ? implicitHeight : 0
+            onTriggered: {
+                if (contextNodeId >= 0)
+                    controller.open_plugin_ui(contextNodeId)
+            }
+        }
+
+        MenuItem {
+            id: isolateUiItem
+            text: "Run UI in Separate Process"
+            checkable: true
+            visible: contextNode !== null && contextNode.type === AppController.NodeKind.Plugin
+                     && contextNode.pluginFormat === "LV2" && contextNode.pluginHasUi !== false
+            height: visible ? implicitHeight : 0
+            onTriggered: {
+                if (contextNodeId >= 0)
+                    controller.set_plugin_ui_isolated(contextNodeId, checked)
+            }
+        }
+
+        MenuItem {
+            text: "UI Scale..."
+            visible: contextNode !== null && contextNode.type === AppController.NodeKind.Plugin && contextNode.pluginHasUi !== false
+            height: visible ? implicitHeight : 0
+            onTriggered: {
+                uiScaleMenu.nodeId = contextNodeId
+                uiScaleMenu.current = controller.get_plugin_ui_scale(contextNodeId)
+                uiScaleMenu.popup()
+            }
+        }
+
+        MenuSeparator {
+            visible: contextNode !== null && contextNode.type === AppController.NodeKind.Plugin
+            height: visible ? implicitHeight : 0
+        }
+
+        MenuItem {
+            text: "Remove Plugin"
+            visible: contextNode !== null && contextNode.type === AppController.NodeKind.Plugin
+            height: visible ? implicitHeight : 0
+            onTriggered: {
+                if (contextNodeId >= 0)
+                    controller.remove_plugin(contextNodeId)
+            }
+        }
+    }
+
+    Menu {
+        id: uiScaleMenu
+        property int nodeId: -1
+        property real current: 0
+
+        Repeater {
+            model: [
+                { label: "Plugin Default", scale: 0 },
+                { label: "100%", scale: 1.0 },
+                { label: "125%", scale: 1.25 },
+                { label: "150%", scale: 1.5 },
+                { label: "200%", scale: 2.0 }
+            ]
+            MenuItem {
+                required property var modelData
+                text: modelData.label
+                checkable: true
+                checked: uiScaleMenu.current === modelData.scale
+                onTriggered: controller.set_plugin_ui_scale(uiScaleMenu.nodeId, modelData.scale)
+            }
+        }
+    }
+
+    Menu {
+        id: sidechainMenu
+        property string pluginStableId: ""
+        property var candidates: []
+
+        MenuItem {
+            text: "None"
+            onTriggered: controller.set_sidechain_source(sidechainMenu.pluginStableId, 0)
+        }
+
+        MenuSeparator {}
+
+        Repeater {
+            model: sidechainMenu.candidates
+            MenuItem {
+                required property var modelData
+                text: modelData.name
+                onTriggered: controller.set_sidechain_source(sidechainMenu.pluginStableId, modelData.id)
+            }
+        }
+    }
+
+    // Solo a node on a monitoring sink; its own links stay as they are.
+    Menu {
+        id: auditionMenu
+        property int nodeId: -1
+        property var sinks: []
+
+        Repeater {
+            model: auditionMenu.sinks
+            MenuItem {
+                required property var modelData
+                text: modelData.name
+                onTriggered: controller.audition_node(auditionMenu.nodeId, modelData.id, true)
+            }
+        }
+    }
+
+    // Listen along the route from a node to the chosen one.
+    Menu {
+        id: probeMenu
+        property int nodeId: -1
+        property var targets: []
+
+        Repeater {
+            model: probeMenu.targets
+            MenuItem {
+                required property var modelData
+                text: modelData.name
+                onTriggered: {
+                    probeDialog.report = null
+                    if (controller.probe_path(probeMenu.nodeId, modelData.id) && !probeDialog.report)
+                        probeDialog.open()
+                }
+            }
+        }
+    }
+
+    Menu {
+        id: portContextMenu
+        property var port: null
+        property var candidates: []
+
+        MenuItem {
+            text: "Split Stereo Pair"
+            enabled: portContextMenu.port !== null && portContextMenu.port.pairPortId != null
+            onTriggered: controller.unpair_port(portContextMenu.port.id)
+        }
+
+        Menu {
+            title: "Pair With"
+            enabled: portContextMenu.candidates.length > 0
+
+            Repeater {
+                model: portContextMenu.candidates
+                MenuItem {
+                    required property var modelData
+                    text: modelData.name
+                    onTriggered: {
+                        // The port further down the node is the right side
+                        if (portContextMenu.port.index < modelData.index)
+                            controller.set_port_pair(portContextMenu.port.id, modelData.id)
+                        else
+                            controller.set_port_pair(modelData.id, portContextMenu.port.id)
+                    }
+                }
+            }
+        }
+
+        Menu {
+            id: channelMenu
+            title: "Channel Position"
+
+            property var positions: ["MONO", "FL", "FR", "FC", "LFE", "RL", "RR", "SL", "SR"]
+
+            MenuItem {
+                text: {
+                    var reported = portContextMenu.port ? portContextMenu.port.reportedChannel : null
+                    return "As Reported" + (reported ? " (" + reported + ")" : "")
+                }
+                checkable: true
+                checked: portContextMenu.port !== null &&
+                         portContextMenu.port.channel === portContextMenu.port.reportedChannel
+                onTriggered: controller.set_port_channel(portContextMenu.port.id, "")
+            }
+
+            MenuSeparator {}
+
+            Repeater {
+                model: channelMenu.positions
+                MenuItem {
+                    required property var modelData
+                    text: modelData
+                    checkable: true
+                    checked: portContextMenu.port !== null && portContextMenu.port.channel === modelData
+                    onTriggered: controller.set_port_channel(portContextMenu.port.id, modelData)
+                }
+            }
+        }
+
+        MenuSeparator {}
+
+        MenuItem {
+            text: "Reset Stereo Pairs"
+            onTriggered: controller.reset_port_pairs(portContextMenu.port.nodeId)
+        }
+
+        MenuItem {
+            text: "Reset Channel Positions"
+            onTriggered: controller.reset_port_channels(portContextMenu.port.nodeId)
+        }
+    }
+
+    Menu {
+        id: quantumMenu
+        property int nodeId: -1
+        property var info: ({})
+
+        MenuItem {
+            text: quantumMenu.info.mixed ? "Graph Default (plugins differ)" : "Graph Default"
+            checkable: true
+            checked: !quantumMenu.info.mixed && !quantumMenu.info.quantum
+            onTriggered: controller.set_chain_quantum(quantumMenu.nodeId, 0)
+        }
+
+        MenuSeparator {}
+
+        Repeater {
+            model: quantumMenu.info.choices || []
+            MenuItem {
+                required property var modelData
+                text: modelData.label
+                checkable: true
+                checked: quantumMenu.info.quantum === modelData.quantum
+                onTriggered: controller.set_chain_quantum(quantumMenu.nodeId, modelData.quantum)
+            }
+        }
+    }
+
+    Menu {
+        id: canvasContextMenu
+
+        MenuItem {
+            text: "Delete Selected Links"
+            enabled: {
+                for (var k in selectedLinks) return true
+                return false
+            }
+            onTriggered: deleteSelectedLinks()
+        }
+
+        MenuSeparator {}
+
+        MenuItem {
+            text: "Add Plugin..."
+            onTriggered: graphView.openPluginBrowser()
+        }
+
+        MenuSeparator {}
+
+        MenuItem {
+            text: "Unhide All"
+            enabled: {
+                for (var k in hiddenNodes) return true
+                return false
+            }
+            onTriggered: {
+                hiddenNodes = {}
+                persistHidden()
+                canvas.requestPaint()
+            }
+        }
+
+        MenuItem {
+            text: "Reset Zoom"
+            onTriggered: {
+                zoom = 1.0
+                panX = 0
+                panY = 0
+                canvas.requestPaint()
+            }
+        }
+
+        MenuItem {
+            text: "Auto Layout"
+            onTriggered: runAutoLayout()
+        }
+    }
+
+    Dialog {
+        id: renameDialog
+        title: "Rename Plugin"
+        standardButtons: Dialog.Ok | Dialog.Cancel
+        anchors.centerIn: parent
+        modal: true
+        width: 320
+
+        contentItem: TextField {
+            id: renameField
+            placeholderText: "New name"
+            selectByMouse: true
+            onAccepted: renameDialog.accept()
+        }
+
+        onAccepted: {
+            var newName = renameField.text.trim()
+            if (newName.length > 0 && contextNodeId >= 0) {
+                controller.rename_plugin(contextNodeId, newName)
+            }
+        }
+    }
+
+    Dialog {
+        id: exportDialog
+        title: "Chain Exported"
+        standardButtons: Dialog.Ok
+        anchors.centerIn: parent
+        modal: true
+        width: 420
+
+        property string path: ""
+
+        contentItem: Text {
+            text: "The filter-chain config was written to:\n" + exportDialog.path +
+                  "\n\nCopy it into ~/.config/pipewire/pipewire.conf.d/ and restart PipeWire " +
+                  "to run the chain without ZestBay."
+            wrapMode: Text.WrapAnywhere
+            color: Theme.textPrimary
+            padding: 12
+        }
+    }
+
+    Dialog {
+        id: chainFileDialog
+        title: "Chain Exported"
+        standardButtons: Dialog.Ok
+        anchors.centerIn: parent
+        modal: true
+        width: 420
+
+        property string path: ""
+
+        contentItem: Text {
+            text: "The chain was written to:\n" + chainFileDialog.path +
+                  "\n\nIt lists the plugins it needs, so whoever imports it " +
+                  "(File > Import) is told which ones they are missing."
+            wrapMode: Text.WrapAnywhere
+            color: Theme.textPrimary
+            padding: 12
+        }
+    }
+
+    Dialog {
+        id: pasteDialog
+        title: "Paste"
+        standardButtons: Dialog.Ok
+        anchors.centerIn: parent
+        modal: true
+        width: 420
+
+        property string summary: ""
+
+        contentItem: Text {
+            text: pasteDialog.summary
+            wrapMode: Text.WordWrap
+            color: Theme.textPrimary
+            padding: 12
+        }
+    }
+
+    // Resampling and channel mixing settings for one node; anything left at
+    // "Graph default" is not touched.
+    Dialog {
+        id: conversionDialog
+        title: "Conversion Settings: " + nodeName
+        standardButtons: Dialog.Ok | Dialog.Cancel
+        anchors.centerIn: parent
+        modal: true
+        width: 380
+
+        property int nodeId: -1
+        property string nodeName: ""
+        readonly property var qualities: {
+            var list = [{ value: -1, text: "Graph default" }]
+            for (var q = 0; q <= 14; q++)
+                list.push({ value: q, text: q === 0 ? "0 (fastest)" : q === 14 ? "14 (best)" : "" + q })
+            return list
+        }
+        readonly property var switches: [
+            { value: 0, text: "Graph default" },
+            { value: 1, text: "On" },
+            { value: 2, text: "Off" }
+        ]
+
+        function switchIndex(value) {
+            return value === undefined ? 0 : (value ? 1 : 2)
+        }
+
+        function switchValue(index) {
+            return index === 0 ? undefined : index === 1
+        }
+
+        function openFor(id, name) {
+            nodeId = id
+            nodeName = name
+            var policy = {}
+            try {
+                policy = JSON.parse(controller.get_conversion_policy_json(id))
+            } catch (e) {}
+            qualityCombo.currentIndex = policy.resampleQuality === undefined ? 0 : policy.resampleQuality + 1
+            disableCombo.currentIndex = switchIndex(policy.disableResample)
+            upmixCombo.currentIndex = switchIndex(policy.upmix)
+            normalizeCombo.currentIndex = switchIndex(policy.normalize)
+            open()
+        }
+
+        contentItem: Grid {
+            columns: 2
+            columnSpacing: 12
+            rowSpacing: 8
+            padding: 12
+            verticalItemAlignment: Grid.AlignVCenter
+
+            Text { text: "Resample quality"; color: Theme.textPrimary }
+            ComboBox {
+                id: qualityCombo
+                width: 200
+                model: conversionDialog.qualities
+                textRole: "text"
+            }
+
+            Text { text: "Skip resampling"; color: Theme.textPrimary }
+            ComboBox {
+                id: disableCombo
+                width: 200
+                model: conversionDialog.switches
+                textRole: "text"
+            }
+
+            Text { text: "Upmix channels"; color: Theme.textPrimary }
+            ComboBox {
+                id: upmixCombo
+                width: 200
+                model: conversionDialog.switches
+                textRole: "text"
+            }
+
+            Text { text: "Normalize downmix"; color: Theme.textPrimary }
+            ComboBox {
+                id: normalizeCombo
+                width: 200
+                model: conversionDialog.switches
+                textRole: "text"
+            }
+        }
+
+        onAccepted: {
+            var policy = {}
+            if (qualityCombo.currentIndex > 0)
+                policy.resampleQuality = qualityCombo.currentIndex - 1
+            var disable = switchValue(disableCombo.currentIndex)
+            if (disable !== undefined) policy.disableResample = disable
+            var upmix = switchValue(upmixCombo.currentIndex)
+            if (upmix !== undefined) policy.upmix = upmix
+            var normalize = switchValue(normalizeCombo.currentIndex)
+            if (normalize !== undefined) policy.normalize = normalize
+            controller.set_conversion_policy(nodeId, JSON.stringify(policy))
+        }
+    }
+
+    // Messages a MIDI link drops. Any of them routes the link through a
+    // hidden filter node.
+    Dialog {
+        id: midiLinkDialog
+        title: "MIDI Link Filter"
+        standardButtons: Dialog.Ok | Dialog.Cancel
+        anchors.centerIn: parent
+        modal: true
+        width: 320
+
+        property int linkId: -1
+
+        function openFor(id) {
+            linkId = id
+            var options = {}
+            try {
+                options = JSON.parse(controller.get_midi_link_options_json(id))
+            } catch (e) {}
+            dropSysex.checked = options.dropSysex === true
+            dropActiveSensing.checked = options.dropActiveSensing === true
+            dropClock.checked = options.dropClock === true
+            open()
+        }
+
+        contentItem: Column {
+            spacing: 4
+            padding: 12
+
+            CheckBox { id: dropSysex; text: "Drop SysEx" }
+            CheckBox { id: dropActiveSensing; text: "Drop active sensing" }
+            CheckBox { id: dropClock; text: "Drop clock and transport" }
+        }
+
+        onAccepted: {
+            controller.set_midi_link_options(linkId, JSON.stringify({
+                dropSysex: dropSysex.checked,
+                dropActiveSensing: dropActiveSensing.checked,
+                dropClock: dropClock.checked
+            }))
+        }
+    }
+
+    function gainLabel(db) {
+        if (db <= -60)
+            return "muted"
+        return (db > 0 ? "+" : "") + db.toFixed(1) + " dB"
+    }
+
+    // Gain of one audio link, for balancing several sources into one sink.
+    // Anything but 0 dB routes the link through a hidden gain node.
+    Dialog {
+        id: linkGainDialog
+        title: "Link Gain"
+        standardButtons: Dialog.Ok | Dialog.Cancel
+        anchors.centerIn: parent
+        modal: true
+        width: 360
+
+        property int linkId: -1
+
+        function openFor(link) {
+            linkId = link.id
+            gainSlider.value = typeof link.gainDb === "number" ? link.gainDb : 0
+            open()
+        }
+
+        contentItem: Row {
+            spacing: 8
+            padding: 12
+
+            Slider {
+                id: gainSlider
+                width: 180
+                anchors.verticalCenter: parent.verticalCenter
+                from: -60
+                to: 12
+                stepSize: 0.5
             }
-        }
 
-        MenuSeparator {
-            visible: contextNode !== null && contextNode.type === "Plugin"
-            height: visible ? implicitHeight : 0
-        }
+            Label {
+                text: gainLabel(gainSlider.value)
+                width: 56
+                anchors.verticalCenter: parent.verticalCenter
+                horizontalAlignment: Text.AlignRight
+            }
 
-        MenuItem {
-            text: "Remove Plugin"
-            visible: contextNode !== null && contextNode.type === "Plugin"
-            height: visible ? implicitHeight : 0
-            onTriggered: {
-                if (contextNodeId >= 0)
-                    controller.remove_plugin(contextNodeId)
+            Button {
+                text: "0 dB"
+                onClicked: gainSlider.value = 0
             }
         }
+
+        onAccepted: controller.set_link_gain(linkId, gainSlider.value)
     }
 
+    // An audio link, once chain presets exist: its gain, or a chain to put
+    // on it.
     Menu {
-        id: canvasContextMenu
+        id: linkMenu
+        property var link: null
+        property var chains: []
 
         MenuItem {
-            text: "Delete Selected Links"
-            enabled: {
-                for (var k in selectedLinks) return true
-                return false
+            text: "Link Gain..."
+            onTriggered: linkGainDialog.openFor(linkMenu.link)
+        }
+
+        Menu {
+            title: "Insert Chain"
+
+            Repeater {
+                model: linkMenu.chains
+                MenuItem {
+                    required property var modelData
+                    text: modelData.missing.length > 0
+                          ? modelData.name + " (missing " + modelData.missing.join(", ") + ")"
+                          : modelData.name
+                    enabled: modelData.missing.length === 0
+                    onTriggered: controller.insert_chain_on_link(linkMenu.link.id, modelData.name)
+                }
             }
-            onTriggered: deleteSelectedLinks()
         }
+    }
 
-        MenuSeparator {}
+    // Save the chain a plugin belongs to under a name, to insert it on
+    // links later. Saved chains are listed to replace or delete.
+    Dialog {
+        id: chainPresetDialog
+        title: "Save Chain as Preset"
+        standardButtons: Dialog.Save | Dialog.Cancel
+        anchors.centerIn: parent
+        modal: true
+        width: 360
 
-        MenuItem {
-            text: "Add Plugin..."
-            onTriggered: graphView.openPluginBrowser()
+        property int nodeId: -1
+        property var chains: []
+
+        function openFor(nodeId) {
+            chainPresetDialog.nodeId = nodeId
+            chains = JSON.parse(controller.get_chain_presets_json())
+            chainNameField.text = ""
+            open()
         }
 
-        MenuSeparator {}
+        contentItem: ColumnLayout {
+            spacing: 6
 
-        MenuItem {
-            text: "Unhide All"
-            enabled: {
-                for (var k in hiddenNodes) return true
-                return false
-            }
-            onTriggered: {
-                hiddenNodes = {}
-                persistHidden()
-                canvas.requestPaint()
+            TextField {
+                id: chainNameField
+                Layout.fillWidth: true
+                placeholderText: "Chain name"
+                selectByMouse: true
+                onAccepted: chainPresetDialog.accept()
             }
-        }
 
-        MenuItem {
-            text: "Reset Zoom"
-            onTriggered: {
-                zoom = 1.0
-                panX = 0
-                panY = 0
-                canvas.requestPaint()
+            Repeater {
+                model: chainPresetDialog.chains
+                RowLayout {
+                    required property var modelData
+                    Layout.fillWidth: true
+
+                    Label {
+                        text: modelData.name + "  (" + modelData.plugins.join(", ") + ")"
+                        Layout.fillWidth: true
+                        elide: Text.ElideRight
+                    }
+                    Button {
+                        text: "Replace"
+                        flat: true
+                        onClicked: chainNameField.text = modelData.name
+                    }
+                    Button {
+                        text: "Delete"
+                        flat: true
+                        onClicked: {
+                            controller.delete_chain_preset(modelData.name)
+                            chainPresetDialog.chains = JSON.parse(controller.get_chain_presets_json())
+                        }
+                    }
+                }
             }
         }
 
-        MenuItem {
-            text: "Auto Layout"
-            onTriggered: runAutoLayout()
+        onAccepted: {
+            var name = chainNameField.text.trim()
+            if (name.length > 0 && nodeId >= 0)
+                controller.save_chain_preset(nodeId, name)
         }
     }
 
+    // A connection that would feed a node's output back into its own input
+    // through other nodes waits here until the user says to go ahead.
     Dialog {
-        id: renameDialog
-        title: "Rename Plugin"
-        standardButtons: Dialog.Ok | Dialog.Cancel
+        id: feedbackDialog
+        title: "Feedback Loop"
+        standardButtons: Dialog.Yes | Dialog.No
         anchors.centerIn: parent
         modal: true
-        width: 320
+        width: 400
 
-        contentItem: TextField {
-            id: renameField
-            placeholderText: "New name"
-            selectByMouse: true
-            onAccepted: renameDialog.accept()
+        property int outputPortId: -1
+        property int inputPortId: -1
+        property bool pair: false
+        property string path: ""
+
+        contentItem: Text {
+            text: "This connection closes an audio feedback loop:\n\n" + feedbackDialog.path
+                  + "\n\nSound would keep going around it and can get very loud. Connect anyway?"
+            wrapMode: Text.WordWrap
+            color: Theme.textPrimary
+            padding: 12
         }
 
-        onAccepted: {
-            var newName = renameField.text.trim()
-            if (newName.length > 0 && contextNodeId >= 0) {
-                controller.rename_plugin(contextNodeId, newName)
+        onAccepted: controller.connect_ports_anyway(outputPortId, inputPortId, pair)
+    }
+
+    // What a probe heard along the route, node by node.
+    Dialog {
+        id: probeDialog
+        title: "Does Sound Reach?"
+        standardButtons: Dialog.Close
+        anchors.centerIn: parent
+        modal: true
+        width: 420
+
+        property var report: null
+
+        function summary() {
+            if (!report)
+                return "Listening along the route..."
+            switch (report.finding.kind) {
+            case "noRoute":
+                return "No audio links lead from " + report.source + " to " + report.target + "."
+            case "sourceSilent":
+                return report.source + " is silent. Check that it is playing and not muted."
+            case "lostAt":
+                return "Sound reaches " + report.lostAt + " but nothing comes out of it."
+                       + " Check whether it is muted, bypassed or turned down."
+            case "reachesInputs":
+                return "Sound reaches the inputs of " + report.target + "."
+            default:
+                return "Sound reaches " + report.target + "."
+            }
+        }
+
+        contentItem: ColumnLayout {
+            spacing: 8
+
+            Text {
+                Layout.fillWidth: true
+                Layout.margins: 12
+                text: probeDialog.summary()
+                wrapMode: Text.WordWrap
+                color: Theme.textPrimary
+            }
+
+            Repeater {
+                model: probeDialog.report ? probeDialog.report.route : []
+                RowLayout {
+                    required property var modelData
+                    Layout.fillWidth: true
+                    Layout.leftMargin: 12
+                    Layout.rightMargin: 12
+
+                    Text {
+                        Layout.fillWidth: true
+                        text: modelData.name
+                        elide: Text.ElideRight
+                        color: Theme.textPrimary
+                    }
+                    Text {
+                        text: modelData.levelDb === null ? "not measured"
+                              : modelData.silent ? "silent"
+                              : modelData.levelDb.toFixed(1) + " dB"
+                        color: modelData.silent ? Theme.statusBypassed : Theme.textSecondary
+                    }
+                }
             }
         }
     }
 
+    Connections {
+        target: controller
+        function onProbe_finished(report) {
+            probeDialog.report = JSON.parse(report)
+            probeDialog.open()
+        }
+        function onParameters_changed(nodeId) {
+            if (!knobDrag)
+                graphView.refreshQuickParams(nodeId)
+        }
+        function onFeedback_loop_blocked(outputPortId, inputPortId, pair, path) {
+            feedbackDialog.outputPortId = outputPortId
+            feedbackDialog.inputPortId = inputPortId
+            feedbackDialog.pair = pair
+            feedbackDialog.path = path
+            feedbackDialog.open()
+        }
+    }
+
     // Confirmation dialog for inserting a plugin node onto a link
     property int pendingInsertLinkId: -1
     property int pendingInsertNodeId: -1
@@ -636,6 +1555,50 @@ Item {
         }
     }
 
+    // Nodes used to be keyed by "Type:DisplayName". Move anything saved under
+    // a node's old key over to its persistent one the first time it shows up.
+    function migrateLegacyKeys() {
+        var layoutMoved = false
+        var hiddenMoved = false
+        var pinnedMoved = false
+        for (var i = 0; i < nodes.length; i++) {
+            var n = nodes[i]
+            var oldKey = n.legacyKey || ""
+            var key = n.layoutKey || ""
+            if (!oldKey || !key || oldKey === key) continue
+            if (savedLayout[oldKey] && !savedLayout[key]) {
+                savedLayout[key] = savedLayout[oldKey]
+                delete savedLayout[oldKey]
+                layoutMoved = true
+            }
+            if (hiddenNodes[oldKey]) {
+                hiddenNodes[key] = true
+                delete hiddenNodes[oldKey]
+                hiddenMoved = true
+            }
+            if (pinnedNodes[oldKey]) {
+                pinnedNodes[key] = true
+                delete pinnedNodes[oldKey]
+                pinnedMoved = true
+            }
+            if (defaultNodeKey === oldKey) {
+                defaultNodeKey = key
+                controller.set_default_node(key)
+            }
+        }
+        if (layoutMoved) {
+            controller.save_layout(JSON.stringify(savedLayout))
+        }
+        if (hiddenMoved) {
+            hiddenNodes = hiddenNodes
+            persistHidden()
+        }
+        if (pinnedMoved) {
+            pinnedNodes = pinnedNodes
+            persistPinned()
+        }
+    }
+
     function persistHidden() {
         var arr = []
         for (var k in hiddenNodes) {
@@ -775,22 +1738,22 @@ Item {
 
     function getNodeColumn(type) {
         if (!type) return "stream"
-        if (type === "Source") return "source"
-        if (type === "Sink" || type === "Duplex") return "sink"
+        if (type === AppController.NodeKind.Source) return "source"
+        if (type === AppController.NodeKind.Sink || type === AppController.NodeKind.Duplex) return "sink"
         return "stream"
     }
 
     function getNodeColor(node) {
-        if (node.mediaType === "Midi") return colMidi
+        if (node.mediaType === AppController.MediaKind.Midi) return colMidi
         if (node.isJack) return colJack
         var type = node.type
         if (!type) return colDefault
-        if (type === "Sink") return node.isVirtual ? colVirtualSink : colSink
-        if (type === "Source") return node.isVirtual ? colVirtualSource : colSource
-        if (type === "StreamOutput") return colStreamOut
-        if (type === "StreamInput") return colStreamIn
-        if (type === "Duplex") return colDuplex
-        if (type === "Plugin") return colLv2
+        if (type === AppController.NodeKind.Sink) return node.isVirtual ? colVirtualSink : colSink
+        if (type === AppController.NodeKind.Source) return node.isVirtual ? colVirtualSource : colSource
+        if (type === AppController.NodeKind.StreamOutput) return colStreamOut
+        if (type === AppController.NodeKind.StreamInput) return colStreamIn
+        if (type === AppController.NodeKind.Duplex) return colDuplex
+        if (type === AppController.NodeKind.Plugin) return colLv2
         return colDefault
     }
 
@@ -800,11 +1763,62 @@ Item {
         var outputs = ports.filter(function(p) { return p.direction === "Output" }).length
         var rows = Math.max(inputs, outputs, 1)
         var h = headerHeight + nodePadding * 2 + rows * (portHeight + portSpacing)
-        if (node.type === "Plugin")
+        if (node.type === AppController.NodeKind.Plugin)
             h += buttonRowHeight + nodePadding
+        if (quickParams[node.id])
+            h += knobRowHeight
         return h
     }
 
+    function refreshQuickParams(nodeId) {
+        if (!quickParams[nodeId]) return
+        try {
+            var quick = JSON.parse(controller.get_node_quick_params_json(nodeId))
+            if (quick.parameters && quick.parameters.length > 0) {
+                quickParams[nodeId] = quick.parameters
+                quickParams = quickParams
+                canvas.requestPaint()
+            }
+        } catch(e) {}
+    }
+
+    // The knob under a point, as { nodeId, param }, or null.
+    function findKnobAt(sx, sy) {
+        var c = toCanvas(sx, sy)
+        for (var i = nodes.length - 1; i >= 0; i--) {
+            var n = nodes[i]
+            var params = quickParams[n.id]
+            if (!params) continue
+            if (n.layoutKey && hiddenNodes[n.layoutKey]) continue
+            var pos = nodePositions[n.id]
+            if (!pos) continue
+            var h = calculateNodeHeight(n)
+            var nw = getNodeWidth(n.id)
+            var rowY = pos.y + h - buttonRowHeight - nodePadding - knobRowHeight
+            if (c.y < rowY || c.y > rowY + knobRowHeight) continue
+            var slotW = (nw - nodePadding * 2) / params.length
+            var slot = Math.floor((c.x - pos.x - nodePadding) / slotW)
+            if (slot >= 0 && slot < params.length)
+                return { nodeId: n.id, param: params[slot] }
+        }
+        return null
+    }
+
+    function setQuickParam(nodeId, param, value) {
+        value = Math.max(param.min, Math.min(param.max, value))
+        if (value === param.value) return
+        param.value = value
+        controller.set_plugin_parameter(nodeId, param.portIndex, value)
+        canvas.requestPaint()
+    }
+
+    function formatQuickValue(param) {
+        if (param.isToggle) return param.value >= 0.5 ? "on" : "off"
+        var v = param.value
+        var a = Math.abs(v)
+        return a >= 1000 ? (v / 1000).toFixed(1) + "k" : a >= 100 ? v.toFixed(0) : a >= 10 ? v.toFixed(1) : v.toFixed(2)
+    }
+
     function calculateNodeWidths(ctx) {
         var newWidths = {}
         ctx.save()
@@ -878,6 +1892,30 @@ Item {
         return ""
     }
 
+    function findPortData(portId) {
+        if (portId < 0) return null
+        for (var nid in portsByNode) {
+            var ports = portsByNode[nid]
+            for (var i = 0; i < ports.length; i++) {
+                if (ports[i].id === portId) return Object.assign({ index: i }, ports[i])
+            }
+        }
+        return null
+    }
+
+    // Audio ports on the same side of the node that `port` could pair with
+    function pairCandidates(port) {
+        var result = []
+        var ports = portsByNode[port.nodeId] || []
+        for (var i = 0; i < ports.length; i++) {
+            var p = ports[i]
+            if (p.id !== port.id && p.id !== port.pairPortId && p.direction === port.direction
+                    && p.mediaType === AppController.MediaKind.Audio)
+                result.push({ id: p.id, name: p.name, index: i })
+        }
+        return result
+    }
+
     function getPortNodeId(portId) {
         for (var nid in portsByNode) {
             var ports = portsByNode[nid]
@@ -927,7 +1965,7 @@ Item {
         var c = toCanvas(sx, sy)
         for (var i = nodes.length - 1; i >= 0; i--) {
             var n = nodes[i]
-            if (n.type !== "Plugin") continue
+            if (n.type !== AppController.NodeKind.Plugin) continue
             if (n.layoutKey && hiddenNodes[n.layoutKey]) continue
             var pos = nodePositions[n.id]
             if (!pos) continue
@@ -985,9 +2023,9 @@ Item {
                 var y = pos.y
                 var ports = portsByNode[node.id] || []
                 var inputs = ports.filter(function(p) { return p.direction === "Input" })
-                    .sort(function(a, b) { var am = a.mediaType === "Midi" ? 0 : 1; var bm = b.mediaType === "Midi" ? 0 : 1; return am !== bm ? am - bm : naturalCmp(a.name, b.name) })
+                    .sort(function(a, b) { var am = portRank(a), bm = portRank(b); return am !== bm ? am - bm : naturalCmp(a.name, b.name) })
                 var outputs = ports.filter(function(p) { return p.direction === "Output" })
-                    .sort(function(a, b) { var am = a.mediaType === "Midi" ? 0 : 1; var bm = b.mediaType === "Midi" ? 0 : 1; return am !== bm ? am - bm : naturalCmp(a.name, b.name) })
+                    .sort(function(a, b) { var am = portRank(a), bm = portRank(b); return am !== bm ? am - bm : naturalCmp(a.name, b.name) })
                 var nw = getNodeWidth(node.id)
 
                 var portBaseY = y + headerHeight + nodePadding
@@ -1008,14 +2046,39 @@ Item {
                 var toPos = newPortPositions[link.inputPortId]
                 if (fromPos && toPos) {
                     var isSelected = selectedLinks[link.id] === true
-                    var isMidiLink = portMediaTypes[link.outputPortId] === "Midi"
-                                  || portMediaTypes[link.inputPortId] === "Midi"
+                    var isMidiLink = portMediaTypes[link.outputPortId] === AppController.MediaKind.Midi
+                                  || portMediaTypes[link.inputPortId] === AppController.MediaKind.Midi
+                    var isCvLink = portMediaTypes[link.outputPortId] === AppController.MediaKind.Cv
                     var linkColor = isSelected ? ("" + Theme.colLinkSelected)
                                   : isMidiLink ? colLinkMidi
+                                  : isCvLink ? colLinkCv
+                                  : link.sidechain ? colLinkSidechain
                                   : (link.active ? colLinkActive : colLinkInactive)
                     var linkWidth = isSelected ? 3 : 2
+                    // Links whose rule is switched off are dimmed: they
+                    // stay until removed but nothing restores them.
+                    var ruleOff = link.ruleEnabled === false && !isSelected
+                    if (ruleOff) {
+                        ctx.save()
+                        ctx.globalAlpha = 0.35
+                    }
+                    if (link.sidechain)
+                        ctx.setLineDash([6 / zoom, 4 / zoom])
                     drawBezier(ctx, fromPos.cx, fromPos.cy, toPos.cx, toPos.cy,
                         linkColor, linkWidth)
+                    if (link.sidechain)
+                        ctx.setLineDash([])
+                    if (ruleOff)
+                        ctx.restore()
+                    if (link.conversion)
+                        drawConversionBadge(ctx, (fromPos.cx + toPos.cx) / 2,
+                            (fromPos.cy + toPos.cy) / 2, link.conversion.label)
+                    else if (link.midiFilter)
+                        drawConversionBadge(ctx, (fromPos.cx + toPos.cx) / 2,
+                            (fromPos.cy + toPos.cy) / 2, "filtered")
+                    else if (typeof link.gainDb === "number")
+                        drawConversionBadge(ctx, (fromPos.cx + toPos.cx) / 2,
+                            (fromPos.cy + toPos.cy) / 2, gainLabel(link.gainDb))
                 }
             }
 
@@ -1030,18 +2093,22 @@ Item {
                 var ny = pos2.y
                 var nPorts = portsByNode[node2.id] || []
                 var nInputs = nPorts.filter(function(p) { return p.direction === "Input" })
-                    .sort(function(a, b) { var am = a.mediaType === "Midi" ? 0 : 1; var bm = b.mediaType === "Midi" ? 0 : 1; return am !== bm ? am - bm : naturalCmp(a.name, b.name) })
+                    .sort(function(a, b) { var am = portRank(a), bm = portRank(b); return am !== bm ? am - bm : naturalCmp(a.name, b.name) })
                 var nOutputs = nPorts.filter(function(p) { return p.direction === "Output" })
-                    .sort(function(a, b) { var am = a.mediaType === "Midi" ? 0 : 1; var bm = b.mediaType === "Midi" ? 0 : 1; return am !== bm ? am - bm : naturalCmp(a.name, b.name) })
+                    .sort(function(a, b) { var am = portRank(a), bm = portRank(b); return am !== bm ? am - bm : naturalCmp(a.name, b.name) })
                 var nh = calculateNodeHeight(node2)
                 var nnw = getNodeWidth(node2.id)
 
                 var isNodeSelected = selectedNodes[node2.id] === true
                 var isDefaultNode = defaultNodeKey !== "" && node2.layoutKey === defaultNodeKey
+                var isCrossHighlighted = crossHighlightName !== "" && node2.name === crossHighlightName
                 ctx.fillStyle = "" + colNodeBg
                 if (isNodeSelected) {
                     ctx.strokeStyle = "" + Theme.selectionOutline
                     ctx.lineWidth = 2.5
+                } else if (isCrossHighlighted) {
+                    ctx.strokeStyle = "" + Theme.snapGuide
+                    ctx.lineWidth = 2.5
                 } else if (isDefaultNode) {
                     ctx.strokeStyle = "" + colDefaultOutline
                     ctx.lineWidth = 2.5
@@ -1097,8 +2164,26 @@ Item {
                 ctx.stroke()
                 ctx.restore()
 
+                // Rule badge: routed by a rule, or only by disabled ones
+                if (node2.ruleState) {
+                    var ruleOn = node2.ruleState === "enabled"
+                    var ruleColor = ruleOn ? ("" + Theme.buttonActiveBorder) : ("" + Theme.statusBypassed)
+                    ctx.save()
+                    ctx.globalAlpha = ruleOn ? 1.0 : 0.6
+                    ctx.font = "bold 8px sans-serif"
+                    ctx.fillStyle = "" + Theme.formatBadgeBg
+                    ctx.strokeStyle = ruleColor
+                    ctx.lineWidth = 1
+                    roundRect(ctx, nx + 4, ny + 3, 12, 12, 2)
+                    ctx.fillStyle = ruleColor
+                    ctx.textAlign = "center"
+                    ctx.textBaseline = "middle"
+                    ctx.fillText("R", nx + 10, ny + 9)
+                    ctx.restore()
+                }
+
                 // Draw format badge (LV2/CLAP/VST3) for plugin nodes
-                if (node2.type === "Plugin" && node2.pluginFormat) {
+                if (node2.type === AppController.NodeKind.Plugin && node2.pluginFormat) {
                     var fmt = node2.pluginFormat
                     var badgeColor = fmt === "CLAP" ? ("" + Theme.badgeClapBg) : fmt === "VST3" ? ("" + Theme.badgeVst3Bg) : ("" + Theme.badgeLv2Bg)
                     var badgeTextCol = fmt === "CLAP" ? ("" + Theme.badgeClapText) : fmt === "VST3" ? ("" + Theme.badgeVst3Text) : ("" + Theme.badgeLv2Text)
@@ -1118,12 +2203,33 @@ Item {
                     ctx.fillText(fmt, badgeX + badgeW / 2, badgeY + badgeH / 2)
                 }
 
+                // Negotiated audio format, straddling the node's bottom edge
+                if (node2.audioFormat && node2.audioFormat.label) {
+                    var afText = node2.audioFormat.label
+                    if (node2.audioFormat.quantum)
+                        afText += " @ " + node2.audioFormat.quantum
+                    ctx.font = "8px sans-serif"
+                    var afW = ctx.measureText(afText).width + 8
+                    var afH = 12
+                    var afX = nx + (nnw - afW) / 2
+                    var afY = ny + nh - afH / 2
+                    ctx.fillStyle = "" + Theme.formatBadgeBg
+                    ctx.strokeStyle = "" + colNodeBorder
+                    ctx.lineWidth = 1
+                    roundRect(ctx, afX, afY, afW, afH, 2)
+                    ctx.fillStyle = "" + Theme.formatBadgeText
+                    ctx.textAlign = "center"
+                    ctx.textBaseline = "middle"
+                    ctx.fillText(afText, afX + afW / 2, afY + afH / 2)
+                }
+
                 var nPortBaseY = ny + headerHeight + nodePadding
                 for (var npi = 0; npi < nInputs.length; npi++) {
                     var npy = nPortBaseY + npi * (portHeight + portSpacing) + portHeight / 2
                     var npx = nx
 
-                    ctx.fillStyle = nInputs[npi].mediaType === "Midi" ? ("" + colMidiPort) : ("" + colPortIn)
+                    ctx.fillStyle = nInputs[npi].mediaType === AppController.MediaKind.Midi ? ("" + colMidiPort)
+                                  : nInputs[npi].mediaType === AppController.MediaKind.Cv ? ("" + colCvPort) : ("" + colPortIn)
                     ctx.beginPath()
                     ctx.arc(npx, npy, portRadius, 0, Math.PI * 2)
                     ctx.fill()
@@ -1152,7 +2258,8 @@ Item {
                     var npyo = nPortBaseY + npo * (portHeight + portSpacing) + portHeight / 2
                     var npxo = nx + nnw
 
-                    ctx.fillStyle = nOutputs[npo].mediaType === "Midi" ? ("" + colMidiPort) : ("" + colPortOut)
+                    ctx.fillStyle = nOutputs[npo].mediaType === AppController.MediaKind.Midi ? ("" + colMidiPort)
+                                  : nOutputs[npo].mediaType === AppController.MediaKind.Cv ? ("" + colCvPort) : ("" + colPortOut)
                     ctx.beginPath()
                     ctx.arc(npxo, npyo, portRadius, 0, Math.PI * 2)
                     ctx.fill()
@@ -1177,7 +2284,41 @@ Item {
                     ctx.fillText(truncate(nOutputs[npo].name, 24), npxo - portRadius - 4, npyo)
                 }
 
-                if (node2.type === "Plugin") {
+                var knobParams = quickParams[node2.id]
+                if (knobParams) {
+                    var knobRowY = ny + nh - buttonRowHeight - nodePadding - knobRowHeight
+                    var knobSlotW = (nnw - nodePadding * 2) / knobParams.length
+                    var knobStart = Math.PI * 0.75
+                    var knobSweep = Math.PI * 1.5
+                    for (var nki = 0; nki < knobParams.length; nki++) {
+                        var kp = knobParams[nki]
+                        var kcx = nx + nodePadding + knobSlotW * (nki + 0.5)
+                        var kcy = knobRowY + knobRadius + 2
+                        var kfrac = kp.max > kp.min ? (kp.value - kp.min) / (kp.max - kp.min) : 0
+                        kfrac = Math.max(0, Math.min(1, kfrac))
+
+                        ctx.lineWidth = 3
+                        ctx.strokeStyle = "" + Theme.buttonBorder
+                        ctx.beginPath()
+                        ctx.arc(kcx, kcy, knobRadius, knobStart, knobStart + knobSweep)
+                        ctx.stroke()
+                        if (kfrac > 0) {
+                            ctx.strokeStyle = "" + colLv2
+                            ctx.beginPath()
+                            ctx.arc(kcx, kcy, knobRadius, knobStart, knobStart + knobSweep * kfrac)
+                            ctx.stroke()
+                        }
+
+                        ctx.fillStyle = "" + Theme.textSecondary
+                        ctx.font = "8px sans-serif"
+                        ctx.textAlign = "center"
+                        ctx.textBaseline = "middle"
+                        ctx.fillText(formatQuickValue(kp), kcx, kcy)
+                        ctx.fillText(truncate(kp.name, Math.max(4, Math.floor(knobSlotW / 5))), kcx, kcy + knobRadius + 9)
+                    }
+                }
+
+                if (node2.type === AppController.NodeKind.Plugin) {
                     var btnY2 = ny + nh - buttonRowHeight - nodePadding
                     var btnW3 = (nnw - nodePadding * 4) / 3
                     var btnH2 = buttonRowHeight
@@ -1292,6 +2433,37 @@ Item {
             clearSelection()
             event.accepted = true
         }
+        if (event.matches(StandardKey.Copy)) {
+            copySelection()
+            event.accepted = true
+        }
+        if (event.matches(StandardKey.Paste)) {
+            pasteFromClipboard()
+            event.accepted = true
+        }
+        if (event.key === Qt.Key_C && event.modifiers === Qt.NoModifier) {
+            var selected = Object.keys(selectedNodes).filter(function(k) { return selectedNodes[k] })
+            if (selected.length === 1)
+                quickConnect(parseInt(selected[0]))
+            event.accepted = true
+        }
+        // S flips the selected A/B switches, or all of them when none is selected.
+        if (event.key === Qt.Key_S && event.modifiers === Qt.NoModifier) {
+            var flipped = 0
+            for (var nid in selectedNodes) {
+                if (selectedNodes[nid] && controller.toggle_ab_switch(parseInt(nid)))
+                    flipped++
+            }
+            if (flipped === 0)
+                controller.toggle_ab_switches()
+            event.accepted = true
+        }
+    }
+
+    // Qt Quick has no clipboard API of its own; this reaches the system one.
+    TextEdit {
+        id: clipboardHelper
+        visible: false
     }
 
     MouseArea {
@@ -1313,6 +2485,30 @@ Item {
             }
 
             if (mouse.button === Qt.RightButton) {
+                var menuPort = findPortData(findPortAt(mouse.x, mouse.y))
+                if (menuPort && menuPort.mediaType === AppController.MediaKind.Audio) {
+                    portContextMenu.port = menuPort
+                    portContextMenu.candidates = pairCandidates(menuPort)
+                    portContextMenu.popup()
+                    return
+                }
+                var menuLinkId = findLinkAt(mouse.x, mouse.y)
+                var menuLink = links.find(function(l) { return l.id === menuLinkId })
+                if (menuLink && portMediaTypes[menuLink.outputPortId] === AppController.MediaKind.Midi) {
+                    midiLinkDialog.openFor(menuLinkId)
+                    return
+                }
+                if (menuLink && portMediaTypes[menuLink.outputPortId] === AppController.MediaKind.Audio) {
+                    var chains = JSON.parse(controller.get_chain_presets_json())
+                    if (chains.length > 0) {
+                        linkMenu.link = menuLink
+                        linkMenu.chains = chains
+                        linkMenu.popup()
+                    } else {
+                        linkGainDialog.openFor(menuLink)
+                    }
+                    return
+                }
                 var nodeId = findNodeAt(mouse.x, mouse.y)
                 if (nodeId >= 0) {
                     contextNodeId = nodeId
@@ -1329,6 +2525,18 @@ Item {
             }
 
             if (mouse.button === Qt.LeftButton) {
+                var knobHit = findKnobAt(mouse.x, mouse.y)
+                if (knobHit) {
+                    if (knobHit.param.isToggle) {
+                        setQuickParam(knobHit.nodeId, knobHit.param,
+                                      knobHit.param.value >= 0.5 ? knobHit.param.min : knobHit.param.max)
+                    } else {
+                        knobDrag = { nodeId: knobHit.nodeId, param: knobHit.param,
+                                     startY: mouse.y, startValue: knobHit.param.value }
+                    }
+                    return
+                }
+
                 var pinHit = findPinButtonAt(mouse.x, mouse.y)
                 if (pinHit && pinHit.layoutKey) {
                     var pk = pinHit.layoutKey
@@ -1396,6 +2604,9 @@ Item {
                         selectedNodes = {}
                         selectedLinks = {}
                         dragNodeId = nodeIdDrag
+                        var focusedNode = findNodeData(nodeIdDrag)
+                        if (focusedNode)
+                            graphView.nodeFocused(focusedNode.name)
                         var c = toCanvas(mouse.x, mouse.y)
                         var pos = nodePositions[nodeIdDrag]
                         dragOffsetX = c.x - pos.x
@@ -1436,6 +2647,15 @@ Item {
         }
 
         onPositionChanged: (mouse) => {
+            if (knobDrag) {
+                // 150 px of travel covers the whole range; Shift for fine steps.
+                var kRange = knobDrag.param.max - knobDrag.param.min
+                var kSpan = (mouse.modifiers & Qt.ShiftModifier) ? 1500 : 150
+                setQuickParam(knobDrag.nodeId, knobDrag.param,
+                              knobDrag.startValue + (knobDrag.startY - mouse.y) / kSpan * kRange)
+                return
+            }
+
             if (mouse.buttons & Qt.MiddleButton) {
                 panX += mouse.x - lastX
                 panY += mouse.y - lastY
@@ -1528,6 +2748,10 @@ Item {
 
         onReleased: (mouse) => {
             activeSnapLines = []
+            if (knobDrag) {
+                knobDrag = null
+                return
+            }
             if (mouse.button === Qt.LeftButton) {
                 if (connectFromPortId >= 0) {
                     var targetId = findPortAt(mouse.x, mouse.y)
@@ -1536,11 +2760,14 @@ Item {
                         var fromNodeId = getPortNodeId(connectFromPortId)
                         var toNodeId = getPortNodeId(targetId)
                         if (targetDir !== connectFromDir && fromNodeId !== toNodeId) {
-                            if (connectFromDir === "Output") {
-                                controller.connect_ports(connectFromPortId, targetId)
-                            } else {
-                                controller.connect_ports(targetId, connectFromPortId)
-                            }
+                            var outId = connectFromDir === "Output" ? connectFromPortId : targetId
+                            var inId = connectFromDir === "Output" ? targetId : connectFromPortId
+                            if (mouse.modifiers & Qt.AltModifier)
+                                controller.create_temporary_link(outId, inId, temporaryLinkSecs)
+                            else if (mouse.modifiers & Qt.ShiftModifier)
+                                controller.connect_port_pair(outId, inId)
+                            else
+                                controller.connect_ports(outId, inId)
                         }
                     }
                     connectFromPortId = -1
@@ -1568,7 +2795,7 @@ Item {
                     var linkUnder = findLinkUnderNode(dragNodeId)
                     if (linkUnder >= 0) {
                         var draggedNode = findNodeData(dragNodeId)
-                        if (draggedNode && draggedNode.type === "Plugin") {
+                        if (draggedNode && draggedNode.type === AppController.NodeKind.Plugin) {
                             pendingInsertLinkId = linkUnder
                             pendingInsertNodeId = dragNodeId
                             insertConfirmDialog.open()
@@ -1597,6 +2824,39 @@ Item {
             }
         }
 
+        onDoubleClicked: (mouse) => {
+            if (mouse.button !== Qt.LeftButton)
+                return
+            var knobReset = findKnobAt(mouse.x, mouse.y)
+            if (knobReset) {
+                setQuickParam(knobReset.nodeId, knobReset.param, knobReset.param.default)
+                return
+            }
+            if (findButtonAt(mouse.x, mouse.y) || findPinButtonAt(mouse.x, mouse.y)
+                    || findPortAt(mouse.x, mouse.y) >= 0)
+                return
+            var nodeId = findNodeAt(mouse.x, mouse.y)
+            if (nodeId < 0)
+                return
+            // The controller runs the configured action and hands back the
+            // ones that need a dialog or view state.
+            var action = controller.activate_node(nodeId)
+            var node = findNodeData(nodeId)
+            if (action === "params") {
+                graphView.openPluginParams(nodeId)
+            } else if (action === "rename" && node) {
+                contextNodeId = nodeId
+                contextNode = node
+                renameField.text = node.name
+                renameDialog.open()
+            } else if (action === "hide" && node && node.layoutKey) {
+                hiddenNodes[node.layoutKey] = true
+                hiddenNodes = hiddenNodes
+                persistHidden()
+                canvas.requestPaint()
+            }
+        }
+
         onWheel: (wheel) => {
             var oldZoom = zoom
             var factor = wheel.angleDelta.y > 0 ? 1.1 : 0.9
@@ -1614,6 +2874,68 @@ Item {
         }
     }
 
+    function hasSidechainInputs(nodeId) {
+        var ports = portsByNode[nodeId] || []
+        for (var i = 0; i < ports.length; i++) {
+            if (ports[i].sidechain) return true
+        }
+        return false
+    }
+
+    // Nodes with audio outputs that could feed a sidechain, excluding the plugin itself
+    function sidechainCandidates(nodeId) {
+        var result = []
+        for (var i = 0; i < nodes.length; i++) {
+            var n = nodes[i]
+            if (n.id === nodeId || n.isGroup) continue
+            var ports = portsByNode[n.id] || []
+            for (var p = 0; p < ports.length; p++) {
+                if (ports[p].direction === "Output" && ports[p].mediaType === AppController.MediaKind.Audio) {
+                    result.push({ id: n.id, name: n.name })
+                    break
+                }
+            }
+        }
+        return result
+    }
+
+    function hasAudioOutputs(nodeId) {
+        var ports = portsByNode[nodeId] || []
+        for (var i = 0; i < ports.length; i++) {
+            if (ports[i].direction === "Output" && ports[i].mediaType === AppController.MediaKind.Audio)
+                return true
+        }
+        return false
+    }
+
+    // Nodes with audio inputs a probe could listen towards
+    function probeTargets(nodeId) {
+        var result = []
+        for (var i = 0; i < nodes.length; i++) {
+            var n = nodes[i]
+            if (n.id === nodeId || n.isGroup) continue
+            var ports = portsByNode[n.id] || []
+            for (var p = 0; p < ports.length; p++) {
+                if (ports[p].direction === "Input" && ports[p].mediaType === AppController.MediaKind.Audio) {
+                    result.push({ id: n.id, name: n.name })
+                    break
+                }
+            }
+        }
+        return result
+    }
+
+    // Sinks and duplex devices a node could be auditioned on
+    function auditionSinks(nodeId) {
+        var result = []
+        for (var i = 0; i < nodes.length; i++) {
+            var n = nodes[i]
+            if (n.id !== nodeId && (n.type === AppController.NodeKind.Sink || n.type === AppController.NodeKind.Duplex))
+                result.push({ id: n.id, name: n.name })
+        }
+        return result
+    }
+
     function drawBezier(ctx, x1, y1, x2, y2, color, lineWidth) {
         var ctrlDist = Math.max(Math.abs(x2 - x1) / 2, 50)
         ctx.strokeStyle = "" + color
@@ -1624,6 +2946,22 @@ Item {
         ctx.stroke()
     }
 
+    // Marks a link where PipeWire converts the sample rate or format,
+    // centred on the link's midpoint.
+    function drawConversionBadge(ctx, cx, cy, label) {
+        ctx.font = "8px sans-serif"
+        var w = ctx.measureText(label).width + 8
+        var h = 12
+        ctx.fillStyle = "" + Theme.formatBadgeBg
+        ctx.strokeStyle = "" + Theme.statusBypassed
+        ctx.lineWidth = 1
+        roundRect(ctx, cx - w / 2, cy - h / 2, w, h, 2)
+        ctx.fillStyle = "" + Theme.statusBypassed
+        ctx.textAlign = "center"
+        ctx.textBaseline = "middle"
+        ctx.fillText(label, cx, cy)
+    }
+
     function roundRect(ctx, x, y, w, h, r) {
         ctx.beginPath()
         ctx.moveTo(x + r, y)
@@ -1836,6 +3174,55 @@ Item {
         }
     }
 
+    function proposeConnection(nodeId) {
+        var json = controller.propose_connection(nodeId)
+        return json ? JSON.parse(json) : null
+    }
+
+    // Connect a node to its usual counterpart: a target for a node with
+    // outputs, a source for one with only inputs.
+    function quickConnect(nodeId) {
+        var proposal = proposeConnection(nodeId)
+        if (!proposal) return
+        if (proposal.direction === "target")
+            controller.connect_nodes(nodeId, proposal.node_id)
+        else
+            controller.connect_nodes(proposal.node_id, nodeId)
+    }
+
+    function copySelection() {
+        var ids = []
+        for (var nid in selectedNodes) {
+            if (selectedNodes[nid]) ids.push(parseInt(nid))
+        }
+        copyNodes(ids)
+    }
+
+    // Put the plugins among node ids and the links between them on the
+    // clipboard as a snippet that paste_selection can re-create.
+    function copyNodes(ids) {
+        if (ids.length === 0) return
+        var snippet = controller.copy_selection(JSON.stringify(ids))
+        if (!snippet) return
+        clipboardHelper.text = snippet
+        clipboardHelper.selectAll()
+        clipboardHelper.copy()
+        clipboardHelper.text = ""
+    }
+
+    function pasteFromClipboard() {
+        clipboardHelper.text = ""
+        clipboardHelper.paste()
+        var text = clipboardHelper.text
+        clipboardHelper.text = ""
+        if (!text) return
+        var summary = controller.paste_selection(text)
+        if (summary.indexOf("skipped") >= 0) {
+            pasteDialog.summary = summary
+            pasteDialog.open()
+        }
+    }
+
     function clearSelection() {
         var hadSelection = false
         for (var k in selectedLinks) { hadSelection = true; break }
@@ -0,0 +1,21 @@
+import QtQuick
+import ZestBay
+
+// Loaded instead of main.qml with --headless: the controller and its poll
+// timer without a window. The graph is managed through the remote API.
+Item {
+    AppController {
+        id: controller
+        Component.onCompleted: controller.init()
+        onError_occurred: message => console.warn(message)
+        onPerformance_profileChanged: pollTimer.interval = controller.get_poll_interval_ms()
+    }
+
+    Timer {
+        id: pollTimer
+        interval: controller.get_poll_interval_ms()
+        running: true
+        repeat: true
+        onTriggered: controller.poll_events()
+    }
+}
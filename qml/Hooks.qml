@@ -0,0 +1,201 @@
+import QtQuick
+import QtQuick.Controls
+import QtQuick.Layouts
+import ZestBay
+
+ApplicationWindow {
+    id: hooksWindow
+    title: "Hooks"
+    color: Theme.windowBg
+    width: 760
+    height: 450
+    minimumWidth: 560
+    minimumHeight: 300
+    visible: false
+
+    required property var controller
+
+    property var events: []
+    property var hooks: []
+
+    function loadHooks() {
+        try {
+            var data = JSON.parse(controller.get_hooks_json())
+            events = data.events
+            hooks = data.hooks
+        } catch(e) {
+            events = []
+            hooks = []
+        }
+    }
+
+    function open() {
+        loadHooks()
+        visible = true
+        raise()
+        requestActivate()
+    }
+
+    // Apply one field change and save. A rejected list is reloaded from
+    // what is saved.
+    function update(index, field, value) {
+        var list = hooks.slice()
+        list[index] = Object.assign({}, list[index])
+        list[index][field] = value
+        save(list)
+    }
+
+    function save(list) {
+        if (controller.save_hooks_json(JSON.stringify(list)))
+            hooks = list
+        else
+            loadHooks()
+    }
+
+    function addHook() {
+        var list = hooks.slice()
+        list.push({
+            id: Date.now().toString(36),
+            name: "",
+            event: events.length > 0 ? events[0].value : "device_connected",
+            command: "",
+            enabled: true,
+            timeout_secs: 10
+        })
+        // Saved once it has a command.
+        hooks = list
+    }
+
+    ColumnLayout {
+        anchors.fill: parent
+        anchors.margins: 16
+        spacing: 8
+
+        Label {
+            text: hooks.length + " hook" + (hooks.length !== 1 ? "s" : "")
+            font.bold: true
+            font.pointSize: 11
+        }
+
+        Label {
+            text: "Hooks run a shell command when an event happens. The command gets the event as JSON on stdin and its name in $ZESTBAY_EVENT, and is stopped after its timeout."
+            opacity: 0.5
+            wrapMode: Text.WordWrap
+            Layout.fillWidth: true
+        }
+
+        ListView {
+            id: hookList
+            Layout.fillWidth: true
+            Layout.fillHeight: true
+            clip: true
+            model: hooks.length
+            spacing: 2
+
+            ScrollBar.vertical: ScrollBar { policy: ScrollBar.AsNeeded }
+
+            delegate: Rectangle {
+                required property int index
+                width: hookList.width - 12
+                height: 44
+                color: index % 2 === 0 ? Theme.rowEven : Theme.rowOdd
+                radius: 3
+
+                property var hook: hooks[index] || {}
+
+                RowLayout {
+                    anchors.fill: parent
+                    anchors.margins: 6
+                    spacing: 6
+
+                    CheckBox {
+                        checked: hook.enabled === true
+                        ToolTip.visible: hovered
+                        ToolTip.text: "Run this hook"
+                        onToggled: hooksWindow.update(index, "enabled", checked)
+                    }
+
+                    ComboBox {
+                        model: events
+                        textRole: "label"
+                        valueRole: "value"
+                        implicitWidth: 160
+                        currentIndex: Math.max(0, events.findIndex(e => e.value === hook.event))
+                        onActivated: hooksWindow.update(index, "event", currentValue)
+                    }
+
+                    TextField {
+                        text: hook.name || ""
+                        placeholderText: "Name"
+                        implicitWidth: 120
+                        onEditingFinished: if (text !== (hook.name || "")) hooksWindow.update(index, "name", text)
+                    }
+
+                    TextField {
+                        text: hook.command || ""
+                        placeholderText: "Command, e.g. notify-send \"ZestBay\" \"$ZESTBAY_EVENT\""
+                        font.family: "monospace"
+                        Layout.fillWidth: true
+                        onEditingFinished: if (text !== (hook.command || "")) hooksWindow.update(index, "command", text)
+                    }
+
+                    SpinBox {
+                        from: 1
+                        to: 300
+                        value: hook.timeout_secs || 10
+                        editable: true
+                        implicitWidth: 90
+                        ToolTip.visible: hovered
+                        ToolTip.text: "Timeout in seconds"
+                        onValueModified: hooksWindow.update(index, "timeout_secs", value)
+                    }
+
+                    Button {
+                        text: "Test"
+                        enabled: (hook.command || "").trim() !== ""
+                        ToolTip.visible: hovered
+                        ToolTip.text: "Run now with a test payload; the result goes to the log"
+                        onClicked: controller.test_hook(hook.id)
+                    }
+
+                    Button {
+                        text: "×"
+                        flat: true
+                        implicitWidth: 28
+                        implicitHeight: 28
+                        font.pointSize: 12
+                        ToolTip.visible: hovered
+                        ToolTip.text: "Remove this hook"
+                        onClicked: {
+                            var list = hooks.slice()
+                            list.splice(index, 1)
+                            hooksWindow.save(list.filter(h => (h.command || "").trim() !== ""))
+                        }
+                    }
+                }
+            }
+        }
+
+        Rectangle {
+            Layout.fillWidth: true
+            height: 1
+            color: Theme.separator
+        }
+
+        RowLayout {
+            Layout.fillWidth: true
+
+            Button {
+                text: "Add Hook"
+                onClicked: hooksWindow.addHook()
+            }
+
+            Item { Layout.fillWidth: true }
+
+            Button {
+                text: "Close"
+                onClicked: hooksWindow.visible = false
+            }
+        }
+    }
+}
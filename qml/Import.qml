@@ -0,0 +1,324 @@
+import QtQuick
+import QtQuick.Controls
+import QtQuick.Layouts
+import ZestBay
+
+ApplicationWindow {
+    id: importWindow
+    title: "Import"
+    color: Theme.windowBg
+    width: 600
+    height: 420
+    minimumWidth: 420
+    minimumHeight: 300
+    visible: false
+
+    required property var controller
+
+    property var candidates: []
+    property string result: ""
+
+    function loadCandidates() {
+        try {
+            candidates = JSON.parse(controller.get_import_candidates_json())
+        } catch(e) {
+            candidates = []
+        }
+    }
+
+    function open() {
+        loadCandidates()
+        result = ""
+        visible = true
+        raise()
+        requestActivate()
+    }
+
+    function importPath(path) {
+        if (path.length === 0)
+            return
+        if (path.endsWith(".zestbay")) {
+            var summary = {}
+            try {
+                summary = JSON.parse(controller.check_config_file(path))
+            } catch(e) {}
+            if (summary.error) {
+                result = "Import failed: " + summary.error
+                return
+            }
+            configDialog.path = path
+            configDialog.summary = summary
+            configDialog.open()
+            return
+        }
+        if (path.endsWith(".zestchain")) {
+            var check = {}
+            try {
+                check = JSON.parse(controller.check_chain_file(path))
+            } catch(e) {}
+            if (check.error) {
+                result = "Import failed: " + check.error
+                return
+            }
+            if (check.missing && check.missing.length > 0) {
+                missingDialog.path = path
+                missingDialog.check = check
+                missingDialog.open()
+                return
+            }
+        }
+        result = controller.import_file(path)
+    }
+
+    ColumnLayout {
+        anchors.fill: parent
+        anchors.margins: 16
+        spacing: 8
+
+        Label {
+            text: "Re-create plugin chains from other setups"
+            font.bold: true
+            font.pointSize: 11
+        }
+
+        Label {
+            text: "PipeWire filter-chain configs are rebuilt from the LV2 plugins they use, or known LV2 versions of them. Carla projects (.carxp), RaySession sessions and shared ZestBay chains (.zestchain) are rebuilt with their plugins, parameters and connections. Plugins not found in the catalog are skipped. A ZestBay configuration (.zestbay) from another machine replaces this one's."
+            opacity: 0.5
+            wrapMode: Text.WordWrap
+            Layout.fillWidth: true
+        }
+
+        ListView {
+            id: candidateList
+            Layout.fillWidth: true
+            Layout.fillHeight: true
+            clip: true
+            model: candidates.length
+            spacing: 2
+
+            ScrollBar.vertical: ScrollBar { policy: ScrollBar.AsNeeded }
+
+            delegate: Rectangle {
+                required property int index
+                width: candidateList.width - 12
+                height: 44
+                color: index % 2 === 0 ? Theme.rowEven : Theme.rowOdd
+                radius: 3
+
+                property var candidate: candidates[index] || {}
+
+                RowLayout {
+                    anchors.fill: parent
+                    anchors.margins: 8
+                    spacing: 8
+
+                    ColumnLayout {
+                        Layout.fillWidth: true
+                        spacing: 2
+
+                        Label {
+                            text: candidate.name || ""
+                            font.pointSize: 9
+                            font.bold: true
+                            elide: Text.ElideRight
+                            Layout.fillWidth: true
+                        }
+
+                        Label {
+                            text: candidate.path || ""
+                            font.pointSize: 8
+                            opacity: 0.5
+                            elide: Text.ElideMiddle
+                            Layout.fillWidth: true
+                        }
+                    }
+
+                    Label {
+                        text: candidate.kind || ""
+                        font.pointSize: 7
+                        font.italic: true
+                        opacity: 0.6
+                    }
+
+                    Button {
+                        text: "Import"
+                        onClicked: importWindow.importPath(candidate.path)
+                    }
+                }
+            }
+        }
+
+        Label {
+            text: "No importable files found in the usual locations."
+            opacity: 0.5
+            visible: candidates.length === 0
+            Layout.alignment: Qt.AlignHCenter
+        }
+
+        RowLayout {
+            Layout.fillWidth: true
+            spacing: 8
+
+            TextField {
+                id: pathField
+                Layout.fillWidth: true
+                placeholderText: "Path to a .conf, .carxp, .zestchain or .zestbay file, or a RaySession session"
+                selectByMouse: true
+                onAccepted: importWindow.importPath(text.trim())
+            }
+
+            Button {
+                text: "Import"
+                enabled: pathField.text.trim().length > 0
+                onClicked: importWindow.importPath(pathField.text.trim())
+            }
+        }
+
+        Label {
+            text: result
+            visible: result.length > 0
+            wrapMode: Text.WordWrap
+            Layout.fillWidth: true
+        }
+
+        Rectangle {
+            Layout.fillWidth: true
+            height: 1
+            color: Theme.separator
+        }
+
+        RowLayout {
+            Layout.fillWidth: true
+
+            Item { Layout.fillWidth: true }
+
+            Button {
+                text: "Close"
+                onClicked: importWindow.visible = false
+            }
+        }
+    }
+
+    Dialog {
+        id: missingDialog
+        title: "Missing Plugins"
+        anchors.centerIn: parent
+        modal: true
+        width: 460
+
+        property string path: ""
+        property var check: ({})
+
+        contentItem: ColumnLayout {
+            spacing: 8
+
+            Label {
+                text: {
+                    var c = missingDialog.check
+                    var missing = c.missing ? c.missing.length : 0
+                    return "\"" + (c.name || "") + "\" needs " + missing +
+                           " plugin(s) that are not installed. " +
+                           (c.available > 0
+                            ? c.available + " of " + c.plugins + " plugin(s) can be imported without them."
+                            : "None of its plugins can be imported.")
+                }
+                wrapMode: Text.WordWrap
+                Layout.fillWidth: true
+                Layout.margins: 12
+            }
+
+            Repeater {
+                model: missingDialog.check.missing || []
+
+                delegate: Label {
+                    required property var modelData
+                    text: "\u2022 " + modelData.name + " (" + modelData.format +
+                          (modelData.author ? ", " + modelData.author : "") + ")\n   " + modelData.uri
+                    font.pointSize: 8
+                    wrapMode: Text.WrapAnywhere
+                    Layout.fillWidth: true
+                    Layout.leftMargin: 12
+                    Layout.rightMargin: 12
+                }
+            }
+        }
+
+        footer: DialogButtonBox {
+            Button {
+                text: "Import Available"
+                enabled: missingDialog.check.available > 0
+                DialogButtonBox.buttonRole: DialogButtonBox.AcceptRole
+            }
+            Button {
+                text: "Cancel"
+                DialogButtonBox.buttonRole: DialogButtonBox.RejectRole
+            }
+        }
+
+        onAccepted: importWindow.result = controller.import_file(missingDialog.path)
+    }
+
+    Dialog {
+        id: configDialog
+        title: "Import Configuration"
+        anchors.centerIn: parent
+        modal: true
+        width: 460
+
+        property string path: ""
+        property var summary: ({})
+
+        contentItem: ColumnLayout {
+            spacing: 8
+
+            Label {
+                text: {
+                    var s = configDialog.summary
+                    var when = s.exported > 0
+                        ? new Date(s.exported * 1000).toLocaleString(Qt.locale(), Locale.ShortFormat)
+                        : "an unknown date"
+                    return "This replaces the plugins, links, rules, layout, hidden nodes and preferences " +
+                           "with the ones exported on " + when + ", and restarts ZestBay. " +
+                           (s.matched || 0) + " device(s) and stream(s) were found here; references to " +
+                           "them are updated. Save the current session first to keep it."
+                }
+                wrapMode: Text.WordWrap
+                Layout.fillWidth: true
+                Layout.margins: 12
+            }
+
+            Label {
+                text: "Not found here, left as they were:"
+                visible: (configDialog.summary.missing || []).length > 0
+                Layout.leftMargin: 12
+            }
+
+            Repeater {
+                model: configDialog.summary.missing || []
+
+                delegate: Label {
+                    required property var modelData
+                    text: "\u2022 " + modelData
+                    font.pointSize: 8
+                    elide: Text.ElideRight
+                    Layout.fillWidth: true
+                    Layout.leftMargin: 12
+                    Layout.rightMargin: 12
+                }
+            }
+        }
+
+        footer: DialogButtonBox {
+            Button {
+                text: "Import and Restart"
+                DialogButtonBox.buttonRole: DialogButtonBox.AcceptRole
+            }
+            Button {
+                text: "Cancel"
+                DialogButtonBox.buttonRole: DialogButtonBox.RejectRole
+            }
+        }
+
+        onAccepted: controller.import_config(configDialog.path)
+    }
+}
@@ -0,0 +1,147 @@
+import QtQuick
+import QtQuick.Controls
+import QtQuick.Layouts
+import ZestBay
+
+ApplicationWindow {
+    id: clockWindow
+    title: "MIDI Clock"
+    color: Theme.windowBg
+    width: 480
+    height: 460
+    minimumWidth: 360
+    minimumHeight: 300
+    visible: false
+
+    required property var controller
+
+    property var sources: []
+    property var receivers: []
+    property var conflicts: []
+    property int sourceId: 0
+
+    function reload() {
+        try {
+            var data = JSON.parse(controller.get_clock_routing_json())
+            // Detected clock sources first.
+            sources = (data.sources || []).slice().sort(function(a, b) {
+                return (b.detected ? 1 : 0) - (a.detected ? 1 : 0)
+            })
+            receivers = data.receivers || []
+            conflicts = data.conflicts || []
+            sourceId = data.sourceId || 0
+        } catch(e) {
+            sources = []
+            receivers = []
+            conflicts = []
+            sourceId = 0
+        }
+    }
+
+    function open() {
+        reload()
+        visible = true
+        raise()
+        requestActivate()
+    }
+
+    Connections {
+        target: controller
+        enabled: clockWindow.visible
+        function onGraph_changed() { clockWindow.reload() }
+    }
+
+    ColumnLayout {
+        anchors.fill: parent
+        anchors.margins: 16
+        spacing: 8
+
+        Label {
+            text: "Clock source"
+            font.bold: true
+            font.pointSize: 11
+        }
+
+        ComboBox {
+            Layout.fillWidth: true
+            model: [{ id: 0, label: "None" }].concat(sources.map(function(s) {
+                return { id: s.id, label: s.detected ? s.name + " (sends clock)" : s.name }
+            }))
+            textRole: "label"
+            valueRole: "id"
+            currentIndex: Math.max(0, sources.findIndex(s => s.id === sourceId) + 1)
+            onActivated: {
+                controller.set_clock_source(currentValue)
+                clockWindow.reload()
+            }
+        }
+
+        Label {
+            text: "The source is linked to each checked receiver, and any other clock source feeding a checked receiver is unlinked, so no synth follows two tempos at once."
+            opacity: 0.5
+            wrapMode: Text.WordWrap
+            Layout.fillWidth: true
+        }
+
+        Repeater {
+            model: conflicts
+
+            Label {
+                required property var modelData
+                text: "⚠ " + modelData.receiver + " gets clock from " + modelData.sources.join(" and ")
+                color: Theme.statusBypassed
+                wrapMode: Text.WordWrap
+                Layout.fillWidth: true
+            }
+        }
+
+        Label {
+            text: "Receivers"
+            font.bold: true
+        }
+
+        ListView {
+            id: receiverList
+            Layout.fillWidth: true
+            Layout.fillHeight: true
+            clip: true
+            model: receivers
+            spacing: 2
+
+            ScrollBar.vertical: ScrollBar { policy: ScrollBar.AsNeeded }
+
+            delegate: Rectangle {
+                required property int index
+                required property var modelData
+                width: receiverList.width - 12
+                height: 32
+                color: index % 2 === 0 ? Theme.rowEven : Theme.rowOdd
+                radius: 3
+
+                CheckBox {
+                    anchors.verticalCenter: parent.verticalCenter
+                    anchors.left: parent.left
+                    anchors.leftMargin: 4
+                    text: modelData.name
+                    checked: modelData.enabled === true
+                    enabled: modelData.id !== sourceId
+                    onToggled: {
+                        controller.set_clock_receiver(modelData.id, checked)
+                        clockWindow.reload()
+                    }
+                }
+            }
+        }
+
+        RowLayout {
+            Layout.fillWidth: true
+
+            Item { Layout.fillWidth: true }
+
+            Button {
+                text: "Close"
+                onClicked: clockWindow.visible = false
+            }
+        }
+    }
+}
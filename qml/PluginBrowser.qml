@@ -4,7 +4,7 @@ import QtQuick.Layouts
 
 ApplicationWindow {
     id: pluginBrowser
-    title: "Add Plugin"
+    title: replaceStableId ? "Replace " + replaceName : "Add Plugin"
     color: Theme.windowBg
     width: 700
     height: 500
@@ -19,8 +19,17 @@ ApplicationWindow {
     property var categories: []
     property string selectedCategory: "All"
     property bool showCompatibleOnly: true
+    property bool showAllVersions: false
+    // Stable id of the plugin being replaced; empty when adding.
+    property string replaceStableId: ""
+    property string replaceName: ""
 
     function loadPlugins() {
+        try {
+            showAllVersions = JSON.parse(controller.get_preferences_json()).show_all_plugin_versions === true
+        } catch(e) {
+            showAllVersions = false
+        }
         try {
             allPlugins = JSON.parse(controller.get_available_plugins_json())
         } catch(e) {
@@ -75,7 +84,31 @@ ApplicationWindow {
         filteredPlugins = result
     }
 
+    // One paragraph per known issue: what is wrong, then what can be done.
+    function compatibilityText(uri) {
+        try {
+            var report = JSON.parse(controller.get_plugin_compat_json(uri))
+            var lines = []
+            for (var i = 0; i < (report.issues || []).length; i++)
+                lines.push(report.issues[i].summary + "\n" + report.issues[i].advice)
+            return lines.length > 0 ? lines.join("\n\n") : "No details available"
+        } catch(e) {
+            return "No details available"
+        }
+    }
+
     function open() {
+        replaceStableId = ""
+        present()
+    }
+
+    function openForReplace(stableId, name) {
+        replaceStableId = stableId
+        replaceName = name
+        present()
+    }
+
+    function present() {
         loadPlugins()
         searchField.text = ""
         visible = true
@@ -124,6 +157,19 @@ ApplicationWindow {
 
             Item { Layout.fillWidth: true }
 
+            CheckBox {
+                text: "All versions"
+                checked: showAllVersions
+                ToolTip.visible: hovered
+                ToolTip.text: "List each format a plugin is installed in, not just the preferred one"
+                onToggled: {
+                    controller.set_preference("show_all_plugin_versions", String(checked))
+                    var query = searchField.text
+                    loadPlugins()
+                    searchField.text = query
+                }
+            }
+
             CheckBox {
                 id: compatibleCheck
                 text: "Compatible only"
@@ -148,7 +194,7 @@ ApplicationWindow {
                 id: pluginDelegate
                 required property int index
                 width: pluginList.width
-                height: 64
+                height: 78
                 color: pluginMouseArea.containsMouse ? Theme.rowHover : (index % 2 === 0 ? Theme.rowEven : Theme.rowOdd)
                 opacity: plugin.compatible ? 1.0 : 0.5
 
@@ -183,6 +229,12 @@ ApplicationWindow {
                                     radius: 3
                                 }
                             }
+                            Label {
+                                visible: !showAllVersions && (plugin.otherFormats || []).length > 0
+                                text: "also " + (plugin.otherFormats || []).join(", ")
+                                font.pointSize: 7
+                                opacity: 0.6
+                            }
                             Label {
                                 visible: plugin.hasUi === false
                                 text: "no UI"
@@ -196,6 +248,10 @@ ApplicationWindow {
                                 text: "incompatible"
                                 font.pointSize: 8
                                 color: Theme.statusError
+
+                                HoverHandler { id: compatHover }
+                                ToolTip.visible: compatHover.hovered
+                                ToolTip.text: compatHover.hovered ? compatibilityText(plugin.uri) : ""
                             }
                         }
 
@@ -226,14 +282,45 @@ ApplicationWindow {
                             elide: Text.ElideRight
                             Layout.fillWidth: true
                         }
+
+                        Label {
+                            visible: text !== ""
+                            text: {
+                                var parts = []
+                                if (plugin.version) parts.push("v" + plugin.version)
+                                if (plugin.modified) parts.push(new Date(plugin.modified * 1000).toLocaleDateString(Qt.locale(), Locale.ShortFormat))
+                                if (plugin.path) parts.push(plugin.path)
+                                return parts.join("  |  ")
+                            }
+                            font.pointSize: 8
+                            opacity: 0.5
+                            elide: Text.ElideMiddle
+                            Layout.fillWidth: true
+
+                            HoverHandler { id: pathHover }
+                            ToolTip.visible: pathHover.hovered && plugin.path !== undefined && plugin.path !== ""
+                            ToolTip.text: plugin.path || ""
+                        }
+                    }
+
+                    Button {
+                        visible: !!plugin.folder
+                        text: "Show in Folder"
+                        flat: true
+                        ToolTip.visible: hovered
+                        ToolTip.text: "Open the folder this plugin is loaded from"
+                        onClicked: Qt.openUrlExternally("file://" + plugin.folder)
                     }
 
                     Button {
-                        text: "Add"
+                        text: replaceStableId ? "Replace" : "Add"
                         enabled: plugin.compatible !== false
                         onClicked: {
                             if (plugin.uri) {
-                                controller.add_plugin(plugin.uri)
+                                if (replaceStableId)
+                                    controller.replace_plugin(replaceStableId, plugin.uri)
+                                else
+                                    controller.add_plugin(plugin.uri)
                                 pluginBrowser.visible = false
                             }
                         }
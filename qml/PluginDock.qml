@@ -0,0 +1,139 @@
+import QtQuick
+import QtQuick.Controls
+import QtQuick.Layouts
+
+// Side panel that docked plugin UIs are shown in. The plugin windows are
+// native X11 windows placed over `content`, so only its geometry is ours.
+Rectangle {
+    id: dock
+    color: Theme.panelBg
+    visible: dockedUis.length > 0
+    width: 480
+
+    required property var controller
+
+    property var dockedUis: []
+    property int minimumWidth: 240
+
+    function refresh() {
+        try {
+            dockedUis = JSON.parse(controller.get_docked_uis_json())
+        } catch (e) {
+            dockedUis = []
+        }
+        Qt.callLater(updateGeometry)
+    }
+
+    function activeId() {
+        for (var i = 0; i < dockedUis.length; i++) {
+            if (dockedUis[i].active)
+                return dockedUis[i].instanceId
+        }
+        return -1
+    }
+
+    function updateGeometry() {
+        if (!visible) {
+            controller.set_dock_geometry(0, 0, 0, 0)
+            return
+        }
+        var pos = content.mapToItem(null, 0, 0)
+        controller.set_dock_geometry(Math.round(pos.x), Math.round(pos.y),
+                                     Math.round(content.width), Math.round(content.height))
+    }
+
+    onVisibleChanged: updateGeometry()
+
+    Rectangle {
+        id: resizeHandle
+        anchors.left: parent.left
+        anchors.top: parent.top
+        anchors.bottom: parent.bottom
+        width: 4
+        color: Theme.separator
+
+        MouseArea {
+            anchors.fill: parent
+            anchors.leftMargin: -2
+            anchors.rightMargin: -2
+            cursorShape: Qt.SplitHCursor
+            property real pressX: 0
+            onPressed: mouse => pressX = mouse.x
+            onPositionChanged: mouse => {
+                var maxWidth = dock.parent ? dock.parent.width - 200 : dock.width
+                dock.width = Math.max(dock.minimumWidth,
+                                      Math.min(maxWidth, dock.width - (mouse.x - pressX)))
+            }
+        }
+    }
+
+    ColumnLayout {
+        anchors.fill: parent
+        anchors.leftMargin: resizeHandle.width
+        spacing: 0
+
+        RowLayout {
+            Layout.fillWidth: true
+            Layout.margins: 4
+            spacing: 4
+
+            Flickable {
+                Layout.fillWidth: true
+                Layout.preferredHeight: tabRow.height
+                contentWidth: tabRow.width
+                clip: true
+                boundsBehavior: Flickable.StopAtBounds
+
+                Row {
+                    id: tabRow
+                    spacing: 2
+
+                    Repeater {
+                        model: dock.dockedUis
+                        delegate: Button {
+                            required property var modelData
+                            text: modelData.name
+                            flat: !modelData.active
+                            highlighted: modelData.active
+                            onClicked: dock.controller.show_docked_ui(modelData.instanceId)
+                        }
+                    }
+                }
+            }
+
+            Button {
+                text: "Float"
+                enabled: dock.activeId() >= 0
+                ToolTip.visible: hovered
+                ToolTip.text: "Move this plugin UI back into its own window"
+                onClicked: dock.controller.undock_plugin_ui(dock.activeId())
+            }
+
+            Button {
+                text: "Close"
+                enabled: dock.activeId() >= 0
+                onClicked: dock.controller.close_docked_ui(dock.activeId())
+            }
+        }
+
+        Rectangle {
+            Layout.fillWidth: true
+            height: 1
+            color: Theme.separatorLight
+        }
+
+        Item {
+            id: content
+            Layout.fillWidth: true
+            Layout.fillHeight: true
+
+            onWidthChanged: dock.updateGeometry()
+            onHeightChanged: dock.updateGeometry()
+            onXChanged: dock.updateGeometry()
+            onYChanged: dock.updateGeometry()
+        }
+    }
+
+    onXChanged: updateGeometry()
+    onYChanged: updateGeometry()
+}
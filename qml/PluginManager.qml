@@ -15,6 +15,7 @@ ApplicationWindow {
     required property var controller
 
     property var plugins: []
+    property var dormantPlugins: []
     property int selectedIndex: -1
     property var selectedPlugin: selectedIndex >= 0 && selectedIndex < plugins.length ? plugins[selectedIndex] : null
 
@@ -24,6 +25,11 @@ ApplicationWindow {
         } catch(e) {
             plugins = []
         }
+        try {
+            dormantPlugins = JSON.parse(controller.get_dormant_plugins_json())
+        } catch(e) {
+            dormantPlugins = []
+        }
         if (selectedIndex >= plugins.length) {
             selectedIndex = -1
         }
@@ -125,6 +131,63 @@ ApplicationWindow {
                         }
                     }
                 }
+
+                Label {
+                    visible: dormantPlugins.length > 0
+                    text: dormantPlugins.length + " not started (manual start)"
+                    font.italic: true
+                    opacity: 0.7
+                }
+
+                Repeater {
+                    model: dormantPlugins.length
+
+                    delegate: Rectangle {
+                        id: dormantDelegate
+                        required property int index
+                        property var plugin: dormantPlugins[index] || {}
+                        Layout.fillWidth: true
+                        height: 36
+                        color: index % 2 === 0 ? Theme.rowEven : Theme.rowOdd
+                        radius: 3
+
+                        RowLayout {
+                            anchors.fill: parent
+                            anchors.margins: 6
+                            spacing: 4
+
+                            Label {
+                                text: plugin.displayName || ""
+                                font.pointSize: 9
+                                opacity: 0.7
+                                elide: Text.ElideRight
+                                Layout.fillWidth: true
+                            }
+
+                            Button {
+                                text: "Start"
+                                flat: true
+                                font.pointSize: 8
+                                onClicked: {
+                                    controller.activate_saved_plugin(plugin.stableId)
+                                    loadPlugins()
+                                }
+                            }
+
+                            Button {
+                                text: "Forget"
+                                flat: true
+                                font.pointSize: 8
+                                ToolTip.visible: hovered
+                                ToolTip.text: "Remove this plugin and its saved settings"
+                                onClicked: {
+                                    controller.remove_plugin_by_stable_id(plugin.stableId)
+                                    loadPlugins()
+                                }
+                            }
+                        }
+                    }
+                }
             }
 
             Rectangle {
@@ -183,6 +246,29 @@ ApplicationWindow {
                             }
                         }
 
+                        Button {
+                            text: "Duplicate"
+                            onClicked: {
+                                if (selectedPlugin) {
+                                    controller.duplicate_plugin(selectedPlugin.stableId, false)
+                                    loadPlugins()
+                                }
+                            }
+                        }
+
+                        CheckBox {
+                            text: "Start at launch"
+                            checked: selectedPlugin ? selectedPlugin.autostart !== false : true
+                            ToolTip.visible: hovered
+                            ToolTip.text: "When off, the plugin is remembered with its settings but only started from this window"
+                            onToggled: {
+                                if (selectedPlugin) {
+                                    controller.set_plugin_autostart(selectedPlugin.stableId, checked)
+                                    loadPlugins()
+                                }
+                            }
+                        }
+
                         Button {
                             text: "Remove Plugin"
                             onClicked: {
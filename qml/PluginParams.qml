@@ -19,8 +19,18 @@ ApplicationWindow {
     property string pluginName: ""
     property string pluginUri: ""
     property bool pluginBypassed: false
+    property string pluginFormat: ""
+    property int pluginOversampling: 1
     property var parameters: []
+    // Descriptions, units and value labels by port index.
+    property var paramDocs: ({})
     property int instanceId: -1
+    property string stableId: ""
+    // Symbols of parameters left alone by Randomize.
+    property var lockedParams: ({})
+    property bool canUndoRandomize: false
+    // Presets saved for this plugin, as [{ name, values }].
+    property var presets: []
 
     property int midiLearnInstanceId: -1
     property int midiLearnPortIndex: -1
@@ -36,8 +46,63 @@ ApplicationWindow {
         onTriggered: loadParams()
     }
 
+    // Output levels in dBFS per channel; peaks fall back slowly.
+    property var meterRms: []
+    property var meterPeak: []
+    readonly property real meterFloor: -60
+
+    // Meters poll on their own, apart from the graph: about 30 Hz, slower
+    // or faster with the performance profile.
+    Timer {
+        id: meterTimer
+        interval: controller.get_meter_interval_ms()
+        running: pluginParams.visible && instanceId >= 0
+        repeat: true
+        onRunningChanged: {
+            controller.set_metering(running)
+            meterRms = []
+            meterPeak = []
+        }
+        onTriggered: loadMeters()
+    }
+
+    Connections {
+        target: controller
+        function onPerformance_profileChanged() {
+            meterTimer.interval = controller.get_meter_interval_ms()
+        }
+    }
+
+    function loadMeters() {
+        var reading = null
+        try {
+            var meters = JSON.parse(controller.get_meters_json())
+            for (var i = 0; i < meters.length; i++) {
+                if (meters[i].instanceId === instanceId) {
+                    reading = meters[i]
+                    break
+                }
+            }
+        } catch(e) {}
+        var rms = reading ? reading.rmsDb : meterRms.map(db => Math.max(meterFloor, db - 3))
+        var peak = []
+        for (var c = 0; c < rms.length; c++) {
+            var held = c < meterPeak.length ? meterPeak[c] - 1 : meterFloor
+            peak.push(Math.max(held, reading ? reading.peakDb[c] : meterFloor))
+        }
+        meterRms = rms
+        meterPeak = peak
+    }
+
+    function meterFraction(db) {
+        return Math.max(0, Math.min(1, (db - meterFloor) / -meterFloor))
+    }
+
     function openForNode(nodeId) {
         pluginNodeId = nodeId
+        lockedParams = {}
+        canUndoRandomize = false
+        paramFileStatus.text = ""
         loadParams()
         visible = true
         raise()
@@ -45,20 +110,78 @@ ApplicationWindow {
     }
 
     function loadParams() {
-        if (pluginNodeId < 0) return
+        if (pluginNodeId < 0 && !followInstance()) return
         try {
             var data = JSON.parse(controller.get_plugin_params_json(pluginNodeId))
             if (!data || !data.parameters) return
             pluginName = data.displayName || ""
+            if (data.pluginUri !== pluginUri) {
+                loadDocs(data.pluginUri || "")
+                loadPresets(data.pluginUri || "")
+            }
             pluginUri = data.pluginUri || ""
             pluginBypassed = data.bypassed || false
+            pluginFormat = data.format || ""
+            pluginOversampling = data.oversampling || 1
             instanceId = data.instanceId || -1
+            stableId = data.stableId || ""
             parameters = data.parameters || []
         } catch(e) {
             parameters = []
         }
     }
 
+    // A native UI or a MIDI mapping changed a parameter; show it now
+    // rather than at the next refresh.
+    function parametersChanged(nodeId) {
+        if (visible && nodeId === pluginNodeId)
+            loadParams()
+    }
+
+    function loadDocs(uri) {
+        try {
+            paramDocs = uri ? JSON.parse(controller.get_plugin_param_docs_json(uri)) : {}
+        } catch(e) {
+            paramDocs = {}
+        }
+    }
+
+    function loadPresets(uri) {
+        try {
+            presets = uri ? JSON.parse(controller.get_plugin_presets_json(uri)) : []
+        } catch(e) {
+            presets = []
+        }
+    }
+
+    function formatValue(portIndex, value) {
+        var doc = paramDocs[portIndex]
+        if (doc && doc.scalePoints) {
+            for (var i = 0; i < doc.scalePoints.length; i++) {
+                if (Math.abs(doc.scalePoints[i].value - value) < 1e-4)
+                    return doc.scalePoints[i].label
+            }
+        }
+        var text = value.toFixed(3)
+        return doc && doc.unit ? text + " " + doc.unit : text
+    }
+
+    // Find the plugin's node again after it was re-instantiated
+    // (e.g. when the oversampling factor changes).
+    function followInstance() {
+        if (instanceId < 0) return false
+        try {
+            var plugins = JSON.parse(controller.get_active_plugins_json())
+            for (var i = 0; i < plugins.length; i++) {
+                if (plugins[i].instanceId === instanceId && plugins[i].nodeId !== null) {
+                    pluginNodeId = plugins[i].nodeId
+                    return true
+                }
+            }
+        } catch(e) {}
+        return false
+    }
+
     function clearMidiLearnState() {
         midiLearnInstanceId = -1
         midiLearnPortIndex = -1
@@ -160,6 +283,24 @@ ApplicationWindow {
                 Layout.fillWidth: true
             }
 
+            ComboBox {
+                id: oversamplingBox
+                visible: pluginFormat !== "" && pluginFormat !== "Builtin"
+                enabled: pluginNodeId >= 0
+                model: ["1x", "2x", "4x"]
+                currentIndex: pluginOversampling === 4 ? 2 : (pluginOversampling === 2 ? 1 : 0)
+                implicitWidth: 72
+                ToolTip.visible: hovered
+                ToolTip.text: "Oversampling: run the plugin at a higher rate to reduce aliasing. Adds latency and CPU load."
+                onActivated: function(index) {
+                    var factor = [1, 2, 4][index]
+                    if (pluginNodeId >= 0 && controller.set_plugin_oversampling(pluginNodeId, factor)) {
+                        pluginOversampling = factor
+                        pluginNodeId = -1
+                    }
+                }
+            }
+
             Switch {
                 id: bypassSwitch
                 text: "Bypass"
@@ -180,6 +321,48 @@ ApplicationWindow {
             Layout.fillWidth: true
         }
 
+        ColumnLayout {
+            Layout.fillWidth: true
+            spacing: 2
+            visible: meterRms.length > 0
+
+            Repeater {
+                model: meterRms.length
+
+                Rectangle {
+                    required property int index
+                    Layout.fillWidth: true
+                    height: 5
+                    radius: 2
+                    color: Theme.surfaceBg
+
+                    property real rms: meterRms[index]
+                    property real peak: meterPeak[index] !== undefined ? meterPeak[index] : meterFloor
+
+                    Rectangle {
+                        width: pluginParams.meterFraction(rms) * parent.width
+                        height: parent.height
+                        radius: 2
+                        color: peak > -1 ? Theme.dspBarHigh
+                             : peak > -12 ? Theme.dspBarMedium
+                             : Theme.dspBarLow
+                    }
+
+                    Rectangle {
+                        x: pluginParams.meterFraction(peak) * (parent.width - width)
+                        width: 2
+                        height: parent.height
+                        color: Theme.textSecondary
+                        visible: peak > meterFloor
+                    }
+
+                    ToolTip.visible: meterHover.hovered
+                    ToolTip.text: "Output " + (index + 1) + ": peak " + peak.toFixed(1) + " dBFS, RMS " + rms.toFixed(1) + " dBFS"
+                    HoverHandler { id: meterHover }
+                }
+            }
+        }
+
         Rectangle {
             Layout.fillWidth: true
             height: 1
@@ -218,6 +401,7 @@ ApplicationWindow {
                 radius: 3
 
                 property var param: parameters[index] || {}
+                property var doc: pluginParams.paramDocs[param.portIndex] || {}
                 property bool isLearning: pluginParams.midiLearnInstanceId === pluginParams.instanceId
                                           && pluginParams.midiLearnPortIndex === param.portIndex
                 property string midiMapping: pluginParams.getMidiMappingText(param.portIndex)
@@ -234,6 +418,11 @@ ApplicationWindow {
                             font.pointSize: 9
                             elide: Text.ElideRight
                             Layout.fillWidth: true
+
+                            HoverHandler { id: nameHover }
+                            ToolTip.visible: nameHover.hovered && !!paramDelegate.doc.comment
+                            ToolTip.text: paramDelegate.doc.comment || ""
+                            ToolTip.delay: 500
                         }
 
                         Label {
@@ -263,7 +452,7 @@ ApplicationWindow {
                         Label {
                             id: valueLabel
                             visible: !valueField.visible
-                            text: param.value !== undefined ? param.value.toFixed(3) : ""
+                            text: param.value !== undefined ? pluginParams.formatValue(param.portIndex, param.value) : ""
                             font.pointSize: 9
                             font.family: "monospace"
                             opacity: 0.8
@@ -393,6 +582,42 @@ ApplicationWindow {
                             }
                         }
 
+                        Button {
+                            text: "K"
+                            flat: true
+                            checkable: true
+                            checked: param.quick === true
+                            implicitWidth: 24
+                            implicitHeight: 20
+                            font.pointSize: 8
+                            ToolTip.visible: hovered
+                            ToolTip.text: "Knob: show this parameter on the plugin's node in the graph"
+                            onToggled: {
+                                controller.set_quick_param_pinned(pluginNodeId, param.symbol || "", checked)
+                                pluginParams.loadParams()
+                            }
+                        }
+
+                        Button {
+                            text: "L"
+                            flat: true
+                            checkable: true
+                            checked: pluginParams.lockedParams[param.symbol] === true
+                            implicitWidth: 24
+                            implicitHeight: 20
+                            font.pointSize: 8
+                            ToolTip.visible: hovered
+                            ToolTip.text: "Lock: keep this parameter when randomizing"
+                            onToggled: {
+                                var locked = Object.assign({}, pluginParams.lockedParams)
+                                if (checked)
+                                    locked[param.symbol] = true
+                                else
+                                    delete locked[param.symbol]
+                                pluginParams.lockedParams = locked
+                            }
+                        }
+
                         Button {
                             text: "R"
                             flat: true
@@ -450,6 +675,140 @@ ApplicationWindow {
             color: Theme.separator
         }
 
+        RowLayout {
+            Layout.fillWidth: true
+            visible: parameters.length > 0
+
+            ComboBox {
+                id: presetBox
+                Layout.fillWidth: true
+                editable: true
+                model: presets.map(p => p.name)
+                ToolTip.visible: hovered && currentIndex >= 0
+                ToolTip.text: currentIndex >= 0 && presets[currentIndex]
+                              ? presets[currentIndex].values + " parameters" : ""
+            }
+
+            Button {
+                text: "Load"
+                enabled: stableId !== "" && presetBox.find(presetBox.editText) >= 0
+                onClicked: {
+                    if (controller.load_plugin_preset(stableId, presetBox.editText)) {
+                        paramFileStatus.text = "Loaded preset " + presetBox.editText
+                        canUndoRandomize = true
+                        loadParams()
+                    }
+                }
+            }
+
+            Button {
+                text: "Save"
+                enabled: stableId !== "" && presetBox.editText.trim() !== ""
+                onClicked: {
+                    var name = presetBox.editText.trim()
+                    if (controller.save_plugin_preset(stableId, name)) {
+                        loadPresets(pluginUri)
+                        presetBox.currentIndex = presetBox.find(name)
+                        paramFileStatus.text = "Saved preset " + name
+                    }
+                }
+            }
+
+            Button {
+                text: "Delete"
+                enabled: presetBox.find(presetBox.editText) >= 0
+                onClicked: {
+                    if (controller.delete_plugin_preset(pluginUri, presetBox.editText)) {
+                        loadPresets(pluginUri)
+                        presetBox.editText = ""
+                    }
+                }
+            }
+        }
+
+        RowLayout {
+            Layout.fillWidth: true
+            visible: parameters.length > 0
+
+            Label {
+                text: "Amount"
+                opacity: 0.6
+            }
+
+            Slider {
+                id: randomizeAmount
+                from: 0.05
+                to: 1.0
+                value: 0.25
+                Layout.fillWidth: true
+                ToolTip.visible: hovered || pressed
+                ToolTip.text: Math.round(value * 100) + "% of each range"
+            }
+
+            Button {
+                text: "Randomize"
+                enabled: stableId !== ""
+                onClicked: {
+                    if (controller.randomize_plugin_params(stableId, randomizeAmount.value,
+                                                           JSON.stringify(Object.keys(lockedParams)))) {
+                        canUndoRandomize = true
+                        loadParams()
+                    }
+                }
+            }
+
+            Button {
+                text: "Undo"
+                enabled: canUndoRandomize
+                onClicked: {
+                    canUndoRandomize = controller.undo_plugin_params(stableId)
+                    loadParams()
+                }
+            }
+        }
+
+        RowLayout {
+            Layout.fillWidth: true
+            visible: parameters.length > 0
+
+            TextField {
+                id: paramFilePath
+                Layout.fillWidth: true
+                placeholderText: "File (.json or .csv); empty exports to params/"
+                selectByMouse: true
+            }
+
+            Button {
+                text: "Export"
+                enabled: stableId !== ""
+                onClicked: {
+                    var path = controller.export_plugin_params(stableId, paramFilePath.text)
+                    paramFileStatus.text = path !== "" ? "Saved to " + path : ""
+                }
+            }
+
+            Button {
+                text: "Import"
+                enabled: stableId !== "" && paramFilePath.text.trim() !== ""
+                onClicked: {
+                    var summary = controller.import_plugin_params(stableId, paramFilePath.text)
+                    paramFileStatus.text = summary
+                    if (summary !== "") {
+                        canUndoRandomize = true
+                        loadParams()
+                    }
+                }
+            }
+        }
+
+        Label {
+            id: paramFileStatus
+            Layout.fillWidth: true
+            visible: text !== ""
+            opacity: 0.6
+            elide: Text.ElideMiddle
+        }
+
         RowLayout {
             Layout.fillWidth: true
 
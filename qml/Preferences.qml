@@ -28,6 +28,10 @@ ApplicationWindow {
 
     function open() {
         loadPrefs();
+        remoteCombo.refresh();
+        compareCombo.editText = prefs.compare_remote || "";
+        remoteInstanceField.text = prefs.remote_instance || "";
+        remoteInstanceTokenField.text = prefs.remote_instance_token || "";
         prefsWindow.visible = true;
         prefsWindow.raise();
         prefsWindow.requestActivate();
@@ -87,6 +91,1344 @@ ApplicationWindow {
                     opacity: 0.8
                 }
 
+                ColumnLayout {
+                    Layout.fillWidth: true
+                    spacing: 4
+
+                    Label {
+                        text: "PipeWire instance"
+                        font.bold: true
+                    }
+                    Label {
+                        text: "The PipeWire daemon to manage, by socket name (pipewire-0) or path, e.g. a second seat's or one shared from a container. Leave empty for the default. Switching saves the session and restarts ZestBay; plugins and rules carry over."
+                        wrapMode: Text.WordWrap
+                        Layout.fillWidth: true
+                        font.pointSize: 9
+                        opacity: 0.5
+                    }
+
+                    RowLayout {
+                        Layout.fillWidth: true
+                        spacing: 8
+
+                        ComboBox {
+                            id: remoteCombo
+                            Layout.fillWidth: true
+                            editable: true
+                            model: []
+                            Component.onCompleted: refresh()
+
+                            function refresh() {
+                                try {
+                                    model = JSON.parse(controller.get_pipewire_remotes_json());
+                                } catch (e) {
+                                    model = [];
+                                }
+                                editText = controller.get_pipewire_remote();
+                            }
+                        }
+                        Button {
+                            text: "Switch"
+                            enabled: remoteCombo.editText.trim() !== controller.get_pipewire_remote()
+                            onClicked: controller.switch_pipewire_remote(remoteCombo.editText)
+                        }
+                    }
+                }
+
+                Rectangle {
+                    Layout.fillWidth: true
+                    height: 1
+                    color: Theme.separatorLight
+                }
+
+                ColumnLayout {
+                    Layout.fillWidth: true
+                    spacing: 4
+
+                    Label {
+                        text: "Compare with instance"
+                        font.bold: true
+                    }
+                    Label {
+                        text: "A second PipeWire instance to show beside the graph, e.g. a remote machine reached over a pipewire-pulse tunnel. It keeps its own rules. Leave empty to hide it."
+                        wrapMode: Text.WordWrap
+                        Layout.fillWidth: true
+                        font.pointSize: 9
+                        opacity: 0.5
+                    }
+
+                    RowLayout {
+                        Layout.fillWidth: true
+                        spacing: 8
+
+                        ComboBox {
+                            id: compareCombo
+                            Layout.fillWidth: true
+                            editable: true
+                            model: remoteCombo.model
+                            Component.onCompleted: editText = prefs.compare_remote || ""
+                        }
+                        Button {
+                            text: "Show"
+                            enabled: compareCombo.editText.trim() !== (prefs.compare_remote || "")
+                            onClicked: {
+                                controller.set_compare_remote(compareCombo.editText);
+                                loadPrefs();
+                            }
+                        }
+                    }
+                }
+
+                Rectangle {
+                    Layout.fillWidth: true
+                    height: 1
+                    color: Theme.separatorLight
+                }
+
+                ColumnLayout {
+                    Layout.fillWidth: true
+                    spacing: 4
+
+                    Label {
+                        text: "Remote ZestBay"
+                        font.bold: true
+                    }
+                    Label {
+                        text: "Manage another ZestBay from a panel beside the graph, e.g. one started with --headless on a machine without a monitor. Enter the host:port its remote API listens on, or wss://host:port when it uses TLS, and its token. Leave the address empty to hide it."
+                        wrapMode: Text.WordWrap
+                        Layout.fillWidth: true
+                        font.pointSize: 9
+                        opacity: 0.5
+                    }
+
+                    GridLayout {
+                        Layout.fillWidth: true
+                        columns: 3
+                        columnSpacing: 12
+                        rowSpacing: 4
+
+                        Label {
+                            text: "Address"
+                            opacity: 0.8
+                        }
+                        TextField {
+                            id: remoteInstanceField
+                            Layout.fillWidth: true
+                            Layout.columnSpan: 2
+                            text: prefs.remote_instance || ""
+                            placeholderText: "rack-pc.local:7380"
+                            selectByMouse: true
+                        }
+
+                        Label {
+                            text: "Token"
+                            opacity: 0.8
+                        }
+                        TextField {
+                            id: remoteInstanceTokenField
+                            Layout.fillWidth: true
+                            text: prefs.remote_instance_token || ""
+                            selectByMouse: true
+                            font.family: "monospace"
+                        }
+                        Button {
+                            text: "Connect"
+                            enabled: remoteInstanceField.text.trim() !== (prefs.remote_instance || "")
+                                     || remoteInstanceTokenField.text.trim() !== (prefs.remote_instance_token || "")
+                            onClicked: {
+                                controller.set_remote_instance(remoteInstanceField.text, remoteInstanceTokenField.text);
+                                loadPrefs();
+                            }
+                        }
+
+                        Label {
+                            text: "Certificate"
+                            opacity: 0.8
+                        }
+                        TextField {
+                            Layout.fillWidth: true
+                            Layout.columnSpan: 2
+                            text: prefs.remote_instance_cert || ""
+                            placeholderText: "PEM file to trust for wss://; empty trusts the usual authorities"
+                            selectByMouse: true
+                            onEditingFinished: if (text !== (prefs.remote_instance_cert || "")) setPref("remote_instance_cert", text)
+                        }
+                    }
+                }
+
+                Rectangle {
+                    Layout.fillWidth: true
+                    height: 1
+                    color: Theme.separatorLight
+                }
+
+                RowLayout {
+                    Layout.fillWidth: true
+                    spacing: 12
+
+                    ColumnLayout {
+                        Layout.fillWidth: true
+                        spacing: 2
+
+                        Label {
+                            text: "Start minimized to tray"
+                            font.bold: true
+                        }
+                        Label {
+                            text: "Launch with the window hidden. Use the system tray icon to show it."
+                            wrapMode: Text.WordWrap
+                            Layout.fillWidth: true
+                            font.pointSize: 9
+                            opacity: 0.5
+                        }
+                    }
+
+                    Switch {
+                        checked: prefs.start_minimized !== undefined ? prefs.start_minimized : false
+                        onToggled: setPref("start_minimized", checked)
+                    }
+                }
+
+                Rectangle {
+                    Layout.fillWidth: true
+                    height: 1
+                    color: Theme.separatorLight
+                }
+
+                RowLayout {
+                    Layout.fillWidth: true
+                    spacing: 12
+
+                    ColumnLayout {
+                        Layout.fillWidth: true
+                        spacing: 2
+
+                        Label {
+                            text: "Close to tray"
+                            font.bold: true
+                        }
+                        Label {
+                            text: "Clicking the window close button hides to the system tray instead of quitting."
+                            wrapMode: Text.WordWrap
+                            Layout.fillWidth: true
+                            font.pointSize: 9
+                            opacity: 0.5
+                        }
+                    }
+
+                    Switch {
+                        checked: prefs.close_to_tray !== undefined ? prefs.close_to_tray : false
+                        onToggled: setPref("close_to_tray", checked)
+                    }
+                }
+
+                Rectangle {
+                    Layout.fillWidth: true
+                    height: 1
+                    color: Theme.separatorLight
+                }
+
+                RowLayout {
+                    Layout.fillWidth: true
+                    spacing: 12
+
+                    readonly property var modes: [
+                        { value: "auto", text: "Automatic" },
+                        { value: "status_notifier", text: "StatusNotifier" },
+                        { value: "xembed", text: "XEmbed (legacy X11)" },
+                        { value: "none", text: "No tray" }
+                    ]
+
+                    ColumnLayout {
+                        Layout.fillWidth: true
+                        spacing: 2
+
+                        Label {
+                            text: "Tray icon"
+                            font.bold: true
+                        }
+                        Label {
+                            text: "Automatic uses StatusNotifier where the desktop hosts it and falls back to an XEmbed icon, or to minimizing instead of hiding on desktops without a tray such as stock GNOME. Takes effect after a restart."
+                            wrapMode: Text.WordWrap
+                            Layout.fillWidth: true
+                            font.pointSize: 9
+                            opacity: 0.5
+                        }
+                    }
+
+                    ComboBox {
+                        Layout.preferredWidth: 180
+                        model: parent.modes
+                        textRole: "text"
+                        valueRole: "value"
+                        currentIndex: {
+                            var current = prefs.tray_mode || "auto"
+                            for (var i = 0; i < parent.modes.length; i++) {
+                                if (parent.modes[i].value === current)
+                                    return i
+                            }
+                            return 0
+                        }
+                        onActivated: setPref("tray_mode", currentValue)
+                    }
+                }
+
+                Rectangle {
+                    Layout.fillWidth: true
+                    height: 1
+                    color: Theme.separatorLight
+                }
+
+                RowLayout {
+                    Layout.fillWidth: true
+                    spacing: 12
+
+                    readonly property var orders: [
+                        { value: "alphabetical", text: "Alphabetical" },
+                        { value: "type", text: "By type" },
+                        { value: "activity", text: "By recent activity" }
+                    ]
+
+                    ColumnLayout {
+                        Layout.fillWidth: true
+                        spacing: 2
+
+                        Label {
+                            text: "Node order"
+                            font.bold: true
+                        }
+                        Label {
+                            text: "How nodes are listed. By type goes from sources through plugins to sinks; by recent activity puts nodes that appeared or were connected last first."
+                            wrapMode: Text.WordWrap
+                            Layout.fillWidth: true
+                            font.pointSize: 9
+                            opacity: 0.5
+                        }
+                    }
+
+                    ComboBox {
+                        Layout.preferredWidth: 180
+                        model: parent.orders
+                        textRole: "text"
+                        valueRole: "value"
+                        currentIndex: {
+                            var current = prefs.node_order || "alphabetical"
+                            for (var i = 0; i < parent.orders.length; i++) {
+                                if (parent.orders[i].value === current)
+                                    return i
+                            }
+                            return 0
+                        }
+                        onActivated: setPref("node_order", currentValue)
+                    }
+                }
+
+                Rectangle {
+                    Layout.fillWidth: true
+                    height: 1
+                    color: Theme.separatorLight
+                }
+
+                ColumnLayout {
+                    Layout.fillWidth: true
+                    spacing: 4
+
+                    RowLayout {
+                        Layout.fillWidth: true
+
+                        Label {
+                            text: "Overrun warning"
+                            font.bold: true
+                            Layout.fillWidth: true
+                        }
+                        Label {
+                            text: xrunSlider.value > 0 ? Math.round(xrunSlider.value) + " / min" : "Off"
+                            font.family: "monospace"
+                            opacity: 0.8
+                        }
+                    }
+
+                    Label {
+                        text: "The tray icon shows a warning when hosted plugins miss their processing deadline this many times within a minute. It also marks disabled rules, active recording and crashed or hung plugins."
+                        wrapMode: Text.WordWrap
+                        Layout.fillWidth: true
+                        font.pointSize: 9
+                        opacity: 0.5
+                    }
+
+                    RowLayout {
+                        Layout.fillWidth: true
+                        spacing: 8
+
+                        Label {
+                            text: "Off"
+                            opacity: 0.4
+                            font.pointSize: 8
+                        }
+                        Slider {
+                            id: xrunSlider
+                            Layout.fillWidth: true
+                            from: 0
+                            to: 50
+                            stepSize: 1
+                            value: prefs.xrun_warning_threshold !== undefined ? prefs.xrun_warning_threshold : 10
+                            onPressedChanged: {
+                                if (!pressed) {
+                                    setPref("xrun_warning_threshold", value);
+                                }
+                            }
+                        }
+                        Label {
+                            text: "50"
+                            opacity: 0.4
+                            font.pointSize: 8
+                        }
+                    }
+                }
+
+                Rectangle {
+                    Layout.fillWidth: true
+                    height: 1
+                    color: Theme.separatorLight
+                }
+
+                ColumnLayout {
+                    Layout.fillWidth: true
+                    spacing: 4
+
+                    RowLayout {
+                        Layout.fillWidth: true
+
+                        Label {
+                            text: "Plugin CPU alert"
+                            font.bold: true
+                            Layout.fillWidth: true
+                        }
+                        Label {
+                            text: cpuAlertSlider.value > 0
+                                  ? Math.round(cpuAlertSlider.value) + "% for " + Math.round(cpuAlertSecondsSlider.value) + " s"
+                                  : "Off"
+                            font.family: "monospace"
+                            opacity: 0.8
+                        }
+                    }
+
+                    Label {
+                        text: "Shows a notification when a hosted plugin uses more than this share of the audio buffer time for the given number of seconds. The CPU overlay keeps each plugin's recent history, also across restarts."
+                        wrapMode: Text.WordWrap
+                        Layout.fillWidth: true
+                        font.pointSize: 9
+                        opacity: 0.5
+                    }
+
+                    RowLayout {
+                        Layout.fillWidth: true
+                        spacing: 8
+
+                        Label {
+                            text: "Off"
+                            opacity: 0.4
+                            font.pointSize: 8
+                        }
+                        Slider {
+                            id: cpuAlertSlider
+                            Layout.fillWidth: true
+                            from: 0
+                            to: 100
+                            stepSize: 5
+                            value: prefs.cpu_alert_percent !== undefined ? prefs.cpu_alert_percent : 90
+                            onPressedChanged: {
+                                if (!pressed) {
+                                    setPref("cpu_alert_percent", value);
+                                }
+                            }
+                        }
+                        Label {
+                            text: "100%"
+                            opacity: 0.4
+                            font.pointSize: 8
+                        }
+                    }
+
+                    RowLayout {
+                        Layout.fillWidth: true
+                        spacing: 8
+                        enabled: cpuAlertSlider.value > 0
+
+                        Label {
+                            text: "1 s"
+                            opacity: 0.4
+                            font.pointSize: 8
+                        }
+                        Slider {
+                            id: cpuAlertSecondsSlider
+                            Layout.fillWidth: true
+                            from: 1
+                            to: 60
+                            stepSize: 1
+                            value: prefs.cpu_alert_seconds !== undefined ? prefs.cpu_alert_seconds : 10
+                            onPressedChanged: {
+                                if (!pressed) {
+                                    setPref("cpu_alert_seconds", value);
+                                }
+                            }
+                        }
+                        Label {
+                            text: "60 s"
+                            opacity: 0.4
+                            font.pointSize: 8
+                        }
+                    }
+                }
+
+                Rectangle {
+                    Layout.fillWidth: true
+                    height: 1
+                    color: Theme.separatorLight
+                }
+
+                RowLayout {
+                    Layout.fillWidth: true
+                    spacing: 12
+
+                    ColumnLayout {
+                        Layout.fillWidth: true
+                        spacing: 2
+
+                        Label {
+                            text: "Log PipeWire commands"
+                            font.bold: true
+                        }
+                        Label {
+                            text: "Records every connect, disconnect and plugin command ZestBay sends, with the time and what caused it (you, a rule, a restore, the remote API). View it under Patchbay → Command Log."
+                            wrapMode: Text.WordWrap
+                            Layout.fillWidth: true
+                            font.pointSize: 9
+                            opacity: 0.5
+                        }
+                    }
+
+                    Switch {
+                        checked: prefs.pw_command_log !== undefined ? prefs.pw_command_log : false
+                        onToggled: setPref("pw_command_log", checked)
+                    }
+                }
+
+                Rectangle {
+                    Layout.fillWidth: true
+                    height: 1
+                    color: Theme.separatorLight
+                }
+
+                RowLayout {
+                    Layout.fillWidth: true
+                    spacing: 12
+
+                    ColumnLayout {
+                        Layout.fillWidth: true
+                        spacing: 2
+
+                        Label {
+                            text: "Check for updates"
+                            font.bold: true
+                        }
+                        Label {
+                            text: "Looks up the latest release on GitHub at startup and shows it in Help → About, with its release notes and how to upgrade on this system."
+                            wrapMode: Text.WordWrap
+                            Layout.fillWidth: true
+                            font.pointSize: 9
+                            opacity: 0.5
+                        }
+                    }
+
+                    Switch {
+                        checked: prefs.check_for_updates !== undefined ? prefs.check_for_updates : false
+                        onToggled: setPref("check_for_updates", checked)
+                    }
+                }
+
+                Rectangle {
+                    Layout.fillWidth: true
+                    height: 1
+                    color: Theme.separatorLight
+                }
+
+                RowLayout {
+                    Layout.fillWidth: true
+                    spacing: 12
+
+                    ColumnLayout {
+                        Layout.fillWidth: true
+                        spacing: 2
+
+                        Label {
+                            text: "Auto-learn patchbay rules"
+                            font.bold: true
+                        }
+                        Label {
+                            text: "Automatically create/update rules when you manually connect ports."
+                            wrapMode: Text.WordWrap
+                            Layout.fillWidth: true
+                            font.pointSize: 9
+                            opacity: 0.5
+                        }
+                    }
+
+                    Switch {
+                        checked: prefs.auto_learn_rules !== undefined ? prefs.auto_learn_rules : true
+                        onToggled: setPref("auto_learn_rules", checked)
+                    }
+                }
+
+                Rectangle {
+                    Layout.fillWidth: true
+                    height: 1
+                    color: Theme.separatorLight
+                }
+
+                RowLayout {
+                    Layout.fillWidth: true
+                    spacing: 12
+
+                    ColumnLayout {
+                        Layout.fillWidth: true
+                        spacing: 2
+
+                        Label {
+                            text: "Route new streams immediately"
+                            font.bold: true
+                        }
+                        Label {
+                            text: "Connect a new app stream as soon as it appears when a learned rule names it, instead of waiting for the rule settle time. Keeps short sounds from being missed."
+                            wrapMode: Text.WordWrap
+                            Layout.fillWidth: true
+                            font.pointSize: 9
+                            opacity: 0.5
+                        }
+                    }
+
+                    Switch {
+                        checked: prefs.fast_route_streams !== undefined ? prefs.fast_route_streams : true
+                        onToggled: setPref("fast_route_streams", checked)
+                    }
+                }
+
+                Rectangle {
+                    Layout.fillWidth: true
+                    height: 1
+                    color: Theme.separatorLight
+                }
+
+                RowLayout {
+                    Layout.fillWidth: true
+                    spacing: 12
+
+                    readonly property var scopes: [
+                        { value: "off", text: "Unlearn the link" },
+                        { value: "node", text: "Until a node is gone" },
+                        { value: "timed", text: "For a while" },
+                        { value: "permanent", text: "Permanently" }
+                    ]
+
+                    ColumnLayout {
+                        Layout.fillWidth: true
+                        spacing: 2
+
+                        Label {
+                            text: "Manual disconnects"
+                            font.bold: true
+                        }
+                        Label {
+                            text: "What disconnecting a rule's link by hand does. Instead of unlearning it, the link can be left down until either node disappears, for a number of minutes, or until you clear it in the rule editor."
+                            wrapMode: Text.WordWrap
+                            Layout.fillWidth: true
+                            font.pointSize: 9
+                            opacity: 0.5
+                        }
+                    }
+
+                    SpinBox {
+                        visible: (prefs.manual_override_scope || "off") === "timed"
+                        from: 1
+                        to: 1440
+                        editable: true
+                        value: prefs.manual_override_minutes !== undefined ? prefs.manual_override_minutes : 30
+                        textFromValue: function(v) { return v + " min" }
+                        valueFromText: function(t) { return parseInt(t) || 1 }
+                        onValueModified: setPref("manual_override_minutes", value)
+                    }
+
+                    ComboBox {
+                        Layout.preferredWidth: 180
+                        model: parent.scopes
+                        textRole: "text"
+                        valueRole: "value"
+                        currentIndex: {
+                            var current = prefs.manual_override_scope || "off"
+                            for (var i = 0; i < parent.scopes.length; i++) {
+                                if (parent.scopes[i].value === current)
+                                    return i
+                            }
+                            return 0
+                        }
+                        onActivated: setPref("manual_override_scope", currentValue)
+                    }
+                }
+
+                Rectangle {
+                    Layout.fillWidth: true
+                    height: 1
+                    color: Theme.separatorLight
+                }
+
+                RowLayout {
+                    Layout.fillWidth: true
+                    spacing: 12
+
+                    ColumnLayout {
+                        Layout.fillWidth: true
+                        spacing: 2
+
+                        Label {
+                            text: "Level-match bypass"
+                            font.bold: true
+                        }
+                        Label {
+                            text: "Match the loudness of a bypassed plugin to its processed output, so A/B comparisons aren't biased by volume."
+                            wrapMode: Text.WordWrap
+                            Layout.fillWidth: true
+                            font.pointSize: 9
+                            opacity: 0.5
+                        }
+                    }
+
+                    Switch {
+                        checked: prefs.bypass_level_match === true
+                        onToggled: setPref("bypass_level_match", checked)
+                    }
+                }
+
+                Rectangle {
+                    Layout.fillWidth: true
+                    height: 1
+                    color: Theme.separatorLight
+                }
+
+                ColumnLayout {
+                    Layout.fillWidth: true
+                    spacing: 2
+
+                    RowLayout {
+                        Layout.fillWidth: true
+
+                        Label {
+                            text: "Plugin tails"
+                            font.bold: true
+                            Layout.fillWidth: true
+                        }
+                        Label {
+                            text: tailSlider.value > 0 ? "up to " + Math.round(tailSlider.value) + " s" : "Off"
+                            font.family: "monospace"
+                            opacity: 0.8
+                        }
+                    }
+
+                    Label {
+                        text: "Let a reverb or delay ring out when it is bypassed or removed: the plugin keeps running on silent input until its output dies away, for at most this long."
+                        wrapMode: Text.WordWrap
+                        Layout.fillWidth: true
+                        font.pointSize: 9
+                        opacity: 0.5
+                    }
+
+                    RowLayout {
+                        Layout.fillWidth: true
+                        spacing: 8
+
+                        Label {
+                            text: "Off"
+                            opacity: 0.4
+                            font.pointSize: 8
+                        }
+                        Slider {
+                            id: tailSlider
+                            Layout.fillWidth: true
+                            from: 0
+                            to: 60
+                            stepSize: 1
+                            value: prefs.plugin_tail_seconds !== undefined ? prefs.plugin_tail_seconds : 10
+                            onPressedChanged: {
+                                if (!pressed) {
+                                    setPref("plugin_tail_seconds", value);
+                                }
+                            }
+                        }
+                        Label {
+                            text: "60 s"
+                            opacity: 0.4
+                            font.pointSize: 8
+                        }
+                    }
+                }
+
+                Rectangle {
+                    Layout.fillWidth: true
+                    height: 1
+                    color: Theme.separatorLight
+                }
+
+                ColumnLayout {
+                    Layout.fillWidth: true
+                    spacing: 2
+
+                    RowLayout {
+                        Layout.fillWidth: true
+
+                        Label {
+                            text: "Output crossfade"
+                            font.bold: true
+                            Layout.fillWidth: true
+                        }
+                        Label {
+                            text: crossfadeSlider.value > 0 ? Math.round(crossfadeSlider.value) + " ms" : "Off"
+                            font.family: "monospace"
+                            opacity: 0.8
+                        }
+                    }
+
+                    Label {
+                        text: "When you make a device the default output from ZestBay, fade the playing streams over to it instead of cutting them across at once."
+                        wrapMode: Text.WordWrap
+                        Layout.fillWidth: true
+                        font.pointSize: 9
+                        opacity: 0.5
+                    }
+
+                    RowLayout {
+                        Layout.fillWidth: true
+                        spacing: 8
+
+                        Label {
+                            text: "Off"
+                            opacity: 0.4
+                            font.pointSize: 8
+                        }
+                        Slider {
+                            id: crossfadeSlider
+                            Layout.fillWidth: true
+                            from: 0
+                            to: 2000
+                            stepSize: 50
+                            value: prefs.output_crossfade_ms !== undefined ? prefs.output_crossfade_ms : 200
+                            onPressedChanged: {
+                                if (!pressed) {
+                                    setPref("output_crossfade_ms", value);
+                                }
+                            }
+                        }
+                        Label {
+                            text: "2 s"
+                            opacity: 0.4
+                            font.pointSize: 8
+                        }
+                    }
+                }
+
+                Rectangle {
+                    Layout.fillWidth: true
+                    height: 1
+                    color: Theme.separatorLight
+                }
+
+                RowLayout {
+                    Layout.fillWidth: true
+                    spacing: 12
+
+                    ColumnLayout {
+                        Layout.fillWidth: true
+                        spacing: 2
+
+                        Label {
+                            text: "Dock plugin UIs"
+                            font.bold: true
+                        }
+                        Label {
+                            text: controller.is_ui_docking_supported()
+                                  ? "Show newly opened plugin UIs in a panel of the main window instead of separate windows."
+                                  : "Show newly opened plugin UIs in a panel of the main window. Only available on X11."
+                            wrapMode: Text.WordWrap
+                            Layout.fillWidth: true
+                            font.pointSize: 9
+                            opacity: 0.5
+                        }
+                    }
+
+                    Switch {
+                        enabled: controller.is_ui_docking_supported()
+                        checked: prefs.dock_plugin_uis === true
+                        onToggled: setPref("dock_plugin_uis", checked)
+                    }
+                }
+
+                Rectangle {
+                    Layout.fillWidth: true
+                    height: 1
+                    color: Theme.separatorLight
+                }
+
+                RowLayout {
+                    Layout.fillWidth: true
+                    spacing: 12
+
+                    ColumnLayout {
+                        Layout.fillWidth: true
+                        spacing: 2
+
+                        Label {
+                            text: "Reopen plugin UIs"
+                            font.bold: true
+                        }
+                        Label {
+                            text: "Open the plugin windows that were open when ZestBay last quit once their plugins are restored."
+                            wrapMode: Text.WordWrap
+                            Layout.fillWidth: true
+                            font.pointSize: 9
+                            opacity: 0.5
+                        }
+                    }
+
+                    Switch {
+                        checked: prefs.reopen_plugin_uis === true
+                        onToggled: setPref("reopen_plugin_uis", checked)
+                    }
+                }
+
+                Rectangle {
+                    Layout.fillWidth: true
+                    height: 1
+                    color: Theme.separatorLight
+                }
+
+                RowLayout {
+                    Layout.fillWidth: true
+                    spacing: 12
+
+                    readonly property var monitors: {
+                        var list = [{ value: "", text: "Same as main window" }]
+                        var screens = Qt.application.screens
+                        for (var i = 0; i < screens.length; i++)
+                            list.push({ value: screens[i].name, text: screens[i].name })
+                        var saved = prefs.plugin_ui_monitor || ""
+                        if (saved !== "" && !list.some(function(m) { return m.value === saved }))
+                            list.push({ value: saved, text: saved + " (not connected)" })
+                        return list
+                    }
+
+                    ColumnLayout {
+                        Layout.fillWidth: true
+                        spacing: 2
+
+                        Label {
+                            text: "Plugin UI monitor"
+                            font.bold: true
+                        }
+                        Label {
+                            text: "Where plugin windows open. A window moved to another monitor opens there from then on."
+                            wrapMode: Text.WordWrap
+                            Layout.fillWidth: true
+                            font.pointSize: 9
+                            opacity: 0.5
+                        }
+                    }
+
+                    ComboBox {
+                        Layout.preferredWidth: 180
+                        model: parent.monitors
+                        textRole: "text"
+                        valueRole: "value"
+                        currentIndex: {
+                            var current = prefs.plugin_ui_monitor || ""
+                            for (var i = 0; i < parent.monitors.length; i++) {
+                                if (parent.monitors[i].value === current)
+                                    return i
+                            }
+                            return 0
+                        }
+                        onActivated: setPref("plugin_ui_monitor", currentValue)
+                    }
+                }
+
+                Rectangle {
+                    Layout.fillWidth: true
+                    height: 1
+                    color: Theme.separatorLight
+                }
+
+                RowLayout {
+                    Layout.fillWidth: true
+                    spacing: 12
+
+                    ColumnLayout {
+                        Layout.fillWidth: true
+                        spacing: 2
+
+                        Label {
+                            text: "Knobs on plugin nodes"
+                            font.bold: true
+                        }
+                        Label {
+                            text: "How many of a plugin's first parameters are drawn as knobs on its node when none are marked K in its parameter window. 0 shows only marked ones."
+                            wrapMode: Text.WordWrap
+                            Layout.fillWidth: true
+                            font.pointSize: 9
+                            opacity: 0.5
+                        }
+                    }
+
+                    SpinBox {
+                        from: 0
+                        to: 6
+                        value: prefs.quick_params_count !== undefined ? prefs.quick_params_count : 3
+                        onValueModified: setPref("quick_params_count", value)
+                    }
+                }
+
+                Rectangle {
+                    Layout.fillWidth: true
+                    height: 1
+                    color: Theme.separatorLight
+                }
+
+                RowLayout {
+                    Layout.fillWidth: true
+                    spacing: 12
+
+                    readonly property var formats: [
+                        { value: "lv2,clap,vst3", text: "LV2" },
+                        { value: "clap,lv2,vst3", text: "CLAP" },
+                        { value: "vst3,clap,lv2", text: "VST3" }
+                    ]
+
+                    ColumnLayout {
+                        Layout.fillWidth: true
+                        spacing: 2
+
+                        Label {
+                            text: "Preferred plugin format"
+                            font.bold: true
+                        }
+                        Label {
+                            text: "Which version the plugin browser lists when a plugin is installed in several formats. Turn on All versions in the browser to see them all."
+                            wrapMode: Text.WordWrap
+                            Layout.fillWidth: true
+                            font.pointSize: 9
+                            opacity: 0.5
+                        }
+                    }
+
+                    ComboBox {
+                        Layout.preferredWidth: 180
+                        model: parent.formats
+                        textRole: "text"
+                        valueRole: "value"
+                        currentIndex: {
+                            var first = (prefs.plugin_format_order || "lv2").split(",")[0]
+                            for (var i = 0; i < parent.formats.length; i++) {
+                                if (parent.formats[i].value.split(",")[0] === first)
+                                    return i
+                            }
+                            return 0
+                        }
+                        onActivated: setPref("plugin_format_order", currentValue)
+                    }
+                }
+
+                Rectangle {
+                    Layout.fillWidth: true
+                    height: 1
+                    color: Theme.separator
+                }
+
+                Label {
+                    text: "Double-click"
+                    font.bold: true
+                    font.pointSize: 11
+                    opacity: 0.8
+                }
+
+                Label {
+                    text: "What double-clicking a node in the graph does, per node type."
+                    font.italic: true
+                    opacity: 0.5
+                    Layout.fillWidth: true
+                    wrapMode: Text.WordWrap
+                }
+
+                Repeater {
+                    model: [
+                        { type: "Plugin", label: "Plugins", plugin: true },
+                        { type: "Sink", label: "Sinks", plugin: false },
+                        { type: "Source", label: "Sources", plugin: false },
+                        { type: "StreamOutput", label: "Playback streams", plugin: false },
+                        { type: "StreamInput", label: "Recording streams", plugin: false },
+                        { type: "Duplex", label: "Duplex devices", plugin: false }
+                    ]
+
+                    RowLayout {
+                        required property var modelData
+                        Layout.fillWidth: true
+                        spacing: 12
+
+                        readonly property var actions: modelData.plugin
+                            ? [
+                                { value: "open_ui", text: "Open plugin UI" },
+                                { value: "params", text: "Show parameters" },
+                                { value: "rename", text: "Rename" },
+                                { value: "hide", text: "Hide" },
+                                { value: "none", text: "Nothing" }
+                            ]
+                            : [
+                                { value: "hide", text: "Hide" },
+                                { value: "none", text: "Nothing" }
+                            ]
+
+                        Label {
+                            text: modelData.label
+                            Layout.fillWidth: true
+                        }
+
+                        ComboBox {
+                            Layout.preferredWidth: 180
+                            model: parent.actions
+                            textRole: "text"
+                            valueRole: "value"
+                            currentIndex: {
+                                var map = prefs.double_click_actions || {}
+                                var current = map[modelData.type] || "none"
+                                for (var i = 0; i < parent.actions.length; i++) {
+                                    if (parent.actions[i].value === current)
+                                        return i
+                                }
+                                return parent.actions.length - 1
+                            }
+                            onActivated: setPref("double_click." + modelData.type, currentValue)
+                        }
+                    }
+                }
+
+                Rectangle {
+                    Layout.fillWidth: true
+                    height: 1
+                    color: Theme.separator
+                }
+
+                Label {
+                    text: "Integrations"
+                    font.bold: true
+                    font.pointSize: 11
+                    opacity: 0.8
+                }
+
+                RowLayout {
+                    Layout.fillWidth: true
+                    spacing: 12
+
+                    ColumnLayout {
+                        Layout.fillWidth: true
+                        spacing: 2
+
+                        Label {
+                            text: "JACK patchbay interface"
+                            font.bold: true
+                        }
+                        Label {
+                            text: "Offer the graph over the jackdbus patchbay interface (org.jackaudio.service), so JACK tools such as RaySession can see and connect nodes. Only works when no real jackdbus is running. Turning it off requires restart."
+                            wrapMode: Text.WordWrap
+                            Layout.fillWidth: true
+                            font.pointSize: 9
+                            opacity: 0.5
+                        }
+                    }
+
+                    Switch {
+                        checked: prefs.jack_dbus_patchbay === true
+                        onToggled: setPref("jack_dbus_patchbay", checked)
+                    }
+                }
+
+                Rectangle {
+                    Layout.fillWidth: true
+                    height: 1
+                    color: Theme.separatorLight
+                }
+
+                RowLayout {
+                    Layout.fillWidth: true
+                    spacing: 12
+
+                    ColumnLayout {
+                        Layout.fillWidth: true
+                        spacing: 2
+
+                        Label {
+                            text: "D-Bus control interface"
+                            font.bold: true
+                        }
+                        Label {
+                            text: "Offer org.zestbay.Patchbay on the session bus: list nodes and links, connect, disconnect, toggle the patchbay and switch scenes from desktop widgets, shortcuts and scripts. Turning it off requires restart."
+                            wrapMode: Text.WordWrap
+                            Layout.fillWidth: true
+                            font.pointSize: 9
+                            opacity: 0.5
+                        }
+                    }
+
+                    Switch {
+                        checked: prefs.patchbay_dbus === true
+                        onToggled: setPref("patchbay_dbus", checked)
+                    }
+                }
+
+                Rectangle {
+                    Layout.fillWidth: true
+                    height: 1
+                    color: Theme.separatorLight
+                }
+
+                RowLayout {
+                    Layout.fillWidth: true
+                    spacing: 12
+
+                    ColumnLayout {
+                        Layout.fillWidth: true
+                        spacing: 2
+
+                        Label {
+                            text: "MQTT status publisher"
+                            font.bold: true
+                        }
+                        Label {
+                            text: "Publish the default sink and whether anything is playing or recording under <topic>/status, and, when commands are accepted, \"mute [node]\", \"unmute [node]\" and \"scene <rule backup>\" on <topic>/command."
+                            wrapMode: Text.WordWrap
+                            Layout.fillWidth: true
+                            font.pointSize: 9
+                            opacity: 0.5
+                        }
+                    }
+
+                    Switch {
+                        checked: prefs.mqtt_enabled === true
+                        onToggled: setPref("mqtt_enabled", checked)
+                    }
+                }
+
+                GridLayout {
+                    Layout.fillWidth: true
+                    columns: 2
+                    columnSpacing: 12
+                    rowSpacing: 4
+                    enabled: prefs.mqtt_enabled === true
+
+                    Label {
+                        text: "Broker"
+                        opacity: 0.8
+                    }
+                    TextField {
+                        Layout.fillWidth: true
+                        text: prefs.mqtt_broker !== undefined ? prefs.mqtt_broker : "localhost:1883"
+                        placeholderText: "host:port"
+                        selectByMouse: true
+                        onEditingFinished: if (text !== prefs.mqtt_broker) setPref("mqtt_broker", text)
+                    }
+
+                    Label {
+                        text: "Topic"
+                        opacity: 0.8
+                    }
+                    TextField {
+                        Layout.fillWidth: true
+                        text: prefs.mqtt_topic !== undefined ? prefs.mqtt_topic : "zestbay"
+                        placeholderText: "zestbay"
+                        selectByMouse: true
+                        onEditingFinished: if (text !== prefs.mqtt_topic) setPref("mqtt_topic", text)
+                    }
+
+                    Label {
+                        text: "Username"
+                        opacity: 0.8
+                    }
+                    TextField {
+                        Layout.fillWidth: true
+                        text: prefs.mqtt_username || ""
+                        placeholderText: "No login"
+                        selectByMouse: true
+                        onEditingFinished: if (text !== (prefs.mqtt_username || "")) setPref("mqtt_username", text)
+                    }
+
+                    Label {
+                        text: "Password"
+                        opacity: 0.8
+                    }
+                    TextField {
+                        Layout.fillWidth: true
+                        text: prefs.mqtt_password || ""
+                        echoMode: TextInput.Password
+                        selectByMouse: true
+                        onEditingFinished: if (text !== (prefs.mqtt_password || "")) setPref("mqtt_password", text)
+                    }
+
+                    Label {
+                        text: "TLS"
+                        opacity: 0.8
+                    }
+                    Switch {
+                        checked: prefs.mqtt_tls === true
+                        onToggled: setPref("mqtt_tls", checked)
+                    }
+
+                    Label {
+                        text: "CA certificate"
+                        opacity: 0.8
+                    }
+                    TextField {
+                        Layout.fillWidth: true
+                        text: prefs.mqtt_tls_ca || ""
+                        placeholderText: "System roots"
+                        selectByMouse: true
+                        onEditingFinished: if (text !== (prefs.mqtt_tls_ca || "")) setPref("mqtt_tls_ca", text)
+                    }
+
+                    Label {
+                        text: "Commands"
+                        opacity: 0.8
+                    }
+                    ComboBox {
+                        property var scopes: [
+                            { value: "read", text: "Ignored (read-only)" },
+                            { value: "control", text: "Accepted (control)" }
+                        ]
+                        Layout.preferredWidth: 180
+                        model: scopes
+                        textRole: "text"
+                        valueRole: "value"
+                        currentIndex: {
+                            var current = prefs.mqtt_scope || "read"
+                            for (var i = 0; i < scopes.length; i++) {
+                                if (scopes[i].value === current)
+                                    return i
+                            }
+                            return 0
+                        }
+                        onActivated: setPref("mqtt_scope", currentValue)
+                    }
+                }
+
+                Rectangle {
+                    Layout.fillWidth: true
+                    height: 1
+                    color: Theme.separatorLight
+                }
+
                 RowLayout {
                     Layout.fillWidth: true
                     spacing: 12
@@ -96,11 +1438,11 @@ ApplicationWindow {
                         spacing: 2
 
                         Label {
-                            text: "Start minimized to tray"
+                            text: "Remote API"
                             font.bold: true
                         }
                         Label {
-                            text: "Launch with the window hidden. Use the system tray icon to show it."
+                            text: "Serve the graph over HTTP (GET /api/graph, POST /api/command) and stream changes over a WebSocket (/api/events) for browser-based remote patchbays. Clients must send one of the tokens below. Only listen on a non-local address with TLS or on networks you trust."
                             wrapMode: Text.WordWrap
                             Layout.fillWidth: true
                             font.pointSize: 9
@@ -109,31 +1451,207 @@ ApplicationWindow {
                     }
 
                     Switch {
-                        checked: prefs.start_minimized !== undefined ? prefs.start_minimized : false
-                        onToggled: setPref("start_minimized", checked)
+                        checked: prefs.remote_api_enabled === true
+                        onToggled: setPref("remote_api_enabled", checked)
+                    }
+                }
+
+                GridLayout {
+                    Layout.fillWidth: true
+                    columns: 3
+                    columnSpacing: 12
+                    rowSpacing: 4
+                    enabled: prefs.remote_api_enabled === true
+
+                    Label {
+                        text: "Listen on"
+                        opacity: 0.8
+                    }
+                    TextField {
+                        Layout.fillWidth: true
+                        Layout.columnSpan: 2
+                        text: prefs.remote_api_bind !== undefined ? prefs.remote_api_bind : "127.0.0.1:7380"
+                        placeholderText: "127.0.0.1:7380"
+                        selectByMouse: true
+                        onEditingFinished: if (text !== prefs.remote_api_bind) setPref("remote_api_bind", text)
+                    }
+
+                    Label {
+                        text: "Token"
+                        opacity: 0.8
+                    }
+                    TextField {
+                        Layout.fillWidth: true
+                        text: prefs.remote_api_token !== undefined ? prefs.remote_api_token : ""
+                        readOnly: true
+                        selectByMouse: true
+                        font.family: "monospace"
+                    }
+                    Button {
+                        text: "Regenerate"
+                        onClicked: setPref("remote_api_token", "")
+                    }
+
+                    Label {
+                        text: "TLS certificate"
+                        opacity: 0.8
+                    }
+                    TextField {
+                        Layout.fillWidth: true
+                        Layout.columnSpan: 2
+                        text: prefs.remote_api_tls_cert || ""
+                        placeholderText: "PEM file; empty serves plain HTTP"
+                        selectByMouse: true
+                        onEditingFinished: if (text !== (prefs.remote_api_tls_cert || "")) setPref("remote_api_tls_cert", text)
+                    }
+
+                    Label {
+                        text: "TLS key"
+                        opacity: 0.8
+                    }
+                    TextField {
+                        Layout.fillWidth: true
+                        Layout.columnSpan: 2
+                        text: prefs.remote_api_tls_key || ""
+                        placeholderText: "PEM file"
+                        selectByMouse: true
+                        onEditingFinished: if (text !== (prefs.remote_api_tls_key || "")) setPref("remote_api_tls_key", text)
+                    }
+
+                    Label {
+                        text: "Allowed origin"
+                        opacity: 0.8
+                    }
+                    TextField {
+                        Layout.fillWidth: true
+                        Layout.columnSpan: 2
+                        text: prefs.remote_api_cors_origin || ""
+                        placeholderText: "e.g. https://patchbay.example; empty blocks other sites"
+                        selectByMouse: true
+                        onEditingFinished: if (text !== (prefs.remote_api_cors_origin || "")) setPref("remote_api_cors_origin", text)
+                    }
+                }
+
+                ColumnLayout {
+                    Layout.fillWidth: true
+                    spacing: 4
+                    enabled: prefs.remote_api_enabled === true
+
+                    Label {
+                        text: "The token above may do everything. Hand out narrower ones here: read-only sees the graph, control also connects ports and runs the patchbay, plugins also bypasses plugins and sets their parameters."
+                        wrapMode: Text.WordWrap
+                        Layout.fillWidth: true
+                        font.pointSize: 9
+                        opacity: 0.5
+                    }
+
+                    Repeater {
+                        model: prefs.remote_api_tokens || []
+
+                        delegate: RowLayout {
+                            required property var modelData
+                            Layout.fillWidth: true
+                            spacing: 8
+
+                            Label {
+                                text: modelData.name
+                                elide: Text.ElideRight
+                                Layout.preferredWidth: 120
+                            }
+                            Label {
+                                text: modelData.scope
+                                opacity: 0.7
+                                Layout.preferredWidth: 60
+                            }
+                            TextField {
+                                Layout.fillWidth: true
+                                text: modelData.token
+                                readOnly: true
+                                selectByMouse: true
+                                font.family: "monospace"
+                            }
+                            Button {
+                                text: "Revoke"
+                                onClicked: {
+                                    controller.revoke_remote_api_token(modelData.token);
+                                    loadPrefs();
+                                }
+                            }
+                        }
+                    }
+
+                    RowLayout {
+                        Layout.fillWidth: true
+                        spacing: 8
+
+                        TextField {
+                            id: newTokenName
+                            Layout.fillWidth: true
+                            placeholderText: "Name, e.g. Kitchen tablet"
+                            selectByMouse: true
+                        }
+                        ComboBox {
+                            id: newTokenScope
+                            model: [
+                                { value: "read", text: "Read-only" },
+                                { value: "control", text: "Control" },
+                                { value: "plugins", text: "Plugins" }
+                            ]
+                            textRole: "text"
+                            valueRole: "value"
+                        }
+                        Button {
+                            text: "Generate"
+                            onClicked: {
+                                controller.generate_remote_api_token(newTokenName.text, newTokenScope.currentValue);
+                                newTokenName.text = "";
+                                loadPrefs();
+                            }
+                        }
                     }
                 }
 
                 Rectangle {
                     Layout.fillWidth: true
                     height: 1
-                    color: Theme.separatorLight
+                    color: Theme.separator
+                }
+
+                Label {
+                    text: "Timing"
+                    font.bold: true
+                    font.pointSize: 11
+                    opacity: 0.8
+                }
+
+                Label {
+                    text: "Adjust timing parameters to fine-tune responsiveness vs. reliability."
+                    font.italic: true
+                    opacity: 0.5
+                    Layout.fillWidth: true
+                    wrapMode: Text.WordWrap
                 }
 
                 RowLayout {
                     Layout.fillWidth: true
                     spacing: 12
 
+                    readonly property var profiles: [
+                        { value: "powersave", text: "Power Saving" },
+                        { value: "balanced", text: "Balanced" },
+                        { value: "performance", text: "Performance" }
+                    ]
+
                     ColumnLayout {
                         Layout.fillWidth: true
                         spacing: 2
 
                         Label {
-                            text: "Close to tray"
+                            text: "Performance profile"
                             font.bold: true
                         }
                         Label {
-                            text: "Clicking the window close button hides to the system tray instead of quitting."
+                            text: "Sets the poll interval, PipeWire tick, meter rate and graph redraws together. Balanced uses the intervals below; Power Saving wakes up less often, and Performance more often. Also in the tray menu."
                             wrapMode: Text.WordWrap
                             Layout.fillWidth: true
                             font.pointSize: 9
@@ -141,32 +1659,44 @@ ApplicationWindow {
                         }
                     }
 
-                    Switch {
-                        checked: prefs.close_to_tray !== undefined ? prefs.close_to_tray : false
-                        onToggled: setPref("close_to_tray", checked)
+                    ComboBox {
+                        Layout.preferredWidth: 180
+                        model: parent.profiles
+                        textRole: "text"
+                        valueRole: "value"
+                        currentIndex: {
+                            var current = prefs.performance_profile || "balanced"
+                            for (var i = 0; i < parent.profiles.length; i++) {
+                                if (parent.profiles[i].value === current)
+                                    return i
+                            }
+                            return 1
+                        }
+                        onActivated: setPref("performance_profile", currentValue)
                     }
                 }
 
-                Rectangle {
-                    Layout.fillWidth: true
-                    height: 1
-                    color: Theme.separatorLight
-                }
-
                 RowLayout {
                     Layout.fillWidth: true
                     spacing: 12
 
+                    readonly property var profiles: [
+                        { value: "", text: "Keep profile" },
+                        { value: "powersave", text: "Power Saving" },
+                        { value: "balanced", text: "Balanced" },
+                        { value: "performance", text: "Performance" }
+                    ]
+
                     ColumnLayout {
                         Layout.fillWidth: true
                         spacing: 2
 
                         Label {
-                            text: "Auto-learn patchbay rules"
+                            text: "On battery"
                             font.bold: true
                         }
                         Label {
-                            text: "Automatically create/update rules when you manually connect ports."
+                            text: "The profile to switch to while the machine runs on battery, as reported by UPower. Picking a profile while on battery overrides it until the power source changes."
                             wrapMode: Text.WordWrap
                             Layout.fillWidth: true
                             font.pointSize: 9
@@ -174,31 +1704,27 @@ ApplicationWindow {
                         }
                     }
 
-                    Switch {
-                        checked: prefs.auto_learn_rules !== undefined ? prefs.auto_learn_rules : true
-                        onToggled: setPref("auto_learn_rules", checked)
+                    ComboBox {
+                        Layout.preferredWidth: 180
+                        model: parent.profiles
+                        textRole: "text"
+                        valueRole: "value"
+                        currentIndex: {
+                            var current = prefs.battery_profile !== undefined ? prefs.battery_profile : "powersave"
+                            for (var i = 0; i < parent.profiles.length; i++) {
+                                if (parent.profiles[i].value === current)
+                                    return i
+                            }
+                            return 1
+                        }
+                        onActivated: setPref("battery_profile", currentValue)
                     }
                 }
 
                 Rectangle {
                     Layout.fillWidth: true
                     height: 1
-                    color: Theme.separator
-                }
-
-                Label {
-                    text: "Timing"
-                    font.bold: true
-                    font.pointSize: 11
-                    opacity: 0.8
-                }
-
-                Label {
-                    text: "Adjust timing parameters to fine-tune responsiveness vs. reliability."
-                    font.italic: true
-                    opacity: 0.5
-                    Layout.fillWidth: true
-                    wrapMode: Text.WordWrap
+                    color: Theme.separatorLight
                 }
 
                 ColumnLayout {
@@ -265,6 +1791,70 @@ ApplicationWindow {
                     color: Theme.separatorLight
                 }
 
+                ColumnLayout {
+                    Layout.fillWidth: true
+                    spacing: 4
+
+                    RowLayout {
+                        Layout.fillWidth: true
+                        Label {
+                            text: "Event storm limit"
+                            font.bold: true
+                        }
+                        Item {
+                            Layout.fillWidth: true
+                        }
+                        Label {
+                            text: stormSlider.value > 0 ? stormSlider.value + " changes/s" : "Off"
+                            font.family: "monospace"
+                            opacity: 0.8
+                        }
+                    }
+
+                    Label {
+                        text: "When the graph changes faster than this, such as a Bluetooth device dropping in and out, rules and link saving pause until it calms down."
+                        wrapMode: Text.WordWrap
+                        Layout.fillWidth: true
+                        font.pointSize: 9
+                        opacity: 0.5
+                    }
+
+                    RowLayout {
+                        Layout.fillWidth: true
+                        spacing: 8
+
+                        Label {
+                            text: "Off"
+                            opacity: 0.4
+                            font.pointSize: 8
+                        }
+                        Slider {
+                            id: stormSlider
+                            Layout.fillWidth: true
+                            from: 0
+                            to: 100
+                            stepSize: 5
+                            value: prefs.storm_events_per_sec !== undefined ? prefs.storm_events_per_sec : 20
+                            onPressedChanged: {
+                                if (!pressed) {
+                                    setPref("storm_events_per_sec", value);
+                                }
+                            }
+                        }
+                        Label {
+                            text: "100/s"
+                            opacity: 0.4
+                            font.pointSize: 8
+                        }
+                    }
+                }
+
+                Rectangle {
+                    Layout.fillWidth: true
+                    height: 1
+                    color: Theme.separatorLight
+                }
+
                 ColumnLayout {
                     Layout.fillWidth: true
                     spacing: 4
@@ -521,6 +2111,70 @@ ApplicationWindow {
                     color: Theme.separatorLight
                 }
 
+                ColumnLayout {
+                    Layout.fillWidth: true
+                    spacing: 4
+
+                    RowLayout {
+                        Layout.fillWidth: true
+                        Label {
+                            text: "Connect retries"
+                            font.bold: true
+                        }
+                        Item {
+                            Layout.fillWidth: true
+                        }
+                        Label {
+                            text: pwRetriesSlider.value
+                            font.family: "monospace"
+                            opacity: 0.8
+                        }
+                    }
+
+                    Label {
+                        text: "How many times a failed connection is retried, with increasing delays, before an error is shown. Helps right after a device is plugged in. Requires restart."
+                        wrapMode: Text.WordWrap
+                        Layout.fillWidth: true
+                        font.pointSize: 9
+                        opacity: 0.5
+                    }
+
+                    RowLayout {
+                        Layout.fillWidth: true
+                        spacing: 8
+
+                        Label {
+                            text: "0"
+                            opacity: 0.4
+                            font.pointSize: 8
+                        }
+                        Slider {
+                            id: pwRetriesSlider
+                            Layout.fillWidth: true
+                            from: 0
+                            to: 10
+                            stepSize: 1
+                            value: prefs.pw_connect_retries !== undefined ? prefs.pw_connect_retries : 5
+                            onPressedChanged: {
+                                if (!pressed) {
+                                    setPref("pw_connect_retries", value);
+                                }
+                            }
+                        }
+                        Label {
+                            text: "10"
+                            opacity: 0.4
+                            font.pointSize: 8
+                        }
+                    }
+                }
+
+                Rectangle {
+                    Layout.fillWidth: true
+                    height: 1
+                    color: Theme.separatorLight
+                }
+
                 ColumnLayout {
                     Layout.fillWidth: true
                     spacing: 4
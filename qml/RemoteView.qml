@@ -0,0 +1,352 @@
+import QtQuick
+import QtQuick.Controls
+import QtQuick.Layouts
+import ZestBay
+
+// Side panel managing another ZestBay, such as one run with --headless on a
+// machine without a monitor, over its remote API. Links, plugin bypass and
+// the patchbay go to that instance; nothing here touches the local graph.
+Rectangle {
+    id: remoteView
+    color: Theme.panelBg
+    visible: snapshot !== null
+    width: 520
+
+    required property var controller
+
+    property var snapshot: null
+    readonly property var graph: snapshot && snapshot.graph ? snapshot.graph : null
+    // What the token may do: "read", "control" or "plugins".
+    readonly property string scope: snapshot && snapshot.scope ? snapshot.scope : "read"
+    readonly property bool canControl: scope === "control" || scope === "plugins"
+    readonly property bool canManagePlugins: scope === "plugins"
+    property int pendingOutputPortId: -1
+    property int minimumWidth: 300
+
+    function refresh() {
+        try {
+            snapshot = JSON.parse(controller.get_remote_instance_json())
+        } catch (e) {
+            snapshot = null
+        }
+        if (!graph)
+            pendingOutputPortId = -1
+    }
+
+    function request(op, args) {
+        var req = args || {}
+        req.op = op
+        controller.send_remote_instance_request(JSON.stringify(req))
+    }
+
+    function lane(kind) {
+        if (!graph)
+            return []
+        return graph.nodes.filter(function(n) {
+            if (kind === "source")
+                return n.type === "Source"
+            if (kind === "sink")
+                return n.type === "Sink"
+            return n.type !== "Source" && n.type !== "Sink"
+        })
+    }
+
+    function portsOf(nodeId) {
+        return graph && graph.ports[String(nodeId)] ? graph.ports[String(nodeId)] : []
+    }
+
+    function portLabel(portId) {
+        if (!graph)
+            return ""
+        for (var i = 0; i < graph.nodes.length; i++) {
+            var node = graph.nodes[i]
+            var ports = portsOf(node.id)
+            for (var j = 0; j < ports.length; j++) {
+                if (ports[j].id === portId)
+                    return node.name + ": " + ports[j].name
+            }
+        }
+        return "#" + portId
+    }
+
+    function linksFrom(portId) {
+        if (!graph)
+            return []
+        return graph.links.filter(function(l) { return l.outputPortId === portId })
+    }
+
+    function portClicked(port) {
+        if (!canControl)
+            return
+        if (port.direction === "Output") {
+            pendingOutputPortId = pendingOutputPortId === port.id ? -1 : port.id
+        } else if (pendingOutputPortId >= 0) {
+            request("connect", { output_port_id: pendingOutputPortId, input_port_id: port.id })
+            pendingOutputPortId = -1
+        }
+    }
+
+    function close() {
+        var token = ""
+        try {
+            token = JSON.parse(controller.get_preferences_json()).remote_instance_token || ""
+        } catch (e) {}
+        controller.set_remote_instance("", token)
+    }
+
+    Component.onCompleted: refresh()
+
+    Connections {
+        target: remoteView.controller
+        function onRemote_instance_changed() { remoteView.refresh() }
+    }
+
+    Rectangle {
+        id: resizeHandle
+        anchors.left: parent.left
+        anchors.top: parent.top
+        anchors.bottom: parent.bottom
+        width: 4
+        color: Theme.separator
+
+        MouseArea {
+            anchors.fill: parent
+            anchors.leftMargin: -2
+            anchors.rightMargin: -2
+            cursorShape: Qt.SplitHCursor
+            property real pressX: 0
+            onPressed: mouse => pressX = mouse.x
+            onPositionChanged: mouse => {
+                var maxWidth = remoteView.parent ? remoteView.parent.width - 200 : remoteView.width
+                remoteView.width = Math.max(remoteView.minimumWidth,
+                                            Math.min(maxWidth, remoteView.width - (mouse.x - pressX)))
+            }
+        }
+    }
+
+    ColumnLayout {
+        anchors.fill: parent
+        anchors.leftMargin: resizeHandle.width
+        spacing: 0
+
+        RowLayout {
+            Layout.fillWidth: true
+            Layout.margins: 6
+            spacing: 8
+
+            Label {
+                text: remoteView.snapshot ? remoteView.snapshot.address : ""
+                font.bold: true
+                elide: Text.ElideRight
+                Layout.fillWidth: true
+            }
+
+            Label {
+                text: remoteView.snapshot && remoteView.snapshot.connected
+                      ? "Connected" : "Connecting…"
+                color: remoteView.snapshot && remoteView.snapshot.connected
+                       ? Theme.statusActive : Theme.statusBypassed
+            }
+
+            Label {
+                visible: remoteView.snapshot !== null && remoteView.snapshot.connected && !remoteView.canControl
+                text: "Read-only"
+                opacity: 0.7
+            }
+
+            Switch {
+                text: "Patchbay"
+                enabled: remoteView.snapshot !== null && remoteView.snapshot.connected && remoteView.canControl
+                checked: remoteView.graph ? remoteView.graph.patchbayEnabled === true : false
+                onToggled: remoteView.request("set_patchbay_enabled", { enabled: checked })
+                ToolTip.visible: hovered
+                ToolTip.text: "Apply the remote instance's patchbay rules"
+            }
+
+            Button {
+                text: "Apply Rules"
+                enabled: remoteView.snapshot !== null && remoteView.snapshot.connected && remoteView.canControl
+                onClicked: remoteView.request("apply_rules")
+            }
+
+            Button {
+                text: "Close"
+                ToolTip.visible: hovered
+                ToolTip.text: "Disconnect from this instance"
+                onClicked: remoteView.close()
+            }
+        }
+
+        Label {
+            Layout.fillWidth: true
+            Layout.leftMargin: 6
+            Layout.bottomMargin: 4
+            visible: text !== ""
+            text: remoteView.snapshot && remoteView.snapshot.error ? remoteView.snapshot.error : ""
+            color: Theme.statusError
+            wrapMode: Text.WordWrap
+        }
+
+        Label {
+            Layout.fillWidth: true
+            Layout.leftMargin: 6
+            Layout.bottomMargin: 4
+            visible: remoteView.pendingOutputPortId >= 0
+            text: "Connecting from " + remoteView.portLabel(remoteView.pendingOutputPortId)
+                  + " — click an input port"
+            color: Theme.colLinkConnecting
+            elide: Text.ElideRight
+        }
+
+        Rectangle {
+            Layout.fillWidth: true
+            height: 1
+            color: Theme.separatorLight
+        }
+
+        RowLayout {
+            Layout.fillWidth: true
+            Layout.fillHeight: true
+            spacing: 0
+
+            Repeater {
+                model: [
+                    { kind: "source", title: "Sources", color: Theme.colSource },
+                    { kind: "stream", title: "Streams & Plugins", color: Theme.colStreamOut },
+                    { kind: "sink", title: "Sinks", color: Theme.colSink }
+                ]
+
+                delegate: ColumnLayout {
+                    id: laneColumn
+                    required property var modelData
+                    Layout.fillWidth: true
+                    Layout.fillHeight: true
+                    Layout.preferredWidth: 1
+                    spacing: 0
+
+                    Rectangle {
+                        Layout.fillWidth: true
+                        height: 24
+                        color: Theme.surfaceBg
+
+                        Label {
+                            anchors.centerIn: parent
+                            text: laneColumn.modelData.title
+                            font.bold: true
+                            color: laneColumn.modelData.color
+                        }
+                    }
+
+                    ListView {
+                        Layout.fillWidth: true
+                        Layout.fillHeight: true
+                        clip: true
+                        spacing: 4
+                        model: remoteView.lane(laneColumn.modelData.kind)
+                        ScrollBar.vertical: ScrollBar {}
+
+                        delegate: Rectangle {
+                            id: nodeCard
+                            required property var modelData
+                            readonly property bool isPlugin: modelData.type === "Plugin"
+                            width: ListView.view.width
+                            height: nodeColumn.implicitHeight + 8
+                            color: Theme.nodeBg
+                            radius: 4
+                            border.width: 1
+                            border.color: Theme.nodeBorder
+
+                            ColumnLayout {
+                                id: nodeColumn
+                                anchors.left: parent.left
+                                anchors.right: parent.right
+                                anchors.top: parent.top
+                                anchors.margins: 4
+                                spacing: 1
+
+                                RowLayout {
+                                    Layout.fillWidth: true
+                                    spacing: 4
+
+                                    Label {
+                                        Layout.fillWidth: true
+                                        text: nodeCard.modelData.name
+                                        font.bold: true
+                                        elide: Text.ElideRight
+                                        opacity: nodeCard.modelData.pluginBypassed ? 0.5 : 1.0
+                                    }
+
+                                    Switch {
+                                        visible: nodeCard.isPlugin
+                                        enabled: remoteView.canManagePlugins
+                                        checked: nodeCard.modelData.pluginBypassed !== true
+                                        onToggled: remoteView.request("set_plugin_bypass",
+                                                                      { node_id: nodeCard.modelData.id, bypassed: !checked })
+                                        ToolTip.visible: hovered
+                                        ToolTip.text: checked ? "Bypass" : "Enable"
+                                    }
+                                }
+
+                                Repeater {
+                                    model: remoteView.portsOf(nodeCard.modelData.id)
+
+                                    delegate: ColumnLayout {
+                                        id: portColumn
+                                        required property var modelData
+                                        readonly property bool isOutput: modelData.direction === "Output"
+                                        Layout.fillWidth: true
+                                        spacing: 0
+
+                                        Label {
+                                            Layout.fillWidth: true
+                                            text: (portColumn.isOutput ? "▸ " : "◂ ") + portColumn.modelData.name
+                                            font.pointSize: 9
+                                            elide: Text.ElideRight
+                                            color: remoteView.pendingOutputPortId === portColumn.modelData.id
+                                                   ? Theme.colLinkConnecting
+                                                   : (portColumn.isOutput ? Theme.colPortOut : Theme.colPortIn)
+
+                                            MouseArea {
+                                                anchors.fill: parent
+                                                cursorShape: Qt.PointingHandCursor
+                                                onClicked: remoteView.portClicked(portColumn.modelData)
+                                            }
+                                        }
+
+                                        Repeater {
+                                            model: portColumn.isOutput ? remoteView.linksFrom(portColumn.modelData.id) : []
+
+                                            delegate: Label {
+                                                required property var modelData
+                                                Layout.fillWidth: true
+                                                Layout.leftMargin: 12
+                                                text: "→ " + remoteView.portLabel(modelData.inputPortId)
+                                                font.pointSize: 8
+                                                elide: Text.ElideRight
+                                                color: modelData.active ? Theme.colLinkActive : Theme.colLinkInactive
+
+                                                MouseArea {
+                                                    anchors.fill: parent
+                                                    enabled: remoteView.canControl
+                                                    cursorShape: Qt.PointingHandCursor
+                                                    onClicked: remoteView.request("disconnect", { link_id: parent.modelData.id })
+                                                }
+
+                                                ToolTip.visible: remoteView.canControl && linkHover.hovered
+                                                ToolTip.text: "Click to disconnect"
+
+                                                HoverHandler {
+                                                    id: linkHover
+                                                }
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
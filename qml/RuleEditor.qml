@@ -1,6 +1,7 @@
 import QtQuick
 import QtQuick.Controls
 import QtQuick.Layouts
+import ZestBay
 
 ApplicationWindow {
     id: ruleEditor
@@ -16,9 +17,36 @@ ApplicationWindow {
 
     property var rules: []
     property var nodeNames: []
-    property var nodeTypes: ["Any", "Sink", "Source", "App Out", "App In", "Duplex", "Plugin"]
+    property var nodeTypes: [
+        { label: "Any", kind: AppController.NodeKind.Unknown },
+        { label: "Sink", kind: AppController.NodeKind.Sink },
+        { label: "Source", kind: AppController.NodeKind.Source },
+        { label: "App Out", kind: AppController.NodeKind.StreamOutput },
+        { label: "App In", kind: AppController.NodeKind.StreamInput },
+        { label: "Duplex", kind: AppController.NodeKind.Duplex },
+        { label: "Plugin", kind: AppController.NodeKind.Plugin }
+    ]
     property var backups: []
+    property var rolePolicies: []
+    property var knownRoles: []
+    property var linkOverrides: []
     property string pendingRestoreFilename: ""
+    property var sceneActions: [
+        { action: "mute", label: "Mute", target: "node" },
+        { action: "unmute", label: "Unmute", target: "node" },
+        { action: "switchLinks", label: "Switch links", target: "" },
+        { action: "bypass", label: "Bypass", target: "plugin" },
+        { action: "unbypass", label: "Unbypass", target: "plugin" },
+        { action: "defaultOutput", label: "Default output", target: "node" }
+    ]
+
+    function sceneActionIndex(action) {
+        for (var i = 0; i < sceneActions.length; i++) {
+            if (sceneActions[i].action === action)
+                return i;
+        }
+        return 0;
+    }
 
     Dialog {
         id: snapshotConfirmDialog
@@ -90,6 +118,128 @@ ApplicationWindow {
         }
     }
 
+    Dialog {
+        id: transitionDialog
+        title: "Scene Transition"
+        anchors.centerIn: parent
+        modal: true
+        standardButtons: Dialog.Ok | Dialog.Cancel
+        width: Math.min(ruleEditor.width * 0.9, 560)
+
+        property string scene: ""
+        property var steps: []
+
+        function openFor(scene) {
+            transitionDialog.scene = scene;
+            try {
+                steps = JSON.parse(controller.get_scene_transition_json(scene));
+            } catch (e) {
+                steps = [];
+            }
+            open();
+        }
+
+        // Edited in place, so the rows are not rebuilt while typing
+        function updateStep(index, key, value) {
+            steps[index][key] = value;
+        }
+
+        ColumnLayout {
+            width: parent.width
+            spacing: 8
+
+            Label {
+                text: "Steps run in order when switching to \"" + transitionDialog.scene + "\", each after its delay. Without a Switch links step, the links switch last. Leave the list empty to switch at once."
+                wrapMode: Text.WordWrap
+                Layout.fillWidth: true
+                opacity: 0.6
+            }
+
+            Repeater {
+                model: transitionDialog.steps
+
+                RowLayout {
+                    required property int index
+                    required property var modelData
+                    Layout.fillWidth: true
+                    spacing: 6
+
+                    SpinBox {
+                        Layout.preferredWidth: 110
+                        from: 0
+                        to: 10000
+                        stepSize: 50
+                        editable: true
+                        value: modelData.delayMs || 0
+                        textFromValue: function(v) { return "+" + v + " ms" }
+                        valueFromText: function(t) { return parseInt(t.replace("+", "")) || 0 }
+                        onValueModified: transitionDialog.updateStep(index, "delayMs", value)
+
+                        ToolTip.visible: hovered
+                        ToolTip.text: "Wait after the previous step"
+                    }
+
+                    ComboBox {
+                        Layout.preferredWidth: 140
+                        model: sceneActions
+                        textRole: "label"
+                        currentIndex: sceneActionIndex(modelData.action)
+                        onActivated: {
+                            var step = { action: sceneActions[currentIndex].action, delayMs: modelData.delayMs || 0 };
+                            var target = sceneActions[currentIndex].target;
+                            if (target !== "")
+                                step[target] = modelData.node || modelData.plugin || "";
+                            var copy = transitionDialog.steps.slice();
+                            copy[index] = step;
+                            transitionDialog.steps = copy;
+                        }
+                    }
+
+                    ComboBox {
+                        property string target: sceneActions[sceneActionIndex(modelData.action)].target
+                        Layout.fillWidth: true
+                        visible: target !== ""
+                        editable: true
+                        model: nodeNames.map(function(n) { return n.name })
+                        editText: modelData[target] || ""
+                        onEditTextChanged: {
+                            if (target !== "")
+                                transitionDialog.updateStep(index, target, editText);
+                        }
+                    }
+
+                    Item {
+                        Layout.fillWidth: true
+                        visible: sceneActions[sceneActionIndex(modelData.action)].target === ""
+                    }
+
+                    Button {
+                        text: "✕"
+                        implicitWidth: 28
+                        onClicked: {
+                            var copy = transitionDialog.steps.slice();
+                            copy.splice(index, 1);
+                            transitionDialog.steps = copy;
+                        }
+                    }
+                }
+            }
+
+            Button {
+                text: "Add Step"
+                onClicked: {
+                    transitionDialog.steps = transitionDialog.steps.concat([{ action: "mute", node: "", delayMs: 0 }]);
+                }
+            }
+        }
+
+        onAccepted: {
+            if (!controller.set_scene_transition_json(scene, JSON.stringify(steps)))
+                console.warn("Scene transition was not saved");
+            loadBackups();
+        }
+    }
+
     Dialog {
         id: backupNameDialog
         title: "Save Backup"
@@ -139,6 +289,33 @@ ApplicationWindow {
         }
     }
 
+    function nodeTypeIndex(kind) {
+        for (var i = 0; i < nodeTypes.length; i++) {
+            if (nodeTypes[i].kind === kind)
+                return i;
+        }
+        return -1;
+    }
+
+    function loadRolePolicies() {
+        try {
+            var data = JSON.parse(controller.get_role_policies_json());
+            rolePolicies = data.policies || [];
+            knownRoles = data.knownRoles || [];
+        } catch (e) {
+            rolePolicies = [];
+            knownRoles = [];
+        }
+    }
+
+    function loadLinkOverrides() {
+        try {
+            linkOverrides = JSON.parse(controller.get_link_overrides_json());
+        } catch (e) {
+            linkOverrides = [];
+        }
+    }
+
     function loadBackups() {
         try {
             backups = JSON.parse(controller.list_rule_backups_json());
@@ -149,6 +326,8 @@ ApplicationWindow {
 
     function open() {
         loadRules();
+        loadRolePolicies();
+        loadLinkOverrides();
         loadBackups();
         visible = true;
         raise();
@@ -162,6 +341,11 @@ ApplicationWindow {
                 loadRules();
             }
         }
+        function onScene_switched(scene) {
+            if (ruleEditor.visible) {
+                loadRules();
+            }
+        }
     }
 
     ColumnLayout {
@@ -242,6 +426,30 @@ ApplicationWindow {
                         opacity: 0.5
                         Layout.fillWidth: true
                     }
+                    Label {
+                        text: "Fallbacks"
+                        font.bold: true
+                        font.pointSize: 9
+                        opacity: 0.5
+                        Layout.preferredWidth: 160
+                        horizontalAlignment: Text.AlignHCenter
+                    }
+                    Label {
+                        text: "Cooldown"
+                        font.bold: true
+                        font.pointSize: 9
+                        opacity: 0.5
+                        Layout.preferredWidth: 96
+                        horizontalAlignment: Text.AlignHCenter
+                    }
+                    Label {
+                        text: "Once"
+                        font.bold: true
+                        font.pointSize: 9
+                        opacity: 0.5
+                        Layout.preferredWidth: 40
+                        horizontalAlignment: Text.AlignHCenter
+                    }
                     Label {
                         text: "Enabled"
                         font.bold: true
@@ -309,15 +517,73 @@ ApplicationWindow {
 
                         Label {
                             text: {
+                                var text = "heuristic matching";
                                 if (rule.portMappings && rule.portMappings.length > 0)
-                                    return rule.portMappings.length + " port mapping" + (rule.portMappings.length > 1 ? "s" : "");
-                                return "heuristic matching";
+                                    text = rule.portMappings.length + " port mapping" + (rule.portMappings.length > 1 ? "s" : "");
+                                if (rule.activeFallback)
+                                    text += " \u2022 target missing, using " + rule.activeFallback;
+                                return text;
                             }
                             font.pointSize: 8
-                            opacity: 0.4
+                            opacity: rule.activeFallback ? 0.8 : 0.4
                         }
                     }
 
+                    TextField {
+                        Layout.preferredWidth: 160
+                        Layout.alignment: Qt.AlignVCenter
+                        text: (rule.fallbackTargets || []).join(", ")
+                        placeholderText: "None"
+                        selectByMouse: true
+                        onEditingFinished: {
+                            if (!rule.id)
+                                return;
+                            var targets = text.split(",").map(function(t) { return t.trim() })
+                                .filter(function(t) { return t !== "" });
+                            if (targets.join(", ") === (rule.fallbackTargets || []).join(", "))
+                                return;
+                            controller.set_rule_fallbacks(rule.id, JSON.stringify(targets));
+                            loadRules();
+                        }
+
+                        ToolTip.visible: hovered
+                        ToolTip.text: "Targets to use, in order, while the rule's target is missing (comma-separated, * and ? allowed). Sources move back once the target returns"
+                    }
+
+                    SpinBox {
+                        Layout.preferredWidth: 96
+                        Layout.alignment: Qt.AlignVCenter
+                        from: 0
+                        to: 3600
+                        stepSize: 5
+                        editable: true
+                        value: rule.cooldownSecs || 0
+                        textFromValue: function(v) { return v > 0 ? v + " s" : "Off" }
+                        valueFromText: function(t) { return parseInt(t) || 0 }
+                        onValueModified: {
+                            if (rule.id)
+                                controller.set_rule_cooldown(rule.id, value);
+                        }
+
+                        ToolTip.visible: hovered
+                        ToolTip.text: "After you disconnect one of this rule's links by hand, wait this long before reconnecting instead of forgetting the link"
+                    }
+
+                    CheckBox {
+                        Layout.preferredWidth: 40
+                        Layout.alignment: Qt.AlignVCenter
+                        checked: rule.oncePerAppearance || false
+                        onToggled: {
+                            if (rule.id) {
+                                controller.set_rule_once_per_appearance(rule.id, checked);
+                                loadRules();
+                            }
+                        }
+
+                        ToolTip.visible: hovered
+                        ToolTip.text: "Connect only once each time the source appears; links you remove stay removed until it comes back"
+                    }
+
                     Item {
                         Layout.preferredWidth: 58
                         Layout.preferredHeight: 30
@@ -506,6 +772,8 @@ ApplicationWindow {
                 ComboBox {
                     id: sourceTypeCombo
                     model: nodeTypes
+                    textRole: "label"
+                    valueRole: "kind"
                     implicitWidth: 120
                 }
 
@@ -524,6 +792,8 @@ ApplicationWindow {
                 ComboBox {
                     id: targetTypeCombo
                     model: nodeTypes
+                    textRole: "label"
+                    valueRole: "kind"
                     implicitWidth: 120
                 }
 
@@ -532,9 +802,8 @@ ApplicationWindow {
                     text: "Create Rule"
                     enabled: sourcePatternField.text.length > 0 && targetPatternField.text.length > 0
                     onClicked: {
-                        var srcType = sourceTypeCombo.currentText === "Any" ? "" : sourceTypeCombo.currentText;
-                        var tgtType = targetTypeCombo.currentText === "Any" ? "" : targetTypeCombo.currentText;
-                        controller.add_rule(sourcePatternField.text, srcType, targetPatternField.text, tgtType);
+                        controller.add_rule(sourcePatternField.text, sourceTypeCombo.currentValue,
+                                            targetPatternField.text, targetTypeCombo.currentValue);
                         sourcePatternField.text = "";
                         targetPatternField.text = "";
                         sourceTypeCombo.currentIndex = 0;
@@ -568,22 +837,249 @@ ApplicationWindow {
                         delegate: Button {
                             required property int index
                             property var nodeInfo: nodeNames[index] || {}
-                            text: (nodeInfo.name || "") + " [" + (nodeInfo.type || "") + "]"
+                            property int typeIndex: nodeTypeIndex(nodeInfo.type)
+                            text: (nodeInfo.name || "") + " [" + (typeIndex > 0 ? nodeTypes[typeIndex].label : "Unknown") + "]"
                             flat: true
                             font.pointSize: 8
                             onClicked: {
                                 if (sourcePatternField.text.length === 0) {
                                     sourcePatternField.text = nodeInfo.name || "";
-                                    var srcIdx = nodeTypes.indexOf(nodeInfo.type || "");
-                                    if (srcIdx >= 0)
-                                        sourceTypeCombo.currentIndex = srcIdx;
+                                    if (typeIndex >= 0)
+                                        sourceTypeCombo.currentIndex = typeIndex;
                                 } else {
                                     targetPatternField.text = nodeInfo.name || "";
-                                    var tgtIdx = nodeTypes.indexOf(nodeInfo.type || "");
-                                    if (tgtIdx >= 0)
-                                        targetTypeCombo.currentIndex = tgtIdx;
+                                    if (typeIndex >= 0)
+                                        targetTypeCombo.currentIndex = typeIndex;
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        Rectangle {
+            Layout.fillWidth: true
+            height: 1
+            color: Theme.separator
+        }
+
+        ColumnLayout {
+            id: roleSection
+            Layout.fillWidth: true
+            spacing: 6
+
+            property bool expanded: false
+
+            RowLayout {
+                Layout.fillWidth: true
+                spacing: 8
+
+                Button {
+                    text: roleSection.expanded ? "Hide Role Policies" : "Role Policies..."
+                    onClicked: {
+                        if (!roleSection.expanded) loadRolePolicies()
+                        roleSection.expanded = !roleSection.expanded
+                    }
+                }
+
+                Label {
+                    text: rolePolicies.length + " polic" + (rolePolicies.length !== 1 ? "ies" : "y")
+                    opacity: 0.6
+                }
+
+                Item { Layout.fillWidth: true }
+            }
+
+            Label {
+                visible: roleSection.expanded
+                text: "Route new app streams by media.role when no rule matches them."
+                font.italic: true
+                opacity: 0.6
+                Layout.fillWidth: true
+            }
+
+            Repeater {
+                model: roleSection.expanded ? rolePolicies.length : 0
+                delegate: RowLayout {
+                    required property int index
+                    property var policy: rolePolicies[index] || {}
+                    Layout.fillWidth: true
+                    spacing: 8
+
+                    Label {
+                        text: policy.role || ""
+                        font.bold: true
+                        Layout.preferredWidth: 140
+                        elide: Text.ElideRight
+                    }
+                    Label {
+                        text: "\u2192"
+                        opacity: 0.5
+                    }
+                    Label {
+                        text: policy.targetPattern || ""
+                        Layout.fillWidth: true
+                        elide: Text.ElideRight
+                    }
+                    Button {
+                        text: "Remove"
+                        font.pointSize: 9
+                        implicitHeight: 28
+                        onClicked: {
+                            controller.remove_role_policy(policy.role || "")
+                            loadRolePolicies()
+                        }
+                    }
+                }
+            }
+
+            RowLayout {
+                visible: roleSection.expanded
+                Layout.fillWidth: true
+                spacing: 8
+
+                ComboBox {
+                    id: roleCombo
+                    editable: true
+                    model: knownRoles
+                    implicitWidth: 160
+                }
+                Label {
+                    text: "\u2192"
+                    opacity: 0.5
+                }
+                ComboBox {
+                    id: roleTargetCombo
+                    editable: true
+                    Layout.fillWidth: true
+                    model: nodeNames.filter(function(n) {
+                        return n.type === AppController.NodeKind.Sink || n.type === AppController.NodeKind.Plugin || n.type === AppController.NodeKind.Duplex;
+                    }).map(function(n) { return n.name; })
+                }
+                Button {
+                    text: "Set"
+                    enabled: roleCombo.editText.length > 0 && roleTargetCombo.editText.length > 0
+                    onClicked: {
+                        controller.set_role_policy(roleCombo.editText, roleTargetCombo.editText)
+                        loadRolePolicies()
+                    }
+                }
+            }
+        }
+
+        Rectangle {
+            Layout.fillWidth: true
+            height: 1
+            color: Theme.separator
+        }
+
+        ColumnLayout {
+            id: overrideSection
+            Layout.fillWidth: true
+            spacing: 6
+
+            property bool expanded: false
+
+            RowLayout {
+                Layout.fillWidth: true
+                spacing: 8
+
+                Button {
+                    text: overrideSection.expanded ? "Hide Overrides" : "Overrides (" + linkOverrides.length + ")..."
+                    onClicked: {
+                        if (!overrideSection.expanded) loadLinkOverrides()
+                        overrideSection.expanded = !overrideSection.expanded
+                    }
+
+                    ToolTip.visible: hovered
+                    ToolTip.text: "Links you disconnected by hand that rules leave down for now"
+                }
+
+                Item { Layout.fillWidth: true }
+
+                Button {
+                    text: "Clear All"
+                    enabled: linkOverrides.length > 0
+                    onClicked: {
+                        controller.clear_link_overrides()
+                        loadLinkOverrides()
+                    }
+
+                    ToolTip.visible: hovered
+                    ToolTip.text: "Let rules restore every overridden link"
+                }
+            }
+
+            ColumnLayout {
+                visible: overrideSection.expanded
+                Layout.fillWidth: true
+                spacing: 4
+
+                Label {
+                    text: "No overrides. Set 'Manual disconnects' in Preferences to keep links you disconnect by hand down instead of unlearning them."
+                    wrapMode: Text.WordWrap
+                    Layout.fillWidth: true
+                    opacity: 0.5
+                    visible: linkOverrides.length === 0
+                }
+
+                ListView {
+                    id: overrideList
+                    Layout.fillWidth: true
+                    Layout.preferredHeight: Math.min(linkOverrides.length * 42, 180)
+                    clip: true
+                    model: linkOverrides.length
+                    spacing: 2
+                    visible: linkOverrides.length > 0
+
+                    ScrollBar.vertical: ScrollBar { policy: ScrollBar.AsNeeded }
+
+                    delegate: Rectangle {
+                        required property int index
+                        width: overrideList.width - 12
+                        height: 38
+                        color: index % 2 === 0 ? Theme.rowEven : Theme.rowOdd
+                        radius: 3
+
+                        property var entry: linkOverrides[index] || {}
+
+                        RowLayout {
+                            anchors.fill: parent
+                            anchors.leftMargin: 8
+                            anchors.rightMargin: 8
+                            spacing: 8
+
+                            ColumnLayout {
+                                Layout.fillWidth: true
+                                spacing: 1
+
+                                Label {
+                                    text: (entry.source || "") + ":" + (entry.outputPort || "")
+                                          + " \u2192 " + (entry.target || "") + ":" + (entry.inputPort || "")
+                                    font.pointSize: 9
+                                    elide: Text.ElideRight
+                                    Layout.fillWidth: true
+                                }
+                                Label {
+                                    text: "Left down " + (entry.scope || "")
+                                    font.pointSize: 8
+                                    opacity: 0.5
                                 }
                             }
+
+                            Button {
+                                text: "Restore"
+                                font.pointSize: 9
+                                implicitHeight: 28
+                                onClicked: {
+                                    controller.remove_link_override(index)
+                                    loadLinkOverrides()
+                                }
+
+                                ToolTip.visible: hovered
+                                ToolTip.text: "Drop the override so rules reconnect this link"
+                            }
                         }
                     }
                 }
@@ -695,9 +1191,26 @@ ApplicationWindow {
                                         font.pointSize: 8
                                         opacity: 0.5
                                     }
+                                    Label {
+                                        text: backup.transitionSteps + " transition steps"
+                                        font.pointSize: 8
+                                        opacity: 0.5
+                                        visible: backup.transitionSteps > 0
+                                    }
                                 }
                             }
 
+                            Button {
+                                text: "Transition"
+                                font.pointSize: 9
+                                implicitHeight: 28
+                                visible: backup.name ? true : false
+                                onClicked: transitionDialog.openFor(backup.name)
+
+                                ToolTip.visible: hovered
+                                ToolTip.text: "Steps to run when switching to this scene"
+                            }
+
                             Button {
                                 text: "Restore"
                                 font.pointSize: 9
@@ -0,0 +1,240 @@
+import QtQuick
+import QtQuick.Controls
+import QtQuick.Layouts
+import ZestBay
+
+ApplicationWindow {
+    id: sessionsWindow
+    title: "Sessions"
+    color: Theme.windowBg
+    width: 520
+    height: 420
+    minimumWidth: 400
+    minimumHeight: 300
+    visible: false
+
+    required property var controller
+
+    property var sessions: []
+    property var recent: []
+    property string current: ""
+    property string result: ""
+
+    signal sessionSaved()
+
+    function reload() {
+        var info = {}
+        try {
+            info = JSON.parse(controller.get_sessions_json())
+        } catch(e) {}
+        sessions = info.sessions || []
+        recent = info.recent || []
+        current = info.current || ""
+    }
+
+    function open() {
+        reload()
+        result = ""
+        nameField.text = current
+        visible = true
+        raise()
+        requestActivate()
+        nameField.forceActiveFocus()
+        nameField.selectAll()
+    }
+
+    function confirmNew() {
+        open()
+        newSessionDialog.open()
+    }
+
+    function saveAs(name) {
+        if (name.length === 0)
+            return
+        var exists = sessions.some(function(s) { return s.name === name })
+        if (exists && name !== current) {
+            overwriteDialog.name = name
+            overwriteDialog.open()
+            return
+        }
+        doSave(name)
+    }
+
+    function doSave(name) {
+        if (controller.save_session_as(name)) {
+            reload()
+            result = "Saved session \"" + name + "\"."
+            sessionSaved()
+        }
+    }
+
+    function savedText(secs) {
+        return secs > 0 ? new Date(secs * 1000).toLocaleString(Qt.locale(), Locale.ShortFormat) : ""
+    }
+
+    ColumnLayout {
+        anchors.fill: parent
+        anchors.margins: 16
+        spacing: 8
+
+        Label {
+            text: current !== "" ? "Session: " + current : "Unsaved session"
+            font.bold: true
+            font.pointSize: 11
+        }
+
+        Label {
+            text: "A session holds the plugins and their settings, links, rules and layout. Loading one restarts ZestBay on it; changes since the last save of the current session are lost."
+            opacity: 0.5
+            wrapMode: Text.WordWrap
+            Layout.fillWidth: true
+        }
+
+        ListView {
+            id: sessionList
+            Layout.fillWidth: true
+            Layout.fillHeight: true
+            clip: true
+            model: sessions.length
+            spacing: 2
+
+            ScrollBar.vertical: ScrollBar { policy: ScrollBar.AsNeeded }
+
+            delegate: Rectangle {
+                required property int index
+                width: sessionList.width - 12
+                height: 40
+                color: index % 2 === 0 ? Theme.rowEven : Theme.rowOdd
+                radius: 3
+
+                property var session: sessions[index] || {}
+
+                RowLayout {
+                    anchors.fill: parent
+                    anchors.margins: 8
+                    spacing: 8
+
+                    Label {
+                        text: session.name || ""
+                        font.pointSize: 9
+                        font.bold: session.name === current
+                        elide: Text.ElideRight
+                        Layout.fillWidth: true
+                    }
+
+                    Label {
+                        text: savedText(session.saved || 0)
+                        font.pointSize: 8
+                        opacity: 0.5
+                    }
+
+                    Button {
+                        text: "Load"
+                        onClicked: controller.load_session(session.name)
+                    }
+
+                    Button {
+                        text: "Delete"
+                        onClicked: {
+                            if (controller.delete_session(session.name)) {
+                                reload()
+                                sessionSaved()
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        Label {
+            text: "No saved sessions yet."
+            opacity: 0.5
+            visible: sessions.length === 0
+            Layout.alignment: Qt.AlignHCenter
+        }
+
+        RowLayout {
+            Layout.fillWidth: true
+            spacing: 8
+
+            TextField {
+                id: nameField
+                Layout.fillWidth: true
+                placeholderText: "Session name"
+                selectByMouse: true
+                onAccepted: sessionsWindow.saveAs(text.trim())
+            }
+
+            Button {
+                text: "Save As"
+                enabled: nameField.text.trim().length > 0
+                onClicked: sessionsWindow.saveAs(nameField.text.trim())
+            }
+        }
+
+        Label {
+            text: result
+            visible: result.length > 0
+            wrapMode: Text.WordWrap
+            Layout.fillWidth: true
+        }
+
+        Rectangle {
+            Layout.fillWidth: true
+            height: 1
+            color: Theme.separator
+        }
+
+        RowLayout {
+            Layout.fillWidth: true
+
+            Button {
+                text: "New Session"
+                onClicked: newSessionDialog.open()
+            }
+
+            Item { Layout.fillWidth: true }
+
+            Button {
+                text: "Close"
+                onClicked: sessionsWindow.visible = false
+            }
+        }
+    }
+
+    Dialog {
+        id: overwriteDialog
+        title: "Replace Session"
+        anchors.centerIn: parent
+        modal: true
+        standardButtons: Dialog.Yes | Dialog.No
+        width: 400
+
+        property string name: ""
+
+        Label {
+            text: "A session named \"" + overwriteDialog.name + "\" already exists. Replace it?"
+            width: parent.width
+            wrapMode: Text.WordWrap
+        }
+
+        onAccepted: sessionsWindow.doSave(name)
+    }
+
+    Dialog {
+        id: newSessionDialog
+        title: "New Session"
+        anchors.centerIn: parent
+        modal: true
+        standardButtons: Dialog.Ok | Dialog.Cancel
+        width: 400
+
+        Label {
+            text: "Remove all plugins, links, rules and layout and restart ZestBay? Save the current session first to keep it."
+            width: parent.width
+            wrapMode: Text.WordWrap
+        }
+
+        onAccepted: controller.new_session()
+    }
+}
@@ -74,11 +74,14 @@ QtObject {
     readonly property color colPortOut:     "#90EE90"
     readonly property color colMidi:        "#FF69B4"
     readonly property color colMidiPort:    "#FF69B4"
+    readonly property color colCvPort:      "#40E0D0"
 
     // ─── Graph: Link colors ───
     readonly property color colLinkActive:     "#32CD32"
     readonly property color colLinkInactive:   dark ? "#555555" : "#aaaaaa"
     readonly property color colLinkMidi:       "#FF69B4"
+    readonly property color colLinkCv:         "#40E0D0"
+    readonly property color colLinkSidechain:  "#FFA500"
     readonly property color colLinkConnecting: "#FFFF00"
     readonly property color colLinkSelected:   "#FF4444"
 
@@ -120,6 +123,10 @@ QtObject {
     readonly property color defaultBadgeBg:   "#004422"
     readonly property color defaultBadgeText: "#00FF88"
 
+    // ─── Negotiated audio format badge ───
+    readonly property color formatBadgeBg:   dark ? "#2a2a3a" : "#e4e4f0"
+    readonly property color formatBadgeText: dark ? "#a0a0c0" : "#505070"
+
     // ─── Gradient fades (for scroll hints) ───
     readonly property color fadeColor:      dark ? "#1e1e1e" : "#f5f5f5"
 
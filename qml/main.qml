@@ -1,16 +1,72 @@
 import QtQuick
 import QtQuick.Controls
 import QtQuick.Layouts
+import Qt.labs.platform as Platform
 import ZestBay
 
 ApplicationWindow {
     id: mainWindow
+    objectName: "mainWindow"
     visible: false
     width: 800
     height: 600
     color: Theme.windowBg
     title: "ZestBay - Qt6"
 
+    // Where "hide to tray" goes: "status_notifier", "xembed" or "none".
+    // Without a tray the window is minimized instead of hidden.
+    readonly property string trayBackend: controller.tray_backend
+
+    onTrayBackendChanged: {
+        if (trayBackend === "none" && !mainWindow.visible && !startupPending)
+            mainWindow.showMinimized();
+    }
+
+    property bool startupPending: true
+
+    // Plugin UIs open on the main window's monitor unless told otherwise.
+    function updateScreens() {
+        var screens = [];
+        for (var i = 0; i < Qt.application.screens.length; i++) {
+            var s = Qt.application.screens[i];
+            screens.push({
+                name: s.name,
+                x: s.virtualX,
+                y: s.virtualY,
+                width: s.width,
+                height: s.height
+            });
+        }
+        controller.set_screens(JSON.stringify(screens), mainWindow.screen ? mainWindow.screen.name : "");
+    }
+
+    onScreenChanged: updateScreens()
+
+    Connections {
+        target: Qt.application
+        function onScreensChanged() {
+            mainWindow.updateScreens();
+        }
+    }
+
+    function hideToTray() {
+        if (trayBackend === "none") {
+            mainWindow.showMinimized();
+        } else {
+            mainWindow.visible = false;
+            controller.set_window_visible(false);
+        }
+    }
+
+    function showFromTray() {
+        mainWindow.visible = true;
+        mainWindow.raise();
+        mainWindow.requestActivate();
+        controller.set_window_visible(true);
+        graphView.refreshData();
+        graphView.forceActiveFocus();
+    }
+
     Component.onCompleted: {
         try {
             var geo = JSON.parse(controller.get_window_geometry_json());
@@ -27,10 +83,15 @@ ApplicationWindow {
         try {
             var prefs = JSON.parse(controller.get_preferences_json());
             if (prefs.start_minimized) {
-                controller.set_window_visible(false);
+                startupPending = false;
+                if (trayBackend === "none")
+                    mainWindow.showMinimized();
+                else
+                    controller.set_window_visible(false);
                 return;
             }
         } catch (e) {}
+        startupPending = false;
         mainWindow.visible = true;
     }
 
@@ -39,8 +100,7 @@ ApplicationWindow {
             var prefs = JSON.parse(controller.get_preferences_json());
             if (prefs.close_to_tray) {
                 close.accepted = false;
-                mainWindow.visible = false;
-                controller.set_window_visible(false);
+                hideToTray();
                 return;
             }
         } catch (e) {}
@@ -69,29 +129,72 @@ ApplicationWindow {
 
     AppController {
         id: controller
-        Component.onCompleted: controller.init()
+        Component.onCompleted: {
+            controller.init();
+            mainWindow.updateScreens();
+        }
+    }
+
+    // Qt draws this one itself, as an XEmbed icon, for panels that do not
+    // host StatusNotifier items.
+    Platform.SystemTrayIcon {
+        visible: mainWindow.trayBackend === "xembed"
+        icon.name: "zestbay-tray"
+        icon.source: controller.get_tray_icon_url()
+        tooltip: "ZestBay"
+
+        onActivated: function(reason) {
+            if (reason !== Platform.SystemTrayIcon.Trigger)
+                return;
+            if (mainWindow.visible)
+                hideToTray();
+            else
+                showFromTray();
+        }
+
+        menu: Platform.Menu {
+            Platform.MenuItem {
+                text: "Show"
+                onTriggered: showFromTray()
+            }
+            Platform.MenuItem {
+                text: "Show All Plugin UIs"
+                onTriggered: controller.show_all_plugin_uis()
+            }
+            Platform.MenuItem {
+                text: "Hide All Plugin UIs"
+                onTriggered: controller.hide_all_plugin_uis()
+            }
+            Platform.MenuSeparator {}
+            Platform.MenuItem {
+                text: "Quit"
+                onTriggered: controller.request_quit()
+            }
+        }
     }
 
     Connections {
         target: controller
         function onGraph_changed() {
-            graphView.refreshData();
+            if (!graphRefreshTimer.running)
+                graphRefreshTimer.start();
+        }
+        function onPerformance_profileChanged() {
+            pollTimer.interval = controller.get_poll_interval_ms();
+            graphRefreshTimer.interval = controller.get_repaint_interval_ms();
+        }
+        function onTemporary_links_changed() {
+            refreshTemporaryLinks();
         }
         function onError_occurred(message) {
             errorDialogText.text = message;
             errorDialog.open();
         }
         function onShow_window_requested() {
-            mainWindow.visible = true;
-            mainWindow.raise();
-            mainWindow.requestActivate();
-            controller.set_window_visible(true);
-            graphView.refreshData();
-            graphView.forceActiveFocus();
+            showFromTray();
         }
         function onHide_window_requested() {
-            mainWindow.visible = false;
-            controller.set_window_visible(false);
+            hideToTray();
         }
         function onMidi_learn_started(instance_id, port_index) {
             pluginParamsDialog.midiLearnInstanceId = instance_id
@@ -108,6 +211,12 @@ ApplicationWindow {
         function onMidi_mapping_conflict(source_json, existing_label) {
             pluginParamsDialog.showConflictDialog(source_json, existing_label)
         }
+        function onDocked_uis_changed() {
+            pluginDock.refresh()
+        }
+        function onParameters_changed(node_id) {
+            pluginParamsDialog.parametersChanged(node_id)
+        }
         function onCrash_recovery_available(crashed_uris) {
             crashRecoveryDialogText.text =
                 "A crash was detected during plugin restore.\n\n" +
@@ -118,6 +227,16 @@ ApplicationWindow {
                 "• Ignore: keeps the current plugins.json. Restart to try again."
             crashRecoveryDialog.open()
         }
+        function onCrash_report_available(report_path, cause) {
+            crashReportDialog.reportPath = report_path
+            crashReportDialogText.text =
+                "ZestBay crashed the last time it ran.\n\n" +
+                "Cause: " + cause + "\n\n" +
+                "A report with a backtrace and the last log lines was saved to:\n" +
+                report_path + "\n\n" +
+                "Reporting the issue with the report attached helps get it fixed."
+            crashReportDialog.open()
+        }
     }
 
     Dialog {
@@ -135,6 +254,20 @@ ApplicationWindow {
         }
     }
 
+    Dialog {
+        id: infoDialog
+        anchors.centerIn: parent
+        modal: true
+        standardButtons: Dialog.Ok
+        width: Math.min(mainWindow.width * 0.6, 500)
+
+        Label {
+            id: infoDialogText
+            width: parent.width
+            wrapMode: Text.WordWrap
+        }
+    }
+
     Dialog {
         id: crashRecoveryDialog
         title: "Crash Recovery"
@@ -160,6 +293,39 @@ ApplicationWindow {
         }
     }
 
+    Dialog {
+        id: crashReportDialog
+        title: "Crash Report"
+        anchors.centerIn: parent
+        modal: true
+        width: Math.min(mainWindow.width * 0.7, 550)
+
+        property string reportPath: ""
+
+        Label {
+            id: crashReportDialogText
+            width: parent.width
+            wrapMode: Text.WrapAnywhere
+        }
+
+        footer: DialogButtonBox {
+            Button {
+                text: "Open Report"
+                DialogButtonBox.buttonRole: DialogButtonBox.ActionRole
+                onClicked: Qt.openUrlExternally("file://" + crashReportDialog.reportPath)
+            }
+            Button {
+                text: "Report Issue"
+                DialogButtonBox.buttonRole: DialogButtonBox.ActionRole
+                onClicked: Qt.openUrlExternally(controller.get_crash_issue_url(crashReportDialog.reportPath))
+            }
+            Button {
+                text: "Close"
+                DialogButtonBox.buttonRole: DialogButtonBox.RejectRole
+            }
+        }
+    }
+
     Timer {
         id: pollTimer
         interval: controller.get_poll_interval_ms()
@@ -168,9 +334,63 @@ ApplicationWindow {
         onTriggered: controller.poll_events()
     }
 
+    // Graph changes are drawn at most once per repaint interval of the
+    // performance profile. When saving power, not at all while hidden to
+    // the tray; showing the window redraws.
+    Timer {
+        id: graphRefreshTimer
+        interval: controller.get_repaint_interval_ms()
+        repeat: false
+        onTriggered: {
+            if (mainWindow.visible || controller.performance_profile !== "powersave")
+                graphView.refreshData();
+        }
+    }
+
     menuBar: MenuBar {
         Menu {
             title: "&File"
+            Action {
+                text: "&New Session..."
+                onTriggered: sessionsDialog.confirmNew()
+            }
+            Action {
+                text: "&Load Session..."
+                onTriggered: sessionsDialog.open()
+            }
+            Action {
+                text: "Save Session &As..."
+                onTriggered: sessionsDialog.open()
+            }
+            Menu {
+                id: recentSessionsMenu
+                title: "&Recent Sessions"
+                enabled: recent.length > 0
+
+                property var recent: []
+
+                function reload() {
+                    try {
+                        recent = JSON.parse(controller.get_sessions_json()).recent || []
+                    } catch(e) {
+                        recent = []
+                    }
+                }
+
+                Component.onCompleted: reload()
+                onAboutToShow: reload()
+
+                Repeater {
+                    model: recentSessionsMenu.recent
+
+                    delegate: MenuItem {
+                        required property var modelData
+                        text: modelData
+                        onTriggered: controller.load_session(modelData)
+                    }
+                }
+            }
+            MenuSeparator {}
             Action {
                 text: "Add &Plugin..."
                 onTriggered: {
@@ -187,6 +407,29 @@ ApplicationWindow {
                 text: "MIDI &Mappings..."
                 onTriggered: midiMappingsDialog.open()
             }
+            Action {
+                text: "&Import..."
+                onTriggered: importDialog.open()
+            }
+            Action {
+                text: "&Export Configuration..."
+                onTriggered: {
+                    var path = controller.export_config()
+                    if (path.length > 0) {
+                        configExportDialog.path = path
+                        configExportDialog.open()
+                    }
+                }
+            }
+            MenuSeparator {}
+            Action {
+                text: "Show All Plugin UIs"
+                onTriggered: controller.show_all_plugin_uis()
+            }
+            Action {
+                text: "Hide All Plugin UIs"
+                onTriggered: controller.hide_all_plugin_uis()
+            }
             MenuSeparator {}
             Action {
                 text: "&Preferences..."
@@ -220,6 +463,43 @@ ApplicationWindow {
                 text: "Snapshot Connections"
                 onTriggered: controller.snapshot_rules()
             }
+            Action {
+                text: "Import WirePlumber Targets"
+                onTriggered: {
+                    var summary = controller.import_wireplumber_rules();
+                    if (summary !== "") {
+                        infoDialog.title = "WirePlumber Import";
+                        infoDialogText.text = summary;
+                        infoDialog.open();
+                    }
+                }
+            }
+            MenuSeparator {}
+            Action {
+                text: "Refresh from PipeWire"
+                onTriggered: controller.refresh_graph()
+            }
+            Action {
+                text: "Full Resync"
+                onTriggered: controller.resync_graph()
+            }
+            Action {
+                text: "Restart PipeWire Connection"
+                onTriggered: controller.restart_pipewire()
+            }
+            MenuSeparator {}
+            Action {
+                text: "Command &Log..."
+                onTriggered: commandLogDialog.open()
+            }
+            Action {
+                text: "&Hooks..."
+                onTriggered: hooksDialog.open()
+            }
+            Action {
+                text: "MIDI &Clock..."
+                onTriggered: midiClockDialog.open()
+            }
         }
         Menu {
             title: "&Help"
@@ -240,6 +520,50 @@ ApplicationWindow {
                 text: "Nodes: " + controller.node_count + "  Links: " + controller.link_count
             }
 
+            Label {
+                readonly property string remote: controller.get_pipewire_remote()
+                visible: remote !== ""
+                text: "Instance: " + remote
+                opacity: 0.7
+            }
+
+            Label {
+                id: sessionLabel
+                property string current: ""
+                function reload() {
+                    try {
+                        current = JSON.parse(controller.get_sessions_json()).current || ""
+                    } catch(e) {
+                        current = ""
+                    }
+                }
+                Component.onCompleted: reload()
+                visible: current !== ""
+                text: "Session: " + current
+                opacity: 0.7
+            }
+
+            Label {
+                visible: controller.latest_version !== ""
+                text: "<a href=\"#\">Update available: " + controller.latest_version + "</a>"
+                textFormat: Text.RichText
+                onLinkActivated: aboutDialog.open()
+            }
+
+            Label {
+                visible: controller.pipewire_status !== ""
+                text: controller.pipewire_status
+                color: Theme.statusBypassed
+                font.bold: true
+            }
+
+            Label {
+                visible: controller.rules_suspended
+                text: "Rules paused: graph event storm"
+                color: Theme.statusBypassed
+                font.bold: true
+            }
+
             Item {
                 Layout.fillWidth: true
             }
@@ -357,10 +681,135 @@ ApplicationWindow {
 
     GraphView {
         id: graphView
-        anchors.fill: parent
+        anchors.left: parent.left
+        anchors.top: parent.top
+        anchors.bottom: parent.bottom
+        anchors.right: remoteView.visible ? remoteView.left
+                     : compareView.visible ? compareView.left
+                     : pluginDock.visible ? pluginDock.left : parent.right
         controller: controller
+        crossHighlightName: compareView.visible ? compareView.crossHighlightName : ""
         onOpenPluginBrowser: pluginBrowser.open()
+        onOpenPluginReplace: (stableId, name) => pluginBrowser.openForReplace(stableId, name)
         onOpenPluginParams: nodeId => pluginParamsDialog.openForNode(nodeId)
+        onNodeFocused: name => {
+            compareView.crossHighlightName = name;
+        }
+    }
+
+    // Shown when the PipeWire thread stops ticking; the graph is frozen
+    // until it recovers or is restarted.
+    Rectangle {
+        id: stallBanner
+        anchors.left: parent.left
+        anchors.right: parent.right
+        anchors.top: parent.top
+        height: stallRow.implicitHeight + 16
+        z: 10
+        visible: controller.pipewire_stalled
+        color: Theme.deleteBg
+        border.color: Theme.statusError
+
+        RowLayout {
+            id: stallRow
+            anchors.fill: parent
+            anchors.margins: 8
+            spacing: 12
+
+            Label {
+                text: "The PipeWire connection stopped responding, so the graph is no longer updated. "
+                      + "It may recover on its own if a plugin is still loading."
+                color: Theme.statusError
+                font.bold: true
+                wrapMode: Text.WordWrap
+                Layout.fillWidth: true
+            }
+            Button {
+                text: "Restart Connection"
+                ToolTip.visible: hovered
+                ToolTip.text: "Start a new PipeWire connection and re-create running plugins with their parameters and links"
+                onClicked: controller.restart_pipewire()
+            }
+        }
+    }
+
+    // Links made with Alt held, counting down to when they revert.
+    property var temporaryLinks: []
+
+    function refreshTemporaryLinks() {
+        temporaryLinks = JSON.parse(controller.get_temporary_links_json())
+    }
+
+    Timer {
+        interval: 1000
+        repeat: true
+        running: temporaryLinks.length > 0
+        onTriggered: refreshTemporaryLinks()
+    }
+
+    Rectangle {
+        id: temporaryLinksBar
+        anchors.left: graphView.left
+        anchors.right: graphView.right
+        anchors.bottom: parent.bottom
+        height: temporaryLinksColumn.implicitHeight + 16
+        z: 10
+        visible: temporaryLinks.length > 0
+        color: Theme.panelBg
+        border.color: Theme.border
+
+        ColumnLayout {
+            id: temporaryLinksColumn
+            anchors.fill: parent
+            anchors.margins: 8
+            spacing: 4
+
+            Repeater {
+                model: temporaryLinks
+                delegate: RowLayout {
+                    spacing: 12
+                    Layout.fillWidth: true
+
+                    Label {
+                        text: "Temporary: " + modelData.label + " (" + modelData.remaining_secs + " s)"
+                        elide: Text.ElideMiddle
+                        Layout.fillWidth: true
+                    }
+                    Button {
+                        text: "Revert Now"
+                        onClicked: controller.cancel_temporary_link(modelData.id)
+                    }
+                }
+            }
+        }
+    }
+
+    RemoteView {
+        id: remoteView
+        anchors.right: compareView.visible ? compareView.left
+                     : pluginDock.visible ? pluginDock.left : parent.right
+        anchors.top: parent.top
+        anchors.bottom: parent.bottom
+        controller: controller
+    }
+
+    CompareView {
+        id: compareView
+        anchors.right: pluginDock.visible ? pluginDock.left : parent.right
+        anchors.top: parent.top
+        anchors.bottom: parent.bottom
+        controller: controller
+        onNodeFocused: name => {
+            compareView.crossHighlightName = name;
+        }
+    }
+
+    PluginDock {
+        id: pluginDock
+        anchors.right: parent.right
+        anchors.top: parent.top
+        anchors.bottom: parent.bottom
+        controller: controller
     }
 
     PluginBrowser {
@@ -388,11 +837,57 @@ ApplicationWindow {
         controller: controller
     }
 
+    MidiClock {
+        id: midiClockDialog
+        controller: controller
+    }
+
+    CommandLog {
+        id: commandLogDialog
+        controller: controller
+    }
+
+    Hooks {
+        id: hooksDialog
+        controller: controller
+    }
+
+    Import {
+        id: importDialog
+        controller: controller
+    }
+
+    Dialog {
+        id: configExportDialog
+        title: "Configuration Exported"
+        standardButtons: Dialog.Ok
+        anchors.centerIn: parent
+        modal: true
+        width: 420
+
+        property string path: ""
+
+        contentItem: Text {
+            text: "The configuration was written to:\n" + configExportDialog.path +
+                  "\n\nCopy it to the other machine and open it with File > Import there. " +
+                  "Devices are matched to that machine's by name, or by description."
+            wrapMode: Text.WrapAnywhere
+            color: Theme.textPrimary
+            padding: 12
+        }
+    }
+
+    Sessions {
+        id: sessionsDialog
+        controller: controller
+        onSessionSaved: sessionLabel.reload()
+    }
+
     Preferences {
         id: preferencesDialog
         controller: controller
         onPollIntervalChanged: intervalMs => {
-            pollTimer.interval = intervalMs;
+            pollTimer.interval = controller.get_poll_interval_ms();
         }
     }
 
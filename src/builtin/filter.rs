@@ -0,0 +1,352 @@
+//! PipeWire filter node that wraps a builtin processing instance.
+//!
+//! Follows `clap::filter::ClapFilterNode`, minus the MIDI ports: builtin
+//! nodes are audio-only and have no MIDI-mappable state of their own.
+
+use std::cell::RefCell;
+use std::ffi::CString;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use pipewire::core::CoreRc;
+
+use super::host::BuiltinPluginInstance;
+use crate::plugin::cpu_stats::{global_cpu_tracker, PluginTimingSlot};
+use crate::plugin::meters::{self, global_meters, MeterProducer};
+use crate::plugin::types::PluginInstanceId;
+
+pub struct BuiltinFilterNode {
+    filter: *mut pipewire::sys::pw_filter,
+    _hook: Box<libspa::sys::spa_hook>,
+    _events: Box<pipewire::sys::pw_filter_events>,
+    _user_data: *mut FilterData,
+    _core: CoreRc,
+    pub instance_id: PluginInstanceId,
+    pub display_name: String,
+}
+
+#[repr(C)]
+struct PortData {
+    index: u32,
+}
+
+struct FilterData {
+    instance_ptr: *mut BuiltinPluginInstance,
+    filter: *mut pipewire::sys::pw_filter,
+    instance_id: PluginInstanceId,
+    display_name: String,
+    event_tx: std::sync::mpsc::Sender<crate::pipewire::PwEvent>,
+    node_id_sent: bool,
+    shutting_down: AtomicBool,
+    input_port_ptrs: Vec<*mut std::ffi::c_void>,
+    output_port_ptrs: Vec<*mut std::ffi::c_void>,
+    cpu_slot: Arc<PluginTimingSlot>,
+    meter: MeterProducer,
+}
+
+unsafe impl Send for FilterData {}
+
+impl BuiltinFilterNode {
+    pub fn new(
+        core: &CoreRc,
+        instance_id: PluginInstanceId,
+        display_name: &str,
+        plugin_instance: Rc<RefCell<BuiltinPluginInstance>>,
+        event_tx: std::sync::mpsc::Sender<crate::pipewire::PwEvent>,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let c_name = CString::new(display_name)
+            .unwrap_or_else(|_| CString::new("ZestBay Builtin").unwrap());
+        let instance_id_str = instance_id.to_string();
+
+        let props = unsafe {
+            let p = pipewire::sys::pw_properties_new(
+                c_str(b"media.type\0"),
+                c_str(b"Audio\0"),
+                c_str(b"media.category\0"),
+                c_str(b"Filter\0"),
+                c_str(b"media.role\0"),
+                c_str(b"DSP\0"),
+                c_str(b"node.virtual\0"),
+                c_str(b"true\0"),
+                c_str(b"node.passive\0"),
+                c_str(b"true\0"),
+                std::ptr::null::<std::os::raw::c_char>(),
+            );
+            let key = CString::new("node.name").unwrap();
+            let val = CString::new(display_name).unwrap_or_else(|_| c_name.clone());
+            pipewire::sys::pw_properties_set(p, key.as_ptr(), val.as_ptr());
+            let key = CString::new("node.description").unwrap();
+            pipewire::sys::pw_properties_set(p, key.as_ptr(), val.as_ptr());
+            let key = CString::new("zestbay.plugin.instance_id").unwrap();
+            let val = CString::new(instance_id_str.as_str()).unwrap();
+            pipewire::sys::pw_properties_set(p, key.as_ptr(), val.as_ptr());
+            p
+        };
+
+        let core_raw = core.as_raw_ptr();
+        let filter = unsafe { pipewire::sys::pw_filter_new(core_raw, c_name.as_ptr(), props) };
+        if filter.is_null() {
+            return Err("Failed to create pw_filter".into());
+        }
+
+        let (audio_inputs, audio_outputs, port_names) = {
+            let inst = plugin_instance.borrow();
+            (inst.audio_inputs, inst.audio_outputs, inst.port_names.clone())
+        };
+        let instance_ptr = plugin_instance.as_ptr();
+        let cpu_slot = global_cpu_tracker().register(instance_id, display_name.to_string());
+
+        let user_data = Box::into_raw(Box::new(FilterData {
+            instance_ptr,
+            filter,
+            instance_id,
+            display_name: display_name.to_string(),
+            event_tx,
+            node_id_sent: false,
+            shutting_down: AtomicBool::new(false),
+            input_port_ptrs: Vec::with_capacity(audio_inputs),
+            output_port_ptrs: Vec::with_capacity(audio_outputs),
+            cpu_slot,
+            meter: global_meters().register(instance_id),
+        }));
+
+        let events = Box::new(pipewire::sys::pw_filter_events {
+            version: pipewire::sys::PW_VERSION_FILTER_EVENTS,
+            destroy: None,
+            state_changed: Some(on_state_changed),
+            io_changed: None,
+            param_changed: None,
+            add_buffer: None,
+            remove_buffer: None,
+            process: Some(on_process),
+            drained: None,
+            command: None,
+        });
+
+        let mut hook = Box::new(unsafe { std::mem::zeroed::<libspa::sys::spa_hook>() });
+        unsafe {
+            pipewire::sys::pw_filter_add_listener(
+                filter,
+                hook.as_mut() as *mut libspa::sys::spa_hook,
+                events.as_ref() as *const pipewire::sys::pw_filter_events,
+                user_data as *mut std::ffi::c_void,
+            );
+        }
+
+        for (i, name) in port_names.iter().enumerate() {
+            let is_input = i < audio_inputs;
+            let port_name = CString::new(name.as_str()).unwrap();
+            let port_props = unsafe {
+                pipewire::sys::pw_properties_new(
+                    c_str(b"port.name\0"),
+                    port_name.as_ptr(),
+                    c_str(b"format.dsp\0"),
+                    c_str(b"32 bit float mono audio\0"),
+                    std::ptr::null::<std::os::raw::c_char>(),
+                )
+            };
+            let direction = if is_input {
+                libspa::sys::SPA_DIRECTION_INPUT
+            } else {
+                libspa::sys::SPA_DIRECTION_OUTPUT
+            };
+            let port_data = unsafe {
+                pipewire::sys::pw_filter_add_port(
+                    filter,
+                    direction,
+                    pipewire::sys::pw_filter_port_flags_PW_FILTER_PORT_FLAG_MAP_BUFFERS,
+                    std::mem::size_of::<PortData>(),
+                    port_props,
+                    std::ptr::null_mut(),
+                    0,
+                )
+            };
+            if !port_data.is_null() {
+                let pd = port_data as *mut PortData;
+                unsafe {
+                    (*pd).index = i as u32;
+                    if is_input {
+                        (*user_data).input_port_ptrs.push(port_data);
+                    } else {
+                        (*user_data).output_port_ptrs.push(port_data);
+                    }
+                }
+            }
+        }
+
+        let flags = pipewire::sys::pw_filter_flags_PW_FILTER_FLAG_RT_PROCESS;
+        let ret =
+            unsafe { pipewire::sys::pw_filter_connect(filter, flags, std::ptr::null_mut(), 0) };
+        if ret < 0 {
+            unsafe {
+                pipewire::sys::pw_filter_destroy(filter);
+                drop(Box::from_raw(user_data));
+            }
+            return Err(format!("Failed to connect pw_filter: error {}", ret).into());
+        }
+
+        log::info!(
+            "Builtin filter node created: {} (instance {}, {} in / {} out)",
+            display_name,
+            instance_id,
+            audio_inputs,
+            audio_outputs,
+        );
+
+        Ok(Self {
+            filter,
+            _hook: hook,
+            _events: events,
+            _user_data: user_data,
+            _core: core.clone(),
+            instance_id,
+            display_name: display_name.to_string(),
+        })
+    }
+
+    /// Ask PipeWire for a quantum through `node.latency` (e.g. `"64/48000"`),
+    /// or withdraw the request with `None`.
+    pub fn set_latency(&self, latency: Option<&str>) {
+        if !self.filter.is_null() {
+            unsafe { crate::plugin::node_latency::request(self.filter, latency) }
+        }
+    }
+
+    pub fn node_id(&self) -> u32 {
+        if self.filter.is_null() {
+            return 0;
+        }
+        unsafe { pipewire::sys::pw_filter_get_node_id(self.filter) }
+    }
+}
+
+impl Drop for BuiltinFilterNode {
+    fn drop(&mut self) {
+        global_cpu_tracker().unregister(self.instance_id);
+        global_meters().unregister(self.instance_id);
+
+        if !self._user_data.is_null() {
+            unsafe {
+                (*self._user_data)
+                    .shutting_down
+                    .store(true, Ordering::SeqCst);
+            }
+        }
+
+        if !self.filter.is_null() {
+            unsafe {
+                pipewire::sys::pw_filter_destroy(self.filter);
+            }
+            self.filter = std::ptr::null_mut();
+        }
+
+        if !self._user_data.is_null() {
+            unsafe {
+                drop(Box::from_raw(self._user_data));
+            }
+            self._user_data = std::ptr::null_mut();
+        }
+    }
+}
+
+#[inline]
+fn c_str(bytes: &[u8]) -> *const std::os::raw::c_char {
+    bytes.as_ptr() as *const std::os::raw::c_char
+}
+
+unsafe extern "C" fn on_state_changed(
+    data: *mut std::ffi::c_void,
+    _old: pipewire::sys::pw_filter_state,
+    state: pipewire::sys::pw_filter_state,
+    _error: *const std::os::raw::c_char,
+) {
+    if state == pipewire::sys::pw_filter_state_PW_FILTER_STATE_PAUSED
+        || state == pipewire::sys::pw_filter_state_PW_FILTER_STATE_STREAMING
+    {
+        let fd = unsafe { &mut *(data as *mut FilterData) };
+        if !fd.node_id_sent && !fd.filter.is_null() {
+            let node_id = unsafe { pipewire::sys::pw_filter_get_node_id(fd.filter) };
+            if node_id != 0 && node_id != u32::MAX {
+                log::info!(
+                    "Builtin filter node ID resolved: instance {} -> pw_node {}",
+                    fd.instance_id,
+                    node_id
+                );
+                let _ = fd.event_tx.send(crate::pipewire::PwEvent::Plugin(
+                    crate::pipewire::PluginEvent::PluginAdded {
+                        instance_id: fd.instance_id,
+                        pw_node_id: node_id,
+                        display_name: fd.display_name.clone(),
+                    },
+                ));
+                fd.node_id_sent = true;
+            }
+        }
+    }
+}
+
+unsafe extern "C" fn on_process(
+    data: *mut std::ffi::c_void,
+    position: *mut libspa::sys::spa_io_position,
+) {
+    unsafe {
+        let fd = &mut *(data as *mut FilterData);
+
+        if fd.shutting_down.load(Ordering::Acquire) {
+            return;
+        }
+
+        let (n_samples, rate) = if !position.is_null() {
+            (
+                (*position).clock.duration as u32,
+                (*position).clock.rate.denom as u32,
+            )
+        } else {
+            return;
+        };
+
+        if n_samples == 0 || n_samples > 8192 {
+            return;
+        }
+
+        let inst = &mut *fd.instance_ptr;
+
+        static SILENCE: [f32; 8192] = [0.0; 8192];
+        let mut input_bufs: [&[f32]; 8] = [&SILENCE[..0]; 8];
+        let n_in = fd.input_port_ptrs.len().min(input_bufs.len());
+        for (slot, port_ptr) in input_bufs.iter_mut().zip(&fd.input_port_ptrs) {
+            let buf = pipewire::sys::pw_filter_get_dsp_buffer(*port_ptr, n_samples);
+            *slot = if buf.is_null() {
+                &SILENCE[..n_samples as usize]
+            } else {
+                std::slice::from_raw_parts(buf as *const f32, n_samples as usize)
+            };
+        }
+
+        static mut SCRATCH: [f32; 8192 * 8] = [0.0; 8192 * 8];
+        let scratch_base = &raw mut SCRATCH;
+        let mut output_bufs: Vec<&mut [f32]> = Vec::with_capacity(fd.output_port_ptrs.len());
+        for (i, port_ptr) in fd.output_port_ptrs.iter().enumerate() {
+            let buf = pipewire::sys::pw_filter_get_dsp_buffer(*port_ptr, n_samples);
+            if !buf.is_null() {
+                output_bufs.push(std::slice::from_raw_parts_mut(
+                    buf as *mut f32,
+                    n_samples as usize,
+                ));
+            } else {
+                let ns = n_samples as usize;
+                output_bufs.push(std::slice::from_raw_parts_mut(
+                    (*scratch_base).as_mut_ptr().add((i % 8) * 8192),
+                    ns,
+                ));
+            }
+        }
+
+        let t0 = std::time::Instant::now();
+        inst.process(&input_bufs[..n_in], &mut output_bufs, n_samples as usize);
+        let elapsed = t0.elapsed().as_nanos() as u64;
+        fd.cpu_slot.record(elapsed, 0, n_samples, rate);
+        meters::record(&mut fd.meter, output_bufs.iter().map(|b| &**b));
+    }
+}
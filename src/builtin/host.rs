@@ -0,0 +1,382 @@
+use crate::plugin::types::{
+    AtomicF32, ParamDoc, ParameterValue, PluginCategory, PluginFormat, PluginInfo, PluginPortInfo,
+    PluginPortType,
+};
+
+/// Chain-level dry/wet mixer with output trim.
+pub const CHAIN_MIX_URI: &str = "urn:zestbay:chain-mix";
+
+/// Chain mixer port indices. Audio ports come first, as in LV2, so control
+/// ports keep stable indices for persistence and MIDI mapping.
+pub const CHAIN_MIX_DRY: [usize; 2] = [0, 1];
+pub const CHAIN_MIX_WET: [usize; 2] = [2, 3];
+pub const CHAIN_MIX_OUT: [usize; 2] = [4, 5];
+pub const CHAIN_MIX_PORT_MIX: usize = 6;
+pub const CHAIN_MIX_PORT_GAIN: usize = 7;
+
+/// Two stereo inputs to one stereo output, switched by a parameter: for
+/// comparing two processing chains or sources without re-patching.
+pub const AB_SWITCH_URI: &str = "urn:zestbay:ab-switch";
+
+/// A/B switch port indices, laid out like the chain mixer's.
+pub const AB_SWITCH_A: [usize; 2] = [0, 1];
+pub const AB_SWITCH_B: [usize; 2] = [2, 3];
+pub const AB_SWITCH_OUT: [usize; 2] = [4, 5];
+pub const AB_SWITCH_PORT_SOURCE: usize = 6;
+
+/// Parameter smoothing time, to keep automation and slider moves click-free.
+const SMOOTHING_SECONDS: f32 = 0.01;
+
+fn audio_port(index: usize, symbol: &str, name: &str, port_type: PluginPortType) -> PluginPortInfo {
+    PluginPortInfo {
+        index,
+        symbol: symbol.to_string(),
+        name: name.to_string(),
+        port_type,
+        default_value: 0.0,
+        min_value: 0.0,
+        max_value: 0.0,
+        is_toggle: false,
+        is_sidechain: false,
+        reports_latency: false,
+        doc: ParamDoc::default(),
+    }
+}
+
+fn chain_mix_info() -> PluginInfo {
+    let ports = vec![
+        audio_port(CHAIN_MIX_DRY[0], "dry_1", "Dry L", PluginPortType::AudioInput),
+        audio_port(CHAIN_MIX_DRY[1], "dry_2", "Dry R", PluginPortType::AudioInput),
+        audio_port(CHAIN_MIX_WET[0], "wet_1", "Wet L", PluginPortType::AudioInput),
+        audio_port(CHAIN_MIX_WET[1], "wet_2", "Wet R", PluginPortType::AudioInput),
+        audio_port(CHAIN_MIX_OUT[0], "out_1", "Out L", PluginPortType::AudioOutput),
+        audio_port(CHAIN_MIX_OUT[1], "out_2", "Out R", PluginPortType::AudioOutput),
+        PluginPortInfo {
+            index: CHAIN_MIX_PORT_MIX,
+            symbol: "mix".to_string(),
+            name: "Dry/Wet".to_string(),
+            port_type: PluginPortType::ControlInput,
+            default_value: 1.0,
+            min_value: 0.0,
+            max_value: 1.0,
+            is_toggle: false,
+            is_sidechain: false,
+            reports_latency: false,
+            doc: ParamDoc::default(),
+        },
+        PluginPortInfo {
+            index: CHAIN_MIX_PORT_GAIN,
+            symbol: "gain_db".to_string(),
+            name: "Output Gain (dB)".to_string(),
+            port_type: PluginPortType::ControlInput,
+            default_value: 0.0,
+            min_value: -24.0,
+            max_value: 12.0,
+            is_toggle: false,
+            is_sidechain: false,
+            reports_latency: false,
+            doc: ParamDoc::default(),
+        },
+    ];
+
+    PluginInfo {
+        uri: CHAIN_MIX_URI.to_string(),
+        name: "Chain Mix".to_string(),
+        format: PluginFormat::Builtin,
+        category: PluginCategory::Mixer,
+        author: Some("ZestBay".to_string()),
+        ports,
+        audio_inputs: 4,
+        audio_outputs: 2,
+        control_inputs: 2,
+        control_outputs: 0,
+        required_features: Vec::new(),
+        compatible: true,
+        has_ui: false,
+        library_path: String::new(),
+        version: None,
+        modified: None,
+        unsupported_ports: Vec::new(),
+    }
+}
+
+fn ab_switch_info() -> PluginInfo {
+    let ports = vec![
+        audio_port(AB_SWITCH_A[0], "a_1", "A L", PluginPortType::AudioInput),
+        audio_port(AB_SWITCH_A[1], "a_2", "A R", PluginPortType::AudioInput),
+        audio_port(AB_SWITCH_B[0], "b_1", "B L", PluginPortType::AudioInput),
+        audio_port(AB_SWITCH_B[1], "b_2", "B R", PluginPortType::AudioInput),
+        audio_port(AB_SWITCH_OUT[0], "out_1", "Out L", PluginPortType::AudioOutput),
+        audio_port(AB_SWITCH_OUT[1], "out_2", "Out R", PluginPortType::AudioOutput),
+        PluginPortInfo {
+            index: AB_SWITCH_PORT_SOURCE,
+            symbol: "source".to_string(),
+            name: "B Selected".to_string(),
+            port_type: PluginPortType::ControlInput,
+            default_value: 0.0,
+            min_value: 0.0,
+            max_value: 1.0,
+            is_toggle: true,
+            is_sidechain: false,
+            reports_latency: false,
+            doc: ParamDoc::default(),
+        },
+    ];
+
+    PluginInfo {
+        uri: AB_SWITCH_URI.to_string(),
+        name: "A/B Switch".to_string(),
+        format: PluginFormat::Builtin,
+        category: PluginCategory::Mixer,
+        author: Some("ZestBay".to_string()),
+        ports,
+        audio_inputs: 4,
+        audio_outputs: 2,
+        control_inputs: 1,
+        control_outputs: 0,
+        required_features: Vec::new(),
+        compatible: true,
+        has_ui: false,
+        library_path: String::new(),
+        version: None,
+        modified: None,
+        unsupported_ports: Vec::new(),
+    }
+}
+
+/// Catalog entries for every builtin plugin.
+pub fn available_plugins() -> Vec<PluginInfo> {
+    vec![chain_mix_info(), ab_switch_info()]
+}
+
+struct BuiltinParam {
+    port_index: usize,
+    symbol: String,
+    name: String,
+    min: f32,
+    max: f32,
+    default: f32,
+    is_toggle: bool,
+    /// Written from the PipeWire main loop, read by the RT callback.
+    value: AtomicF32,
+}
+
+enum BuiltinDsp {
+    ChainMix { mix: f32, gain: f32 },
+    /// `position` runs from 0 (A) to 1 (B); it is smoothed like the
+    /// parameters, so a switch is a crossfade of a few milliseconds.
+    AbSwitch { position: f32 },
+}
+
+pub struct BuiltinPluginInstance {
+    pub plugin_uri: String,
+    pub bypassed: bool,
+    pub audio_inputs: usize,
+    pub audio_outputs: usize,
+    /// PipeWire port names, inputs then outputs.
+    pub port_names: Vec<String>,
+    params: Vec<BuiltinParam>,
+    dsp: BuiltinDsp,
+    smoothing: f32,
+}
+
+impl BuiltinPluginInstance {
+    pub fn new(uri: &str, sample_rate: f64) -> Option<Self> {
+        let info = available_plugins().into_iter().find(|p| p.uri == uri)?;
+
+        let params = info
+            .ports
+            .iter()
+            .filter(|p| p.port_type == PluginPortType::ControlInput)
+            .map(|p| BuiltinParam {
+                port_index: p.index,
+                symbol: p.symbol.clone(),
+                name: p.name.clone(),
+                min: p.min_value,
+                max: p.max_value,
+                default: p.default_value,
+                is_toggle: p.is_toggle,
+                value: AtomicF32::new(p.default_value),
+            })
+            .collect();
+        let port_names = info
+            .ports
+            .iter()
+            .filter(|p| p.port_type.is_audio())
+            .map(|p| p.symbol.clone())
+            .collect();
+
+        let dsp = match uri {
+            CHAIN_MIX_URI => BuiltinDsp::ChainMix { mix: 1.0, gain: 1.0 },
+            AB_SWITCH_URI => BuiltinDsp::AbSwitch { position: 0.0 },
+            _ => return None,
+        };
+
+        Some(Self {
+            plugin_uri: uri.to_string(),
+            bypassed: false,
+            audio_inputs: info.audio_inputs,
+            audio_outputs: info.audio_outputs,
+            port_names,
+            params,
+            dsp,
+            smoothing: 1.0 - (-1.0 / (SMOOTHING_SECONDS * sample_rate as f32)).exp(),
+        })
+    }
+
+    pub fn set_parameter(&mut self, port_index: usize, value: f32) {
+        if let Some(p) = self.params.iter().find(|p| p.port_index == port_index) {
+            p.value.store(value.clamp(p.min, p.max));
+        }
+    }
+
+    pub fn get_parameters(&self) -> Vec<ParameterValue> {
+        self.params
+            .iter()
+            .map(|p| ParameterValue {
+                port_index: p.port_index,
+                symbol: p.symbol.clone(),
+                name: p.name.clone(),
+                value: p.value.load(),
+                min: p.min,
+                max: p.max,
+                default: p.default,
+                is_toggle: p.is_toggle,
+            })
+            .collect()
+    }
+
+    #[cfg(test)]
+    fn param(&self, port_index: usize) -> f32 {
+        param_value(&self.params, port_index)
+    }
+
+    /// RT-safe: no allocation, no locks.
+    pub fn process(&mut self, inputs: &[&[f32]], outputs: &mut [&mut [f32]], n_samples: usize) {
+        let coef = self.smoothing;
+
+        match &mut self.dsp {
+            BuiltinDsp::ChainMix { mix, gain } => {
+                let (target_mix, target_gain) = if self.bypassed {
+                    (1.0, 1.0)
+                } else {
+                    let db = param_value(&self.params, CHAIN_MIX_PORT_GAIN);
+                    let mix = param_value(&self.params, CHAIN_MIX_PORT_MIX);
+                    (mix, 10f32.powf(db / 20.0))
+                };
+                for i in 0..n_samples {
+                    *mix += (target_mix - *mix) * coef;
+                    *gain += (target_gain - *gain) * coef;
+                    for (ch, out) in outputs.iter_mut().enumerate().take(2) {
+                        let dry = inputs.get(CHAIN_MIX_DRY[ch]).map_or(0.0, |b| b[i]);
+                        let wet = inputs.get(CHAIN_MIX_WET[ch]).map_or(0.0, |b| b[i]);
+                        out[i] = (dry * (1.0 - *mix) + wet * *mix) * *gain;
+                    }
+                }
+            }
+            BuiltinDsp::AbSwitch { position } => {
+                // Bypassed, the switch passes A through.
+                let b_selected =
+                    !self.bypassed && param_value(&self.params, AB_SWITCH_PORT_SOURCE) >= 0.5;
+                let target = if b_selected { 1.0 } else { 0.0 };
+                for i in 0..n_samples {
+                    *position += (target - *position) * coef;
+                    for (ch, out) in outputs.iter_mut().enumerate().take(2) {
+                        let a = inputs.get(AB_SWITCH_A[ch]).map_or(0.0, |b| b[i]);
+                        let b = inputs.get(AB_SWITCH_B[ch]).map_or(0.0, |b| b[i]);
+                        out[i] = a * (1.0 - *position) + b * *position;
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn param_value(params: &[BuiltinParam], port_index: usize) -> f32 {
+    params
+        .iter()
+        .find(|p| p.port_index == port_index)
+        .map(|p| p.value.load())
+        .unwrap_or(0.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run_block(inst: &mut BuiltinPluginInstance, dry: f32, wet: f32, n: usize) -> Vec<f32> {
+        let dry_buf = vec![dry; n];
+        let wet_buf = vec![wet; n];
+        let inputs: Vec<&[f32]> = vec![&dry_buf, &dry_buf, &wet_buf, &wet_buf];
+        let mut out_l = vec![0.0; n];
+        let mut out_r = vec![0.0; n];
+        {
+            let mut outputs: Vec<&mut [f32]> = vec![&mut out_l, &mut out_r];
+            inst.process(&inputs, &mut outputs, n);
+        }
+        out_l
+    }
+
+    #[test]
+    fn test_chain_mix_defaults_to_fully_wet() {
+        let mut inst = BuiltinPluginInstance::new(CHAIN_MIX_URI, 48000.0).unwrap();
+        let out = run_block(&mut inst, 1.0, 0.25, 64);
+        assert!(out.iter().all(|s| (s - 0.25).abs() < 1e-6));
+    }
+
+    #[test]
+    fn test_chain_mix_settles_to_target() {
+        let mut inst = BuiltinPluginInstance::new(CHAIN_MIX_URI, 48000.0).unwrap();
+        inst.set_parameter(CHAIN_MIX_PORT_MIX, 0.5);
+        inst.set_parameter(CHAIN_MIX_PORT_GAIN, -6.0);
+        let out = run_block(&mut inst, 1.0, 0.0, 48000);
+        let expected = 0.5 * 10f32.powf(-6.0 / 20.0);
+        assert!((out.last().unwrap() - expected).abs() < 1e-3);
+        // Smoothed: the first sample has barely moved from fully wet.
+        assert!(out[0] < 0.05);
+    }
+
+    #[test]
+    fn test_chain_mix_parameters_are_clamped() {
+        let mut inst = BuiltinPluginInstance::new(CHAIN_MIX_URI, 48000.0).unwrap();
+        inst.set_parameter(CHAIN_MIX_PORT_GAIN, 100.0);
+        inst.set_parameter(CHAIN_MIX_PORT_MIX, -1.0);
+        assert_eq!(inst.param(CHAIN_MIX_PORT_GAIN), 12.0);
+        assert_eq!(inst.param(CHAIN_MIX_PORT_MIX), 0.0);
+    }
+
+    #[test]
+    fn test_ab_switch_crossfades_to_the_selected_input() {
+        let mut inst = BuiltinPluginInstance::new(AB_SWITCH_URI, 48000.0).unwrap();
+        // Inputs are A then B, as the chain mixer's are dry then wet.
+        let out = run_block(&mut inst, 1.0, 0.25, 64);
+        assert!(out.iter().all(|s| (s - 1.0).abs() < 1e-6));
+
+        inst.set_parameter(AB_SWITCH_PORT_SOURCE, 1.0);
+        let out = run_block(&mut inst, 1.0, 0.25, 4800);
+        assert!(out[0] > 0.95);
+        assert!((out.last().unwrap() - 0.25).abs() < 1e-3);
+
+        inst.bypassed = true;
+        let out = run_block(&mut inst, 1.0, 0.25, 4800);
+        assert!((out.last().unwrap() - 1.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_ab_switch_source_is_a_toggle() {
+        let inst = BuiltinPluginInstance::new(AB_SWITCH_URI, 48000.0).unwrap();
+        let params = inst.get_parameters();
+        assert_eq!(params.len(), 1);
+        assert_eq!(params[0].port_index, AB_SWITCH_PORT_SOURCE);
+        assert!(params[0].is_toggle);
+        assert_eq!(
+            inst.port_names,
+            ["a_1", "a_2", "b_1", "b_2", "out_1", "out_2"]
+        );
+    }
+
+    #[test]
+    fn test_unknown_uri_is_rejected() {
+        assert!(BuiltinPluginInstance::new("urn:zestbay:nope", 48000.0).is_none());
+    }
+}
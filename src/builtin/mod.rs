@@ -0,0 +1,11 @@
+//! Processing nodes implemented by ZestBay itself.
+//!
+//! Builtin plugins are hosted exactly like LV2/CLAP/VST3 instances: they
+//! appear in the plugin catalog, expose parameters, and are persisted with
+//! the session. They exist to support graph features (e.g. chain-level
+//! dry/wet) that need DSP ZestBay controls directly.
+
+pub mod filter;
+pub mod host;
+
+pub use host::{AB_SWITCH_URI, BuiltinPluginInstance, CHAIN_MIX_URI, available_plugins};
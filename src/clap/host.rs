@@ -56,6 +56,8 @@ pub struct ClapPluginInstance {
     /// Audio port layout (flattened to mono channels, matching the LV2 approach)
     pub audio_input_channels: usize,
     pub audio_output_channels: usize,
+    /// Input channels belonging to non-main (sidechain) audio ports
+    pub sidechain_inputs: Vec<usize>,
 
     /// Whether this plugin accepts MIDI input (has note input ports)
     pub has_midi_in: bool,
@@ -74,6 +76,14 @@ pub struct ClapPluginInstance {
     pub port_updates: SharedPortUpdates,
 
     pub bypassed: bool,
+    bypass: crate::plugin::bypass::BypassCrossfader,
+    /// Set while the instance rings out before it is removed
+    pub ringing_out: bool,
+    tail: crate::plugin::tail::PluginTail,
+    tail_finished: std::sync::atomic::AtomicBool,
+    /// Resampler around process() when the plugin is oversampled
+    oversampler: Option<crate::plugin::oversample::Oversampler>,
+    /// Graph sample rate; the plugin itself runs at this times the oversampling factor
     pub sample_rate: f64,
     activated: bool,
     processing: bool,
@@ -94,6 +104,7 @@ pub struct ClapParam {
     pub max: f64,
     pub default: f64,
     pub is_toggle: bool,
+    pub doc: ParamDoc,
 }
 
 unsafe impl Send for ClapPluginInstance {}
@@ -113,6 +124,7 @@ impl ClapPluginInstance {
         plugin_id: &str,
         plugin_info: &PluginInfo,
         sample_rate: f64,
+        oversampling: usize,
     ) -> Option<Self> { unsafe {
         // Register this thread (PW thread) as the CLAP "main thread" so that
         // thread_check.is_main_thread() returns true for param/GUI calls.
@@ -221,6 +233,7 @@ impl ClapPluginInstance {
         let mut output_port_infos = Vec::new();
         let mut audio_input_channels = 0usize;
         let mut audio_output_channels = 0usize;
+        let mut sidechain_inputs = Vec::new();
 
         if let Some(get_ext) = plugin_ref.get_extension {
             let ext = get_ext(
@@ -238,6 +251,13 @@ impl ClapPluginInstance {
                         if let Some(get_fn) = audio_ports.get {
                             if get_fn(plugin_ptr, idx, true, &mut info) {
                                 let ch = info.channel_count as usize;
+                                let is_main = info.flags
+                                    & clap_sys::ext::audio_ports::CLAP_AUDIO_PORT_IS_MAIN
+                                    != 0;
+                                if !is_main {
+                                    sidechain_inputs
+                                        .extend(audio_input_channels..audio_input_channels + ch);
+                                }
                                 audio_input_channels += ch;
                                 input_port_infos.push(ClapAudioPortDesc {
                                     channel_count: ch,
@@ -329,11 +349,17 @@ impl ClapPluginInstance {
                                     if let Some(get_val) = pe.get_value {
                                         get_val(plugin_ptr, info.id, &mut value);
                                     }
-                                    let is_toggle = info.flags
+                                    let is_stepped = info.flags
                                         & clap_sys::ext::params::CLAP_PARAM_IS_STEPPED
-                                        != 0
+                                        != 0;
+                                    let is_toggle = is_stepped
                                         && info.min_value == 0.0
                                         && info.max_value == 1.0;
+                                    let scale_points = if is_stepped {
+                                        clap_step_labels(pe, plugin_ptr, &info)
+                                    } else {
+                                        Vec::new()
+                                    };
                                     params.push(ClapParam {
                                         id: info.id,
                                         port_index: params.len(),
@@ -343,6 +369,10 @@ impl ClapPluginInstance {
                                         max: info.max_value,
                                         default: info.default_value,
                                         is_toggle,
+                                        doc: ParamDoc {
+                                            scale_points,
+                                            ..ParamDoc::default()
+                                        },
                                     });
                                 }
                             }
@@ -367,9 +397,10 @@ impl ClapPluginInstance {
         });
 
         // Activate
-        let max_frames: u32 = 8192;
+        let oversampling = oversampling.max(1);
+        let max_frames = (crate::plugin::oversample::MAX_BLOCK * oversampling) as u32;
         let activated = if let Some(activate) = plugin_ref.activate {
-            activate(plugin_ptr, sample_rate, 1, max_frames)
+            activate(plugin_ptr, sample_rate * oversampling as f64, 1, max_frames)
         } else {
             true
         };
@@ -389,6 +420,31 @@ impl ClapPluginInstance {
             true
         };
 
+        let mut bypass = crate::plugin::bypass::BypassCrossfader::new(
+            audio_input_channels.min(audio_output_channels),
+            sample_rate,
+        );
+        let tail = crate::plugin::tail::PluginTail::new(audio_input_channels, sample_rate);
+        let oversampler = crate::plugin::oversample::Oversampler::new(
+            oversampling,
+            audio_input_channels,
+            audio_output_channels,
+        );
+        let mut plugin_latency = 0;
+        if let Some(get_ext) = plugin_ref.get_extension {
+            let ext = get_ext(plugin_ptr, clap_sys::ext::latency::CLAP_EXT_LATENCY.as_ptr());
+            if !ext.is_null() {
+                let latency_ext = &*(ext as *const clap_sys::ext::latency::clap_plugin_latency);
+                if let Some(get_fn) = latency_ext.get {
+                    plugin_latency = get_fn(plugin_ptr) as usize;
+                }
+            }
+        }
+        bypass.set_latency(crate::plugin::oversample::compensated_latency(
+            oversampler.as_ref(),
+            plugin_latency,
+        ));
+
         let mut inst = Self {
             id: instance_id,
             plugin: plugin_ptr,
@@ -399,6 +455,7 @@ impl ClapPluginInstance {
             display_name: plugin_info.name.clone(),
             audio_input_channels,
             audio_output_channels,
+            sidechain_inputs,
             has_midi_in,
             has_midi_out,
             input_port_infos,
@@ -407,6 +464,11 @@ impl ClapPluginInstance {
             params_ext,
             port_updates,
             bypassed: false,
+            bypass,
+            ringing_out: false,
+            tail,
+            tail_finished: std::sync::atomic::AtomicBool::new(false),
+            oversampler,
             sample_rate,
             activated,
             processing,
@@ -433,6 +495,68 @@ impl ClapPluginInstance {
         outputs: &mut [&mut [f32]],
         sample_count: usize,
         midi_events: &[crate::midi::processing::RawMidiEvent],
+    ) { unsafe {
+        // While bypassed or being removed the plugin may run on muted
+        // inputs, so that its tail rings out.
+        let mut tail = std::mem::take(&mut self.tail);
+        tail.process(
+            self.bypassed || self.ringing_out,
+            inputs,
+            outputs,
+            sample_count,
+            midi_events,
+            |ins, outs, events| {
+                self.run_block(ins, outs, sample_count, events);
+            },
+        );
+        self.bypass.set_keep_wet(tail.is_ringing());
+        self.tail_finished
+            .store(tail.is_finished(), std::sync::atomic::Ordering::Relaxed);
+        self.tail = tail;
+
+        // Crossfade to the latency-aligned input while bypassed
+        self.bypass
+            .process(self.bypassed, inputs, outputs, sample_count);
+    }}
+
+    /// Whether the instance's inputs are muted and its tail has rung out.
+    pub fn tail_finished(&self) -> bool {
+        self.tail_finished
+            .load(std::sync::atomic::Ordering::Relaxed)
+    }
+
+    /// Run the plugin on one block, through the oversampler if there is
+    /// one.
+    unsafe fn run_block(
+        &mut self,
+        inputs: &[&[f32]],
+        outputs: &mut [&mut [f32]],
+        sample_count: usize,
+        midi_events: &[crate::midi::processing::RawMidiEvent],
+    ) { unsafe {
+        if let Some(mut os) = self.oversampler.take() {
+            let mut scaled = [crate::midi::processing::RawMidiEvent {
+                offset: 0,
+                data: [0; 3],
+                size: 0,
+            }; crate::midi::processing::MAX_MIDI_EVENTS];
+            let n_events = os.scale_midi_events(midi_events, &mut scaled);
+            os.process(inputs, outputs, sample_count, |ins, outs, n| {
+                self.run(ins, outs, n, &scaled[..n_events]);
+            });
+            self.oversampler = Some(os);
+        } else {
+            self.run(inputs, outputs, sample_count, midi_events);
+        }
+    }}
+
+    /// Run the plugin on buffers at its own (possibly oversampled) rate.
+    unsafe fn run(
+        &mut self,
+        inputs: &[&[f32]],
+        outputs: &mut [&mut [f32]],
+        sample_count: usize,
+        midi_events: &[crate::midi::processing::RawMidiEvent],
     ) { unsafe {
         // Read parameter changes from the shared port_updates
         // and build CLAP input events
@@ -577,20 +701,6 @@ impl ClapPluginInstance {
             process_fn(self.plugin, &process);
         }
 
-        // When bypassed, overwrite plugin audio output with passthrough
-        if self.bypassed {
-            for (i, output) in outputs.iter_mut().enumerate() {
-                if i < inputs.len() {
-                    let n = output.len().min(inputs[i].len()).min(sample_count);
-                    output[..n].copy_from_slice(&inputs[i][..n]);
-                } else {
-                    for s in output.iter_mut().take(sample_count) {
-                        *s = 0.0;
-                    }
-                }
-            }
-        }
-
         // Update port_updates with current param values
         for (i, p) in self.params.iter().enumerate() {
             if let Some(slot) = self.port_updates.control_inputs.get(i) {
@@ -631,6 +741,15 @@ impl ClapPluginInstance {
             .collect()
     }
 
+    /// Parameter docs worth reporting, by port index.
+    pub fn param_docs(&self) -> Vec<(usize, ParamDoc)> {
+        self.params
+            .iter()
+            .filter(|p| !p.doc.is_empty())
+            .map(|p| (p.port_index, p.doc.clone()))
+            .collect()
+    }
+
     pub fn get_info(&self, pw_node_id: Option<u32>) -> PluginInstanceInfo {
         PluginInstanceInfo {
             id: self.id,
@@ -643,6 +762,7 @@ impl ClapPluginInstance {
             active: true,
             bypassed: self.bypassed,
             lv2_state: Vec::new(),
+            oversampling: self.oversampler.as_ref().map_or(1, |os| os.factor() as u32),
         }
     }
 }
@@ -756,6 +876,34 @@ unsafe extern "C" fn host_request_callback(_host: *const clap_sys::host::clap_ho
     log::debug!("CLAP: host_request_callback");
 }
 
+/// The plugin's text for each step of a stepped parameter, when it has few
+/// enough steps to list.
+unsafe fn clap_step_labels(
+    pe: &clap_sys::ext::params::clap_plugin_params,
+    plugin: *const clap_sys::plugin::clap_plugin,
+    info: &clap_sys::ext::params::clap_param_info,
+) -> Vec<ScalePoint> {
+    let Some(value_to_text) = pe.value_to_text else {
+        return Vec::new();
+    };
+    let (min, max) = (info.min_value.round() as i64, info.max_value.round() as i64);
+    if max < min || (max - min) as usize >= MAX_LABELLED_STEPS {
+        return Vec::new();
+    }
+    (min..=max)
+        .filter_map(|step| {
+            let mut buf = [0 as std::ffi::c_char; 256];
+            let capacity = buf.len() as u32;
+            let ok =
+                unsafe { value_to_text(plugin, info.id, step as f64, buf.as_mut_ptr(), capacity) };
+            ok.then(|| ScalePoint {
+                value: step as f32,
+                label: read_clap_name(&buf),
+            })
+        })
+        .collect()
+}
+
 fn read_clap_name(name: &[std::ffi::c_char]) -> String {
     let bytes: Vec<u8> = name.iter().take_while(|&&c| c != 0).map(|&c| c as u8).collect();
     String::from_utf8(bytes).unwrap_or_else(|_| "?".to_string())
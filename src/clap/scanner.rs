@@ -240,6 +240,16 @@ fn scan_clap_file(path: &Path, plugins: &mut Vec<PluginInfo>) {
                 None
             };
 
+            let version = if desc.version.is_null() {
+                None
+            } else {
+                CStr::from_ptr(desc.version)
+                    .to_str()
+                    .ok()
+                    .filter(|v| !v.is_empty())
+                    .map(String::from)
+            };
+
             let features = parse_features(desc.features);
             let category = category_from_features(&features);
 
@@ -271,6 +281,9 @@ fn scan_clap_file(path: &Path, plugins: &mut Vec<PluginInfo>) {
                 compatible: true,
                 has_ui,
                 library_path: path_str.to_string(),
+                version,
+                modified: modified_secs(path_str),
+                unsupported_ports: Vec::new(),
             });
         }
 
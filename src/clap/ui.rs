@@ -324,6 +324,20 @@ unsafe fn open_embedded_x11_gui(
             return;
         }
 
+        // Scale override, before asking for the size. Plugins using logical
+        // pixels refuse it and follow the desktop scaling instead.
+        if let Some(scale) = crate::plugin::ui_scale::scale_for(instance_id)
+            && let Some(set_scale) = gui.set_scale
+        {
+            let accepted = set_scale(plugin_ptr, scale as f64);
+            log::info!(
+                "CLAP gui.set_scale({}) = {} (instance {})",
+                scale,
+                accepted,
+                instance_id
+            );
+        }
+
         // Query the preferred size
         let mut width: u32 = 800;
         let mut height: u32 = 600;
@@ -458,6 +472,12 @@ unsafe fn open_embedded_x11_gui(
         let _ = event_tx.send(crate::pipewire::PwEvent::Plugin(
             crate::pipewire::PluginEvent::PluginUiOpened { instance_id },
         ));
+        let _ = event_tx.send(crate::pipewire::PwEvent::Plugin(
+            crate::pipewire::PluginEvent::PluginUiWindow {
+                instance_id,
+                window_id: window as u64,
+            },
+        ));
     }
 }
 
@@ -0,0 +1,168 @@
+//! The control thread: work at control rate that should not run on the GUI
+//! thread.
+//!
+//! Config files are serialized and written here, and rule scans run here on
+//! a copy of the patchbay. The bridge sends requests and picks up scan
+//! results in `poll_events`, so a heavy graph or a slow disk never holds up
+//! a frame.
+//!
+//! Requests queue up while a job runs. Of the writes queued for one file
+//! only the last runs, and of queued scans only the last, so a burst of
+//! parameter changes costs one write.
+
+use std::collections::HashSet;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+
+use crate::patchbay::{GraphSnapshot, PatchbayManager};
+use crate::pipewire::{CommandOrigin, GraphState, PwCommand};
+
+/// How long quitting waits for queued writes.
+const FLUSH_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Serializes a config file and writes it.
+type WriteJob = Box<dyn FnOnce() + Send>;
+
+enum Request {
+    /// `file` names the config file the job writes; a later write to the
+    /// same file replaces a queued one.
+    Write { file: &'static str, job: WriteJob },
+    Scan {
+        patchbay: Box<PatchbayManager>,
+        graph: Arc<GraphState>,
+    },
+    /// Answered once everything queued before it is done.
+    Flush(Sender<()>),
+}
+
+/// A finished rule scan.
+pub struct ScanResult {
+    /// The copy the scan ran on, with its updated cooldowns, overrides and
+    /// target ids.
+    pub patchbay: Box<PatchbayManager>,
+    /// The graph state the scan was asked about, to tell a result for a
+    /// replaced graph.
+    pub state: Arc<GraphState>,
+    /// The copy of it the scan saw.
+    pub graph: GraphSnapshot,
+    pub commands: Vec<(PwCommand, CommandOrigin)>,
+}
+
+static REQUESTS: OnceLock<Sender<Request>> = OnceLock::new();
+
+/// Start the control thread. The receiver gets the results of
+/// [`scan`] requests.
+pub fn start() -> Result<Receiver<ScanResult>, String> {
+    let (request_tx, request_rx) = mpsc::channel();
+    let (result_tx, result_rx) = mpsc::channel();
+    std::thread::Builder::new()
+        .name("zestbay-control".into())
+        .spawn(move || run(request_rx, result_tx))
+        .map_err(|e| format!("Failed to spawn control thread: {}", e))?;
+    REQUESTS
+        .set(request_tx)
+        .map_err(|_| "Control thread already started".to_string())?;
+    Ok(result_rx)
+}
+
+/// Queue a config write. Before the thread is started it runs right away.
+pub fn write(file: &'static str, job: impl FnOnce() + Send + 'static) {
+    match REQUESTS.get() {
+        // Jobs that panic are caught, so the thread outlives every sender.
+        Some(tx) => {
+            let _ = tx.send(Request::Write {
+                file,
+                job: Box::new(job),
+            });
+        }
+        None => job(),
+    }
+}
+
+pub fn is_running() -> bool {
+    REQUESTS.get().is_some()
+}
+
+/// Queue a rule scan of `graph` on `patchbay`, a copy of the bridge's.
+pub fn scan(patchbay: PatchbayManager, graph: Arc<GraphState>) {
+    if let Some(tx) = REQUESTS.get() {
+        let _ = tx.send(Request::Scan {
+            patchbay: Box::new(patchbay),
+            graph,
+        });
+    }
+}
+
+/// Run a rule scan on the calling thread.
+pub fn scan_now(mut patchbay: Box<PatchbayManager>, graph: Arc<GraphState>) -> ScanResult {
+    let snapshot = GraphSnapshot::from_state(&graph);
+    let commands = patchbay.scan_with_origins(&snapshot);
+    ScanResult {
+        patchbay,
+        state: graph,
+        graph: snapshot,
+        commands,
+    }
+}
+
+/// Wait until the writes queued so far are on disk. Call before reading a
+/// config file back or replacing it directly, and before exiting.
+pub fn flush() {
+    let Some(tx) = REQUESTS.get() else {
+        return;
+    };
+    let (done_tx, done_rx) = mpsc::channel();
+    if tx.send(Request::Flush(done_tx)).is_ok() && done_rx.recv_timeout(FLUSH_TIMEOUT).is_err() {
+        log::warn!("Control thread did not finish its writes in time");
+    }
+}
+
+fn run(requests: Receiver<Request>, results: Sender<ScanResult>) {
+    while let Ok(first) = requests.recv() {
+        let mut batch = vec![first];
+        batch.extend(requests.try_iter());
+        for request in coalesce(batch) {
+            match request {
+                Request::Write { job, .. } => {
+                    if crate::crash_report::catch_unwind(job).is_err() {
+                        log::error!("A config write panicked");
+                    }
+                }
+                Request::Scan { patchbay, graph } => {
+                    match crate::crash_report::catch_unwind(|| scan_now(patchbay, graph)) {
+                        Ok(result) => {
+                            let _ = results.send(result);
+                        }
+                        Err(_) => log::error!("A rule scan panicked"),
+                    }
+                }
+                Request::Flush(done) => {
+                    let _ = done.send(());
+                }
+            }
+        }
+    }
+}
+
+/// Drop writes and scans that a later one in `batch` replaces. A flush is a
+/// barrier: nothing before it is dropped for something after it.
+fn coalesce(batch: Vec<Request>) -> Vec<Request> {
+    let mut written = HashSet::new();
+    let mut scanned = false;
+    let mut kept: Vec<Request> = batch
+        .into_iter()
+        .rev()
+        .filter(|request| match request {
+            Request::Write { file, .. } => written.insert(*file),
+            Request::Scan { .. } => !std::mem::replace(&mut scanned, true),
+            Request::Flush(_) => {
+                written.clear();
+                scanned = false;
+                true
+            }
+        })
+        .collect();
+    kept.reverse();
+    kept
+}
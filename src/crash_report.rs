@@ -0,0 +1,230 @@
+//! Writing a crash report when the process dies.
+//!
+//! A panic hook and a handler for fatal signals write a report to
+//! `~/.config/zestbay/crashes/` with a backtrace, a short summary of what was
+//! running and the last log lines. The next start offers the reports it has
+//! not shown yet. The format lives in [`zestbay_core::crash`].
+
+use std::cell::Cell;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use log::Log;
+use parking_lot::Mutex;
+use zestbay_core::crash::{self, CrashReport, LOG_LINES, LogRing};
+
+static LOG: OnceLock<Mutex<LogRing>> = OnceLock::new();
+static STATE: Mutex<Vec<(String, String)>> = parking_lot::const_mutex(Vec::new());
+/// Set by the first report, so a panic that ends in `abort()` is not
+/// reported twice.
+static CRASHED: AtomicBool = AtomicBool::new(false);
+
+thread_local! {
+    static CATCHING: Cell<u32> = const { Cell::new(0) };
+}
+
+const FATAL_SIGNALS: [libc::c_int; 5] = [
+    libc::SIGSEGV,
+    libc::SIGBUS,
+    libc::SIGILL,
+    libc::SIGFPE,
+    libc::SIGABRT,
+];
+
+/// Shared by normal and demo mode, unlike the rest of the config.
+pub fn reports_dir() -> PathBuf {
+    dirs::config_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("zestbay")
+        .join("crashes")
+}
+
+/// Forwards to env_logger and keeps the last [`LOG_LINES`] lines for a
+/// report.
+struct RingLogger {
+    inner: env_logger::Logger,
+}
+
+impl Log for RingLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        self.inner.enabled(metadata)
+    }
+
+    fn log(&self, record: &log::Record) {
+        if !self.inner.matches(record) {
+            return;
+        }
+        self.inner.log(record);
+        let line = format!(
+            "{} [{} {}] {}",
+            now_ms(),
+            record.level(),
+            record.target(),
+            record.args()
+        );
+        LOG.get_or_init(|| Mutex::new(LogRing::new(LOG_LINES)))
+            .lock()
+            .push(line);
+    }
+
+    fn flush(&self) {
+        self.inner.flush();
+    }
+}
+
+/// Set up env_logger (`RUST_LOG`, default `info`) with the lines also kept
+/// for crash reports.
+pub fn init_logging() {
+    let inner =
+        env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).build();
+    let max_level = inner.filter();
+    if log::set_boxed_logger(Box::new(RingLogger { inner })).is_ok() {
+        log::set_max_level(max_level);
+    }
+}
+
+/// Record a line of the state summary, replacing an earlier value for `key`.
+pub fn set_state(key: &str, value: impl Into<String>) {
+    let value = value.into();
+    let mut state = STATE.lock();
+    match state.iter_mut().find(|(k, _)| k == key) {
+        Some(entry) => entry.1 = value,
+        None => state.push((key.to_string(), value)),
+    }
+}
+
+/// Install the panic hook and the fatal signal handlers.
+pub fn install() {
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        if CATCHING.with(Cell::get) == 0 {
+            let message = info
+                .payload()
+                .downcast_ref::<&str>()
+                .map(|s| s.to_string())
+                .or_else(|| info.payload().downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "Box<dyn Any>".to_string());
+            let location = info
+                .location()
+                .map(|l| format!(" at {}:{}", l.file(), l.line()))
+                .unwrap_or_default();
+            write_report(format!("panicked{}: {}", location, message), true);
+        }
+        previous(info);
+    }));
+
+    for sig in FATAL_SIGNALS {
+        unsafe {
+            libc::signal(sig, on_fatal_signal as *const () as libc::sighandler_t);
+        }
+    }
+}
+
+/// `std::panic::catch_unwind`, for panics the caller handles: they are not
+/// crashes and get no report.
+pub fn catch_unwind<R>(f: impl FnOnce() -> R) -> std::thread::Result<R> {
+    CATCHING.with(|c| c.set(c.get() + 1));
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(f));
+    CATCHING.with(|c| c.set(c.get() - 1));
+    result
+}
+
+/// Best effort: allocating and taking a backtrace are not async-signal-safe,
+/// and a lock held by the crashing thread is skipped rather than waited on.
+/// The default action runs afterwards either way, so the worst case is a
+/// missing or partial report.
+extern "C" fn on_fatal_signal(sig: libc::c_int) {
+    let name = match sig {
+        libc::SIGSEGV => "SIGSEGV (segmentation fault)",
+        libc::SIGBUS => "SIGBUS (bus error)",
+        libc::SIGILL => "SIGILL (illegal instruction)",
+        libc::SIGFPE => "SIGFPE (arithmetic exception)",
+        libc::SIGABRT => "SIGABRT (abort)",
+        _ => "fatal signal",
+    };
+    write_report(name.to_string(), false);
+    unsafe {
+        libc::signal(sig, libc::SIG_DFL);
+        libc::raise(sig);
+    }
+}
+
+fn write_report(cause: String, blocking: bool) {
+    if CRASHED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    let state = if blocking {
+        Some(STATE.lock())
+    } else {
+        STATE.try_lock()
+    };
+    let log = LOG.get().and_then(|ring| {
+        if blocking {
+            Some(ring.lock())
+        } else {
+            ring.try_lock()
+        }
+    });
+    let report = CrashReport {
+        time_ms: now_ms(),
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        cause,
+        thread: std::thread::current()
+            .name()
+            .unwrap_or("unnamed")
+            .to_string(),
+        backtrace: std::backtrace::Backtrace::force_capture().to_string(),
+        state: state.map(|s| s.clone()).unwrap_or_default(),
+        log: log
+            .map(|ring| ring.lines().map(str::to_string).collect())
+            .unwrap_or_default(),
+    };
+    let dir = reports_dir();
+    let path = dir.join(report.file_name());
+    let written =
+        std::fs::create_dir_all(&dir).and_then(|_| std::fs::write(&path, report.to_text()));
+    match written {
+        Ok(()) => eprintln!(
+            "ZestBay crashed. A report was written to {}",
+            path.display()
+        ),
+        Err(e) => eprintln!("ZestBay crashed and could not write a report: {}", e),
+    }
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+fn seen_path() -> PathBuf {
+    reports_dir().join("last_seen")
+}
+
+/// Reports written since the last call, oldest first. Each report is
+/// returned once.
+pub fn take_unseen() -> Vec<PathBuf> {
+    let seen_ms = std::fs::read_to_string(seen_path())
+        .ok()
+        .and_then(|s| s.trim().parse().ok())
+        .unwrap_or(0);
+    let reports = crash::unseen_reports(&reports_dir(), seen_ms);
+    if let Some(last) = reports.last().and_then(|p| crash::report_time(p))
+        && let Err(e) = std::fs::write(seen_path(), last.to_string())
+    {
+        log::warn!("Failed to record seen crash reports: {}", e);
+    }
+    reports
+}
+
+/// The cause line of the report at `path`.
+pub fn report_cause(path: &Path) -> String {
+    std::fs::read_to_string(path)
+        .ok()
+        .and_then(|text| crash::report_cause(&text).map(str::to_string))
+        .unwrap_or_default()
+}
@@ -0,0 +1,90 @@
+//! Running hook scripts. What a hook is and its payload live in
+//! [`zestbay_core::hooks`].
+//!
+//! Each run gets its own thread, so a slow script never holds up the UI;
+//! one that outlives its timeout is killed.
+
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use zestbay_core::hooks::{self, Hook, HookEvent};
+
+/// Run every enabled hook for `event` with `data` in its payload.
+pub fn fire(all: &[Hook], event: HookEvent, data: serde_json::Value) {
+    let mut matching = hooks::hooks_for(all, event).peekable();
+    if matching.peek().is_none() {
+        return;
+    }
+    let payload = hooks::payload(event, now_ms(), data);
+    for hook in matching {
+        run(hook.clone(), event, payload.clone());
+    }
+}
+
+/// Run one hook on a worker thread.
+pub fn run(hook: Hook, event: HookEvent, payload: String) {
+    let spawned = std::thread::Builder::new()
+        .name("hook".into())
+        .spawn(move || run_and_log(&hook, event, &payload));
+    if let Err(e) = spawned {
+        log::warn!("Could not start a hook thread: {}", e);
+    }
+}
+
+fn run_and_log(hook: &Hook, event: HookEvent, payload: &str) {
+    match run_blocking(hook, event, payload) {
+        Ok(()) => log::info!("Hook \"{}\" ran for {}", hook.label(), event.as_str()),
+        Err(e) => log::warn!("Hook \"{}\": {}", hook.label(), e),
+    }
+}
+
+fn run_blocking(hook: &Hook, event: HookEvent, payload: &str) -> Result<(), String> {
+    let mut child = Command::new("sh")
+        .arg("-c")
+        .arg(&hook.command)
+        .env("ZESTBAY_EVENT", event.as_str())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        // Script errors go to our stderr, next to the log.
+        .stderr(Stdio::inherit())
+        .spawn()
+        .map_err(|e| format!("could not start: {}", e))?;
+    // Written from its own thread: a script that never reads stdin would
+    // block the write once the pipe is full and the timeout below would
+    // never fire. Killing the script closes the pipe and ends the write; a
+    // script that ignores stdin closes it early, which is fine too.
+    if let Some(mut stdin) = child.stdin.take() {
+        let payload = payload.to_string();
+        let spawned = std::thread::Builder::new()
+            .name("hook-stdin".into())
+            .spawn(move || {
+                let _ = stdin.write_all(payload.as_bytes());
+            });
+        if let Err(e) = spawned {
+            log::warn!("Could not start a hook stdin thread: {}", e);
+        }
+    }
+
+    let deadline = Instant::now() + hook.timeout();
+    loop {
+        match child.try_wait() {
+            Ok(Some(status)) if status.success() => return Ok(()),
+            Ok(Some(status)) => return Err(format!("exited with {}", status)),
+            Ok(None) if Instant::now() >= deadline => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(format!("killed after {} s", hook.timeout().as_secs()));
+            }
+            Ok(None) => std::thread::sleep(Duration::from_millis(50)),
+            Err(e) => return Err(e.to_string()),
+        }
+    }
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
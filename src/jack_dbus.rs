@@ -0,0 +1,529 @@
+//! A JACK-compatible patchbay on the session bus.
+//!
+//! Implements the parts of jackdbus' `org.jackaudio.JackPatchbay` interface
+//! (and `JackControl.IsStarted`) that patchbay tools use, so JACK-era tools
+//! such as RaySession or `jack_connect`-style scripts can list and wire the
+//! nodes ZestBay sees. The application publishes a snapshot of the graph
+//! into [`JackDbusState`] and executes the link requests queued there; the
+//! D-Bus side only reads the snapshot and diffs it to emit change signals.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use zbus::object_server::SignalEmitter;
+
+const BUS_NAME: &str = "org.jackaudio.service";
+const OBJECT_PATH: &str = "/org/jackaudio/Controller";
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+// jack/types.h JackPortFlags
+const JACK_PORT_IS_INPUT: u32 = 0x1;
+const JACK_PORT_IS_OUTPUT: u32 = 0x2;
+const JACK_PORT_IS_PHYSICAL: u32 = 0x4;
+// jackdbus port types
+const JACKDBUS_PORT_TYPE_AUDIO: u32 = 0;
+const JACKDBUS_PORT_TYPE_MIDI: u32 = 1;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PatchbayPort {
+    pub id: u32,
+    pub name: String,
+    pub is_output: bool,
+    pub is_physical: bool,
+    pub is_midi: bool,
+}
+
+impl PatchbayPort {
+    fn flags(&self) -> u32 {
+        let direction = if self.is_output {
+            JACK_PORT_IS_OUTPUT
+        } else {
+            JACK_PORT_IS_INPUT
+        };
+        if self.is_physical {
+            direction | JACK_PORT_IS_PHYSICAL
+        } else {
+            direction
+        }
+    }
+
+    fn port_type(&self) -> u32 {
+        if self.is_midi {
+            JACKDBUS_PORT_TYPE_MIDI
+        } else {
+            JACKDBUS_PORT_TYPE_AUDIO
+        }
+    }
+}
+
+/// A node, presented as a JACK client.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PatchbayClient {
+    pub id: u32,
+    pub name: String,
+    pub ports: Vec<PatchbayPort>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PatchbayConnection {
+    pub id: u32,
+    pub output_port: u32,
+    pub input_port: u32,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PatchbayGraph {
+    pub clients: Vec<PatchbayClient>,
+    pub connections: Vec<PatchbayConnection>,
+}
+
+impl PatchbayGraph {
+    fn find_port(&self, port_id: u32) -> Option<(&PatchbayClient, &PatchbayPort)> {
+        self.clients
+            .iter()
+            .find_map(|c| c.ports.iter().find(|p| p.id == port_id).map(|p| (c, p)))
+    }
+
+    fn find_port_by_name(&self, client: &str, port: &str) -> Option<&PatchbayPort> {
+        self.clients
+            .iter()
+            .filter(|c| c.name == client)
+            .find_map(|c| c.ports.iter().find(|p| p.name == port))
+    }
+
+    /// The (output, input) port pair for two ports given in either order.
+    fn ordered_pair(&self, a: u32, b: u32) -> Option<(u32, u32)> {
+        let (_, pa) = self.find_port(a)?;
+        let (_, pb) = self.find_port(b)?;
+        match (pa.is_output, pb.is_output) {
+            (true, false) => Some((a, b)),
+            (false, true) => Some((b, a)),
+            _ => None,
+        }
+    }
+
+    fn connection_tuple(&self, c: &PatchbayConnection) -> Option<ConnectionTuple> {
+        let (oc, op) = self.find_port(c.output_port)?;
+        let (ic, ip) = self.find_port(c.input_port)?;
+        Some((
+            oc.id as u64,
+            oc.name.clone(),
+            op.id as u64,
+            op.name.clone(),
+            ic.id as u64,
+            ic.name.clone(),
+            ip.id as u64,
+            ip.name.clone(),
+            c.id as u64,
+        ))
+    }
+}
+
+/// A link change asked for over D-Bus, executed by the application.
+#[derive(Debug, Clone, Copy)]
+pub enum PatchbayRequest {
+    Connect {
+        output_port_id: u32,
+        input_port_id: u32,
+    },
+    Disconnect {
+        link_id: u32,
+    },
+}
+
+#[derive(Clone)]
+pub struct JackDbusState {
+    graph: Arc<Mutex<(u64, PatchbayGraph)>>,
+    pub requests: Arc<Mutex<Vec<PatchbayRequest>>>,
+}
+
+impl JackDbusState {
+    fn new() -> Self {
+        Self {
+            graph: Arc::new(Mutex::new((0, PatchbayGraph::default()))),
+            requests: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    /// Publish a new graph snapshot. The version only moves when the graph
+    /// actually changed, as JACK clients use it to skip refetching.
+    pub fn publish(&self, graph: PatchbayGraph) {
+        let mut current = self.graph.lock().unwrap_or_else(|e| e.into_inner());
+        if current.1 != graph {
+            current.0 += 1;
+            current.1 = graph;
+        }
+    }
+
+    fn snapshot(&self) -> (u64, PatchbayGraph) {
+        self.graph.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    fn request(&self, request: PatchbayRequest) {
+        self.requests
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(request);
+    }
+}
+
+type PortTuple = (u64, String, u32, u32);
+type ClientTuple = (u64, String, Vec<PortTuple>);
+type ConnectionTuple = (u64, String, u64, String, u64, String, u64, String, u64);
+
+fn failed(message: &str) -> zbus::fdo::Error {
+    zbus::fdo::Error::Failed(message.to_string())
+}
+
+struct JackPatchbay {
+    state: JackDbusState,
+}
+
+#[zbus::interface(name = "org.jackaudio.JackPatchbay")]
+impl JackPatchbay {
+    fn get_all_ports(&self) -> Vec<String> {
+        let (_, graph) = self.state.snapshot();
+        graph
+            .clients
+            .iter()
+            .flat_map(|c| {
+                c.ports
+                    .iter()
+                    .map(move |p| format!("{}:{}", c.name, p.name))
+            })
+            .collect()
+    }
+
+    fn get_graph(
+        &self,
+        _known_graph_version: u64,
+    ) -> (u64, Vec<ClientTuple>, Vec<ConnectionTuple>) {
+        let (version, graph) = self.state.snapshot();
+        let clients = graph
+            .clients
+            .iter()
+            .map(|c| {
+                let ports = c
+                    .ports
+                    .iter()
+                    .map(|p| (p.id as u64, p.name.clone(), p.flags(), p.port_type()))
+                    .collect();
+                (c.id as u64, c.name.clone(), ports)
+            })
+            .collect();
+        let connections = graph
+            .connections
+            .iter()
+            .filter_map(|c| graph.connection_tuple(c))
+            .collect();
+        (version, clients, connections)
+    }
+
+    fn connect_ports_by_name(
+        &self,
+        client1_name: &str,
+        port1_name: &str,
+        client2_name: &str,
+        port2_name: &str,
+    ) -> zbus::fdo::Result<()> {
+        let (_, graph) = self.state.snapshot();
+        let a = graph
+            .find_port_by_name(client1_name, port1_name)
+            .ok_or_else(|| failed("port not found"))?;
+        let b = graph
+            .find_port_by_name(client2_name, port2_name)
+            .ok_or_else(|| failed("port not found"))?;
+        self.connect_ports_by_id(a.id as u64, b.id as u64)
+    }
+
+    #[zbus(name = "ConnectPortsByID")]
+    fn connect_ports_by_id(&self, port1_id: u64, port2_id: u64) -> zbus::fdo::Result<()> {
+        let (_, graph) = self.state.snapshot();
+        let (output_port_id, input_port_id) = graph
+            .ordered_pair(port1_id as u32, port2_id as u32)
+            .ok_or_else(|| failed("ports cannot be connected"))?;
+        self.state.request(PatchbayRequest::Connect {
+            output_port_id,
+            input_port_id,
+        });
+        Ok(())
+    }
+
+    fn disconnect_ports_by_name(
+        &self,
+        client1_name: &str,
+        port1_name: &str,
+        client2_name: &str,
+        port2_name: &str,
+    ) -> zbus::fdo::Result<()> {
+        let (_, graph) = self.state.snapshot();
+        let a = graph
+            .find_port_by_name(client1_name, port1_name)
+            .ok_or_else(|| failed("port not found"))?;
+        let b = graph
+            .find_port_by_name(client2_name, port2_name)
+            .ok_or_else(|| failed("port not found"))?;
+        self.disconnect_ports_by_id(a.id as u64, b.id as u64)
+    }
+
+    #[zbus(name = "DisconnectPortsByID")]
+    fn disconnect_ports_by_id(&self, port1_id: u64, port2_id: u64) -> zbus::fdo::Result<()> {
+        let (_, graph) = self.state.snapshot();
+        let (output, input) = graph
+            .ordered_pair(port1_id as u32, port2_id as u32)
+            .ok_or_else(|| failed("ports are not connected"))?;
+        let link = graph
+            .connections
+            .iter()
+            .find(|c| c.output_port == output && c.input_port == input)
+            .ok_or_else(|| failed("ports are not connected"))?;
+        self.state
+            .request(PatchbayRequest::Disconnect { link_id: link.id });
+        Ok(())
+    }
+
+    #[zbus(name = "DisconnectPortsByConnectionID")]
+    fn disconnect_ports_by_connection_id(&self, connection_id: u64) -> zbus::fdo::Result<()> {
+        let (_, graph) = self.state.snapshot();
+        if !graph
+            .connections
+            .iter()
+            .any(|c| c.id as u64 == connection_id)
+        {
+            return Err(failed("no such connection"));
+        }
+        self.state.request(PatchbayRequest::Disconnect {
+            link_id: connection_id as u32,
+        });
+        Ok(())
+    }
+
+    #[zbus(name = "GetClientPID")]
+    fn get_client_pid(&self, _client_id: u64) -> i64 {
+        0
+    }
+
+    #[zbus(signal)]
+    async fn graph_changed(emitter: &SignalEmitter<'_>, new_graph_version: u64)
+    -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    async fn client_appeared(
+        emitter: &SignalEmitter<'_>,
+        new_graph_version: u64,
+        client_id: u64,
+        client_name: &str,
+    ) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    async fn client_disappeared(
+        emitter: &SignalEmitter<'_>,
+        new_graph_version: u64,
+        client_id: u64,
+        client_name: &str,
+    ) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    #[allow(clippy::too_many_arguments)]
+    async fn port_appeared(
+        emitter: &SignalEmitter<'_>,
+        new_graph_version: u64,
+        client_id: u64,
+        client_name: &str,
+        port_id: u64,
+        port_name: &str,
+        port_flags: u32,
+        port_type: u32,
+    ) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    async fn port_disappeared(
+        emitter: &SignalEmitter<'_>,
+        new_graph_version: u64,
+        client_id: u64,
+        client_name: &str,
+        port_id: u64,
+        port_name: &str,
+    ) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    #[allow(clippy::too_many_arguments)]
+    async fn ports_connected(
+        emitter: &SignalEmitter<'_>,
+        new_graph_version: u64,
+        client1_id: u64,
+        client1_name: &str,
+        port1_id: u64,
+        port1_name: &str,
+        client2_id: u64,
+        client2_name: &str,
+        port2_id: u64,
+        port2_name: &str,
+        connection_id: u64,
+    ) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    #[allow(clippy::too_many_arguments)]
+    async fn ports_disconnected(
+        emitter: &SignalEmitter<'_>,
+        new_graph_version: u64,
+        client1_id: u64,
+        client1_name: &str,
+        port1_id: u64,
+        port1_name: &str,
+        client2_id: u64,
+        client2_name: &str,
+        port2_id: u64,
+        port2_name: &str,
+        connection_id: u64,
+    ) -> zbus::Result<()>;
+}
+
+/// Tools check the server is running before they query the patchbay.
+struct JackControl;
+
+#[zbus::interface(name = "org.jackaudio.JackControl")]
+impl JackControl {
+    fn is_started(&self) -> bool {
+        true
+    }
+}
+
+/// Emit the signals that take a client from `old` to `new`.
+async fn emit_changes(
+    emitter: &SignalEmitter<'_>,
+    version: u64,
+    old: &PatchbayGraph,
+    new: &PatchbayGraph,
+) -> zbus::Result<()> {
+    let old_clients: HashMap<u32, &PatchbayClient> =
+        old.clients.iter().map(|c| (c.id, c)).collect();
+    let new_clients: HashMap<u32, &PatchbayClient> =
+        new.clients.iter().map(|c| (c.id, c)).collect();
+
+    for c in &old.connections {
+        if !new.connections.contains(c)
+            && let Some(t) = old.connection_tuple(c)
+        {
+            JackPatchbay::ports_disconnected(
+                emitter, version, t.0, &t.1, t.2, &t.3, t.4, &t.5, t.6, &t.7, t.8,
+            )
+            .await?;
+        }
+    }
+    for client in &old.clients {
+        let remaining = new_clients.get(&client.id);
+        for port in &client.ports {
+            if !remaining.is_some_and(|c| c.ports.iter().any(|p| p.id == port.id)) {
+                JackPatchbay::port_disappeared(
+                    emitter,
+                    version,
+                    client.id as u64,
+                    &client.name,
+                    port.id as u64,
+                    &port.name,
+                )
+                .await?;
+            }
+        }
+        if remaining.is_none() {
+            JackPatchbay::client_disappeared(emitter, version, client.id as u64, &client.name)
+                .await?;
+        }
+    }
+    for client in &new.clients {
+        let existing = old_clients.get(&client.id);
+        if existing.is_none() {
+            JackPatchbay::client_appeared(emitter, version, client.id as u64, &client.name).await?;
+        }
+        for port in &client.ports {
+            if !existing.is_some_and(|c| c.ports.iter().any(|p| p.id == port.id)) {
+                JackPatchbay::port_appeared(
+                    emitter,
+                    version,
+                    client.id as u64,
+                    &client.name,
+                    port.id as u64,
+                    &port.name,
+                    port.flags(),
+                    port.port_type(),
+                )
+                .await?;
+            }
+        }
+    }
+    for c in &new.connections {
+        if !old.connections.contains(c)
+            && let Some(t) = new.connection_tuple(c)
+        {
+            JackPatchbay::ports_connected(
+                emitter, version, t.0, &t.1, t.2, &t.3, t.4, &t.5, t.6, &t.7, t.8,
+            )
+            .await?;
+        }
+    }
+    JackPatchbay::graph_changed(emitter, version).await
+}
+
+async fn serve(state: JackDbusState) -> zbus::Result<()> {
+    let connection = zbus::connection::Builder::session()?
+        .name(BUS_NAME)?
+        .serve_at(
+            OBJECT_PATH,
+            JackPatchbay {
+                state: state.clone(),
+            },
+        )?
+        .serve_at(OBJECT_PATH, JackControl)?
+        .build()
+        .await?;
+    let iface = connection
+        .object_server()
+        .interface::<_, JackPatchbay>(OBJECT_PATH)
+        .await?;
+    log::info!("JACK patchbay interface available as {}", BUS_NAME);
+
+    let (mut version, mut graph) = state.snapshot();
+    loop {
+        tokio::time::sleep(POLL_INTERVAL).await;
+        let (new_version, new_graph) = state.snapshot();
+        if new_version == version {
+            continue;
+        }
+        if let Err(e) = emit_changes(iface.signal_emitter(), new_version, &graph, &new_graph).await
+        {
+            log::warn!("JACK patchbay: failed to emit change signals: {}", e);
+        }
+        version = new_version;
+        graph = new_graph;
+    }
+}
+
+/// Start serving the patchbay on its own thread. The bus name is only
+/// available when no real jackdbus is running.
+pub fn spawn_jack_dbus() -> JackDbusState {
+    let state = JackDbusState::new();
+    let service_state = state.clone();
+
+    std::thread::Builder::new()
+        .name("zestbay-jack-dbus".into())
+        .spawn(move || {
+            let runtime = match tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+            {
+                Ok(rt) => rt,
+                Err(e) => {
+                    log::warn!("JACK patchbay: failed to start runtime: {}", e);
+                    return;
+                }
+            };
+            if let Err(e) = runtime.block_on(serve(service_state)) {
+                log::warn!("JACK patchbay interface unavailable: {}", e);
+            }
+        })
+        .expect("Failed to spawn JACK D-Bus thread");
+
+    state
+}
@@ -2,6 +2,7 @@ pub mod filter;
 pub mod host;
 pub mod log;
 pub mod options;
+pub mod qt_ui;
 pub mod scanner;
 pub mod state;
 pub mod types;
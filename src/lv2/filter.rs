@@ -11,6 +11,7 @@ use super::types::*;
 use crate::midi::filter::ResolvedMappings;
 use crate::midi::processing::MidiProcessingState;
 use crate::plugin::cpu_stats::{global_cpu_tracker, PluginTimingSlot};
+use crate::plugin::meters::{self, global_meters, MeterProducer};
 
 pub struct Lv2FilterNode {
     filter: *mut pipewire::sys::pw_filter,
@@ -30,6 +31,10 @@ pub struct FilterConfig {
     pub sample_rate: u32,
     pub has_midi_in: bool,
     pub has_midi_out: bool,
+    /// Audio input channels that are sidechains rather than main inputs.
+    pub sidechain_inputs: Vec<usize>,
+    pub cv_inputs: usize,
+    pub cv_outputs: usize,
 }
 
 #[repr(C)]
@@ -49,11 +54,15 @@ struct FilterData {
     output_port_ptrs: Vec<*mut std::ffi::c_void>,
     midi_in_port_ptr: *mut std::ffi::c_void,
     midi_out_port_ptr: *mut std::ffi::c_void,
+    /// CV ports, in the order of the instance's `cv_in_bufs` / `cv_out_bufs`
+    cv_in_port_ptrs: Vec<*mut std::ffi::c_void>,
+    cv_out_port_ptrs: Vec<*mut std::ffi::c_void>,
     n_audio_inputs: usize,
     n_audio_outputs: usize,
     /// Plugin accepts MIDI input (feed PipeWire MIDI to LV2 atom input buffers)
     has_midi_in: bool,
     cpu_slot: Arc<PluginTimingSlot>,
+    meter: MeterProducer,
     midi_state: MidiProcessingState,
 }
 
@@ -127,10 +136,13 @@ impl Lv2FilterNode {
             output_port_ptrs: Vec::with_capacity(config.audio_outputs),
             midi_in_port_ptr: std::ptr::null_mut(),
             midi_out_port_ptr: std::ptr::null_mut(),
+            cv_in_port_ptrs: Vec::with_capacity(config.cv_inputs),
+            cv_out_port_ptrs: Vec::with_capacity(config.cv_outputs),
             n_audio_inputs: config.audio_inputs,
             n_audio_outputs: config.audio_outputs,
             has_midi_in: config.has_midi_in,
             cpu_slot,
+            meter: global_meters().register(config.instance_id),
             midi_state: MidiProcessingState::new(),
         }));
 
@@ -168,6 +180,16 @@ impl Lv2FilterNode {
                     std::ptr::null::<std::os::raw::c_char>(),
                 )
             };
+            if config.sidechain_inputs.contains(&i) {
+                let group = CString::new(crate::plugin::types::SIDECHAIN_PORT_GROUP).unwrap();
+                unsafe {
+                    pipewire::sys::pw_properties_set(
+                        port_props,
+                        c_str(b"port.group\0"),
+                        group.as_ptr(),
+                    );
+                }
+            }
             let port_data = unsafe {
                 pipewire::sys::pw_filter_add_port(
                     filter,
@@ -223,6 +245,55 @@ impl Lv2FilterNode {
             }
         }
 
+        // CV ports: float buffers like audio, grouped so the graph can tell
+        // them apart.
+        for (direction, prefix, count) in [
+            (libspa::sys::SPA_DIRECTION_INPUT, "cv_in", config.cv_inputs),
+            (
+                libspa::sys::SPA_DIRECTION_OUTPUT,
+                "cv_out",
+                config.cv_outputs,
+            ),
+        ] {
+            for i in 0..count {
+                let port_name = CString::new(format!("{}_{}", prefix, i)).unwrap();
+                let group = CString::new(crate::plugin::types::CV_PORT_GROUP).unwrap();
+                let port_props = unsafe {
+                    pipewire::sys::pw_properties_new(
+                        c_str(b"port.name\0"),
+                        port_name.as_ptr(),
+                        c_str(b"format.dsp\0"),
+                        c_str(b"32 bit float mono audio\0"),
+                        c_str(b"port.group\0"),
+                        group.as_ptr(),
+                        std::ptr::null::<std::os::raw::c_char>(),
+                    )
+                };
+                let port_data = unsafe {
+                    pipewire::sys::pw_filter_add_port(
+                        filter,
+                        direction,
+                        pipewire::sys::pw_filter_port_flags_PW_FILTER_PORT_FLAG_MAP_BUFFERS,
+                        std::mem::size_of::<PortData>(),
+                        port_props,
+                        std::ptr::null_mut(),
+                        0,
+                    )
+                };
+                if port_data.is_null() {
+                    log::error!("Failed to add CV port {}_{}", prefix, i);
+                }
+                // Kept even when null so port i stays CV buffer i.
+                unsafe {
+                    if direction == libspa::sys::SPA_DIRECTION_INPUT {
+                        (*user_data).cv_in_port_ptrs.push(port_data);
+                    } else {
+                        (*user_data).cv_out_port_ptrs.push(port_data);
+                    }
+                }
+            }
+        }
+
         // MIDI input port
         {
             let port_name = CString::new("midi_in").unwrap();
@@ -299,11 +370,13 @@ impl Lv2FilterNode {
         }
 
         log::info!(
-            "LV2 filter node created: {} (instance {}, {} in / {} out)",
+            "LV2 filter node created: {} (instance {}, {} in / {} out, {} CV in / {} CV out)",
             config.display_name,
             config.instance_id,
             config.audio_inputs,
             config.audio_outputs,
+            config.cv_inputs,
+            config.cv_outputs,
         );
 
         Ok(Self {
@@ -317,6 +390,14 @@ impl Lv2FilterNode {
         })
     }
 
+    /// Ask PipeWire for a quantum through `node.latency` (e.g. `"64/48000"`),
+    /// or withdraw the request with `None`.
+    pub fn set_latency(&self, latency: Option<&str>) {
+        if !self.filter.is_null() {
+            unsafe { crate::plugin::node_latency::request(self.filter, latency) }
+        }
+    }
+
     pub fn node_id(&self) -> u32 {
         if self.filter.is_null() {
             return 0;
@@ -366,6 +447,7 @@ impl Lv2FilterNode {
 impl Drop for Lv2FilterNode {
     fn drop(&mut self) {
         global_cpu_tracker().unregister(self.instance_id);
+        global_meters().unregister(self.instance_id);
 
         if !self._user_data.is_null() {
             unsafe {
@@ -550,6 +632,20 @@ unsafe extern "C" fn on_process(
             }
         }
 
+        let ns = n_samples as usize;
+        for (port_ptr, cv) in fd.cv_in_port_ptrs.iter().zip(inst.cv_in_bufs.iter_mut()) {
+            let buf = if port_ptr.is_null() {
+                std::ptr::null_mut()
+            } else {
+                pipewire::sys::pw_filter_get_dsp_buffer(*port_ptr, n_samples)
+            };
+            if buf.is_null() {
+                cv.data[..ns].fill(0.0);
+            } else {
+                cv.data[..ns].copy_from_slice(std::slice::from_raw_parts(buf as *const f32, ns));
+            }
+        }
+
         let t0 = std::time::Instant::now();
         inst.process(
             &input_bufs,
@@ -558,7 +654,18 @@ unsafe extern "C" fn on_process(
             &midi_events_buf[..n_midi_events],
         );
         let elapsed = t0.elapsed().as_nanos() as u64;
+
+        for (port_ptr, cv) in fd.cv_out_port_ptrs.iter().zip(inst.cv_out_bufs.iter()) {
+            if port_ptr.is_null() {
+                continue;
+            }
+            let buf = pipewire::sys::pw_filter_get_dsp_buffer(*port_ptr, n_samples);
+            if !buf.is_null() {
+                std::slice::from_raw_parts_mut(buf as *mut f32, ns).copy_from_slice(&cv.data[..ns]);
+            }
+        }
         fd.cpu_slot
             .record(elapsed, inst.last_worker_ns, n_samples, rate);
+        meters::record(&mut fd.meter, output_bufs.iter().map(|b| &**b));
     }
 }
@@ -111,9 +111,22 @@ pub struct Lv2PluginInstance {
     pub control_outputs: Vec<ControlPort>,
     pub atom_in_bufs: Vec<AtomBuf>,
     pub atom_out_bufs: Vec<AtomBuf>,
+    /// CV ports, filled and drained by the filter node around process()
+    pub cv_in_bufs: Vec<CvBuf>,
+    pub cv_out_bufs: Vec<CvBuf>,
     pub port_updates: SharedPortUpdates,
     atom_sequence_urid: u32,
     pub bypassed: bool,
+    bypass: crate::plugin::bypass::BypassCrossfader,
+    /// Set while the instance rings out before it is removed
+    pub ringing_out: bool,
+    tail: crate::plugin::tail::PluginTail,
+    tail_finished: std::sync::atomic::AtomicBool,
+    /// Position in `control_outputs` of the `lv2:reportsLatency` port
+    latency_port: Option<usize>,
+    /// Resampler around run() when the plugin is oversampled
+    oversampler: Option<crate::plugin::oversample::Oversampler>,
+    /// Graph sample rate; the plugin itself runs at this times the oversampling factor
     pub sample_rate: f64,
     /// Worker thread for plugins that require the worker#schedule feature
     pub worker: Option<Lv2Worker>,
@@ -134,6 +147,13 @@ pub struct AtomBuf {
     pub data: Vec<u8>,
 }
 
+/// The buffer of one CV port, long enough for the largest block at the
+/// plugin's (possibly oversampled) rate.
+pub struct CvBuf {
+    pub port_index: usize,
+    pub data: Vec<f32>,
+}
+
 #[derive(Debug, Clone)]
 pub struct ControlPort {
     pub index: usize,
@@ -153,9 +173,13 @@ impl Lv2PluginInstance {
         plugin_info: &Lv2PluginInfo,
         sample_rate: f64,
         block_length: u32,
+        oversampling: usize,
         urid_mapper: &Arc<UridMapper>,
     ) -> Option<Self> {
         let id = next_instance_id();
+        let oversampling = oversampling.max(1);
+        let plugin_rate = sample_rate * oversampling as f64;
+        let plugin_block = block_length * oversampling as u32;
         let atom_sequence_urid = urid_mapper.map("http://lv2plug.in/ns/ext/atom#Sequence");
 
         let mut urid_map = Box::new(urid_mapper.as_lv2_urid_map());
@@ -167,7 +191,7 @@ impl Lv2PluginInstance {
         let log_setup = Lv2LogSetup::new(urid_mapper);
         let log_feature = log_setup.make_feature();
 
-        let options_setup = Lv2OptionsSetup::new(urid_mapper, sample_rate, block_length);
+        let options_setup = Lv2OptionsSetup::new(urid_mapper, plugin_rate, plugin_block);
         let options_feature = options_setup.make_feature();
         let buf_size_features = options_setup.make_buf_size_features();
 
@@ -248,7 +272,7 @@ impl Lv2PluginInstance {
         };
 
         log::debug!("LV2 new: calling plugin.instantiate()");
-        let mut instance = unsafe { plugin.instantiate(sample_rate, features) }?;
+        let mut instance = unsafe { plugin.instantiate(plugin_rate, features) }?;
         log::debug!("LV2 new: instantiate() returned OK");
 
         let mut audio_input_indices = Vec::new();
@@ -257,6 +281,9 @@ impl Lv2PluginInstance {
         let mut control_outputs = Vec::new();
         let mut atom_in_bufs = Vec::new();
         let mut atom_out_bufs = Vec::new();
+        let mut cv_in_bufs = Vec::new();
+        let mut cv_out_bufs = Vec::new();
+        let cv_len = crate::plugin::oversample::MAX_BLOCK * oversampling;
 
         for port_info in &plugin_info.ports {
             match port_info.port_type {
@@ -302,6 +329,18 @@ impl Lv2PluginInstance {
                         data: vec![0u8; ATOM_BUF_SIZE],
                     });
                 }
+                Lv2PortType::CvInput => {
+                    cv_in_bufs.push(CvBuf {
+                        port_index: port_info.index,
+                        data: vec![0.0; cv_len],
+                    });
+                }
+                Lv2PortType::CvOutput => {
+                    cv_out_bufs.push(CvBuf {
+                        port_index: port_info.index,
+                        data: vec![0.0; cv_len],
+                    });
+                }
             }
         }
 
@@ -322,7 +361,7 @@ impl Lv2PluginInstance {
         // connected before activate(), as required by the LV2 spec.
         // process() will reconnect them to real buffers each cycle.
         log::debug!("LV2 new: connecting audio ports ({} in, {} out)", audio_input_indices.len(), audio_output_indices.len());
-        let mut dummy_audio_buf = vec![0.0f32; block_length as usize];
+        let mut dummy_audio_buf = vec![0.0f32; plugin_block as usize];
         for &idx in audio_input_indices.iter().chain(audio_output_indices.iter()) {
             unsafe {
                 instance.connect_port_mut(idx, dummy_audio_buf.as_mut_ptr());
@@ -330,6 +369,13 @@ impl Lv2PluginInstance {
         }
         log::debug!("LV2 new: audio ports connected");
 
+        // CV buffers never move, so they are connected once.
+        for cv in cv_in_bufs.iter_mut().chain(cv_out_bufs.iter_mut()) {
+            unsafe {
+                instance.connect_port_mut(cv.port_index, cv.data.as_mut_ptr());
+            }
+        }
+
         log::debug!("LV2 new: connecting atom ports ({} in, {} out)", atom_in_bufs.len(), atom_out_bufs.len());
         for ab in atom_in_bufs.iter_mut() {
             init_atom_sequence(&mut ab.data, ATOM_BUF_SIZE, false, atom_sequence_urid);
@@ -439,6 +485,22 @@ impl Lv2PluginInstance {
         let active_instance = unsafe { instance.activate() };
         log::debug!("LV2 new: activate() completed");
 
+        let latency_port = plugin_info
+            .ports
+            .iter()
+            .find(|p| p.reports_latency)
+            .and_then(|lp| control_outputs.iter().position(|cp| cp.index == lp.index));
+        let bypass = crate::plugin::bypass::BypassCrossfader::new(
+            audio_input_indices.len().min(audio_output_indices.len()),
+            sample_rate,
+        );
+        let tail = crate::plugin::tail::PluginTail::new(audio_input_indices.len(), sample_rate);
+        let oversampler = crate::plugin::oversample::Oversampler::new(
+            oversampling,
+            audio_input_indices.len(),
+            audio_output_indices.len(),
+        );
+
         Some(Self {
             id,
             instance: active_instance,
@@ -456,9 +518,17 @@ impl Lv2PluginInstance {
             control_outputs,
             atom_in_bufs,
             atom_out_bufs,
+            cv_in_bufs,
+            cv_out_bufs,
             port_updates,
             atom_sequence_urid,
             bypassed: false,
+            bypass,
+            ringing_out: false,
+            tail,
+            tail_finished: std::sync::atomic::AtomicBool::new(false),
+            latency_port,
+            oversampler,
             sample_rate,
             worker,
             last_worker_ns: 0,
@@ -493,6 +563,86 @@ impl Lv2PluginInstance {
         sample_count: usize,
         midi_events: &[crate::midi::processing::RawMidiEvent],
     ) {
+        // While bypassed or being removed the plugin may run on muted
+        // inputs, so that its tail rings out.
+        let mut tail = std::mem::take(&mut self.tail);
+        let mut plugin_latency = 0;
+        tail.process(
+            self.bypassed || self.ringing_out,
+            inputs,
+            outputs,
+            sample_count,
+            midi_events,
+            |ins, outs, events| {
+                plugin_latency = unsafe { self.run_block(ins, outs, sample_count, events) };
+            },
+        );
+        self.bypass.set_keep_wet(tail.is_ringing());
+        self.tail_finished
+            .store(tail.is_finished(), std::sync::atomic::Ordering::Relaxed);
+        self.tail = tail;
+
+        // When bypassed the output is crossfaded to the input, delayed by
+        // the plugin latency plus any resampling delay.
+        self.bypass.set_latency(plugin_latency);
+        self.bypass
+            .process(self.bypassed, inputs, outputs, sample_count);
+        if self.bypassed {
+            for cv in &mut self.cv_out_bufs {
+                cv.data[..sample_count].fill(0.0);
+            }
+        }
+    }
+
+    /// Whether the instance's inputs are muted and its tail has rung out.
+    pub fn tail_finished(&self) -> bool {
+        self.tail_finished
+            .load(std::sync::atomic::Ordering::Relaxed)
+    }
+
+    /// Run the plugin on one block, through the oversampler if there is
+    /// one. Returns the plugin latency at the graph rate.
+    unsafe fn run_block(
+        &mut self,
+        inputs: &[&[f32]],
+        outputs: &mut [&mut [f32]],
+        sample_count: usize,
+        midi_events: &[crate::midi::processing::RawMidiEvent],
+    ) -> usize {
+        if let Some(mut os) = self.oversampler.take() {
+            let mut scaled = [crate::midi::processing::RawMidiEvent {
+                offset: 0,
+                data: [0; 3],
+                size: 0,
+            }; crate::midi::processing::MAX_MIDI_EVENTS];
+            let n_events = os.scale_midi_events(midi_events, &mut scaled);
+            for cv in &mut self.cv_in_bufs {
+                crate::plugin::cv::hold_in_place(&mut cv.data, sample_count, os.factor());
+            }
+            let mut plugin_latency = 0;
+            os.process(inputs, outputs, sample_count, |ins, outs, n| unsafe {
+                plugin_latency = self.run(ins, outs, n, &scaled[..n_events]);
+            });
+            for cv in &mut self.cv_out_bufs {
+                crate::plugin::cv::pick_in_place(&mut cv.data, sample_count, os.factor());
+            }
+            let latency = os.total_latency(plugin_latency);
+            self.oversampler = Some(os);
+            latency
+        } else {
+            unsafe { self.run(inputs, outputs, sample_count, midi_events) }
+        }
+    }
+
+    /// Run the plugin on buffers at its own rate. Returns the latency the
+    /// plugin reports, in samples at that rate.
+    unsafe fn run(
+        &mut self,
+        inputs: &[&[f32]],
+        outputs: &mut [&mut [f32]],
+        sample_count: usize,
+        midi_events: &[crate::midi::processing::RawMidiEvent],
+    ) -> usize {
         // Connect audio ports (buffer pointers change each cycle)
         for (i, &port_idx) in self.audio_input_indices.iter().enumerate() {
             if i < inputs.len() {
@@ -598,8 +748,8 @@ impl Lv2PluginInstance {
         }
 
         // Always call run() so the plugin keeps its internal state alive
-        // (visualizers, worker threads, etc.). When bypassed we just
-        // overwrite the audio output with a passthrough copy afterwards.
+        // (visualizers, worker threads, etc.). When bypassed the output is
+        // crossfaded to the latency-aligned input afterwards.
         unsafe {
             self.instance.run(sample_count);
         }
@@ -614,19 +764,10 @@ impl Lv2PluginInstance {
             self.last_worker_ns = worker.drain_worker_ns();
         }
 
-        // When bypassed, overwrite plugin audio output with passthrough
-        if self.bypassed {
-            for (i, output) in outputs.iter_mut().enumerate() {
-                if i < inputs.len() {
-                    let copy_len = output.len().min(inputs[i].len()).min(sample_count);
-                    output[..copy_len].copy_from_slice(&inputs[i][..copy_len]);
-                } else {
-                    for sample in output.iter_mut().take(sample_count) {
-                        *sample = 0.0;
-                    }
-                }
-            }
-        }
+        // The latency port is updated by run(), so read it every block.
+        let latency = self
+            .latency_port
+            .map_or(0, |i| self.control_outputs[i].value.max(0.0) as usize);
 
         for (cp, slot) in self
             .control_outputs
@@ -658,6 +799,8 @@ impl Lv2PluginInstance {
                 }
             }
         }
+
+        latency
     }
 
     pub fn set_parameter(&mut self, port_index: usize, value: f32) {
@@ -730,6 +873,7 @@ impl Lv2PluginInstance {
             active: true,
             bypassed: self.bypassed,
             lv2_state: Vec::new(),
+            oversampling: self.oversampler.as_ref().map_or(1, |os| os.factor() as u32),
         }
     }
 
@@ -0,0 +1,157 @@
+//! Native Qt windows for hosting LV2 Qt plugin UIs.
+//!
+//! UIs built for the same Qt major version as ZestBay are instantiated in
+//! process and reparented into a top-level QWidget, instead of being wrapped
+//! into GTK by suil:
+//! - The application object is a QApplication so QWidgets can exist
+//! - UI work is queued onto the Qt main thread from any thread
+//! - A shared QTimer drives port updates and idle callbacks for open UIs
+
+use std::ffi::c_void;
+use std::sync::Mutex;
+
+use cxx_qt_lib::{QByteArray, QGuiApplication, QVector};
+
+#[cxx::bridge(namespace = "zestbay::qt_ui")]
+mod ffi {
+    unsafe extern "C++" {
+        include!("cxx-qt-lib/qguiapplication.h");
+        #[namespace = ""]
+        type QGuiApplication = cxx_qt_lib::QGuiApplication;
+        include!("cxx-qt-lib/core/qvector/qvector_QByteArray.h");
+        #[namespace = ""]
+        type QVector_QByteArray = cxx_qt_lib::QVector<cxx_qt_lib::QByteArray>;
+
+        include!("zestbay/src/lv2/qt_ui_host.h");
+        #[namespace = ""]
+        type QWidget;
+
+        fn new_widget_application(args: &QVector_QByteArray) -> UniquePtr<QGuiApplication>;
+        fn has_widget_application() -> bool;
+        fn wake_main_thread();
+        fn start_idle_timer(interval_ms: i32);
+        fn stop_idle_timer();
+        fn create_window(instance_id: u64, title: &str) -> *mut QWidget;
+        unsafe fn embed_widget(window: *mut QWidget, widget: *mut QWidget);
+        unsafe fn resize_window(window: *mut QWidget, width: i32, height: i32);
+        unsafe fn present_window(window: *mut QWidget);
+        unsafe fn destroy_window(window: *mut QWidget);
+    }
+
+    extern "Rust" {
+        fn qt_ui_dispatch();
+        fn qt_ui_idle();
+        fn qt_ui_window_closed(instance_id: u64);
+    }
+}
+
+const LV2_UI_QT5: &str = "http://lv2plug.in/ns/extensions/ui#Qt5UI";
+const LV2_UI_QT6: &str = "http://lv2plug.in/ns/extensions/ui#Qt6UI";
+
+/// The LV2 UI class that can be hosted natively. A Qt5 UI cannot share a
+/// process with Qt6 widgets (and vice versa), so the other major version
+/// still goes through suil.
+pub const NATIVE_UI_TYPE: &str = if env!("QT_VERSION").as_bytes()[0] == b'5' {
+    LV2_UI_QT5
+} else {
+    LV2_UI_QT6
+};
+
+const IDLE_INTERVAL_MS: i32 = 30;
+
+type MainThreadTask = Box<dyn FnOnce() + Send>;
+
+static PENDING_TASKS: Mutex<Vec<MainThreadTask>> = Mutex::new(Vec::new());
+
+/// Create the application object. Replaces `QGuiApplication::new()` so that
+/// plugin UIs can create widgets.
+pub fn new_application() -> cxx::UniquePtr<QGuiApplication> {
+    use std::os::unix::ffi::OsStrExt;
+
+    let mut args = QVector::<QByteArray>::default();
+    for arg in std::env::args_os() {
+        args.append(QByteArray::from(arg.as_bytes()));
+    }
+    ffi::new_widget_application(&args)
+}
+
+/// Whether the running application can host widget-based UIs.
+pub fn has_widget_application() -> bool {
+    ffi::has_widget_application()
+}
+
+/// Run `task` on the Qt main thread during a later event loop iteration.
+pub fn run_on_main_thread(task: impl FnOnce() + Send + 'static) {
+    PENDING_TASKS.lock().unwrap().push(Box::new(task));
+    ffi::wake_main_thread();
+}
+
+/// Start (or keep running) the shared idle timer. Main thread only.
+pub fn start_idle_timer() {
+    ffi::start_idle_timer(IDLE_INTERVAL_MS);
+}
+
+/// Stop the shared idle timer. Main thread only.
+pub fn stop_idle_timer() {
+    ffi::stop_idle_timer();
+}
+
+fn qt_ui_dispatch() {
+    let tasks = std::mem::take(&mut *PENDING_TASKS.lock().unwrap());
+    for task in tasks {
+        task();
+    }
+}
+
+fn qt_ui_idle() {
+    super::ui::idle_qt_windows();
+}
+
+fn qt_ui_window_closed(instance_id: u64) {
+    super::ui::close_qt_window(instance_id);
+}
+
+/// A top-level Qt window that a plugin UI widget is embedded in. Only
+/// created, used and dropped on the Qt main thread.
+pub struct QtPluginWindow {
+    window: *mut ffi::QWidget,
+}
+
+impl QtPluginWindow {
+    pub fn new(instance_id: u64, title: &str) -> Option<Self> {
+        let window = ffi::create_window(instance_id, title);
+        if window.is_null() {
+            return None;
+        }
+        Some(Self { window })
+    }
+
+    /// The container widget, for the `ui:parent` feature.
+    pub fn as_parent(&self) -> *mut c_void {
+        self.window as *mut c_void
+    }
+
+    /// Reparent the plugin's `QWidget` into this window and size the
+    /// window to it.
+    ///
+    /// # Safety
+    /// `widget` must be a live `QWidget*` returned by a Qt UI's instantiate.
+    pub unsafe fn embed(&self, widget: *mut c_void) {
+        unsafe { ffi::embed_widget(self.window, widget as *mut ffi::QWidget) };
+    }
+
+    pub fn set_size(&self, width: i32, height: i32) {
+        unsafe { ffi::resize_window(self.window, width, height) };
+    }
+
+    pub fn present(&self) {
+        unsafe { ffi::present_window(self.window) };
+    }
+}
+
+impl Drop for QtPluginWindow {
+    fn drop(&mut self) {
+        // Deferred delete: this may run from inside the window's closeEvent.
+        unsafe { ffi::destroy_window(self.window) };
+    }
+}
@@ -0,0 +1,146 @@
+#include "zestbay/src/lv2/qt_ui_host.h"
+
+#include <QtCore/QMetaObject>
+#include <QtCore/QPointer>
+#include <QtCore/QString>
+#include <QtCore/QTimer>
+#include <QtGui/QCloseEvent>
+#include <QtWidgets/QApplication>
+#include <QtWidgets/QVBoxLayout>
+
+#include "cxx-qt-lib/qcoreapplication.h"
+#include "zestbay/src/lv2/qt_ui.cxx.h"
+
+namespace zestbay {
+namespace qt_ui {
+
+namespace {
+
+// Top-level window holding a plugin's QWidget. Closing it tells the Rust
+// side, which tears the plugin UI down and then destroys the window.
+class PluginUiWindow : public QWidget
+{
+public:
+  explicit PluginUiWindow(::std::uint64_t instance_id)
+    : QWidget(nullptr, Qt::Window)
+    , m_instance_id(instance_id)
+  {
+    auto layout = new QVBoxLayout(this);
+    layout->setContentsMargins(0, 0, 0, 0);
+    layout->setSpacing(0);
+  }
+
+  void detach() { m_detached = true; }
+
+protected:
+  void closeEvent(QCloseEvent* event) override
+  {
+    event->accept();
+    if (!m_detached) {
+      m_detached = true;
+      qt_ui_window_closed(m_instance_id);
+    }
+  }
+
+private:
+  ::std::uint64_t m_instance_id;
+  bool m_detached = false;
+};
+
+QPointer<QTimer> idle_timer;
+
+} // namespace
+
+::std::unique_ptr<QGuiApplication>
+new_widget_application(const QVector<QByteArray>& args)
+{
+  // Same lifetime handling as cxx-qt-lib's qguiapplicationNew: the argument
+  // storage is parented to the application so argc/argv outlive it.
+  auto argsData = new ::rust::cxxqtlib1::ApplicationArgsData(args);
+  auto ptr =
+    ::std::make_unique<QApplication>(argsData->size(), argsData->data());
+  Q_ASSERT(ptr != nullptr);
+  argsData->setParent(ptr.get());
+
+  return ptr;
+}
+
+bool
+has_widget_application()
+{
+  return qobject_cast<QApplication*>(QCoreApplication::instance()) != nullptr;
+}
+
+void
+wake_main_thread()
+{
+  auto app = QCoreApplication::instance();
+  if (app == nullptr) {
+    return;
+  }
+  QMetaObject::invokeMethod(
+    app, []() { qt_ui_dispatch(); }, Qt::QueuedConnection);
+}
+
+void
+start_idle_timer(::std::int32_t interval_ms)
+{
+  if (idle_timer.isNull()) {
+    idle_timer = new QTimer(QCoreApplication::instance());
+    QObject::connect(idle_timer, &QTimer::timeout, []() { qt_ui_idle(); });
+  }
+  if (!idle_timer->isActive()) {
+    idle_timer->start(interval_ms);
+  }
+}
+
+void
+stop_idle_timer()
+{
+  if (!idle_timer.isNull()) {
+    idle_timer->stop();
+  }
+}
+
+QWidget*
+create_window(::std::uint64_t instance_id, ::rust::Str title)
+{
+  auto window = new PluginUiWindow(instance_id);
+  window->setWindowTitle(
+    QString::fromUtf8(title.data(), static_cast<qsizetype>(title.size())));
+  return window;
+}
+
+void
+embed_widget(QWidget* window, QWidget* widget)
+{
+  widget->setParent(window);
+  window->layout()->addWidget(widget);
+  widget->show();
+  window->resize(widget->sizeHint().expandedTo(widget->minimumSize()));
+}
+
+void
+resize_window(QWidget* window, ::std::int32_t width, ::std::int32_t height)
+{
+  window->resize(width, height);
+}
+
+void
+present_window(QWidget* window)
+{
+  window->show();
+  window->raise();
+  window->activateWindow();
+}
+
+void
+destroy_window(QWidget* window)
+{
+  static_cast<PluginUiWindow*>(window)->detach();
+  window->hide();
+  window->deleteLater();
+}
+
+} // namespace qt_ui
+} // namespace zestbay
@@ -0,0 +1,51 @@
+#pragma once
+
+#include <cstdint>
+#include <memory>
+
+#include <QtCore/QByteArray>
+#include <QtCore/QVector>
+#include <QtGui/QGuiApplication>
+#include <QtWidgets/QWidget>
+
+#include "rust/cxx.h"
+
+namespace zestbay {
+namespace qt_ui {
+
+// Construct a QApplication (rather than a plain QGuiApplication) so that
+// Qt plugin UIs can create QWidgets in our process.
+::std::unique_ptr<QGuiApplication>
+new_widget_application(const QVector<QByteArray>& args);
+
+bool
+has_widget_application();
+
+// Queue a call to qt_ui_dispatch() on the Qt main thread. Safe from any thread.
+void
+wake_main_thread();
+
+// Start the shared timer that calls qt_ui_idle() on the Qt main thread.
+void
+start_idle_timer(::std::int32_t interval_ms);
+
+void
+stop_idle_timer();
+
+QWidget*
+create_window(::std::uint64_t instance_id, ::rust::Str title);
+
+void
+embed_widget(QWidget* window, QWidget* widget);
+
+void
+resize_window(QWidget* window, ::std::int32_t width, ::std::int32_t height);
+
+void
+present_window(QWidget* window);
+
+void
+destroy_window(QWidget* window);
+
+} // namespace qt_ui
+} // namespace zestbay
@@ -1,8 +1,10 @@
 use lilv::World;
 
 use super::types::*;
+use crate::plugin::docs::lv2_unit_symbol;
 
-const PROVIDED_FEATURES: &[&str] = &[
+/// LV2 host features ZestBay provides to plugins.
+pub const PROVIDED_FEATURES: &[&str] = &[
     "http://lv2plug.in/ns/ext/urid#map",
     "http://lv2plug.in/ns/ext/urid#unmap",
     "http://lv2plug.in/ns/ext/worker#schedule",
@@ -30,6 +32,8 @@ pub struct Lv2PortClassification {
     pub audio_outputs: usize,
     pub control_inputs: usize,
     pub control_outputs: usize,
+    /// Symbols of required ports of a kind ZestBay cannot connect.
+    pub unsupported_ports: Vec<String>,
 }
 
 pub fn classify_lv2_ports(
@@ -43,12 +47,19 @@ pub fn classify_lv2_ports(
     let atom_class = world.new_uri("http://lv2plug.in/ns/ext/atom#AtomPort");
     let cv_class = world.new_uri("http://lv2plug.in/ns/lv2core#CVPort");
     let toggled_prop = world.new_uri("http://lv2plug.in/ns/lv2core#toggled");
+    let sidechain_prop = world.new_uri("http://lv2plug.in/ns/lv2core#isSideChain");
+    let latency_prop = world.new_uri("http://lv2plug.in/ns/lv2core#reportsLatency");
+    let optional_prop = world.new_uri("http://lv2plug.in/ns/lv2core#connectionOptional");
+    let comment_pred = world.new_uri("http://www.w3.org/2000/01/rdf-schema#comment");
+    let unit_pred = world.new_uri("http://lv2plug.in/ns/extensions/units#unit");
+    let unit_symbol_pred = world.new_uri("http://lv2plug.in/ns/extensions/units#symbol");
 
     let mut ports = Vec::new();
     let mut audio_inputs = 0usize;
     let mut audio_outputs = 0usize;
     let mut control_inputs = 0usize;
     let mut control_outputs = 0usize;
+    let mut unsupported_ports = Vec::new();
 
     let port_ranges = plugin.port_ranges_float();
 
@@ -72,12 +83,16 @@ pub fn classify_lv2_ports(
         let is_atom = port.is_a(&atom_class);
         let is_cv = port.is_a(&cv_class);
 
-        let port_type = if (is_audio || is_cv) && is_input {
+        let port_type = if is_audio && is_input {
             audio_inputs += 1;
             Lv2PortType::AudioInput
-        } else if (is_audio || is_cv) && is_output {
+        } else if is_audio && is_output {
             audio_outputs += 1;
             Lv2PortType::AudioOutput
+        } else if is_cv && is_input {
+            Lv2PortType::CvInput
+        } else if is_cv && is_output {
+            Lv2PortType::CvOutput
         } else if is_control && is_input {
             control_inputs += 1;
             Lv2PortType::ControlInput
@@ -89,10 +104,47 @@ pub fn classify_lv2_ports(
         } else if is_atom && is_output {
             Lv2PortType::AtomOutput
         } else {
+            if !port.has_property(&optional_prop) {
+                unsupported_ports.push(port_symbol);
+            }
             continue;
         };
 
         let is_toggle = is_control && is_input && port.has_property(&toggled_prop);
+        let is_sidechain = port_type == Lv2PortType::AudioInput && port.has_property(&sidechain_prop);
+        let reports_latency =
+            port_type == Lv2PortType::ControlOutput && port.has_property(&latency_prop);
+
+        let doc = if is_control {
+            let unit = port.get(&unit_pred).and_then(|unit| {
+                world
+                    .get(Some(&unit), Some(&unit_symbol_pred), None)
+                    .and_then(|s| s.as_str().map(String::from))
+                    .or_else(|| unit.as_uri().and_then(lv2_unit_symbol).map(String::from))
+            });
+            ParamDoc {
+                comment: port
+                    .get(&comment_pred)
+                    .and_then(|c| c.as_str().map(|s| s.trim().to_string()))
+                    .filter(|c| !c.is_empty()),
+                unit: unit.filter(|u| !u.is_empty()),
+                scale_points: port
+                    .scale_points()
+                    .iter()
+                    .filter_map(|point| {
+                        let value = point.value();
+                        Some(ScalePoint {
+                            value: value
+                                .as_float()
+                                .or_else(|| value.as_int().map(|i| i as f32))?,
+                            label: point.label().as_str()?.to_string(),
+                        })
+                    })
+                    .collect(),
+            }
+        } else {
+            ParamDoc::default()
+        };
 
         ports.push(Lv2PortInfo {
             index: i,
@@ -103,6 +155,9 @@ pub fn classify_lv2_ports(
             min_value: port_range.min,
             max_value: port_range.max,
             is_toggle,
+            is_sidechain,
+            reports_latency,
+            doc,
         });
     }
 
@@ -112,6 +167,7 @@ pub fn classify_lv2_ports(
         audio_outputs,
         control_inputs,
         control_outputs,
+        unsupported_ports,
     })
 }
 
@@ -127,6 +183,8 @@ pub fn scan_plugins_with_world(world: &World) -> Vec<Lv2PluginInfo> {
     let ui_x11 = world.new_uri("http://lv2plug.in/ns/extensions/ui#X11UI");
     let ui_qt5 = world.new_uri("http://lv2plug.in/ns/extensions/ui#Qt5UI");
     let ui_classes = [&ui_gtk3, &ui_x11, &ui_qt5, &ui_gtk2, &ui_gtk4];
+    let minor_version = world.new_uri("http://lv2plug.in/ns/lv2core#minorVersion");
+    let micro_version = world.new_uri("http://lv2plug.in/ns/lv2core#microVersion");
 
     let mut plugins = Vec::new();
 
@@ -164,9 +222,10 @@ pub fn scan_plugins_with_world(world: &World) -> Vec<Lv2PluginInfo> {
             .filter_map(|n| n.as_uri().map(String::from))
             .collect();
 
-        let compatible = required_features
-            .iter()
-            .all(|req| PROVIDED_FEATURES.iter().any(|provided| provided == req));
+        let compatible = classification.unsupported_ports.is_empty()
+            && required_features
+                .iter()
+                .all(|req| PROVIDED_FEATURES.iter().any(|provided| provided == req));
 
         let has_ui = plugin
             .uis()
@@ -176,6 +235,22 @@ pub fn scan_plugins_with_world(world: &World) -> Vec<Lv2PluginInfo> {
             })
             .unwrap_or(false);
 
+        let library_path = plugin
+            .library_uri()
+            .and_then(|uri| uri.path())
+            .map(|(_, path)| path)
+            .unwrap_or_default();
+        let version_part = |predicate: &lilv::node::Node| {
+            plugin
+                .value(predicate)
+                .iter()
+                .next()
+                .and_then(|v| v.as_int())
+        };
+        let version = version_part(&minor_version)
+            .zip(version_part(&micro_version))
+            .map(|(minor, micro)| format!("{}.{}", minor, micro));
+
         plugins.push(Lv2PluginInfo {
             uri,
             name,
@@ -190,7 +265,10 @@ pub fn scan_plugins_with_world(world: &World) -> Vec<Lv2PluginInfo> {
             compatible,
             has_ui,
             format: PluginFormat::Lv2,
-            library_path: String::new(),
+            modified: modified_secs(&library_path),
+            library_path,
+            version,
+            unsupported_ports: classification.unsupported_ports,
         });
     }
 
@@ -9,6 +9,8 @@ use std::sync::Arc;
 
 use super::urid::UridMapper;
 
+pub use zestbay_core::plugin::state::StateEntry;
+
 pub const LV2_STATE__INTERFACE: &str = "http://lv2plug.in/ns/ext/state#interface";
 
 #[allow(non_camel_case_types)]
@@ -55,40 +57,6 @@ pub struct LV2_State_Interface {
     ) -> LV2_State_Status,
 }
 
-#[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
-pub struct StateEntry {
-    pub key_uri: String,
-    pub type_uri: String,
-    pub value: Vec<u8>,
-    pub flags: u32,
-}
-
-impl StateEntry {
-    pub fn new_string(key_uri: &str, value: &str) -> Self {
-        let atom_string_uri = "http://lv2plug.in/ns/ext/atom#String";
-        let mut bytes = value.as_bytes().to_vec();
-        bytes.push(0); // null-terminated C string
-        Self {
-            key_uri: key_uri.to_string(),
-            type_uri: atom_string_uri.to_string(),
-            value: bytes,
-            flags: 0,
-        }
-    }
-
-    pub fn as_string(&self) -> Option<&str> {
-        if !self.type_uri.contains("String") {
-            return None;
-        }
-        let bytes = if self.value.last() == Some(&0) {
-            &self.value[..self.value.len() - 1]
-        } else {
-            &self.value
-        };
-        std::str::from_utf8(bytes).ok()
-    }
-}
-
 struct StoreContext {
     entries: Vec<StateEntry>,
     mapper: Arc<UridMapper>,
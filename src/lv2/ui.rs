@@ -2,11 +2,14 @@ use std::collections::HashMap;
 use std::ffi::{CStr, CString};
 use std::os::raw::{c_char, c_int, c_uint, c_ulong, c_void};
 use std::ptr;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::mpsc::Sender;
 use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
 
 use crate::lv2::urid::UridMapper;
 use crate::pipewire::{PluginEvent, PwCommand, PwEvent};
+use crate::plugin::ui_geometry::UiGeometry;
 
 #[link(name = "suil-0")]
 unsafe extern "C" {
@@ -81,6 +84,10 @@ unsafe extern "C" {
     fn gtk_window_new(window_type: c_int) -> *mut c_void;
     fn gtk_window_set_title(window: *mut c_void, title: *const c_char);
     fn gtk_window_set_default_size(window: *mut c_void, width: c_int, height: c_int);
+    fn gtk_window_move(window: *mut c_void, x: c_int, y: c_int);
+    fn gtk_window_resize(window: *mut c_void, width: c_int, height: c_int);
+    fn gtk_window_get_position(window: *mut c_void, x: *mut c_int, y: *mut c_int);
+    fn gtk_window_get_size(window: *mut c_void, width: *mut c_int, height: *mut c_int);
     fn gtk_container_add(container: *mut c_void, widget: *mut c_void);
     fn gtk_widget_show_all(widget: *mut c_void);
     fn gtk_widget_destroy(widget: *mut c_void);
@@ -93,6 +100,11 @@ unsafe extern "C" {
     fn gtk_drawing_area_new() -> *mut c_void;
     fn gtk_widget_set_size_request(widget: *mut c_void, width: c_int, height: c_int);
     fn gtk_widget_set_can_focus(widget: *mut c_void, can_focus: c_int);
+    fn gtk_widget_get_preferred_size(
+        widget: *mut c_void,
+        minimum: *mut GtkRequisition,
+        natural: *mut GtkRequisition,
+    );
 }
 
 #[link(name = "gdk-3")]
@@ -221,12 +233,38 @@ unsafe extern "C" fn ui_resize_x11_callback(
 const LV2_DATA_ACCESS_URI: &CStr = c"http://lv2plug.in/ns/ext/data-access";
 const LV2_INSTANCE_ACCESS_URI: &CStr = c"http://lv2plug.in/ns/ext/instance-access";
 const LV2_UI_PARENT_URI: &CStr = c"http://lv2plug.in/ns/extensions/ui#parent";
+const LV2_UI_PORT_MAP_URI: &CStr = c"http://lv2plug.in/ns/extensions/ui#portMap";
+const LV2_UI_SCALE_FACTOR_URI: &str = "http://lv2plug.in/ns/extensions/ui#scaleFactor";
+
+#[repr(C)]
+struct Lv2Option {
+    context: u32,
+    subject: u32,
+    key: u32,
+    size: u32,
+    type_: u32,
+    value: *const c_void,
+}
+
+/// LV2 UI port map feature — lets the UI look up port indices by symbol.
+#[repr(C)]
+struct LV2UIPortMap {
+    handle: *mut c_void,
+    port_index: unsafe extern "C" fn(handle: *mut c_void, symbol: *const c_char) -> c_uint,
+}
 
 #[repr(C)]
 struct LV2ExtensionDataFeature {
     data_access: unsafe extern "C" fn(*const c_char) -> *const c_void,
 }
 
+#[repr(C)]
+#[derive(Default)]
+struct GtkRequisition {
+    width: c_int,
+    height: c_int,
+}
+
 #[repr(C)]
 struct Lv2UiIdleInterface {
     idle: Option<unsafe extern "C" fn(ui_handle: *mut c_void) -> c_int>,
@@ -337,46 +375,23 @@ struct UiTimerData {
     x11_window: *mut super::x11_ui::X11PluginWindow,
 }
 
-unsafe extern "C" fn ui_timer_callback(data: *mut c_void) -> c_int {
-    if data.is_null() {
-        return 0;
-    }
-    // SAFETY: we need mutable access for the prev_control caches
-    let td = unsafe { &mut *(data as *mut UiTimerData) };
-    if td.closing.load(std::sync::atomic::Ordering::Acquire) {
-        td.timer_removed.store(true, std::sync::atomic::Ordering::Release);
-        return 0;
-    }
-
-    // Helper: forward a port event to the UI via either suil or direct descriptor
-    let send_port_event = |port_index: usize, size: u32, format: u32, buf: *const c_void| {
-        unsafe {
-            if !td.suil_instance.is_null() {
-                suil_instance_port_event(
-                    td.suil_instance,
-                    port_index as c_uint,
-                    size,
-                    format,
-                    buf,
-                );
-            } else if !td.ui_handle.is_null() {
-                // Direct X11 UI — call the descriptor's port_event via the handle
-                // The port_event function pointer is stored at a known offset in the descriptor.
-                // We use a trampoline approach: the ui_handle's descriptor is accessible.
-                // For now, we skip port forwarding for direct UIs — the timer idle call
-                // is the critical part. Port events for direct UIs need the descriptor pointer.
-                // TODO: store descriptor port_event fn pointer in UiTimerData
-            }
-        }
-    };
-
+/// Forward changed control values and pending atom output events to a UI.
+/// `prev_control_*` cache the last forwarded values so unchanged ports are
+/// skipped.
+fn forward_port_updates(
+    port_updates: &super::types::SharedPortUpdates,
+    prev_control_outputs: &mut [f32],
+    prev_control_inputs: &mut [f32],
+    atom_event_transfer_urid: u32,
+    mut send_port_event: impl FnMut(usize, u32, u32, *const c_void),
+) {
     // Forward control outputs to UI, but only when the value changed
-    for (i, slot) in td.port_updates.control_outputs.iter().enumerate() {
+    for (i, slot) in port_updates.control_outputs.iter().enumerate() {
         let val = slot.value.load();
-        let prev = td.prev_control_outputs.get(i).copied().unwrap_or(f32::NAN);
+        let prev = prev_control_outputs.get(i).copied().unwrap_or(f32::NAN);
         if val.to_bits() != prev.to_bits() {
-            if i < td.prev_control_outputs.len() {
-                td.prev_control_outputs[i] = val;
+            if i < prev_control_outputs.len() {
+                prev_control_outputs[i] = val;
             }
             send_port_event(
                 slot.port_index,
@@ -388,12 +403,12 @@ unsafe extern "C" fn ui_timer_callback(data: *mut c_void) -> c_int {
     }
 
     // Forward control inputs to UI, but only when the value changed
-    for (i, slot) in td.port_updates.control_inputs.iter().enumerate() {
+    for (i, slot) in port_updates.control_inputs.iter().enumerate() {
         let val = slot.value.load();
-        let prev = td.prev_control_inputs.get(i).copied().unwrap_or(f32::NAN);
+        let prev = prev_control_inputs.get(i).copied().unwrap_or(f32::NAN);
         if val.to_bits() != prev.to_bits() {
-            if i < td.prev_control_inputs.len() {
-                td.prev_control_inputs[i] = val;
+            if i < prev_control_inputs.len() {
+                prev_control_inputs[i] = val;
             }
             send_port_event(
                 slot.port_index,
@@ -404,8 +419,8 @@ unsafe extern "C" fn ui_timer_callback(data: *mut c_void) -> c_int {
         }
     }
 
-    if td.atom_event_transfer_urid != 0 {
-        for atom_buf in td.port_updates.atom_outputs.iter() {
+    if atom_event_transfer_urid != 0 {
+        for atom_buf in port_updates.atom_outputs.iter() {
             if let Some(data) = atom_buf.read() {
                 if data.len() < 16 {
                     continue;
@@ -428,15 +443,12 @@ unsafe extern "C" fn ui_timer_callback(data: *mut c_void) -> c_int {
                         break;
                     }
 
-                    unsafe {
-                        suil_instance_port_event(
-                            td.suil_instance,
-                            atom_buf.port_index as c_uint,
-                            atom_total as c_uint,
-                            td.atom_event_transfer_urid as c_uint,
-                            data[event_atom_offset..].as_ptr() as *const c_void,
-                        );
-                    }
+                    send_port_event(
+                        atom_buf.port_index,
+                        atom_total as u32,
+                        atom_event_transfer_urid,
+                        data[event_atom_offset..].as_ptr() as *const c_void,
+                    );
 
                     let padded_size = (atom_total + 7) & !7;
                     offset += 8 + padded_size;
@@ -444,6 +456,51 @@ unsafe extern "C" fn ui_timer_callback(data: *mut c_void) -> c_int {
             }
         }
     }
+}
+
+unsafe extern "C" fn ui_timer_callback(data: *mut c_void) -> c_int {
+    if data.is_null() {
+        return 0;
+    }
+    // SAFETY: we need mutable access for the prev_control caches
+    let td = unsafe { &mut *(data as *mut UiTimerData) };
+    let _busy = BusyGuard::enter(td.instance_id);
+    if td.closing.load(std::sync::atomic::Ordering::Acquire) {
+        td.timer_removed.store(true, std::sync::atomic::Ordering::Release);
+        return 0;
+    }
+
+    // Helper: forward a port event to the UI via either suil or direct descriptor
+    let suil_instance = td.suil_instance;
+    let ui_handle = td.ui_handle;
+    let send_port_event = |port_index: usize, size: u32, format: u32, buf: *const c_void| {
+        unsafe {
+            if !suil_instance.is_null() {
+                suil_instance_port_event(
+                    suil_instance,
+                    port_index as c_uint,
+                    size,
+                    format,
+                    buf,
+                );
+            } else if !ui_handle.is_null() {
+                // Direct X11 UI — call the descriptor's port_event via the handle
+                // The port_event function pointer is stored at a known offset in the descriptor.
+                // We use a trampoline approach: the ui_handle's descriptor is accessible.
+                // For now, we skip port forwarding for direct UIs — the timer idle call
+                // is the critical part. Port events for direct UIs need the descriptor pointer.
+                // TODO: store descriptor port_event fn pointer in UiTimerData
+            }
+        }
+    };
+
+    forward_port_updates(
+        &td.port_updates,
+        &mut td.prev_control_outputs,
+        &mut td.prev_control_inputs,
+        td.atom_event_transfer_urid,
+        send_port_event,
+    );
 
     // Pump X11 events for direct X11 UIs
     if !td.x11_window.is_null() {
@@ -524,6 +581,90 @@ pub fn is_ui_open(instance_id: u64) -> bool {
     open_ui_set().lock().unwrap().contains(&instance_id)
 }
 
+// A plugin UI callback that never returns blocks gtk_main() and with it
+// every other in-process UI. The GTK thread stamps a heartbeat on each
+// command poll and a watchdog thread reports when it stops.
+
+/// Heartbeat age at which the GTK thread counts as stuck.
+const GTK_STALL_TIMEOUT: Duration = Duration::from_secs(5);
+const GTK_WATCHDOG_INTERVAL: Duration = Duration::from_millis(500);
+const NO_INSTANCE: u64 = u64::MAX;
+
+static GTK_EPOCH: OnceLock<Instant> = OnceLock::new();
+/// Time of the last command poll, in `gtk_clock_ms` units; 0 until the
+/// GTK main loop is running.
+static GTK_HEARTBEAT_MS: AtomicU64 = AtomicU64::new(0);
+/// Instance whose UI code the GTK thread is running, or `NO_INSTANCE`.
+static GTK_BUSY_INSTANCE: AtomicU64 = AtomicU64::new(NO_INSTANCE);
+static GTK_STALLED: AtomicBool = AtomicBool::new(false);
+static GTK_SHUTTING_DOWN: AtomicBool = AtomicBool::new(false);
+/// Where the watchdog reports stalls; set on the first UI open.
+static GTK_EVENT_TX: OnceLock<Mutex<Sender<PwEvent>>> = OnceLock::new();
+
+fn gtk_clock_ms() -> u64 {
+    GTK_EPOCH.get_or_init(Instant::now).elapsed().as_millis() as u64 + 1
+}
+
+fn gtk_heartbeat() {
+    GTK_HEARTBEAT_MS.store(gtk_clock_ms(), Ordering::Relaxed);
+}
+
+/// Attributes GTK thread time to a plugin UI while alive, so a stall can
+/// name the plugin responsible.
+struct BusyGuard;
+
+impl BusyGuard {
+    fn enter(instance_id: u64) -> Self {
+        GTK_BUSY_INSTANCE.store(instance_id, Ordering::Relaxed);
+        Self
+    }
+}
+
+impl Drop for BusyGuard {
+    fn drop(&mut self) {
+        GTK_BUSY_INSTANCE.store(NO_INSTANCE, Ordering::Relaxed);
+    }
+}
+
+/// The shared GTK thread is stuck in plugin UI code.
+pub fn is_gtk_thread_stalled() -> bool {
+    GTK_STALLED.load(Ordering::Acquire)
+}
+
+fn send_gtk_event(event: PluginEvent) {
+    if let Some(tx) = GTK_EVENT_TX.get()
+        && let Ok(tx) = tx.lock()
+    {
+        let _ = tx.send(PwEvent::Plugin(event));
+    }
+}
+
+fn gtk_watchdog_main() {
+    while !GTK_SHUTTING_DOWN.load(Ordering::Relaxed) {
+        std::thread::sleep(GTK_WATCHDOG_INTERVAL);
+        let last = GTK_HEARTBEAT_MS.load(Ordering::Relaxed);
+        if last == 0 {
+            continue;
+        }
+        let age = Duration::from_millis(gtk_clock_ms().saturating_sub(last));
+        if age >= GTK_STALL_TIMEOUT {
+            if !GTK_STALLED.swap(true, Ordering::AcqRel) {
+                let busy = GTK_BUSY_INSTANCE.load(Ordering::Relaxed);
+                let instance_id = (busy != NO_INSTANCE).then_some(busy);
+                log::error!(
+                    "GTK UI thread unresponsive for {:?} (in UI of instance {:?})",
+                    age,
+                    instance_id
+                );
+                send_gtk_event(PluginEvent::PluginUiStalled { instance_id });
+            }
+        } else if GTK_STALLED.swap(false, Ordering::AcqRel) {
+            log::info!("GTK UI thread is responding again");
+            send_gtk_event(PluginEvent::PluginUiRecovered);
+        }
+    }
+}
+
 fn ensure_gtk_thread() -> &'static Mutex<Sender<GtkCommand>> {
     GTK_CMD_TX.get_or_init(|| {
         let (tx, rx) = std::sync::mpsc::channel::<GtkCommand>();
@@ -535,6 +676,13 @@ fn ensure_gtk_thread() -> &'static Mutex<Sender<GtkCommand>> {
             })
             .expect("Failed to spawn GTK thread");
 
+        if let Err(e) = std::thread::Builder::new()
+            .name("zestbay-gtk-watchdog".into())
+            .spawn(gtk_watchdog_main)
+        {
+            log::warn!("Failed to spawn GTK watchdog thread: {}", e);
+        }
+
         Mutex::new(tx)
     })
 }
@@ -550,6 +698,7 @@ unsafe extern "C" fn gtk_poll_commands(data: *mut c_void) -> c_int {
         return 0;
     }
     let state = unsafe { &mut *(data as *mut GtkThreadState) };
+    gtk_heartbeat();
 
     while let Ok(cmd) = state.cmd_rx.try_recv() {
         match cmd {
@@ -630,6 +779,51 @@ unsafe extern "C" fn destroy_instance_id_data(data: *mut c_void, _closure: *mut
     }
 }
 
+/// Reports a UI window's geometry to the application whenever it changes.
+struct GeometryWatch {
+    instance_id: u64,
+    event_tx: Sender<PwEvent>,
+    last: std::cell::Cell<Option<UiGeometry>>,
+}
+
+unsafe extern "C" fn on_window_configure(
+    widget: *mut c_void,
+    _event: *mut c_void,
+    data: *mut c_void,
+) -> c_int {
+    if data.is_null() {
+        return 0;
+    }
+    let watch = unsafe { &*(data as *const GeometryWatch) };
+    let (mut x, mut y, mut width, mut height) = (0, 0, 0, 0);
+    unsafe {
+        gtk_window_get_position(widget, &mut x, &mut y);
+        gtk_window_get_size(widget, &mut width, &mut height);
+    }
+    let geometry = UiGeometry {
+        x,
+        y,
+        width,
+        height,
+    };
+    if watch.last.replace(Some(geometry)) != Some(geometry) {
+        let _ = watch
+            .event_tx
+            .send(PwEvent::Plugin(PluginEvent::PluginUiGeometry {
+                instance_id: watch.instance_id,
+                geometry,
+            }));
+    }
+    // Let GTK handle the event as well.
+    0
+}
+
+unsafe extern "C" fn destroy_geometry_watch(data: *mut c_void, _closure: *mut c_void) {
+    if !data.is_null() {
+        let _ = unsafe { Box::from_raw(data as *mut GeometryWatch) };
+    }
+}
+
 fn gtk_thread_main(cmd_rx: std::sync::mpsc::Receiver<GtkCommand>) {
     unsafe {
         std::env::set_var("GDK_BACKEND", "x11");
@@ -648,6 +842,7 @@ fn gtk_thread_main(cmd_rx: std::sync::mpsc::Receiver<GtkCommand>) {
     }
 
     log::info!("Persistent GTK thread started — running gtk_main()");
+    gtk_heartbeat();
     unsafe {
         gtk_main();
     }
@@ -658,6 +853,7 @@ fn gtk_thread_main(cmd_rx: std::sync::mpsc::Receiver<GtkCommand>) {
 
 fn handle_open_window(state: &mut GtkThreadState, req: OpenUiRequest) {
     let instance_id = req.instance_id;
+    let _busy = BusyGuard::enter(instance_id);
 
     if let Some(ws) = state.windows.get(&instance_id) {
         log::info!(
@@ -867,35 +1063,34 @@ fn handle_open_window(state: &mut GtkThreadState, req: OpenUiRequest) {
     let atom_float_urid = req.urid_mapper.map("http://lv2plug.in/ns/ext/atom#Float");
     let options_uri: &CStr = c"http://lv2plug.in/ns/ext/options#options";
     let sample_rate_value: f32 = 48000.0;
-
-    #[repr(C)]
-    struct Lv2Option {
-        context: u32,
-        subject: u32,
-        key: u32,
-        size: u32,
-        type_: u32,
-        value: *const c_void,
-    }
-
-    let options = [
-        Lv2Option {
-            context: 0, // LV2_OPTIONS_INSTANCE
+    let scale_factor_value = crate::plugin::ui_scale::scale_for(instance_id);
+
+    let mut options = vec![Lv2Option {
+        context: 0, // LV2_OPTIONS_INSTANCE
+        subject: 0,
+        key: sample_rate_urid,
+        size: std::mem::size_of::<f32>() as u32,
+        type_: atom_float_urid,
+        value: &sample_rate_value as *const f32 as *const c_void,
+    }];
+    if let Some(ref scale_factor) = scale_factor_value {
+        options.push(Lv2Option {
+            context: 0,
             subject: 0,
-            key: sample_rate_urid,
+            key: req.urid_mapper.map(LV2_UI_SCALE_FACTOR_URI),
             size: std::mem::size_of::<f32>() as u32,
             type_: atom_float_urid,
-            value: &sample_rate_value as *const f32 as *const c_void,
-        },
-        Lv2Option {
-            context: 0,
-            subject: 0,
-            key: 0,
-            size: 0,
-            type_: 0,
-            value: ptr::null(),
-        },
-    ];
+            value: scale_factor as *const f32 as *const c_void,
+        });
+    }
+    options.push(Lv2Option {
+        context: 0,
+        subject: 0,
+        key: 0,
+        size: 0,
+        type_: 0,
+        value: ptr::null(),
+    });
 
     let options_feature = lv2_raw::core::LV2Feature {
         uri: options_uri.as_ptr(),
@@ -1495,6 +1690,35 @@ fn handle_open_window(state: &mut GtkThreadState, req: OpenUiRequest) {
             0,
         );
 
+        // Reopen where the user last left this plugin's window, or on the
+        // monitor it belongs on.
+        let mut natural = GtkRequisition::default();
+        gtk_widget_get_preferred_size(window, ptr::null_mut(), &mut natural);
+        let placement =
+            crate::plugin::ui_geometry::placement_for(instance_id, natural.width, natural.height);
+        if let Some(geometry) = placement {
+            gtk_window_move(window, geometry.x, geometry.y);
+            gtk_window_resize(window, geometry.width, geometry.height);
+        }
+
+        let configure_signal = c"configure-event";
+        let watch = Box::into_raw(Box::new(GeometryWatch {
+            instance_id,
+            event_tx: req.event_tx.clone(),
+            last: std::cell::Cell::new(None),
+        }));
+        g_signal_connect_data(
+            window,
+            configure_signal.as_ptr(),
+            Some(std::mem::transmute::<
+                unsafe extern "C" fn(*mut c_void, *mut c_void, *mut c_void) -> c_int,
+                unsafe extern "C" fn(),
+            >(on_window_configure)),
+            watch as *mut c_void,
+            Some(destroy_geometry_watch),
+            0,
+        );
+
         gtk_widget_show_all(window);
 
         let atom_event_transfer_urid = req
@@ -1558,6 +1782,14 @@ fn handle_open_window(state: &mut GtkThreadState, req: OpenUiRequest) {
         let _ = req
             .event_tx
             .send(PwEvent::Plugin(PluginEvent::PluginUiOpened { instance_id }));
+
+        let gdk_window = gtk_widget_get_window(window);
+        if !gdk_window.is_null() {
+            let _ = req.event_tx.send(PwEvent::Plugin(PluginEvent::PluginUiWindow {
+                instance_id,
+                window_id: gdk_x11_window_get_xid(gdk_window) as u64,
+            }));
+        }
     }
 }
 
@@ -1566,6 +1798,7 @@ fn handle_close_window(state: &mut GtkThreadState, instance_id: u64, destroyed_b
         Some(ws) => ws,
         None => return,
     };
+    let _busy = BusyGuard::enter(instance_id);
 
     unsafe {
         (*ws.timer_data)
@@ -1607,6 +1840,430 @@ fn handle_close_window(state: &mut GtkThreadState, instance_id: u64, destroyed_b
     }
 }
 
+// === Native Qt UI hosting ===
+
+/// Everything the Qt main thread needs to open a native Qt UI, gathered up
+/// front so it never has to load the lilv world itself.
+struct QtUiRequest {
+    open: OpenUiRequest,
+    plugin_name: String,
+    symbol_to_index: Vec<(String, usize)>,
+    ui_uri: String,
+    bundle_path: String,
+    binary_path: String,
+}
+
+/// Feature data handed to a native Qt UI. Boxed so the pointers the UI
+/// holds on to stay valid for its whole lifetime.
+struct QtUiFeatures {
+    urid_map: lv2_raw::urid::LV2UridMap,
+    data_access: Option<LV2ExtensionDataFeature>,
+    resize: LV2UIResize,
+    port_map: LV2UIPortMap,
+    sample_rate: f32,
+    scale_factor: f32,
+    options: [Lv2Option; 3],
+    list: Vec<lv2_raw::core::LV2Feature>,
+}
+
+struct QtWindowState {
+    window: Box<super::qt_ui::QtPluginWindow>,
+    descriptor: *const LV2UI_Descriptor,
+    ui_handle: *mut c_void,
+    controller_ptr: *mut c_void,
+    _features: Box<QtUiFeatures>,
+    _urid_mapper: Arc<UridMapper>,
+    port_updates: super::types::SharedPortUpdates,
+    atom_event_transfer_urid: u32,
+    idle_iface: Option<&'static Lv2UiIdleInterface>,
+    prev_control_outputs: Vec<f32>,
+    prev_control_inputs: Vec<f32>,
+    event_tx: Sender<PwEvent>,
+}
+
+thread_local! {
+    /// Open native Qt UIs. Only touched on the Qt main thread.
+    static QT_WINDOWS: std::cell::RefCell<HashMap<u64, QtWindowState>> =
+        std::cell::RefCell::new(HashMap::new());
+}
+
+unsafe extern "C" fn ui_resize_qt_callback(
+    handle: *mut c_void,
+    width: c_int,
+    height: c_int,
+) -> c_int {
+    unsafe {
+        if !handle.is_null() {
+            let win = &*(handle as *const super::qt_ui::QtPluginWindow);
+            win.set_size(width, height);
+        }
+    }
+    0
+}
+
+fn handle_open_qt_window(req: QtUiRequest) {
+    let instance_id = req.open.instance_id;
+
+    let focused = QT_WINDOWS.with(|windows| {
+        windows
+            .borrow()
+            .get(&instance_id)
+            .map(|ws| ws.window.present())
+            .is_some()
+    });
+    if focused {
+        log::info!(
+            "Plugin UI already open for instance {} — focusing",
+            instance_id
+        );
+        return;
+    }
+
+    let event_tx = req.open.event_tx.clone();
+    let fail = |message: String| {
+        log::error!("{}", message);
+        let _ = event_tx.send(PwEvent::Plugin(PluginEvent::PluginError {
+            instance_id: Some(instance_id),
+            message,
+            fatal: false,
+        }));
+    };
+
+    let (Ok(c_plugin_uri), Ok(c_ui_uri), Ok(c_bundle_path), Ok(c_binary_path)) = (
+        CString::new(req.open.plugin_uri.as_str()),
+        CString::new(req.ui_uri.as_str()),
+        CString::new(req.bundle_path.as_str()),
+        CString::new(req.binary_path.as_str()),
+    ) else {
+        fail(format!(
+            "Invalid UI paths for plugin: {}",
+            req.open.plugin_uri
+        ));
+        return;
+    };
+
+    // The library is never unloaded: deleteLater() calls queued by the UI
+    // can still run its code after cleanup.
+    let descriptor = unsafe {
+        let lib = libc::dlopen(c_binary_path.as_ptr(), libc::RTLD_NOW | libc::RTLD_LOCAL);
+        if lib.is_null() {
+            fail(format!("Failed to load UI binary: {}", req.binary_path));
+            return;
+        }
+        let desc_sym = libc::dlsym(lib, c"lv2ui_descriptor".as_ptr());
+        if desc_sym.is_null() {
+            libc::dlclose(lib);
+            fail(format!("No lv2ui_descriptor in {}", req.binary_path));
+            return;
+        }
+        let lv2ui_descriptor_fn: unsafe extern "C" fn(c_uint) -> *const LV2UI_Descriptor =
+            std::mem::transmute(desc_sym);
+
+        let mut ui_descriptor: *const LV2UI_Descriptor = ptr::null();
+        for idx in 0.. {
+            let desc = lv2ui_descriptor_fn(idx);
+            if desc.is_null() {
+                break;
+            }
+            if CStr::from_ptr((*desc).uri) == c_ui_uri.as_c_str() {
+                ui_descriptor = desc;
+                break;
+            }
+        }
+        if ui_descriptor.is_null() {
+            libc::dlclose(lib);
+            fail(format!(
+                "UI descriptor not found for {}",
+                req.open.plugin_uri
+            ));
+            return;
+        }
+        ui_descriptor
+    };
+    let Some(instantiate) = (unsafe { (*descriptor).instantiate }) else {
+        fail(format!(
+            "UI of {} cannot be instantiated",
+            req.open.plugin_uri
+        ));
+        return;
+    };
+
+    let title = format!("ZestBay — {}", req.plugin_name);
+    let Some(window) = super::qt_ui::QtPluginWindow::new(instance_id, &title) else {
+        fail(format!(
+            "Failed to create Qt window for instance {}",
+            instance_id
+        ));
+        return;
+    };
+    let window = Box::new(window);
+
+    let has_atom_inputs = !req.open.port_updates.atom_inputs.is_empty();
+    let controller_ptr = Box::into_raw(Box::new(UiController {
+        instance_id,
+        cmd_tx: req.open.cmd_tx,
+        symbol_to_index: req.symbol_to_index,
+        port_updates: if has_atom_inputs {
+            Some(req.open.port_updates.clone())
+        } else {
+            None
+        },
+    })) as *mut c_void;
+
+    let sample_rate_urid = req
+        .open
+        .urid_mapper
+        .map("http://lv2plug.in/ns/ext/parameters#sampleRate");
+    let atom_float_urid = req
+        .open
+        .urid_mapper
+        .map("http://lv2plug.in/ns/ext/atom#Float");
+
+    let mut features = Box::new(QtUiFeatures {
+        urid_map: req.open.urid_mapper.as_lv2_urid_map(),
+        data_access: req
+            .open
+            .extension_data_fn
+            .map(|data_access| LV2ExtensionDataFeature { data_access }),
+        resize: LV2UIResize {
+            handle: &*window as *const super::qt_ui::QtPluginWindow as *mut c_void,
+            ui_resize: ui_resize_qt_callback,
+        },
+        port_map: LV2UIPortMap {
+            handle: controller_ptr,
+            port_index: port_index_callback,
+        },
+        sample_rate: 48000.0, // TODO: get actual sample rate
+        scale_factor: 1.0,
+        options: unsafe { std::mem::zeroed() },
+        list: Vec::new(),
+    });
+    let f = &mut *features;
+    f.options[0] = Lv2Option {
+        context: 0, // LV2_OPTIONS_INSTANCE
+        subject: 0,
+        key: sample_rate_urid,
+        size: std::mem::size_of::<f32>() as u32,
+        type_: atom_float_urid,
+        value: &f.sample_rate as *const f32 as *const c_void,
+    };
+    if let Some(scale_factor) = crate::plugin::ui_scale::scale_for(instance_id) {
+        f.scale_factor = scale_factor;
+        f.options[1] = Lv2Option {
+            context: 0,
+            subject: 0,
+            key: req.open.urid_mapper.map(LV2_UI_SCALE_FACTOR_URI),
+            size: std::mem::size_of::<f32>() as u32,
+            type_: atom_float_urid,
+            value: &f.scale_factor as *const f32 as *const c_void,
+        };
+    }
+
+    f.list = vec![
+        unsafe { UridMapper::make_feature(&mut f.urid_map) },
+        lv2_raw::core::LV2Feature {
+            uri: LV2_UI_PARENT_URI.as_ptr(),
+            data: window.as_parent(),
+        },
+        lv2_raw::core::LV2Feature {
+            uri: LV2_UI_RESIZE_URI.as_ptr(),
+            data: &mut f.resize as *mut LV2UIResize as *mut c_void,
+        },
+        lv2_raw::core::LV2Feature {
+            uri: LV2_UI_PORT_MAP_URI.as_ptr(),
+            data: &mut f.port_map as *mut LV2UIPortMap as *mut c_void,
+        },
+        lv2_raw::core::LV2Feature {
+            uri: c"http://lv2plug.in/ns/ext/options#options".as_ptr(),
+            data: f.options.as_mut_ptr() as *mut c_void,
+        },
+    ];
+    if let Some(ref mut data_access) = f.data_access {
+        f.list.push(lv2_raw::core::LV2Feature {
+            uri: LV2_DATA_ACCESS_URI.as_ptr(),
+            data: data_access as *mut LV2ExtensionDataFeature as *mut c_void,
+        });
+    }
+    if !req.open.lv2_handle.is_null() {
+        f.list.push(lv2_raw::core::LV2Feature {
+            uri: LV2_INSTANCE_ACCESS_URI.as_ptr(),
+            data: req.open.lv2_handle,
+        });
+    }
+    let mut feature_ptrs: Vec<*const lv2_raw::core::LV2Feature> =
+        f.list.iter().map(|feature| feature as *const _).collect();
+    feature_ptrs.push(ptr::null());
+
+    let mut widget: *mut c_void = ptr::null_mut();
+    let ui_handle = unsafe {
+        instantiate(
+            descriptor,
+            c_plugin_uri.as_ptr(),
+            c_bundle_path.as_ptr(),
+            port_write_callback,
+            controller_ptr,
+            &mut widget,
+            feature_ptrs.as_ptr(),
+        )
+    };
+    if ui_handle.is_null() || widget.is_null() {
+        unsafe {
+            if !ui_handle.is_null()
+                && let Some(cleanup) = (*descriptor).cleanup
+            {
+                cleanup(ui_handle);
+            }
+            let _ = Box::from_raw(controller_ptr as *mut UiController);
+        }
+        fail(format!(
+            "Failed to instantiate Qt UI for {}",
+            req.open.plugin_uri
+        ));
+        return;
+    }
+
+    unsafe { window.embed(widget) };
+
+    if let Some(port_event_fn) = unsafe { (*descriptor).port_event } {
+        for &(port_index, value) in &req.open.control_values {
+            unsafe {
+                port_event_fn(
+                    ui_handle,
+                    port_index as c_uint,
+                    std::mem::size_of::<f32>() as c_uint,
+                    0,
+                    &value as *const f32 as *const c_void,
+                );
+            }
+        }
+    }
+
+    let idle_iface: Option<&'static Lv2UiIdleInterface> = unsafe {
+        (*descriptor).extension_data.and_then(|ext_data| {
+            let ext = ext_data(LV2_UI_IDLE_INTERFACE.as_ptr());
+            (!ext.is_null()).then(|| &*(ext as *const Lv2UiIdleInterface))
+        })
+    };
+
+    window.present();
+
+    let atom_event_transfer_urid = req
+        .open
+        .urid_mapper
+        .map("http://lv2plug.in/ns/ext/atom#eventTransfer");
+    let port_updates = req.open.port_updates;
+    QT_WINDOWS.with(|windows| {
+        windows.borrow_mut().insert(
+            instance_id,
+            QtWindowState {
+                window,
+                descriptor,
+                ui_handle,
+                controller_ptr,
+                _features: features,
+                _urid_mapper: req.open.urid_mapper,
+                prev_control_outputs: vec![f32::NAN; port_updates.control_outputs.len()],
+                prev_control_inputs: vec![f32::NAN; port_updates.control_inputs.len()],
+                port_updates,
+                atom_event_transfer_urid,
+                idle_iface,
+                event_tx: req.open.event_tx.clone(),
+            },
+        );
+    });
+    super::qt_ui::start_idle_timer();
+
+    open_ui_set().lock().unwrap().insert(instance_id);
+
+    log::info!("Qt plugin UI opened for instance {}", instance_id);
+
+    let _ = req
+        .open
+        .event_tx
+        .send(PwEvent::Plugin(PluginEvent::PluginUiOpened { instance_id }));
+}
+
+/// Forward port updates to every native Qt UI and run their idle callbacks.
+/// Called from the shared Qt timer.
+pub(super) fn idle_qt_windows() {
+    let mut finished = Vec::new();
+    QT_WINDOWS.with(|windows| {
+        let Ok(mut windows) = windows.try_borrow_mut() else {
+            return;
+        };
+        for (&instance_id, ws) in windows.iter_mut() {
+            let ui_handle = ws.ui_handle;
+            let port_event_fn = unsafe { (*ws.descriptor).port_event };
+            forward_port_updates(
+                &ws.port_updates,
+                &mut ws.prev_control_outputs,
+                &mut ws.prev_control_inputs,
+                ws.atom_event_transfer_urid,
+                |port_index, size, format, buf| {
+                    if let Some(port_event_fn) = port_event_fn {
+                        unsafe {
+                            port_event_fn(ui_handle, port_index as c_uint, size, format, buf)
+                        };
+                    }
+                },
+            );
+
+            if let Some(idle_fn) = ws.idle_iface.and_then(|iface| iface.idle)
+                && unsafe { idle_fn(ui_handle) } != 0
+            {
+                finished.push(instance_id);
+            }
+        }
+    });
+    for instance_id in finished {
+        close_qt_window(instance_id);
+    }
+}
+
+/// Tear down a native Qt UI and its window. Qt main thread only.
+pub(super) fn close_qt_window(instance_id: u64) {
+    let removed = QT_WINDOWS.with(|windows| {
+        windows
+            .try_borrow_mut()
+            .map(|mut windows| windows.remove(&instance_id))
+    });
+    let ws = match removed {
+        Ok(Some(ws)) => ws,
+        Ok(None) => return,
+        Err(_) => {
+            // Called from inside a UI callback; finish once it has returned.
+            super::qt_ui::run_on_main_thread(move || close_qt_window(instance_id));
+            return;
+        }
+    };
+    if QT_WINDOWS.with(|windows| windows.borrow().is_empty()) {
+        super::qt_ui::stop_idle_timer();
+    }
+
+    unsafe {
+        if let Some(cleanup) = (*ws.descriptor).cleanup {
+            cleanup(ws.ui_handle);
+        }
+        let _ = Box::from_raw(ws.controller_ptr as *mut UiController);
+    }
+    let event_tx = ws.event_tx.clone();
+    drop(ws);
+
+    open_ui_set().lock().unwrap().remove(&instance_id);
+
+    log::info!("Qt plugin UI closed for instance {}", instance_id);
+
+    let _ = event_tx.send(PwEvent::Plugin(PluginEvent::PluginUiClosed { instance_id }));
+}
+
+/// Close every native Qt UI. Qt main thread only; used on shutdown.
+pub fn close_qt_windows() {
+    let ids: Vec<u64> = QT_WINDOWS.with(|windows| windows.borrow().keys().copied().collect());
+    for instance_id in ids {
+        close_qt_window(instance_id);
+    }
+}
+
 /// Global UI bridge client — lazily spawned on first use.
 static UI_BRIDGE: OnceLock<Mutex<Option<super::ui_bridge_client::UiBridgeClient>>> = OnceLock::new();
 
@@ -1629,6 +2286,68 @@ fn get_or_spawn_bridge(
     bridge_lock.lock().unwrap().is_some()
 }
 
+/// Plugin URIs whose UIs run isolated, each in a bridge process of its own.
+static ISOLATED_PLUGINS: OnceLock<Mutex<std::collections::HashSet<String>>> = OnceLock::new();
+
+static ISOLATED_UIS: OnceLock<Mutex<HashMap<u64, super::ui_bridge_client::IsolatedUi>>> =
+    OnceLock::new();
+
+fn isolated_plugins() -> &'static Mutex<std::collections::HashSet<String>> {
+    ISOLATED_PLUGINS.get_or_init(|| Mutex::new(std::collections::HashSet::new()))
+}
+
+fn isolated_uis() -> &'static Mutex<HashMap<u64, super::ui_bridge_client::IsolatedUi>> {
+    ISOLATED_UIS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Replace the set of plugins whose UIs open isolated. Takes effect the
+/// next time a UI is opened.
+pub fn set_isolated_plugins(uris: impl IntoIterator<Item = String>) {
+    let mut set = isolated_plugins().lock().unwrap();
+    set.clear();
+    set.extend(uris);
+}
+
+fn is_plugin_isolated(plugin_uri: &str) -> bool {
+    isolated_plugins().lock().unwrap().contains(plugin_uri)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn open_isolated_ui(
+    plugin_uri: &str,
+    instance_id: u64,
+    (ui_uri, ui_type_uri, bundle_path, binary_path): &(String, String, String, String),
+    port_updates: &super::types::SharedPortUpdates,
+    urid_mapper: &Arc<UridMapper>,
+    event_tx: &Sender<PwEvent>,
+    cmd_tx: &Sender<PwCommand>,
+) -> Result<(), String> {
+    let mut uis = isolated_uis().lock().unwrap();
+    uis.retain(|_, ui| !ui.has_exited());
+    if uis.contains_key(&instance_id) {
+        log::info!("Isolated UI already open for instance {}", instance_id);
+        return Ok(());
+    }
+    let display_name = plugin_uri.rsplit('/').next().unwrap_or(plugin_uri);
+    log::info!("Opening isolated UI for {}", plugin_uri);
+    let ui = super::ui_bridge_client::IsolatedUi::open(
+        instance_id,
+        plugin_uri,
+        ui_uri,
+        ui_type_uri,
+        bundle_path,
+        binary_path,
+        display_name,
+        port_updates.clone(),
+        urid_mapper,
+        48000.0, // TODO: get actual sample rate
+        event_tx.clone(),
+        cmd_tx.clone(),
+    )?;
+    uis.insert(instance_id, ui);
+    Ok(())
+}
+
 pub fn open_plugin_ui(
     plugin_uri: &str,
     instance_id: u64,
@@ -1646,12 +2365,54 @@ pub fn open_plugin_ui(
     let plugin = world.plugins().iter().find(|p| p.uri().as_uri() == uri_node.as_uri());
 
     let mut ui_info: Option<(String, String, String, String)> = None;
+    let mut qt_details: Option<(String, Vec<(String, usize)>)> = None;
     if let Some(plugin) = plugin {
+        let ui_class_qt = world.new_uri(super::qt_ui::NATIVE_UI_TYPE);
         let ui_class_x11 = world.new_uri(LV2_UI_X11);
         let ui_class_gtk3 = world.new_uri(LV2_UI_GTK3);
         let ui_class_gtk2 = world.new_uri(LV2_UI_GTK2);
 
-        if let Some(uis) = plugin.uis() {
+        // A Qt UI built for our own Qt is hosted natively, in preference to
+        // any other UI the plugin offers.
+        if let Some(uis) = plugin.uis()
+            && let Some(ui) = uis.iter().find(|ui| ui.is_a(&ui_class_qt))
+        {
+            let ui_uri = ui.uri().as_uri().map(String::from).unwrap_or_default();
+            let bundle_path = ui
+                .bundle_uri()
+                .and_then(|n| n.path().map(|(_, p)| p))
+                .unwrap_or_default();
+            let binary_path = ui
+                .binary_uri()
+                .and_then(|n| n.path().map(|(_, p)| p))
+                .unwrap_or_default();
+            ui_info = Some((
+                ui_uri,
+                super::qt_ui::NATIVE_UI_TYPE.to_string(),
+                bundle_path,
+                binary_path,
+            ));
+
+            let plugin_name = plugin
+                .name()
+                .as_str()
+                .map(String::from)
+                .unwrap_or_else(|| plugin_uri.to_string());
+            let mut symbol_to_index: Vec<(String, usize)> = Vec::new();
+            for i in 0..plugin.port_ranges_float().len() {
+                if let Some(port) = plugin.port_by_index(i)
+                    && let Some(sym_node) = port.symbol()
+                    && let Some(sym) = sym_node.as_str()
+                {
+                    symbol_to_index.push((sym.to_string(), i));
+                }
+            }
+            qt_details = Some((plugin_name, symbol_to_index));
+        }
+
+        if ui_info.is_none()
+            && let Some(uis) = plugin.uis()
+        {
             // Check for any UI type
             for ui in uis.iter() {
                 for (class_node, type_uri) in [
@@ -1699,10 +2460,70 @@ pub fn open_plugin_ui(
         false
     });
 
+    let _ = GTK_EVENT_TX.get_or_init(|| Mutex::new(event_tx.clone()));
+
+    // Native Qt UIs run on the Qt main thread and never touch the GTK
+    // thread. Isolated plugins still get a bridge process of their own.
+    if !is_plugin_isolated(plugin_uri)
+        && super::qt_ui::has_widget_application()
+        && let Some((plugin_name, symbol_to_index)) = qt_details
+        && let Some((ui_uri, _, bundle_path, binary_path)) = ui_info.clone()
+    {
+        log::info!("Hosting Qt UI natively for {}", plugin_uri);
+        let req = QtUiRequest {
+            open: OpenUiRequest {
+                plugin_uri: plugin_uri.to_string(),
+                instance_id,
+                cmd_tx,
+                event_tx,
+                control_values,
+                port_updates,
+                urid_mapper,
+                lv2_handle,
+                extension_data_fn,
+            },
+            plugin_name,
+            symbol_to_index,
+            ui_uri,
+            bundle_path,
+            binary_path,
+        };
+        super::qt_ui::run_on_main_thread(move || handle_open_qt_window(req));
+        return;
+    }
+
+    let uses_shared_bridge = is_x11_ui && !needs_instance_access;
+    let gtk_stalled = is_gtk_thread_stalled();
+
+    // Isolated UIs get a bridge process of their own. While the shared GTK
+    // thread is stuck, in-process UIs go there too instead of queueing
+    // behind it. UIs needing instance-access can only run in-process.
+    if is_plugin_isolated(plugin_uri) || (gtk_stalled && !uses_shared_bridge) {
+        if needs_instance_access {
+            log::warn!(
+                "UI of {} requires instance-access and cannot be isolated",
+                plugin_uri
+            );
+        } else if let Some(ref ui) = ui_info {
+            match open_isolated_ui(
+                plugin_uri,
+                instance_id,
+                ui,
+                &port_updates,
+                &urid_mapper,
+                &event_tx,
+                &cmd_tx,
+            ) {
+                Ok(()) => return,
+                Err(e) => log::warn!("Isolated UI for {} failed: {}", plugin_uri, e),
+            }
+        }
+    }
+
     // For X11 UIs that DON'T need instance-access, use the bridge process
     // (avoids GLX/EGL conflicts on Wayland). Plugins that need instance-access
     // must run in-process via suil/GTK.
-    if is_x11_ui && !needs_instance_access {
+    if uses_shared_bridge {
         if let Some((ref ui_uri, ref ui_type_uri, ref bundle_path, ref binary_path)) = ui_info {
             if get_or_spawn_bridge(&event_tx, &cmd_tx) {
                 let bridge_lock = UI_BRIDGE.get().unwrap();
@@ -1721,6 +2542,7 @@ pub fn open_plugin_ui(
                         control_values.clone(),
                         &urid_mapper,
                         48000.0, // TODO: get actual sample rate
+                        None,
                     );
                     return;
                 }
@@ -1729,6 +2551,16 @@ pub fn open_plugin_ui(
         }
     }
 
+    if gtk_stalled {
+        let _ = event_tx.send(PwEvent::Plugin(PluginEvent::PluginError {
+            instance_id: Some(instance_id),
+            message: "The plugin UI thread is not responding; try again once it recovers"
+                .to_string(),
+            fatal: false,
+        }));
+        return;
+    }
+
     // For GTK UIs (or X11 fallback), use the old suil/GTK path
     let gtk_tx = ensure_gtk_thread();
     let tx = gtk_tx.lock().unwrap();
@@ -1746,6 +2578,19 @@ pub fn open_plugin_ui(
 }
 
 pub fn close_plugin_ui(instance_id: u64) {
+    super::qt_ui::run_on_main_thread(move || close_qt_window(instance_id));
+
+    let isolated = ISOLATED_UIS
+        .get()
+        .and_then(|uis| uis.lock().unwrap().remove(&instance_id));
+    if let Some(ui) = isolated {
+        // Stopping the process may wait out a hung UI; keep that off the
+        // caller's thread.
+        let _ = std::thread::Builder::new()
+            .name("ui-isolated-close".into())
+            .spawn(move || drop(ui));
+    }
+
     if let Some(tx) = GTK_CMD_TX.get()
         && let Ok(tx) = tx.lock()
     {
@@ -1757,6 +2602,10 @@ pub fn close_plugin_ui(instance_id: u64) {
 }
 
 pub fn shutdown_gtk_thread() {
+    GTK_SHUTTING_DOWN.store(true, Ordering::Relaxed);
+    if let Some(uis) = ISOLATED_UIS.get() {
+        uis.lock().unwrap().clear();
+    }
     if let Some(tx) = GTK_CMD_TX.get()
         && let Ok(tx) = tx.lock()
     {
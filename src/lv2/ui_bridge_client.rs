@@ -2,16 +2,31 @@
 //!
 //! Spawns `zestbay-ui-bridge` as a child process and communicates
 //! via stdin/stdout JSON messages.
+//!
+//! Besides the shared bridge for X11 UIs, [`IsolatedUi`] runs a single
+//! plugin UI in a bridge process of its own, with control values mirrored
+//! through a shared-memory block.
 
 use std::collections::HashMap;
 use std::io::{BufRead, BufReader, Write};
 use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::mpsc::Sender;
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 use crate::lv2::urid::UridMapper;
+use crate::plugin::types::SharedPortUpdates;
 use crate::pipewire::{PluginEvent, PwCommand, PwEvent};
 use crate::ui_bridge::protocol::{BridgeMessage, HostMessage};
+use crate::ui_bridge::shm::ControlBlock;
+
+/// How long a bridge gets to exit after `Quit` before it is killed. A hung
+/// plugin UI must not hold up closing it or quitting ZestBay.
+const SHUTDOWN_GRACE: Duration = Duration::from_secs(1);
+
+/// Interval at which an isolated UI's control block is synchronised.
+const SHM_SYNC_INTERVAL: Duration = Duration::from_millis(16);
 
 pub struct UiBridgeClient {
     child: Child,
@@ -19,12 +34,23 @@ pub struct UiBridgeClient {
     stdin_tx: Sender<String>,
     /// Opened instance IDs.
     open_instances: Arc<Mutex<std::collections::HashSet<u64>>>,
+    /// Set once the bridge's stdout closes, i.e. the process is gone.
+    exited: Arc<AtomicBool>,
 }
 
 impl UiBridgeClient {
     pub fn spawn(
         event_tx: Sender<PwEvent>,
         cmd_tx: Sender<PwCommand>,
+    ) -> Result<Self, String> {
+        Self::spawn_process(event_tx, cmd_tx, false)
+    }
+
+    /// `single`: the bridge hosts one UI and exits when it closes.
+    fn spawn_process(
+        event_tx: Sender<PwEvent>,
+        cmd_tx: Sender<PwCommand>,
+        single: bool,
     ) -> Result<Self, String> {
         // Find the bridge binary: check next to our executable first,
         // then the system install location, then PATH.
@@ -48,7 +74,11 @@ impl UiBridgeClient {
 
         log::info!("UI bridge binary: {:?}", bridge_path);
 
-        let mut child = Command::new(&bridge_path)
+        let mut command = Command::new(&bridge_path);
+        if single {
+            command.arg("--single");
+        }
+        let mut child = command
             .stdin(Stdio::piped())
             .stdout(Stdio::piped())
             .stderr(Stdio::inherit()) // bridge errors go to our stderr
@@ -64,6 +94,8 @@ impl UiBridgeClient {
 
         let open_instances = Arc::new(Mutex::new(std::collections::HashSet::<u64>::new()));
         let open_instances_clone = open_instances.clone();
+        let exited = Arc::new(AtomicBool::new(false));
+        let exited_clone = exited.clone();
 
         // Stdin writer thread
         let (stdin_tx, stdin_rx) = std::sync::mpsc::channel::<String>();
@@ -108,9 +140,22 @@ impl UiBridgeClient {
                         BridgeMessage::Ready => {
                             log::info!("UI bridge process ready");
                         }
-                        BridgeMessage::Opened { instance_id } => {
+                        BridgeMessage::Opened {
+                            instance_id,
+                            window_id,
+                        } => {
                             log::info!("UI bridge: UI opened for instance {}", instance_id);
                             open_instances_clone.lock().unwrap().insert(instance_id);
+                            let _ = event_tx
+                                .send(PwEvent::Plugin(PluginEvent::PluginUiOpened { instance_id }));
+                            if window_id != 0 {
+                                let _ = event_tx.send(PwEvent::Plugin(
+                                    PluginEvent::PluginUiWindow {
+                                        instance_id,
+                                        window_id,
+                                    },
+                                ));
+                            }
                         }
                         BridgeMessage::OpenFailed { instance_id, error } => {
                             log::error!("UI bridge: UI open failed for instance {}: {}", instance_id, error);
@@ -123,6 +168,8 @@ impl UiBridgeClient {
                         BridgeMessage::Closed { instance_id } => {
                             log::info!("UI bridge: UI closed for instance {}", instance_id);
                             open_instances_clone.lock().unwrap().remove(&instance_id);
+                            let _ = event_tx
+                                .send(PwEvent::Plugin(PluginEvent::PluginUiClosed { instance_id }));
                         }
                         BridgeMessage::PortWrite { instance_id, port_index, value } => {
                             let _ = cmd_tx.send(PwCommand::SetPluginParameter {
@@ -137,6 +184,21 @@ impl UiBridgeClient {
                     }
                 }
                 log::info!("UI bridge stdout reader exited");
+
+                // UIs still open when the pipe closes went down with the
+                // process.
+                let lost: Vec<u64> = open_instances_clone.lock().unwrap().drain().collect();
+                for instance_id in lost {
+                    log::error!("UI bridge exited with the UI for instance {} open", instance_id);
+                    let _ = event_tx.send(PwEvent::Plugin(PluginEvent::PluginError {
+                        instance_id: Some(instance_id),
+                        message: "Plugin UI process exited unexpectedly".to_string(),
+                        fatal: false,
+                    }));
+                    let _ = event_tx
+                        .send(PwEvent::Plugin(PluginEvent::PluginUiClosed { instance_id }));
+                }
+                exited_clone.store(true, Ordering::Release);
             })
             .map_err(|e| format!("Failed to spawn stdout thread: {}", e))?;
 
@@ -144,6 +206,7 @@ impl UiBridgeClient {
             child,
             stdin_tx,
             open_instances,
+            exited,
         })
     }
 
@@ -159,6 +222,7 @@ impl UiBridgeClient {
         control_values: Vec<(usize, f32)>,
         urid_mapper: &Arc<UridMapper>,
         sample_rate: f32,
+        shm_path: Option<String>,
     ) {
         let urid_map: Vec<(String, u32)> = urid_mapper.snapshot();
 
@@ -174,6 +238,7 @@ impl UiBridgeClient {
             urid_map,
             lv2_handle: 0, // Not usable across processes
             sample_rate,
+            shm_path,
         };
 
         if let Ok(json) = serde_json::to_string(&msg) {
@@ -200,11 +265,25 @@ impl UiBridgeClient {
         self.open_instances.lock().unwrap().contains(&instance_id)
     }
 
+    pub fn has_exited(&self) -> bool {
+        self.exited.load(Ordering::Acquire)
+    }
+
     pub fn shutdown(&mut self) {
         let msg = HostMessage::Quit;
         if let Ok(json) = serde_json::to_string(&msg) {
             let _ = self.stdin_tx.send(json);
         }
+        let deadline = Instant::now() + SHUTDOWN_GRACE;
+        loop {
+            match self.child.try_wait() {
+                Ok(Some(_)) | Err(_) => return,
+                Ok(None) if Instant::now() >= deadline => break,
+                Ok(None) => std::thread::sleep(Duration::from_millis(20)),
+            }
+        }
+        log::warn!("UI bridge did not exit after Quit; killing it");
+        let _ = self.child.kill();
         let _ = self.child.wait();
     }
 }
@@ -214,3 +293,105 @@ impl Drop for UiBridgeClient {
         self.shutdown();
     }
 }
+
+/// A plugin UI running alone in a bridge process. Control values go
+/// through a shared-memory block: a sync thread publishes the plugin's
+/// [`SharedPortUpdates`] into it and turns the UI's writes into parameter
+/// commands. A UI that hangs or crashes only takes its own process down.
+pub struct IsolatedUi {
+    client: UiBridgeClient,
+    stop: Arc<AtomicBool>,
+}
+
+impl IsolatedUi {
+    #[allow(clippy::too_many_arguments)]
+    pub fn open(
+        instance_id: u64,
+        plugin_uri: &str,
+        ui_uri: &str,
+        ui_type_uri: &str,
+        bundle_path: &str,
+        binary_path: &str,
+        display_name: &str,
+        port_updates: SharedPortUpdates,
+        urid_mapper: &Arc<UridMapper>,
+        sample_rate: f32,
+        event_tx: Sender<PwEvent>,
+        cmd_tx: Sender<PwCommand>,
+    ) -> Result<Self, String> {
+        let slots = port_updates
+            .control_inputs
+            .iter()
+            .chain(port_updates.control_outputs.iter())
+            .map(|slot| slot.port_index + 1)
+            .max()
+            .unwrap_or(0);
+        let path = crate::ui_bridge::shm::default_path(instance_id);
+        let block = ControlBlock::create(&path, slots)
+            .map_err(|e| format!("Cannot create control block {:?}: {}", path, e))?;
+        let control_values = port_updates.snapshot_all();
+        for &(port_index, value) in &control_values {
+            block.publish(port_index, value);
+        }
+
+        let client = UiBridgeClient::spawn_process(event_tx, cmd_tx.clone(), true)?;
+        client.open_ui(
+            instance_id,
+            plugin_uri,
+            ui_uri,
+            ui_type_uri,
+            bundle_path,
+            binary_path,
+            display_name,
+            control_values,
+            urid_mapper,
+            sample_rate,
+            Some(path.to_string_lossy().into_owned()),
+        );
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let stop_flag = stop.clone();
+        let exited = client.exited.clone();
+        std::thread::Builder::new()
+            .name(format!("ui-shm-{}", instance_id))
+            .spawn(move || {
+                let mut seen = Vec::new();
+                while !stop_flag.load(Ordering::Acquire) && !exited.load(Ordering::Acquire) {
+                    for slot in port_updates
+                        .control_inputs
+                        .iter()
+                        .chain(port_updates.control_outputs.iter())
+                    {
+                        block.publish(slot.port_index, slot.value.load());
+                    }
+                    block.take_ui_updates(&mut seen, |port_index, value| {
+                        let _ = cmd_tx.send(PwCommand::SetPluginParameter {
+                            instance_id,
+                            port_index,
+                            value,
+                        });
+                    });
+                    std::thread::sleep(SHM_SYNC_INTERVAL);
+                }
+            })
+            .map_err(|e| format!("Failed to spawn control sync thread: {}", e))?;
+
+        log::info!(
+            "Isolated UI process started for instance {} ({} control slots)",
+            instance_id,
+            slots
+        );
+        Ok(Self { client, stop })
+    }
+
+    /// The UI process is gone (closed by the user, or crashed).
+    pub fn has_exited(&self) -> bool {
+        self.client.has_exited()
+    }
+}
+
+impl Drop for IsolatedUi {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Release);
+    }
+}
@@ -1,18 +1,30 @@
+mod builtin;
 mod clap;
+mod control;
+mod crash_report;
+mod hooks;
+mod jack_dbus;
 mod layout;
 mod lv2;
 mod midi;
+mod mqtt;
 mod patchbay;
+mod patchbay_dbus;
 mod pipewire;
 mod plugin;
+mod portal;
+mod power;
+mod remote_api;
 mod tray;
 mod ui;
 pub mod ui_bridge;
+mod update_check;
 mod vst3;
 
 use cxx_qt::casting::Upcast;
 use cxx_qt_lib::{QGuiApplication, QQmlApplicationEngine, QQmlEngine, QString, QUrl};
 use std::pin::Pin;
+use std::sync::OnceLock;
 use std::sync::atomic::{AtomicBool, Ordering};
 
 /// Global flag: when true, skip restoring saved plugins on startup.
@@ -27,8 +39,21 @@ pub static PLUGINS_FROZEN: AtomicBool = AtomicBool::new(false);
 /// Dangerous — a crashing plugin will take down the entire process.
 pub static NO_PROBE: AtomicBool = AtomicBool::new(false);
 
+/// PipeWire instance given with `--remote <name|socket path>`, overriding the
+/// saved preference for this run.
+pub static PIPEWIRE_REMOTE: OnceLock<String> = OnceLock::new();
+
+/// Global flag: when true (`--demo`), a simulated graph stands in for
+/// PipeWire and settings are kept apart from the real ones.
+pub static DEMO_MODE: AtomicBool = AtomicBool::new(false);
+
+/// Global flag: when true (`--headless`), no window or tray icon is shown
+/// and the remote API is served, so another ZestBay can manage this
+/// machine's graph over the network.
+pub static HEADLESS: AtomicBool = AtomicBool::new(false);
+
 fn main() {
-    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
+    crash_report::init_logging();
 
     let args: Vec<String> = std::env::args().collect();
 
@@ -49,9 +74,64 @@ fn main() {
         NO_PROBE.store(true, Ordering::SeqCst);
     }
 
+    let remote = args
+        .iter()
+        .enumerate()
+        .find_map(|(i, a)| match a.strip_prefix("--remote") {
+            Some("") => args.get(i + 1).cloned(),
+            Some(value) => value.strip_prefix('=').map(str::to_string),
+            None => None,
+        });
+    if let Some(remote) = remote {
+        log::info!("Using PipeWire instance {} via --remote", remote);
+        let _ = PIPEWIRE_REMOTE.set(remote);
+    }
+
+    if args.iter().any(|a| a == "--demo") {
+        log::info!("Demo mode enabled via --demo flag: not connecting to PipeWire");
+        DEMO_MODE.store(true, Ordering::SeqCst);
+    }
+
+    if args.iter().any(|a| a == "--headless") {
+        log::info!("Headless mode enabled via --headless flag: serving the remote API without a window");
+        HEADLESS.store(true, Ordering::SeqCst);
+        // No display is needed; an explicit platform still wins.
+        if std::env::var_os("QT_QPA_PLATFORM").is_none() {
+            // SAFETY: no other threads have been started yet.
+            unsafe { std::env::set_var("QT_QPA_PLATFORM", "offscreen") };
+        }
+    }
+
+    if !DEMO_MODE.load(Ordering::SeqCst) && portal::activate_running_instance() {
+        log::info!("ZestBay is already running; asked it to show its window");
+        return;
+    }
+
+    crash_report::install();
+    let modes: Vec<&str> = [
+        ("safe mode", &SAFE_MODE),
+        ("no probe", &NO_PROBE),
+        ("demo", &DEMO_MODE),
+        ("headless", &HEADLESS),
+    ]
+    .into_iter()
+    .filter(|(_, flag)| flag.load(Ordering::SeqCst))
+    .map(|(name, _)| name)
+    .collect();
+    if modes.is_empty() {
+        crash_report::set_state("Mode", "normal");
+    } else {
+        crash_report::set_state("Mode", modes.join(", "));
+    }
+    crash_report::set_state(
+        "PipeWire remote",
+        PIPEWIRE_REMOTE.get().map_or("default", String::as_str),
+    );
+
     log::info!("Starting ZestBay");
 
-    let mut app = QGuiApplication::new();
+    // A QApplication, so native Qt plugin UIs can create widgets
+    let mut app = lv2::qt_ui::new_application();
 
     QGuiApplication::set_desktop_file_name(&QString::from("zestbay"));
 
@@ -77,7 +157,11 @@ fn main() {
             .release();
     }
 
-    let url = QUrl::from("qrc:/qt/qml/ZestBay/qml/main.qml");
+    let url = if HEADLESS.load(Ordering::SeqCst) {
+        QUrl::from("qrc:/qt/qml/ZestBay/qml/Headless.qml")
+    } else {
+        QUrl::from("qrc:/qt/qml/ZestBay/qml/main.qml")
+    };
     log::info!("Loading QML from: {:?}", url.to_string());
     if let Some(engine) = engine.as_mut() {
         engine.load(&url);
@@ -100,5 +184,7 @@ fn main() {
 
     log::info!("Qt event loop exited");
 
+    control::flush();
+    lv2::ui::close_qt_windows();
     lv2::ui::shutdown_gtk_thread();
 }
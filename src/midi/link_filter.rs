@@ -0,0 +1,257 @@
+//! The internal node a filtered MIDI link runs through.
+//!
+//! One MIDI input, one MIDI output; every event is copied across unless the
+//! link's [`MidiLinkOptions`] drop it. The node is named after its filter
+//! id (see [`filter_node_name`]) so the UI can find it in the graph and
+//! draw the two links through it as one.
+
+use std::ffi::CString;
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+
+use pipewire::core::CoreRc;
+
+pub use zestbay_core::midi::link_filter::*;
+
+pub struct MidiLinkFilterNode {
+    filter: *mut pipewire::sys::pw_filter,
+    _hook: Box<libspa::sys::spa_hook>,
+    _events: Box<pipewire::sys::pw_filter_events>,
+    user_data: *mut FilterData,
+    _core: CoreRc,
+}
+
+struct FilterData {
+    shutting_down: AtomicBool,
+    /// [`MidiLinkOptions::to_bits`], changed from the main loop.
+    options: AtomicU8,
+    state: MidiLinkFilterState,
+    in_port: *mut std::ffi::c_void,
+    out_port: *mut std::ffi::c_void,
+}
+
+unsafe impl Send for FilterData {}
+
+#[repr(C)]
+struct PortData {
+    index: u32,
+}
+
+impl MidiLinkFilterNode {
+    pub fn new(
+        core: &CoreRc,
+        filter_id: u32,
+        options: MidiLinkOptions,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let node_name = CString::new(filter_node_name(filter_id)).unwrap();
+        let description = CString::new(format!("ZestBay MIDI Filter {}", filter_id)).unwrap();
+
+        let props = unsafe {
+            let p = pipewire::sys::pw_properties_new(
+                c_str(b"media.type\0"),
+                c_str(b"Midi\0"),
+                c_str(b"media.category\0"),
+                c_str(b"Filter\0"),
+                c_str(b"media.role\0"),
+                c_str(b"DSP\0"),
+                std::ptr::null::<std::os::raw::c_char>(),
+            );
+            let key = CString::new("node.name").unwrap();
+            pipewire::sys::pw_properties_set(p, key.as_ptr(), node_name.as_ptr());
+            let key = CString::new("node.description").unwrap();
+            pipewire::sys::pw_properties_set(p, key.as_ptr(), description.as_ptr());
+            p
+        };
+
+        let core_raw = core.as_raw_ptr();
+        let filter = unsafe { pipewire::sys::pw_filter_new(core_raw, node_name.as_ptr(), props) };
+        if filter.is_null() {
+            return Err("Failed to create MIDI link pw_filter".into());
+        }
+
+        let user_data = Box::into_raw(Box::new(FilterData {
+            shutting_down: AtomicBool::new(false),
+            options: AtomicU8::new(options.to_bits()),
+            state: MidiLinkFilterState::default(),
+            in_port: std::ptr::null_mut(),
+            out_port: std::ptr::null_mut(),
+        }));
+
+        for (direction, name) in [
+            (libspa::sys::SPA_DIRECTION_INPUT, "midi_in"),
+            (libspa::sys::SPA_DIRECTION_OUTPUT, "midi_out"),
+        ] {
+            let port_name = CString::new(name).unwrap();
+            let port_props = unsafe {
+                pipewire::sys::pw_properties_new(
+                    c_str(b"port.name\0"),
+                    port_name.as_ptr(),
+                    c_str(b"format.dsp\0"),
+                    c_str(b"8 bit raw midi\0"),
+                    std::ptr::null::<std::os::raw::c_char>(),
+                )
+            };
+            let port_data = unsafe {
+                pipewire::sys::pw_filter_add_port(
+                    filter,
+                    direction,
+                    pipewire::sys::pw_filter_port_flags_PW_FILTER_PORT_FLAG_MAP_BUFFERS,
+                    std::mem::size_of::<PortData>(),
+                    port_props,
+                    std::ptr::null_mut(),
+                    0,
+                )
+            };
+            if port_data.is_null() {
+                log::error!("Failed to add MIDI link filter port {}", name);
+            } else if direction == libspa::sys::SPA_DIRECTION_INPUT {
+                unsafe { (*user_data).in_port = port_data };
+            } else {
+                unsafe { (*user_data).out_port = port_data };
+            }
+        }
+
+        let events = Box::new(pipewire::sys::pw_filter_events {
+            version: pipewire::sys::PW_VERSION_FILTER_EVENTS,
+            destroy: None,
+            state_changed: None,
+            io_changed: None,
+            param_changed: None,
+            add_buffer: None,
+            remove_buffer: None,
+            process: Some(on_process),
+            drained: None,
+            command: None,
+        });
+
+        let mut hook = Box::new(unsafe { std::mem::zeroed::<libspa::sys::spa_hook>() });
+        unsafe {
+            pipewire::sys::pw_filter_add_listener(
+                filter,
+                hook.as_mut() as *mut libspa::sys::spa_hook,
+                events.as_ref() as *const pipewire::sys::pw_filter_events,
+                user_data as *mut std::ffi::c_void,
+            );
+        }
+
+        let flags = pipewire::sys::pw_filter_flags_PW_FILTER_FLAG_RT_PROCESS;
+        let ret =
+            unsafe { pipewire::sys::pw_filter_connect(filter, flags, std::ptr::null_mut(), 0) };
+        if ret < 0 {
+            unsafe {
+                pipewire::sys::pw_filter_destroy(filter);
+                drop(Box::from_raw(user_data));
+            }
+            return Err(format!("Failed to connect MIDI link pw_filter: error {}", ret).into());
+        }
+
+        log::info!("MIDI link filter {} created", filter_id);
+
+        Ok(Self {
+            filter,
+            _hook: hook,
+            _events: events,
+            user_data,
+            _core: core.clone(),
+        })
+    }
+
+    pub fn set_options(&self, options: MidiLinkOptions) {
+        if !self.user_data.is_null() {
+            unsafe {
+                (*self.user_data)
+                    .options
+                    .store(options.to_bits(), Ordering::Relaxed);
+            }
+        }
+    }
+}
+
+impl Drop for MidiLinkFilterNode {
+    fn drop(&mut self) {
+        if !self.user_data.is_null() {
+            unsafe {
+                (*self.user_data)
+                    .shutting_down
+                    .store(true, Ordering::SeqCst);
+            }
+        }
+
+        if !self.filter.is_null() {
+            unsafe {
+                pipewire::sys::pw_filter_destroy(self.filter);
+            }
+            self.filter = std::ptr::null_mut();
+        }
+
+        if !self.user_data.is_null() {
+            unsafe {
+                drop(Box::from_raw(self.user_data));
+            }
+            self.user_data = std::ptr::null_mut();
+        }
+    }
+}
+
+#[inline]
+fn c_str(bytes: &[u8]) -> *const std::os::raw::c_char {
+    bytes.as_ptr() as *const std::os::raw::c_char
+}
+
+/// RT callback: copy each control of the input sequence that passes the
+/// filter to the output sequence. The output can only be smaller than the
+/// input, which fits the output buffer.
+unsafe extern "C" fn on_process(
+    data: *mut std::ffi::c_void,
+    position: *mut libspa::sys::spa_io_position,
+) {
+    unsafe {
+        let fd = &mut *(data as *mut FilterData);
+
+        if fd.shutting_down.load(Ordering::Acquire) || position.is_null() {
+            return;
+        }
+        if fd.in_port.is_null() || fd.out_port.is_null() {
+            return;
+        }
+
+        let n_samples = (*position).clock.duration as u32;
+        let in_buf = pipewire::sys::pw_filter_get_dsp_buffer(fd.in_port, 0);
+        let out_buf = pipewire::sys::pw_filter_get_dsp_buffer(fd.out_port, n_samples);
+        if out_buf.is_null() {
+            return;
+        }
+        crate::midi::processing::clear_midi_buffer(out_buf);
+        if in_buf.is_null() {
+            return;
+        }
+
+        let options = MidiLinkOptions::from_bits(fd.options.load(Ordering::Relaxed));
+        let in_seq = in_buf as *const libspa::sys::spa_pod_sequence;
+        let out_seq = out_buf as *mut libspa::sys::spa_pod_sequence;
+        let body = &(*in_seq).body;
+        let body_size = (*in_seq).pod.size;
+        let mut out_pos =
+            (out_buf as *mut u8).add(std::mem::size_of::<libspa::sys::spa_pod_sequence>());
+
+        let mut ctrl = libspa::sys::spa_pod_control_first(body);
+        while libspa::sys::spa_pod_control_is_inside(body, body_size, ctrl) {
+            let value_size = (*ctrl).value.size as usize;
+            let keep = if (*ctrl).type_ == libspa::sys::SPA_CONTROL_Midi {
+                let bytes = (&(*ctrl).value as *const libspa::sys::spa_pod as *const u8)
+                    .add(std::mem::size_of::<libspa::sys::spa_pod>());
+                let event = std::slice::from_raw_parts(bytes, value_size);
+                fd.state.passes(options, event)
+            } else {
+                true
+            };
+            if keep {
+                let span =
+                    (std::mem::size_of::<libspa::sys::spa_pod_control>() + value_size + 7) & !7;
+                std::ptr::copy_nonoverlapping(ctrl as *const u8, out_pos, span);
+                out_pos = out_pos.add(span);
+                (*out_seq).pod.size += span as u32;
+            }
+            ctrl = libspa::sys::spa_pod_control_next(ctrl);
+        }
+    }
+}
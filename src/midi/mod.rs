@@ -1,5 +1,6 @@
 pub mod filter;
+pub mod link_filter;
 pub mod processing;
-pub mod types;
 
+pub use zestbay_core::midi::types;
 pub use types::*;
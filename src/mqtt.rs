@@ -0,0 +1,341 @@
+//! Graph status publisher and command listener for MQTT brokers.
+//!
+//! Home-automation setups subscribe to a few retained topics under the
+//! configured base topic and send plain-text commands back:
+//!
+//! - `<base>/status`: JSON snapshot of everything below
+//! - `<base>/default_sink`: description of the default audio sink
+//! - `<base>/playing`, `<base>/recording`: `ON` while any application
+//!   stream is playing to / recording from a device
+//! - `<base>/available`: `online`, or `offline` via the last will
+//! - `<base>/command`: `mute [node]`, `unmute [node]`, `scene <name>`
+//!
+//! Commands are only read when the configured [`ApiScope`] allows them, the
+//! same way the remote API checks a token's scope; a read-only connection
+//! does not subscribe to the command topic at all. The broker login and TLS
+//! are set from the preferences, with `mqtts://` implying TLS.
+//!
+//! The application publishes [`MqttStatus`] snapshots into [`MqttState`] and
+//! executes the commands queued there; the client thread only forwards.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use rumqttc::{Client, Event, LastWill, MqttOptions, Outgoing, Packet, QoS, Transport};
+use serde::Serialize;
+use zestbay_core::access::ApiScope;
+
+const DEFAULT_PORT: u16 = 1883;
+const DEFAULT_TLS_PORT: u16 = 8883;
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct MqttStatus {
+    /// Description of the default audio sink, if the session manager set one.
+    pub default_sink: Option<String>,
+    /// Application streams currently playing to a device.
+    pub playing: Vec<String>,
+    /// Application streams currently recording from a device.
+    pub recording: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MqttCommand {
+    /// Mute or unmute a node by name; `None` means the default sink.
+    Mute { node: Option<String>, mute: bool },
+    /// Switch to the rule set saved under this name.
+    Scene(String),
+}
+
+impl MqttCommand {
+    fn parse(payload: &str) -> Option<Self> {
+        let payload = payload.trim();
+        let (verb, arg) = match payload.split_once(char::is_whitespace) {
+            Some((verb, arg)) => (verb, arg.trim()),
+            None => (payload, ""),
+        };
+        let node = (!arg.is_empty()).then(|| arg.to_string());
+        match verb.to_ascii_lowercase().as_str() {
+            "mute" => Some(MqttCommand::Mute { node, mute: true }),
+            "unmute" => Some(MqttCommand::Mute { node, mute: false }),
+            "scene" => node.map(MqttCommand::Scene),
+            _ => None,
+        }
+    }
+
+    /// The scope the connection needs to run this command.
+    pub fn scope(&self) -> ApiScope {
+        match self {
+            MqttCommand::Mute { .. } | MqttCommand::Scene(_) => ApiScope::Control,
+        }
+    }
+}
+
+/// How to log in to the broker and what its commands may do.
+#[derive(Debug, Clone, Default)]
+pub struct MqttSecurity<'a> {
+    /// Broker login; no credentials are sent while the username is empty.
+    pub username: &'a str,
+    pub password: &'a str,
+    /// Connect over TLS, also implied by an `mqtts://` broker.
+    pub tls: bool,
+    /// PEM file with the CA to trust; empty uses the system roots.
+    pub tls_ca: &'a str,
+    /// Commands needing more than this are ignored.
+    pub scope: ApiScope,
+}
+
+#[derive(Clone)]
+pub struct MqttState {
+    status: Arc<Mutex<(u64, MqttStatus)>>,
+    pub commands: Arc<Mutex<Vec<MqttCommand>>>,
+    stopped: Arc<AtomicBool>,
+}
+
+impl MqttState {
+    fn new() -> Self {
+        Self {
+            status: Arc::new(Mutex::new((0, MqttStatus::default()))),
+            commands: Arc::new(Mutex::new(Vec::new())),
+            stopped: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Disconnect from the broker and end the client threads.
+    pub fn stop(&self) {
+        self.stopped.store(true, Ordering::Release);
+    }
+
+    fn is_stopped(&self) -> bool {
+        self.stopped.load(Ordering::Acquire)
+    }
+
+    /// Publish a new status snapshot. Unchanged snapshots are not resent.
+    pub fn publish(&self, status: MqttStatus) {
+        let mut current = self.status.lock().unwrap_or_else(|e| e.into_inner());
+        if current.1 != status {
+            current.0 += 1;
+            current.1 = status;
+        }
+    }
+
+    /// Make the publisher resend the current status, e.g. after the broker
+    /// dropped a non-persistent session.
+    fn touch(&self) {
+        self.status.lock().unwrap_or_else(|e| e.into_inner()).0 += 1;
+    }
+
+    fn snapshot(&self) -> (u64, MqttStatus) {
+        self.status
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+}
+
+/// Split `[mqtt[s]://]host[:port]` into host, port and whether the scheme
+/// asks for TLS, defaulting to the standard port for it.
+fn parse_broker(broker: &str) -> Result<(String, u16, bool), String> {
+    let broker = broker.trim();
+    let (broker, tls) = match broker.strip_prefix("mqtts://") {
+        Some(rest) => (rest, true),
+        None => (broker.strip_prefix("mqtt://").unwrap_or(broker), false),
+    };
+    let default_port = if tls { DEFAULT_TLS_PORT } else { DEFAULT_PORT };
+    match broker.rsplit_once(':') {
+        Some((host, port)) if !host.is_empty() => {
+            let port = port
+                .parse::<u16>()
+                .map_err(|_| format!("Invalid MQTT broker port: {}", port))?;
+            Ok((host.to_string(), port, tls))
+        }
+        _ if !broker.is_empty() => Ok((broker.to_string(), default_port, tls)),
+        _ => Err("No MQTT broker configured".into()),
+    }
+}
+
+/// TLS transport trusting the CA in `ca_path`, or the system roots.
+fn tls_transport(ca_path: &str) -> Result<Transport, String> {
+    let ca_path = ca_path.trim();
+    if ca_path.is_empty() {
+        return Ok(Transport::tls_with_default_config());
+    }
+    let ca = std::fs::read(ca_path)
+        .map_err(|e| format!("Cannot read MQTT CA certificate {}: {}", ca_path, e))?;
+    Ok(Transport::tls(ca, None, None))
+}
+
+fn on_off(active: bool) -> &'static str {
+    if active { "ON" } else { "OFF" }
+}
+
+fn publish_status(client: &Client, base: &str, status: &MqttStatus) {
+    let json = serde_json::to_string(status).unwrap_or_default();
+    let topics = [
+        (format!("{}/status", base), json),
+        (
+            format!("{}/default_sink", base),
+            status.default_sink.clone().unwrap_or_default(),
+        ),
+        (
+            format!("{}/playing", base),
+            on_off(!status.playing.is_empty()).to_string(),
+        ),
+        (
+            format!("{}/recording", base),
+            on_off(!status.recording.is_empty()).to_string(),
+        ),
+    ];
+    for (topic, payload) in topics {
+        if let Err(e) = client.publish(topic, QoS::AtLeastOnce, true, payload) {
+            log::warn!("MQTT: failed to publish status: {}", e);
+            return;
+        }
+    }
+}
+
+/// Connect to `broker` and keep publishing status under `base_topic` until
+/// the application exits. Reconnects are handled by the client thread.
+pub fn spawn_mqtt(
+    broker: &str,
+    base_topic: &str,
+    security: &MqttSecurity,
+) -> Result<MqttState, String> {
+    let (host, port, tls) = parse_broker(broker)?;
+    let base = base_topic.trim().trim_end_matches('/').to_string();
+    if base.is_empty() {
+        return Err("No MQTT topic configured".into());
+    }
+
+    let availability = format!("{}/available", base);
+    let command_topic = format!("{}/command", base);
+    let mut options = MqttOptions::new(format!("zestbay-{}", std::process::id()), host, port);
+    options.set_keep_alive(Duration::from_secs(30));
+    options.set_last_will(LastWill::new(
+        availability.clone(),
+        "offline",
+        QoS::AtLeastOnce,
+        true,
+    ));
+    if !security.username.is_empty() {
+        options.set_credentials(security.username, security.password);
+    }
+    if tls || security.tls {
+        options.set_transport(tls_transport(security.tls_ca)?);
+    }
+    let accepts_commands = security.scope.allows(ApiScope::Control);
+    let scope = security.scope;
+    let (client, mut connection) = Client::new(options, 16);
+
+    let state = MqttState::new();
+
+    let publisher_state = state.clone();
+    let publisher = client.clone();
+    let publisher_base = base.clone();
+    let availability_topic = availability.clone();
+    std::thread::Builder::new()
+        .name("zestbay-mqtt-publish".into())
+        .spawn(move || {
+            let mut published = None;
+            while !publisher_state.is_stopped() {
+                let (version, status) = publisher_state.snapshot();
+                if published != Some(version) {
+                    publish_status(&publisher, &publisher_base, &status);
+                    published = Some(version);
+                }
+                std::thread::sleep(POLL_INTERVAL);
+            }
+            let _ = publisher.try_publish(availability_topic, QoS::AtLeastOnce, true, "offline");
+            let _ = publisher.try_disconnect();
+        })
+        .map_err(|e| format!("Failed to spawn MQTT thread: {}", e))?;
+
+    let listener_state = state.clone();
+    std::thread::Builder::new()
+        .name("zestbay-mqtt".into())
+        .spawn(move || {
+            for notification in connection.iter() {
+                match notification {
+                    Ok(Event::Incoming(Packet::ConnAck(_))) => {
+                        log::info!("MQTT: connected, publishing under {}", base);
+                        // This thread drives the connection, so it must not
+                        // block on a full request queue.
+                        if accepts_commands {
+                            let _ = client.try_subscribe(command_topic.as_str(), QoS::AtLeastOnce);
+                        }
+                        let _ = client.try_publish(
+                            availability.as_str(),
+                            QoS::AtLeastOnce,
+                            true,
+                            "online",
+                        );
+                        listener_state.touch();
+                    }
+                    Ok(Event::Incoming(Packet::Publish(publish))) => {
+                        if publish.topic != command_topic {
+                            continue;
+                        }
+                        let payload = String::from_utf8_lossy(&publish.payload);
+                        match MqttCommand::parse(&payload) {
+                            Some(command) if !scope.allows(command.scope()) => log::warn!(
+                                "MQTT: ignoring {:?}, commands need the {} scope",
+                                command,
+                                command.scope().as_str()
+                            ),
+                            Some(command) => {
+                                log::info!("MQTT: command {:?}", command);
+                                listener_state
+                                    .commands
+                                    .lock()
+                                    .unwrap_or_else(|e| e.into_inner())
+                                    .push(command);
+                            }
+                            None => log::warn!("MQTT: unknown command {:?}", payload),
+                        }
+                    }
+                    Ok(Event::Outgoing(Outgoing::Disconnect)) => break,
+                    Ok(_) => {}
+                    Err(_) if listener_state.is_stopped() => break,
+                    Err(e) => {
+                        log::warn!("MQTT: connection error: {}", e);
+                        std::thread::sleep(RECONNECT_DELAY);
+                    }
+                }
+            }
+        })
+        .map_err(|e| format!("Failed to spawn MQTT thread: {}", e))?;
+
+    Ok(state)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mqtts_scheme_implies_tls_and_its_port() {
+        assert_eq!(
+            parse_broker("mqtts://broker.lan").unwrap(),
+            ("broker.lan".to_string(), DEFAULT_TLS_PORT, true)
+        );
+        assert_eq!(
+            parse_broker("mqtt://broker.lan:1884").unwrap(),
+            ("broker.lan".to_string(), 1884, false)
+        );
+        assert_eq!(
+            parse_broker("localhost").unwrap(),
+            ("localhost".to_string(), DEFAULT_PORT, false)
+        );
+    }
+
+    #[test]
+    fn test_commands_need_the_control_scope() {
+        let mute = MqttCommand::parse("mute Speakers").unwrap();
+        assert!(!ApiScope::Read.allows(mute.scope()));
+        assert!(ApiScope::Control.allows(mute.scope()));
+        let scene = MqttCommand::parse("scene Evening").unwrap();
+        assert!(!ApiScope::Read.allows(scene.scope()));
+    }
+}
@@ -1,4 +1 @@
-pub mod manager;
-pub mod rules;
-
-pub use manager::PatchbayManager;
+pub use zestbay_core::patchbay::*;
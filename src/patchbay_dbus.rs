@@ -0,0 +1,452 @@
+//! ZestBay's own control interface on the session bus.
+//!
+//! `org.zestbay.Patchbay` offers the everyday operations to desktop widgets,
+//! KDE shortcuts and scripts without the remote API: listing nodes and
+//! links, connecting and disconnecting ports, switching the patchbay rules
+//! on and off, switching scenes and flipping A/B switch nodes. As with the
+//! JACK interface, the application publishes a snapshot into
+//! [`PatchbayDbusState`] and runs the requests queued there; the D-Bus side
+//! reads the snapshot and announces changes with signals, in the manner of
+//! MPRIS.
+//!
+//! ```sh
+//! busctl --user call org.zestbay.Patchbay /org/zestbay/Patchbay \
+//!     org.zestbay.Patchbay SwitchScene s "Live"
+//! ```
+
+use std::sync::mpsc::{self, Sender, TryRecvError};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use zbus::object_server::SignalEmitter;
+
+const BUS_NAME: &str = "org.zestbay.Patchbay";
+const OBJECT_PATH: &str = "/org/zestbay/Patchbay";
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+/// How long a connect waits for the application to say whether it was made.
+const REPLY_TIMEOUT: Duration = Duration::from_secs(2);
+const REPLY_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BusPort {
+    pub id: u32,
+    pub name: String,
+    pub is_output: bool,
+    /// `Audio`, `Midi`, `Video` or `Cv`.
+    pub media: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BusNode {
+    pub id: u32,
+    pub name: String,
+    pub description: String,
+    /// The node type as rules name it, such as `Sink` or `Plugin`.
+    pub kind: String,
+    pub ports: Vec<BusPort>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BusLink {
+    pub id: u32,
+    pub output_port: u32,
+    pub input_port: u32,
+}
+
+/// What the interface shows, as published by the application.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BusSnapshot {
+    pub nodes: Vec<BusNode>,
+    pub links: Vec<BusLink>,
+    pub patchbay_enabled: bool,
+    /// Saved rule backups by name; each is a scene.
+    pub scenes: Vec<String>,
+    /// The scene switched to last; empty before any.
+    pub scene: String,
+}
+
+impl BusSnapshot {
+    fn find_port(&self, port_id: u32) -> Option<(&BusNode, &BusPort)> {
+        self.nodes
+            .iter()
+            .find_map(|n| n.ports.iter().find(|p| p.id == port_id).map(|p| (n, p)))
+    }
+
+    /// A port by node name or description and port name.
+    fn find_port_by_name(&self, node: &str, port: &str) -> Option<&BusPort> {
+        self.nodes
+            .iter()
+            .filter(|n| n.name == node || n.description == node)
+            .find_map(|n| n.ports.iter().find(|p| p.name == port))
+    }
+
+    /// The (output, input) port pair for two ports given in either order.
+    fn ordered_pair(&self, a: u32, b: u32) -> Option<(u32, u32)> {
+        let (_, pa) = self.find_port(a)?;
+        let (_, pb) = self.find_port(b)?;
+        match (pa.is_output, pb.is_output) {
+            (true, false) => Some((a, b)),
+            (false, true) => Some((b, a)),
+            _ => None,
+        }
+    }
+
+    fn same_graph(&self, other: &BusSnapshot) -> bool {
+        self.nodes == other.nodes && self.links == other.links
+    }
+}
+
+/// A change asked for over D-Bus, run by the application.
+#[derive(Debug, Clone)]
+pub enum ControlRequest {
+    /// Answered on `reply` with the feedback loop the link would close, if
+    /// it was refused for that.
+    Connect {
+        output_port_id: u32,
+        input_port_id: u32,
+        allow_feedback: bool,
+        reply: Sender<Result<(), String>>,
+    },
+    Disconnect {
+        link_id: u32,
+    },
+    SetPatchbayEnabled(bool),
+    SwitchScene(String),
+    ToggleAbSwitches,
+}
+
+#[derive(Clone)]
+pub struct PatchbayDbusState {
+    snapshot: Arc<Mutex<(u64, BusSnapshot)>>,
+    pub requests: Arc<Mutex<Vec<ControlRequest>>>,
+}
+
+impl PatchbayDbusState {
+    fn new() -> Self {
+        Self {
+            snapshot: Arc::new(Mutex::new((0, BusSnapshot::default()))),
+            requests: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    /// Publish a new snapshot. The scene is kept from the last one; it
+    /// changes through [`Self::set_scene`]. The version only moves when
+    /// something changed.
+    pub fn publish(&self, mut snapshot: BusSnapshot) {
+        let mut current = self.snapshot.lock().unwrap_or_else(|e| e.into_inner());
+        snapshot.scene = current.1.scene.clone();
+        if current.1 != snapshot {
+            current.0 += 1;
+            current.1 = snapshot;
+        }
+    }
+
+    /// Note the scene switched to.
+    pub fn set_scene(&self, scene: &str) {
+        let mut current = self.snapshot.lock().unwrap_or_else(|e| e.into_inner());
+        if current.1.scene != scene {
+            current.0 += 1;
+            current.1.scene = scene.to_string();
+        }
+    }
+
+    fn snapshot(&self) -> (u64, BusSnapshot) {
+        self.snapshot
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    fn request(&self, request: ControlRequest) {
+        self.requests
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(request);
+    }
+}
+
+fn failed(message: &str) -> zbus::fdo::Error {
+    zbus::fdo::Error::Failed(message.to_string())
+}
+
+struct Patchbay {
+    state: PatchbayDbusState,
+}
+
+impl Patchbay {
+    /// Queue a connect and wait for the application to run it.
+    async fn request_connect(
+        &self,
+        port1_id: u32,
+        port2_id: u32,
+        allow_feedback: bool,
+    ) -> zbus::fdo::Result<()> {
+        let (_, snapshot) = self.state.snapshot();
+        let (output_port_id, input_port_id) = snapshot
+            .ordered_pair(port1_id, port2_id)
+            .ok_or_else(|| failed("ports cannot be connected"))?;
+        let (reply, replies) = mpsc::channel();
+        self.state.request(ControlRequest::Connect {
+            output_port_id,
+            input_port_id,
+            allow_feedback,
+            reply,
+        });
+        let asked = Instant::now();
+        loop {
+            match replies.try_recv() {
+                Ok(result) => {
+                    return result.map_err(|path| {
+                        failed(&format!("the link closes a feedback loop: {}", path))
+                    });
+                }
+                Err(TryRecvError::Disconnected) => return Err(failed("request dropped")),
+                Err(TryRecvError::Empty) if asked.elapsed() > REPLY_TIMEOUT => {
+                    return Err(failed("no answer from ZestBay"));
+                }
+                Err(TryRecvError::Empty) => tokio::time::sleep(REPLY_POLL_INTERVAL).await,
+            }
+        }
+    }
+}
+
+#[zbus::interface(name = "org.zestbay.Patchbay")]
+impl Patchbay {
+    /// (id, name, description, type) of every node.
+    fn list_nodes(&self) -> Vec<(u32, String, String, String)> {
+        let (_, snapshot) = self.state.snapshot();
+        snapshot
+            .nodes
+            .iter()
+            .map(|n| (n.id, n.name.clone(), n.description.clone(), n.kind.clone()))
+            .collect()
+    }
+
+    /// (id, name, direction, media) of a node's ports; direction is `in`
+    /// or `out`.
+    fn list_ports(&self, node_id: u32) -> zbus::fdo::Result<Vec<(u32, String, String, String)>> {
+        let (_, snapshot) = self.state.snapshot();
+        let node = snapshot
+            .nodes
+            .iter()
+            .find(|n| n.id == node_id)
+            .ok_or_else(|| failed("no such node"))?;
+        Ok(node
+            .ports
+            .iter()
+            .map(|p| {
+                let direction = if p.is_output { "out" } else { "in" };
+                (p.id, p.name.clone(), direction.to_string(), p.media.clone())
+            })
+            .collect())
+    }
+
+    /// (id, output node, output port, input node, input port) of every
+    /// link.
+    fn list_links(&self) -> Vec<(u32, u32, u32, u32, u32)> {
+        let (_, snapshot) = self.state.snapshot();
+        snapshot
+            .links
+            .iter()
+            .filter_map(|l| {
+                let (output_node, _) = snapshot.find_port(l.output_port)?;
+                let (input_node, _) = snapshot.find_port(l.input_port)?;
+                Some((
+                    l.id,
+                    output_node.id,
+                    l.output_port,
+                    input_node.id,
+                    l.input_port,
+                ))
+            })
+            .collect()
+    }
+
+    /// Link two ports, given in either order. Fails naming the loop when
+    /// the link would close an audio feedback loop.
+    async fn connect(&self, port1_id: u32, port2_id: u32) -> zbus::fdo::Result<()> {
+        self.request_connect(port1_id, port2_id, false).await
+    }
+
+    /// Link two ports, given in either order, even into a feedback loop.
+    async fn connect_allowing_feedback(
+        &self,
+        port1_id: u32,
+        port2_id: u32,
+    ) -> zbus::fdo::Result<()> {
+        self.request_connect(port1_id, port2_id, true).await
+    }
+
+    /// Link two ports by node name (or description) and port name.
+    async fn connect_by_name(
+        &self,
+        node1: &str,
+        port1: &str,
+        node2: &str,
+        port2: &str,
+    ) -> zbus::fdo::Result<()> {
+        let (_, snapshot) = self.state.snapshot();
+        let a = snapshot
+            .find_port_by_name(node1, port1)
+            .ok_or_else(|| failed("port not found"))?;
+        let b = snapshot
+            .find_port_by_name(node2, port2)
+            .ok_or_else(|| failed("port not found"))?;
+        self.request_connect(a.id, b.id, false).await
+    }
+
+    fn disconnect(&self, link_id: u32) -> zbus::fdo::Result<()> {
+        let (_, snapshot) = self.state.snapshot();
+        if !snapshot.links.iter().any(|l| l.id == link_id) {
+            return Err(failed("no such link"));
+        }
+        self.state.request(ControlRequest::Disconnect { link_id });
+        Ok(())
+    }
+
+    /// Unlink two ports, given in either order.
+    fn disconnect_ports(&self, port1_id: u32, port2_id: u32) -> zbus::fdo::Result<()> {
+        let (_, snapshot) = self.state.snapshot();
+        let (output, input) = snapshot
+            .ordered_pair(port1_id, port2_id)
+            .ok_or_else(|| failed("ports are not connected"))?;
+        let link = snapshot
+            .links
+            .iter()
+            .find(|l| l.output_port == output && l.input_port == input)
+            .ok_or_else(|| failed("ports are not connected"))?;
+        self.state
+            .request(ControlRequest::Disconnect { link_id: link.id });
+        Ok(())
+    }
+
+    fn set_patchbay_enabled(&self, enabled: bool) {
+        self.state
+            .request(ControlRequest::SetPatchbayEnabled(enabled));
+    }
+
+    /// Switch the patchbay rules on or off, returning the new setting.
+    fn toggle_patchbay(&self) -> bool {
+        let enabled = !self.state.snapshot().1.patchbay_enabled;
+        self.set_patchbay_enabled(enabled);
+        enabled
+    }
+
+    fn list_scenes(&self) -> Vec<String> {
+        self.state.snapshot().1.scenes
+    }
+
+    /// Switch to the scene saved under `name`, running its transition.
+    fn switch_scene(&self, name: &str) -> zbus::fdo::Result<()> {
+        let (_, snapshot) = self.state.snapshot();
+        if !snapshot.scenes.iter().any(|s| s.eq_ignore_ascii_case(name)) {
+            return Err(failed("no such scene"));
+        }
+        self.state
+            .request(ControlRequest::SwitchScene(name.to_string()));
+        Ok(())
+    }
+
+    /// Flip every A/B switch node to its other input.
+    fn toggle_ab_switches(&self) {
+        self.state.request(ControlRequest::ToggleAbSwitches);
+    }
+
+    #[zbus(property)]
+    fn patchbay_enabled(&self) -> bool {
+        self.state.snapshot().1.patchbay_enabled
+    }
+
+    #[zbus(property)]
+    fn scene(&self) -> String {
+        self.state.snapshot().1.scene
+    }
+
+    /// Bumped on every change, so clients can tell whether to refetch.
+    #[zbus(property)]
+    fn version(&self) -> u64 {
+        self.state.snapshot().0
+    }
+
+    /// Nodes, ports or links changed.
+    #[zbus(signal)]
+    async fn graph_changed(emitter: &SignalEmitter<'_>, version: u64) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    async fn scene_switched(emitter: &SignalEmitter<'_>, scene: &str) -> zbus::Result<()>;
+}
+
+async fn serve(state: PatchbayDbusState) -> zbus::Result<()> {
+    let connection = zbus::connection::Builder::session()?
+        .name(BUS_NAME)?
+        .serve_at(
+            OBJECT_PATH,
+            Patchbay {
+                state: state.clone(),
+            },
+        )?
+        .build()
+        .await?;
+    let iface = connection
+        .object_server()
+        .interface::<_, Patchbay>(OBJECT_PATH)
+        .await?;
+    log::info!("Patchbay control interface available as {}", BUS_NAME);
+
+    let (mut version, mut snapshot) = state.snapshot();
+    loop {
+        tokio::time::sleep(POLL_INTERVAL).await;
+        let (new_version, new_snapshot) = state.snapshot();
+        if new_version == version {
+            continue;
+        }
+        let emitter = iface.signal_emitter();
+        let patchbay = iface.get().await;
+        let result = async {
+            if !new_snapshot.same_graph(&snapshot) {
+                Patchbay::graph_changed(emitter, new_version).await?;
+            }
+            if new_snapshot.patchbay_enabled != snapshot.patchbay_enabled {
+                patchbay.patchbay_enabled_changed(emitter).await?;
+            }
+            if new_snapshot.scene != snapshot.scene {
+                patchbay.scene_changed(emitter).await?;
+                Patchbay::scene_switched(emitter, &new_snapshot.scene).await?;
+            }
+            patchbay.version_changed(emitter).await
+        }
+        .await;
+        if let Err(e) = result {
+            log::warn!("Patchbay control: failed to emit change signals: {}", e);
+        }
+        version = new_version;
+        snapshot = new_snapshot;
+    }
+}
+
+/// Start serving the control interface on its own thread.
+pub fn spawn_patchbay_dbus() -> PatchbayDbusState {
+    let state = PatchbayDbusState::new();
+    let service_state = state.clone();
+
+    std::thread::Builder::new()
+        .name("zestbay-patchbay-dbus".into())
+        .spawn(move || {
+            let runtime = match tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+            {
+                Ok(rt) => rt,
+                Err(e) => {
+                    log::warn!("Patchbay control: failed to start runtime: {}", e);
+                    return;
+                }
+            };
+            if let Err(e) = runtime.block_on(serve(service_state)) {
+                log::warn!("Patchbay control interface unavailable: {}", e);
+            }
+        })
+        .expect("Failed to spawn patchbay D-Bus thread");
+
+    state
+}
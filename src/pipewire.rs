@@ -1,8 +1,9 @@
+mod link_gain;
 mod manager;
-pub mod state;
-mod types;
+mod probe;
+mod simulation;
 
-pub use state::GraphState;
-pub use types::*;
+pub use zestbay_core::graph::*;
 
 pub use manager::start;
+pub use simulation::start as start_demo;
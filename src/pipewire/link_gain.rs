@@ -0,0 +1,237 @@
+//! The internal node a link with a gain runs through.
+//!
+//! One mono audio input, one mono audio output, with the link's gain in
+//! between. The node is named after its gain id (see [`gain_node_name`]) so
+//! the UI can find it in the graph and draw the two links through it as
+//! one.
+
+use std::ffi::CString;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+
+use pipewire::core::CoreRc;
+
+use crate::patchbay::link_gain::{GainSmoother, db_to_gain, gain_node_name};
+
+pub struct LinkGainNode {
+    filter: *mut pipewire::sys::pw_filter,
+    _hook: Box<libspa::sys::spa_hook>,
+    _events: Box<pipewire::sys::pw_filter_events>,
+    user_data: *mut FilterData,
+    _core: CoreRc,
+}
+
+struct FilterData {
+    shutting_down: AtomicBool,
+    /// The linear gain as `f32` bits, changed from the main loop.
+    gain: AtomicU32,
+    smoother: GainSmoother,
+    in_port: *mut std::ffi::c_void,
+    out_port: *mut std::ffi::c_void,
+}
+
+unsafe impl Send for FilterData {}
+
+#[repr(C)]
+struct PortData {
+    index: u32,
+}
+
+impl LinkGainNode {
+    pub fn new(
+        core: &CoreRc,
+        gain_id: u32,
+        gain_db: f32,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let node_name = CString::new(gain_node_name(gain_id)).unwrap();
+        let description = CString::new(format!("ZestBay Link Gain {}", gain_id)).unwrap();
+
+        let props = unsafe {
+            let p = pipewire::sys::pw_properties_new(
+                c_str(b"media.type\0"),
+                c_str(b"Audio\0"),
+                c_str(b"media.category\0"),
+                c_str(b"Filter\0"),
+                c_str(b"media.role\0"),
+                c_str(b"DSP\0"),
+                c_str(b"node.virtual\0"),
+                c_str(b"true\0"),
+                std::ptr::null::<std::os::raw::c_char>(),
+            );
+            let key = CString::new("node.name").unwrap();
+            pipewire::sys::pw_properties_set(p, key.as_ptr(), node_name.as_ptr());
+            let key = CString::new("node.description").unwrap();
+            pipewire::sys::pw_properties_set(p, key.as_ptr(), description.as_ptr());
+            p
+        };
+
+        let core_raw = core.as_raw_ptr();
+        let filter = unsafe { pipewire::sys::pw_filter_new(core_raw, node_name.as_ptr(), props) };
+        if filter.is_null() {
+            return Err("Failed to create link gain pw_filter".into());
+        }
+
+        let gain = db_to_gain(gain_db);
+        let user_data = Box::into_raw(Box::new(FilterData {
+            shutting_down: AtomicBool::new(false),
+            gain: AtomicU32::new(gain.to_bits()),
+            smoother: GainSmoother::new(gain),
+            in_port: std::ptr::null_mut(),
+            out_port: std::ptr::null_mut(),
+        }));
+
+        for (direction, name) in [
+            (libspa::sys::SPA_DIRECTION_INPUT, "input"),
+            (libspa::sys::SPA_DIRECTION_OUTPUT, "output"),
+        ] {
+            let port_name = CString::new(name).unwrap();
+            let port_props = unsafe {
+                pipewire::sys::pw_properties_new(
+                    c_str(b"port.name\0"),
+                    port_name.as_ptr(),
+                    c_str(b"format.dsp\0"),
+                    c_str(b"32 bit float mono audio\0"),
+                    std::ptr::null::<std::os::raw::c_char>(),
+                )
+            };
+            let port_data = unsafe {
+                pipewire::sys::pw_filter_add_port(
+                    filter,
+                    direction,
+                    pipewire::sys::pw_filter_port_flags_PW_FILTER_PORT_FLAG_MAP_BUFFERS,
+                    std::mem::size_of::<PortData>(),
+                    port_props,
+                    std::ptr::null_mut(),
+                    0,
+                )
+            };
+            if port_data.is_null() {
+                log::error!("Failed to add link gain port {}", name);
+            } else if direction == libspa::sys::SPA_DIRECTION_INPUT {
+                unsafe { (*user_data).in_port = port_data };
+            } else {
+                unsafe { (*user_data).out_port = port_data };
+            }
+        }
+
+        let events = Box::new(pipewire::sys::pw_filter_events {
+            version: pipewire::sys::PW_VERSION_FILTER_EVENTS,
+            destroy: None,
+            state_changed: None,
+            io_changed: None,
+            param_changed: None,
+            add_buffer: None,
+            remove_buffer: None,
+            process: Some(on_process),
+            drained: None,
+            command: None,
+        });
+
+        let mut hook = Box::new(unsafe { std::mem::zeroed::<libspa::sys::spa_hook>() });
+        unsafe {
+            pipewire::sys::pw_filter_add_listener(
+                filter,
+                hook.as_mut() as *mut libspa::sys::spa_hook,
+                events.as_ref() as *const pipewire::sys::pw_filter_events,
+                user_data as *mut std::ffi::c_void,
+            );
+        }
+
+        let flags = pipewire::sys::pw_filter_flags_PW_FILTER_FLAG_RT_PROCESS;
+        let ret =
+            unsafe { pipewire::sys::pw_filter_connect(filter, flags, std::ptr::null_mut(), 0) };
+        if ret < 0 {
+            unsafe {
+                pipewire::sys::pw_filter_destroy(filter);
+                drop(Box::from_raw(user_data));
+            }
+            return Err(format!("Failed to connect link gain pw_filter: error {}", ret).into());
+        }
+
+        log::info!("Link gain {} created at {:+.1} dB", gain_id, gain_db);
+
+        Ok(Self {
+            filter,
+            _hook: hook,
+            _events: events,
+            user_data,
+            _core: core.clone(),
+        })
+    }
+
+    pub fn set_gain(&self, gain_db: f32) {
+        if !self.user_data.is_null() {
+            unsafe {
+                (*self.user_data)
+                    .gain
+                    .store(db_to_gain(gain_db).to_bits(), Ordering::Relaxed);
+            }
+        }
+    }
+}
+
+impl Drop for LinkGainNode {
+    fn drop(&mut self) {
+        if !self.user_data.is_null() {
+            unsafe {
+                (*self.user_data)
+                    .shutting_down
+                    .store(true, Ordering::SeqCst);
+            }
+        }
+
+        if !self.filter.is_null() {
+            unsafe {
+                pipewire::sys::pw_filter_destroy(self.filter);
+            }
+            self.filter = std::ptr::null_mut();
+        }
+
+        if !self.user_data.is_null() {
+            unsafe {
+                drop(Box::from_raw(self.user_data));
+            }
+            self.user_data = std::ptr::null_mut();
+        }
+    }
+}
+
+#[inline]
+fn c_str(bytes: &[u8]) -> *const std::os::raw::c_char {
+    bytes.as_ptr() as *const std::os::raw::c_char
+}
+
+/// RT callback: the input times the gain, ramped when it changed.
+unsafe extern "C" fn on_process(
+    data: *mut std::ffi::c_void,
+    position: *mut libspa::sys::spa_io_position,
+) {
+    unsafe {
+        let fd = &mut *(data as *mut FilterData);
+
+        if fd.shutting_down.load(Ordering::Acquire) || position.is_null() {
+            return;
+        }
+        if fd.in_port.is_null() || fd.out_port.is_null() {
+            return;
+        }
+
+        let n_samples = (*position).clock.duration as u32;
+        if n_samples == 0 || n_samples > 8192 {
+            return;
+        }
+        let out_buf = pipewire::sys::pw_filter_get_dsp_buffer(fd.out_port, n_samples);
+        if out_buf.is_null() {
+            return;
+        }
+        let output = std::slice::from_raw_parts_mut(out_buf as *mut f32, n_samples as usize);
+        let in_buf = pipewire::sys::pw_filter_get_dsp_buffer(fd.in_port, n_samples);
+        if in_buf.is_null() {
+            output.fill(0.0);
+            return;
+        }
+        let input = std::slice::from_raw_parts(in_buf as *const f32, n_samples as usize);
+
+        let gain = f32::from_bits(fd.gain.load(Ordering::Relaxed));
+        fd.smoother.process(gain, input, output);
+    }
+}
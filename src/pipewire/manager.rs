@@ -1,25 +1,37 @@
-use std::cell::RefCell;
-use std::collections::HashMap;
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::rc::Rc;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicU32, Ordering};
 use std::sync::mpsc::{Receiver, Sender};
 use std::time::{Duration, Instant};
 
+use libspa::param::audio::{AudioFormat, AudioInfoRaw};
 use libspa::utils::dict::DictRef;
 use pipewire::{
-    context::ContextRc, link::Link as PwLink, main_loop::MainLoopRc, registry::GlobalObject,
+    context::ContextRc,
+    link::{Link as PwLink, LinkListener, LinkState as PwLinkState},
+    main_loop::MainLoopRc,
+    metadata::{Metadata, MetadataListener},
+    proxy::{ProxyListener, ProxyT},
+    registry::GlobalObject,
     types::ObjectType,
 };
 
-use super::state::GraphState;
+use super::conversion::{ConvertParam, ConvertValue};
+use super::heartbeat::Heartbeat;
+use super::state::{GraphState, PruneReport};
+use super::transaction::{LinkStep, Transaction, TxOutcome};
 use super::types::*;
+use crate::patchbay::crossfade;
 
 #[derive(Debug)]
 enum InternalOp {
     Connect {
         output_port_id: ObjectId,
         input_port_id: ObjectId,
+        /// Number of earlier attempts that failed; 0 for a fresh request.
+        attempt: u32,
     },
     Disconnect {
         link_id: ObjectId,
@@ -30,10 +42,22 @@ enum InternalOp {
         display_name: String,
         format: String,
         lv2_state: Vec<crate::lv2::state::StateEntry>,
+        oversampling: u32,
+    },
+    ReloadPlugin {
+        plugin_uri: String,
+        instance_id: u64,
+        display_name: String,
+        format: String,
+        oversampling: u32,
     },
     RemovePlugin {
         instance_id: u64,
     },
+    /// Destroy an instance; RemovePlugin lets its tail ring out first.
+    DropPlugin {
+        instance_id: u64,
+    },
     OpenPluginUI {
         instance_id: u64,
     },
@@ -42,10 +66,16 @@ enum InternalOp {
     },
 }
 
+/// Start the PipeWire thread. It bumps `heartbeat` on every tick of its
+/// main loop, and while waiting for the daemon, so the UI can tell when it
+/// stops responding.
 pub fn start(
     graph: Arc<GraphState>,
     tick_interval_ms: u64,
     operation_cooldown_ms: u64,
+    connect_retries: u32,
+    remote: Option<String>,
+    heartbeat: Heartbeat,
 ) -> (Receiver<PwEvent>, Sender<PwCommand>) {
     let (event_tx, event_rx) = std::sync::mpsc::channel();
     let (cmd_tx, cmd_rx) = std::sync::mpsc::channel();
@@ -56,9 +86,17 @@ pub fn start(
     let cooldown = operation_cooldown_ms.max(1);
 
     std::thread::spawn(move || {
-        if let Err(e) =
-            run_pipewire_thread(graph, event_tx.clone(), cmd_rx, cmd_tx_for_pw, tick, cooldown)
-        {
+        if let Err(e) = run_pipewire_thread(
+            graph,
+            event_tx.clone(),
+            cmd_rx,
+            cmd_tx_for_pw,
+            tick,
+            cooldown,
+            connect_retries,
+            remote,
+            heartbeat,
+        ) {
             log::error!("PipeWire thread error: {}", e);
             let _ = event_tx.send(PwEvent::Error(e.to_string()));
         }
@@ -67,6 +105,56 @@ pub fn start(
     (event_rx, cmd_tx)
 }
 
+/// Backoff between attempts to reach a daemon that is not up yet.
+const CONNECT_WAIT_MIN: Duration = Duration::from_millis(500);
+const CONNECT_WAIT_MAX: Duration = Duration::from_secs(5);
+
+/// Connect to the PipeWire daemon, waiting for it if ZestBay started first,
+/// e.g. when autostart races the PipeWire user service. Gives up only when
+/// the UI has gone away.
+///
+/// `remote` is a socket name in the runtime directory or an absolute socket
+/// path; `None` uses `PIPEWIRE_REMOTE` or the default instance.
+fn connect_when_available(
+    context: &ContextRc,
+    remote: Option<&str>,
+    event_tx: &Sender<PwEvent>,
+    heartbeat: &Heartbeat,
+) -> Result<pipewire::core::CoreRc, Box<dyn std::error::Error>> {
+    let mut attempt = 0;
+    let mut wait = CONNECT_WAIT_MIN;
+    loop {
+        heartbeat.beat();
+        let props = remote.map(|name| {
+            pipewire::properties::properties! {
+                *pipewire::keys::REMOTE_NAME => name,
+            }
+        });
+        match context.connect_rc(props) {
+            Ok(core) => {
+                if attempt > 0 {
+                    log::info!("Connected to PipeWire after {} attempts", attempt + 1);
+                }
+                let _ = event_tx.send(PwEvent::Connected);
+                return Ok(core);
+            }
+            Err(e) => {
+                attempt += 1;
+                if attempt == 1 {
+                    log::warn!(
+                        "PipeWire instance {} is not available ({}), waiting for it",
+                        remote.unwrap_or("(default)"),
+                        e
+                    );
+                }
+                event_tx.send(PwEvent::WaitingForDaemon { attempt })?;
+                std::thread::sleep(wait);
+                wait = (wait * 2).min(CONNECT_WAIT_MAX);
+            }
+        }
+    }
+}
+
 fn run_pipewire_thread(
     graph: Arc<GraphState>,
     event_tx: Sender<PwEvent>,
@@ -74,12 +162,15 @@ fn run_pipewire_thread(
     cmd_tx: Sender<PwCommand>,
     tick_interval_ms: u64,
     operation_cooldown_ms: u64,
+    connect_retries: u32,
+    remote: Option<String>,
+    heartbeat: Heartbeat,
 ) -> Result<(), Box<dyn std::error::Error>> {
     pipewire::init();
 
     let mainloop = MainLoopRc::new(None)?;
     let context = ContextRc::new(&mainloop, None)?;
-    let core = context.connect_rc(None)?;
+    let core = connect_when_available(&context, remote.as_deref(), &event_tx, &heartbeat)?;
     let registry = core.get_registry_rc()?;
 
     // Detect the PipeWire graph sample rate and quantum from core properties.
@@ -87,10 +178,94 @@ fn run_pipewire_thread(
     let pw_sample_rate = Rc::new(AtomicU32::new(48000));
     let pw_quantum = Rc::new(AtomicU32::new(1024));
 
+    // Create PipeWire command channel before the core and registry listeners so
+    // that they can queue follow-up commands (resyncs after errors, cleanup
+    // for MIDI device removal).
+    let (pw_cmd_tx, pw_cmd_rx) = pipewire::channel::channel();
+    std::thread::spawn({
+        let pw_cmd_tx = pw_cmd_tx.clone();
+        move || {
+            while let Ok(cmd) = cmd_rx.recv() {
+                if pw_cmd_tx.send(cmd).is_err() {
+                    break;
+                }
+            }
+        }
+    });
+
+    let changes_pending: Rc<RefCell<bool>> = Rc::new(RefCell::new(false));
+
+    // Graph refresh bookkeeping: the globals seen by the re-enumerating
+    // registry, and the core sync that marks the end of the enumeration.
+    let seen_globals: Rc<RefCell<Option<HashSet<ObjectId>>>> = Rc::new(RefCell::new(None));
+    let refresh_seq: Rc<RefCell<Option<libspa::utils::result::AsyncSeq>>> =
+        Rc::new(RefCell::new(None));
+    // Link transactions: the sync issued after the in-flight step, and
+    // whether the server has answered it yet.
+    let tx_sync: Rc<RefCell<Option<(libspa::utils::result::AsyncSeq, bool)>>> =
+        Rc::new(RefCell::new(None));
+    let transactions: Rc<RefCell<VecDeque<Transaction>>> = Rc::new(RefCell::new(VecDeque::new()));
+    let format_watches: FormatWatches = Rc::new(RefCell::new(HashMap::new()));
+
     let _core_listener = {
         let pw_sample_rate = pw_sample_rate.clone();
         let pw_quantum = pw_quantum.clone();
+        let graph = graph.clone();
+        let event_tx = event_tx.clone();
+        let changes_pending = changes_pending.clone();
+        let seen_globals = seen_globals.clone();
+        let refresh_seq = refresh_seq.clone();
+        let pw_cmd_tx = pw_cmd_tx.clone();
+        let tx_sync = tx_sync.clone();
+        let format_watches = format_watches.clone();
+        let last_error_resync: RefCell<Option<Instant>> = RefCell::new(None);
         core.add_listener_local()
+            .error(move |id, seq, res, message| {
+                log::warn!(
+                    "PipeWire error on object {} (seq {}): {} ({})",
+                    id, seq, message, res
+                );
+                // ENOENT means we acted on an object the server no longer
+                // has, i.e. our view of the graph is out of date.
+                if res == -libc::ENOENT {
+                    let due = last_error_resync
+                        .borrow()
+                        .is_none_or(|t| t.elapsed() >= ERROR_RESYNC_INTERVAL);
+                    if due {
+                        *last_error_resync.borrow_mut() = Some(Instant::now());
+                        log::warn!("Graph appears out of sync, scheduling full resync");
+                        let _ = pw_cmd_tx.send(PwCommand::ResyncGraph);
+                    }
+                }
+            })
+            .done(move |id, seq| {
+                if id != pipewire::core::PW_ID_CORE {
+                    return;
+                }
+                if let Some((awaited, reached)) = tx_sync.borrow_mut().as_mut()
+                    && *awaited == seq
+                {
+                    *reached = true;
+                }
+                if *refresh_seq.borrow() != Some(seq) {
+                    return;
+                }
+                *refresh_seq.borrow_mut() = None;
+                let Some(live) = seen_globals.borrow_mut().take() else {
+                    return;
+                };
+                let report = graph.retain_objects(&live);
+                format_watches.borrow_mut().retain(|id, _| live.contains(id));
+                log::info!(
+                    "Graph refresh complete: {} globals live, pruned {} nodes, {} ports, {} links",
+                    live.len(),
+                    report.nodes.len(),
+                    report.ports.len(),
+                    report.links.len(),
+                );
+                emit_prune_events(&event_tx, &report);
+                *changes_pending.borrow_mut() = true;
+            })
             .info(move |info| {
                 if let Some(props) = info.props() {
                     if let Some(rate_str) = props.get("default.clock.rate") {
@@ -117,141 +292,35 @@ fn run_pipewire_thread(
     let pending_ops: Rc<RefCell<Vec<InternalOp>>> = Rc::new(RefCell::new(Vec::new()));
     let last_op_time: Rc<RefCell<Instant>> =
         Rc::new(RefCell::new(Instant::now() - Duration::from_secs(1)));
-    let changes_pending: Rc<RefCell<bool>> = Rc::new(RefCell::new(false));
 
-    // Create PipeWire command channel before the registry listener so that
-    // global_remove can send cleanup commands for MIDI device removal.
-    let (pw_cmd_tx, pw_cmd_rx) = pipewire::channel::channel();
-    std::thread::spawn({
-        let pw_cmd_tx = pw_cmd_tx.clone();
-        move || {
-            while let Ok(cmd) = cmd_rx.recv() {
-                if pw_cmd_tx.send(cmd).is_err() {
-                    break;
-                }
-            }
-        }
-    });
-
-    let _registry_listener = {
-        let graph = graph.clone();
-        let event_tx = event_tx.clone();
-        let changes_pending = changes_pending.clone();
+    let registry_ctx = RegistryContext {
+        graph: graph.clone(),
+        event_tx: event_tx.clone(),
+        changes_pending: changes_pending.clone(),
+        pw_cmd_tx: pw_cmd_tx.clone(),
+        seen: seen_globals.clone(),
+        default_metadata: Rc::new(RefCell::new(None)),
+        format_watches: format_watches.clone(),
+    };
 
-        registry
-            .add_listener_local()
-            .global({
-                let graph = graph.clone();
-                let event_tx = event_tx.clone();
-                let changes_pending = changes_pending.clone();
-
-                move |global| {
-                    match global.type_ {
-                        ObjectType::Node => {
-                            if let Some(node) = parse_node(global) {
-                                if node.node_type == Some(NodeType::Plugin)
-                                    && let Some(props) = global.props.as_ref()
-                                    && let Some(id_str) = props.get("zestbay.plugin.instance_id")
-                                    && let Ok(instance_id) = id_str.parse::<u64>()
-                                {
-                                    let _ = event_tx.send(PwEvent::Plugin(PluginEvent::PluginAdded {
-                                        instance_id,
-                                        pw_node_id: global.id,
-                                        display_name: node.display_name().to_string(),
-                                    }));
-                                }
-                                graph.insert_node(node.clone());
-                                let _ = event_tx.send(PwEvent::NodeChanged(node));
-                                *changes_pending.borrow_mut() = true;
-                            }
-                        }
-                        ObjectType::Port => {
-                            if let Some(port) = parse_port(global, &graph) {
-                                log::debug!(
-                                    "Port registered: id={} node={} name={:?} dir={:?}",
-                                    port.id,
-                                    port.node_id,
-                                    port.name,
-                                    port.direction
-                                );
-                                graph.insert_port(port.clone());
-                                let _ = event_tx.send(PwEvent::PortChanged(port));
-                                *changes_pending.borrow_mut() = true;
-                            } else {
-                                log::debug!(
-                                    "Port global {} could not be parsed (props: {:?})",
-                                    global.id,
-                                    global.props.as_ref().map(|p| props_to_debug(p))
-                                );
-                            }
-                        }
-                        ObjectType::Link => {
-                            if let Some(link) = parse_link_from_props(global) {
-                                graph.insert_link(link.clone());
-                                let _ = event_tx.send(PwEvent::LinkChanged(link));
-                                *changes_pending.borrow_mut() = true;
-                            }
-                        }
-                        _ => {}
-                    }
-                }
-            })
-            .global_remove({
-                let graph = graph.clone();
-                let event_tx = event_tx.clone();
-                let changes_pending = changes_pending.clone();
-                let pw_cmd_tx = pw_cmd_tx.clone();
-
-                move |id| {
-                    if let Some(node) = graph.remove_node(id) {
-                        // If a MIDI bridge node was removed (e.g. USB MIDI device
-                        // unplugged), clean up any MIDI mappings that referenced it
-                        // so stale mappings don't linger in the table.
-                        if node.is_bridge && node.media_type == Some(MediaType::Midi) {
-                            let device_name = node.display_name().to_string();
-                            log::info!(
-                                "MIDI device removed: {} (node {}), cleaning up mappings",
-                                device_name, id,
-                            );
-                            let _ = pw_cmd_tx.send(PwCommand::RemoveMidiMappingsForDevice {
-                                device_name,
-                            });
-                        }
-                        let removed_link_ids = graph.cleanup_node(id);
-                        // Destroy lingering PipeWire link objects and emit
-                        // LinkRemoved so the UI and persistence layer know.
-                        for link_id in removed_link_ids {
-                            let _ = pw_cmd_tx.send(PwCommand::Disconnect { link_id });
-                            let _ = event_tx.send(PwEvent::LinkRemoved(link_id));
-                        }
-                        let _ = event_tx.send(PwEvent::NodeRemoved(id));
-                        *changes_pending.borrow_mut() = true;
-                    } else if let Some(port) = graph.remove_port(id) {
-                        // Also remove any links that referenced this port.
-                        // PipeWire may remove ports before their parent node,
-                        // so stale links must be cleaned up here too.
-                        let removed_link_ids = graph.cleanup_port(id);
-                        // Destroy lingering PipeWire link objects.
-                        for link_id in removed_link_ids {
-                            let _ = pw_cmd_tx.send(PwCommand::Disconnect { link_id });
-                            let _ = event_tx.send(PwEvent::LinkRemoved(link_id));
-                        }
-                        let _ = event_tx.send(PwEvent::PortRemoved {
-                            port_id: id,
-                            node_id: port.node_id,
-                        });
-                        *changes_pending.borrow_mut() = true;
-                    } else if graph.remove_link(id).is_some() {
-                        let _ = event_tx.send(PwEvent::LinkRemoved(id));
-                        *changes_pending.borrow_mut() = true;
-                    }
-                }
-            })
-            .register()
+    // The registry proxy is swapped out when the graph is refreshed, so it
+    // lives in a shared slot together with its listener.
+    let registry_slot: RegistrySlot = {
+        let listener = register_registry_listener(&registry, &registry_ctx);
+        Rc::new(RefCell::new((registry, listener)))
     };
 
     let (internal_tx, internal_rx) = pipewire::channel::channel::<InternalOp>();
 
+    // Connect requests waiting on PipeWire's verdict, and failed ones waiting
+    // for their backoff to expire.
+    let link_attempts: Rc<RefCell<Vec<LinkAttempt>>> = Rc::new(RefCell::new(Vec::new()));
+    let connect_retries_due: Rc<RefCell<Vec<ConnectRetry>>> = Rc::new(RefCell::new(Vec::new()));
+    // Removed plugins letting their tails ring out.
+    let ringing_out: Rc<RefCell<Vec<RingingOut>>> = Rc::new(RefCell::new(Vec::new()));
+    // Node volumes being ramped for crossfades.
+    let volume_ramps: Rc<RefCell<Vec<VolumeRamp>>> = Rc::new(RefCell::new(Vec::new()));
+
     let lv2_instances: Rc<
         RefCell<HashMap<u64, std::rc::Rc<RefCell<crate::lv2::host::Lv2PluginInstance>>>>,
     > = Rc::new(RefCell::new(HashMap::new()));
@@ -271,13 +340,34 @@ fn run_pipewire_thread(
     let vst3_filters: Rc<RefCell<HashMap<u64, crate::vst3::filter::Vst3FilterNode>>> =
         Rc::new(RefCell::new(HashMap::new()));
 
+    let builtin_instances: GlobalSharedMutHashMap<u64, crate::builtin::BuiltinPluginInstance> =
+        Rc::new(RefCell::new(HashMap::new()));
+    let builtin_filters: Rc<RefCell<HashMap<u64, crate::builtin::filter::BuiltinFilterNode>>> =
+        Rc::new(RefCell::new(HashMap::new()));
+
     // MIDI controller mapping state
     let midi_mapping_table: Rc<RefCell<crate::midi::MidiMappingTable>> =
         Rc::new(RefCell::new(crate::midi::MidiMappingTable::new()));
     let midi_learn_state: Rc<RefCell<Option<crate::midi::MidiLearnState>>> =
         Rc::new(RefCell::new(None));
+    // Internal nodes of filtered MIDI links, by filter id
+    let midi_link_filters: Rc<RefCell<HashMap<u32, crate::midi::link_filter::MidiLinkFilterNode>>> =
+        Rc::new(RefCell::new(HashMap::new()));
+    // Internal nodes of links with a gain, by gain id
+    let link_gains: Rc<RefCell<HashMap<u32, super::link_gain::LinkGainNode>>> =
+        Rc::new(RefCell::new(HashMap::new()));
+    // Running signal probes, by probe id
+    let probes: Rc<RefCell<HashMap<u32, super::probe::ProbeNode>>> =
+        Rc::new(RefCell::new(HashMap::new()));
+
+    // The tick timer, for SetTickInterval. It is created after the command
+    // receiver but outlives the main loop run, so the command handler never
+    // sees it dangling.
+    let tick_timer: Rc<Cell<*const pipewire::loop_::TimerSource<'static>>> =
+        Rc::new(Cell::new(std::ptr::null()));
 
     let _cmd_receiver = pw_cmd_rx.attach(mainloop.loop_(), {
+        let tick_timer = tick_timer.clone();
         let pending_ops = pending_ops.clone();
         let lv2_instances = lv2_instances.clone();
         let lv2_filters = lv2_filters.clone();
@@ -285,12 +375,51 @@ fn run_pipewire_thread(
         let clap_filters = clap_filters.clone();
         let vst3_instances = vst3_instances.clone();
         let vst3_filters = vst3_filters.clone();
+        let builtin_instances = builtin_instances.clone();
+        let builtin_filters = builtin_filters.clone();
+        let pw_sample_rate = pw_sample_rate.clone();
         let event_tx = event_tx.clone();
         let midi_mapping_table = midi_mapping_table.clone();
         let midi_learn_state = midi_learn_state.clone();
+        let midi_link_filters = midi_link_filters.clone();
+        let link_gains = link_gains.clone();
+        let probes = probes.clone();
+        let volume_ramps = volume_ramps.clone();
+        let core = core.clone();
+        let registry_slot = registry_slot.clone();
+        let registry_ctx = registry_ctx.clone();
+        let refresh_seq = refresh_seq.clone();
+        let changes_pending = changes_pending.clone();
+        let transactions = transactions.clone();
+        let mainloop = mainloop.clone();
 
         move |cmd| {
             match cmd {
+                PwCommand::Shutdown => {
+                    log::info!("PipeWire thread shutting down");
+                    mainloop.quit();
+                }
+                PwCommand::RefreshGraph => {
+                    start_graph_refresh(&core, &registry_slot, &registry_ctx, &refresh_seq);
+                }
+                PwCommand::Transaction { label, ops } => {
+                    log::debug!("Queued link transaction '{}' ({} ops)", label, ops.len());
+                    transactions
+                        .borrow_mut()
+                        .push_back(Transaction::new(label, ops));
+                }
+                PwCommand::ResyncGraph => {
+                    // Plugin filters live on this thread and are untouched;
+                    // their nodes are rediscovered like any other global.
+                    log::info!("Full graph resync: dropping local graph state");
+                    registry_ctx.graph.clear();
+                    registry_ctx.format_watches.borrow_mut().clear();
+                    *changes_pending.borrow_mut() = true;
+                    // Supersede any refresh in flight: its registry is replaced
+                    // and its sync reply will no longer match.
+                    *refresh_seq.borrow_mut() = None;
+                    start_graph_refresh(&core, &registry_slot, &registry_ctx, &refresh_seq);
+                }
                 PwCommand::SetPluginParameter {
                     instance_id,
                     port_index,
@@ -317,8 +446,44 @@ fn run_pipewire_thread(
                             port_index,
                             value,
                         }));
+                    } else if let Some(instance) = builtin_instances.borrow().get(&instance_id) {
+                        instance.borrow_mut().set_parameter(port_index, value);
+                        let _ = event_tx.send(PwEvent::Plugin(PluginEvent::ParameterChanged {
+                            instance_id,
+                            port_index,
+                            value,
+                        }));
                     }
                 }
+                PwCommand::SetNodeMute { node_id, mute } => {
+                    set_node_mute(&registry_slot.borrow().0, node_id, mute);
+                }
+                PwCommand::SetNodeConversion { node_id, params } => {
+                    set_node_conversion(&registry_slot.borrow().0, node_id, &params);
+                }
+                PwCommand::RampNodeVolume {
+                    node_id,
+                    from,
+                    to,
+                    duration_ms,
+                } => {
+                    let mut ramps = volume_ramps.borrow_mut();
+                    ramps.retain(|r| r.node_id != node_id);
+                    let start = if duration_ms == 0 { to } else { from };
+                    set_node_volume(&registry_slot.borrow().0, node_id, start);
+                    if duration_ms > 0 {
+                        ramps.push(VolumeRamp {
+                            node_id,
+                            from,
+                            to,
+                            start: Instant::now(),
+                            duration: Duration::from_millis(duration_ms as u64),
+                        });
+                    }
+                }
+                PwCommand::SetDefaultSink { node_name } => {
+                    set_default_sink(&registry_ctx.default_metadata, &node_name);
+                }
                 PwCommand::SetPluginBypass {
                     instance_id,
                     bypassed,
@@ -329,6 +494,25 @@ fn run_pipewire_thread(
                         instance.borrow_mut().bypassed = bypassed;
                     } else if let Some(instance) = vst3_instances.borrow().get(&instance_id) {
                         instance.borrow_mut().bypassed = bypassed;
+                    } else if let Some(instance) = builtin_instances.borrow().get(&instance_id) {
+                        instance.borrow_mut().bypassed = bypassed;
+                    }
+                }
+                PwCommand::SetPluginQuantum {
+                    instance_id,
+                    quantum,
+                } => {
+                    let rate = pw_sample_rate.load(Ordering::Relaxed);
+                    let latency = quantum.map(|q| crate::plugin::quantum::node_latency(q, rate));
+                    let latency = latency.as_deref();
+                    if let Some(filter) = lv2_filters.borrow().get(&instance_id) {
+                        filter.set_latency(latency);
+                    } else if let Some(filter) = clap_filters.borrow().get(&instance_id) {
+                        filter.set_latency(latency);
+                    } else if let Some(filter) = vst3_filters.borrow().get(&instance_id) {
+                        filter.set_latency(latency);
+                    } else if let Some(filter) = builtin_filters.borrow().get(&instance_id) {
+                        filter.set_latency(latency);
                     }
                 }
                 PwCommand::StartMidiLearn {
@@ -428,6 +612,91 @@ fn run_pipewire_thread(
                         device_name,
                     );
                 }
+                PwCommand::SetMidiLinkFilter { filter_id, options } => {
+                    let mut filters = midi_link_filters.borrow_mut();
+                    if let Some(filter) = filters.get(&filter_id) {
+                        filter.set_options(options);
+                    } else {
+                        match crate::midi::link_filter::MidiLinkFilterNode::new(
+                            &core, filter_id, options,
+                        ) {
+                            Ok(filter) => {
+                                filters.insert(filter_id, filter);
+                            }
+                            Err(e) => {
+                                log::error!("Failed to create MIDI link filter: {}", e);
+                                let _ = event_tx.send(PwEvent::Error(e.to_string()));
+                            }
+                        }
+                    }
+                }
+                PwCommand::RemoveMidiLinkFilter { filter_id } => {
+                    if midi_link_filters.borrow_mut().remove(&filter_id).is_some() {
+                        log::info!("MIDI link filter {} removed", filter_id);
+                    }
+                }
+                PwCommand::SetLinkGain { gain_id, gain_db } => {
+                    let mut gains = link_gains.borrow_mut();
+                    if let Some(gain) = gains.get(&gain_id) {
+                        gain.set_gain(gain_db);
+                    } else {
+                        match super::link_gain::LinkGainNode::new(&core, gain_id, gain_db) {
+                            Ok(gain) => {
+                                gains.insert(gain_id, gain);
+                            }
+                            Err(e) => {
+                                log::error!("Failed to create link gain: {}", e);
+                                let _ = event_tx.send(PwEvent::Error(e.to_string()));
+                            }
+                        }
+                    }
+                }
+                PwCommand::RemoveLinkGain { gain_id } => {
+                    if link_gains.borrow_mut().remove(&gain_id).is_some() {
+                        log::info!("Link gain {} removed", gain_id);
+                    }
+                }
+                PwCommand::StartProbe { probe_id, inputs } => {
+                    match super::probe::ProbeNode::new(&core, probe_id, inputs) {
+                        Ok(probe) => {
+                            probes.borrow_mut().insert(probe_id, probe);
+                        }
+                        Err(e) => {
+                            log::error!("Failed to create probe: {}", e);
+                            let _ = event_tx.send(PwEvent::Error(e.to_string()));
+                        }
+                    }
+                }
+                PwCommand::StopProbe { probe_id } => {
+                    if let Some(probe) = probes.borrow_mut().remove(&probe_id) {
+                        let peaks_db = probe.peaks_db();
+                        drop(probe);
+                        log::info!("Probe {} stopped", probe_id);
+                        let _ = event_tx.send(PwEvent::ProbeFinished { probe_id, peaks_db });
+                    }
+                }
+                PwCommand::SaveLv2States => {
+                    for (&instance_id, inst_rc) in lv2_instances.borrow().iter() {
+                        let inst = inst_rc.borrow();
+                        if inst.has_state_interface()
+                            && let Some(state) = unsafe { inst.save_state() }
+                        {
+                            let _ = event_tx.send(PwEvent::Plugin(PluginEvent::Lv2StateSaved {
+                                instance_id,
+                                state,
+                            }));
+                        }
+                    }
+                    let _ = event_tx.send(PwEvent::Plugin(PluginEvent::Lv2StatesSaved));
+                }
+                PwCommand::SetTickInterval { interval_ms } => {
+                    let timer = tick_timer.get();
+                    if !timer.is_null() {
+                        let tick = Duration::from_millis(interval_ms.max(1));
+                        let _ = unsafe { &*timer }.update_timer(Some(tick), Some(tick));
+                        log::info!("PipeWire tick interval set to {} ms", interval_ms);
+                    }
+                }
                 cmd => {
                     let op = match cmd {
                         PwCommand::Connect {
@@ -436,6 +705,7 @@ fn run_pipewire_thread(
                         } => InternalOp::Connect {
                             output_port_id,
                             input_port_id,
+                            attempt: 0,
                         },
                         PwCommand::Disconnect { link_id } => InternalOp::Disconnect { link_id },
                         PwCommand::AddPlugin {
@@ -444,12 +714,27 @@ fn run_pipewire_thread(
                             display_name,
                             format,
                             lv2_state,
+                            oversampling,
                         } => InternalOp::AddPlugin {
                             plugin_uri,
                             instance_id,
                             display_name,
                             format,
                             lv2_state,
+                            oversampling,
+                        },
+                        PwCommand::ReloadPlugin {
+                            plugin_uri,
+                            instance_id,
+                            display_name,
+                            format,
+                            oversampling,
+                        } => InternalOp::ReloadPlugin {
+                            plugin_uri,
+                            instance_id,
+                            display_name,
+                            format,
+                            oversampling,
                         },
                         PwCommand::RemovePlugin { instance_id } => {
                             InternalOp::RemovePlugin { instance_id }
@@ -462,12 +747,29 @@ fn run_pipewire_thread(
                         }
                         PwCommand::SetPluginParameter { .. }
                         | PwCommand::SetPluginBypass { .. }
+                        | PwCommand::SetPluginQuantum { .. }
+                        | PwCommand::SetNodeMute { .. }
+                        | PwCommand::SetNodeConversion { .. }
+                        | PwCommand::RampNodeVolume { .. }
+                        | PwCommand::SetDefaultSink { .. }
                         | PwCommand::StartMidiLearn { .. }
                         | PwCommand::CancelMidiLearn
                         | PwCommand::AddMidiMapping(..)
                         | PwCommand::RemoveMidiMapping(..)
                         | PwCommand::RemoveMidiMappingsForPlugin { .. }
-                        | PwCommand::RemoveMidiMappingsForDevice { .. } => unreachable!(),
+                        | PwCommand::RemoveMidiMappingsForDevice { .. }
+                        | PwCommand::SetMidiLinkFilter { .. }
+                        | PwCommand::RemoveMidiLinkFilter { .. }
+                        | PwCommand::SetLinkGain { .. }
+                        | PwCommand::RemoveLinkGain { .. }
+                        | PwCommand::StartProbe { .. }
+                        | PwCommand::StopProbe { .. }
+                        | PwCommand::SaveLv2States
+                        | PwCommand::SetTickInterval { .. }
+                        | PwCommand::RefreshGraph
+                        | PwCommand::ResyncGraph
+                        | PwCommand::Shutdown
+                        | PwCommand::Transaction { .. } => unreachable!(),
                     };
                     pending_ops.borrow_mut().push(op);
                 }
@@ -481,9 +783,55 @@ fn run_pipewire_thread(
         let internal_tx = internal_tx.clone();
         let changes_pending = changes_pending.clone();
         let event_tx = event_tx.clone();
+        let graph = graph.clone();
+        let pw_cmd_tx = pw_cmd_tx.clone();
+        let core = core.clone();
+        let registry_slot = registry_slot.clone();
+        let transactions = transactions.clone();
+        let tx_sync = tx_sync.clone();
+        let active_tx: RefCell<Option<(Transaction, Instant)>> = RefCell::new(None);
+        let link_attempts = link_attempts.clone();
+        let connect_retries_due = connect_retries_due.clone();
+        let ringing_out = ringing_out.clone();
+        let volume_ramps = volume_ramps.clone();
+        let lv2_instances = lv2_instances.clone();
+        let clap_instances = clap_instances.clone();
+        let vst3_instances = vst3_instances.clone();
+        let last_ghost_refresh: RefCell<Instant> =
+            RefCell::new(Instant::now() - GHOST_REFRESH_INTERVAL);
 
         move |_| {
             let now = Instant::now();
+            heartbeat.beat();
+
+            // Runs once per settled batch of registry changes: prune objects
+            // whose removal events were missed, and verify ghost candidates
+            // (nodes whose ports vanished) against PipeWire.
+            let flush_batch = || {
+                if !*changes_pending.borrow() {
+                    return;
+                }
+                *changes_pending.borrow_mut() = false;
+
+                let report = graph.prune_orphans();
+                if !report.is_empty() {
+                    log::warn!(
+                        "Pruned stale graph objects: {} ports, {} links",
+                        report.ports.len(),
+                        report.links.len(),
+                    );
+                    emit_prune_events(&event_tx, &report);
+                }
+
+                if !graph.ghost_candidates().is_empty()
+                    && last_ghost_refresh.borrow().elapsed() >= GHOST_REFRESH_INTERVAL
+                {
+                    *last_ghost_refresh.borrow_mut() = now;
+                    let _ = pw_cmd_tx.send(PwCommand::RefreshGraph);
+                }
+
+                let _ = event_tx.send(PwEvent::BatchComplete);
+            };
 
             {
                 let mut ops = pending_ops.borrow_mut();
@@ -501,13 +849,65 @@ fn run_pipewire_thread(
                 }
             }
 
+            sweep_link_attempts(
+                &link_attempts,
+                &connect_retries_due,
+                connect_retries,
+                &graph,
+                &event_tx,
+            );
+            connect_retries_due.borrow_mut().retain(|retry| {
+                if retry.due > now {
+                    return true;
+                }
+                let _ = internal_tx.send(InternalOp::Connect {
+                    output_port_id: retry.output_port_id,
+                    input_port_id: retry.input_port_id,
+                    attempt: retry.attempt,
+                });
+                false
+            });
+
+            drive_transaction(
+                &active_tx,
+                &transactions,
+                &tx_sync,
+                &graph,
+                &core,
+                &registry_slot,
+                &event_tx,
+            );
+
+            ringing_out.borrow_mut().retain(|r| {
+                let done = now >= r.deadline
+                    || tail_finished(
+                        r.instance_id,
+                        &lv2_instances,
+                        &clap_instances,
+                        &vst3_instances,
+                    );
+                if done {
+                    let _ = internal_tx.send(InternalOp::DropPlugin {
+                        instance_id: r.instance_id,
+                    });
+                }
+                !done
+            });
+
+            volume_ramps.borrow_mut().retain(|r| {
+                let t = now.duration_since(r.start).as_secs_f32() / r.duration.as_secs_f32();
+                set_node_volume(
+                    &registry_slot.borrow().0,
+                    r.node_id,
+                    crossfade::ramp_volume(r.from, r.to, t),
+                );
+                t < 1.0
+            });
+
             if now.duration_since(*last_op_time.borrow())
                 < Duration::from_millis(operation_cooldown_ms)
             {
-                if *changes_pending.borrow() {
-                    *changes_pending.borrow_mut() = false;
-                    let _ = event_tx.send(PwEvent::BatchComplete);
-                }
+                flush_batch();
                 return;
             }
 
@@ -521,10 +921,7 @@ fn run_pipewire_thread(
                 *last_op_time.borrow_mut() = now;
             }
 
-            if *changes_pending.borrow() {
-                *changes_pending.borrow_mut() = false;
-                let _ = event_tx.send(PwEvent::BatchComplete);
-            }
+            flush_batch();
         }
     });
 
@@ -532,19 +929,26 @@ fn run_pipewire_thread(
         Some(Duration::from_millis(tick_interval_ms)),
         Some(Duration::from_millis(tick_interval_ms)),
     );
+    tick_timer.set((&_timer as *const pipewire::loop_::TimerSource<'_>).cast());
 
     let _internal_receiver = internal_rx.attach(mainloop.loop_(), {
         let graph = graph.clone();
         let core = core.clone();
-        let registry = registry.clone();
+        let registry_slot = registry_slot.clone();
         let event_tx = event_tx.clone();
         let cmd_tx = cmd_tx.clone();
+        let link_attempts = link_attempts.clone();
+        let connect_retries_due = connect_retries_due.clone();
+        let ringing_out = ringing_out.clone();
+        let internal_tx = internal_tx.clone();
         let lv2_instances = lv2_instances.clone();
         let lv2_filters = lv2_filters.clone();
         let clap_instances = clap_instances.clone();
         let clap_filters = clap_filters.clone();
         let vst3_instances = vst3_instances.clone();
         let vst3_filters = vst3_filters.clone();
+        let builtin_instances = builtin_instances.clone();
+        let builtin_filters = builtin_filters.clone();
         let urid_mapper = urid_mapper.clone();
         let pw_sample_rate = pw_sample_rate.clone();
         let pw_quantum = pw_quantum.clone();
@@ -553,11 +957,31 @@ fn run_pipewire_thread(
             InternalOp::Connect {
                 output_port_id,
                 input_port_id,
-            } => {
-                create_link(&graph, &core, output_port_id, input_port_id);
-            }
+                attempt,
+            } => match create_link(&graph, &core, output_port_id, input_port_id) {
+                Ok(link) => {
+                    link_attempts.borrow_mut().push(LinkAttempt::watch(
+                        link,
+                        output_port_id,
+                        input_port_id,
+                        attempt,
+                    ));
+                }
+                Err(ConnectError::NotReady(reason)) => {
+                    schedule_connect_retry(
+                        &connect_retries_due,
+                        connect_retries,
+                        output_port_id,
+                        input_port_id,
+                        attempt,
+                        &reason,
+                        &event_tx,
+                    );
+                }
+                Err(ConnectError::Rejected) => {}
+            },
             InternalOp::Disconnect { link_id } => {
-                registry.destroy_global(link_id);
+                registry_slot.borrow().0.destroy_global(link_id);
             }
             InternalOp::AddPlugin {
                 plugin_uri,
@@ -565,7 +989,78 @@ fn run_pipewire_thread(
                 display_name,
                 format,
                 lv2_state,
+                oversampling,
+            } => {
+                let sample_rate = pw_sample_rate.load(Ordering::Relaxed) as f64;
+                let block_length = pw_quantum.load(Ordering::Relaxed);
+                handle_add_plugin(
+                    &core,
+                    &event_tx,
+                    &lv2_instances,
+                    &lv2_filters,
+                    &clap_instances,
+                    &clap_filters,
+                    &vst3_instances,
+                    &vst3_filters,
+                    &builtin_instances,
+                    &builtin_filters,
+                    &urid_mapper,
+                    &plugin_uri,
+                    instance_id,
+                    &display_name,
+                    &format,
+                    sample_rate,
+                    block_length,
+                    &lv2_state,
+                    oversampling,
+                );
+            }
+            InternalOp::ReloadPlugin {
+                plugin_uri,
+                instance_id,
+                display_name,
+                format,
+                oversampling,
             } => {
+                // Tear the old instance down without reporting it removed;
+                // the new filter reports PluginAdded under the same id.
+                let mut lv2_state = Vec::new();
+                match format.as_str() {
+                    "CLAP" => {
+                        crate::clap::ui::close_clap_gui(instance_id, &event_tx);
+                        clap_filters.borrow_mut().remove(&instance_id);
+                        clap_instances.borrow_mut().remove(&instance_id);
+                    }
+                    "VST3" => {
+                        crate::vst3::ui::close_vst3_gui(instance_id, &event_tx);
+                        vst3_filters.borrow_mut().remove(&instance_id);
+                        vst3_instances.borrow_mut().remove(&instance_id);
+                    }
+                    "Builtin" => {
+                        builtin_filters.borrow_mut().remove(&instance_id);
+                        builtin_instances.borrow_mut().remove(&instance_id);
+                    }
+                    _ => {
+                        if let Some(inst_rc) = lv2_instances.borrow().get(&instance_id) {
+                            let inst = inst_rc.borrow();
+                            if inst.has_state_interface()
+                                && let Some(state) = unsafe { inst.save_state() }
+                            {
+                                lv2_state = state;
+                            }
+                        }
+                        if !lv2_state.is_empty() {
+                            let _ = event_tx.send(PwEvent::Plugin(PluginEvent::Lv2StateSaved {
+                                instance_id,
+                                state: lv2_state.clone(),
+                            }));
+                        }
+                        crate::lv2::ui::close_plugin_ui(instance_id);
+                        lv2_filters.borrow_mut().remove(&instance_id);
+                        lv2_instances.borrow_mut().remove(&instance_id);
+                    }
+                }
+
                 let sample_rate = pw_sample_rate.load(Ordering::Relaxed) as f64;
                 let block_length = pw_quantum.load(Ordering::Relaxed);
                 handle_add_plugin(
@@ -577,6 +1072,8 @@ fn run_pipewire_thread(
                     &clap_filters,
                     &vst3_instances,
                     &vst3_filters,
+                    &builtin_instances,
+                    &builtin_filters,
                     &urid_mapper,
                     &plugin_uri,
                     instance_id,
@@ -585,10 +1082,35 @@ fn run_pipewire_thread(
                     sample_rate,
                     block_length,
                     &lv2_state,
+                    oversampling,
                 );
             }
             InternalOp::RemovePlugin { instance_id } => {
-                // Try LV2 first, then CLAP, then VST3
+                if ringing_out
+                    .borrow()
+                    .iter()
+                    .any(|r| r.instance_id == instance_id)
+                {
+                    return;
+                }
+                if start_ring_out(
+                    instance_id,
+                    &lv2_instances,
+                    &clap_instances,
+                    &vst3_instances,
+                ) {
+                    log::info!("Plugin instance {} rings out before removal", instance_id);
+                    let limit = Duration::from_secs(crate::plugin::tail::tail_seconds() as u64);
+                    ringing_out.borrow_mut().push(RingingOut {
+                        instance_id,
+                        deadline: Instant::now() + limit + RING_OUT_GRACE,
+                    });
+                } else {
+                    let _ = internal_tx.send(InternalOp::DropPlugin { instance_id });
+                }
+            }
+            InternalOp::DropPlugin { instance_id } => {
+                // Try LV2 first, then CLAP, then builtin, then VST3
                 if lv2_instances.borrow().contains_key(&instance_id) {
                     {
                         let instances = lv2_instances.borrow();
@@ -618,6 +1140,9 @@ fn run_pipewire_thread(
                     crate::clap::ui::close_clap_gui(instance_id, &event_tx);
                     clap_filters.borrow_mut().remove(&instance_id);
                     clap_instances.borrow_mut().remove(&instance_id);
+                } else if builtin_instances.borrow().contains_key(&instance_id) {
+                    builtin_filters.borrow_mut().remove(&instance_id);
+                    builtin_instances.borrow_mut().remove(&instance_id);
                 } else {
                     crate::vst3::ui::close_vst3_gui(instance_id, &event_tx);
                     vst3_filters.borrow_mut().remove(&instance_id);
@@ -668,31 +1193,378 @@ fn run_pipewire_thread(
                     let inst = instance.borrow();
                     let controller_ptr = inst.controller_ptr();
                     let display_name = inst.display_name.clone();
+                    let edits = inst.editor_edits();
                     drop(inst);
                     unsafe {
                         crate::vst3::ui::open_vst3_gui(
                             controller_ptr,
                             instance_id,
                             &display_name,
+                            edits,
                             &event_tx,
                             &cmd_tx,
                         );
                     }
                 }
             }
-
-            InternalOp::ClosePluginUI { instance_id } => {
-                crate::lv2::ui::close_plugin_ui(instance_id);
-                crate::clap::ui::close_clap_gui(instance_id, &event_tx);
-                crate::vst3::ui::close_vst3_gui(instance_id, &event_tx);
+
+            InternalOp::ClosePluginUI { instance_id } => {
+                crate::lv2::ui::close_plugin_ui(instance_id);
+                crate::clap::ui::close_clap_gui(instance_id, &event_tx);
+                crate::vst3::ui::close_vst3_gui(instance_id, &event_tx);
+            }
+        }
+    });
+
+    log::info!("PipeWire thread started");
+    mainloop.run();
+
+    Ok(())
+}
+
+/// Shared state the registry listener needs. Bundled so a fresh listener can
+/// be attached when the registry is re-enumerated.
+#[derive(Clone)]
+struct RegistryContext {
+    graph: Arc<GraphState>,
+    event_tx: Sender<PwEvent>,
+    changes_pending: Rc<RefCell<bool>>,
+    pw_cmd_tx: pipewire::channel::Sender<PwCommand>,
+    /// Global IDs announced since a refresh started; `None` when idle.
+    seen: Rc<RefCell<Option<HashSet<ObjectId>>>>,
+    /// The bound "default" metadata object, which carries the default sink.
+    default_metadata: Rc<RefCell<Option<DefaultMetadata>>>,
+    /// Bound audio nodes whose negotiated format is tracked.
+    format_watches: FormatWatches,
+}
+
+struct DefaultMetadata {
+    id: ObjectId,
+    proxy: Metadata,
+    _listener: MetadataListener,
+}
+
+struct FormatWatch {
+    _proxy: pipewire::node::Node,
+    _listener: pipewire::node::NodeListener,
+}
+
+type FormatWatches = Rc<RefCell<HashMap<ObjectId, FormatWatch>>>;
+
+type RegistrySlot = Rc<RefCell<(pipewire::registry::RegistryRc, pipewire::registry::Listener)>>;
+
+/// Minimum time between automatic refreshes triggered by ghost candidates.
+const GHOST_REFRESH_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Minimum time between automatic resyncs triggered by server errors.
+const ERROR_RESYNC_INTERVAL: Duration = Duration::from_secs(10);
+
+/// First delay between attempts of a failed connect; doubled per retry.
+const CONNECT_RETRY_BASE_DELAY: Duration = Duration::from_millis(100);
+
+/// Upper bound for the connect retry delay.
+const CONNECT_RETRY_MAX_DELAY: Duration = Duration::from_secs(5);
+
+/// How long a new link proxy may stay silent before the graph decides
+/// whether it was created.
+const LINK_CONFIRM_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// How long a transaction step may wait for the server's sync reply before
+/// it is verified regardless.
+const TX_STEP_TIMEOUT: Duration = Duration::from_secs(2);
+
+fn register_registry_listener(
+    registry: &pipewire::registry::RegistryRc,
+    ctx: &RegistryContext,
+) -> pipewire::registry::Listener {
+    registry
+        .add_listener_local()
+        .global({
+            let graph = ctx.graph.clone();
+            let event_tx = ctx.event_tx.clone();
+            let changes_pending = ctx.changes_pending.clone();
+            let seen = ctx.seen.clone();
+            let default_metadata = ctx.default_metadata.clone();
+            let format_watches = ctx.format_watches.clone();
+            let registry = registry.downgrade();
+
+            move |global| {
+                if let Some(ref mut seen) = *seen.borrow_mut() {
+                    seen.insert(global.id);
+                }
+                match global.type_ {
+                    ObjectType::Node => {
+                        if let Some(node) = parse_node(global) {
+                            // Only announce plugins the first time their node
+                            // appears, not when a refresh re-enumerates it.
+                            let is_new = graph.get_node(global.id).is_none();
+                            if is_new
+                                && node.node_type == Some(NodeType::Plugin)
+                                && let Some(props) = global.props.as_ref()
+                                && let Some(id_str) = props.get("zestbay.plugin.instance_id")
+                                && let Ok(instance_id) = id_str.parse::<u64>()
+                            {
+                                let _ = event_tx.send(PwEvent::Plugin(PluginEvent::PluginAdded {
+                                    instance_id,
+                                    pw_node_id: global.id,
+                                    display_name: node.display_name().to_string(),
+                                }));
+                            }
+                            let watch_format = node.media_type == Some(MediaType::Audio)
+                                && !format_watches.borrow().contains_key(&global.id);
+                            graph.insert_node(node.clone());
+                            let _ = event_tx.send(PwEvent::NodeChanged(node));
+                            *changes_pending.borrow_mut() = true;
+                            if watch_format
+                                && let Some(registry) = registry.upgrade()
+                                && let Some(watch) =
+                                    watch_node_format(&registry, global, &graph, &changes_pending)
+                            {
+                                format_watches.borrow_mut().insert(global.id, watch);
+                            }
+                        }
+                    }
+                    ObjectType::Port => {
+                        if let Some(port) = parse_port(global, &graph) {
+                            log::debug!(
+                                "Port registered: id={} node={} name={:?} dir={:?}",
+                                port.id,
+                                port.node_id,
+                                port.name,
+                                port.direction
+                            );
+                            graph.insert_port(port.clone());
+                            let _ = event_tx.send(PwEvent::PortChanged(port));
+                            *changes_pending.borrow_mut() = true;
+                        } else {
+                            log::debug!(
+                                "Port global {} could not be parsed (props: {:?})",
+                                global.id,
+                                global.props.as_ref().map(|p| props_to_debug(p))
+                            );
+                        }
+                    }
+                    ObjectType::Link => {
+                        if let Some(link) = parse_link_from_props(global) {
+                            graph.insert_link(link.clone());
+                            let _ = event_tx.send(PwEvent::LinkChanged(link));
+                            *changes_pending.borrow_mut() = true;
+                        }
+                    }
+                    ObjectType::Metadata => {
+                        let name = global.props.as_ref().and_then(|p| p.get("metadata.name"));
+                        if name == Some("default")
+                            && let Some(registry) = registry.upgrade()
+                        {
+                            bind_default_metadata(
+                                &registry,
+                                global,
+                                &graph,
+                                &changes_pending,
+                                &default_metadata,
+                            );
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        })
+        .global_remove({
+            let graph = ctx.graph.clone();
+            let event_tx = ctx.event_tx.clone();
+            let changes_pending = ctx.changes_pending.clone();
+            let pw_cmd_tx = ctx.pw_cmd_tx.clone();
+            let default_metadata = ctx.default_metadata.clone();
+            let format_watches = ctx.format_watches.clone();
+
+            move |id| {
+                format_watches.borrow_mut().remove(&id);
+                if default_metadata.borrow().as_ref().map(|m| m.id) == Some(id) {
+                    *default_metadata.borrow_mut() = None;
+                    graph.set_default_sink(None);
+                    *changes_pending.borrow_mut() = true;
+                    return;
+                }
+                if let Some(node) = graph.remove_node(id) {
+                    // If a MIDI bridge node was removed (e.g. USB MIDI device
+                    // unplugged), clean up any MIDI mappings that referenced it
+                    // so stale mappings don't linger in the table.
+                    if node.is_bridge && node.media_type == Some(MediaType::Midi) {
+                        let device_name = node.display_name().to_string();
+                        log::info!(
+                            "MIDI device removed: {} (node {}), cleaning up mappings",
+                            device_name, id,
+                        );
+                        let _ = pw_cmd_tx.send(PwCommand::RemoveMidiMappingsForDevice {
+                            device_name,
+                        });
+                    }
+                    let removed_link_ids = graph.cleanup_node(id);
+                    // Destroy lingering PipeWire link objects and emit
+                    // LinkRemoved so the UI and persistence layer know.
+                    for link_id in removed_link_ids {
+                        let _ = pw_cmd_tx.send(PwCommand::Disconnect { link_id });
+                        let _ = event_tx.send(PwEvent::LinkRemoved(link_id));
+                    }
+                    let _ = event_tx.send(PwEvent::NodeRemoved(id));
+                    *changes_pending.borrow_mut() = true;
+                } else if let Some(port) = graph.remove_port(id) {
+                    // Also remove any links that referenced this port.
+                    // PipeWire may remove ports before their parent node,
+                    // so stale links must be cleaned up here too.
+                    let removed_link_ids = graph.cleanup_port(id);
+                    // Destroy lingering PipeWire link objects.
+                    for link_id in removed_link_ids {
+                        let _ = pw_cmd_tx.send(PwCommand::Disconnect { link_id });
+                        let _ = event_tx.send(PwEvent::LinkRemoved(link_id));
+                    }
+                    let _ = event_tx.send(PwEvent::PortRemoved {
+                        port_id: id,
+                        node_id: port.node_id,
+                    });
+                    *changes_pending.borrow_mut() = true;
+                } else if graph.remove_link(id).is_some() {
+                    let _ = event_tx.send(PwEvent::LinkRemoved(id));
+                    *changes_pending.borrow_mut() = true;
+                }
+            }
+        })
+        .register()
+}
+
+/// Re-enumerate the registry through a fresh proxy. Every global announced
+/// before the following core sync is recorded in `ctx.seen`; the core `done`
+/// listener then drops whatever the graph holds that PipeWire no longer has.
+fn start_graph_refresh(
+    core: &pipewire::core::CoreRc,
+    registry_slot: &RegistrySlot,
+    ctx: &RegistryContext,
+    refresh_seq: &Rc<RefCell<Option<libspa::utils::result::AsyncSeq>>>,
+) {
+    if refresh_seq.borrow().is_some() {
+        log::debug!("Graph refresh already in progress");
+        return;
+    }
+
+    let registry = match core.get_registry_rc() {
+        Ok(r) => r,
+        Err(e) => {
+            log::error!("Graph refresh: failed to get registry: {}", e);
+            return;
+        }
+    };
+
+    *ctx.seen.borrow_mut() = Some(HashSet::new());
+    let listener = register_registry_listener(&registry, ctx);
+    *registry_slot.borrow_mut() = (registry, listener);
+
+    match core.sync(0) {
+        Ok(seq) => {
+            log::info!("Graph refresh: re-enumerating PipeWire registry");
+            *refresh_seq.borrow_mut() = Some(seq);
+        }
+        Err(e) => {
+            log::error!("Graph refresh: core sync failed: {}", e);
+            *ctx.seen.borrow_mut() = None;
+        }
+    }
+}
+
+/// Tell the UI about objects dropped by a consistency pass.
+/// Advance the active link transaction by at most one step. A step is sent,
+/// followed by a core sync; once the sync comes back (or times out) the
+/// graph is checked and the next step, or the next rollback step, is sent.
+fn drive_transaction(
+    active: &RefCell<Option<(Transaction, Instant)>>,
+    queue: &RefCell<VecDeque<Transaction>>,
+    tx_sync: &RefCell<Option<(libspa::utils::result::AsyncSeq, bool)>>,
+    graph: &Arc<GraphState>,
+    core: &pipewire::core::CoreRc,
+    registry_slot: &RegistrySlot,
+    event_tx: &Sender<PwEvent>,
+) {
+    let mut active = active.borrow_mut();
+    if active.is_none() {
+        let Some(tx) = queue.borrow_mut().pop_front() else {
+            return;
+        };
+        *active = Some((tx, Instant::now()));
+    }
+    let Some((tx, step_sent)) = active.as_mut() else {
+        return;
+    };
+
+    if tx.has_in_flight() {
+        let reached = matches!(*tx_sync.borrow(), Some((_, true)));
+        if !reached && step_sent.elapsed() < TX_STEP_TIMEOUT {
+            return;
+        }
+        *tx_sync.borrow_mut() = None;
+        let rolling_back = tx.is_rolling_back();
+        if !tx.verify(graph) {
+            if rolling_back {
+                log::error!("Transaction '{}': rollback step failed", tx.label);
+            } else {
+                log::warn!("Transaction '{}': step failed, rolling back", tx.label);
+            }
+        }
+    }
+
+    match tx.next_step(graph) {
+        Some(step) => {
+            log::debug!("Transaction '{}': {}", tx.label, step);
+            match step {
+                LinkStep::Connect {
+                    output_port_id,
+                    input_port_id,
+                } => {
+                    // Verified against the graph after the sync, so the proxy
+                    // is not needed.
+                    let _ = create_link(graph, core, output_port_id, input_port_id);
+                }
+                LinkStep::Disconnect {
+                    output_port_id,
+                    input_port_id,
+                } => {
+                    if let Some(link) = graph.find_link(output_port_id, input_port_id) {
+                        registry_slot.borrow().0.destroy_global(link.id);
+                    }
+                }
+            }
+            match core.sync(0) {
+                Ok(seq) => *tx_sync.borrow_mut() = Some((seq, false)),
+                Err(e) => log::error!("Transaction '{}': sync failed: {}", tx.label, e),
             }
+            *step_sent = Instant::now();
         }
-    });
-
-    log::info!("PipeWire thread started");
-    mainloop.run();
+        None => {
+            match tx.outcome() {
+                TxOutcome::Committed => {
+                    log::info!("Transaction '{}' committed", tx.label);
+                }
+                TxOutcome::RolledBack { failed } => {
+                    log::warn!("Transaction '{}' rolled back after failing to {}", tx.label, failed);
+                    let _ = event_tx.send(PwEvent::Error(format!(
+                        "{} failed (could not {}); changes were rolled back",
+                        tx.label, failed
+                    )));
+                }
+            }
+            *active = None;
+        }
+    }
+}
 
-    Ok(())
+fn emit_prune_events(event_tx: &Sender<PwEvent>, report: &PruneReport) {
+    for &link_id in &report.links {
+        let _ = event_tx.send(PwEvent::LinkRemoved(link_id));
+    }
+    for &(port_id, node_id) in &report.ports {
+        let _ = event_tx.send(PwEvent::PortRemoved { port_id, node_id });
+    }
+    for &node_id in &report.nodes {
+        let _ = event_tx.send(PwEvent::NodeRemoved(node_id));
+    }
 }
 
 fn parse_node(global: &GlobalObject<&DictRef>) -> Option<Node> {
@@ -755,6 +1627,33 @@ fn parse_node(global: &GlobalObject<&DictRef>) -> Option<Node> {
         .map(|v| v == "jack")
         .unwrap_or(false);
     let is_bridge = effective_class.contains("Bridge");
+    let media_role = props
+        .get("media.role")
+        .filter(|r| !r.is_empty())
+        .map(|r| r.to_string());
+    let application_id = props
+        .get("application.id")
+        .filter(|v| !v.is_empty())
+        .map(|v| v.to_string())
+        .or_else(|| {
+            props
+                .get("application.process.id")
+                .filter(|v| !v.is_empty())
+                .map(|pid| format!("pid:{}", pid))
+        });
+    let application_name = props
+        .get("application.name")
+        .filter(|v| !v.is_empty())
+        .map(|v| v.to_string());
+    let application_binary = props
+        .get("application.process.binary")
+        .filter(|v| !v.is_empty())
+        .map(|v| v.to_string());
+    let device_serial = props
+        .get("device.serial")
+        .or_else(|| props.get("api.bluez5.address"))
+        .filter(|v| !v.is_empty())
+        .map(|v| v.to_string());
 
     Some(Node {
         id: global.id,
@@ -765,6 +1664,11 @@ fn parse_node(global: &GlobalObject<&DictRef>) -> Option<Node> {
         is_virtual,
         is_jack,
         is_bridge,
+        media_role,
+        application_id,
+        application_name,
+        application_binary,
+        device_serial,
         ready: true,
     })
 }
@@ -835,14 +1739,16 @@ fn parse_port(global: &GlobalObject<&DictRef>, graph: &Arc<GraphState>) -> Optio
     };
 
     let format_dsp = props.get("format.dsp").map(String::from);
+    let port_group = props.get("port.group").map(String::from);
     let media_type = if format_dsp.as_deref().map_or(false, |v| v.contains("midi")) {
         Some(MediaType::Midi)
     } else if name.starts_with("midi_") {
         Some(MediaType::Midi)
+    } else if port_group.as_deref() == Some(crate::plugin::types::CV_PORT_GROUP) {
+        Some(MediaType::Cv)
     } else {
         graph.get_node(node_id).and_then(|n| n.media_type)
     };
-    let port_group = props.get("port.group").map(String::from);
     let port_alias = props.get("port.alias").map(String::from);
 
     Some(Port {
@@ -858,6 +1764,305 @@ fn parse_port(global: &GlobalObject<&DictRef>, graph: &Arc<GraphState>) -> Optio
     })
 }
 
+/// Bind the session manager's "default" metadata and track the default
+/// audio sink it announces.
+fn bind_default_metadata(
+    registry: &pipewire::registry::RegistryRc,
+    global: &GlobalObject<&DictRef>,
+    graph: &Arc<GraphState>,
+    changes_pending: &Rc<RefCell<bool>>,
+    slot: &Rc<RefCell<Option<DefaultMetadata>>>,
+) {
+    let proxy: Metadata = match registry.bind(global) {
+        Ok(m) => m,
+        Err(e) => {
+            log::warn!("Failed to bind default metadata {}: {}", global.id, e);
+            return;
+        }
+    };
+    let listener = proxy
+        .add_listener_local()
+        .property({
+            let graph = graph.clone();
+            let changes_pending = changes_pending.clone();
+            move |subject, key, _type, value| {
+                // A `None` key clears every property.
+                if subject == 0 && matches!(key, Some("default.audio.sink") | None) {
+                    // Values are JSON objects like {"name":"alsa_output..."}.
+                    let name = value
+                        .and_then(|v| serde_json::from_str::<serde_json::Value>(v).ok())
+                        .and_then(|v| v["name"].as_str().map(str::to_string));
+                    log::debug!("Default audio sink: {:?}", name);
+                    graph.set_default_sink(name);
+                    *changes_pending.borrow_mut() = true;
+                }
+                0
+            }
+        })
+        .register();
+    *slot.borrow_mut() = Some(DefaultMetadata {
+        id: global.id,
+        proxy,
+        _listener: listener,
+    });
+}
+
+/// Make `node_name` the default audio sink. Like `wpctl set-default`, this
+/// sets the configured default, which the session manager remembers and
+/// follows by updating `default.audio.sink` and moving the streams.
+fn set_default_sink(slot: &Rc<RefCell<Option<DefaultMetadata>>>, node_name: &str) {
+    let slot = slot.borrow();
+    let Some(metadata) = slot.as_ref() else {
+        log::warn!("No default metadata to set {} as default", node_name);
+        return;
+    };
+    let value = serde_json::json!({ "name": node_name }).to_string();
+    metadata.proxy.set_property(
+        0,
+        "default.configured.audio.sink",
+        Some("Spa:String:JSON"),
+        Some(&value),
+    );
+    log::info!("Default audio sink set to {}", node_name);
+}
+
+/// Sample formats by their PipeWire value, named without endianness.
+const SAMPLE_FORMAT_NAMES: &[(AudioFormat, &str)] = &[
+    (AudioFormat::U8, "U8"),
+    (AudioFormat::S16LE, "S16"),
+    (AudioFormat::S16BE, "S16"),
+    (AudioFormat::S24LE, "S24"),
+    (AudioFormat::S24BE, "S24"),
+    (AudioFormat::S24_32LE, "S24_32"),
+    (AudioFormat::S24_32BE, "S24_32"),
+    (AudioFormat::S32LE, "S32"),
+    (AudioFormat::S32BE, "S32"),
+    (AudioFormat::F32LE, "F32"),
+    (AudioFormat::F32BE, "F32"),
+    (AudioFormat::F64LE, "F64"),
+    (AudioFormat::F64BE, "F64"),
+    (AudioFormat::U8P, "U8P"),
+    (AudioFormat::S16P, "S16P"),
+    (AudioFormat::S24_32P, "S24_32P"),
+    (AudioFormat::S32P, "S32P"),
+    (AudioFormat::S24P, "S24P"),
+    (AudioFormat::F32P, "F32P"),
+    (AudioFormat::F64P, "F64P"),
+];
+
+/// Rate, channels and sample format from a raw audio `Format` param.
+fn parse_audio_format(pod: &libspa::pod::Pod) -> Option<(u32, u32, String)> {
+    use libspa::param::format::{MediaSubtype, MediaType as SpaMediaType};
+
+    let (media_type, media_subtype) = libspa::param::format_utils::parse_format(pod).ok()?;
+    if media_type != SpaMediaType::Audio || media_subtype != MediaSubtype::Raw {
+        return None;
+    }
+    let mut info = AudioInfoRaw::new();
+    info.parse(pod).ok()?;
+    let format = info.format();
+    let name = SAMPLE_FORMAT_NAMES
+        .iter()
+        .find(|(f, _)| *f == format)
+        .map(|(_, name)| name.to_string())
+        .unwrap_or_else(|| format!("{:?}", format));
+    Some((info.rate(), info.channels(), name))
+}
+
+/// The software volume in a node's `Props` param.
+fn parse_props_volume(pod: &libspa::pod::Pod) -> Option<f32> {
+    let (_, value) =
+        libspa::pod::deserialize::PodDeserializer::deserialize_any_from(pod.as_bytes()).ok()?;
+    let libspa::pod::Value::Object(object) = value else {
+        return None;
+    };
+    object
+        .properties
+        .into_iter()
+        .find(|p| p.key == libspa::sys::SPA_PROP_volume)
+        .and_then(|p| match p.value {
+            libspa::pod::Value::Float(volume) => Some(volume),
+            _ => None,
+        })
+}
+
+/// Bind an audio node and keep its negotiated format in the graph, from its
+/// `Format` param and the quantum it requests through `node.latency`, along
+/// with its software volume from its `Props` param.
+fn watch_node_format(
+    registry: &pipewire::registry::RegistryRc,
+    global: &GlobalObject<&DictRef>,
+    graph: &Arc<GraphState>,
+    changes_pending: &Rc<RefCell<bool>>,
+) -> Option<FormatWatch> {
+    let node: pipewire::node::Node = match registry.bind(global) {
+        Ok(n) => n,
+        Err(e) => {
+            log::debug!("Failed to bind node {} for its format: {}", global.id, e);
+            return None;
+        }
+    };
+    let node_id = global.id;
+    let listener = node
+        .add_listener_local()
+        .info({
+            let graph = graph.clone();
+            let changes_pending = changes_pending.clone();
+            move |info| {
+                let Some(props) = info.props() else {
+                    return;
+                };
+                // "256/48000": the quantum is the numerator.
+                let quantum = props
+                    .get("node.latency")
+                    .and_then(|l| l.split('/').next())
+                    .and_then(|q| q.trim().parse().ok());
+                if graph.update_node_format(node_id, |f| f.quantum = quantum) {
+                    *changes_pending.borrow_mut() = true;
+                }
+            }
+        })
+        .param({
+            let graph = graph.clone();
+            let changes_pending = changes_pending.clone();
+            move |_seq, id, _index, _next, param| {
+                if id == libspa::param::ParamType::Props {
+                    if let Some(volume) = param.and_then(parse_props_volume) {
+                        graph.set_node_volume(node_id, volume);
+                    }
+                    return;
+                }
+                if id != libspa::param::ParamType::Format {
+                    return;
+                }
+                // No param means the format was cleared, e.g. on suspend.
+                let parsed = param.and_then(parse_audio_format);
+                let changed = graph.update_node_format(node_id, |f| {
+                    f.rate = parsed.as_ref().map(|p| p.0);
+                    f.channels = parsed.as_ref().map(|p| p.1);
+                    f.sample_format = parsed.as_ref().map(|p| p.2.clone());
+                });
+                if changed {
+                    log::debug!(
+                        "Node {} format: {}",
+                        node_id,
+                        graph.get_node_format(node_id).unwrap_or_default().label()
+                    );
+                    *changes_pending.borrow_mut() = true;
+                }
+            }
+        })
+        .register();
+    node.subscribe_params(&[
+        libspa::param::ParamType::Format,
+        libspa::param::ParamType::Props,
+    ]);
+    Some(FormatWatch {
+        _proxy: node,
+        _listener: listener,
+    })
+}
+
+/// Mute or unmute a node through its `Props` parameter, the same switch
+/// `wpctl set-mute` flips.
+fn set_node_mute(registry: &pipewire::registry::RegistryRc, node_id: ObjectId, mute: bool) {
+    let property = libspa::pod::Property {
+        key: libspa::sys::SPA_PROP_mute,
+        flags: libspa::pod::PropertyFlags::empty(),
+        value: libspa::pod::Value::Bool(mute),
+    };
+    if set_node_props(registry, node_id, vec![property], "mute") {
+        let state = if mute { "muted" } else { "unmuted" };
+        log::info!("Node {} {}", node_id, state);
+    }
+}
+
+/// Set a node's software volume, which scales the sound on top of the
+/// channel volumes desktop mixers show.
+fn set_node_volume(registry: &pipewire::registry::RegistryRc, node_id: ObjectId, volume: f32) {
+    let property = libspa::pod::Property {
+        key: libspa::sys::SPA_PROP_volume,
+        flags: libspa::pod::PropertyFlags::empty(),
+        value: libspa::pod::Value::Float(volume),
+    };
+    set_node_props(registry, node_id, vec![property], "volume");
+}
+
+/// Set audioconvert options on a node as `Props { params = [ key value ... ] }`,
+/// which its adapter applies on the fly.
+fn set_node_conversion(
+    registry: &pipewire::registry::RegistryRc,
+    node_id: ObjectId,
+    params: &[ConvertParam],
+) {
+    if params.is_empty() {
+        return;
+    }
+    let mut fields = Vec::with_capacity(params.len() * 2);
+    for param in params {
+        fields.push(libspa::pod::Value::String(param.key.to_string()));
+        fields.push(match param.value {
+            ConvertValue::Int(v) => libspa::pod::Value::Int(v),
+            ConvertValue::Bool(v) => libspa::pod::Value::Bool(v),
+        });
+    }
+    let property = libspa::pod::Property {
+        key: libspa::sys::SPA_PROP_params,
+        flags: libspa::pod::PropertyFlags::empty(),
+        value: libspa::pod::Value::Struct(fields),
+    };
+    if set_node_props(registry, node_id, vec![property], "conversion") {
+        let summary: Vec<String> = params.iter().map(|p| p.to_string()).collect();
+        log::info!("Node {} conversion: {}", node_id, summary.join(" "));
+    }
+}
+
+/// Bind `node_id` and set `properties` on its `Props` param. `what` names
+/// the change in warnings.
+fn set_node_props(
+    registry: &pipewire::registry::RegistryRc,
+    node_id: ObjectId,
+    properties: Vec<libspa::pod::Property>,
+    what: &str,
+) -> bool {
+    let global = GlobalObject::<&DictRef> {
+        id: node_id,
+        permissions: pipewire::permissions::PermissionFlags::all(),
+        type_: ObjectType::Node,
+        version: 3,
+        props: None,
+    };
+    let node: pipewire::node::Node = match registry.bind(&global) {
+        Ok(n) => n,
+        Err(e) => {
+            log::warn!("Failed to bind node {} to set {}: {}", node_id, what, e);
+            return false;
+        }
+    };
+    let props = libspa::pod::Value::Object(libspa::pod::Object {
+        type_: libspa::utils::SpaTypes::ObjectParamProps.as_raw(),
+        id: libspa::param::ParamType::Props.as_raw(),
+        properties,
+    });
+    let bytes = match libspa::pod::serialize::PodSerializer::serialize(
+        std::io::Cursor::new(Vec::new()),
+        &props,
+    ) {
+        Ok((cursor, _)) => cursor.into_inner(),
+        Err(e) => {
+            log::warn!("Failed to build {} param: {:?}", what, e);
+            return false;
+        }
+    };
+    match libspa::pod::Pod::from_bytes(&bytes) {
+        Some(pod) => {
+            node.set_param(libspa::param::ParamType::Props, 0, pod);
+            true
+        }
+        None => false,
+    }
+}
+
 fn props_to_debug(props: &DictRef) -> Vec<(String, String)> {
     props
         .iter()
@@ -880,6 +2085,70 @@ fn parse_link_from_props(global: &GlobalObject<&DictRef>) -> Option<Link> {
 
 type GlobalSharedMutHashMap<K, V> = Rc<RefCell<HashMap<K, Rc<RefCell<V>>>>>;
 
+/// A removed plugin whose tail is ringing out.
+struct RingingOut {
+    instance_id: u64,
+    /// When to drop it even if the tail hasn't been seen to end, e.g.
+    /// because nothing is linked to it and it isn't being run.
+    deadline: Instant,
+}
+
+/// A node volume ramp in progress.
+struct VolumeRamp {
+    node_id: ObjectId,
+    from: f32,
+    to: f32,
+    start: Instant,
+    duration: Duration,
+}
+
+/// Time allowed on top of the tail limit before a ringing plugin is
+/// dropped regardless.
+const RING_OUT_GRACE: Duration = Duration::from_secs(1);
+
+/// Mute a plugin's inputs so that its tail rings out before it is removed.
+/// Returns false when it is to be removed at once: tails are off, or it is
+/// a builtin plugin, which has none.
+fn start_ring_out(
+    instance_id: u64,
+    lv2_instances: &GlobalSharedMutHashMap<u64, crate::lv2::host::Lv2PluginInstance>,
+    clap_instances: &GlobalSharedMutHashMap<u64, crate::clap::host::ClapPluginInstance>,
+    vst3_instances: &GlobalSharedMutHashMap<u64, crate::vst3::host::Vst3PluginInstance>,
+) -> bool {
+    if crate::plugin::tail::tail_seconds() == 0 {
+        return false;
+    }
+    if let Some(instance) = lv2_instances.borrow().get(&instance_id) {
+        instance.borrow_mut().ringing_out = true;
+    } else if let Some(instance) = clap_instances.borrow().get(&instance_id) {
+        instance.borrow_mut().ringing_out = true;
+    } else if let Some(instance) = vst3_instances.borrow().get(&instance_id) {
+        instance.borrow_mut().ringing_out = true;
+    } else {
+        return false;
+    }
+    true
+}
+
+/// Whether a ringing plugin's tail has ended. An instance that is gone
+/// counts as finished.
+fn tail_finished(
+    instance_id: u64,
+    lv2_instances: &GlobalSharedMutHashMap<u64, crate::lv2::host::Lv2PluginInstance>,
+    clap_instances: &GlobalSharedMutHashMap<u64, crate::clap::host::ClapPluginInstance>,
+    vst3_instances: &GlobalSharedMutHashMap<u64, crate::vst3::host::Vst3PluginInstance>,
+) -> bool {
+    if let Some(instance) = lv2_instances.borrow().get(&instance_id) {
+        instance.borrow().tail_finished()
+    } else if let Some(instance) = clap_instances.borrow().get(&instance_id) {
+        instance.borrow().tail_finished()
+    } else if let Some(instance) = vst3_instances.borrow().get(&instance_id) {
+        instance.borrow().tail_finished()
+    } else {
+        true
+    }
+}
+
 #[allow(clippy::too_many_arguments)]
 fn handle_add_plugin(
     core: &pipewire::core::CoreRc,
@@ -890,6 +2159,8 @@ fn handle_add_plugin(
     clap_filters: &Rc<RefCell<HashMap<u64, crate::clap::filter::ClapFilterNode>>>,
     vst3_instances: &GlobalSharedMutHashMap<u64, crate::vst3::host::Vst3PluginInstance>,
     vst3_filters: &Rc<RefCell<HashMap<u64, crate::vst3::filter::Vst3FilterNode>>>,
+    builtin_instances: &GlobalSharedMutHashMap<u64, crate::builtin::BuiltinPluginInstance>,
+    builtin_filters: &Rc<RefCell<HashMap<u64, crate::builtin::filter::BuiltinFilterNode>>>,
     urid_mapper: &Arc<crate::lv2::urid::UridMapper>,
     plugin_uri: &str,
     instance_id: u64,
@@ -898,7 +2169,9 @@ fn handle_add_plugin(
     sample_rate: f64,
     block_length: u32,
     lv2_state: &[crate::lv2::state::StateEntry],
+    oversampling: u32,
 ) {
+    let oversampling = oversampling as usize;
     match format {
         "CLAP" => handle_add_clap_plugin(
             core,
@@ -909,6 +2182,7 @@ fn handle_add_plugin(
             instance_id,
             display_name,
             sample_rate,
+            oversampling,
         ),
         "VST3" => handle_add_vst3_plugin(
             core,
@@ -919,6 +2193,17 @@ fn handle_add_plugin(
             instance_id,
             display_name,
             sample_rate,
+            oversampling,
+        ),
+        "Builtin" => handle_add_builtin_plugin(
+            core,
+            event_tx,
+            builtin_instances,
+            builtin_filters,
+            plugin_uri,
+            instance_id,
+            display_name,
+            sample_rate,
         ),
         _ => handle_add_lv2_plugin(
             core,
@@ -932,6 +2217,7 @@ fn handle_add_plugin(
             sample_rate,
             block_length,
             lv2_state,
+            oversampling,
         ),
     }
 }
@@ -948,6 +2234,7 @@ fn handle_add_lv2_plugin(
     sample_rate: f64,
     block_length: u32,
     lv2_state: &[crate::lv2::state::StateEntry],
+    oversampling: usize,
 ) {
     let urid_clone = urid_mapper.clone();
     let uri_owned = plugin_uri.to_string();
@@ -980,7 +2267,7 @@ fn handle_add_lv2_plugin(
         }
     }
 
-    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+    let result = crate::crash_report::catch_unwind(|| {
         let world = lilv::World::with_load_all();
         let uri_node = world.new_uri(&uri_owned);
 
@@ -1006,6 +2293,7 @@ fn handle_add_lv2_plugin(
                 &plugin_info,
                 sr,
                 bl,
+                oversampling,
                 &urid_clone,
             )
         };
@@ -1014,7 +2302,7 @@ fn handle_add_lv2_plugin(
             Some(inst) => Ok((inst, plugin_info)),
             None => Err(format!("Failed to instantiate plugin: {}", uri_owned)),
         }
-    }));
+    });
 
     let (lv2_instance, plugin_info) = match result {
         Ok(Ok((inst, info))) => (inst, info),
@@ -1067,6 +2355,22 @@ fn handle_add_lv2_plugin(
         p.port_type == crate::plugin::types::PluginPortType::AtomOutput
     });
 
+    let sidechain_inputs: Vec<usize> = plugin_info
+        .ports
+        .iter()
+        .filter(|p| p.port_type == crate::plugin::types::PluginPortType::AudioInput)
+        .enumerate()
+        .filter(|(_, p)| p.is_sidechain)
+        .map(|(i, _)| i)
+        .collect();
+    let cv_ports = |port_type: crate::plugin::types::PluginPortType| {
+        plugin_info
+            .ports
+            .iter()
+            .filter(|p| p.port_type == port_type)
+            .count()
+    };
+
     let filter_config = crate::lv2::filter::FilterConfig {
         instance_id,
         display_name: display_name.to_string(),
@@ -1075,6 +2379,9 @@ fn handle_add_lv2_plugin(
         sample_rate: sample_rate as u32,
         has_midi_in,
         has_midi_out,
+        sidechain_inputs,
+        cv_inputs: cv_ports(crate::plugin::types::PluginPortType::CvInput),
+        cv_outputs: cv_ports(crate::plugin::types::PluginPortType::CvOutput),
     };
 
     match crate::lv2::filter::Lv2FilterNode::new(
@@ -1111,6 +2418,7 @@ fn handle_add_clap_plugin(
     instance_id: u64,
     display_name: &str,
     sample_rate: f64,
+    oversampling: usize,
 ) {
     let uri_owned = plugin_uri.to_string();
     let sr = sample_rate;
@@ -1141,7 +2449,7 @@ fn handle_add_clap_plugin(
         }
     }
 
-    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+    let result = crate::crash_report::catch_unwind(|| {
         let all_clap = crate::clap::scanner::scan_plugins();
         let clap_info = match all_clap.iter().find(|p| p.uri == uri_owned) {
             Some(info) => info.clone(),
@@ -1155,6 +2463,7 @@ fn handle_add_clap_plugin(
                 &uri_owned,
                 &clap_info,
                 sr,
+                oversampling,
             )
         };
 
@@ -1162,7 +2471,7 @@ fn handle_add_clap_plugin(
             Some(inst) => Ok(inst),
             None => Err(format!("Failed to instantiate CLAP plugin: {}", uri_owned)),
         }
-    }));
+    });
 
     let clap_instance = match result {
         Ok(Ok(inst)) => inst,
@@ -1195,10 +2504,16 @@ fn handle_add_clap_plugin(
         }
     };
 
+    let docs = clap_instance.param_docs();
+    if !docs.is_empty() {
+        let _ = event_tx.send(PwEvent::Plugin(PluginEvent::ParamDocs { instance_id, docs }));
+    }
+
     let audio_inputs = clap_instance.audio_input_channels;
     let audio_outputs = clap_instance.audio_output_channels;
     let has_midi_in = clap_instance.has_midi_in;
     let has_midi_out = clap_instance.has_midi_out;
+    let sidechain_inputs = clap_instance.sidechain_inputs.clone();
     let instance_rc = std::rc::Rc::new(RefCell::new(clap_instance));
 
     let filter_config = crate::clap::filter::FilterConfig {
@@ -1208,6 +2523,7 @@ fn handle_add_clap_plugin(
         audio_outputs,
         has_midi_in,
         has_midi_out,
+        sidechain_inputs,
     };
 
     match crate::clap::filter::ClapFilterNode::new(
@@ -1235,6 +2551,49 @@ fn handle_add_clap_plugin(
     }
 }
 
+/// Builtin DSP is our own code, so unlike the external formats there is no
+/// sandbox probe or panic guard around instantiation.
+fn handle_add_builtin_plugin(
+    core: &pipewire::core::CoreRc,
+    event_tx: &Sender<PwEvent>,
+    builtin_instances: &GlobalSharedMutHashMap<u64, crate::builtin::BuiltinPluginInstance>,
+    builtin_filters: &Rc<RefCell<HashMap<u64, crate::builtin::filter::BuiltinFilterNode>>>,
+    plugin_uri: &str,
+    instance_id: u64,
+    display_name: &str,
+    sample_rate: f64,
+) {
+    let Some(instance) = crate::builtin::BuiltinPluginInstance::new(plugin_uri, sample_rate) else {
+        let _ = event_tx.send(PwEvent::Plugin(PluginEvent::PluginError {
+            instance_id: Some(instance_id),
+            message: format!("Unknown builtin plugin: {}", plugin_uri),
+            fatal: true,
+        }));
+        return;
+    };
+    let instance_rc = std::rc::Rc::new(RefCell::new(instance));
+
+    match crate::builtin::filter::BuiltinFilterNode::new(
+        core,
+        instance_id,
+        display_name,
+        instance_rc.clone(),
+        event_tx.clone(),
+    ) {
+        Ok(filter) => {
+            builtin_instances.borrow_mut().insert(instance_id, instance_rc);
+            builtin_filters.borrow_mut().insert(instance_id, filter);
+        }
+        Err(e) => {
+            let _ = event_tx.send(PwEvent::Plugin(PluginEvent::PluginError {
+                instance_id: Some(instance_id),
+                message: format!("Failed to create builtin filter node: {}", e),
+                fatal: true,
+            }));
+        }
+    }
+}
+
 fn handle_add_vst3_plugin(
     core: &pipewire::core::CoreRc,
     event_tx: &Sender<PwEvent>,
@@ -1244,6 +2603,7 @@ fn handle_add_vst3_plugin(
     instance_id: u64,
     display_name: &str,
     sample_rate: f64,
+    oversampling: usize,
 ) {
     let uri_owned = plugin_uri.to_string();
     let sr = sample_rate;
@@ -1274,7 +2634,7 @@ fn handle_add_vst3_plugin(
         }
     }
 
-    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+    let result = crate::crash_report::catch_unwind(|| {
         let all_vst3 = crate::vst3::scanner::scan_plugins();
         let vst3_info = match all_vst3.iter().find(|p| p.uri == uri_owned) {
             Some(info) => info.clone(),
@@ -1288,6 +2648,7 @@ fn handle_add_vst3_plugin(
                 &uri_owned,
                 &vst3_info,
                 sr,
+                oversampling,
             )
         };
 
@@ -1295,7 +2656,7 @@ fn handle_add_vst3_plugin(
             Some(inst) => Ok(inst),
             None => Err(format!("Failed to instantiate VST3 plugin: {}", uri_owned)),
         }
-    }));
+    });
 
     let vst3_instance = match result {
         Ok(Ok(inst)) => inst,
@@ -1328,10 +2689,16 @@ fn handle_add_vst3_plugin(
         }
     };
 
+    let docs = vst3_instance.param_docs();
+    if !docs.is_empty() {
+        let _ = event_tx.send(PwEvent::Plugin(PluginEvent::ParamDocs { instance_id, docs }));
+    }
+
     let audio_inputs = vst3_instance.audio_input_channels;
     let audio_outputs = vst3_instance.audio_output_channels;
     let has_midi_in = vst3_instance.has_midi_in;
     let has_midi_out = vst3_instance.has_midi_out;
+    let sidechain_inputs = vst3_instance.sidechain_inputs.clone();
     let instance_rc = std::rc::Rc::new(RefCell::new(vst3_instance));
 
     let filter_config = crate::vst3::filter::FilterConfig {
@@ -1341,6 +2708,7 @@ fn handle_add_vst3_plugin(
         audio_outputs,
         has_midi_in,
         has_midi_out,
+        sidechain_inputs,
     };
 
     match crate::vst3::filter::Vst3FilterNode::new(
@@ -1404,6 +2772,9 @@ fn build_plugin_info(
         has_ui: false,
         format: crate::lv2::PluginFormat::Lv2,
         library_path: String::new(),
+        version: None,
+        modified: None,
+        unsupported_ports: classification.unsupported_ports,
     })
 }
 
@@ -1431,25 +2802,47 @@ fn handle_open_plugin_ui(
     );
 }
 
+/// Why `create_link` did not produce a link proxy.
+enum ConnectError {
+    /// A port is missing or the server refused; worth retrying shortly, e.g.
+    /// while a freshly plugged device is still announcing its ports.
+    NotReady(String),
+    /// The request can never succeed (wrong direction, self-loop, media
+    /// type mismatch). Already logged.
+    Rejected,
+}
+
 fn create_link(
     graph: &Arc<GraphState>,
     core: &pipewire::core::CoreRc,
     output_port_id: ObjectId,
     input_port_id: ObjectId,
-) {
+) -> Result<PwLink, ConnectError> {
     let output_port = match graph.get_port(output_port_id) {
         Some(p) if p.direction == PortDirection::Output => p,
-        _ => {
+        Some(_) => {
             log::warn!("Invalid output port {}", output_port_id);
-            return;
+            return Err(ConnectError::Rejected);
+        }
+        None => {
+            return Err(ConnectError::NotReady(format!(
+                "output port {} not found",
+                output_port_id
+            )));
         }
     };
 
     let input_port = match graph.get_port(input_port_id) {
         Some(p) if p.direction == PortDirection::Input => p,
-        _ => {
+        Some(_) => {
             log::warn!("Invalid input port {}", input_port_id);
-            return;
+            return Err(ConnectError::Rejected);
+        }
+        None => {
+            return Err(ConnectError::NotReady(format!(
+                "input port {} not found",
+                input_port_id
+            )));
         }
     };
 
@@ -1458,7 +2851,7 @@ fn create_link(
             "Rejected self-loop: port {} and port {} belong to the same node {}",
             output_port_id, input_port_id, output_port.node_id
         );
-        return;
+        return Err(ConnectError::Rejected);
     }
 
     // Reject cross-media-type links (e.g. Midi → Audio or Audio → Midi)
@@ -1471,7 +2864,7 @@ fn create_link(
                 output_port_id, output_port.name, out_mt,
                 input_port_id, input_port.name, in_mt,
             );
-            return;
+            return Err(ConnectError::Rejected);
         }
     }
 
@@ -1489,8 +2882,164 @@ fn create_link(
         *pipewire::keys::OBJECT_LINGER => "true",
     };
 
-    if let Err(e) = core.create_object::<PwLink>("link-factory", &props) {
-        log::error!("Failed to create link: {}", e);
+    core.create_object::<PwLink>("link-factory", &props)
+        .map_err(|e| ConnectError::NotReady(e.to_string()))
+}
+
+/// A link proxy held until PipeWire reports whether the link came up. The
+/// link itself lingers on the server once the proxy is dropped.
+struct LinkAttempt {
+    output_port_id: ObjectId,
+    input_port_id: ObjectId,
+    attempt: u32,
+    started: Instant,
+    outcome: Rc<RefCell<Option<Result<(), String>>>>,
+    _link: PwLink,
+    _link_listener: LinkListener,
+    _proxy_listener: ProxyListener,
+}
+
+impl LinkAttempt {
+    fn watch(link: PwLink, output_port_id: ObjectId, input_port_id: ObjectId, attempt: u32) -> Self {
+        let outcome: Rc<RefCell<Option<Result<(), String>>>> = Rc::new(RefCell::new(None));
+
+        let link_listener = link
+            .add_listener_local()
+            .info({
+                let outcome = outcome.clone();
+                move |info| {
+                    let result = match info.state() {
+                        PwLinkState::Error(msg) => Err(msg.to_string()),
+                        PwLinkState::Paused | PwLinkState::Active => Ok(()),
+                        _ => return,
+                    };
+                    let mut outcome = outcome.borrow_mut();
+                    if outcome.is_none() {
+                        *outcome = Some(result);
+                    }
+                }
+            })
+            .register();
+
+        let proxy_listener = link
+            .upcast_ref()
+            .add_listener_local()
+            .error({
+                let outcome = outcome.clone();
+                move |_seq, res, message| {
+                    let mut outcome = outcome.borrow_mut();
+                    if outcome.is_none() {
+                        *outcome = Some(Err(format!("{} ({})", message, res)));
+                    }
+                }
+            })
+            .register();
+
+        Self {
+            output_port_id,
+            input_port_id,
+            attempt,
+            started: Instant::now(),
+            outcome,
+            _link: link,
+            _link_listener: link_listener,
+            _proxy_listener: proxy_listener,
+        }
+    }
+}
+
+struct ConnectRetry {
+    output_port_id: ObjectId,
+    input_port_id: ObjectId,
+    attempt: u32,
+    due: Instant,
+}
+
+/// Delay before retry number `attempt + 1`: doubles each time, capped.
+fn connect_retry_delay(attempt: u32) -> Duration {
+    CONNECT_RETRY_BASE_DELAY
+        .saturating_mul(1u32 << attempt.min(16))
+        .min(CONNECT_RETRY_MAX_DELAY)
+}
+
+fn schedule_connect_retry(
+    retries: &RefCell<Vec<ConnectRetry>>,
+    max_retries: u32,
+    output_port_id: ObjectId,
+    input_port_id: ObjectId,
+    attempt: u32,
+    reason: &str,
+    event_tx: &Sender<PwEvent>,
+) {
+    if attempt >= max_retries {
+        log::error!(
+            "Giving up on link {} -> {} after {} attempts: {}",
+            output_port_id,
+            input_port_id,
+            attempt + 1,
+            reason
+        );
+        let _ = event_tx.send(PwEvent::Error(format!(
+            "Could not connect port {} to port {}: {}",
+            output_port_id, input_port_id, reason
+        )));
+        return;
+    }
+
+    let delay = connect_retry_delay(attempt);
+    log::info!(
+        "Link {} -> {} failed ({}), retrying in {:?}",
+        output_port_id,
+        input_port_id,
+        reason,
+        delay
+    );
+    retries.borrow_mut().push(ConnectRetry {
+        output_port_id,
+        input_port_id,
+        attempt: attempt + 1,
+        due: Instant::now() + delay,
+    });
+}
+
+/// Drop link proxies whose outcome is known, scheduling retries for the
+/// ones that failed. Links that never report are judged by the graph once
+/// `LINK_CONFIRM_TIMEOUT` has passed.
+fn sweep_link_attempts(
+    attempts: &RefCell<Vec<LinkAttempt>>,
+    retries: &RefCell<Vec<ConnectRetry>>,
+    max_retries: u32,
+    graph: &GraphState,
+    event_tx: &Sender<PwEvent>,
+) {
+    let finished: Vec<LinkAttempt> = {
+        let mut attempts = attempts.borrow_mut();
+        let (done, pending): (Vec<_>, Vec<_>) = attempts.drain(..).partition(|a| {
+            a.outcome.borrow().is_some() || a.started.elapsed() >= LINK_CONFIRM_TIMEOUT
+        });
+        *attempts = pending;
+        done
+    };
+
+    for attempt in finished {
+        let outcome = attempt.outcome.borrow_mut().take().unwrap_or_else(|| {
+            if graph.find_link(attempt.output_port_id, attempt.input_port_id).is_some() {
+                Ok(())
+            } else {
+                Err("no response from PipeWire".to_string())
+            }
+        });
+        if let Err(reason) = outcome {
+            schedule_connect_retry(
+                retries,
+                max_retries,
+                attempt.output_port_id,
+                attempt.input_port_id,
+                attempt.attempt,
+                &reason,
+                event_tx,
+            );
+        }
     }
 }
 
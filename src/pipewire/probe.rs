@@ -0,0 +1,239 @@
+//! The internal node a signal probe listens through.
+//!
+//! One mono audio input per tap and no outputs; each input keeps the peak
+//! it has heard since the node was created. The node is named after its
+//! probe id (see [`probe_node_name`]) so the UI can link the taps to it
+//! once it shows up in the graph.
+
+use std::ffi::CString;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+
+use pipewire::core::CoreRc;
+
+use crate::patchbay::probe::probe_node_name;
+
+pub struct ProbeNode {
+    filter: *mut pipewire::sys::pw_filter,
+    _hook: Box<libspa::sys::spa_hook>,
+    _events: Box<pipewire::sys::pw_filter_events>,
+    user_data: *mut FilterData,
+    _core: CoreRc,
+}
+
+struct FilterData {
+    shutting_down: AtomicBool,
+    ports: Vec<*mut std::ffi::c_void>,
+    /// The peak absolute sample of each port as `f32` bits.
+    peaks: Vec<AtomicU32>,
+}
+
+unsafe impl Send for FilterData {}
+
+#[repr(C)]
+struct PortData {
+    index: u32,
+}
+
+impl ProbeNode {
+    pub fn new(
+        core: &CoreRc,
+        probe_id: u32,
+        inputs: usize,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let node_name = CString::new(probe_node_name(probe_id)).unwrap();
+        let description = CString::new(format!("ZestBay Probe {}", probe_id)).unwrap();
+
+        let props = unsafe {
+            let p = pipewire::sys::pw_properties_new(
+                c_str(b"media.type\0"),
+                c_str(b"Audio\0"),
+                c_str(b"media.category\0"),
+                c_str(b"Capture\0"),
+                c_str(b"media.role\0"),
+                c_str(b"DSP\0"),
+                c_str(b"node.virtual\0"),
+                c_str(b"true\0"),
+                c_str(b"node.passive\0"),
+                c_str(b"true\0"),
+                std::ptr::null::<std::os::raw::c_char>(),
+            );
+            let key = CString::new("node.name").unwrap();
+            pipewire::sys::pw_properties_set(p, key.as_ptr(), node_name.as_ptr());
+            let key = CString::new("node.description").unwrap();
+            pipewire::sys::pw_properties_set(p, key.as_ptr(), description.as_ptr());
+            p
+        };
+
+        let core_raw = core.as_raw_ptr();
+        let filter = unsafe { pipewire::sys::pw_filter_new(core_raw, node_name.as_ptr(), props) };
+        if filter.is_null() {
+            return Err("Failed to create probe pw_filter".into());
+        }
+
+        let user_data = Box::into_raw(Box::new(FilterData {
+            shutting_down: AtomicBool::new(false),
+            ports: Vec::with_capacity(inputs),
+            peaks: (0..inputs)
+                .map(|_| AtomicU32::new(0f32.to_bits()))
+                .collect(),
+        }));
+
+        for i in 0..inputs {
+            let port_name = CString::new(format!("tap_{}", i)).unwrap();
+            let port_props = unsafe {
+                pipewire::sys::pw_properties_new(
+                    c_str(b"port.name\0"),
+                    port_name.as_ptr(),
+                    c_str(b"format.dsp\0"),
+                    c_str(b"32 bit float mono audio\0"),
+                    std::ptr::null::<std::os::raw::c_char>(),
+                )
+            };
+            let port_data = unsafe {
+                pipewire::sys::pw_filter_add_port(
+                    filter,
+                    libspa::sys::SPA_DIRECTION_INPUT,
+                    pipewire::sys::pw_filter_port_flags_PW_FILTER_PORT_FLAG_MAP_BUFFERS,
+                    std::mem::size_of::<PortData>(),
+                    port_props,
+                    std::ptr::null_mut(),
+                    0,
+                )
+            };
+            if port_data.is_null() {
+                log::error!("Failed to add probe port tap_{}", i);
+            }
+            // Kept even when null so port i stays peak i.
+            unsafe { (*user_data).ports.push(port_data) };
+        }
+
+        let events = Box::new(pipewire::sys::pw_filter_events {
+            version: pipewire::sys::PW_VERSION_FILTER_EVENTS,
+            destroy: None,
+            state_changed: None,
+            io_changed: None,
+            param_changed: None,
+            add_buffer: None,
+            remove_buffer: None,
+            process: Some(on_process),
+            drained: None,
+            command: None,
+        });
+
+        let mut hook = Box::new(unsafe { std::mem::zeroed::<libspa::sys::spa_hook>() });
+        unsafe {
+            pipewire::sys::pw_filter_add_listener(
+                filter,
+                hook.as_mut() as *mut libspa::sys::spa_hook,
+                events.as_ref() as *const pipewire::sys::pw_filter_events,
+                user_data as *mut std::ffi::c_void,
+            );
+        }
+
+        let flags = pipewire::sys::pw_filter_flags_PW_FILTER_FLAG_RT_PROCESS;
+        let ret =
+            unsafe { pipewire::sys::pw_filter_connect(filter, flags, std::ptr::null_mut(), 0) };
+        if ret < 0 {
+            unsafe {
+                pipewire::sys::pw_filter_destroy(filter);
+                drop(Box::from_raw(user_data));
+            }
+            return Err(format!("Failed to connect probe pw_filter: error {}", ret).into());
+        }
+
+        log::info!("Probe {} created with {} inputs", probe_id, inputs);
+
+        Ok(Self {
+            filter,
+            _hook: hook,
+            _events: events,
+            user_data,
+            _core: core.clone(),
+        })
+    }
+
+    /// The peak heard at each input so far, in dBFS.
+    pub fn peaks_db(&self) -> Vec<f32> {
+        if self.user_data.is_null() {
+            return Vec::new();
+        }
+        unsafe {
+            (*self.user_data)
+                .peaks
+                .iter()
+                .map(|p| {
+                    let peak = f32::from_bits(p.load(Ordering::Relaxed));
+                    20.0 * peak.max(1e-9).log10()
+                })
+                .collect()
+        }
+    }
+}
+
+impl Drop for ProbeNode {
+    fn drop(&mut self) {
+        if !self.user_data.is_null() {
+            unsafe {
+                (*self.user_data)
+                    .shutting_down
+                    .store(true, Ordering::SeqCst);
+            }
+        }
+
+        if !self.filter.is_null() {
+            unsafe {
+                pipewire::sys::pw_filter_destroy(self.filter);
+            }
+            self.filter = std::ptr::null_mut();
+        }
+
+        if !self.user_data.is_null() {
+            unsafe {
+                drop(Box::from_raw(self.user_data));
+            }
+            self.user_data = std::ptr::null_mut();
+        }
+    }
+}
+
+#[inline]
+fn c_str(bytes: &[u8]) -> *const std::os::raw::c_char {
+    bytes.as_ptr() as *const std::os::raw::c_char
+}
+
+/// RT callback: raise each input's peak to the loudest sample of the block.
+unsafe extern "C" fn on_process(
+    data: *mut std::ffi::c_void,
+    position: *mut libspa::sys::spa_io_position,
+) {
+    unsafe {
+        let fd = &*(data as *const FilterData);
+
+        if fd.shutting_down.load(Ordering::Acquire) || position.is_null() {
+            return;
+        }
+
+        let n_samples = (*position).clock.duration as u32;
+        if n_samples == 0 || n_samples > 8192 {
+            return;
+        }
+        for (port, peak) in fd.ports.iter().zip(&fd.peaks) {
+            if port.is_null() {
+                continue;
+            }
+            let buf = pipewire::sys::pw_filter_get_dsp_buffer(*port, n_samples);
+            if buf.is_null() {
+                continue;
+            }
+            let input = std::slice::from_raw_parts(buf as *const f32, n_samples as usize);
+            let loudest = input
+                .iter()
+                .map(|s| s.abs())
+                .filter(|s| s.is_finite())
+                .fold(0.0f32, f32::max);
+            if loudest > f32::from_bits(peak.load(Ordering::Relaxed)) {
+                peak.store(loudest.to_bits(), Ordering::Relaxed);
+            }
+        }
+    }
+}
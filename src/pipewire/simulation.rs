@@ -0,0 +1,54 @@
+//! Stand-in for the PipeWire thread when ZestBay runs with `--demo`: a
+//! thread that answers commands from a [`DemoGraph`] instead of a daemon.
+
+use std::sync::Arc;
+use std::sync::mpsc::{Receiver, RecvTimeoutError, Sender};
+use std::time::Duration;
+
+use super::DemoGraph;
+use super::heartbeat::Heartbeat;
+use super::state::GraphState;
+use super::types::*;
+
+/// Start the demo thread. It hands back the same channels as [`super::start`]
+/// and beats `heartbeat` every `tick_interval_ms`.
+pub fn start(
+    graph: Arc<GraphState>,
+    tick_interval_ms: u64,
+    heartbeat: Heartbeat,
+) -> (Receiver<PwEvent>, Sender<PwCommand>) {
+    let (event_tx, event_rx) = std::sync::mpsc::channel();
+    let (cmd_tx, cmd_rx) = std::sync::mpsc::channel();
+    let tick = Duration::from_millis(tick_interval_ms.max(1));
+
+    std::thread::spawn(move || {
+        log::info!("Demo mode: simulating a PipeWire graph");
+        let mut demo = DemoGraph::new();
+        let _ = event_tx.send(PwEvent::Connected);
+        for event in demo.populate(&graph) {
+            let _ = event_tx.send(event);
+        }
+        let _ = event_tx.send(PwEvent::BatchComplete);
+
+        loop {
+            heartbeat.beat();
+            match cmd_rx.recv_timeout(tick) {
+                Ok(PwCommand::Shutdown) | Err(RecvTimeoutError::Disconnected) => break,
+                Ok(cmd) => {
+                    let events = demo.handle(&graph, cmd);
+                    if events.is_empty() {
+                        continue;
+                    }
+                    for event in events {
+                        let _ = event_tx.send(event);
+                    }
+                    let _ = event_tx.send(PwEvent::BatchComplete);
+                }
+                Err(RecvTimeoutError::Timeout) => {}
+            }
+        }
+        log::info!("Demo thread exiting");
+    });
+
+    (event_rx, cmd_tx)
+}
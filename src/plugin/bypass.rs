@@ -0,0 +1,300 @@
+//! Click-free bypass shared by the plugin hosts.
+//!
+//! The plugin keeps running while bypassed; its output is crossfaded against
+//! the input delayed by the plugin's reported latency, so toggling bypass
+//! neither clicks nor shifts the signal in time.
+//!
+//! With level matching enabled the crossfader also meters the processed and
+//! dry signals, and scales the dry signal while bypassed so that A/B
+//! comparisons are not biased by a loudness difference.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Longest plugin latency the dry path can compensate, in samples.
+pub const MAX_BYPASS_DELAY: usize = 16384;
+
+/// Crossfade length when bypass is toggled.
+const FADE_SECONDS: f64 = 0.02;
+
+const RING_LEN: usize = MAX_BYPASS_DELAY + 1;
+
+/// Integration time of the RMS meters used for level matching.
+const METER_SECONDS: f32 = 0.3;
+
+/// Smoothing time of the level-match gain.
+const MATCH_SMOOTHING_SECONDS: f32 = 0.05;
+
+/// Level-match compensation is limited to ±24 dB.
+const MAX_MATCH_GAIN: f32 = 15.848_932;
+
+/// Mean-square level below which a signal counts as silent and the
+/// compensation is held rather than recomputed.
+const SILENCE_POWER: f32 = 1e-8;
+
+static LEVEL_MATCH: AtomicBool = AtomicBool::new(false);
+
+/// Enable or disable bypass level matching for every plugin instance.
+pub fn set_level_match(enabled: bool) {
+    LEVEL_MATCH.store(enabled, Ordering::Relaxed);
+}
+
+pub fn level_match_enabled() -> bool {
+    LEVEL_MATCH.load(Ordering::Relaxed)
+}
+
+pub struct BypassCrossfader {
+    /// One ring buffer of input history per passthrough channel.
+    delay: Vec<Vec<f32>>,
+    write_pos: usize,
+    latency: usize,
+    /// Weight of the plugin output: 1.0 = active, 0.0 = fully bypassed.
+    wet: f32,
+    step: f32,
+    sample_rate: f32,
+    /// Mean-square levels of the processed and (delayed) dry signals.
+    wet_power: f32,
+    dry_power: f32,
+    /// Current gain applied to the dry signal while level matching.
+    match_gain: f32,
+    match_coef: f32,
+    /// Keep the plugin output at full level while bypassed; see
+    /// [`set_keep_wet`](Self::set_keep_wet).
+    keep_wet: bool,
+}
+
+impl BypassCrossfader {
+    /// `channels` is the number of output channels that have a matching
+    /// input to pass through. Allocates the delay lines up front so
+    /// [`process`](Self::process) never allocates.
+    pub fn new(channels: usize, sample_rate: f64) -> Self {
+        let fade_samples = (FADE_SECONDS * sample_rate).max(1.0);
+        Self {
+            delay: vec![vec![0.0; RING_LEN]; channels],
+            write_pos: 0,
+            latency: 0,
+            wet: 1.0,
+            step: (1.0 / fade_samples) as f32,
+            sample_rate: sample_rate as f32,
+            wet_power: 0.0,
+            dry_power: 0.0,
+            match_gain: 1.0,
+            match_coef: 1.0 - (-1.0 / (MATCH_SMOOTHING_SECONDS * sample_rate as f32)).exp(),
+            keep_wet: false,
+        }
+    }
+
+    pub fn latency(&self) -> usize {
+        self.latency
+    }
+
+    /// Set the plugin latency the dry path is delayed by. Values beyond
+    /// [`MAX_BYPASS_DELAY`] are clamped.
+    pub fn set_latency(&mut self, samples: usize) {
+        self.latency = samples.min(MAX_BYPASS_DELAY);
+    }
+
+    /// While set, bypassing fades the dry signal in on top of the plugin
+    /// output instead of replacing it. Used while a bypassed plugin's tail
+    /// rings out: it hears silence then, so only the tail is left.
+    pub fn set_keep_wet(&mut self, keep: bool) {
+        self.keep_wet = keep;
+    }
+
+    /// Gain currently applied to the bypassed signal by level matching.
+    pub fn level_match_gain_db(&self) -> f32 {
+        20.0 * self.match_gain.log10()
+    }
+
+    /// Mix the plugin `outputs` (already processed) with the delayed
+    /// `inputs` according to `bypassed`. RT-safe: no allocation, no locks.
+    pub fn process(
+        &mut self,
+        bypassed: bool,
+        inputs: &[&[f32]],
+        outputs: &mut [&mut [f32]],
+        sample_count: usize,
+    ) {
+        self.process_with(
+            bypassed,
+            level_match_enabled(),
+            inputs,
+            outputs,
+            sample_count,
+        );
+    }
+
+    fn process_with(
+        &mut self,
+        bypassed: bool,
+        level_match: bool,
+        inputs: &[&[f32]],
+        outputs: &mut [&mut [f32]],
+        sample_count: usize,
+    ) {
+        let target = if bypassed { 0.0 } else { 1.0 };
+        // Nothing to align, fade or meter: leave the plugin output alone.
+        if self.latency == 0 && self.wet == target && !bypassed && !level_match {
+            self.match_gain = 1.0;
+            return;
+        }
+
+        // Compensation from the levels metered so far; it only moves
+        // towards unity when matching is off.
+        let match_target = if !level_match {
+            1.0
+        } else if self.wet_power > SILENCE_POWER && self.dry_power > SILENCE_POWER {
+            (self.wet_power / self.dry_power)
+                .sqrt()
+                .clamp(1.0 / MAX_MATCH_GAIN, MAX_MATCH_GAIN)
+        } else {
+            self.match_gain
+        };
+        let mut wet_sum = 0.0f32;
+        let mut dry_sum = 0.0f32;
+        let mut match_end = self.match_gain;
+
+        let n = outputs
+            .iter()
+            .map(|o| o.len())
+            .fold(sample_count, usize::min);
+        let start = self.write_pos;
+        let latency = self.latency;
+        let mut wet_end = self.wet;
+
+        for (ch, out) in outputs.iter_mut().enumerate() {
+            let input = inputs.get(ch).filter(|_| ch < self.delay.len());
+            let mut pos = start;
+            let mut wet = self.wet;
+            let mut gain = self.match_gain;
+            for (i, sample) in out.iter_mut().enumerate().take(n) {
+                let dry = match input {
+                    Some(input) => {
+                        let line = &mut self.delay[ch];
+                        line[pos] = input.get(i).copied().unwrap_or(0.0);
+                        let read = (pos + RING_LEN - latency) % RING_LEN;
+                        pos = (pos + 1) % RING_LEN;
+                        line[read]
+                    }
+                    None => 0.0,
+                };
+                wet_sum += *sample * *sample;
+                dry_sum += dry * dry;
+                gain += (match_target - gain) * self.match_coef;
+                let dry = dry * gain;
+
+                wet = if target > wet {
+                    (wet + self.step).min(1.0)
+                } else {
+                    (wet - self.step).max(0.0)
+                };
+                *sample = if self.keep_wet {
+                    *sample + dry * (1.0 - wet)
+                } else if wet == 0.0 {
+                    dry
+                } else if wet == 1.0 {
+                    *sample
+                } else {
+                    *sample * wet + dry * (1.0 - wet)
+                };
+            }
+            wet_end = wet;
+            match_end = gain;
+        }
+
+        if outputs.is_empty() {
+            wet_end = target;
+        }
+        self.wet = wet_end;
+        self.match_gain = match_end;
+
+        let frames = (n * outputs.len().max(1)) as f32;
+        if n > 0 && wet_sum.is_finite() {
+            let coef = 1.0 - (-(n as f32) / (METER_SECONDS * self.sample_rate)).exp();
+            self.wet_power += (wet_sum / frames - self.wet_power) * coef;
+            self.dry_power += (dry_sum / frames - self.dry_power) * coef;
+        }
+        self.write_pos = (start + n) % RING_LEN;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(fader: &mut BypassCrossfader, bypassed: bool, input: &[f32], wet: f32) -> Vec<f32> {
+        let mut out = vec![wet; input.len()];
+        {
+            let inputs: [&[f32]; 1] = [input];
+            let mut outputs: [&mut [f32]; 1] = [&mut out];
+            fader.process(bypassed, &inputs, &mut outputs, input.len());
+        }
+        out
+    }
+
+    #[test]
+    fn test_active_without_latency_is_untouched() {
+        let mut fader = BypassCrossfader::new(1, 48000.0);
+        let out = run(&mut fader, false, &[1.0; 64], 0.5);
+        assert!(out.iter().all(|s| *s == 0.5));
+    }
+
+    #[test]
+    fn test_bypass_ramps_instead_of_switching() {
+        let mut fader = BypassCrossfader::new(1, 1000.0); // 20-sample fade
+        let out = run(&mut fader, true, &[1.0; 40], 0.0);
+        // Plugin output is silent, dry is 1.0: the first sample is mostly wet.
+        assert!(out[0] < 0.1);
+        assert!(out[10] > 0.4 && out[10] < 0.6);
+        assert_eq!(out[39], 1.0);
+        for pair in out.windows(2) {
+            assert!(pair[1] >= pair[0]);
+        }
+    }
+
+    #[test]
+    fn test_dry_path_is_delayed_by_latency() {
+        let mut fader = BypassCrossfader::new(1, 1.0); // instant fade
+        fader.set_latency(3);
+        let input: Vec<f32> = (1..=8).map(|i| i as f32).collect();
+        let out = run(&mut fader, true, &input, 0.0);
+        assert_eq!(out, vec![0.0, 0.0, 0.0, 1.0, 2.0, 3.0, 4.0, 5.0]);
+
+        // History carries across blocks.
+        let out = run(&mut fader, true, &[9.0, 10.0], 0.0);
+        assert_eq!(out, vec![6.0, 7.0]);
+    }
+
+    #[test]
+    fn test_level_match_scales_bypassed_signal() {
+        let mut fader = BypassCrossfader::new(1, 1000.0);
+        let input = [1.0; 100];
+        let mut out = vec![0.0; 100];
+        // Plugin attenuates by 6 dB; meter while active, then bypass.
+        for block in 0..40 {
+            out.fill(0.5);
+            let inputs: [&[f32]; 1] = [&input];
+            let mut outputs: [&mut [f32]; 1] = [&mut out];
+            fader.process_with(block >= 20, true, &inputs, &mut outputs, 100);
+        }
+        assert!((out[99] - 0.5).abs() < 0.01);
+        assert!((fader.level_match_gain_db() + 6.02).abs() < 0.1);
+    }
+
+    #[test]
+    fn test_keep_wet_adds_dry_to_tail() {
+        let mut fader = BypassCrossfader::new(1, 1.0); // instant fade
+        fader.set_keep_wet(true);
+        let out = run(&mut fader, true, &[1.0; 4], 0.25);
+        assert_eq!(out, vec![1.25; 4]);
+        fader.set_keep_wet(false);
+        let out = run(&mut fader, true, &[1.0; 4], 0.25);
+        assert_eq!(out, vec![1.0; 4]);
+    }
+
+    #[test]
+    fn test_latency_is_clamped() {
+        let mut fader = BypassCrossfader::new(1, 48000.0);
+        fader.set_latency(usize::MAX);
+        assert_eq!(fader.latency(), MAX_BYPASS_DELAY);
+    }
+}
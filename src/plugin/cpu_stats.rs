@@ -7,6 +7,7 @@ use std::collections::HashMap;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{Arc, Mutex, OnceLock};
 
+use super::cpu_history::CpuSample;
 use super::types::PluginInstanceId;
 
 /// Timing data for a single plugin, written from the RT thread.
@@ -23,6 +24,8 @@ pub struct PluginTimingSlot {
     pub last_rate: AtomicU64,
     /// Cumulative nanoseconds spent in the worker thread (async, off RT).
     pub worker_total_ns: AtomicU64,
+    /// Calls that took longer than the buffer they processed. Never reset.
+    pub overruns: AtomicU64,
 }
 
 impl PluginTimingSlot {
@@ -34,6 +37,7 @@ impl PluginTimingSlot {
             last_quantum: AtomicU64::new(0),
             last_rate: AtomicU64::new(0),
             worker_total_ns: AtomicU64::new(0),
+            overruns: AtomicU64::new(0),
         }
     }
 
@@ -48,6 +52,10 @@ impl PluginTimingSlot {
         if worker_ns > 0 {
             self.worker_total_ns.fetch_add(worker_ns, Ordering::Relaxed);
         }
+        // elapsed > quantum / rate seconds, without floating point
+        if rate > 0 && elapsed_ns.saturating_mul(rate as u64) > quantum as u64 * 1_000_000_000 {
+            self.overruns.fetch_add(1, Ordering::Relaxed);
+        }
     }
 
     /// Read and reset the accumulated stats (called from the UI thread).
@@ -107,6 +115,17 @@ pub struct PluginCpuSnapshot {
     pub worker_percent: f64,
 }
 
+impl PluginCpuSnapshot {
+    /// This window as a history sample ending at `time_ms`.
+    pub fn sample(&self, time_ms: u64) -> CpuSample {
+        CpuSample {
+            time_ms,
+            dsp_percent: self.dsp_percent as f32,
+            worker_percent: self.worker_percent as f32,
+        }
+    }
+}
+
 /// Global registry of per-plugin timing slots.
 pub struct PluginCpuTracker {
     slots: Mutex<HashMap<PluginInstanceId, (String, Arc<PluginTimingSlot>)>>,
@@ -145,6 +164,16 @@ impl PluginCpuTracker {
         });
         results
     }
+
+    /// Overruns counted by all registered plugins so far. Drops when a
+    /// plugin is removed, so callers should diff with `saturating_sub`.
+    pub fn total_overruns(&self) -> u64 {
+        let slots = self.slots.lock().unwrap();
+        slots
+            .values()
+            .map(|(_, slot)| slot.overruns.load(Ordering::Relaxed))
+            .sum()
+    }
 }
 
 /// Global singleton so filter callbacks can access it without passing through PipeWire.
@@ -0,0 +1,621 @@
+//! PipeWire `libpipewire-module-filter-chain` configuration export and import.
+//!
+//! A chain of hosted LV2 plugins can be written out as a filter-chain
+//! config snippet, so static processing keeps working in PipeWire without
+//! ZestBay running. Only LV2 plugins can be exported: filter-chain has no
+//! loader for CLAP, VST3 or ZestBay's built-in nodes.
+//!
+//! The other way round, the graphs of an existing filter-chain config are
+//! mapped onto catalog plugins so they can be re-created as hosted chains:
+//! LV2 nodes directly by URI, LADSPA nodes through known LV2 ports of the
+//! same plugin. Nodes without an equivalent (e.g. filter-chain builtins) are
+//! skipped and bridged over.
+
+use std::fmt::Write;
+
+use serde_json::{Map, Value};
+
+use super::import::{ImportedChain, ImportedLink, ImportedStage};
+use super::types::{PluginFormat, PluginInfo, PluginInstanceInfo, PluginPortType};
+
+/// One plugin of the chain being exported, in signal order.
+pub struct ChainStage<'a> {
+    pub instance: &'a PluginInstanceInfo,
+    pub plugin: &'a PluginInfo,
+}
+
+/// Main (non-sidechain) audio port symbols of `plugin` in one direction.
+fn audio_symbols(plugin: &PluginInfo, port_type: PluginPortType) -> Vec<&str> {
+    plugin
+        .ports
+        .iter()
+        .filter(|p| p.port_type == port_type && !p.is_sidechain)
+        .map(|p| p.symbol.as_str())
+        .collect()
+}
+
+/// Quote `s` as an SPA-JSON string.
+fn quote(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' | '\\' => {
+                out.push('\\');
+                out.push(c);
+            }
+            '\n' => out.push_str("\\n"),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// A filter-chain node name derived from the display name: lowercase ASCII
+/// alphanumerics and underscores, unique within the graph.
+fn node_label(display_name: &str, taken: &[String]) -> String {
+    let mut base: String = display_name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '_'
+            }
+        })
+        .collect::<String>()
+        .trim_matches('_')
+        .to_string();
+    if base.is_empty() {
+        base = "plugin".to_string();
+    }
+    let mut label = base.clone();
+    let mut n = 2;
+    while taken.contains(&label) {
+        label = format!("{}_{}", base, n);
+        n += 1;
+    }
+    label
+}
+
+fn channel_positions(channels: usize) -> &'static str {
+    match channels {
+        1 => "[ MONO ]",
+        2 => "[ FL FR ]",
+        _ => "",
+    }
+}
+
+fn stream_props(out: &mut String, key: &str, name: &str, channels: usize, extra: &[(&str, &str)]) {
+    let _ = writeln!(out, "            {} = {{", key);
+    let _ = writeln!(out, "                node.name = {}", quote(name));
+    for (k, v) in extra {
+        let _ = writeln!(out, "                {} = {}", k, v);
+    }
+    let _ = writeln!(out, "                audio.channels = {}", channels);
+    let positions = channel_positions(channels);
+    if !positions.is_empty() {
+        let _ = writeln!(out, "                audio.position = {}", positions);
+    }
+    let _ = writeln!(out, "            }}");
+}
+
+/// Render `stages` as a filter-chain config that creates a virtual sink named
+/// `name`. Bypassed plugins are left out of the graph. Fails if a plugin is
+/// not LV2 or the chain has no audio path through it.
+pub fn export_chain(name: &str, stages: &[ChainStage]) -> Result<String, String> {
+    if let Some(stage) = stages
+        .iter()
+        .find(|s| s.instance.format != PluginFormat::Lv2)
+    {
+        return Err(format!(
+            "'{}' is a {} plugin; filter-chain can only load LV2 plugins",
+            stage.instance.display_name, stage.instance.format
+        ));
+    }
+    let active: Vec<&ChainStage> = stages.iter().filter(|s| !s.instance.bypassed).collect();
+    if active.is_empty() {
+        return Err("the chain has no active plugins".to_string());
+    }
+
+    let mut labels: Vec<String> = Vec::new();
+    for stage in &active {
+        let label = node_label(&stage.instance.display_name, &labels);
+        labels.push(label);
+    }
+
+    let inputs = audio_symbols(active[0].plugin, PluginPortType::AudioInput);
+    let last = active[active.len() - 1];
+    let outputs = audio_symbols(last.plugin, PluginPortType::AudioOutput);
+    if inputs.is_empty() {
+        return Err(format!(
+            "'{}' has no audio inputs",
+            active[0].instance.display_name
+        ));
+    }
+    if outputs.is_empty() {
+        return Err(format!(
+            "'{}' has no audio outputs",
+            last.instance.display_name
+        ));
+    }
+
+    // Same channel matching as the live chain: a narrower side is fanned out
+    // from its last port, surplus outputs are left unconnected.
+    let mut links: Vec<(String, String)> = Vec::new();
+    for (i, pair) in active.windows(2).enumerate() {
+        let outs = audio_symbols(pair[0].plugin, PluginPortType::AudioOutput);
+        let ins = audio_symbols(pair[1].plugin, PluginPortType::AudioInput);
+        if outs.is_empty() || ins.is_empty() {
+            return Err(format!(
+                "'{}' and '{}' cannot be linked",
+                pair[0].instance.display_name, pair[1].instance.display_name
+            ));
+        }
+        for (ch, input) in ins.iter().enumerate() {
+            let output = outs[ch.min(outs.len() - 1)];
+            links.push((
+                format!("{}:{}", labels[i], output),
+                format!("{}:{}", labels[i + 1], input),
+            ));
+        }
+    }
+
+    let node_name = node_label(name, &[]);
+    let mut out = String::new();
+    let _ = writeln!(out, "# Filter chain exported from ZestBay.");
+    let _ = writeln!(
+        out,
+        "# Copy into ~/.config/pipewire/pipewire.conf.d/ and restart PipeWire."
+    );
+    let _ = writeln!(out, "context.modules = [");
+    let _ = writeln!(out, "    {{   name = libpipewire-module-filter-chain");
+    let _ = writeln!(out, "        args = {{");
+    let _ = writeln!(out, "            node.description = {}", quote(name));
+    let _ = writeln!(out, "            media.name = {}", quote(name));
+    let _ = writeln!(out, "            filter.graph = {{");
+    let _ = writeln!(out, "                nodes = [");
+    for (stage, label) in active.iter().zip(&labels) {
+        let _ = writeln!(out, "                    {{");
+        let _ = writeln!(out, "                        type = lv2");
+        let _ = writeln!(out, "                        name = {}", label);
+        let _ = writeln!(
+            out,
+            "                        plugin = {}",
+            quote(&stage.instance.plugin_uri)
+        );
+        let controls: Vec<_> = stage
+            .instance
+            .parameters
+            .iter()
+            .filter(|p| !p.symbol.is_empty())
+            .collect();
+        if !controls.is_empty() {
+            let _ = writeln!(out, "                        control = {{");
+            for param in controls {
+                let _ = writeln!(
+                    out,
+                    "                            {} = {}",
+                    quote(&param.symbol),
+                    param.value
+                );
+            }
+            let _ = writeln!(out, "                        }}");
+        }
+        let _ = writeln!(out, "                    }}");
+    }
+    let _ = writeln!(out, "                ]");
+    if !links.is_empty() {
+        let _ = writeln!(out, "                links = [");
+        for (output, input) in &links {
+            let _ = writeln!(
+                out,
+                "                    {{ output = {} input = {} }}",
+                quote(output),
+                quote(input)
+            );
+        }
+        let _ = writeln!(out, "                ]");
+    }
+    let port_list = |label: &str, symbols: &[&str]| {
+        symbols
+            .iter()
+            .map(|s| quote(&format!("{}:{}", label, s)))
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+    let _ = writeln!(
+        out,
+        "                inputs = [ {} ]",
+        port_list(&labels[0], &inputs)
+    );
+    let _ = writeln!(
+        out,
+        "                outputs = [ {} ]",
+        port_list(&labels[labels.len() - 1], &outputs)
+    );
+    let _ = writeln!(out, "            }}");
+    stream_props(
+        &mut out,
+        "capture.props",
+        &format!("effect_input.{}", node_name),
+        inputs.len(),
+        &[("media.class", "Audio/Sink")],
+    );
+    stream_props(
+        &mut out,
+        "playback.props",
+        &format!("effect_output.{}", node_name),
+        outputs.len(),
+        &[("node.passive", "true")],
+    );
+    let _ = writeln!(out, "        }}");
+    let _ = writeln!(out, "    }}");
+    let _ = writeln!(out, "]");
+    Ok(out)
+}
+
+// ---------------------------------------------------------------------------
+// Import
+// ---------------------------------------------------------------------------
+
+/// Minimal SPA-JSON reader: bare words for keys and values, `=` or `:`
+/// between key and value, optional commas and `#` comments.
+struct SpaJsonParser<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+}
+
+impl<'a> SpaJsonParser<'a> {
+    fn new(text: &'a str) -> Self {
+        Self {
+            chars: text.chars().peekable(),
+        }
+    }
+
+    /// Skip whitespace, commas and comments, returning the next character.
+    fn peek_token(&mut self) -> Option<char> {
+        while let Some(&c) = self.chars.peek() {
+            if c.is_whitespace() || c == ',' {
+                self.chars.next();
+            } else if c == '#' {
+                for c in self.chars.by_ref() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+            } else {
+                return Some(c);
+            }
+        }
+        None
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        if self.peek_token() == Some('"') {
+            self.chars.next();
+            let mut out = String::new();
+            loop {
+                match self.chars.next() {
+                    Some('"') => return Ok(out),
+                    Some('\\') => match self.chars.next() {
+                        Some('n') => out.push('\n'),
+                        Some('t') => out.push('\t'),
+                        Some(c) => out.push(c),
+                        None => return Err("unterminated string".to_string()),
+                    },
+                    Some(c) => out.push(c),
+                    None => return Err("unterminated string".to_string()),
+                }
+            }
+        }
+        let mut out = String::new();
+        while let Some(&c) = self.chars.peek() {
+            if c.is_whitespace() || matches!(c, ',' | '=' | ':' | '{' | '}' | '[' | ']' | '"' | '#')
+            {
+                break;
+            }
+            out.push(c);
+            self.chars.next();
+        }
+        if out.is_empty() {
+            return Err(format!(
+                "unexpected '{}'",
+                self.chars.peek().unwrap_or(&' ')
+            ));
+        }
+        Ok(out)
+    }
+
+    fn value(&mut self) -> Result<Value, String> {
+        match self.peek_token() {
+            Some('{') => {
+                self.chars.next();
+                self.object_body(Some('}'))
+            }
+            Some('[') => {
+                self.chars.next();
+                let mut items = Vec::new();
+                loop {
+                    match self.peek_token() {
+                        Some(']') => {
+                            self.chars.next();
+                            return Ok(Value::Array(items));
+                        }
+                        Some(_) => items.push(self.value()?),
+                        None => return Err("unterminated array".to_string()),
+                    }
+                }
+            }
+            Some('"') => self.string().map(Value::String),
+            Some(_) => {
+                let word = self.string()?;
+                Ok(match word.as_str() {
+                    "true" => Value::Bool(true),
+                    "false" => Value::Bool(false),
+                    "null" => Value::Null,
+                    _ => match word.parse::<f64>() {
+                        Ok(n) => serde_json::Number::from_f64(n)
+                            .map(Value::Number)
+                            .unwrap_or(Value::String(word)),
+                        Err(_) => Value::String(word),
+                    },
+                })
+            }
+            None => Err("unexpected end of input".to_string()),
+        }
+    }
+
+    /// Key/value pairs up to `end`, or to the end of input for the top level.
+    fn object_body(&mut self, end: Option<char>) -> Result<Value, String> {
+        let mut map = Map::new();
+        loop {
+            match self.peek_token() {
+                c if c == end => {
+                    self.chars.next();
+                    return Ok(Value::Object(map));
+                }
+                None => return Err("unterminated object".to_string()),
+                Some(_) => {
+                    let key = self.string()?;
+                    if matches!(self.peek_token(), Some('=' | ':')) {
+                        self.chars.next();
+                    }
+                    let value = self.value()?;
+                    map.insert(key, value);
+                }
+            }
+        }
+    }
+}
+
+/// Parse an SPA-JSON document (a PipeWire `.conf` file) into a JSON object.
+pub fn parse_spa_json(text: &str) -> Result<Value, String> {
+    SpaJsonParser::new(text).object_body(None)
+}
+
+/// LADSPA plugins with an LV2 build under a different identifier, keyed by
+/// LADSPA label.
+const LADSPA_EQUIVALENTS: &[(&str, &str)] = &[
+    (
+        "noise_suppressor_mono",
+        "https://github.com/werman/noise-suppression-for-voice#mono",
+    ),
+    (
+        "noise_suppressor_stereo",
+        "https://github.com/werman/noise-suppression-for-voice#stereo",
+    ),
+];
+
+/// The LV2 plugin equivalent to a filter-chain node, if the catalog has one.
+fn find_equivalent<'a>(
+    node: &Map<String, Value>,
+    catalog: &'a [PluginInfo],
+) -> Option<&'a PluginInfo> {
+    let kind = node.get("type").and_then(Value::as_str).unwrap_or("");
+    let plugin = node.get("plugin").and_then(Value::as_str).unwrap_or("");
+    let label = node.get("label").and_then(Value::as_str).unwrap_or("");
+    let lv2 = |uri: &str| {
+        catalog
+            .iter()
+            .find(|p| p.format == PluginFormat::Lv2 && p.uri == uri)
+    };
+    match kind {
+        "lv2" => lv2(plugin),
+        "ladspa" if !label.is_empty() => LADSPA_EQUIVALENTS
+            .iter()
+            .find(|(l, _)| *l == label)
+            .and_then(|(_, uri)| lv2(*uri))
+            // Plugin collections such as swh-plugins keep the LADSPA label
+            // as the last URI segment of their LV2 versions.
+            .or_else(|| {
+                catalog.iter().find(|p| {
+                    p.format == PluginFormat::Lv2
+                        && (p.uri.ends_with(&format!("/{}", label))
+                            || p.uri.ends_with(&format!("#{}", label)))
+                })
+            }),
+        _ => None,
+    }
+}
+
+/// The catalog port a filter-chain port name refers to: LV2 symbol first,
+/// then (for LADSPA) the human-readable port name.
+fn find_port(plugin: &PluginInfo, name: &str, port_type: PluginPortType) -> Option<usize> {
+    let ports = plugin.ports.iter().filter(|p| p.port_type == port_type);
+    ports.clone().position(|p| p.symbol == name).or_else(|| {
+        ports
+            .clone()
+            .position(|p| p.name.eq_ignore_ascii_case(name))
+    })
+}
+
+/// Split `"node:port"` into its parts. A bare node name means its first port.
+fn split_port(endpoint: &str) -> (&str, Option<&str>) {
+    match endpoint.split_once(':') {
+        Some((node, port)) => (node, Some(port)),
+        None => (endpoint, None),
+    }
+}
+
+fn import_graph(name: String, graph: &Map<String, Value>, catalog: &[PluginInfo]) -> ImportedChain {
+    let empty = Vec::new();
+    let nodes = graph
+        .get("nodes")
+        .and_then(Value::as_array)
+        .unwrap_or(&empty);
+    let links = graph
+        .get("links")
+        .and_then(Value::as_array)
+        .unwrap_or(&empty);
+
+    let mut stages = Vec::new();
+    let mut plugins: Vec<&PluginInfo> = Vec::new();
+    let mut skipped = Vec::new();
+    // filter-chain node name -> stage index, or None when skipped
+    let mut by_label: Vec<(String, Option<usize>)> = Vec::new();
+    for node in nodes.iter().filter_map(Value::as_object) {
+        let label = node
+            .get("name")
+            .and_then(Value::as_str)
+            .unwrap_or("")
+            .to_string();
+        match find_equivalent(node, catalog) {
+            Some(plugin) => {
+                let controls = node
+                    .get("control")
+                    .and_then(Value::as_object)
+                    .map(|control| {
+                        control
+                            .iter()
+                            .filter_map(|(key, value)| {
+                                let port = plugin.ports.iter().find(|p| {
+                                    p.port_type == PluginPortType::ControlInput
+                                        && (p.symbol == *key || p.name.eq_ignore_ascii_case(key))
+                                })?;
+                                Some((port.index, value.as_f64()? as f32))
+                            })
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                by_label.push((label, Some(stages.len())));
+                stages.push(ImportedStage {
+                    display_name: None,
+                    plugin_uri: plugin.uri.clone(),
+                    format: PluginFormat::Lv2,
+                    controls,
+                    bypassed: false,
+                });
+                plugins.push(plugin);
+            }
+            None => {
+                let kind = node.get("type").and_then(Value::as_str).unwrap_or("?");
+                let what = node
+                    .get("label")
+                    .or_else(|| node.get("plugin"))
+                    .and_then(Value::as_str)
+                    .unwrap_or("");
+                skipped.push(format!("{} ({} {})", label, kind, what));
+                by_label.push((label, None));
+            }
+        }
+    }
+    let stage_of = |label: &str| by_label.iter().find(|(l, _)| l == label).map(|(_, s)| *s);
+
+    // Raw edges between node labels; skipped nodes are bridged by joining
+    // every edge into one with every edge out of it.
+    let mut edges: Vec<(String, Option<String>, String, Option<String>)> = links
+        .iter()
+        .filter_map(Value::as_object)
+        .filter_map(|link| {
+            let (on, op) = split_port(link.get("output")?.as_str()?);
+            let (inn, ip) = split_port(link.get("input")?.as_str()?);
+            Some((
+                on.to_string(),
+                op.map(str::to_string),
+                inn.to_string(),
+                ip.map(str::to_string),
+            ))
+        })
+        .collect();
+    for (label, stage) in &by_label {
+        if stage.is_some() {
+            continue;
+        }
+        let (through, rest): (Vec<_>, Vec<_>) = edges
+            .into_iter()
+            .partition(|(on, _, inn, _)| on == label || inn == label);
+        edges = rest;
+        for (on, op, _, _) in through.iter().filter(|(_, _, inn, _)| inn == label) {
+            for (_, _, inn, ip) in through.iter().filter(|(on, _, _, _)| on == label) {
+                edges.push((on.clone(), op.clone(), inn.clone(), ip.clone()));
+            }
+        }
+    }
+
+    let mut imported_links = Vec::new();
+    for (on, op, inn, ip) in edges {
+        let (Some(Some(from)), Some(Some(to))) = (stage_of(&on), stage_of(&inn)) else {
+            continue;
+        };
+        let output = op
+            .and_then(|p| find_port(plugins[from], &p, PluginPortType::AudioOutput))
+            .unwrap_or(0);
+        let input = ip
+            .and_then(|p| find_port(plugins[to], &p, PluginPortType::AudioInput))
+            .unwrap_or(0);
+        let link = ImportedLink {
+            from,
+            output,
+            to,
+            input,
+        };
+        if !imported_links.contains(&link) {
+            imported_links.push(link);
+        }
+    }
+
+    ImportedChain {
+        name,
+        stages,
+        links: imported_links,
+        external: Vec::new(),
+        skipped,
+    }
+}
+
+/// Find every filter-chain graph in a PipeWire config and map it onto
+/// `catalog`. Fails if the file cannot be parsed or has no filter-chain.
+pub fn import_config(text: &str, catalog: &[PluginInfo]) -> Result<Vec<ImportedChain>, String> {
+    let root = parse_spa_json(text)?;
+    let empty = Vec::new();
+    let modules = root
+        .get("context.modules")
+        .and_then(Value::as_array)
+        .unwrap_or(&empty);
+
+    let mut chains = Vec::new();
+    for (i, module) in modules.iter().enumerate() {
+        let is_filter_chain = module
+            .get("name")
+            .and_then(Value::as_str)
+            .is_some_and(|n| n == "libpipewire-module-filter-chain");
+        let Some(args) = module.get("args").filter(|_| is_filter_chain) else {
+            continue;
+        };
+        let Some(graph) = args.get("filter.graph").and_then(Value::as_object) else {
+            continue;
+        };
+        let name = args
+            .get("node.description")
+            .or_else(|| args.get("media.name"))
+            .and_then(Value::as_str)
+            .map(str::to_string)
+            .unwrap_or_else(|| format!("Filter Chain {}", i + 1));
+        chains.push(import_graph(name, graph, catalog));
+    }
+    if chains.is_empty() {
+        return Err("no libpipewire-module-filter-chain graph found".to_string());
+    }
+    Ok(chains)
+}
@@ -0,0 +1,388 @@
+//! Re-creating plugin setups made with other tools as hosted chains.
+//!
+//! Importers map whatever they read onto plugins of the catalog and describe
+//! the result as an [`ImportedChain`]; the application then adds the plugins
+//! and queues their links like a restored session. Besides PipeWire
+//! filter-chain configs (see [`super::filter_chain`]) this reads:
+//! - Carla projects (`.carxp`), matching plugins by URI / plugin ID
+//! - RaySession sessions, by importing the Carla projects of their clients
+
+use std::path::{Path, PathBuf};
+
+use super::types::{PluginFormat, PluginInfo, PluginPortType};
+
+/// A plugin of an imported setup, mapped onto the catalog.
+#[derive(Debug, Clone)]
+pub struct ImportedStage {
+    /// Name the plugin had in the imported setup, if it should be kept.
+    pub display_name: Option<String>,
+    pub plugin_uri: String,
+    pub format: PluginFormat,
+    /// Control values by catalog port index.
+    pub controls: Vec<(usize, f32)>,
+    pub bypassed: bool,
+}
+
+/// An audio link between two imported stages, as indices into
+/// [`ImportedChain::stages`] and audio port (channel) indices.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ImportedLink {
+    pub from: usize,
+    pub output: usize,
+    pub to: usize,
+    pub input: usize,
+}
+
+/// An audio link between an imported stage and a node outside the setup,
+/// named the JACK way (client and port name).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExternalLink {
+    pub stage: usize,
+    /// Audio port (channel) index on the stage.
+    pub port: usize,
+    /// Whether the stage's port is an output (the link leaves the stage).
+    pub from_stage: bool,
+    pub node_name: String,
+    pub port_name: String,
+}
+
+/// One imported setup re-expressed as catalog plugins.
+#[derive(Debug, Clone)]
+pub struct ImportedChain {
+    pub name: String,
+    pub stages: Vec<ImportedStage>,
+    pub links: Vec<ImportedLink>,
+    pub external: Vec<ExternalLink>,
+    /// Descriptions of plugins that had no equivalent in the catalog.
+    pub skipped: Vec<String>,
+}
+
+/// Carla's own host ports in its internal patchbay. Their wiring depends on
+/// how Carla itself was connected, so links to them are not imported.
+const CARLA_HOST_CLIENTS: &[&str] = &["Audio Input", "Audio Output", "Midi Input", "Midi Output"];
+
+fn child<'a, 'i>(node: roxmltree::Node<'a, 'i>, tag: &str) -> Option<roxmltree::Node<'a, 'i>> {
+    node.children().find(|n| n.has_tag_name(tag))
+}
+
+fn child_text<'a>(node: roxmltree::Node<'a, '_>, tag: &str) -> Option<&'a str> {
+    child(node, tag).and_then(|n| n.text()).map(str::trim)
+}
+
+/// Position of the audio port called `name` (by name, then symbol) among
+/// the plugin's audio ports of `port_type`.
+fn audio_port_index(plugin: &PluginInfo, name: &str, port_type: PluginPortType) -> Option<usize> {
+    let ports = plugin.ports.iter().filter(|p| p.port_type == port_type);
+    ports
+        .clone()
+        .position(|p| p.name == name)
+        .or_else(|| ports.clone().position(|p| p.symbol == name))
+}
+
+fn audio_port_count(plugin: &PluginInfo, port_type: PluginPortType) -> usize {
+    plugin
+        .ports
+        .iter()
+        .filter(|p| p.port_type == port_type && !p.is_sidechain)
+        .count()
+}
+
+/// The catalog plugin a Carla `<Plugin><Info>` block refers to.
+fn find_carla_plugin<'a>(
+    info: roxmltree::Node,
+    catalog: &'a [PluginInfo],
+) -> Option<&'a PluginInfo> {
+    let kind = child_text(info, "Type").unwrap_or("");
+    let name = child_text(info, "Name").unwrap_or("");
+    let find = |format: PluginFormat, matches: &dyn Fn(&PluginInfo) -> bool| {
+        catalog.iter().find(|p| p.format == format && matches(p))
+    };
+    match kind {
+        "LV2" => {
+            let uri = child_text(info, "URI")?;
+            find(PluginFormat::Lv2, &|p| p.uri == uri)
+        }
+        "CLAP" => {
+            let id = child_text(info, "Label").unwrap_or("");
+            find(PluginFormat::Clap, &|p| p.uri == id)
+                .or_else(|| find(PluginFormat::Clap, &|p| p.name == name))
+        }
+        "VST3" => {
+            let binary = child_text(info, "Binary").unwrap_or("");
+            find(PluginFormat::Vst3, &|p| {
+                !binary.is_empty() && p.library_path == binary && p.name == name
+            })
+            .or_else(|| find(PluginFormat::Vst3, &|p| p.name == name))
+        }
+        _ => None,
+    }
+}
+
+/// Read a Carla project. Plugins are linked as in its patchbay, or one
+/// after the other for projects saved in rack mode.
+pub fn import_carla_project(
+    name: &str,
+    text: &str,
+    catalog: &[PluginInfo],
+) -> Result<ImportedChain, String> {
+    let doc = roxmltree::Document::parse(text).map_err(|e| format!("invalid XML: {}", e))?;
+    let root = doc.root_element();
+    if !root.has_tag_name("CARLA-PROJECT") {
+        return Err("not a Carla project".to_string());
+    }
+
+    let mut stages = Vec::new();
+    let mut plugins: Vec<&PluginInfo> = Vec::new();
+    let mut skipped = Vec::new();
+    // Carla client name -> stage index, or None when skipped
+    let mut clients: Vec<(String, Option<usize>)> = Vec::new();
+    for node in root.children().filter(|n| n.has_tag_name("Plugin")) {
+        let Some(info) = child(node, "Info") else {
+            continue;
+        };
+        let carla_name = child_text(info, "Name").unwrap_or("").to_string();
+        let Some(plugin) = find_carla_plugin(info, catalog) else {
+            let kind = child_text(info, "Type").unwrap_or("?");
+            skipped.push(format!("{} ({})", carla_name, kind));
+            clients.push((carla_name, None));
+            continue;
+        };
+
+        let data = child(node, "Data");
+        let controls = data
+            .map(|data| {
+                data.children()
+                    .filter(|n| n.has_tag_name("Parameter"))
+                    .filter_map(|param| {
+                        let symbol = child_text(param, "Symbol").unwrap_or("");
+                        let param_name = child_text(param, "Name").unwrap_or("");
+                        let value: f32 = child_text(param, "Value")?.parse().ok()?;
+                        let port = plugin.ports.iter().find(|p| {
+                            p.port_type == PluginPortType::ControlInput
+                                && ((!symbol.is_empty() && p.symbol == symbol)
+                                    || p.name == param_name)
+                        })?;
+                        Some((port.index, value))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        let bypassed = data
+            .and_then(|data| child_text(data, "Active"))
+            .is_some_and(|active| active == "No");
+
+        clients.push((carla_name.clone(), Some(stages.len())));
+        stages.push(ImportedStage {
+            display_name: (!carla_name.is_empty()).then_some(carla_name),
+            plugin_uri: plugin.uri.clone(),
+            format: plugin.format,
+            controls,
+            bypassed,
+        });
+        plugins.push(plugin);
+    }
+
+    // Split "client:port", preferring known plugin names since those may
+    // contain colons themselves.
+    let split = |endpoint: &str| -> Option<(String, String)> {
+        clients
+            .iter()
+            .map(|(client, _)| client)
+            .find(|client| {
+                endpoint.len() > client.len()
+                    && endpoint.starts_with(client.as_str())
+                    && endpoint[client.len()..].starts_with(':')
+            })
+            .map(|client| (client.clone(), endpoint[client.len() + 1..].to_string()))
+            .or_else(|| {
+                endpoint
+                    .split_once(':')
+                    .map(|(c, p)| (c.to_string(), p.to_string()))
+            })
+    };
+    let stage_of = |client: &str| clients.iter().find(|(c, _)| c == client).map(|(_, s)| *s);
+
+    let mut links = Vec::new();
+    let mut external = Vec::new();
+    let patchbay = child(root, "Patchbay");
+    let connections = patchbay
+        .into_iter()
+        .chain(child(root, "ExternalPatchbay"))
+        .flat_map(|section| section.children().filter(|n| n.has_tag_name("Connection")));
+    for connection in connections {
+        let (Some(source), Some(target)) = (
+            child_text(connection, "Source").and_then(&split),
+            child_text(connection, "Target").and_then(&split),
+        ) else {
+            continue;
+        };
+        if CARLA_HOST_CLIENTS.contains(&source.0.as_str())
+            || CARLA_HOST_CLIENTS.contains(&target.0.as_str())
+        {
+            continue;
+        }
+        match (stage_of(&source.0), stage_of(&target.0)) {
+            (Some(Some(from)), Some(Some(to))) => {
+                let output =
+                    audio_port_index(plugins[from], &source.1, PluginPortType::AudioOutput);
+                let input = audio_port_index(plugins[to], &target.1, PluginPortType::AudioInput);
+                if let (Some(output), Some(input)) = (output, input) {
+                    let link = ImportedLink {
+                        from,
+                        output,
+                        to,
+                        input,
+                    };
+                    if !links.contains(&link) {
+                        links.push(link);
+                    }
+                }
+            }
+            (Some(Some(stage)), None) => {
+                if let Some(port) =
+                    audio_port_index(plugins[stage], &source.1, PluginPortType::AudioOutput)
+                {
+                    external.push(ExternalLink {
+                        stage,
+                        port,
+                        from_stage: true,
+                        node_name: target.0,
+                        port_name: target.1,
+                    });
+                }
+            }
+            (None, Some(Some(stage))) => {
+                if let Some(port) =
+                    audio_port_index(plugins[stage], &target.1, PluginPortType::AudioInput)
+                {
+                    external.push(ExternalLink {
+                        stage,
+                        port,
+                        from_stage: false,
+                        node_name: source.0,
+                        port_name: source.1,
+                    });
+                }
+            }
+            // Links to skipped plugins, or between two outside clients.
+            _ => {}
+        }
+    }
+
+    if patchbay.is_none() {
+        // Rack mode: every plugin processes the output of the previous one.
+        for from in 1..stages.len() {
+            let outs = audio_port_count(plugins[from - 1], PluginPortType::AudioOutput);
+            let ins = audio_port_count(plugins[from], PluginPortType::AudioInput);
+            if outs == 0 {
+                continue;
+            }
+            for input in 0..ins {
+                links.push(ImportedLink {
+                    from: from - 1,
+                    output: input.min(outs - 1),
+                    to: from,
+                    input,
+                });
+            }
+        }
+    }
+
+    Ok(ImportedChain {
+        name: name.to_string(),
+        stages,
+        links,
+        external,
+        skipped,
+    })
+}
+
+/// Carla projects belonging to a RaySession session, at most two levels
+/// below the session directory (the clients' own directories).
+fn ray_session_projects(dir: &Path) -> Vec<PathBuf> {
+    let mut projects = Vec::new();
+    let mut dirs = vec![(dir.to_path_buf(), 0)];
+    while let Some((dir, depth)) = dirs.pop() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for path in entries.filter_map(|e| e.ok().map(|e| e.path())) {
+            if path.is_dir() && depth < 2 {
+                dirs.push((path, depth + 1));
+            } else if path.extension().is_some_and(|ext| ext == "carxp") {
+                projects.push(path);
+            }
+        }
+    }
+    projects.sort();
+    projects
+}
+
+/// Import a Carla project, or every Carla project of a RaySession session
+/// (given as its directory or `raysession.xml`).
+pub fn import_session(path: &Path, catalog: &[PluginInfo]) -> Result<Vec<ImportedChain>, String> {
+    let stem = |p: &Path| {
+        p.file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    };
+    let read =
+        |p: &Path| std::fs::read_to_string(p).map_err(|e| format!("failed to read {:?}: {}", p, e));
+
+    if path.extension().is_some_and(|ext| ext == "carxp") {
+        return Ok(vec![import_carla_project(
+            &stem(path),
+            &read(path)?,
+            catalog,
+        )?]);
+    }
+
+    let session_dir = if path.is_dir() {
+        path.to_path_buf()
+    } else if path.file_name().is_some_and(|n| n == "raysession.xml") {
+        path.parent().map(Path::to_path_buf).unwrap_or_default()
+    } else {
+        return Err("expected a Carla project (.carxp) or a RaySession session".to_string());
+    };
+    if !session_dir.join("raysession.xml").exists() {
+        return Err(format!("{:?} is not a RaySession session", session_dir));
+    }
+
+    let session_name = stem(&session_dir);
+    let chains: Vec<ImportedChain> = ray_session_projects(&session_dir)
+        .iter()
+        .filter_map(|project| {
+            let name = format!("{} / {}", session_name, stem(project));
+            match read(project).and_then(|text| import_carla_project(&name, &text, catalog)) {
+                Ok(chain) => Some(chain),
+                Err(e) => {
+                    log::warn!("Skipping {:?}: {}", project, e);
+                    None
+                }
+            }
+        })
+        .collect();
+    if chains.is_empty() {
+        return Err(format!(
+            "no Carla projects found in session '{}'",
+            session_name
+        ));
+    }
+    Ok(chains)
+}
+
+/// RaySession sessions under the default session root, for the import list.
+pub fn find_ray_sessions() -> Vec<PathBuf> {
+    let Some(home) = dirs::home_dir() else {
+        return Vec::new();
+    };
+    let Ok(entries) = std::fs::read_dir(home.join("Ray Sessions")) else {
+        return Vec::new();
+    };
+    let mut sessions: Vec<PathBuf> = entries
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.join("raysession.xml").exists())
+        .collect();
+    sessions.sort();
+    sessions
+}
@@ -0,0 +1,83 @@
+//! Per-plugin output meters for real-time process callbacks.
+//!
+//! Each filter owns the producer end of a [`meter_ring`] and pushes a
+//! [`MeterFrame`] after every process call while metering is on; the UI
+//! drains the consumer ends at its own rate. Metering is off until a meter
+//! is shown, so nobody pays for levels nobody reads.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+
+use zestbay_core::plugin::meter::{MeterConsumer, MeterFrame, RING_CAPACITY, meter_ring};
+pub use zestbay_core::plugin::meter::{MeterProducer, MeterReading};
+
+use super::types::PluginInstanceId;
+
+static METERING: AtomicBool = AtomicBool::new(false);
+
+/// Whether filters should measure their outputs. Checked from the RT thread.
+#[inline]
+pub fn metering() -> bool {
+    METERING.load(Ordering::Relaxed)
+}
+
+/// Measure `outputs` into `meter` if metering is on.
+#[inline]
+pub fn record<'a>(meter: &mut MeterProducer, outputs: impl IntoIterator<Item = &'a [f32]>) {
+    if metering() {
+        meter.push(MeterFrame::measure(outputs));
+    }
+}
+
+/// Global registry of the reading ends of plugin meters.
+pub struct PluginMeterRegistry {
+    consumers: Mutex<HashMap<PluginInstanceId, MeterConsumer>>,
+}
+
+impl PluginMeterRegistry {
+    pub fn new() -> Self {
+        Self {
+            consumers: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Register a plugin and return the end its RT thread pushes to.
+    pub fn register(&self, id: PluginInstanceId) -> MeterProducer {
+        let (producer, consumer) = meter_ring(RING_CAPACITY);
+        self.consumers.lock().unwrap().insert(id, consumer);
+        producer
+    }
+
+    /// Unregister a plugin when it's removed.
+    pub fn unregister(&self, id: PluginInstanceId) {
+        self.consumers.lock().unwrap().remove(&id);
+    }
+
+    /// Turn metering on or off. Frames left from an earlier run are
+    /// dropped, so the first reading is current.
+    pub fn set_metering(&self, on: bool) {
+        if on && !metering() {
+            for consumer in self.consumers.lock().unwrap().values_mut() {
+                while consumer.pop().is_some() {}
+            }
+        }
+        METERING.store(on, Ordering::Relaxed);
+    }
+
+    /// The levels of every plugin that processed audio since the last call.
+    pub fn take_readings(&self) -> Vec<(PluginInstanceId, MeterReading)> {
+        let mut consumers = self.consumers.lock().unwrap();
+        consumers
+            .iter_mut()
+            .filter_map(|(id, consumer)| consumer.read().map(|r| (*id, r)))
+            .collect()
+    }
+}
+
+/// Global singleton so filter callbacks can access it without passing through PipeWire.
+static GLOBAL_METERS: OnceLock<PluginMeterRegistry> = OnceLock::new();
+
+pub fn global_meters() -> &'static PluginMeterRegistry {
+    GLOBAL_METERS.get_or_init(PluginMeterRegistry::new)
+}
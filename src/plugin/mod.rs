@@ -1,14 +1,23 @@
 //! Format-agnostic plugin abstraction layer.
 //!
-//! This module defines the shared types and interfaces used by all plugin
-//! format backends (LV2, CLAP, VST3).  Each backend lives in its own
-//! top-level module (`src/lv2/`, `src/clap/`, `src/vst3/`) and feeds into
-//! the unified [`PluginManager`].
+//! The shared types and the unified [`PluginManager`] live in
+//! `zestbay_core::plugin`; this module adds the parts that run plugins.
+//! Each format backend lives in its own top-level module (`src/lv2/`,
+//! `src/clap/`, `src/vst3/`) and feeds into the manager.
 
+pub mod bypass;
 pub mod cpu_stats;
-pub mod manager;
+pub mod filter_chain;
+pub mod import;
+pub mod meters;
+pub mod node_latency;
+pub mod oversample;
 pub mod sandbox;
-pub mod types;
+pub mod tail;
+pub mod ui_scale;
 
-pub use manager::PluginManager;
+pub use zestbay_core::plugin::{
+    PluginManager, chain_file, cpu_history, cv, docs, formats, manager, meter, param_file, preset,
+    quantum, quick_params, randomize, replace, snippet, types, ui_geometry,
+};
 pub use types::*;
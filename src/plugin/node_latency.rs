@@ -0,0 +1,33 @@
+//! Updating `node.latency` on a running plugin filter node.
+//!
+//! The value is a fraction, `"<quantum>/<rate>"`, built by
+//! [`crate::plugin::quantum::node_latency`]. PipeWire picks up the change
+//! without the node being re-created.
+
+use std::ffi::CString;
+
+/// Set or remove `node.latency` on `filter`.
+///
+/// # Safety
+/// `filter` must be a live `pw_filter`, used on the PipeWire thread.
+pub unsafe fn request(filter: *mut pipewire::sys::pw_filter, latency: Option<&str>) {
+    let key = CString::new("node.latency").unwrap();
+    let value = latency.and_then(|l| CString::new(l).ok());
+    // A null value removes the key.
+    let item = libspa::sys::spa_dict_item {
+        key: key.as_ptr(),
+        value: value.as_ref().map_or(std::ptr::null(), |v| v.as_ptr()),
+    };
+    let dict = libspa::sys::spa_dict {
+        flags: 0,
+        n_items: 1,
+        items: &item,
+    };
+    let changed =
+        unsafe { pipewire::sys::pw_filter_update_properties(filter, std::ptr::null_mut(), &dict) };
+    log::debug!(
+        "node.latency -> {:?} ({} properties changed)",
+        latency,
+        changed
+    );
+}
@@ -0,0 +1,329 @@
+//! Optional 2x/4x oversampling around a hosted plugin's process call.
+//!
+//! The plugin is instantiated at `factor` times the graph rate. Each block
+//! is upsampled, processed at the high rate and filtered back down, so
+//! nonlinear plugins (saturators, clippers) alias less. The resampling
+//! filters are linear-phase FIRs whose delay is a whole number of samples at
+//! the graph rate; [`Oversampler::latency`] reports it so the bypass path can
+//! be aligned with it.
+
+use crate::midi::processing::{MAX_MIDI_EVENTS, RawMidiEvent};
+
+/// Oversampling factors offered per plugin instance.
+pub const OVERSAMPLING_FACTORS: [usize; 3] = [1, 2, 4];
+
+/// Largest block the PipeWire filters hand to a plugin, in graph-rate samples.
+pub const MAX_BLOCK: usize = 8192;
+
+/// Plugin channels beyond this are not fed or read while oversampling.
+const MAX_CHANNELS: usize = 32;
+
+/// Taps of the first (graph rate <-> 2x) stage. It has to hold the whole
+/// audio band, so it is the long one.
+const STAGE1_TAPS: usize = 129;
+const STAGE1_CUTOFF: f64 = 0.23;
+
+/// Taps of the second (2x <-> 4x) stage. The signal is already band-limited
+/// to a quarter of this rate, so a short filter is enough.
+const STAGE2_TAPS: usize = 33;
+const STAGE2_CUTOFF: f64 = 0.25;
+
+pub fn is_valid_factor(factor: usize) -> bool {
+    OVERSAMPLING_FACTORS.contains(&factor)
+}
+
+/// Linear-phase FIR with a doubled history buffer, so the convolution reads
+/// one contiguous slice.
+struct Fir {
+    taps: Vec<f32>,
+    history: Vec<f32>,
+    pos: usize,
+}
+
+impl Fir {
+    /// Blackman-windowed sinc lowpass; `cutoff` is in cycles per sample.
+    fn lowpass(len: usize, cutoff: f64) -> Self {
+        let mid = (len - 1) as f64 / 2.0;
+        let mut taps: Vec<f64> = (0..len)
+            .map(|i| {
+                let x = i as f64 - mid;
+                let sinc = if x == 0.0 {
+                    2.0 * cutoff
+                } else {
+                    (2.0 * std::f64::consts::PI * cutoff * x).sin() / (std::f64::consts::PI * x)
+                };
+                let phase = 2.0 * std::f64::consts::PI * i as f64 / (len - 1) as f64;
+                sinc * (0.42 - 0.5 * phase.cos() + 0.08 * (2.0 * phase).cos())
+            })
+            .collect();
+        let sum: f64 = taps.iter().sum();
+        for t in &mut taps {
+            *t /= sum;
+        }
+        Self {
+            taps: taps.into_iter().map(|t| t as f32).collect(),
+            history: vec![0.0; 2 * len],
+            pos: 0,
+        }
+    }
+
+    fn push(&mut self, x: f32) {
+        let len = self.taps.len();
+        self.pos = if self.pos == 0 { len - 1 } else { self.pos - 1 };
+        self.history[self.pos] = x;
+        self.history[self.pos + len] = x;
+    }
+
+    fn output(&self) -> f32 {
+        let len = self.taps.len();
+        self.history[self.pos..self.pos + len]
+            .iter()
+            .zip(&self.taps)
+            .map(|(x, t)| x * t)
+            .sum()
+    }
+
+    /// Zero-stuff `src` to twice its rate and interpolate into `dst`.
+    fn upsample(&mut self, src: &[f32], dst: &mut [f32]) {
+        for (x, pair) in src.iter().zip(dst.chunks_exact_mut(2)) {
+            self.push(*x);
+            pair[0] = 2.0 * self.output();
+            self.push(0.0);
+            pair[1] = 2.0 * self.output();
+        }
+    }
+
+    /// Filter `src` and keep every second sample. Sampling on the even
+    /// phase keeps the up/down round trip an integer delay.
+    fn downsample(&mut self, src: &[f32], dst: &mut [f32]) {
+        for (pair, y) in src.chunks_exact(2).zip(dst.iter_mut()) {
+            self.push(pair[0]);
+            *y = self.output();
+            self.push(pair[1]);
+        }
+    }
+}
+
+/// One 2x step: an interpolator per input and a decimator per output.
+struct Stage {
+    up: Vec<Fir>,
+    down: Vec<Fir>,
+}
+
+pub struct Oversampler {
+    factor: usize,
+    stages: Vec<Stage>,
+    /// Plugin-side buffers at the high rate.
+    high_in: Vec<Vec<f32>>,
+    high_out: Vec<Vec<f32>>,
+    /// Ping-pong buffers for the intermediate stage.
+    scratch: [Vec<f32>; 2],
+    latency: usize,
+}
+
+impl Oversampler {
+    /// Returns `None` for a factor of 1 (or an unsupported one): the plugin
+    /// then runs directly on the graph buffers.
+    pub fn new(factor: usize, inputs: usize, outputs: usize) -> Option<Self> {
+        if factor < 2 || !is_valid_factor(factor) {
+            return None;
+        }
+        let inputs = inputs.min(MAX_CHANNELS);
+        let outputs = outputs.min(MAX_CHANNELS);
+        let specs = [(STAGE1_TAPS, STAGE1_CUTOFF), (STAGE2_TAPS, STAGE2_CUTOFF)];
+        let stages: Vec<Stage> = specs
+            .iter()
+            .take(factor.trailing_zeros() as usize)
+            .map(|&(taps, cutoff)| Stage {
+                up: (0..inputs).map(|_| Fir::lowpass(taps, cutoff)).collect(),
+                down: (0..outputs).map(|_| Fir::lowpass(taps, cutoff)).collect(),
+            })
+            .collect();
+
+        // A stage running at 2^k times the graph rate delays by its FIR
+        // delay on the way up and again on the way down.
+        let latency = specs
+            .iter()
+            .take(stages.len())
+            .enumerate()
+            .map(|(k, &(taps, _))| (taps - 1) >> k)
+            .sum::<usize>()
+            / 2;
+
+        let high_len = MAX_BLOCK * factor;
+        Some(Self {
+            factor,
+            stages,
+            high_in: vec![vec![0.0; high_len]; inputs],
+            high_out: vec![vec![0.0; high_len]; outputs],
+            scratch: [vec![0.0; high_len], vec![0.0; high_len]],
+            latency,
+        })
+    }
+
+    pub fn factor(&self) -> usize {
+        self.factor
+    }
+
+    /// Delay added by the resampling filters, in graph-rate samples.
+    pub fn latency(&self) -> usize {
+        self.latency
+    }
+
+    /// Total latency in graph-rate samples for a plugin reporting
+    /// `plugin_latency` samples at the high rate.
+    pub fn total_latency(&self, plugin_latency: usize) -> usize {
+        self.latency + (plugin_latency + self.factor / 2) / self.factor
+    }
+
+    /// Copy `events` into `out` with their offsets moved to the high rate.
+    pub fn scale_midi_events(
+        &self,
+        events: &[RawMidiEvent],
+        out: &mut [RawMidiEvent; MAX_MIDI_EVENTS],
+    ) -> usize {
+        let count = events.len().min(MAX_MIDI_EVENTS);
+        for (dst, src) in out.iter_mut().zip(&events[..count]) {
+            *dst = *src;
+            dst.offset = src.offset * self.factor as u32;
+        }
+        count
+    }
+
+    /// Upsample `inputs`, let `run` process `sample_count * factor` frames,
+    /// and downsample the result into `outputs`. RT-safe: no allocation.
+    pub fn process<F>(
+        &mut self,
+        inputs: &[&[f32]],
+        outputs: &mut [&mut [f32]],
+        sample_count: usize,
+        mut run: F,
+    ) where
+        F: FnMut(&[&[f32]], &mut [&mut [f32]], usize),
+    {
+        let n = sample_count.min(MAX_BLOCK);
+        let high_n = n * self.factor;
+
+        for (ch, high) in self.high_in.iter_mut().enumerate() {
+            let mut len = n;
+            match inputs.get(ch) {
+                Some(input) if input.len() >= n => {
+                    self.scratch[0][..n].copy_from_slice(&input[..n])
+                }
+                _ => self.scratch[0][..n].fill(0.0),
+            }
+            for stage in &mut self.stages {
+                let [src, dst] = &mut self.scratch;
+                stage.up[ch].upsample(&src[..len], &mut dst[..2 * len]);
+                self.scratch.swap(0, 1);
+                len *= 2;
+            }
+            high[..high_n].copy_from_slice(&self.scratch[0][..high_n]);
+        }
+
+        {
+            let (n_in, n_out) = (self.high_in.len(), self.high_out.len());
+            let mut ins: [&[f32]; MAX_CHANNELS] = Default::default();
+            for (slot, buf) in ins.iter_mut().zip(&self.high_in) {
+                *slot = &buf[..high_n];
+            }
+            let mut outs: [&mut [f32]; MAX_CHANNELS] = Default::default();
+            for (slot, buf) in outs.iter_mut().zip(self.high_out.iter_mut()) {
+                *slot = &mut buf[..high_n];
+            }
+            run(&ins[..n_in], &mut outs[..n_out], high_n);
+        }
+
+        for (ch, output) in outputs.iter_mut().enumerate().take(self.high_out.len()) {
+            let mut len = high_n;
+            self.scratch[0][..high_n].copy_from_slice(&self.high_out[ch][..high_n]);
+            for stage in self.stages.iter_mut().rev() {
+                let [src, dst] = &mut self.scratch;
+                stage.down[ch].downsample(&src[..len], &mut dst[..len / 2]);
+                self.scratch.swap(0, 1);
+                len /= 2;
+            }
+            let m = n.min(output.len());
+            output[..m].copy_from_slice(&self.scratch[0][..m]);
+        }
+    }
+}
+
+/// Graph-rate latency of a plugin reporting `plugin_latency` samples at its
+/// own (possibly oversampled) rate.
+pub fn compensated_latency(oversampler: Option<&Oversampler>, plugin_latency: usize) -> usize {
+    match oversampler {
+        Some(os) => os.total_latency(plugin_latency),
+        None => plugin_latency,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn passthrough(factor: usize, input: &[f32]) -> (Vec<f32>, usize) {
+        let mut os = Oversampler::new(factor, 1, 1).unwrap();
+        let mut out = vec![0.0; input.len()];
+        let mut frames = 0;
+        for (src, dst) in input.chunks(256).zip(out.chunks_mut(256)) {
+            let inputs: [&[f32]; 1] = [src];
+            let mut outputs: [&mut [f32]; 1] = [dst];
+            os.process(&inputs, &mut outputs, src.len(), |ins, outs, n| {
+                frames += n;
+                outs[0][..n].copy_from_slice(&ins[0][..n]);
+            });
+        }
+        assert_eq!(frames, input.len() * factor);
+        (out, os.latency())
+    }
+
+    #[test]
+    fn test_factor_one_is_disabled() {
+        assert!(Oversampler::new(1, 2, 2).is_none());
+        assert!(Oversampler::new(3, 2, 2).is_none());
+    }
+
+    #[test]
+    fn test_passthrough_is_delayed_by_reported_latency() {
+        let input: Vec<f32> = (0..4096)
+            .map(|i| (2.0 * std::f32::consts::PI * 0.05 * i as f32).sin())
+            .collect();
+        for factor in [2, 4] {
+            let (out, latency) = passthrough(factor, &input);
+            assert!(latency > 0);
+            for i in 1024..input.len() {
+                assert!(
+                    (out[i] - input[i - latency]).abs() < 0.01,
+                    "{}x mismatch at {}",
+                    factor,
+                    i
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_plugin_latency_is_scaled_to_graph_rate() {
+        let os = Oversampler::new(4, 1, 1).unwrap();
+        assert_eq!(os.total_latency(8), os.latency() + 2);
+        assert_eq!(compensated_latency(None, 8), 8);
+    }
+
+    #[test]
+    fn test_midi_offsets_move_to_high_rate() {
+        let os = Oversampler::new(2, 1, 1).unwrap();
+        let events = [RawMidiEvent {
+            offset: 10,
+            data: [0x90, 60, 100],
+            size: 3,
+        }];
+        let mut out = [RawMidiEvent {
+            offset: 0,
+            data: [0; 3],
+            size: 0,
+        }; MAX_MIDI_EVENTS];
+        assert_eq!(os.scale_midi_events(&events, &mut out), 1);
+        assert_eq!(out[0].offset, 20);
+    }
+}
@@ -418,6 +418,9 @@ pub fn run_probe_main(args: &[String]) -> ! {
                 has_ui: false,
                 format: crate::lv2::PluginFormat::Lv2,
                 library_path: String::new(),
+                version: None,
+                modified: None,
+                unsupported_ports: classification.unsupported_ports,
             };
             eprintln!(
                 "probe: LV2 plugin found: {} (ports: {} audio_in, {} audio_out, {} ctrl_in)",
@@ -437,6 +440,7 @@ pub fn run_probe_main(args: &[String]) -> ! {
                     &info,
                     sample_rate,
                     block_length,
+                    1,
                     &urid_mapper,
                 )
             };
@@ -451,6 +455,7 @@ pub fn run_probe_main(args: &[String]) -> ! {
                         uri,
                         info,
                         sample_rate,
+                        1,
                     )
                 };
             }
@@ -464,6 +469,7 @@ pub fn run_probe_main(args: &[String]) -> ! {
                         uri,
                         info,
                         sample_rate,
+                        1,
                     )
                 };
             }
@@ -0,0 +1,143 @@
+//! Tails of removed and bypassed plugins, shared by the plugin hosts.
+//!
+//! With tails enabled, a plugin being removed or bypassed keeps running
+//! with its inputs faded to silence and no new notes, so a reverb or delay
+//! rings out instead of stopping dead and a synth releases what it holds. A removed plugin's tail plays on its
+//! own and the manager destroys the instance once it has ended; a bypassed
+//! one's is mixed over the dry signal by the bypass crossfader.
+
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use crate::midi::processing::{MAX_MIDI_EVENTS, RawMidiEvent};
+
+pub use zestbay_core::plugin::tail::*;
+
+/// Fade of the inputs when a tail starts or is called off.
+const FADE_SECONDS: f64 = 0.02;
+
+const MAX_BLOCK: usize = 8192;
+
+/// Inputs beyond this are left out while faded, as with oversampling.
+const MAX_CHANNELS: usize = 32;
+
+static TAIL_SECONDS: AtomicU32 = AtomicU32::new(0);
+
+/// Let tails ring out for at most `seconds`; 0 cuts plugins off at once.
+pub fn set_tail_seconds(seconds: u32) {
+    TAIL_SECONDS.store(seconds, Ordering::Relaxed);
+}
+
+pub fn tail_seconds() -> u32 {
+    TAIL_SECONDS.load(Ordering::Relaxed)
+}
+
+#[derive(Default)]
+pub struct PluginTail {
+    gate: TailGate,
+    /// Faded copies of the inputs, allocated up front.
+    faded: Vec<Vec<f32>>,
+    /// Gain of the inputs: 1.0 while playing, 0.0 once muted.
+    gain: f32,
+    step: f32,
+    sample_rate: f64,
+    muted: bool,
+}
+
+impl PluginTail {
+    pub fn new(channels: usize, sample_rate: f64) -> Self {
+        let fade_samples = (FADE_SECONDS * sample_rate).max(1.0);
+        Self {
+            gate: TailGate::new(sample_rate),
+            faded: vec![vec![0.0; MAX_BLOCK]; channels.min(MAX_CHANNELS)],
+            gain: 1.0,
+            step: (1.0 / fade_samples) as f32,
+            sample_rate,
+            muted: false,
+        }
+    }
+
+    /// Whether the plugin is ringing out: its inputs are muted and the tail
+    /// has not ended yet.
+    pub fn is_ringing(&self) -> bool {
+        self.gate.is_active()
+    }
+
+    /// Whether the inputs are muted and the tail has ended.
+    pub fn is_finished(&self) -> bool {
+        self.muted && !self.gate.is_active()
+    }
+
+    /// Run one block through `run`. While `mute` is set and tails are
+    /// enabled, `run` gets the inputs faded to silence and the MIDI events
+    /// without note-ons. RT-safe: no allocation, no locks.
+    pub fn process<F>(
+        &mut self,
+        mute: bool,
+        inputs: &[&[f32]],
+        outputs: &mut [&mut [f32]],
+        sample_count: usize,
+        midi_events: &[RawMidiEvent],
+        mut run: F,
+    ) where
+        F: FnMut(&[&[f32]], &mut [&mut [f32]], &[RawMidiEvent]),
+    {
+        let mute = mute && tail_seconds() > 0;
+        if mute != self.muted {
+            self.muted = mute;
+            if mute {
+                self.gate
+                    .start((tail_seconds() as f64 * self.sample_rate) as usize);
+            } else {
+                self.gate.stop();
+            }
+        }
+        if !self.muted && self.gain >= 1.0 {
+            run(inputs, outputs, midi_events);
+            return;
+        }
+
+        let n = sample_count.min(MAX_BLOCK);
+        let target = if self.muted { 0.0 } else { 1.0 };
+        let mut gain_end = target;
+        for (ch, faded) in self.faded.iter_mut().enumerate() {
+            let input = inputs.get(ch);
+            let mut gain = self.gain;
+            for (i, sample) in faded[..n].iter_mut().enumerate() {
+                gain = if target > gain {
+                    (gain + self.step).min(1.0)
+                } else {
+                    (gain - self.step).max(0.0)
+                };
+                *sample = input.and_then(|x| x.get(i)).copied().unwrap_or(0.0) * gain;
+            }
+            gain_end = gain;
+        }
+        self.gain = gain_end;
+
+        let mut events = [RawMidiEvent {
+            offset: 0,
+            data: [0; 3],
+            size: 0,
+        }; MAX_MIDI_EVENTS];
+        let mut n_events = 0;
+        for event in midi_events {
+            if !self.muted || !is_note_on(event) {
+                events[n_events] = *event;
+                n_events += 1;
+            }
+        }
+
+        {
+            let mut ins: [&[f32]; MAX_CHANNELS] = Default::default();
+            for (slot, buf) in ins.iter_mut().zip(&self.faded) {
+                *slot = &buf[..n];
+            }
+            run(&ins[..self.faded.len()], outputs, &events[..n_events]);
+        }
+        self.gate.observe(outputs.iter().map(|o| &**o), n);
+    }
+}
+
+fn is_note_on(event: &RawMidiEvent) -> bool {
+    event.size == 3 && event.data[0] & 0xF0 == 0x90 && event.data[2] > 0
+}
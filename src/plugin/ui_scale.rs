@@ -0,0 +1,37 @@
+//! Scale factor override for plugin editors.
+//!
+//! Small plugin GUIs can be drawn larger on HiDPI screens independently of
+//! the main window scaling. The application keeps the override per plugin
+//! and hands it over here by instance ID before asking for a UI to open; the
+//! format hosts pass it on as LV2 `ui:scaleFactor`, VST3 content scale or
+//! CLAP `gui.set_scale`.
+
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+/// Scale factors offered in the UI, besides the plugin's own default.
+pub const UI_SCALE_FACTORS: [f32; 4] = [1.0, 1.25, 1.5, 2.0];
+
+static SCALES: Mutex<BTreeMap<u64, f32>> = Mutex::new(BTreeMap::new());
+
+pub fn is_valid_scale(scale: f32) -> bool {
+    UI_SCALE_FACTORS.contains(&scale)
+}
+
+/// Set (or clear) the scale the next UI of `instance_id` opens with.
+pub fn set_for_instance(instance_id: u64, scale: Option<f32>) {
+    let mut scales = SCALES.lock().unwrap();
+    match scale {
+        Some(scale) => {
+            scales.insert(instance_id, scale);
+        }
+        None => {
+            scales.remove(&instance_id);
+        }
+    }
+}
+
+/// The scale override for a new UI of `instance_id`, if any.
+pub fn scale_for(instance_id: u64) -> Option<f32> {
+    SCALES.lock().unwrap().get(&instance_id).copied()
+}
@@ -0,0 +1,255 @@
+//! Window show/hide support that does not depend on X11.
+//!
+//! Under Wayland an application cannot raise its own window or quietly keep
+//! running without one, so ZestBay goes through the desktop instead:
+//!
+//! - `org.freedesktop.portal.Background` is asked for permission to keep
+//!   running once the window is hidden to the tray, so the desktop neither
+//!   treats the windowless process as stale nor kills it.
+//! - `org.freedesktop.Application` is served as `org.zestbay.ZestBay`. A second
+//!   launch (from the app grid, a background-apps list or a shortcut) hands
+//!   its activation token to the running instance through `Activate` and
+//!   exits, and the running instance uses the token to raise its window.
+//!
+//! Everything is optional: without a portal or session bus the X11-style
+//! show/hide in main.qml is used as before.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use zbus::zvariant::{OwnedValue, Value};
+
+const APP_BUS_NAME: &str = "org.zestbay.ZestBay";
+const APP_OBJECT_PATH: &str = "/org/zestbay/ZestBay";
+const APPLICATION_INTERFACE: &str = "org.freedesktop.Application";
+const PORTAL_BUS_NAME: &str = "org.freedesktop.portal.Desktop";
+const PORTAL_OBJECT_PATH: &str = "/org/freedesktop/portal/desktop";
+const BACKGROUND_INTERFACE: &str = "org.freedesktop.portal.Background";
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Whether the session is running on Wayland rather than X11/XWayland.
+pub fn is_wayland_session() -> bool {
+    let forced_x11 = std::env::var("QT_QPA_PLATFORM").is_ok_and(|p| p.starts_with("xcb"));
+    std::env::var_os("WAYLAND_DISPLAY").is_some() && !forced_x11
+}
+
+#[derive(Clone)]
+pub struct PortalState {
+    /// Set once the Background portal was found on the session bus.
+    pub background_supported: Arc<AtomicBool>,
+    /// Set when another launch asked this instance to show itself.
+    pub activate_requested: Arc<AtomicBool>,
+    /// Activation token that came with the last activation request.
+    pub activation_token: Arc<Mutex<Option<String>>>,
+    background_wanted: Arc<AtomicBool>,
+}
+
+impl PortalState {
+    fn new() -> Self {
+        Self {
+            background_supported: Arc::new(AtomicBool::new(false)),
+            activate_requested: Arc::new(AtomicBool::new(false)),
+            activation_token: Arc::new(Mutex::new(None)),
+            background_wanted: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Ask the desktop to let ZestBay keep running without a window. The
+    /// portal remembers the answer, so this is only sent once per run.
+    pub fn request_background(&self) {
+        self.background_wanted.store(true, Ordering::Release);
+    }
+
+    /// Take the pending activation, returning its token if there was one.
+    pub fn take_activation(&self) -> Option<Option<String>> {
+        if !self.activate_requested.swap(false, Ordering::AcqRel) {
+            return None;
+        }
+        Some(
+            self.activation_token
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .take(),
+        )
+    }
+}
+
+/// The token a launcher passed for raising a window: the Wayland activation
+/// token, or the X11 startup notification id.
+fn activation_token(platform_data: &mut HashMap<String, OwnedValue>) -> Option<String> {
+    ["activation-token", "desktop-startup-id"]
+        .iter()
+        .filter_map(|key| platform_data.remove(*key))
+        .find_map(|value| String::try_from(value).ok())
+}
+
+struct Application {
+    state: PortalState,
+}
+
+#[zbus::interface(name = "org.freedesktop.Application")]
+impl Application {
+    fn activate(&self, platform_data: HashMap<String, OwnedValue>) {
+        let mut platform_data = platform_data;
+        let token = activation_token(&mut platform_data);
+        log::info!("Activation requested (token: {})", token.is_some());
+        *self
+            .state
+            .activation_token
+            .lock()
+            .unwrap_or_else(|e| e.into_inner()) = token;
+        self.state.activate_requested.store(true, Ordering::Release);
+    }
+
+    fn open(&self, _uris: Vec<String>, platform_data: HashMap<String, OwnedValue>) {
+        self.activate(platform_data);
+    }
+
+    fn activate_action(
+        &self,
+        _action_name: &str,
+        _parameter: Vec<OwnedValue>,
+        platform_data: HashMap<String, OwnedValue>,
+    ) {
+        self.activate(platform_data);
+    }
+}
+
+async fn background_portal_version(connection: &zbus::Connection) -> zbus::Result<u32> {
+    let proxy = zbus::Proxy::new(
+        connection,
+        PORTAL_BUS_NAME,
+        PORTAL_OBJECT_PATH,
+        BACKGROUND_INTERFACE,
+    )
+    .await?;
+    Ok(proxy.get_property::<u32>("version").await?)
+}
+
+async fn request_background(connection: &zbus::Connection) -> zbus::Result<()> {
+    let proxy = zbus::Proxy::new(
+        connection,
+        PORTAL_BUS_NAME,
+        PORTAL_OBJECT_PATH,
+        BACKGROUND_INTERFACE,
+    )
+    .await?;
+    let options: HashMap<&str, Value> = HashMap::from([
+        ("handle_token", Value::from("zestbay_background")),
+        (
+            "reason",
+            Value::from(
+                "ZestBay keeps routing audio and hosting plugins while its window is hidden.",
+            ),
+        ),
+    ]);
+    let _request: zbus::zvariant::OwnedObjectPath =
+        proxy.call("RequestBackground", &("", options)).await?;
+    Ok(())
+}
+
+async fn serve(state: PortalState) -> zbus::Result<()> {
+    let connection = zbus::connection::Builder::session()?
+        .serve_at(
+            APP_OBJECT_PATH,
+            Application {
+                state: state.clone(),
+            },
+        )?
+        .build()
+        .await?;
+
+    if let Err(e) = connection.request_name(APP_BUS_NAME).await {
+        log::warn!("Could not own {}: {}", APP_BUS_NAME, e);
+    }
+
+    // Without the portal the activation interface is still served.
+    match background_portal_version(&connection).await {
+        Ok(version) => {
+            log::info!("Background portal available (version {})", version);
+            state.background_supported.store(true, Ordering::Release);
+        }
+        Err(_) => log::info!("Background portal not available"),
+    }
+
+    let mut requested = false;
+    loop {
+        tokio::time::sleep(POLL_INTERVAL).await;
+        if requested
+            || !state.background_supported.load(Ordering::Acquire)
+            || !state.background_wanted.load(Ordering::Acquire)
+        {
+            continue;
+        }
+        requested = true;
+        match request_background(&connection).await {
+            Ok(()) => log::info!("Requested permission to run in the background"),
+            Err(e) => log::warn!("Background portal request failed: {}", e),
+        }
+    }
+}
+
+/// Serve the activation interface and watch for background requests on
+/// their own thread.
+pub fn spawn_portal() -> PortalState {
+    let state = PortalState::new();
+    let service_state = state.clone();
+
+    std::thread::Builder::new()
+        .name("zestbay-portal".into())
+        .spawn(move || {
+            let runtime = match tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+            {
+                Ok(rt) => rt,
+                Err(e) => {
+                    log::warn!("Portal: failed to start runtime: {}", e);
+                    return;
+                }
+            };
+            if let Err(e) = runtime.block_on(serve(service_state)) {
+                log::warn!("Portal integration unavailable: {}", e);
+            }
+        })
+        .expect("Failed to spawn portal thread");
+
+    state
+}
+
+/// If another ZestBay is already running, ask it to show its window,
+/// forwarding this launch's activation token. Returns true when it did, in
+/// which case this process should exit.
+pub fn activate_running_instance() -> bool {
+    let runtime = match tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+    {
+        Ok(rt) => rt,
+        Err(_) => return false,
+    };
+    runtime.block_on(async {
+        let Ok(connection) = zbus::Connection::session().await else {
+            return false;
+        };
+        let mut platform_data: HashMap<&str, Value> = HashMap::new();
+        if let Ok(token) = std::env::var("XDG_ACTIVATION_TOKEN") {
+            platform_data.insert("activation-token", Value::from(token));
+        }
+        if let Ok(id) = std::env::var("DESKTOP_STARTUP_ID") {
+            platform_data.insert("desktop-startup-id", Value::from(id));
+        }
+        connection
+            .call_method(
+                Some(APP_BUS_NAME),
+                APP_OBJECT_PATH,
+                Some(APPLICATION_INTERFACE),
+                "Activate",
+                &(platform_data,),
+            )
+            .await
+            .is_ok()
+    })
+}
@@ -0,0 +1,77 @@
+//! Whether the machine runs on battery, from UPower on the system bus.
+//!
+//! Used to switch to the battery performance profile. Without UPower, or on
+//! a machine without a battery, ZestBay is taken to be on AC power.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+const UPOWER_BUS_NAME: &str = "org.freedesktop.UPower";
+const UPOWER_OBJECT_PATH: &str = "/org/freedesktop/UPower";
+const UPOWER_INTERFACE: &str = "org.freedesktop.UPower";
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+#[derive(Clone)]
+pub struct PowerState {
+    on_battery: Arc<AtomicBool>,
+}
+
+impl PowerState {
+    pub fn on_battery(&self) -> bool {
+        self.on_battery.load(Ordering::Acquire)
+    }
+}
+
+async fn watch(state: PowerState) -> zbus::Result<()> {
+    let connection = zbus::Connection::system().await?;
+    // The proxy caches OnBattery and keeps it current from
+    // PropertiesChanged, so polling it costs no bus traffic.
+    let proxy = zbus::Proxy::new(
+        &connection,
+        UPOWER_BUS_NAME,
+        UPOWER_OBJECT_PATH,
+        UPOWER_INTERFACE,
+    )
+    .await?;
+    let mut last = None;
+    loop {
+        let on_battery = proxy.get_property::<bool>("OnBattery").await?;
+        if last != Some(on_battery) {
+            log::info!("Power: {}", if on_battery { "on battery" } else { "on AC" });
+            last = Some(on_battery);
+            state.on_battery.store(on_battery, Ordering::Release);
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+/// Follow UPower's `OnBattery` on its own thread.
+pub fn spawn_power_watch() -> PowerState {
+    let state = PowerState {
+        on_battery: Arc::new(AtomicBool::new(false)),
+    };
+    let watch_state = state.clone();
+
+    std::thread::Builder::new()
+        .name("zestbay-power".into())
+        .spawn(move || {
+            let runtime = match tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+            {
+                Ok(rt) => rt,
+                Err(e) => {
+                    log::warn!("Power: failed to start runtime: {}", e);
+                    return;
+                }
+            };
+            if let Err(e) = runtime.block_on(watch(watch_state.clone())) {
+                log::info!("Power: UPower not available: {}", e);
+                watch_state.on_battery.store(false, Ordering::Release);
+            }
+        })
+        .expect("Failed to spawn power thread");
+
+    state
+}
@@ -0,0 +1,677 @@
+//! Embedded HTTP + WebSocket server for remote patchbays.
+//!
+//! Every request must carry an access token, either as
+//! `Authorization: Bearer <token>` or as a `?token=` query parameter (which
+//! is what browsers have to use for WebSockets). What a token may do depends
+//! on its [`ApiScope`]. With a certificate and key configured the server
+//! only speaks TLS (`https://` and `wss://`). Browsers on other origins
+//! are only let in when an allowed origin is configured; no CORS headers
+//! are sent otherwise.
+//!
+//! - `GET /api/graph`: the current graph as `{ nodes, links, ports }`, in the
+//!   same shape the QML views get
+//! - `POST /api/command`: one [`RemoteRequest`] as JSON, e.g.
+//!   `{"op":"connect","output_port_id":41,"input_port_id":57}`
+//! - `GET /api/events`: WebSocket streaming `{"event":..., "data":...}`
+//!   messages (`session` with the token's scope first, then `graph`,
+//!   `parameter`, `patchbay`, `error`); commands can be sent back over the
+//!   same socket
+//!
+//! The application publishes snapshots and events into [`RemoteApiState`]
+//! and executes the requests queued there; the server threads only forward.
+
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, SyncSender, TryRecvError};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use rustls::{ServerConfig, ServerConnection, StreamOwned};
+use serde::{Deserialize, Serialize};
+use tungstenite::protocol::Role;
+use tungstenite::{Message, WebSocket};
+use zestbay_core::access::{ApiScope, ApiToken, find_token};
+
+const ACCEPT_INTERVAL: Duration = Duration::from_millis(100);
+const SOCKET_POLL_INTERVAL: Duration = Duration::from_millis(50);
+const MAX_HEADER_LINES: usize = 64;
+const MAX_HEADER_LINE_BYTES: u64 = 8 * 1024;
+/// Connections served at once; more are turned away until one closes.
+const MAX_CONNECTIONS: usize = 32;
+const MAX_BODY_BYTES: usize = 64 * 1024;
+/// How long a write to a client may block before the connection is dropped.
+const WRITE_TIMEOUT: Duration = Duration::from_secs(5);
+/// Events queued for one WebSocket client. A client this far behind is
+/// disconnected rather than buffered for.
+const MAX_QUEUED_EVENTS: usize = 256;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum RemoteRequest {
+    /// Refused with an `error` event naming the loop when the link would
+    /// close an audio feedback loop, unless `allow_feedback` is set.
+    Connect {
+        output_port_id: u32,
+        input_port_id: u32,
+        #[serde(default)]
+        allow_feedback: bool,
+    },
+    Disconnect {
+        link_id: u32,
+    },
+    SetPluginBypass {
+        node_id: u32,
+        bypassed: bool,
+    },
+    SetPluginParameter {
+        node_id: u32,
+        port_index: u32,
+        value: f32,
+    },
+    SetPatchbayEnabled {
+        enabled: bool,
+    },
+    ApplyRules,
+}
+
+impl RemoteRequest {
+    /// The scope a token needs to make this request.
+    pub fn scope(&self) -> ApiScope {
+        match self {
+            RemoteRequest::Connect { .. }
+            | RemoteRequest::Disconnect { .. }
+            | RemoteRequest::SetPatchbayEnabled { .. }
+            | RemoteRequest::ApplyRules => ApiScope::Control,
+            RemoteRequest::SetPluginBypass { .. } | RemoteRequest::SetPluginParameter { .. } => {
+                ApiScope::Plugins
+            }
+        }
+    }
+}
+
+/// A client connection, plain or TLS.
+trait Stream: Read + Write + Send {}
+
+impl<T: Read + Write + Send> Stream for T {}
+
+#[derive(Clone)]
+pub struct RemoteApiState {
+    graph: Arc<Mutex<String>>,
+    subscribers: Arc<Mutex<Vec<SyncSender<String>>>>,
+    pub requests: Arc<Mutex<Vec<RemoteRequest>>>,
+    tokens: Arc<[ApiToken]>,
+    tls: Option<Arc<ServerConfig>>,
+    /// The origin browsers may call from, sent as
+    /// `Access-Control-Allow-Origin`; `None` sends no CORS headers.
+    cors_origin: Option<Arc<str>>,
+    connections: Arc<AtomicUsize>,
+    stopped: Arc<AtomicBool>,
+    /// The accept thread, which owns the listener.
+    listener: Arc<Mutex<Option<JoinHandle<()>>>>,
+}
+
+/// Counts a connection as open until dropped.
+struct ConnectionSlot(Arc<AtomicUsize>);
+
+impl ConnectionSlot {
+    fn take(connections: &Arc<AtomicUsize>) -> Option<Self> {
+        connections
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |n| {
+                (n < MAX_CONNECTIONS).then_some(n + 1)
+            })
+            .ok()
+            .map(|_| Self(connections.clone()))
+    }
+}
+
+impl Drop for ConnectionSlot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+impl RemoteApiState {
+    fn new(
+        tokens: Vec<ApiToken>,
+        tls: Option<Arc<ServerConfig>>,
+        cors_origin: Option<&str>,
+    ) -> Self {
+        Self {
+            graph: Arc::new(Mutex::new("{}".to_string())),
+            subscribers: Arc::new(Mutex::new(Vec::new())),
+            requests: Arc::new(Mutex::new(Vec::new())),
+            tokens: tokens.into(),
+            tls,
+            cors_origin: cors_origin.map(Into::into),
+            connections: Arc::new(AtomicUsize::new(0)),
+            stopped: Arc::new(AtomicBool::new(false)),
+            listener: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Replace the graph snapshot and stream it to connected clients if it
+    /// changed.
+    pub fn publish_graph(&self, graph: serde_json::Value) {
+        let json = graph.to_string();
+        {
+            let mut current = self.graph.lock().unwrap_or_else(|e| e.into_inner());
+            if *current == json {
+                return;
+            }
+            *current = json;
+        }
+        self.emit("graph", graph);
+    }
+
+    /// Send an event to every connected WebSocket client. Clients whose
+    /// queue is full are dropped and get disconnected.
+    pub fn emit(&self, event: &str, data: serde_json::Value) {
+        let message = serde_json::json!({ "event": event, "data": data }).to_string();
+        self.subscribers
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .retain(|tx| tx.try_send(message.clone()).is_ok());
+    }
+
+    /// Close the listener and every open connection. Returns once the
+    /// listener is closed, so the address can be bound again right away.
+    pub fn stop(&self) {
+        self.stopped.store(true, Ordering::Release);
+        let listener = self
+            .listener
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .take();
+        if let Some(listener) = listener {
+            let _ = listener.join();
+        }
+    }
+
+    fn is_stopped(&self) -> bool {
+        self.stopped.load(Ordering::Acquire)
+    }
+
+    fn subscribe(&self) -> Receiver<String> {
+        let (tx, rx) = mpsc::sync_channel(MAX_QUEUED_EVENTS);
+        self.subscribers
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(tx);
+        rx
+    }
+
+    fn request(&self, request: RemoteRequest) {
+        self.requests
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(request);
+    }
+
+    fn graph_json(&self) -> String {
+        self.graph.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+}
+
+/// A random hex token for new installs.
+pub fn generate_token() -> String {
+    let mut bytes = [0u8; 16];
+    let read = std::fs::File::open("/dev/urandom").and_then(|mut f| f.read_exact(&mut bytes));
+    if read.is_err() {
+        // Only reachable without /dev/urandom; the user can still set a
+        // token of their own.
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos();
+        bytes = (nanos ^ std::process::id() as u128).to_le_bytes();
+    }
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Load the PEM certificate chain and private key the server presents.
+pub fn load_tls_config(cert_path: &str, key_path: &str) -> Result<Arc<ServerConfig>, String> {
+    let certs = CertificateDer::pem_file_iter(cert_path)
+        .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+        .map_err(|e| format!("Cannot read certificate {}: {}", cert_path, e))?;
+    if certs.is_empty() {
+        return Err(format!("No certificate in {}", cert_path));
+    }
+    let key = PrivateKeyDer::from_pem_file(key_path)
+        .map_err(|e| format!("Cannot read private key {}: {}", key_path, e))?;
+    let config = ServerConfig::builder()
+        .with_no_client_auth()
+        .with_single_cert(certs, key)
+        .map_err(|e| format!("Invalid certificate or key: {}", e))?;
+    Ok(Arc::new(config))
+}
+
+struct HttpRequest {
+    method: String,
+    path: String,
+    query: HashMap<String, String>,
+    headers: HashMap<String, String>,
+    body: Vec<u8>,
+}
+
+/// Read one line of at most [`MAX_HEADER_LINE_BYTES`]. `None` for a longer
+/// line, or one cut off by the end of the stream.
+fn read_header_line(reader: &mut impl BufRead, line: &mut String) -> Option<()> {
+    line.clear();
+    reader
+        .by_ref()
+        .take(MAX_HEADER_LINE_BYTES)
+        .read_line(line)
+        .ok()?;
+    line.ends_with('\n').then_some(())
+}
+
+impl HttpRequest {
+    fn read(reader: &mut impl BufRead) -> Option<Self> {
+        let mut line = String::new();
+        read_header_line(reader, &mut line)?;
+        let mut parts = line.split_whitespace();
+        let method = parts.next()?.to_string();
+        let target = parts.next()?.to_string();
+
+        let mut headers = HashMap::new();
+        let mut ended = false;
+        for _ in 0..MAX_HEADER_LINES {
+            read_header_line(reader, &mut line)?;
+            let header = line.trim_end();
+            if header.is_empty() {
+                ended = true;
+                break;
+            }
+            if let Some((name, value)) = header.split_once(':') {
+                headers.insert(name.trim().to_ascii_lowercase(), value.trim().to_string());
+            }
+        }
+        if !ended {
+            return None;
+        }
+
+        let length = headers
+            .get("content-length")
+            .and_then(|v| v.parse::<usize>().ok())
+            .unwrap_or(0);
+        if length > MAX_BODY_BYTES {
+            return None;
+        }
+        let mut body = vec![0; length];
+        reader.read_exact(&mut body).ok()?;
+
+        let (path, query) = match target.split_once('?') {
+            Some((path, query)) => (path.to_string(), parse_query(query)),
+            None => (target, HashMap::new()),
+        };
+        Some(Self {
+            method,
+            path,
+            query,
+            headers,
+            body,
+        })
+    }
+
+    fn token(&self) -> Option<&str> {
+        self.headers
+            .get("authorization")
+            .and_then(|v| v.strip_prefix("Bearer "))
+            .or_else(|| self.query.get("token").map(String::as_str))
+    }
+
+    fn is_websocket_upgrade(&self) -> bool {
+        self.headers
+            .get("upgrade")
+            .is_some_and(|v| v.eq_ignore_ascii_case("websocket"))
+    }
+}
+
+fn parse_query(query: &str) -> HashMap<String, String> {
+    query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .map(|(k, v)| (k.to_string(), percent_decode(v)))
+        .collect()
+}
+
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = (bytes[i] == b'%' && i + 2 < bytes.len())
+            .then(|| std::str::from_utf8(&bytes[i + 1..i + 3]).ok())
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (escaped, bytes[i]) {
+            (Some(b), _) => {
+                out.push(b);
+                i += 3;
+                continue;
+            }
+            (None, b'+') => out.push(b' '),
+            (None, b) => out.push(b),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+fn respond(
+    stream: &mut impl Write,
+    cors: Option<&str>,
+    status: &str,
+    content_type: &str,
+    body: &str,
+) {
+    let cors = cors
+        .map(|origin| {
+            format!(
+                "Access-Control-Allow-Origin: {}\r\n\
+                 Access-Control-Allow-Headers: Authorization, Content-Type\r\n\
+                 Access-Control-Allow-Methods: GET, POST, OPTIONS\r\n\
+                 Vary: Origin\r\n",
+                origin
+            )
+        })
+        .unwrap_or_default();
+    let response = format!(
+        "HTTP/1.1 {}\r\n\
+         Content-Type: {}\r\n\
+         Content-Length: {}\r\n\
+         {}\
+         Connection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        cors,
+        body
+    );
+    let _ = stream.write_all(response.as_bytes());
+}
+
+fn respond_json(stream: &mut impl Write, cors: Option<&str>, status: &str, body: &str) {
+    respond(stream, cors, status, "application/json", body);
+}
+
+fn respond_error(stream: &mut impl Write, cors: Option<&str>, status: &str, message: &str) {
+    let body = serde_json::json!({ "error": message }).to_string();
+    respond_json(stream, cors, status, &body);
+}
+
+fn handle_connection(tcp: TcpStream, state: RemoteApiState) {
+    let _ = tcp.set_read_timeout(Some(Duration::from_secs(10)));
+    let _ = tcp.set_write_timeout(Some(WRITE_TIMEOUT));
+    // Kept to change the timeout once the stream is wrapped.
+    let Ok(socket) = tcp.try_clone() else {
+        return;
+    };
+    let mut stream: Box<dyn Stream> = match state.tls {
+        Some(ref config) => match ServerConnection::new(config.clone()) {
+            Ok(connection) => Box::new(StreamOwned::new(connection, tcp)),
+            Err(e) => {
+                log::warn!("Remote API: TLS setup failed: {}", e);
+                return;
+            }
+        },
+        None => Box::new(tcp),
+    };
+    let cors = state.cors_origin.clone();
+    let cors = cors.as_deref();
+    let request = HttpRequest::read(&mut BufReader::new(&mut stream));
+    let Some(request) = request else {
+        respond_error(&mut stream, cors, "400 Bad Request", "malformed request");
+        return;
+    };
+
+    if request.method == "OPTIONS" {
+        respond(&mut stream, cors, "204 No Content", "text/plain", "");
+        return;
+    }
+    let Some(scope) = request
+        .token()
+        .and_then(|t| find_token(&state.tokens, t))
+        .map(|t| t.scope)
+    else {
+        respond_error(
+            &mut stream,
+            cors,
+            "401 Unauthorized",
+            "missing or wrong token",
+        );
+        return;
+    };
+
+    match (request.method.as_str(), request.path.as_str()) {
+        ("GET", "/api/graph") => {
+            respond_json(&mut stream, cors, "200 OK", &state.graph_json());
+        }
+        ("POST", "/api/command") => match serde_json::from_slice::<RemoteRequest>(&request.body) {
+            Ok(command) if !scope.allows(command.scope()) => {
+                respond_error(&mut stream, cors, "403 Forbidden", &forbidden(scope));
+            }
+            Ok(command) => {
+                log::info!("Remote API: {:?}", command);
+                state.request(command);
+                respond_json(&mut stream, cors, "202 Accepted", "{}");
+            }
+            Err(e) => respond_error(&mut stream, cors, "400 Bad Request", &e.to_string()),
+        },
+        ("GET", "/api/events") if request.is_websocket_upgrade() => {
+            serve_websocket(stream, &socket, &request, scope, state);
+        }
+        _ => respond_error(&mut stream, cors, "404 Not Found", "no such endpoint"),
+    }
+}
+
+fn forbidden(scope: ApiScope) -> String {
+    format!("a {} token may not do this", scope.as_str())
+}
+
+fn serve_websocket(
+    mut stream: Box<dyn Stream>,
+    socket: &TcpStream,
+    request: &HttpRequest,
+    scope: ApiScope,
+    state: RemoteApiState,
+) {
+    let Some(key) = request.headers.get("sec-websocket-key") else {
+        let cors = state.cors_origin.as_deref();
+        respond_error(
+            &mut stream,
+            cors,
+            "400 Bad Request",
+            "missing Sec-WebSocket-Key",
+        );
+        return;
+    };
+    let accept = tungstenite::handshake::derive_accept_key(key.as_bytes());
+    let handshake = format!(
+        "HTTP/1.1 101 Switching Protocols\r\n\
+         Upgrade: websocket\r\n\
+         Connection: Upgrade\r\n\
+         Sec-WebSocket-Accept: {}\r\n\r\n",
+        accept
+    );
+    if stream.write_all(handshake.as_bytes()).is_err() {
+        return;
+    }
+    // Short reads let one thread both read commands and push events.
+    let _ = socket.set_read_timeout(Some(SOCKET_POLL_INTERVAL));
+
+    let events = state.subscribe();
+    let mut socket = WebSocket::from_raw_socket(stream, Role::Server, None);
+    let session = serde_json::json!({ "event": "session", "data": { "scope": scope } });
+    let initial = serde_json::json!({
+        "event": "graph",
+        "data": serde_json::from_str::<serde_json::Value>(&state.graph_json())
+            .unwrap_or_default(),
+    });
+    for message in [session, initial] {
+        if socket.send(Message::Text(message.to_string())).is_err() {
+            return;
+        }
+    }
+
+    'serve: while !state.is_stopped() {
+        match socket.read() {
+            Ok(Message::Text(text)) => {
+                let error = match serde_json::from_str::<RemoteRequest>(&text) {
+                    Ok(command) if !scope.allows(command.scope()) => Some(forbidden(scope)),
+                    Ok(command) => {
+                        log::info!("Remote API: {:?}", command);
+                        state.request(command);
+                        None
+                    }
+                    Err(e) => Some(e.to_string()),
+                };
+                if let Some(message) = error {
+                    let error =
+                        serde_json::json!({ "event": "error", "data": { "message": message } });
+                    let _ = socket.send(Message::Text(error.to_string()));
+                }
+            }
+            Ok(Message::Close(_)) => break,
+            Ok(_) => {}
+            Err(tungstenite::Error::Io(e))
+                if matches!(
+                    e.kind(),
+                    std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+                ) => {}
+            Err(_) => break,
+        }
+        loop {
+            match events.try_recv() {
+                Ok(event) => {
+                    if socket.send(Message::Text(event)).is_err() {
+                        return;
+                    }
+                }
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    log::warn!("Remote API: client fell too far behind on events, closing");
+                    break 'serve;
+                }
+            }
+        }
+    }
+    let _ = socket.close(None);
+}
+
+/// Listen on `bind` (`host:port`) and serve the API until stopped, to
+/// clients presenting one of `tokens`, over TLS when `tls` is given.
+/// Browsers may call it from `cors_origin` (`*` for any) if set.
+pub fn spawn_remote_api(
+    bind: &str,
+    tokens: Vec<ApiToken>,
+    tls: Option<Arc<ServerConfig>>,
+    cors_origin: Option<&str>,
+) -> Result<RemoteApiState, String> {
+    if tokens.iter().all(|t| t.token.is_empty()) {
+        return Err("No access token configured".into());
+    }
+    let listener =
+        TcpListener::bind(bind.trim()).map_err(|e| format!("Cannot listen on {}: {}", bind, e))?;
+    listener
+        .set_nonblocking(true)
+        .map_err(|e| format!("Cannot listen on {}: {}", bind, e))?;
+    log::info!(
+        "Remote API listening on {}{}",
+        bind,
+        if tls.is_some() { " (TLS)" } else { "" }
+    );
+
+    let state = RemoteApiState::new(tokens, tls, cors_origin);
+    let server_state = state.clone();
+    let listener = std::thread::Builder::new()
+        .name("zestbay-remote-api".into())
+        .spawn(move || {
+            while !server_state.is_stopped() {
+                match listener.accept() {
+                    Ok((stream, peer)) => {
+                        let Some(slot) = ConnectionSlot::take(&server_state.connections) else {
+                            log::warn!(
+                                "Remote API: {} connections open, refusing {}",
+                                MAX_CONNECTIONS,
+                                peer
+                            );
+                            continue;
+                        };
+                        log::debug!("Remote API: connection from {}", peer);
+                        let _ = stream.set_nonblocking(false);
+                        let connection_state = server_state.clone();
+                        let _ = std::thread::Builder::new()
+                            .name("zestbay-remote-conn".into())
+                            .spawn(move || {
+                                let _slot = slot;
+                                handle_connection(stream, connection_state);
+                            });
+                    }
+                    Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                        std::thread::sleep(ACCEPT_INTERVAL);
+                    }
+                    Err(e) => {
+                        log::warn!("Remote API: accept failed: {}", e);
+                        std::thread::sleep(ACCEPT_INTERVAL);
+                    }
+                }
+            }
+            log::info!("Remote API stopped");
+        })
+        .map_err(|e| format!("Failed to spawn remote API thread: {}", e))?;
+    *state.listener.lock().unwrap_or_else(|e| e.into_inner()) = Some(listener);
+
+    Ok(state)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn token() -> Vec<ApiToken> {
+        vec![ApiToken {
+            name: "Main".into(),
+            token: "secret".into(),
+            scope: ApiScope::Plugins,
+        }]
+    }
+
+    #[test]
+    fn test_stop_frees_the_address_for_a_restart() {
+        let port = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let bind = format!("127.0.0.1:{}", port);
+
+        let first = spawn_remote_api(&bind, token(), None, None).unwrap();
+        first.stop();
+        let second = spawn_remote_api(&bind, token(), None, None).unwrap();
+        second.stop();
+        // Stopping twice, as a restart after a failed start may, is harmless.
+        second.stop();
+    }
+
+    #[test]
+    fn test_slow_client_is_dropped_when_its_queue_fills() {
+        let state = RemoteApiState::new(token(), None, None);
+        let events = state.subscribe();
+        for i in 0..MAX_QUEUED_EVENTS {
+            state.emit("tick", serde_json::json!(i));
+        }
+        assert_eq!(state.subscribers.lock().unwrap().len(), 1);
+        state.emit("tick", serde_json::json!("overflow"));
+        assert!(state.subscribers.lock().unwrap().is_empty());
+
+        // The queued events still arrive, then the client is told it is cut off.
+        assert_eq!(events.try_iter().count(), MAX_QUEUED_EVENTS);
+        assert_eq!(events.try_recv(), Err(TryRecvError::Disconnected));
+    }
+}
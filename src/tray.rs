@@ -1,7 +1,108 @@
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 use ksni::blocking::TrayMethods;
+use zestbay_core::profile::PerformanceProfile;
+
+/// How the tray icon is shown. StatusNotifier needs a host (KDE, most
+/// panels, GNOME with the AppIndicator extension); the XEmbed icon is drawn by
+/// Qt for older X11 panels; without either, closing the window minimizes it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TrayBackend {
+    StatusNotifier,
+    Xembed,
+    None,
+}
+
+impl TrayBackend {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TrayBackend::StatusNotifier => "status_notifier",
+            TrayBackend::Xembed => "xembed",
+            TrayBackend::None => "none",
+        }
+    }
+
+    /// The backend for a `tray_mode` preference. `auto` starts with
+    /// StatusNotifier and falls back once the tray reports it has no host.
+    pub fn from_mode(mode: &str) -> Self {
+        match mode {
+            "xembed" => TrayBackend::Xembed,
+            "none" => TrayBackend::None,
+            _ => TrayBackend::StatusNotifier,
+        }
+    }
+
+    /// What to use when no StatusNotifier host is running. GNOME and Wayland
+    /// sessions have no XEmbed tray either.
+    pub fn fallback() -> Self {
+        let desktop = std::env::var("XDG_CURRENT_DESKTOP")
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        let gnome = desktop.split(':').any(|d| d == "gnome");
+        if gnome || crate::portal::is_wayland_session() {
+            TrayBackend::None
+        } else if std::env::var_os("DISPLAY").is_some() {
+            TrayBackend::Xembed
+        } else {
+            TrayBackend::None
+        }
+    }
+}
+
+/// How often the tray thread checks for a new [`TrayStatus`].
+const STATUS_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// What the tray icon reflects besides "running". Several can be true at
+/// once; the icon badge shows the most urgent and the tooltip lists all.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TrayStatus {
+    pub rules_disabled: bool,
+    /// Why a plugin was lost or hung, until the window is shown again.
+    pub plugin_fault: Option<String>,
+    pub recording: bool,
+    /// Plugin overruns in the last minute, when above the warning threshold.
+    pub xruns_per_minute: Option<u64>,
+    /// The performance profile in use, ticked in the menu.
+    pub profile: PerformanceProfile,
+}
+
+impl TrayStatus {
+    fn overlay_icon_name(&self) -> &'static str {
+        if self.plugin_fault.is_some() {
+            "dialog-error"
+        } else if self.xruns_per_minute.is_some() {
+            "dialog-warning"
+        } else if self.recording {
+            "media-record"
+        } else if self.rules_disabled {
+            "media-playback-pause"
+        } else {
+            ""
+        }
+    }
+
+    fn description(&self) -> String {
+        let mut lines = Vec::new();
+        if let Some(ref fault) = self.plugin_fault {
+            lines.push(fault.clone());
+        }
+        if let Some(xruns) = self.xruns_per_minute {
+            lines.push(format!("{} plugin overruns in the last minute", xruns));
+        }
+        if self.recording {
+            lines.push("Recording".to_string());
+        }
+        if self.rules_disabled {
+            lines.push("Patchbay rules disabled".to_string());
+        }
+        if self.profile != PerformanceProfile::Balanced {
+            lines.push(format!("{} profile", self.profile.label()));
+        }
+        lines.join("\n")
+    }
+}
 
 #[derive(Clone)]
 pub struct PluginEntry {
@@ -18,6 +119,12 @@ pub struct TrayState {
     pub window_visible: Arc<AtomicBool>,
     pub plugins: Arc<Mutex<Vec<PluginEntry>>>,
     pub open_plugin_ui: Arc<Mutex<Option<u32>>>,
+    pub show_all_uis_requested: Arc<AtomicBool>,
+    pub hide_all_uis_requested: Arc<AtomicBool>,
+    pub profile_requested: Arc<Mutex<Option<PerformanceProfile>>>,
+    /// Set when the StatusNotifier icon could not be registered.
+    pub unavailable: Arc<AtomicBool>,
+    status: Arc<Mutex<(u64, TrayStatus)>>,
 }
 
 impl TrayState {
@@ -29,13 +136,35 @@ impl TrayState {
             window_visible: Arc::new(AtomicBool::new(true)),
             plugins: Arc::new(Mutex::new(Vec::new())),
             open_plugin_ui: Arc::new(Mutex::new(None)),
+            show_all_uis_requested: Arc::new(AtomicBool::new(false)),
+            hide_all_uis_requested: Arc::new(AtomicBool::new(false)),
+            profile_requested: Arc::new(Mutex::new(None)),
+            unavailable: Arc::new(AtomicBool::new(false)),
+            status: Arc::new(Mutex::new((0, TrayStatus::default()))),
         }
     }
+
+    /// Update what the icon shows. Unchanged status does not touch the icon.
+    pub fn set_status(&self, status: TrayStatus) {
+        let mut current = self.status.lock().unwrap_or_else(|e| e.into_inner());
+        if current.1 != status {
+            current.0 += 1;
+            current.1 = status;
+        }
+    }
+
+    fn status(&self) -> (u64, TrayStatus) {
+        self.status
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
 }
 
 struct ZestBayTray {
     state: TrayState,
     icon_theme_path: String,
+    status: TrayStatus,
 }
 
 impl ksni::Tray for ZestBayTray {
@@ -59,6 +188,31 @@ impl ksni::Tray for ZestBayTray {
         ksni::Category::ApplicationStatus
     }
 
+    fn status(&self) -> ksni::Status {
+        if self.status.plugin_fault.is_some() {
+            ksni::Status::NeedsAttention
+        } else {
+            ksni::Status::Active
+        }
+    }
+
+    fn overlay_icon_name(&self) -> String {
+        self.status.overlay_icon_name().into()
+    }
+
+    // Hosts that ignore overlays still swap the icon on NeedsAttention.
+    fn attention_icon_name(&self) -> String {
+        "dialog-error".into()
+    }
+
+    fn tool_tip(&self) -> ksni::ToolTip {
+        ksni::ToolTip {
+            title: "ZestBay".into(),
+            description: self.status.description(),
+            ..Default::default()
+        }
+    }
+
     fn activate(&mut self, _x: i32, _y: i32) {
         let currently_visible = self.state.window_visible.load(Ordering::Acquire);
         log::info!("Tray: activate (left-click), currently_visible={currently_visible}");
@@ -93,7 +247,27 @@ impl ksni::Tray for ZestBayTray {
             .unwrap_or_else(|e| e.into_inner())
             .clone();
         if !plugins.is_empty() {
-            let mut plugin_items: Vec<ksni::MenuItem<Self>> = Vec::new();
+            let mut plugin_items: Vec<ksni::MenuItem<Self>> = vec![
+                StandardItem {
+                    label: "Show All".into(),
+                    activate: Box::new(|tray: &mut Self| {
+                        log::info!("Tray: show all plugin UIs");
+                        tray.state.show_all_uis_requested.store(true, Ordering::Release);
+                    }),
+                    ..Default::default()
+                }
+                .into(),
+                StandardItem {
+                    label: "Hide All".into(),
+                    activate: Box::new(|tray: &mut Self| {
+                        log::info!("Tray: hide all plugin UIs");
+                        tray.state.hide_all_uis_requested.store(true, Ordering::Release);
+                    }),
+                    ..Default::default()
+                }
+                .into(),
+                ksni::MenuItem::Separator,
+            ];
             for plugin in &plugins {
                 let node_id = plugin.node_id;
                 let has_ui = plugin.has_ui;
@@ -125,6 +299,37 @@ impl ksni::Tray for ZestBayTray {
         }
 
         items.push(ksni::MenuItem::Separator);
+        items.push(
+            SubMenu {
+                label: "Performance Profile".into(),
+                submenu: vec![
+                    RadioGroup {
+                        selected: PerformanceProfile::ALL
+                            .iter()
+                            .position(|&p| p == self.status.profile)
+                            .unwrap_or(1),
+                        select: Box::new(|tray: &mut Self, index| {
+                            let profile = PerformanceProfile::ALL[index];
+                            log::info!("Tray: performance profile {}", profile.as_str());
+                            tray.status.profile = profile;
+                            if let Ok(mut req) = tray.state.profile_requested.lock() {
+                                *req = Some(profile);
+                            }
+                        }),
+                        options: PerformanceProfile::ALL
+                            .iter()
+                            .map(|p| RadioItem {
+                                label: p.label().into(),
+                                ..Default::default()
+                            })
+                            .collect(),
+                    }
+                    .into(),
+                ],
+                ..Default::default()
+            }
+            .into(),
+        );
         items.push(
             StandardItem {
                 label: "Quit".into(),
@@ -142,39 +347,53 @@ impl ksni::Tray for ZestBayTray {
     }
 }
 
+/// The bundled icon theme next to the executable, or in the working
+/// directory during development. Empty when neither exists.
+pub fn icon_theme_path() -> String {
+    let mut path = std::env::current_exe()
+        .ok()
+        .and_then(|p| p.parent().map(|d| d.join("icons")))
+        .unwrap_or_default();
+    if !path.exists() {
+        path = std::path::PathBuf::from("icons");
+    }
+    if path.exists() {
+        path.to_string_lossy().into_owned()
+    } else {
+        String::new()
+    }
+}
+
 pub fn spawn_tray() -> TrayState {
     let state = TrayState::new();
     let tray_state = state.clone();
-
-    let icon_theme_path = {
-        let mut path = std::env::current_exe()
-            .ok()
-            .and_then(|p| p.parent().map(|d| d.join("icons")))
-            .unwrap_or_default();
-        if !path.exists() {
-            path = std::path::PathBuf::from("icons");
-        }
-        if path.exists() {
-            path.to_string_lossy().into_owned()
-        } else {
-            String::new()
-        }
-    };
+    let icon_theme_path = icon_theme_path();
 
     std::thread::Builder::new()
         .name("zestbay-tray".into())
         .spawn(move || {
+            let unavailable = tray_state.unavailable.clone();
             let tray = ZestBayTray {
-                state: tray_state,
+                state: tray_state.clone(),
                 icon_theme_path,
+                status: TrayStatus::default(),
             };
             match tray.spawn() {
-                Ok(_handle) => loop {
-                    std::thread::park();
-                },
+                Ok(handle) => {
+                    let mut shown = 0;
+                    loop {
+                        std::thread::sleep(STATUS_POLL_INTERVAL);
+                        let (version, status) = tray_state.status();
+                        if version != shown {
+                            shown = version;
+                            handle.update(move |tray| tray.status = status);
+                        }
+                    }
+                }
                 Err(e) => {
                     log::warn!("Failed to create system tray icon: {}", e);
                     log::warn!("The application will still run but won't have a tray icon.");
+                    unavailable.store(true, Ordering::Release);
                 }
             }
         })
@@ -1 +1,4 @@
+pub mod compare;
+pub mod plugin_dock;
 pub mod qobject_bridge;
+pub mod remote_client;
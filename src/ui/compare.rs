@@ -0,0 +1,274 @@
+//! A second PipeWire instance shown next to the main graph, for A/B
+//! comparisons such as the local graph against a remote machine reached over
+//! a pipewire-pulse tunnel.
+//!
+//! The instance gets its own PipeWire thread, [`GraphState`] and
+//! [`PatchbayManager`], so its rules are learned, stored and applied
+//! independently of the main graph's. It never hosts plugins.
+
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::mpsc::{Receiver, Sender};
+use std::time::{Duration, Instant};
+
+use crate::patchbay::{GraphSnapshot, PatchbayManager};
+use crate::pipewire::{GraphChange, GraphState, ObjectId, PwCommand, PwEvent};
+use zestbay_core::config;
+
+use super::qobject_bridge::qobject::{MediaKind, NodeKind};
+
+pub struct CompareGraph {
+    remote: String,
+    graph: Arc<GraphState>,
+    event_rx: Receiver<PwEvent>,
+    cmd_tx: Sender<PwCommand>,
+    patchbay: PatchbayManager,
+    /// Graph changes the rules engine can act on.
+    rule_changes: Receiver<GraphChange>,
+    rules_path: PathBuf,
+    connected: bool,
+    /// When the graph last changed; rules are applied once it settles.
+    dirty_since: Option<Instant>,
+}
+
+impl CompareGraph {
+    /// Connect to `remote` on a PipeWire thread of its own. Rules are loaded
+    /// from and saved to `rules_path`.
+    pub fn start(
+        remote: &str,
+        rules_path: PathBuf,
+        tick_interval_ms: u64,
+        operation_cooldown_ms: u64,
+        connect_retries: u32,
+    ) -> Self {
+        let graph = GraphState::new();
+        let (event_rx, cmd_tx) = crate::pipewire::start(
+            graph.clone(),
+            tick_interval_ms,
+            operation_cooldown_ms,
+            connect_retries,
+            Some(remote.to_string()),
+            crate::pipewire::Heartbeat::default(),
+        );
+        let rule_changes = graph.subscribe(PatchbayManager::graph_filter());
+        let mut patchbay = PatchbayManager::new();
+        let rules = config::RULES
+            .load(&rules_path)
+            .unwrap_or_else(|e| {
+                log::error!("Failed to load {:?}: {}", rules_path, e);
+                None
+            })
+            .unwrap_or_default();
+        patchbay.set_rules(rules);
+        patchbay.rules_dirty = false;
+        log::info!("Compare graph: connecting to {}", remote);
+
+        Self {
+            remote: remote.to_string(),
+            graph,
+            event_rx,
+            cmd_tx,
+            patchbay,
+            rule_changes,
+            rules_path,
+            connected: false,
+            dirty_since: None,
+        }
+    }
+
+    pub fn remote(&self) -> &str {
+        &self.remote
+    }
+
+    /// Send `cmd` to this instance's PipeWire thread unless a link it makes
+    /// would close an audio feedback loop there, as the main graph does.
+    /// Returns false when it was refused.
+    fn send(&self, cmd: PwCommand) -> bool {
+        if let Some(found) = self.graph.closed_feedback_loop(&cmd) {
+            log::warn!(
+                "Compare graph {}: refused link {} -> {}: it closes a feedback loop: {}",
+                self.remote,
+                found.output_port_id,
+                found.input_port_id,
+                self.graph.feedback_loop_text(&found.nodes)
+            );
+            return false;
+        }
+        let _ = self.cmd_tx.send(cmd);
+        true
+    }
+
+    /// Disconnect and end the PipeWire thread.
+    fn stop(&self) {
+        let _ = self.cmd_tx.send(PwCommand::Shutdown);
+    }
+
+    /// Drain PipeWire events and apply rules once the graph has settled for
+    /// `settle`. Returns true when the graph changed.
+    pub fn poll(&mut self, settle: Duration) -> bool {
+        let mut changed = false;
+        while let Ok(event) = self.event_rx.try_recv() {
+            match event {
+                PwEvent::Connected => {
+                    log::info!("Compare graph: connected to {}", self.remote);
+                    self.connected = true;
+                    changed = true;
+                }
+                PwEvent::WaitingForDaemon { .. } => {
+                    changed |= self.connected;
+                    self.connected = false;
+                }
+                PwEvent::Error(msg) => {
+                    log::warn!("Compare graph {}: {}", self.remote, msg);
+                }
+                PwEvent::Plugin(_) => {}
+                _ => changed = true,
+            }
+        }
+
+        let rules_touched = self.rule_changes.try_iter().count() > 0;
+        if rules_touched || self.patchbay.expire_cooldowns() {
+            self.dirty_since = Some(Instant::now());
+        }
+        if self.dirty_since.is_some_and(|t| t.elapsed() >= settle) {
+            self.dirty_since = None;
+            let commands = self.patchbay.scan(&GraphSnapshot::from_state(&self.graph));
+            if !commands.is_empty() {
+                log::info!(
+                    "Compare graph: applying {} patchbay rule commands",
+                    commands.len()
+                );
+            }
+            for cmd in commands {
+                self.send(cmd);
+            }
+        }
+        if self.patchbay.rules_dirty {
+            self.patchbay.rules_dirty = false;
+            self.save_rules();
+        }
+        changed
+    }
+
+    fn save_rules(&self) {
+        if let Err(e) = config::RULES.save(&self.rules_path, self.patchbay.rules()) {
+            log::error!("Failed to save rules to {:?}: {}", self.rules_path, e);
+        }
+    }
+
+    pub fn set_rules_enabled(&mut self, enabled: bool) {
+        self.patchbay.enabled = enabled;
+        if enabled {
+            self.dirty_since = Some(Instant::now());
+        }
+    }
+
+    /// Connect two ports, learning a rule for it when `learn` is set.
+    pub fn connect(&mut self, output_port_id: ObjectId, input_port_id: ObjectId, learn: bool) {
+        let sent = self.send(PwCommand::Connect {
+            output_port_id,
+            input_port_id,
+        });
+        if !sent || !learn {
+            return;
+        }
+        let graph = &self.graph;
+        if let (Some(out_port), Some(in_port)) = (
+            graph.get_port(output_port_id),
+            graph.get_port(input_port_id),
+        ) && let (Some(source), Some(target)) = (
+            graph.get_node(out_port.node_id),
+            graph.get_node(in_port.node_id),
+        ) {
+            self.patchbay
+                .learn_from_link(&source, &target, &out_port, &in_port);
+        }
+    }
+
+    /// Disconnect a link, dropping the learned port mapping when `learn` is
+    /// set so the rule engine does not restore it.
+    pub fn disconnect(&mut self, link_id: ObjectId, learn: bool) {
+        let _ = self.cmd_tx.send(PwCommand::Disconnect { link_id });
+        if !learn {
+            return;
+        }
+        let graph = &self.graph;
+        if let Some(link) = graph.get_link(link_id)
+            && let (Some(source), Some(target), Some(out_port), Some(in_port)) = (
+                graph.get_node(link.output_node_id),
+                graph.get_node(link.input_node_id),
+                graph.get_port(link.output_port_id),
+                graph.get_port(link.input_port_id),
+            )
+        {
+            self.patchbay
+                .unlearn_from_link(&source, &target, &out_port, &in_port);
+        }
+    }
+
+    /// Nodes with their ports, and links, for the side-by-side view.
+    pub fn snapshot(&self) -> serde_json::Value {
+        let mut nodes: Vec<_> = self
+            .graph
+            .get_all_nodes()
+            .into_iter()
+            .filter(|n| n.ready)
+            .collect();
+        nodes.sort_by(|a, b| {
+            crate::pipewire::state::natural_cmp(a.display_name(), b.display_name())
+        });
+        let nodes: Vec<serde_json::Value> = nodes
+            .iter()
+            .map(|n| {
+                let ports: Vec<serde_json::Value> = self
+                    .graph
+                    .get_ports_for_node(n.id)
+                    .iter()
+                    .map(|p| {
+                        serde_json::json!({
+                            "id": p.id,
+                            "name": p.display_name(),
+                            "direction": format!("{:?}", p.direction),
+                        })
+                    })
+                    .collect();
+                serde_json::json!({
+                    "id": n.id,
+                    "name": n.display_name(),
+                    "type": NodeKind::from(n.node_type).repr,
+                    "mediaType": MediaKind::from(n.media_type).repr,
+                    "ports": ports,
+                })
+            })
+            .collect();
+        let links: Vec<serde_json::Value> = self
+            .graph
+            .get_all_links()
+            .iter()
+            .map(|l| {
+                serde_json::json!({
+                    "id": l.id,
+                    "outputNodeId": l.output_node_id,
+                    "outputPortId": l.output_port_id,
+                    "inputNodeId": l.input_node_id,
+                    "inputPortId": l.input_port_id,
+                    "active": l.active,
+                })
+            })
+            .collect();
+        serde_json::json!({
+            "remote": self.remote,
+            "connected": self.connected,
+            "rulesEnabled": self.patchbay.enabled,
+            "ruleCount": self.patchbay.rules().len(),
+            "nodes": nodes,
+            "links": links,
+        })
+    }
+}
+
+impl Drop for CompareGraph {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
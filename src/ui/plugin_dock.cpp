@@ -0,0 +1,141 @@
+#include "zestbay/src/ui/plugin_dock.h"
+
+#include <map>
+
+#include <QtCore/QPointer>
+#include <QtCore/QRect>
+#include <QtCore/QString>
+#include <QtGui/QGuiApplication>
+#include <QtGui/QWindow>
+
+namespace zestbay {
+namespace dock {
+
+namespace {
+
+// Native child window of the main window, kept over the QML dock area.
+// Docked plugin windows are reparented into it.
+QPointer<QWindow> container;
+// Foreign-window wrappers of the docked plugin windows, by X11 window ID.
+::std::map<::std::uint64_t, QPointer<QWindow>> docked;
+// Wrappers of windows floated again. Destroying a wrapper hides its window,
+// so they are kept until the window itself is gone.
+::std::map<::std::uint64_t, QPointer<QWindow>> floating;
+QRect dock_rect;
+
+QWindow*
+main_window()
+{
+  for (auto window : QGuiApplication::topLevelWindows()) {
+    if (window->objectName() == QStringLiteral("mainWindow")) {
+      return window;
+    }
+  }
+  return nullptr;
+}
+
+QWindow*
+ensure_container()
+{
+  if (container.isNull()) {
+    auto parent = main_window();
+    if (parent == nullptr) {
+      return nullptr;
+    }
+    container = new QWindow(parent);
+    container->setGeometry(dock_rect);
+  }
+  return container;
+}
+
+} // namespace
+
+bool
+dock_supported()
+{
+  return QGuiApplication::platformName() == QStringLiteral("xcb");
+}
+
+bool
+dock_attach(::std::uint64_t window_id)
+{
+  if (!dock_supported()) {
+    return false;
+  }
+  if (docked.count(window_id) != 0) {
+    return true;
+  }
+  auto parent = ensure_container();
+  if (parent == nullptr) {
+    return false;
+  }
+  QWindow* foreign = nullptr;
+  auto it = floating.find(window_id);
+  if (it != floating.end()) {
+    foreign = it->second;
+    floating.erase(it);
+  }
+  if (foreign == nullptr) {
+    foreign = QWindow::fromWinId(static_cast<WId>(window_id));
+  }
+  if (foreign == nullptr) {
+    return false;
+  }
+  foreign->setParent(parent);
+  foreign->setPosition(0, 0);
+  docked[window_id] = foreign;
+  return true;
+}
+
+void
+dock_show(::std::uint64_t window_id)
+{
+  for (auto& [id, window] : docked) {
+    if (!window.isNull()) {
+      window->setVisible(id == window_id);
+    }
+  }
+  if (!container.isNull()) {
+    container->setVisible(docked.count(window_id) != 0);
+  }
+}
+
+void
+dock_release(::std::uint64_t window_id, bool refloat)
+{
+  QWindow* window = nullptr;
+  for (auto map : { &docked, &floating }) {
+    auto it = map->find(window_id);
+    if (it != map->end()) {
+      window = it->second;
+      map->erase(it);
+    }
+  }
+  if (window != nullptr) {
+    if (refloat) {
+      window->setParent(nullptr);
+      window->show();
+      floating[window_id] = window;
+    } else {
+      delete window;
+    }
+  }
+  if (docked.empty() && !container.isNull()) {
+    container->hide();
+  }
+}
+
+void
+dock_set_geometry(::std::int32_t x,
+                  ::std::int32_t y,
+                  ::std::int32_t width,
+                  ::std::int32_t height)
+{
+  dock_rect = QRect(x, y, width, height);
+  if (!container.isNull()) {
+    container->setGeometry(dock_rect);
+  }
+}
+
+} // namespace dock
+} // namespace zestbay
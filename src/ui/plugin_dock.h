@@ -0,0 +1,33 @@
+#pragma once
+
+#include <cstdint>
+
+namespace zestbay {
+namespace dock {
+
+// Embedding foreign windows needs X11; it is not possible under Wayland.
+bool
+dock_supported();
+
+// Reparent the X11 window `window_id` into the dock area of the main window.
+bool
+dock_attach(::std::uint64_t window_id);
+
+// Show the docked window `window_id` and hide the others; 0 hides the dock.
+void
+dock_show(::std::uint64_t window_id);
+
+// Stop docking `window_id`. With `refloat` the window goes back to being a
+// top-level window; otherwise it is assumed to be gone already.
+void
+dock_release(::std::uint64_t window_id, bool refloat);
+
+// Dock area geometry in main window coordinates.
+void
+dock_set_geometry(::std::int32_t x,
+                  ::std::int32_t y,
+                  ::std::int32_t width,
+                  ::std::int32_t height);
+
+} // namespace dock
+} // namespace zestbay
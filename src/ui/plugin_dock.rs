@@ -0,0 +1,42 @@
+//! Docking plugin UI windows into the main window.
+//!
+//! A plugin UI's X11 top-level window is wrapped with `QWindow::fromWinId()`
+//! and reparented into a native child window that main.qml keeps over its
+//! dock panel. Only possible on X11; under Wayland UIs always float.
+
+#[cxx::bridge(namespace = "zestbay::dock")]
+mod ffi {
+    unsafe extern "C++" {
+        include!("zestbay/src/ui/plugin_dock.h");
+
+        fn dock_supported() -> bool;
+        fn dock_attach(window_id: u64) -> bool;
+        fn dock_show(window_id: u64);
+        fn dock_release(window_id: u64, refloat: bool);
+        fn dock_set_geometry(x: i32, y: i32, width: i32, height: i32);
+    }
+}
+
+pub fn is_supported() -> bool {
+    ffi::dock_supported()
+}
+
+/// Move `window_id` into the dock. Returns false if it could not be docked.
+pub fn attach(window_id: u64) -> bool {
+    ffi::dock_attach(window_id)
+}
+
+/// Show one docked window, or hide the dock with `None`.
+pub fn show(window_id: Option<u64>) {
+    ffi::dock_show(window_id.unwrap_or(0));
+}
+
+/// Take `window_id` out of the dock again: back to a top-level window with
+/// `refloat`, or just forgotten once the window has been destroyed.
+pub fn release(window_id: u64, refloat: bool) {
+    ffi::dock_release(window_id, refloat);
+}
+
+pub fn set_geometry(x: i32, y: i32, width: i32, height: i32) {
+    ffi::dock_set_geometry(x, y, width, height);
+}
@@ -5,6 +5,29 @@ pub mod qobject {
         type QString = cxx_qt_lib::QString;
     }
 
+    /// Node roles as QML sees them in the `type` field of node JSON. In rule
+    /// filters `Unknown` stands for "any type".
+    #[qenum(AppController)]
+    enum NodeKind {
+        Unknown,
+        Sink,
+        Source,
+        StreamOutput,
+        StreamInput,
+        Duplex,
+        Plugin,
+    }
+
+    /// Media carried by a node or port, as in the `mediaType` JSON field.
+    #[qenum(AppController)]
+    enum MediaKind {
+        Unknown,
+        Audio,
+        Video,
+        Midi,
+        Cv,
+    }
+
     extern "RustQt" {
         #[qobject]
         #[qml_element]
@@ -13,6 +36,12 @@ pub mod qobject {
         #[qproperty(i32, node_count)]
         #[qproperty(i32, link_count)]
         #[qproperty(QString, cpu_usage)]
+        #[qproperty(QString, tray_backend)]
+        #[qproperty(QString, pipewire_status)]
+        #[qproperty(bool, pipewire_stalled)]
+        #[qproperty(bool, rules_suspended)]
+        #[qproperty(QString, latest_version)]
+        #[qproperty(QString, performance_profile)]
         type AppController = super::AppControllerRust;
 
         #[qinvokable]
@@ -24,6 +53,39 @@ pub mod qobject {
         #[qinvokable]
         fn request_quit(self: Pin<&mut Self>);
 
+        #[qinvokable]
+        fn get_pipewire_remote(self: Pin<&mut Self>) -> QString;
+
+        #[qinvokable]
+        fn get_pipewire_remotes_json(self: Pin<&mut Self>) -> QString;
+
+        #[qinvokable]
+        fn switch_pipewire_remote(self: Pin<&mut Self>, remote: QString);
+
+        #[qinvokable]
+        fn get_sessions_json(self: Pin<&mut Self>) -> QString;
+
+        #[qinvokable]
+        fn save_session_as(self: Pin<&mut Self>, name: QString) -> bool;
+
+        #[qinvokable]
+        fn load_session(self: Pin<&mut Self>, name: QString);
+
+        #[qinvokable]
+        fn new_session(self: Pin<&mut Self>);
+
+        #[qinvokable]
+        fn delete_session(self: Pin<&mut Self>, name: QString) -> bool;
+
+        #[qinvokable]
+        fn refresh_graph(self: Pin<&mut Self>);
+
+        #[qinvokable]
+        fn resync_graph(self: Pin<&mut Self>);
+
+        #[qinvokable]
+        fn restart_pipewire(self: Pin<&mut Self>);
+
         #[qinvokable]
         fn get_nodes_json(self: Pin<&mut Self>) -> QString;
 
@@ -35,30 +97,162 @@ pub mod qobject {
 
         #[qinvokable]
         fn connect_ports(self: Pin<&mut Self>, output_port_id: u32, input_port_id: u32);
+        #[qinvokable]
+        fn connect_port_pair(self: Pin<&mut Self>, output_port_id: u32, input_port_id: u32);
+        #[qinvokable]
+        fn connect_ports_anyway(
+            self: Pin<&mut Self>,
+            output_port_id: u32,
+            input_port_id: u32,
+            pair: bool,
+        );
+        #[qinvokable]
+        fn set_port_pair(self: Pin<&mut Self>, left_port_id: u32, right_port_id: u32) -> bool;
+        #[qinvokable]
+        fn unpair_port(self: Pin<&mut Self>, port_id: u32) -> bool;
+        #[qinvokable]
+        fn reset_port_pairs(self: Pin<&mut Self>, node_id: u32);
+        #[qinvokable]
+        fn set_port_channel(self: Pin<&mut Self>, port_id: u32, position: QString) -> bool;
+        #[qinvokable]
+        fn reset_port_channels(self: Pin<&mut Self>, node_id: u32);
 
         #[qinvokable]
         fn disconnect_link(self: Pin<&mut Self>, link_id: u32);
 
+        #[qinvokable]
+        fn create_temporary_link(
+            self: Pin<&mut Self>,
+            output_port_id: u32,
+            input_port_id: u32,
+            ttl_secs: u32,
+        ) -> u32;
+
+        #[qinvokable]
+        fn cancel_temporary_link(self: Pin<&mut Self>, route_id: u32);
+
+        #[qinvokable]
+        fn get_temporary_links_json(self: Pin<&mut Self>) -> QString;
+
+        #[qinvokable]
+        fn audition_node(
+            self: Pin<&mut Self>,
+            node_id: u32,
+            monitor_sink_id: u32,
+            enable: bool,
+        ) -> bool;
+
+        #[qinvokable]
+        fn get_audition_node(self: Pin<&mut Self>) -> i32;
+
+        #[qinvokable]
+        fn probe_path(self: Pin<&mut Self>, source_node_id: u32, target_node_id: u32) -> bool;
+
+        #[qinvokable]
+        fn set_default_output(self: Pin<&mut Self>, node_id: u32) -> bool;
+
+        #[qinvokable]
+        fn get_default_output(self: Pin<&mut Self>) -> i32;
+
+        #[qinvokable]
+        fn propose_connection(self: Pin<&mut Self>, node_id: u32) -> QString;
+
+        #[qinvokable]
+        fn connect_nodes(self: Pin<&mut Self>, source_id: u32, target_id: u32);
+
         #[qinvokable]
         fn insert_node_on_link(self: Pin<&mut Self>, link_id: u32, node_id: u32);
 
+        #[qinvokable]
+        fn insert_chain_on_link(self: Pin<&mut Self>, link_id: u32, chain_name: QString)
+        -> QString;
+
+        #[qinvokable]
+        fn get_chain_json(self: Pin<&mut Self>, node_id: u32) -> QString;
+
+        #[qinvokable]
+        fn reorder_chain(self: Pin<&mut Self>, node_ids_json: QString) -> bool;
+
+        #[qinvokable]
+        fn add_chain_mix(self: Pin<&mut Self>, node_id: u32) -> QString;
+
+        #[qinvokable]
+        fn add_noise_suppression(self: Pin<&mut Self>, node_id: u32) -> QString;
+
+        #[qinvokable]
+        fn get_chain_quantum_json(self: Pin<&mut Self>, node_id: u32) -> QString;
+
+        #[qinvokable]
+        fn set_chain_quantum(self: Pin<&mut Self>, node_id: u32, quantum: u32) -> bool;
+
+        #[qinvokable]
+        fn get_conversion_policy_json(self: Pin<&mut Self>, node_id: u32) -> QString;
+
+        #[qinvokable]
+        fn set_conversion_policy(self: Pin<&mut Self>, node_id: u32, json: QString) -> bool;
+
+        #[qinvokable]
+        fn get_midi_link_options_json(self: Pin<&mut Self>, link_id: u32) -> QString;
+
+        #[qinvokable]
+        fn set_midi_link_options(self: Pin<&mut Self>, link_id: u32, json: QString) -> bool;
+
+        #[qinvokable]
+        fn set_link_gain(self: Pin<&mut Self>, link_id: u32, gain_db: f64) -> bool;
+
+        #[qinvokable]
+        fn get_clock_routing_json(self: Pin<&mut Self>) -> QString;
+
+        #[qinvokable]
+        fn set_clock_source(self: Pin<&mut Self>, node_id: u32) -> bool;
+
+        #[qinvokable]
+        fn set_clock_receiver(self: Pin<&mut Self>, node_id: u32, enabled: bool) -> bool;
+
+        #[qinvokable]
+        fn export_chain_filter_chain(self: Pin<&mut Self>, node_id: u32) -> QString;
+
+        #[qinvokable]
+        fn export_chain_file(self: Pin<&mut Self>, node_id: u32) -> QString;
+
+        #[qinvokable]
+        fn get_import_candidates_json(self: Pin<&mut Self>) -> QString;
+
+        #[qinvokable]
+        fn check_chain_file(self: Pin<&mut Self>, path: QString) -> QString;
+
+        #[qinvokable]
+        fn import_file(self: Pin<&mut Self>, path: QString) -> QString;
+
+        #[qinvokable]
+        fn export_config(self: Pin<&mut Self>) -> QString;
+
+        #[qinvokable]
+        fn check_config_file(self: Pin<&mut Self>, path: QString) -> QString;
+
+        #[qinvokable]
+        fn import_config(self: Pin<&mut Self>, path: QString);
+
+        #[qinvokable]
+        fn set_app_group_expanded(self: Pin<&mut Self>, group_id: u32, expanded: bool);
+
         #[qinvokable]
         fn get_layout_json(self: Pin<&mut Self>) -> QString;
 
         #[qinvokable]
-        fn save_layout(self: Pin<&mut Self>, json: QString);
+        fn save_layout(self: Pin<&mut Self>, json: QString) -> bool;
 
         #[qinvokable]
         fn get_hidden_json(self: Pin<&mut Self>) -> QString;
 
         #[qinvokable]
-        fn save_hidden(self: Pin<&mut Self>, json: QString);
+        fn save_hidden(self: Pin<&mut Self>, json: QString) -> bool;
 
         #[qinvokable]
         fn get_pinned_json(self: Pin<&mut Self>) -> QString;
 
         #[qinvokable]
-        fn save_pinned(self: Pin<&mut Self>, json: QString);
+        fn save_pinned(self: Pin<&mut Self>, json: QString) -> bool;
 
         #[qinvokable]
         fn get_available_plugins_json(self: Pin<&mut Self>) -> QString;
@@ -78,18 +272,94 @@ pub mod qobject {
         #[qinvokable]
         fn get_plugin_params_json(self: Pin<&mut Self>, node_id: u32) -> QString;
 
+        #[qinvokable]
+        fn get_plugin_param_docs_json(self: Pin<&mut Self>, plugin_uri: QString) -> QString;
+
+        #[qinvokable]
+        fn get_plugin_compat_json(self: Pin<&mut Self>, plugin_uri: QString) -> QString;
+
         #[qinvokable]
         fn set_plugin_parameter(self: Pin<&mut Self>, node_id: u32, port_index: u32, value: f32);
 
+        #[qinvokable]
+        fn get_node_quick_params_json(self: Pin<&mut Self>, node_id: u32) -> QString;
+
+        #[qinvokable]
+        fn set_quick_param_pinned(
+            self: Pin<&mut Self>,
+            node_id: u32,
+            symbol: QString,
+            pinned: bool,
+        ) -> bool;
+
         #[qinvokable]
         fn set_plugin_bypass(self: Pin<&mut Self>, node_id: u32, bypassed: bool);
 
+        #[qinvokable]
+        fn toggle_ab_switch(self: Pin<&mut Self>, node_id: u32) -> bool;
+
+        #[qinvokable]
+        fn toggle_ab_switches(self: Pin<&mut Self>) -> u32;
+
+        #[qinvokable]
+        fn set_plugin_oversampling(self: Pin<&mut Self>, node_id: u32, factor: u32) -> bool;
+
+        #[qinvokable]
+        fn set_plugin_ui_isolated(self: Pin<&mut Self>, node_id: u32, isolated: bool) -> bool;
+
+        #[qinvokable]
+        fn is_plugin_ui_isolated(self: Pin<&mut Self>, node_id: u32) -> bool;
+
+        #[qinvokable]
+        fn set_plugin_ui_scale(self: Pin<&mut Self>, node_id: u32, scale: f64) -> bool;
+
+        #[qinvokable]
+        fn show_all_plugin_uis(self: Pin<&mut Self>);
+
+        #[qinvokable]
+        fn hide_all_plugin_uis(self: Pin<&mut Self>);
+
+        #[qinvokable]
+        fn get_plugin_ui_scale(self: Pin<&mut Self>, node_id: u32) -> f64;
+
         #[qinvokable]
         fn get_active_plugins_json(self: Pin<&mut Self>) -> QString;
 
         #[qinvokable]
         fn remove_plugin_by_stable_id(self: Pin<&mut Self>, stable_id: QString);
 
+        #[qinvokable]
+        fn get_dormant_plugins_json(self: Pin<&mut Self>) -> QString;
+
+        #[qinvokable]
+        fn activate_saved_plugin(self: Pin<&mut Self>, stable_id: QString) -> bool;
+
+        #[qinvokable]
+        fn set_plugin_autostart(self: Pin<&mut Self>, stable_id: QString, autostart: bool);
+
+        #[qinvokable]
+        fn duplicate_plugin(
+            self: Pin<&mut Self>,
+            stable_id: QString,
+            mirror_connections: bool,
+        ) -> QString;
+
+        #[qinvokable]
+        fn copy_selection(self: Pin<&mut Self>, node_ids_json: QString) -> QString;
+
+        #[qinvokable]
+        fn paste_selection(self: Pin<&mut Self>, json: QString) -> QString;
+
+        #[qinvokable]
+        fn replace_plugin(self: Pin<&mut Self>, stable_id: QString, new_uri: QString) -> QString;
+
+        #[qinvokable]
+        fn set_sidechain_source(
+            self: Pin<&mut Self>,
+            stable_id: QString,
+            source_node_id: u32,
+        ) -> bool;
+
         #[qinvokable]
         fn reset_plugin_params_by_stable_id(self: Pin<&mut Self>, stable_id: QString);
 
@@ -101,6 +371,52 @@ pub mod qobject {
             value: f32,
         );
 
+        #[qinvokable]
+        fn randomize_plugin_params(
+            self: Pin<&mut Self>,
+            stable_id: QString,
+            amount: f64,
+            locked_params_json: QString,
+        ) -> bool;
+
+        #[qinvokable]
+        fn undo_plugin_params(self: Pin<&mut Self>, stable_id: QString) -> bool;
+
+        #[qinvokable]
+        fn export_plugin_params(
+            self: Pin<&mut Self>,
+            stable_id: QString,
+            path: QString,
+        ) -> QString;
+
+        #[qinvokable]
+        fn import_plugin_params(
+            self: Pin<&mut Self>,
+            stable_id: QString,
+            path: QString,
+        ) -> QString;
+
+        #[qinvokable]
+        fn get_plugin_presets_json(self: Pin<&mut Self>, plugin_uri: QString) -> QString;
+
+        #[qinvokable]
+        fn save_plugin_preset(self: Pin<&mut Self>, stable_id: QString, name: QString) -> bool;
+
+        #[qinvokable]
+        fn load_plugin_preset(self: Pin<&mut Self>, stable_id: QString, name: QString) -> bool;
+
+        #[qinvokable]
+        fn delete_plugin_preset(self: Pin<&mut Self>, plugin_uri: QString, name: QString) -> bool;
+
+        #[qinvokable]
+        fn get_chain_presets_json(self: Pin<&mut Self>) -> QString;
+
+        #[qinvokable]
+        fn save_chain_preset(self: Pin<&mut Self>, node_id: u32, name: QString) -> bool;
+
+        #[qinvokable]
+        fn delete_chain_preset(self: Pin<&mut Self>, name: QString) -> bool;
+
         #[qinvokable]
         fn get_rules_json(self: Pin<&mut Self>) -> QString;
 
@@ -110,6 +426,24 @@ pub mod qobject {
         #[qinvokable]
         fn remove_rule(self: Pin<&mut Self>, rule_id: QString);
 
+        #[qinvokable]
+        fn set_rule_cooldown(self: Pin<&mut Self>, rule_id: QString, secs: i32);
+
+        #[qinvokable]
+        fn set_rule_once_per_appearance(self: Pin<&mut Self>, rule_id: QString, once: bool);
+
+        #[qinvokable]
+        fn set_rule_fallbacks(self: Pin<&mut Self>, rule_id: QString, targets_json: QString);
+
+        #[qinvokable]
+        fn get_link_overrides_json(self: Pin<&mut Self>) -> QString;
+
+        #[qinvokable]
+        fn remove_link_override(self: Pin<&mut Self>, index: i32);
+
+        #[qinvokable]
+        fn clear_link_overrides(self: Pin<&mut Self>);
+
         #[qinvokable]
         fn apply_rules(self: Pin<&mut Self>);
 
@@ -119,6 +453,43 @@ pub mod qobject {
         #[qinvokable]
         fn toggle_patchbay(self: Pin<&mut Self>, enabled: bool);
 
+        #[qinvokable]
+        fn import_wireplumber_rules(self: Pin<&mut Self>) -> QString;
+
+        #[qinvokable]
+        fn get_compare_json(self: Pin<&mut Self>) -> QString;
+
+        #[qinvokable]
+        fn set_compare_remote(self: Pin<&mut Self>, remote: QString);
+
+        #[qinvokable]
+        fn compare_connect_ports(self: Pin<&mut Self>, output_port_id: u32, input_port_id: u32);
+
+        #[qinvokable]
+        fn compare_disconnect_link(self: Pin<&mut Self>, link_id: u32);
+
+        #[qinvokable]
+        fn toggle_compare_rules(self: Pin<&mut Self>, enabled: bool);
+
+        #[qinvokable]
+        fn generate_remote_api_token(
+            self: Pin<&mut Self>,
+            name: QString,
+            scope: QString,
+        ) -> QString;
+
+        #[qinvokable]
+        fn revoke_remote_api_token(self: Pin<&mut Self>, token: QString);
+
+        #[qinvokable]
+        fn get_remote_instance_json(self: Pin<&mut Self>) -> QString;
+
+        #[qinvokable]
+        fn set_remote_instance(self: Pin<&mut Self>, address: QString, token: QString);
+
+        #[qinvokable]
+        fn send_remote_instance_request(self: Pin<&mut Self>, request_json: QString);
+
         #[qinvokable]
         fn get_node_names_json(self: Pin<&mut Self>) -> QString;
 
@@ -126,22 +497,37 @@ pub mod qobject {
         fn add_rule(
             self: Pin<&mut Self>,
             source_pattern: QString,
-            source_type: QString,
+            source_type: NodeKind,
             target_pattern: QString,
-            target_type: QString,
+            target_type: NodeKind,
         );
 
+        #[qinvokable]
+        fn get_role_policies_json(self: Pin<&mut Self>) -> QString;
+
+        #[qinvokable]
+        fn set_role_policy(self: Pin<&mut Self>, role: QString, target_pattern: QString);
+
+        #[qinvokable]
+        fn remove_role_policy(self: Pin<&mut Self>, role: QString);
+
         #[qinvokable]
         fn get_window_geometry_json(self: Pin<&mut Self>) -> QString;
 
         #[qinvokable]
-        fn save_window_geometry(self: Pin<&mut Self>, json: QString);
+        fn save_window_geometry(self: Pin<&mut Self>, json: QString) -> bool;
+
+        #[qinvokable]
+        fn set_screens(self: Pin<&mut Self>, screens_json: QString, main_screen: QString);
+
+        #[qinvokable]
+        fn get_tray_icon_url(self: Pin<&mut Self>) -> QString;
 
         #[qinvokable]
         fn get_viewport_json(self: Pin<&mut Self>) -> QString;
 
         #[qinvokable]
-        fn save_viewport(self: Pin<&mut Self>, json: QString);
+        fn save_viewport(self: Pin<&mut Self>, json: QString) -> bool;
 
         #[qinvokable]
         fn get_preferences_json(self: Pin<&mut Self>) -> QString;
@@ -152,9 +538,21 @@ pub mod qobject {
         #[qinvokable]
         fn reset_preferences(self: Pin<&mut Self>);
 
+        #[qinvokable]
+        fn activate_node(self: Pin<&mut Self>, node_id: u32) -> QString;
+
         #[qinvokable]
         fn get_poll_interval_ms(self: Pin<&mut Self>) -> i32;
 
+        #[qinvokable]
+        fn get_meter_interval_ms(self: Pin<&mut Self>) -> i32;
+
+        #[qinvokable]
+        fn get_repaint_interval_ms(self: Pin<&mut Self>) -> i32;
+
+        #[qinvokable]
+        fn get_profile_json(self: Pin<&mut Self>) -> QString;
+
         #[qinvokable]
         fn set_window_visible(self: Pin<&mut Self>, visible: bool);
 
@@ -164,6 +562,25 @@ pub mod qobject {
         #[qinvokable]
         fn get_plugin_cpu_json(self: Pin<&mut Self>) -> QString;
 
+        #[qinvokable]
+        fn get_plugin_cpu_history_json(self: Pin<&mut Self>, stable_id: QString) -> QString;
+
+        #[qinvokable]
+        fn set_metering(self: Pin<&mut Self>, enabled: bool);
+
+        #[qinvokable]
+        fn get_meters_json(self: Pin<&mut Self>) -> QString;
+
+        #[qinvokable]
+        fn get_pw_command_log_json(
+            self: Pin<&mut Self>,
+            filter_json: QString,
+            limit: i32,
+        ) -> QString;
+
+        #[qinvokable]
+        fn clear_pw_command_log(self: Pin<&mut Self>);
+
         #[qinvokable]
         fn get_default_node(self: Pin<&mut Self>) -> QString;
 
@@ -176,6 +593,15 @@ pub mod qobject {
         #[qinvokable]
         fn get_qt_version(self: Pin<&mut Self>) -> QString;
 
+        #[qinvokable]
+        fn check_for_updates(self: Pin<&mut Self>);
+
+        #[qinvokable]
+        fn get_update_json(self: Pin<&mut Self>) -> QString;
+
+        #[qinvokable]
+        fn get_crash_issue_url(self: Pin<&mut Self>, report_path: QString) -> QString;
+
         #[qinvokable]
         fn backup_rules(self: Pin<&mut Self>, name: QString) -> QString;
 
@@ -189,7 +615,17 @@ pub mod qobject {
         fn delete_rule_backup(self: Pin<&mut Self>, filename: QString);
 
         #[qinvokable]
-        fn auto_layout(
+        fn get_scene_transition_json(self: Pin<&mut Self>, scene: QString) -> QString;
+
+        #[qinvokable]
+        fn set_scene_transition_json(
+            self: Pin<&mut Self>,
+            scene: QString,
+            steps_json: QString,
+        ) -> bool;
+
+        #[qinvokable]
+        fn auto_layout(
             self: Pin<&mut Self>,
             node_sizes_json: QString,
             pinned_positions_json: QString,
@@ -217,6 +653,15 @@ pub mod qobject {
         #[qinvokable]
         fn get_midi_mappings_json(self: Pin<&mut Self>) -> QString;
 
+        #[qinvokable]
+        fn get_hooks_json(self: Pin<&mut Self>) -> QString;
+
+        #[qinvokable]
+        fn save_hooks_json(self: Pin<&mut Self>, json: QString) -> bool;
+
+        #[qinvokable]
+        fn test_hook(self: Pin<&mut Self>, hook_id: QString);
+
         #[qinvokable]
         fn get_midi_mapping_for_param_json(
             self: Pin<&mut Self>,
@@ -226,15 +671,55 @@ pub mod qobject {
 
         #[qinvokable]
         fn restore_known_good(self: Pin<&mut Self>) -> bool;
+
+        #[qinvokable]
+        fn is_ui_docking_supported(self: Pin<&mut Self>) -> bool;
+
+        #[qinvokable]
+        fn get_docked_uis_json(self: Pin<&mut Self>) -> QString;
+
+        #[qinvokable]
+        fn show_docked_ui(self: Pin<&mut Self>, instance_id: u64);
+
+        #[qinvokable]
+        fn undock_plugin_ui(self: Pin<&mut Self>, instance_id: u64);
+
+        #[qinvokable]
+        fn close_docked_ui(self: Pin<&mut Self>, instance_id: u64);
+
+        #[qinvokable]
+        fn set_dock_geometry(self: Pin<&mut Self>, x: i32, y: i32, width: i32, height: i32);
     }
 
     unsafe extern "RustQt" {
         #[qsignal]
         fn graph_changed(self: Pin<&mut AppController>);
 
+        #[qsignal]
+        fn compare_changed(self: Pin<&mut AppController>);
+
+        #[qsignal]
+        fn remote_instance_changed(self: Pin<&mut AppController>);
+
         #[qsignal]
         fn error_occurred(self: Pin<&mut AppController>, message: QString);
 
+        /// A connection was held back because it would close a feedback
+        /// loop; `path` names the nodes around it.
+        #[qsignal]
+        fn feedback_loop_blocked(
+            self: Pin<&mut AppController>,
+            output_port_id: u32,
+            input_port_id: u32,
+            pair: bool,
+            path: QString,
+        );
+
+        /// A probe started with `probe_path` is done; `report` is JSON with
+        /// the route, the level at each node on it and the finding.
+        #[qsignal]
+        fn probe_finished(self: Pin<&mut AppController>, report: QString);
+
         #[qsignal]
         fn show_window_requested(self: Pin<&mut AppController>);
 
@@ -258,6 +743,27 @@ pub mod qobject {
 
         #[qsignal]
         fn crash_recovery_available(self: Pin<&mut AppController>, crashed_uris: QString);
+
+        #[qsignal]
+        fn temporary_links_changed(self: Pin<&mut AppController>);
+
+        #[qsignal]
+        fn crash_report_available(self: Pin<&mut AppController>, report_path: QString, cause: QString);
+
+        #[qsignal]
+        fn docked_uis_changed(self: Pin<&mut AppController>);
+
+        /// A plugin parameter changed outside the generic editor, e.g. in
+        /// the plugin's own UI or from a MIDI mapping.
+        #[qsignal]
+        fn parameters_changed(self: Pin<&mut AppController>, node_id: u32);
+
+        #[qsignal]
+        fn update_status_changed(self: Pin<&mut AppController>);
+
+        /// A scene transition switched the rules.
+        #[qsignal]
+        fn scene_switched(self: Pin<&mut AppController>, scene: QString);
     }
 }
 
@@ -271,10 +777,67 @@ use std::time::{Duration, Instant};
 
 use std::path::PathBuf;
 
+use crate::builtin::host::AB_SWITCH_PORT_SOURCE;
 use crate::plugin::PluginManager;
-use crate::patchbay::{PatchbayManager, rules};
-use crate::pipewire::{GraphState, PluginEvent, Node, NodeType, Port, PortDirection, PwCommand, PwEvent};
-use crate::tray::TrayState;
+use crate::plugin::chain_file::{CHAIN_FILE_EXTENSION, ChainFile};
+use crate::plugin::cpu_history::{CpuAlerts, CpuHistory, CpuSample};
+use crate::plugin::cpu_stats::PluginCpuSnapshot;
+use crate::plugin::manager::ChainPreset;
+use crate::plugin::meter;
+use crate::plugin::param_file::{ParamFile, ParamFileFormat};
+use crate::plugin::preset::PluginPreset;
+use crate::plugin::quantum;
+use crate::plugin::quick_params;
+use crate::plugin::randomize::{self, ParamHistory};
+use crate::plugin::replace::{replacement_port, transfer_parameters};
+use crate::plugin::snippet::PluginSnippet;
+use crate::plugin::types::SavedPluginLink;
+use crate::plugin::ui_geometry::MonitorArea;
+use crate::patchbay::audition::audition_pairs;
+use crate::midi::link_filter::{self, MidiLinkOptions, SavedMidiLinkFilter};
+use crate::patchbay::link_gain::{self, SavedLinkGain};
+use crate::patchbay::clock;
+use crate::patchbay::crossfade::handover_pairs;
+use crate::patchbay::overrides::{LinkOverride, OverrideMode};
+use crate::patchbay::probe::{self, PROBE_LISTEN, ProbeFinding, ProbeHop};
+use crate::patchbay::propose::{self, ConnectionHistory};
+use crate::patchbay::storm::{StormChange, StormGuard};
+use crate::patchbay::temporary::{MAX_TTL, PortPair, TemporaryRoute, TemporaryRoutes};
+use crate::patchbay::transition::{SceneAction, TransitionRunner, TransitionStep};
+use crate::patchbay::{GraphSnapshot, PatchbayManager, rules};
+use crate::pipewire::{AuditEntry, AuditFilter, AuditLog, CommandOrigin, GraphChange, GraphFilter, GraphObject, GraphState, Heartbeat, LinkOp, MediaType, PluginEvent, Node, NodeFormat, NodeIdentities, NodeOrder, NodeSortKey, NodeType, Port, PortDirection, PwCommand, PwEvent, StallChange, StallWatch};
+use crate::tray::{TrayBackend, TrayState};
+use qobject::{MediaKind, NodeKind};
+use zestbay_core::access::{ApiScope, ApiToken};
+use zestbay_core::config::{self, ConfigSchema};
+use zestbay_core::graph::ConversionPolicy;
+use zestbay_core::graph::channels::{self, ChannelOverrides};
+use zestbay_core::graph::pairs;
+use zestbay_core::hooks::{Hook, HookEvent};
+use zestbay_core::plugin::formats::{DEFAULT_FORMAT_ORDER, format_order_pref, parse_format_order};
+use zestbay_core::portable::{self, BundleNode, ConfigBundle, NodeRemap};
+use zestbay_core::profile::{
+    DEFAULT_METER_INTERVAL_MS, DEFAULT_REPAINT_INTERVAL_MS, PerformanceProfile, ProfileChooser,
+    ProfileSettings,
+};
+use zestbay_core::session;
+use zestbay_core::ui_state::{
+    HiddenNodes, NodeLayout, PinnedNodes, UiState, Viewport, WindowGeometry,
+};
+use zestbay_core::update::Release;
+
+/// How long the PipeWire thread may go without ticking before the UI warns
+/// that it stopped responding. Loading a heavy plugin can block it for a few
+/// seconds.
+const PW_STALL_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long after an output crossfade the old sink's volume waits for the
+/// session manager to move its streams away before it is put back anyway.
+const OUTPUT_CROSSFADE_SETTLE: Duration = Duration::from_secs(2);
+
+/// How long quitting waits for the PipeWire thread to save LV2 plugin
+/// state.
+const LV2_STATE_TIMEOUT: Duration = Duration::from_secs(2);
 
 /// Tracks the mapping between virtual sub-node IDs (used in the UI for split
 /// bridge nodes) and the real PipeWire node ID + port group.
@@ -335,8 +898,276 @@ impl BridgeSplitState {
         self.port_to_virtual.get(&port_id).copied()
     }
 
+    /// Whether `id` is in the bridge sub-node range; app group IDs above it
+    /// are not.
     fn is_virtual_id(&self, id: u32) -> bool {
-        id >= Self::VIRTUAL_ID_BASE
+        (Self::VIRTUAL_ID_BASE..AppGroupState::GROUP_ID_BASE).contains(&id)
+    }
+}
+
+/// A set of streams from the same application, shown as one node in the UI
+/// unless expanded.
+#[derive(Debug, Clone)]
+struct AppGroup {
+    id: u32,
+    name: String,
+    node_type: NodeType,
+    members: Vec<u32>,
+}
+
+/// Groups application streams that share an `application.id` (or process ID)
+/// under one collapsible parent entry. Group IDs are kept for the whole
+/// session so expand/collapse state and layout survive graph refreshes.
+#[derive(Debug, Default)]
+struct AppGroupState {
+    /// (application key, stream type) -> group node ID
+    ids: HashMap<(String, NodeType), u32>,
+    /// group node ID -> member node IDs, rebuilt on each refresh
+    members: HashMap<u32, Vec<u32>>,
+    expanded: std::collections::HashSet<u32>,
+    next_group_id: u32,
+}
+
+impl AppGroupState {
+    /// Group IDs live above the bridge sub-node range so the two never collide.
+    const GROUP_ID_BASE: u32 = 2_000_000;
+
+    fn new() -> Self {
+        Self {
+            next_group_id: Self::GROUP_ID_BASE,
+            ..Default::default()
+        }
+    }
+
+    /// Recompute groups from the current node list. Only applications with
+    /// at least two ready streams of the same direction form a group.
+    fn rebuild(&mut self, nodes: &[Node]) -> Vec<AppGroup> {
+        let mut by_key: HashMap<(String, NodeType), Vec<&Node>> = HashMap::new();
+        for n in nodes.iter().filter(|n| n.ready && !n.is_bridge) {
+            let Some(node_type) = n.node_type else { continue };
+            if !matches!(node_type, NodeType::StreamOutput | NodeType::StreamInput) {
+                continue;
+            }
+            if let Some(ref app_id) = n.application_id {
+                by_key.entry((app_id.clone(), node_type)).or_default().push(n);
+            }
+        }
+
+        self.members.clear();
+        let mut groups = Vec::new();
+        for (key, mut streams) in by_key {
+            if streams.len() < 2 {
+                continue;
+            }
+            streams.sort_by_key(|n| n.id);
+            let id = match self.ids.get(&key) {
+                Some(&id) => id,
+                None => {
+                    let id = self.next_group_id;
+                    self.next_group_id += 1;
+                    self.ids.insert(key.clone(), id);
+                    id
+                }
+            };
+            let name = streams
+                .iter()
+                .find_map(|n| n.application_name.clone())
+                .unwrap_or_else(|| streams[0].display_name().to_string());
+            let members: Vec<u32> = streams.iter().map(|n| n.id).collect();
+            self.members.insert(id, members.clone());
+            groups.push(AppGroup {
+                id,
+                name,
+                node_type: key.1,
+                members,
+            });
+        }
+        groups.sort_by_key(|g| g.id);
+        groups
+    }
+
+    fn members(&self, group_id: u32) -> Option<&[u32]> {
+        self.members.get(&group_id).map(|m| m.as_slice())
+    }
+
+    fn is_expanded(&self, group_id: u32) -> bool {
+        self.expanded.contains(&group_id)
+    }
+
+    fn set_expanded(&mut self, group_id: u32, expanded: bool) {
+        if expanded {
+            self.expanded.insert(group_id);
+        } else {
+            self.expanded.remove(&group_id);
+        }
+    }
+}
+
+/// A chain mixer that has been spawned but not yet wired around its chain,
+/// because its PipeWire node and ports do not exist yet.
+#[derive(Debug)]
+struct PendingChainMix {
+    instance_id: u64,
+    chain: Vec<u32>,
+    created: Instant,
+}
+
+/// A chain preset whose plugins have been created but not yet put on the
+/// link, because their PipeWire nodes and ports do not exist yet.
+#[derive(Debug)]
+struct PendingChainInsert {
+    link_id: u32,
+    head: u64,
+    tail: u64,
+    created: Instant,
+}
+
+/// A noise suppressor waiting for its node to appear so it can be wired in
+/// after `source`.
+#[derive(Debug)]
+struct PendingSuppressor {
+    instance_id: u64,
+    name: String,
+    source: u32,
+    created: Instant,
+}
+
+/// A signal probe listening along the route from one node to another.
+#[derive(Debug)]
+struct RunningProbe {
+    id: u32,
+    source: u32,
+    target: u32,
+    hops: Vec<ProbeHop>,
+    started: Instant,
+    /// When the taps were linked to the probe node.
+    listening_since: Option<Instant>,
+    stopping: bool,
+}
+
+/// A switch of the default sink that crossfades the streams over.
+#[derive(Debug)]
+struct OutputCrossfade {
+    old_sink: u32,
+    new_sink: u32,
+    new_sink_name: String,
+    /// The sinks' volumes when the fade began: the old sink's is put back
+    /// afterwards and the new sink's is what it fades up to.
+    old_volume: f32,
+    new_volume: f32,
+    /// Extra links playing the old sink's streams on the new one while
+    /// fading.
+    pairs: Vec<PortPair>,
+    ends: Instant,
+    /// When the default was changed; the old sink's volume is put back once
+    /// its streams have been moved off it.
+    switched: Option<Instant>,
+}
+
+impl AppGroup {
+    fn layout_key(&self) -> String {
+        let prefix = match self.node_type {
+            NodeType::StreamInput => "StreamIn",
+            _ => "StreamOut",
+        };
+        format!("AppGroup:{}:{}", prefix, self.name)
+    }
+}
+
+impl From<Option<NodeType>> for NodeKind {
+    fn from(node_type: Option<NodeType>) -> Self {
+        match node_type {
+            Some(NodeType::Sink) => Self::Sink,
+            Some(NodeType::Source) => Self::Source,
+            Some(NodeType::StreamOutput) => Self::StreamOutput,
+            Some(NodeType::StreamInput) => Self::StreamInput,
+            Some(NodeType::Duplex) => Self::Duplex,
+            Some(NodeType::Plugin) => Self::Plugin,
+            None => Self::Unknown,
+        }
+    }
+}
+
+impl NodeKind {
+    /// The node type this kind stands for. `Unknown`, and any value QML
+    /// passes that is not a variant, means none.
+    fn node_type(self) -> Option<NodeType> {
+        match self {
+            Self::Sink => Some(NodeType::Sink),
+            Self::Source => Some(NodeType::Source),
+            Self::StreamOutput => Some(NodeType::StreamOutput),
+            Self::StreamInput => Some(NodeType::StreamInput),
+            Self::Duplex => Some(NodeType::Duplex),
+            Self::Plugin => Some(NodeType::Plugin),
+            _ => None,
+        }
+    }
+}
+
+impl From<Option<MediaType>> for MediaKind {
+    fn from(media_type: Option<MediaType>) -> Self {
+        match media_type {
+            Some(MediaType::Audio) => Self::Audio,
+            Some(MediaType::Video) => Self::Video,
+            Some(MediaType::Midi) => Self::Midi,
+            Some(MediaType::Cv) => Self::Cv,
+            None => Self::Unknown,
+        }
+    }
+}
+
+const NODE_KINDS: [NodeKind; 6] = [
+    NodeKind::Sink,
+    NodeKind::Source,
+    NodeKind::StreamOutput,
+    NodeKind::StreamInput,
+    NodeKind::Duplex,
+    NodeKind::Plugin,
+];
+
+/// The node type behind a `type` value in node JSON.
+fn node_type_of_repr(repr: i64) -> Option<NodeType> {
+    NODE_KINDS
+        .iter()
+        .find(|k| i64::from(k.repr) == repr)
+        .and_then(|k| k.node_type())
+}
+
+/// What an entry of the node JSON sorts on. Group and bridge sub-node ids
+/// are not graph nodes and have no activity.
+fn node_sort_key<'a>(node: &'a serde_json::Value, graph: &GraphState) -> NodeSortKey<'a> {
+    let id = node["id"].as_u64().unwrap_or(0) as u32;
+    NodeSortKey {
+        id,
+        name: node["name"].as_str().unwrap_or(""),
+        node_type: node["type"].as_i64().and_then(node_type_of_repr),
+        activity: graph.node_activity(id),
+    }
+}
+
+/// Remote API clients read node and media types as names. Swap the enum
+/// values in node or port JSON back to the names they had before QML got
+/// typed enums.
+fn name_kinds(items: &mut serde_json::Value) {
+    const MEDIA_KINDS: [(MediaKind, &str); 4] = [
+        (MediaKind::Audio, "Audio"),
+        (MediaKind::Video, "Video"),
+        (MediaKind::Midi, "Midi"),
+        (MediaKind::Cv, "Cv"),
+    ];
+    for item in items.as_array_mut().into_iter().flatten() {
+        if let Some(repr) = item["type"].as_i64() {
+            let name = node_type_of_repr(repr)
+                .map_or_else(|| "Unknown".to_string(), |t| format!("{:?}", t));
+            item["type"] = serde_json::json!(name);
+        }
+        if let Some(repr) = item["mediaType"].as_i64() {
+            let name = MEDIA_KINDS
+                .iter()
+                .find(|(k, _)| i64::from(k.repr) == repr)
+                .map_or("Unknown", |(_, name)| name);
+            item["mediaType"] = serde_json::json!(name);
+        }
     }
 }
 
@@ -346,13 +1177,33 @@ pub struct AppControllerRust {
     node_count: i32,
     link_count: i32,
     cpu_usage: QString,
+    tray_backend: QString,
+    /// Shown in the status bar while PipeWire is unreachable; empty otherwise.
+    pipewire_status: QString,
+    /// The PipeWire thread has stopped responding.
+    pipewire_stalled: bool,
+    /// Version of a newer release found by the update check; empty otherwise.
+    latest_version: QString,
+    /// The performance profile in use, after the battery switch.
+    performance_profile: QString,
 
     graph: Option<Arc<GraphState>>,
     event_rx: Option<Receiver<PwEvent>>,
     cmd_tx: Option<Sender<PwCommand>>,
+    /// Bumped by the PipeWire thread on every tick.
+    pw_heartbeat: Heartbeat,
+    pw_watch: StallWatch,
     patchbay: Option<PatchbayManager>,
+    /// Graph changes the rules engine can act on; only these schedule a scan.
+    rule_changes: Option<Receiver<GraphChange>>,
+    /// Link changes, which are saved to `links.json` and redrawn.
+    link_changes: Option<Receiver<GraphChange>>,
+    /// Node and port changes, which refresh the cached view of the graph.
+    view_changes: Option<Receiver<GraphChange>>,
+    /// Port pairs linked the moment their stream appeared, until the next
+    /// scan; see `route_new_streams`.
+    fast_routed: std::collections::HashSet<(u32, u32)>,
     plugin_manager: Option<PluginManager>,
-    last_change_counter: u64,
 
     next_instance_id: u64,
 
@@ -360,6 +1211,9 @@ pub struct AppControllerRust {
 
     last_change_time: Option<std::time::Instant>,
     rules_apply_pending: bool,
+    /// Finished rule scans from the control thread.
+    scan_results: Option<Receiver<crate::control::ScanResult>>,
+    scan_in_flight: bool,
     rules_loaded: bool,
 
     params_dirty: bool,
@@ -368,24 +1222,132 @@ pub struct AppControllerRust {
     pending_restore_count: usize,
     restore_started_at: Option<std::time::Instant>,
     pending_links: Vec<SavedPluginLink>,
+    /// Saved plugins set to manual start that have not been started yet.
+    dormant_plugins: Vec<SavedPlugin>,
+    /// Saved links to or from dormant plugins, restored when they start.
+    dormant_links: Vec<SavedPluginLink>,
+    /// Stable IDs of running plugins that are not started at launch.
+    manual_start_plugins: std::collections::BTreeSet<String>,
+    /// Processing buffer requested for each plugin by stable ID, set per
+    /// chain.
+    plugin_quanta: HashMap<String, u32>,
+    /// Parameter values to restore on undo, per plugin stable ID.
+    param_history: ParamHistory,
+    pending_chain_mixes: Vec<PendingChainMix>,
+    pending_chain_inserts: Vec<PendingChainInsert>,
+    pending_suppressors: Vec<PendingSuppressor>,
+    /// Links that revert on their own after a while.
+    temporary_routes: TemporaryRoutes,
+    /// The node being auditioned and the temporary routes mirroring it.
+    audition: Option<(u32, Vec<u32>)>,
+    probe: Option<RunningProbe>,
+    next_probe_id: u32,
+    output_crossfade: Option<OutputCrossfade>,
+    /// Sidechain source per plugin stable ID, as the source's layout key.
+    sidechain_sources: HashMap<String, String>,
+    /// Parameter symbols pinned to each plugin's node, by stable ID.
+    quick_params: HashMap<String, Vec<String>>,
+    /// Conversion settings per node, by layout key.
+    conversion_policies: std::collections::BTreeMap<String, ConversionPolicy>,
+    /// Nodes whose conversion policy has been sent since they appeared.
+    conversion_applied: std::collections::HashSet<u32>,
+    /// The MIDI clock source and its receivers.
+    clock_routing: clock::ClockRouting,
+    /// MIDI links routed through a filter node.
+    midi_link_filters: Vec<SavedMidiLinkFilter>,
+    /// Audio links routed through a gain node.
+    link_gains: Vec<SavedLinkGain>,
+    /// Transition steps per scene name.
+    scene_transitions: std::collections::BTreeMap<String, Vec<TransitionStep>>,
+    /// The running scene transition and the rule backup it switches to.
+    scene_transition: Option<(String, TransitionRunner)>,
+    /// Stereo pair overrides per device, by layout key.
+    stereo_pairs: std::collections::BTreeMap<String, pairs::PairOverrides>,
+    /// Channel positions set by hand, by node name and port name.
+    channel_overrides: ChannelOverrides,
+    /// Persistent identities of non-plugin nodes, keying layout, hidden and
+    /// pinned entries.
+    node_ids: NodeIdentities,
+    /// Identity of each node in the graph as of the last refresh.
+    node_keys: HashMap<u32, String>,
+    /// LV2 plugin URIs whose UIs run in a process of their own.
+    isolated_ui_plugins: std::collections::BTreeSet<String>,
+    ui_windows: SavedUiWindows,
+    /// The monitors, as the main window sees them, and the one it is on.
+    screens: Vec<MonitorArea>,
+    main_screen: String,
+    /// Editor scale factor override per plugin URI.
+    ui_scales: std::collections::BTreeMap<String, f32>,
+    /// Stable IDs of the plugins whose UIs "Hide All" closed.
+    hidden_uis: Vec<String>,
 
     links_dirty: bool,
     links_dirty_since: Option<std::time::Instant>,
+    /// Trips while graph changes come faster than the rules should follow.
+    storm_guard: StormGuard,
 
     prefs: Preferences,
 
     tray_state: Option<TrayState>,
+    portal: Option<crate::portal::PortalState>,
+    power: Option<crate::power::PowerState>,
+    profile_chooser: ProfileChooser,
+    jack_dbus: Option<crate::jack_dbus::JackDbusState>,
+    patchbay_dbus: Option<crate::patchbay_dbus::PatchbayDbusState>,
+    mqtt: Option<crate::mqtt::MqttState>,
+    remote_api: Option<crate::remote_api::RemoteApiState>,
+    /// Pending release lookup, while one runs.
+    update_rx: Option<Receiver<Result<Release, String>>>,
+    /// Outcome of the last release lookup.
+    update_result: Option<Result<Release, String>>,
+    /// Second PipeWire instance shown beside the main graph.
+    compare: Option<crate::ui::compare::CompareGraph>,
+    /// Another ZestBay managed over its remote API.
+    remote_instance: Option<crate::ui::remote_client::RemoteInstance>,
 
     prev_cpu_ticks: u64,
     prev_cpu_time: Option<Instant>,
     cpu_avg: f64,
     cpu_history: Vec<f64>,
 
+    /// Shown on the tray icon until the window is next shown.
+    plugin_fault: Option<String>,
+    recording_active: bool,
+    prev_overruns: u64,
+    /// Plugin overruns per poll over the last minute, for the tray warning.
+    overrun_samples: std::collections::VecDeque<(Instant, u64)>,
+    /// Latest per-plugin CPU snapshots, by instance ID.
+    plugin_cpu: Vec<(u64, String, PluginCpuSnapshot)>,
+    plugin_cpu_sampled: Option<Instant>,
+    /// Per-plugin load over the last minutes, kept across restarts.
+    plugin_cpu_history: CpuHistory,
+    plugin_cpu_alerts: CpuAlerts,
+
+    /// Where sent commands are logged while `prefs.pw_command_log` is on.
+    command_log: AuditLog,
+    /// Node pairs the user connected by hand, for quick connect.
+    connection_history: ConnectionHistory,
+    /// Commands run on events, from `hooks.json`.
+    hooks: Vec<Hook>,
+    /// Whether overruns are over the warning threshold, so the storm hook
+    /// fires once per storm.
+    xrun_storm: bool,
+    /// Set while a remote client's request runs, so the commands it causes
+    /// are logged as remote rather than as user actions.
+    command_origin: Option<CommandOrigin>,
+
     bridge_split: BridgeSplitState,
+    app_groups: AppGroupState,
 
     midi_mappings: Vec<crate::midi::MidiCcMapping>,
     midi_learn_target: Option<(u64, usize, String, crate::midi::MappingMode)>,
     plugins_frozen: bool,
+
+    /// X11 window of each open plugin UI, by instance ID.
+    plugin_ui_windows: HashMap<u64, u64>,
+    /// Instances whose UI is docked, in the order they were docked.
+    docked_uis: Vec<u64>,
+    active_docked_ui: Option<u64>,
 }
 
 impl Default for AppControllerRust {
@@ -400,30 +1362,104 @@ impl Default for AppControllerRust {
             cmd_tx: None,
             patchbay: None,
             plugin_manager: None,
-            last_change_counter: 0,
+            rule_changes: None,
+            link_changes: None,
+            view_changes: None,
+            fast_routed: std::collections::HashSet::new(),
+            pw_heartbeat: Heartbeat::default(),
+            pw_watch: StallWatch::new(PW_STALL_TIMEOUT, Instant::now()),
             next_instance_id: 1,
             cached_nodes: Vec::new(),
             last_change_time: None,
             rules_apply_pending: false,
+            scan_results: None,
+            scan_in_flight: false,
             rules_loaded: false,
             params_dirty: false,
             params_dirty_since: None,
             pending_restore_count: 0,
             restore_started_at: None,
             pending_links: Vec::new(),
+            dormant_plugins: Vec::new(),
+            dormant_links: Vec::new(),
+            manual_start_plugins: std::collections::BTreeSet::new(),
+            plugin_quanta: HashMap::new(),
+            param_history: ParamHistory::default(),
+            pending_chain_mixes: Vec::new(),
+            pending_chain_inserts: Vec::new(),
+            pending_suppressors: Vec::new(),
+            temporary_routes: TemporaryRoutes::default(),
+            audition: None,
+            probe: None,
+            next_probe_id: 1,
+            output_crossfade: None,
+            sidechain_sources: load_sidechain_sources(),
+            quick_params: load_quick_params(),
+            stereo_pairs: load_stereo_pairs(),
+            channel_overrides: load_channel_overrides(),
+            conversion_policies: load_conversion_policies(),
+            conversion_applied: std::collections::HashSet::new(),
+            clock_routing: load_clock_routing(),
+            midi_link_filters: load_midi_link_filters(),
+            link_gains: load_link_gains(),
+            scene_transitions: load_scene_transitions(),
+            scene_transition: None,
+            node_ids: load_node_ids(),
+            node_keys: HashMap::new(),
+            isolated_ui_plugins: load_isolated_ui_plugins(),
+            ui_windows: load_ui_windows(),
+            screens: Vec::new(),
+            main_screen: String::new(),
+            ui_scales: load_ui_scales(),
+            hidden_uis: Vec::new(),
             links_dirty: false,
             links_dirty_since: None,
+            storm_guard: StormGuard::default(),
             prefs: load_preferences(),
             tray_state: None,
+            portal: None,
+            power: None,
+            profile_chooser: ProfileChooser::default(),
+            jack_dbus: None,
+            patchbay_dbus: None,
+            mqtt: None,
+            remote_api: None,
+            update_rx: None,
+            update_result: None,
+            compare: None,
+            remote_instance: None,
             cpu_usage: QString::from("0.0%"),
+            tray_backend: QString::from(TrayBackend::StatusNotifier.as_str()),
+            pipewire_status: QString::default(),
+            pipewire_stalled: false,
+            rules_suspended: false,
+            latest_version: QString::default(),
+            performance_profile: QString::from(PerformanceProfile::default().as_str()),
             prev_cpu_ticks: 0,
             prev_cpu_time: None,
             cpu_avg: 0.0,
             cpu_history: vec![0.0; 120],
+            plugin_fault: None,
+            recording_active: false,
+            prev_overruns: 0,
+            overrun_samples: std::collections::VecDeque::new(),
+            plugin_cpu: Vec::new(),
+            plugin_cpu_sampled: None,
+            plugin_cpu_history: load_plugin_cpu_history(),
+            plugin_cpu_alerts: CpuAlerts::default(),
+            command_log: AuditLog::new(config_path("pw_commands.log")),
+            connection_history: load_connection_history(),
+            hooks: load_hooks(),
+            xrun_storm: false,
+            command_origin: None,
             bridge_split: BridgeSplitState::new(),
+            app_groups: AppGroupState::new(),
             midi_mappings: Vec::new(),
             midi_learn_target: None,
             plugins_frozen: false,
+            plugin_ui_windows: HashMap::new(),
+            docked_uis: Vec::new(),
+            active_docked_ui: None,
         }
     }
 }
@@ -434,7 +1470,7 @@ impl qobject::AppController {
 
         let prefs = load_preferences();
         log::info!(
-            "Preferences: rule_settle={}ms, params_persist={}ms, links_persist={}ms, poll={}ms, auto_learn={}, pw_tick={}ms, pw_cooldown={}ms",
+            "Preferences: rule_settle={}ms, params_persist={}ms, links_persist={}ms, poll={}ms, auto_learn={}, pw_tick={}ms, pw_cooldown={}ms, pw_connect_retries={}",
             prefs.rule_settle_ms,
             prefs.params_persist_ms,
             prefs.links_persist_ms,
@@ -442,10 +1478,23 @@ impl qobject::AppController {
             prefs.auto_learn_rules,
             prefs.pw_tick_interval_ms,
             prefs.pw_operation_cooldown_ms,
+            prefs.pw_connect_retries,
         );
+        crate::plugin::bypass::set_level_match(prefs.bypass_level_match);
+        crate::plugin::tail::set_tail_seconds(prefs.plugin_tail_seconds);
         self.as_mut().rust_mut().prefs = prefs;
+        let profile = self.active_profile();
+        self.as_mut()
+            .set_performance_profile(QString::from(profile.as_str()));
+        crate::lv2::ui::set_isolated_plugins(self.rust().isolated_ui_plugins.iter().cloned());
+
+        match crate::control::start() {
+            Ok(scan_results) => self.as_mut().rust_mut().scan_results = Some(scan_results),
+            Err(e) => log::error!("{}; rule scans and config writes stay on the GUI thread", e),
+        }
 
         let graph = GraphState::new();
+        graph.set_channel_overrides(self.rust().channel_overrides.clone());
 
         // Scan all plugin formats and populate the unified plugin manager
         let lv2_scanner = crate::lv2::Lv2Manager::new();
@@ -458,16 +1507,24 @@ impl qobject::AppController {
         let vst3_plugins = crate::vst3::scanner::scan_plugins();
         plugin_manager.extend_available_plugins(vst3_plugins);
 
+        plugin_manager.extend_available_plugins(crate::builtin::available_plugins());
+
         plugin_manager.sort_catalog();
+        plugin_manager.set_format_order(parse_format_order(&self.rust().prefs.plugin_format_order));
+        plugin_manager.set_show_all_versions(self.rust().prefs.show_all_plugin_versions);
+        plugin_manager.set_presets(load_plugin_presets());
+        plugin_manager.set_chain_presets(load_chain_presets());
 
-        let (event_rx, cmd_tx) = crate::pipewire::start(
-            graph.clone(),
-            self.rust().prefs.pw_tick_interval_ms,
-            self.rust().prefs.pw_operation_cooldown_ms,
-        );
+        let (event_rx, cmd_tx) =
+            self.start_pipewire(graph.clone(), self.rust().pw_heartbeat.clone());
 
-        let patchbay = PatchbayManager::new(graph.clone());
+        let mut patchbay = PatchbayManager::new();
+        patchbay.set_override_mode(self.rust().prefs.override_mode());
+        let rule_changes = graph.subscribe(PatchbayManager::graph_filter());
 
+        self.as_mut().rust_mut().rule_changes = Some(rule_changes);
+        self.as_mut().rust_mut().link_changes = Some(graph.subscribe(link_filter()));
+        self.as_mut().rust_mut().view_changes = Some(graph.subscribe(view_filter()));
         self.as_mut().rust_mut().graph = Some(graph);
         self.as_mut().rust_mut().event_rx = Some(event_rx);
         self.as_mut().rust_mut().cmd_tx = Some(cmd_tx);
@@ -483,7 +1540,27 @@ impl qobject::AppController {
             self.as_mut().rust_mut().pending_links = saved_links;
         }
 
-        let saved = load_saved_plugins();
+        let (dormant, saved): (Vec<_>, Vec<_>) = load_saved_plugins()
+            .into_iter()
+            .partition(|sp| sp.manual_start);
+        if !dormant.is_empty() {
+            log::info!("{} saved plugins are set to manual start", dormant.len());
+            let (dormant_links, links): (Vec<_>, Vec<_>) =
+                std::mem::take(&mut self.as_mut().rust_mut().pending_links)
+                    .into_iter()
+                    .partition(|l| dormant.iter().any(|sp| l.touches(&sp.display_name)));
+            self.as_mut().rust_mut().pending_links = links;
+            self.as_mut().rust_mut().dormant_links = dormant_links;
+            self.as_mut().rust_mut().dormant_plugins = dormant
+                .into_iter()
+                .map(|mut sp| {
+                    if sp.stable_id.is_empty() {
+                        sp.stable_id = uuid::Uuid::new_v4().to_string();
+                    }
+                    sp
+                })
+                .collect();
+        }
         let safe_mode = crate::SAFE_MODE.load(std::sync::atomic::Ordering::SeqCst);
 
         let crashed_uris_str = if !safe_mode && has_crash_marker() {
@@ -518,108 +1595,31 @@ impl qobject::AppController {
             self.as_mut().rust_mut().pending_restore_count = saved.len();
             self.as_mut().rust_mut().restore_started_at = Some(Instant::now());
             for sp in saved {
-                let instance_id = self.rust().next_instance_id;
-                self.as_mut().rust_mut().next_instance_id += 1;
-
-                let restored_params: Vec<crate::lv2::Lv2ParameterValue> = if let Some(ref mgr) =
-                    self.rust().plugin_manager
-                {
-                    if let Some(plugin_info) = mgr.find_plugin(&sp.uri) {
-                        plugin_info
-                            .ports
-                            .iter()
-                            .filter(|port| port.port_type == crate::lv2::Lv2PortType::ControlInput)
-                            .map(|port| {
-                                let saved_value = sp.parameters.iter().find(|s| {
-                                    s.port_index == port.index
-                                        || (!s.symbol.is_empty() && s.symbol == port.symbol)
-                                });
-                                crate::lv2::Lv2ParameterValue {
-                                    port_index: port.index,
-                                    symbol: port.symbol.clone(),
-                                    name: port.name.clone(),
-                                    value: saved_value
-                                        .map(|s| s.value)
-                                        .unwrap_or(port.default_value),
-                                    min: port.min_value,
-                                    max: port.max_value,
-                                    default: port.default_value,
-                                    is_toggle: port.is_toggle,
-                                }
-                            })
-                            .collect()
-                    } else {
-                        sp.parameters
-                            .iter()
-                            .map(|p| crate::lv2::Lv2ParameterValue {
-                                port_index: p.port_index,
-                                symbol: p.symbol.clone(),
-                                name: String::new(),
-                                value: p.value,
-                                min: 0.0,
-                                max: 1.0,
-                                default: 0.0,
-                                is_toggle: false,
-                            })
-                            .collect()
-                    }
-                } else {
-                    Vec::new()
-                };
-
-                let sid = if sp.stable_id.is_empty() {
-                    uuid::Uuid::new_v4().to_string()
-                } else {
-                    sp.stable_id.clone()
-                };
-
-                let plugin_format = match sp.format.as_str() {
-                    "CLAP" => crate::plugin::PluginFormat::Clap,
-                    "VST3" => crate::plugin::PluginFormat::Vst3,
-                    _ => crate::plugin::PluginFormat::Lv2,
-                };
-
-                if let Some(ref mut mgr) = self.as_mut().rust_mut().plugin_manager {
-                    let info = crate::lv2::Lv2InstanceInfo {
-                        id: instance_id,
-                        stable_id: sid,
-                        plugin_uri: sp.uri.clone(),
-                        format: plugin_format,
-                        display_name: sp.display_name.clone(),
-                        pw_node_id: None,
-                        parameters: restored_params,
-                        active: true,
-                        bypassed: sp.bypassed,
-                        lv2_state: sp.lv2_state.clone(),
-                    };
-                    mgr.register_instance(info);
-                }
-
-                let format_str = sp.format.clone();
-                if let Some(ref tx) = self.rust().cmd_tx {
-                    log::info!("Restoring plugin: {} ({}) [{}]", sp.display_name, sp.uri, format_str);
-                    let _ = tx.send(PwCommand::AddPlugin {
-                        plugin_uri: sp.uri,
-                        instance_id,
-                        display_name: sp.display_name,
-                        format: format_str,
-                        lv2_state: sp.lv2_state,
-                    });
-                }
+                self.as_mut().start_saved_plugin(sp);
             }
         }
 
         let saved_midi = load_midi_mappings();
         if !saved_midi.is_empty() {
             log::info!("Restoring {} saved MIDI mappings", saved_midi.len());
-            if let Some(ref tx) = self.rust().cmd_tx {
-                for mapping in saved_midi {
-                    let _ = tx.send(PwCommand::AddMidiMapping(mapping));
-                }
+            for mapping in saved_midi {
+                self.send_command(CommandOrigin::Restore, PwCommand::AddMidiMapping(mapping));
             }
         }
 
-        let tray_state = crate::tray::spawn_tray();
+        let headless = crate::HEADLESS.load(std::sync::atomic::Ordering::SeqCst);
+        let backend = if headless {
+            TrayBackend::None
+        } else {
+            TrayBackend::from_mode(&self.rust().prefs.tray_mode)
+        };
+        log::info!("Tray backend: {}", backend.as_str());
+        self.as_mut().set_tray_backend(QString::from(backend.as_str()));
+        let tray_state = if backend == TrayBackend::StatusNotifier {
+            crate::tray::spawn_tray()
+        } else {
+            TrayState::new()
+        };
         if self.rust().prefs.start_minimized {
             tray_state
                 .window_visible
@@ -627,7 +1627,44 @@ impl qobject::AppController {
         }
         self.as_mut().rust_mut().tray_state = Some(tray_state);
 
+        // A demo session must not take activation requests meant for the
+        // real instance, and a headless one has no window to show.
+        if !crate::DEMO_MODE.load(std::sync::atomic::Ordering::SeqCst) && !headless {
+            let portal = crate::portal::spawn_portal();
+            if self.rust().prefs.start_minimized {
+                portal.request_background();
+            }
+            self.as_mut().rust_mut().portal = Some(portal);
+        }
+        self.as_mut().rust_mut().power = Some(crate::power::spawn_power_watch());
+
+        if self.rust().prefs.jack_dbus_patchbay {
+            self.as_mut().rust_mut().jack_dbus = Some(crate::jack_dbus::spawn_jack_dbus());
+        }
+
+        if self.rust().prefs.patchbay_dbus {
+            self.as_mut().rust_mut().patchbay_dbus =
+                Some(crate::patchbay_dbus::spawn_patchbay_dbus());
+        }
+
+        if self.rust().prefs.mqtt_enabled {
+            self.as_mut().restart_mqtt();
+        }
+
+        if self.rust().prefs.remote_api_enabled || headless {
+            self.as_mut().restart_remote_api();
+        }
+
+        if self.rust().prefs.check_for_updates {
+            self.as_mut().check_for_updates();
+        }
+
+        self.as_mut().restart_compare();
+        self.as_mut().restart_remote_instance();
+
         if let Some(ref uris) = crashed_uris_str {
+            self.as_mut().rust_mut().plugin_fault =
+                Some(format!("Plugin restore crashed: {}", uris));
             if has_known_good_plugins() {
                 self.as_mut().crash_recovery_available(QString::from(uris.as_str()));
             } else {
@@ -641,6 +1678,15 @@ impl qobject::AppController {
             }
         }
 
+        if let Some(report) = crate::crash_report::take_unseen().pop() {
+            let cause = crate::crash_report::report_cause(&report);
+            log::warn!("Crash report from the last run: {}", report.display());
+            self.as_mut().crash_report_available(
+                QString::from(report.to_string_lossy().as_ref()),
+                QString::from(cause.as_str()),
+            );
+        }
+
         log::info!("AppController initialized successfully");
     }
 
@@ -656,16 +1702,28 @@ impl qobject::AppController {
                 }
             }
 
+            let role_policies = load_role_policies();
+            if !role_policies.is_empty() {
+                log::info!("Loaded {} role policies", role_policies.len());
+                if let Some(ref mut patchbay) = self.as_mut().rust_mut().patchbay {
+                    patchbay.set_role_policies(role_policies);
+                }
+            }
+
+            let overrides = load_link_overrides();
+            if !overrides.is_empty() {
+                log::info!("Loaded {} manual link overrides", overrides.len());
+                if let Some(ref mut patchbay) = self.as_mut().rust_mut().patchbay {
+                    patchbay.set_overrides(overrides);
+                }
+            }
+
             // Load default node setting
             let default_node_path = config_path("default_node.txt");
             if let Ok(key) = std::fs::read_to_string(&default_node_path) {
                 let key = key.trim().to_string();
                 if !key.is_empty() {
-                    let display_name = if let Some(pos) = key.find(':') {
-                        key[pos + 1..].to_string()
-                    } else {
-                        key.clone()
-                    };
+                    let display_name = layout_key_display_name(&key, &self.rust().node_ids);
                     log::info!("Loaded default node: {}", display_name);
                     if let Some(ref mut patchbay) = self.as_mut().rust_mut().patchbay {
                         patchbay.set_default_target(Some(display_name));
@@ -677,7 +1735,11 @@ impl qobject::AppController {
         let mut changed = false;
         let mut link_changed = false;
         let mut error_msg: Option<String> = None;
+        let mut docked_changed = false;
+        // Plugin nodes with parameter changes, signalled once per poll.
+        let mut params_changed = std::collections::BTreeSet::new();
         let mut plugin_events: Vec<PluginEvent> = Vec::new();
+        let mut probe_results = Vec::new();
 
         let has_events = self.rust().event_rx.is_some();
         if has_events {
@@ -685,30 +1747,49 @@ impl qobject::AppController {
             if let Some(rx) = rx {
                 while let Ok(event) = rx.try_recv() {
                     match event {
+                        // Arrive through the graph subscriptions below.
                         PwEvent::NodeChanged(_)
                         | PwEvent::NodeRemoved(_)
                         | PwEvent::PortChanged(_)
                         | PwEvent::PortRemoved { .. }
-                        | PwEvent::BatchComplete => {
-                            changed = true;
-                        }
-                        PwEvent::LinkChanged(_) | PwEvent::LinkRemoved(_) => {
+                        | PwEvent::LinkChanged(_)
+                        | PwEvent::LinkRemoved(_) => {}
+                        PwEvent::BatchComplete => {
                             changed = true;
-                            link_changed = true;
                         }
                         PwEvent::Error(msg) => {
                             log::error!("PipeWire error: {}", msg);
                             error_msg = Some(msg);
                         }
+                        PwEvent::WaitingForDaemon { attempt } => {
+                            log::debug!("Waiting for PipeWire (attempt {})", attempt);
+                            self.as_mut()
+                                .set_pipewire_status(QString::from("Waiting for PipeWire…"));
+                        }
+                        PwEvent::Connected => {
+                            self.as_mut().set_pipewire_status(QString::default());
+                            self.start_midi_link_filters();
+                            self.start_link_gains();
+                            // Restores queued while waiting only start now.
+                            if self.rust().pending_restore_count > 0 {
+                                self.as_mut().rust_mut().restore_started_at = Some(Instant::now());
+                            }
+                        }
                         PwEvent::Plugin(plugin_event) => {
                             changed = true;
                             plugin_events.push(plugin_event);
                         }
+                        PwEvent::ProbeFinished { probe_id, peaks_db } => {
+                            probe_results.push((probe_id, peaks_db));
+                        }
                     }
                 }
                 self.as_mut().rust_mut().event_rx = Some(rx);
             }
         }
+        for (probe_id, peaks_db) in probe_results {
+            self.as_mut().finish_probe(probe_id, &peaks_db);
+        }
 
         for event in plugin_events {
             match event {
@@ -725,6 +1806,11 @@ impl qobject::AppController {
                     );
                     if let Some(ref mut mgr) = self.as_mut().rust_mut().plugin_manager {
                         mgr.set_instance_pw_node_id(instance_id, pw_node_id);
+                        if let Some(uri) =
+                            mgr.get_instance(instance_id).map(|i| i.plugin_uri.clone())
+                        {
+                            mgr.clear_load_failure(&uri);
+                        }
                     }
                     if pw_node_id != 0
                         && pw_node_id != u32::MAX
@@ -736,20 +1822,25 @@ impl qobject::AppController {
                     if let Some(ref mgr) = self.rust().plugin_manager
                         && let Some(info) = mgr.get_instance(instance_id)
                         && (!info.parameters.is_empty() || info.bypassed)
-                        && let Some(ref tx) = self.rust().cmd_tx
                     {
                         for param in &info.parameters {
-                            let _ = tx.send(PwCommand::SetPluginParameter {
-                                instance_id,
-                                port_index: param.port_index,
-                                value: param.value,
-                            });
+                            self.send_command(
+                                CommandOrigin::Restore,
+                                PwCommand::SetPluginParameter {
+                                    instance_id,
+                                    port_index: param.port_index,
+                                    value: param.value,
+                                },
+                            );
                         }
                         if info.bypassed {
-                            let _ = tx.send(PwCommand::SetPluginBypass {
-                                instance_id,
-                                bypassed: true,
-                            });
+                            self.send_command(
+                                CommandOrigin::Restore,
+                                PwCommand::SetPluginBypass {
+                                    instance_id,
+                                    bypassed: true,
+                                },
+                            );
                         }
                         log::info!(
                             "Restored {} params + bypass={} for instance {}",
@@ -759,7 +1850,23 @@ impl qobject::AppController {
                         );
                     }
 
+                    let quantum = self.rust().plugin_manager.as_ref().and_then(|mgr| {
+                        let info = mgr.get_instance(instance_id)?;
+                        self.rust().plugin_quanta.get(&info.stable_id).copied()
+                    });
+                    if quantum.is_some() {
+                        self.send_command(
+                            CommandOrigin::Restore,
+                            PwCommand::SetPluginQuantum {
+                                instance_id,
+                                quantum,
+                            },
+                        );
+                    }
+
                     if self.rust().pending_restore_count > 0 {
+                        self.as_mut().reopen_plugin_ui(instance_id);
+
                         let count = self.rust().pending_restore_count - 1;
                         self.as_mut().rust_mut().pending_restore_count = count;
                         if count == 0 {
@@ -771,15 +1878,48 @@ impl qobject::AppController {
                 }
                 PluginEvent::PluginRemoved { instance_id } => {
                     log::info!("LV2 plugin removed: instance={}", instance_id);
+                    let stable_id = self
+                        .rust()
+                        .plugin_manager
+                        .as_ref()
+                        .and_then(|m| m.get_instance(instance_id))
+                        .map(|info| info.stable_id.clone());
+                    if let Some(ref sid) = stable_id
+                        && self.as_mut().rust_mut().sidechain_sources.remove(sid).is_some()
+                    {
+                        save_sidechain_sources(&self.rust().sidechain_sources);
+                    }
+                    if let Some(ref sid) = stable_id
+                        && self.as_mut().rust_mut().quick_params.remove(sid).is_some()
+                    {
+                        save_quick_params(&self.rust().quick_params);
+                    }
+                    if let Some(ref sid) = stable_id {
+                        let mut rust = self.as_mut().rust_mut();
+                        let had_geometry = rust.ui_windows.geometry.remove(sid).is_some();
+                        let had_monitor = rust.ui_windows.monitors.remove(sid).is_some();
+                        let was_open = rust.ui_windows.open.remove(sid);
+                        if had_geometry || had_monitor || was_open {
+                            save_ui_windows(&self.rust().ui_windows);
+                        }
+                    }
                     if let Some(ref mut mgr) = self.as_mut().rust_mut().plugin_manager {
                         mgr.remove_instance(instance_id);
                     }
-                    persist_active_plugins(self.rust().plugin_manager.as_ref());
+                    persist_active_plugins(self.rust());
                     self.as_mut().rust_mut().links_dirty = true;
                     if self.rust().links_dirty_since.is_none() {
                         self.as_mut().rust_mut().links_dirty_since = Some(Instant::now());
                     }
                 }
+                PluginEvent::ParamDocs { instance_id, docs } => {
+                    if let Some(ref mut mgr) = self.as_mut().rust_mut().plugin_manager
+                        && let Some(uri) =
+                            mgr.get_instance(instance_id).map(|i| i.plugin_uri.clone())
+                    {
+                        mgr.set_param_docs(&uri, docs);
+                    }
+                }
                 PluginEvent::ParameterChanged {
                     instance_id,
                     port_index,
@@ -788,6 +1928,23 @@ impl qobject::AppController {
                     if let Some(ref mut mgr) = self.as_mut().rust_mut().plugin_manager {
                         mgr.update_parameter(instance_id, port_index, value);
                     }
+                    let node_id = self
+                        .rust()
+                        .plugin_manager
+                        .as_ref()
+                        .and_then(|m| m.get_instance(instance_id))
+                        .and_then(|info| info.pw_node_id);
+                    params_changed.extend(node_id);
+                    if let Some(ref remote) = self.rust().remote_api {
+                        remote.emit(
+                            "parameter",
+                            serde_json::json!({
+                                "nodeId": node_id,
+                                "portIndex": port_index,
+                                "value": value,
+                            }),
+                        );
+                    }
                     self.as_mut().rust_mut().params_dirty = true;
                     if self.rust().params_dirty_since.is_none() {
                         self.as_mut().rust_mut().params_dirty_since = Some(Instant::now());
@@ -795,9 +1952,96 @@ impl qobject::AppController {
                 }
                 PluginEvent::PluginUiOpened { instance_id } => {
                     log::info!("LV2 plugin UI opened: instance={}", instance_id);
+                    if let Some(sid) = self.stable_id_for_instance(instance_id) {
+                        self.as_mut().rust_mut().hidden_uis.retain(|h| *h != sid);
+                        if self.as_mut().rust_mut().ui_windows.open.insert(sid) {
+                            save_ui_windows(&self.rust().ui_windows);
+                        }
+                    }
                 }
                 PluginEvent::PluginUiClosed { instance_id } => {
                     log::info!("LV2 plugin UI closed: instance={}", instance_id);
+                    // Settings made in the UI may live in the plugin's state
+                    // rather than its ports, e.g. a sampler's loaded file.
+                    if self.is_lv2_instance(instance_id) {
+                        self.send_command(CommandOrigin::System, PwCommand::SaveLv2States);
+                    }
+                    if let Some(sid) = self.stable_id_for_instance(instance_id) {
+                        self.as_mut().rust_mut().ui_windows.open.remove(&sid);
+                        save_ui_windows(&self.rust().ui_windows);
+                    }
+                    let window = self
+                        .as_mut()
+                        .rust_mut()
+                        .plugin_ui_windows
+                        .remove(&instance_id);
+                    if let Some(window) = window {
+                        if self.rust().docked_uis.contains(&instance_id) {
+                            docked_changed = true;
+                        }
+                        self.as_mut().forget_docked_ui(instance_id, window, false);
+                    }
+                }
+                PluginEvent::PluginUiWindow {
+                    instance_id,
+                    window_id,
+                } => {
+                    self.as_mut()
+                        .rust_mut()
+                        .plugin_ui_windows
+                        .insert(instance_id, window_id);
+                    if self.rust().prefs.dock_plugin_uis
+                        && crate::ui::plugin_dock::attach(window_id)
+                    {
+                        log::info!("Docked plugin UI: instance={}", instance_id);
+                        let mut rust = self.as_mut().rust_mut();
+                        rust.docked_uis.push(instance_id);
+                        rust.active_docked_ui = Some(instance_id);
+                        crate::ui::plugin_dock::show(Some(window_id));
+                        docked_changed = true;
+                    }
+                }
+                PluginEvent::PluginUiGeometry {
+                    instance_id,
+                    geometry,
+                } => {
+                    // Written out when the window closes or on quit. A docked
+                    // window's position is relative to the dock, so skip it.
+                    if !self.rust().docked_uis.contains(&instance_id)
+                        && let Some(sid) = self.stable_id_for_instance(instance_id)
+                    {
+                        let monitor = geometry
+                            .monitor(&self.rust().screens)
+                            .map(|m| m.name.clone());
+                        let mut rust = self.as_mut().rust_mut();
+                        if let Some(monitor) = monitor {
+                            rust.ui_windows.monitors.insert(sid.clone(), monitor);
+                        }
+                        rust.ui_windows.geometry.insert(sid, geometry);
+                    }
+                }
+                PluginEvent::PluginUiStalled { instance_id } => {
+                    let plugin_name = instance_id.and_then(|id| {
+                        self.rust()
+                            .plugin_manager
+                            .as_ref()
+                            .and_then(|mgr| mgr.get_instance(id))
+                            .map(|info| info.display_name.clone())
+                    });
+                    error_msg = Some(match plugin_name {
+                        Some(name) => format!(
+                            "The UI of \"{}\" stopped responding and is blocking other plugin windows. \
+                             Consider running its UI in a separate process.",
+                            name
+                        ),
+                        None => "A plugin UI stopped responding and is blocking other plugin windows."
+                            .to_string(),
+                    });
+                    self.as_mut().rust_mut().plugin_fault = error_msg.clone();
+                }
+                PluginEvent::PluginUiRecovered => {
+                    log::info!("Plugin UI thread recovered");
+                    self.as_mut().rust_mut().plugin_fault = None;
                 }
                 PluginEvent::PluginError {
                     instance_id,
@@ -821,9 +2065,14 @@ impl qobject::AppController {
 
                         if fatal {
                             if let Some(ref mut mgr) = self.as_mut().rust_mut().plugin_manager {
+                                if let Some(uri) =
+                                    mgr.get_instance(id).map(|i| i.plugin_uri.clone())
+                                {
+                                    mgr.record_load_failure(&uri, &message);
+                                }
                                 mgr.remove_instance(id);
                             }
-                            persist_active_plugins(self.rust().plugin_manager.as_ref());
+                            persist_active_plugins(self.rust());
 
                             if self.rust().pending_restore_count > 0 {
                                 let count = self.rust().pending_restore_count - 1;
@@ -841,6 +2090,9 @@ impl qobject::AppController {
                         } else {
                             error_msg = Some(format!("Plugin failed to load: {}", message));
                         }
+                        if fatal {
+                            self.as_mut().rust_mut().plugin_fault = error_msg.clone();
+                        }
                     } else {
                         error_msg = Some(message);
                     }
@@ -890,13 +2142,11 @@ impl qobject::AppController {
                         state.len(),
                         instance_id
                     );
-                    if let Some(ref mut mgr) = self.as_mut().rust_mut().plugin_manager {
-                        if let Some(info) = mgr.get_instance_mut(instance_id) {
-                            info.lv2_state = state;
-                        }
+                    if self.as_mut().store_lv2_state(instance_id, state) {
+                        persist_active_plugins(self.rust());
                     }
-                    persist_active_plugins(self.rust().plugin_manager.as_ref());
                 }
+                PluginEvent::Lv2StatesSaved => {}
                 PluginEvent::MidiCcReceived { ref device_name, channel, cc, message_type } => {
                     if let Some((instance_id, port_index, label, mode)) =
                         self.as_mut().rust_mut().midi_learn_target.take()
@@ -915,42 +2165,90 @@ impl qobject::AppController {
                             mode,
                             label,
                         };
-                        if let Some(ref tx) = self.rust().cmd_tx {
-                            let _ = tx.send(PwCommand::CancelMidiLearn);
-                            let _ = tx.send(PwCommand::AddMidiMapping(mapping));
-                        }
+                        self.send_command(CommandOrigin::User, PwCommand::CancelMidiLearn);
+                        self.send_command(CommandOrigin::User, PwCommand::AddMidiMapping(mapping));
                     }
                 }
             }
         }
 
-        if let Some(ref graph) = self.rust().graph {
-            let current = graph.change_counter();
-            let last = self.rust().last_change_counter;
-            if current != last {
+        if let Some(ref rx) = self.rust().link_changes {
+            for change in rx.try_iter() {
                 changed = true;
-                self.as_mut().rust_mut().last_change_counter = current;
+                link_changed |= matches!(
+                    change,
+                    GraphChange::LinkChanged(_) | GraphChange::LinkRemoved(_)
+                );
             }
         }
+        if let Some(ref rx) = self.rust().view_changes
+            && rx.try_iter().count() > 0
+        {
+            changed = true;
+        }
 
         if changed {
             self.as_mut().rust_mut().last_change_time = Some(Instant::now());
-            self.as_mut().rust_mut().rules_apply_pending = true;
         }
 
-        if link_changed
-            && self.rust().pending_restore_count == 0
-            && self.rust().pending_links.is_empty()
-        {
-            self.as_mut().rust_mut().links_dirty = true;
-            if self.rust().links_dirty_since.is_none() {
-                self.as_mut().rust_mut().links_dirty_since = Some(Instant::now());
+        // Plugin parameter traffic and video nodes do not call for a rule
+        // scan; only changes passing the patchbay's filter do.
+        let mut appeared = Vec::new();
+        let mut rule_events = 0;
+        if let Some(ref rx) = self.rust().rule_changes {
+            for change in rx.try_iter() {
+                rule_events += 1;
+                match change {
+                    GraphChange::NodeChanged(n) => appeared.push(n.id),
+                    GraphChange::PortChanged(p) => appeared.push(p.node_id),
+                    _ => {}
+                }
             }
         }
+        if rule_events > 0 {
+            self.as_mut().rust_mut().last_change_time = Some(Instant::now());
+            self.as_mut().rust_mut().rules_apply_pending = true;
+        }
+        // The first fill of the graph is not a storm.
+        if !self.rust().cached_nodes.is_empty() {
+            self.as_mut().track_event_storm(rule_events);
+        }
+        if !appeared.is_empty() {
+            self.as_mut().route_new_streams(appeared);
+        }
+
+        // Rules paused after a manual disconnect apply again once their
+        // cooldown runs out, even if nothing else changed.
+        let cooled_down = match self.as_mut().rust_mut().patchbay {
+            Some(ref mut patchbay) => patchbay.expire_cooldowns(),
+            None => false,
+        };
+        if cooled_down {
+            self.as_mut().rust_mut().rules_apply_pending = true;
+        }
+
+        if link_changed
+            && self.rust().pending_restore_count == 0
+            && self.rust().pending_links.is_empty()
+        {
+            self.as_mut().rust_mut().links_dirty = true;
+            if self.rust().links_dirty_since.is_none() {
+                self.as_mut().rust_mut().links_dirty_since = Some(Instant::now());
+            }
+        }
+
+        let scanned = self
+            .rust()
+            .scan_results
+            .as_ref()
+            .and_then(|rx| rx.try_iter().last());
+        if let Some(result) = scanned {
+            self.as_mut().finish_rule_scan(result);
+        }
 
         let rule_settle_ms = self.rust().prefs.rule_settle_ms;
         let should_apply = {
-            let pending = self.rust().rules_apply_pending;
+            let pending = self.rust().rules_apply_pending && !self.rust().scan_in_flight;
             let patchbay_enabled = self
                 .rust()
                 .patchbay
@@ -962,36 +2260,12 @@ impl qobject::AppController {
                 .last_change_time
                 .map(|t| t.elapsed() >= Duration::from_millis(rule_settle_ms))
                 .unwrap_or(false);
-            pending && patchbay_enabled && settled
+            pending && patchbay_enabled && settled && !self.rust().storm_guard.is_tripped()
         };
 
         if should_apply {
             self.as_mut().rust_mut().rules_apply_pending = false;
-            let commands = if let Some(ref mut patchbay) = self.as_mut().rust_mut().patchbay {
-                patchbay.scan()
-            } else {
-                Vec::new()
-            };
-            if !commands.is_empty() {
-                log::info!("Auto-applying {} patchbay rule commands", commands.len());
-                if let Some(ref tx) = self.rust().cmd_tx {
-                    for cmd in commands {
-                        let _ = tx.send(cmd);
-                    }
-                }
-            }
-            if self
-                .rust()
-                .patchbay
-                .as_ref()
-                .map(|p| p.rules_dirty)
-                .unwrap_or(false)
-            {
-                if let Some(ref mut patchbay) = self.as_mut().rust_mut().patchbay {
-                    patchbay.rules_dirty = false;
-                }
-                save_rules(self.rust().patchbay.as_ref());
-            }
+            self.as_mut().request_rule_scan();
         }
 
         let params_persist_ms = self.rust().prefs.params_persist_ms;
@@ -1006,7 +2280,7 @@ impl qobject::AppController {
         if should_persist_params {
             self.as_mut().rust_mut().params_dirty = false;
             self.as_mut().rust_mut().params_dirty_since = None;
-            persist_active_plugins(self.rust().plugin_manager.as_ref());
+            persist_active_plugins(self.rust());
         }
 
         let should_restore_links = {
@@ -1022,77 +2296,24 @@ impl qobject::AppController {
             let links = std::mem::take(&mut self.as_mut().rust_mut().pending_links);
             log::info!("Attempting to restore {} saved LV2 links", links.len());
             if let Some(ref graph) = self.rust().graph {
+                let saved_ids = self.rust().node_ids.saved_ids(&graph.get_all_nodes());
                 for saved_link in &links {
-                    let all_nodes = graph.get_all_nodes();
-
-                    let mut out_port_id = None;
-                    // First try matching by node display name
-                    for n in all_nodes
-                        .iter()
-                        .filter(|n| n.display_name() == saved_link.output_node_name)
-                    {
-                        let ports = graph.get_ports_for_node(n.id);
-                        if let Some(p) = ports.iter().find(|p| {
-                            p.name == saved_link.output_port_name
-                                && p.direction == PortDirection::Output
-                        }) {
-                            out_port_id = Some(p.id);
-                            break;
-                        }
-                    }
-                    // If not found, check bridge sub-nodes by device name (from port.alias)
-                    if out_port_id.is_none() {
-                        for n in all_nodes.iter().filter(|n| n.is_bridge) {
-                            let groups = graph.get_bridge_port_groups(n.id);
-                            for (group, device_name) in &groups {
-                                if *device_name == saved_link.output_node_name {
-                                    let ports = graph.get_ports_for_bridge_group(n.id, group);
-                                    if let Some(p) = ports.iter().find(|p| {
-                                        p.name == saved_link.output_port_name
-                                            && p.direction == PortDirection::Output
-                                    }) {
-                                        out_port_id = Some(p.id);
-                                        break;
-                                    }
-                                }
-                            }
-                            if out_port_id.is_some() { break; }
-                        }
-                    }
-
-                    let mut in_port_id = None;
-                    for n in all_nodes
-                        .iter()
-                        .filter(|n| n.display_name() == saved_link.input_node_name)
-                    {
-                        let ports = graph.get_ports_for_node(n.id);
-                        if let Some(p) = ports.iter().find(|p| {
-                            p.name == saved_link.input_port_name
-                                && p.direction == PortDirection::Input
-                        }) {
-                            in_port_id = Some(p.id);
-                            break;
-                        }
-                    }
-                    // If not found, check bridge sub-nodes by device name
-                    if in_port_id.is_none() {
-                        for n in all_nodes.iter().filter(|n| n.is_bridge) {
-                            let groups = graph.get_bridge_port_groups(n.id);
-                            for (group, device_name) in &groups {
-                                if *device_name == saved_link.input_node_name {
-                                    let ports = graph.get_ports_for_bridge_group(n.id, group);
-                                    if let Some(p) = ports.iter().find(|p| {
-                                        p.name == saved_link.input_port_name
-                                            && p.direction == PortDirection::Input
-                                    }) {
-                                        in_port_id = Some(p.id);
-                                        break;
-                                    }
-                                }
-                            }
-                            if in_port_id.is_some() { break; }
-                        }
-                    }
+                    let out_port_id = find_saved_link_port(
+                        graph,
+                        &saved_ids,
+                        saved_link.output_node_identity.as_deref(),
+                        &saved_link.output_node_name,
+                        &saved_link.output_port_name,
+                        PortDirection::Output,
+                    );
+                    let in_port_id = find_saved_link_port(
+                        graph,
+                        &saved_ids,
+                        saved_link.input_node_identity.as_deref(),
+                        &saved_link.input_node_name,
+                        &saved_link.input_port_name,
+                        PortDirection::Input,
+                    );
 
                     if let (Some(out_id), Some(in_id)) = (out_port_id, in_port_id) {
                         log::info!(
@@ -1102,12 +2323,13 @@ impl qobject::AppController {
                             saved_link.input_node_name,
                             saved_link.input_port_name
                         );
-                        if let Some(ref tx) = self.rust().cmd_tx {
-                            let _ = tx.send(PwCommand::Connect {
+                        self.send_command(
+                            CommandOrigin::Restore,
+                            PwCommand::Connect {
                                 output_port_id: out_id,
                                 input_port_id: in_id,
-                            });
-                        }
+                            },
+                        );
                     } else {
                         log::warn!(
                             "Could not find ports for saved link: {}:{} -> {}:{}",
@@ -1121,9 +2343,19 @@ impl qobject::AppController {
             }
         }
 
+        self.as_mut().wire_pending_chain_mixes();
+        self.as_mut().wire_pending_chain_inserts();
+        self.as_mut().wire_pending_suppressors();
+        self.as_mut().expire_temporary_routes();
+        self.as_mut().end_orphaned_audition();
+        self.as_mut().advance_probe();
+        self.as_mut().advance_output_crossfade(false);
+        self.as_mut().advance_scene_transition();
+
         let links_persist_ms = self.rust().prefs.links_persist_ms;
         let should_persist_links = {
             self.rust().links_dirty
+                && !self.rust().storm_guard.is_tripped()
                 && self
                     .rust()
                     .links_dirty_since
@@ -1133,7 +2365,11 @@ impl qobject::AppController {
         if should_persist_links {
             self.as_mut().rust_mut().links_dirty = false;
             self.as_mut().rust_mut().links_dirty_since = None;
-            persist_lv2_links(self.rust().graph.as_ref());
+            persist_lv2_links(
+                self.rust().graph.as_ref(),
+                &self.rust().dormant_links,
+                &self.rust().node_ids,
+            );
         }
 
         let tray_state = self.rust().tray_state.clone();
@@ -1160,13 +2396,123 @@ impl qobject::AppController {
                     self.as_mut().open_plugin_ui(node_id);
                 }
             }
+            if tray.show_all_uis_requested.swap(false, Ordering::AcqRel) {
+                self.as_mut().show_all_plugin_uis();
+            }
+            if tray.hide_all_uis_requested.swap(false, Ordering::AcqRel) {
+                self.as_mut().hide_all_plugin_uis();
+            }
+            let profile = tray
+                .profile_requested
+                .lock()
+                .ok()
+                .and_then(|mut req| req.take());
+            if let Some(profile) = profile {
+                self.as_mut().set_preference(
+                    QString::from("performance_profile"),
+                    QString::from(profile.as_str()),
+                );
+            }
+            if tray.unavailable.swap(false, Ordering::AcqRel)
+                && self.rust().prefs.tray_mode == "auto"
+            {
+                let backend = TrayBackend::fallback();
+                log::info!("Tray: no StatusNotifier host, using {}", backend.as_str());
+                self.as_mut().set_tray_backend(QString::from(backend.as_str()));
+            }
+        }
+
+        let on_battery = self.rust().power.as_ref().is_some_and(|p| p.on_battery());
+        if self
+            .as_mut()
+            .rust_mut()
+            .profile_chooser
+            .set_on_battery(on_battery)
+        {
+            self.as_mut().apply_profile();
+        }
+
+        let activation = self.rust().portal.as_ref().and_then(|p| p.take_activation());
+        if let Some(token) = activation {
+            log::info!("Portal: activation requested — emitting signal to QML");
+            if let Some(token) = token.filter(|_| crate::portal::is_wayland_session()) {
+                // Qt's Wayland backend reads the token from the environment
+                // the next time the window asks to be activated.
+                unsafe { std::env::set_var("XDG_ACTIVATION_TOKEN", token) };
+            }
+            if let Some(ref tray) = self.rust().tray_state {
+                tray.window_visible.store(true, std::sync::atomic::Ordering::Release);
+            }
+            self.as_mut().show_window_requested();
+        }
+
+        if let Some(ref jack) = self.rust().jack_dbus {
+            let requests =
+                std::mem::take(&mut *jack.requests.lock().unwrap_or_else(|e| e.into_inner()));
+            for request in requests {
+                log::info!("JACK patchbay: {:?}", request);
+                let cmd = match request {
+                    crate::jack_dbus::PatchbayRequest::Connect {
+                        output_port_id,
+                        input_port_id,
+                    } => PwCommand::Connect {
+                        output_port_id,
+                        input_port_id,
+                    },
+                    crate::jack_dbus::PatchbayRequest::Disconnect { link_id } => {
+                        PwCommand::Disconnect { link_id }
+                    }
+                };
+                self.send_command(CommandOrigin::Remote, cmd);
+            }
+        }
+
+        let control_requests = self.rust().patchbay_dbus.as_ref().map(|dbus| {
+            std::mem::take(&mut *dbus.requests.lock().unwrap_or_else(|e| e.into_inner()))
+        });
+        for request in control_requests.unwrap_or_default() {
+            self.as_mut().run_control_request(request);
+        }
+
+        let update = self
+            .rust()
+            .update_rx
+            .as_ref()
+            .and_then(|rx| rx.try_recv().ok());
+        if let Some(result) = update {
+            self.as_mut().finish_update_check(result);
+        }
+
+        let mqtt_commands = self.rust().mqtt.as_ref().map(|mqtt| {
+            std::mem::take(&mut *mqtt.commands.lock().unwrap_or_else(|e| e.into_inner()))
+        });
+        for command in mqtt_commands.unwrap_or_default() {
+            self.as_mut().run_mqtt_command(command);
+        }
+
+        let remote_requests = self.rust().remote_api.as_ref().map(|remote| {
+            std::mem::take(&mut *remote.requests.lock().unwrap_or_else(|e| e.into_inner()))
+        });
+        for request in remote_requests.unwrap_or_default() {
+            self.as_mut().run_remote_request(request);
         }
 
         if let Some(msg) = error_msg {
+            if let Some(ref remote) = self.rust().remote_api {
+                remote.emit("error", serde_json::json!({ "message": msg }));
+            }
             let qmsg = QString::from(&msg);
             self.as_mut().error_occurred(qmsg);
         }
 
+        if docked_changed {
+            self.as_mut().docked_uis_changed();
+        }
+
+        for node_id in params_changed {
+            self.as_mut().parameters_changed(node_id);
+        }
+
         const RESTORE_TIMEOUT_SECS: u64 = 30;
         if self.rust().pending_restore_count > 0 {
             if let Some(started) = self.rust().restore_started_at {
@@ -1181,14 +2527,31 @@ impl qobject::AppController {
                     self.as_mut().rust_mut().pending_restore_count = 0;
                     self.as_mut().rust_mut().restore_started_at = None;
                     remove_crash_marker();
-                    persist_active_plugins(self.rust().plugin_manager.as_ref());
+                    persist_active_plugins(self.rust());
                 }
             }
         }
 
         if changed {
+            self.as_mut().maintain_sidechains();
+            self.as_mut().maintain_conversion_policies();
+            self.as_mut().maintain_clock_routing();
+            self.as_mut().maintain_midi_link_filters();
+            self.as_mut().maintain_link_gains();
+            let known: std::collections::HashSet<u32> =
+                self.rust().cached_nodes.iter().map(|n| n.id).collect();
             self.as_mut().refresh_cache();
+            // The first fill of the cache is the startup graph, not new
+            // devices.
+            if !known.is_empty() {
+                self.fire_device_hooks(&known);
+            }
             self.as_mut().sync_tray_plugins();
+            self.sync_jack_patchbay();
+            self.sync_patchbay_dbus();
+            self.sync_mqtt();
+            self.as_mut().sync_remote_api();
+            self.as_mut().update_recording_active();
             self.as_mut().graph_changed();
         }
 
@@ -1207,6 +2570,337 @@ impl qobject::AppController {
             h.push(avg);
         }
         self.as_mut().set_cpu_usage(QString::from(&cpu_str));
+
+        self.as_mut().check_pipewire_heartbeat();
+        self.as_mut().sample_plugin_cpu();
+        self.as_mut().sync_tray_status();
+
+        let settle = Duration::from_millis(self.rust().prefs.rule_settle_ms);
+        let compare_changed = match self.as_mut().rust_mut().compare.as_mut() {
+            Some(compare) => compare.poll(settle),
+            None => false,
+        };
+        if compare_changed {
+            self.as_mut().compare_changed();
+        }
+
+        if self
+            .rust()
+            .remote_instance
+            .as_ref()
+            .is_some_and(|r| r.poll())
+        {
+            self.as_mut().remote_instance_changed();
+        }
+    }
+
+    fn update_recording_active(mut self: Pin<&mut Self>) {
+        let streams = self
+            .rust()
+            .graph
+            .as_ref()
+            .map(|graph| active_streams(&graph.get_all_nodes(), &graph.get_all_links()).1)
+            .unwrap_or_default();
+        let recording = !streams.is_empty();
+        if recording && !self.rust().recording_active {
+            crate::hooks::fire(
+                &self.rust().hooks,
+                HookEvent::RecordingStarted,
+                serde_json::json!({ "streams": streams }),
+            );
+        }
+        self.as_mut().rust_mut().recording_active = recording;
+    }
+
+    /// Run the device hooks for sinks, sources and duplex devices not in
+    /// `known`.
+    fn fire_device_hooks(&self, known: &std::collections::HashSet<u32>) {
+        if self.rust().hooks.is_empty() {
+            return;
+        }
+        let devices = self.rust().cached_nodes.iter().filter(|n| {
+            !known.contains(&n.id)
+                && matches!(
+                    n.node_type,
+                    Some(NodeType::Sink | NodeType::Source | NodeType::Duplex)
+                )
+        });
+        for node in devices {
+            crate::hooks::fire(
+                &self.rust().hooks,
+                HookEvent::DeviceConnected,
+                serde_json::json!({
+                    "node_id": node.id,
+                    "name": node.name,
+                    "description": node.display_name(),
+                    "node_type": node.node_type.map(rules::node_type_label),
+                    "media_type": node.media_type.map(|m| format!("{:?}", m)),
+                    "device_serial": node.device_serial,
+                }),
+            );
+        }
+    }
+
+    /// Start or stop the comparison graph to match the preference.
+    fn restart_compare(mut self: Pin<&mut Self>) {
+        let remote = self.rust().prefs.compare_remote.trim().to_string();
+        if self.rust().compare.as_ref().map_or("", |c| c.remote()) == remote {
+            return;
+        }
+        self.as_mut().rust_mut().compare = None;
+        if !remote.is_empty() {
+            let key = remote.replace(|c: char| !c.is_ascii_alphanumeric() && c != '-', "_");
+            let prefs = &self.rust().prefs;
+            let compare = crate::ui::compare::CompareGraph::start(
+                &remote,
+                config_path(&format!("rules-{}.json", key)),
+                prefs.pw_tick_interval_ms,
+                prefs.pw_operation_cooldown_ms,
+                prefs.pw_connect_retries,
+            );
+            self.as_mut().rust_mut().compare = Some(compare);
+        }
+        self.as_mut().compare_changed();
+    }
+
+    /// Connect to the remote instance in the preferences, or drop the
+    /// connection when there is none.
+    fn restart_remote_instance(mut self: Pin<&mut Self>) {
+        let prefs = &self.rust().prefs;
+        let address = prefs.remote_instance.trim().to_string();
+        let token = prefs.remote_instance_token.clone();
+        let cert = prefs.remote_instance_cert.clone();
+        if self
+            .rust()
+            .remote_instance
+            .as_ref()
+            .is_some_and(|r| r.address() == address && r.token() == token && r.cert() == cert)
+        {
+            return;
+        }
+        self.as_mut().rust_mut().remote_instance = None;
+        if !address.is_empty() {
+            let remote = crate::ui::remote_client::RemoteInstance::start(&address, &token, &cert);
+            self.as_mut().rust_mut().remote_instance = Some(remote);
+        }
+        self.as_mut().remote_instance_changed();
+    }
+
+    /// Warn when the PipeWire thread stops ticking, and clear the warning if
+    /// it comes back by itself.
+    fn check_pipewire_heartbeat(mut self: Pin<&mut Self>) {
+        let count = self.rust().pw_heartbeat.count();
+        let now = Instant::now();
+        match self.as_mut().rust_mut().pw_watch.check(count, now) {
+            Some(StallChange::Stalled) => {
+                log::error!(
+                    "PipeWire thread has not responded for {:?}; the graph is no longer updated",
+                    self.rust().pw_watch.silent_for(now)
+                );
+                self.as_mut().set_pipewire_stalled(true);
+                self.as_mut()
+                    .set_pipewire_status(QString::from("PipeWire thread not responding"));
+                self.as_mut().rust_mut().plugin_fault =
+                    Some("PipeWire thread not responding".to_string());
+            }
+            Some(StallChange::Recovered) => {
+                log::warn!("PipeWire thread is responding again");
+                self.as_mut().set_pipewire_stalled(false);
+                self.as_mut().set_pipewire_status(QString::default());
+                self.as_mut().rust_mut().plugin_fault = None;
+            }
+            None => {}
+        }
+    }
+
+    /// Abandon a PipeWire thread that stopped responding and start a fresh
+    /// one on a new graph. Running plugins are re-created with their current
+    /// parameters and their links restored, as at startup. The old thread
+    /// cannot be killed; it is told to shut down should it ever wake up.
+    pub fn restart_pipewire(mut self: Pin<&mut Self>) {
+        log::warn!("Restarting the PipeWire thread");
+        persist_lv2_links(
+            self.rust().graph.as_ref(),
+            &self.rust().dormant_links,
+            &self.rust().node_ids,
+        );
+        persist_active_plugins(self.rust());
+        self.log_command(CommandOrigin::System, &PwCommand::Shutdown);
+        if let Some(tx) = self.as_mut().rust_mut().cmd_tx.take() {
+            let _ = tx.send(PwCommand::Shutdown);
+        }
+        self.as_mut().rust_mut().event_rx = None;
+
+        let graph = GraphState::new();
+        graph.set_channel_overrides(self.rust().channel_overrides.clone());
+        let heartbeat = Heartbeat::default();
+        let (event_rx, cmd_tx) = self.start_pipewire(graph.clone(), heartbeat.clone());
+        let rule_changes = graph.subscribe(PatchbayManager::graph_filter());
+        let link_changes = graph.subscribe(link_filter());
+        let view_changes = graph.subscribe(view_filter());
+        {
+            let mut rust = self.as_mut().rust_mut();
+            rust.rule_changes = Some(rule_changes);
+            rust.link_changes = Some(link_changes);
+            rust.view_changes = Some(view_changes);
+            rust.graph = Some(graph);
+            rust.event_rx = Some(event_rx);
+            rust.cmd_tx = Some(cmd_tx);
+            rust.pw_heartbeat = heartbeat;
+            rust.pw_watch.reset(Instant::now());
+            rust.plugin_fault = None;
+            rust.plugin_ui_windows.clear();
+        }
+        self.as_mut().set_pipewire_stalled(false);
+        self.as_mut().set_pipewire_status(QString::default());
+
+        let rust = self.rust();
+        let running: Vec<SavedPlugin> = rust
+            .plugin_manager
+            .as_ref()
+            .map(|mgr| {
+                mgr.active_instances()
+                    .values()
+                    .map(|info| {
+                        let quantum = rust.plugin_quanta.get(&info.stable_id).copied();
+                        saved_plugin(info, false, quantum)
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        if let Some(ref mut mgr) = self.as_mut().rust_mut().plugin_manager {
+            for sp in &running {
+                if let Some(id) = mgr.instance_id_for_stable_id(&sp.stable_id) {
+                    mgr.remove_instance(id);
+                }
+            }
+        }
+        let dormant: Vec<String> = self
+            .rust()
+            .dormant_plugins
+            .iter()
+            .map(|sp| sp.display_name.clone())
+            .collect();
+        crate::control::flush();
+        self.as_mut().rust_mut().pending_links = load_saved_links()
+            .into_iter()
+            .filter(|l| !dormant.iter().any(|name| l.touches(name)))
+            .collect();
+        log::info!("Re-creating {} plugins on the new thread", running.len());
+        for sp in running {
+            self.as_mut().start_saved_plugin(sp);
+        }
+        for mapping in load_midi_mappings() {
+            self.send_command(CommandOrigin::Restore, PwCommand::AddMidiMapping(mapping));
+        }
+        self.as_mut().graph_changed();
+    }
+
+    /// Record per-plugin CPU snapshots into the history and report plugins
+    /// that stay over the alert threshold.
+    fn sample_plugin_cpu(mut self: Pin<&mut Self>) {
+        const INTERVAL: Duration = Duration::from_millis(500);
+
+        if self
+            .rust()
+            .plugin_cpu_sampled
+            .is_some_and(|t| t.elapsed() < INTERVAL)
+        {
+            return;
+        }
+        let snapshots = crate::plugin::cpu_stats::global_cpu_tracker().take_all_snapshots();
+        let now_ms = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+        let percent = self.rust().prefs.cpu_alert_percent as f32;
+        let seconds = self.rust().prefs.cpu_alert_seconds;
+
+        let mut alerts = Vec::new();
+        {
+            let mut pinned = self.as_mut().rust_mut();
+            let rust = &mut *pinned;
+            rust.plugin_cpu_sampled = Some(Instant::now());
+            for (id, name, snap) in &snapshots {
+                let Some(stable_id) = rust
+                    .plugin_manager
+                    .as_ref()
+                    .and_then(|mgr| mgr.get_instance(*id))
+                    .map(|info| info.stable_id.clone())
+                else {
+                    continue;
+                };
+                let history = &mut rust.plugin_cpu_history;
+                history.record(&stable_id, snap.sample(now_ms));
+                if rust
+                    .plugin_cpu_alerts
+                    .update(history, &stable_id, percent, seconds * 1000)
+                {
+                    alerts.push((name.clone(), snap.dsp_percent));
+                }
+            }
+            rust.plugin_cpu = snapshots;
+        }
+
+        for (name, dsp) in alerts {
+            log::warn!(
+                "{} has been over {}% DSP load for {} s (now {:.0}%)",
+                name,
+                percent,
+                seconds,
+                dsp
+            );
+            self.as_mut().error_occurred(QString::from(&format!(
+                "{} has used over {}% of the audio buffer time for {} seconds (now {:.0}%). \
+                 It may cause dropouts; consider a larger quantum or removing it.",
+                name, percent, seconds, dsp
+            )));
+        }
+    }
+
+    /// Reflect rules, plugin faults, recording and overruns on the tray icon.
+    fn sync_tray_status(mut self: Pin<&mut Self>) {
+        const WINDOW: Duration = Duration::from_secs(60);
+
+        let overruns = crate::plugin::cpu_stats::global_cpu_tracker().total_overruns();
+        let new_overruns = overruns.saturating_sub(self.rust().prev_overruns);
+        self.as_mut().rust_mut().prev_overruns = overruns;
+        let now = Instant::now();
+        let recent = {
+            let samples = &mut self.as_mut().rust_mut().overrun_samples;
+            if new_overruns > 0 {
+                samples.push_back((now, new_overruns));
+            }
+            while samples
+                .front()
+                .is_some_and(|(t, _)| now.duration_since(*t) > WINDOW)
+            {
+                samples.pop_front();
+            }
+            samples.iter().map(|(_, n)| n).sum::<u64>()
+        };
+
+        let threshold = self.rust().prefs.xrun_warning_threshold;
+        let storm = threshold > 0 && recent >= threshold;
+        if storm && !self.rust().xrun_storm {
+            crate::hooks::fire(
+                &self.rust().hooks,
+                HookEvent::XrunStorm,
+                serde_json::json!({ "xruns_per_minute": recent, "threshold": threshold }),
+            );
+        }
+        self.as_mut().rust_mut().xrun_storm = storm;
+
+        let Some(ref tray) = self.rust().tray_state else {
+            return;
+        };
+        tray.set_status(crate::tray::TrayStatus {
+            rules_disabled: !self.rust().patchbay_enabled,
+            plugin_fault: self.rust().plugin_fault.clone(),
+            recording: self.rust().recording_active,
+            xruns_per_minute: storm.then_some(recent),
+            profile: self.active_profile(),
+        });
     }
 
     fn refresh_cache(mut self: Pin<&mut Self>) {
@@ -1225,6 +2919,16 @@ impl qobject::AppController {
         self.as_mut().rust_mut().cached_nodes = nodes;
     }
 
+    pub fn refresh_graph(self: Pin<&mut Self>) {
+        log::info!("Refresh from PipeWire requested");
+        self.send_command(CommandOrigin::User, PwCommand::RefreshGraph);
+    }
+
+    pub fn resync_graph(self: Pin<&mut Self>) {
+        log::info!("Full graph resync requested");
+        self.send_command(CommandOrigin::User, PwCommand::ResyncGraph);
+    }
+
     pub fn get_nodes_json(mut self: Pin<&mut Self>) -> QString {
         if let Some(graph) = self.rust().graph.clone() {
             let nodes = graph.get_all_nodes();
@@ -1234,18 +2938,58 @@ impl qobject::AppController {
                 nodes.iter().filter(|n| n.ready).count()
             );
 
+            self.as_mut().refresh_node_keys(&nodes);
+
             // Rebuild bridge split state each refresh
             self.as_mut().rust_mut().bridge_split.clear();
 
             let mut json_nodes: Vec<serde_json::Value> = Vec::new();
 
+            // Group streams of the same application. Collapsed groups stand in
+            // for their members: member ports are registered against the group
+            // ID so links are drawn to the group node.
+            let app_groups = self.as_mut().rust_mut().app_groups.rebuild(&nodes);
+            let mut parent_of: HashMap<u32, u32> = HashMap::new();
+            let mut collapsed: std::collections::HashSet<u32> = std::collections::HashSet::new();
+            for group in &app_groups {
+                let expanded = self.rust().app_groups.is_expanded(group.id);
+                for &member in &group.members {
+                    parent_of.insert(member, group.id);
+                    if !expanded {
+                        collapsed.insert(member);
+                        for port in graph.get_ports_for_node(member) {
+                            self.as_mut().rust_mut().bridge_split
+                                .register_port(port.id, group.id);
+                        }
+                    }
+                }
+                if expanded {
+                    continue;
+                }
+                let media_type = nodes
+                    .iter()
+                    .find(|n| n.id == group.members[0])
+                    .and_then(|n| n.media_type);
+                json_nodes.push(serde_json::json!({
+                    "id": group.id,
+                    "name": format!("{} ({})", group.name, group.members.len()),
+                    "type": NodeKind::from(Some(group.node_type)).repr,
+                    "mediaType": MediaKind::from(media_type).repr,
+                    "isVirtual": false,
+                    "isJack": false,
+                    "layoutKey": group.layout_key(),
+                    "ready": true,
+                    "isGroup": true,
+                    "childCount": group.members.len(),
+                }));
+            }
+
             for n in nodes.iter().filter(|n| n.ready) {
-                let media_str = match n.media_type {
-                    Some(crate::pipewire::MediaType::Audio) => "Audio",
-                    Some(crate::pipewire::MediaType::Video) => "Video",
-                    Some(crate::pipewire::MediaType::Midi) => "Midi",
-                    None => "Unknown",
-                };
+                if collapsed.contains(&n.id) || is_link_node(&n.name) {
+                    continue;
+                }
+
+                let media_kind = MediaKind::from(n.media_type).repr;
 
                 // Split bridge nodes into per-device sub-nodes
                 if n.is_bridge {
@@ -1253,7 +2997,8 @@ impl qobject::AppController {
                     if groups.is_empty() {
                         // No ports with groups yet — show the bridge as-is
                         let mgr = self.rust().plugin_manager.as_ref();
-                        json_nodes.push(node_to_json(n, mgr));
+                        let format = graph.get_node_format(n.id);
+                        json_nodes.push(node_to_json(n, mgr, &self.rust().node_keys, format));
                     } else {
                         for (group, device_name) in &groups {
                             let vid = self.as_mut().rust_mut().bridge_split
@@ -1269,21 +3014,21 @@ impl qobject::AppController {
                             // Determine sub-node type based on port directions
                             let has_inputs = group_ports.iter().any(|p| p.direction == PortDirection::Input);
                             let has_outputs = group_ports.iter().any(|p| p.direction == PortDirection::Output);
-                            let type_str = if has_inputs && has_outputs {
-                                "Duplex"
+                            let kind = if has_inputs && has_outputs {
+                                NodeKind::Duplex
                             } else if has_outputs {
-                                "Source"
+                                NodeKind::Source
                             } else if has_inputs {
-                                "Sink"
+                                NodeKind::Sink
                             } else {
-                                "Duplex"
+                                NodeKind::Duplex
                             };
 
                             json_nodes.push(serde_json::json!({
                                 "id": vid,
                                 "name": device_name,
-                                "type": type_str,
-                                "mediaType": media_str,
+                                "type": kind.repr,
+                                "mediaType": media_kind,
                                 "isVirtual": n.is_virtual,
                                 "isJack": n.is_jack,
                                 "layoutKey": format!("MidiBridge:{}", device_name),
@@ -1293,10 +3038,28 @@ impl qobject::AppController {
                     }
                 } else {
                     let mgr = self.rust().plugin_manager.as_ref();
-                    json_nodes.push(node_to_json(n, mgr));
+                    let format = graph.get_node_format(n.id);
+                    let mut val = node_to_json(n, mgr, &self.rust().node_keys, format);
+                    if let Some(&parent_id) = parent_of.get(&n.id) {
+                        val["parentId"] = serde_json::json!(parent_id);
+                    }
+                    if let Some(state) = self
+                        .rust()
+                        .patchbay
+                        .as_ref()
+                        .and_then(|pb| rule_state(pb, n))
+                    {
+                        val["ruleState"] = serde_json::json!(state);
+                    }
+                    json_nodes.push(val);
                 }
             }
 
+            let order = self.rust().prefs.node_order();
+            json_nodes.sort_by(|a, b| {
+                order.compare(&node_sort_key(a, &graph), &node_sort_key(b, &graph))
+            });
+
             let json = serde_json::to_string(&json_nodes).unwrap_or_default();
             QString::from(&json)
         } else {
@@ -1306,10 +3069,11 @@ impl qobject::AppController {
 
     pub fn get_links_json(self: Pin<&mut Self>) -> QString {
         if let Some(ref graph) = self.rust().graph {
-            let links = graph.get_all_links();
+            let links = self.logical_links(graph);
             let json_links: Vec<serde_json::Value> = links
                 .iter()
-                .map(|l| {
+                .map(|logical| {
+                    let l = &logical.link;
                     // Rewrite node IDs for ports belonging to bridge sub-nodes
                     let out_node = self.rust().bridge_split
                         .resolve_port_virtual_node(l.output_port_id)
@@ -1317,6 +3081,15 @@ impl qobject::AppController {
                     let in_node = self.rust().bridge_split
                         .resolve_port_virtual_node(l.input_port_id)
                         .unwrap_or(l.input_node_id);
+                    let sidechain = graph
+                        .get_port(l.input_port_id)
+                        .is_some_and(|p| is_sidechain_port(&p));
+                    let conversion = link_conversion(graph, l);
+                    let rule = self
+                        .rust()
+                        .patchbay
+                        .as_ref()
+                        .and_then(|pb| link_rule(pb, graph, l));
                     serde_json::json!({
                         "id": l.id,
                         "outputNodeId": out_node,
@@ -1324,6 +3097,13 @@ impl qobject::AppController {
                         "inputNodeId": in_node,
                         "inputPortId": l.input_port_id,
                         "active": l.active,
+                        "sidechain": sidechain,
+                        "resampled": conversion.as_ref().is_some_and(|c| c.resampled),
+                        "conversion": conversion,
+                        "ruleId": rule.as_ref().map(|(id, _)| id),
+                        "ruleEnabled": rule.as_ref().map(|(_, enabled)| enabled),
+                        "midiFilter": logical.midi_filter,
+                        "gainDb": logical.gain_db,
                     })
                 })
                 .collect();
@@ -1337,6 +3117,26 @@ impl qobject::AppController {
     pub fn get_ports_json(self: Pin<&mut Self>, node_id: u32) -> QString {
         log::debug!("get_ports_json: node_id={}", node_id);
         if let Some(ref graph) = self.rust().graph {
+            // Collapsed application group: expose every member's ports,
+            // numbered by stream so identical channel names stay distinct.
+            if let Some(members) = self.rust().app_groups.members(node_id) {
+                let mut json_ports: Vec<serde_json::Value> = Vec::new();
+                for (idx, member) in members.iter().enumerate() {
+                    for p in graph.get_ports_for_node(*member) {
+                        let media_kind = MediaKind::from(p.media_type).repr;
+                        json_ports.push(serde_json::json!({
+                            "id": p.id,
+                            "name": format!("{} #{}", p.display_name(), idx + 1),
+                            "direction": format!("{:?}", p.direction),
+                            "nodeId": node_id,
+                            "mediaType": media_kind,
+                        }));
+                    }
+                }
+                let json = serde_json::to_string(&json_ports).unwrap_or_default();
+                return QString::from(&json);
+            }
+
             // Check if this is a virtual bridge sub-node ID
             let ports = if let Some((real_node_id, group)) =
                 self.rust().bridge_split.resolve_virtual_node(node_id).cloned()
@@ -1346,15 +3146,17 @@ impl qobject::AppController {
                 graph.get_ports_for_node(node_id)
             };
 
+            let pair_node_id = self
+                .rust()
+                .bridge_split
+                .resolve_virtual_node(node_id)
+                .map_or(node_id, |(real_node_id, _)| *real_node_id);
+            let stereo_pairs = self.port_pairs(pair_node_id, &ports);
+
             let json_ports: Vec<serde_json::Value> = ports
                 .iter()
                 .map(|p| {
-                    let media_str = match p.media_type {
-                        Some(crate::pipewire::MediaType::Audio) => "Audio",
-                        Some(crate::pipewire::MediaType::Video) => "Video",
-                        Some(crate::pipewire::MediaType::Midi) => "Midi",
-                        None => "Unknown",
-                    };
+                    let media_kind = MediaKind::from(p.media_type).repr;
                     // For bridge sub-node ports, use a cleaner display name
                     // from port.alias (the part after the colon) or fall back to default
                     let display_name = if self.rust().bridge_split.is_virtual_id(node_id) {
@@ -1375,7 +3177,11 @@ impl qobject::AppController {
                         "name": display_name,
                         "direction": format!("{:?}", p.direction),
                         "nodeId": node_id,
-                        "mediaType": media_str,
+                        "mediaType": media_kind,
+                        "sidechain": is_sidechain_port(p),
+                        "pairPortId": pairs::partner(&stereo_pairs, p.id),
+                        "channel": p.channel,
+                        "reportedChannel": graph.reported_channel(p.id),
                     })
                 })
                 .collect();
@@ -1386,10 +3192,277 @@ impl qobject::AppController {
         }
     }
 
+    /// The stereo pairs among `ports` of `node_id`, with the device's
+    /// overrides applied.
+    fn port_pairs(&self, node_id: u32, ports: &[Port]) -> Vec<pairs::StereoPair> {
+        let overrides = self
+            .rust()
+            .graph
+            .as_ref()
+            .and_then(|graph| graph.get_node(node_id))
+            .and_then(|node| self.rust().stereo_pairs.get(&self.layout_key_of(&node)));
+        pairs::stereo_pairs(ports, overrides)
+    }
+
+    fn layout_key_of(&self, node: &Node) -> String {
+        layout_key(
+            node,
+            self.rust().plugin_manager.as_ref(),
+            &self.rust().node_keys,
+        )
+    }
+
+    /// The port `port_id` is paired with, if any.
+    fn port_partner(&self, port_id: u32) -> Option<Port> {
+        let graph = self.rust().graph.as_ref()?;
+        let port = graph.get_port(port_id)?;
+        let ports = graph.get_ports_for_node(port.node_id);
+        let partner = pairs::partner(&self.port_pairs(port.node_id, &ports), port_id)?;
+        ports.into_iter().find(|p| p.id == partner)
+    }
+
+    pub fn set_app_group_expanded(mut self: Pin<&mut Self>, group_id: u32, expanded: bool) {
+        if self.rust().app_groups.members(group_id).is_none() {
+            log::warn!("set_app_group_expanded: unknown group {}", group_id);
+            return;
+        }
+        self.as_mut()
+            .rust_mut()
+            .app_groups
+            .set_expanded(group_id, expanded);
+        self.as_mut().graph_changed();
+    }
+
+    /// Connect two ports and, when both are one side of a stereo pair, their
+    /// partners as well.
+    pub fn connect_port_pair(mut self: Pin<&mut Self>, output_port_id: u32, input_port_id: u32) {
+        let partners = self
+            .port_partner(output_port_id)
+            .zip(self.port_partner(input_port_id));
+        let mut loop_path = self.feedback_check(output_port_id, input_port_id);
+        if loop_path.is_none()
+            && let Some((ref output, ref input)) = partners
+        {
+            loop_path = self.feedback_check(output.id, input.id);
+        }
+        if let Some(path) = loop_path {
+            self.as_mut().feedback_loop_blocked(
+                output_port_id,
+                input_port_id,
+                true,
+                QString::from(path.as_str()),
+            );
+            return;
+        }
+        self.as_mut()
+            .connect_user_ports(output_port_id, input_port_id, false);
+        if let Some((output, input)) = partners {
+            self.as_mut().connect_user_ports(output.id, input.id, false);
+        }
+    }
+
     pub fn connect_ports(mut self: Pin<&mut Self>, output_port_id: u32, input_port_id: u32) {
-        // Reject self-loops: don't connect a node's output to its own input
-        // For bridge nodes, allow cross-device connections (different port groups)
-        if let Some(ref graph) = self.rust().graph {
+        if let Some(path) = self.feedback_check(output_port_id, input_port_id) {
+            self.as_mut().feedback_loop_blocked(
+                output_port_id,
+                input_port_id,
+                false,
+                QString::from(path.as_str()),
+            );
+            return;
+        }
+        self.connect_user_ports(output_port_id, input_port_id, false);
+    }
+
+    /// Make a connection held back as a feedback loop, after the user said
+    /// to go ahead.
+    pub fn connect_ports_anyway(
+        mut self: Pin<&mut Self>,
+        output_port_id: u32,
+        input_port_id: u32,
+        pair: bool,
+    ) {
+        log::warn!(
+            "Connecting {} -> {} despite the feedback loop it closes",
+            output_port_id,
+            input_port_id
+        );
+        let partners = if pair {
+            self.port_partner(output_port_id)
+                .zip(self.port_partner(input_port_id))
+        } else {
+            None
+        };
+        self.as_mut()
+            .connect_user_ports(output_port_id, input_port_id, true);
+        if let Some((output, input)) = partners {
+            self.as_mut().connect_user_ports(output.id, input.id, true);
+        }
+    }
+
+    /// The loop, as "A → B → A", when a link from `output_port_id` to
+    /// `input_port_id` would close one, to ask the user before connecting.
+    fn feedback_check(&self, output_port_id: u32, input_port_id: u32) -> Option<String> {
+        let graph = self.rust().graph.as_ref()?;
+        let found = graph.closed_feedback_loop(&PwCommand::Connect {
+            output_port_id,
+            input_port_id,
+        })?;
+        Some(graph.feedback_loop_text(&found.nodes))
+    }
+
+    /// Pair two ports of the same device by hand, whatever was detected.
+    pub fn set_port_pair(mut self: Pin<&mut Self>, left_port_id: u32, right_port_id: u32) -> bool {
+        let Some(graph) = self.rust().graph.clone() else {
+            return false;
+        };
+        let (Some(left), Some(right)) =
+            (graph.get_port(left_port_id), graph.get_port(right_port_id))
+        else {
+            return false;
+        };
+        if left.id == right.id
+            || left.node_id != right.node_id
+            || left.direction != right.direction
+            || left.port_group != right.port_group
+        {
+            log::warn!(
+                "set_port_pair: ports {} and {} are not on the same side of one device",
+                left_port_id,
+                right_port_id
+            );
+            return false;
+        }
+        let Some(node) = graph.get_node(left.node_id) else {
+            return false;
+        };
+        let key = self.layout_key_of(&node);
+        self.as_mut()
+            .rust_mut()
+            .stereo_pairs
+            .entry(key)
+            .or_default()
+            .pair(&left.name, &right.name);
+        save_stereo_pairs(&self.rust().stereo_pairs);
+        self.as_mut().graph_changed();
+        true
+    }
+
+    /// Split the stereo pair `port_id` is in, so both sides connect alone.
+    pub fn unpair_port(mut self: Pin<&mut Self>, port_id: u32) -> bool {
+        let Some(partner) = self.port_partner(port_id) else {
+            return false;
+        };
+        let Some(graph) = self.rust().graph.clone() else {
+            return false;
+        };
+        let (Some(port), Some(node)) = (graph.get_port(port_id), graph.get_node(partner.node_id))
+        else {
+            return false;
+        };
+        let key = self.layout_key_of(&node);
+        self.as_mut()
+            .rust_mut()
+            .stereo_pairs
+            .entry(key)
+            .or_default()
+            .split(&port.name, Some(&partner.name));
+        save_stereo_pairs(&self.rust().stereo_pairs);
+        self.as_mut().graph_changed();
+        true
+    }
+
+    /// Forget the pair overrides of a device and go back to detected pairs.
+    pub fn reset_port_pairs(mut self: Pin<&mut Self>, node_id: u32) {
+        let node_id = self
+            .rust()
+            .bridge_split
+            .resolve_virtual_node(node_id)
+            .map_or(node_id, |(real_node_id, _)| *real_node_id);
+        let Some(node) = self.rust().graph.as_ref().and_then(|g| g.get_node(node_id)) else {
+            return;
+        };
+        let key = self.layout_key_of(&node);
+        if self.as_mut().rust_mut().stereo_pairs.remove(&key).is_some() {
+            save_stereo_pairs(&self.rust().stereo_pairs);
+            self.as_mut().graph_changed();
+        }
+    }
+
+    /// Override the channel position a device port reports, e.g. `FL` for a
+    /// port reported as `AUX0`; an empty `position` goes back to the
+    /// reported one. Pairing and auto-connect use the override.
+    pub fn set_port_channel(mut self: Pin<&mut Self>, port_id: u32, position: QString) -> bool {
+        let position = position.to_string().trim().to_ascii_uppercase();
+        if !position.is_empty() && !channels::is_channel_position(&position) {
+            log::warn!("set_port_channel: {:?} is not a channel position", position);
+            return false;
+        }
+        let Some(graph) = self.rust().graph.clone() else {
+            return false;
+        };
+        let Some(port) = graph.get_port(port_id) else {
+            return false;
+        };
+        let Some(node) = graph.get_node(port.node_id) else {
+            return false;
+        };
+        if node.node_type == Some(NodeType::Plugin) {
+            log::warn!("set_port_channel: port {} is a plugin's", port_id);
+            return false;
+        }
+        // Setting the reported position is the same as no override.
+        let reported = graph.reported_channel(port_id);
+        let position =
+            Some(position.as_str()).filter(|p| !p.is_empty() && reported.as_deref() != Some(*p));
+        let changed = self
+            .as_mut()
+            .rust_mut()
+            .channel_overrides
+            .set(&node.name, &port.name, position);
+        if changed {
+            self.apply_channel_overrides(&graph);
+        }
+        true
+    }
+
+    /// Forget the channel overrides of a device's ports.
+    pub fn reset_port_channels(mut self: Pin<&mut Self>, node_id: u32) {
+        let node_id = self
+            .rust()
+            .bridge_split
+            .resolve_virtual_node(node_id)
+            .map_or(node_id, |(real_node_id, _)| *real_node_id);
+        let Some(graph) = self.rust().graph.clone() else {
+            return;
+        };
+        let Some(node) = graph.get_node(node_id) else {
+            return;
+        };
+        if self.as_mut().rust_mut().channel_overrides.clear(&node.name) {
+            self.apply_channel_overrides(&graph);
+        }
+    }
+
+    fn apply_channel_overrides(mut self: Pin<&mut Self>, graph: &GraphState) {
+        let overrides = self.rust().channel_overrides.clone();
+        save_channel_overrides(&overrides);
+        graph.set_channel_overrides(overrides);
+        self.as_mut().graph_changed();
+    }
+
+    /// Connect two ports for the user or a remote client, lifting any
+    /// override on the link and learning it as a rule. Returns false when
+    /// the link was refused as a self-loop or feedback loop.
+    fn connect_user_ports(
+        mut self: Pin<&mut Self>,
+        output_port_id: u32,
+        input_port_id: u32,
+        allow_feedback: bool,
+    ) -> bool {
+        // Reject self-loops: don't connect a node's output to its own input
+        // For bridge nodes, allow cross-device connections (different port groups)
+        if let Some(ref graph) = self.rust().graph {
             let out_port = graph.get_port(output_port_id);
             let in_port = graph.get_port(input_port_id);
             if let (Some(op), Some(ip)) = (&out_port, &in_port) {
@@ -1404,21 +3477,65 @@ impl qobject::AppController {
                             "Rejected self-loop connect: ports {} and {} belong to the same node/device",
                             output_port_id, input_port_id
                         );
-                        return;
+                        return false;
                     }
                 }
             }
         }
 
-        if let Some(ref tx) = self.rust().cmd_tx {
-            log::info!("Connect request: {} -> {}", output_port_id, input_port_id);
-            let _ = tx.send(PwCommand::Connect {
+        log::info!("Connect request: {} -> {}", output_port_id, input_port_id);
+        let sent = self.try_send_command(
+            CommandOrigin::User,
+            PwCommand::Connect {
                 output_port_id,
                 input_port_id,
-            });
+            },
+            allow_feedback,
+        );
+        if sent.is_err() {
+            return false;
+        }
+
+        // Connecting a link by hand lifts any override on it.
+        let graph = self.rust().graph.clone();
+        if let Some(ref graph) = graph
+            && let (Some(out_port), Some(in_port)) = (
+                graph.get_port(output_port_id),
+                graph.get_port(input_port_id),
+            )
+            && let (Some(source_node), Some(target_node)) = (
+                graph.get_node(out_port.node_id),
+                graph.get_node(in_port.node_id),
+            )
+            && let Some(ref mut patchbay) = self.as_mut().rust_mut().patchbay
+        {
+            patchbay.clear_override_for(&source_node, &target_node, &out_port, &in_port);
+        }
+        self.as_mut().save_link_overrides_if_dirty();
+
+        if let Some(ref graph) = graph
+            && let Some(source_node) = graph
+                .get_port(output_port_id)
+                .and_then(|p| graph.get_node(p.node_id))
+            && let Some(target_node) = graph
+                .get_port(input_port_id)
+                .and_then(|p| graph.get_node(p.node_id))
+        {
+            let now_ms = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_millis() as u64;
+            self.as_mut().rust_mut().connection_history.record(
+                source_node.display_name(),
+                target_node.display_name(),
+                now_ms,
+            );
+            save_connection_history(&self.rust().connection_history);
         }
 
-        let learned = if !self.rust().prefs.auto_learn_rules {
+        // Rules apply through the feedback check, so a loop let through
+        // here is not learned as one.
+        let learned = if !self.rust().prefs.auto_learn_rules || allow_feedback {
             false
         } else {
             let graph = self.rust().graph.clone();
@@ -1470,14 +3587,22 @@ impl qobject::AppController {
         if self.rust().links_dirty_since.is_none() {
             self.as_mut().rust_mut().links_dirty_since = Some(Instant::now());
         }
+        true
     }
 
     pub fn disconnect_link(mut self: Pin<&mut Self>, link_id: u32) {
-        let link_info = self.rust().graph.as_ref().and_then(|g| g.get_link(link_id));
+        // A filtered MIDI link or a link with a gain goes with its node.
+        let filtered = match self.as_mut().remove_midi_link_filter(link_id) {
+            Some(link) => Some(link),
+            None => self.as_mut().remove_link_gain(link_id),
+        };
+        let link_info = filtered
+            .clone()
+            .or_else(|| self.rust().graph.as_ref().and_then(|g| g.get_link(link_id)));
 
-        if let Some(ref tx) = self.rust().cmd_tx {
-            log::info!("Disconnect request: {}", link_id);
-            let _ = tx.send(PwCommand::Disconnect { link_id });
+        log::info!("Disconnect request: {}", link_id);
+        if filtered.is_none() {
+            self.send_command(CommandOrigin::User, PwCommand::Disconnect { link_id });
         }
 
         if let Some(link) = link_info {
@@ -1526,6 +3651,7 @@ impl qobject::AppController {
             if unlearned {
                 save_rules(self.rust().patchbay.as_ref());
             }
+            self.as_mut().save_link_overrides_if_dirty();
         }
 
         self.as_mut().rust_mut().links_dirty = true;
@@ -1534,1748 +3660,8765 @@ impl qobject::AppController {
         }
     }
 
-    pub fn insert_node_on_link(mut self: Pin<&mut Self>, link_id: u32, node_id: u32) {
-        let graph = self.rust().graph.clone();
-        let Some(ref graph) = graph else { return };
-
-        let Some(link) = graph.get_link(link_id) else {
-            log::warn!("insert_node_on_link: link {} not found", link_id);
-            return;
-        };
-
-        let Some(node) = graph.get_node(node_id) else {
-            log::warn!("insert_node_on_link: node {} not found", node_id);
-            return;
+    /// Link two ports for `ttl_secs`, taking the input port over: other links
+    /// into it are disconnected until the route ends. Returns the route id
+    /// for `cancel_temporary_link`, or 0 after reporting the failure through
+    /// `error_occurred`.
+    pub fn create_temporary_link(
+        mut self: Pin<&mut Self>,
+        output_port_id: u32,
+        input_port_id: u32,
+        ttl_secs: u32,
+    ) -> u32 {
+        let snapshot = self.graph_snapshot();
+        let created = self.as_mut().rust_mut().temporary_routes.create(
+            &snapshot,
+            output_port_id,
+            input_port_id,
+            Duration::from_secs(u64::from(ttl_secs)),
+            Instant::now(),
+        );
+        let (route_id, ops) = match created {
+            Ok(created) => created,
+            Err(e) => {
+                self.as_mut()
+                    .error_occurred(QString::from(&format!("Temporary link: {}", e)));
+                return 0;
+            }
         };
+        log::info!(
+            "Temporary link {} -> {} for {} s",
+            output_port_id,
+            input_port_id,
+            ttl_secs
+        );
+        self.send_command(
+            CommandOrigin::User,
+            PwCommand::Transaction {
+                label: "Temporary link".to_string(),
+                ops,
+            },
+        );
+        self.as_mut().temporary_links_changed();
+        route_id
+    }
 
-        if link.output_node_id == node_id || link.input_node_id == node_id {
-            log::warn!("insert_node_on_link: node {} is already part of link {}, ignoring", node_id, link_id);
-            return;
-        }
-
-        if node.node_type != Some(NodeType::Plugin) {
-            log::warn!("insert_node_on_link: node {} is not an LV2 plugin, ignoring", node_id);
-            return;
+    /// End a temporary route now and put back what it displaced.
+    pub fn cancel_temporary_link(mut self: Pin<&mut Self>, route_id: u32) {
+        if let Some(route) = self.as_mut().rust_mut().temporary_routes.cancel(route_id) {
+            self.as_mut().revert_temporary_route(&route);
         }
+    }
 
-        let node_ports = graph.get_ports_for_node(node_id);
-        let mut node_inputs: Vec<_> = node_ports
-            .iter()
-            .filter(|p| p.direction == PortDirection::Input && p.media_type == Some(crate::pipewire::MediaType::Audio))
-            .collect();
-        let mut node_outputs: Vec<_> = node_ports
+    /// Running temporary routes as
+    /// `[{id, output_port_id, input_port_id, label, remaining_secs}]`.
+    pub fn get_temporary_links_json(self: Pin<&mut Self>) -> QString {
+        let graph = self.rust().graph.as_ref();
+        let port_label = |port_id: u32| {
+            graph
+                .and_then(|g| {
+                    let port = g.get_port(port_id)?;
+                    let node = g.get_node(port.node_id)?;
+                    Some(format!("{}:{}", node.display_name(), port.display_name()))
+                })
+                .unwrap_or_else(|| port_id.to_string())
+        };
+        let now = Instant::now();
+        let routes: Vec<serde_json::Value> = self
+            .rust()
+            .temporary_routes
+            .routes()
             .iter()
-            .filter(|p| p.direction == PortDirection::Output && p.media_type == Some(crate::pipewire::MediaType::Audio))
+            .map(|r| {
+                serde_json::json!({
+                    "id": r.id,
+                    "output_port_id": r.link.0,
+                    "input_port_id": r.link.1,
+                    "label": format!("{} → {}", port_label(r.link.0), port_label(r.link.1)),
+                    "remaining_secs": r.expires.saturating_duration_since(now).as_secs(),
+                })
+            })
             .collect();
+        QString::from(&serde_json::to_string(&routes).unwrap_or_else(|_| "[]".into()))
+    }
 
-        if node_inputs.is_empty() || node_outputs.is_empty() {
-            log::warn!("insert_node_on_link: node {} has no audio input/output ports", node_id);
-            return;
+    /// Solo `node_id` on `monitor_sink_id`, e.g. headphones, while `enable`:
+    /// its outputs are mirrored to the sink through temporary routes, so
+    /// other feeds into the sink drop out until the audition ends. The
+    /// node's own links are not touched. One node is auditioned at a time;
+    /// starting another ends the first. Returns false when the audition
+    /// could not start, or there was none of `node_id` to stop.
+    pub fn audition_node(
+        mut self: Pin<&mut Self>,
+        node_id: u32,
+        monitor_sink_id: u32,
+        enable: bool,
+    ) -> bool {
+        let ended = match self.rust().audition {
+            Some((current, _)) if current == node_id || enable => self.as_mut().end_audition(),
+            _ if !enable => return false,
+            _ => Vec::new(),
+        };
+        if !enable {
+            return true;
         }
 
-        node_inputs.sort_by(|a, b| crate::pipewire::state::natural_cmp(&a.name, &b.name));
-        node_outputs.sort_by(|a, b| crate::pipewire::state::natural_cmp(&a.name, &b.name));
-
-        let upstream_out = link.output_port_id;
-        let downstream_in = link.input_port_id;
-
-        let upstream_node_id = link.output_node_id;
-        let downstream_node_id = link.input_node_id;
+        // Plan as if the ended audition's links were already gone, so they
+        // are not taken for feeds to put back later.
+        let mut snapshot = self.graph_snapshot();
+        snapshot.apply(&ended.into_iter().map(PwCommand::from).collect::<Vec<_>>());
+        let pairs = audition_pairs(&snapshot, node_id, monitor_sink_id);
+        if pairs.is_empty() {
+            self.as_mut().error_occurred(QString::from(
+                "Audition: the node has no outputs that fit the monitoring sink",
+            ));
+            return false;
+        }
+        let now = Instant::now();
+        let mut route_ids = Vec::new();
+        let mut ops = Vec::new();
+        for (output_port_id, input_port_id) in pairs {
+            let created = self.as_mut().rust_mut().temporary_routes.create(
+                &snapshot,
+                output_port_id,
+                input_port_id,
+                MAX_TTL,
+                now,
+            );
+            match created {
+                Ok((route_id, route_ops)) => {
+                    route_ids.push(route_id);
+                    ops.extend(route_ops);
+                }
+                Err(e) => {
+                    // Nothing was sent yet, so the routes planned so far
+                    // are just dropped.
+                    for route_id in route_ids {
+                        self.as_mut().rust_mut().temporary_routes.cancel(route_id);
+                    }
+                    self.as_mut()
+                        .error_occurred(QString::from(&format!("Audition: {}", e)));
+                    return false;
+                }
+            }
+        }
+        log::info!("Auditioning node {} on {}", node_id, monitor_sink_id);
+        self.send_command(
+            CommandOrigin::User,
+            PwCommand::Transaction {
+                label: "Audition".to_string(),
+                ops,
+            },
+        );
+        self.as_mut().rust_mut().audition = Some((node_id, route_ids));
+        self.as_mut().temporary_links_changed();
+        true
+    }
 
-        let upstream_ports: Vec<_> = graph
-            .get_ports_for_node(upstream_node_id)
-            .into_iter()
-            .filter(|p| p.direction == PortDirection::Output && p.media_type == Some(crate::pipewire::MediaType::Audio))
-            .collect();
-        let downstream_ports: Vec<_> = graph
-            .get_ports_for_node(downstream_node_id)
-            .into_iter()
-            .filter(|p| p.direction == PortDirection::Input && p.media_type == Some(crate::pipewire::MediaType::Audio))
-            .collect();
+    /// The node being auditioned, or -1.
+    pub fn get_audition_node(self: Pin<&mut Self>) -> i32 {
+        self.rust()
+            .audition
+            .as_ref()
+            .map_or(-1, |(node_id, _)| *node_id as i32)
+    }
 
-        let upstream_idx = upstream_ports.iter().position(|p| p.id == upstream_out).unwrap_or(0);
-        let downstream_idx = downstream_ports.iter().position(|p| p.id == downstream_in).unwrap_or(0);
+    /// Check whether sound from `source_node_id` reaches `target_node_id`:
+    /// the outputs of every node on the route are listened to for a moment
+    /// and `probe_finished` reports where the sound stops. Nothing is
+    /// linked or unlinked on the route itself. Returns false when the probe
+    /// could not start; a missing route is reported right away.
+    pub fn probe_path(mut self: Pin<&mut Self>, source_node_id: u32, target_node_id: u32) -> bool {
+        if self.rust().probe.is_some() {
+            self.as_mut()
+                .error_occurred(QString::from("Probe: another probe is still listening"));
+            return false;
+        }
+        if source_node_id == target_node_id {
+            return false;
+        }
+        let snapshot = self.graph_snapshot();
+        let Some(hops) = probe::route(&snapshot, source_node_id, target_node_id) else {
+            let report = self.probe_report(source_node_id, target_node_id, &[], &[]);
+            self.as_mut().probe_finished(QString::from(&report));
+            return true;
+        };
 
-        let all_links = graph.get_all_links();
-        let mut links_to_remove = Vec::new();
-        let mut rewire_pairs: Vec<(u32, usize, u32, usize)> = Vec::new();
+        let id = self.rust().next_probe_id;
+        self.as_mut().rust_mut().next_probe_id += 1;
+        log::info!(
+            "Probing {} -> {} over {} nodes",
+            source_node_id,
+            target_node_id,
+            hops.len()
+        );
+        self.send_command(
+            CommandOrigin::User,
+            PwCommand::StartProbe {
+                probe_id: id,
+                inputs: probe::taps(&hops).len(),
+            },
+        );
+        self.as_mut().rust_mut().probe = Some(RunningProbe {
+            id,
+            source: source_node_id,
+            target: target_node_id,
+            hops,
+            started: Instant::now(),
+            listening_since: None,
+            stopping: false,
+        });
+        true
+    }
 
-        for existing in &all_links {
-            if existing.output_node_id == upstream_node_id && existing.input_node_id == downstream_node_id {
-                let u_idx = upstream_ports.iter().position(|p| p.id == existing.output_port_id);
-                let d_idx = downstream_ports.iter().position(|p| p.id == existing.input_port_id);
-                if let (Some(ui), Some(di)) = (u_idx, d_idx) {
-                    links_to_remove.push(existing.id);
-                    rewire_pairs.push((existing.output_port_id, ui, existing.input_port_id, di));
+    /// Link the taps to the probe node once it shows up, and stop it once
+    /// it has listened long enough.
+    fn advance_probe(mut self: Pin<&mut Self>) {
+        const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+        let Some((id, started, listening_since, stopping, taps)) =
+            self.rust().probe.as_ref().map(|p| {
+                (
+                    p.id,
+                    p.started,
+                    p.listening_since,
+                    p.stopping,
+                    probe::taps(&p.hops),
+                )
+            })
+        else {
+            return;
+        };
+        if started.elapsed() > PROBE_TIMEOUT + PROBE_LISTEN {
+            log::warn!("Probe {} timed out", id);
+            self.send_command(CommandOrigin::User, PwCommand::StopProbe { probe_id: id });
+            self.as_mut().rust_mut().probe = None;
+            self.as_mut()
+                .error_occurred(QString::from("Probe: no answer from PipeWire"));
+            return;
+        }
+        if stopping {
+            return;
+        }
+        if let Some(since) = listening_since {
+            if since.elapsed() >= PROBE_LISTEN {
+                self.send_command(CommandOrigin::User, PwCommand::StopProbe { probe_id: id });
+                if let Some(ref mut running) = self.as_mut().rust_mut().probe {
+                    running.stopping = true;
                 }
             }
+            return;
         }
 
-        if links_to_remove.is_empty() {
-            links_to_remove.push(link_id);
-            rewire_pairs.push((upstream_out, upstream_idx, downstream_in, downstream_idx));
+        let snapshot = self.graph_snapshot();
+        let name = probe::probe_node_name(id);
+        let Some(node) = snapshot.nodes().iter().find(|n| n.name == name) else {
+            return;
+        };
+        let inputs = snapshot.input_ports(node.id);
+        let mut ops = Vec::new();
+        for (i, &tap) in taps.iter().enumerate() {
+            let port_name = format!("tap_{}", i);
+            // The ports may show up after the node.
+            let Some(input) = inputs.iter().find(|p| p.name == port_name) else {
+                return;
+            };
+            ops.push(LinkOp::Connect {
+                output_port_id: tap,
+                input_port_id: input.id,
+            });
+        }
+        self.send_command(
+            CommandOrigin::User,
+            PwCommand::Transaction {
+                label: "Probe".to_string(),
+                ops,
+            },
+        );
+        if let Some(ref mut running) = self.as_mut().rust_mut().probe {
+            running.listening_since = Some(Instant::now());
         }
+    }
 
-        if let Some(ref tx) = self.rust().cmd_tx {
-            for lid in &links_to_remove {
-                let _ = tx.send(PwCommand::Disconnect { link_id: *lid });
-            }
+    fn finish_probe(mut self: Pin<&mut Self>, probe_id: u32, peaks_db: &[f32]) {
+        let Some(running) = self.as_mut().rust_mut().probe.take_if(|p| p.id == probe_id) else {
+            return;
+        };
+        let report = self.probe_report(running.source, running.target, &running.hops, peaks_db);
+        self.as_mut().probe_finished(QString::from(&report));
+    }
 
-            let max_in = node_inputs.len() - 1;
-            let max_out = node_outputs.len() - 1;
-            for (up_port, up_idx, down_port, down_idx) in &rewire_pairs {
-                let in_idx = *up_idx.min(&max_in);
-                let out_idx = *down_idx.min(&max_out);
+    /// The report `probe_finished` carries: each node on the route with the
+    /// loudest peak at its taps, and the finding.
+    fn probe_report(
+        &self,
+        source: u32,
+        target: u32,
+        hops: &[ProbeHop],
+        peaks_db: &[f32],
+    ) -> String {
+        let levels = probe::hop_levels(hops, peaks_db);
+        let finding = probe::finding(hops, &levels);
+        let graph = self.rust().graph.clone();
+        let name_of = |node_id: u32| {
+            graph
+                .as_ref()
+                .and_then(|g| g.get_node(node_id))
+                .map(|n| n.display_name().to_string())
+                .unwrap_or_else(|| format!("Node {}", node_id))
+        };
+        log::info!(
+            "Probe {} -> {}: {:?}",
+            name_of(source),
+            name_of(target),
+            finding
+        );
+        let route: Vec<serde_json::Value> = hops
+            .iter()
+            .zip(&levels)
+            .map(|(hop, level)| {
+                serde_json::json!({
+                    "nodeId": hop.node_id,
+                    "name": name_of(hop.node_id),
+                    "levelDb": level.map(|db| db.max(-120.0)),
+                    "silent": level.is_some_and(|db| db <= probe::SILENCE_DB),
+                })
+            })
+            .collect();
+        let lost_at = match finding {
+            ProbeFinding::LostAt { node_id } => name_of(node_id),
+            _ => String::new(),
+        };
+        serde_json::json!({
+            "sourceId": source,
+            "source": name_of(source),
+            "targetId": target,
+            "target": name_of(target),
+            "route": route,
+            "finding": finding,
+            "lostAt": lost_at,
+        })
+        .to_string()
+    }
 
-                let _ = tx.send(PwCommand::Connect {
-                    output_port_id: *up_port,
-                    input_port_id: node_inputs[in_idx].id,
-                });
-                let _ = tx.send(PwCommand::Connect {
-                    output_port_id: node_outputs[out_idx].id,
-                    input_port_id: *down_port,
-                });
+    /// Make the audio sink `node_id` the default output. With output
+    /// crossfading on, the streams on the current default are faded over to
+    /// it first and the default changes once the old sink is silent. Returns
+    /// false when `node_id` is not an audio sink.
+    pub fn set_default_output(mut self: Pin<&mut Self>, node_id: u32) -> bool {
+        let Some(graph) = self.rust().graph.clone() else {
+            return false;
+        };
+        let Some(node) = graph.get_node(node_id).filter(|n| {
+            n.node_type == Some(NodeType::Sink) && n.media_type == Some(MediaType::Audio)
+        }) else {
+            return false;
+        };
+        // A crossfade still running is cut short. The sinks it was ramping
+        // start from the volumes they had before it.
+        let running: Vec<(u32, f32)> = self
+            .rust()
+            .output_crossfade
+            .iter()
+            .flat_map(|f| [(f.old_sink, f.old_volume), (f.new_sink, f.new_volume)])
+            .collect();
+        self.as_mut().advance_output_crossfade(true);
+        let start_volume = |id: u32| {
+            running
+                .iter()
+                .find(|(sink, _)| *sink == id)
+                .map(|&(_, volume)| volume)
+                .or_else(|| graph.get_node_volume(id))
+                .unwrap_or(1.0)
+        };
+
+        let duration_ms = self.rust().prefs.output_crossfade_ms;
+        let old_sink = default_sink_id(&graph);
+        let pairs = match old_sink {
+            Some(old_sink) if duration_ms > 0 => {
+                handover_pairs(&self.graph_snapshot(), old_sink, node_id)
             }
-        }
+            _ => Vec::new(),
+        };
+        let Some(old_sink) = old_sink.filter(|_| !pairs.is_empty()) else {
+            self.send_command(
+                CommandOrigin::User,
+                PwCommand::SetDefaultSink {
+                    node_name: node.name,
+                },
+            );
+            return true;
+        };
 
         log::info!(
-            "insert_node_on_link: inserted node {} on {} links between nodes {} and {}",
+            "Crossfading output from node {} to {} over {} ms",
+            old_sink,
             node_id,
-            links_to_remove.len(),
-            upstream_node_id,
-            downstream_node_id
+            duration_ms
+        );
+        let old_volume = start_volume(old_sink);
+        let new_volume = start_volume(node_id);
+        // The new sink goes silent before the streams are linked to it.
+        self.send_command(
+            CommandOrigin::User,
+            PwCommand::RampNodeVolume {
+                node_id,
+                from: 0.0,
+                to: new_volume,
+                duration_ms,
+            },
+        );
+        for &(output_port_id, input_port_id) in &pairs {
+            self.send_command(
+                CommandOrigin::User,
+                PwCommand::Connect {
+                    output_port_id,
+                    input_port_id,
+                },
+            );
+        }
+        self.send_command(
+            CommandOrigin::User,
+            PwCommand::RampNodeVolume {
+                node_id: old_sink,
+                from: old_volume,
+                to: 0.0,
+                duration_ms,
+            },
         );
+        self.as_mut().rust_mut().output_crossfade = Some(OutputCrossfade {
+            old_sink,
+            new_sink: node_id,
+            new_sink_name: node.name,
+            old_volume,
+            new_volume,
+            pairs,
+            ends: Instant::now() + Duration::from_millis(duration_ms as u64),
+            switched: None,
+        });
+        true
+    }
 
-        let mut rule_data: Vec<(Node, Node, Port, Port)> = Vec::new();
-        let mut new_link_data: Vec<(Node, Node, Port, Port, Node, Node, Port, Port)> = Vec::new();
+    /// The node id of the default audio sink, or -1.
+    pub fn get_default_output(self: Pin<&mut Self>) -> i32 {
+        self.rust()
+            .graph
+            .as_ref()
+            .and_then(|g| default_sink_id(g))
+            .map_or(-1, |id| id as i32)
+    }
 
-        {
-            let max_in = node_inputs.len() - 1;
-            let max_out = node_outputs.len() - 1;
+    /// Move an output crossfade on once its ramps are done: change the
+    /// default and drop the extra links, then put the old sink's volume back
+    /// once the session manager has moved the streams off it. `finish` ends
+    /// it at once, for a new switch.
+    fn advance_output_crossfade(mut self: Pin<&mut Self>, finish: bool) {
+        let Some(graph) = self.rust().graph.clone() else {
+            return;
+        };
+        let Some(fade) = self.as_mut().rust_mut().output_crossfade.take() else {
+            return;
+        };
+        let now = Instant::now();
+        let switched = match fade.switched {
+            Some(switched) => switched,
+            None if now < fade.ends && !finish => {
+                self.as_mut().rust_mut().output_crossfade = Some(fade);
+                return;
+            }
+            None => {
+                // The extra links are dropped on the next PipeWire tick,
+                // sooner than the session manager relinks the streams, so
+                // its links to the same ports are not refused as doubles.
+                self.send_command(
+                    CommandOrigin::User,
+                    PwCommand::SetDefaultSink {
+                        node_name: fade.new_sink_name.clone(),
+                    },
+                );
+                for &(output_port_id, input_port_id) in &fade.pairs {
+                    if let Some(link) = graph.find_link(output_port_id, input_port_id) {
+                        self.send_command(
+                            CommandOrigin::User,
+                            PwCommand::Disconnect { link_id: link.id },
+                        );
+                    }
+                }
+                now
+            }
+        };
 
-            for (up_port_id, up_idx, down_port_id, down_idx) in &rewire_pairs {
-                if let (Some(source_node), Some(target_node), Some(out_port), Some(in_port)) = (
-                    graph.get_node(upstream_node_id),
-                    graph.get_node(downstream_node_id),
-                    graph.get_port(*up_port_id),
-                    graph.get_port(*down_port_id),
-                ) {
-                    rule_data.push((source_node, target_node, out_port, in_port));
-                }
-
-                let in_idx = *up_idx.min(&max_in);
-                let out_idx = *down_idx.min(&max_out);
-
-                if let (Some(up_node), Some(ins_node), Some(up_port), Some(ins_in_port)) = (
-                    graph.get_node(upstream_node_id),
-                    graph.get_node(node_id),
-                    graph.get_port(*up_port_id),
-                    graph.get_port(node_inputs[in_idx].id),
-                ) {
-                    if let (Some(ins_node2), Some(dn_node), Some(ins_out_port), Some(dn_port)) = (
-                        graph.get_node(node_id),
-                        graph.get_node(downstream_node_id),
-                        graph.get_port(node_outputs[out_idx].id),
-                        graph.get_port(*down_port_id),
-                    ) {
-                        new_link_data.push((
-                            up_node, ins_node, up_port, ins_in_port,
-                            ins_node2, dn_node, ins_out_port, dn_port,
-                        ));
-                    }
-                }
-            }
+        let streams_moved = !graph.get_all_links().iter().any(|l| {
+            l.input_node_id == fade.old_sink
+                && graph.get_node(l.output_node_id).and_then(|n| n.node_type)
+                    == Some(NodeType::StreamOutput)
+        });
+        if streams_moved || finish || now.duration_since(switched) >= OUTPUT_CROSSFADE_SETTLE {
+            self.send_command(
+                CommandOrigin::User,
+                PwCommand::RampNodeVolume {
+                    node_id: fade.old_sink,
+                    from: fade.old_volume,
+                    to: fade.old_volume,
+                    duration_ms: 0,
+                },
+            );
+        } else {
+            self.as_mut().rust_mut().output_crossfade = Some(OutputCrossfade {
+                switched: Some(switched),
+                ..fade
+            });
         }
+    }
 
-        let mut rules_changed = false;
-        if let Some(ref mut patchbay) = self.as_mut().rust_mut().patchbay {
-            for (source_node, target_node, out_port, in_port) in &rule_data {
-                if patchbay.unlearn_from_link(source_node, target_node, out_port, in_port) {
-                    log::info!(
-                        "insert_node_on_link: unlearned rule {}:{} -> {}:{}",
-                        source_node.display_name(),
-                        out_port.name,
-                        target_node.display_name(),
-                        in_port.name,
-                    );
-                    rules_changed = true;
-                }
-            }
-
-            for (up_node, ins_node, up_port, ins_in_port, ins_node2, dn_node, ins_out_port, dn_port) in &new_link_data {
-                if patchbay.learn_from_link(up_node, ins_node, up_port, ins_in_port) {
-                    log::info!(
-                        "insert_node_on_link: learned rule {}:{} -> {}:{}",
-                        up_node.display_name(),
-                        up_port.name,
-                        ins_node.display_name(),
-                        ins_in_port.name,
-                    );
-                    rules_changed = true;
-                }
-                if patchbay.learn_from_link(ins_node2, dn_node, ins_out_port, dn_port) {
-                    log::info!(
-                        "insert_node_on_link: learned rule {}:{} -> {}:{}",
-                        ins_node2.display_name(),
-                        ins_out_port.name,
-                        dn_node.display_name(),
-                        dn_port.name,
-                    );
-                    rules_changed = true;
-                }
+    /// End the audition and put back what it displaced. Returns the link
+    /// changes sent.
+    fn end_audition(mut self: Pin<&mut Self>) -> Vec<LinkOp> {
+        let Some((node_id, route_ids)) = self.as_mut().rust_mut().audition.take() else {
+            return Vec::new();
+        };
+        let snapshot = self.graph_snapshot();
+        let mut ops = Vec::new();
+        for route_id in route_ids {
+            if let Some(route) = self.as_mut().rust_mut().temporary_routes.cancel(route_id) {
+                ops.extend(route.plan_revert(&snapshot));
             }
         }
+        log::info!("Audition of node {} ended", node_id);
+        if !ops.is_empty() {
+            self.send_command(
+                CommandOrigin::User,
+                PwCommand::Transaction {
+                    label: "Audition ended".to_string(),
+                    ops: ops.clone(),
+                },
+            );
+        }
+        self.as_mut().temporary_links_changed();
+        ops
+    }
 
-        if rules_changed {
-            save_rules(self.rust().patchbay.as_ref());
+    /// End the audition once its node is gone, so the sink gets its other
+    /// feeds back instead of waiting for the routes to expire, or once its
+    /// routes have expired.
+    fn end_orphaned_audition(mut self: Pin<&mut Self>) {
+        let Some((node_id, ref route_ids)) = self.rust().audition else {
+            return;
+        };
+        let node_gone = self
+            .rust()
+            .graph
+            .as_ref()
+            .is_some_and(|g| g.get_node(node_id).is_none());
+        let expired = !self
+            .rust()
+            .temporary_routes
+            .routes()
+            .iter()
+            .any(|r| route_ids.contains(&r.id));
+        if node_gone || expired {
+            self.as_mut().end_audition();
         }
+    }
 
-        self.as_mut().rust_mut().links_dirty = true;
-        if self.rust().links_dirty_since.is_none() {
-            self.as_mut().rust_mut().links_dirty_since = Some(Instant::now());
+    /// Undo every temporary route, auditions included, and give PipeWire a
+    /// moment to do it, so none outlives the app or is saved as a link.
+    fn revert_temporary_routes_for_exit(&self) {
+        const TIMEOUT: Duration = Duration::from_secs(1);
+
+        let routes = self.rust().temporary_routes.routes();
+        let snapshot = self.graph_snapshot();
+        let ops: Vec<LinkOp> = routes
+            .iter()
+            .flat_map(|r| r.plan_revert(&snapshot))
+            .collect();
+        if ops.is_empty() {
+            return;
+        }
+        self.send_command(
+            CommandOrigin::User,
+            PwCommand::Transaction {
+                label: "Temporary links ended".to_string(),
+                ops,
+            },
+        );
+        let deadline = Instant::now() + TIMEOUT;
+        while Instant::now() < deadline {
+            let snapshot = self.graph_snapshot();
+            if routes
+                .iter()
+                .all(|r| snapshot.find_link(r.link.0, r.link.1).is_none())
+            {
+                return;
+            }
+            std::thread::sleep(Duration::from_millis(20));
         }
+        log::warn!("Temporary links were still up at exit");
     }
 
-    pub fn request_quit(self: Pin<&mut Self>) {
-        log::info!("Quit requested");
-        remove_crash_marker();
-        persist_lv2_links(self.rust().graph.as_ref());
-        persist_active_plugins(self.rust().plugin_manager.as_ref());
-        if !crate::PLUGINS_FROZEN.load(std::sync::atomic::Ordering::SeqCst) {
-            save_known_good_plugins();
+    fn expire_temporary_routes(mut self: Pin<&mut Self>) {
+        if self.rust().temporary_routes.is_empty() {
+            return;
+        }
+        let expired = self
+            .as_mut()
+            .rust_mut()
+            .temporary_routes
+            .take_expired(Instant::now());
+        for route in expired {
+            self.as_mut().revert_temporary_route(&route);
         }
-        crate::lv2::ui::shutdown_gtk_thread();
-        std::process::exit(0);
     }
 
-    pub fn restore_known_good(self: Pin<&mut Self>) -> bool {
-        if restore_known_good_plugins() {
-            log::info!("Known-good plugins restored. Restart to load them.");
-            crate::PLUGINS_FROZEN.store(false, std::sync::atomic::Ordering::SeqCst);
-            true
-        } else {
-            false
+    fn revert_temporary_route(mut self: Pin<&mut Self>, route: &TemporaryRoute) {
+        let ops = route.plan_revert(&self.graph_snapshot());
+        log::info!("Temporary link {} -> {} ended", route.link.0, route.link.1);
+        if !ops.is_empty() {
+            self.send_command(
+                CommandOrigin::User,
+                PwCommand::Transaction {
+                    label: "Temporary link ended".to_string(),
+                    ops,
+                },
+            );
         }
+        self.as_mut().temporary_links_changed();
     }
 
-    pub fn get_layout_json(self: Pin<&mut Self>) -> QString {
-        let path = config_path("layout.json");
-        let json = match std::fs::read_to_string(&path) {
-            Ok(s) => s,
-            Err(_) => "{}".to_string(),
+    /// The node `node_id` is most likely connected to next, as
+    /// `{node_id, name, direction, reason}`: `direction` is "target" or
+    /// "source" and `reason` is "rule", "history" or "default". Empty if
+    /// nothing stands out.
+    pub fn propose_connection(self: Pin<&mut Self>, node_id: u32) -> QString {
+        let Some(ref graph) = self.rust().graph else {
+            return QString::from("");
         };
-        log::debug!("get_layout_json: loaded from {:?}", path);
-        QString::from(&json)
+        let snapshot = GraphSnapshot::from_state(graph);
+        let default_sink = graph.default_sink();
+        let patchbay = self.rust().patchbay.as_ref();
+        let defaults: Vec<&str> = patchbay
+            .and_then(|p| p.default_target())
+            .into_iter()
+            .chain(default_sink.as_deref())
+            .collect();
+        let Some(proposal) = propose::propose_connection(
+            &snapshot,
+            node_id,
+            patchbay,
+            &self.rust().connection_history,
+            &defaults,
+        ) else {
+            return QString::from("");
+        };
+        let name = snapshot
+            .node(proposal.node_id)
+            .map(|n| n.display_name().to_string())
+            .unwrap_or_default();
+        let direction = match proposal.direction {
+            PortDirection::Input => "target",
+            PortDirection::Output => "source",
+        };
+        let json = serde_json::json!({
+            "node_id": proposal.node_id,
+            "name": name,
+            "direction": direction,
+            "reason": proposal.reason.as_str(),
+        });
+        QString::from(&json.to_string())
     }
 
-    pub fn save_layout(self: Pin<&mut Self>, json: QString) {
-        let path = config_path("layout.json");
-        let s: String = json.to_string();
-        if let Some(parent) = path.parent() {
-            let _ = std::fs::create_dir_all(parent);
-        }
-        if let Err(e) = std::fs::write(&path, &s) {
-            log::error!("Failed to save layout to {:?}: {}", path, e);
-        } else {
-            log::debug!("save_layout: written to {:?}", path);
+    /// Connect each output of `source_id` to the matching input of
+    /// `target_id`, as if the user had dragged every link.
+    pub fn connect_nodes(mut self: Pin<&mut Self>, source_id: u32, target_id: u32) {
+        let Some(ref graph) = self.rust().graph else {
+            return;
+        };
+        let commands =
+            propose::plan_connection(&GraphSnapshot::from_state(graph), source_id, target_id);
+        for cmd in commands {
+            if let PwCommand::Connect {
+                output_port_id,
+                input_port_id,
+            } = cmd
+            {
+                self.as_mut().connect_ports(output_port_id, input_port_id);
+            }
         }
     }
 
-    /// `node_sizes_json`: layoutKey → [width, height]. `pinned_positions_json`: layoutKey → [x, y].
-    /// Returns layoutKey → [x, y]. Pinned nodes keep their positions; free nodes are laid out.
-    pub fn auto_layout(mut self: Pin<&mut Self>, node_sizes_json: QString, pinned_positions_json: QString) -> QString {
-        use crate::layout;
-
-        let sizes_str: String = node_sizes_json.to_string();
-        let node_sizes: std::collections::HashMap<String, Vec<f64>> =
-            serde_json::from_str(&sizes_str).unwrap_or_default();
-
-        let pinned_str: String = pinned_positions_json.to_string();
-        let pinned_by_key: std::collections::HashMap<String, Vec<f64>> =
-            serde_json::from_str(&pinned_str).unwrap_or_default();
-
-        let graph = match self.rust().graph.clone() {
-            Some(g) => g,
-            None => return QString::from("{}"),
+    /// The linear plugin chain `node_id` belongs to, in signal order, as
+    /// `[{id, name}]`. Empty if the node is not a plugin.
+    pub fn get_chain_json(self: Pin<&mut Self>, node_id: u32) -> QString {
+        let Some(ref graph) = self.rust().graph else {
+            return QString::from("[]");
         };
+        let entries: Vec<serde_json::Value> = crate::patchbay::chain::detect_chain(graph, node_id)
+            .into_iter()
+            .filter_map(|id| graph.get_node(id))
+            .map(|n| serde_json::json!({ "id": n.id, "name": n.display_name() }))
+            .collect();
+        let json = serde_json::to_string(&entries).unwrap_or_else(|_| "[]".to_string());
+        QString::from(&json)
+    }
 
-        let all_nodes = graph.get_all_nodes();
-        let all_links = graph.get_all_links();
-
-        let mut layout_nodes: Vec<(u32, String, &str, f64, f64)> = Vec::new();
-        let mut layout_ports: Vec<(u32, u32, usize, bool)> = Vec::new();
-        let mut layout_links: Vec<(u32, u32, u32, u32, u32)> = Vec::new();
-        let mut id_to_layout_key: std::collections::HashMap<u32, String> = std::collections::HashMap::new();
+    /// Rewire a linear plugin chain so that it runs in the given order. The
+    /// nodes must currently form one chain; the changes are applied as a
+    /// single transaction.
+    pub fn reorder_chain(self: Pin<&mut Self>, node_ids_json: QString) -> bool {
+        let order: Vec<u32> = match serde_json::from_str(&node_ids_json.to_string()) {
+            Ok(v) => v,
+            Err(e) => {
+                log::warn!("reorder_chain: invalid node list: {}", e);
+                return false;
+            }
+        };
+        let Some(ref graph) = self.rust().graph else {
+            return false;
+        };
 
-        // Phase 1: resolve bridge virtual IDs (needs mutable self for bridge_split)
-        let mut bridge_vids: Vec<(u32, u32, String, String)> = Vec::new();
-        for n in all_nodes.iter().filter(|n| n.ready && n.is_bridge) {
-            let groups = graph.get_bridge_port_groups(n.id);
-            for (group, device_name) in &groups {
-                let vid = self.as_mut().rust_mut().bridge_split
-                    .get_or_create_virtual_id(n.id, group);
-                bridge_vids.push((n.id, vid, group.clone(), device_name.clone()));
+        let ops = match crate::patchbay::chain::plan_reorder(graph, &order) {
+            Ok(ops) => ops,
+            Err(e) => {
+                log::warn!("reorder_chain: {}", e);
+                return false;
             }
+        };
+        if ops.is_empty() {
+            return true;
         }
 
-        // Phase 2: build layout data (immutable self access for plugin_manager)
-        let mgr = self.rust().plugin_manager.as_ref();
-
-        for n in all_nodes.iter().filter(|n| n.ready) {
-            if n.is_bridge {
-                let groups = graph.get_bridge_port_groups(n.id);
-                if groups.is_empty() {
-                    let key = layout_key(n, mgr);
-                    let (w, h) = get_node_size(&node_sizes, &key, n.id);
-                    let type_str = node_type_str(n);
-                    layout_nodes.push((n.id, n.display_name().to_string(), type_str, w, h));
-                    id_to_layout_key.insert(n.id, key);
+        log::info!("reorder_chain: {:?} ({} link changes)", order, ops.len());
+        self.send_command(
+            CommandOrigin::User,
+            PwCommand::Transaction {
+                label: "Reorder chain".to_string(),
+                ops,
+            },
+        );
+        true
+    }
 
-                    let ports = graph.get_ports_for_node(n.id);
-                    add_ports_to_layout(&ports, n.id, &mut layout_ports);
-                } else {
-                    for &(real_id, vid, ref _group, ref device_name) in &bridge_vids {
-                        if real_id != n.id { continue; }
+    /// Put the best installed noise suppressor right after the source
+    /// `node_id`, take over the source's links, and add a rule so the source
+    /// goes through it whenever it appears. Returns the suppressor's display
+    /// name, or an empty string after reporting the failure through
+    /// `error_occurred`.
+    pub fn add_noise_suppression(mut self: Pin<&mut Self>, node_id: u32) -> QString {
+        let channels = match self.rust().graph.as_ref().and_then(|g| g.get_node(node_id)) {
+            Some(node) if node.node_type == Some(NodeType::Source) => self
+                .rust()
+                .graph
+                .as_ref()
+                .map(|g| {
+                    g.get_ports_for_node(node_id)
+                        .iter()
+                        .filter(|p| {
+                            p.direction == PortDirection::Output
+                                && p.media_type == Some(MediaType::Audio)
+                        })
+                        .count()
+                })
+                .unwrap_or(0),
+            _ => {
+                log::warn!("add_noise_suppression: node {} is not a source", node_id);
+                return QString::from("");
+            }
+        };
+        let uri = self
+            .rust()
+            .plugin_manager
+            .as_ref()
+            .and_then(|mgr| {
+                crate::plugin::suppression::find_suppressor(mgr.available_plugins(), channels)
+            })
+            .map(|p| p.uri.clone());
+        let Some(uri) = uri else {
+            self.as_mut().error_occurred(QString::from(
+                "No noise suppression plugin is installed.\n\
+                 Install noise-suppression-for-voice (RNNoise), speech-denoiser or \
+                 noise-repellent, then rescan plugins.",
+            ));
+            return QString::from("");
+        };
 
-                        let key = format!("MidiBridge:{}", device_name);
-                        let (w, h) = get_node_size(&node_sizes, &key, vid);
+        let instance_id = self.rust().next_instance_id;
+        let name = self.as_mut().add_plugin(QString::from(&uri));
+        if name.is_empty() {
+            return name;
+        }
+        log::info!(
+            "add_noise_suppression: '{}' ({}) after node {}",
+            name,
+            uri,
+            node_id
+        );
+        self.as_mut()
+            .rust_mut()
+            .pending_suppressors
+            .push(PendingSuppressor {
+                instance_id,
+                name: name.to_string(),
+                source: node_id,
+                created: Instant::now(),
+            });
+        name
+    }
 
-                        let group_ports = graph.get_ports_for_bridge_group(n.id, &_group);
-                        let has_inputs = group_ports.iter().any(|p| p.direction == PortDirection::Input);
-                        let has_outputs = group_ports.iter().any(|p| p.direction == PortDirection::Output);
-                        let type_str = if has_inputs && has_outputs { "Duplex" }
-                            else if has_outputs { "Source" }
-                            else if has_inputs { "Sink" }
-                            else { "Duplex" };
+    fn wire_pending_suppressors(mut self: Pin<&mut Self>) {
+        const SUPPRESSOR_TIMEOUT: Duration = Duration::from_secs(10);
 
-                        layout_nodes.push((vid, device_name.clone(), type_str, w, h));
-                        id_to_layout_key.insert(vid, key);
+        if self.rust().pending_suppressors.is_empty() {
+            return;
+        }
+        let Some(graph) = self.rust().graph.clone() else {
+            return;
+        };
 
-                        add_ports_to_layout(&group_ports, vid, &mut layout_ports);
-                    }
+        let pending = std::mem::take(&mut self.as_mut().rust_mut().pending_suppressors);
+        let mut waiting = Vec::new();
+        for pending in pending {
+            let ready = self
+                .rust()
+                .plugin_manager
+                .as_ref()
+                .and_then(|m| m.get_instance(pending.instance_id))
+                .and_then(|info| info.pw_node_id)
+                .filter(|id| !graph.get_ports_for_node(*id).is_empty());
+            let Some(plugin_node) = ready else {
+                if pending.created.elapsed() > SUPPRESSOR_TIMEOUT {
+                    log::warn!(
+                        "add_noise_suppression: '{}' never appeared, not wiring it",
+                        pending.name
+                    );
+                } else {
+                    waiting.push(pending);
                 }
-            } else {
-                let key = layout_key(n, mgr);
-                let (w, h) = get_node_size(&node_sizes, &key, n.id);
-                let type_str = node_type_str(n);
-                layout_nodes.push((n.id, n.display_name().to_string(), type_str, w, h));
-                id_to_layout_key.insert(n.id, key);
+                continue;
+            };
+            let Some(source) = graph.get_node(pending.source) else {
+                log::warn!("add_noise_suppression: source {} is gone", pending.source);
+                continue;
+            };
 
-                let ports = graph.get_ports_for_node(n.id);
-                add_ports_to_layout(&ports, n.id, &mut layout_ports);
+            match crate::patchbay::chain::plan_insert_after(&graph, pending.source, plugin_node) {
+                Ok(ops) => {
+                    self.send_command(
+                        CommandOrigin::User,
+                        PwCommand::Transaction {
+                            label: format!("Noise suppression for {}", source.display_name()),
+                            ops,
+                        },
+                    );
+                }
+                Err(e) => {
+                    log::warn!("add_noise_suppression: {}", e);
+                    continue;
+                }
             }
-        }
 
-        for l in &all_links {
-            let out_node = self.rust().bridge_split
-                .resolve_port_virtual_node(l.output_port_id)
-                .unwrap_or(l.output_node_id);
-            let in_node = self.rust().bridge_split
-                .resolve_port_virtual_node(l.input_port_id)
-                .unwrap_or(l.input_node_id);
-
-            if id_to_layout_key.contains_key(&out_node) && id_to_layout_key.contains_key(&in_node) {
-                layout_links.push((l.id, out_node, l.output_port_id, in_node, l.input_port_id));
+            let rule = crate::patchbay::rules::AutoConnectRule::new(
+                source.display_name(),
+                Some(NodeType::Source),
+                pending.name.as_str(),
+                Some(NodeType::Plugin),
+                None,
+            );
+            if let Some(ref mut patchbay) = self.as_mut().rust_mut().patchbay {
+                patchbay.add_rule(rule);
             }
+            save_rules(self.rust().patchbay.as_ref());
         }
+        self.as_mut().rust_mut().pending_suppressors = waiting;
+    }
 
-        // Disambiguate duplicate layout keys by appending #N for the 2nd, 3rd, etc.
-        // Build a stable ordering: sort node IDs so the suffix assignment is deterministic.
-        let mut key_counts: std::collections::HashMap<String, u32> = std::collections::HashMap::new();
-        let mut sorted_ids: Vec<u32> = id_to_layout_key.keys().copied().collect();
-        sorted_ids.sort();
-        let mut unique_keys: std::collections::HashMap<u32, String> = std::collections::HashMap::new();
-        for node_id in &sorted_ids {
-            if let Some(base_key) = id_to_layout_key.get(node_id) {
-                let count = key_counts.entry(base_key.clone()).or_insert(0);
-                let unique_key = if *count == 0 {
-                    base_key.clone()
-                } else {
-                    format!("{}#{}", base_key, count)
-                };
-                *count += 1;
-                unique_keys.insert(*node_id, unique_key);
-            }
-        }
+    /// The processing buffer requested for the chain `node_id` belongs to, as
+    /// `{quantum, mixed, choices: [{quantum, label}]}`. `quantum` is 0 when
+    /// the chain uses the graph default and `mixed` is set when its plugins
+    /// ask for different buffers.
+    pub fn get_chain_quantum_json(self: Pin<&mut Self>, node_id: u32) -> QString {
+        let plugins = self.chain_plugins(node_id);
+        let requests: Vec<Option<u32>> = plugins
+            .iter()
+            .map(|(_, sid)| self.rust().plugin_quanta.get(sid).copied())
+            .collect();
+        let shared = quantum::chain_quantum(&requests);
+        let rate = self
+            .rust()
+            .graph
+            .as_ref()
+            .and_then(|g| g.get_node_format(node_id))
+            .and_then(|f| f.rate)
+            .unwrap_or(48000);
+        let choices: Vec<serde_json::Value> = quantum::QUANTUM_CHOICES
+            .iter()
+            .map(|&q| {
+                serde_json::json!({
+                    "quantum": q,
+                    "label": format!("{} samples ({:.1} ms)", q, quantum::latency_ms(q, rate)),
+                })
+            })
+            .collect();
+        let json = serde_json::json!({
+            "quantum": shared.unwrap_or(0),
+            "mixed": shared.is_none() && requests.iter().any(Option::is_some),
+            "choices": choices,
+        });
+        QString::from(&json.to_string())
+    }
 
-        let config = layout::graph::LayoutConfig::default();
-        let mut pinned_by_id: std::collections::HashMap<u32, (f64, f64)> = std::collections::HashMap::new();
-        for (key, pos) in &pinned_by_key {
-            if pos.len() >= 2 {
-                for (&node_id, ukey) in &unique_keys {
-                    if ukey == key {
-                        pinned_by_id.insert(node_id, (pos[0], pos[1]));
-                    }
+    /// Ask for a processing buffer of `quantum` samples on every plugin of
+    /// the chain `node_id` belongs to; 0 goes back to the graph default.
+    pub fn set_chain_quantum(mut self: Pin<&mut Self>, node_id: u32, quantum: u32) -> bool {
+        let request = if quantum == 0 {
+            None
+        } else {
+            match quantum::check_quantum(quantum) {
+                Ok(q) => Some(q),
+                Err(e) => {
+                    log::warn!("set_chain_quantum: {}", e);
+                    return false;
                 }
             }
+        };
+        let plugins = self.chain_plugins(node_id);
+        if plugins.is_empty() {
+            log::warn!("set_chain_quantum: node {} is not a hosted plugin", node_id);
+            return false;
         }
-
-        for &(id, ref name, type_str, w, h) in &layout_nodes {
-            let pin_tag = if pinned_by_id.contains_key(&id) { " [PINNED]" } else { "" };
-            log::info!("  node {}: {}({}) {}x{}{}", id, name, type_str, w as i32, h as i32, pin_tag);
-        }
-        log::info!("auto_layout: {} nodes ({} pinned), {} links",
-            layout_nodes.len(), pinned_by_id.len(), layout_links.len());
-
-        let positions = layout::sugiyama_layout(layout_nodes, layout_ports, layout_links, config, &pinned_by_id);
-
-        let mut result: serde_json::Map<String, serde_json::Value> = serde_json::Map::new();
-        for (node_id, (x, y)) in &positions {
-            if let Some(key) = unique_keys.get(node_id) {
-                let pin_tag = if pinned_by_id.contains_key(node_id) { " [P]" } else { "" };
-                log::info!("  result {}: ({:.0}, {:.0}){}", key, x, y, pin_tag);
-                result.insert(key.clone(), serde_json::json!([x, y]));
+        for (instance_id, sid) in plugins {
+            match request {
+                Some(q) => {
+                    self.as_mut().rust_mut().plugin_quanta.insert(sid, q);
+                }
+                None => {
+                    self.as_mut().rust_mut().plugin_quanta.remove(&sid);
+                }
             }
+            self.send_command(
+                CommandOrigin::User,
+                PwCommand::SetPluginQuantum {
+                    instance_id,
+                    quantum: request,
+                },
+            );
         }
-
-        let json = serde_json::to_string(&serde_json::Value::Object(result)).unwrap_or_else(|_| "{}".to_string());
-        log::info!("auto_layout: computed {} positions", positions.len());
-        QString::from(&json)
+        log::info!("Chain of node {}: quantum {:?}", node_id, request);
+        persist_active_plugins(self.rust());
+        true
     }
 
-    pub fn get_hidden_json(self: Pin<&mut Self>) -> QString {
-        let path = config_path("hidden.json");
-        let json = match std::fs::read_to_string(&path) {
-            Ok(s) => s,
-            Err(_) => "[]".to_string(),
+    /// Instance and stable IDs of the hosted plugins in the chain `node_id`
+    /// belongs to.
+    fn chain_plugins(&self, node_id: u32) -> Vec<(u64, String)> {
+        let (Some(graph), Some(mgr)) = (&self.rust().graph, &self.rust().plugin_manager) else {
+            return Vec::new();
         };
-        log::debug!("get_hidden_json: loaded from {:?}", path);
-        QString::from(&json)
+        crate::patchbay::chain::detect_chain(graph, node_id)
+            .into_iter()
+            .filter_map(|id| {
+                mgr.active_instances()
+                    .values()
+                    .find(|info| info.pw_node_id == Some(id))
+                    .map(|info| (info.id, info.stable_id.clone()))
+            })
+            .collect()
     }
 
-    pub fn save_hidden(self: Pin<&mut Self>, json: QString) {
-        let path = config_path("hidden.json");
-        let s: String = json.to_string();
-        if let Some(parent) = path.parent() {
-            let _ = std::fs::create_dir_all(parent);
+    /// Wrap the chain `node_id` belongs to in a Chain Mix node, giving it
+    /// dry/wet and output gain controls. The mixer is an ordinary plugin
+    /// instance, so its parameters and links persist like any other. Returns
+    /// the mixer's display name, or an empty string on failure.
+    pub fn add_chain_mix(mut self: Pin<&mut Self>, node_id: u32) -> QString {
+        let chain = match self.rust().graph {
+            Some(ref graph) => crate::patchbay::chain::detect_chain(graph, node_id),
+            None => return QString::from(""),
+        };
+        if chain.is_empty() {
+            log::warn!("add_chain_mix: node {} is not a plugin", node_id);
+            return QString::from("");
         }
-        if let Err(e) = std::fs::write(&path, &s) {
-            log::error!("Failed to save hidden to {:?}: {}", path, e);
-        } else {
-            log::debug!("save_hidden: written to {:?}", path);
+
+        let instance_id = self.rust().next_instance_id;
+        let name = self
+            .as_mut()
+            .add_plugin(QString::from(crate::builtin::CHAIN_MIX_URI));
+        if name.is_empty() {
+            return name;
         }
+
+        log::info!("add_chain_mix: '{}' around chain {:?}", name, chain);
+        self.as_mut()
+            .rust_mut()
+            .pending_chain_mixes
+            .push(PendingChainMix {
+                instance_id,
+                chain,
+                created: Instant::now(),
+            });
+        name
     }
 
-    pub fn get_pinned_json(self: Pin<&mut Self>) -> QString {
-        let path = config_path("pinned.json");
-        let json = match std::fs::read_to_string(&path) {
-            Ok(s) => s,
-            Err(_) => "[]".to_string(),
-        };
-        QString::from(&json)
+    /// Write the chain `node_id` belongs to as a PipeWire filter-chain config
+    /// under `exports/` in the config directory. Returns the written path, or
+    /// an empty string after reporting the failure through `error_occurred`.
+    pub fn export_chain_filter_chain(mut self: Pin<&mut Self>, node_id: u32) -> QString {
+        let result = self
+            .render_chain_filter_chain(node_id)
+            .and_then(|(name, config)| save_chain_export(&name, &config));
+        match result {
+            Ok(path) => {
+                log::info!("Exported filter chain to {:?}", path);
+                QString::from(path.to_string_lossy().as_ref())
+            }
+            Err(e) => {
+                log::warn!("export_chain_filter_chain: {}", e);
+                let msg = format!("Chain export failed: {}", e);
+                self.as_mut().error_occurred(QString::from(msg.as_str()));
+                QString::from("")
+            }
+        }
     }
 
-    pub fn save_pinned(self: Pin<&mut Self>, json: QString) {
-        let path = config_path("pinned.json");
-        let s: String = json.to_string();
-        if let Some(parent) = path.parent() {
-            let _ = std::fs::create_dir_all(parent);
+    /// Write the chain `node_id` belongs to as a `.zestchain` file under
+    /// `chains/` in the config directory. Returns the written path, or an
+    /// empty string after reporting the failure through `error_occurred`.
+    pub fn export_chain_file(mut self: Pin<&mut Self>, node_id: u32) -> QString {
+        let result = self.render_chain_file(node_id).and_then(|chain| {
+            save_config_export(
+                "chains",
+                &chain.name,
+                CHAIN_FILE_EXTENSION,
+                &chain.to_json(),
+            )
+        });
+        match result {
+            Ok(path) => {
+                log::info!("Exported chain file to {:?}", path);
+                QString::from(path.to_string_lossy().as_ref())
+            }
+            Err(e) => {
+                log::warn!("export_chain_file: {}", e);
+                let msg = format!("Chain export failed: {}", e);
+                self.as_mut().error_occurred(QString::from(msg.as_str()));
+                QString::from("")
+            }
+        }
+    }
+
+    /// Files that `import_file` or `import_config` can read, found in the
+    /// usual locations, as a JSON array of `{path, name, kind}`.
+    pub fn get_import_candidates_json(self: Pin<&mut Self>) -> QString {
+        let mut candidates = Vec::new();
+        for dir in filter_chain_config_dirs() {
+            let Ok(entries) = std::fs::read_dir(&dir) else {
+                continue;
+            };
+            let mut paths: Vec<PathBuf> = entries
+                .filter_map(|e| e.ok().map(|e| e.path()))
+                .filter(|p| p.extension().is_some_and(|ext| ext == "conf"))
+                .collect();
+            paths.sort();
+            for path in paths {
+                let is_filter_chain = std::fs::read_to_string(&path)
+                    .is_ok_and(|text| text.contains("libpipewire-module-filter-chain"));
+                if !is_filter_chain {
+                    continue;
+                }
+                let name = path
+                    .file_stem()
+                    .map(|s| s.to_string_lossy().into_owned())
+                    .unwrap_or_default();
+                candidates.push(serde_json::json!({
+                    "path": path.to_string_lossy(),
+                    "name": name,
+                    "kind": "Filter chain",
+                }));
+            }
+        }
+        // The user's chains, then ones shipped system-wide.
+        let chain_dirs = std::iter::once((config_path("chains"), "ZestBay chain")).chain(
+            system_config_paths("chains")
+                .into_iter()
+                .map(|dir| (dir, "ZestBay chain (system)")),
+        );
+        for (dir, kind) in chain_dirs {
+            let Ok(entries) = std::fs::read_dir(&dir) else {
+                continue;
+            };
+            let mut paths: Vec<PathBuf> = entries
+                .filter_map(|e| e.ok().map(|e| e.path()))
+                .filter(|p| p.extension().is_some_and(|ext| ext == CHAIN_FILE_EXTENSION))
+                .collect();
+            paths.sort();
+            for path in paths {
+                let name = path
+                    .file_stem()
+                    .map(|s| s.to_string_lossy().into_owned())
+                    .unwrap_or_default();
+                candidates.push(serde_json::json!({
+                    "path": path.to_string_lossy(),
+                    "name": name,
+                    "kind": kind,
+                }));
+            }
+        }
+        let exports = std::fs::read_dir(config_path("exports"));
+        let mut configs: Vec<PathBuf> = exports
+            .into_iter()
+            .flatten()
+            .filter_map(|e| e.ok().map(|e| e.path()))
+            .filter(|p| {
+                p.extension()
+                    .is_some_and(|ext| ext == portable::CONFIG_BUNDLE_EXTENSION)
+            })
+            .collect();
+        configs.sort();
+        for path in configs {
+            let name = path
+                .file_stem()
+                .map(|s| s.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            candidates.push(serde_json::json!({
+                "path": path.to_string_lossy(),
+                "name": name,
+                "kind": "ZestBay configuration",
+            }));
         }
-        if let Err(e) = std::fs::write(&path, &s) {
-            log::error!("Failed to save pinned to {:?}: {}", path, e);
+        for session in crate::plugin::import::find_ray_sessions() {
+            let name = session
+                .file_name()
+                .map(|s| s.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            candidates.push(serde_json::json!({
+                "path": session.to_string_lossy(),
+                "name": name,
+                "kind": "RaySession",
+            }));
         }
+        let json = serde_json::to_string(&candidates).unwrap_or_else(|_| "[]".into());
+        QString::from(&json)
     }
 
-    pub fn get_available_plugins_json(self: Pin<&mut Self>) -> QString {
-        if let Some(ref mgr) = self.rust().plugin_manager {
-            let json_plugins: Vec<serde_json::Value> = mgr
-                .available_plugins()
+    /// What importing the `.zestchain` file at `path` would create, as JSON:
+    /// `{name, plugins, available, missing: [{name, uri, format, author}]}`,
+    /// or `{error}` when the file cannot be read. `available` counts the
+    /// plugins that would be created.
+    pub fn check_chain_file(self: Pin<&mut Self>, path: QString) -> QString {
+        let path = PathBuf::from(path.to_string());
+        let checked = read_chain_file(&path).map(|chain| {
+            let catalog = self
+                .rust()
+                .plugin_manager
+                .as_ref()
+                .map(|mgr| mgr.available_plugins())
+                .unwrap_or_default();
+            let missing = chain.missing(catalog);
+            let available = chain
+                .plugins
                 .iter()
-                .map(|p| {
+                .filter(|p| {
+                    !missing
+                        .iter()
+                        .any(|m| m.uri == p.uri && m.format == p.format)
+                })
+                .count();
+            let missing: Vec<serde_json::Value> = missing
+                .iter()
+                .map(|r| {
                     serde_json::json!({
-                        "uri": p.uri,
-                        "name": p.name,
-                        "category": p.category.display_name(),
-                        "author": p.author.as_deref().unwrap_or(""),
-                        "audioIn": p.audio_inputs,
-                        "audioOut": p.audio_outputs,
-                        "controlIn": p.control_inputs,
-                        "controlOut": p.control_outputs,
-                        "compatible": p.compatible,
-                        "requiredFeatures": p.required_features,
-                        "hasUi": p.has_ui,
-                        "format": p.format.as_str(),
+                        "name": r.name,
+                        "uri": r.uri,
+                        "format": r.format.as_str(),
+                        "author": r.author.as_deref().unwrap_or(""),
                     })
                 })
                 .collect();
-            let json = serde_json::to_string(&json_plugins).unwrap_or_default();
-            QString::from(&json)
-        } else {
-            QString::from("[]")
-        }
+            serde_json::json!({
+                "name": chain.name,
+                "plugins": chain.plugins.len(),
+                "available": available,
+                "missing": missing,
+            })
+        });
+        let json = checked.unwrap_or_else(|e| serde_json::json!({ "error": e }));
+        QString::from(&json.to_string())
     }
 
-    pub fn add_plugin(mut self: Pin<&mut Self>, uri: QString) -> QString {
-        let uri_str: String = uri.to_string();
-
-        let (display_name, initial_params, plugin_format) = if let Some(ref mgr) =
-            self.rust().plugin_manager
+    /// Re-create the plugin chains described by the file at `path` as hosted
+    /// plugins. Returns a summary for the user, or an empty string after
+    /// reporting the failure through `error_occurred`.
+    pub fn import_file(mut self: Pin<&mut Self>, path: QString) -> QString {
+        let path = PathBuf::from(path.to_string());
+        if path
+            .extension()
+            .is_some_and(|ext| ext == CHAIN_FILE_EXTENSION)
         {
-            let plugin = mgr.find_plugin(&uri_str);
-            let base_name = plugin
-                .map(|p| p.name.clone())
-                .unwrap_or_else(|| uri_str.clone());
-            let name = self.unique_display_name(&base_name);
-            let format = plugin
-                .map(|p| p.format)
-                .unwrap_or(crate::plugin::PluginFormat::Lv2);
-            let params: Vec<crate::lv2::Lv2ParameterValue> = plugin
-                .map(|p| {
-                    p.ports
-                        .iter()
-                        .filter(|port| port.port_type == crate::lv2::Lv2PortType::ControlInput)
-                        .map(|port| crate::lv2::Lv2ParameterValue {
-                            port_index: port.index,
-                            symbol: port.symbol.clone(),
-                            name: port.name.clone(),
-                            value: port.default_value,
-                            min: port.min_value,
-                            max: port.max_value,
-                            default: port.default_value,
-                            is_toggle: port.is_toggle,
-                        })
-                        .collect()
-                })
-                .unwrap_or_default();
-            (name, params, format)
-        } else {
-            return QString::from("");
+            return self.import_chain_file(&path);
+        }
+        let result = match self.rust().plugin_manager.as_ref() {
+            Some(mgr) if path.extension().is_some_and(|ext| ext == "conf") => {
+                std::fs::read_to_string(&path)
+                    .map_err(|e| format!("failed to read {:?}: {}", path, e))
+                    .and_then(|text| {
+                        crate::plugin::filter_chain::import_config(&text, mgr.available_plugins())
+                    })
+            }
+            Some(mgr) => crate::plugin::import::import_session(&path, mgr.available_plugins()),
+            None => Err("plugins are not loaded".to_string()),
+        };
+        let chains = match result {
+            Ok(chains) => chains,
+            Err(e) => {
+                log::warn!("import_file: {}", e);
+                let msg = format!("Import failed: {}", e);
+                self.as_mut().error_occurred(QString::from(msg.as_str()));
+                return QString::from("");
+            }
         };
 
-        let instance_id = self.rust().next_instance_id;
-        self.as_mut().rust_mut().next_instance_id += 1;
-
-        let format_str = plugin_format.as_str().to_string();
+        let mut summary = Vec::new();
+        for chain in &chains {
+            let created = self.as_mut().create_imported_chain(chain);
+            let mut line = format!("{}: {} plugin(s) created", chain.name, created);
+            if !chain.skipped.is_empty() {
+                line.push_str(&format!(", skipped {}", chain.skipped.join(", ")));
+            }
+            summary.push(line);
+        }
+        log::info!("Imported {:?}: {}", path, summary.join("; "));
+        QString::from(summary.join("\n").as_str())
+    }
 
-        if let Some(ref mut mgr) = self.as_mut().rust_mut().plugin_manager {
-            let info = crate::lv2::Lv2InstanceInfo {
-                id: instance_id,
-                stable_id: uuid::Uuid::new_v4().to_string(),
-                plugin_uri: uri_str.clone(),
-                format: plugin_format,
-                display_name: display_name.clone(),
-                pw_node_id: None,
-                parameters: initial_params,
-                active: true,
-                bypassed: false,
-                lv2_state: Vec::new(),
+    /// Add the plugins of an imported chain and queue its links. Returns the
+    /// number of plugins added.
+    fn create_imported_chain(
+        mut self: Pin<&mut Self>,
+        chain: &crate::plugin::import::ImportedChain,
+    ) -> usize {
+        let mut names = Vec::new();
+        for stage in &chain.stages {
+            let Some(plugin) = self
+                .rust()
+                .plugin_manager
+                .as_ref()
+                .and_then(|mgr| mgr.find_plugin_with_format(&stage.plugin_uri, stage.format))
+                .cloned()
+            else {
+                names.push(None);
+                continue;
             };
-            mgr.register_instance(info);
-        }
+            let mut parameters = initial_parameters(&plugin);
+            for (port_index, value) in &stage.controls {
+                if let Some(param) = parameters.iter_mut().find(|p| p.port_index == *port_index) {
+                    param.value = value.max(param.min).min(param.max);
+                }
+            }
 
-        if let Some(ref tx) = self.rust().cmd_tx {
-            log::info!(
-                "Adding plugin: uri={} instance_id={} name={} format={}",
-                uri_str,
-                instance_id,
-                display_name,
-                format_str
+            let base_name = stage.display_name.as_deref().unwrap_or(&plugin.name);
+            let display_name = self.unique_display_name(base_name);
+            let instance_id = self.rust().next_instance_id;
+            self.as_mut().rust_mut().next_instance_id += 1;
+            if let Some(ref mut mgr) = self.as_mut().rust_mut().plugin_manager {
+                mgr.register_instance(crate::lv2::Lv2InstanceInfo {
+                    id: instance_id,
+                    stable_id: uuid::Uuid::new_v4().to_string(),
+                    plugin_uri: plugin.uri.clone(),
+                    format: plugin.format,
+                    display_name: display_name.clone(),
+                    pw_node_id: None,
+                    parameters,
+                    active: true,
+                    bypassed: stage.bypassed,
+                    lv2_state: Vec::new(),
+                    oversampling: 1,
+                });
+            }
+            // Parameters and bypass are pushed to the new filter from the
+            // registered instance info once PluginAdded arrives.
+            self.send_command(
+                CommandOrigin::User,
+                PwCommand::AddPlugin {
+                    plugin_uri: plugin.uri.clone(),
+                    instance_id,
+                    display_name: display_name.clone(),
+                    format: plugin.format.as_str().to_string(),
+                    lv2_state: Vec::new(),
+                    oversampling: 1,
+                },
             );
-            let _ = tx.send(PwCommand::AddPlugin {
-                plugin_uri: uri_str.clone(),
-                instance_id,
-                display_name: display_name.clone(),
-                format: format_str,
-                lv2_state: Vec::new(),
-            });
+            self.as_mut().rust_mut().pending_restore_count += 1;
+            if self.rust().restore_started_at.is_none() {
+                self.as_mut().rust_mut().restore_started_at = Some(Instant::now());
+            }
+            names.push(Some(display_name));
         }
 
-        persist_active_plugins(self.rust().plugin_manager.as_ref());
-
-        QString::from(&display_name)
-    }
-
-    pub fn remove_plugin(self: Pin<&mut Self>, node_id: u32) {
-        let instance_id = self.find_instance_id_for_node(node_id);
-        if let Some(instance_id) = instance_id {
-            if let Some(ref tx) = self.rust().cmd_tx {
-                log::info!(
-                    "Remove plugin: node_id={} instance_id={}",
-                    node_id,
-                    instance_id
-                );
-                let _ = tx.send(PwCommand::RemovePlugin { instance_id });
+        let mut links: Vec<SavedPluginLink> = Vec::new();
+        for link in &chain.links {
+            if let (Some(from), Some(to)) = (&names[link.from], &names[link.to]) {
+                links.push(SavedPluginLink {
+                    output_node_name: from.clone(),
+                    output_port_name: format!("output_{}", link.output),
+                    input_node_name: to.clone(),
+                    input_port_name: format!("input_{}", link.input),
+                    output_node_identity: None,
+                    input_node_identity: None,
+                });
             }
-        } else {
-            log::warn!(
-                "remove_plugin: no LV2 instance found for node_id={}",
-                node_id
-            );
         }
+        for link in &chain.external {
+            let Some(stage) = &names[link.stage] else {
+                continue;
+            };
+            links.push(if link.from_stage {
+                SavedPluginLink {
+                    output_node_name: stage.clone(),
+                    output_port_name: format!("output_{}", link.port),
+                    input_node_name: link.node_name.clone(),
+                    input_port_name: link.port_name.clone(),
+                    output_node_identity: None,
+                    input_node_identity: None,
+                }
+            } else {
+                SavedPluginLink {
+                    output_node_name: link.node_name.clone(),
+                    output_port_name: link.port_name.clone(),
+                    input_node_name: stage.clone(),
+                    input_port_name: format!("input_{}", link.port),
+                    output_node_identity: None,
+                    input_node_identity: None,
+                }
+            });
+        }
+        // Reuse the saved-link restore path, which waits for the plugins.
+        self.as_mut().rust_mut().pending_links.extend(links);
+
+        persist_active_plugins(self.rust());
+        names.iter().flatten().count()
     }
 
-    pub fn open_plugin_ui(self: Pin<&mut Self>, node_id: u32) {
-        let instance_id = self.find_instance_id_for_node(node_id);
-        if let Some(instance_id) = instance_id {
-            if let Some(ref tx) = self.rust().cmd_tx {
-                log::info!(
-                    "Open plugin UI: node_id={} instance_id={}",
-                    node_id,
-                    instance_id
-                );
-                let _ = tx.send(PwCommand::OpenPluginUI { instance_id });
+    /// Create the plugins of a `.zestchain` file that are installed, wired as
+    /// in the file. Missing plugins are skipped, so checking the file first
+    /// with `check_chain_file` is up to the caller.
+    fn import_chain_file(mut self: Pin<&mut Self>, path: &std::path::Path) -> QString {
+        let chain = match read_chain_file(path) {
+            Ok(chain) => chain,
+            Err(e) => {
+                log::warn!("import_file: {}", e);
+                let msg = format!("Import failed: {}", e);
+                self.as_mut().error_occurred(QString::from(msg.as_str()));
+                return QString::from("");
             }
-        } else {
-            log::warn!(
-                "open_plugin_ui: no LV2 instance found for node_id={}",
-                node_id
-            );
+        };
+        let (created, skipped) = self.as_mut().create_from_snippet(&chain.snippet());
+        let mut summary = format!("{}: {} plugin(s) created", chain.name, created);
+        if !skipped.is_empty() {
+            summary.push_str(&format!(", skipped {}", skipped.join(", ")));
         }
+        log::info!("Imported {:?}: {}", path, summary);
+        QString::from(summary.as_str())
     }
 
-    pub fn rename_plugin(mut self: Pin<&mut Self>, node_id: u32, new_name: QString) {
-        let name_str: String = new_name.to_string();
-        let instance_id = self.find_instance_id_for_node(node_id);
-        if let Some(instance_id) = instance_id {
-            log::info!(
-                "Rename plugin: node_id={} instance_id={} new_name={}",
-                node_id,
-                instance_id,
-                name_str
-            );
-            if let Some(ref mut mgr) = self.as_mut().rust_mut().plugin_manager
-                && let Some(info) = mgr.get_instance_mut(instance_id)
-            {
-                info.display_name = name_str.clone();
-            }
-            if let Some(ref graph) = self.rust().graph {
-                graph.set_node_description(node_id, &name_str);
-            }
-            persist_active_plugins(self.rust().plugin_manager.as_ref());
-        } else {
-            log::warn!(
-                "rename_plugin: no LV2 instance found for node_id={}",
-                node_id
-            );
+    /// The chain `node_id` belongs to, with its parameters and internal
+    /// links, as a chain file named after its head plugin.
+    fn render_chain_file(&self, node_id: u32) -> Result<ChainFile, String> {
+        let graph = self
+            .rust()
+            .graph
+            .as_ref()
+            .ok_or_else(|| "not connected to PipeWire".to_string())?;
+        let chain = crate::patchbay::chain::detect_chain(graph, node_id);
+        if chain.is_empty() {
+            return Err(format!("node {} is not a plugin", node_id));
         }
-    }
+        let mgr = self
+            .rust()
+            .plugin_manager
+            .as_ref()
+            .ok_or_else(|| "plugins are not loaded".to_string())?;
 
-    pub fn get_plugin_params_json(self: Pin<&mut Self>, node_id: u32) -> QString {
-        let instance_id = self.find_instance_id_for_node(node_id);
-        if let Some(instance_id) = instance_id
-            && let Some(ref mgr) = self.rust().plugin_manager
-            && let Some(info) = mgr.get_instance(instance_id)
-        {
-            let params: Vec<serde_json::Value> = info
-                .parameters
-                .iter()
-                .map(|p| {
-                    serde_json::json!({
-                        "portIndex": p.port_index,
-                        "symbol": p.symbol,
-                        "name": p.name,
-                        "value": p.value,
-                        "min": p.min,
-                        "max": p.max,
-                        "default": p.default,
-                        "isToggle": p.is_toggle,
-                    })
-                })
-                .collect();
-            let result = serde_json::json!({
-                "instanceId": instance_id,
-                "pluginUri": info.plugin_uri,
-                "displayName": info.display_name,
-                "bypassed": info.bypassed,
-                "parameters": params,
-            });
-            let json = serde_json::to_string(&result).unwrap_or_default();
-            return QString::from(&json);
+        let mut instances = Vec::new();
+        for id in &chain {
+            let instance = mgr
+                .active_instances()
+                .values()
+                .find(|info| info.pw_node_id == Some(*id))
+                .ok_or_else(|| format!("node {} is not a hosted plugin", id))?;
+            instances.push(instance);
         }
-        QString::from("{}")
+        let links = build_persistable_links(graph, &self.rust().node_ids);
+        let snippet = PluginSnippet::copy(&instances, &links);
+        let name = strip_instance_suffix(&instances[0].display_name);
+        Ok(ChainFile::new(name, snippet, mgr.available_plugins()))
     }
 
-    pub fn set_plugin_parameter(
-        mut self: Pin<&mut Self>,
-        node_id: u32,
-        port_index: u32,
-        value: f32,
-    ) {
-        let instance_id = self.find_instance_id_for_node(node_id);
-        if let Some(instance_id) = instance_id {
-            if let Some(ref tx) = self.rust().cmd_tx {
-                let _ = tx.send(PwCommand::SetPluginParameter {
-                    instance_id,
-                    port_index: port_index as usize,
-                    value,
-                });
-            }
-            if let Some(ref mut mgr) = self.as_mut().rust_mut().plugin_manager {
-                mgr.update_parameter(instance_id, port_index as usize, value);
-            }
-            self.as_mut().rust_mut().params_dirty = true;
-            if self.rust().params_dirty_since.is_none() {
-                self.as_mut().rust_mut().params_dirty_since = Some(Instant::now());
-            }
+    /// The chain's name (its head plugin's display name) and filter-chain
+    /// config text.
+    fn render_chain_filter_chain(&self, node_id: u32) -> Result<(String, String), String> {
+        let chain = match self.rust().graph {
+            Some(ref graph) => crate::patchbay::chain::detect_chain(graph, node_id),
+            None => Vec::new(),
+        };
+        if chain.is_empty() {
+            return Err(format!("node {} is not a plugin", node_id));
+        }
+        let mgr = self
+            .rust()
+            .plugin_manager
+            .as_ref()
+            .ok_or_else(|| "plugins are not loaded".to_string())?;
+
+        let mut stages = Vec::new();
+        for id in &chain {
+            let instance = mgr
+                .active_instances()
+                .values()
+                .find(|info| info.pw_node_id == Some(*id))
+                .ok_or_else(|| format!("node {} is not a hosted plugin", id))?;
+            let plugin = mgr
+                .find_plugin_with_format(&instance.plugin_uri, instance.format)
+                .ok_or_else(|| format!("'{}' is not in the catalog", instance.display_name))?;
+            stages.push(crate::plugin::filter_chain::ChainStage { instance, plugin });
         }
+        let name = stages[0].instance.display_name.clone();
+        let config = crate::plugin::filter_chain::export_chain(&name, &stages)?;
+        Ok((name, config))
     }
 
-    pub fn set_plugin_bypass(mut self: Pin<&mut Self>, node_id: u32, bypassed: bool) {
-        let instance_id = self.find_instance_id_for_node(node_id);
-        if let Some(instance_id) = instance_id {
-            if let Some(ref tx) = self.rust().cmd_tx {
-                let _ = tx.send(PwCommand::SetPluginBypass {
-                    instance_id,
-                    bypassed,
-                });
-            }
-            if let Some(ref mut mgr) = self.as_mut().rust_mut().plugin_manager
-                && let Some(info) = mgr.get_instance_mut(instance_id)
-            {
-                info.bypassed = bypassed;
-            }
-            self.as_mut().rust_mut().params_dirty = true;
-            if self.rust().params_dirty_since.is_none() {
-                self.as_mut().rust_mut().params_dirty_since = Some(Instant::now());
+    /// Wire chain mixers whose node and ports have appeared, as one
+    /// transaction each.
+    fn wire_pending_chain_mixes(mut self: Pin<&mut Self>) {
+        const CHAIN_MIX_TIMEOUT: Duration = Duration::from_secs(10);
+
+        if self.rust().pending_chain_mixes.is_empty() {
+            return;
+        }
+        let Some(graph) = self.rust().graph.clone() else {
+            return;
+        };
+        let expected_ports = self
+            .rust()
+            .plugin_manager
+            .as_ref()
+            .and_then(|m| m.find_plugin(crate::builtin::CHAIN_MIX_URI))
+            .map(|p| p.audio_inputs + p.audio_outputs)
+            .unwrap_or(0);
+
+        let pending = std::mem::take(&mut self.as_mut().rust_mut().pending_chain_mixes);
+        let mut waiting = Vec::new();
+        for mix in pending {
+            let mix_node = self
+                .rust()
+                .plugin_manager
+                .as_ref()
+                .and_then(|m| m.get_instance(mix.instance_id))
+                .and_then(|info| info.pw_node_id)
+                .filter(|id| graph.get_ports_for_node(*id).len() >= expected_ports);
+
+            match mix_node {
+                Some(mix_node) => {
+                    match crate::patchbay::chain::plan_chain_mix(&graph, &mix.chain, mix_node) {
+                        Ok(ops) => {
+                            self.send_command(
+                                CommandOrigin::User,
+                                PwCommand::Transaction {
+                                    label: "Chain dry/wet".to_string(),
+                                    ops,
+                                },
+                            );
+                        }
+                        Err(e) => log::warn!("add_chain_mix: {}", e),
+                    }
+                }
+                None if mix.created.elapsed() > CHAIN_MIX_TIMEOUT => {
+                    log::warn!(
+                        "add_chain_mix: mixer instance {} never appeared, not wiring chain {:?}",
+                        mix.instance_id,
+                        mix.chain
+                    );
+                }
+                None => waiting.push(mix),
             }
         }
+        self.as_mut().rust_mut().pending_chain_mixes = waiting;
     }
 
-    pub fn start_midi_learn(
+    /// Create the plugins of chain preset `chain_name` and put them on a
+    /// link once they are up, wired to each other as saved. Refuses a chain
+    /// with plugins that are not installed, since leaving them out would
+    /// cut the signal path. Returns a summary, or an empty string when
+    /// nothing was created.
+    pub fn insert_chain_on_link(
         mut self: Pin<&mut Self>,
-        instance_id: u64,
-        port_index: u32,
-        label: QString,
-        mode: QString,
-    ) {
-        let mode_str = mode.to_string();
-        let mapping_mode = match mode_str.as_str() {
-            "toggle" => crate::midi::MappingMode::Toggle,
-            "momentary" => crate::midi::MappingMode::Momentary,
-            _ => crate::midi::MappingMode::Continuous,
+        link_id: u32,
+        chain_name: QString,
+    ) -> QString {
+        let name = chain_name.to_string();
+        if self.rust().plugins_frozen {
+            log::warn!("insert_chain_on_link: plugins are frozen in safe mode");
+            return QString::from("");
+        }
+        if !self
+            .rust()
+            .graph
+            .as_ref()
+            .is_some_and(|g| g.get_link(link_id).is_some())
+        {
+            log::warn!("insert_chain_on_link: link {} not found", link_id);
+            return QString::from("");
+        }
+        let chain = match self.rust().plugin_manager.as_ref() {
+            Some(mgr) => mgr.insertable_chain_preset(&name).cloned(),
+            None => return QString::from(""),
         };
-        let label_str = label.to_string();
-        self.as_mut().rust_mut().midi_learn_target = Some((
-            instance_id,
-            port_index as usize,
-            label_str.clone(),
-            mapping_mode,
-        ));
-        if let Some(ref tx) = self.rust().cmd_tx {
-            let _ = tx.send(PwCommand::StartMidiLearn {
-                instance_id,
-                port_index: port_index as usize,
-                label: label_str,
-                mode: mapping_mode,
+        let chain = match chain {
+            Ok(chain) => chain,
+            Err(e) => {
+                log::warn!("insert_chain_on_link: {}", e);
+                let msg = format!("Cannot insert chain: {}", e);
+                self.as_mut().error_occurred(QString::from(msg.as_str()));
+                return QString::from("");
+            }
+        };
+
+        // Created plugins take consecutive instance IDs, in chain order,
+        // and every one is installed so none are skipped.
+        let head = self.rust().next_instance_id;
+        let (created, _) = self.as_mut().create_from_snippet(&chain.snippet());
+        let tail = self.rust().next_instance_id - 1;
+        self.as_mut()
+            .rust_mut()
+            .pending_chain_inserts
+            .push(PendingChainInsert {
+                link_id,
+                head,
+                tail,
+                created: Instant::now(),
             });
-        }
-    }
 
-    pub fn cancel_midi_learn(mut self: Pin<&mut Self>) {
-        self.as_mut().rust_mut().midi_learn_target = None;
-        if let Some(ref tx) = self.rust().cmd_tx {
-            let _ = tx.send(PwCommand::CancelMidiLearn);
-        }
+        let summary = format!("{}: {} plugin(s) inserted", chain.name, created);
+        log::info!("insert_chain_on_link: link {}: {}", link_id, summary);
+        QString::from(summary.as_str())
     }
 
-    pub fn remove_midi_mapping_for_param(
-        self: Pin<&mut Self>,
+    /// The node of a plugin instance once it is up with all the audio
+    /// ports of `direction` it declares, which are the ones a splice onto
+    /// a link connects.
+    fn plugin_node_ready(
+        &self,
+        graph: &GraphState,
         instance_id: u64,
-        port_index: u32,
-    ) {
-        let target = crate::midi::MidiCcTarget {
-            instance_id,
-            port_index: port_index as usize,
-        };
-        let source = self.rust().midi_mappings
-            .iter()
-            .find(|m| m.target == target)
-            .map(|m| m.source.clone());
-        if let Some(source) = source {
-            if let Some(ref tx) = self.rust().cmd_tx {
-                let _ = tx.send(PwCommand::RemoveMidiMapping(source));
-            }
-        }
+        direction: PortDirection,
+    ) -> Option<u32> {
+        let mgr = self.rust().plugin_manager.as_ref()?;
+        let info = mgr.get_instance(instance_id)?;
+        let expected = mgr
+            .find_plugin_with_format(&info.plugin_uri, info.format)
+            .map(|p| match direction {
+                PortDirection::Input => p.audio_inputs,
+                PortDirection::Output => p.audio_outputs,
+            })
+            .unwrap_or(0)
+            .max(1);
+        info.pw_node_id.filter(|id| {
+            graph
+                .get_ports_for_node(*id)
+                .iter()
+                .filter(|p| p.direction == direction && p.media_type == Some(MediaType::Audio))
+                .count()
+                >= expected
+        })
     }
 
-    pub fn get_midi_mappings_json(self: Pin<&mut Self>) -> QString {
-        let json = serde_json::to_string(&self.rust().midi_mappings).unwrap_or_else(|_| "[]".to_string());
-        QString::from(&json)
-    }
+    /// Put inserted chains whose first and last plugins are up on their link.
+    fn wire_pending_chain_inserts(mut self: Pin<&mut Self>) {
+        const CHAIN_INSERT_TIMEOUT: Duration = Duration::from_secs(10);
 
-    pub fn get_midi_mapping_for_param_json(
-        self: Pin<&mut Self>,
-        instance_id: u64,
-        port_index: u32,
-    ) -> QString {
-        let target = crate::midi::MidiCcTarget {
-            instance_id,
-            port_index: port_index as usize,
+        if self.rust().pending_chain_inserts.is_empty() {
+            return;
+        }
+        let Some(graph) = self.rust().graph.clone() else {
+            return;
         };
-        let mapping = self.rust().midi_mappings
-            .iter()
-            .find(|m| m.target == target);
-        match mapping {
-            Some(m) => {
-                let json = serde_json::to_string(m).unwrap_or_else(|_| "{}".to_string());
-                QString::from(&json)
+
+        let pending = std::mem::take(&mut self.as_mut().rust_mut().pending_chain_inserts);
+        let mut waiting = Vec::new();
+        for insert in pending {
+            let head = self.plugin_node_ready(&graph, insert.head, PortDirection::Input);
+            let tail = self.plugin_node_ready(&graph, insert.tail, PortDirection::Output);
+            match (head, tail) {
+                (Some(head), Some(tail)) => {
+                    self.as_mut()
+                        .insert_nodes_on_link(insert.link_id, head, tail);
+                }
+                _ if insert.created.elapsed() > CHAIN_INSERT_TIMEOUT => {
+                    log::warn!(
+                        "insert_chain_on_link: plugins {}..={} never appeared, not inserting on link {}",
+                        insert.head,
+                        insert.tail,
+                        insert.link_id
+                    );
+                }
+                _ => waiting.push(insert),
             }
-            None => QString::from(""),
         }
+        self.as_mut().rust_mut().pending_chain_inserts = waiting;
     }
 
-    pub fn get_active_plugins_json(self: Pin<&mut Self>) -> QString {
-        if let Some(ref mgr) = self.rust().plugin_manager {
-            let mut entries: Vec<serde_json::Value> = mgr
-                .active_instances()
-                .values()
-                .map(|info| {
-                    let params: Vec<serde_json::Value> = info
-                        .parameters
-                        .iter()
-                        .map(|p| {
-                            serde_json::json!({
-                                "portIndex": p.port_index,
-                                "symbol": p.symbol,
-                                "name": p.name,
-                                "value": p.value,
-                                "min": p.min,
-                                "max": p.max,
-                                "default": p.default,
-                                "isToggle": p.is_toggle,
-                            })
-                        })
-                        .collect();
-                    serde_json::json!({
-                        "instanceId": info.id,
-                        "stableId": info.stable_id,
-                        "pluginUri": info.plugin_uri,
-                        "displayName": info.display_name,
-                        "bypassed": info.bypassed,
-                        "active": info.pw_node_id.is_some(),
-                        "parameters": params,
-                    })
-                })
-                .collect();
-            entries.sort_by(|a, b| {
-                let a_name = a["displayName"].as_str().unwrap_or("");
-                let b_name = b["displayName"].as_str().unwrap_or("");
-                a_name.cmp(b_name)
-            });
-            let json = serde_json::to_string(&entries).unwrap_or_default();
-            QString::from(&json)
-        } else {
-            QString::from("[]")
-        }
+    pub fn insert_node_on_link(self: Pin<&mut Self>, link_id: u32, node_id: u32) {
+        self.insert_nodes_on_link(link_id, node_id, node_id);
     }
 
-    pub fn remove_plugin_by_stable_id(mut self: Pin<&mut Self>, stable_id: QString) {
-        let sid: String = stable_id.to_string();
+    /// Put the plugins from `head_id` to `tail_id`, already linked to each
+    /// other, on a link: its source then feeds `head_id` and `tail_id`
+    /// feeds its destination. A single plugin is its own head and tail.
+    fn insert_nodes_on_link(mut self: Pin<&mut Self>, link_id: u32, head_id: u32, tail_id: u32) {
+        let graph = self.rust().graph.clone();
+        let Some(ref graph) = graph else { return };
 
-        let instance_id = self
-            .rust()
-            .plugin_manager
-            .as_ref()
-            .and_then(|mgr| mgr.instance_id_for_stable_id(&sid));
+        let Some(link) = graph.get_link(link_id) else {
+            log::warn!("insert_node_on_link: link {} not found", link_id);
+            return;
+        };
 
-        if let Some(instance_id) = instance_id {
-            if let Some(ref tx) = self.rust().cmd_tx {
-                let _ = tx.send(PwCommand::RemovePlugin { instance_id });
-            }
-            if let Some(ref mut mgr) = self.as_mut().rust_mut().plugin_manager {
-                mgr.remove_instance(instance_id);
-            }
-            persist_active_plugins(self.rust().plugin_manager.as_ref());
-            log::info!("Removed plugin instance (stable_id={})", sid);
-        } else {
+        let (Some(node), Some(tail)) = (graph.get_node(head_id), graph.get_node(tail_id)) else {
             log::warn!(
-                "remove_plugin_by_stable_id: no instance found for stable_id={}",
-                sid
+                "insert_node_on_link: node {} or {} not found",
+                head_id,
+                tail_id
             );
+            return;
+        };
+
+        for node_id in [head_id, tail_id] {
+            if link.output_node_id == node_id || link.input_node_id == node_id {
+                log::warn!(
+                    "insert_node_on_link: node {} is already part of link {}, ignoring",
+                    node_id,
+                    link_id
+                );
+                return;
+            }
         }
-    }
 
-    pub fn reset_plugin_params_by_stable_id(mut self: Pin<&mut Self>, stable_id: QString) {
-        let sid: String = stable_id.to_string();
+        if node.node_type != Some(NodeType::Plugin) || tail.node_type != Some(NodeType::Plugin) {
+            log::warn!("insert_node_on_link: node {} is not an LV2 plugin, ignoring", head_id);
+            return;
+        }
 
-        let resets: Vec<(u64, usize, f32)> = if let Some(ref mgr) = self.rust().plugin_manager {
-            if let Some(info) = mgr.find_by_stable_id(&sid) {
-                info.parameters
-                    .iter()
-                    .map(|p| (info.id, p.port_index, p.default))
-                    .collect()
-            } else {
-                Vec::new()
-            }
-        } else {
-            Vec::new()
-        };
+        let mut node_inputs: Vec<_> = graph
+            .get_ports_for_node(head_id)
+            .into_iter()
+            .filter(|p| p.direction == PortDirection::Input && p.media_type == Some(crate::pipewire::MediaType::Audio))
+            .collect();
+        let mut node_outputs: Vec<_> = graph
+            .get_ports_for_node(tail_id)
+            .into_iter()
+            .filter(|p| p.direction == PortDirection::Output && p.media_type == Some(crate::pipewire::MediaType::Audio))
+            .collect();
 
-        if resets.is_empty() {
+        if node_inputs.is_empty() || node_outputs.is_empty() {
+            log::warn!("insert_node_on_link: node {} has no audio input/output ports", head_id);
             return;
         }
 
-        let instance_id = resets[0].0;
-        for (_, port_index, default) in &resets {
-            if let Some(ref mut mgr) = self.as_mut().rust_mut().plugin_manager {
-                mgr.update_parameter(instance_id, *port_index, *default);
-            }
-            if let Some(ref tx) = self.rust().cmd_tx {
-                let _ = tx.send(PwCommand::SetPluginParameter {
-                    instance_id,
-                    port_index: *port_index,
-                    value: *default,
-                });
+        node_inputs.sort_by(|a, b| crate::pipewire::state::natural_cmp(&a.name, &b.name));
+        node_outputs.sort_by(|a, b| crate::pipewire::state::natural_cmp(&a.name, &b.name));
+
+        let upstream_out = link.output_port_id;
+        let downstream_in = link.input_port_id;
+
+        let upstream_node_id = link.output_node_id;
+        let downstream_node_id = link.input_node_id;
+
+        let upstream_ports: Vec<_> = graph
+            .get_ports_for_node(upstream_node_id)
+            .into_iter()
+            .filter(|p| p.direction == PortDirection::Output && p.media_type == Some(crate::pipewire::MediaType::Audio))
+            .collect();
+        let downstream_ports: Vec<_> = graph
+            .get_ports_for_node(downstream_node_id)
+            .into_iter()
+            .filter(|p| p.direction == PortDirection::Input && p.media_type == Some(crate::pipewire::MediaType::Audio))
+            .collect();
+
+        let upstream_idx = upstream_ports.iter().position(|p| p.id == upstream_out).unwrap_or(0);
+        let downstream_idx = downstream_ports.iter().position(|p| p.id == downstream_in).unwrap_or(0);
+
+        let all_links = graph.get_all_links();
+        let mut links_to_remove = Vec::new();
+        let mut rewire_pairs: Vec<(u32, usize, u32, usize)> = Vec::new();
+
+        for existing in &all_links {
+            if existing.output_node_id == upstream_node_id && existing.input_node_id == downstream_node_id {
+                let u_idx = upstream_ports.iter().position(|p| p.id == existing.output_port_id);
+                let d_idx = downstream_ports.iter().position(|p| p.id == existing.input_port_id);
+                if let (Some(ui), Some(di)) = (u_idx, d_idx) {
+                    links_to_remove.push(existing.id);
+                    rewire_pairs.push((existing.output_port_id, ui, existing.input_port_id, di));
+                }
             }
         }
 
-        self.as_mut().rust_mut().params_dirty = true;
-        if self.rust().params_dirty_since.is_none() {
-            self.as_mut().rust_mut().params_dirty_since = Some(Instant::now());
+        if links_to_remove.is_empty() {
+            links_to_remove.push(link_id);
+            rewire_pairs.push((upstream_out, upstream_idx, downstream_in, downstream_idx));
+        }
+
+        let mut ops: Vec<LinkOp> = links_to_remove
+            .iter()
+            .map(|lid| LinkOp::Disconnect { link_id: *lid })
+            .collect();
+
+        let max_in = node_inputs.len() - 1;
+        let max_out = node_outputs.len() - 1;
+        for (up_port, up_idx, down_port, down_idx) in &rewire_pairs {
+            let in_idx = *up_idx.min(&max_in);
+            let out_idx = *down_idx.min(&max_out);
+
+            ops.push(LinkOp::Connect {
+                output_port_id: *up_port,
+                input_port_id: node_inputs[in_idx].id,
+            });
+            ops.push(LinkOp::Connect {
+                output_port_id: node_outputs[out_idx].id,
+                input_port_id: *down_port,
+            });
+        }
+
+        let sent = self.try_send_command(
+            CommandOrigin::InsertNodeOnLink,
+            PwCommand::Transaction {
+                label: format!("Insert {}", node.display_name()),
+                ops,
+            },
+            false,
+        );
+        if let Err(path) = sent {
+            let msg = format!("Not inserted: it would close a feedback loop: {}", path);
+            self.as_mut().error_occurred(QString::from(msg.as_str()));
+            return;
         }
+
         log::info!(
-            "Reset {} params to defaults for stable_id={}",
-            resets.len(),
-            sid
+            "insert_node_on_link: inserted node {} on {} links between nodes {} and {}",
+            head_id,
+            links_to_remove.len(),
+            upstream_node_id,
+            downstream_node_id
         );
-    }
 
-    pub fn set_plugin_param_by_stable_id(
-        mut self: Pin<&mut Self>,
-        stable_id: QString,
-        port_index: u32,
-        value: f32,
-    ) {
-        let sid: String = stable_id.to_string();
+        let mut rule_data: Vec<(Node, Node, Port, Port)> = Vec::new();
+        let mut new_link_data: Vec<(Node, Node, Port, Port, Node, Node, Port, Port)> = Vec::new();
 
-        let instance_id = self
-            .rust()
-            .plugin_manager
-            .as_ref()
-            .and_then(|mgr| mgr.instance_id_for_stable_id(&sid));
+        {
+            let max_in = node_inputs.len() - 1;
+            let max_out = node_outputs.len() - 1;
 
-        if let Some(instance_id) = instance_id {
-            if let Some(ref mut mgr) = self.as_mut().rust_mut().plugin_manager {
-                mgr.update_parameter(instance_id, port_index as usize, value);
+            for (up_port_id, up_idx, down_port_id, down_idx) in &rewire_pairs {
+                if let (Some(source_node), Some(target_node), Some(out_port), Some(in_port)) = (
+                    graph.get_node(upstream_node_id),
+                    graph.get_node(downstream_node_id),
+                    graph.get_port(*up_port_id),
+                    graph.get_port(*down_port_id),
+                ) {
+                    rule_data.push((source_node, target_node, out_port, in_port));
+                }
+
+                let in_idx = *up_idx.min(&max_in);
+                let out_idx = *down_idx.min(&max_out);
+
+                if let (Some(up_node), Some(ins_node), Some(up_port), Some(ins_in_port)) = (
+                    graph.get_node(upstream_node_id),
+                    graph.get_node(head_id),
+                    graph.get_port(*up_port_id),
+                    graph.get_port(node_inputs[in_idx].id),
+                ) {
+                    if let (Some(ins_node2), Some(dn_node), Some(ins_out_port), Some(dn_port)) = (
+                        graph.get_node(tail_id),
+                        graph.get_node(downstream_node_id),
+                        graph.get_port(node_outputs[out_idx].id),
+                        graph.get_port(*down_port_id),
+                    ) {
+                        new_link_data.push((
+                            up_node, ins_node, up_port, ins_in_port,
+                            ins_node2, dn_node, ins_out_port, dn_port,
+                        ));
+                    }
+                }
             }
-            if let Some(ref tx) = self.rust().cmd_tx {
-                let _ = tx.send(PwCommand::SetPluginParameter {
-                    instance_id,
-                    port_index: port_index as usize,
-                    value,
-                });
+        }
+
+        let mut rules_changed = false;
+        if let Some(ref mut patchbay) = self.as_mut().rust_mut().patchbay {
+            for (source_node, target_node, out_port, in_port) in &rule_data {
+                if patchbay.unlearn_from_link(source_node, target_node, out_port, in_port) {
+                    log::info!(
+                        "insert_node_on_link: unlearned rule {}:{} -> {}:{}",
+                        source_node.display_name(),
+                        out_port.name,
+                        target_node.display_name(),
+                        in_port.name,
+                    );
+                    rules_changed = true;
+                }
             }
-            self.as_mut().rust_mut().params_dirty = true;
-            if self.rust().params_dirty_since.is_none() {
-                self.as_mut().rust_mut().params_dirty_since = Some(Instant::now());
+
+            for (up_node, ins_node, up_port, ins_in_port, ins_node2, dn_node, ins_out_port, dn_port) in &new_link_data {
+                if patchbay.learn_from_link(up_node, ins_node, up_port, ins_in_port) {
+                    log::info!(
+                        "insert_node_on_link: learned rule {}:{} -> {}:{}",
+                        up_node.display_name(),
+                        up_port.name,
+                        ins_node.display_name(),
+                        ins_in_port.name,
+                    );
+                    rules_changed = true;
+                }
+                if patchbay.learn_from_link(ins_node2, dn_node, ins_out_port, dn_port) {
+                    log::info!(
+                        "insert_node_on_link: learned rule {}:{} -> {}:{}",
+                        ins_node2.display_name(),
+                        ins_out_port.name,
+                        dn_node.display_name(),
+                        dn_port.name,
+                    );
+                    rules_changed = true;
+                }
             }
         }
+
+        if rules_changed {
+            save_rules(self.rust().patchbay.as_ref());
+        }
+
+        self.as_mut().rust_mut().links_dirty = true;
+        if self.rust().links_dirty_since.is_none() {
+            self.as_mut().rust_mut().links_dirty_since = Some(Instant::now());
+        }
     }
 
-    pub fn get_window_geometry_json(self: Pin<&mut Self>) -> QString {
-        let path = config_path("window.json");
-        let json = match std::fs::read_to_string(&path) {
-            Ok(s) => s,
-            Err(_) => "{}".to_string(),
-        };
-        QString::from(&json)
+    pub fn request_quit(mut self: Pin<&mut Self>) {
+        log::info!("Quit requested");
+        self.as_mut().collect_lv2_states();
+        self.save_session();
+        std::process::exit(0);
     }
 
-    pub fn save_window_geometry(self: Pin<&mut Self>, json: QString) {
-        let path = config_path("window.json");
-        let s: String = json.to_string();
-        if let Some(parent) = path.parent() {
-            let _ = std::fs::create_dir_all(parent);
+    /// Ask the PipeWire thread for the state of every LV2 plugin and wait
+    /// for it, so plugins that keep settings outside their ports (file
+    /// paths, loaded samples) are saved as they are now.
+    fn collect_lv2_states(mut self: Pin<&mut Self>) {
+        let has_lv2 = self.rust().plugin_manager.as_ref().is_some_and(|mgr| {
+            mgr.active_instances()
+                .values()
+                .any(|info| info.format == crate::plugin::PluginFormat::Lv2)
+        });
+        if !has_lv2 || self.rust().cmd_tx.is_none() {
+            return;
         }
-        if let Err(e) = std::fs::write(&path, &s) {
-            log::error!("Failed to save window geometry to {:?}: {}", path, e);
+        self.send_command(CommandOrigin::System, PwCommand::SaveLv2States);
+        let deadline = Instant::now() + LV2_STATE_TIMEOUT;
+        loop {
+            let Some(rx) = self.rust().event_rx.as_ref() else {
+                return;
+            };
+            match rx.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+                Ok(PwEvent::Plugin(PluginEvent::Lv2StateSaved { instance_id, state })) => {
+                    self.as_mut().store_lv2_state(instance_id, state);
+                }
+                Ok(PwEvent::Plugin(PluginEvent::Lv2StatesSaved)) => return,
+                // Nothing else matters on the way out.
+                Ok(_) => {}
+                Err(_) => {
+                    log::warn!("LV2 plugin state was not saved in time");
+                    return;
+                }
+            }
         }
     }
 
-    pub fn get_viewport_json(self: Pin<&mut Self>) -> QString {
-        let path = config_path("viewport.json");
-        let json = match std::fs::read_to_string(&path) {
-            Ok(s) => s,
-            Err(_) => "{}".to_string(),
-        };
-        QString::from(&json)
+    /// Keep `state` as instance `instance_id`'s LV2 state. Returns whether
+    /// it differs from what was kept before.
+    fn store_lv2_state(
+        mut self: Pin<&mut Self>,
+        instance_id: u64,
+        state: Vec<crate::lv2::state::StateEntry>,
+    ) -> bool {
+        if let Some(ref mut mgr) = self.as_mut().rust_mut().plugin_manager {
+            if let Some(info) = mgr.get_instance_mut(instance_id) {
+                if info.lv2_state != state {
+                    info.lv2_state = state;
+                    return true;
+                }
+            }
+        }
+        false
     }
 
-    pub fn save_viewport(self: Pin<&mut Self>, json: QString) {
-        let path = config_path("viewport.json");
-        let s: String = json.to_string();
-        if let Some(parent) = path.parent() {
-            let _ = std::fs::create_dir_all(parent);
-        }
-        if let Err(e) = std::fs::write(&path, &s) {
-            log::error!("Failed to save viewport to {:?}: {}", path, e);
+    fn is_lv2_instance(&self, instance_id: u64) -> bool {
+        self.rust()
+            .plugin_manager
+            .as_ref()
+            .and_then(|mgr| mgr.get_instance(instance_id))
+            .is_some_and(|info| info.format == crate::plugin::PluginFormat::Lv2)
+    }
+
+    /// Persist plugins, links and UI windows and close plugin UIs, before
+    /// the process exits or restarts.
+    fn save_session(&self) {
+        remove_crash_marker();
+        self.revert_temporary_routes_for_exit();
+        persist_lv2_links(
+            self.rust().graph.as_ref(),
+            &self.rust().dormant_links,
+            &self.rust().node_ids,
+        );
+        persist_active_plugins(self.rust());
+        save_ui_windows(&self.rust().ui_windows);
+        save_plugin_cpu_history(self.rust());
+        if !crate::PLUGINS_FROZEN.load(std::sync::atomic::Ordering::SeqCst) {
+            save_known_good_plugins();
         }
+        crate::control::flush();
+        crate::lv2::ui::shutdown_gtk_thread();
     }
 
-    pub fn get_rules_json(self: Pin<&mut Self>) -> QString {
-        if let Some(ref patchbay) = self.rust().patchbay {
-            let json_rules: Vec<serde_json::Value> = patchbay
-                .rules()
-                .iter()
-                .map(|r| {
-                    let mappings: Vec<serde_json::Value> = r
-                        .port_mappings
-                        .iter()
-                        .map(|m| {
-                            serde_json::json!({
-                                "outputPort": m.output_port_name,
-                                "inputPort": m.input_port_name,
-                            })
-                        })
-                        .collect();
-                    serde_json::json!({
-                        "id": r.id,
-                        "sourcePattern": r.source_pattern,
-                        "sourceType": r.source_node_type.map(rules::node_type_label).unwrap_or("Any"),
-                        "targetPattern": r.target_pattern,
-                        "targetType": r.target_node_type.map(rules::node_type_label).unwrap_or("Any"),
-                        "sourceLabel": r.source_label(),
-                        "targetLabel": r.target_label(),
-                        "enabled": r.enabled,
-                        "portMappings": mappings,
-                    })
-                })
-                .collect();
-            let json = serde_json::to_string(&json_rules).unwrap_or_default();
-            QString::from(&json)
-        } else {
-            QString::from("[]")
-        }
+    /// The PipeWire instance to connect to: `--remote`, then the preference.
+    /// `None` leaves it to `PIPEWIRE_REMOTE` or the default instance.
+    /// A copy of the graph for the rules engine; empty before PipeWire is
+    /// started.
+    fn graph_snapshot(&self) -> GraphSnapshot {
+        self.rust()
+            .graph
+            .as_deref()
+            .map(GraphSnapshot::from_state)
+            .unwrap_or_default()
     }
 
-    pub fn toggle_rule(mut self: Pin<&mut Self>, rule_id: QString) {
-        let id: String = rule_id.to_string();
-        if let Some(ref mut patchbay) = self.as_mut().rust_mut().patchbay {
-            patchbay.toggle_rule(&id);
+    /// Start the PipeWire thread on `graph`, or the simulated one in demo
+    /// mode.
+    fn start_pipewire(
+        &self,
+        graph: Arc<GraphState>,
+        heartbeat: Heartbeat,
+    ) -> (Receiver<PwEvent>, Sender<PwCommand>) {
+        let prefs = &self.rust().prefs;
+        let tick_interval_ms = self.profile_settings().pw_tick_interval_ms;
+        if crate::DEMO_MODE.load(std::sync::atomic::Ordering::SeqCst) {
+            return crate::pipewire::start_demo(graph, tick_interval_ms, heartbeat);
         }
-        save_rules(self.rust().patchbay.as_ref());
+        crate::pipewire::start(
+            graph,
+            tick_interval_ms,
+            prefs.pw_operation_cooldown_ms,
+            prefs.pw_connect_retries,
+            self.pipewire_remote(),
+            heartbeat,
+        )
     }
 
-    pub fn remove_rule(mut self: Pin<&mut Self>, rule_id: QString) {
-        let id: String = rule_id.to_string();
-        if let Some(ref mut patchbay) = self.as_mut().rust_mut().patchbay {
-            patchbay.remove_rule(&id);
-        }
-        save_rules(self.rust().patchbay.as_ref());
+    /// The profile in use: the chosen one, or the battery one on battery.
+    fn active_profile(&self) -> PerformanceProfile {
+        let prefs = &self.rust().prefs;
+        self.rust().profile_chooser.active(
+            PerformanceProfile::parse(&prefs.performance_profile).unwrap_or_default(),
+            PerformanceProfile::parse(&prefs.battery_profile),
+        )
     }
 
-    pub fn apply_rules(mut self: Pin<&mut Self>) {
-        let commands = if let Some(ref mut patchbay) = self.as_mut().rust_mut().patchbay {
-            patchbay.scan()
-        } else {
-            Vec::new()
-        };
-        if let Some(ref tx) = self.rust().cmd_tx {
-            for cmd in commands {
-                let _ = tx.send(cmd);
-            }
-        }
+    fn profile_settings(&self) -> ProfileSettings {
+        self.active_profile()
+            .settings(self.rust().prefs.balanced_settings())
     }
 
-    pub fn snapshot_rules(mut self: Pin<&mut Self>) {
-        if let Some(ref mut patchbay) = self.as_mut().rust_mut().patchbay {
-            patchbay.snapshot_current_connections();
+    /// Put the profile in use into effect. The PipeWire tick is set here;
+    /// QML re-reads its intervals when `performance_profile` changes.
+    fn apply_profile(mut self: Pin<&mut Self>) {
+        let profile = self.active_profile();
+        let settings = self.profile_settings();
+        self.send_command(
+            CommandOrigin::System,
+            PwCommand::SetTickInterval {
+                interval_ms: settings.pw_tick_interval_ms,
+            },
+        );
+        if self.rust().performance_profile.to_string() != profile.as_str() {
+            log::info!("Performance profile: {}", profile.as_str());
+            self.as_mut()
+                .set_performance_profile(QString::from(profile.as_str()));
         }
-        save_rules(self.rust().patchbay.as_ref());
-        log::info!("Snapshot: replaced rules with current connections");
     }
 
-    pub fn toggle_patchbay(mut self: Pin<&mut Self>, enabled: bool) {
-        if let Some(ref mut patchbay) = self.as_mut().rust_mut().patchbay {
-            patchbay.enabled = enabled;
-        }
-        self.as_mut().set_patchbay_enabled(enabled);
+    fn pipewire_remote(&self) -> Option<String> {
+        crate::PIPEWIRE_REMOTE
+            .get()
+            .cloned()
+            .or_else(|| Some(self.rust().prefs.pipewire_remote.clone()))
+            .filter(|r| !r.is_empty())
     }
 
-    pub fn get_node_names_json(self: Pin<&mut Self>) -> QString {
-        if let Some(ref graph) = self.rust().graph {
-            let nodes = graph.get_all_nodes();
-            let mut entries: Vec<serde_json::Value> = Vec::new();
-
-            for n in nodes.iter().filter(|n| n.ready) {
-                let media_str = match n.media_type {
-                    Some(crate::pipewire::MediaType::Audio) => "Audio",
-                    Some(crate::pipewire::MediaType::Video) => "Video",
-                    Some(crate::pipewire::MediaType::Midi) => "Midi",
-                    None => "Unknown",
-                };
-
-                if n.is_bridge {
-                    // For bridge nodes, list each device sub-node separately
-                    let groups = graph.get_bridge_port_groups(n.id);
-                    for (_group, device_name) in &groups {
-                        entries.push(serde_json::json!({
-                            "name": device_name,
-                            "type": "Duplex",
-                            "mediaType": media_str,
-                        }));
-                    }
-                } else {
-                    let type_str = match n.node_type {
-                        Some(NodeType::Sink) => "Sink",
-                        Some(NodeType::Source) => "Source",
-                        Some(NodeType::StreamOutput) => "App Out",
-                        Some(NodeType::StreamInput) => "App In",
-                        Some(NodeType::Duplex) => "Duplex",
-                        Some(NodeType::Plugin) => "Plugin",
-                        None => "Unknown",
-                    };
-                    entries.push(serde_json::json!({
-                        "name": n.display_name(),
-                        "type": type_str,
-                        "mediaType": media_str,
-                    }));
-                }
-            }
+    pub fn get_compare_json(self: Pin<&mut Self>) -> QString {
+        let json = match self.rust().compare {
+            Some(ref compare) => compare.snapshot(),
+            None => serde_json::Value::Null,
+        };
+        QString::from(&json.to_string())
+    }
 
-            entries.sort_by(|a, b| {
-                let a_name = a["name"].as_str().unwrap_or("");
-                let b_name = b["name"].as_str().unwrap_or("");
-                a_name.cmp(b_name)
-            });
-            entries.dedup_by(|a, b| {
-                a["name"].as_str() == b["name"].as_str() && a["type"].as_str() == b["type"].as_str()
-            });
-            let json = serde_json::to_string(&entries).unwrap_or_default();
-            QString::from(&json)
-        } else {
-            QString::from("[]")
-        }
+    pub fn set_compare_remote(mut self: Pin<&mut Self>, remote: QString) {
+        self.as_mut().rust_mut().prefs.compare_remote = remote.to_string().trim().to_string();
+        save_preferences(&self.rust().prefs);
+        self.as_mut().restart_compare();
     }
 
-    pub fn add_rule(
+    pub fn compare_connect_ports(
         mut self: Pin<&mut Self>,
-        source_pattern: QString,
-        source_type: QString,
-        target_pattern: QString,
-        target_type: QString,
+        output_port_id: u32,
+        input_port_id: u32,
     ) {
-        let src_pat: String = source_pattern.to_string();
-        let src_type: String = source_type.to_string();
-        let tgt_pat: String = target_pattern.to_string();
-        let tgt_type: String = target_type.to_string();
-
-        let src_node_type = parse_node_type(&src_type);
-        let tgt_node_type = parse_node_type(&tgt_type);
-
-        let rule = crate::patchbay::rules::AutoConnectRule::new(
-            src_pat,
-            src_node_type,
-            tgt_pat,
-            tgt_node_type,
-            None,
-        );
+        let learn = self.rust().prefs.auto_learn_rules;
+        if let Some(ref mut compare) = self.as_mut().rust_mut().compare {
+            compare.connect(output_port_id, input_port_id, learn);
+        }
+    }
 
-        if let Some(ref mut patchbay) = self.as_mut().rust_mut().patchbay {
-            patchbay.add_rule(rule);
+    pub fn compare_disconnect_link(mut self: Pin<&mut Self>, link_id: u32) {
+        let learn = self.rust().prefs.auto_learn_rules;
+        if let Some(ref mut compare) = self.as_mut().rust_mut().compare {
+            compare.disconnect(link_id, learn);
         }
-        save_rules(self.rust().patchbay.as_ref());
     }
 
-    pub fn get_preferences_json(self: Pin<&mut Self>) -> QString {
-        let json = serde_json::to_string(&self.rust().prefs).unwrap_or_default();
-        QString::from(&json)
+    pub fn toggle_compare_rules(mut self: Pin<&mut Self>, enabled: bool) {
+        if let Some(ref mut compare) = self.as_mut().rust_mut().compare {
+            compare.set_rules_enabled(enabled);
+        }
+        self.as_mut().compare_changed();
     }
 
-    pub fn set_preference(mut self: Pin<&mut Self>, key: QString, value: QString) {
-        let key_str: String = key.to_string();
-        let val_str: String = value.to_string();
+    /// Hand out a new remote API token with `scope` (`read`, `control` or
+    /// `plugins`) and return it; empty when the scope is unknown.
+    pub fn generate_remote_api_token(
+        mut self: Pin<&mut Self>,
+        name: QString,
+        scope: QString,
+    ) -> QString {
+        let Some(scope) = ApiScope::parse(&scope.to_string()) else {
+            log::warn!("Unknown remote API token scope {}", scope);
+            return QString::default();
+        };
+        let name = name.to_string().trim().to_string();
+        let token = ApiToken {
+            name: if name.is_empty() {
+                format!("{} token", scope.as_str())
+            } else {
+                name
+            },
+            token: crate::remote_api::generate_token(),
+            scope,
+        };
+        let value = QString::from(&token.token);
+        log::info!(
+            "Remote API: new {} token \"{}\"",
+            scope.as_str(),
+            token.name
+        );
+        self.as_mut().rust_mut().prefs.remote_api_tokens.push(token);
+        save_preferences(&self.rust().prefs);
+        self.as_mut().restart_remote_api();
+        value
+    }
 
-        match key_str.as_str() {
-            "rule_settle_ms" => {
-                if let Ok(v) = val_str.parse::<u64>() {
-                    self.as_mut().rust_mut().prefs.rule_settle_ms = v.clamp(0, 10000);
-                }
-            }
-            "params_persist_ms" => {
-                if let Ok(v) = val_str.parse::<u64>() {
-                    self.as_mut().rust_mut().prefs.params_persist_ms = v.clamp(100, 30000);
-                }
-            }
-            "links_persist_ms" => {
-                if let Ok(v) = val_str.parse::<u64>() {
-                    self.as_mut().rust_mut().prefs.links_persist_ms = v.clamp(100, 30000);
-                }
-            }
-            "poll_interval_ms" => {
-                if let Ok(v) = val_str.parse::<u64>() {
-                    self.as_mut().rust_mut().prefs.poll_interval_ms = v.clamp(16, 1000);
-                }
-            }
-            "auto_learn_rules" => {
-                if let Ok(v) = val_str.parse::<bool>() {
-                    self.as_mut().rust_mut().prefs.auto_learn_rules = v;
-                }
-            }
-            "start_minimized" => {
-                if let Ok(v) = val_str.parse::<bool>() {
-                    self.as_mut().rust_mut().prefs.start_minimized = v;
-                }
-            }
-            "close_to_tray" => {
-                if let Ok(v) = val_str.parse::<bool>() {
-                    self.as_mut().rust_mut().prefs.close_to_tray = v;
-                }
-            }
-            "pw_tick_interval_ms" => {
-                if let Ok(v) = val_str.parse::<u64>() {
-                    self.as_mut().rust_mut().prefs.pw_tick_interval_ms = v.clamp(1, 200);
-                }
-            }
-            "pw_operation_cooldown_ms" => {
-                if let Ok(v) = val_str.parse::<u64>() {
-                    self.as_mut().rust_mut().prefs.pw_operation_cooldown_ms = v.clamp(10, 1000);
-                }
-            }
-            _ => {
-                log::warn!("Unknown preference key: {}", key_str);
-                return;
-            }
+    pub fn revoke_remote_api_token(mut self: Pin<&mut Self>, token: QString) {
+        let token = token.to_string();
+        let tokens = &mut self.as_mut().rust_mut().prefs.remote_api_tokens;
+        let before = tokens.len();
+        tokens.retain(|t| t.token != token);
+        if tokens.len() == before {
+            return;
         }
-
-        log::info!("Preference updated: {} = {}", key_str, val_str);
         save_preferences(&self.rust().prefs);
+        self.as_mut().restart_remote_api();
     }
 
-    pub fn reset_preferences(mut self: Pin<&mut Self>) {
-        self.as_mut().rust_mut().prefs = Preferences::default();
+    pub fn get_remote_instance_json(self: Pin<&mut Self>) -> QString {
+        let json = match self.rust().remote_instance {
+            Some(ref remote) => remote.snapshot(),
+            None => serde_json::Value::Null,
+        };
+        QString::from(&json.to_string())
+    }
+
+    pub fn set_remote_instance(mut self: Pin<&mut Self>, address: QString, token: QString) {
+        let prefs = &mut self.as_mut().rust_mut().prefs;
+        prefs.remote_instance = address.to_string().trim().to_string();
+        prefs.remote_instance_token = token.to_string().trim().to_string();
         save_preferences(&self.rust().prefs);
-        log::info!("Preferences reset to defaults");
+        self.as_mut().restart_remote_instance();
     }
 
-    pub fn get_poll_interval_ms(self: Pin<&mut Self>) -> i32 {
-        self.rust().prefs.poll_interval_ms as i32
+    /// Send one remote API request, in its JSON form, to the remote instance.
+    pub fn send_remote_instance_request(self: Pin<&mut Self>, request_json: QString) {
+        let Some(ref remote) = self.rust().remote_instance else {
+            return;
+        };
+        match serde_json::from_str::<crate::remote_api::RemoteRequest>(&request_json.to_string()) {
+            Ok(request) => remote.send(request),
+            Err(e) => log::warn!("Invalid remote instance request {}: {}", request_json, e),
+        }
     }
 
-    pub fn get_cpu_history(self: Pin<&mut Self>) -> QString {
-        let json = serde_json::to_string(&self.rust().cpu_history).unwrap_or_default();
-        QString::from(&json)
+    pub fn get_pipewire_remote(self: Pin<&mut Self>) -> QString {
+        QString::from(&self.pipewire_remote().unwrap_or_default())
     }
 
-    pub fn get_plugin_cpu_json(self: Pin<&mut Self>) -> QString {
-        use crate::plugin::cpu_stats::global_cpu_tracker;
+    /// PipeWire sockets in the runtime directory, e.g. a second seat's or one
+    /// bind-mounted from a container.
+    pub fn get_pipewire_remotes_json(self: Pin<&mut Self>) -> QString {
+        use std::os::unix::fs::FileTypeExt;
 
-        let snapshots = global_cpu_tracker().take_all_snapshots();
-        let items: Vec<serde_json::Value> = snapshots
+        let mut remotes: Vec<String> = std::env::var_os("XDG_RUNTIME_DIR")
+            .and_then(|dir| std::fs::read_dir(dir).ok())
             .into_iter()
-            .map(|(id, name, snap)| {
-                serde_json::json!({
-                    "id": id,
-                    "name": name,
-                    "dspPercent": (snap.dsp_percent * 100.0).round() / 100.0,
-                    "avgUs": snap.avg_ns / 1000,
-                    "lastUs": snap.last_ns / 1000,
-                    "calls": snap.calls,
-                    "workerPercent": (snap.worker_percent * 100.0).round() / 100.0,
-                    "workerAvgUs": snap.worker_avg_ns / 1000,
-                })
-            })
+            .flatten()
+            .flatten()
+            .filter(|entry| entry.file_type().is_ok_and(|t| t.is_socket()))
+            .filter_map(|entry| entry.file_name().into_string().ok())
+            .filter(|name| name.starts_with("pipewire-") && !name.ends_with("-manager"))
             .collect();
-        let json = serde_json::to_string(&items).unwrap_or_default();
-        QString::from(&json)
-    }
-
-    pub fn get_default_node(self: Pin<&mut Self>) -> QString {
-        let path = config_path("default_node.txt");
-        match std::fs::read_to_string(&path) {
-            Ok(s) => QString::from(&s.trim().to_string()),
-            Err(_) => QString::from(""),
+        remotes.sort();
+        if let Some(current) = self.pipewire_remote()
+            && !remotes.contains(&current)
+        {
+            remotes.insert(0, current);
         }
+        QString::from(&serde_json::to_string(&remotes).unwrap_or_default())
     }
 
-    pub fn set_default_node(mut self: Pin<&mut Self>, layout_key: QString) {
-        let key: String = layout_key.to_string();
-        let path = config_path("default_node.txt");
-        if let Some(parent) = path.parent() {
-            let _ = std::fs::create_dir_all(parent);
-        }
-        if key.is_empty() {
-            let _ = std::fs::remove_file(&path);
-            log::info!("Cleared default node");
-        } else {
-            if let Err(e) = std::fs::write(&path, &key) {
-                log::error!("Failed to save default node to {:?}: {}", path, e);
-            } else {
-                log::info!("Set default node: {}", key);
+    /// Switch to another PipeWire instance. The PipeWire thread owns the
+    /// connection and every hosted plugin, so ZestBay saves its session and
+    /// restarts itself connected to the new instance.
+    pub fn switch_pipewire_remote(mut self: Pin<&mut Self>, remote: QString) {
+        let remote = remote.to_string().trim().to_string();
+        self.as_mut().rust_mut().prefs.pipewire_remote = remote.clone();
+        save_preferences(&self.rust().prefs);
+        let name = if remote.is_empty() { "(default)" } else { &remote };
+        log::info!("Switching PipeWire instance to {}, restarting", name);
+
+        // Drop --remote so the saved preference applies after the restart.
+        let mut args = std::env::args().skip(1);
+        let mut kept = Vec::new();
+        while let Some(arg) = args.next() {
+            if arg == "--remote" {
+                args.next();
+            } else if !arg.starts_with("--remote=") {
+                kept.push(arg);
             }
         }
 
-        // Update patchbay manager with the new default
-        if let Some(ref mut patchbay) = self.as_mut().rust_mut().patchbay {
-            if key.is_empty() {
-                patchbay.set_default_target(None);
-            } else {
-                // Extract the display name from the layout key (format is "Type:DisplayName")
-                let display_name = if let Some(pos) = key.find(':') {
-                    key[pos + 1..].to_string()
-                } else {
-                    key.clone()
-                };
-                patchbay.set_default_target(Some(display_name));
-            }
-        }
+        self.relaunch(kept, || {});
     }
 
-    pub fn get_app_version(self: Pin<&mut Self>) -> QString {
-        QString::from(env!("CARGO_PKG_VERSION"))
+    /// Save the session and restart ZestBay with `args`. `before_exec` runs
+    /// once everything is on disk, to change config files under the new
+    /// process.
+    fn relaunch(mut self: Pin<&mut Self>, args: Vec<String>, before_exec: impl FnOnce()) {
+        use std::os::unix::process::CommandExt;
+
+        let exe = match std::env::current_exe() {
+            Ok(exe) => exe,
+            Err(e) => {
+                let msg = format!("Cannot restart ZestBay: {}", e);
+                self.as_mut().error_occurred(QString::from(&msg));
+                return;
+            }
+        };
+        self.save_session();
+        before_exec();
+        let e = std::process::Command::new(exe).args(args).exec();
+        log::error!("Failed to restart ZestBay: {}", e);
+        std::process::exit(1);
     }
 
-    pub fn get_qt_version(self: Pin<&mut Self>) -> QString {
-        QString::from(env!("QT_VERSION"))
+    /// Saved sessions with when they were saved, the recent list and the
+    /// session in use.
+    pub fn get_sessions_json(self: Pin<&mut Self>) -> QString {
+        let mut saved: Vec<serde_json::Value> = std::fs::read_dir(config_path("sessions"))
+            .into_iter()
+            .flatten()
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| {
+                path.extension()
+                    .is_some_and(|ext| ext == session::SESSION_FILE_EXTENSION)
+            })
+            .filter_map(|path| {
+                let text = std::fs::read_to_string(&path).ok()?;
+                let file = session::SessionFile::parse(&text).ok()?;
+                Some(serde_json::json!({ "name": file.name, "saved": file.saved }))
+            })
+            .collect();
+        saved.sort_by_key(|s| s["name"].as_str().unwrap_or_default().to_lowercase());
+        let index = load_session_index();
+        QString::from(
+            &serde_json::json!({
+                "current": index.current,
+                "recent": index.recent,
+                "sessions": saved,
+            })
+            .to_string(),
+        )
     }
 
-    pub fn backup_rules(self: Pin<&mut Self>, name: QString) -> QString {
-        let name_str: String = name.to_string();
-        let backup_dir = config_path("rule_backups");
-        if let Err(e) = std::fs::create_dir_all(&backup_dir) {
-            log::error!("Failed to create backup dir: {}", e);
-            return QString::from("");
-        }
+    /// Bundle the patch as it is now into the session `name`, replacing one
+    /// of that name, and make it the current session.
+    pub fn save_session_as(mut self: Pin<&mut Self>, name: QString) -> bool {
+        let name = name.to_string().trim().to_string();
+        let Some(file_name) = session::session_file_name(&name) else {
+            self.as_mut()
+                .error_occurred(QString::from("A session needs a name"));
+            return false;
+        };
+        self.as_mut().collect_lv2_states();
+        persist_lv2_links(
+            self.rust().graph.as_ref(),
+            &self.rust().dormant_links,
+            &self.rust().node_ids,
+        );
+        persist_active_plugins(self.rust());
+        crate::control::flush();
 
-        let now = std::time::SystemTime::now()
+        let files = session::SESSION_FILES
+            .iter()
+            .filter_map(|&file| {
+                let text = std::fs::read_to_string(config_path(file)).ok()?;
+                let value = serde_json::from_str(&text).ok()?;
+                Some((file.to_string(), value))
+            })
+            .collect();
+        let saved = std::time::SystemTime::now()
             .duration_since(std::time::UNIX_EPOCH)
             .unwrap_or_default()
             .as_secs();
-        // Format as YYYYMMDD_HHMMSS using simple arithmetic (UTC)
-        let secs = now as i64;
-        let days = secs / 86400;
-        let time_of_day = (secs % 86400) as u32;
-        let hours = time_of_day / 3600;
-        let minutes = (time_of_day % 3600) / 60;
-        let seconds = time_of_day % 60;
-        // Compute date from days since epoch (1970-01-01)
-        let (year, month, day) = days_to_ymd(days);
-        let timestamp = format!("{:04}{:02}{:02}_{:02}{:02}{:02}", year, month, day, hours, minutes, seconds);
-        let safe_name = if name_str.trim().is_empty() {
-            timestamp.clone()
-        } else {
-            let sanitized: String = name_str
-                .trim()
-                .chars()
-                .map(|c| if c.is_alphanumeric() || c == '-' || c == '_' || c == ' ' { c } else { '_' })
-                .collect();
-            format!("{}_{}", timestamp, sanitized)
+        let session = session::SessionFile::new(&name, saved, files);
+
+        let dir = config_path("sessions");
+        let path = dir.join(file_name);
+        if let Err(e) =
+            std::fs::create_dir_all(&dir).and_then(|_| std::fs::write(&path, session.render()))
+        {
+            let msg = format!("Failed to save session {:?}: {}", path, e);
+            log::error!("{}", msg);
+            self.as_mut().error_occurred(QString::from(&msg));
+            return false;
+        }
+        log::info!("Session '{}' saved to {:?}", name, path);
+        let mut index = load_session_index();
+        index.opened(&name);
+        save_session_index(&index);
+        true
+    }
+
+    /// Replace the patch with the session `name` and restart on it.
+    pub fn load_session(mut self: Pin<&mut Self>, name: QString) {
+        let name = name.to_string();
+        let path = session::session_file_name(&name).map(|f| config_path("sessions").join(f));
+        let session = match path.as_ref().map(std::fs::read_to_string) {
+            Some(Ok(text)) => session::SessionFile::parse(&text),
+            Some(Err(e)) => Err(e.to_string()),
+            None => Err("no such session".to_string()),
         };
-        let filename = format!("{}.json", safe_name);
-        let dest = backup_dir.join(&filename);
+        let session = match session {
+            Ok(session) => session,
+            Err(e) => {
+                let msg = format!("Cannot load session '{}': {}", name, e);
+                log::error!("{}", msg);
+                self.as_mut().error_occurred(QString::from(&msg));
+                return;
+            }
+        };
+        log::info!("Loading session '{}', restarting", session.name);
+        let mut index = load_session_index();
+        index.opened(&session.name);
+        save_session_index(&index);
+        let args = std::env::args().skip(1).collect();
+        self.relaunch(args, move || {
+            write_config_files(session.contents(), "the session")
+        });
+    }
 
-        let src = config_path("rules.json");
-        match std::fs::read_to_string(&src) {
-            Ok(content) => {
-                if let Err(e) = std::fs::write(&dest, &content) {
-                    log::error!("Failed to write backup {:?}: {}", dest, e);
-                    return QString::from("");
-                }
-                log::info!("Rules backed up to {:?}", dest);
-                QString::from(&filename)
+    /// Start over with no plugins, links, rules or layout, and restart.
+    /// System-wide defaults apply as on a first start.
+    pub fn new_session(self: Pin<&mut Self>) {
+        log::info!("Starting a new session, restarting");
+        let mut index = load_session_index();
+        index.current = None;
+        save_session_index(&index);
+        let empty = session::SessionFile::new("", 0, Default::default());
+        let args = std::env::args().skip(1).collect();
+        self.relaunch(args, move || {
+            write_config_files(empty.contents(), "the session")
+        });
+    }
+
+    pub fn delete_session(self: Pin<&mut Self>, name: QString) -> bool {
+        let name = name.to_string();
+        let Some(file_name) = session::session_file_name(&name) else {
+            return false;
+        };
+        let path = config_path("sessions").join(file_name);
+        if let Err(e) = std::fs::remove_file(&path) {
+            log::error!("Failed to delete session {:?}: {}", path, e);
+            return false;
+        }
+        let mut index = load_session_index();
+        index.forget(&name);
+        save_session_index(&index);
+        true
+    }
+
+    /// Bundle the whole configuration into `exports/` as a `.zestbay` file
+    /// to import on another machine. Returns the written path, or an empty
+    /// string after reporting the failure through `error_occurred`.
+    pub fn export_config(mut self: Pin<&mut Self>) -> QString {
+        self.as_mut().collect_lv2_states();
+        persist_lv2_links(
+            self.rust().graph.as_ref(),
+            &self.rust().dormant_links,
+            &self.rust().node_ids,
+        );
+        persist_active_plugins(self.rust());
+        crate::control::flush();
+
+        let files = portable::bundle_files()
+            .filter_map(|file| {
+                let text = std::fs::read_to_string(config_path(file)).ok()?;
+                let value = serde_json::from_str(&text).ok()?;
+                Some((file.to_string(), value))
+            })
+            .collect();
+        let exported = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let bundle = ConfigBundle::new(exported, self.bundle_nodes(), files);
+        let result = save_config_export(
+            "exports",
+            "zestbay-config",
+            portable::CONFIG_BUNDLE_EXTENSION,
+            &bundle.render(),
+        );
+        match result {
+            Ok(path) => {
+                log::info!("Exported the configuration to {:?}", path);
+                QString::from(path.to_string_lossy().as_ref())
             }
             Err(e) => {
-                log::error!("Failed to read rules for backup: {}", e);
+                log::warn!("export_config: {}", e);
+                let msg = format!("Configuration export failed: {}", e);
+                self.as_mut().error_occurred(QString::from(msg.as_str()));
                 QString::from("")
             }
         }
     }
 
-    pub fn list_rule_backups_json(self: Pin<&mut Self>) -> QString {
-        let backup_dir = config_path("rule_backups");
-        let mut backups: Vec<serde_json::Value> = Vec::new();
-
-        if let Ok(entries) = std::fs::read_dir(&backup_dir) {
-            for entry in entries.flatten() {
-                let path = entry.path();
-                if path.extension().and_then(|e| e.to_str()) == Some("json") {
-                    let filename = path
-                        .file_name()
-                        .and_then(|n| n.to_str())
-                        .unwrap_or("")
-                        .to_string();
+    /// What importing the `.zestbay` file at `path` would do, as JSON:
+    /// `{exported, files, matched, renamed, missing: [description]}`, or
+    /// `{error}` when the file cannot be read or does not validate.
+    pub fn check_config_file(self: Pin<&mut Self>, path: QString) -> QString {
+        let path = PathBuf::from(path.to_string());
+        let json = match self.read_config_bundle(&path) {
+            Ok((bundle, remap)) => serde_json::json!({
+                "exported": bundle.exported,
+                "files": bundle.files.len(),
+                "matched": remap.matched,
+                "renamed": remap.renames.len(),
+                "missing": remap.missing,
+            }),
+            Err(e) => serde_json::json!({ "error": e }),
+        };
+        QString::from(&json.to_string())
+    }
 
-                    // Parse rule count from the file
-                    let rule_count = std::fs::read_to_string(&path)
-                        .ok()
-                        .and_then(|s| serde_json::from_str::<Vec<serde_json::Value>>(&s).ok())
-                        .map(|v| v.len())
-                        .unwrap_or(0);
+    /// Replace the configuration with the `.zestbay` file at `path`, with
+    /// its devices remapped onto this machine's, and restart on it.
+    pub fn import_config(mut self: Pin<&mut Self>, path: QString) {
+        let path = PathBuf::from(path.to_string());
+        let (mut bundle, remap) = match self.read_config_bundle(&path) {
+            Ok(read) => read,
+            Err(e) => {
+                let msg = format!("Cannot import {:?}: {}", path, e);
+                log::error!("{}", msg);
+                self.as_mut().error_occurred(QString::from(&msg));
+                return;
+            }
+        };
+        let rewritten = bundle.remap(&remap);
+        let prefs = serde_json::to_value(&self.rust().prefs).unwrap_or_default();
+        bundle.keep_local_preferences(&prefs);
+        log::info!(
+            "Importing the configuration from {:?} ({} of {} nodes matched, {} references \
+             rewritten, not found: {:?}), restarting",
+            path,
+            remap.matched,
+            bundle.nodes.len(),
+            rewritten,
+            remap.missing
+        );
+        // The imported patch is not a saved session.
+        let mut index = load_session_index();
+        index.current = None;
+        save_session_index(&index);
+        let args = std::env::args().skip(1).collect();
+        self.relaunch(args, move || {
+            write_config_files(bundle.contents(), "the imported configuration")
+        });
+    }
 
-                    // Extract display name from filename: strip .json, split on first _
-                    // Format: YYYYMMDD_HHMMSS_OptionalName.json
-                    let stem = filename.trim_end_matches(".json");
-                    let display_name = if stem.len() > 16 && stem.chars().nth(15) == Some('_') {
-                        stem[16..].to_string()
-                    } else {
-                        String::new()
-                    };
+    /// Read and validate a `.zestbay` file and match its nodes to the ones
+    /// in the graph.
+    fn read_config_bundle(
+        &self,
+        path: &std::path::Path,
+    ) -> Result<(ConfigBundle, NodeRemap), String> {
+        let text = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+        let bundle = ConfigBundle::parse(&text)?;
+        check_config_bundle(&bundle)?;
+        let remap = portable::remap_nodes(&bundle.nodes, &self.bundle_nodes());
+        Ok((bundle, remap))
+    }
 
-                    // Get timestamp from filename
-                    let date_str = if stem.len() >= 15 {
-                        let d = &stem[..8];
-                        let t = &stem[9..15];
-                        format!(
-                            "{}-{}-{} {}:{}:{}",
-                            &d[..4], &d[4..6], &d[6..8],
-                            &t[..2], &t[2..4], &t[4..6]
-                        )
-                    } else {
-                        // Fallback to file modification time
-                        entry
-                            .metadata()
-                            .ok()
-                            .and_then(|m| m.modified().ok())
-                            .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
-                            .map(|dur| {
-                                let secs = dur.as_secs() as i64;
-                                let tod = (secs % 86400) as u32;
-                                let (y, mo, d) = days_to_ymd(secs / 86400);
-                                format!("{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
-                                    y, mo, d, tod / 3600, (tod % 3600) / 60, tod % 60)
-                            })
-                            .unwrap_or_default()
-                    };
+    /// The devices and streams in the graph, as a configuration bundle
+    /// records them.
+    fn bundle_nodes(&self) -> Vec<BundleNode> {
+        let Some(graph) = self.rust().graph.as_ref() else {
+            return Vec::new();
+        };
+        graph
+            .get_all_nodes()
+            .into_iter()
+            .filter(|n| n.ready && n.node_type != Some(NodeType::Plugin) && !is_link_node(&n.name))
+            .map(|n| BundleNode {
+                name: n.name.clone(),
+                description: n.display_name().to_string(),
+                node_type: n.node_type,
+                layout_key: self.layout_key_of(&n),
+            })
+            .collect()
+    }
 
-                    backups.push(serde_json::json!({
-                        "filename": filename,
-                        "name": display_name,
-                        "date": date_str,
-                        "ruleCount": rule_count,
-                    }));
-                }
-            }
+    pub fn restore_known_good(self: Pin<&mut Self>) -> bool {
+        if restore_known_good_plugins() {
+            log::info!("Known-good plugins restored. Restart to load them.");
+            crate::PLUGINS_FROZEN.store(false, std::sync::atomic::Ordering::SeqCst);
+            true
+        } else {
+            false
         }
+    }
 
-        // Sort newest first
-        backups.sort_by(|a, b| {
-            let fa = a["filename"].as_str().unwrap_or("");
-            let fb = b["filename"].as_str().unwrap_or("");
-            fb.cmp(fa)
-        });
-
-        let json = serde_json::to_string(&backups).unwrap_or_else(|_| "[]".to_string());
-        QString::from(&json)
+    pub fn is_ui_docking_supported(self: Pin<&mut Self>) -> bool {
+        crate::ui::plugin_dock::is_supported()
     }
 
-    pub fn restore_rule_backup(mut self: Pin<&mut Self>, filename: QString) {
-        let filename_str: String = filename.to_string();
-        let backup_path = config_path("rule_backups").join(&filename_str);
+    pub fn get_docked_uis_json(self: Pin<&mut Self>) -> QString {
+        let active = self.rust().active_docked_ui;
+        let uis: Vec<serde_json::Value> = self
+            .rust()
+            .docked_uis
+            .iter()
+            .map(|&id| {
+                let name = self
+                    .rust()
+                    .plugin_manager
+                    .as_ref()
+                    .and_then(|mgr| mgr.get_instance(id))
+                    .map(|info| info.display_name.clone())
+                    .unwrap_or_default();
+                serde_json::json!({
+                    "instanceId": id,
+                    "name": name,
+                    "active": active == Some(id),
+                })
+            })
+            .collect();
+        QString::from(&serde_json::to_string(&uis).unwrap_or_default())
+    }
 
-        match std::fs::read_to_string(&backup_path) {
-            Ok(content) => {
-                // Validate it parses as rules
-                match serde_json::from_str::<Vec<crate::patchbay::rules::AutoConnectRule>>(&content) {
-                    Ok(rules) => {
-                        // Write to rules.json
-                        let rules_path = config_path("rules.json");
-                        if let Err(e) = std::fs::write(&rules_path, &content) {
-                            log::error!("Failed to write restored rules: {}", e);
-                            return;
-                        }
-                        // Load into patchbay manager
-                        if let Some(ref mut patchbay) = self.as_mut().rust_mut().patchbay {
-                            patchbay.set_rules(rules.clone());
-                        }
-                        log::info!("Restored {} rules from backup {:?}", rules.len(), filename_str);
-                    }
-                    Err(e) => {
-                        log::error!("Backup file {:?} contains invalid rules: {}", filename_str, e);
-                    }
-                }
-            }
-            Err(e) => {
-                log::error!("Failed to read backup {:?}: {}", backup_path, e);
-            }
+    pub fn show_docked_ui(mut self: Pin<&mut Self>, instance_id: u64) {
+        if !self.rust().docked_uis.contains(&instance_id) {
+            return;
         }
+        self.as_mut().rust_mut().active_docked_ui = Some(instance_id);
+        crate::ui::plugin_dock::show(self.rust().plugin_ui_windows.get(&instance_id).copied());
+        self.as_mut().docked_uis_changed();
     }
 
-    pub fn delete_rule_backup(self: Pin<&mut Self>, filename: QString) {
-        let filename_str: String = filename.to_string();
-        let backup_path = config_path("rule_backups").join(&filename_str);
-        if let Err(e) = std::fs::remove_file(&backup_path) {
-            log::error!("Failed to delete backup {:?}: {}", backup_path, e);
-        } else {
-            log::info!("Deleted rule backup: {}", filename_str);
+    /// Move a docked plugin UI back into a window of its own.
+    pub fn undock_plugin_ui(mut self: Pin<&mut Self>, instance_id: u64) {
+        let Some(window) = self.rust().plugin_ui_windows.get(&instance_id).copied() else {
+            return;
+        };
+        if !self.rust().docked_uis.contains(&instance_id) {
+            return;
         }
+        self.as_mut().forget_docked_ui(instance_id, window, true);
+        self.as_mut().docked_uis_changed();
     }
 
-    pub fn set_window_visible(self: Pin<&mut Self>, visible: bool) {
-        if let Some(ref tray) = self.rust().tray_state {
-            use std::sync::atomic::Ordering;
-            tray.window_visible.store(visible, Ordering::Release);
-            log::info!("Window visible state updated to {}", visible);
+    pub fn close_docked_ui(self: Pin<&mut Self>, instance_id: u64) {
+        self.send_command(
+            CommandOrigin::User,
+            PwCommand::ClosePluginUI { instance_id },
+        );
+    }
+
+    /// Position of the dock's content area, in main window coordinates.
+    pub fn set_dock_geometry(self: Pin<&mut Self>, x: i32, y: i32, width: i32, height: i32) {
+        crate::ui::plugin_dock::set_geometry(x, y, width.max(0), height.max(0));
+    }
+
+    /// Drop `instance_id` from the dock and show the next docked UI in its
+    /// place if it was the visible one.
+    fn forget_docked_ui(mut self: Pin<&mut Self>, instance_id: u64, window: u64, refloat: bool) {
+        crate::ui::plugin_dock::release(window, refloat);
+        let mut rust = self.as_mut().rust_mut();
+        rust.docked_uis.retain(|&id| id != instance_id);
+        if rust.active_docked_ui == Some(instance_id) {
+            rust.active_docked_ui = rust.docked_uis.last().copied();
+            let next = rust
+                .active_docked_ui
+                .and_then(|id| rust.plugin_ui_windows.get(&id).copied());
+            crate::ui::plugin_dock::show(next);
         }
     }
 
-    fn sync_tray_plugins(self: Pin<&mut Self>) {
-        let tray = match self.rust().tray_state.as_ref() {
-            Some(t) => t,
-            None => return,
+    pub fn get_layout_json(self: Pin<&mut Self>) -> QString {
+        ui_state_json::<NodeLayout>("{}")
+    }
+
+    pub fn save_layout(self: Pin<&mut Self>, json: QString) -> bool {
+        save_ui_state::<NodeLayout>(&json.to_string())
+    }
+
+    /// `node_sizes_json`: layoutKey → [width, height]. `pinned_positions_json`: layoutKey → [x, y].
+    /// Returns layoutKey → [x, y]. Pinned nodes keep their positions; free nodes are laid out.
+    pub fn auto_layout(mut self: Pin<&mut Self>, node_sizes_json: QString, pinned_positions_json: QString) -> QString {
+        use crate::layout;
+
+        let node_sizes = match NodeLayout::parse(&node_sizes_json.to_string()) {
+            Ok(sizes) => sizes.0,
+            Err(e) => {
+                log::warn!("auto_layout: rejected node sizes: {}", e);
+                return QString::from("{}");
+            }
         };
-        let mgr = match self.rust().plugin_manager.as_ref() {
-            Some(m) => m,
-            None => return,
+        let pinned_by_key = match NodeLayout::parse(&pinned_positions_json.to_string()) {
+            Ok(pinned) => pinned.0,
+            Err(e) => {
+                log::warn!("auto_layout: rejected pinned positions: {}", e);
+                return QString::from("{}");
+            }
         };
 
-        let mut entries: Vec<crate::tray::PluginEntry> = mgr
-            .active_instances()
-            .values()
-            .filter(|info| info.pw_node_id.is_some())
-            .map(|info| {
-                let has_ui = mgr
-                    .find_plugin(&info.plugin_uri)
-                    .map(|p| p.has_ui)
-                    .unwrap_or(false);
-                crate::tray::PluginEntry {
-                    name: info.display_name.clone(),
-                    node_id: info.pw_node_id.unwrap_or(0),
-                    has_ui,
-                }
-            })
-            .collect();
-        entries.sort_by(|a, b| a.name.cmp(&b.name));
-
-        if let Ok(mut plugins) = tray.plugins.lock() {
+        let graph = match self.rust().graph.clone() {
+            Some(g) => g,
+            None => return QString::from("{}"),
+        };
+
+        let all_nodes = graph.get_all_nodes();
+        let all_links = graph.get_all_links();
+
+        let mut layout_nodes: Vec<(u32, String, &str, f64, f64)> = Vec::new();
+        let mut layout_ports: Vec<(u32, u32, usize, bool)> = Vec::new();
+        let mut layout_links: Vec<(u32, u32, u32, u32, u32)> = Vec::new();
+        let mut id_to_layout_key: std::collections::HashMap<u32, String> = std::collections::HashMap::new();
+
+        // Phase 1: resolve bridge virtual IDs (needs mutable self for bridge_split)
+        let mut bridge_vids: Vec<(u32, u32, String, String)> = Vec::new();
+        for n in all_nodes.iter().filter(|n| n.ready && n.is_bridge) {
+            let groups = graph.get_bridge_port_groups(n.id);
+            for (group, device_name) in &groups {
+                let vid = self.as_mut().rust_mut().bridge_split
+                    .get_or_create_virtual_id(n.id, group);
+                bridge_vids.push((n.id, vid, group.clone(), device_name.clone()));
+            }
+        }
+
+        // Collapsed application groups are laid out as a single node
+        let app_groups = self.as_mut().rust_mut().app_groups.rebuild(&all_nodes);
+        let collapsed_groups: Vec<AppGroup> = app_groups
+            .into_iter()
+            .filter(|g| !self.rust().app_groups.is_expanded(g.id))
+            .collect();
+        let mut collapsed_members: std::collections::HashSet<u32> = std::collections::HashSet::new();
+        for group in &collapsed_groups {
+            for &member in &group.members {
+                collapsed_members.insert(member);
+                for port in graph.get_ports_for_node(member) {
+                    self.as_mut().rust_mut().bridge_split
+                        .register_port(port.id, group.id);
+                }
+            }
+        }
+
+        // Phase 2: build layout data (immutable self access for plugin_manager)
+        let mgr = self.rust().plugin_manager.as_ref();
+
+        for group in &collapsed_groups {
+            let key = group.layout_key();
+            let (w, h) = get_node_size(&node_sizes, &key, group.id);
+            let type_str = if group.node_type == NodeType::StreamInput { "StreamInput" } else { "StreamOutput" };
+            layout_nodes.push((group.id, group.name.clone(), type_str, w, h));
+            id_to_layout_key.insert(group.id, key);
+
+            let ports: Vec<Port> = group
+                .members
+                .iter()
+                .flat_map(|&m| graph.get_ports_for_node(m))
+                .collect();
+            add_ports_to_layout(&ports, group.id, &mut layout_ports);
+        }
+
+        for n in all_nodes.iter().filter(|n| n.ready && !collapsed_members.contains(&n.id)) {
+            if n.is_bridge {
+                let groups = graph.get_bridge_port_groups(n.id);
+                if groups.is_empty() {
+                    let key = layout_key(n, mgr, &self.rust().node_keys);
+                    let (w, h) = get_node_size(&node_sizes, &key, n.id);
+                    let type_str = node_type_str(n);
+                    layout_nodes.push((n.id, n.display_name().to_string(), type_str, w, h));
+                    id_to_layout_key.insert(n.id, key);
+
+                    let ports = graph.get_ports_for_node(n.id);
+                    add_ports_to_layout(&ports, n.id, &mut layout_ports);
+                } else {
+                    for &(real_id, vid, ref _group, ref device_name) in &bridge_vids {
+                        if real_id != n.id { continue; }
+
+                        let key = format!("MidiBridge:{}", device_name);
+                        let (w, h) = get_node_size(&node_sizes, &key, vid);
+
+                        let group_ports = graph.get_ports_for_bridge_group(n.id, &_group);
+                        let has_inputs = group_ports.iter().any(|p| p.direction == PortDirection::Input);
+                        let has_outputs = group_ports.iter().any(|p| p.direction == PortDirection::Output);
+                        let type_str = if has_inputs && has_outputs { "Duplex" }
+                            else if has_outputs { "Source" }
+                            else if has_inputs { "Sink" }
+                            else { "Duplex" };
+
+                        layout_nodes.push((vid, device_name.clone(), type_str, w, h));
+                        id_to_layout_key.insert(vid, key);
+
+                        add_ports_to_layout(&group_ports, vid, &mut layout_ports);
+                    }
+                }
+            } else {
+                let key = layout_key(n, mgr, &self.rust().node_keys);
+                let (w, h) = get_node_size(&node_sizes, &key, n.id);
+                let type_str = node_type_str(n);
+                layout_nodes.push((n.id, n.display_name().to_string(), type_str, w, h));
+                id_to_layout_key.insert(n.id, key);
+
+                let ports = graph.get_ports_for_node(n.id);
+                add_ports_to_layout(&ports, n.id, &mut layout_ports);
+            }
+        }
+
+        for l in &all_links {
+            let out_node = self.rust().bridge_split
+                .resolve_port_virtual_node(l.output_port_id)
+                .unwrap_or(l.output_node_id);
+            let in_node = self.rust().bridge_split
+                .resolve_port_virtual_node(l.input_port_id)
+                .unwrap_or(l.input_node_id);
+
+            if id_to_layout_key.contains_key(&out_node) && id_to_layout_key.contains_key(&in_node) {
+                layout_links.push((l.id, out_node, l.output_port_id, in_node, l.input_port_id));
+            }
+        }
+
+        // Disambiguate duplicate layout keys by appending #N for the 2nd, 3rd, etc.
+        // Build a stable ordering: sort node IDs so the suffix assignment is deterministic.
+        let mut key_counts: std::collections::HashMap<String, u32> = std::collections::HashMap::new();
+        let mut sorted_ids: Vec<u32> = id_to_layout_key.keys().copied().collect();
+        sorted_ids.sort();
+        let mut unique_keys: std::collections::HashMap<u32, String> = std::collections::HashMap::new();
+        for node_id in &sorted_ids {
+            if let Some(base_key) = id_to_layout_key.get(node_id) {
+                let count = key_counts.entry(base_key.clone()).or_insert(0);
+                let unique_key = if *count == 0 {
+                    base_key.clone()
+                } else {
+                    format!("{}#{}", base_key, count)
+                };
+                *count += 1;
+                unique_keys.insert(*node_id, unique_key);
+            }
+        }
+
+        let config = layout::graph::LayoutConfig::default();
+        let mut pinned_by_id: std::collections::HashMap<u32, (f64, f64)> = std::collections::HashMap::new();
+        for (key, pos) in &pinned_by_key {
+            for (&node_id, ukey) in &unique_keys {
+                if ukey == key {
+                    pinned_by_id.insert(node_id, (pos[0], pos[1]));
+                }
+            }
+        }
+
+        for &(id, ref name, type_str, w, h) in &layout_nodes {
+            let pin_tag = if pinned_by_id.contains_key(&id) { " [PINNED]" } else { "" };
+            log::info!("  node {}: {}({}) {}x{}{}", id, name, type_str, w as i32, h as i32, pin_tag);
+        }
+        log::info!("auto_layout: {} nodes ({} pinned), {} links",
+            layout_nodes.len(), pinned_by_id.len(), layout_links.len());
+
+        let positions = layout::sugiyama_layout(layout_nodes, layout_ports, layout_links, config, &pinned_by_id);
+
+        let mut result: serde_json::Map<String, serde_json::Value> = serde_json::Map::new();
+        for (node_id, (x, y)) in &positions {
+            if let Some(key) = unique_keys.get(node_id) {
+                let pin_tag = if pinned_by_id.contains_key(node_id) { " [P]" } else { "" };
+                log::info!("  result {}: ({:.0}, {:.0}){}", key, x, y, pin_tag);
+                result.insert(key.clone(), serde_json::json!([x, y]));
+            }
+        }
+
+        let json = serde_json::to_string(&serde_json::Value::Object(result)).unwrap_or_else(|_| "{}".to_string());
+        log::info!("auto_layout: computed {} positions", positions.len());
+        QString::from(&json)
+    }
+
+    pub fn get_hidden_json(self: Pin<&mut Self>) -> QString {
+        ui_state_json::<HiddenNodes>("[]")
+    }
+
+    pub fn save_hidden(self: Pin<&mut Self>, json: QString) -> bool {
+        save_ui_state::<HiddenNodes>(&json.to_string())
+    }
+
+    pub fn get_pinned_json(self: Pin<&mut Self>) -> QString {
+        ui_state_json::<PinnedNodes>("[]")
+    }
+
+    pub fn save_pinned(self: Pin<&mut Self>, json: QString) -> bool {
+        save_ui_state::<PinnedNodes>(&json.to_string())
+    }
+
+    pub fn get_available_plugins_json(self: Pin<&mut Self>) -> QString {
+        if let Some(ref mgr) = self.rust().plugin_manager {
+            let json_plugins: Vec<serde_json::Value> = mgr
+                .catalog()
+                .into_iter()
+                .map(|p| {
+                    serde_json::json!({
+                        "uri": p.uri,
+                        "name": p.name,
+                        "category": p.category.display_name(),
+                        "author": p.author.as_deref().unwrap_or(""),
+                        "audioIn": p.audio_inputs,
+                        "audioOut": p.audio_outputs,
+                        "controlIn": p.control_inputs,
+                        "controlOut": p.control_outputs,
+                        "compatible": p.compatible,
+                        "requiredFeatures": p.required_features,
+                        "hasUi": p.has_ui,
+                        "format": p.format.as_str(),
+                        "version": p.version,
+                        "path": p.library_path,
+                        "folder": std::path::Path::new(&p.library_path)
+                            .parent()
+                            .map(|dir| dir.to_string_lossy()),
+                        "modified": p.modified,
+                        "otherFormats": mgr
+                            .other_formats(p)
+                            .iter()
+                            .map(|f| f.as_str())
+                            .collect::<Vec<_>>(),
+                    })
+                })
+                .collect();
+            let json = serde_json::to_string(&json_plugins).unwrap_or_default();
+            QString::from(&json)
+        } else {
+            QString::from("[]")
+        }
+    }
+
+    pub fn add_plugin(mut self: Pin<&mut Self>, uri: QString) -> QString {
+        let uri_str: String = uri.to_string();
+
+        let (display_name, initial_params, plugin_format) = if let Some(ref mgr) =
+            self.rust().plugin_manager
+        {
+            let plugin = mgr.find_plugin(&uri_str);
+            let base_name = plugin
+                .map(|p| p.name.clone())
+                .unwrap_or_else(|| uri_str.clone());
+            let name = self.unique_display_name(&base_name);
+            let format = plugin
+                .map(|p| p.format)
+                .unwrap_or(crate::plugin::PluginFormat::Lv2);
+            let params = plugin.map(initial_parameters).unwrap_or_default();
+            (name, params, format)
+        } else {
+            return QString::from("");
+        };
+
+        let instance_id = self.rust().next_instance_id;
+        self.as_mut().rust_mut().next_instance_id += 1;
+
+        let format_str = plugin_format.as_str().to_string();
+
+        if let Some(ref mut mgr) = self.as_mut().rust_mut().plugin_manager {
+            let info = crate::lv2::Lv2InstanceInfo {
+                id: instance_id,
+                stable_id: uuid::Uuid::new_v4().to_string(),
+                plugin_uri: uri_str.clone(),
+                format: plugin_format,
+                display_name: display_name.clone(),
+                pw_node_id: None,
+                parameters: initial_params,
+                active: true,
+                bypassed: false,
+                lv2_state: Vec::new(),
+                oversampling: 1,
+            };
+            mgr.register_instance(info);
+        }
+
+        log::info!(
+            "Adding plugin: uri={} instance_id={} name={} format={}",
+            uri_str,
+            instance_id,
+            display_name,
+            format_str
+        );
+        self.send_command(
+            CommandOrigin::User,
+            PwCommand::AddPlugin {
+                plugin_uri: uri_str.clone(),
+                instance_id,
+                display_name: display_name.clone(),
+                format: format_str,
+                lv2_state: Vec::new(),
+                oversampling: 1,
+            },
+        );
+
+        persist_active_plugins(self.rust());
+
+        QString::from(&display_name)
+    }
+
+    pub fn remove_plugin(self: Pin<&mut Self>, node_id: u32) {
+        let instance_id = self.find_instance_id_for_node(node_id);
+        if let Some(instance_id) = instance_id {
+            log::info!(
+                "Remove plugin: node_id={} instance_id={}",
+                node_id,
+                instance_id
+            );
+            self.send_command(CommandOrigin::User, PwCommand::RemovePlugin { instance_id });
+        } else {
+            log::warn!(
+                "remove_plugin: no LV2 instance found for node_id={}",
+                node_id
+            );
+        }
+    }
+
+    pub fn open_plugin_ui(self: Pin<&mut Self>, node_id: u32) {
+        let instance_id = self.find_instance_id_for_node(node_id);
+        if let Some(instance_id) = instance_id {
+            log::info!(
+                "Open plugin UI: node_id={} instance_id={}",
+                node_id,
+                instance_id
+            );
+            self.send_open_plugin_ui(instance_id);
+        } else {
+            log::warn!(
+                "open_plugin_ui: no LV2 instance found for node_id={}",
+                node_id
+            );
+        }
+    }
+
+    /// Reopen the plugin UIs that the last "Hide All" closed.
+    pub fn show_all_plugin_uis(mut self: Pin<&mut Self>) {
+        let hidden = std::mem::take(&mut self.as_mut().rust_mut().hidden_uis);
+        log::info!("Showing {} hidden plugin UIs", hidden.len());
+        for sid in hidden {
+            let instance_id = self
+                .rust()
+                .plugin_manager
+                .as_ref()
+                .and_then(|mgr| mgr.instance_id_for_stable_id(&sid));
+            if let Some(instance_id) = instance_id {
+                self.send_open_plugin_ui(instance_id);
+            }
+        }
+    }
+
+    /// Close every open plugin UI, remembering them for "Show All".
+    pub fn hide_all_plugin_uis(mut self: Pin<&mut Self>) {
+        let open: Vec<String> = self.rust().ui_windows.open.iter().cloned().collect();
+        if open.is_empty() {
+            return;
+        }
+        log::info!("Hiding {} plugin UIs", open.len());
+        if let Some(ref mgr) = self.rust().plugin_manager {
+            for sid in &open {
+                if let Some(instance_id) = mgr.instance_id_for_stable_id(sid) {
+                    self.send_command(
+                        CommandOrigin::User,
+                        PwCommand::ClosePluginUI { instance_id },
+                    );
+                }
+            }
+        }
+        let mut rust = self.as_mut().rust_mut();
+        for sid in open {
+            if !rust.hidden_uis.contains(&sid) {
+                rust.hidden_uis.push(sid);
+            }
+        }
+    }
+
+    /// Ask for the UI of `instance_id`, placed where it was last closed.
+    fn send_open_plugin_ui(&self, instance_id: u64) {
+        let sid = self.stable_id_for_instance(instance_id);
+        let geometry = sid
+            .as_ref()
+            .and_then(|sid| self.rust().ui_windows.geometry.get(sid).copied());
+        crate::plugin::ui_geometry::set_restore(instance_id, geometry);
+        let monitor = self.ui_monitor(sid.as_deref()).cloned();
+        crate::plugin::ui_geometry::set_monitor(instance_id, monitor);
+        let scale = self
+            .plugin_uri_for_instance(instance_id)
+            .and_then(|uri| self.rust().ui_scales.get(&uri).copied());
+        crate::plugin::ui_scale::set_for_instance(instance_id, scale);
+        self.send_command(CommandOrigin::User, PwCommand::OpenPluginUI { instance_id });
+    }
+
+    /// The monitor a plugin's UI opens on: the one it was last left on,
+    /// else the one chosen in the preferences, else the main window's.
+    /// Monitors not connected now are passed over.
+    fn ui_monitor(&self, stable_id: Option<&str>) -> Option<&MonitorArea> {
+        let rust = self.rust();
+        let connected = |name: &str| rust.screens.iter().find(|m| m.name == name);
+        stable_id
+            .and_then(|sid| rust.ui_windows.monitors.get(sid))
+            .and_then(|name| connected(name))
+            .or_else(|| connected(&rust.prefs.plugin_ui_monitor))
+            .or_else(|| connected(&rust.main_screen))
+    }
+
+    /// Reopen a restored plugin's UI if it was open at shutdown and the
+    /// preference asks for it.
+    fn reopen_plugin_ui(mut self: Pin<&mut Self>, instance_id: u64) {
+        let Some(sid) = self.stable_id_for_instance(instance_id) else {
+            return;
+        };
+        if !self.rust().ui_windows.open.contains(&sid) {
+            return;
+        }
+        if self.rust().prefs.reopen_plugin_uis {
+            log::info!("Reopening plugin UI: instance={}", instance_id);
+            self.send_open_plugin_ui(instance_id);
+        } else {
+            self.as_mut().rust_mut().ui_windows.open.remove(&sid);
+            save_ui_windows(&self.rust().ui_windows);
+        }
+    }
+
+    fn stable_id_for_instance(&self, instance_id: u64) -> Option<String> {
+        self.rust()
+            .plugin_manager
+            .as_ref()
+            .and_then(|mgr| mgr.get_instance(instance_id))
+            .map(|info| info.stable_id.clone())
+    }
+
+    fn plugin_uri_for_instance(&self, instance_id: u64) -> Option<String> {
+        self.rust()
+            .plugin_manager
+            .as_ref()
+            .and_then(|mgr| mgr.get_instance(instance_id))
+            .map(|info| info.plugin_uri.clone())
+    }
+
+    pub fn rename_plugin(mut self: Pin<&mut Self>, node_id: u32, new_name: QString) {
+        let name_str: String = new_name.to_string();
+        let instance_id = self.find_instance_id_for_node(node_id);
+        if let Some(instance_id) = instance_id {
+            log::info!(
+                "Rename plugin: node_id={} instance_id={} new_name={}",
+                node_id,
+                instance_id,
+                name_str
+            );
+            if let Some(ref mut mgr) = self.as_mut().rust_mut().plugin_manager
+                && let Some(info) = mgr.get_instance_mut(instance_id)
+            {
+                info.display_name = name_str.clone();
+            }
+            if let Some(ref graph) = self.rust().graph {
+                graph.set_node_description(node_id, &name_str);
+            }
+            persist_active_plugins(self.rust());
+        } else {
+            log::warn!(
+                "rename_plugin: no LV2 instance found for node_id={}",
+                node_id
+            );
+        }
+    }
+
+    pub fn get_plugin_params_json(self: Pin<&mut Self>, node_id: u32) -> QString {
+        let instance_id = self.find_instance_id_for_node(node_id);
+        if let Some(instance_id) = instance_id
+            && let Some(ref mgr) = self.rust().plugin_manager
+            && let Some(info) = mgr.get_instance(instance_id)
+        {
+            let pins = self
+                .rust()
+                .quick_params
+                .get(&info.stable_id)
+                .cloned()
+                .unwrap_or_default();
+            let params: Vec<serde_json::Value> = info
+                .parameters
+                .iter()
+                .map(|p| {
+                    serde_json::json!({
+                        "portIndex": p.port_index,
+                        "symbol": p.symbol,
+                        "name": p.name,
+                        "value": p.value,
+                        "min": p.min,
+                        "max": p.max,
+                        "default": p.default,
+                        "isToggle": p.is_toggle,
+                        "quick": pins.contains(&p.symbol),
+                    })
+                })
+                .collect();
+            let result = serde_json::json!({
+                "instanceId": instance_id,
+                "stableId": info.stable_id,
+                "pluginUri": info.plugin_uri,
+                "displayName": info.display_name,
+                "format": info.format.as_str(),
+                "bypassed": info.bypassed,
+                "oversampling": info.oversampling,
+                "parameters": params,
+            });
+            let json = serde_json::to_string(&result).unwrap_or_default();
+            return QString::from(&json);
+        }
+        QString::from("{}")
+    }
+
+    /// Descriptions, units and labelled values of a plugin's parameters,
+    /// keyed by port index. Parameters without any are left out.
+    pub fn get_plugin_param_docs_json(self: Pin<&mut Self>, plugin_uri: QString) -> QString {
+        let uri = plugin_uri.to_string();
+        let Some(ref mgr) = self.rust().plugin_manager else {
+            return QString::from("{}");
+        };
+        let docs: serde_json::Map<String, serde_json::Value> = mgr
+            .param_docs(&uri)
+            .into_iter()
+            .map(|(port_index, doc)| {
+                let scale_points: Vec<serde_json::Value> = doc
+                    .scale_points
+                    .iter()
+                    .map(|p| serde_json::json!({ "value": p.value, "label": p.label }))
+                    .collect();
+                let entry = serde_json::json!({
+                    "comment": doc.comment,
+                    "unit": doc.unit,
+                    "scalePoints": scale_points,
+                });
+                (port_index.to_string(), entry)
+            })
+            .collect();
+        let json = serde_json::to_string(&docs).unwrap_or_default();
+        QString::from(&json)
+    }
+
+    /// Why a plugin is marked incompatible or failed to load, with what the
+    /// user can do about each issue.
+    pub fn get_plugin_compat_json(self: Pin<&mut Self>, plugin_uri: QString) -> QString {
+        let uri = plugin_uri.to_string();
+        let Some(issues) = self
+            .rust()
+            .plugin_manager
+            .as_ref()
+            .and_then(|mgr| mgr.compat_issues(&uri, crate::lv2::scanner::PROVIDED_FEATURES))
+        else {
+            return QString::from("{}");
+        };
+        let entries: Vec<serde_json::Value> = issues
+            .iter()
+            .map(|issue| {
+                let mut entry = serde_json::to_value(issue).unwrap_or_default();
+                entry["blocking"] = issue.is_blocking().into();
+                entry["fixable"] = issue.fixable_by_user().into();
+                entry["summary"] = issue.summary().into();
+                entry["advice"] = issue.advice().into();
+                entry
+            })
+            .collect();
+        let result = serde_json::json!({
+            "uri": uri,
+            "compatible": !issues.iter().any(|i| i.is_blocking()),
+            "issues": entries,
+        });
+        QString::from(&serde_json::to_string(&result).unwrap_or_default())
+    }
+
+    pub fn set_plugin_parameter(
+        mut self: Pin<&mut Self>,
+        node_id: u32,
+        port_index: u32,
+        value: f32,
+    ) {
+        let instance_id = self.find_instance_id_for_node(node_id);
+        if let Some(instance_id) = instance_id {
+            self.send_command(
+                CommandOrigin::User,
+                PwCommand::SetPluginParameter {
+                    instance_id,
+                    port_index: port_index as usize,
+                    value,
+                },
+            );
+            if let Some(ref mut mgr) = self.as_mut().rust_mut().plugin_manager {
+                mgr.update_parameter(instance_id, port_index as usize, value);
+            }
+            self.as_mut().rust_mut().params_dirty = true;
+            if self.rust().params_dirty_since.is_none() {
+                self.as_mut().rust_mut().params_dirty_since = Some(Instant::now());
+            }
+        }
+    }
+
+    /// The parameters shown as knobs on a plugin's node: the pinned ones,
+    /// or else the first few. Set them with `set_plugin_parameter`.
+    pub fn get_node_quick_params_json(self: Pin<&mut Self>, node_id: u32) -> QString {
+        let count = self.rust().prefs.quick_params_count as usize;
+        let Some(info) = self
+            .find_instance_id_for_node(node_id)
+            .and_then(|id| self.rust().plugin_manager.as_ref()?.get_instance(id))
+        else {
+            return QString::from("{}");
+        };
+        let pins = self
+            .rust()
+            .quick_params
+            .get(&info.stable_id)
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        let shown = quick_params::quick_params(&info.parameters, pins, count);
+        let params: Vec<serde_json::Value> = shown
+            .into_iter()
+            .map(|p| {
+                serde_json::json!({
+                    "portIndex": p.port_index,
+                    "symbol": p.symbol,
+                    "name": p.name,
+                    "value": p.value,
+                    "min": p.min,
+                    "max": p.max,
+                    "default": p.default,
+                    "isToggle": p.is_toggle,
+                })
+            })
+            .collect();
+        let result = serde_json::json!({
+            "stableId": info.stable_id,
+            "pinned": !pins.is_empty(),
+            "parameters": params,
+        });
+        QString::from(&serde_json::to_string(&result).unwrap_or_default())
+    }
+
+    /// Pin a parameter to its plugin's node, or unpin it. Once one is
+    /// pinned only pinned ones are shown.
+    pub fn set_quick_param_pinned(
+        mut self: Pin<&mut Self>,
+        node_id: u32,
+        symbol: QString,
+        pinned: bool,
+    ) -> bool {
+        let Some(sid) = self
+            .find_instance_id_for_node(node_id)
+            .and_then(|id| self.stable_id_for_instance(id))
+        else {
+            return false;
+        };
+        let changed = {
+            let mut rust = self.as_mut().rust_mut();
+            let pins = rust.quick_params.entry(sid.clone()).or_default();
+            let changed = quick_params::set_pinned(pins, &symbol.to_string(), pinned);
+            if pins.is_empty() {
+                rust.quick_params.remove(&sid);
+            }
+            changed
+        };
+        if changed {
+            save_quick_params(&self.rust().quick_params);
+            self.as_mut().graph_changed();
+        }
+        changed
+    }
+
+    pub fn set_plugin_bypass(mut self: Pin<&mut Self>, node_id: u32, bypassed: bool) {
+        let instance_id = self.find_instance_id_for_node(node_id);
+        if let Some(instance_id) = instance_id {
+            self.send_command(
+                CommandOrigin::User,
+                PwCommand::SetPluginBypass {
+                    instance_id,
+                    bypassed,
+                },
+            );
+            if let Some(ref mut mgr) = self.as_mut().rust_mut().plugin_manager
+                && let Some(info) = mgr.get_instance_mut(instance_id)
+            {
+                info.bypassed = bypassed;
+            }
+            self.as_mut().rust_mut().params_dirty = true;
+            if self.rust().params_dirty_since.is_none() {
+                self.as_mut().rust_mut().params_dirty_since = Some(Instant::now());
+            }
+        }
+    }
+
+    /// Flip an A/B switch node to its other input. Returns false when the
+    /// node is not an A/B switch.
+    pub fn toggle_ab_switch(mut self: Pin<&mut Self>, node_id: u32) -> bool {
+        let Some(b_selected) = self
+            .find_instance_id_for_node(node_id)
+            .and_then(|id| self.rust().plugin_manager.as_ref()?.get_instance(id))
+            .and_then(ab_switch_selects_b)
+        else {
+            return false;
+        };
+        let value = if b_selected { 0.0 } else { 1.0 };
+        self.as_mut()
+            .set_plugin_parameter(node_id, AB_SWITCH_PORT_SOURCE as u32, value);
+        self.as_mut().graph_changed();
+        true
+    }
+
+    /// Flip every A/B switch node at once, for the toggle hotkey. Returns
+    /// how many were flipped.
+    pub fn toggle_ab_switches(mut self: Pin<&mut Self>) -> u32 {
+        let nodes: Vec<u32> = match self.rust().plugin_manager.as_ref() {
+            Some(mgr) => mgr
+                .active_instances()
+                .values()
+                .filter(|info| info.plugin_uri == crate::builtin::AB_SWITCH_URI)
+                .filter_map(|info| info.pw_node_id)
+                .collect(),
+            None => return 0,
+        };
+        let mut flipped = 0;
+        for node_id in nodes {
+            if self.as_mut().toggle_ab_switch(node_id) {
+                flipped += 1;
+            }
+        }
+        flipped
+    }
+
+    /// Run a plugin at 1x, 2x or 4x the graph rate. The plugin has to be
+    /// re-instantiated, so its connections are captured here and restored
+    /// once the new node appears; parameters, bypass and LV2 state carry over.
+    pub fn set_plugin_oversampling(mut self: Pin<&mut Self>, node_id: u32, factor: u32) -> bool {
+        if !crate::plugin::oversample::is_valid_factor(factor as usize) {
+            log::warn!("set_plugin_oversampling: unsupported factor {}", factor);
+            return false;
+        }
+        let Some(instance_id) = self.find_instance_id_for_node(node_id) else {
+            log::warn!("set_plugin_oversampling: no instance for node_id={}", node_id);
+            return false;
+        };
+        let Some(info) = self
+            .rust()
+            .plugin_manager
+            .as_ref()
+            .and_then(|mgr| mgr.get_instance(instance_id))
+            .cloned()
+        else {
+            return false;
+        };
+        if info.format == crate::plugin::PluginFormat::Builtin {
+            return false;
+        }
+        if info.oversampling == factor {
+            return true;
+        }
+
+        let links: Vec<SavedPluginLink> = match self.rust().graph.as_ref() {
+            Some(graph) => build_persistable_links(graph, &self.rust().node_ids)
+                .into_iter()
+                .filter(|link| {
+                    link.output_node_name == info.display_name
+                        || link.input_node_name == info.display_name
+                })
+                .collect(),
+            None => Vec::new(),
+        };
+
+        if let Some(ref mut mgr) = self.as_mut().rust_mut().plugin_manager
+            && let Some(info) = mgr.get_instance_mut(instance_id)
+        {
+            info.oversampling = factor;
+            // The node goes away; PluginAdded fills in the new one.
+            info.pw_node_id = None;
+        }
+
+        log::info!(
+            "Reloading plugin {} at {}x oversampling ({} links to restore)",
+            info.display_name,
+            factor,
+            links.len()
+        );
+        self.send_command(
+            CommandOrigin::User,
+            PwCommand::ReloadPlugin {
+                plugin_uri: info.plugin_uri.clone(),
+                instance_id,
+                display_name: info.display_name.clone(),
+                format: info.format.as_str().to_string(),
+                oversampling: factor,
+            },
+        );
+
+        if !links.is_empty() {
+            self.as_mut().rust_mut().pending_restore_count += 1;
+            self.as_mut().rust_mut().pending_links.extend(links);
+        }
+
+        persist_active_plugins(self.rust());
+        true
+    }
+
+    /// Run this plugin's UI in a process of its own from the next time it
+    /// is opened. The setting applies to every instance of the plugin.
+    pub fn set_plugin_ui_isolated(mut self: Pin<&mut Self>, node_id: u32, isolated: bool) -> bool {
+        let Some(plugin_uri) = self.lv2_plugin_uri_for_node(node_id) else {
+            log::warn!("set_plugin_ui_isolated: no LV2 plugin for node_id={}", node_id);
+            return false;
+        };
+        let changed = if isolated {
+            self.as_mut().rust_mut().isolated_ui_plugins.insert(plugin_uri.clone())
+        } else {
+            self.as_mut().rust_mut().isolated_ui_plugins.remove(&plugin_uri)
+        };
+        if changed {
+            log::info!("Plugin UI isolation for {}: {}", plugin_uri, isolated);
+            crate::lv2::ui::set_isolated_plugins(self.rust().isolated_ui_plugins.iter().cloned());
+            save_isolated_ui_plugins(&self.rust().isolated_ui_plugins);
+        }
+        true
+    }
+
+    pub fn is_plugin_ui_isolated(self: Pin<&mut Self>, node_id: u32) -> bool {
+        self.lv2_plugin_uri_for_node(node_id)
+            .is_some_and(|uri| self.rust().isolated_ui_plugins.contains(&uri))
+    }
+
+    /// Draw this plugin's editor at `scale` (0 for the plugin's default)
+    /// from the next time it is opened. Applies to every instance.
+    pub fn set_plugin_ui_scale(mut self: Pin<&mut Self>, node_id: u32, scale: f64) -> bool {
+        let Some(plugin_uri) = self
+            .find_instance_id_for_node(node_id)
+            .and_then(|id| self.plugin_uri_for_instance(id))
+        else {
+            log::warn!("set_plugin_ui_scale: no plugin for node_id={}", node_id);
+            return false;
+        };
+        let scale = scale as f32;
+        if scale == 0.0 {
+            self.as_mut().rust_mut().ui_scales.remove(&plugin_uri);
+        } else if crate::plugin::ui_scale::is_valid_scale(scale) {
+            self.as_mut()
+                .rust_mut()
+                .ui_scales
+                .insert(plugin_uri.clone(), scale);
+        } else {
+            log::warn!("set_plugin_ui_scale: unsupported scale {}", scale);
+            return false;
+        }
+        log::info!("Plugin UI scale for {}: {}", plugin_uri, scale);
+        save_ui_scales(&self.rust().ui_scales);
+        true
+    }
+
+    pub fn get_plugin_ui_scale(self: Pin<&mut Self>, node_id: u32) -> f64 {
+        self.find_instance_id_for_node(node_id)
+            .and_then(|id| self.plugin_uri_for_instance(id))
+            .and_then(|uri| self.rust().ui_scales.get(&uri).copied())
+            .unwrap_or(0.0) as f64
+    }
+
+    fn lv2_plugin_uri_for_node(&self, node_id: u32) -> Option<String> {
+        let instance_id = self.find_instance_id_for_node(node_id)?;
+        self.rust()
+            .plugin_manager
+            .as_ref()
+            .and_then(|mgr| mgr.get_instance(instance_id))
+            .filter(|info| info.format == crate::plugin::PluginFormat::Lv2)
+            .map(|info| info.plugin_uri.clone())
+    }
+
+    pub fn start_midi_learn(
+        mut self: Pin<&mut Self>,
+        instance_id: u64,
+        port_index: u32,
+        label: QString,
+        mode: QString,
+    ) {
+        let mode_str = mode.to_string();
+        let mapping_mode = match mode_str.as_str() {
+            "toggle" => crate::midi::MappingMode::Toggle,
+            "momentary" => crate::midi::MappingMode::Momentary,
+            _ => crate::midi::MappingMode::Continuous,
+        };
+        let label_str = label.to_string();
+        self.as_mut().rust_mut().midi_learn_target = Some((
+            instance_id,
+            port_index as usize,
+            label_str.clone(),
+            mapping_mode,
+        ));
+        self.send_command(
+            CommandOrigin::User,
+            PwCommand::StartMidiLearn {
+                instance_id,
+                port_index: port_index as usize,
+                label: label_str,
+                mode: mapping_mode,
+            },
+        );
+    }
+
+    pub fn cancel_midi_learn(mut self: Pin<&mut Self>) {
+        self.as_mut().rust_mut().midi_learn_target = None;
+        self.send_command(CommandOrigin::User, PwCommand::CancelMidiLearn);
+    }
+
+    pub fn remove_midi_mapping_for_param(
+        self: Pin<&mut Self>,
+        instance_id: u64,
+        port_index: u32,
+    ) {
+        let target = crate::midi::MidiCcTarget {
+            instance_id,
+            port_index: port_index as usize,
+        };
+        let source = self.rust().midi_mappings
+            .iter()
+            .find(|m| m.target == target)
+            .map(|m| m.source.clone());
+        if let Some(source) = source {
+            self.send_command(CommandOrigin::User, PwCommand::RemoveMidiMapping(source));
+        }
+    }
+
+    pub fn get_midi_mappings_json(self: Pin<&mut Self>) -> QString {
+        let json = serde_json::to_string(&self.rust().midi_mappings).unwrap_or_else(|_| "[]".to_string());
+        QString::from(&json)
+    }
+
+    /// Hooks and the events they can run on, as
+    /// `{events: [{value, label}], hooks: [...]}`.
+    pub fn get_hooks_json(self: Pin<&mut Self>) -> QString {
+        let events: Vec<serde_json::Value> = HookEvent::ALL
+            .iter()
+            .map(|e| serde_json::json!({ "value": e.as_str(), "label": e.label() }))
+            .collect();
+        let json = serde_json::json!({ "events": events, "hooks": self.rust().hooks });
+        QString::from(&json.to_string())
+    }
+
+    /// Replace the hooks with `json`, a list of hooks. Returns false after
+    /// reporting the problem through `error_occurred`.
+    pub fn save_hooks_json(mut self: Pin<&mut Self>, json: QString) -> bool {
+        let hooks = serde_json::from_str::<Vec<Hook>>(&json.to_string())
+            .map_err(|e| e.to_string())
+            .and_then(|hooks| {
+                hooks.iter().try_for_each(Hook::validate)?;
+                Ok(hooks)
+            });
+        match hooks {
+            Ok(hooks) => {
+                save_hooks(&hooks);
+                self.as_mut().rust_mut().hooks = hooks;
+                true
+            }
+            Err(e) => {
+                self.as_mut()
+                    .error_occurred(QString::from(&format!("Hooks not saved: {}", e)));
+                false
+            }
+        }
+    }
+
+    /// Run a hook now with a payload marked `"test": true`.
+    pub fn test_hook(self: Pin<&mut Self>, hook_id: QString) {
+        let hook_id = hook_id.to_string();
+        let Some(hook) = self.rust().hooks.iter().find(|h| h.id == hook_id).cloned() else {
+            return;
+        };
+        let event = hook.event;
+        let now_ms = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+        let payload =
+            zestbay_core::hooks::payload(event, now_ms, serde_json::json!({ "test": true }));
+        crate::hooks::run(hook, event, payload);
+    }
+
+    pub fn get_midi_mapping_for_param_json(
+        self: Pin<&mut Self>,
+        instance_id: u64,
+        port_index: u32,
+    ) -> QString {
+        let target = crate::midi::MidiCcTarget {
+            instance_id,
+            port_index: port_index as usize,
+        };
+        let mapping = self.rust().midi_mappings
+            .iter()
+            .find(|m| m.target == target);
+        match mapping {
+            Some(m) => {
+                let json = serde_json::to_string(m).unwrap_or_else(|_| "{}".to_string());
+                QString::from(&json)
+            }
+            None => QString::from(""),
+        }
+    }
+
+    pub fn get_active_plugins_json(self: Pin<&mut Self>) -> QString {
+        if let Some(ref mgr) = self.rust().plugin_manager {
+            let mut entries: Vec<serde_json::Value> = mgr
+                .active_instances()
+                .values()
+                .map(|info| {
+                    let params: Vec<serde_json::Value> = info
+                        .parameters
+                        .iter()
+                        .map(|p| {
+                            serde_json::json!({
+                                "portIndex": p.port_index,
+                                "symbol": p.symbol,
+                                "name": p.name,
+                                "value": p.value,
+                                "min": p.min,
+                                "max": p.max,
+                                "default": p.default,
+                                "isToggle": p.is_toggle,
+                            })
+                        })
+                        .collect();
+                    serde_json::json!({
+                        "instanceId": info.id,
+                        "stableId": info.stable_id,
+                        "pluginUri": info.plugin_uri,
+                        "displayName": info.display_name,
+                        "bypassed": info.bypassed,
+                        "oversampling": info.oversampling,
+                        "nodeId": info.pw_node_id,
+                        "active": info.pw_node_id.is_some(),
+                        "autostart": !self.rust().manual_start_plugins.contains(&info.stable_id),
+                        "parameters": params,
+                    })
+                })
+                .collect();
+            entries.sort_by(|a, b| {
+                let a_name = a["displayName"].as_str().unwrap_or("");
+                let b_name = b["displayName"].as_str().unwrap_or("");
+                a_name
+                    .cmp(b_name)
+                    .then_with(|| a["instanceId"].as_u64().cmp(&b["instanceId"].as_u64()))
+            });
+            let json = serde_json::to_string(&entries).unwrap_or_default();
+            QString::from(&json)
+        } else {
+            QString::from("[]")
+        }
+    }
+
+    /// Feed the sidechain inputs of a plugin from `source_node_id`, or clear
+    /// the sidechain when it is 0. The source is remembered by layout key, so
+    /// the link is re-created whenever the source node comes back.
+    pub fn set_sidechain_source(
+        mut self: Pin<&mut Self>,
+        stable_id: QString,
+        source_node_id: u32,
+    ) -> bool {
+        let sid = stable_id.to_string();
+        let Some(graph) = self.rust().graph.clone() else {
+            return false;
+        };
+        let plugin_node = match self.rust().plugin_manager.as_ref() {
+            Some(mgr) => match mgr.find_by_stable_id(&sid) {
+                Some(info) => info.pw_node_id,
+                None => {
+                    log::warn!("set_sidechain_source: unknown plugin {}", sid);
+                    return false;
+                }
+            },
+            None => return false,
+        };
+
+        let source_key = if source_node_id == 0 {
+            None
+        } else {
+            match graph.get_node(source_node_id) {
+                Some(node) => Some(layout_key(
+                    &node,
+                    self.rust().plugin_manager.as_ref(),
+                    &self.rust().node_keys,
+                )),
+                None => {
+                    log::warn!("set_sidechain_source: node {} not found", source_node_id);
+                    return false;
+                }
+            }
+        };
+
+        // Drop whatever currently feeds the sidechain before switching source.
+        if let Some(node_id) = plugin_node {
+            let sc_ports = sidechain_ports(&graph, node_id);
+            if sc_ports.is_empty() {
+                log::warn!("set_sidechain_source: plugin {} has no sidechain inputs", sid);
+                return false;
+            }
+            let keep_from = source_key.as_ref().map(|_| source_node_id);
+            let ops: Vec<LinkOp> = graph
+                .get_all_links()
+                .into_iter()
+                .filter(|l| sc_ports.iter().any(|p| p.id == l.input_port_id))
+                .filter(|l| Some(l.output_node_id) != keep_from)
+                .map(|l| LinkOp::Disconnect { link_id: l.id })
+                .collect();
+            if !ops.is_empty() {
+                self.send_command(
+                    CommandOrigin::User,
+                    PwCommand::Transaction {
+                        label: "Clear sidechain".to_string(),
+                        ops,
+                    },
+                );
+            }
+        }
+
+        match source_key {
+            Some(key) => {
+                log::info!("Sidechain for {}: {}", sid, key);
+                self.as_mut().rust_mut().sidechain_sources.insert(sid, key);
+            }
+            None => {
+                log::info!("Sidechain for {} cleared", sid);
+                self.as_mut().rust_mut().sidechain_sources.remove(&sid);
+            }
+        }
+        save_sidechain_sources(&self.rust().sidechain_sources);
+        self.as_mut().maintain_sidechains();
+        true
+    }
+
+    /// The conversion settings of a node as JSON; `{}` when it uses the
+    /// graph defaults.
+    pub fn get_conversion_policy_json(self: Pin<&mut Self>, node_id: u32) -> QString {
+        let policies = &self.rust().conversion_policies;
+        let policy = self
+            .rust()
+            .graph
+            .as_ref()
+            .and_then(|graph| graph.get_node(node_id))
+            .and_then(|node| policies.get(&self.layout_key_of(&node)))
+            .cloned()
+            .unwrap_or_default();
+        QString::from(&serde_json::to_string(&policy).unwrap_or_default())
+    }
+
+    /// Replace a node's conversion settings and apply them right away. An
+    /// empty policy returns the node to the graph defaults.
+    pub fn set_conversion_policy(mut self: Pin<&mut Self>, node_id: u32, json: QString) -> bool {
+        let policy: ConversionPolicy = match serde_json::from_str(&json.to_string()) {
+            Ok(p) => p,
+            Err(e) => {
+                log::warn!("set_conversion_policy: invalid policy: {}", e);
+                return false;
+            }
+        };
+        let Some(node) = self.rust().graph.as_ref().and_then(|g| g.get_node(node_id)) else {
+            log::warn!("set_conversion_policy: node {} not found", node_id);
+            return false;
+        };
+        let key = self.layout_key_of(&node);
+        let old = if policy.is_empty() {
+            self.as_mut().rust_mut().conversion_policies.remove(&key)
+        } else {
+            self.as_mut()
+                .rust_mut()
+                .conversion_policies
+                .insert(key, policy.clone())
+        };
+        let params = policy.params_replacing(&old.unwrap_or_default());
+        if !params.is_empty() {
+            self.send_command(
+                CommandOrigin::User,
+                PwCommand::SetNodeConversion { node_id, params },
+            );
+        }
+        self.as_mut().rust_mut().conversion_applied.insert(node_id);
+        save_conversion_policies(&self.rust().conversion_policies);
+        true
+    }
+
+    /// Send each node's conversion policy once after it appears, so the
+    /// settings follow a device across reconnects.
+    fn maintain_conversion_policies(mut self: Pin<&mut Self>) {
+        let Some(graph) = self.rust().graph.clone() else {
+            return;
+        };
+        let nodes = graph.get_all_nodes();
+        self.as_mut()
+            .rust_mut()
+            .conversion_applied
+            .retain(|id| nodes.iter().any(|n| n.id == *id));
+        if self.rust().conversion_policies.is_empty() {
+            return;
+        }
+        self.as_mut().refresh_node_keys(&nodes);
+
+        let policies = &self.rust().conversion_policies;
+        let mut pending = Vec::new();
+        for node in nodes.iter().filter(|n| n.ready) {
+            if self.rust().conversion_applied.contains(&node.id) {
+                continue;
+            }
+            if let Some(policy) = policies.get(&self.layout_key_of(node)) {
+                pending.push((node.id, policy.params()));
+            }
+        }
+        for (node_id, params) in pending {
+            self.as_mut().rust_mut().conversion_applied.insert(node_id);
+            self.send_command(
+                CommandOrigin::System,
+                PwCommand::SetNodeConversion { node_id, params },
+            );
+        }
+    }
+
+    /// The links the graph view draws: a filtered MIDI link or a link with
+    /// a gain shows as one link from its source to its target, under the id
+    /// of the link into the hidden node.
+    fn logical_links(&self, graph: &GraphState) -> Vec<LogicalLink> {
+        let links = graph.get_all_links();
+        let link_nodes: HashMap<u32, String> = graph
+            .get_all_nodes()
+            .into_iter()
+            .filter(|n| is_link_node(&n.name))
+            .map(|n| (n.id, n.name))
+            .collect();
+
+        let mut logical = Vec::new();
+        for l in &links {
+            if link_nodes.contains_key(&l.output_node_id) {
+                continue;
+            }
+            let Some(name) = link_nodes.get(&l.input_node_id) else {
+                logical.push(LogicalLink {
+                    link: l.clone(),
+                    midi_filter: None,
+                    gain_db: None,
+                });
+                continue;
+            };
+            let midi_filter = link_filter::filter_id_of(name).and_then(|id| {
+                let filters = &self.rust().midi_link_filters;
+                filters.iter().find(|f| f.id == id).map(|f| f.options)
+            });
+            let gain_db = link_gain::gain_id_of(name).and_then(|id| {
+                let gains = &self.rust().link_gains;
+                gains.iter().find(|g| g.id == id).map(|g| g.gain_db)
+            });
+            for onward in links.iter().filter(|o| o.output_node_id == l.input_node_id) {
+                let link = crate::pipewire::Link {
+                    input_node_id: onward.input_node_id,
+                    input_port_id: onward.input_port_id,
+                    active: l.active && onward.active,
+                    ..l.clone()
+                };
+                logical.push(LogicalLink {
+                    link,
+                    midi_filter,
+                    gain_db,
+                });
+            }
+        }
+        logical
+    }
+
+    /// The saved filter whose ends are `link`'s.
+    fn midi_link_filter_index(
+        &self,
+        graph: &GraphState,
+        link: &crate::pipewire::Link,
+    ) -> Option<usize> {
+        let source = graph.get_node(link.output_node_id)?;
+        let target = graph.get_node(link.input_node_id)?;
+        let output_port = graph.get_port(link.output_port_id)?;
+        let input_port = graph.get_port(link.input_port_id)?;
+        self.rust().midi_link_filters.iter().position(|f| {
+            f.matches(
+                source.display_name(),
+                &output_port.name,
+                target.display_name(),
+                &input_port.name,
+            )
+        })
+    }
+
+    /// What a MIDI link drops, as JSON; all false when it passes
+    /// everything.
+    pub fn get_midi_link_options_json(self: Pin<&mut Self>, link_id: u32) -> QString {
+        let options = self
+            .rust()
+            .graph
+            .as_ref()
+            .and_then(|graph| {
+                self.logical_links(graph)
+                    .into_iter()
+                    .find(|l| l.link.id == link_id)
+                    .and_then(|l| l.midi_filter)
+            })
+            .unwrap_or_default();
+        QString::from(&serde_json::to_string(&options).unwrap_or_default())
+    }
+
+    /// Set what a MIDI link drops. The first option set routes the link
+    /// through a filter node; clearing them all links the ends directly
+    /// again.
+    pub fn set_midi_link_options(mut self: Pin<&mut Self>, link_id: u32, json: QString) -> bool {
+        let options: MidiLinkOptions = match serde_json::from_str(&json.to_string()) {
+            Ok(o) => o,
+            Err(e) => {
+                log::warn!("set_midi_link_options: invalid options: {}", e);
+                return false;
+            }
+        };
+        let Some(graph) = self.rust().graph.clone() else {
+            return false;
+        };
+        let Some(LogicalLink { link, .. }) = self
+            .logical_links(&graph)
+            .into_iter()
+            .find(|l| l.link.id == link_id)
+        else {
+            log::warn!("set_midi_link_options: link {} not found", link_id);
+            return false;
+        };
+        let (Some(source), Some(output_port), Some(target), Some(input_port)) = (
+            graph.get_node(link.output_node_id),
+            graph.get_port(link.output_port_id),
+            graph.get_node(link.input_node_id),
+            graph.get_port(link.input_port_id),
+        ) else {
+            return false;
+        };
+        if output_port.media_type != Some(MediaType::Midi) {
+            log::warn!("set_midi_link_options: link {} is not a MIDI link", link_id);
+            return false;
+        }
+
+        match self.midi_link_filter_index(&graph, &link) {
+            Some(index) if options.is_empty() => {
+                let filter = self.as_mut().rust_mut().midi_link_filters.remove(index);
+                self.send_command(
+                    CommandOrigin::User,
+                    PwCommand::RemoveMidiLinkFilter {
+                        filter_id: filter.id,
+                    },
+                );
+                self.send_command(
+                    CommandOrigin::User,
+                    PwCommand::Connect {
+                        output_port_id: link.output_port_id,
+                        input_port_id: link.input_port_id,
+                    },
+                );
+            }
+            Some(index) => {
+                let filter_id = {
+                    let mut rust = self.as_mut().rust_mut();
+                    let filter = &mut rust.midi_link_filters[index];
+                    filter.options = options;
+                    filter.id
+                };
+                self.send_command(
+                    CommandOrigin::User,
+                    PwCommand::SetMidiLinkFilter { filter_id, options },
+                );
+            }
+            None if options.is_empty() => return true,
+            None => {
+                let filter_id = self
+                    .rust()
+                    .midi_link_filters
+                    .iter()
+                    .map(|f| f.id + 1)
+                    .max()
+                    .unwrap_or(1);
+                self.as_mut()
+                    .rust_mut()
+                    .midi_link_filters
+                    .push(SavedMidiLinkFilter {
+                        id: filter_id,
+                        source: source.display_name().to_string(),
+                        output_port: output_port.name.clone(),
+                        target: target.display_name().to_string(),
+                        input_port: input_port.name.clone(),
+                        options,
+                    });
+                self.send_command(
+                    CommandOrigin::User,
+                    PwCommand::SetMidiLinkFilter { filter_id, options },
+                );
+            }
+        }
+        save_midi_link_filters(&self.rust().midi_link_filters);
+        true
+    }
+
+    /// If `link_id` is a filtered MIDI link, forget its filter and remove
+    /// the filter node, which takes both halves of the link with it.
+    /// Returns the link as the graph view shows it.
+    fn remove_midi_link_filter(
+        mut self: Pin<&mut Self>,
+        link_id: u32,
+    ) -> Option<crate::pipewire::Link> {
+        let graph = self.rust().graph.clone()?;
+        let LogicalLink {
+            link, midi_filter, ..
+        } = self
+            .logical_links(&graph)
+            .into_iter()
+            .find(|l| l.link.id == link_id)?;
+        midi_filter?;
+        let index = self.midi_link_filter_index(&graph, &link)?;
+        let filter = self.as_mut().rust_mut().midi_link_filters.remove(index);
+        self.send_command(
+            CommandOrigin::User,
+            PwCommand::RemoveMidiLinkFilter {
+                filter_id: filter.id,
+            },
+        );
+        save_midi_link_filters(&self.rust().midi_link_filters);
+        Some(link)
+    }
+
+    /// Create the filter node of every saved filtered link. They stay up
+    /// while ZestBay runs, whether or not the link's ends are present.
+    fn start_midi_link_filters(&self) {
+        for filter in &self.rust().midi_link_filters {
+            self.send_command(
+                CommandOrigin::System,
+                PwCommand::SetMidiLinkFilter {
+                    filter_id: filter.id,
+                    options: filter.options,
+                },
+            );
+        }
+    }
+
+    /// Route each filtered link whose ends and filter node are present
+    /// through the filter, replacing a direct link between the ends.
+    fn maintain_midi_link_filters(self: Pin<&mut Self>) {
+        if self.rust().midi_link_filters.is_empty() {
+            return;
+        }
+        let snapshot = self.graph_snapshot();
+        let mut ops = Vec::new();
+        for filter in &self.rust().midi_link_filters {
+            let Some((output, input)) = midi_link_filter_ends(&snapshot, filter) else {
+                continue;
+            };
+            if let Some(node) = snapshot
+                .nodes()
+                .iter()
+                .find(|n| link_filter::filter_id_of(&n.name) == Some(filter.id))
+            {
+                route_through_node(&snapshot, (output, input), node.id, &mut ops);
+            }
+        }
+        if !ops.is_empty() {
+            self.send_command(
+                CommandOrigin::System,
+                PwCommand::Transaction {
+                    label: "MIDI filter".to_string(),
+                    ops,
+                },
+            );
+        }
+    }
+
+    /// Drop rule commands that would link the ends of a filtered MIDI link
+    /// or a link with a gain directly, or unlink its hidden node.
+    fn keep_link_nodes(
+        &self,
+        graph: &GraphSnapshot,
+        commands: Vec<(PwCommand, CommandOrigin)>,
+    ) -> Vec<(PwCommand, CommandOrigin)> {
+        let filters = &self.rust().midi_link_filters;
+        let gains = &self.rust().link_gains;
+        if filters.is_empty() && gains.is_empty() {
+            return commands;
+        }
+        let ends: Vec<(u32, u32)> = filters
+            .iter()
+            .filter_map(|f| midi_link_filter_ends(graph, f))
+            .chain(gains.iter().filter_map(|g| link_gain_ends(graph, g)))
+            .collect();
+        let filter_nodes: Vec<u32> = graph
+            .nodes()
+            .iter()
+            .filter(|n| is_link_node(&n.name))
+            .map(|n| n.id)
+            .collect();
+        commands
+            .into_iter()
+            .filter(|(cmd, _)| match *cmd {
+                PwCommand::Connect {
+                    output_port_id,
+                    input_port_id,
+                } => !ends.contains(&(output_port_id, input_port_id)),
+                PwCommand::Disconnect { link_id } => !graph.links().iter().any(|l| {
+                    l.id == link_id
+                        && (filter_nodes.contains(&l.output_node_id)
+                            || filter_nodes.contains(&l.input_node_id))
+                }),
+                _ => true,
+            })
+            .collect()
+    }
+
+    /// The saved gain whose ends are `link`'s.
+    fn link_gain_index(&self, graph: &GraphState, link: &crate::pipewire::Link) -> Option<usize> {
+        let source = graph.get_node(link.output_node_id)?;
+        let target = graph.get_node(link.input_node_id)?;
+        let output_port = graph.get_port(link.output_port_id)?;
+        let input_port = graph.get_port(link.input_port_id)?;
+        self.rust().link_gains.iter().position(|g| {
+            g.matches(
+                source.display_name(),
+                &output_port.name,
+                target.display_name(),
+                &input_port.name,
+            )
+        })
+    }
+
+    /// Set the gain of an audio link in dB. Any gain but 0 dB routes the
+    /// link through a gain node; 0 dB links the ends directly again.
+    pub fn set_link_gain(mut self: Pin<&mut Self>, link_id: u32, gain_db: f64) -> bool {
+        let gain_db = (gain_db as f32).clamp(link_gain::MIN_GAIN_DB, link_gain::MAX_GAIN_DB);
+        if gain_db.is_nan() {
+            return false;
+        }
+        let Some(graph) = self.rust().graph.clone() else {
+            return false;
+        };
+        let Some(LogicalLink { link, .. }) = self
+            .logical_links(&graph)
+            .into_iter()
+            .find(|l| l.link.id == link_id)
+        else {
+            log::warn!("set_link_gain: link {} not found", link_id);
+            return false;
+        };
+        let (Some(source), Some(output_port), Some(target), Some(input_port)) = (
+            graph.get_node(link.output_node_id),
+            graph.get_port(link.output_port_id),
+            graph.get_node(link.input_node_id),
+            graph.get_port(link.input_port_id),
+        ) else {
+            return false;
+        };
+        if output_port.media_type != Some(MediaType::Audio) {
+            log::warn!("set_link_gain: link {} is not an audio link", link_id);
+            return false;
+        }
+
+        match self.link_gain_index(&graph, &link) {
+            Some(index) if gain_db == 0.0 => {
+                let gain = self.as_mut().rust_mut().link_gains.remove(index);
+                self.send_command(
+                    CommandOrigin::User,
+                    PwCommand::RemoveLinkGain { gain_id: gain.id },
+                );
+                self.send_command(
+                    CommandOrigin::User,
+                    PwCommand::Connect {
+                        output_port_id: link.output_port_id,
+                        input_port_id: link.input_port_id,
+                    },
+                );
+            }
+            Some(index) => {
+                let gain_id = {
+                    let mut rust = self.as_mut().rust_mut();
+                    let gain = &mut rust.link_gains[index];
+                    gain.gain_db = gain_db;
+                    gain.id
+                };
+                self.send_command(
+                    CommandOrigin::User,
+                    PwCommand::SetLinkGain { gain_id, gain_db },
+                );
+            }
+            None if gain_db == 0.0 => return true,
+            None => {
+                let gain_id = self
+                    .rust()
+                    .link_gains
+                    .iter()
+                    .map(|g| g.id + 1)
+                    .max()
+                    .unwrap_or(1);
+                self.as_mut().rust_mut().link_gains.push(SavedLinkGain {
+                    id: gain_id,
+                    source: source.display_name().to_string(),
+                    output_port: output_port.name.clone(),
+                    target: target.display_name().to_string(),
+                    input_port: input_port.name.clone(),
+                    gain_db,
+                });
+                self.send_command(
+                    CommandOrigin::User,
+                    PwCommand::SetLinkGain { gain_id, gain_db },
+                );
+            }
+        }
+        save_link_gains(&self.rust().link_gains);
+        true
+    }
+
+    /// If `link_id` is a link with a gain, forget the gain and remove its
+    /// node, which takes both halves of the link with it. Returns the link
+    /// as the graph view shows it.
+    fn remove_link_gain(mut self: Pin<&mut Self>, link_id: u32) -> Option<crate::pipewire::Link> {
+        let graph = self.rust().graph.clone()?;
+        let LogicalLink { link, gain_db, .. } = self
+            .logical_links(&graph)
+            .into_iter()
+            .find(|l| l.link.id == link_id)?;
+        gain_db?;
+        let index = self.link_gain_index(&graph, &link)?;
+        let gain = self.as_mut().rust_mut().link_gains.remove(index);
+        self.send_command(
+            CommandOrigin::User,
+            PwCommand::RemoveLinkGain { gain_id: gain.id },
+        );
+        save_link_gains(&self.rust().link_gains);
+        Some(link)
+    }
+
+    /// Create the gain node of every saved link with a gain. Like MIDI
+    /// filters they stay up while ZestBay runs.
+    fn start_link_gains(&self) {
+        for gain in &self.rust().link_gains {
+            self.send_command(
+                CommandOrigin::System,
+                PwCommand::SetLinkGain {
+                    gain_id: gain.id,
+                    gain_db: gain.gain_db,
+                },
+            );
+        }
+    }
+
+    /// Route each link with a gain whose ends and gain node are present
+    /// through the gain node, replacing a direct link between the ends.
+    fn maintain_link_gains(self: Pin<&mut Self>) {
+        if self.rust().link_gains.is_empty() {
+            return;
+        }
+        let snapshot = self.graph_snapshot();
+        let mut ops = Vec::new();
+        for gain in &self.rust().link_gains {
+            let Some((output, input)) = link_gain_ends(&snapshot, gain) else {
+                continue;
+            };
+            if let Some(node) = snapshot
+                .nodes()
+                .iter()
+                .find(|n| link_gain::gain_id_of(&n.name) == Some(gain.id))
+            {
+                route_through_node(&snapshot, (output, input), node.id, &mut ops);
+            }
+        }
+        if !ops.is_empty() {
+            self.send_command(
+                CommandOrigin::System,
+                PwCommand::Transaction {
+                    label: "Link gain".to_string(),
+                    ops,
+                },
+            );
+        }
+    }
+
+    /// The MIDI clock panel: the chosen source, every endpoint that could
+    /// send or take clock, and receivers fed by more than one clock.
+    pub fn get_clock_routing_json(mut self: Pin<&mut Self>) -> QString {
+        let Some(graph) = self.rust().graph.clone() else {
+            return QString::from("{}");
+        };
+        self.as_mut().refresh_node_keys(&graph.get_all_nodes());
+        let snapshot = GraphSnapshot::from_state(&graph);
+        let endpoints = clock::endpoints(&snapshot);
+        let routing = &self.rust().clock_routing;
+
+        let mut source_id = None;
+        let mut sources = Vec::new();
+        let mut receivers = Vec::new();
+        for endpoint in &endpoints {
+            let Some(key) = self.clock_key(&graph, endpoint) else {
+                continue;
+            };
+            let id = self.clock_endpoint_id(endpoint);
+            let name = self.clock_endpoint_name(&graph, endpoint);
+            let outputs = endpoint.midi_ports(&snapshot, PortDirection::Output);
+            let inputs = endpoint.midi_ports(&snapshot, PortDirection::Input);
+            if routing.source.as_ref() == Some(&key) {
+                source_id = Some(id);
+            }
+            if !outputs.is_empty() {
+                sources.push(serde_json::json!({
+                    "id": id,
+                    "name": name,
+                    "detected": clock::is_clock_source(&snapshot, endpoint),
+                }));
+            }
+            if !inputs.is_empty() {
+                receivers.push(serde_json::json!({
+                    "id": id,
+                    "name": name,
+                    "enabled": routing.is_enabled(&key),
+                }));
+            }
+        }
+
+        let clock_sources = self.clock_sources(&graph, &snapshot);
+        let conflicts: Vec<serde_json::Value> = clock::fan_in(&snapshot, &clock_sources)
+            .iter()
+            .map(|(receiver, sources)| {
+                serde_json::json!({
+                    "receiver": self.clock_endpoint_name(&graph, receiver),
+                    "sources": sources
+                        .iter()
+                        .map(|s| self.clock_endpoint_name(&graph, s))
+                        .collect::<Vec<_>>(),
+                })
+            })
+            .collect();
+
+        let json = serde_json::json!({
+            "sourceId": source_id,
+            "sources": sources,
+            "receivers": receivers,
+            "conflicts": conflicts,
+        });
+        QString::from(&json.to_string())
+    }
+
+    /// Make `node_id` the clock source, or clear the source when it is 0.
+    /// The old source's links to the receivers are removed.
+    pub fn set_clock_source(mut self: Pin<&mut Self>, node_id: u32) -> bool {
+        let Some(graph) = self.rust().graph.clone() else {
+            return false;
+        };
+        let key = if node_id == 0 {
+            None
+        } else {
+            let endpoint = self.clock_endpoint(node_id);
+            match self.clock_key(&graph, &endpoint) {
+                Some(key) => Some(key),
+                None => {
+                    log::warn!("set_clock_source: node {} not found", node_id);
+                    return false;
+                }
+            }
+        };
+        if key == self.rust().clock_routing.source {
+            return true;
+        }
+
+        let receivers: Vec<String> = self
+            .rust()
+            .clock_routing
+            .receivers
+            .iter()
+            .filter(|r| r.enabled)
+            .map(|r| r.key.clone())
+            .collect();
+        if let Some(old) = self.rust().clock_routing.source.clone() {
+            for receiver in &receivers {
+                self.disconnect_clock(&graph, &old, receiver);
+            }
+        }
+
+        log::info!("MIDI clock source: {}", key.as_deref().unwrap_or("none"));
+        self.as_mut().rust_mut().clock_routing.source = key;
+        save_clock_routing(&self.rust().clock_routing);
+        self.as_mut().maintain_clock_routing();
+        true
+    }
+
+    /// Turn clock on or off for one receiver. Turning it off removes the
+    /// link from the clock source.
+    pub fn set_clock_receiver(mut self: Pin<&mut Self>, node_id: u32, enabled: bool) -> bool {
+        let Some(graph) = self.rust().graph.clone() else {
+            return false;
+        };
+        let endpoint = self.clock_endpoint(node_id);
+        let Some(key) = self.clock_key(&graph, &endpoint) else {
+            log::warn!("set_clock_receiver: node {} not found", node_id);
+            return false;
+        };
+        self.as_mut()
+            .rust_mut()
+            .clock_routing
+            .set_enabled(&key, enabled);
+        save_clock_routing(&self.rust().clock_routing);
+        if enabled {
+            self.as_mut().maintain_clock_routing();
+        } else if let Some(source) = self.rust().clock_routing.source.clone() {
+            self.disconnect_clock(&graph, &source, &key);
+        }
+        true
+    }
+
+    /// Link the clock source to every enabled receiver that is present,
+    /// unlinking other clock sources from them.
+    fn maintain_clock_routing(mut self: Pin<&mut Self>) {
+        if self.rust().clock_routing.source.is_none() {
+            return;
+        }
+        let Some(graph) = self.rust().graph.clone() else {
+            return;
+        };
+        self.as_mut().refresh_node_keys(&graph.get_all_nodes());
+        let snapshot = GraphSnapshot::from_state(&graph);
+        let routing = &self.rust().clock_routing;
+
+        let mut source = None;
+        let mut receivers = Vec::new();
+        for endpoint in clock::endpoints(&snapshot) {
+            let Some(key) = self.clock_key(&graph, &endpoint) else {
+                continue;
+            };
+            if routing.source.as_ref() == Some(&key) {
+                source = Some(endpoint);
+            } else if routing.is_enabled(&key) {
+                receivers.push(endpoint);
+            }
+        }
+        let Some(source) = source else {
+            return;
+        };
+        let clock_sources = self.clock_sources(&graph, &snapshot);
+        let ops = clock::routing_ops(&snapshot, &source, &receivers, &clock_sources);
+        if !ops.is_empty() {
+            self.send_command(
+                CommandOrigin::System,
+                PwCommand::Transaction {
+                    label: "MIDI clock".to_string(),
+                    ops,
+                },
+            );
+        }
+    }
+
+    /// Remove the links from the endpoint saved as `source_key` to the one
+    /// saved as `receiver_key`.
+    fn disconnect_clock(&self, graph: &GraphState, source_key: &str, receiver_key: &str) {
+        let snapshot = GraphSnapshot::from_state(graph);
+        let endpoints = clock::endpoints(&snapshot);
+        let find = |key: &str| {
+            endpoints
+                .iter()
+                .find(|e| self.clock_key(graph, e).as_deref() == Some(key))
+        };
+        let (Some(source), Some(receiver)) = (find(source_key), find(receiver_key)) else {
+            return;
+        };
+        let outputs = source.midi_ports(&snapshot, PortDirection::Output);
+        let inputs = receiver.midi_ports(&snapshot, PortDirection::Input);
+        let ops: Vec<LinkOp> = snapshot
+            .links()
+            .iter()
+            .filter(|l| outputs.iter().any(|p| p.id == l.output_port_id))
+            .filter(|l| inputs.iter().any(|p| p.id == l.input_port_id))
+            .map(|l| LinkOp::Disconnect { link_id: l.id })
+            .collect();
+        if !ops.is_empty() {
+            self.send_command(
+                CommandOrigin::User,
+                PwCommand::Transaction {
+                    label: "Remove MIDI clock".to_string(),
+                    ops,
+                },
+            );
+        }
+    }
+
+    /// Endpoints that send clock: those that look like it, and the chosen
+    /// source.
+    fn clock_sources(&self, graph: &GraphState, snapshot: &GraphSnapshot) -> Vec<clock::Endpoint> {
+        let source = self.rust().clock_routing.source.as_deref();
+        clock::endpoints(snapshot)
+            .into_iter()
+            .filter(|e| {
+                clock::is_clock_source(snapshot, e)
+                    || (source.is_some() && self.clock_key(graph, e).as_deref() == source)
+            })
+            .collect()
+    }
+
+    /// The endpoint behind a node id from the UI, which may be a bridge
+    /// device's virtual id.
+    fn clock_endpoint(&self, node_id: u32) -> clock::Endpoint {
+        match self.rust().bridge_split.resolve_virtual_node(node_id) {
+            Some((real_node_id, group)) => clock::Endpoint {
+                node_id: *real_node_id,
+                group: Some(group.clone()),
+            },
+            None => clock::Endpoint {
+                node_id,
+                group: None,
+            },
+        }
+    }
+
+    /// The node id the UI shows `endpoint` under.
+    fn clock_endpoint_id(&self, endpoint: &clock::Endpoint) -> u32 {
+        endpoint
+            .group
+            .as_ref()
+            .and_then(|group| {
+                let key = (endpoint.node_id, group.clone());
+                self.rust().bridge_split.real_to_virtual.get(&key).copied()
+            })
+            .unwrap_or(endpoint.node_id)
+    }
+
+    /// How `endpoint` is saved: its layout key, as the graph view keys it.
+    fn clock_key(&self, graph: &GraphState, endpoint: &clock::Endpoint) -> Option<String> {
+        let node = graph.get_node(endpoint.node_id)?;
+        Some(match &endpoint.group {
+            Some(group) => format!("MidiBridge:{}", bridge_device_name(graph, &node, group)),
+            None => self.layout_key_of(&node),
+        })
+    }
+
+    fn clock_endpoint_name(&self, graph: &GraphState, endpoint: &clock::Endpoint) -> String {
+        match (graph.get_node(endpoint.node_id), &endpoint.group) {
+            (Some(node), Some(group)) => bridge_device_name(graph, &node, group),
+            (Some(node), None) => node.display_name().to_string(),
+            (None, _) => format!("Node {}", endpoint.node_id),
+        }
+    }
+
+    /// Connect every configured sidechain source whose plugin and source
+    /// nodes are both present. Already-linked channels are left alone.
+    fn maintain_sidechains(mut self: Pin<&mut Self>) {
+        if self.rust().sidechain_sources.is_empty() {
+            return;
+        }
+        let Some(graph) = self.rust().graph.clone() else {
+            return;
+        };
+        let nodes = graph.get_all_nodes();
+        self.as_mut().refresh_node_keys(&nodes);
+        let Some(ref mgr) = self.rust().plugin_manager else {
+            return;
+        };
+        let node_keys = &self.rust().node_keys;
+
+        let mut ops = Vec::new();
+        for (sid, source_key) in &self.rust().sidechain_sources {
+            let Some(plugin_node) = mgr.find_by_stable_id(sid).and_then(|i| i.pw_node_id) else {
+                continue;
+            };
+            let Some(source) = nodes
+                .iter()
+                .find(|n| n.ready && layout_key(n, Some(mgr), node_keys) == *source_key)
+            else {
+                continue;
+            };
+            let mut source_outputs: Vec<_> = graph
+                .get_ports_for_node(source.id)
+                .into_iter()
+                .filter(|p| {
+                    p.direction == PortDirection::Output
+                        && p.media_type == Some(crate::pipewire::MediaType::Audio)
+                })
+                .collect();
+            if source_outputs.is_empty() {
+                continue;
+            }
+            source_outputs.sort_by(|a, b| crate::pipewire::state::natural_cmp(&a.name, &b.name));
+
+            for (ch, sc_port) in sidechain_ports(&graph, plugin_node).iter().enumerate() {
+                let out = &source_outputs[ch.min(source_outputs.len() - 1)];
+                if graph.find_link(out.id, sc_port.id).is_none() {
+                    ops.push(LinkOp::Connect {
+                        output_port_id: out.id,
+                        input_port_id: sc_port.id,
+                    });
+                }
+            }
+        }
+
+        // Sent as a transaction so a repeat pass issued before the links
+        // show up finds its steps already satisfied instead of duplicating.
+        if !ops.is_empty() {
+            self.send_command(
+                CommandOrigin::System,
+                PwCommand::Transaction {
+                    label: "Sidechain".to_string(),
+                    ops,
+                },
+            );
+        }
+    }
+
+    /// Register a saved plugin and ask the PipeWire thread to instantiate it
+    /// with its saved parameters and state.
+    fn start_saved_plugin(mut self: Pin<&mut Self>, sp: SavedPlugin) {
+        let instance_id = self.rust().next_instance_id;
+        self.as_mut().rust_mut().next_instance_id += 1;
+
+        let restored_params: Vec<crate::lv2::Lv2ParameterValue> = if let Some(ref mgr) =
+            self.rust().plugin_manager
+        {
+            if let Some(plugin_info) = mgr.find_plugin(&sp.uri) {
+                plugin_info
+                    .ports
+                    .iter()
+                    .filter(|port| port.port_type == crate::lv2::Lv2PortType::ControlInput)
+                    .map(|port| {
+                        let saved_value = sp.parameters.iter().find(|s| {
+                            s.port_index == port.index
+                                || (!s.symbol.is_empty() && s.symbol == port.symbol)
+                        });
+                        crate::lv2::Lv2ParameterValue {
+                            port_index: port.index,
+                            symbol: port.symbol.clone(),
+                            name: port.name.clone(),
+                            value: saved_value
+                                .map(|s| s.value)
+                                .unwrap_or(port.default_value),
+                            min: port.min_value,
+                            max: port.max_value,
+                            default: port.default_value,
+                            is_toggle: port.is_toggle,
+                        }
+                    })
+                    .collect()
+            } else {
+                sp.parameters
+                    .iter()
+                    .map(|p| crate::lv2::Lv2ParameterValue {
+                        port_index: p.port_index,
+                        symbol: p.symbol.clone(),
+                        name: String::new(),
+                        value: p.value,
+                        min: 0.0,
+                        max: 1.0,
+                        default: 0.0,
+                        is_toggle: false,
+                    })
+                    .collect()
+            }
+        } else {
+            Vec::new()
+        };
+
+        let sid = if sp.stable_id.is_empty() {
+            uuid::Uuid::new_v4().to_string()
+        } else {
+            sp.stable_id.clone()
+        };
+
+        let plugin_format = match sp.format.as_str() {
+            "CLAP" => crate::plugin::PluginFormat::Clap,
+            "VST3" => crate::plugin::PluginFormat::Vst3,
+            "Builtin" => crate::plugin::PluginFormat::Builtin,
+            _ => crate::plugin::PluginFormat::Lv2,
+        };
+
+        match sp.quantum {
+            Some(quantum) => {
+                self.as_mut()
+                    .rust_mut()
+                    .plugin_quanta
+                    .insert(sid.clone(), quantum);
+            }
+            None => {
+                self.as_mut().rust_mut().plugin_quanta.remove(&sid);
+            }
+        }
+
+        if let Some(ref mut mgr) = self.as_mut().rust_mut().plugin_manager {
+            let info = crate::lv2::Lv2InstanceInfo {
+                id: instance_id,
+                stable_id: sid,
+                plugin_uri: sp.uri.clone(),
+                format: plugin_format,
+                display_name: sp.display_name.clone(),
+                pw_node_id: None,
+                parameters: restored_params,
+                active: true,
+                bypassed: sp.bypassed,
+                lv2_state: sp.lv2_state.clone(),
+                oversampling: sp.oversampling,
+            };
+            mgr.register_instance(info);
+        }
+
+        let format_str = sp.format.clone();
+        log::info!(
+            "Restoring plugin: {} ({}) [{}]",
+            sp.display_name,
+            sp.uri,
+            format_str
+        );
+        self.send_command(
+            CommandOrigin::Restore,
+            PwCommand::AddPlugin {
+                plugin_uri: sp.uri,
+                instance_id,
+                display_name: sp.display_name,
+                format: format_str,
+                lv2_state: sp.lv2_state,
+                oversampling: sp.oversampling,
+            },
+        );
+    }
+
+    /// Assign persistent identities to `nodes`. Sidechain sources saved under
+    /// a node's old "Type:DisplayName" key move over to its identity, rules
+    /// saved by name pick up the identities of their nodes, and a renamed
+    /// default node keeps acting as the default target.
+    fn refresh_node_keys(mut self: Pin<&mut Self>, nodes: &[Node]) {
+        let node_keys = self.as_mut().rust_mut().node_ids.identify(nodes);
+        let renamed = self.as_mut().rust_mut().node_ids.take_dirty();
+        if renamed {
+            save_node_ids(&self.rust().node_ids);
+        }
+
+        let saved_ids = self.rust().node_ids.saved_ids(nodes);
+        if let Some(ref mut patchbay) = self.as_mut().rust_mut().patchbay {
+            patchbay.set_identities(saved_ids);
+            if patchbay.adopt_identities(nodes) {
+                patchbay.rules_dirty = false;
+                save_rules(Some(&*patchbay));
+            }
+        }
+
+        let mut migrated = false;
+        for n in nodes {
+            let Some(id) = node_keys.get(&n.id) else {
+                continue;
+            };
+            let old_key = type_name_key(n, None);
+            for source in self.as_mut().rust_mut().sidechain_sources.values_mut() {
+                if *source == old_key {
+                    *source = format!("Node:{}", id);
+                    migrated = true;
+                }
+            }
+        }
+        if migrated {
+            save_sidechain_sources(&self.rust().sidechain_sources);
+        }
+        self.as_mut().rust_mut().node_keys = node_keys;
+
+        if renamed && let Ok(key) = std::fs::read_to_string(config_path("default_node.txt")) {
+            let display_name = layout_key_display_name(key.trim(), &self.rust().node_ids);
+            if let Some(ref mut patchbay) = self.as_mut().rust_mut().patchbay {
+                patchbay.set_default_target(Some(display_name));
+            }
+        }
+    }
+
+    pub fn remove_plugin_by_stable_id(mut self: Pin<&mut Self>, stable_id: QString) {
+        let sid: String = stable_id.to_string();
+
+        let instance_id = self
+            .rust()
+            .plugin_manager
+            .as_ref()
+            .and_then(|mgr| mgr.instance_id_for_stable_id(&sid));
+
+        if let Some(instance_id) = instance_id {
+            self.send_command(CommandOrigin::User, PwCommand::RemovePlugin { instance_id });
+            if let Some(ref mut mgr) = self.as_mut().rust_mut().plugin_manager {
+                mgr.remove_instance(instance_id);
+            }
+            self.as_mut().rust_mut().manual_start_plugins.remove(&sid);
+            self.as_mut().rust_mut().plugin_quanta.remove(&sid);
+            self.as_mut().rust_mut().param_history.forget(&sid);
+            self.as_mut().rust_mut().plugin_cpu_history.forget(&sid);
+            self.as_mut().rust_mut().plugin_cpu_alerts.forget(&sid);
+            persist_active_plugins(self.rust());
+            log::info!("Removed plugin instance (stable_id={})", sid);
+        } else if let Some(pos) = self
+            .rust()
+            .dormant_plugins
+            .iter()
+            .position(|sp| sp.stable_id == sid)
+        {
+            let sp = self.as_mut().rust_mut().dormant_plugins.remove(pos);
+            self.as_mut()
+                .rust_mut()
+                .dormant_links
+                .retain(|l| !l.touches(&sp.display_name));
+            persist_active_plugins(self.rust());
+            persist_lv2_links(
+                self.rust().graph.as_ref(),
+                &self.rust().dormant_links,
+                &self.rust().node_ids,
+            );
+            log::info!(
+                "Removed saved plugin {} (stable_id={})",
+                sp.display_name,
+                sid
+            );
+        } else {
+            log::warn!(
+                "remove_plugin_by_stable_id: no instance found for stable_id={}",
+                sid
+            );
+        }
+    }
+
+    /// Saved plugins set to manual start that are not running.
+    pub fn get_dormant_plugins_json(self: Pin<&mut Self>) -> QString {
+        let mut entries: Vec<serde_json::Value> = self
+            .rust()
+            .dormant_plugins
+            .iter()
+            .map(|sp| {
+                serde_json::json!({
+                    "stableId": sp.stable_id,
+                    "pluginUri": sp.uri,
+                    "displayName": sp.display_name,
+                    "format": sp.format,
+                    "bypassed": sp.bypassed,
+                    "paramCount": sp.parameters.len(),
+                    "autostart": !sp.manual_start,
+                })
+            })
+            .collect();
+        entries.sort_by(|a, b| {
+            let a_name = a["displayName"].as_str().unwrap_or("");
+            let b_name = b["displayName"].as_str().unwrap_or("");
+            a_name.cmp(b_name)
+        });
+        let json = serde_json::to_string(&entries).unwrap_or_default();
+        QString::from(&json)
+    }
+
+    /// Start a manual-start plugin with its saved parameters and state, and
+    /// restore its saved links once it is up. It stays manual-start.
+    pub fn activate_saved_plugin(mut self: Pin<&mut Self>, stable_id: QString) -> bool {
+        let sid = stable_id.to_string();
+        if self.rust().plugins_frozen {
+            log::warn!("activate_saved_plugin: plugins are frozen in safe mode");
+            return false;
+        }
+        let Some(pos) = self
+            .rust()
+            .dormant_plugins
+            .iter()
+            .position(|sp| sp.stable_id == sid)
+        else {
+            log::warn!("activate_saved_plugin: no saved plugin {}", sid);
+            return false;
+        };
+        let sp = self.as_mut().rust_mut().dormant_plugins.remove(pos);
+        if sp.manual_start {
+            self.as_mut().rust_mut().manual_start_plugins.insert(sid);
+        }
+
+        let (links, dormant_links): (Vec<_>, Vec<_>) =
+            std::mem::take(&mut self.as_mut().rust_mut().dormant_links)
+                .into_iter()
+                .partition(|l| l.touches(&sp.display_name));
+        self.as_mut().rust_mut().dormant_links = dormant_links;
+
+        self.as_mut().start_saved_plugin(sp);
+        if !links.is_empty() {
+            // Restored through the saved-link path, which waits for the
+            // plugin to be added and the graph to settle.
+            self.as_mut().rust_mut().pending_restore_count += 1;
+            self.as_mut().rust_mut().pending_links.extend(links);
+        }
+        persist_active_plugins(self.rust());
+        true
+    }
+
+    /// Choose whether a running or not yet started plugin is started at
+    /// launch.
+    pub fn set_plugin_autostart(mut self: Pin<&mut Self>, stable_id: QString, autostart: bool) {
+        let sid = stable_id.to_string();
+        if let Some(sp) = self
+            .as_mut()
+            .rust_mut()
+            .dormant_plugins
+            .iter_mut()
+            .find(|sp| sp.stable_id == sid)
+        {
+            sp.manual_start = !autostart;
+        } else if autostart {
+            self.as_mut().rust_mut().manual_start_plugins.remove(&sid);
+        } else {
+            self.as_mut()
+                .rust_mut()
+                .manual_start_plugins
+                .insert(sid.clone());
+        }
+        if autostart {
+            log::info!("Plugin {} starts at launch", sid);
+        } else {
+            log::info!("Plugin {} set to manual start", sid);
+        }
+        persist_active_plugins(self.rust());
+    }
+
+    /// Create a second instance of a plugin with the same parameters, bypass
+    /// state and LV2 state. With `mirror_connections`, the copy is wired to the
+    /// same sources and targets as the original once it appears in the graph.
+    /// Returns the new display name, or an empty string on failure.
+    pub fn duplicate_plugin(
+        mut self: Pin<&mut Self>,
+        stable_id: QString,
+        mirror_connections: bool,
+    ) -> QString {
+        let sid: String = stable_id.to_string();
+
+        let Some(source) = self
+            .rust()
+            .plugin_manager
+            .as_ref()
+            .and_then(|mgr| mgr.find_by_stable_id(&sid))
+            .cloned()
+        else {
+            log::warn!("duplicate_plugin: no instance found for stable_id={}", sid);
+            return QString::from("");
+        };
+
+        let display_name = self.unique_display_name(strip_instance_suffix(&source.display_name));
+        let instance_id = self.rust().next_instance_id;
+        self.as_mut().rust_mut().next_instance_id += 1;
+
+        let mirrored: Vec<SavedPluginLink> = match (mirror_connections, self.rust().graph.as_ref()) {
+            (true, Some(graph)) => build_persistable_links(graph, &self.rust().node_ids)
+                .into_iter()
+                .filter_map(|link| {
+                    let from_source = link.output_node_name == source.display_name;
+                    let to_source = link.input_node_name == source.display_name;
+                    match (from_source, to_source) {
+                        (true, false) => Some(SavedPluginLink {
+                            output_node_name: display_name.clone(),
+                            ..link
+                        }),
+                        (false, true) => Some(SavedPluginLink {
+                            input_node_name: display_name.clone(),
+                            ..link
+                        }),
+                        _ => None,
+                    }
+                })
+                .collect(),
+            _ => Vec::new(),
+        };
+
+        let format_str = source.format.as_str().to_string();
+        let info = crate::lv2::Lv2InstanceInfo {
+            id: instance_id,
+            stable_id: uuid::Uuid::new_v4().to_string(),
+            plugin_uri: source.plugin_uri.clone(),
+            format: source.format,
+            display_name: display_name.clone(),
+            pw_node_id: None,
+            parameters: source.parameters.clone(),
+            active: true,
+            bypassed: source.bypassed,
+            lv2_state: source.lv2_state.clone(),
+            oversampling: source.oversampling,
+        };
+        if let Some(ref mut mgr) = self.as_mut().rust_mut().plugin_manager {
+            mgr.register_instance(info);
+        }
+
+        // Parameters and bypass are pushed to the new filter from the
+        // registered instance info once PluginAdded arrives.
+        log::info!(
+            "Duplicating plugin {} as {} (instance_id={}, {} mirrored links)",
+            source.display_name,
+            display_name,
+            instance_id,
+            mirrored.len()
+        );
+        self.send_command(
+            CommandOrigin::User,
+            PwCommand::AddPlugin {
+                plugin_uri: source.plugin_uri.clone(),
+                instance_id,
+                display_name: display_name.clone(),
+                format: format_str,
+                lv2_state: source.lv2_state.clone(),
+                oversampling: source.oversampling,
+            },
+        );
+
+        if !mirrored.is_empty() {
+            // Reuse the saved-link restore path: it waits for the pending
+            // plugin to be added and for the graph to settle.
+            self.as_mut().rust_mut().pending_restore_count += 1;
+            self.as_mut().rust_mut().pending_links.extend(mirrored);
+        }
+
+        persist_active_plugins(self.rust());
+
+        QString::from(&display_name)
+    }
+
+    /// The hosted plugins among `node_ids_json` and the links between them,
+    /// as a snippet for the clipboard. Returns an empty string when none of
+    /// the nodes is a hosted plugin.
+    pub fn copy_selection(self: Pin<&mut Self>, node_ids_json: QString) -> QString {
+        let ids: Vec<u32> = match serde_json::from_str(&node_ids_json.to_string()) {
+            Ok(ids) => ids,
+            Err(e) => {
+                log::warn!("copy_selection: invalid node list: {}", e);
+                return QString::from("");
+            }
+        };
+        let (Some(mgr), Some(graph)) = (
+            self.rust().plugin_manager.as_ref(),
+            self.rust().graph.as_ref(),
+        ) else {
+            return QString::from("");
+        };
+        let instances: Vec<&crate::lv2::Lv2InstanceInfo> = ids
+            .iter()
+            .filter_map(|id| {
+                mgr.active_instances()
+                    .values()
+                    .find(|info| info.pw_node_id == Some(*id))
+            })
+            .collect();
+        if instances.is_empty() {
+            return QString::from("");
+        }
+
+        let links = build_persistable_links(graph, &self.rust().node_ids);
+        let snippet = PluginSnippet::copy(&instances, &links);
+        log::info!(
+            "Copied {} plugin(s) and {} link(s)",
+            snippet.plugins.len(),
+            snippet.links.len()
+        );
+        QString::from(&snippet.to_json())
+    }
+
+    /// Add the plugins of a snippet from `copy_selection` as new instances
+    /// and wire them as they were. Plugins that are not installed are
+    /// skipped. Returns a summary for the user, or an empty string after
+    /// reporting the failure through `error_occurred`.
+    pub fn paste_selection(mut self: Pin<&mut Self>, json: QString) -> QString {
+        let snippet = match PluginSnippet::parse(&json.to_string()) {
+            Ok(snippet) => snippet,
+            Err(e) => {
+                log::warn!("paste_selection: {}", e);
+                let msg = format!("Paste failed: {}", e);
+                self.as_mut().error_occurred(QString::from(msg.as_str()));
+                return QString::from("");
+            }
+        };
+
+        let (pasted, skipped) = self.as_mut().create_from_snippet(&snippet);
+        let mut summary = format!("Pasted {} plugin(s)", pasted);
+        if !skipped.is_empty() {
+            summary.push_str(&format!(", skipped {} (not installed)", skipped.join(", ")));
+        }
+        log::info!("paste_selection: {}", summary);
+        QString::from(summary.as_str())
+    }
+
+    /// Add the installed plugins of `snippet` as new instances and queue the
+    /// links between them. Returns the number of plugins added and the names
+    /// of those skipped.
+    fn create_from_snippet(
+        mut self: Pin<&mut Self>,
+        snippet: &PluginSnippet,
+    ) -> (usize, Vec<String>) {
+        let mut names = Vec::new();
+        let mut skipped = Vec::new();
+        for copied in &snippet.plugins {
+            let Some(plugin) = self
+                .rust()
+                .plugin_manager
+                .as_ref()
+                .and_then(|mgr| mgr.find_plugin_with_format(&copied.uri, copied.format))
+                .cloned()
+            else {
+                skipped.push(copied.name.clone());
+                names.push(None);
+                continue;
+            };
+
+            let display_name = self.unique_display_name(strip_instance_suffix(&copied.name));
+            let instance_id = self.rust().next_instance_id;
+            self.as_mut().rust_mut().next_instance_id += 1;
+            if let Some(ref mut mgr) = self.as_mut().rust_mut().plugin_manager {
+                mgr.register_instance(crate::lv2::Lv2InstanceInfo {
+                    id: instance_id,
+                    stable_id: uuid::Uuid::new_v4().to_string(),
+                    plugin_uri: plugin.uri.clone(),
+                    format: plugin.format,
+                    display_name: display_name.clone(),
+                    pw_node_id: None,
+                    parameters: transfer_parameters(&copied.parameter_values(), &plugin),
+                    active: true,
+                    bypassed: copied.bypassed,
+                    lv2_state: copied.state.clone(),
+                    oversampling: copied.oversampling,
+                });
+            }
+            // Parameters and bypass are pushed to the new filter from the
+            // registered instance info once PluginAdded arrives.
+            self.send_command(
+                CommandOrigin::User,
+                PwCommand::AddPlugin {
+                    plugin_uri: plugin.uri.clone(),
+                    instance_id,
+                    display_name: display_name.clone(),
+                    format: plugin.format.as_str().to_string(),
+                    lv2_state: copied.state.clone(),
+                    oversampling: copied.oversampling,
+                },
+            );
+            names.push(Some(display_name));
+        }
+
+        let links = snippet.links_between(&names);
+        if !links.is_empty() {
+            // Reuse the saved-link restore path, which waits for the plugins.
+            self.as_mut().rust_mut().pending_restore_count += 1;
+            self.as_mut().rust_mut().pending_links.extend(links);
+        }
+        persist_active_plugins(self.rust());
+
+        (names.iter().flatten().count(), skipped)
+    }
+
+    /// Swap a plugin for a different one in place. The new plugin takes over
+    /// the old one's links, bypass, oversampling, sidechain source and every
+    /// parameter it shares by symbol or name. Returns the new display name,
+    /// or an empty string when either plugin is unknown.
+    pub fn replace_plugin(
+        mut self: Pin<&mut Self>,
+        stable_id: QString,
+        new_uri: QString,
+    ) -> QString {
+        let sid = stable_id.to_string();
+        let uri = new_uri.to_string();
+        if self.rust().plugins_frozen {
+            log::warn!("replace_plugin: plugins are frozen in safe mode");
+            return QString::from("");
+        }
+
+        let Some(old) = self
+            .rust()
+            .plugin_manager
+            .as_ref()
+            .and_then(|mgr| mgr.find_by_stable_id(&sid))
+            .cloned()
+        else {
+            log::warn!("replace_plugin: no instance found for stable_id={}", sid);
+            return QString::from("");
+        };
+        let Some((new_plugin, renamed)) = self.rust().plugin_manager.as_ref().and_then(|mgr| {
+            let renamed = mgr
+                .find_plugin(&old.plugin_uri)
+                .is_some_and(|p| p.name != strip_instance_suffix(&old.display_name));
+            mgr.find_plugin(&uri).cloned().map(|p| (p, renamed))
+        }) else {
+            log::warn!("replace_plugin: unknown plugin {}", uri);
+            return QString::from("");
+        };
+
+        // A name the user picked stays with the slot; a default one follows
+        // the new plugin.
+        let display_name = if renamed {
+            old.display_name.clone()
+        } else {
+            self.unique_display_name(&new_plugin.name)
+        };
+
+        let links: Vec<SavedPluginLink> = match self.rust().graph.as_ref() {
+            Some(graph) => build_persistable_links(graph, &self.rust().node_ids)
+                .into_iter()
+                .filter_map(|link| {
+                    if link.output_node_name == old.display_name {
+                        let port = replacement_port(&link.output_port_name, &new_plugin)?;
+                        Some(SavedPluginLink {
+                            output_node_name: display_name.clone(),
+                            output_port_name: port,
+                            ..link
+                        })
+                    } else if link.input_node_name == old.display_name {
+                        let port = replacement_port(&link.input_port_name, &new_plugin)?;
+                        Some(SavedPluginLink {
+                            input_node_name: display_name.clone(),
+                            input_port_name: port,
+                            ..link
+                        })
+                    } else {
+                        None
+                    }
+                })
+                .collect(),
+            None => Vec::new(),
+        };
+
+        let instance_id = self.rust().next_instance_id;
+        self.as_mut().rust_mut().next_instance_id += 1;
+        let new_sid = uuid::Uuid::new_v4().to_string();
+        let info = crate::lv2::Lv2InstanceInfo {
+            id: instance_id,
+            stable_id: new_sid.clone(),
+            plugin_uri: uri.clone(),
+            format: new_plugin.format,
+            display_name: display_name.clone(),
+            pw_node_id: None,
+            parameters: transfer_parameters(&old.parameters, &new_plugin),
+            active: true,
+            bypassed: old.bypassed,
+            lv2_state: Vec::new(),
+            oversampling: old.oversampling,
+        };
+        if let Some(ref mut mgr) = self.as_mut().rust_mut().plugin_manager {
+            mgr.remove_instance(old.id);
+            mgr.register_instance(info);
+        }
+
+        // Both commands go out together so the old filter is gone by the
+        // time the new one is added under the same name.
+        log::info!(
+            "Replacing plugin {} with {} as {} (instance_id={}, {} links)",
+            old.display_name,
+            uri,
+            display_name,
+            instance_id,
+            links.len()
+        );
+        self.send_command(
+            CommandOrigin::User,
+            PwCommand::RemovePlugin {
+                instance_id: old.id,
+            },
+        );
+        self.send_command(
+            CommandOrigin::User,
+            PwCommand::AddPlugin {
+                plugin_uri: uri,
+                instance_id,
+                display_name: display_name.clone(),
+                format: new_plugin.format.as_str().to_string(),
+                lv2_state: Vec::new(),
+                oversampling: old.oversampling,
+            },
+        );
+
+        if let Some(source) = self.as_mut().rust_mut().sidechain_sources.remove(&sid) {
+            if new_plugin.ports.iter().any(|p| p.is_sidechain) {
+                self.as_mut()
+                    .rust_mut()
+                    .sidechain_sources
+                    .insert(new_sid.clone(), source);
+            }
+            save_sidechain_sources(&self.rust().sidechain_sources);
+        }
+        if self.as_mut().rust_mut().manual_start_plugins.remove(&sid) {
+            self.as_mut()
+                .rust_mut()
+                .manual_start_plugins
+                .insert(new_sid);
+        }
+
+        if !links.is_empty() {
+            self.as_mut().rust_mut().pending_restore_count += 1;
+            self.as_mut().rust_mut().pending_links.extend(links);
+        }
+
+        persist_active_plugins(self.rust());
+
+        QString::from(&display_name)
+    }
+
+    pub fn reset_plugin_params_by_stable_id(mut self: Pin<&mut Self>, stable_id: QString) {
+        let sid: String = stable_id.to_string();
+
+        let resets: Vec<(u64, usize, f32)> = if let Some(ref mgr) = self.rust().plugin_manager {
+            if let Some(info) = mgr.find_by_stable_id(&sid) {
+                info.parameters
+                    .iter()
+                    .map(|p| (info.id, p.port_index, p.default))
+                    .collect()
+            } else {
+                Vec::new()
+            }
+        } else {
+            Vec::new()
+        };
+
+        if resets.is_empty() {
+            return;
+        }
+
+        let instance_id = resets[0].0;
+        let values: Vec<(usize, f32)> = resets
+            .iter()
+            .map(|&(_, port, value)| (port, value))
+            .collect();
+        self.as_mut().apply_plugin_params(instance_id, &values);
+        log::info!(
+            "Reset {} params to defaults for stable_id={}",
+            resets.len(),
+            sid
+        );
+    }
+
+    pub fn set_plugin_param_by_stable_id(
+        mut self: Pin<&mut Self>,
+        stable_id: QString,
+        port_index: u32,
+        value: f32,
+    ) {
+        let sid: String = stable_id.to_string();
+
+        let instance_id = self
+            .rust()
+            .plugin_manager
+            .as_ref()
+            .and_then(|mgr| mgr.instance_id_for_stable_id(&sid));
+
+        if let Some(instance_id) = instance_id {
+            if let Some(ref mut mgr) = self.as_mut().rust_mut().plugin_manager {
+                mgr.update_parameter(instance_id, port_index as usize, value);
+            }
+            self.send_command(
+                CommandOrigin::User,
+                PwCommand::SetPluginParameter {
+                    instance_id,
+                    port_index: port_index as usize,
+                    value,
+                },
+            );
+            self.as_mut().rust_mut().params_dirty = true;
+            if self.rust().params_dirty_since.is_none() {
+                self.as_mut().rust_mut().params_dirty_since = Some(Instant::now());
+            }
+        }
+    }
+
+    /// Move every parameter of a plugin by a random offset of up to `amount`
+    /// (0 to 1) of its range, skipping the symbols in `locked_params_json`.
+    /// The previous values are kept for `undo_plugin_params`.
+    pub fn randomize_plugin_params(
+        mut self: Pin<&mut Self>,
+        stable_id: QString,
+        amount: f64,
+        locked_params_json: QString,
+    ) -> bool {
+        let sid = stable_id.to_string();
+        let locked: Vec<String> = match serde_json::from_str(&locked_params_json.to_string()) {
+            Ok(locked) => locked,
+            Err(e) => {
+                log::warn!("randomize_plugin_params: invalid locked list: {}", e);
+                return false;
+            }
+        };
+
+        let Some((instance_id, changes, previous)) = self
+            .rust()
+            .plugin_manager
+            .as_ref()
+            .and_then(|mgr| mgr.find_by_stable_id(&sid))
+            .map(|info| {
+                let mut rng = randomize::Rng::from_entropy();
+                let changes =
+                    randomize::randomize(&info.parameters, amount as f32, &locked, &mut rng);
+                let previous: Vec<(usize, f32)> = info
+                    .parameters
+                    .iter()
+                    .filter(|p| changes.iter().any(|&(port, _)| port == p.port_index))
+                    .map(|p| (p.port_index, p.value))
+                    .collect();
+                (info.id, changes, previous)
+            })
+        else {
+            log::warn!(
+                "randomize_plugin_params: no instance found for stable_id={}",
+                sid
+            );
+            return false;
+        };
+        if changes.is_empty() {
+            return false;
+        }
+
+        self.as_mut()
+            .rust_mut()
+            .param_history
+            .record(&sid, previous);
+        self.as_mut().apply_plugin_params(instance_id, &changes);
+        log::info!(
+            "Randomized {} params by {:.0}% for stable_id={}",
+            changes.len(),
+            amount * 100.0,
+            sid
+        );
+        true
+    }
+
+    /// Restore the parameters a plugin had before its latest randomization.
+    pub fn undo_plugin_params(mut self: Pin<&mut Self>, stable_id: QString) -> bool {
+        let sid = stable_id.to_string();
+        let instance_id = self
+            .rust()
+            .plugin_manager
+            .as_ref()
+            .and_then(|mgr| mgr.instance_id_for_stable_id(&sid));
+        let Some(instance_id) = instance_id else {
+            return false;
+        };
+        let Some(previous) = self.as_mut().rust_mut().param_history.undo(&sid) else {
+            return false;
+        };
+        self.as_mut().apply_plugin_params(instance_id, &previous);
+        log::info!("Undid parameter change for stable_id={}", sid);
+        true
+    }
+
+    /// Write a plugin's parameters to `path`, as CSV for a `.csv` file and
+    /// JSON otherwise; an empty path writes JSON under `params/` in the
+    /// config directory. Returns the written path, or an empty string after
+    /// reporting the failure through `error_occurred`.
+    pub fn export_plugin_params(
+        mut self: Pin<&mut Self>,
+        stable_id: QString,
+        path: QString,
+    ) -> QString {
+        let sid = stable_id.to_string();
+        let path = std::path::PathBuf::from(path.to_string().trim());
+        let result = self
+            .rust()
+            .plugin_manager
+            .as_ref()
+            .and_then(|mgr| mgr.find_by_stable_id(&sid))
+            .map(ParamFile::new)
+            .ok_or_else(|| format!("no plugin instance {}", sid))
+            .and_then(|file| {
+                if path.as_os_str().is_empty() {
+                    let json = file.render(ParamFileFormat::Json);
+                    return save_config_export("params", &file.name, "json", &json);
+                }
+                let text = file.render(ParamFileFormat::from_path(&path));
+                if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+                    let _ = std::fs::create_dir_all(parent);
+                }
+                std::fs::write(&path, text)
+                    .map(|_| path.clone())
+                    .map_err(|e| format!("failed to write {:?}: {}", path, e))
+            });
+        match result {
+            Ok(path) => {
+                log::info!("Exported parameters of {} to {:?}", sid, path);
+                QString::from(path.to_string_lossy().as_ref())
+            }
+            Err(e) => {
+                log::warn!("export_plugin_params: {}", e);
+                let msg = format!("Parameter export failed: {}", e);
+                self.as_mut().error_occurred(QString::from(msg.as_str()));
+                QString::from("")
+            }
+        }
+    }
+
+    /// Set a plugin's parameters from a file written by
+    /// `export_plugin_params` or edited elsewhere, matching them by symbol.
+    /// The previous values can be restored with `undo_plugin_params`.
+    /// Returns a summary, or an empty string after reporting the failure
+    /// through `error_occurred`.
+    pub fn import_plugin_params(
+        mut self: Pin<&mut Self>,
+        stable_id: QString,
+        path: QString,
+    ) -> QString {
+        let sid = stable_id.to_string();
+        let path = std::path::PathBuf::from(path.to_string().trim());
+        let file = std::fs::read_to_string(&path)
+            .map_err(|e| format!("failed to read {:?}: {}", path, e))
+            .and_then(|text| ParamFile::parse(&text, ParamFileFormat::from_path(&path)));
+        let result = file.and_then(|file| {
+            self.rust()
+                .plugin_manager
+                .as_ref()
+                .and_then(|mgr| mgr.find_by_stable_id(&sid))
+                .map(|info| {
+                    let (values, unknown) = file.values_for(&info.parameters);
+                    let previous: Vec<(usize, f32)> = info
+                        .parameters
+                        .iter()
+                        .filter(|p| values.iter().any(|&(port, _)| port == p.port_index))
+                        .map(|p| (p.port_index, p.value))
+                        .collect();
+                    (info.id, values, previous, unknown)
+                })
+                .ok_or_else(|| format!("no plugin instance {}", sid))
+        });
+        let (instance_id, values, previous, unknown) = match result {
+            Ok(result) => result,
+            Err(e) => {
+                log::warn!("import_plugin_params: {}", e);
+                let msg = format!("Parameter import failed: {}", e);
+                self.as_mut().error_occurred(QString::from(msg.as_str()));
+                return QString::from("");
+            }
+        };
+
+        if !values.is_empty() {
+            self.as_mut()
+                .rust_mut()
+                .param_history
+                .record(&sid, previous);
+            self.as_mut().apply_plugin_params(instance_id, &values);
+        }
+        log::info!(
+            "Imported {} parameters for stable_id={} from {:?}",
+            values.len(),
+            sid,
+            path
+        );
+        let mut summary = format!("Set {} parameter(s)", values.len());
+        if !unknown.is_empty() {
+            summary.push_str(&format!("; not found: {}", unknown.join(", ")));
+        }
+        QString::from(summary.as_str())
+    }
+
+    /// The presets saved for a plugin, as `[{ name, values }]` sorted by
+    /// name, where `values` is how many parameters each sets.
+    pub fn get_plugin_presets_json(self: Pin<&mut Self>, plugin_uri: QString) -> QString {
+        let uri = plugin_uri.to_string();
+        let presets: Vec<serde_json::Value> = self
+            .rust()
+            .plugin_manager
+            .as_ref()
+            .map(|mgr| mgr.presets_for(&uri))
+            .unwrap_or_default()
+            .iter()
+            .map(|p| serde_json::json!({ "name": p.name, "values": p.values.len() }))
+            .collect();
+        QString::from(&serde_json::to_string(&presets).unwrap_or_else(|_| "[]".into()))
+    }
+
+    /// Save a plugin instance's parameters as a preset of its plugin,
+    /// replacing one of the same name.
+    pub fn save_plugin_preset(mut self: Pin<&mut Self>, stable_id: QString, name: QString) -> bool {
+        let sid = stable_id.to_string();
+        let name = name.to_string();
+        let saved = match self.as_mut().rust_mut().plugin_manager {
+            Some(ref mut mgr) => mgr
+                .instance_id_for_stable_id(&sid)
+                .is_some_and(|instance_id| mgr.save_preset(instance_id, &name)),
+            None => false,
+        };
+        if !saved {
+            return false;
+        }
+        log::info!("Saved preset \"{}\" from stable_id={}", name.trim(), sid);
+        if let Some(ref mgr) = self.rust().plugin_manager {
+            save_plugin_presets(mgr.presets());
+        }
+        true
+    }
+
+    /// Load a preset of the instance's plugin into it. The previous values
+    /// can be restored with `undo_plugin_params`.
+    pub fn load_plugin_preset(mut self: Pin<&mut Self>, stable_id: QString, name: QString) -> bool {
+        let sid = stable_id.to_string();
+        let name = name.to_string();
+        let Some((instance_id, uri, previous)) = self
+            .rust()
+            .plugin_manager
+            .as_ref()
+            .and_then(|mgr| mgr.find_by_stable_id(&sid))
+            .map(|info| {
+                let previous: Vec<(usize, f32)> = info
+                    .parameters
+                    .iter()
+                    .map(|p| (p.port_index, p.value))
+                    .collect();
+                (info.id, info.plugin_uri.clone(), previous)
+            })
+        else {
+            return false;
+        };
+        let commands = self
+            .as_mut()
+            .rust_mut()
+            .plugin_manager
+            .as_mut()
+            .and_then(|mgr| mgr.load_preset(instance_id, &uri, &name));
+        let Some(commands) = commands else {
+            return false;
+        };
+
+        self.as_mut()
+            .rust_mut()
+            .param_history
+            .record(&sid, previous);
+        log::info!(
+            "Loading preset \"{}\" into stable_id={} ({} parameters)",
+            name,
+            sid,
+            commands.len()
+        );
+        for cmd in commands {
+            self.send_command(CommandOrigin::User, cmd);
+        }
+        self.as_mut().rust_mut().params_dirty = true;
+        if self.rust().params_dirty_since.is_none() {
+            self.as_mut().rust_mut().params_dirty_since = Some(Instant::now());
+        }
+        true
+    }
+
+    /// The saved chain presets, as `[{ name, plugins, missing }]` sorted by
+    /// name: the display names of a chain's plugins in order, and those of
+    /// the plugins it needs that are not installed.
+    pub fn get_chain_presets_json(self: Pin<&mut Self>) -> QString {
+        let chains: Vec<serde_json::Value> = match self.rust().plugin_manager {
+            Some(ref mgr) => mgr
+                .chain_presets()
+                .iter()
+                .map(|chain| {
+                    let plugins: Vec<&str> =
+                        chain.plugins.iter().map(|p| p.name.as_str()).collect();
+                    let missing: Vec<&str> = chain
+                        .missing(mgr.available_plugins())
+                        .into_iter()
+                        .map(|r| r.name.as_str())
+                        .collect();
+                    serde_json::json!({
+                        "name": chain.name,
+                        "plugins": plugins,
+                        "missing": missing,
+                    })
+                })
+                .collect(),
+            None => Vec::new(),
+        };
+        QString::from(&serde_json::to_string(&chains).unwrap_or_else(|_| "[]".into()))
+    }
+
+    /// Save the chain `node_id` belongs to, with its parameters and internal
+    /// links, as chain preset `name`, replacing one of the same name.
+    pub fn save_chain_preset(mut self: Pin<&mut Self>, node_id: u32, name: QString) -> bool {
+        let name = name.to_string();
+        let chain: Vec<u64> = self
+            .chain_plugins(node_id)
+            .into_iter()
+            .map(|(instance_id, _)| instance_id)
+            .collect();
+        let links = match self.rust().graph {
+            Some(ref graph) => build_persistable_links(graph, &self.rust().node_ids),
+            None => Vec::new(),
+        };
+        let saved = self
+            .as_mut()
+            .rust_mut()
+            .plugin_manager
+            .as_mut()
+            .is_some_and(|mgr| mgr.save_chain_preset(&name, &chain, &links));
+        if !saved {
+            return false;
+        }
+        log::info!(
+            "Saved chain preset \"{}\" ({} plugins)",
+            name.trim(),
+            chain.len()
+        );
+        if let Some(ref mgr) = self.rust().plugin_manager {
+            save_chain_presets(mgr.chain_presets());
+        }
+        true
+    }
+
+    pub fn delete_chain_preset(mut self: Pin<&mut Self>, name: QString) -> bool {
+        let deleted = self
+            .as_mut()
+            .rust_mut()
+            .plugin_manager
+            .as_mut()
+            .is_some_and(|mgr| mgr.delete_chain_preset(&name.to_string()));
+        if deleted && let Some(ref mgr) = self.rust().plugin_manager {
+            save_chain_presets(mgr.chain_presets());
+        }
+        deleted
+    }
+
+    pub fn delete_plugin_preset(
+        mut self: Pin<&mut Self>,
+        plugin_uri: QString,
+        name: QString,
+    ) -> bool {
+        let deleted = self
+            .as_mut()
+            .rust_mut()
+            .plugin_manager
+            .as_mut()
+            .is_some_and(|mgr| mgr.delete_preset(&plugin_uri.to_string(), &name.to_string()));
+        if deleted && let Some(ref mgr) = self.rust().plugin_manager {
+            save_plugin_presets(mgr.presets());
+        }
+        deleted
+    }
+
+    fn apply_plugin_params(mut self: Pin<&mut Self>, instance_id: u64, values: &[(usize, f32)]) {
+        for &(port_index, value) in values {
+            if let Some(ref mut mgr) = self.as_mut().rust_mut().plugin_manager {
+                mgr.update_parameter(instance_id, port_index, value);
+            }
+            self.send_command(
+                CommandOrigin::User,
+                PwCommand::SetPluginParameter {
+                    instance_id,
+                    port_index,
+                    value,
+                },
+            );
+        }
+
+        self.as_mut().rust_mut().params_dirty = true;
+        if self.rust().params_dirty_since.is_none() {
+            self.as_mut().rust_mut().params_dirty_since = Some(Instant::now());
+        }
+    }
+
+    pub fn get_window_geometry_json(self: Pin<&mut Self>) -> QString {
+        ui_state_json::<WindowGeometry>("{}")
+    }
+
+    /// File URL of the tray icon, for the Qt-drawn fallback tray.
+    pub fn get_tray_icon_url(self: Pin<&mut Self>) -> QString {
+        let theme = crate::tray::icon_theme_path();
+        if theme.is_empty() {
+            return QString::default();
+        }
+        let path = std::path::Path::new(&theme).join("hicolor/256x256/apps/zestbay-tray.png");
+        let path = std::fs::canonicalize(&path).unwrap_or(path);
+        QString::from(&format!("file://{}", path.display()))
+    }
+
+    pub fn save_window_geometry(self: Pin<&mut Self>, json: QString) -> bool {
+        save_ui_state::<WindowGeometry>(&json.to_string())
+    }
+
+    /// The monitors as `[{ name, x, y, width, height }]` and the name of
+    /// the one the main window is on, for placing plugin UIs.
+    pub fn set_screens(mut self: Pin<&mut Self>, screens_json: QString, main_screen: QString) {
+        match serde_json::from_str::<Vec<MonitorArea>>(&screens_json.to_string()) {
+            Ok(screens) => {
+                let mut rust = self.as_mut().rust_mut();
+                rust.screens = screens;
+                rust.main_screen = main_screen.to_string();
+            }
+            Err(e) => log::warn!("set_screens: {}", e),
+        }
+    }
+
+    pub fn get_viewport_json(self: Pin<&mut Self>) -> QString {
+        ui_state_json::<Viewport>("{}")
+    }
+
+    pub fn save_viewport(self: Pin<&mut Self>, json: QString) -> bool {
+        save_ui_state::<Viewport>(&json.to_string())
+    }
+
+    pub fn get_rules_json(self: Pin<&mut Self>) -> QString {
+        if let Some(ref patchbay) = self.rust().patchbay {
+            let json_rules: Vec<serde_json::Value> = patchbay
+                .rules()
+                .iter()
+                .map(|r| {
+                    let mappings: Vec<serde_json::Value> = r
+                        .port_mappings
+                        .iter()
+                        .map(|m| {
+                            serde_json::json!({
+                                "outputPort": m.output_port_name,
+                                "inputPort": m.input_port_name,
+                            })
+                        })
+                        .collect();
+                    serde_json::json!({
+                        "id": r.id,
+                        "sourcePattern": r.source_pattern,
+                        "sourceType": r.source_node_type.map(rules::node_type_label).unwrap_or("Any"),
+                        "targetPattern": r.target_pattern,
+                        "targetType": r.target_node_type.map(rules::node_type_label).unwrap_or("Any"),
+                        "sourceLabel": r.source_label(),
+                        "targetLabel": r.target_label(),
+                        "enabled": r.enabled,
+                        "cooldownSecs": r.cooldown_secs,
+                        "oncePerAppearance": r.once_per_appearance,
+                        "fallbackTargets": r.fallback_targets,
+                        "activeFallback": patchbay.active_fallback(&r.id),
+                        "portMappings": mappings,
+                    })
+                })
+                .collect();
+            let json = serde_json::to_string(&json_rules).unwrap_or_default();
+            QString::from(&json)
+        } else {
+            QString::from("[]")
+        }
+    }
+
+    pub fn toggle_rule(mut self: Pin<&mut Self>, rule_id: QString) {
+        let id: String = rule_id.to_string();
+        if let Some(ref mut patchbay) = self.as_mut().rust_mut().patchbay {
+            patchbay.toggle_rule(&id);
+        }
+        save_rules(self.rust().patchbay.as_ref());
+        // The graph view marks links and nodes by rule state.
+        self.as_mut().graph_changed();
+    }
+
+    pub fn remove_rule(mut self: Pin<&mut Self>, rule_id: QString) {
+        let id: String = rule_id.to_string();
+        if let Some(ref mut patchbay) = self.as_mut().rust_mut().patchbay {
+            patchbay.remove_rule(&id);
+        }
+        save_rules(self.rust().patchbay.as_ref());
+        // The graph view marks links and nodes by rule state.
+        self.as_mut().graph_changed();
+    }
+
+    pub fn set_rule_cooldown(mut self: Pin<&mut Self>, rule_id: QString, secs: i32) {
+        let id = rule_id.to_string();
+        let changed = match self.as_mut().rust_mut().patchbay {
+            Some(ref mut patchbay) => patchbay.set_rule_cooldown(&id, secs.max(0) as u64),
+            None => false,
+        };
+        if changed {
+            save_rules(self.rust().patchbay.as_ref());
+        }
+    }
+
+    pub fn set_rule_once_per_appearance(mut self: Pin<&mut Self>, rule_id: QString, once: bool) {
+        let id = rule_id.to_string();
+        let changed = match self.as_mut().rust_mut().patchbay {
+            Some(ref mut patchbay) => patchbay.set_rule_once_per_appearance(&id, once),
+            None => false,
+        };
+        if changed {
+            save_rules(self.rust().patchbay.as_ref());
+        }
+    }
+
+    /// Set the targets a rule falls back to, in order, from a JSON array of
+    /// node name patterns.
+    pub fn set_rule_fallbacks(mut self: Pin<&mut Self>, rule_id: QString, targets_json: QString) {
+        let id = rule_id.to_string();
+        let targets: Vec<String> = match serde_json::from_str(&targets_json.to_string()) {
+            Ok(t) => t,
+            Err(e) => {
+                log::warn!("set_rule_fallbacks: invalid targets: {}", e);
+                return;
+            }
+        };
+        let targets = targets
+            .into_iter()
+            .map(|t| t.trim().to_string())
+            .filter(|t| !t.is_empty())
+            .collect();
+        let changed = match self.as_mut().rust_mut().patchbay {
+            Some(ref mut patchbay) => patchbay.set_rule_fallbacks(&id, targets),
+            None => false,
+        };
+        if changed {
+            save_rules(self.rust().patchbay.as_ref());
+            self.as_mut().apply_rules();
+        }
+    }
+
+    pub fn get_link_overrides_json(self: Pin<&mut Self>) -> QString {
+        let Some(ref patchbay) = self.rust().patchbay else {
+            return QString::from("[]");
+        };
+        let overrides: Vec<serde_json::Value> = patchbay
+            .overrides()
+            .iter()
+            .map(|o| {
+                serde_json::json!({
+                    "source": o.source,
+                    "outputPort": o.output_port,
+                    "target": o.target,
+                    "inputPort": o.input_port,
+                    "scope": o.scope_label(),
+                })
+            })
+            .collect();
+        QString::from(&serde_json::to_string(&overrides).unwrap_or_default())
+    }
+
+    pub fn remove_link_override(mut self: Pin<&mut Self>, index: i32) {
+        let removed = match self.as_mut().rust_mut().patchbay {
+            Some(ref mut patchbay) if index >= 0 => patchbay.remove_override(index as usize),
+            _ => false,
+        };
+        if removed {
+            self.as_mut().save_link_overrides_if_dirty();
+            self.as_mut().rust_mut().rules_apply_pending = true;
+        }
+    }
+
+    pub fn clear_link_overrides(mut self: Pin<&mut Self>) {
+        if let Some(ref mut patchbay) = self.as_mut().rust_mut().patchbay {
+            patchbay.clear_overrides();
+        }
+        self.as_mut().save_link_overrides_if_dirty();
+        self.as_mut().rust_mut().rules_apply_pending = true;
+    }
+
+    fn save_link_overrides_if_dirty(mut self: Pin<&mut Self>) {
+        let dirty = match self.as_mut().rust_mut().patchbay {
+            Some(ref mut patchbay) => std::mem::take(&mut patchbay.overrides_dirty),
+            None => false,
+        };
+        if dirty {
+            save_link_overrides(self.rust().patchbay.as_ref());
+        }
+    }
+
+    pub fn apply_rules(mut self: Pin<&mut Self>) {
+        if self.rust().scan_in_flight {
+            self.as_mut().rust_mut().rules_apply_pending = true;
+        } else {
+            self.as_mut().request_rule_scan();
+        }
+    }
+
+    /// Scan the rules against the graph on the control thread, or right here
+    /// when it is not running. The result lands in `finish_rule_scan`.
+    fn request_rule_scan(mut self: Pin<&mut Self>) {
+        let (Some(patchbay), Some(graph)) =
+            (self.rust().patchbay.clone(), self.rust().graph.clone())
+        else {
+            return;
+        };
+        if crate::control::is_running() {
+            crate::control::scan(patchbay, graph);
+            self.as_mut().rust_mut().scan_in_flight = true;
+        } else {
+            let result = crate::control::scan_now(Box::new(patchbay), graph);
+            self.as_mut().finish_rule_scan(result);
+        }
+    }
+
+    /// Link streams that match a learned rule as soon as they appear, rather
+    /// than after the graph settles: notification sounds and the like can
+    /// be gone by then. The scan still follows and does the rest.
+    fn route_new_streams(mut self: Pin<&mut Self>, mut node_ids: Vec<u32>) {
+        let enabled = self.rust().patchbay.as_ref().is_some_and(|p| p.enabled);
+        if !enabled || !self.rust().prefs.fast_route_streams || self.rust().storm_guard.is_tripped()
+        {
+            return;
+        }
+        node_ids.sort_unstable();
+        node_ids.dedup();
+        let graph = self.graph_snapshot();
+        let Some(patchbay) = self.rust().patchbay.as_ref() else {
+            return;
+        };
+        let commands: Vec<(PwCommand, CommandOrigin)> = node_ids
+            .into_iter()
+            .flat_map(|id| patchbay.route_new_stream(&graph, id))
+            .collect();
+        let commands = self.rust().temporary_routes.filter(&graph, commands);
+        let commands = self.keep_link_nodes(&graph, commands);
+
+        // A stream's ports arrive over several polls; each connect is sent
+        // once, not again while PipeWire is still creating the link.
+        let mut sent = Vec::new();
+        for (cmd, origin) in commands {
+            if let PwCommand::Connect {
+                output_port_id,
+                input_port_id,
+            } = cmd
+            {
+                let pair = (output_port_id, input_port_id);
+                if !self.as_mut().rust_mut().fast_routed.insert(pair) {
+                    continue;
+                }
+            }
+            sent.push((cmd, origin));
+        }
+        if !sent.is_empty() {
+            log::info!("Routing new stream right away: {} commands", sent.len());
+            for (cmd, origin) in sent {
+                self.send_command(origin, cmd);
+            }
+        }
+    }
+
+    /// Take over the patchbay a scan ran on and send its commands. If the
+    /// rules were edited or the graph replaced meanwhile, the result is
+    /// dropped and the scan runs again.
+    fn finish_rule_scan(mut self: Pin<&mut Self>, result: crate::control::ScanResult) {
+        self.as_mut().rust_mut().scan_in_flight = false;
+        self.as_mut().rust_mut().fast_routed.clear();
+        let same_graph = self
+            .rust()
+            .graph
+            .as_ref()
+            .is_some_and(|g| Arc::ptr_eq(g, &result.state));
+        let Some(current) = self.rust().patchbay.as_ref() else {
+            return;
+        };
+        if !same_graph || !current.enabled || current.revision() != result.patchbay.revision() {
+            log::debug!("Rules or graph changed during a scan; scanning again");
+            self.as_mut().rust_mut().rules_apply_pending = true;
+            return;
+        }
+
+        let mut patchbay = *result.patchbay;
+        patchbay.rules_dirty |= current.rules_dirty;
+        patchbay.overrides_dirty |= current.overrides_dirty;
+        let rules_dirty = std::mem::take(&mut patchbay.rules_dirty);
+        self.as_mut().rust_mut().patchbay = Some(patchbay);
+
+        let commands = self
+            .rust()
+            .temporary_routes
+            .filter(&result.graph, result.commands);
+        let commands = self.keep_link_nodes(&result.graph, commands);
+        if !commands.is_empty() {
+            log::info!("Auto-applying {} patchbay rule commands", commands.len());
+            for (cmd, origin) in commands {
+                self.send_command(origin, cmd);
+            }
+        }
+        if rules_dirty {
+            save_rules(self.rust().patchbay.as_ref());
+        }
+        self.as_mut().save_link_overrides_if_dirty();
+    }
+
+    pub fn snapshot_rules(mut self: Pin<&mut Self>) {
+        let snapshot = self.graph_snapshot();
+        if let Some(ref mut patchbay) = self.as_mut().rust_mut().patchbay {
+            patchbay.snapshot_current_connections(&snapshot);
+        }
+        save_rules(self.rust().patchbay.as_ref());
+        log::info!("Snapshot: replaced rules with current connections");
+    }
+
+    /// Turn the stream targets WirePlumber remembered into rules and role
+    /// policies. Returns a summary for the user, or an empty string after
+    /// reporting the failure through `error_occurred`.
+    pub fn import_wireplumber_rules(mut self: Pin<&mut Self>) -> QString {
+        use crate::patchbay::wireplumber;
+
+        let targets = wireplumber::state_dir()
+            .ok_or_else(|| "could not determine the WirePlumber state directory".to_string())
+            .and_then(|dir| wireplumber::read_state_dir(&dir));
+        let targets = match targets {
+            Ok(targets) => targets,
+            Err(e) => {
+                log::warn!("import_wireplumber_rules: {}", e);
+                let msg = format!("WirePlumber import failed: {}", e);
+                self.as_mut().error_occurred(QString::from(msg.as_str()));
+                return QString::from("");
+            }
+        };
+        let nodes = self
+            .rust()
+            .graph
+            .as_ref()
+            .map(|g| g.get_all_nodes())
+            .unwrap_or_default();
+        let import = wireplumber::convert(&targets, &nodes);
+
+        let mut added_rules = 0;
+        let mut added_policies = 0;
+        if let Some(ref mut patchbay) = self.as_mut().rust_mut().patchbay {
+            for rule in import.rules {
+                let exists = patchbay.rules().iter().any(|r| {
+                    r.source_pattern == rule.source_pattern
+                        && r.source_node_type == rule.source_node_type
+                        && r.target_pattern == rule.target_pattern
+                        && r.target_node_type == rule.target_node_type
+                });
+                if !exists {
+                    patchbay.add_rule(rule);
+                    added_rules += 1;
+                }
+            }
+            // Policies set in ZestBay take precedence over imported ones.
+            for policy in import.role_policies {
+                let exists = patchbay
+                    .role_policies()
+                    .iter()
+                    .any(|p| p.role.eq_ignore_ascii_case(&policy.role));
+                if !exists {
+                    patchbay.set_role_policy(policy);
+                    added_policies += 1;
+                }
+            }
+        }
+        save_rules(self.rust().patchbay.as_ref());
+        save_role_policies(self.rust().patchbay.as_ref());
+        self.as_mut().rust_mut().rules_apply_pending = true;
+
+        let policies = if added_policies == 1 { "policy" } else { "policies" };
+        let mut summary = format!(
+            "Imported {} rule(s) and {} role {} from {} WirePlumber target(s).",
+            added_rules,
+            added_policies,
+            policies,
+            targets.len()
+        );
+        if !import.skipped.is_empty() {
+            summary.push_str(&format!("\nSkipped: {}", import.skipped.join(", ")));
+        }
+        log::info!("WirePlumber import: {}", summary);
+        QString::from(summary.as_str())
+    }
+
+    pub fn toggle_patchbay(mut self: Pin<&mut Self>, enabled: bool) {
+        if let Some(ref mut patchbay) = self.as_mut().rust_mut().patchbay {
+            patchbay.enabled = enabled;
+        }
+        self.as_mut().set_patchbay_enabled(enabled);
+        if let Some(ref remote) = self.rust().remote_api {
+            remote.emit("patchbay", serde_json::json!({ "enabled": enabled }));
+        }
+        self.sync_patchbay_dbus();
+    }
+
+    pub fn get_node_names_json(self: Pin<&mut Self>) -> QString {
+        if let Some(ref graph) = self.rust().graph {
+            let nodes = graph.get_all_nodes();
+            let mut entries: Vec<serde_json::Value> = Vec::new();
+
+            for n in nodes.iter().filter(|n| n.ready) {
+                if is_link_node(&n.name) {
+                    continue;
+                }
+                let media_kind = MediaKind::from(n.media_type).repr;
+
+                if n.is_bridge {
+                    // For bridge nodes, list each device sub-node separately
+                    let groups = graph.get_bridge_port_groups(n.id);
+                    for (_group, device_name) in &groups {
+                        entries.push(serde_json::json!({
+                            "name": device_name,
+                            "type": NodeKind::Duplex.repr,
+                            "mediaType": media_kind,
+                        }));
+                    }
+                } else {
+                    entries.push(serde_json::json!({
+                        "name": n.display_name(),
+                        "type": NodeKind::from(n.node_type).repr,
+                        "mediaType": media_kind,
+                    }));
+                }
+            }
+
+            entries.sort_by(|a, b| {
+                let a_name = a["name"].as_str().unwrap_or("");
+                let b_name = b["name"].as_str().unwrap_or("");
+                a_name.cmp(b_name)
+            });
+            entries.dedup_by(|a, b| {
+                a["name"].as_str() == b["name"].as_str() && a["type"] == b["type"]
+            });
+            let json = serde_json::to_string(&entries).unwrap_or_default();
+            QString::from(&json)
+        } else {
+            QString::from("[]")
+        }
+    }
+
+    pub fn add_rule(
+        mut self: Pin<&mut Self>,
+        source_pattern: QString,
+        source_type: NodeKind,
+        target_pattern: QString,
+        target_type: NodeKind,
+    ) {
+        let rule = crate::patchbay::rules::AutoConnectRule::new(
+            source_pattern.to_string(),
+            source_type.node_type(),
+            target_pattern.to_string(),
+            target_type.node_type(),
+            None,
+        );
+
+        if let Some(ref mut patchbay) = self.as_mut().rust_mut().patchbay {
+            patchbay.add_rule(rule);
+        }
+        save_rules(self.rust().patchbay.as_ref());
+    }
+
+    pub fn get_role_policies_json(self: Pin<&mut Self>) -> QString {
+        let policies: Vec<serde_json::Value> = self
+            .rust()
+            .patchbay
+            .as_ref()
+            .map(|p| p.role_policies())
+            .unwrap_or_default()
+            .iter()
+            .map(|p| {
+                serde_json::json!({
+                    "role": p.role,
+                    "targetPattern": p.target_pattern,
+                    "enabled": p.enabled,
+                })
+            })
+            .collect();
+        let json = serde_json::json!({
+            "policies": policies,
+            "knownRoles": crate::patchbay::roles::KNOWN_ROLES,
+        });
+        QString::from(&json.to_string())
+    }
+
+    pub fn set_role_policy(mut self: Pin<&mut Self>, role: QString, target_pattern: QString) {
+        let role_str: String = role.to_string().trim().to_string();
+        let target_str: String = target_pattern.to_string().trim().to_string();
+        if role_str.is_empty() {
+            return;
+        }
+
+        if let Some(ref mut patchbay) = self.as_mut().rust_mut().patchbay {
+            if target_str.is_empty() {
+                patchbay.remove_role_policy(&role_str);
+            } else {
+                patchbay.set_role_policy(crate::patchbay::roles::RolePolicy::new(
+                    role_str.clone(),
+                    target_str.clone(),
+                ));
+            }
+        }
+        log::info!("Role policy: {} -> {}", role_str, target_str);
+        save_role_policies(self.rust().patchbay.as_ref());
+        self.as_mut().rust_mut().rules_apply_pending = true;
+    }
+
+    pub fn remove_role_policy(mut self: Pin<&mut Self>, role: QString) {
+        let role_str: String = role.to_string();
+        if let Some(ref mut patchbay) = self.as_mut().rust_mut().patchbay {
+            patchbay.remove_role_policy(&role_str);
+        }
+        save_role_policies(self.rust().patchbay.as_ref());
+    }
+
+    pub fn get_preferences_json(self: Pin<&mut Self>) -> QString {
+        let json = serde_json::to_string(&self.rust().prefs).unwrap_or_default();
+        QString::from(&json)
+    }
+
+    /// Pause rules and link saving while graph changes arrive faster than
+    /// the storm limit, and pick up what waited once they calm down.
+    fn track_event_storm(mut self: Pin<&mut Self>, events: u32) {
+        let limit = self.rust().prefs.storm_events_per_sec;
+        let change = self
+            .as_mut()
+            .rust_mut()
+            .storm_guard
+            .record(Instant::now(), events, limit);
+        match change {
+            Some(StormChange::Started { events_per_sec }) => {
+                log::warn!(
+                    "Graph event storm: {:.0} changes/s, pausing rules and link saving",
+                    events_per_sec
+                );
+                self.as_mut().set_rules_suspended(true);
+                self.as_mut().error_occurred(QString::from(&format!(
+                    "The graph is changing {:.0} times a second, likely a device dropping in and out. \
+                     Rules and link saving are paused until it calms down.",
+                    events_per_sec
+                )));
+            }
+            Some(StormChange::Ended { lasted }) => {
+                log::info!(
+                    "Graph event storm over after {:.0}s, resuming rules",
+                    lasted.as_secs_f32()
+                );
+                self.as_mut().set_rules_suspended(false);
+                self.as_mut().rust_mut().rules_apply_pending = true;
+            }
+            None => {}
+        }
+    }
+
+    pub fn set_preference(mut self: Pin<&mut Self>, key: QString, value: QString) {
+        let key_str: String = key.to_string();
+        let val_str: String = value.to_string();
+
+        match key_str.as_str() {
+            "rule_settle_ms" => {
+                if let Ok(v) = val_str.parse::<u64>() {
+                    self.as_mut().rust_mut().prefs.rule_settle_ms = v.clamp(0, 10000);
+                }
+            }
+            "storm_events_per_sec" => {
+                if let Ok(v) = val_str.parse::<u32>() {
+                    self.as_mut().rust_mut().prefs.storm_events_per_sec = v.clamp(0, 1000);
+                }
+            }
+            "params_persist_ms" => {
+                if let Ok(v) = val_str.parse::<u64>() {
+                    self.as_mut().rust_mut().prefs.params_persist_ms = v.clamp(100, 30000);
+                }
+            }
+            "links_persist_ms" => {
+                if let Ok(v) = val_str.parse::<u64>() {
+                    self.as_mut().rust_mut().prefs.links_persist_ms = v.clamp(100, 30000);
+                }
+            }
+            "poll_interval_ms" => {
+                if let Ok(v) = val_str.parse::<u64>() {
+                    self.as_mut().rust_mut().prefs.poll_interval_ms = v.clamp(16, 1000);
+                }
+            }
+            "performance_profile" => {
+                if PerformanceProfile::parse(&val_str).is_some() {
+                    self.as_mut().rust_mut().prefs.performance_profile = val_str.clone();
+                    // Picked by hand: it holds over the battery profile
+                    // until the power source changes.
+                    self.as_mut().rust_mut().profile_chooser.picked();
+                    self.as_mut().apply_profile();
+                }
+            }
+            "battery_profile" => {
+                if val_str.is_empty() || PerformanceProfile::parse(&val_str).is_some() {
+                    self.as_mut().rust_mut().prefs.battery_profile = val_str.clone();
+                    self.as_mut().apply_profile();
+                }
+            }
+            "auto_learn_rules" => {
+                if let Ok(v) = val_str.parse::<bool>() {
+                    self.as_mut().rust_mut().prefs.auto_learn_rules = v;
+                }
+            }
+            "fast_route_streams" => {
+                if let Ok(v) = val_str.parse::<bool>() {
+                    self.as_mut().rust_mut().prefs.fast_route_streams = v;
+                }
+            }
+            "manual_override_scope" | "manual_override_minutes" => {
+                if key_str == "manual_override_scope" {
+                    if matches!(val_str.as_str(), "off" | "node" | "timed" | "permanent") {
+                        self.as_mut().rust_mut().prefs.manual_override_scope = val_str;
+                    }
+                } else if let Ok(v) = val_str.parse::<u32>() {
+                    self.as_mut().rust_mut().prefs.manual_override_minutes = v.clamp(1, 24 * 60);
+                }
+                let mode = self.rust().prefs.override_mode();
+                if let Some(ref mut patchbay) = self.as_mut().rust_mut().patchbay {
+                    patchbay.set_override_mode(mode);
+                }
+            }
+            "bypass_level_match" => {
+                if let Ok(v) = val_str.parse::<bool>() {
+                    self.as_mut().rust_mut().prefs.bypass_level_match = v;
+                    crate::plugin::bypass::set_level_match(v);
+                }
+            }
+            "plugin_tail_seconds" => {
+                if let Ok(v) = val_str.parse::<f64>() {
+                    let seconds = v.clamp(0.0, 60.0) as u32;
+                    self.as_mut().rust_mut().prefs.plugin_tail_seconds = seconds;
+                    crate::plugin::tail::set_tail_seconds(seconds);
+                }
+            }
+            "output_crossfade_ms" => {
+                if let Ok(v) = val_str.parse::<f64>() {
+                    self.as_mut().rust_mut().prefs.output_crossfade_ms =
+                        v.clamp(0.0, 2000.0) as u32;
+                }
+            }
+            "start_minimized" => {
+                if let Ok(v) = val_str.parse::<bool>() {
+                    self.as_mut().rust_mut().prefs.start_minimized = v;
+                }
+            }
+            "close_to_tray" => {
+                if let Ok(v) = val_str.parse::<bool>() {
+                    self.as_mut().rust_mut().prefs.close_to_tray = v;
+                }
+            }
+            "xrun_warning_threshold" => {
+                if let Ok(v) = val_str.parse::<f64>() {
+                    self.as_mut().rust_mut().prefs.xrun_warning_threshold =
+                        v.clamp(0.0, 1000.0) as u64;
+                }
+            }
+            "cpu_alert_percent" => {
+                if let Ok(v) = val_str.parse::<f64>() {
+                    self.as_mut().rust_mut().prefs.cpu_alert_percent = v.clamp(0.0, 100.0) as u32;
+                }
+            }
+            "cpu_alert_seconds" => {
+                if let Ok(v) = val_str.parse::<f64>() {
+                    self.as_mut().rust_mut().prefs.cpu_alert_seconds = v.clamp(1.0, 600.0) as u64;
+                }
+            }
+            "pw_command_log" => {
+                if let Ok(v) = val_str.parse::<bool>() {
+                    self.as_mut().rust_mut().prefs.pw_command_log = v;
+                }
+            }
+            "check_for_updates" => {
+                if let Ok(v) = val_str.parse::<bool>() {
+                    self.as_mut().rust_mut().prefs.check_for_updates = v;
+                    if v && self.rust().update_result.is_none() {
+                        self.as_mut().check_for_updates();
+                    }
+                }
+            }
+            "tray_mode" => {
+                if matches!(val_str.as_str(), "auto" | "status_notifier" | "xembed" | "none") {
+                    self.as_mut().rust_mut().prefs.tray_mode = val_str;
+                }
+            }
+            "node_order" => {
+                if NodeOrder::from_pref(&val_str).is_some() {
+                    self.as_mut().rust_mut().prefs.node_order = val_str;
+                    self.as_mut().graph_changed();
+                }
+            }
+            "dock_plugin_uis" => {
+                if let Ok(v) = val_str.parse::<bool>() {
+                    self.as_mut().rust_mut().prefs.dock_plugin_uis = v;
+                }
+            }
+            "plugin_format_order" => {
+                let order = parse_format_order(&val_str);
+                self.as_mut().rust_mut().prefs.plugin_format_order = format_order_pref(&order);
+                if let Some(ref mut mgr) = self.as_mut().rust_mut().plugin_manager {
+                    mgr.set_format_order(order);
+                }
+            }
+            "show_all_plugin_versions" => {
+                if let Ok(v) = val_str.parse::<bool>() {
+                    self.as_mut().rust_mut().prefs.show_all_plugin_versions = v;
+                    if let Some(ref mut mgr) = self.as_mut().rust_mut().plugin_manager {
+                        mgr.set_show_all_versions(v);
+                    }
+                }
+            }
+            "reopen_plugin_uis" => {
+                if let Ok(v) = val_str.parse::<bool>() {
+                    self.as_mut().rust_mut().prefs.reopen_plugin_uis = v;
+                }
+            }
+            "plugin_ui_monitor" => {
+                self.as_mut().rust_mut().prefs.plugin_ui_monitor = val_str;
+            }
+            "quick_params_count" => {
+                if let Ok(v) = val_str.parse::<u32>() {
+                    self.as_mut().rust_mut().prefs.quick_params_count =
+                        v.min(quick_params::MAX_QUICK_PARAMS as u32);
+                    self.as_mut().graph_changed();
+                }
+            }
+            "jack_dbus_patchbay" => {
+                if let Ok(v) = val_str.parse::<bool>() {
+                    self.as_mut().rust_mut().prefs.jack_dbus_patchbay = v;
+                    // The bus name is held until exit, so turning this off
+                    // only takes effect after a restart.
+                    if v && self.rust().jack_dbus.is_none() {
+                        self.as_mut().rust_mut().jack_dbus =
+                            Some(crate::jack_dbus::spawn_jack_dbus());
+                        self.sync_jack_patchbay();
+                    }
+                }
+            }
+            "patchbay_dbus" => {
+                if let Ok(v) = val_str.parse::<bool>() {
+                    self.as_mut().rust_mut().prefs.patchbay_dbus = v;
+                    // Like the JACK interface, this holds its bus name until
+                    // exit.
+                    if v && self.rust().patchbay_dbus.is_none() {
+                        self.as_mut().rust_mut().patchbay_dbus =
+                            Some(crate::patchbay_dbus::spawn_patchbay_dbus());
+                        self.sync_patchbay_dbus();
+                    }
+                }
+            }
+            "mqtt_enabled" => {
+                if let Ok(v) = val_str.parse::<bool>() {
+                    self.as_mut().rust_mut().prefs.mqtt_enabled = v;
+                    self.as_mut().restart_mqtt();
+                }
+            }
+            "mqtt_broker" => {
+                self.as_mut().rust_mut().prefs.mqtt_broker = val_str.trim().to_string();
+                self.as_mut().restart_mqtt();
+            }
+            "mqtt_topic" => {
+                self.as_mut().rust_mut().prefs.mqtt_topic = val_str.trim().to_string();
+                self.as_mut().restart_mqtt();
+            }
+            "mqtt_username" => {
+                self.as_mut().rust_mut().prefs.mqtt_username = val_str.trim().to_string();
+                self.as_mut().restart_mqtt();
+            }
+            "mqtt_password" => {
+                self.as_mut().rust_mut().prefs.mqtt_password = val_str.clone();
+                self.as_mut().restart_mqtt();
+            }
+            "mqtt_tls" => {
+                if let Ok(v) = val_str.parse::<bool>() {
+                    self.as_mut().rust_mut().prefs.mqtt_tls = v;
+                    self.as_mut().restart_mqtt();
+                }
+            }
+            "mqtt_tls_ca" => {
+                self.as_mut().rust_mut().prefs.mqtt_tls_ca = val_str.trim().to_string();
+                self.as_mut().restart_mqtt();
+            }
+            "mqtt_scope" => {
+                let Some(scope) = ApiScope::parse(&val_str) else {
+                    log::warn!("Invalid MQTT scope: {}", val_str);
+                    return;
+                };
+                self.as_mut().rust_mut().prefs.mqtt_scope = scope;
+                self.as_mut().restart_mqtt();
+            }
+            "remote_api_enabled" => {
+                if let Ok(v) = val_str.parse::<bool>() {
+                    self.as_mut().rust_mut().prefs.remote_api_enabled = v;
+                    self.as_mut().restart_remote_api();
+                }
+            }
+            "remote_api_bind" => {
+                self.as_mut().rust_mut().prefs.remote_api_bind = val_str.trim().to_string();
+                self.as_mut().restart_remote_api();
+            }
+            "remote_api_token" => {
+                self.as_mut().rust_mut().prefs.remote_api_token = val_str.trim().to_string();
+                self.as_mut().restart_remote_api();
+            }
+            "remote_api_tls_cert" => {
+                self.as_mut().rust_mut().prefs.remote_api_tls_cert = val_str.trim().to_string();
+                self.as_mut().restart_remote_api();
+            }
+            "remote_api_tls_key" => {
+                self.as_mut().rust_mut().prefs.remote_api_tls_key = val_str.trim().to_string();
+                self.as_mut().restart_remote_api();
+            }
+            "remote_api_cors_origin" => {
+                self.as_mut().rust_mut().prefs.remote_api_cors_origin = val_str.trim().to_string();
+                self.as_mut().restart_remote_api();
+            }
+            "remote_instance_cert" => {
+                self.as_mut().rust_mut().prefs.remote_instance_cert = val_str.trim().to_string();
+                self.as_mut().restart_remote_instance();
+            }
+            pref_key if pref_key.starts_with("double_click.") => {
+                let type_name = &pref_key["double_click.".len()..];
+                let node_type =
+                    serde_json::from_value::<NodeType>(serde_json::Value::String(type_name.into()));
+                let action = serde_json::from_value::<NodeAction>(serde_json::Value::String(
+                    val_str.clone(),
+                ));
+                match (node_type, action) {
+                    (Ok(node_type), Ok(action)) if action.applies_to(node_type) => {
+                        self.as_mut()
+                            .rust_mut()
+                            .prefs
+                            .double_click_actions
+                            .insert(node_type, action);
+                    }
+                    _ => {
+                        log::warn!("Invalid double-click action: {} = {}", key_str, val_str);
+                        return;
+                    }
+                }
+            }
+            "pw_tick_interval_ms" => {
+                if let Ok(v) = val_str.parse::<u64>() {
+                    self.as_mut().rust_mut().prefs.pw_tick_interval_ms = v.clamp(1, 200);
+                    self.as_mut().apply_profile();
+                }
+            }
+            "pw_operation_cooldown_ms" => {
+                if let Ok(v) = val_str.parse::<u64>() {
+                    self.as_mut().rust_mut().prefs.pw_operation_cooldown_ms = v.clamp(10, 1000);
+                }
+            }
+            "pw_connect_retries" => {
+                if let Ok(v) = val_str.parse::<u32>() {
+                    self.as_mut().rust_mut().prefs.pw_connect_retries = v.clamp(0, 10);
+                }
+            }
+            _ => {
+                log::warn!("Unknown preference key: {}", key_str);
+                return;
+            }
+        }
+
+        log::info!("Preference updated: {} = {}", key_str, val_str);
+        save_preferences(&self.rust().prefs);
+    }
+
+    pub fn reset_preferences(mut self: Pin<&mut Self>) {
+        self.as_mut().rust_mut().prefs = Preferences::default();
+        crate::plugin::bypass::set_level_match(self.rust().prefs.bypass_level_match);
+        crate::plugin::tail::set_tail_seconds(self.rust().prefs.plugin_tail_seconds);
+        if let Some(ref mut mgr) = self.as_mut().rust_mut().plugin_manager {
+            mgr.set_format_order(DEFAULT_FORMAT_ORDER.to_vec());
+            mgr.set_show_all_versions(false);
+        }
+        save_preferences(&self.rust().prefs);
+        self.as_mut().apply_profile();
+        log::info!("Preferences reset to defaults");
+    }
+
+    /// Run the configured double-click action for a node. Actions that need
+    /// a dialog or view state are handed back to QML by name; an empty
+    /// string means there is nothing left to do.
+    pub fn activate_node(self: Pin<&mut Self>, node_id: u32) -> QString {
+        let Some(node_type) = self.node_type_for_id(node_id) else {
+            return QString::default();
+        };
+        match self.rust().prefs.double_click_action(node_type) {
+            NodeAction::OpenUi => {
+                self.open_plugin_ui(node_id);
+                QString::default()
+            }
+            action => QString::from(action.as_str()),
+        }
+    }
+
+    /// Node type of a graph view node, including split bridge sub-nodes and
+    /// application groups.
+    fn node_type_for_id(&self, node_id: u32) -> Option<NodeType> {
+        if self.find_instance_id_for_node(node_id).is_some() {
+            return Some(NodeType::Plugin);
+        }
+        let rust = self.rust();
+        let real_id = match rust.bridge_split.resolve_virtual_node(node_id) {
+            Some(&(real_id, _)) => real_id,
+            None => rust
+                .app_groups
+                .members(node_id)
+                .and_then(|members| members.first().copied())
+                .unwrap_or(node_id),
+        };
+        rust.cached_nodes
+            .iter()
+            .find(|n| n.id == real_id)
+            .and_then(|n| n.node_type)
+    }
+
+    pub fn get_poll_interval_ms(self: Pin<&mut Self>) -> i32 {
+        self.profile_settings().poll_interval_ms as i32
+    }
+
+    pub fn get_meter_interval_ms(self: Pin<&mut Self>) -> i32 {
+        self.profile_settings().meter_interval_ms as i32
+    }
+
+    /// The shortest time between graph redraws.
+    pub fn get_repaint_interval_ms(self: Pin<&mut Self>) -> i32 {
+        self.profile_settings().repaint_interval_ms as i32
+    }
+
+    /// The profile in use, why, and the intervals it sets.
+    pub fn get_profile_json(self: Pin<&mut Self>) -> QString {
+        let prefs = &self.rust().prefs;
+        let json = serde_json::json!({
+            "profile": self.active_profile(),
+            "chosen": prefs.performance_profile,
+            "batteryProfile": prefs.battery_profile,
+            "onBattery": self.rust().profile_chooser.on_battery(),
+            "settings": self.profile_settings(),
+        });
+        QString::from(&json.to_string())
+    }
+
+    pub fn get_cpu_history(self: Pin<&mut Self>) -> QString {
+        let json = serde_json::to_string(&self.rust().cpu_history).unwrap_or_default();
+        QString::from(&json)
+    }
+
+    pub fn get_plugin_cpu_json(self: Pin<&mut Self>) -> QString {
+        let mgr = self.rust().plugin_manager.as_ref();
+        let items: Vec<serde_json::Value> = self
+            .rust()
+            .plugin_cpu
+            .iter()
+            .map(|(id, name, snap)| {
+                let stable_id = mgr
+                    .and_then(|m| m.get_instance(*id))
+                    .map(|info| info.stable_id.as_str());
+                serde_json::json!({
+                    "id": id,
+                    "stableId": stable_id,
+                    "name": name,
+                    "dspPercent": (snap.dsp_percent * 100.0).round() / 100.0,
+                    "avgUs": snap.avg_ns / 1000,
+                    "lastUs": snap.last_ns / 1000,
+                    "calls": snap.calls,
+                    "workerPercent": (snap.worker_percent * 100.0).round() / 100.0,
+                    "workerAvgUs": snap.worker_avg_ns / 1000,
+                })
+            })
+            .collect();
+        let json = serde_json::to_string(&items).unwrap_or_default();
+        QString::from(&json)
+    }
+
+    /// Recent load samples of a plugin, oldest first, including ones from
+    /// before the last restart.
+    pub fn get_plugin_cpu_history_json(self: Pin<&mut Self>, stable_id: QString) -> QString {
+        let sid = stable_id.to_string();
+        let samples: Vec<CpuSample> = self
+            .rust()
+            .plugin_cpu_history
+            .samples(&sid)
+            .map(|s| s.iter().copied().collect())
+            .unwrap_or_default();
+        let json = serde_json::json!({
+            "stableId": sid,
+            "alerting": self.rust().plugin_cpu_alerts.is_active(&sid),
+            "samples": samples,
+        });
+        QString::from(&json.to_string())
+    }
+
+    /// Start or stop plugin output metering. Meters are polled with
+    /// [`get_meters_json`](Self::get_meters_json) on their own timer, apart
+    /// from `poll_events`.
+    pub fn set_metering(self: Pin<&mut Self>, enabled: bool) {
+        crate::plugin::meters::global_meters().set_metering(enabled);
+    }
+
+    /// Peak and RMS levels in dBFS per output channel of every plugin that
+    /// processed audio since the last call. Cheap enough to call at 60 Hz:
+    /// only the meter rings are read, never the graph.
+    pub fn get_meters_json(self: Pin<&mut Self>) -> QString {
+        let readings = crate::plugin::meters::global_meters().take_readings();
+        let mgr = self.rust().plugin_manager.as_ref();
+        let db = |levels: &[f32]| -> Vec<f32> {
+            levels
+                .iter()
+                .map(|&l| (meter::to_dbfs(l) * 10.0).round() / 10.0)
+                .collect()
+        };
+        let items: Vec<serde_json::Value> = readings
+            .iter()
+            .map(|(id, reading)| {
+                let node_id = mgr
+                    .and_then(|m| m.get_instance(*id))
+                    .and_then(|info| info.pw_node_id);
+                serde_json::json!({
+                    "instanceId": id,
+                    "nodeId": node_id,
+                    "peakDb": db(&reading.peak),
+                    "rmsDb": db(&reading.rms),
+                })
+            })
+            .collect();
+        QString::from(&serde_json::to_string(&items).unwrap_or_default())
+    }
+
+    /// The most recent logged commands matching `filter_json`, an
+    /// [`AuditFilter`] object, oldest first.
+    pub fn get_pw_command_log_json(
+        self: Pin<&mut Self>,
+        filter_json: QString,
+        limit: i32,
+    ) -> QString {
+        let filter: AuditFilter =
+            serde_json::from_str(&filter_json.to_string()).unwrap_or_default();
+        let entries: Vec<serde_json::Value> = self
+            .rust()
+            .command_log
+            .query(&filter, limit.max(0) as usize)
+            .into_iter()
+            .map(|entry| {
+                let mut item = serde_json::to_value(&entry).unwrap_or_default();
+                item["originLabel"] = serde_json::json!(entry.origin.to_string());
+                item
+            })
+            .collect();
+        let json = serde_json::json!({
+            "enabled": self.rust().prefs.pw_command_log,
+            "path": self.rust().command_log.path().display().to_string(),
+            "entries": entries,
+        });
+        QString::from(&json.to_string())
+    }
+
+    pub fn clear_pw_command_log(self: Pin<&mut Self>) {
+        self.rust().command_log.clear();
+    }
+
+    pub fn get_default_node(self: Pin<&mut Self>) -> QString {
+        let path = config_path("default_node.txt");
+        match std::fs::read_to_string(&path) {
+            Ok(s) => QString::from(&s.trim().to_string()),
+            Err(_) => QString::from(""),
+        }
+    }
+
+    pub fn set_default_node(mut self: Pin<&mut Self>, layout_key: QString) {
+        let key: String = layout_key.to_string();
+        let path = config_path("default_node.txt");
+        if let Some(parent) = path.parent() {
+            let _ = std::fs::create_dir_all(parent);
+        }
+        if key.is_empty() {
+            let _ = std::fs::remove_file(&path);
+            log::info!("Cleared default node");
+        } else {
+            if let Err(e) = std::fs::write(&path, &key) {
+                log::error!("Failed to save default node to {:?}: {}", path, e);
+            } else {
+                log::info!("Set default node: {}", key);
+            }
+        }
+
+        // Update patchbay manager with the new default
+        let display_name =
+            (!key.is_empty()).then(|| layout_key_display_name(&key, &self.rust().node_ids));
+        if let Some(ref mut patchbay) = self.as_mut().rust_mut().patchbay {
+            patchbay.set_default_target(display_name);
+        }
+    }
+
+    pub fn get_app_version(self: Pin<&mut Self>) -> QString {
+        QString::from(env!("CARGO_PKG_VERSION"))
+    }
+
+    /// Look up the latest release in the background; `update_status_changed`
+    /// fires when the answer is in.
+    pub fn check_for_updates(mut self: Pin<&mut Self>) {
+        if self.rust().update_rx.is_some() {
+            return;
+        }
+        log::info!("Checking for updates");
+        self.as_mut().rust_mut().update_rx = Some(crate::update_check::spawn());
+        self.as_mut().update_status_changed();
+    }
+
+    /// `{enabled, checking, current, latest, newer, name, notes, url,
+    /// published, hint, error}` for the update section of the About window.
+    pub fn get_update_json(self: Pin<&mut Self>) -> QString {
+        let current = env!("CARGO_PKG_VERSION");
+        let rust = self.rust();
+        let mut json = serde_json::json!({
+            "enabled": rust.prefs.check_for_updates,
+            "checking": rust.update_rx.is_some(),
+            "current": current,
+            "hint": crate::update_check::upgrade_hint(),
+        });
+        match &rust.update_result {
+            Some(Ok(release)) => {
+                json["latest"] = release.version().into();
+                json["newer"] = release.is_newer_than(current).into();
+                json["name"] = release.name.as_deref().unwrap_or_default().into();
+                json["notes"] = release.body.as_deref().unwrap_or_default().into();
+                json["url"] = release.html_url.as_str().into();
+                json["published"] = release.published_at.as_deref().unwrap_or_default().into();
+            }
+            Some(Err(e)) => json["error"] = e.as_str().into(),
+            None => {}
+        }
+        QString::from(&json.to_string())
+    }
+
+    /// A prefilled new-issue link for the crash report at `report_path`.
+    pub fn get_crash_issue_url(self: Pin<&mut Self>, report_path: QString) -> QString {
+        let path = PathBuf::from(report_path.to_string());
+        let cause = crate::crash_report::report_cause(&path);
+        let url = zestbay_core::crash::issue_url(env!("CARGO_PKG_VERSION"), &cause);
+        QString::from(&url)
+    }
+
+    pub fn get_qt_version(self: Pin<&mut Self>) -> QString {
+        QString::from(env!("QT_VERSION"))
+    }
+
+    pub fn backup_rules(self: Pin<&mut Self>, name: QString) -> QString {
+        let name_str: String = name.to_string();
+        let backup_dir = config_path("rule_backups");
+        if let Err(e) = std::fs::create_dir_all(&backup_dir) {
+            log::error!("Failed to create backup dir: {}", e);
+            return QString::from("");
+        }
+
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        // Format as YYYYMMDD_HHMMSS using simple arithmetic (UTC)
+        let secs = now as i64;
+        let days = secs / 86400;
+        let time_of_day = (secs % 86400) as u32;
+        let hours = time_of_day / 3600;
+        let minutes = (time_of_day % 3600) / 60;
+        let seconds = time_of_day % 60;
+        // Compute date from days since epoch (1970-01-01)
+        let (year, month, day) = days_to_ymd(days);
+        let timestamp = format!("{:04}{:02}{:02}_{:02}{:02}{:02}", year, month, day, hours, minutes, seconds);
+        let safe_name = if name_str.trim().is_empty() {
+            timestamp.clone()
+        } else {
+            let sanitized: String = name_str
+                .trim()
+                .chars()
+                .map(|c| if c.is_alphanumeric() || c == '-' || c == '_' || c == ' ' { c } else { '_' })
+                .collect();
+            format!("{}_{}", timestamp, sanitized)
+        };
+        let filename = format!("{}.json", safe_name);
+        let dest = backup_dir.join(&filename);
+
+        crate::control::flush();
+        let src = config_path("rules.json");
+        match std::fs::read_to_string(&src) {
+            Ok(content) => {
+                if let Err(e) = std::fs::write(&dest, &content) {
+                    log::error!("Failed to write backup {:?}: {}", dest, e);
+                    return QString::from("");
+                }
+                log::info!("Rules backed up to {:?}", dest);
+                QString::from(&filename)
+            }
+            Err(e) => {
+                log::error!("Failed to read rules for backup: {}", e);
+                QString::from("")
+            }
+        }
+    }
+
+    pub fn list_rule_backups_json(self: Pin<&mut Self>) -> QString {
+        let backup_dir = config_path("rule_backups");
+        let mut backups: Vec<serde_json::Value> = Vec::new();
+
+        if let Ok(entries) = std::fs::read_dir(&backup_dir) {
+            for entry in entries.flatten() {
+                let path = entry.path();
+                if path.extension().and_then(|e| e.to_str()) == Some("json") {
+                    let filename = path
+                        .file_name()
+                        .and_then(|n| n.to_str())
+                        .unwrap_or("")
+                        .to_string();
+
+                    // Parse rule count from the file
+                    let rule_count = std::fs::read_to_string(&path)
+                        .ok()
+                        .and_then(|s| serde_json::from_str::<Vec<serde_json::Value>>(&s).ok())
+                        .map(|v| v.len())
+                        .unwrap_or(0);
+
+                    // Extract display name from filename: strip .json, split on first _
+                    // Format: YYYYMMDD_HHMMSS_OptionalName.json
+                    let stem = filename.trim_end_matches(".json");
+                    let display_name = if stem.len() > 16 && stem.chars().nth(15) == Some('_') {
+                        stem[16..].to_string()
+                    } else {
+                        String::new()
+                    };
+
+                    // Get timestamp from filename
+                    let date_str = if stem.len() >= 15 {
+                        let d = &stem[..8];
+                        let t = &stem[9..15];
+                        format!(
+                            "{}-{}-{} {}:{}:{}",
+                            &d[..4], &d[4..6], &d[6..8],
+                            &t[..2], &t[2..4], &t[4..6]
+                        )
+                    } else {
+                        // Fallback to file modification time
+                        entry
+                            .metadata()
+                            .ok()
+                            .and_then(|m| m.modified().ok())
+                            .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+                            .map(|dur| {
+                                let secs = dur.as_secs() as i64;
+                                let tod = (secs % 86400) as u32;
+                                let (y, mo, d) = days_to_ymd(secs / 86400);
+                                format!("{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+                                    y, mo, d, tod / 3600, (tod % 3600) / 60, tod % 60)
+                            })
+                            .unwrap_or_default()
+                    };
+
+                    let transition_steps = self
+                        .rust()
+                        .scene_transitions
+                        .get(&display_name)
+                        .map_or(0, Vec::len);
+                    backups.push(serde_json::json!({
+                        "filename": filename,
+                        "name": display_name,
+                        "date": date_str,
+                        "ruleCount": rule_count,
+                        "transitionSteps": transition_steps,
+                    }));
+                }
+            }
+        }
+
+        // Sort newest first
+        backups.sort_by(|a, b| {
+            let fa = a["filename"].as_str().unwrap_or("");
+            let fb = b["filename"].as_str().unwrap_or("");
+            fb.cmp(fa)
+        });
+
+        let json = serde_json::to_string(&backups).unwrap_or_else(|_| "[]".to_string());
+        QString::from(&json)
+    }
+
+    /// Switch to the rules of a backup. When the backup is a scene with a
+    /// transition, its steps run from the next poll on and the rules are
+    /// switched by its [`SceneAction::SwitchLinks`] step; a transition still
+    /// running is called off.
+    pub fn restore_rule_backup(mut self: Pin<&mut Self>, filename: QString) {
+        let filename = filename.to_string();
+        if let Some((previous, _)) = self.as_mut().rust_mut().scene_transition.take() {
+            log::info!("Scene transition to {:?} called off", previous);
+        }
+        let scene = rule_backup_name(filename.trim_end_matches(".json"));
+        match self.rust().scene_transitions.get(scene) {
+            Some(steps) if !scene.is_empty() && !steps.is_empty() => {
+                log::info!(
+                    "Starting scene transition to {:?} ({} steps)",
+                    scene,
+                    steps.len()
+                );
+                let runner = TransitionRunner::new(steps.clone(), Instant::now());
+                self.as_mut().rust_mut().scene_transition = Some((filename, runner));
+            }
+            _ => self.as_mut().load_rule_backup(&filename),
+        }
+    }
+
+    /// Replace the rules with a backup's, without applying them.
+    fn load_rule_backup(mut self: Pin<&mut Self>, filename_str: &str) {
+        let backup_path = config_path("rule_backups").join(filename_str);
+
+        match std::fs::read_to_string(&backup_path) {
+            Ok(content) => {
+                // Validate it parses as rules, upgrading backups taken by
+                // older versions
+                let parsed = config::RULES.upgrade(&content).and_then(|(data, _)| {
+                    serde_json::from_value::<Vec<crate::patchbay::rules::AutoConnectRule>>(data)
+                        .map_err(config::ConfigError::from)
+                });
+                match parsed {
+                    Ok(rules) => {
+                        // Write to rules.json, after any queued save of the old rules
+                        crate::control::flush();
+                        let rules_path = config_path("rules.json");
+                        if let Err(e) = config::RULES.save(&rules_path, &rules) {
+                            log::error!("Failed to write restored rules: {}", e);
+                            return;
+                        }
+                        // Load into patchbay manager
+                        if let Some(ref mut patchbay) = self.as_mut().rust_mut().patchbay {
+                            patchbay.set_rules(rules.clone());
+                        }
+                        log::info!("Restored {} rules from backup {:?}", rules.len(), filename_str);
+                        let scene = rule_backup_name(filename_str.trim_end_matches(".json"));
+                        crate::hooks::fire(
+                            &self.rust().hooks,
+                            HookEvent::SceneActivated,
+                            serde_json::json!({ "scene": scene, "rules": rules.len() }),
+                        );
+                        if let Some(ref dbus) = self.rust().patchbay_dbus {
+                            dbus.set_scene(scene);
+                        }
+                    }
+                    Err(e) => {
+                        log::error!("Backup file {:?} contains invalid rules: {}", filename_str, e);
+                    }
+                }
+            }
+            Err(e) => {
+                log::error!("Failed to read backup {:?}: {}", backup_path, e);
+            }
+        }
+    }
+
+    /// Run the steps of the scene transition that are due.
+    fn advance_scene_transition(mut self: Pin<&mut Self>) {
+        let Some((filename, mut runner)) = self.as_mut().rust_mut().scene_transition.take() else {
+            return;
+        };
+        for action in runner.take_due(Instant::now()) {
+            self.as_mut().run_scene_action(&filename, action);
+        }
+        if !runner.is_done() {
+            self.as_mut().rust_mut().scene_transition = Some((filename, runner));
+        }
+    }
+
+    fn run_scene_action(mut self: Pin<&mut Self>, filename: &str, action: SceneAction) {
+        log::debug!("Scene transition step: {:?}", action);
+        let nodes = match self.rust().graph.as_ref() {
+            Some(graph) => graph.get_all_nodes(),
+            None => return,
+        };
+        let find = |name: &str| {
+            let node = node_named(&nodes, name).map(|n| n.id);
+            if node.is_none() {
+                log::warn!("Scene transition: no node named {:?}", name);
+            }
+            node
+        };
+        match action {
+            SceneAction::Mute { ref node } | SceneAction::Unmute { ref node } => {
+                if let Some(node_id) = find(node) {
+                    self.send_command(
+                        CommandOrigin::User,
+                        PwCommand::SetNodeMute {
+                            node_id,
+                            mute: matches!(action, SceneAction::Mute { .. }),
+                        },
+                    );
+                }
+            }
+            SceneAction::Bypass { ref plugin } | SceneAction::Unbypass { ref plugin } => {
+                if let Some(node_id) = find(plugin) {
+                    let bypassed = matches!(action, SceneAction::Bypass { .. });
+                    self.as_mut().set_plugin_bypass(node_id, bypassed);
+                }
+            }
+            SceneAction::DefaultOutput { node } => {
+                if let Some(node_id) = find(&node) {
+                    self.as_mut().set_default_output(node_id);
+                }
+            }
+            SceneAction::SwitchLinks => {
+                self.as_mut().load_rule_backup(filename);
+                self.as_mut().apply_rules();
+                let scene = rule_backup_name(filename.trim_end_matches(".json"));
+                self.as_mut().scene_switched(QString::from(scene));
+            }
+        }
+    }
+
+    /// The transition steps of `scene`, as a JSON array.
+    pub fn get_scene_transition_json(self: Pin<&mut Self>, scene: QString) -> QString {
+        let steps = self
+            .rust()
+            .scene_transitions
+            .get(&scene.to_string())
+            .cloned()
+            .unwrap_or_default();
+        QString::from(&serde_json::to_string(&steps).unwrap_or_else(|_| "[]".into()))
+    }
+
+    /// Set the transition steps of `scene` from a JSON array; an empty one
+    /// switches the scene at once again. Returns false when the JSON is not
+    /// a list of steps.
+    pub fn set_scene_transition_json(
+        mut self: Pin<&mut Self>,
+        scene: QString,
+        steps_json: QString,
+    ) -> bool {
+        let steps: Vec<TransitionStep> = match serde_json::from_str(&steps_json.to_string()) {
+            Ok(steps) => steps,
+            Err(e) => {
+                log::warn!("Invalid scene transition: {}", e);
+                return false;
+            }
+        };
+        let scene = scene.to_string();
+        let transitions = &mut self.as_mut().rust_mut().scene_transitions;
+        if steps.is_empty() {
+            transitions.remove(&scene);
+        } else {
+            transitions.insert(scene, steps);
+        }
+        save_scene_transitions(&self.rust().scene_transitions);
+        true
+    }
+
+    pub fn delete_rule_backup(self: Pin<&mut Self>, filename: QString) {
+        let filename_str: String = filename.to_string();
+        let backup_path = config_path("rule_backups").join(&filename_str);
+        if let Err(e) = std::fs::remove_file(&backup_path) {
+            log::error!("Failed to delete backup {:?}: {}", backup_path, e);
+        } else {
+            log::info!("Deleted rule backup: {}", filename_str);
+        }
+    }
+
+    pub fn set_window_visible(mut self: Pin<&mut Self>, visible: bool) {
+        if let Some(ref tray) = self.rust().tray_state {
+            use std::sync::atomic::Ordering;
+            tray.window_visible.store(visible, Ordering::Release);
+            log::info!("Window visible state updated to {}", visible);
+        }
+        if visible {
+            self.as_mut().rust_mut().plugin_fault = None;
+        } else if let Some(ref portal) = self.rust().portal {
+            portal.request_background();
+        }
+    }
+
+    /// Publish the current graph to the JACK patchbay interface, with nodes
+    /// as clients and their audio and MIDI ports.
+    fn sync_jack_patchbay(&self) {
+        use crate::jack_dbus::{PatchbayClient, PatchbayConnection, PatchbayGraph, PatchbayPort};
+        use crate::pipewire::MediaType;
+
+        let (Some(jack), Some(graph)) =
+            (self.rust().jack_dbus.as_ref(), self.rust().graph.as_ref())
+        else {
+            return;
+        };
+        let clients = graph
+            .get_all_nodes()
+            .into_iter()
+            .map(|node| {
+                let is_physical = !node.is_virtual
+                    && matches!(node.node_type, Some(NodeType::Sink | NodeType::Source));
+                let ports = graph
+                    .get_ports_for_node(node.id)
+                    .into_iter()
+                    .filter(|p| matches!(p.media_type, Some(MediaType::Audio | MediaType::Midi)))
+                    .map(|p| PatchbayPort {
+                        id: p.id,
+                        name: p.name.clone(),
+                        is_output: p.direction == PortDirection::Output,
+                        is_physical,
+                        is_midi: p.media_type == Some(MediaType::Midi),
+                    })
+                    .collect();
+                PatchbayClient {
+                    id: node.id,
+                    name: node.display_name().to_string(),
+                    ports,
+                }
+            })
+            .filter(|c| !c.ports.is_empty())
+            .collect();
+        let connections = graph
+            .get_all_links()
+            .into_iter()
+            .map(|l| PatchbayConnection {
+                id: l.id,
+                output_port: l.output_port_id,
+                input_port: l.input_port_id,
+            })
+            .collect();
+        jack.publish(PatchbayGraph {
+            clients,
+            connections,
+        });
+    }
+
+    /// Publish the graph, the patchbay switch and the scenes to the
+    /// `org.zestbay.Patchbay` control interface.
+    fn sync_patchbay_dbus(&self) {
+        use crate::patchbay_dbus::{BusLink, BusNode, BusPort, BusSnapshot};
+
+        let (Some(dbus), Some(graph)) = (
+            self.rust().patchbay_dbus.as_ref(),
+            self.rust().graph.as_ref(),
+        ) else {
+            return;
+        };
+        let nodes = graph
+            .get_all_nodes()
+            .into_iter()
+            .filter(|n| n.ready && !is_link_node(&n.name))
+            .map(|node| BusNode {
+                id: node.id,
+                name: node.name.clone(),
+                description: node.display_name().to_string(),
+                kind: node
+                    .node_type
+                    .map(rules::node_type_label)
+                    .unwrap_or("")
+                    .to_string(),
+                ports: graph
+                    .get_ports_for_node(node.id)
+                    .into_iter()
+                    .map(|p| BusPort {
+                        id: p.id,
+                        name: p.name.clone(),
+                        is_output: p.direction == PortDirection::Output,
+                        media: p.media_type.map(|m| format!("{:?}", m)).unwrap_or_default(),
+                    })
+                    .collect(),
+            })
+            .collect();
+        let links = graph
+            .get_all_links()
+            .into_iter()
+            .map(|l| BusLink {
+                id: l.id,
+                output_port: l.output_port_id,
+                input_port: l.input_port_id,
+            })
+            .collect();
+        dbus.publish(BusSnapshot {
+            nodes,
+            links,
+            patchbay_enabled: self.rust().patchbay.as_ref().is_some_and(|p| p.enabled),
+            scenes: rule_backup_names(),
+            scene: String::new(),
+        });
+    }
+
+    /// (Re)connect the MQTT publisher with the current preferences, or stop
+    /// it when disabled.
+    fn finish_update_check(mut self: Pin<&mut Self>, result: Result<Release, String>) {
+        let current = env!("CARGO_PKG_VERSION");
+        let latest = match &result {
+            Ok(release) if release.is_newer_than(current) => {
+                log::info!(
+                    "Update available: {} (running {})",
+                    release.version(),
+                    current
+                );
+                release.version().to_string()
+            }
+            Ok(_) => {
+                log::info!("ZestBay {} is up to date", current);
+                String::new()
+            }
+            Err(e) => {
+                log::warn!("Update check failed: {}", e);
+                String::new()
+            }
+        };
+        self.as_mut().rust_mut().update_rx = None;
+        self.as_mut().rust_mut().update_result = Some(result);
+        self.as_mut().set_latest_version(QString::from(&latest));
+        self.as_mut().update_status_changed();
+    }
+
+    fn restart_mqtt(mut self: Pin<&mut Self>) {
+        if let Some(mqtt) = self.as_mut().rust_mut().mqtt.take() {
+            mqtt.stop();
+        }
+        let prefs = &self.rust().prefs;
+        if !prefs.mqtt_enabled {
+            return;
+        }
+        let security = crate::mqtt::MqttSecurity {
+            username: &prefs.mqtt_username,
+            password: &prefs.mqtt_password,
+            tls: prefs.mqtt_tls,
+            tls_ca: &prefs.mqtt_tls_ca,
+            scope: prefs.mqtt_scope,
+        };
+        match crate::mqtt::spawn_mqtt(&prefs.mqtt_broker, &prefs.mqtt_topic, &security) {
+            Ok(mqtt) => {
+                self.as_mut().rust_mut().mqtt = Some(mqtt);
+                self.sync_mqtt();
+            }
+            Err(e) => {
+                log::warn!("MQTT: {}", e);
+                self.as_mut()
+                    .error_occurred(QString::from(&format!("MQTT: {}", e)));
+            }
+        }
+    }
+
+    /// Publish the default sink and which application streams are playing
+    /// or recording.
+    fn sync_mqtt(&self) {
+        let (Some(mqtt), Some(graph)) = (self.rust().mqtt.as_ref(), self.rust().graph.as_ref())
+        else {
+            return;
+        };
+        let nodes = graph.get_all_nodes();
+        let default_sink = graph.default_sink().map(|name| {
+            nodes
+                .iter()
+                .find(|n| n.name == name)
+                .map(|n| n.display_name().to_string())
+                .unwrap_or(name)
+        });
+        let (playing, recording) = active_streams(&nodes, &graph.get_all_links());
+        mqtt.publish(crate::mqtt::MqttStatus {
+            default_sink,
+            playing,
+            recording,
+        });
+    }
+
+    /// (Re)start the remote API with the current preferences, or stop it
+    /// when disabled.
+    fn restart_remote_api(mut self: Pin<&mut Self>) {
+        if let Some(remote) = self.as_mut().rust_mut().remote_api.take() {
+            remote.stop();
+        }
+        if !self.rust().prefs.remote_api_enabled
+            && !crate::HEADLESS.load(std::sync::atomic::Ordering::SeqCst)
+        {
+            return;
+        }
+        if self.rust().prefs.remote_api_token.is_empty() {
+            self.as_mut().rust_mut().prefs.remote_api_token = crate::remote_api::generate_token();
+            save_preferences(&self.rust().prefs);
+        }
+        let prefs = &self.rust().prefs;
+        let tls = match (
+            prefs.remote_api_tls_cert.trim(),
+            prefs.remote_api_tls_key.trim(),
+        ) {
+            ("", "") => Ok(None),
+            (cert, key) => crate::remote_api::load_tls_config(cert, key).map(Some),
+        };
+        let main_token = ApiToken {
+            name: "Main".into(),
+            token: prefs.remote_api_token.clone(),
+            scope: ApiScope::Plugins,
+        };
+        let tokens = std::iter::once(main_token)
+            .chain(prefs.remote_api_tokens.iter().cloned())
+            .collect();
+        let cors_origin = Some(prefs.remote_api_cors_origin.as_str()).filter(|o| !o.is_empty());
+        let started = tls.and_then(|tls| {
+            crate::remote_api::spawn_remote_api(&prefs.remote_api_bind, tokens, tls, cors_origin)
+        });
+        match started {
+            Ok(remote) => {
+                self.as_mut().rust_mut().remote_api = Some(remote);
+                self.as_mut().sync_remote_api();
+            }
+            Err(e) => {
+                log::warn!("Remote API: {}", e);
+                self.as_mut()
+                    .error_occurred(QString::from(&format!("Remote API: {}", e)));
+            }
+        }
+    }
+
+    /// Publish the graph to remote API clients in the shape the QML views
+    /// get it.
+    fn sync_remote_api(mut self: Pin<&mut Self>) {
+        if self.rust().remote_api.is_none() {
+            return;
+        }
+        let parse = |json: QString| {
+            serde_json::from_str::<serde_json::Value>(&json.to_string()).unwrap_or_default()
+        };
+        let mut nodes = parse(self.as_mut().get_nodes_json());
+        name_kinds(&mut nodes);
+        let links = parse(self.as_mut().get_links_json());
+        let mut ports = serde_json::Map::new();
+        let node_ids: Vec<u64> = nodes
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|n| n["id"].as_u64())
+            .collect();
+        for id in node_ids {
+            let mut node_ports = parse(self.as_mut().get_ports_json(id as u32));
+            name_kinds(&mut node_ports);
+            ports.insert(id.to_string(), node_ports);
+        }
+        let patchbay_enabled = self.rust().patchbay.as_ref().is_some_and(|p| p.enabled);
+        if let Some(ref remote) = self.rust().remote_api {
+            remote.publish_graph(serde_json::json!({
+                "nodes": nodes,
+                "links": links,
+                "ports": ports,
+                "patchbayEnabled": patchbay_enabled,
+            }));
+        }
+    }
+
+    fn run_remote_request(mut self: Pin<&mut Self>, request: crate::remote_api::RemoteRequest) {
+        use crate::remote_api::RemoteRequest;
+
+        self.as_mut().rust_mut().command_origin = Some(CommandOrigin::Remote);
+        match request {
+            RemoteRequest::Connect {
+                output_port_id,
+                input_port_id,
+                allow_feedback,
+            } => match self.feedback_check(output_port_id, input_port_id) {
+                // No dialog to ask through: the client hears why instead.
+                Some(path) if !allow_feedback => {
+                    if let Some(ref remote) = self.rust().remote_api {
+                        let message =
+                            format!("Not connected: the link closes a feedback loop: {}", path);
+                        remote.emit(
+                            "error",
+                            serde_json::json!({
+                                "message": message,
+                                "output_port_id": output_port_id,
+                                "input_port_id": input_port_id,
+                            }),
+                        );
+                    }
+                }
+                _ => {
+                    self.as_mut()
+                        .connect_user_ports(output_port_id, input_port_id, allow_feedback);
+                }
+            },
+            RemoteRequest::Disconnect { link_id } => self.as_mut().disconnect_link(link_id),
+            RemoteRequest::SetPluginBypass { node_id, bypassed } => {
+                self.as_mut().set_plugin_bypass(node_id, bypassed)
+            }
+            RemoteRequest::SetPluginParameter {
+                node_id,
+                port_index,
+                value,
+            } => self
+                .as_mut()
+                .set_plugin_parameter(node_id, port_index, value),
+            RemoteRequest::SetPatchbayEnabled { enabled } => self.as_mut().toggle_patchbay(enabled),
+            RemoteRequest::ApplyRules => self.as_mut().apply_rules(),
+        }
+        self.as_mut().rust_mut().command_origin = None;
+    }
+
+    fn run_mqtt_command(mut self: Pin<&mut Self>, command: crate::mqtt::MqttCommand) {
+        match command {
+            crate::mqtt::MqttCommand::Mute { node, mute } => {
+                let Some(graph) = self.rust().graph.as_ref() else {
+                    return;
+                };
+                let Some(name) = node.or_else(|| graph.default_sink()) else {
+                    log::warn!("MQTT: no default sink to mute");
+                    return;
+                };
+                let nodes = graph.get_all_nodes();
+                match node_named(&nodes, &name) {
+                    Some(target) => {
+                        self.send_command(
+                            CommandOrigin::Remote,
+                            PwCommand::SetNodeMute {
+                                node_id: target.id,
+                                mute,
+                            },
+                        );
+                    }
+                    None => log::warn!("MQTT: no node named {:?}", name),
+                }
+            }
+            crate::mqtt::MqttCommand::Scene(name) => match find_rule_backup(&name) {
+                Some(filename) => {
+                    log::info!("MQTT: switching to scene {:?}", name);
+                    self.as_mut().restore_rule_backup(QString::from(&filename));
+                    // A transition applies the rules in its own time.
+                    if self.rust().scene_transition.is_none() {
+                        self.as_mut().apply_rules();
+                    }
+                }
+                None => log::warn!("MQTT: no rule backup named {:?}", name),
+            },
+        }
+    }
+
+    /// Run a request made over the `org.zestbay.Patchbay` interface.
+    fn run_control_request(
+        mut self: Pin<&mut Self>,
+        request: crate::patchbay_dbus::ControlRequest,
+    ) {
+        use crate::patchbay_dbus::ControlRequest;
+
+        log::info!("Patchbay control: {:?}", request);
+        match request {
+            ControlRequest::Connect {
+                output_port_id,
+                input_port_id,
+                allow_feedback,
+                reply,
+            } => {
+                let result = self.try_send_command(
+                    CommandOrigin::Remote,
+                    PwCommand::Connect {
+                        output_port_id,
+                        input_port_id,
+                    },
+                    allow_feedback,
+                );
+                let _ = reply.send(result);
+            }
+            ControlRequest::Disconnect { link_id } => {
+                self.send_command(CommandOrigin::Remote, PwCommand::Disconnect { link_id })
+            }
+            ControlRequest::SetPatchbayEnabled(enabled) => self.as_mut().toggle_patchbay(enabled),
+            ControlRequest::SwitchScene(name) => match find_rule_backup(&name) {
+                Some(filename) => {
+                    self.as_mut().restore_rule_backup(QString::from(&filename));
+                    // A transition applies the rules in its own time.
+                    if self.rust().scene_transition.is_none() {
+                        self.as_mut().apply_rules();
+                    }
+                }
+                None => log::warn!("Patchbay control: no rule backup named {:?}", name),
+            },
+            ControlRequest::ToggleAbSwitches => {
+                self.as_mut().toggle_ab_switches();
+            }
+        }
+    }
+
+    fn sync_tray_plugins(self: Pin<&mut Self>) {
+        let tray = match self.rust().tray_state.as_ref() {
+            Some(t) => t,
+            None => return,
+        };
+        let mgr = match self.rust().plugin_manager.as_ref() {
+            Some(m) => m,
+            None => return,
+        };
+
+        let mut entries: Vec<crate::tray::PluginEntry> = mgr
+            .active_instances()
+            .values()
+            .filter(|info| info.pw_node_id.is_some())
+            .map(|info| {
+                let has_ui = mgr
+                    .find_plugin(&info.plugin_uri)
+                    .map(|p| p.has_ui)
+                    .unwrap_or(false);
+                crate::tray::PluginEntry {
+                    name: info.display_name.clone(),
+                    node_id: info.pw_node_id.unwrap_or(0),
+                    has_ui,
+                }
+            })
+            .collect();
+        entries.sort_by(|a, b| a.name.cmp(&b.name));
+
+        if let Ok(mut plugins) = tray.plugins.lock() {
             *plugins = entries;
         }
     }
 
-    fn find_instance_id_for_node(&self, node_id: u32) -> Option<u64> {
-        if let Some(ref mgr) = self.rust().plugin_manager {
-            for (id, info) in mgr.active_instances() {
-                if info.pw_node_id == Some(node_id) {
-                    return Some(*id);
-                }
-            }
-        }
-        None
+    /// Send `cmd` to the PipeWire thread, logging it first if the command
+    /// log is on.
+    fn send_command(&self, origin: CommandOrigin, cmd: PwCommand) {
+        // A refused link is logged; callers that report it to someone use
+        // `try_send_command` themselves.
+        let _ = self.try_send_command(origin, cmd, false);
+    }
+
+    /// Send `cmd` to the PipeWire thread, unless a link it makes would
+    /// close an audio feedback loop and `allow_feedback` is not set. Every
+    /// command passes through here, so links made by rules, scenes, remote
+    /// clients and inserts are held to the same check as those made by
+    /// hand. The error is the loop, as "A → B → A".
+    fn try_send_command(
+        &self,
+        origin: CommandOrigin,
+        cmd: PwCommand,
+        allow_feedback: bool,
+    ) -> Result<(), String> {
+        let Some(ref tx) = self.rust().cmd_tx else {
+            return Ok(());
+        };
+        if !allow_feedback
+            && let Some(ref graph) = self.rust().graph
+            && let Some(found) = graph.closed_feedback_loop(&cmd)
+        {
+            let text = graph.feedback_loop_text(&found.nodes);
+            log::warn!(
+                "Refused link {} -> {}: it closes a feedback loop: {}",
+                found.output_port_id,
+                found.input_port_id,
+                text
+            );
+            return Err(text);
+        }
+        self.log_command(origin, &cmd);
+        let _ = tx.send(cmd);
+        Ok(())
+    }
+
+    fn log_command(&self, origin: CommandOrigin, cmd: &PwCommand) {
+        let rust = self.rust();
+        if !rust.prefs.pw_command_log {
+            return;
+        }
+        let origin = match (origin, &rust.command_origin) {
+            (CommandOrigin::User, Some(o)) => o.clone(),
+            (origin, _) => origin,
+        };
+        let time_ms = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+        let entry = AuditEntry::new(time_ms, origin, cmd, rust.graph.as_deref());
+        if let Err(e) = rust.command_log.append(&entry) {
+            log::warn!(
+                "Failed to write {}: {}",
+                rust.command_log.path().display(),
+                e
+            );
+        }
+    }
+
+    fn find_instance_id_for_node(&self, node_id: u32) -> Option<u64> {
+        if let Some(ref mgr) = self.rust().plugin_manager {
+            for (id, info) in mgr.active_instances() {
+                if info.pw_node_id == Some(node_id) {
+                    return Some(*id);
+                }
+            }
+        }
+        None
+    }
+
+    fn unique_display_name(&self, base_name: &str) -> String {
+        let existing: Vec<String> = if let Some(ref mgr) = self.rust().plugin_manager {
+            mgr.active_instances()
+                .values()
+                .map(|info| info.display_name.clone())
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        if !existing.iter().any(|n| n == base_name) {
+            return base_name.to_string();
+        }
+
+        for n in 2.. {
+            let candidate = format!("{} #{}", base_name, n);
+            if !existing.iter().any(|n| n == &candidate) {
+                return candidate;
+            }
+        }
+        unreachable!()
+    }
+}
+
+/// Strip a trailing " #N" instance counter, so that duplicating "Comp #2"
+/// yields "Comp #3" rather than "Comp #2 #2".
+fn strip_instance_suffix(name: &str) -> &str {
+    match name.rsplit_once(" #") {
+        Some((base, n)) if !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()) => base,
+        _ => name,
+    }
+}
+
+/// Convert days since Unix epoch to (year, month, day).
+fn days_to_ymd(days: i64) -> (i64, u32, u32) {
+    // Algorithm from http://howardhinnant.github.io/date_algorithms.html
+    let z = days + 719468;
+    let era = (if z >= 0 { z } else { z - 146096 }) / 146097;
+    let doe = (z - era * 146097) as u32; // day of era [0, 146096]
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365; // year of era [0, 399]
+    let y = yoe as i64 + era * 400;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100); // day of year [0, 365]
+    let mp = (5 * doy + 2) / 153; // [0, 11]
+    let d = doy - (153 * mp + 2) / 5 + 1; // [1, 31]
+    let m = if mp < 10 { mp + 3 } else { mp - 9 }; // [1, 12]
+    let y = if m <= 2 { y + 1 } else { y };
+    (y, m, d)
+}
+
+fn config_path(filename: &str) -> PathBuf {
+    let dir = dirs::config_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("zestbay");
+    // Demo sessions get their own rules, layout and plugins, so they start
+    // the same every time and never touch the real setup.
+    if crate::DEMO_MODE.load(std::sync::atomic::Ordering::SeqCst) {
+        return dir.join("demo").join(filename);
+    }
+    dir.join(filename)
+}
+
+/// System-wide defaults for `filename`, most specific first. Demo sessions
+/// ignore them, like the rest of the real setup.
+fn system_config_paths(filename: &str) -> Vec<PathBuf> {
+    if crate::DEMO_MODE.load(std::sync::atomic::Ordering::SeqCst) {
+        return Vec::new();
+    }
+    config::SYSTEM_CONFIG_DIRS
+        .iter()
+        .map(|dir| std::path::Path::new(dir).join(filename))
+        .collect()
+}
+
+/// Read a versioned config file, migrating it first if an older version wrote
+/// it, layered over the system-wide default of the same name. Missing or
+/// unreadable files give the default.
+fn load_config<T: serde::de::DeserializeOwned + Default>(
+    schema: &ConfigSchema,
+    filename: &str,
+    layering: config::Layering,
+) -> T {
+    let defaults = system_config_paths(filename);
+    match schema.load_layered(&config_path(filename), &defaults, layering) {
+        Ok(Some(data)) => data,
+        Ok(None) => T::default(),
+        Err(e) => {
+            log::error!("Failed to load {}: {}", filename, e);
+            T::default()
+        }
+    }
+}
+
+/// A saved UI state file as JSON for QML, or `empty` if it is missing or
+/// does not pass validation.
+fn ui_state_json<T: UiState>(empty: &str) -> QString {
+    let state = match T::SCHEMA.load::<T>(&config_path(T::FILE)) {
+        Ok(Some(state)) => state,
+        Ok(None) => return QString::from(empty),
+        Err(e) => {
+            log::error!("Failed to load {}: {}", T::FILE, e);
+            return QString::from(empty);
+        }
+    };
+    if let Err(e) = state.validate() {
+        log::warn!("Ignoring {}: {}", T::FILE, e);
+        return QString::from(empty);
+    }
+    QString::from(&serde_json::to_string(&state).unwrap_or_else(|_| empty.to_string()))
+}
+
+/// Validate UI state sent from QML and save it. Rejected payloads leave the
+/// file as it was.
+fn save_ui_state<T: UiState>(json: &str) -> bool {
+    let state = match T::parse(json) {
+        Ok(state) => state,
+        Err(e) => {
+            log::warn!("Rejected {} from the UI: {}", T::FILE, e);
+            return false;
+        }
+    };
+    let path = config_path(T::FILE);
+    if let Err(e) = T::SCHEMA.save(&path, &state) {
+        log::error!("Failed to save {:?}: {}", path, e);
+        return false;
+    }
+    log::debug!("Saved {:?}", path);
+    true
+}
+
+fn crash_marker_path() -> PathBuf {
+    config_path(".zestbay-restoring")
+}
+
+fn write_crash_marker(plugin_uris: &[String]) {
+    let path = crash_marker_path();
+    if let Some(parent) = path.parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+    let content = plugin_uris.join("\n");
+    if let Err(e) = std::fs::write(&path, &content) {
+        log::error!("Failed to write crash marker: {}", e);
+    }
+}
+
+fn remove_crash_marker() {
+    let path = crash_marker_path();
+    let _ = std::fs::remove_file(&path);
+}
+
+fn has_crash_marker() -> bool {
+    crash_marker_path().exists()
+}
+
+fn read_crash_marker() -> Vec<String> {
+    let path = crash_marker_path();
+    match std::fs::read_to_string(&path) {
+        Ok(s) => s.lines().filter(|l| !l.is_empty()).map(String::from).collect(),
+        Err(_) => Vec::new(),
+    }
+}
+
+fn known_good_plugins_path() -> PathBuf {
+    config_path("plugins.known_good.json")
+}
+
+fn save_known_good_plugins() {
+    crate::control::flush();
+    let src = config_path("plugins.json");
+    let dst = known_good_plugins_path();
+    if src.exists() {
+        if let Err(e) = std::fs::copy(&src, &dst) {
+            log::error!("Failed to save known-good plugins snapshot: {}", e);
+        } else {
+            log::info!("Saved known-good plugins snapshot to {:?}", dst);
+        }
+    }
+}
+
+fn has_known_good_plugins() -> bool {
+    known_good_plugins_path().exists()
+}
+
+fn restore_known_good_plugins() -> bool {
+    crate::control::flush();
+    let src = known_good_plugins_path();
+    let dst = config_path("plugins.json");
+    if !src.exists() {
+        log::warn!("No known-good plugins snapshot to restore");
+        return false;
+    }
+    match std::fs::copy(&src, &dst) {
+        Ok(_) => {
+            log::info!("Restored plugins.json from known-good snapshot");
+            true
+        }
+        Err(e) => {
+            log::error!("Failed to restore known-good plugins: {}", e);
+            false
+        }
+    }
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+struct SavedPlugin {
+    #[serde(default)]
+    stable_id: String,
+    uri: String,
+    display_name: String,
+    #[serde(default)]
+    bypassed: bool,
+    #[serde(default)]
+    parameters: Vec<SavedPluginParam>,
+    /// "LV2", "CLAP", or "VST3".  Defaults to "LV2" for backwards compat.
+    #[serde(default = "default_lv2_format_str")]
+    format: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    lv2_state: Vec<crate::lv2::state::StateEntry>,
+    #[serde(default = "default_oversampling")]
+    oversampling: u32,
+    /// Left out of the startup restore and started on request instead.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    manual_start: bool,
+    /// Requested processing buffer in samples; the graph default if unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    quantum: Option<u32>,
+}
+
+/// Plugin UI windows by plugin stable ID: where each was last placed, and
+/// which were open when ZestBay last ran.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Default)]
+struct SavedUiWindows {
+    #[serde(default)]
+    geometry: HashMap<String, crate::plugin::ui_geometry::UiGeometry>,
+    /// The monitor each was last left on, by name.
+    #[serde(default)]
+    monitors: HashMap<String, String>,
+    #[serde(default)]
+    open: std::collections::BTreeSet<String>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+struct SavedPluginParam {
+    port_index: usize,
+    symbol: String,
+    value: f32,
+}
+
+fn default_lv2_format_str() -> String {
+    "LV2".to_string()
+}
+
+fn default_oversampling() -> u32 {
+    1
+}
+
+fn load_saved_plugins() -> Vec<SavedPlugin> {
+    load_config(&config::PLUGINS, "plugins.json", config::Layering::Replace)
+}
+
+/// Write the running plugins, and the manual-start ones not started yet, to
+/// `plugins.json`.
+fn saved_plugin(
+    info: &crate::lv2::Lv2InstanceInfo,
+    manual_start: bool,
+    quantum: Option<u32>,
+) -> SavedPlugin {
+    let params: Vec<SavedPluginParam> = info
+        .parameters
+        .iter()
+        .map(|p| SavedPluginParam {
+            port_index: p.port_index,
+            symbol: p.symbol.clone(),
+            value: p.value,
+        })
+        .collect();
+    SavedPlugin {
+        stable_id: info.stable_id.clone(),
+        uri: info.plugin_uri.clone(),
+        display_name: info.display_name.clone(),
+        bypassed: info.bypassed,
+        parameters: params,
+        format: info.format.as_str().to_string(),
+        lv2_state: info.lv2_state.clone(),
+        oversampling: info.oversampling,
+        manual_start,
+        quantum,
+    }
+}
+
+fn persist_active_plugins(state: &AppControllerRust) {
+    if let Some(ref mgr) = state.plugin_manager {
+        let mut active: Vec<String> = mgr
+            .active_instances()
+            .values()
+            .map(|info| format!("{} ({})", info.display_name, info.plugin_uri))
+            .collect();
+        active.sort();
+        crate::crash_report::set_state("Plugins", active.join("; "));
+    }
+    if crate::PLUGINS_FROZEN.load(std::sync::atomic::Ordering::SeqCst) {
+        log::info!("persist_active_plugins: skipped (plugins frozen in safe mode)");
+        return;
+    }
+    let mut plugins: Vec<SavedPlugin> = if let Some(ref mgr) = state.plugin_manager {
+        mgr.active_instances()
+            .values()
+            .map(|info| {
+                saved_plugin(
+                    info,
+                    state.manual_start_plugins.contains(&info.stable_id),
+                    state.plugin_quanta.get(&info.stable_id).copied(),
+                )
+            })
+            .collect()
+    } else {
+        Vec::new()
+    };
+    plugins.extend(state.dormant_plugins.iter().cloned());
+    plugins.sort_by(|a, b| a.stable_id.cmp(&b.stable_id));
+    let path = config_path("plugins.json");
+    crate::control::write("plugins.json", move || {
+        if let Err(e) = config::PLUGINS.save(&path, &plugins) {
+            log::error!("Failed to save plugins to {:?}: {}", path, e);
+        } else {
+            log::debug!("persist_active_plugins: {} plugins written", plugins.len());
+        }
+    });
+}
+
+/// The graph changes that call for saving `links.json`.
+fn link_filter() -> GraphFilter {
+    GraphFilter::all().object(GraphObject::Link)
+}
+
+/// The graph changes that call for refreshing the cached nodes and ports.
+fn view_filter() -> GraphFilter {
+    GraphFilter::all()
+        .object(GraphObject::Node)
+        .object(GraphObject::Port)
+}
+
+fn load_saved_links() -> Vec<SavedPluginLink> {
+    load_config(&config::LINKS, "links.json", config::Layering::Replace)
+}
+
+fn is_sidechain_port(port: &Port) -> bool {
+    port.direction == PortDirection::Input
+        && port.port_group.as_deref() == Some(crate::plugin::types::SIDECHAIN_PORT_GROUP)
+}
+
+/// A plugin node's sidechain inputs, in channel order.
+fn sidechain_ports(graph: &GraphState, node_id: u32) -> Vec<Port> {
+    let mut ports: Vec<_> = graph
+        .get_ports_for_node(node_id)
+        .into_iter()
+        .filter(is_sidechain_port)
+        .collect();
+    ports.sort_by(|a, b| crate::pipewire::state::natural_cmp(&a.name, &b.name));
+    ports
+}
+
+fn load_sidechain_sources() -> HashMap<String, String> {
+    load_config(
+        &config::SIDECHAINS,
+        "sidechains.json",
+        config::Layering::Replace,
+    )
+}
+
+fn save_sidechain_sources(sources: &HashMap<String, String>) {
+    let path = config_path("sidechains.json");
+    if let Err(e) = config::SIDECHAINS.save(&path, sources) {
+        log::error!("Failed to save sidechain sources to {:?}: {}", path, e);
+    }
+}
+
+fn load_conversion_policies() -> std::collections::BTreeMap<String, ConversionPolicy> {
+    let path = config_path("conversion.json");
+    match std::fs::read_to_string(&path) {
+        Ok(s) => serde_json::from_str(&s).unwrap_or_default(),
+        Err(_) => std::collections::BTreeMap::new(),
+    }
+}
+
+fn save_conversion_policies(policies: &std::collections::BTreeMap<String, ConversionPolicy>) {
+    let policies = policies.clone();
+    let path = config_path("conversion.json");
+    crate::control::write("conversion.json", move || {
+        if let Some(parent) = path.parent() {
+            let _ = std::fs::create_dir_all(parent);
+        }
+        let json = serde_json::to_string_pretty(&policies).unwrap_or_default();
+        if let Err(e) = std::fs::write(&path, &json) {
+            log::error!("Failed to save conversion policies to {:?}: {}", path, e);
+        }
+    });
+}
+
+/// The output and input port a saved filtered link joins, if both ends are
+/// in the graph.
+fn midi_link_filter_ends(
+    graph: &GraphSnapshot,
+    filter: &SavedMidiLinkFilter,
+) -> Option<(u32, u32)> {
+    saved_link_ends(
+        graph,
+        (&filter.source, &filter.output_port),
+        (&filter.target, &filter.input_port),
+    )
+}
+
+/// The output and input port named by node display name and port name, if
+/// both are in the graph.
+fn saved_link_ends(
+    graph: &GraphSnapshot,
+    (source, output_port): (&str, &str),
+    (target, input_port): (&str, &str),
+) -> Option<(u32, u32)> {
+    let find = |node_name: &str, port_name: &str, direction: PortDirection| {
+        graph
+            .nodes()
+            .iter()
+            .filter(|n| n.display_name() == node_name)
+            .find_map(|n| {
+                let ports = match direction {
+                    PortDirection::Output => graph.output_ports(n.id),
+                    PortDirection::Input => graph.input_ports(n.id),
+                };
+                ports
+                    .into_iter()
+                    .find(|p| p.name == port_name)
+                    .map(|p| p.id)
+            })
+    };
+    Some((
+        find(source, output_port, PortDirection::Output)?,
+        find(target, input_port, PortDirection::Input)?,
+    ))
+}
+
+/// The output and input port a saved link with a gain joins, if both ends
+/// are in the graph.
+fn link_gain_ends(graph: &GraphSnapshot, gain: &SavedLinkGain) -> Option<(u32, u32)> {
+    saved_link_ends(
+        graph,
+        (&gain.source, &gain.output_port),
+        (&gain.target, &gain.input_port),
+    )
+}
+
+/// Whether a node is one of the hidden nodes a filtered MIDI link or a link
+/// with a gain runs through, or a probe listens through.
+fn is_link_node(name: &str) -> bool {
+    link_filter::filter_id_of(name).is_some()
+        || link_gain::gain_id_of(name).is_some()
+        || probe::probe_id_of(name).is_some()
+}
+
+/// Queue the links that run `output` to `input` through the one-in,
+/// one-out `node`, and the removal of a direct link between them.
+fn route_through_node(
+    graph: &GraphSnapshot,
+    (output, input): (u32, u32),
+    node: u32,
+    ops: &mut Vec<LinkOp>,
+) {
+    let node_in = graph.input_ports(node);
+    let node_out = graph.output_ports(node);
+    let (Some(node_in), Some(node_out)) = (node_in.first(), node_out.first()) else {
+        return;
+    };
+    for (from, to) in [(output, node_in.id), (node_out.id, input)] {
+        if graph.find_link(from, to).is_none() {
+            ops.push(LinkOp::Connect {
+                output_port_id: from,
+                input_port_id: to,
+            });
+        }
+    }
+    if let Some(direct) = graph.find_link(output, input) {
+        ops.push(LinkOp::Disconnect { link_id: direct.id });
+    }
+}
+
+fn load_quick_params() -> HashMap<String, Vec<String>> {
+    let path = config_path("quick_params.json");
+    match std::fs::read_to_string(&path) {
+        Ok(s) => serde_json::from_str(&s).unwrap_or_default(),
+        Err(_) => HashMap::new(),
+    }
+}
+
+fn save_quick_params(pins: &HashMap<String, Vec<String>>) {
+    let pins = pins.clone();
+    let path = config_path("quick_params.json");
+    crate::control::write("quick_params.json", move || {
+        if let Some(parent) = path.parent() {
+            let _ = std::fs::create_dir_all(parent);
+        }
+        let json = serde_json::to_string_pretty(&pins).unwrap_or_default();
+        if let Err(e) = std::fs::write(&path, &json) {
+            log::error!("Failed to save quick parameters to {:?}: {}", path, e);
+        }
+    });
+}
+
+fn load_plugin_presets() -> HashMap<String, Vec<PluginPreset>> {
+    let path = config_path("plugin_presets.json");
+    match std::fs::read_to_string(&path) {
+        Ok(s) => serde_json::from_str(&s).unwrap_or_default(),
+        Err(_) => HashMap::new(),
+    }
+}
+
+fn save_plugin_presets(presets: &HashMap<String, Vec<PluginPreset>>) {
+    let presets = presets.clone();
+    let path = config_path("plugin_presets.json");
+    crate::control::write("plugin_presets.json", move || {
+        if let Some(parent) = path.parent() {
+            let _ = std::fs::create_dir_all(parent);
+        }
+        let json = serde_json::to_string_pretty(&presets).unwrap_or_default();
+        if let Err(e) = std::fs::write(&path, &json) {
+            log::error!("Failed to save plugin presets to {:?}: {}", path, e);
+        }
+    });
+}
+
+fn load_chain_presets() -> Vec<ChainPreset> {
+    load_config(
+        &config::CHAIN_PRESETS,
+        "chain_presets.json",
+        config::Layering::Replace,
+    )
+}
+
+fn save_chain_presets(chains: &[ChainPreset]) {
+    let chains = chains.to_vec();
+    let path = config_path("chain_presets.json");
+    crate::control::write("chain_presets.json", move || {
+        if let Err(e) = config::CHAIN_PRESETS.save(&path, &chains) {
+            log::error!("Failed to save chain presets to {:?}: {}", path, e);
+        }
+    });
+}
+
+fn load_session_index() -> session::SessionIndex {
+    let path = config_path("sessions.json");
+    match std::fs::read_to_string(&path) {
+        Ok(s) => serde_json::from_str(&s).unwrap_or_default(),
+        Err(_) => Default::default(),
+    }
+}
+
+fn save_session_index(index: &session::SessionIndex) {
+    let index = index.clone();
+    let path = config_path("sessions.json");
+    crate::control::write("sessions.json", move || {
+        if let Some(parent) = path.parent() {
+            let _ = std::fs::create_dir_all(parent);
+        }
+        let json = serde_json::to_string_pretty(&index).unwrap_or_default();
+        if let Err(e) = std::fs::write(&path, &json) {
+            log::error!("Failed to save sessions to {:?}: {}", path, e);
+        }
+    });
+}
+
+/// Put a session's or an imported configuration's files in place of the
+/// working ones, removing those it does not have. Runs after the config
+/// writes are flushed, just before restarting.
+fn write_config_files<'a>(
+    contents: impl Iterator<Item = (&'static str, Option<&'a serde_json::Value>)>,
+    from: &str,
+) {
+    for (file, contents) in contents {
+        let path = config_path(file);
+        let result = match contents {
+            Some(value) => std::fs::write(
+                &path,
+                serde_json::to_string_pretty(value).unwrap_or_default(),
+            ),
+            None => match std::fs::remove_file(&path) {
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+                other => other,
+            },
+        };
+        if let Err(e) = result {
+            log::error!("Failed to restore {:?} from {}: {}", path, from, e);
+        }
+    }
+}
+
+/// Check that each file of a configuration bundle loads as what ZestBay
+/// keeps in it, so a bad file is refused before anything is replaced.
+fn check_config_bundle(bundle: &ConfigBundle) -> Result<(), String> {
+    fn versioned<T: serde::de::DeserializeOwned>(
+        schema: &ConfigSchema,
+        value: &serde_json::Value,
+    ) -> Result<(), String> {
+        let (data, _) = schema
+            .upgrade(&value.to_string())
+            .map_err(|e| e.to_string())?;
+        serde_json::from_value::<T>(data)
+            .map(drop)
+            .map_err(|e| e.to_string())
+    }
+    fn ui_state<T: UiState>(value: &serde_json::Value) -> Result<(), String> {
+        let (data, _) = T::SCHEMA
+            .upgrade(&value.to_string())
+            .map_err(|e| e.to_string())?;
+        let state: T = serde_json::from_value(data).map_err(|e| e.to_string())?;
+        state.validate().map_err(|e| e.to_string())
+    }
+    fn bare<T: serde::de::DeserializeOwned>(value: &serde_json::Value) -> Result<(), String> {
+        serde_json::from_value::<T>(value.clone())
+            .map(drop)
+            .map_err(|e| e.to_string())
     }
 
-    fn unique_display_name(&self, base_name: &str) -> String {
-        let existing: Vec<String> = if let Some(ref mgr) = self.rust().plugin_manager {
-            mgr.active_instances()
-                .values()
-                .map(|info| info.display_name.clone())
-                .collect()
-        } else {
-            Vec::new()
+    for (file, value) in bundle.contents() {
+        let Some(value) = value else {
+            continue;
         };
+        let checked = match file {
+            "plugins.json" => versioned::<Vec<SavedPlugin>>(&config::PLUGINS, value),
+            "rules.json" => {
+                versioned::<Vec<crate::patchbay::rules::AutoConnectRule>>(&config::RULES, value)
+            }
+            "preferences.json" => versioned::<Preferences>(&config::PREFERENCES, value),
+            "layout.json" => ui_state::<NodeLayout>(value),
+            "hidden.json" => ui_state::<HiddenNodes>(value),
+            "pinned.json" => ui_state::<PinnedNodes>(value),
+            "viewport.json" => ui_state::<Viewport>(value),
+            "links.json" => versioned::<Vec<SavedPluginLink>>(&config::LINKS, value),
+            "sidechains.json" => versioned::<HashMap<String, String>>(&config::SIDECHAINS, value),
+            "link_gains.json" => versioned::<Vec<SavedLinkGain>>(&config::LINK_GAINS, value),
+            "midi_mappings.json" => {
+                versioned::<Vec<crate::midi::MidiCcMapping>>(&config::MIDI_MAPPINGS, value)
+            }
+            "plugin_presets.json" => bare::<HashMap<String, Vec<PluginPreset>>>(value),
+            "chain_presets.json" => versioned::<Vec<ChainPreset>>(&config::CHAIN_PRESETS, value),
+            "quick_params.json" => bare::<HashMap<String, Vec<String>>>(value),
+            "scene_transitions.json" => {
+                bare::<std::collections::BTreeMap<String, Vec<TransitionStep>>>(value)
+            }
+            _ => Ok(()),
+        };
+        checked.map_err(|e| format!("{}: {}", file, e))?;
+    }
+    Ok(())
+}
 
-        if !existing.iter().any(|n| n == base_name) {
-            return base_name.to_string();
-        }
+fn load_link_gains() -> Vec<SavedLinkGain> {
+    load_config(
+        &config::LINK_GAINS,
+        "link_gains.json",
+        config::Layering::Replace,
+    )
+}
 
-        for n in 2.. {
-            let candidate = format!("{} #{}", base_name, n);
-            if !existing.iter().any(|n| n == &candidate) {
-                return candidate;
-            }
+fn save_link_gains(gains: &[SavedLinkGain]) {
+    let gains = gains.to_vec();
+    let path = config_path("link_gains.json");
+    crate::control::write("link_gains.json", move || {
+        if let Err(e) = config::LINK_GAINS.save(&path, &gains) {
+            log::error!("Failed to save link gains to {:?}: {}", path, e);
         }
-        unreachable!()
+    });
+}
+
+fn load_midi_link_filters() -> Vec<SavedMidiLinkFilter> {
+    let path = config_path("midi_link_filters.json");
+    match std::fs::read_to_string(&path) {
+        Ok(s) => serde_json::from_str(&s).unwrap_or_default(),
+        Err(_) => Vec::new(),
     }
 }
 
-/// Convert days since Unix epoch to (year, month, day).
-fn days_to_ymd(days: i64) -> (i64, u32, u32) {
-    // Algorithm from http://howardhinnant.github.io/date_algorithms.html
-    let z = days + 719468;
-    let era = (if z >= 0 { z } else { z - 146096 }) / 146097;
-    let doe = (z - era * 146097) as u32; // day of era [0, 146096]
-    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365; // year of era [0, 399]
-    let y = yoe as i64 + era * 400;
-    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100); // day of year [0, 365]
-    let mp = (5 * doy + 2) / 153; // [0, 11]
-    let d = doy - (153 * mp + 2) / 5 + 1; // [1, 31]
-    let m = if mp < 10 { mp + 3 } else { mp - 9 }; // [1, 12]
-    let y = if m <= 2 { y + 1 } else { y };
-    (y, m, d)
+fn load_scene_transitions() -> std::collections::BTreeMap<String, Vec<TransitionStep>> {
+    let path = config_path("scene_transitions.json");
+    match std::fs::read_to_string(&path) {
+        Ok(s) => serde_json::from_str(&s).unwrap_or_default(),
+        Err(_) => std::collections::BTreeMap::new(),
+    }
 }
 
-fn config_path(filename: &str) -> PathBuf {
-    dirs::config_dir()
-        .unwrap_or_else(|| PathBuf::from("."))
-        .join("zestbay")
-        .join(filename)
+fn save_scene_transitions(transitions: &std::collections::BTreeMap<String, Vec<TransitionStep>>) {
+    let transitions = transitions.clone();
+    let path = config_path("scene_transitions.json");
+    crate::control::write("scene_transitions.json", move || {
+        if let Some(parent) = path.parent() {
+            let _ = std::fs::create_dir_all(parent);
+        }
+        let json = serde_json::to_string_pretty(&transitions).unwrap_or_default();
+        if let Err(e) = std::fs::write(&path, &json) {
+            log::error!("Failed to save scene transitions to {:?}: {}", path, e);
+        }
+    });
 }
 
-fn crash_marker_path() -> PathBuf {
-    config_path(".zestbay-restoring")
+fn save_midi_link_filters(filters: &[SavedMidiLinkFilter]) {
+    let filters = filters.to_vec();
+    let path = config_path("midi_link_filters.json");
+    crate::control::write("midi_link_filters.json", move || {
+        if let Some(parent) = path.parent() {
+            let _ = std::fs::create_dir_all(parent);
+        }
+        let json = serde_json::to_string_pretty(&filters).unwrap_or_default();
+        if let Err(e) = std::fs::write(&path, &json) {
+            log::error!("Failed to save MIDI link filters to {:?}: {}", path, e);
+        }
+    });
 }
 
-fn write_crash_marker(plugin_uris: &[String]) {
-    let path = crash_marker_path();
-    if let Some(parent) = path.parent() {
-        let _ = std::fs::create_dir_all(parent);
-    }
-    let content = plugin_uris.join("\n");
-    if let Err(e) = std::fs::write(&path, &content) {
-        log::error!("Failed to write crash marker: {}", e);
+fn load_clock_routing() -> clock::ClockRouting {
+    let path = config_path("clock.json");
+    match std::fs::read_to_string(&path) {
+        Ok(s) => serde_json::from_str(&s).unwrap_or_default(),
+        Err(_) => clock::ClockRouting::default(),
     }
 }
 
-fn remove_crash_marker() {
-    let path = crash_marker_path();
-    let _ = std::fs::remove_file(&path);
+fn save_clock_routing(routing: &clock::ClockRouting) {
+    let routing = routing.clone();
+    let path = config_path("clock.json");
+    crate::control::write("clock.json", move || {
+        if let Some(parent) = path.parent() {
+            let _ = std::fs::create_dir_all(parent);
+        }
+        let json = serde_json::to_string_pretty(&routing).unwrap_or_default();
+        if let Err(e) = std::fs::write(&path, &json) {
+            log::error!("Failed to save MIDI clock routing to {:?}: {}", path, e);
+        }
+    });
 }
 
-fn has_crash_marker() -> bool {
-    crash_marker_path().exists()
+fn load_stereo_pairs() -> std::collections::BTreeMap<String, pairs::PairOverrides> {
+    let path = config_path("stereo_pairs.json");
+    match std::fs::read_to_string(&path) {
+        Ok(s) => serde_json::from_str(&s).unwrap_or_default(),
+        Err(_) => std::collections::BTreeMap::new(),
+    }
 }
 
-fn read_crash_marker() -> Vec<String> {
-    let path = crash_marker_path();
+fn load_channel_overrides() -> ChannelOverrides {
+    let path = config_path("port_channels.json");
     match std::fs::read_to_string(&path) {
-        Ok(s) => s.lines().filter(|l| !l.is_empty()).map(String::from).collect(),
-        Err(_) => Vec::new(),
+        Ok(s) => serde_json::from_str(&s).unwrap_or_default(),
+        Err(_) => ChannelOverrides::default(),
     }
 }
 
-fn known_good_plugins_path() -> PathBuf {
-    config_path("plugins.known_good.json")
+fn save_channel_overrides(overrides: &ChannelOverrides) {
+    let overrides = overrides.clone();
+    let path = config_path("port_channels.json");
+    crate::control::write("port_channels.json", move || {
+        if let Some(parent) = path.parent() {
+            let _ = std::fs::create_dir_all(parent);
+        }
+        let json = serde_json::to_string_pretty(&overrides).unwrap_or_default();
+        if let Err(e) = std::fs::write(&path, &json) {
+            log::error!("Failed to save channel overrides to {:?}: {}", path, e);
+        }
+    });
 }
 
-fn save_known_good_plugins() {
-    let src = config_path("plugins.json");
-    let dst = known_good_plugins_path();
-    if src.exists() {
-        if let Err(e) = std::fs::copy(&src, &dst) {
-            log::error!("Failed to save known-good plugins snapshot: {}", e);
-        } else {
-            log::info!("Saved known-good plugins snapshot to {:?}", dst);
+fn save_stereo_pairs(overrides: &std::collections::BTreeMap<String, pairs::PairOverrides>) {
+    let overrides: std::collections::BTreeMap<_, _> = overrides
+        .iter()
+        .filter(|(_, o)| !o.is_empty())
+        .map(|(k, o)| (k.clone(), o.clone()))
+        .collect();
+    let path = config_path("stereo_pairs.json");
+    crate::control::write("stereo_pairs.json", move || {
+        if let Some(parent) = path.parent() {
+            let _ = std::fs::create_dir_all(parent);
         }
+        let json = serde_json::to_string_pretty(&overrides).unwrap_or_default();
+        if let Err(e) = std::fs::write(&path, &json) {
+            log::error!("Failed to save stereo pairs to {:?}: {}", path, e);
+        }
+    });
+}
+
+fn load_hooks() -> Vec<Hook> {
+    load_config(&config::HOOKS, "hooks.json", config::Layering::Replace)
+}
+
+fn save_hooks(hooks: &[Hook]) {
+    let path = config_path("hooks.json");
+    if let Err(e) = config::HOOKS.save(&path, hooks) {
+        log::error!("Failed to save hooks to {:?}: {}", path, e);
     }
 }
 
-fn has_known_good_plugins() -> bool {
-    known_good_plugins_path().exists()
+fn load_connection_history() -> ConnectionHistory {
+    let path = config_path("connection_history.json");
+    match std::fs::read_to_string(&path) {
+        Ok(s) => serde_json::from_str(&s).unwrap_or_default(),
+        Err(_) => ConnectionHistory::default(),
+    }
 }
 
-fn restore_known_good_plugins() -> bool {
-    let src = known_good_plugins_path();
-    let dst = config_path("plugins.json");
-    if !src.exists() {
-        log::warn!("No known-good plugins snapshot to restore");
-        return false;
+fn save_connection_history(history: &ConnectionHistory) {
+    let path = config_path("connection_history.json");
+    if let Some(parent) = path.parent() {
+        let _ = std::fs::create_dir_all(parent);
     }
-    match std::fs::copy(&src, &dst) {
-        Ok(_) => {
-            log::info!("Restored plugins.json from known-good snapshot");
-            true
-        }
-        Err(e) => {
-            log::error!("Failed to restore known-good plugins: {}", e);
-            false
-        }
+    let json = serde_json::to_string_pretty(history).unwrap_or_default();
+    if let Err(e) = std::fs::write(&path, &json) {
+        log::error!("Failed to save connection history to {:?}: {}", path, e);
     }
 }
 
-#[derive(serde::Serialize, serde::Deserialize, Clone)]
-struct SavedPlugin {
-    #[serde(default)]
-    stable_id: String,
-    uri: String,
-    display_name: String,
-    #[serde(default)]
-    bypassed: bool,
-    #[serde(default)]
-    parameters: Vec<SavedPluginParam>,
-    /// "LV2", "CLAP", or "VST3".  Defaults to "LV2" for backwards compat.
-    #[serde(default = "default_lv2_format_str")]
-    format: String,
-    #[serde(default, skip_serializing_if = "Vec::is_empty")]
-    lv2_state: Vec<crate::lv2::state::StateEntry>,
+fn load_node_ids() -> NodeIdentities {
+    load_config(
+        &config::NODE_IDS,
+        "node_ids.json",
+        config::Layering::Replace,
+    )
 }
 
-#[derive(serde::Serialize, serde::Deserialize, Clone)]
-struct SavedPluginParam {
-    port_index: usize,
-    symbol: String,
-    value: f32,
+fn save_node_ids(node_ids: &NodeIdentities) {
+    let node_ids = node_ids.clone();
+    let path = config_path("node_ids.json");
+    crate::control::write("node_ids.json", move || {
+        if let Err(e) = config::NODE_IDS.save(&path, &node_ids) {
+            log::error!("Failed to save node identities to {:?}: {}", path, e);
+        }
+    });
 }
 
-#[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
-struct SavedPluginLink {
-    output_node_name: String,
-    output_port_name: String,
-    input_node_name: String,
-    input_port_name: String,
+fn load_ui_windows() -> SavedUiWindows {
+    load_config(
+        &config::PLUGIN_WINDOWS,
+        "plugin_windows.json",
+        config::Layering::Replace,
+    )
 }
 
-fn default_lv2_format_str() -> String {
-    "LV2".to_string()
+fn save_ui_windows(windows: &SavedUiWindows) {
+    let windows = windows.clone();
+    let path = config_path("plugin_windows.json");
+    crate::control::write("plugin_windows.json", move || {
+        if let Err(e) = config::PLUGIN_WINDOWS.save(&path, &windows) {
+            log::error!("Failed to save plugin UI windows to {:?}: {}", path, e);
+        }
+    });
 }
 
-fn load_saved_plugins() -> Vec<SavedPlugin> {
-    let path = config_path("plugins.json");
+fn load_plugin_cpu_history() -> CpuHistory {
+    let path = config_path("plugin_cpu_history.json");
     match std::fs::read_to_string(&path) {
         Ok(s) => serde_json::from_str(&s).unwrap_or_default(),
-        Err(_) => Vec::new(),
+        Err(_) => CpuHistory::default(),
     }
 }
 
-fn persist_active_plugins(plugin_manager: Option<&PluginManager>) {
-    if crate::PLUGINS_FROZEN.load(std::sync::atomic::Ordering::SeqCst) {
-        log::info!("persist_active_plugins: skipped (plugins frozen in safe mode)");
-        return;
-    }
-    let mut plugins: Vec<SavedPlugin> = if let Some(mgr) = plugin_manager {
-        mgr.active_instances()
-            .values()
-            .map(|info| {
-                let params: Vec<SavedPluginParam> = info
-                    .parameters
-                    .iter()
-                    .map(|p| SavedPluginParam {
-                        port_index: p.port_index,
-                        symbol: p.symbol.clone(),
-                        value: p.value,
-                    })
-                    .collect();
-                SavedPlugin {
-                    stable_id: info.stable_id.clone(),
-                    uri: info.plugin_uri.clone(),
-                    display_name: info.display_name.clone(),
-                    bypassed: info.bypassed,
-                    parameters: params,
-                    format: info.format.as_str().to_string(),
-                    lv2_state: info.lv2_state.clone(),
-                }
-            })
-            .collect()
-    } else {
-        Vec::new()
-    };
-    plugins.sort_by(|a, b| a.stable_id.cmp(&b.stable_id));
-    let path = config_path("plugins.json");
+/// Save the CPU history of plugins that are still in the session.
+fn save_plugin_cpu_history(rust: &AppControllerRust) {
+    let mut history = rust.plugin_cpu_history.clone();
+    history.retain(|sid| {
+        rust.plugin_manager
+            .as_ref()
+            .is_some_and(|mgr| mgr.find_by_stable_id(sid).is_some())
+            || rust.dormant_plugins.iter().any(|sp| sp.stable_id == sid)
+    });
+    let path = config_path("plugin_cpu_history.json");
     if let Some(parent) = path.parent() {
         let _ = std::fs::create_dir_all(parent);
     }
-    let json = serde_json::to_string_pretty(&plugins).unwrap_or_default();
+    let json = serde_json::to_string(&history).unwrap_or_default();
     if let Err(e) = std::fs::write(&path, &json) {
-        log::error!("Failed to save plugins to {:?}: {}", path, e);
-    } else {
-        log::debug!("persist_active_plugins: {} plugins written", plugins.len());
+        log::error!("Failed to save plugin CPU history to {:?}: {}", path, e);
     }
 }
 
-fn load_saved_links() -> Vec<SavedPluginLink> {
-    let path = config_path("links.json");
-    match std::fs::read_to_string(&path) {
-        Ok(s) => serde_json::from_str(&s).unwrap_or_default(),
-        Err(_) => Vec::new(),
+fn load_ui_scales() -> std::collections::BTreeMap<String, f32> {
+    load_config(
+        &config::UI_SCALE,
+        "ui_scale.json",
+        config::Layering::Replace,
+    )
+}
+
+fn save_ui_scales(scales: &std::collections::BTreeMap<String, f32>) {
+    let path = config_path("ui_scale.json");
+    if let Err(e) = config::UI_SCALE.save(&path, scales) {
+        log::error!("Failed to save plugin UI scales to {:?}: {}", path, e);
     }
 }
 
-fn load_midi_mappings() -> Vec<crate::midi::MidiCcMapping> {
-    let path = config_path("midi_mappings.json");
-    match std::fs::read_to_string(&path) {
-        Ok(s) => serde_json::from_str(&s).unwrap_or_default(),
-        Err(_) => Vec::new(),
+fn load_isolated_ui_plugins() -> std::collections::BTreeSet<String> {
+    load_config(
+        &config::UI_ISOLATION,
+        "ui_isolation.json",
+        config::Layering::Replace,
+    )
+}
+
+fn save_isolated_ui_plugins(uris: &std::collections::BTreeSet<String>) {
+    let path = config_path("ui_isolation.json");
+    if let Err(e) = config::UI_ISOLATION.save(&path, uris) {
+        log::error!("Failed to save UI isolation settings to {:?}: {}", path, e);
+    }
+}
+
+/// Parameter values of a fresh instance of `plugin`: every control input
+/// at its default.
+fn initial_parameters(plugin: &crate::plugin::PluginInfo) -> Vec<crate::lv2::Lv2ParameterValue> {
+    plugin
+        .ports
+        .iter()
+        .filter(|port| port.port_type == crate::lv2::Lv2PortType::ControlInput)
+        .map(|port| crate::lv2::Lv2ParameterValue {
+            port_index: port.index,
+            symbol: port.symbol.clone(),
+            name: port.name.clone(),
+            value: port.default_value,
+            min: port.min_value,
+            max: port.max_value,
+            default: port.default_value,
+            is_toggle: port.is_toggle,
+        })
+        .collect()
+}
+
+/// Directories searched for PipeWire filter-chain configs to import.
+fn filter_chain_config_dirs() -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    if let Some(config) = dirs::config_dir() {
+        dirs.push(config.join("pipewire").join("filter-chain.conf.d"));
+        dirs.push(config.join("pipewire").join("pipewire.conf.d"));
     }
+    dirs.push(PathBuf::from("/etc/pipewire/filter-chain.conf.d"));
+    dirs.push(PathBuf::from("/usr/share/pipewire/filter-chain"));
+    dirs.push(config_path("exports"));
+    dirs
+}
+
+/// Write an exported filter-chain config as `exports/<name>.conf`.
+fn save_chain_export(name: &str, config: &str) -> Result<PathBuf, String> {
+    save_config_export("exports", name, "conf", config)
+}
+
+/// Write `contents` as `<dir>/<name>.<extension>` in the config directory,
+/// with `name` reduced to characters safe in a file name.
+fn save_config_export(
+    dir: &str,
+    name: &str,
+    extension: &str,
+    contents: &str,
+) -> Result<PathBuf, String> {
+    let file_name: String = name
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    let path = config_path(dir).join(format!("{}.{}", file_name, extension));
+    if let Some(parent) = path.parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+    std::fs::write(&path, contents).map_err(|e| format!("failed to write {:?}: {}", path, e))?;
+    Ok(path)
+}
+
+fn read_chain_file(path: &std::path::Path) -> Result<ChainFile, String> {
+    let text =
+        std::fs::read_to_string(path).map_err(|e| format!("failed to read {:?}: {}", path, e))?;
+    ChainFile::parse(&text)
+}
+
+/// Application streams currently playing to and recording from a device, by
+/// application name, sorted and without duplicates.
+fn active_streams(nodes: &[Node], links: &[crate::pipewire::Link]) -> (Vec<String>, Vec<String>) {
+    let is_active = |node_id: u32| {
+        links
+            .iter()
+            .any(|l| l.active && (l.output_node_id == node_id || l.input_node_id == node_id))
+    };
+    let (mut playing, mut recording) = (Vec::new(), Vec::new());
+    for node in nodes
+        .iter()
+        .filter(|n| n.media_type == Some(crate::pipewire::MediaType::Audio) && is_active(n.id))
+    {
+        let name = node
+            .application_name
+            .clone()
+            .unwrap_or_else(|| node.display_name().to_string());
+        match node.node_type {
+            Some(NodeType::StreamOutput) => playing.push(name),
+            Some(NodeType::StreamInput) => recording.push(name),
+            _ => {}
+        }
+    }
+    for list in [&mut playing, &mut recording] {
+        list.sort();
+        list.dedup();
+    }
+    (playing, recording)
+}
+
+/// The node called `name`, by node name or, ignoring case, display name.
+fn node_named<'a>(nodes: &'a [Node], name: &str) -> Option<&'a Node> {
+    nodes
+        .iter()
+        .find(|n| n.name == name || n.display_name().eq_ignore_ascii_case(name))
+}
+
+/// The node id of the default audio sink, if it is in the graph.
+fn default_sink_id(graph: &GraphState) -> Option<u32> {
+    let name = graph.default_sink()?;
+    graph
+        .get_all_nodes()
+        .into_iter()
+        .find(|n| n.name == name && n.node_type == Some(NodeType::Sink))
+        .map(|n| n.id)
+}
+
+/// The name part of a rule backup's file stem, which is
+/// `YYYYMMDD_HHMMSS_OptionalName`.
+fn rule_backup_name(stem: &str) -> &str {
+    if stem.len() > 16 && stem.chars().nth(15) == Some('_') {
+        &stem[16..]
+    } else {
+        stem
+    }
+}
+
+/// The newest rule backup saved under `name`, which is how scenes are
+/// referred to from outside.
+fn find_rule_backup(name: &str) -> Option<String> {
+    let entries = std::fs::read_dir(config_path("rule_backups")).ok()?;
+    entries
+        .flatten()
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .filter(|filename| {
+            let Some(stem) = filename.strip_suffix(".json") else {
+                return false;
+            };
+            rule_backup_name(stem).eq_ignore_ascii_case(name) || stem == name
+        })
+        .max()
+}
+
+/// The names of the saved rule backups, which are the scenes.
+fn rule_backup_names() -> Vec<String> {
+    let Ok(entries) = std::fs::read_dir(config_path("rule_backups")) else {
+        return Vec::new();
+    };
+    let mut names: Vec<String> = entries
+        .flatten()
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .filter_map(|filename| {
+            let name = rule_backup_name(filename.strip_suffix(".json")?);
+            (!name.is_empty()).then(|| name.to_string())
+        })
+        .collect();
+    names.sort_by_key(|n| n.to_lowercase());
+    names.dedup_by(|a, b| a.eq_ignore_ascii_case(b));
+    names
+}
+
+fn load_midi_mappings() -> Vec<crate::midi::MidiCcMapping> {
+    load_config(
+        &config::MIDI_MAPPINGS,
+        "midi_mappings.json",
+        config::Layering::Replace,
+    )
 }
 
 fn persist_midi_mappings(mappings: &[crate::midi::MidiCcMapping]) {
+    let mappings = mappings.to_vec();
     let path = config_path("midi_mappings.json");
-    if let Some(parent) = path.parent() {
-        let _ = std::fs::create_dir_all(parent);
-    }
-    let json = serde_json::to_string_pretty(mappings).unwrap_or_default();
-    if let Err(e) = std::fs::write(&path, &json) {
-        log::error!("Failed to save MIDI mappings to {:?}: {}", path, e);
-    } else {
-        log::debug!("persist_midi_mappings: {} mappings written", mappings.len());
-    }
+    crate::control::write("midi_mappings.json", move || {
+        if let Err(e) = config::MIDI_MAPPINGS.save(&path, &mappings) {
+            log::error!("Failed to save MIDI mappings to {:?}: {}", path, e);
+        } else {
+            log::debug!("persist_midi_mappings: {} mappings written", mappings.len());
+        }
+    });
+}
+
+/// The name of one device on a bridge node, as the graph view labels it.
+fn bridge_device_name(graph: &GraphState, node: &Node, group: &str) -> String {
+    graph
+        .get_bridge_port_groups(node.id)
+        .remove(group)
+        .unwrap_or_else(|| group.to_string())
 }
 
 fn bridge_device_name_for_port(port: &Port) -> Option<String> {
@@ -3284,8 +12427,62 @@ fn bridge_device_name_for_port(port: &Port) -> Option<String> {
     })
 }
 
-fn build_persistable_links(graph: &GraphState) -> Vec<SavedPluginLink> {
+/// The port a saved link names: on the node with the saved `identity` when
+/// one is in the graph, otherwise on a node or bridge device with the saved
+/// display name. Links saved before identities existed only have the name.
+fn find_saved_link_port(
+    graph: &GraphState,
+    saved_ids: &HashMap<u32, String>,
+    identity: Option<&str>,
+    node_name: &str,
+    port_name: &str,
+    direction: PortDirection,
+) -> Option<u32> {
+    let on_node = |node_id: u32| {
+        graph
+            .get_ports_for_node(node_id)
+            .iter()
+            .find(|p| p.name == port_name && p.direction == direction)
+            .map(|p| p.id)
+    };
+    if let Some(identity) = identity
+        && let Some(id) = saved_ids
+            .iter()
+            .filter(|(_, saved)| saved.as_str() == identity)
+            .find_map(|(&node_id, _)| on_node(node_id))
+    {
+        return Some(id);
+    }
+
+    let all_nodes = graph.get_all_nodes();
+    if let Some(id) = all_nodes
+        .iter()
+        .filter(|n| n.display_name() == node_name)
+        .find_map(|n| on_node(n.id))
+    {
+        return Some(id);
+    }
+    // Bridge sub-nodes are saved under their device name (from port.alias).
+    for n in all_nodes.iter().filter(|n| n.is_bridge) {
+        for (group, device_name) in &graph.get_bridge_port_groups(n.id) {
+            if device_name != node_name {
+                continue;
+            }
+            if let Some(p) = graph
+                .get_ports_for_bridge_group(n.id, group)
+                .iter()
+                .find(|p| p.name == port_name && p.direction == direction)
+            {
+                return Some(p.id);
+            }
+        }
+    }
+    None
+}
+
+fn build_persistable_links(graph: &GraphState, node_ids: &NodeIdentities) -> Vec<SavedPluginLink> {
     let links = graph.get_all_links();
+    let saved_ids = node_ids.saved_ids(&graph.get_all_nodes());
     let mut saved_links = Vec::new();
 
     for link in &links {
@@ -3315,6 +12512,13 @@ fn build_persistable_links(graph: &GraphState) -> Vec<SavedPluginLink> {
         if !involves_lv2 && !involves_midi {
             continue;
         }
+        // Taps of a running probe.
+        if in_node
+            .as_ref()
+            .is_some_and(|n| probe::probe_id_of(&n.name).is_some())
+        {
+            continue;
+        }
 
         if let (Some(out_node), Some(in_node), Some(out_port), Some(in_port)) =
             (out_node, in_node, out_port, in_port)
@@ -3333,11 +12537,19 @@ fn build_persistable_links(graph: &GraphState) -> Vec<SavedPluginLink> {
             } else {
                 in_node.display_name().to_string()
             };
+            // Bridge devices share one node, so their identity says nothing.
+            let identity = |node: &Node| {
+                (!node.is_bridge)
+                    .then(|| saved_ids.get(&node.id).cloned())
+                    .flatten()
+            };
             saved_links.push(SavedPluginLink {
                 output_node_name: out_name,
                 output_port_name: out_port.name.clone(),
                 input_node_name: in_name,
                 input_port_name: in_port.name.clone(),
+                output_node_identity: identity(&out_node),
+                input_node_identity: identity(&in_node),
             });
         }
     }
@@ -3345,30 +12557,35 @@ fn build_persistable_links(graph: &GraphState) -> Vec<SavedPluginLink> {
     saved_links
 }
 
-fn persist_lv2_links(graph: Option<&Arc<GraphState>>) {
-    let links = if let Some(graph) = graph {
-        build_persistable_links(graph)
-    } else {
-        Vec::new()
-    };
+/// Write the plugin and MIDI links in the graph to `links.json`, keeping
+/// `dormant` links whose plugins are not running. The links are collected
+/// on the control thread, keyed by node identity as well as name.
+fn persist_lv2_links(
+    graph: Option<&Arc<GraphState>>,
+    dormant: &[SavedPluginLink],
+    node_ids: &NodeIdentities,
+) {
+    let graph = graph.cloned();
+    let dormant = dormant.to_vec();
+    let node_ids = node_ids.clone();
     let path = config_path("links.json");
-    if let Some(parent) = path.parent() {
-        let _ = std::fs::create_dir_all(parent);
-    }
-    let json = serde_json::to_string_pretty(&links).unwrap_or_default();
-    if let Err(e) = std::fs::write(&path, &json) {
-        log::error!("Failed to save links to {:?}: {}", path, e);
-    } else {
-        log::debug!("persist_lv2_links: {} links written", links.len());
-    }
+    crate::control::write("links.json", move || {
+        let mut links = if let Some(graph) = graph {
+            build_persistable_links(&graph, &node_ids)
+        } else {
+            Vec::new()
+        };
+        links.extend(dormant);
+        if let Err(e) = config::LINKS.save(&path, &links) {
+            log::error!("Failed to save links to {:?}: {}", path, e);
+        } else {
+            log::debug!("persist_lv2_links: {} links written", links.len());
+        }
+    });
 }
 
 fn load_rules() -> Vec<crate::patchbay::rules::AutoConnectRule> {
-    let path = config_path("rules.json");
-    match std::fs::read_to_string(&path) {
-        Ok(s) => serde_json::from_str(&s).unwrap_or_default(),
-        Err(_) => Vec::new(),
-    }
+    load_config(&config::RULES, "rules.json", config::Layering::Replace)
 }
 
 fn save_rules(patchbay: Option<&PatchbayManager>) {
@@ -3378,60 +12595,98 @@ fn save_rules(patchbay: Option<&PatchbayManager>) {
         Vec::new()
     };
     let path = config_path("rules.json");
-    if let Some(parent) = path.parent() {
-        let _ = std::fs::create_dir_all(parent);
-    }
-    let json = serde_json::to_string_pretty(&rules).unwrap_or_default();
-    if let Err(e) = std::fs::write(&path, &json) {
-        log::error!("Failed to save rules to {:?}: {}", path, e);
+    crate::control::write("rules.json", move || {
+        if let Err(e) = config::RULES.save(&path, &rules) {
+            log::error!("Failed to save rules to {:?}: {}", path, e);
+        } else {
+            log::debug!("save_rules: {} rules written", rules.len());
+        }
+    });
+}
+
+fn load_role_policies() -> Vec<crate::patchbay::roles::RolePolicy> {
+    load_config(&config::ROLES, "roles.json", config::Layering::Replace)
+}
+
+fn save_role_policies(patchbay: Option<&PatchbayManager>) {
+    let policies: Vec<crate::patchbay::roles::RolePolicy> = if let Some(mgr) = patchbay {
+        mgr.role_policies().to_vec()
     } else {
-        log::debug!("save_rules: {} rules written", rules.len());
-    }
+        Vec::new()
+    };
+    let path = config_path("roles.json");
+    crate::control::write("roles.json", move || {
+        if let Err(e) = config::ROLES.save(&path, &policies) {
+            log::error!("Failed to save role policies to {:?}: {}", path, e);
+        } else {
+            log::debug!("save_role_policies: {} policies written", policies.len());
+        }
+    });
 }
 
-fn parse_node_type(s: &str) -> Option<NodeType> {
-    match s {
-        "Sink" => Some(NodeType::Sink),
-        "Source" => Some(NodeType::Source),
-        "App Out" | "StreamOutput" => Some(NodeType::StreamOutput),
-        "App In" | "StreamInput" => Some(NodeType::StreamInput),
-        "Duplex" => Some(NodeType::Duplex),
-        "Plugin" | "Lv2Plugin" => Some(NodeType::Plugin),
-        _ => None,
-    }
+fn load_link_overrides() -> Vec<LinkOverride> {
+    load_config(
+        &config::LINK_OVERRIDES,
+        "link_overrides.json",
+        config::Layering::Replace,
+    )
+}
+
+/// Write the overrides that outlive a restart; ones scoped to a node's
+/// lifetime are left out.
+fn save_link_overrides(patchbay: Option<&PatchbayManager>) {
+    let overrides: Vec<LinkOverride> = patchbay
+        .map(|mgr| {
+            mgr.overrides()
+                .iter()
+                .filter(|o| o.is_persistent())
+                .cloned()
+                .collect()
+        })
+        .unwrap_or_default();
+    let path = config_path("link_overrides.json");
+    crate::control::write("link_overrides.json", move || {
+        if let Err(e) = config::LINK_OVERRIDES.save(&path, &overrides) {
+            log::error!("Failed to save link overrides to {:?}: {}", path, e);
+        } else {
+            log::debug!("save_link_overrides: {} overrides written", overrides.len());
+        }
+    });
 }
 
 fn node_to_json(
     n: &Node,
     plugin_manager: Option<&crate::plugin::manager::PluginManager>,
+    node_keys: &HashMap<u32, String>,
+    format: Option<NodeFormat>,
 ) -> serde_json::Value {
-    let type_str = match n.node_type {
-        Some(NodeType::Sink) => "Sink",
-        Some(NodeType::Source) => "Source",
-        Some(NodeType::StreamOutput) => "StreamOutput",
-        Some(NodeType::StreamInput) => "StreamInput",
-        Some(NodeType::Duplex) => "Duplex",
-        Some(NodeType::Plugin) => "Plugin",
-        None => "Unknown",
-    };
-    let media_str = match n.media_type {
-        Some(crate::pipewire::MediaType::Audio) => "Audio",
-        Some(crate::pipewire::MediaType::Video) => "Video",
-        Some(crate::pipewire::MediaType::Midi) => "Midi",
-        None => "Unknown",
-    };
-
     let mut val = serde_json::json!({
         "id": n.id,
         "name": n.display_name(),
-        "type": type_str,
-        "mediaType": media_str,
+        "type": NodeKind::from(n.node_type).repr,
+        "mediaType": MediaKind::from(n.media_type).repr,
         "isVirtual": n.is_virtual,
         "isJack": n.is_jack,
-        "layoutKey": layout_key(n, plugin_manager),
+        "layoutKey": layout_key(n, plugin_manager, node_keys),
         "ready": n.ready,
     });
 
+    // Lets the UI carry entries saved under the old key over to the new one
+    if node_keys.contains_key(&n.id) {
+        val["legacyKey"] = serde_json::json!(type_name_key(n, plugin_manager));
+    }
+
+    // Negotiated audio format, for "48kHz / 2ch / F32" badges
+    if let Some(format) = format.filter(|f| *f != NodeFormat::default()) {
+        val["audioFormat"] = serde_json::json!({
+            "rate": format.rate,
+            "channels": format.channels,
+            "sampleFormat": format.sample_format,
+            "quantum": format.quantum,
+            "label": format.label(),
+        });
+    }
+
     // Enrich plugin nodes with format and hasUi info
     if n.node_type == Some(NodeType::Plugin) {
         if let Some(mgr) = plugin_manager {
@@ -3450,6 +12705,10 @@ fn node_to_json(
                 val["pluginFormat"] = serde_json::json!(format_str);
                 val["pluginHasUi"] = serde_json::json!(has_ui);
                 val["pluginBypassed"] = serde_json::json!(instance.bypassed);
+                val["stableId"] = serde_json::json!(instance.stable_id);
+                if let Some(b_selected) = ab_switch_selects_b(instance) {
+                    val["abSource"] = serde_json::json!(if b_selected { "B" } else { "A" });
+                }
             }
         }
     }
@@ -3457,9 +12716,121 @@ fn node_to_json(
     val
 }
 
+/// Whether an A/B switch instance has its B input selected; `None` for
+/// any other plugin.
+fn ab_switch_selects_b(info: &crate::plugin::PluginInstanceInfo) -> Option<bool> {
+    if info.plugin_uri != crate::builtin::AB_SWITCH_URI {
+        return None;
+    }
+    let source = info
+        .parameters
+        .iter()
+        .find(|p| p.port_index == AB_SWITCH_PORT_SOURCE)
+        .map_or(0.0, |p| p.value);
+    Some(source >= 0.5)
+}
+
+/// A link as the graph view draws it, with the hidden node it may run
+/// through.
+struct LogicalLink {
+    link: crate::pipewire::Link,
+    /// What it drops, when it runs through a MIDI filter node.
+    midi_filter: Option<MidiLinkOptions>,
+    /// Its gain in dB, when it runs through a gain node.
+    gain_db: Option<f32>,
+}
+
+/// Format conversion PipeWire inserts on a link, from the formats its two
+/// nodes negotiated.
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct LinkConversion {
+    resampled: bool,
+    output_rate: Option<u32>,
+    input_rate: Option<u32>,
+    output_format: Option<String>,
+    input_format: Option<String>,
+    /// Short description such as "44.1kHz → 48kHz".
+    label: String,
+}
+
+/// The id of the rule `link` belongs to, and whether that rule is enabled.
+fn link_rule(
+    patchbay: &PatchbayManager,
+    graph: &GraphState,
+    link: &crate::pipewire::Link,
+) -> Option<(String, bool)> {
+    let source = graph.get_node(link.output_node_id)?;
+    let target = graph.get_node(link.input_node_id)?;
+    let output_port = graph.get_port(link.output_port_id)?;
+    let input_port = graph.get_port(link.input_port_id)?;
+    patchbay
+        .owning_rule(&source, &target, &output_port, &input_port)
+        .map(|rule| (rule.id.clone(), rule.enabled))
+}
+
+/// "enabled" when any rule routes `node`, "disabled" when it is the source
+/// of rules that are all off, `None` when no rule names it.
+fn rule_state(patchbay: &PatchbayManager, node: &Node) -> Option<&'static str> {
+    let mut any = false;
+    for rule in patchbay.rules_from(node) {
+        if rule.enabled {
+            return Some("enabled");
+        }
+        any = true;
+    }
+    any.then_some("disabled")
+}
+
+fn link_conversion(graph: &GraphState, link: &crate::pipewire::Link) -> Option<LinkConversion> {
+    let output = graph.get_node_format(link.output_node_id)?;
+    let input = graph.get_node_format(link.input_node_id)?;
+    let resampled = output.resamples_to(&input);
+    let converted = output.converts_to(&input);
+    if !resampled && !converted {
+        return None;
+    }
+
+    let mut changes = Vec::new();
+    if let (true, Some(from), Some(to)) = (resampled, output.rate, input.rate) {
+        changes.push(format!(
+            "{} → {}",
+            crate::pipewire::format_rate(from),
+            crate::pipewire::format_rate(to)
+        ));
+    }
+    if let (true, Some(from), Some(to)) = (converted, &output.sample_format, &input.sample_format) {
+        changes.push(format!("{} → {}", from, to));
+    }
+    Some(LinkConversion {
+        resampled,
+        output_rate: output.rate,
+        input_rate: input.rate,
+        output_format: output.sample_format,
+        input_format: input.sample_format,
+        label: changes.join(", "),
+    })
+}
+
+/// Key for a node's saved layout position, hidden and pinned state:
+/// `Node:<uuid>` for nodes with a persistent identity, otherwise
+/// `Type:DisplayName`.
 fn layout_key(
     node: &Node,
     plugin_manager: Option<&crate::plugin::manager::PluginManager>,
+    node_keys: &HashMap<u32, String>,
+) -> String {
+    match node_keys.get(&node.id) {
+        Some(id) => format!("Node:{}", id),
+        None => type_name_key(node, plugin_manager),
+    }
+}
+
+/// `Type:DisplayName` key, used by plugins and by every node before
+/// persistent identities existed.
+fn type_name_key(
+    node: &Node,
+    plugin_manager: Option<&crate::plugin::manager::PluginManager>,
 ) -> String {
     let prefix = match node.node_type {
         Some(NodeType::Sink) => "Sink".to_string(),
@@ -3484,6 +12855,17 @@ fn layout_key(
     format!("{}:{}", prefix, node.display_name())
 }
 
+/// The node display name a layout key stands for, which is what the default
+/// target is matched on.
+fn layout_key_display_name(key: &str, node_ids: &NodeIdentities) -> String {
+    if let Some(id) = key.strip_prefix("Node:")
+        && let Some(identity) = node_ids.get(id)
+    {
+        return identity.name.clone();
+    }
+    key.split_once(':').map_or(key, |(_, name)| name).to_string()
+}
+
 fn node_type_str(n: &Node) -> &'static str {
     match n.node_type {
         Some(NodeType::Sink) => "Sink",
@@ -3497,21 +12879,17 @@ fn node_type_str(n: &Node) -> &'static str {
 }
 
 fn get_node_size(
-    node_sizes: &std::collections::HashMap<String, Vec<f64>>,
+    node_sizes: &std::collections::BTreeMap<String, [f64; 2]>,
     layout_key: &str,
     node_id: u32,
 ) -> (f64, f64) {
-    if let Some(size) = node_sizes.get(layout_key) {
-        if size.len() >= 2 {
-            return (size[0], size[1]);
-        }
-    }
-    if let Some(size) = node_sizes.get(&node_id.to_string()) {
-        if size.len() >= 2 {
-            return (size[0], size[1]);
-        }
+    match node_sizes
+        .get(layout_key)
+        .or_else(|| node_sizes.get(&node_id.to_string()))
+    {
+        Some(&[w, h]) => (w, h),
+        None => (180.0, 80.0),
     }
-    (180.0, 80.0)
 }
 
 fn add_ports_to_layout(
@@ -3536,11 +12914,48 @@ fn add_ports_to_layout(
     }
 }
 
+/// An action run on a node from the graph view, e.g. on double-click.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum NodeAction {
+    OpenUi,
+    Params,
+    Rename,
+    Hide,
+    None,
+}
+
+impl NodeAction {
+    fn applies_to(self, node_type: NodeType) -> bool {
+        match self {
+            NodeAction::OpenUi | NodeAction::Params | NodeAction::Rename => {
+                node_type == NodeType::Plugin
+            }
+            NodeAction::Hide | NodeAction::None => true,
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            NodeAction::OpenUi => "open_ui",
+            NodeAction::Params => "params",
+            NodeAction::Rename => "rename",
+            NodeAction::Hide => "hide",
+            NodeAction::None => "",
+        }
+    }
+}
+
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 struct Preferences {
     #[serde(default = "Preferences::default_rule_settle_ms")]
     pub rule_settle_ms: u64,
 
+    /// Graph changes per second that pause rules and link saving; 0 never
+    /// does.
+    #[serde(default = "Preferences::default_storm_events_per_sec")]
+    pub storm_events_per_sec: u32,
+
     #[serde(default = "Preferences::default_params_persist_ms")]
     pub params_persist_ms: u64,
 
@@ -3553,23 +12968,211 @@ struct Preferences {
     #[serde(default = "Preferences::default_auto_learn_rules")]
     pub auto_learn_rules: bool,
 
+    /// Link a new stream a learned rule names without waiting for the
+    /// graph to settle.
+    #[serde(default = "Preferences::default_fast_route_streams")]
+    pub fast_route_streams: bool,
+
+    /// What disconnecting a rule-owned link by hand does: `off` unlearns
+    /// it, `node`, `timed` and `permanent` record an override instead.
+    #[serde(default = "Preferences::default_manual_override_scope")]
+    pub manual_override_scope: String,
+
+    /// Length of a `timed` override.
+    #[serde(default = "Preferences::default_manual_override_minutes")]
+    pub manual_override_minutes: u32,
+
+    #[serde(default = "Preferences::default_bypass_level_match")]
+    pub bypass_level_match: bool,
+
+    /// Longest a removed or bypassed plugin's tail may ring out; 0 cuts
+    /// plugins off at once.
+    #[serde(default = "Preferences::default_plugin_tail_seconds")]
+    pub plugin_tail_seconds: u32,
+
+    /// Length of the crossfade when ZestBay switches the default output;
+    /// 0 switches at once.
+    #[serde(default = "Preferences::default_output_crossfade_ms")]
+    pub output_crossfade_ms: u32,
+
     #[serde(default = "Preferences::default_start_minimized")]
     pub start_minimized: bool,
 
     #[serde(default = "Preferences::default_close_to_tray")]
     pub close_to_tray: bool,
 
+    /// PipeWire instance to manage: a socket name or path. Empty uses
+    /// `PIPEWIRE_REMOTE` or the default instance.
+    #[serde(default)]
+    pub pipewire_remote: String,
+
+    /// Second PipeWire instance shown beside the main graph; empty hides it.
+    #[serde(default)]
+    pub compare_remote: String,
+
+    /// `host:port` of another ZestBay's remote API to manage from here,
+    /// such as one run with `--headless`; empty hides it.
+    #[serde(default)]
+    pub remote_instance: String,
+
+    /// That instance's remote API token.
+    #[serde(default)]
+    pub remote_instance_token: String,
+
+    /// PEM certificate to trust for a `wss://` instance with a self-made
+    /// one; empty trusts the usual certificate authorities.
+    #[serde(default)]
+    pub remote_instance_cert: String,
+
+    /// `auto`, `status_notifier`, `xembed` or `none`; see [`TrayBackend`].
+    #[serde(default = "Preferences::default_tray_mode")]
+    pub tray_mode: String,
+
+    /// How node lists are sorted: a [`NodeOrder`] name.
+    #[serde(default = "Preferences::default_node_order")]
+    pub node_order: String,
+
+    /// Plugin overruns per minute at which the tray icon warns; 0 is off.
+    #[serde(default = "Preferences::default_xrun_warning_threshold")]
+    pub xrun_warning_threshold: u64,
+
+    /// DSP load, in percent of the buffer time, above which a plugin raises
+    /// an alert; 0 is off.
+    #[serde(default = "Preferences::default_cpu_alert_percent")]
+    pub cpu_alert_percent: u32,
+
+    /// How long a plugin must stay over `cpu_alert_percent` before the alert.
+    #[serde(default = "Preferences::default_cpu_alert_seconds")]
+    pub cpu_alert_seconds: u64,
+
+    /// Log every command sent to PipeWire, and what sent it, to
+    /// `pw_commands.log`.
+    #[serde(default = "Preferences::default_pw_command_log")]
+    pub pw_command_log: bool,
+
+    /// Look up the latest GitHub release at startup.
+    #[serde(default = "Preferences::default_check_for_updates")]
+    pub check_for_updates: bool,
+
+    #[serde(default = "Preferences::default_dock_plugin_uis")]
+    pub dock_plugin_uis: bool,
+
+    /// Formats in the order their version of a plugin is listed, such as
+    /// `clap,lv2,vst3`; see [`parse_format_order`].
+    #[serde(default = "Preferences::default_plugin_format_order")]
+    pub plugin_format_order: String,
+
+    /// List every format a plugin is installed in instead of one version.
+    #[serde(default)]
+    pub show_all_plugin_versions: bool,
+
+    #[serde(default = "Preferences::default_reopen_plugin_uis")]
+    pub reopen_plugin_uis: bool,
+
+    /// The monitor plugin UIs open on until moved elsewhere; empty follows
+    /// the main window.
+    #[serde(default)]
+    pub plugin_ui_monitor: String,
+
+    /// Knobs on a plugin's node when none are pinned; 0 shows only pinned
+    /// ones.
+    #[serde(default = "Preferences::default_quick_params_count")]
+    pub quick_params_count: u32,
+
+    #[serde(default = "Preferences::default_jack_dbus_patchbay")]
+    pub jack_dbus_patchbay: bool,
+
+    /// Serve `org.zestbay.Patchbay` on the session bus.
+    #[serde(default)]
+    pub patchbay_dbus: bool,
+
+    #[serde(default = "Preferences::default_mqtt_enabled")]
+    pub mqtt_enabled: bool,
+
+    /// `host[:port]` of the MQTT broker.
+    #[serde(default = "Preferences::default_mqtt_broker")]
+    pub mqtt_broker: String,
+
+    /// Base topic status is published under and commands are read from.
+    #[serde(default = "Preferences::default_mqtt_topic")]
+    pub mqtt_topic: String,
+
+    /// Broker login; none is sent while the username is empty.
+    #[serde(default)]
+    pub mqtt_username: String,
+    #[serde(default)]
+    pub mqtt_password: String,
+
+    /// Connect to the broker over TLS, trusting the PEM CA in
+    /// `mqtt_tls_ca` or, when that is empty, the system roots.
+    #[serde(default)]
+    pub mqtt_tls: bool,
+    #[serde(default)]
+    pub mqtt_tls_ca: String,
+
+    /// What commands received over MQTT may do; read-only ignores them.
+    #[serde(default)]
+    pub mqtt_scope: ApiScope,
+
+    #[serde(default = "Preferences::default_remote_api_enabled")]
+    pub remote_api_enabled: bool,
+
+    /// `host:port` the remote API listens on.
+    #[serde(default = "Preferences::default_remote_api_bind")]
+    pub remote_api_bind: String,
+
+    /// Access token remote clients must send; generated on first use. It
+    /// may do everything.
+    #[serde(default)]
+    pub remote_api_token: String,
+
+    /// Further tokens handed out with narrower scopes.
+    #[serde(default)]
+    pub remote_api_tokens: Vec<ApiToken>,
+
+    /// PEM certificate chain and private key; with both set the remote API
+    /// only accepts TLS connections.
+    #[serde(default)]
+    pub remote_api_tls_cert: String,
+    #[serde(default)]
+    pub remote_api_tls_key: String,
+
+    /// The origin browser clients may call the remote API from, `*` for
+    /// any; empty sends no CORS headers, so only same-origin pages and
+    /// non-browser clients get through.
+    #[serde(default)]
+    pub remote_api_cors_origin: String,
+
+    /// What double-clicking a node does, by node type. Ordered so the
+    /// preferences JSON is the same on every call.
+    #[serde(default = "Preferences::default_double_click_actions")]
+    pub double_click_actions: std::collections::BTreeMap<NodeType, NodeAction>,
+
     #[serde(default = "Preferences::default_pw_tick_interval_ms")]
     pub pw_tick_interval_ms: u64,
 
     #[serde(default = "Preferences::default_pw_operation_cooldown_ms")]
     pub pw_operation_cooldown_ms: u64,
+
+    #[serde(default = "Preferences::default_pw_connect_retries")]
+    pub pw_connect_retries: u32,
+
+    /// `powersave`, `balanced` or `performance`.
+    #[serde(default = "Preferences::default_performance_profile")]
+    pub performance_profile: String,
+
+    /// The profile switched to on battery; empty stays on the chosen one.
+    #[serde(default = "Preferences::default_battery_profile")]
+    pub battery_profile: String,
 }
 
 impl Preferences {
     fn default_rule_settle_ms() -> u64 {
         500
     }
+    fn default_storm_events_per_sec() -> u32 {
+        crate::patchbay::storm::DEFAULT_STORM_EVENTS_PER_SEC
+    }
     fn default_params_persist_ms() -> u64 {
         1000
     }
@@ -3582,42 +13185,194 @@ impl Preferences {
     fn default_auto_learn_rules() -> bool {
         true
     }
+    fn default_fast_route_streams() -> bool {
+        true
+    }
+    fn default_manual_override_scope() -> String {
+        "off".into()
+    }
+    fn default_manual_override_minutes() -> u32 {
+        30
+    }
+
+    fn override_mode(&self) -> OverrideMode {
+        OverrideMode::from_pref(&self.manual_override_scope, self.manual_override_minutes)
+    }
+    fn default_bypass_level_match() -> bool {
+        false
+    }
+    fn default_plugin_tail_seconds() -> u32 {
+        crate::plugin::tail::DEFAULT_TAIL_SECONDS
+    }
+    fn default_output_crossfade_ms() -> u32 {
+        crate::patchbay::crossfade::DEFAULT_CROSSFADE_MS
+    }
     fn default_start_minimized() -> bool {
         false
     }
     fn default_close_to_tray() -> bool {
         false
     }
+    fn default_tray_mode() -> String {
+        "auto".into()
+    }
+    fn default_node_order() -> String {
+        NodeOrder::default().as_str().into()
+    }
+
+    fn node_order(&self) -> NodeOrder {
+        NodeOrder::from_pref(&self.node_order).unwrap_or_default()
+    }
+    fn default_xrun_warning_threshold() -> u64 {
+        10
+    }
+    fn default_cpu_alert_percent() -> u32 {
+        90
+    }
+    fn default_cpu_alert_seconds() -> u64 {
+        10
+    }
+    fn default_pw_command_log() -> bool {
+        false
+    }
+    fn default_check_for_updates() -> bool {
+        false
+    }
+    fn default_dock_plugin_uis() -> bool {
+        false
+    }
+    fn default_plugin_format_order() -> String {
+        format_order_pref(&DEFAULT_FORMAT_ORDER)
+    }
+    fn default_reopen_plugin_uis() -> bool {
+        false
+    }
+    fn default_quick_params_count() -> u32 {
+        quick_params::DEFAULT_QUICK_PARAMS as u32
+    }
+    fn default_jack_dbus_patchbay() -> bool {
+        false
+    }
+    fn default_mqtt_enabled() -> bool {
+        false
+    }
+    fn default_mqtt_broker() -> String {
+        "localhost:1883".to_string()
+    }
+    fn default_mqtt_topic() -> String {
+        "zestbay".to_string()
+    }
+    fn default_remote_api_enabled() -> bool {
+        false
+    }
+    fn default_remote_api_bind() -> String {
+        "127.0.0.1:7380".to_string()
+    }
+    fn default_double_click_actions() -> std::collections::BTreeMap<NodeType, NodeAction> {
+        std::collections::BTreeMap::from([(NodeType::Plugin, NodeAction::OpenUi)])
+    }
+
+    fn double_click_action(&self, node_type: NodeType) -> NodeAction {
+        self.double_click_actions
+            .get(&node_type)
+            .copied()
+            .unwrap_or(NodeAction::None)
+    }
     fn default_pw_tick_interval_ms() -> u64 {
         10
     }
     fn default_pw_operation_cooldown_ms() -> u64 {
         50
     }
+    fn default_pw_connect_retries() -> u32 {
+        5
+    }
+    fn default_performance_profile() -> String {
+        PerformanceProfile::Balanced.as_str().to_string()
+    }
+    fn default_battery_profile() -> String {
+        PerformanceProfile::Powersave.as_str().to_string()
+    }
+
+    /// The intervals of the balanced profile: the timing preferences.
+    fn balanced_settings(&self) -> ProfileSettings {
+        ProfileSettings {
+            poll_interval_ms: self.poll_interval_ms,
+            pw_tick_interval_ms: self.pw_tick_interval_ms,
+            meter_interval_ms: DEFAULT_METER_INTERVAL_MS,
+            repaint_interval_ms: DEFAULT_REPAINT_INTERVAL_MS,
+        }
+    }
 }
 
 impl Default for Preferences {
     fn default() -> Self {
         Self {
             rule_settle_ms: Self::default_rule_settle_ms(),
+            storm_events_per_sec: Self::default_storm_events_per_sec(),
             params_persist_ms: Self::default_params_persist_ms(),
             links_persist_ms: Self::default_links_persist_ms(),
             poll_interval_ms: Self::default_poll_interval_ms(),
             auto_learn_rules: Self::default_auto_learn_rules(),
+            fast_route_streams: Self::default_fast_route_streams(),
+            manual_override_scope: Self::default_manual_override_scope(),
+            manual_override_minutes: Self::default_manual_override_minutes(),
+            bypass_level_match: Self::default_bypass_level_match(),
+            plugin_tail_seconds: Self::default_plugin_tail_seconds(),
+            output_crossfade_ms: Self::default_output_crossfade_ms(),
             start_minimized: Self::default_start_minimized(),
             close_to_tray: Self::default_close_to_tray(),
+            pipewire_remote: String::new(),
+            compare_remote: String::new(),
+            remote_instance: String::new(),
+            remote_instance_token: String::new(),
+            remote_instance_cert: String::new(),
+            tray_mode: Self::default_tray_mode(),
+            node_order: Self::default_node_order(),
+            xrun_warning_threshold: Self::default_xrun_warning_threshold(),
+            cpu_alert_percent: Self::default_cpu_alert_percent(),
+            cpu_alert_seconds: Self::default_cpu_alert_seconds(),
+            pw_command_log: Self::default_pw_command_log(),
+            check_for_updates: Self::default_check_for_updates(),
+            dock_plugin_uis: Self::default_dock_plugin_uis(),
+            plugin_format_order: Self::default_plugin_format_order(),
+            show_all_plugin_versions: false,
+            reopen_plugin_uis: Self::default_reopen_plugin_uis(),
+            plugin_ui_monitor: String::new(),
+            quick_params_count: Self::default_quick_params_count(),
+            jack_dbus_patchbay: Self::default_jack_dbus_patchbay(),
+            patchbay_dbus: false,
+            mqtt_enabled: Self::default_mqtt_enabled(),
+            mqtt_broker: Self::default_mqtt_broker(),
+            mqtt_topic: Self::default_mqtt_topic(),
+            mqtt_username: String::new(),
+            mqtt_password: String::new(),
+            mqtt_tls: false,
+            mqtt_tls_ca: String::new(),
+            mqtt_scope: ApiScope::default(),
+            remote_api_enabled: Self::default_remote_api_enabled(),
+            remote_api_bind: Self::default_remote_api_bind(),
+            remote_api_token: String::new(),
+            remote_api_tokens: Vec::new(),
+            remote_api_tls_cert: String::new(),
+            remote_api_tls_key: String::new(),
+            remote_api_cors_origin: String::new(),
+            double_click_actions: Self::default_double_click_actions(),
             pw_tick_interval_ms: Self::default_pw_tick_interval_ms(),
             pw_operation_cooldown_ms: Self::default_pw_operation_cooldown_ms(),
+            pw_connect_retries: Self::default_pw_connect_retries(),
+            performance_profile: Self::default_performance_profile(),
+            battery_profile: Self::default_battery_profile(),
         }
     }
 }
 
 fn load_preferences() -> Preferences {
-    let path = config_path("preferences.json");
-    match std::fs::read_to_string(&path) {
-        Ok(s) => serde_json::from_str(&s).unwrap_or_default(),
-        Err(_) => Preferences::default(),
-    }
+    load_config(
+        &config::PREFERENCES,
+        "preferences.json",
+        config::Layering::MergeKeys,
+    )
 }
 
 fn read_process_cpu_ticks() -> u64 {
@@ -3684,11 +13439,7 @@ fn measure_cpu_usage(
 
 fn save_preferences(prefs: &Preferences) {
     let path = config_path("preferences.json");
-    if let Some(parent) = path.parent() {
-        let _ = std::fs::create_dir_all(parent);
-    }
-    let json = serde_json::to_string_pretty(prefs).unwrap_or_default();
-    if let Err(e) = std::fs::write(&path, &json) {
+    if let Err(e) = config::PREFERENCES.save(&path, prefs) {
         log::error!("Failed to save preferences to {:?}: {}", path, e);
     } else {
         log::debug!("save_preferences: written to {:?}", path);
@@ -0,0 +1,335 @@
+//! Another ZestBay, usually one started with `--headless` on a machine
+//! without a monitor, managed from this one over its remote API.
+//!
+//! A thread keeps a WebSocket open to the instance's `/api/events`, keeps
+//! the latest graph it streams and sends [`RemoteRequest`]s back over the
+//! same socket. When the connection drops it is retried until the instance
+//! is closed. `wss://` addresses are reached over TLS, trusting either the
+//! usual certificate authorities or one certificate given for the instance.
+
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, ServerName};
+use rustls::{ClientConfig, ClientConnection, RootCertStore, StreamOwned};
+use tungstenite::{Message, WebSocket};
+use zestbay_core::access::ApiScope;
+
+use crate::remote_api::RemoteRequest;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+const RETRY_INTERVAL: Duration = Duration::from_secs(3);
+const SOCKET_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+#[derive(Default)]
+struct Shared {
+    /// The last graph the instance sent, as `{ nodes, links, ports, ... }`.
+    graph: Option<serde_json::Value>,
+    connected: bool,
+    /// Why the last connection attempt failed or the instance's last error.
+    error: Option<String>,
+    /// What the token may do, as the instance said when connecting.
+    scope: Option<ApiScope>,
+    changed: bool,
+}
+
+/// The connection to the instance, plain or TLS.
+trait Stream: Read + Write + Send {}
+
+impl<T: Read + Write + Send> Stream for T {}
+
+struct Target {
+    /// `host:port`
+    host: String,
+    url: String,
+    tls: Option<Arc<ClientConfig>>,
+}
+
+pub struct RemoteInstance {
+    address: String,
+    token: String,
+    cert: String,
+    shared: Arc<Mutex<Shared>>,
+    requests: Sender<RemoteRequest>,
+    stopped: Arc<AtomicBool>,
+}
+
+impl RemoteInstance {
+    /// Start connecting to the instance at `address` (`host:port`, with or
+    /// without `ws://`, or `wss://host:port` for TLS) using its access token.
+    /// `cert` is the PEM certificate to trust for TLS instead of the usual
+    /// authorities, for instances with a self-made one; empty uses those.
+    pub fn start(address: &str, token: &str, cert: &str) -> Self {
+        let shared = Arc::new(Mutex::new(Shared::default()));
+        let stopped = Arc::new(AtomicBool::new(false));
+        let (requests, request_rx) = mpsc::channel();
+
+        let address = address.trim();
+        let (scheme, host) = match address.split_once("://") {
+            Some((scheme, host)) => (scheme, host),
+            None => ("ws", address),
+        };
+        let host = host.trim_end_matches('/').to_string();
+        let tls = match scheme {
+            "wss" => tls_config(cert).map(Some),
+            "ws" => Ok(None),
+            _ => Err(format!("Unknown scheme {}://, use ws:// or wss://", scheme)),
+        };
+        match tls {
+            Ok(tls) => {
+                let target = Target {
+                    url: format!(
+                        "{}://{}/api/events?token={}",
+                        scheme,
+                        host,
+                        percent_encode(token)
+                    ),
+                    host,
+                    tls,
+                };
+                let thread_shared = shared.clone();
+                let thread_stopped = stopped.clone();
+                let spawned = std::thread::Builder::new()
+                    .name("zestbay-remote-client".into())
+                    .spawn(move || run(&target, &thread_shared, &thread_stopped, &request_rx));
+                if let Err(e) = spawned {
+                    log::error!("Failed to spawn remote instance thread: {}", e);
+                }
+                log::info!("Remote instance: connecting to {}", address);
+            }
+            Err(e) => {
+                log::warn!("Remote instance {}: {}", address, e);
+                update(&shared, |s| s.error = Some(e));
+            }
+        }
+
+        Self {
+            address: address.to_string(),
+            token: token.to_string(),
+            cert: cert.to_string(),
+            shared,
+            requests,
+            stopped,
+        }
+    }
+
+    pub fn address(&self) -> &str {
+        &self.address
+    }
+
+    pub fn token(&self) -> &str {
+        &self.token
+    }
+
+    pub fn cert(&self) -> &str {
+        &self.cert
+    }
+
+    /// Queue a request for the instance. Requests made while disconnected
+    /// are sent once the connection is back.
+    pub fn send(&self, request: RemoteRequest) {
+        let _ = self.requests.send(request);
+    }
+
+    /// Whether anything shown changed since the last call.
+    pub fn poll(&self) -> bool {
+        std::mem::take(&mut self.lock().changed)
+    }
+
+    /// The instance's graph and connection state for the remote view.
+    pub fn snapshot(&self) -> serde_json::Value {
+        let shared = self.lock();
+        serde_json::json!({
+            "address": self.address,
+            "connected": shared.connected,
+            "error": shared.error,
+            "scope": shared.scope,
+            "graph": shared.graph,
+        })
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Shared> {
+        self.shared.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Drop for RemoteInstance {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::Release);
+    }
+}
+
+fn update(shared: &Mutex<Shared>, f: impl FnOnce(&mut Shared)) {
+    let mut shared = shared.lock().unwrap_or_else(|e| e.into_inner());
+    f(&mut shared);
+    shared.changed = true;
+}
+
+fn run(
+    target: &Target,
+    shared: &Mutex<Shared>,
+    stopped: &AtomicBool,
+    requests: &Receiver<RemoteRequest>,
+) {
+    let host = &target.host;
+    while !stopped.load(Ordering::Acquire) {
+        match connect(target) {
+            Ok(mut socket) => {
+                log::info!("Remote instance: connected to {}", host);
+                update(shared, |s| {
+                    s.connected = true;
+                    s.error = None;
+                });
+                let reason = serve(&mut socket, shared, stopped, requests);
+                let _ = socket.close(None);
+                if let Some(ref reason) = reason {
+                    log::warn!("Remote instance {}: {}", host, reason);
+                }
+                update(shared, |s| {
+                    s.connected = false;
+                    s.scope = None;
+                    s.error = reason;
+                });
+            }
+            Err(e) => {
+                log::debug!("Remote instance {}: {}", host, e);
+                update(shared, |s| s.error = Some(e));
+            }
+        }
+        // Sleep in short steps so closing the view is not held up.
+        for _ in 0..(RETRY_INTERVAL.as_millis() / SOCKET_POLL_INTERVAL.as_millis()) {
+            if stopped.load(Ordering::Acquire) {
+                break;
+            }
+            std::thread::sleep(SOCKET_POLL_INTERVAL);
+        }
+    }
+    log::info!("Remote instance: disconnected from {}", host);
+}
+
+fn tls_config(cert_path: &str) -> Result<Arc<ClientConfig>, String> {
+    let mut roots = RootCertStore::empty();
+    if cert_path.is_empty() {
+        roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+    } else {
+        let certs = CertificateDer::pem_file_iter(cert_path)
+            .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+            .map_err(|e| format!("Cannot read certificate {}: {}", cert_path, e))?;
+        let (added, _) = roots.add_parsable_certificates(certs);
+        if added == 0 {
+            return Err(format!("No usable certificate in {}", cert_path));
+        }
+    }
+    let config = ClientConfig::builder()
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+    Ok(Arc::new(config))
+}
+
+fn connect(target: &Target) -> Result<WebSocket<Box<dyn Stream>>, String> {
+    use std::net::ToSocketAddrs;
+
+    let host = &target.host;
+    let addr = host
+        .to_socket_addrs()
+        .map_err(|e| format!("Cannot resolve {}: {}", host, e))?
+        .next()
+        .ok_or_else(|| format!("Cannot resolve {}", host))?;
+    let stream = TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT)
+        .map_err(|e| format!("Cannot connect to {}: {}", host, e))?;
+    let _ = stream.set_read_timeout(Some(CONNECT_TIMEOUT));
+    // Kept to change the timeout once the stream is wrapped.
+    let tcp = stream
+        .try_clone()
+        .map_err(|e| format!("Cannot connect to {}: {}", host, e))?;
+    let stream: Box<dyn Stream> = match target.tls {
+        Some(ref config) => {
+            let name = host
+                .rsplit_once(':')
+                .map_or(host.as_str(), |(name, _)| name);
+            let name = ServerName::try_from(name.trim_matches(['[', ']']).to_string())
+                .map_err(|_| format!("Invalid host name in {}", host))?;
+            let connection = ClientConnection::new(config.clone(), name)
+                .map_err(|e| format!("TLS setup for {} failed: {}", host, e))?;
+            Box::new(StreamOwned::new(connection, stream))
+        }
+        None => Box::new(stream),
+    };
+    let (socket, _) =
+        tungstenite::client::client(target.url.as_str(), stream).map_err(|e| match e {
+            tungstenite::HandshakeError::Failure(tungstenite::Error::Http(response))
+                if response.status() == 401 =>
+            {
+                "Wrong access token".to_string()
+            }
+            e => format!("Handshake with {} failed: {}", host, e),
+        })?;
+    // Short reads let one thread both take events and send requests.
+    let _ = tcp.set_read_timeout(Some(SOCKET_POLL_INTERVAL));
+    Ok(socket)
+}
+
+/// Pass events and requests until the connection ends. Returns why it
+/// ended, or `None` when the view was closed.
+fn serve(
+    socket: &mut WebSocket<Box<dyn Stream>>,
+    shared: &Mutex<Shared>,
+    stopped: &AtomicBool,
+    requests: &Receiver<RemoteRequest>,
+) -> Option<String> {
+    while !stopped.load(Ordering::Acquire) {
+        match socket.read() {
+            Ok(Message::Text(text)) => {
+                let Ok(mut event) = serde_json::from_str::<serde_json::Value>(&text) else {
+                    continue;
+                };
+                let data = event["data"].take();
+                match event["event"].as_str() {
+                    Some("session") => {
+                        let scope = serde_json::from_value(data["scope"].take()).ok();
+                        update(shared, |s| s.scope = scope);
+                    }
+                    Some("graph") => update(shared, |s| s.graph = Some(data)),
+                    Some("error") => {
+                        let message = data["message"].as_str().unwrap_or_default().to_string();
+                        update(shared, |s| s.error = Some(message));
+                    }
+                    _ => {}
+                }
+            }
+            Ok(Message::Close(_)) => return Some("Closed by the instance".into()),
+            Ok(_) => {}
+            Err(tungstenite::Error::Io(e))
+                if matches!(
+                    e.kind(),
+                    std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+                ) => {}
+            Err(e) => return Some(format!("Connection lost: {}", e)),
+        }
+        while let Ok(request) = requests.try_recv() {
+            let Ok(text) = serde_json::to_string(&request) else {
+                continue;
+            };
+            if let Err(e) = socket.send(Message::Text(text)) {
+                return Some(format!("Connection lost: {}", e));
+            }
+        }
+    }
+    None
+}
+
+fn percent_encode(text: &str) -> String {
+    text.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (b as char).to_string()
+            }
+            b => format!("%{:02X}", b),
+        })
+        .collect()
+}
@@ -521,7 +521,10 @@ fn open_ui(msg: HostMessage, windows: &mut HashMap<u64, PluginUiWindow>) -> Brid
 
         windows.insert(instance_id, *plugin_win);
 
-        BridgeMessage::Opened { instance_id }
+        BridgeMessage::Opened {
+            instance_id,
+            window_id: host_window as u64,
+        }
     }
 }
 
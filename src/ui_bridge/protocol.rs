@@ -6,6 +6,7 @@ use serde::{Deserialize, Serialize};
 
 /// Messages from the host to the bridge.
 #[derive(Debug, Serialize, Deserialize)]
+#[allow(clippy::large_enum_variant)]
 #[serde(tag = "cmd")]
 pub enum HostMessage {
     /// Open a plugin UI.
@@ -25,6 +26,10 @@ pub enum HostMessage {
         /// Passed as 0 when running out-of-process (not usable).
         lv2_handle: u64,
         sample_rate: f32,
+        /// Shared-memory control block for isolated UIs. When set, control
+        /// values travel through it instead of `PortEvent`/`PortWrite`.
+        #[serde(default)]
+        shm_path: Option<String>,
     },
     /// Send a port value update to the UI.
     PortEvent {
@@ -47,6 +52,10 @@ pub enum BridgeMessage {
     /// UI was opened successfully.
     Opened {
         instance_id: u64,
+        /// X11 window holding the UI, for docking it in the host's main
+        /// window; 0 if there is none.
+        #[serde(default)]
+        window_id: u64,
     },
     /// UI failed to open.
     OpenFailed {
@@ -0,0 +1,51 @@
+//! Background lookup of the latest release on GitHub.
+//!
+//! Off unless enabled in Preferences. The request goes through `curl`, which
+//! every desktop this runs on has, rather than pulling a TLS stack into the
+//! binary for one request per start.
+
+use std::process::Command;
+use std::sync::mpsc::{self, Receiver};
+
+use zestbay_core::update::{LATEST_RELEASE_URL, Release};
+
+/// Fetch the latest release on a worker thread. The receiver gets exactly
+/// one result.
+pub fn spawn() -> Receiver<Result<Release, String>> {
+    let (tx, rx) = mpsc::channel();
+    std::thread::Builder::new()
+        .name("update-check".into())
+        .spawn(move || {
+            let _ = tx.send(fetch_latest());
+        })
+        .ok();
+    rx
+}
+
+fn fetch_latest() -> Result<Release, String> {
+    let output = Command::new("curl")
+        .args(["--silent", "--show-error", "--fail", "--location"])
+        .args(["--max-time", "15"])
+        .args(["--header", "Accept: application/vnd.github+json"])
+        .args([
+            "--user-agent",
+            concat!("zestbay/", env!("CARGO_PKG_VERSION")),
+        ])
+        .arg(LATEST_RELEASE_URL)
+        .output()
+        .map_err(|e| format!("could not run curl: {}", e))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("release lookup failed: {}", stderr.trim()));
+    }
+    Release::parse(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// How to upgrade on this system.
+pub fn upgrade_hint() -> &'static str {
+    let os_release = std::fs::read_to_string("/etc/os-release")
+        .or_else(|_| std::fs::read_to_string("/usr/lib/os-release"))
+        .unwrap_or_default();
+    let flatpak = std::env::var_os("FLATPAK_ID").is_some();
+    zestbay_core::update::upgrade_hint(&os_release, flatpak)
+}
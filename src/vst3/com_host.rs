@@ -8,14 +8,15 @@
 //!   sometimes `queryInterface` for `IRunLoop`.
 //!
 //! - **IComponentHandler** — set on the controller via `setComponentHandler()`.
-//!   Plugins call `performEdit()` when the user tweaks a knob in the GUI.
+//!   Plugins call `performEdit()` when the user tweaks a knob in the GUI; the
+//!   edit is queued in [`EditorEdits`] for the GUI thread to pass on.
 //!
 //! - **IRunLoop** — Linux-specific. Plugins (esp. JUCE-based) register timers
 //!   and file-descriptor event handlers that must be serviced on the GUI thread.
 
 use std::collections::HashMap;
 use std::os::raw::c_void;
-use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
 use std::sync::{Arc, Mutex};
 
 use vst3::Steinberg::IBStream_::IStreamSeekMode_::*;
@@ -178,11 +179,60 @@ pub unsafe fn release_host_component_handler(ch: *mut HostComponentHandler) {
 // IComponentHandler
 // =========================================================================
 
+/// Parameter edits made in a plugin's editor, on their way to the manager.
+///
+/// `performEdit()` is called on whatever thread the editor runs on, so it
+/// only queues the edit here. The GUI thread drains the queue each tick and
+/// sends the edits on as `SetPluginParameter` commands, the same path the
+/// parameter panel takes.
+#[derive(Clone, Default)]
+pub struct EditorEdits {
+    /// (port_index, normalized value), latest value per port.
+    queue: Arc<Mutex<Vec<(usize, f32)>>>,
+    /// Set by `restartComponent(kParamValuesChanged)`, e.g. when the editor
+    /// loads one of its own presets: every value has to be read again.
+    values_changed: Arc<AtomicBool>,
+    /// Mapping from VST3 ParamID → port_index, shared with the handler.
+    param_map: Arc<Mutex<HashMap<u32, usize>>>,
+}
+
+impl EditorEdits {
+    fn push(&self, port_index: usize, value: f32) {
+        let mut queue = self.queue.lock().unwrap_or_else(|e| e.into_inner());
+        match queue.iter_mut().find(|(index, _)| *index == port_index) {
+            Some(edit) => edit.1 = value,
+            None => queue.push((port_index, value)),
+        }
+    }
+
+    /// Take the queued edits, oldest port first.
+    pub fn take(&self) -> Vec<(usize, f32)> {
+        std::mem::take(&mut *self.queue.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+
+    /// Whether the plugin asked for all values to be re-read since the
+    /// last call.
+    pub fn take_values_changed(&self) -> bool {
+        self.values_changed.swap(false, Ordering::AcqRel)
+    }
+
+    /// Queue every parameter's current value, read with `value_of`.
+    pub fn push_all(&self, value_of: impl Fn(u32) -> f64) {
+        let params: Vec<(u32, usize)> = match self.param_map.lock() {
+            Ok(map) => map.iter().map(|(&id, &index)| (id, index)).collect(),
+            Err(_) => return,
+        };
+        for (id, port_index) in params {
+            self.push(port_index, value_of(id) as f32);
+        }
+    }
+}
+
 /// Our host-side IComponentHandler.
 ///
 /// When the plugin's GUI changes a parameter, it calls `performEdit()`.
 /// We write the new value into the shared `PortUpdates` so the audio thread
-/// picks it up.
+/// picks it up, and queue it in `edits` so the manager hears about it.
 #[repr(C)]
 pub struct HostComponentHandler {
     vtbl: *const IComponentHandlerVtbl,
@@ -192,6 +242,8 @@ pub struct HostComponentHandler {
     pub param_map: Arc<Mutex<HashMap<u32, usize>>>,
     /// Shared port updates for lock-free parameter sync.
     pub port_updates: SharedPortUpdates,
+    /// Edits for the GUI thread to pass on to the manager.
+    pub edits: EditorEdits,
 }
 
 unsafe impl Send for HostComponentHandler {}
@@ -274,6 +326,7 @@ unsafe extern "system" fn host_ch_perform_edit(
                     .find(|s| s.port_index == port_index)
                 {
                     slot.value.store(value_normalized as f32);
+                    (*ch).edits.push(port_index, value_normalized as f32);
                     return kResultOk;
                 }
             }
@@ -288,16 +341,22 @@ unsafe extern "system" fn host_ch_end_edit(_this: *mut IComponentHandler, _id: P
 }
 
 unsafe extern "system" fn host_ch_restart_component(
-    _this: *mut IComponentHandler,
+    this: *mut IComponentHandler,
     flags: int32,
 ) -> tresult {
     log::debug!(
         "VST3 IComponentHandler::restartComponent(flags=0x{:x})",
         flags
     );
-    // We could handle specific restart flags here (e.g., kParamValuesChanged
-    // to re-read param values, kLatencyChanged to update latency compensation).
-    // For now, acknowledge the request.
+    // Values changed without performEdit() (e.g. a preset loaded in the
+    // editor): the GUI thread re-reads them all. Other flags, such as
+    // kLatencyChanged, are acknowledged but not acted on.
+    if flags & RestartFlags_::kParamValuesChanged != 0 {
+        unsafe {
+            let ch = this as *mut HostComponentHandler;
+            (*ch).edits.values_changed.store(true, Ordering::Release);
+        }
+    }
     kResultOk
 }
 
@@ -307,12 +366,17 @@ pub fn new_host_component_handler(
     param_map: HashMap<u32, usize>,
     port_updates: SharedPortUpdates,
 ) -> *mut HostComponentHandler {
+    let param_map = Arc::new(Mutex::new(param_map));
     let ch = Box::new(HostComponentHandler {
         vtbl: &HOST_COMPONENT_HANDLER_VTBL,
         ref_count: AtomicU32::new(1),
         instance_id,
-        param_map: Arc::new(Mutex::new(param_map)),
+        param_map: param_map.clone(),
         port_updates,
+        edits: EditorEdits {
+            param_map,
+            ..EditorEdits::default()
+        },
     });
     Box::into_raw(ch)
 }
@@ -1112,6 +1176,13 @@ mod tests {
             let r = host_ch_perform_edit(ch as *mut IComponentHandler, 999, 0.5);
             assert_eq!(r, kResultOk);
 
+            // Edits are queued for the manager, the latest value per port
+            let r = host_ch_perform_edit(ch as *mut IComponentHandler, 100, 0.5);
+            assert_eq!(r, kResultOk);
+            let edits = (*ch).edits.clone();
+            assert_eq!(edits.take(), vec![(0, 0.5), (1, 0.25)]);
+            assert!(edits.take().is_empty());
+
             release_host_component_handler(ch);
         }
     }
@@ -1136,6 +1207,46 @@ mod tests {
         }
     }
 
+    // ---- HostComponentHandler: restartComponent re-reads values ----
+
+    #[test]
+    fn component_handler_restart_queues_every_value() {
+        unsafe {
+            let port_updates = Arc::new(PortUpdates {
+                control_inputs: Vec::new(),
+                control_outputs: Vec::new(),
+                atom_outputs: Vec::new(),
+                atom_inputs: Vec::new(),
+            });
+            let mut param_map = HashMap::new();
+            param_map.insert(100u32, 0usize);
+            param_map.insert(200u32, 1usize);
+            let ch = new_host_component_handler(7, param_map, port_updates);
+            let edits = (*ch).edits.clone();
+
+            let r = host_ch_restart_component(
+                ch as *mut IComponentHandler,
+                RestartFlags_::kLatencyChanged,
+            );
+            assert_eq!(r, kResultOk);
+            assert!(!edits.take_values_changed());
+
+            host_ch_restart_component(
+                ch as *mut IComponentHandler,
+                RestartFlags_::kParamValuesChanged,
+            );
+            assert!(edits.take_values_changed());
+            assert!(!edits.take_values_changed());
+
+            edits.push_all(|id| if id == 100 { 0.25 } else { 1.0 });
+            let mut queued = edits.take();
+            queued.sort_by_key(|(index, _)| *index);
+            assert_eq!(queued, vec![(0, 0.25), (1, 1.0)]);
+
+            release_host_component_handler(ch);
+        }
+    }
+
     // ---- HostRunLoop: timer registration ----
 
     #[test]
@@ -15,6 +15,7 @@ use super::host::Vst3PluginInstance;
 use crate::midi::filter::ResolvedMappings;
 use crate::midi::processing::MidiProcessingState;
 use crate::plugin::cpu_stats::{global_cpu_tracker, PluginTimingSlot};
+use crate::plugin::meters::{self, global_meters, MeterProducer};
 use crate::plugin::types::PluginInstanceId;
 
 pub struct Vst3FilterNode {
@@ -34,6 +35,8 @@ pub struct FilterConfig {
     pub audio_outputs: usize,
     pub has_midi_in: bool,
     pub has_midi_out: bool,
+    /// Audio input channels that are sidechains rather than main inputs.
+    pub sidechain_inputs: Vec<usize>,
 }
 
 #[repr(C)]
@@ -58,6 +61,7 @@ struct FilterData {
     /// Plugin accepts MIDI input (feed PipeWire MIDI to VST3 events)
     has_midi_in: bool,
     cpu_slot: Arc<PluginTimingSlot>,
+    meter: MeterProducer,
     midi_state: MidiProcessingState,
 }
 
@@ -134,6 +138,7 @@ impl Vst3FilterNode {
             n_audio_outputs: config.audio_outputs,
             has_midi_in: config.has_midi_in,
             cpu_slot,
+            meter: global_meters().register(config.instance_id),
             midi_state: MidiProcessingState::new(),
         }));
 
@@ -172,6 +177,16 @@ impl Vst3FilterNode {
                     std::ptr::null::<std::os::raw::c_char>(),
                 )
             };
+            if config.sidechain_inputs.contains(&i) {
+                let group = CString::new(crate::plugin::types::SIDECHAIN_PORT_GROUP).unwrap();
+                unsafe {
+                    pipewire::sys::pw_properties_set(
+                        port_props,
+                        c_str(b"port.group\0"),
+                        group.as_ptr(),
+                    );
+                }
+            }
             let port_data = unsafe {
                 pipewire::sys::pw_filter_add_port(
                     filter,
@@ -318,6 +333,14 @@ impl Vst3FilterNode {
         })
     }
 
+    /// Ask PipeWire for a quantum through `node.latency` (e.g. `"64/48000"`),
+    /// or withdraw the request with `None`.
+    pub fn set_latency(&self, latency: Option<&str>) {
+        if !self.filter.is_null() {
+            unsafe { crate::plugin::node_latency::request(self.filter, latency) }
+        }
+    }
+
     pub fn node_id(&self) -> u32 {
         if self.filter.is_null() {
             return 0;
@@ -367,6 +390,7 @@ impl Vst3FilterNode {
 impl Drop for Vst3FilterNode {
     fn drop(&mut self) {
         global_cpu_tracker().unregister(self.instance_id);
+        global_meters().unregister(self.instance_id);
 
         if !self._user_data.is_null() {
             unsafe {
@@ -539,5 +563,6 @@ unsafe extern "C" fn on_process(
         );
         let elapsed = t0.elapsed().as_nanos() as u64;
         fd.cpu_slot.record(elapsed, 0, n_samples, rate);
+        meters::record(&mut fd.meter, output_bufs.iter().map(|b| &**b));
     }
 }
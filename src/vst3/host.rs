@@ -9,7 +9,7 @@ use vst3::Steinberg::Vst::*;
 use vst3::Steinberg::*;
 
 use super::com_host::{
-    HostApplication, HostComponentHandler,
+    EditorEdits, HostApplication, HostComponentHandler,
     new_host_application, new_host_component_handler,
 };
 
@@ -531,6 +531,7 @@ pub struct Vst3Param {
     /// Is this the bypass parameter?
     pub is_bypass: bool,
     pub is_toggle: bool,
+    pub doc: ParamDoc,
 }
 
 /// A running VST3 plugin instance.
@@ -546,6 +547,8 @@ pub struct Vst3PluginInstance {
 
     pub audio_input_channels: usize,
     pub audio_output_channels: usize,
+    /// Input channels belonging to aux (sidechain) buses
+    pub sidechain_inputs: Vec<usize>,
 
     /// Whether this plugin accepts MIDI input (has event input bus)
     pub has_midi_in: bool,
@@ -561,6 +564,14 @@ pub struct Vst3PluginInstance {
     pub port_updates: SharedPortUpdates,
 
     pub bypassed: bool,
+    bypass: crate::plugin::bypass::BypassCrossfader,
+    /// Set while the instance rings out before it is removed
+    pub ringing_out: bool,
+    tail: crate::plugin::tail::PluginTail,
+    tail_finished: std::sync::atomic::AtomicBool,
+    /// Resampler around process() when the plugin is oversampled
+    oversampler: Option<crate::plugin::oversample::Oversampler>,
+    /// Graph sample rate; the plugin itself runs at this times the oversampling factor
     pub sample_rate: f64,
     active: bool,
     processing: bool,
@@ -594,6 +605,7 @@ impl Vst3PluginInstance {
         plugin_id: &str,
         plugin_info: &PluginInfo,
         sample_rate: f64,
+        oversampling: usize,
     ) -> Option<Self> {
         unsafe {
             let instance_id = next_instance_id();
@@ -747,12 +759,16 @@ impl Vst3PluginInstance {
             let mut output_bus_descs = Vec::new();
             let mut audio_input_channels = 0usize;
             let mut audio_output_channels = 0usize;
+            let mut sidechain_inputs = Vec::new();
 
             let in_bus_count = component.getBusCount(K_AUDIO, K_INPUT);
             for idx in 0..in_bus_count {
                 let mut bus_info: BusInfo = std::mem::zeroed();
                 if component.getBusInfo(K_AUDIO, K_INPUT, idx, &mut bus_info) == kResultOk {
                     let ch = bus_info.channelCount as usize;
+                    if bus_info.busType == vst3::Steinberg::Vst::BusTypes_::kAux as i32 {
+                        sidechain_inputs.extend(audio_input_channels..audio_input_channels + ch);
+                    }
                     audio_input_channels += ch;
                     // Activate the bus
                     component.activateBus(K_AUDIO, K_INPUT, idx, 1);
@@ -825,6 +841,12 @@ impl Vst3PluginInstance {
                             let name = read_string128(&pinfo.title);
                             let value = ctrl.getParamNormalized(pinfo.id);
                             let is_toggle = pinfo.stepCount == 1;
+                            let units = read_string128(&pinfo.units);
+                            let doc = ParamDoc {
+                                comment: None,
+                                unit: (!units.is_empty()).then_some(units),
+                                scale_points: vst3_step_labels(ctrl, &pinfo),
+                            };
                             params.push(Vst3Param {
                                 id: pinfo.id,
                                 port_index: port_idx,
@@ -833,6 +855,7 @@ impl Vst3PluginInstance {
                                 default: pinfo.defaultNormalizedValue,
                                 is_bypass: false,
                                 is_toggle,
+                                doc,
                             });
                             port_idx += 1;
                         }
@@ -883,8 +906,9 @@ impl Vst3PluginInstance {
             let mut setup = ProcessSetup {
                 processMode: ProcessModes_::kRealtime as i32,
                 symbolicSampleSize: SymbolicSampleSizes_::kSample32 as i32,
-                maxSamplesPerBlock: 8192,
-                sampleRate: sample_rate,
+                maxSamplesPerBlock: (crate::plugin::oversample::MAX_BLOCK * oversampling.max(1))
+                    as i32,
+                sampleRate: sample_rate * oversampling.max(1) as f64,
             };
 
             if processor.setupProcessing(&mut setup) != kResultOk {
@@ -906,6 +930,21 @@ impl Vst3PluginInstance {
                 log::warn!("VST3: setProcessing returned error for {} (continuing anyway)", plugin_id);
             }
 
+            let mut bypass = crate::plugin::bypass::BypassCrossfader::new(
+                audio_input_channels.min(audio_output_channels),
+                sample_rate,
+            );
+            let tail = crate::plugin::tail::PluginTail::new(audio_input_channels, sample_rate);
+            let oversampler = crate::plugin::oversample::Oversampler::new(
+                oversampling,
+                audio_input_channels,
+                audio_output_channels,
+            );
+            bypass.set_latency(crate::plugin::oversample::compensated_latency(
+                oversampler.as_ref(),
+                processor.getLatencySamples() as usize,
+            ));
+
             Some(Self {
                 id: instance_id,
                 component,
@@ -916,6 +955,7 @@ impl Vst3PluginInstance {
                 display_name: plugin_info.name.clone(),
                 audio_input_channels,
                 audio_output_channels,
+                sidechain_inputs,
                 has_midi_in,
                 has_midi_out,
                 input_bus_descs,
@@ -924,6 +964,11 @@ impl Vst3PluginInstance {
                 bypass_param_id,
                 port_updates,
                 bypassed: false,
+                bypass,
+                ringing_out: false,
+                tail,
+                tail_finished: std::sync::atomic::AtomicBool::new(false),
+                oversampler,
                 sample_rate,
                 host_app,
                 component_handler,
@@ -948,6 +993,72 @@ impl Vst3PluginInstance {
         outputs: &mut [&mut [f32]],
         sample_count: usize,
         midi_events: &[crate::midi::processing::RawMidiEvent],
+    ) {
+        unsafe {
+            // While bypassed or being removed the plugin may run on muted
+            // inputs, so that its tail rings out.
+            let mut tail = std::mem::take(&mut self.tail);
+            tail.process(
+                self.bypassed || self.ringing_out,
+                inputs,
+                outputs,
+                sample_count,
+                midi_events,
+                |ins, outs, events| {
+                    self.run_block(ins, outs, sample_count, events);
+                },
+            );
+            self.bypass.set_keep_wet(tail.is_ringing());
+            self.tail_finished
+                .store(tail.is_finished(), std::sync::atomic::Ordering::Relaxed);
+            self.tail = tail;
+
+            // Crossfade to the latency-aligned input while bypassed
+            self.bypass
+                .process(self.bypassed, inputs, outputs, sample_count);
+        }
+    }
+
+    /// Whether the instance's inputs are muted and its tail has rung out.
+    pub fn tail_finished(&self) -> bool {
+        self.tail_finished
+            .load(std::sync::atomic::Ordering::Relaxed)
+    }
+
+    /// Run the plugin on one block, through the oversampler if there is
+    /// one.
+    unsafe fn run_block(
+        &mut self,
+        inputs: &[&[f32]],
+        outputs: &mut [&mut [f32]],
+        sample_count: usize,
+        midi_events: &[crate::midi::processing::RawMidiEvent],
+    ) {
+        unsafe {
+            if let Some(mut os) = self.oversampler.take() {
+                let mut scaled = [crate::midi::processing::RawMidiEvent {
+                    offset: 0,
+                    data: [0; 3],
+                    size: 0,
+                }; crate::midi::processing::MAX_MIDI_EVENTS];
+                let n_events = os.scale_midi_events(midi_events, &mut scaled);
+                os.process(inputs, outputs, sample_count, |ins, outs, n| {
+                    self.run(ins, outs, n, &scaled[..n_events]);
+                });
+                self.oversampler = Some(os);
+            } else {
+                self.run(inputs, outputs, sample_count, midi_events);
+            }
+        }
+    }
+
+    /// Run the plugin on buffers at its own (possibly oversampled) rate.
+    unsafe fn run(
+        &mut self,
+        inputs: &[&[f32]],
+        outputs: &mut [&mut [f32]],
+        sample_count: usize,
+        midi_events: &[crate::midi::processing::RawMidiEvent],
     ) {
         unsafe {
             // Read parameter changes from shared port_updates and build
@@ -1059,20 +1170,6 @@ impl Vst3PluginInstance {
 
             self.processor.process(&mut process_data);
 
-            // When bypassed, overwrite plugin audio output with passthrough
-            if self.bypassed {
-                for (i, output) in outputs.iter_mut().enumerate() {
-                    if i < inputs.len() {
-                        let n = output.len().min(inputs[i].len()).min(sample_count);
-                        output[..n].copy_from_slice(&inputs[i][..n]);
-                    } else {
-                        for s in output.iter_mut().take(sample_count) {
-                            *s = 0.0;
-                        }
-                    }
-                }
-            }
-
             // Sync param values back to port_updates
             for (i, p) in self.params.iter().enumerate() {
                 if let Some(slot) = self.port_updates.control_inputs.get(i) {
@@ -1113,6 +1210,15 @@ impl Vst3PluginInstance {
         }
     }
 
+    /// The queue the component handler puts editor edits in, for the GUI
+    /// thread to drain.
+    pub fn editor_edits(&self) -> EditorEdits {
+        if self.component_handler.is_null() {
+            return EditorEdits::default();
+        }
+        unsafe { (*self.component_handler).edits.clone() }
+    }
+
     pub fn get_parameters(&self) -> Vec<ParameterValue> {
         self.params
             .iter()
@@ -1129,6 +1235,15 @@ impl Vst3PluginInstance {
             .collect()
     }
 
+    /// Parameter docs worth reporting, by port index.
+    pub fn param_docs(&self) -> Vec<(usize, ParamDoc)> {
+        self.params
+            .iter()
+            .filter(|p| !p.doc.is_empty())
+            .map(|p| (p.port_index, p.doc.clone()))
+            .collect()
+    }
+
     pub fn get_info(&self, pw_node_id: Option<u32>) -> PluginInstanceInfo {
         PluginInstanceInfo {
             id: self.id,
@@ -1141,6 +1256,7 @@ impl Vst3PluginInstance {
             active: true,
             bypassed: self.bypassed,
             lv2_state: Vec::new(),
+            oversampling: self.oversampler.as_ref().map_or(1, |os| os.factor() as u32),
         }
     }
 
@@ -1284,6 +1400,30 @@ impl Drop for Vst3PluginInstance {
     }
 }
 
+/// The controller's text for each step of a stepped parameter, at the
+/// normalized values the parameters use, when it has few enough steps to
+/// list.
+unsafe fn vst3_step_labels(
+    ctrl: &vst3::ComPtr<IEditController>,
+    pinfo: &ParameterInfo,
+) -> Vec<ScalePoint> {
+    let steps = pinfo.stepCount;
+    if steps <= 0 || steps as usize >= MAX_LABELLED_STEPS {
+        return Vec::new();
+    }
+    (0..=steps)
+        .filter_map(|step| {
+            let value = step as f64 / steps as f64;
+            let mut label: String128 = [0; 128];
+            let result = unsafe { ctrl.getParamStringByValue(pinfo.id, value, &mut label) };
+            (result == kResultOk).then(|| ScalePoint {
+                value: value as f32,
+                label: read_string128(&label),
+            })
+        })
+        .collect()
+}
+
 /// Read a null-terminated UTF-16 string from a String128 ([u16; 128]).
 fn read_string128(buf: &[u16]) -> String {
     let chars: Vec<u16> = buf.iter().take_while(|&&c| c != 0).copied().collect();
@@ -252,6 +252,7 @@ fn scan_vst3_bundle(bundle_path: &Path, plugins: &mut Vec<PluginInfo>) {
             // Try to get extended info (vendor, subcategories) from IPluginFactory2
             let mut vendor: Option<String> = None;
             let mut sub_categories = String::new();
+            let mut version: Option<String> = None;
 
             if let Some(ref f2) = factory2 {
                 let mut info2: PClassInfo2 = std::mem::zeroed();
@@ -261,6 +262,10 @@ fn scan_vst3_bundle(bundle_path: &Path, plugins: &mut Vec<PluginInfo>) {
                         vendor = Some(v);
                     }
                     sub_categories = read_cstr(&info2.subCategories);
+                    let v = read_cstr(&info2.version);
+                    if !v.is_empty() {
+                        version = Some(v);
+                    }
                 }
             }
 
@@ -299,6 +304,9 @@ fn scan_vst3_bundle(bundle_path: &Path, plugins: &mut Vec<PluginInfo>) {
                 // at instantiation time via IEditController::createView().
                 has_ui: true,
                 library_path: bundle_str.to_string(),
+                version,
+                modified: modified_secs(bundle_str),
+                unsupported_ports: Vec::new(),
             });
         }
 
@@ -3,6 +3,12 @@
 //! VST3 plugins provide their GUI via `IEditController::createView()` which
 //! returns an `IPlugView`.  We embed the view in a host-created X11 window,
 //! similar to the CLAP GUI approach.
+//!
+//! Each open editor gets an idle loop on its own thread: it services the
+//! plugin's `IRunLoop` timers, follows the window when the user resizes it,
+//! and passes the edits the editor makes (queued by the component handler)
+//! on to the PipeWire thread as `SetPluginParameter`, so the manager and the
+//! parameter panel stay in sync with the editor.
 
 use std::collections::HashMap;
 use std::os::raw::{c_char, c_int, c_long, c_ulong, c_void};
@@ -15,7 +21,7 @@ use vst3::Steinberg::Vst::{IEditController, ViewType};
 
 use crate::plugin::types::PluginInstanceId;
 
-use super::com_host::{HostRunLoop, run_loop_tick};
+use super::com_host::{EditorEdits, HostRunLoop, run_loop_tick};
 
 // ---------------------------------------------------------------------------
 // X11 FFI bindings (shared with CLAP — ideally in a common module, but
@@ -205,7 +211,8 @@ unsafe extern "system" fn host_plug_frame_resize_view(
 
         // Resize the X11 host window
         with_guis(|m| {
-            if let Some(state) = m.get(&instance_id) {
+            if let Some(state) = m.get_mut(&instance_id) {
+                state.size = (w, h);
                 if !state.x11_display.is_null() && state.x11_window != 0 {
                     XResizeWindow(state.x11_display, state.x11_window, w, h);
                     XFlush(state.x11_display);
@@ -244,6 +251,9 @@ struct Vst3GuiState {
     plug_frame: *mut HostPlugFrame,
     x11_display: *mut c_void,
     x11_window: c_ulong,
+    /// Size the view was last given, so our own resizes are not echoed
+    /// back to it from ConfigureNotify.
+    size: (i32, i32),
     running: std::sync::Arc<AtomicBool>,
     run_loop: *mut HostRunLoop,
 }
@@ -271,10 +281,40 @@ where
 ///
 /// # Safety
 /// Called from the PW thread.
+/// Pass a scale factor to views implementing `IPlugViewContentScaleSupport`.
+unsafe fn set_content_scale(view: *mut IPlugView, scale: f32, instance_id: PluginInstanceId) {
+    unsafe {
+        let mut obj: *mut c_void = std::ptr::null_mut();
+        let result = ((*(*view).vtbl).base.queryInterface)(
+            view as *mut FUnknown,
+            &IPlugViewContentScaleSupport_iid,
+            &mut obj,
+        );
+        if result != kResultOk || obj.is_null() {
+            log::info!(
+                "VST3: view has no content scale support, ignoring {}x (instance {})",
+                scale,
+                instance_id
+            );
+            return;
+        }
+        let scale_support = obj as *mut IPlugViewContentScaleSupport;
+        let result = ((*(*scale_support).vtbl).setContentScaleFactor)(scale_support, scale);
+        log::info!(
+            "VST3: setContentScaleFactor({}) = {} (instance {})",
+            scale,
+            result,
+            instance_id
+        );
+        ((*(*scale_support).vtbl).base.release)(scale_support as *mut FUnknown);
+    }
+}
+
 pub unsafe fn open_vst3_gui(
     controller_ptr: *mut IEditController,
     instance_id: PluginInstanceId,
     display_name: &str,
+    edits: EditorEdits,
     event_tx: &std::sync::mpsc::Sender<crate::pipewire::PwEvent>,
     cmd_tx: &std::sync::mpsc::Sender<crate::pipewire::PwCommand>,
 ) {
@@ -354,6 +394,11 @@ pub unsafe fn open_vst3_gui(
         // setFrame
         ((*(*view).vtbl).setFrame)(view, plug_frame as *mut IPlugFrame);
 
+        // Content scale override, before asking for the (scaled) size
+        if let Some(scale) = crate::plugin::ui_scale::scale_for(instance_id) {
+            set_content_scale(view, scale, instance_id);
+        }
+
         // getSize
         let mut rect: ViewRect = std::mem::zeroed();
         let size_ok = ((*(*view).vtbl).getSize)(view, &mut rect);
@@ -458,33 +503,46 @@ pub unsafe fn open_vst3_gui(
                     plug_frame,
                     x11_display: display,
                     x11_window: window,
+                    size: (width, height),
                     running: running.clone(),
                     run_loop,
                 },
             );
         });
 
+        // The idle loop holds its own references to the view and the
+        // controller, so neither goes away under it while it winds down.
+        ((*(*view).vtbl).base.addRef)(view as *mut FUnknown);
+        ((*(*controller_ptr).vtbl).base.base.addRef)(controller_ptr as *mut FUnknown);
+
         // Spawn X11 event loop thread
         let display_addr = display as usize;
         let run_loop_addr = run_loop as usize;
+        let view_addr = view as usize;
+        let controller_addr = controller_ptr as usize;
         let cmd_tx_clone = cmd_tx.clone();
         let running_clone = running.clone();
-        std::thread::Builder::new()
+        let spawned = std::thread::Builder::new()
             .name(format!("vst3-gui-{}", instance_id))
             .spawn(move || {
-                let display = display_addr as *mut c_void;
-                let run_loop = run_loop_addr as *mut HostRunLoop;
-                x11_event_loop(
-                    display,
+                let editor = EditorLoop {
+                    display: display_addr as *mut c_void,
                     window,
-                    wm_delete,
+                    wm_delete_atom: wm_delete,
                     instance_id,
-                    running_clone,
-                    cmd_tx_clone,
-                    run_loop,
-                );
-            })
-            .ok();
+                    view: view_addr as *mut IPlugView,
+                    controller: controller_addr as *mut IEditController,
+                    run_loop: run_loop_addr as *mut HostRunLoop,
+                    edits,
+                };
+                x11_event_loop(&editor, running_clone, cmd_tx_clone);
+                editor.release();
+            });
+        if let Err(e) = spawned {
+            log::error!("VST3: failed to spawn GUI thread: {}", e);
+            ((*(*view).vtbl).base.release)(view as *mut FUnknown);
+            ((*(*controller_ptr).vtbl).base.base.release)(controller_ptr as *mut FUnknown);
+        }
 
         log::info!(
             "VST3 embedded X11 GUI opened for instance {}",
@@ -493,6 +551,12 @@ pub unsafe fn open_vst3_gui(
         let _ = event_tx.send(crate::pipewire::PwEvent::Plugin(
             crate::pipewire::PluginEvent::PluginUiOpened { instance_id },
         ));
+        let _ = event_tx.send(crate::pipewire::PwEvent::Plugin(
+            crate::pipewire::PluginEvent::PluginUiWindow {
+                instance_id,
+                window_id: window as u64,
+            },
+        ));
     }
 }
 
@@ -528,17 +592,114 @@ unsafe fn find_child_window(display: *mut c_void, parent: c_ulong) -> c_ulong {
     }
 }
 
-/// X11 event loop for a VST3 GUI window.
-fn x11_event_loop(
+/// What the idle loop of one open editor works with.
+struct EditorLoop {
     display: *mut c_void,
     window: c_ulong,
     wm_delete_atom: c_ulong,
     instance_id: PluginInstanceId,
+    /// Referenced for the loop's lifetime; see [`EditorLoop::release`].
+    view: *mut IPlugView,
+    controller: *mut IEditController,
+    run_loop: *mut HostRunLoop,
+    edits: EditorEdits,
+}
+
+impl EditorLoop {
+    /// Pass the editor's edits on to the PipeWire thread, which applies
+    /// them and reports them to the manager as `ParameterChanged`.
+    fn send_edits(&self, cmd_tx: &std::sync::mpsc::Sender<crate::pipewire::PwCommand>) {
+        if self.edits.take_values_changed() {
+            log::debug!(
+                "VST3 editor changed all parameter values (instance {})",
+                self.instance_id
+            );
+            let controller = self.controller;
+            self.edits.push_all(|id| unsafe {
+                ((*(*controller).vtbl).getParamNormalized)(controller, id)
+            });
+        }
+        for (port_index, value) in self.edits.take() {
+            let _ = cmd_tx.send(crate::pipewire::PwCommand::SetPluginParameter {
+                instance_id: self.instance_id,
+                port_index,
+                value,
+            });
+        }
+    }
+
+    /// The user resized the host window: let the view follow within its
+    /// size constraints, and snap the window to the size it accepted.
+    /// Views that cannot resize keep their size.
+    unsafe fn follow_window_size(&self, width: i32, height: i32) {
+        unsafe {
+            let known = with_guis(|m| m.get(&self.instance_id).map(|s| s.size));
+            let Some(known) = known else { return };
+            if known == (width, height) {
+                return;
+            }
+
+            let view = self.view;
+            let (w, h) = if ((*(*view).vtbl).canResize)(view) == kResultTrue {
+                let mut rect = ViewRect {
+                    left: 0,
+                    top: 0,
+                    right: width,
+                    bottom: height,
+                };
+                ((*(*view).vtbl).checkSizeConstraint)(view, &mut rect);
+                let size = (
+                    (rect.right - rect.left).max(1),
+                    (rect.bottom - rect.top).max(1),
+                );
+                with_guis(|m| {
+                    if let Some(state) = m.get_mut(&self.instance_id) {
+                        state.size = size;
+                    }
+                });
+                ((*(*view).vtbl).onSize)(view, &mut rect);
+                log::debug!(
+                    "VST3 GUI resized to {}x{} (instance {})",
+                    size.0,
+                    size.1,
+                    self.instance_id
+                );
+                size
+            } else {
+                known
+            };
+
+            if (w, h) != (width, height) {
+                XResizeWindow(self.display, self.window, w, h);
+                XFlush(self.display);
+            }
+        }
+    }
+
+    /// Drop the loop's references to the view and the controller.
+    fn release(self) {
+        unsafe {
+            ((*(*self.view).vtbl).base.release)(self.view as *mut FUnknown);
+            ((*(*self.controller).vtbl).base.base.release)(self.controller as *mut FUnknown);
+        }
+    }
+}
+
+/// X11 event loop for a VST3 GUI window: the editor's idle loop.
+fn x11_event_loop(
+    editor: &EditorLoop,
     running: std::sync::Arc<AtomicBool>,
     cmd_tx: std::sync::mpsc::Sender<crate::pipewire::PwCommand>,
-    run_loop: *mut HostRunLoop,
 ) {
     let tick = std::time::Duration::from_millis(8);
+    let EditorLoop {
+        display,
+        window,
+        wm_delete_atom,
+        instance_id,
+        run_loop,
+        ..
+    } = *editor;
 
     // Track the plugin's embedded child window (e.g. yabridge's Wine window).
     // Initially 0 — discovered via ReparentNotify/MapNotify from XQueryTree.
@@ -549,6 +710,9 @@ fn x11_event_loop(
             // Service IRunLoop timers and fd event handlers
             run_loop_tick(run_loop);
 
+            // Edits made in the editor since the last tick
+            editor.send_edits(&cmd_tx);
+
             while XPending(display) > 0 {
                 let mut event = std::mem::zeroed::<[u8; 192]>();
                 XNextEvent(display, &mut event);
@@ -592,8 +756,17 @@ fn x11_event_loop(
                         }
                     }
                     CONFIGURE_NOTIFY => {
-                        // Child window resized — logged for debugging.
-                        // Actual resize coordination is handled by IPlugFrame::resizeView.
+                        // XConfigureEvent: window at 40, width/height at 56/60.
+                        // Child windows report here too (SubstructureNotify);
+                        // only our own window's size is followed. Resizes the
+                        // plugin asks for go through IPlugFrame::resizeView.
+                        let base = event.as_ptr();
+                        let event_window = *(base.add(40) as *const c_ulong);
+                        if event_window == window {
+                            let width = *(base.add(56) as *const c_int);
+                            let height = *(base.add(60) as *const c_int);
+                            editor.follow_window_size(width, height);
+                        }
                     }
                     _ => {}
                 }